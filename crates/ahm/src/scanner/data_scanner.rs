@@ -30,7 +30,7 @@ use tracing::{debug, error, info, warn};
 
 use super::metrics::{BucketMetrics, DiskMetrics, MetricsCollector, ScannerMetrics};
 use crate::heal::HealManager;
-use crate::scanner::lifecycle::ScannerItem;
+use crate::scanner::lifecycle::{ScannerItem, abort_incomplete_multipart_uploads};
 use crate::{
     HealRequest,
     error::{Error, Result},
@@ -609,6 +609,7 @@ impl Scanner {
                         index: p.index.clone(),
                         checksums: p.checksums.clone(),
                         error: None,
+                        nonce: p.nonce.clone(),
                     })
                     .collect(),
                 erasure: rustfs_filemeta::ErasureInfo {
@@ -934,13 +935,14 @@ impl Scanner {
         drop(config);
         let mut scan_futures = Vec::new();
 
-        for disk in disks {
+        // Every disk of the set holds the same objects, lifecycle is applied from the first one only
+        for (i, disk) in disks.into_iter().enumerate() {
             let semaphore = semaphore.clone();
             let scanner = self.clone_for_background();
 
             let future = async move {
                 let _permit = semaphore.acquire().await.unwrap();
-                scanner.scan_disk(&disk).await
+                scanner.scan_disk(&disk, i == 0).await
             };
 
             scan_futures.push(future);
@@ -977,11 +979,17 @@ impl Scanner {
             set_index, pool_index, successful_scans, failed_scans
         );
 
+        abort_incomplete_multipart_uploads(&set_disks).await;
+
         Ok(all_disk_objects)
     }
 
     /// Scan a single disk
-    async fn scan_disk(&self, disk: &DiskStore) -> Result<HashMap<String, HashMap<String, rustfs_filemeta::FileMeta>>> {
+    async fn scan_disk(
+        &self,
+        disk: &DiskStore,
+        apply_lifecycle: bool,
+    ) -> Result<HashMap<String, HashMap<String, rustfs_filemeta::FileMeta>>> {
         let disk_path = disk.path().to_string_lossy().to_string();
 
         // Start global metrics collection for disk scan
@@ -1123,7 +1131,7 @@ impl Scanner {
                 }
             }
 
-            match self.scan_volume(disk, &volume.name).await {
+            match self.scan_volume(disk, &volume.name, apply_lifecycle).await {
                 Ok(object_metadata) => {
                     disk_objects.insert(volume.name, object_metadata);
                 }
@@ -1160,7 +1168,12 @@ impl Scanner {
     ///
     /// This method collects all objects from a disk for a specific bucket.
     /// It returns a map of object names to their metadata for later analysis.
-    async fn scan_volume(&self, disk: &DiskStore, bucket: &str) -> Result<HashMap<String, rustfs_filemeta::FileMeta>> {
+    async fn scan_volume(
+        &self,
+        disk: &DiskStore,
+        bucket: &str,
+        apply_lifecycle: bool,
+    ) -> Result<HashMap<String, rustfs_filemeta::FileMeta>> {
         let ecstore = match rustfs_ecstore::new_object_layer_fn() {
             Some(ecstore) => ecstore,
             None => {
@@ -1170,10 +1183,14 @@ impl Scanner {
         };
         let bucket_info = ecstore.get_bucket_info(bucket, &Default::default()).await.ok();
         let versioning_config = bucket_info.map(|bi| Arc::new(VersioningConfig { enabled: bi.versioning }));
-        let lifecycle_config = rustfs_ecstore::bucket::metadata_sys::get_lifecycle_config(bucket)
-            .await
-            .ok()
-            .map(|(c, _)| Arc::new(c));
+        let lifecycle_config = if apply_lifecycle {
+            rustfs_ecstore::bucket::metadata_sys::get_lifecycle_config(bucket)
+                .await
+                .ok()
+                .map(|(c, _)| Arc::new(c))
+        } else {
+            None
+        };
         // Start global metrics collection for volume scan
         let stop_fn = Metrics::time(Metric::ScanObject);
 
@@ -1261,6 +1278,12 @@ impl Scanner {
                             }
                         }
                     } else {
+                        // Objects given a time to live are reaped by the node whose scanner comes across them
+                        // when the node that wrote them is gone
+                        if apply_lifecycle {
+                            rustfs_ecstore::object_ttl::schedule_entry_expiry(bucket, &entry);
+                        }
+
                         // Apply lifecycle actions
                         if let Some(lifecycle_config) = &lifecycle_config {
                             let mut scanner_item =
@@ -1548,6 +1571,8 @@ impl Scanner {
 
         // Ensure buckets_count is correctly set
         data_usage.buckets_count = data_usage.buckets_usage.len() as u64;
+        // Tells the readers of the saved usage which scan it comes from
+        data_usage.last_update = Some(SystemTime::now());
 
         // Log statistics before storing
         info!(
@@ -12,18 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use rustfs_common::metrics::IlmAction;
-use rustfs_ecstore::bucket::lifecycle::bucket_lifecycle_audit::LcEventSrc;
-use rustfs_ecstore::bucket::lifecycle::bucket_lifecycle_ops::{apply_lifecycle_action, eval_action_from_lifecycle};
-use rustfs_ecstore::bucket::metadata_sys::get_object_lock_config;
+use rustfs_common::metrics::{IlmAction, Metrics};
+use rustfs_ecstore::StorageAPI;
+use rustfs_ecstore::bucket::lifecycle::bucket_lifecycle_audit::{LcAuditEntry, LcEventSrc, audit_lifecycle_action};
+use rustfs_ecstore::bucket::lifecycle::bucket_lifecycle_ops::{
+    GLOBAL_ExpiryState, LifecycleOps, apply_lifecycle_action, eval_action_from_lifecycle,
+};
+use rustfs_ecstore::bucket::lifecycle::lifecycle::{Lifecycle, expected_expiry_time};
+use rustfs_ecstore::bucket::metadata_sys::{get_lifecycle_config, get_object_lock_config};
+use rustfs_ecstore::bucket::object_lock::objectlock_sys::enforce_retention_for_deletion;
 use rustfs_ecstore::cmd::bucket_targets::VersioningConfig;
-use rustfs_ecstore::store_api::ObjectInfo;
-use rustfs_filemeta::FileMetaVersion;
+use rustfs_ecstore::set_disk::SetDisks;
+use rustfs_ecstore::store_api::{ObjectInfo, ObjectOptions, ObjectToDelete};
 use rustfs_filemeta::metacache::MetaCacheEntry;
 use s3s::dto::BucketLifecycleConfiguration as LifecycleConfig;
-use tracing::info;
+use time::OffsetDateTime;
+use tracing::{debug, warn};
 
 #[derive(Clone)]
 pub struct ScannerItem {
@@ -41,40 +48,82 @@ impl ScannerItem {
         }
     }
 
-    pub async fn apply_actions(&mut self, object: &str, mut meta: MetaCacheEntry) -> anyhow::Result<()> {
-        info!("apply_actions called for object: {}", object);
+    /// Evaluate the lifecycle rules on every version of `object` and apply the due actions
+    pub async fn apply_actions(&mut self, object: &str, meta: MetaCacheEntry) -> anyhow::Result<()> {
         if self.lifecycle.is_none() {
-            info!("No lifecycle config for object: {}", object);
             return Ok(());
         }
-        info!("Lifecycle config exists for object: {}", object);
 
-        let file_meta = match meta.xl_meta() {
-            Ok(meta) => meta,
+        let fivs = match meta.file_info_versions(&self.bucket) {
+            Ok(fivs) => fivs,
             Err(e) => {
-                tracing::error!("Failed to get xl_meta for {}: {}", object, e);
+                warn!("lifecycle: failed to read versions of {}/{}: {}", self.bucket, object, e);
                 return Ok(());
             }
         };
 
-        let latest_version = file_meta.versions.first().cloned().unwrap_or_default();
-        let file_meta_version = FileMetaVersion::try_from(latest_version.meta.as_slice()).unwrap_or_default();
-
-        let obj_info = ObjectInfo {
-            bucket: self.bucket.clone(),
-            name: object.to_string(),
-            version_id: latest_version.header.version_id,
-            mod_time: latest_version.header.mod_time,
-            size: file_meta_version.object.as_ref().map_or(0, |o| o.size),
-            user_defined: serde_json::from_slice(file_meta.data.as_slice()).unwrap_or_default(),
-            ..Default::default()
-        };
+        let versioned = self.versioning.as_ref().is_some_and(|v| v.is_enabled());
+        let objs: Vec<ObjectInfo> = fivs
+            .versions
+            .iter()
+            .map(|fi| ObjectInfo::from_file_info(fi, &self.bucket, object, versioned))
+            .collect();
 
-        self.apply_lifecycle(&obj_info).await;
+        for oi in self.apply_newer_noncurrent_limit(objs).await {
+            self.apply_lifecycle(&oi).await;
+        }
 
         Ok(())
     }
 
+    /// Queue for expiry the noncurrent versions beyond the newer ones a rule keeps, returning
+    /// the versions left to evaluate
+    async fn apply_newer_noncurrent_limit(&self, objs: Vec<ObjectInfo>) -> Vec<ObjectInfo> {
+        let (Some(lc), Some(latest)) = (self.lifecycle.as_ref(), objs.first()) else {
+            return objs;
+        };
+        let event = lc.noncurrent_versions_expiration_limit(&latest.to_lifecycle_opts()).await;
+        if event.newer_noncurrent_versions == 0 {
+            return objs;
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let mut remaining = event.newer_noncurrent_versions;
+        let mut kept = Vec::with_capacity(objs.len());
+        let mut to_delete = Vec::new();
+        for oi in objs {
+            if oi.is_latest || oi.delete_marker {
+                kept.push(oi);
+                continue;
+            }
+            if remaining > 0 {
+                remaining -= 1;
+                kept.push(oi);
+                continue;
+            }
+            let due = oi
+                .successor_mod_time
+                .map(|succ| expected_expiry_time(succ, event.noncurrent_days as i32));
+            if due.is_none_or(|due| now < due) || enforce_retention_for_deletion(&oi) {
+                kept.push(oi);
+                continue;
+            }
+            to_delete.push(ObjectToDelete {
+                object_name: oi.name.clone(),
+                version_id: oi.version_id,
+            });
+        }
+
+        if !to_delete.is_empty() {
+            GLOBAL_ExpiryState
+                .write()
+                .await
+                .enqueue_by_newer_noncurrent(&self.bucket, to_delete, event)
+                .await;
+        }
+        kept
+    }
+
     async fn apply_lifecycle(&mut self, oi: &ObjectInfo) -> (IlmAction, i64) {
         let size = oi.size;
         if self.lifecycle.is_none() {
@@ -90,17 +139,10 @@ impl ScannerItem {
             (None, None)
         };
 
-        let lc_evt = eval_action_from_lifecycle(
-            self.lifecycle.as_ref().unwrap(),
-            olcfg
-                .as_ref()
-                .and_then(|(c, _)| c.rule.as_ref().and_then(|r| r.default_retention.clone())),
-            rcfg.clone(),
-            oi,
-        )
-        .await;
+        let lc_evt =
+            eval_action_from_lifecycle(self.lifecycle.as_ref().unwrap(), olcfg.as_ref().map(|(c, _)| c), rcfg.clone(), oi).await;
 
-        info!("lifecycle: {} Initial scan: {}", oi.name, lc_evt.action);
+        debug!("lifecycle: {} Initial scan: {}", oi.name, lc_evt.action);
 
         let mut new_size = size;
         match lc_evt.action {
@@ -123,3 +165,56 @@ impl ScannerItem {
         (lc_evt.action, new_size)
     }
 }
+
+/// Abort the multipart uploads of `set_disks` whose bucket lifecycle has an
+/// AbortIncompleteMultipartUpload rule due for them
+pub async fn abort_incomplete_multipart_uploads(set_disks: &SetDisks) {
+    let uploads = match set_disks.list_all_multipart_uploads().await {
+        Ok(uploads) => uploads,
+        Err(e) => {
+            warn!("lifecycle: failed to list multipart uploads: {}", e);
+            return;
+        }
+    };
+
+    let now = OffsetDateTime::now_utc();
+    let mut configs: HashMap<String, Option<LifecycleConfig>> = HashMap::new();
+    for upload in uploads {
+        if !configs.contains_key(&upload.bucket) {
+            let lc = get_lifecycle_config(&upload.bucket).await.ok().map(|(lc, _)| lc);
+            configs.insert(upload.bucket.clone(), lc);
+        }
+        let (Some(Some(lc)), Some(initiated)) = (configs.get(&upload.bucket), upload.initiated) else {
+            continue;
+        };
+
+        let event = lc.eval_abort_multipart(&upload.object, initiated, now);
+        if event.action != IlmAction::AbortMultipartUploadAction {
+            continue;
+        }
+
+        let time_ilm = Metrics::time_ilm(event.action);
+        if let Err(e) = set_disks
+            .abort_multipart_upload(&upload.bucket, &upload.object, &upload.upload_id, &ObjectOptions::default())
+            .await
+        {
+            warn!(
+                "lifecycle: failed to abort multipart upload {} of {}/{}: {}",
+                upload.upload_id, upload.bucket, upload.object, e
+            );
+            continue;
+        }
+        time_ilm(1)();
+        audit_lifecycle_action(LcAuditEntry {
+            bucket: upload.bucket.clone(),
+            object: upload.object.clone(),
+            version_id: None,
+            upload_id: Some(upload.upload_id.clone()),
+            rule_id: event.rule_id,
+            action: event.action,
+            source: LcEventSrc::Scanner,
+            versions: 1,
+        })
+        .await;
+    }
+}
@@ -8,7 +8,7 @@ use rustfs_ecstore::{
     disk::endpoint::Endpoint,
     endpoints::{EndpointServerPools, Endpoints, PoolEndpoints},
     store::ECStore,
-    store_api::{ObjectIO, ObjectOptions, PutObjReader, StorageAPI},
+    store_api::{MakeBucketOptions, ObjectIO, ObjectOptions, PutObjReader, StorageAPI},
 };
 use serial_test::serial;
 use std::sync::Once;
@@ -108,8 +108,15 @@ async fn setup_test_env() -> (Vec<PathBuf>, Arc<ECStore>, Arc<ECStoreHealStorage
 
 /// Test helper: Create a test bucket
 async fn create_test_bucket(ecstore: &Arc<ECStore>, bucket_name: &str) {
+    // The tests share a store and some of them make the same bucket
     (**ecstore)
-        .make_bucket(bucket_name, &Default::default())
+        .make_bucket(
+            bucket_name,
+            &MakeBucketOptions {
+                force_create: true,
+                ..Default::default()
+            },
+        )
         .await
         .expect("Failed to create test bucket");
     info!("Created test bucket: {}", bucket_name);
@@ -88,6 +88,34 @@ pub struct BucketTargetUsageInfo {
     pub replicated_count: u64,
 }
 
+/// Most prefixes of a bucket whose usage is kept, the objects of the prefixes past it are only counted in
+/// the bucket
+pub const MAX_PREFIXES_PER_BUCKET: usize = 1000;
+
+/// Prefix of `object` its usage is kept under: its first directory, with the trailing slash, or the empty
+/// prefix for the objects at the root of the bucket
+pub fn usage_prefix(object: &str) -> &str {
+    object.find('/').map_or("", |i| &object[..=i])
+}
+
+/// Usage of the objects under a prefix of a bucket
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrefixUsageInfo {
+    pub size: u64,
+    pub objects_count: u64,
+    pub versions_count: u64,
+    pub delete_markers_count: u64,
+}
+
+impl PrefixUsageInfo {
+    pub fn merge(&mut self, other: &PrefixUsageInfo) {
+        self.size += other.size;
+        self.objects_count += other.objects_count;
+        self.versions_count += other.versions_count;
+        self.delete_markers_count += other.delete_markers_count;
+    }
+}
+
 /// Bucket usage info provides bucket-level statistics
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct BucketUsageInfo {
@@ -112,6 +140,9 @@ pub struct BucketUsageInfo {
     pub replica_size: u64,
     pub replica_count: u64,
     pub replication_info: HashMap<String, BucketTargetUsageInfo>,
+    /// Usage by the prefix of [`usage_prefix`], for at most [`MAX_PREFIXES_PER_BUCKET`] prefixes
+    #[serde(default)]
+    pub prefixes_usage: HashMap<String, PrefixUsageInfo>,
 }
 
 /// DataUsageInfo represents data usage stats of the underlying storage
@@ -973,12 +1004,21 @@ impl DataUsageInfo {
             }
         }
 
+        let prefix_usage = PrefixUsageInfo {
+            size: total_size,
+            objects_count: 1,
+            versions_count,
+            delete_markers_count,
+        };
+        let prefix = usage_prefix(object_path.get(bucket_name.len() + 1..).unwrap_or_default());
+
         // Update bucket statistics
         if let Some(bucket_usage) = self.buckets_usage.get_mut(&bucket_name) {
             bucket_usage.size += total_size;
             bucket_usage.objects_count += 1;
             bucket_usage.versions_count += versions_count;
             bucket_usage.delete_markers_count += delete_markers_count;
+            bucket_usage.add_prefix_usage(prefix, &prefix_usage);
 
             // Update size histogram based on latest object size
             let size_ranges = [
@@ -1025,6 +1065,7 @@ impl DataUsageInfo {
                 delete_markers_count,
                 ..Default::default()
             };
+            bucket_usage.add_prefix_usage(prefix, &prefix_usage);
 
             // Set size histogram
             let size_ranges = [
@@ -1187,6 +1228,10 @@ impl BucketUsageInfo {
             entry.replicated_count += info.replicated_count;
         }
 
+        for (prefix, usage) in &other.prefixes_usage {
+            self.add_prefix_usage(prefix, usage);
+        }
+
         // Merge backward compatibility fields
         self.replication_pending_size_v1 += other.replication_pending_size_v1;
         self.replication_failed_size_v1 += other.replication_failed_size_v1;
@@ -1194,6 +1239,16 @@ impl BucketUsageInfo {
         self.replication_pending_count_v1 += other.replication_pending_count_v1;
         self.replication_failed_count_v1 += other.replication_failed_count_v1;
     }
+
+    /// Add `usage` to the usage of `prefix`, unless the bucket already keeps the usage of as many prefixes as
+    /// it may
+    pub fn add_prefix_usage(&mut self, prefix: &str, usage: &PrefixUsageInfo) {
+        if let Some(existing) = self.prefixes_usage.get_mut(prefix) {
+            existing.merge(usage);
+        } else if self.prefixes_usage.len() < MAX_PREFIXES_PER_BUCKET {
+            self.prefixes_usage.insert(prefix.to_owned(), usage.clone());
+        }
+    }
 }
 
 impl SizeSummary {
@@ -1263,6 +1318,36 @@ mod tests {
         assert_eq!(usage1.versions_count, 15);
     }
 
+    #[test]
+    fn test_prefix_usage() {
+        assert_eq!(usage_prefix("a/b/c"), "a/");
+        assert_eq!(usage_prefix("a"), "");
+
+        let mut usage = BucketUsageInfo::new();
+        let one = PrefixUsageInfo {
+            size: 10,
+            objects_count: 1,
+            versions_count: 1,
+            delete_markers_count: 0,
+        };
+        usage.add_prefix_usage("a/", &one);
+        usage.add_prefix_usage("a/", &one);
+
+        let mut other = BucketUsageInfo::new();
+        other.add_prefix_usage("b/", &one);
+        usage.merge(&other);
+
+        assert_eq!(usage.prefixes_usage["a/"].size, 20);
+        assert_eq!(usage.prefixes_usage["a/"].objects_count, 2);
+        assert_eq!(usage.prefixes_usage["b/"], one);
+
+        for i in 0..MAX_PREFIXES_PER_BUCKET {
+            usage.add_prefix_usage(&format!("p{i}/"), &one);
+        }
+        assert_eq!(usage.prefixes_usage.len(), MAX_PREFIXES_PER_BUCKET);
+        assert_eq!(usage.prefixes_usage["a/"].objects_count, 2);
+    }
+
     #[test]
     fn test_size_summary_add() {
         let mut summary1 = SizeSummary::new();
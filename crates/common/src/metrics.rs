@@ -40,10 +40,27 @@ pub enum IlmAction {
     DeleteRestoredVersionAction,
     DeleteAllVersionsAction,
     DelMarkerDeleteAllVersionsAction,
+    AbortMultipartUploadAction,
     ActionCount,
 }
 
 impl IlmAction {
+    pub fn from_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Self::NoneAction),
+            1 => Some(Self::DeleteAction),
+            2 => Some(Self::DeleteVersionAction),
+            3 => Some(Self::TransitionAction),
+            4 => Some(Self::TransitionVersionAction),
+            5 => Some(Self::DeleteRestoredAction),
+            6 => Some(Self::DeleteRestoredVersionAction),
+            7 => Some(Self::DeleteAllVersionsAction),
+            8 => Some(Self::DelMarkerDeleteAllVersionsAction),
+            9 => Some(Self::AbortMultipartUploadAction),
+            _ => None,
+        }
+    }
+
     pub fn delete_restored(&self) -> bool {
         *self == Self::DeleteRestoredAction || *self == Self::DeleteRestoredVersionAction
     }
@@ -253,6 +270,8 @@ pub struct Metrics {
     latency: Vec<LockedLastMinuteLatency>,
     actions: Vec<AtomicU64>,
     actions_latency: Vec<LockedLastMinuteLatency>,
+    // Lifecycle actions per "bucket/rule id", then per action
+    rule_actions: Arc<RwLock<HashMap<String, HashMap<String, u64>>>>,
     // Current paths contains disk -> tracker mappings
     current_paths: Arc<RwLock<HashMap<String, Arc<CurrentPathTracker>>>>,
 
@@ -281,6 +300,7 @@ impl Metrics {
             latency,
             actions: (0..IlmAction::ActionCount as usize).map(|_| AtomicU64::new(0)).collect(),
             actions_latency: vec![LockedLastMinuteLatency::default(); IlmAction::ActionCount as usize],
+            rule_actions: Arc::new(RwLock::new(HashMap::new())),
             current_paths: Arc::new(RwLock::new(HashMap::new())),
             cycle_info: Arc::new(RwLock::new(None)),
         }
@@ -391,6 +411,24 @@ impl Metrics {
         })
     }
 
+    /// Count `versions` handled by a lifecycle `action` of the rule `rule_id` of `bucket`
+    pub async fn inc_ilm_rule(&self, bucket: &str, rule_id: &str, action: IlmAction, versions: u64) {
+        if action == IlmAction::NoneAction || versions == 0 {
+            return;
+        }
+        let mut rule_actions = self.rule_actions.write().await;
+        *rule_actions
+            .entry(format!("{bucket}/{rule_id}"))
+            .or_default()
+            .entry(action.to_string())
+            .or_default() += versions;
+    }
+
+    /// Lifecycle actions per "bucket/rule id", then per action
+    pub async fn ilm_rule_actions(&self) -> HashMap<String, HashMap<String, u64>> {
+        self.rule_actions.read().await.clone()
+    }
+
     /// Increment time with specific duration
     pub async fn inc_time(metric: Metric, duration: Duration) {
         let metric = metric as usize;
@@ -473,6 +511,17 @@ impl Metrics {
             }
         }
 
+        // Lifetime lifecycle actions
+        for i in 0..IlmAction::ActionCount as usize {
+            let count = self.actions[i].load(Ordering::Relaxed);
+            if count > 0 {
+                if let Some(action) = IlmAction::from_index(i) {
+                    metrics.life_time_ilm.insert(action.to_string(), count);
+                }
+            }
+        }
+        metrics.life_time_ilm_rules = self.ilm_rule_actions().await;
+
         // Last minute statistics for realtime metrics
         for i in 0..Metric::LastRealtime as usize {
             let last_min = self.latency[i].total().await;
@@ -96,6 +96,26 @@ pub const RUSTFS_TLS_KEY: &str = "rustfs_key.pem";
 /// This is the default cert for TLS.
 pub const RUSTFS_TLS_CERT: &str = "rustfs_cert.pem";
 
+/// CA bundle for client certificates
+/// When present in the TLS directory, clients may authenticate with a certificate signed by it.
+pub const RUSTFS_TLS_CLIENT_CA: &str = "client_ca.pem";
+
+/// Client certificate identity rules
+/// JSON list mapping client certificate subjects and SANs to IAM users, read from the TLS directory.
+pub const RUSTFS_TLS_CLIENT_IDENTITY: &str = "client_identity.json";
+
+/// CA bundle for node certificates
+/// When present in the TLS directory, nodes mutually authenticate their RPCs with certificates signed by it.
+pub const RUSTFS_TLS_NODE_CA: &str = "node_ca.pem";
+
+/// Node certificate
+/// Certificate this node presents to its peers, read from the TLS directory.
+pub const RUSTFS_TLS_NODE_CERT: &str = "node_cert.pem";
+
+/// Node private key
+/// Private key of the node certificate, read from the TLS directory.
+pub const RUSTFS_TLS_NODE_KEY: &str = "node_key.pem";
+
 /// Default port for rustfs
 /// This is the default port for rustfs.
 /// This is used to bind the server to a specific port.
@@ -132,6 +152,12 @@ pub const DEFAULT_OBS_LOG_FILENAME: &str = concat!(DEFAULT_LOG_FILENAME, ".log")
 /// Default value: rustfs-sink.log
 pub const DEFAULT_SINK_FILE_LOG_FILE: &str = concat!(DEFAULT_LOG_FILENAME, "-sink.log");
 
+/// Default crash log file for rustfs
+/// This is the emergency log file written synchronously on panic or fatal signal.
+/// It lives in the log directory so postmortems don't depend on stderr capture.
+/// Default value: rustfs-crash.log
+pub const DEFAULT_CRASH_LOG_FILE: &str = concat!(DEFAULT_LOG_FILENAME, "-crash.log");
+
 /// Default log directory for rustfs
 /// This is the default log directory for rustfs.
 /// It is used to store the logs of the application.
@@ -0,0 +1,74 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::notify::{COMMENT_KEY, ENABLE_KEY};
+
+// AMQP Keys
+pub const AMQP_URL: &str = "url";
+pub const AMQP_EXCHANGE: &str = "exchange";
+pub const AMQP_EXCHANGE_TYPE: &str = "exchange_type";
+pub const AMQP_ROUTING_KEY: &str = "routing_key";
+pub const AMQP_MANDATORY: &str = "mandatory";
+pub const AMQP_DURABLE: &str = "durable";
+pub const AMQP_DELIVERY_MODE: &str = "delivery_mode";
+pub const AMQP_PUBLISHER_CONFIRMS: &str = "publisher_confirms";
+pub const AMQP_TLS_CA: &str = "tls_ca";
+pub const AMQP_QUEUE_DIR: &str = "queue_dir";
+pub const AMQP_QUEUE_LIMIT: &str = "queue_limit";
+
+/// A list of all valid configuration keys for an AMQP target.
+pub const NOTIFY_AMQP_KEYS: &[&str] = &[
+    ENABLE_KEY, // "enable" is a common key
+    AMQP_URL,
+    AMQP_EXCHANGE,
+    AMQP_EXCHANGE_TYPE,
+    AMQP_ROUTING_KEY,
+    AMQP_MANDATORY,
+    AMQP_DURABLE,
+    AMQP_DELIVERY_MODE,
+    AMQP_PUBLISHER_CONFIRMS,
+    AMQP_TLS_CA,
+    AMQP_QUEUE_DIR,
+    AMQP_QUEUE_LIMIT,
+    COMMENT_KEY,
+];
+
+// AMQP Environment Variables
+pub const ENV_AMQP_ENABLE: &str = "RUSTFS_NOTIFY_AMQP_ENABLE";
+pub const ENV_AMQP_URL: &str = "RUSTFS_NOTIFY_AMQP_URL";
+pub const ENV_AMQP_EXCHANGE: &str = "RUSTFS_NOTIFY_AMQP_EXCHANGE";
+pub const ENV_AMQP_EXCHANGE_TYPE: &str = "RUSTFS_NOTIFY_AMQP_EXCHANGE_TYPE";
+pub const ENV_AMQP_ROUTING_KEY: &str = "RUSTFS_NOTIFY_AMQP_ROUTING_KEY";
+pub const ENV_AMQP_MANDATORY: &str = "RUSTFS_NOTIFY_AMQP_MANDATORY";
+pub const ENV_AMQP_DURABLE: &str = "RUSTFS_NOTIFY_AMQP_DURABLE";
+pub const ENV_AMQP_DELIVERY_MODE: &str = "RUSTFS_NOTIFY_AMQP_DELIVERY_MODE";
+pub const ENV_AMQP_PUBLISHER_CONFIRMS: &str = "RUSTFS_NOTIFY_AMQP_PUBLISHER_CONFIRMS";
+pub const ENV_AMQP_TLS_CA: &str = "RUSTFS_NOTIFY_AMQP_TLS_CA";
+pub const ENV_AMQP_QUEUE_DIR: &str = "RUSTFS_NOTIFY_AMQP_QUEUE_DIR";
+pub const ENV_AMQP_QUEUE_LIMIT: &str = "RUSTFS_NOTIFY_AMQP_QUEUE_LIMIT";
+
+pub const ENV_NOTIFY_AMQP_KEYS: &[&str; 12] = &[
+    ENV_AMQP_ENABLE,
+    ENV_AMQP_URL,
+    ENV_AMQP_EXCHANGE,
+    ENV_AMQP_EXCHANGE_TYPE,
+    ENV_AMQP_ROUTING_KEY,
+    ENV_AMQP_MANDATORY,
+    ENV_AMQP_DURABLE,
+    ENV_AMQP_DELIVERY_MODE,
+    ENV_AMQP_PUBLISHER_CONFIRMS,
+    ENV_AMQP_TLS_CA,
+    ENV_AMQP_QUEUE_DIR,
+    ENV_AMQP_QUEUE_LIMIT,
+];
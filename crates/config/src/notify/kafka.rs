@@ -0,0 +1,66 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::notify::{COMMENT_KEY, ENABLE_KEY};
+
+// Kafka Keys
+pub const KAFKA_BROKERS: &str = "brokers";
+pub const KAFKA_TOPIC: &str = "topic";
+pub const KAFKA_SASL_MECHANISM: &str = "sasl_mechanism";
+pub const KAFKA_SASL_USERNAME: &str = "sasl_username";
+pub const KAFKA_SASL_PASSWORD: &str = "sasl_password";
+pub const KAFKA_TLS: &str = "tls";
+pub const KAFKA_TLS_SKIP_VERIFY: &str = "tls_skip_verify";
+pub const KAFKA_QUEUE_DIR: &str = "queue_dir";
+pub const KAFKA_QUEUE_LIMIT: &str = "queue_limit";
+
+/// A list of all valid configuration keys for a Kafka target.
+pub const NOTIFY_KAFKA_KEYS: &[&str] = &[
+    ENABLE_KEY, // "enable" is a common key
+    KAFKA_BROKERS,
+    KAFKA_TOPIC,
+    KAFKA_SASL_MECHANISM,
+    KAFKA_SASL_USERNAME,
+    KAFKA_SASL_PASSWORD,
+    KAFKA_TLS,
+    KAFKA_TLS_SKIP_VERIFY,
+    KAFKA_QUEUE_DIR,
+    KAFKA_QUEUE_LIMIT,
+    COMMENT_KEY,
+];
+
+// Kafka Environment Variables
+pub const ENV_KAFKA_ENABLE: &str = "RUSTFS_NOTIFY_KAFKA_ENABLE";
+pub const ENV_KAFKA_BROKERS: &str = "RUSTFS_NOTIFY_KAFKA_BROKERS";
+pub const ENV_KAFKA_TOPIC: &str = "RUSTFS_NOTIFY_KAFKA_TOPIC";
+pub const ENV_KAFKA_SASL_MECHANISM: &str = "RUSTFS_NOTIFY_KAFKA_SASL_MECHANISM";
+pub const ENV_KAFKA_SASL_USERNAME: &str = "RUSTFS_NOTIFY_KAFKA_SASL_USERNAME";
+pub const ENV_KAFKA_SASL_PASSWORD: &str = "RUSTFS_NOTIFY_KAFKA_SASL_PASSWORD";
+pub const ENV_KAFKA_TLS: &str = "RUSTFS_NOTIFY_KAFKA_TLS";
+pub const ENV_KAFKA_TLS_SKIP_VERIFY: &str = "RUSTFS_NOTIFY_KAFKA_TLS_SKIP_VERIFY";
+pub const ENV_KAFKA_QUEUE_DIR: &str = "RUSTFS_NOTIFY_KAFKA_QUEUE_DIR";
+pub const ENV_KAFKA_QUEUE_LIMIT: &str = "RUSTFS_NOTIFY_KAFKA_QUEUE_LIMIT";
+
+pub const ENV_NOTIFY_KAFKA_KEYS: &[&str; 10] = &[
+    ENV_KAFKA_ENABLE,
+    ENV_KAFKA_BROKERS,
+    ENV_KAFKA_TOPIC,
+    ENV_KAFKA_SASL_MECHANISM,
+    ENV_KAFKA_SASL_USERNAME,
+    ENV_KAFKA_SASL_PASSWORD,
+    ENV_KAFKA_TLS,
+    ENV_KAFKA_TLS_SKIP_VERIFY,
+    ENV_KAFKA_QUEUE_DIR,
+    ENV_KAFKA_QUEUE_LIMIT,
+];
@@ -22,6 +22,10 @@ pub const MQTT_USERNAME: &str = "username";
 pub const MQTT_PASSWORD: &str = "password";
 pub const MQTT_RECONNECT_INTERVAL: &str = "reconnect_interval";
 pub const MQTT_KEEP_ALIVE_INTERVAL: &str = "keep_alive_interval";
+pub const MQTT_PROTOCOL_VERSION: &str = "protocol_version";
+pub const MQTT_TLS_CA: &str = "tls_ca";
+pub const MQTT_TLS_CLIENT_CERT: &str = "tls_client_cert";
+pub const MQTT_TLS_CLIENT_KEY: &str = "tls_client_key";
 pub const MQTT_QUEUE_DIR: &str = "queue_dir";
 pub const MQTT_QUEUE_LIMIT: &str = "queue_limit";
 
@@ -35,6 +39,10 @@ pub const NOTIFY_MQTT_KEYS: &[&str] = &[
     MQTT_PASSWORD,
     MQTT_RECONNECT_INTERVAL,
     MQTT_KEEP_ALIVE_INTERVAL,
+    MQTT_PROTOCOL_VERSION,
+    MQTT_TLS_CA,
+    MQTT_TLS_CLIENT_CERT,
+    MQTT_TLS_CLIENT_KEY,
     MQTT_QUEUE_DIR,
     MQTT_QUEUE_LIMIT,
     COMMENT_KEY,
@@ -49,10 +57,14 @@ pub const ENV_MQTT_USERNAME: &str = "RUSTFS_NOTIFY_MQTT_USERNAME";
 pub const ENV_MQTT_PASSWORD: &str = "RUSTFS_NOTIFY_MQTT_PASSWORD";
 pub const ENV_MQTT_RECONNECT_INTERVAL: &str = "RUSTFS_NOTIFY_MQTT_RECONNECT_INTERVAL";
 pub const ENV_MQTT_KEEP_ALIVE_INTERVAL: &str = "RUSTFS_NOTIFY_MQTT_KEEP_ALIVE_INTERVAL";
+pub const ENV_MQTT_PROTOCOL_VERSION: &str = "RUSTFS_NOTIFY_MQTT_PROTOCOL_VERSION";
+pub const ENV_MQTT_TLS_CA: &str = "RUSTFS_NOTIFY_MQTT_TLS_CA";
+pub const ENV_MQTT_TLS_CLIENT_CERT: &str = "RUSTFS_NOTIFY_MQTT_TLS_CLIENT_CERT";
+pub const ENV_MQTT_TLS_CLIENT_KEY: &str = "RUSTFS_NOTIFY_MQTT_TLS_CLIENT_KEY";
 pub const ENV_MQTT_QUEUE_DIR: &str = "RUSTFS_NOTIFY_MQTT_QUEUE_DIR";
 pub const ENV_MQTT_QUEUE_LIMIT: &str = "RUSTFS_NOTIFY_MQTT_QUEUE_LIMIT";
 
-pub const ENV_NOTIFY_MQTT_KEYS: &[&str; 10] = &[
+pub const ENV_NOTIFY_MQTT_KEYS: &[&str; 14] = &[
     ENV_MQTT_ENABLE,
     ENV_MQTT_BROKER,
     ENV_MQTT_TOPIC,
@@ -61,6 +73,10 @@ pub const ENV_NOTIFY_MQTT_KEYS: &[&str; 10] = &[
     ENV_MQTT_PASSWORD,
     ENV_MQTT_RECONNECT_INTERVAL,
     ENV_MQTT_KEEP_ALIVE_INTERVAL,
+    ENV_MQTT_PROTOCOL_VERSION,
+    ENV_MQTT_TLS_CA,
+    ENV_MQTT_TLS_CLIENT_CERT,
+    ENV_MQTT_TLS_CLIENT_KEY,
     ENV_MQTT_QUEUE_DIR,
     ENV_MQTT_QUEUE_LIMIT,
 ];
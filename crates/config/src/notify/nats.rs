@@ -0,0 +1,66 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::notify::{COMMENT_KEY, ENABLE_KEY};
+
+// NATS Keys
+pub const NATS_ADDRESS: &str = "address";
+pub const NATS_SUBJECT: &str = "subject";
+pub const NATS_USERNAME: &str = "username";
+pub const NATS_PASSWORD: &str = "password";
+pub const NATS_TOKEN: &str = "token";
+pub const NATS_TLS: &str = "tls";
+pub const NATS_JETSTREAM: &str = "jetstream";
+pub const NATS_QUEUE_DIR: &str = "queue_dir";
+pub const NATS_QUEUE_LIMIT: &str = "queue_limit";
+
+/// A list of all valid configuration keys for a NATS target.
+pub const NOTIFY_NATS_KEYS: &[&str] = &[
+    ENABLE_KEY, // "enable" is a common key
+    NATS_ADDRESS,
+    NATS_SUBJECT,
+    NATS_USERNAME,
+    NATS_PASSWORD,
+    NATS_TOKEN,
+    NATS_TLS,
+    NATS_JETSTREAM,
+    NATS_QUEUE_DIR,
+    NATS_QUEUE_LIMIT,
+    COMMENT_KEY,
+];
+
+// NATS Environment Variables
+pub const ENV_NATS_ENABLE: &str = "RUSTFS_NOTIFY_NATS_ENABLE";
+pub const ENV_NATS_ADDRESS: &str = "RUSTFS_NOTIFY_NATS_ADDRESS";
+pub const ENV_NATS_SUBJECT: &str = "RUSTFS_NOTIFY_NATS_SUBJECT";
+pub const ENV_NATS_USERNAME: &str = "RUSTFS_NOTIFY_NATS_USERNAME";
+pub const ENV_NATS_PASSWORD: &str = "RUSTFS_NOTIFY_NATS_PASSWORD";
+pub const ENV_NATS_TOKEN: &str = "RUSTFS_NOTIFY_NATS_TOKEN";
+pub const ENV_NATS_TLS: &str = "RUSTFS_NOTIFY_NATS_TLS";
+pub const ENV_NATS_JETSTREAM: &str = "RUSTFS_NOTIFY_NATS_JETSTREAM";
+pub const ENV_NATS_QUEUE_DIR: &str = "RUSTFS_NOTIFY_NATS_QUEUE_DIR";
+pub const ENV_NATS_QUEUE_LIMIT: &str = "RUSTFS_NOTIFY_NATS_QUEUE_LIMIT";
+
+pub const ENV_NOTIFY_NATS_KEYS: &[&str; 10] = &[
+    ENV_NATS_ENABLE,
+    ENV_NATS_ADDRESS,
+    ENV_NATS_SUBJECT,
+    ENV_NATS_USERNAME,
+    ENV_NATS_PASSWORD,
+    ENV_NATS_TOKEN,
+    ENV_NATS_TLS,
+    ENV_NATS_JETSTREAM,
+    ENV_NATS_QUEUE_DIR,
+    ENV_NATS_QUEUE_LIMIT,
+];
@@ -17,6 +17,11 @@
 pub const ENV_OBS_ENDPOINT: &str = "RUSTFS_OBS_ENDPOINT";
 pub const ENV_OBS_USE_STDOUT: &str = "RUSTFS_OBS_USE_STDOUT";
 pub const ENV_OBS_SAMPLE_RATIO: &str = "RUSTFS_OBS_SAMPLE_RATIO";
+/// Comma-separated span targets that are always sampled, e.g. `lock,ecstore::set_disk`
+pub const ENV_OBS_SAMPLE_ALWAYS_TARGETS: &str = "RUSTFS_OBS_SAMPLE_ALWAYS_TARGETS";
+pub const ENV_OBS_SAMPLE_PARENT_BASED: &str = "RUSTFS_OBS_SAMPLE_PARENT_BASED";
+/// Comma-separated resource detectors out of `host`, `k8s` and `cloud`, empty disables detection
+pub const ENV_OBS_RESOURCE_DETECTORS: &str = "RUSTFS_OBS_RESOURCE_DETECTORS";
 pub const ENV_OBS_METER_INTERVAL: &str = "RUSTFS_OBS_METER_INTERVAL";
 pub const ENV_OBS_SERVICE_NAME: &str = "RUSTFS_OBS_SERVICE_NAME";
 pub const ENV_OBS_SERVICE_VERSION: &str = "RUSTFS_OBS_SERVICE_VERSION";
@@ -29,7 +34,17 @@ pub const ENV_OBS_LOG_ROTATION_SIZE_MB: &str = "RUSTFS_OBS_LOG_ROTATION_SIZE_MB"
 pub const ENV_OBS_LOG_ROTATION_TIME: &str = "RUSTFS_OBS_LOG_ROTATION_TIME";
 pub const ENV_OBS_LOG_KEEP_FILES: &str = "RUSTFS_OBS_LOG_KEEP_FILES";
 
+/// JSON file with `sinks` and `logger` sections overriding the environment, watched for changes
+pub const ENV_OBS_CONFIG_FILE: &str = "RUSTFS_OBS_CONFIG_FILE";
+/// Seconds between checks of the logger configuration, `0` disables hot reload
+pub const ENV_OBS_CONFIG_RELOAD_INTERVAL: &str = "RUSTFS_OBS_CONFIG_RELOAD_INTERVAL";
+
 pub const ENV_AUDIT_LOGGER_QUEUE_CAPACITY: &str = "RUSTFS_AUDIT_LOGGER_QUEUE_CAPACITY";
+/// Per-API audit matrix, e.g. `HeadObject=off,ListObjectsV2=failures,*=on`
+pub const ENV_AUDIT_API_FILTER: &str = "RUSTFS_AUDIT_API_FILTER";
 
 // Default values for observability configuration
 pub const DEFAULT_AUDIT_LOGGER_QUEUE_CAPACITY: usize = 10000;
+pub const DEFAULT_OBS_SAMPLE_PARENT_BASED: bool = true;
+pub const DEFAULT_OBS_RESOURCE_DETECTORS: &str = "host,k8s,cloud";
+pub const DEFAULT_OBS_CONFIG_RELOAD_INTERVAL: u64 = 10;
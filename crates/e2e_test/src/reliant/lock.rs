@@ -14,29 +14,25 @@
 // limitations under the License.
 
 use async_trait::async_trait;
-use rustfs_ecstore::{disk::endpoint::Endpoint, lock_utils::create_unique_clients};
 use rustfs_lock::client::{LockClient, local::LocalClient};
-use rustfs_lock::types::{LockInfo, LockResponse, LockStats};
+use rustfs_lock::local::LocalLockMap;
+use rustfs_lock::types::{LockInfo, LockResponse, LockStats, LockStatus};
 use rustfs_lock::{LockId, LockMetadata, LockPriority, LockType};
 use rustfs_lock::{LockRequest, NamespaceLock, NamespaceLockManager};
 use rustfs_protos::{node_service_time_out_client, proto_gen::node_service::GenerallyLockRequest};
 use serial_test::serial;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Instant, SystemTime};
 use std::{error::Error, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
 use tokio::time::sleep;
 use tonic::Request;
-use url::Url;
 
 const CLUSTER_ADDR: &str = "http://localhost:9000";
 
-fn get_cluster_endpoints() -> Vec<Endpoint> {
-    vec![Endpoint {
-        url: Url::parse(CLUSTER_ADDR).unwrap(),
-        is_local: false,
-        pool_idx: 0,
-        set_idx: 0,
-        disk_idx: 0,
-    }]
-}
+/// Cluster sizes the namespace lock suite runs against, giving a quorum of 2, 2 and 3 nodes
+const CLUSTER_SIZES: [usize; 3] = [2, 3, 5];
 
 #[tokio::test]
 #[serial]
@@ -52,12 +48,17 @@ async fn test_lock_unlock_rpc() -> Result<(), Box<dyn Error>> {
         metadata: LockMetadata::default(),
         priority: LockPriority::Normal,
         deadlock_detection: false,
+        cancel: None,
+        deadline: None,
     };
     let args = serde_json::to_string(&args)?;
 
     let mut client = node_service_time_out_client(&CLUSTER_ADDR.to_string()).await?;
     println!("got client");
-    let request = Request::new(GenerallyLockRequest { args: args.clone() });
+    let request = Request::new(GenerallyLockRequest {
+        args: args.clone(),
+        timeout_ms: None,
+    });
 
     println!("start request");
     let response = client.lock(request).await?.into_inner();
@@ -66,7 +67,7 @@ async fn test_lock_unlock_rpc() -> Result<(), Box<dyn Error>> {
         panic!("can not get lock: {error_info}");
     }
 
-    let request = Request::new(GenerallyLockRequest { args });
+    let request = Request::new(GenerallyLockRequest { args, timeout_ms: None });
     let response = client.un_lock(request).await?.into_inner();
     if let Some(error_info) = response.error_info {
         panic!("can not get un_lock: {error_info}");
@@ -285,217 +286,6 @@ async fn test_transactional_lock_rollback_on_release_failure() -> Result<(), Box
     Ok(())
 }
 
-#[tokio::test]
-#[serial]
-#[ignore = "requires running RustFS server at localhost:9000"]
-async fn test_lock_unlock_ns_lock() -> Result<(), Box<dyn Error>> {
-    let endpoints = get_cluster_endpoints();
-    let clients = create_unique_clients(&endpoints).await?;
-    let ns_lock = NamespaceLock::with_clients("test".to_string(), clients);
-
-    let resources = vec!["foo".to_string()];
-    let result = ns_lock
-        .lock_batch(&resources, "dandan", Duration::from_secs(5), Duration::from_secs(10))
-        .await;
-    match &result {
-        Ok(success) => println!("Lock result: {success}"),
-        Err(e) => println!("Lock error: {e}"),
-    }
-    let result = result?;
-    assert!(result, "Lock should succeed, but got: {result}");
-
-    ns_lock.unlock_batch(&resources, "dandan").await?;
-    Ok(())
-}
-
-#[tokio::test]
-#[serial]
-#[ignore = "requires running RustFS server at localhost:9000"]
-async fn test_concurrent_lock_attempts() -> Result<(), Box<dyn Error>> {
-    let endpoints = get_cluster_endpoints();
-    let clients = create_unique_clients(&endpoints).await?;
-    let ns_lock = NamespaceLock::with_clients("test".to_string(), clients);
-    let resource = vec!["concurrent_resource".to_string()];
-
-    // First lock should succeed
-    println!("Attempting first lock...");
-    let result1 = ns_lock
-        .lock_batch(&resource, "owner1", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    println!("First lock result: {result1}");
-    assert!(result1, "First lock should succeed");
-
-    // Second lock should fail (resource already locked)
-    println!("Attempting second lock...");
-    let result2 = ns_lock
-        .lock_batch(&resource, "owner2", Duration::from_secs(1), Duration::from_secs(10))
-        .await?;
-    println!("Second lock result: {result2}");
-    assert!(!result2, "Second lock should fail");
-
-    // Unlock by first owner
-    println!("Unlocking first lock...");
-    ns_lock.unlock_batch(&resource, "owner1").await?;
-    println!("First lock unlocked");
-
-    // Now second owner should be able to lock
-    println!("Attempting third lock...");
-    let result3 = ns_lock
-        .lock_batch(&resource, "owner2", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    println!("Third lock result: {result3}");
-    assert!(result3, "Lock should succeed after unlock");
-
-    // Clean up
-    println!("Cleaning up...");
-    ns_lock.unlock_batch(&resource, "owner2").await?;
-    println!("Test completed");
-
-    Ok(())
-}
-
-#[tokio::test]
-#[serial]
-#[ignore = "requires running RustFS server at localhost:9000"]
-async fn test_read_write_lock_compatibility() -> Result<(), Box<dyn Error>> {
-    let endpoints = get_cluster_endpoints();
-    let clients = create_unique_clients(&endpoints).await?;
-    let ns_lock = NamespaceLock::with_clients("test_rw".to_string(), clients);
-    let resource = vec!["rw_resource".to_string()];
-
-    // First read lock should succeed
-    let result1 = ns_lock
-        .rlock_batch(&resource, "reader1", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    assert!(result1, "First read lock should succeed");
-
-    // Second read lock should also succeed (read locks are compatible)
-    let result2 = ns_lock
-        .rlock_batch(&resource, "reader2", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    assert!(result2, "Second read lock should succeed");
-
-    // Write lock should fail (read locks are held)
-    let result3 = ns_lock
-        .lock_batch(&resource, "writer1", Duration::from_secs(1), Duration::from_secs(10))
-        .await?;
-    assert!(!result3, "Write lock should fail when read locks are held");
-
-    // Release read locks
-    ns_lock.runlock_batch(&resource, "reader1").await?;
-    ns_lock.runlock_batch(&resource, "reader2").await?;
-
-    // Now write lock should succeed
-    let result4 = ns_lock
-        .lock_batch(&resource, "writer1", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    assert!(result4, "Write lock should succeed after read locks released");
-
-    // Clean up
-    ns_lock.unlock_batch(&resource, "writer1").await?;
-
-    Ok(())
-}
-
-#[tokio::test]
-#[serial]
-#[ignore = "requires running RustFS server at localhost:9000"]
-async fn test_lock_timeout() -> Result<(), Box<dyn Error>> {
-    let endpoints = get_cluster_endpoints();
-    let clients = create_unique_clients(&endpoints).await?;
-    let ns_lock = NamespaceLock::with_clients("test_timeout".to_string(), clients);
-    let resource = vec!["timeout_resource".to_string()];
-
-    // First lock with short timeout
-    let result1 = ns_lock
-        .lock_batch(&resource, "owner1", Duration::from_secs(2), Duration::from_secs(1))
-        .await?;
-    assert!(result1, "First lock should succeed");
-
-    // Wait for lock to expire
-    sleep(Duration::from_secs(5)).await;
-
-    // Second lock should succeed after timeout
-    let result2 = ns_lock
-        .lock_batch(&resource, "owner2", Duration::from_secs(5), Duration::from_secs(1))
-        .await?;
-    assert!(result2, "Lock should succeed after timeout");
-
-    // Clean up
-    ns_lock.unlock_batch(&resource, "owner2").await?;
-
-    Ok(())
-}
-
-#[tokio::test]
-#[serial]
-#[ignore = "requires running RustFS server at localhost:9000"]
-async fn test_batch_lock_operations() -> Result<(), Box<dyn Error>> {
-    let endpoints = get_cluster_endpoints();
-    let clients = create_unique_clients(&endpoints).await?;
-    let ns_lock = NamespaceLock::with_clients("test_batch".to_string(), clients);
-    let resources = vec![
-        "batch_resource1".to_string(),
-        "batch_resource2".to_string(),
-        "batch_resource3".to_string(),
-    ];
-
-    // Lock all resources
-    let result = ns_lock
-        .lock_batch(&resources, "batch_owner", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    assert!(result, "Batch lock should succeed");
-
-    // Try to lock one of the resources with different owner - should fail
-    let single_resource = vec!["batch_resource2".to_string()];
-    let result2 = ns_lock
-        .lock_batch(&single_resource, "other_owner", Duration::from_secs(1), Duration::from_secs(10))
-        .await?;
-    assert!(!result2, "Lock should fail for already locked resource");
-
-    // Unlock all resources
-    ns_lock.unlock_batch(&resources, "batch_owner").await?;
-
-    // Now should be able to lock single resource
-    let result3 = ns_lock
-        .lock_batch(&single_resource, "other_owner", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    assert!(result3, "Lock should succeed after batch unlock");
-
-    // Clean up
-    ns_lock.unlock_batch(&single_resource, "other_owner").await?;
-
-    Ok(())
-}
-
-#[tokio::test]
-#[serial]
-#[ignore = "requires running RustFS server at localhost:9000"]
-async fn test_multiple_namespaces() -> Result<(), Box<dyn Error>> {
-    let endpoints = get_cluster_endpoints();
-    let clients = create_unique_clients(&endpoints).await?;
-    let ns_lock1 = NamespaceLock::with_clients("namespace1".to_string(), clients.clone());
-    let ns_lock2 = NamespaceLock::with_clients("namespace2".to_string(), clients);
-    let resource = vec!["shared_resource".to_string()];
-
-    // Lock same resource in different namespaces - both should succeed
-    let result1 = ns_lock1
-        .lock_batch(&resource, "owner1", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    assert!(result1, "Lock in namespace1 should succeed");
-
-    let result2 = ns_lock2
-        .lock_batch(&resource, "owner2", Duration::from_secs(5), Duration::from_secs(10))
-        .await?;
-    assert!(result2, "Lock in namespace2 should succeed");
-
-    // Clean up
-    ns_lock1.unlock_batch(&resource, "owner1").await?;
-    ns_lock2.unlock_batch(&resource, "owner2").await?;
-
-    Ok(())
-}
-
 #[tokio::test]
 #[serial]
 #[ignore = "requires running RustFS server at localhost:9000"]
@@ -510,13 +300,18 @@ async fn test_rpc_read_lock() -> Result<(), Box<dyn Error>> {
         metadata: LockMetadata::default(),
         priority: LockPriority::Normal,
         deadlock_detection: false,
+        cancel: None,
+        deadline: None,
     };
     let args_str = serde_json::to_string(&args)?;
 
     let mut client = node_service_time_out_client(&CLUSTER_ADDR.to_string()).await?;
 
     // First read lock
-    let request = Request::new(GenerallyLockRequest { args: args_str.clone() });
+    let request = Request::new(GenerallyLockRequest {
+        args: args_str.clone(),
+        timeout_ms: None,
+    });
     let response = client.r_lock(request).await?.into_inner();
     if let Some(error_info) = response.error_info {
         panic!("can not get read lock: {error_info}");
@@ -533,16 +328,24 @@ async fn test_rpc_read_lock() -> Result<(), Box<dyn Error>> {
         metadata: LockMetadata::default(),
         priority: LockPriority::Normal,
         deadlock_detection: false,
+        cancel: None,
+        deadline: None,
     };
     let args2_str = serde_json::to_string(&args2)?;
-    let request2 = Request::new(GenerallyLockRequest { args: args2_str });
+    let request2 = Request::new(GenerallyLockRequest {
+        args: args2_str,
+        timeout_ms: None,
+    });
     let response2 = client.r_lock(request2).await?.into_inner();
     if let Some(error_info) = response2.error_info {
         panic!("can not get second read lock: {error_info}");
     }
 
     // Unlock both
-    let request = Request::new(GenerallyLockRequest { args: args_str });
+    let request = Request::new(GenerallyLockRequest {
+        args: args_str,
+        timeout_ms: None,
+    });
     let response = client.r_un_lock(request).await?.into_inner();
     if let Some(error_info) = response.error_info {
         panic!("can not unlock read lock: {error_info}");
@@ -565,20 +368,28 @@ async fn test_lock_refresh() -> Result<(), Box<dyn Error>> {
         metadata: LockMetadata::default(),
         priority: LockPriority::Normal,
         deadlock_detection: false,
+        cancel: None,
+        deadline: None,
     };
     let args_str = serde_json::to_string(&args)?;
 
     let mut client = node_service_time_out_client(&CLUSTER_ADDR.to_string()).await?;
 
     // Acquire lock
-    let request = Request::new(GenerallyLockRequest { args: args_str.clone() });
+    let request = Request::new(GenerallyLockRequest {
+        args: args_str.clone(),
+        timeout_ms: None,
+    });
     let response = client.lock(request).await?.into_inner();
     if let Some(error_info) = response.error_info {
         panic!("can not get lock: {error_info}");
     }
 
     // Refresh lock
-    let request = Request::new(GenerallyLockRequest { args: args_str.clone() });
+    let request = Request::new(GenerallyLockRequest {
+        args: args_str.clone(),
+        timeout_ms: None,
+    });
     let response = client.refresh(request).await?.into_inner();
     if let Some(error_info) = response.error_info {
         panic!("can not refresh lock: {error_info}");
@@ -586,7 +397,10 @@ async fn test_lock_refresh() -> Result<(), Box<dyn Error>> {
     assert!(response.success, "Lock refresh should succeed");
 
     // Unlock
-    let request = Request::new(GenerallyLockRequest { args: args_str });
+    let request = Request::new(GenerallyLockRequest {
+        args: args_str,
+        timeout_ms: None,
+    });
     let response = client.un_lock(request).await?.into_inner();
     if let Some(error_info) = response.error_info {
         panic!("can not unlock: {error_info}");
@@ -609,13 +423,18 @@ async fn test_force_unlock() -> Result<(), Box<dyn Error>> {
         metadata: LockMetadata::default(),
         priority: LockPriority::Normal,
         deadlock_detection: false,
+        cancel: None,
+        deadline: None,
     };
     let args_str = serde_json::to_string(&args)?;
 
     let mut client = node_service_time_out_client(&CLUSTER_ADDR.to_string()).await?;
 
     // Acquire lock
-    let request = Request::new(GenerallyLockRequest { args: args_str.clone() });
+    let request = Request::new(GenerallyLockRequest {
+        args: args_str.clone(),
+        timeout_ms: None,
+    });
     let response = client.lock(request).await?.into_inner();
     if let Some(error_info) = response.error_info {
         panic!("can not get lock: {error_info}");
@@ -632,9 +451,14 @@ async fn test_force_unlock() -> Result<(), Box<dyn Error>> {
         metadata: LockMetadata::default(),
         priority: LockPriority::Normal,
         deadlock_detection: false,
+        cancel: None,
+        deadline: None,
     };
     let force_args_str = serde_json::to_string(&force_args)?;
-    let request = Request::new(GenerallyLockRequest { args: force_args_str });
+    let request = Request::new(GenerallyLockRequest {
+        args: force_args_str,
+        timeout_ms: None,
+    });
     let response = client.force_un_lock(request).await?.into_inner();
     if let Some(error_info) = response.error_info {
         panic!("can not force unlock: {error_info}");
@@ -644,47 +468,412 @@ async fn test_force_unlock() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Lock node of the in-process test cluster
+///
+/// Every node keeps its own lock map like a separate server, failures are injected per node.
+#[derive(Debug)]
+pub(super) struct TestNode {
+    locks: LocalLockMap,
+    ttls: Mutex<HashMap<LockId, Duration>>,
+    offline: AtomicBool,
+    fail_release: AtomicBool,
+}
+
+impl TestNode {
+    pub(super) fn new() -> Self {
+        Self {
+            locks: LocalLockMap::new(),
+            ttls: Mutex::new(HashMap::new()),
+            offline: AtomicBool::new(false),
+            fail_release: AtomicBool::new(false),
+        }
+    }
+
+    async fn holds(&self, lock_id: &LockId) -> bool {
+        let Some(entry) = self.locks.entry(lock_id).await else { return false };
+        let entry = entry.read().await;
+        let expired = entry.expires_at.is_some_and(|exp| exp <= Instant::now());
+        !expired && (entry.writer.is_some() || !entry.readers.is_empty())
+    }
+
+    async fn acquire(&self, request: &LockRequest) -> rustfs_lock::error::Result<LockResponse> {
+        if self.offline.load(Ordering::SeqCst) {
+            return Ok(LockResponse::failure("node offline", Duration::ZERO));
+        }
+
+        let acquired = match request.lock_type {
+            LockType::Exclusive => self.locks.lock_with_ttl_id(request).await,
+            LockType::Shared => self.locks.rlock_with_ttl_id(request).await,
+        }
+        .map_err(|e| rustfs_lock::error::LockError::internal(e.to_string()))?;
+        if !acquired {
+            return Ok(LockResponse::failure("lock held", Duration::ZERO));
+        }
+
+        self.ttls.lock().await.insert(request.lock_id.clone(), request.ttl);
+        let now = SystemTime::now();
+        Ok(LockResponse::success(
+            LockInfo {
+                id: request.lock_id.clone(),
+                resource: request.resource.clone(),
+                lock_type: request.lock_type,
+                status: LockStatus::Acquired,
+                owner: request.owner.clone(),
+                acquired_at: now,
+                expires_at: now + request.ttl,
+                last_refreshed: now,
+                metadata: request.metadata.clone(),
+                priority: request.priority,
+                wait_start_time: None,
+            },
+            Duration::ZERO,
+        ))
+    }
+}
+
+#[async_trait]
+impl LockClient for TestNode {
+    async fn acquire_exclusive(&self, request: &LockRequest) -> rustfs_lock::error::Result<LockResponse> {
+        self.acquire(request).await
+    }
+
+    async fn acquire_shared(&self, request: &LockRequest) -> rustfs_lock::error::Result<LockResponse> {
+        self.acquire(request).await
+    }
+
+    async fn release(&self, lock_id: &LockId) -> rustfs_lock::error::Result<bool> {
+        if self.offline.load(Ordering::SeqCst) || self.fail_release.load(Ordering::SeqCst) {
+            return Err(rustfs_lock::error::LockError::internal("Simulated release failure"));
+        }
+        self.ttls.lock().await.remove(lock_id);
+        self.locks
+            .unlock_by_id(lock_id)
+            .await
+            .map_err(|e| rustfs_lock::error::LockError::internal(e.to_string()))?;
+        Ok(true)
+    }
+
+    async fn refresh(&self, lock_id: &LockId) -> rustfs_lock::error::Result<bool> {
+        if self.offline.load(Ordering::SeqCst) || !self.holds(lock_id).await {
+            return Ok(false);
+        }
+        let Some(ttl) = self.ttls.lock().await.get(lock_id).copied() else { return Ok(false) };
+        if let Some(entry) = self.locks.entry(lock_id).await {
+            entry.write().await.expires_at = Some(Instant::now() + ttl);
+        }
+        Ok(true)
+    }
+
+    async fn force_release(&self, lock_id: &LockId) -> rustfs_lock::error::Result<bool> {
+        self.release(lock_id).await
+    }
+
+    async fn check_status(&self, _lock_id: &LockId) -> rustfs_lock::error::Result<Option<LockInfo>> {
+        Ok(None)
+    }
+
+    async fn get_stats(&self) -> rustfs_lock::error::Result<LockStats> {
+        Ok(self.locks.get_stats().await)
+    }
+
+    async fn close(&self) -> rustfs_lock::error::Result<()> {
+        self.locks.shutdown().await;
+        Ok(())
+    }
+
+    async fn is_online(&self) -> bool {
+        !self.offline.load(Ordering::SeqCst)
+    }
+
+    async fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// In-process lock cluster standing in for a multi-node deployment
+struct TestCluster {
+    nodes: Vec<Arc<TestNode>>,
+}
+
+impl TestCluster {
+    fn new(size: usize) -> Self {
+        Self {
+            nodes: (0..size).map(|_| Arc::new(TestNode::new())).collect(),
+        }
+    }
+
+    fn ns_lock(&self, namespace: &str) -> NamespaceLock {
+        let clients = self.nodes.iter().map(|n| n.clone() as Arc<dyn LockClient>).collect();
+        NamespaceLock::with_clients(namespace.to_string(), clients)
+    }
+
+    /// Take the last `count` nodes offline
+    fn fail_nodes(&self, count: usize) {
+        for node in self.nodes.iter().rev().take(count) {
+            node.offline.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Number of nodes currently holding the lock on `resource`
+    async fn holders(&self, ns_lock: &NamespaceLock, resource: &str) -> usize {
+        let lock_id = LockId::new_deterministic(&ns_lock.get_resource_key(resource));
+        let mut count = 0;
+        for node in &self.nodes {
+            if node.holds(&lock_id).await {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    async fn refresh(&self, ns_lock: &NamespaceLock, resource: &str) -> usize {
+        let lock_id = LockId::new_deterministic(&ns_lock.get_resource_key(resource));
+        let mut refreshed = 0;
+        for node in &self.nodes {
+            if node.refresh(&lock_id).await.unwrap_or(false) {
+                refreshed += 1;
+            }
+        }
+        refreshed
+    }
+
+    async fn shutdown(&self) {
+        for node in &self.nodes {
+            let _ = node.close().await;
+        }
+    }
+}
+
+fn resources(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cluster_lock_unlock() -> Result<(), Box<dyn Error>> {
+    for size in CLUSTER_SIZES {
+        let cluster = TestCluster::new(size);
+        let ns_lock = cluster.ns_lock("test");
+        let resource = resources(&["foo"]);
+
+        let locked = ns_lock
+            .lock_batch(&resource, "owner1", Duration::from_millis(100), Duration::from_secs(10))
+            .await?;
+        assert!(locked, "lock should succeed on {size} nodes");
+        assert_eq!(cluster.holders(&ns_lock, "foo").await, size);
+
+        let locked = ns_lock
+            .lock_batch(&resource, "owner2", Duration::from_millis(100), Duration::from_secs(10))
+            .await?;
+        assert!(!locked, "second owner should not get the lock on {size} nodes");
+        assert_eq!(cluster.holders(&ns_lock, "foo").await, size, "failed attempt must not release the holder");
+
+        ns_lock.unlock_batch(&resource, "owner1").await?;
+        assert_eq!(cluster.holders(&ns_lock, "foo").await, 0);
+
+        let locked = ns_lock
+            .lock_batch(&resource, "owner2", Duration::from_millis(100), Duration::from_secs(10))
+            .await?;
+        assert!(locked, "lock should succeed after unlock on {size} nodes");
+        ns_lock.unlock_batch(&resource, "owner2").await?;
+
+        cluster.shutdown().await;
+    }
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
-#[ignore = "requires running RustFS server at localhost:9000"]
-async fn test_global_lock_map_sharing() -> Result<(), Box<dyn Error>> {
-    let endpoints = get_cluster_endpoints();
-    let clients = create_unique_clients(&endpoints).await?;
-    let ns_lock1 = NamespaceLock::with_clients("global_test".to_string(), clients.clone());
-    let ns_lock2 = NamespaceLock::with_clients("global_test".to_string(), clients);
-
-    let resource = vec!["global_test_resource".to_string()];
-
-    // First instance acquires lock
-    println!("First lock map attempting to acquire lock...");
-    let result1 = ns_lock1
-        .lock_batch(&resource, "owner1", std::time::Duration::from_secs(5), std::time::Duration::from_secs(10))
-        .await?;
-    println!("First lock result: {result1}");
-    assert!(result1, "First lock should succeed");
+async fn test_cluster_read_write_compatibility() -> Result<(), Box<dyn Error>> {
+    for size in CLUSTER_SIZES {
+        let cluster = TestCluster::new(size);
+        let ns_lock = cluster.ns_lock("test_rw");
+        let resource = resources(&["rw_resource"]);
+        let timeout = Duration::from_millis(100);
+        let ttl = Duration::from_secs(10);
 
-    // Second instance should fail to acquire the same lock
-    println!("Second lock map attempting to acquire lock...");
-    let result2 = ns_lock2
-        .lock_batch(&resource, "owner2", std::time::Duration::from_secs(1), std::time::Duration::from_secs(10))
-        .await?;
-    println!("Second lock result: {result2}");
-    assert!(!result2, "Second lock should fail because resource is already locked");
+        assert!(ns_lock.rlock_batch(&resource, "reader1", timeout, ttl).await?);
+        assert!(ns_lock.rlock_batch(&resource, "reader2", timeout, ttl).await?);
+        assert!(!ns_lock.lock_batch(&resource, "writer1", timeout, ttl).await?);
 
-    // Release lock from first instance
-    println!("First lock map releasing lock...");
-    ns_lock1.unlock_batch(&resource, "owner1").await?;
+        ns_lock.runlock_batch(&resource, "reader1").await?;
+        ns_lock.runlock_batch(&resource, "reader2").await?;
 
-    // Now second instance should be able to acquire lock
-    println!("Second lock map attempting to acquire lock again...");
-    let result3 = ns_lock2
-        .lock_batch(&resource, "owner2", std::time::Duration::from_secs(5), std::time::Duration::from_secs(10))
-        .await?;
-    println!("Third lock result: {result3}");
-    assert!(result3, "Lock should succeed after first lock is released");
+        assert!(ns_lock.lock_batch(&resource, "writer1", timeout, ttl).await?);
+        ns_lock.unlock_batch(&resource, "writer1").await?;
 
-    // Clean up
-    ns_lock2.unlock_batch(&resource, "owner2").await?;
+        cluster.shutdown().await;
+    }
+    Ok(())
+}
 
+#[tokio::test]
+#[serial]
+async fn test_cluster_batch_lock() -> Result<(), Box<dyn Error>> {
+    for size in CLUSTER_SIZES {
+        let cluster = TestCluster::new(size);
+        let ns_lock = cluster.ns_lock("test_batch");
+        let batch = resources(&["batch_resource1", "batch_resource2", "batch_resource3"]);
+        let single = resources(&["batch_resource2"]);
+        let timeout = Duration::from_millis(100);
+        let ttl = Duration::from_secs(10);
+
+        assert!(ns_lock.lock_batch(&batch, "batch_owner", timeout, ttl).await?);
+        assert!(!ns_lock.lock_batch(&single, "other_owner", timeout, ttl).await?);
+
+        ns_lock.unlock_batch(&batch, "batch_owner").await?;
+        assert!(ns_lock.lock_batch(&single, "other_owner", timeout, ttl).await?);
+        ns_lock.unlock_batch(&single, "other_owner").await?;
+
+        cluster.shutdown().await;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cluster_namespaces() -> Result<(), Box<dyn Error>> {
+    for size in CLUSTER_SIZES {
+        let cluster = TestCluster::new(size);
+        let resource = resources(&["shared_resource"]);
+        let timeout = Duration::from_millis(100);
+        let ttl = Duration::from_secs(10);
+
+        // Different namespaces never conflict
+        let ns_lock1 = cluster.ns_lock("namespace1");
+        let ns_lock2 = cluster.ns_lock("namespace2");
+        assert!(ns_lock1.lock_batch(&resource, "owner1", timeout, ttl).await?);
+        assert!(ns_lock2.lock_batch(&resource, "owner2", timeout, ttl).await?);
+
+        // Two handles on the same namespace share the node lock maps
+        let ns_lock3 = cluster.ns_lock("namespace1");
+        assert!(!ns_lock3.lock_batch(&resource, "owner3", timeout, ttl).await?);
+        ns_lock1.unlock_batch(&resource, "owner1").await?;
+        assert!(ns_lock3.lock_batch(&resource, "owner3", timeout, ttl).await?);
+
+        ns_lock2.unlock_batch(&resource, "owner2").await?;
+        ns_lock3.unlock_batch(&resource, "owner3").await?;
+
+        cluster.shutdown().await;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cluster_partial_node_failures() -> Result<(), Box<dyn Error>> {
+    for size in CLUSTER_SIZES {
+        for failed in 1..=size {
+            let cluster = TestCluster::new(size);
+            cluster.fail_nodes(failed);
+            let ns_lock = cluster.ns_lock("test_failures");
+
+            let request = LockRequest::new(ns_lock.get_resource_key("resource"), LockType::Exclusive, "owner")
+                .with_acquire_timeout(Duration::from_millis(100))
+                .with_ttl(Duration::from_secs(10));
+            let response = ns_lock.acquire_lock(&request).await?;
+            assert!(!response.success, "lock must fail with {failed}/{size} nodes down");
+
+            // Every node must grant the lock, reaching a majority is not enough and gets rolled back
+            let error = response.error.unwrap_or_default();
+            let expected = format!("{}/{} required", size - failed, ns_lock.quorum());
+            assert!(error.contains(&expected), "unexpected error with {failed}/{size} down: {error}");
+            assert_eq!(cluster.holders(&ns_lock, "resource").await, 0, "rollback must leave no lock behind");
+
+            cluster.shutdown().await;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cluster_release_failure() -> Result<(), Box<dyn Error>> {
+    for size in CLUSTER_SIZES {
+        let cluster = TestCluster::new(size);
+        let ns_lock = cluster.ns_lock("test_release_failure");
+        let resource = resources(&["release_resource"]);
+        let ttl = Duration::from_millis(300);
+
+        assert!(
+            ns_lock
+                .lock_batch(&resource, "owner1", Duration::from_millis(100), ttl)
+                .await?
+        );
+
+        // One node fails to release, it keeps the lock until the ttl runs out
+        cluster.nodes[0].fail_release.store(true, Ordering::SeqCst);
+        ns_lock.unlock_batch(&resource, "owner1").await?;
+        assert_eq!(cluster.holders(&ns_lock, "release_resource").await, 1);
+        assert!(
+            !ns_lock
+                .lock_batch(&resource, "owner2", Duration::from_millis(50), ttl)
+                .await?
+        );
+
+        cluster.nodes[0].fail_release.store(false, Ordering::SeqCst);
+        sleep(ttl + Duration::from_millis(100)).await;
+        assert!(
+            ns_lock
+                .lock_batch(&resource, "owner2", Duration::from_millis(100), ttl)
+                .await?
+        );
+        ns_lock.unlock_batch(&resource, "owner2").await?;
+
+        cluster.shutdown().await;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cluster_refresh_expiry() -> Result<(), Box<dyn Error>> {
+    for size in CLUSTER_SIZES {
+        let cluster = TestCluster::new(size);
+        let ns_lock = cluster.ns_lock("test_refresh");
+        let resource = resources(&["refresh_resource"]);
+        let ttl = Duration::from_millis(300);
+
+        assert!(
+            ns_lock
+                .lock_batch(&resource, "owner1", Duration::from_millis(100), ttl)
+                .await?
+        );
+
+        // Refreshing keeps the lock alive well past its original ttl
+        for _ in 0..4 {
+            sleep(Duration::from_millis(150)).await;
+            assert_eq!(cluster.refresh(&ns_lock, "refresh_resource").await, size);
+        }
+        assert_eq!(cluster.holders(&ns_lock, "refresh_resource").await, size);
+        assert!(
+            !ns_lock
+                .lock_batch(&resource, "owner2", Duration::from_millis(50), ttl)
+                .await?
+        );
+
+        // A node that misses the refresh lets its copy expire while the others keep theirs
+        cluster.fail_nodes(1);
+        sleep(Duration::from_millis(150)).await;
+        assert_eq!(cluster.refresh(&ns_lock, "refresh_resource").await, size - 1);
+        sleep(Duration::from_millis(200)).await;
+        assert_eq!(cluster.holders(&ns_lock, "refresh_resource").await, size - 1);
+
+        // Once the owner is gone nobody renews the lease, the lock expires everywhere and can be taken over
+        cluster.nodes[size - 1].offline.store(false, Ordering::SeqCst);
+        drop(ns_lock);
+        let ns_lock = cluster.ns_lock("test_refresh");
+        sleep(ttl + Duration::from_millis(100)).await;
+        assert_eq!(cluster.holders(&ns_lock, "refresh_resource").await, 0);
+        assert!(
+            ns_lock
+                .lock_batch(&resource, "owner2", Duration::from_millis(100), ttl)
+                .await?
+        );
+        assert_eq!(cluster.refresh(&ns_lock, "refresh_resource").await, size);
+        ns_lock.unlock_batch(&resource, "owner2").await?;
+
+        cluster.shutdown().await;
+    }
     Ok(())
 }
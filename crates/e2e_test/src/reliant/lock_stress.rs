@@ -0,0 +1,470 @@
+#![cfg(test)]
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stress harness for distributed locks.
+//!
+//! Hundreds of lockers take exclusive and shared locks on a few hot resources through namespace
+//! locks, while every call to a node may be delayed or dropped, before or after the node acted on
+//! it. Each resource tracks who holds it, so a writer next to anyone else fails the run, and every
+//! locker must get its turn often enough.
+//!
+//! The nodes are in-process by default. Setting `RUSTFS_LOCK_STRESS_ENDPOINTS` to the comma
+//! separated node addresses of a running cluster, e.g. the one of `docker-compose.yml`, runs the
+//! ignored `test_lock_stress_cluster` against it, and `test_lock_partition_cluster` which cuts
+//! nodes off with the RPC faults of `rustfs_protos::faults`. `RUSTFS_LOCK_STRESS_SEED` replays the
+//! faults of an earlier run.
+
+use async_trait::async_trait;
+use rustfs_lock::client::{LockClient, remote::RemoteClient};
+use rustfs_lock::types::{LockInfo, LockResponse, LockStats};
+use rustfs_lock::{LockId, LockRequest, LockType, NamespaceLock, QuorumPolicy};
+use rustfs_protos::faults;
+use serial_test::serial;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::lock::TestNode;
+
+const ENV_LOCK_STRESS_ENDPOINTS: &str = "RUSTFS_LOCK_STRESS_ENDPOINTS";
+const ENV_LOCK_STRESS_SEED: &str = "RUSTFS_LOCK_STRESS_SEED";
+
+/// Shape of a stress run
+#[derive(Debug, Clone)]
+struct StressConfig {
+    lockers: usize,
+    resources: usize,
+    /// Lock attempts of every locker
+    rounds: usize,
+    /// One lock attempt in `writer_every` is exclusive, the others shared
+    writer_every: usize,
+    max_hold: Duration,
+    acquire_timeout: Duration,
+    ttl: Duration,
+    /// Longest delay injected into a call to a node
+    max_delay: Duration,
+    /// Share of the calls to a node that are dropped
+    drop_rate: f64,
+    seed: u64,
+}
+
+impl Default for StressConfig {
+    fn default() -> Self {
+        Self {
+            lockers: 200,
+            resources: 8,
+            rounds: 5,
+            writer_every: 3,
+            max_hold: Duration::from_millis(5),
+            acquire_timeout: Duration::from_secs(10),
+            ttl: Duration::from_secs(1),
+            max_delay: Duration::from_millis(3),
+            drop_rate: 0.005,
+            seed: std::env::var(ENV_LOCK_STRESS_SEED)
+                .ok()
+                .and_then(|seed| seed.parse().ok())
+                .unwrap_or_else(|| {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                    now.as_nanos() as u64 ^ std::process::id() as u64
+                }),
+        }
+    }
+}
+
+/// Small deterministic generator, so a seed replays the faults of a run
+#[derive(Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn chance(&mut self, rate: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < rate
+    }
+
+    fn duration(&mut self, max: Duration) -> Duration {
+        Duration::from_micros(self.next() % (max.as_micros() as u64 + 1))
+    }
+}
+
+/// How an injected fault hits a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    None,
+    /// The request never reaches the node
+    DropRequest,
+    /// The node acts on the request, its response is lost
+    DropResponse,
+}
+
+/// Lock client delaying and dropping the calls to the node behind it
+#[derive(Debug)]
+struct FlakyClient {
+    inner: Arc<dyn LockClient>,
+    rng: Mutex<SplitMix64>,
+    max_delay: Duration,
+    drop_rate: f64,
+    dropped: AtomicUsize,
+}
+
+impl FlakyClient {
+    fn new(inner: Arc<dyn LockClient>, config: &StressConfig, node: usize) -> Self {
+        Self {
+            inner,
+            rng: Mutex::new(SplitMix64(config.seed.wrapping_add(node as u64))),
+            max_delay: config.max_delay,
+            drop_rate: config.drop_rate,
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Wait out the injected delay and pick the fault of a call
+    async fn fault(&self) -> Fault {
+        let (delay, fault) = {
+            let mut rng = self.rng.lock().unwrap();
+            let fault = match rng.chance(self.drop_rate) {
+                false => Fault::None,
+                true if rng.chance(0.5) => Fault::DropRequest,
+                true => Fault::DropResponse,
+            };
+            (rng.duration(self.max_delay), fault)
+        };
+        tokio::time::sleep(delay).await;
+        if fault != Fault::None {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        fault
+    }
+
+    async fn call<T>(
+        &self,
+        op: impl std::future::Future<Output = rustfs_lock::error::Result<T>>,
+    ) -> rustfs_lock::error::Result<T> {
+        let fault = self.fault().await;
+        if fault == Fault::DropRequest {
+            return Err(rustfs_lock::error::LockError::internal("request dropped"));
+        }
+        let result = op.await;
+        if fault == Fault::DropResponse {
+            return Err(rustfs_lock::error::LockError::internal("response dropped"));
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl LockClient for FlakyClient {
+    async fn acquire_exclusive(&self, request: &LockRequest) -> rustfs_lock::error::Result<LockResponse> {
+        self.call(self.inner.acquire_exclusive(request)).await
+    }
+
+    async fn acquire_shared(&self, request: &LockRequest) -> rustfs_lock::error::Result<LockResponse> {
+        self.call(self.inner.acquire_shared(request)).await
+    }
+
+    async fn release(&self, lock_id: &LockId) -> rustfs_lock::error::Result<bool> {
+        self.call(self.inner.release(lock_id)).await
+    }
+
+    async fn refresh(&self, lock_id: &LockId) -> rustfs_lock::error::Result<bool> {
+        self.call(self.inner.refresh(lock_id)).await
+    }
+
+    async fn force_release(&self, lock_id: &LockId) -> rustfs_lock::error::Result<bool> {
+        self.inner.force_release(lock_id).await
+    }
+
+    async fn check_status(&self, lock_id: &LockId) -> rustfs_lock::error::Result<Option<LockInfo>> {
+        self.inner.check_status(lock_id).await
+    }
+
+    async fn get_stats(&self) -> rustfs_lock::error::Result<LockStats> {
+        self.inner.get_stats().await
+    }
+
+    async fn close(&self) -> rustfs_lock::error::Result<()> {
+        self.inner.close().await
+    }
+
+    async fn is_online(&self) -> bool {
+        self.inner.is_online().await
+    }
+
+    async fn is_local(&self) -> bool {
+        self.inner.is_local().await
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        self.inner.endpoint()
+    }
+}
+
+/// Who holds a resource according to the lockers themselves
+#[derive(Debug, Default)]
+struct Occupancy {
+    writers: AtomicUsize,
+    readers: AtomicUsize,
+}
+
+impl Occupancy {
+    /// Enter the resource, the error describes the broken invariant
+    fn enter(&self, lock_type: LockType) -> Result<(), String> {
+        match lock_type {
+            LockType::Exclusive => {
+                let writers = self.writers.fetch_add(1, Ordering::SeqCst) + 1;
+                let readers = self.readers.load(Ordering::SeqCst);
+                if writers > 1 || readers > 0 {
+                    return Err(format!("writer entered next to {} writers and {readers} readers", writers - 1));
+                }
+            }
+            LockType::Shared => {
+                self.readers.fetch_add(1, Ordering::SeqCst);
+                let writers = self.writers.load(Ordering::SeqCst);
+                if writers > 0 {
+                    return Err(format!("reader entered next to {writers} writers"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn leave(&self, lock_type: LockType) {
+        match lock_type {
+            LockType::Exclusive => self.writers.fetch_sub(1, Ordering::SeqCst),
+            LockType::Shared => self.readers.fetch_sub(1, Ordering::SeqCst),
+        };
+    }
+}
+
+/// What a locker went through
+#[derive(Debug, Default)]
+struct LockerReport {
+    acquired: usize,
+    longest_wait: Duration,
+}
+
+/// What a stress run went through
+#[derive(Debug)]
+struct StressReport {
+    lockers: Vec<LockerReport>,
+    violations: Vec<String>,
+    dropped: usize,
+    elapsed: Duration,
+}
+
+async fn run_stress(config: StressConfig, nodes: Vec<Arc<dyn LockClient>>) -> StressReport {
+    println!("lock stress run with seed {} on {} nodes", config.seed, nodes.len());
+    let flaky: Vec<_> = nodes
+        .into_iter()
+        .enumerate()
+        .map(|(node, client)| Arc::new(FlakyClient::new(client, &config, node)))
+        .collect();
+    let clients = flaky.iter().map(|c| c.clone() as Arc<dyn LockClient>).collect();
+    let ns_lock = Arc::new(
+        NamespaceLock::with_clients(format!("stress-{}", config.seed), clients).with_quorum_policy(QuorumPolicy::Majority),
+    );
+    let resources: Arc<Vec<(String, Occupancy)>> = Arc::new(
+        (0..config.resources)
+            .map(|i| (format!("hot-{i}"), Occupancy::default()))
+            .collect(),
+    );
+    let violations = Arc::new(Mutex::new(Vec::new()));
+
+    let start = Instant::now();
+    let lockers: Vec<_> = (0..config.lockers)
+        .map(|locker| {
+            let (config, ns_lock, resources, violations) =
+                (config.clone(), ns_lock.clone(), resources.clone(), violations.clone());
+            tokio::spawn(async move {
+                let mut rng = SplitMix64(config.seed ^ (locker as u64).rotate_left(32));
+                let owner = format!("locker-{locker}");
+                let mut report = LockerReport::default();
+                for round in 0..config.rounds {
+                    let (resource, occupancy) = &resources[rng.next() as usize % resources.len()];
+                    let lock_type = if (locker + round) % config.writer_every == 0 {
+                        LockType::Exclusive
+                    } else {
+                        LockType::Shared
+                    };
+
+                    let waiting = Instant::now();
+                    let guard = ns_lock
+                        .get_lock(
+                            std::slice::from_ref(resource),
+                            &owner,
+                            lock_type,
+                            config.acquire_timeout,
+                            config.ttl,
+                            None,
+                        )
+                        .await;
+                    report.longest_wait = report.longest_wait.max(waiting.elapsed());
+                    let Ok(Some(guard)) = guard else { continue };
+                    report.acquired += 1;
+
+                    if let Err(violation) = occupancy.enter(lock_type) {
+                        violations.lock().unwrap().push(format!("{resource}: {violation} ({owner})"));
+                    }
+                    tokio::time::sleep(rng.duration(config.max_hold)).await;
+                    occupancy.leave(lock_type);
+                    guard.release().await;
+                }
+                report
+            })
+        })
+        .collect();
+
+    let mut reports = Vec::with_capacity(lockers.len());
+    for locker in lockers {
+        reports.push(locker.await.expect("locker panicked"));
+    }
+    let violations = std::mem::take(&mut *violations.lock().unwrap());
+    StressReport {
+        lockers: reports,
+        violations,
+        dropped: flaky.iter().map(|c| c.dropped.load(Ordering::Relaxed)).sum(),
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Assert mutual exclusion held and no locker starved
+fn check_report(config: &StressConfig, report: &StressReport) {
+    let acquired: usize = report.lockers.iter().map(|r| r.acquired).sum();
+    let least = report.lockers.iter().map(|r| r.acquired).min().unwrap_or_default();
+    let longest_wait = report.lockers.iter().map(|r| r.longest_wait).max().unwrap_or_default();
+    println!(
+        "seed {}: {acquired}/{} locks in {:?}, {} calls dropped, least acquired by a locker {least}, longest wait {longest_wait:?}",
+        config.seed,
+        config.lockers * config.rounds,
+        report.elapsed,
+        report.dropped
+    );
+
+    assert!(
+        report.violations.is_empty(),
+        "seed {}: mutual exclusion broken: {:?}",
+        config.seed,
+        report.violations
+    );
+    // Dropped calls cost a locker an attempt now and then, never most of its turns
+    assert!(
+        least * 2 >= config.rounds,
+        "seed {}: a locker got only {least} of {} locks",
+        config.seed,
+        config.rounds
+    );
+    assert!(
+        longest_wait <= config.acquire_timeout + config.ttl,
+        "seed {}: a locker waited {longest_wait:?}",
+        config.seed
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[serial]
+async fn test_lock_stress_in_process() {
+    let config = StressConfig::default();
+    let nodes = (0..5).map(|_| Arc::new(TestNode::new()) as Arc<dyn LockClient>).collect();
+    let report = run_stress(config.clone(), nodes).await;
+    check_report(&config, &report);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[serial]
+#[ignore = "requires a running RustFS cluster listed in RUSTFS_LOCK_STRESS_ENDPOINTS"]
+async fn test_lock_stress_cluster() {
+    let nodes = remote_clients(&cluster_endpoints());
+    let config = StressConfig {
+        max_hold: Duration::from_millis(20),
+        ..Default::default()
+    };
+    let report = run_stress(config.clone(), nodes).await;
+    check_report(&config, &report);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[serial]
+#[ignore = "requires a running RustFS cluster listed in RUSTFS_LOCK_STRESS_ENDPOINTS"]
+async fn test_lock_partition_cluster() {
+    let endpoints = cluster_endpoints();
+    assert!(endpoints.len() >= 3, "partitions need a cluster of at least 3 nodes");
+    let ns_lock = Arc::new(
+        NamespaceLock::with_clients("partition".to_string(), remote_clients(&endpoints))
+            .with_quorum_policy(QuorumPolicy::Majority),
+    );
+    let quorum = ns_lock.quorum();
+    let resources = vec!["partitioned".to_string()];
+    let (timeout, ttl) = (Duration::from_secs(2), Duration::from_secs(3));
+    let partition = |endpoints: &[String]| endpoints.iter().for_each(|endpoint| faults::partition(endpoint));
+
+    // A minority cut off does not keep the lock from being granted, nor from being exclusive
+    partition(&endpoints[quorum..]);
+    let guard = ns_lock
+        .get_lock(&resources, "owner1", LockType::Exclusive, timeout, ttl, None)
+        .await
+        .unwrap()
+        .expect("lock granted by the majority");
+    let other = ns_lock
+        .get_lock(&resources, "owner2", LockType::Exclusive, timeout, ttl, None)
+        .await
+        .unwrap();
+    assert!(other.is_none(), "lock granted twice with a minority cut off");
+    guard.release().await;
+    faults::heal_all();
+
+    // Cut off from a majority nobody gets the lock
+    partition(&endpoints[endpoints.len() - quorum..]);
+    let guard = ns_lock
+        .get_lock(&resources, "owner3", LockType::Exclusive, timeout, ttl, None)
+        .await
+        .unwrap();
+    assert!(guard.is_none(), "lock granted without a majority");
+    faults::heal_all();
+
+    // A holder cut off from a majority loses its lease
+    let guard = ns_lock
+        .get_lock(&resources, "owner4", LockType::Exclusive, timeout, ttl, None)
+        .await
+        .unwrap()
+        .expect("lock granted once healed");
+    partition(&endpoints[..quorum]);
+    tokio::time::timeout(ttl * 2, guard.lost())
+        .await
+        .expect("lease lost once cut off from the majority");
+    faults::heal_all();
+    guard.release().await;
+}
+
+/// Addresses of the nodes of the cluster under test
+fn cluster_endpoints() -> Vec<String> {
+    std::env::var(ENV_LOCK_STRESS_ENDPOINTS)
+        .unwrap_or_else(|_| "http://localhost:9000".to_string())
+        .split(',')
+        .map(|endpoint| endpoint.trim().to_string())
+        .collect()
+}
+
+fn remote_clients(endpoints: &[String]) -> Vec<Arc<dyn LockClient>> {
+    endpoints
+        .iter()
+        .map(|endpoint| Arc::new(RemoteClient::new(endpoint.clone())) as Arc<dyn LockClient>)
+        .collect()
+}
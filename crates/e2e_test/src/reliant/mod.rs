@@ -14,5 +14,6 @@
 
 mod lifecycle;
 mod lock;
+mod lock_stress;
 mod node_interact_test;
 mod sql;
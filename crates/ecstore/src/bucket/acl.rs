@@ -0,0 +1,128 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Canned ACL of a bucket
+//!
+//! The ACL is kept in the bucket metadata, apart from the bucket policy. Requests are evaluated against
+//! the bucket policy together with the statements the ACL amounts to, unless the public access block of
+//! the bucket ignores public ACLs. Only the `private`, `public-read` and `public-read-write` canned ACLs
+//! grant anything.
+
+use rustfs_policy::policy::statement::BPStatement;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::metadata_sys;
+use crate::error::{Error, Result};
+
+pub const CANNED_ACL_PRIVATE: &str = "private";
+pub const CANNED_ACL_PUBLIC_READ: &str = "public-read";
+pub const CANNED_ACL_PUBLIC_READ_WRITE: &str = "public-read-write";
+
+/// Bucket actions everyone is allowed by a public bucket ACL
+const PUBLIC_READ_BUCKET_ACTIONS: &[&str] = &["s3:GetBucketLocation", "s3:ListBucket"];
+const PUBLIC_WRITE_BUCKET_ACTIONS: &[&str] = &["s3:ListBucketMultipartUploads"];
+
+/// Object actions everyone is allowed by a public bucket ACL
+const PUBLIC_READ_OBJECT_ACTIONS: &[&str] = &["s3:GetObject"];
+const PUBLIC_WRITE_OBJECT_ACTIONS: &[&str] = &[
+    "s3:PutObject",
+    "s3:DeleteObject",
+    "s3:AbortMultipartUpload",
+    "s3:ListMultipartUploadParts",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketAcl {
+    /// Name of the canned ACL, private when empty
+    #[serde(default)]
+    pub canned: String,
+}
+
+impl BucketAcl {
+    pub fn new(canned: &str) -> Self {
+        Self {
+            canned: canned.to_owned(),
+        }
+    }
+
+    pub fn unmarshal(buf: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(buf)?)
+    }
+
+    pub fn marshal(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// ACL of `bucket`, private when it has none
+    pub async fn get(bucket: &str) -> Result<Self> {
+        match metadata_sys::get_bucket_acl_config(bucket).await {
+            Ok((acl, _)) => Ok(acl),
+            Err(Error::ConfigNotFound) => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Statements of the bucket policy the ACL of `bucket` is evaluated as
+    pub fn statements(&self, bucket: &str) -> Vec<BPStatement> {
+        let (bucket_actions, object_actions) = match self.canned.as_str() {
+            CANNED_ACL_PUBLIC_READ => (PUBLIC_READ_BUCKET_ACTIONS.to_vec(), PUBLIC_READ_OBJECT_ACTIONS.to_vec()),
+            CANNED_ACL_PUBLIC_READ_WRITE => (
+                [PUBLIC_READ_BUCKET_ACTIONS, PUBLIC_WRITE_BUCKET_ACTIONS].concat(),
+                [PUBLIC_READ_OBJECT_ACTIONS, PUBLIC_WRITE_OBJECT_ACTIONS].concat(),
+            ),
+            _ => return Vec::new(),
+        };
+
+        vec![
+            public_statement(&bucket_actions, &format!("arn:aws:s3:::{bucket}")),
+            public_statement(&object_actions, &format!("arn:aws:s3:::{bucket}/*")),
+        ]
+    }
+}
+
+/// Statement a public ACL of an object is evaluated as
+///
+/// Writing an object is a permission of its bucket, a public object can only be read.
+pub fn public_object_statement(bucket: &str, object: &str) -> BPStatement {
+    public_statement(PUBLIC_READ_OBJECT_ACTIONS, &format!("arn:aws:s3:::{bucket}/{object}"))
+}
+
+/// Statement allowing everyone the `actions` on `resource`
+fn public_statement(actions: &[&str], resource: &str) -> BPStatement {
+    let statement = json!({
+        "Effect": "Allow",
+        "Principal": {"AWS": ["*"]},
+        "Action": actions,
+        "Resource": [resource],
+    });
+    // Actions deserialize from borrowed strings, which a `Value` does not hand out
+    serde_json::from_str(&statement.to_string()).expect("canned ACL statements are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_acl_statements() {
+        assert!(BucketAcl::default().statements("mybucket").is_empty());
+        assert!(BucketAcl::new(CANNED_ACL_PRIVATE).statements("mybucket").is_empty());
+        assert_eq!(BucketAcl::new(CANNED_ACL_PUBLIC_READ).statements("mybucket").len(), 2);
+
+        let acl = BucketAcl::new(CANNED_ACL_PUBLIC_READ_WRITE);
+        assert_eq!(BucketAcl::unmarshal(&acl.marshal().unwrap()).unwrap(), acl);
+    }
+}
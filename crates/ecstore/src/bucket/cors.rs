@@ -0,0 +1,124 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CORS configuration of a bucket: what S3 accepts in one, and the rule a cross-origin request matches
+
+use s3s::dto::{CORSConfiguration, CORSRule};
+
+use crate::error::{Error, Result};
+
+/// Most rules a CORS configuration may have
+pub const MAX_CORS_RULES: usize = 100;
+
+/// Methods a CORS rule may allow
+const CORS_METHODS: &[&str] = &["GET", "PUT", "POST", "DELETE", "HEAD"];
+
+pub fn validate_cors_config(config: &CORSConfiguration) -> Result<()> {
+    if config.cors_rules.is_empty() {
+        return Err(Error::other("a CORS configuration needs at least one rule"));
+    }
+    if config.cors_rules.len() > MAX_CORS_RULES {
+        return Err(Error::other(format!("a CORS configuration can have at most {MAX_CORS_RULES} rules")));
+    }
+
+    for rule in &config.cors_rules {
+        if rule.allowed_methods.is_empty() || rule.allowed_origins.is_empty() {
+            return Err(Error::other("a CORS rule needs an AllowedMethod and an AllowedOrigin"));
+        }
+        if let Some(method) = rule.allowed_methods.iter().find(|m| !CORS_METHODS.contains(&m.as_str())) {
+            return Err(Error::other(format!(
+                "Found unsupported HTTP method in CORS config. Unsupported method is {method}"
+            )));
+        }
+        let patterns = rule.allowed_origins.iter().chain(rule.allowed_headers.iter().flatten());
+        if let Some(pattern) = patterns.into_iter().find(|p| p.matches('*').count() > 1) {
+            return Err(Error::other(format!("{pattern} can not have more than one wildcard")));
+        }
+        if rule.max_age_seconds.is_some_and(|age| age < 0) {
+            return Err(Error::other("MaxAgeSeconds can not be negative"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `value` matches `pattern`, where a `*` stands for any run of characters
+fn is_match(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            value.len() >= prefix.len() + suffix.len() && value.starts_with(prefix) && value.ends_with(suffix)
+        }
+        None => pattern == value,
+    }
+}
+
+/// First rule of `config` allowing a request from `origin` with `method` and the `headers`, as a
+/// preflight request names them in `Access-Control-Request-Headers`
+pub fn find_cors_rule<'a>(config: &'a CORSConfiguration, origin: &str, method: &str, headers: &[&str]) -> Option<&'a CORSRule> {
+    config.cors_rules.iter().find(|rule| {
+        rule.allowed_origins.iter().any(|pattern| is_match(pattern, origin))
+            && rule.allowed_methods.iter().any(|allowed| allowed == method)
+            && headers.iter().all(|header| {
+                let header = header.to_ascii_lowercase();
+                rule.allowed_headers
+                    .iter()
+                    .flatten()
+                    .any(|pattern| is_match(&pattern.to_ascii_lowercase(), &header))
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(origins: &[&str], methods: &[&str], headers: &[&str]) -> CORSRule {
+        CORSRule {
+            allowed_origins: origins.iter().map(|s| s.to_string()).collect(),
+            allowed_methods: methods.iter().map(|s| s.to_string()).collect(),
+            allowed_headers: (!headers.is_empty()).then(|| headers.iter().map(|s| s.to_string()).collect()),
+            expose_headers: None,
+            id: None,
+            max_age_seconds: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_cors_config() {
+        let config = |rules| CORSConfiguration { cors_rules: rules };
+        assert!(validate_cors_config(&config(vec![rule(&["*"], &["GET"], &[])])).is_ok());
+        assert!(validate_cors_config(&config(vec![])).is_err());
+        assert!(validate_cors_config(&config(vec![rule(&["*"], &["PATCH"], &[])])).is_err());
+        assert!(validate_cors_config(&config(vec![rule(&["https://*.*.com"], &["GET"], &[])])).is_err());
+        assert!(validate_cors_config(&config(vec![rule(&[], &["GET"], &[])])).is_err());
+    }
+
+    #[test]
+    fn test_find_cors_rule() {
+        let config = CORSConfiguration {
+            cors_rules: vec![
+                rule(&["https://*.example.com"], &["GET", "PUT"], &["x-amz-*", "Content-Type"]),
+                rule(&["*"], &["GET"], &[]),
+            ],
+        };
+        let find =
+            |origin, method, headers: &[&str]| find_cors_rule(&config, origin, method, headers).map(|r| r.allowed_methods.len());
+
+        assert_eq!(find("https://app.example.com", "PUT", &["content-type", "x-amz-date"]), Some(2));
+        assert_eq!(find("https://app.example.com", "PUT", &["authorization"]), None);
+        assert_eq!(find("https://example.org", "PUT", &[]), None);
+        assert_eq!(find("https://example.org", "GET", &[]), Some(1));
+        assert_eq!(find("https://example.org", "GET", &["x-amz-date"]), None);
+    }
+}
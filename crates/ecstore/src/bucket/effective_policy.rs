@@ -0,0 +1,365 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Effective bucket policies
+//!
+//! Resolves, for a bucket and an optional prefix, what the server applies to objects written
+//! there: server settings merged with the bucket configuration, and the lifecycle and replication
+//! rules whose filter covers the prefix. It also tells whether the bucket is open to anonymous callers.
+
+use std::collections::BTreeMap;
+
+use s3s::dto::{BucketLifecycleConfiguration, ReplicationConfiguration};
+use serde::Serialize;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+use crate::{
+    bucket::{
+        encryption::{BucketEncryptionPolicy, ObjectEncryption},
+        metadata_sys,
+        public_access::{PublicAccessBlock, is_bucket_public},
+        quota::BucketQuota,
+    },
+    compress::{MIN_COMPRESSIBLE_SIZE, STANDARD_EXCLUDE_COMPRESS_CONTENT_TYPES, STANDARD_EXCLUDE_COMPRESS_EXTENSIONS},
+    error::{Error, Result},
+};
+
+/// Where an effective setting comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicySource {
+    /// Server wide setting, the bucket has no say
+    Global,
+    /// Bucket configuration
+    Bucket,
+    /// Nothing configured
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveEncryption {
+    pub source: PolicySource,
+    /// Encryption applied to uploads without SSE headers
+    pub default_algorithm: Option<String>,
+    pub kms_key_id: Option<String>,
+    pub bucket_key_enabled: bool,
+    pub deny_unencrypted_uploads: bool,
+    pub kms_context: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveCompression {
+    pub source: PolicySource,
+    pub enabled: bool,
+    pub min_size: usize,
+    pub excluded_extensions: Vec<String>,
+    pub excluded_content_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveLifecycleRule {
+    pub id: Option<String>,
+    pub status: String,
+    pub prefix: String,
+    /// The rule only covers part of the requested prefix
+    pub partial: bool,
+    pub expiration_days: Option<i32>,
+    pub expiration_date: Option<String>,
+    pub transition_days: Option<i32>,
+    pub transition_storage_class: Option<String>,
+    pub noncurrent_expiration_days: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveReplicationRule {
+    pub id: Option<String>,
+    pub status: String,
+    pub priority: Option<i32>,
+    pub prefix: String,
+    /// The rule only covers part of the requested prefix
+    pub partial: bool,
+    pub destination: String,
+    pub delete_marker_replication: bool,
+    pub delete_replication: bool,
+}
+
+/// Everything applied to objects under `bucket/prefix`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectivePolicy {
+    pub bucket: String,
+    pub prefix: String,
+    pub encryption: EffectiveEncryption,
+    pub compression: EffectiveCompression,
+    pub lifecycle: Vec<EffectiveLifecycleRule>,
+    pub replication: Vec<EffectiveReplicationRule>,
+    pub quota: Option<BucketQuota>,
+    /// The bucket policy lets anonymous callers in, once the public access block applies
+    pub public: bool,
+    pub public_access_block: PublicAccessBlock,
+}
+
+/// A missing bucket configuration is no error here, it just resolves to nothing
+fn optional<T>(result: Result<(T, OffsetDateTime)>) -> Result<Option<T>> {
+    match result {
+        Ok((config, _)) => Ok(Some(config)),
+        Err(Error::ConfigNotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether a rule filtering on `rule_prefix` covers objects under `prefix`, and whether only partly
+fn covers(rule_prefix: &str, prefix: &str) -> Option<bool> {
+    if prefix.starts_with(rule_prefix) {
+        Some(false)
+    } else if rule_prefix.starts_with(prefix) {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+fn format_date(date: impl Into<OffsetDateTime>) -> Option<String> {
+    date.into().format(&Rfc3339).ok()
+}
+
+pub fn resolve_encryption(default: Option<ObjectEncryption>, policy: BucketEncryptionPolicy) -> EffectiveEncryption {
+    let source = if default.is_some() || policy != BucketEncryptionPolicy::default() {
+        PolicySource::Bucket
+    } else {
+        PolicySource::None
+    };
+    EffectiveEncryption {
+        source,
+        default_algorithm: default.as_ref().map(|d| d.sse_type.clone()),
+        kms_key_id: default.as_ref().and_then(|d| d.kms_key_id.clone()),
+        bucket_key_enabled: default.as_ref().is_some_and(|d| d.bucket_key_enabled),
+        deny_unencrypted_uploads: policy.deny_unencrypted_uploads,
+        kms_context: policy.kms_context,
+    }
+}
+
+/// Compression is a server setting, there is no bucket compression configuration
+pub fn resolve_compression(enabled: bool) -> EffectiveCompression {
+    EffectiveCompression {
+        source: PolicySource::Global,
+        enabled,
+        min_size: MIN_COMPRESSIBLE_SIZE,
+        excluded_extensions: STANDARD_EXCLUDE_COMPRESS_EXTENSIONS.iter().map(|s| s.to_string()).collect(),
+        excluded_content_types: STANDARD_EXCLUDE_COMPRESS_CONTENT_TYPES
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+    }
+}
+
+/// Lifecycle rules covering `prefix`, disabled ones included so operators see why nothing happens
+pub fn resolve_lifecycle(config: &BucketLifecycleConfiguration, prefix: &str) -> Vec<EffectiveLifecycleRule> {
+    config
+        .rules
+        .iter()
+        .filter_map(|rule| {
+            let filter = rule.filter.as_ref();
+            let rule_prefix = filter
+                .and_then(|f| f.prefix.clone().or_else(|| f.and.as_ref().and_then(|a| a.prefix.clone())))
+                .or_else(|| rule.prefix.clone())
+                .unwrap_or_default();
+            let partial = covers(&rule_prefix, prefix)?;
+
+            let transition = rule.transitions.as_ref().and_then(|t| t.first());
+            Some(EffectiveLifecycleRule {
+                id: rule.id.clone(),
+                status: rule.status.as_str().to_string(),
+                prefix: rule_prefix,
+                partial,
+                expiration_days: rule.expiration.as_ref().and_then(|e| e.days),
+                expiration_date: rule.expiration.as_ref().and_then(|e| e.date.clone()).and_then(format_date),
+                transition_days: transition.and_then(|t| t.days),
+                transition_storage_class: transition
+                    .and_then(|t| t.storage_class.as_ref())
+                    .map(|s| s.as_str().to_string()),
+                noncurrent_expiration_days: rule.noncurrent_version_expiration.as_ref().and_then(|e| e.noncurrent_days),
+            })
+        })
+        .collect()
+}
+
+/// Replication rules covering `prefix`, in the order they are evaluated
+pub fn resolve_replication(config: &ReplicationConfiguration, prefix: &str) -> Vec<EffectiveReplicationRule> {
+    let mut rules: Vec<_> = config
+        .rules
+        .iter()
+        .filter_map(|rule| {
+            let filter = rule.filter.as_ref();
+            let rule_prefix = filter
+                .and_then(|f| f.prefix.clone().or_else(|| f.and.as_ref().and_then(|a| a.prefix.clone())))
+                .or_else(|| rule.prefix.clone())
+                .unwrap_or_default();
+            let partial = covers(&rule_prefix, prefix)?;
+
+            Some(EffectiveReplicationRule {
+                id: rule.id.clone(),
+                status: rule.status.as_str().to_string(),
+                priority: rule.priority,
+                prefix: rule_prefix,
+                partial,
+                destination: rule.destination.bucket.clone(),
+                delete_marker_replication: rule
+                    .delete_marker_replication
+                    .as_ref()
+                    .and_then(|d| d.status.as_ref())
+                    .is_some_and(|s| s.as_str() == "Enabled"),
+                delete_replication: rule
+                    .delete_replication
+                    .as_ref()
+                    .is_some_and(|d| d.status.as_str() == "Enabled"),
+            })
+        })
+        .collect();
+    rules.sort_by_key(|r| std::cmp::Reverse(r.priority));
+    rules
+}
+
+/// Resolve the policies applied to objects under `bucket/prefix`
+pub async fn resolve_effective_policy(bucket: &str, prefix: &str) -> Result<EffectivePolicy> {
+    let sse_config = optional(metadata_sys::get_sse_config(bucket).await)?;
+    let encryption_policy = optional(metadata_sys::get_encryption_policy(bucket).await)?.unwrap_or_default();
+    let lifecycle = optional(metadata_sys::get_lifecycle_config(bucket).await)?;
+    let replication = optional(metadata_sys::get_replication_config(bucket).await)?;
+    let quota = optional(metadata_sys::get_quota_config(bucket).await)?;
+    let public = is_bucket_public(bucket).await?;
+    let public_access_block = PublicAccessBlock::get(bucket).await?;
+
+    Ok(EffectivePolicy {
+        bucket: bucket.to_string(),
+        prefix: prefix.to_string(),
+        encryption: resolve_encryption(sse_config.as_ref().and_then(ObjectEncryption::from_bucket_default), encryption_policy),
+        compression: resolve_compression(crate::compress::compression_enabled()),
+        lifecycle: lifecycle.map(|c| resolve_lifecycle(&c, prefix)).unwrap_or_default(),
+        replication: replication.map(|c| resolve_replication(&c, prefix)).unwrap_or_default(),
+        quota,
+        public,
+        public_access_block,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::dto::{
+        Destination, ExpirationStatus, LifecycleExpiration, LifecycleRule, LifecycleRuleFilter, ReplicationRule,
+        ReplicationRuleStatus,
+    };
+
+    fn lifecycle_rule(id: &str, prefix: &str, days: i32) -> LifecycleRule {
+        LifecycleRule {
+            id: Some(id.to_string()),
+            status: ExpirationStatus::from_static(ExpirationStatus::ENABLED),
+            filter: Some(LifecycleRuleFilter {
+                prefix: Some(prefix.to_string()),
+                ..Default::default()
+            }),
+            expiration: Some(LifecycleExpiration {
+                days: Some(days),
+                ..Default::default()
+            }),
+            abort_incomplete_multipart_upload: None,
+            noncurrent_version_expiration: None,
+            noncurrent_version_transitions: None,
+            prefix: None,
+            transitions: None,
+        }
+    }
+
+    fn replication_rule(id: &str, prefix: &str, priority: i32) -> ReplicationRule {
+        ReplicationRule {
+            id: Some(id.to_string()),
+            status: ReplicationRuleStatus::from_static(ReplicationRuleStatus::ENABLED),
+            priority: Some(priority),
+            prefix: Some(prefix.to_string()),
+            destination: Destination {
+                bucket: format!("arn:aws:s3:::{id}"),
+                access_control_translation: None,
+                account: None,
+                encryption_configuration: None,
+                metrics: None,
+                replication_time: None,
+                storage_class: None,
+            },
+            delete_marker_replication: None,
+            delete_replication: None,
+            existing_object_replication: None,
+            filter: None,
+            source_selection_criteria: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_rules_by_prefix() {
+        let lifecycle = BucketLifecycleConfiguration {
+            rules: vec![
+                lifecycle_rule("all", "", 365),
+                lifecycle_rule("logs", "logs/", 30),
+                lifecycle_rule("old-logs", "logs/2020/", 1),
+                lifecycle_rule("images", "images/", 7),
+            ],
+        };
+        let rules = resolve_lifecycle(&lifecycle, "logs/");
+        assert_eq!(
+            rules
+                .iter()
+                .map(|r| (r.id.as_deref().unwrap(), r.partial, r.expiration_days))
+                .collect::<Vec<_>>(),
+            vec![
+                ("all", false, Some(365)),
+                ("logs", false, Some(30)),
+                ("old-logs", true, Some(1))
+            ]
+        );
+
+        let replication = ReplicationConfiguration {
+            role: String::new(),
+            rules: vec![
+                replication_rule("low", "", 1),
+                replication_rule("high", "logs/", 2),
+                replication_rule("images", "images/", 3),
+            ],
+        };
+        let rules = resolve_replication(&replication, "logs/app/");
+        assert_eq!(rules.iter().map(|r| r.id.as_deref().unwrap()).collect::<Vec<_>>(), vec!["high", "low"]);
+    }
+
+    #[test]
+    fn test_resolve_encryption() {
+        let none = resolve_encryption(None, BucketEncryptionPolicy::default());
+        assert_eq!(none.source, PolicySource::None);
+        assert_eq!(none.default_algorithm, None);
+
+        let mut default = ObjectEncryption::new("aws:kms");
+        default.kms_key_id = Some("key".to_string());
+        let policy = BucketEncryptionPolicy {
+            deny_unencrypted_uploads: true,
+            ..Default::default()
+        };
+        let bucket = resolve_encryption(Some(default), policy);
+        assert_eq!(bucket.source, PolicySource::Bucket);
+        assert_eq!(bucket.default_algorithm.as_deref(), Some("aws:kms"));
+        assert_eq!(bucket.kms_key_id.as_deref(), Some("key"));
+        assert!(bucket.deny_unencrypted_uploads);
+    }
+}
@@ -0,0 +1,557 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Data keys of SSE-S3, SSE-KMS and SSE-C encrypted objects
+//!
+//! Every object gets a data key and a random base nonce. SSE-S3 data keys are random and sealed
+//! with the master key of the cluster, SSE-KMS data keys are generated and wrapped by the KMS and
+//! SSE-C data keys are random and sealed with the key the client sends along with every request.
+//! Either way the sealed key is bound to the bucket and object name and kept in the object
+//! metadata next to the nonce. Parts of a multipart upload share the key of the upload, but every
+//! upload of a part is sealed with a random nonce of its own, kept with the part, so that uploading
+//! a part number again never reuses a nonce under that key.
+
+use super::kms::{ERR_KMS_NOT_CONFIGURED, Kms, kms};
+use super::{SSE_KMS_CONTEXT_META, SSE_KMS_KEY_ID_META, SSE_TYPE_CUSTOMER, SSE_TYPE_META};
+use crate::error::{Error, Result};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use base64::Engine;
+use md5::{Digest, Md5};
+use rand::RngCore;
+use rustfs_filemeta::ObjectPartInfo;
+use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
+use rustfs_utils::crypto::{base64_decode, base64_encode};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Object metadata keeping the sealed data key of an encrypted object
+pub const SSE_SEALED_KEY_META: &str = "sse-sealed-key";
+pub const SSE_MASTER_KEY_ID_META: &str = "sse-master-key-id";
+pub const SSE_IV_META: &str = "sse-iv";
+/// Object metadata keeping the nonces of the parts of an encrypted multipart object by part number
+pub const SSE_PART_NONCES_META: &str = "sse-part-nonces";
+
+pub const ERR_MASTER_KEY_NOT_CONFIGURED: &str = "Server side encryption specified but KMS is not configured";
+
+static MASTER_KEY: OnceLock<MasterKey> = OnceLock::new();
+
+/// Key of the cluster that seals the data keys of objects
+#[derive(Clone)]
+pub struct MasterKey {
+    id: String,
+    key: [u8; 32],
+}
+
+impl fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MasterKey").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+impl MasterKey {
+    pub fn new(id: impl Into<String>, key: [u8; 32]) -> Self {
+        Self { id: id.into(), key }
+    }
+
+    /// Parse a master key given as `<key-id>:<base64 of 32 bytes>`
+    pub fn parse(value: &str) -> Result<Self> {
+        let (id, key) = value
+            .split_once(':')
+            .ok_or_else(|| Error::other("master key must be <key-id>:<base64 key>"))?;
+        if id.is_empty() {
+            return Err(Error::other("master key id must not be empty"));
+        }
+        let key = base64::engine::general_purpose::STANDARD
+            .decode(key.trim())
+            .map_err(|e| Error::other(format!("master key is not valid base64: {e}")))?;
+        let key: [u8; 32] = key.try_into().map_err(|_| Error::other("master key must be 32 bytes long"))?;
+        Ok(Self::new(id, key))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub(crate) fn key(&self) -> &[u8; 32] {
+        &self.key
+    }
+}
+
+/// Install the master key of the cluster, false if one is installed already
+pub fn set_master_key(key: MasterKey) -> bool {
+    MASTER_KEY.set(key).is_ok()
+}
+
+/// The master key of the cluster, `None` when SSE-S3 is not configured
+pub fn master_key() -> Option<&'static MasterKey> {
+    MASTER_KEY.get()
+}
+
+fn meta_key(name: &str) -> String {
+    format!("{RESERVED_METADATA_PREFIX_LOWER}{name}")
+}
+
+fn stored<'a>(metadata: &'a HashMap<String, String>, name: &str) -> Result<&'a String> {
+    metadata
+        .get(&meta_key(name))
+        .ok_or_else(|| Error::other(format!("encrypted object misses {name}")))
+}
+
+fn stored_iv(metadata: &HashMap<String, String>) -> Result<[u8; 12]> {
+    let iv = base64_decode(stored(metadata, SSE_IV_META)?.as_bytes()).map_err(Error::other)?;
+    iv.try_into().map_err(|_| Error::other("object nonce must be 12 bytes long"))
+}
+
+/// Encryption context the KMS binds the data key of `bucket/object` to
+fn kms_context(bucket: &str, object: &str, context: &BTreeMap<String, String>) -> Result<Vec<u8>> {
+    let mut context = context.clone();
+    context.insert(format!("{bucket}/{object}"), String::new());
+    Ok(serde_json::to_vec(&context)?)
+}
+
+pub(crate) fn stored_kms_context(metadata: &HashMap<String, String>) -> Result<BTreeMap<String, String>> {
+    match metadata.get(&meta_key(SSE_KMS_CONTEXT_META)) {
+        Some(context) => Ok(serde_json::from_str(context)?),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+/// Key of an SSE-C object as sent by the client, it is never stored
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CustomerKey {
+    key: [u8; 32],
+}
+
+impl fmt::Debug for CustomerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomerKey").finish_non_exhaustive()
+    }
+}
+
+impl CustomerKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    /// Parse a key given as base64 of 32 bytes
+    pub fn from_base64(value: &str) -> Option<Self> {
+        let key = base64::engine::general_purpose::STANDARD.decode(value).ok()?;
+        Some(Self::new(key.try_into().ok()?))
+    }
+
+    /// Base64 of the MD5 of the key, which clients send and get back to check the key
+    pub fn key_md5(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(Md5::digest(self.key))
+    }
+}
+
+/// Whether the object metadata holds a sealed data key
+pub fn is_encrypted(metadata: &HashMap<String, String>) -> bool {
+    metadata.contains_key(&meta_key(SSE_SEALED_KEY_META))
+}
+
+/// Whether the object is encrypted with a customer-provided key
+pub fn is_customer_encrypted(metadata: &HashMap<String, String>) -> bool {
+    metadata.get(&meta_key(SSE_TYPE_META)).is_some_and(|t| t == SSE_TYPE_CUSTOMER)
+}
+
+/// Encrypt `data_key` with `kek`, returning the random nonce followed by the ciphertext
+fn seal_key(kek: &[u8; 32], data_key: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; 12];
+    rand::rng().fill_bytes(&mut nonce);
+    let cipher = Aes256Gcm::new_from_slice(kek).map_err(Error::other)?;
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: data_key, aad })
+        .map_err(|e| Error::other(format!("seal object key: {e}")))?;
+
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(&sealed);
+    Ok(data)
+}
+
+/// Decrypt a data key sealed by [`seal_key`], `None` if `kek` or `aad` do not match
+fn unseal_key(kek: &[u8; 32], sealed: &[u8], aad: &[u8]) -> Result<Option<[u8; 32]>> {
+    if sealed.len() < 12 {
+        return Err(Error::other("sealed object key is too short"));
+    }
+    let cipher = Aes256Gcm::new_from_slice(kek).map_err(Error::other)?;
+    let Ok(key) = cipher.decrypt(Nonce::from_slice(&sealed[..12]), Payload { msg: &sealed[12..], aad }) else {
+        return Ok(None);
+    };
+    key.try_into()
+        .map(Some)
+        .map_err(|_| Error::other("object key must be 32 bytes long"))
+}
+
+/// Data key and base nonce an object is encrypted with
+#[derive(Clone, PartialEq, Eq)]
+pub struct ObjectKey {
+    key: [u8; 32],
+    iv: [u8; 12],
+}
+
+impl fmt::Debug for ObjectKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectKey").finish_non_exhaustive()
+    }
+}
+
+impl ObjectKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        let mut iv = [0u8; 12];
+        rand::rng().fill_bytes(&mut key);
+        rand::rng().fill_bytes(&mut iv);
+        Self { key, iv }
+    }
+
+    pub fn key(&self) -> [u8; 32] {
+        self.key
+    }
+
+    /// Nonce of the stream of part `part_number`, 1 for objects written in one piece
+    pub fn part_nonce(&self, part_number: usize) -> [u8; 12] {
+        rustfs_rio::part_nonce(self.iv, part_number)
+    }
+
+    /// Random nonce to seal an upload of a multipart part with
+    pub fn random_part_nonce() -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        rand::rng().fill_bytes(&mut nonce);
+        nonce
+    }
+
+    /// Nonce `part` is sealed with, the one recorded for it or else the one derived from its number
+    pub fn nonce_of(&self, part: &ObjectPartInfo) -> Result<[u8; 12]> {
+        match &part.nonce {
+            Some(nonce) => nonce[..]
+                .try_into()
+                .map_err(|_| Error::other(format!("nonce of part {} must be 12 bytes long", part.number))),
+            None => Ok(self.part_nonce(part.number)),
+        }
+    }
+
+    fn associated_data(master: &MasterKey, bucket: &str, object: &str) -> Vec<u8> {
+        format!("{}/{bucket}/{object}", master.id).into_bytes()
+    }
+
+    /// Seal the key with `master` for `bucket/object` and record it in the object metadata
+    pub fn seal(&self, master: &MasterKey, bucket: &str, object: &str, metadata: &mut HashMap<String, String>) -> Result<()> {
+        let sealed = seal_key(&master.key, &self.key, &Self::associated_data(master, bucket, object))?;
+        metadata.insert(meta_key(SSE_SEALED_KEY_META), base64_encode(&sealed));
+        metadata.insert(meta_key(SSE_MASTER_KEY_ID_META), master.id.clone());
+        metadata.insert(meta_key(SSE_IV_META), base64_encode(&self.iv));
+        Ok(())
+    }
+
+    /// Generate a key wrapped by the KMS key `key_id` for `bucket/object` and record it in the object metadata
+    pub async fn generate_kms(
+        kms: &dyn Kms,
+        key_id: &str,
+        bucket: &str,
+        object: &str,
+        context: &BTreeMap<String, String>,
+        metadata: &mut HashMap<String, String>,
+    ) -> Result<Self> {
+        let data_key = kms.generate_key(key_id, &kms_context(bucket, object, context)?).await?;
+        let mut iv = [0u8; 12];
+        rand::rng().fill_bytes(&mut iv);
+
+        metadata.insert(meta_key(SSE_SEALED_KEY_META), base64_encode(&data_key.ciphertext));
+        metadata.insert(meta_key(SSE_KMS_KEY_ID_META), key_id.to_string());
+        metadata.insert(meta_key(SSE_IV_META), base64_encode(&iv));
+        Ok(Self {
+            key: data_key.plaintext,
+            iv,
+        })
+    }
+
+    /// Unseal the key recorded in the metadata of `bucket/object` with `master`
+    pub fn unseal(master: &MasterKey, bucket: &str, object: &str, metadata: &HashMap<String, String>) -> Result<Self> {
+        let key_id = stored(metadata, SSE_MASTER_KEY_ID_META)?;
+        if key_id != &master.id {
+            return Err(Error::other(format!("object is sealed with unknown master key {key_id}")));
+        }
+        let sealed = base64_decode(stored(metadata, SSE_SEALED_KEY_META)?.as_bytes()).map_err(Error::other)?;
+        let key = unseal_key(&master.key, &sealed, &Self::associated_data(master, bucket, object))?
+            .ok_or_else(|| Error::other("failed to unseal object key"))?;
+
+        Ok(Self {
+            key,
+            iv: stored_iv(metadata)?,
+        })
+    }
+
+    /// Seal the key with the key of an SSE-C request for `bucket/object` and record it in the object metadata
+    pub fn seal_customer(
+        &self,
+        customer_key: &CustomerKey,
+        bucket: &str,
+        object: &str,
+        metadata: &mut HashMap<String, String>,
+    ) -> Result<()> {
+        let aad = format!("{SSE_TYPE_CUSTOMER}/{bucket}/{object}");
+        let sealed = seal_key(&customer_key.key, &self.key, aad.as_bytes())?;
+        metadata.insert(meta_key(SSE_SEALED_KEY_META), base64_encode(&sealed));
+        metadata.insert(meta_key(SSE_IV_META), base64_encode(&self.iv));
+        Ok(())
+    }
+
+    /// Unseal the key of the SSE-C object `bucket/object` with the key of the request, which fails
+    /// with [`Error::InvalidSseCustomerKey`] if it is not the key the object was written with
+    pub fn unseal_customer(
+        customer_key: &CustomerKey,
+        bucket: &str,
+        object: &str,
+        metadata: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let sealed = base64_decode(stored(metadata, SSE_SEALED_KEY_META)?.as_bytes()).map_err(Error::other)?;
+        let aad = format!("{SSE_TYPE_CUSTOMER}/{bucket}/{object}");
+        let key = unseal_key(&customer_key.key, &sealed, aad.as_bytes())?
+            .ok_or_else(|| Error::InvalidSseCustomerKey(bucket.to_string(), object.to_string()))?;
+
+        Ok(Self {
+            key,
+            iv: stored_iv(metadata)?,
+        })
+    }
+
+    /// Unwrap the key recorded in the metadata of `bucket/object` with `kms`
+    pub async fn unwrap_kms(kms: &dyn Kms, bucket: &str, object: &str, metadata: &HashMap<String, String>) -> Result<Self> {
+        let key_id = stored(metadata, SSE_KMS_KEY_ID_META)?;
+        let sealed = base64_decode(stored(metadata, SSE_SEALED_KEY_META)?.as_bytes()).map_err(Error::other)?;
+        let context = kms_context(bucket, object, &stored_kms_context(metadata)?)?;
+
+        Ok(Self {
+            key: kms.decrypt_key(key_id, &sealed, &context).await?,
+            iv: stored_iv(metadata)?,
+        })
+    }
+}
+
+/// Unseal the key of an encrypted object with the master key or the KMS of the cluster, or with
+/// `customer_key` for SSE-C objects
+pub async fn object_key(
+    bucket: &str,
+    object: &str,
+    metadata: &HashMap<String, String>,
+    customer_key: Option<&CustomerKey>,
+) -> Result<ObjectKey> {
+    if is_customer_encrypted(metadata) {
+        let customer_key = customer_key.ok_or_else(|| {
+            Error::InvalidArgument(
+                bucket.to_string(),
+                object.to_string(),
+                "object is encrypted with a customer-provided key".to_string(),
+            )
+        })?;
+        return ObjectKey::unseal_customer(customer_key, bucket, object, metadata);
+    }
+
+    if metadata.contains_key(&meta_key(SSE_MASTER_KEY_ID_META)) {
+        let master = master_key().ok_or_else(|| Error::other(ERR_MASTER_KEY_NOT_CONFIGURED))?;
+        return ObjectKey::unseal(master, bucket, object, metadata);
+    }
+
+    let kms = kms().ok_or_else(|| Error::other(ERR_KMS_NOT_CONFIGURED))?;
+    ObjectKey::unwrap_kms(kms.as_ref(), bucket, object, metadata).await
+}
+
+/// Wrap the data key of an SSE-KMS object with the KMS key `key_id` and the encryption `context`
+/// instead, updating the metadata of `bucket/object`. The object data stays as it is.
+pub async fn rewrap_kms_key(
+    bucket: &str,
+    object: &str,
+    metadata: &mut HashMap<String, String>,
+    key_id: &str,
+    context: &BTreeMap<String, String>,
+) -> Result<()> {
+    let kms = kms().ok_or_else(|| Error::other(ERR_KMS_NOT_CONFIGURED))?;
+    let key = ObjectKey::unwrap_kms(kms.as_ref(), bucket, object, metadata).await?;
+    let sealed = kms
+        .encrypt_key(key_id, &key.key, &kms_context(bucket, object, context)?)
+        .await?;
+
+    metadata.insert(meta_key(SSE_SEALED_KEY_META), base64_encode(&sealed));
+    metadata.insert(meta_key(SSE_KMS_KEY_ID_META), key_id.to_string());
+    if context.is_empty() {
+        metadata.remove(&meta_key(SSE_KMS_CONTEXT_META));
+    } else {
+        metadata.insert(meta_key(SSE_KMS_CONTEXT_META), serde_json::to_string(context)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_master_key() {
+        let key = MasterKey::parse("my-key:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=").unwrap();
+        assert_eq!(key.id(), "my-key");
+        assert_eq!(&key.key, b"0123456789abcdef0123456789abcdef");
+
+        assert!(MasterKey::parse("MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=").is_err());
+        assert!(MasterKey::parse(":MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=").is_err());
+        assert!(MasterKey::parse("short:MDEyMw==").is_err());
+    }
+
+    #[test]
+    fn test_seal_unseal_object_key() {
+        let master = MasterKey::new("k1", [7u8; 32]);
+        let key = ObjectKey::generate();
+
+        let mut metadata = HashMap::new();
+        assert!(!is_encrypted(&metadata));
+        key.seal(&master, "bucket", "dir/object", &mut metadata).unwrap();
+        assert!(is_encrypted(&metadata));
+        assert_eq!(ObjectKey::unseal(&master, "bucket", "dir/object", &metadata).unwrap(), key);
+
+        // The sealed key is bound to the object and the master key
+        assert!(ObjectKey::unseal(&master, "bucket", "other", &metadata).is_err());
+        assert!(ObjectKey::unseal(&MasterKey::new("k1", [8u8; 32]), "bucket", "dir/object", &metadata).is_err());
+        assert!(ObjectKey::unseal(&MasterKey::new("k2", [7u8; 32]), "bucket", "dir/object", &metadata).is_err());
+
+        assert_ne!(key.part_nonce(1), key.part_nonce(2));
+    }
+
+    #[test]
+    fn test_nonce_of_part() {
+        let key = ObjectKey::generate();
+        let mut part = ObjectPartInfo {
+            number: 3,
+            ..Default::default()
+        };
+        assert_eq!(key.nonce_of(&part).unwrap(), key.part_nonce(3));
+
+        // Uploads of a part get a nonce of their own, whatever the part number
+        let nonce = ObjectKey::random_part_nonce();
+        assert_ne!(nonce, ObjectKey::random_part_nonce());
+        part.nonce = Some(bytes::Bytes::copy_from_slice(&nonce));
+        assert_eq!(key.nonce_of(&part).unwrap(), nonce);
+
+        part.nonce = Some(bytes::Bytes::from_static(b"short"));
+        assert!(key.nonce_of(&part).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_customer_object_key() {
+        let customer_key = CustomerKey::new(*b"0123456789abcdef0123456789abcdef");
+        assert_eq!(customer_key.key_md5(), "hRasmdxgYDKV3nvbahU1MA==");
+        assert_eq!(
+            CustomerKey::from_base64("MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY="),
+            Some(customer_key)
+        );
+        assert!(CustomerKey::from_base64("MDEyMw==").is_none());
+
+        let key = ObjectKey::generate();
+        let mut metadata = HashMap::from([(meta_key(SSE_TYPE_META), SSE_TYPE_CUSTOMER.to_string())]);
+        key.seal_customer(&customer_key, "bucket", "object", &mut metadata).unwrap();
+        assert!(is_customer_encrypted(&metadata));
+        assert_eq!(object_key("bucket", "object", &metadata, Some(&customer_key)).await.unwrap(), key);
+
+        // Reading needs the key the object was written with
+        assert!(matches!(
+            object_key("bucket", "object", &metadata, None).await,
+            Err(Error::InvalidArgument(..))
+        ));
+        let other = CustomerKey::new([1u8; 32]);
+        assert!(matches!(
+            object_key("bucket", "object", &metadata, Some(&other)).await,
+            Err(Error::InvalidSseCustomerKey(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_kms_key_rewrap() {
+        let kms = MasterKey::new("kek", [9u8; 32]);
+        let context = BTreeMap::from([("team".to_string(), "a".to_string())]);
+        let mut metadata = HashMap::new();
+        metadata.insert(meta_key(SSE_KMS_CONTEXT_META), serde_json::to_string(&context).unwrap());
+        let key = ObjectKey::generate_kms(&kms, "kek", "bucket", "object", &context, &mut metadata)
+            .await
+            .unwrap();
+        assert!(is_encrypted(&metadata));
+        assert_eq!(ObjectKey::unwrap_kms(&kms, "bucket", "object", &metadata).await.unwrap(), key);
+
+        // The wrapped key is bound to the object and the encryption context
+        assert!(ObjectKey::unwrap_kms(&kms, "bucket", "other", &metadata).await.is_err());
+        let mut tampered = metadata.clone();
+        tampered.remove(&meta_key(SSE_KMS_CONTEXT_META));
+        assert!(ObjectKey::unwrap_kms(&kms, "bucket", "object", &tampered).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_object_reader_decrypts_range() {
+        use crate::store_api::{GetObjectReader, HTTPRangeSpec, ObjectInfo, ObjectOptions};
+        use rustfs_rio::{EncryptReader, WarpReader};
+        use std::io::Cursor;
+        use tokio::io::AsyncReadExt;
+
+        set_master_key(MasterKey::new("test-key", [3u8; 32]));
+        let plaintext: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+
+        let key = ObjectKey::generate();
+        let mut user_defined = HashMap::new();
+        key.seal(master_key().unwrap(), "bucket", "object", &mut user_defined)
+            .unwrap();
+        user_defined.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size"), plaintext.len().to_string());
+
+        let mut encrypted = Vec::new();
+        EncryptReader::new(WarpReader::new(Cursor::new(plaintext.clone())), key.key(), key.part_nonce(1))
+            .read_to_end(&mut encrypted)
+            .await
+            .unwrap();
+
+        let oi = ObjectInfo {
+            bucket: "bucket".to_string(),
+            name: "object".to_string(),
+            size: encrypted.len() as i64,
+            user_defined,
+            ..Default::default()
+        };
+        let rs = HTTPRangeSpec {
+            is_suffix_length: false,
+            start: 70_000,
+            end: 139_999,
+        };
+        let (mut rd, off, length) = GetObjectReader::new(
+            Box::new(Cursor::new(encrypted.clone())),
+            Some(rs),
+            &oi,
+            &ObjectOptions::default(),
+            &http::HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        // The whole ciphertext is read to decrypt the range
+        assert_eq!((off, length), (0, encrypted.len() as i64));
+        assert_eq!(rd.object_info.size, plaintext.len() as i64);
+        assert_eq!(rd.read_all().await.unwrap(), &plaintext[70_000..140_000]);
+
+        // Data movement copies the stored ciphertext
+        let opts = ObjectOptions {
+            no_decryption: true,
+            ..Default::default()
+        };
+        let (mut rd, _, _) =
+            GetObjectReader::new(Box::new(Cursor::new(encrypted.clone())), None, &oi, &opts, &http::HeaderMap::new())
+                .await
+                .unwrap();
+        assert_eq!(rd.read_all().await.unwrap(), encrypted);
+    }
+}
@@ -0,0 +1,496 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key management services handing out the data keys of SSE-KMS objects
+//!
+//! The KMS keeps the key encryption keys (KEKs). Objects only store the data key as wrapped by
+//! the KMS, so a KEK can be replaced by re-wrapping the data keys without touching object data.
+
+use super::SSE_KMS_KEY_ID_META;
+use super::keys::{MasterKey, SSE_SEALED_KEY_META, rewrap_kms_key, stored_kms_context};
+use crate::error::{Error, Result};
+use crate::store::ECStore;
+use crate::store_api::{ObjectInfo, ObjectOptions, StorageAPI, WritePreconditions};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose;
+use rand::RngCore;
+use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+use tracing::warn;
+
+pub const ERR_KMS_NOT_CONFIGURED: &str = "Server side encryption specified with SSE-KMS but KMS is not configured";
+
+static GLOBAL_KMS: OnceLock<Arc<dyn Kms>> = OnceLock::new();
+
+/// Data key generated by a KMS, in plain and wrapped by the KEK
+pub struct DataKey {
+    pub plaintext: [u8; 32],
+    pub ciphertext: Vec<u8>,
+}
+
+impl fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataKey").finish_non_exhaustive()
+    }
+}
+
+/// A key management service. `context` is bound to the wrapped key and must be given again to unwrap it.
+#[async_trait::async_trait]
+pub trait Kms: Send + Sync + fmt::Debug {
+    /// KEK used when neither the request nor the bucket default encryption names one
+    fn default_key_id(&self) -> &str;
+
+    /// Generate a data key wrapped by the KEK `key_id`
+    async fn generate_key(&self, key_id: &str, context: &[u8]) -> Result<DataKey>;
+
+    /// Wrap an existing data key with the KEK `key_id`
+    async fn encrypt_key(&self, key_id: &str, plaintext: &[u8; 32], context: &[u8]) -> Result<Vec<u8>>;
+
+    /// Unwrap a data key wrapped by the KEK `key_id`
+    async fn decrypt_key(&self, key_id: &str, ciphertext: &[u8], context: &[u8]) -> Result<[u8; 32]>;
+}
+
+/// Install the KMS of the cluster, false if one is installed already
+pub fn set_kms(kms: Arc<dyn Kms>) -> bool {
+    GLOBAL_KMS.set(kms).is_ok()
+}
+
+/// The KMS of the cluster, `None` when SSE-KMS is not configured
+pub fn kms() -> Option<&'static Arc<dyn Kms>> {
+    GLOBAL_KMS.get()
+}
+
+fn into_key(plaintext: Vec<u8>) -> Result<[u8; 32]> {
+    plaintext
+        .try_into()
+        .map_err(|_| Error::other("KMS returned a data key that is not 32 bytes long"))
+}
+
+/// The master key serves as a single-key KMS when no external KMS is configured
+#[async_trait::async_trait]
+impl Kms for MasterKey {
+    fn default_key_id(&self) -> &str {
+        self.id()
+    }
+
+    async fn generate_key(&self, key_id: &str, context: &[u8]) -> Result<DataKey> {
+        let mut plaintext = [0u8; 32];
+        rand::rng().fill_bytes(&mut plaintext);
+        let ciphertext = self.encrypt_key(key_id, &plaintext, context).await?;
+        Ok(DataKey { plaintext, ciphertext })
+    }
+
+    async fn encrypt_key(&self, key_id: &str, plaintext: &[u8; 32], context: &[u8]) -> Result<Vec<u8>> {
+        if key_id != self.id() {
+            return Err(Error::other(format!("KMS key {key_id} does not exist")));
+        }
+        let mut nonce = [0u8; 12];
+        rand::rng().fill_bytes(&mut nonce);
+        let cipher = Aes256Gcm::new_from_slice(self.key()).map_err(Error::other)?;
+        let sealed = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: context,
+                },
+            )
+            .map_err(|e| Error::other(format!("wrap data key: {e}")))?;
+
+        let mut ciphertext = nonce.to_vec();
+        ciphertext.extend_from_slice(&sealed);
+        Ok(ciphertext)
+    }
+
+    async fn decrypt_key(&self, key_id: &str, ciphertext: &[u8], context: &[u8]) -> Result<[u8; 32]> {
+        if key_id != self.id() {
+            return Err(Error::other(format!("KMS key {key_id} does not exist")));
+        }
+        if ciphertext.len() < 12 {
+            return Err(Error::other("wrapped data key is too short"));
+        }
+        let cipher = Aes256Gcm::new_from_slice(self.key()).map_err(Error::other)?;
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&ciphertext[..12]),
+                Payload {
+                    msg: &ciphertext[12..],
+                    aad: context,
+                },
+            )
+            .map_err(|_| Error::other("failed to unwrap data key"))?;
+        into_key(plaintext)
+    }
+}
+
+#[derive(Serialize)]
+struct KesGenerateRequest {
+    context: String,
+}
+
+#[derive(Serialize)]
+struct KesEncryptRequest {
+    plaintext: String,
+    context: String,
+}
+
+#[derive(Serialize)]
+struct KesDecryptRequest {
+    ciphertext: String,
+    context: String,
+}
+
+#[derive(Deserialize)]
+struct KesDataKey {
+    #[serde(default)]
+    plaintext: String,
+    #[serde(default)]
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct KesError {
+    message: String,
+}
+
+/// Client of a KES server, or any KMS speaking the KES HTTP API
+#[derive(Debug, Clone)]
+pub struct KesClient {
+    endpoint: String,
+    default_key_id: String,
+    client: reqwest::Client,
+}
+
+impl KesClient {
+    /// Client of the KES server at `endpoint`, authenticated with the PEM encoded client certificate
+    /// and private key `identity` and trusting the PEM encoded CA certificate `ca` if given
+    pub fn new(endpoint: &str, default_key_id: &str, identity: Option<&[u8]>, ca: Option<&[u8]>) -> Result<Self> {
+        let mut builder = reqwest::Client::builder().use_rustls_tls().timeout(Duration::from_secs(10));
+        if let Some(identity) = identity {
+            builder = builder.identity(reqwest::Identity::from_pem(identity).map_err(Error::other)?);
+        }
+        if let Some(ca) = ca {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(ca).map_err(Error::other)?);
+        }
+
+        Ok(Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            default_key_id: default_key_id.to_string(),
+            client: builder.build().map_err(Error::other)?,
+        })
+    }
+
+    async fn call<T: Serialize + Sync>(&self, api: &str, key_id: &str, body: &T) -> Result<KesDataKey> {
+        let url = format!("{}/v1/key/{api}/{}", self.endpoint, urlencoding::encode(key_id));
+        let resp = self
+            .client
+            .post(&url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| Error::other(format!("KMS request {api} failed: {e}")))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let message = match resp.json::<KesError>().await {
+                Ok(err) => err.message,
+                Err(_) => status.to_string(),
+            };
+            return Err(Error::other(format!("KMS {api} with key {key_id} failed: {message}")));
+        }
+        resp.json::<KesDataKey>()
+            .await
+            .map_err(|e| Error::other(format!("invalid KMS {api} response: {e}")))
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(|_| Error::other("KMS returned invalid base64"))
+}
+
+#[async_trait::async_trait]
+impl Kms for KesClient {
+    fn default_key_id(&self) -> &str {
+        &self.default_key_id
+    }
+
+    async fn generate_key(&self, key_id: &str, context: &[u8]) -> Result<DataKey> {
+        let resp = self
+            .call(
+                "generate",
+                key_id,
+                &KesGenerateRequest {
+                    context: general_purpose::STANDARD.encode(context),
+                },
+            )
+            .await?;
+        Ok(DataKey {
+            plaintext: into_key(decode(&resp.plaintext)?)?,
+            ciphertext: decode(&resp.ciphertext)?,
+        })
+    }
+
+    async fn encrypt_key(&self, key_id: &str, plaintext: &[u8; 32], context: &[u8]) -> Result<Vec<u8>> {
+        let resp = self
+            .call(
+                "encrypt",
+                key_id,
+                &KesEncryptRequest {
+                    plaintext: general_purpose::STANDARD.encode(plaintext),
+                    context: general_purpose::STANDARD.encode(context),
+                },
+            )
+            .await?;
+        decode(&resp.ciphertext)
+    }
+
+    async fn decrypt_key(&self, key_id: &str, ciphertext: &[u8], context: &[u8]) -> Result<[u8; 32]> {
+        let resp = self
+            .call(
+                "decrypt",
+                key_id,
+                &KesDecryptRequest {
+                    ciphertext: general_purpose::STANDARD.encode(ciphertext),
+                    context: general_purpose::STANDARD.encode(context),
+                },
+            )
+            .await?;
+        into_key(decode(&resp.plaintext)?)
+    }
+}
+
+static GLOBAL_KEY_ROTATION: LazyLock<Mutex<Option<KeyRotationResult>>> = LazyLock::new(|| Mutex::new(None));
+
+/// Progress of re-wrapping the data keys of the SSE-KMS objects of a bucket
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct KeyRotationResult {
+    pub bucket: String,
+    pub prefix: String,
+    pub key_id: String,
+    pub running: bool,
+    /// Object versions whose data key is wrapped by the new KMS key now
+    pub rotated: usize,
+    /// SSE-KMS object versions wrapped by the new KMS key already, or changed while the rotation ran
+    pub skipped: usize,
+    pub failed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+fn update_key_rotation(f: impl FnOnce(&mut KeyRotationResult)) {
+    if let Some(result) = GLOBAL_KEY_ROTATION.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        f(result);
+    }
+}
+
+/// Progress of the current or last key rotation on this node
+pub fn kms_key_rotation_status() -> Option<KeyRotationResult> {
+    GLOBAL_KEY_ROTATION.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Start re-wrapping the data keys of every SSE-KMS object version under `prefix` of `bucket` with the
+/// KMS key `key_id` in the background, so the previous key can be retired. Only object metadata is
+/// rewritten. One rotation runs at a time on a node, its progress is read with [`kms_key_rotation_status`].
+pub fn start_kms_key_rotation(store: Arc<ECStore>, bucket: &str, prefix: &str, key_id: &str) -> Result<()> {
+    {
+        let mut current = GLOBAL_KEY_ROTATION.lock().unwrap_or_else(|e| e.into_inner());
+        if current.as_ref().is_some_and(|result| result.running) {
+            return Err(Error::other("a KMS key rotation is already running"));
+        }
+        *current = Some(KeyRotationResult {
+            bucket: bucket.to_string(),
+            prefix: prefix.to_string(),
+            key_id: key_id.to_string(),
+            running: true,
+            ..Default::default()
+        });
+    }
+
+    let (bucket, prefix, key_id) = (bucket.to_string(), prefix.to_string(), key_id.to_string());
+    tokio::spawn(async move {
+        if let Err(err) = rotate_kms_keys(store, &bucket, &prefix, &key_id).await {
+            warn!("rotate KMS keys of {} failed: {}", bucket, err);
+            update_key_rotation(|result| result.last_error = Some(err.to_string()));
+        }
+        update_key_rotation(|result| result.running = false);
+    });
+    Ok(())
+}
+
+async fn rotate_kms_keys(store: Arc<ECStore>, bucket: &str, prefix: &str, key_id: &str) -> Result<()> {
+    let kms_key_meta = format!("{RESERVED_METADATA_PREFIX_LOWER}{SSE_KMS_KEY_ID_META}");
+    let (mut marker, mut version_marker) = (None, None);
+
+    loop {
+        let page = store
+            .clone()
+            .list_object_versions(bucket, prefix, marker, version_marker, None, 1000)
+            .await?;
+
+        for oi in page.objects.iter().filter(|oi| !oi.delete_marker && oi.is_encrypted()) {
+            let Some(current) = oi.user_defined.get(&kms_key_meta) else {
+                continue;
+            };
+            if current == key_id {
+                update_key_rotation(|result| result.skipped += 1);
+                continue;
+            }
+
+            match rotate_object_key(&store, oi, key_id).await {
+                Ok(()) => update_key_rotation(|result| result.rotated += 1),
+                // Overwritten since it was listed, the new version has a data key of its own
+                Err(Error::PreconditionFailed(_, _)) => update_key_rotation(|result| result.skipped += 1),
+                Err(err) => {
+                    warn!("rotate KMS key of {}/{} {:?} failed: {}", bucket, oi.name, oi.version_id, err);
+                    update_key_rotation(|result| {
+                        result.failed += 1;
+                        result.last_error = Some(format!("{}: {}", oi.name, err));
+                    });
+                }
+            }
+        }
+
+        if !page.is_truncated {
+            return Ok(());
+        }
+        marker = page.next_marker;
+        version_marker = page.next_version_idmarker;
+    }
+}
+
+/// Re-wrap the data key of the listed version `oi`, which must still have the ETag it was listed with when
+/// its metadata is rewritten under the object lock, or it would get a data key sealed for other data
+async fn rotate_object_key(store: &Arc<ECStore>, oi: &ObjectInfo, key_id: &str) -> Result<()> {
+    let mut metadata = oi.user_defined.clone();
+    let context = stored_kms_context(&metadata)?;
+    rewrap_kms_key(&oi.bucket, &oi.name, &mut metadata, key_id, &context).await?;
+
+    let eval_metadata = [SSE_SEALED_KEY_META, SSE_KMS_KEY_ID_META]
+        .iter()
+        .filter_map(|name| {
+            let key = format!("{RESERVED_METADATA_PREFIX_LOWER}{name}");
+            metadata.get(&key).map(|v| (key, v.clone()))
+        })
+        .collect();
+
+    let Some(etag) = oi.etag.clone() else {
+        return Err(Error::PreconditionFailed(oi.bucket.clone(), oi.name.clone()));
+    };
+    store
+        .put_object_metadata(
+            &oi.bucket,
+            &oi.name,
+            &ObjectOptions {
+                version_id: oi.version_id.map(|v| v.to_string()),
+                mod_time: oi.mod_time,
+                eval_metadata: Some(eval_metadata),
+                write_preconditions: Some(WritePreconditions {
+                    if_match: Some(etag),
+                    if_none_match: None,
+                    mod_time: oi.mod_time,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_master_key_kms() {
+        let kms = MasterKey::new("k1", [5u8; 32]);
+        let dk = kms.generate_key("k1", b"ctx").await.unwrap();
+        assert_eq!(kms.decrypt_key("k1", &dk.ciphertext, b"ctx").await.unwrap(), dk.plaintext);
+        assert!(kms.decrypt_key("k1", &dk.ciphertext, b"other").await.is_err());
+        assert!(kms.generate_key("k2", b"ctx").await.is_err());
+    }
+
+    /// Serve one KES request, answering with `body` and `status`, and return the request
+    async fn serve_once(listener: TcpListener, status: &'static str, body: &'static str) -> String {
+        let (mut conn, _) = listener.accept().await.unwrap();
+        let mut req = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = conn.read(&mut buf).await.unwrap();
+            req.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&req);
+            if let Some((head, rest)) = text.split_once("\r\n\r\n") {
+                let len = head
+                    .lines()
+                    .find_map(|l| {
+                        l.to_ascii_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().to_string())
+                    })
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(0);
+                if rest.len() >= len {
+                    break;
+                }
+            }
+        }
+        let resp = format!(
+            "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        conn.write_all(resp.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&req).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_kes_client_generate_key() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // plaintext is 32 bytes of 0x01, ciphertext "wrapped"
+        let server = tokio::spawn(serve_once(
+            listener,
+            "200 OK",
+            r#"{"plaintext":"AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=","ciphertext":"d3JhcHBlZA=="}"#,
+        ));
+
+        let kes = KesClient::new(&format!("http://{addr}/"), "my-key", None, None).unwrap();
+        let dk = kes.generate_key("my-key", b"ctx").await.unwrap();
+        assert_eq!(dk.plaintext, [1u8; 32]);
+        assert_eq!(dk.ciphertext, b"wrapped");
+
+        let req = server.await.unwrap();
+        assert!(req.starts_with("POST /v1/key/generate/my-key "));
+        assert!(req.contains(r#"{"context":"Y3R4"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_kes_client_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve_once(listener, "404 Not Found", r#"{"message":"key does not exist"}"#));
+
+        let kes = KesClient::new(&format!("http://{addr}"), "my-key", None, None).unwrap();
+        let err = kes.decrypt_key("missing", b"wrapped", b"ctx").await.unwrap_err();
+        assert!(err.to_string().contains("key does not exist"));
+        server.await.unwrap();
+    }
+}
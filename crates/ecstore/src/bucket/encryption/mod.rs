@@ -0,0 +1,217 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::error::{Error, Result};
+use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
+use s3s::dto::{ServerSideEncryption, ServerSideEncryptionConfiguration};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+pub mod keys;
+pub mod kms;
+
+use keys::{CustomerKey, SSE_IV_META, SSE_MASTER_KEY_ID_META, SSE_PART_NONCES_META, SSE_SEALED_KEY_META};
+
+pub const SSE_TYPE_CUSTOMER: &str = "SSE-C";
+
+/// Object metadata recording the server-side encryption an object was written with
+pub const SSE_TYPE_META: &str = "sse-type";
+pub const SSE_KMS_KEY_ID_META: &str = "sse-kms-key-id";
+pub const SSE_KMS_CONTEXT_META: &str = "sse-kms-context";
+pub const SSE_BUCKET_KEY_META: &str = "sse-bucket-key-enabled";
+
+/// Bucket settings that complement the S3 default encryption configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BucketEncryptionPolicy {
+    /// Reject uploads that neither request SSE nor get it from the bucket default encryption
+    pub deny_unencrypted_uploads: bool,
+    /// KMS encryption context added to every SSE-KMS upload, request context keys take precedence
+    pub kms_context: BTreeMap<String, String>,
+}
+
+impl BucketEncryptionPolicy {
+    pub fn marshal(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    pub fn unmarshal(buf: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(buf)?)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.kms_context.keys().any(|k| k.is_empty()) {
+            return Err(Error::other("KMS context keys must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+/// Server-side encryption applied to a single object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectEncryption {
+    /// `AES256`, `aws:kms`, `aws:kms:dsse` or `SSE-C`
+    pub sse_type: String,
+    pub kms_key_id: Option<String>,
+    pub kms_context: BTreeMap<String, String>,
+    pub bucket_key_enabled: bool,
+    /// Key of an SSE-C request, never written to the object metadata
+    pub customer_key: Option<CustomerKey>,
+}
+
+impl ObjectEncryption {
+    pub fn new(sse_type: impl Into<String>) -> Self {
+        Self {
+            sse_type: sse_type.into(),
+            kms_key_id: None,
+            kms_context: BTreeMap::new(),
+            bucket_key_enabled: false,
+            customer_key: None,
+        }
+    }
+
+    /// The encryption the bucket default configuration applies to uploads without SSE headers
+    pub fn from_bucket_default(config: &ServerSideEncryptionConfiguration) -> Option<Self> {
+        let rule = config.rules.first()?;
+        let default = rule.apply_server_side_encryption_by_default.as_ref()?;
+        Some(Self {
+            sse_type: default.sse_algorithm.as_str().to_string(),
+            kms_key_id: default.kms_master_key_id.clone(),
+            kms_context: BTreeMap::new(),
+            bucket_key_enabled: rule.bucket_key_enabled.unwrap_or_default(),
+            customer_key: None,
+        })
+    }
+
+    pub fn is_kms(&self) -> bool {
+        self.sse_type == ServerSideEncryption::AWS_KMS || self.sse_type == ServerSideEncryption::AWS_KMS_DSSE
+    }
+
+    /// Add the bucket KMS context, keeping keys the request already set
+    pub fn apply_bucket_context(&mut self, policy: &BucketEncryptionPolicy) {
+        if !self.is_kms() {
+            return;
+        }
+        for (k, v) in &policy.kms_context {
+            self.kms_context.entry(k.clone()).or_insert_with(|| v.clone());
+        }
+    }
+
+    /// Record the encryption in the reserved object metadata
+    pub fn write_metadata(&self, metadata: &mut HashMap<String, String>) -> Result<()> {
+        let key = |name: &str| format!("{RESERVED_METADATA_PREFIX_LOWER}{name}");
+
+        metadata.insert(key(SSE_TYPE_META), self.sse_type.clone());
+        if let Some(kms_key_id) = &self.kms_key_id {
+            metadata.insert(key(SSE_KMS_KEY_ID_META), kms_key_id.clone());
+        }
+        if !self.kms_context.is_empty() {
+            metadata.insert(key(SSE_KMS_CONTEXT_META), serde_json::to_string(&self.kms_context)?);
+        }
+        if self.bucket_key_enabled {
+            metadata.insert(key(SSE_BUCKET_KEY_META), "true".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Remove the encryption recorded by [`ObjectEncryption::write_metadata`] and the sealed data key
+pub fn clear_encryption_metadata(metadata: &mut HashMap<String, String>) {
+    for name in [
+        SSE_TYPE_META,
+        SSE_KMS_KEY_ID_META,
+        SSE_KMS_CONTEXT_META,
+        SSE_BUCKET_KEY_META,
+        SSE_SEALED_KEY_META,
+        SSE_MASTER_KEY_ID_META,
+        SSE_IV_META,
+        SSE_PART_NONCES_META,
+    ] {
+        metadata.remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}{name}"));
+    }
+}
+
+/// Check a default encryption configuration before it is stored
+pub fn validate_sse_config(config: &ServerSideEncryptionConfiguration) -> Result<()> {
+    if config.rules.len() != 1 {
+        return Err(Error::other("exactly one server-side encryption rule is required"));
+    }
+    let Some(default) = config.rules[0].apply_server_side_encryption_by_default.as_ref() else {
+        return Err(Error::other("ApplyServerSideEncryptionByDefault is required"));
+    };
+    match default.sse_algorithm.as_str() {
+        ServerSideEncryption::AES256 => {
+            if default.kms_master_key_id.is_some() {
+                return Err(Error::other("KMSMasterKeyID is only allowed with aws:kms"));
+            }
+        }
+        ServerSideEncryption::AWS_KMS | ServerSideEncryption::AWS_KMS_DSSE => {}
+        other => return Err(Error::other(format!("unsupported SSEAlgorithm {other}"))),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::dto::{ServerSideEncryptionByDefault, ServerSideEncryptionRule};
+
+    fn sse_config(algorithm: &str, key_id: Option<&str>) -> ServerSideEncryptionConfiguration {
+        ServerSideEncryptionConfiguration {
+            rules: vec![ServerSideEncryptionRule {
+                apply_server_side_encryption_by_default: Some(ServerSideEncryptionByDefault {
+                    kms_master_key_id: key_id.map(str::to_string),
+                    sse_algorithm: ServerSideEncryption::from(algorithm.to_string()),
+                }),
+                bucket_key_enabled: Some(true),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_validate_sse_config() {
+        assert!(validate_sse_config(&sse_config("AES256", None)).is_ok());
+        assert!(validate_sse_config(&sse_config("aws:kms", Some("key-1"))).is_ok());
+        assert!(validate_sse_config(&sse_config("AES256", Some("key-1"))).is_err());
+        assert!(validate_sse_config(&sse_config("rot13", None)).is_err());
+    }
+
+    #[test]
+    fn test_bucket_default_with_kms_context() {
+        let policy = BucketEncryptionPolicy {
+            deny_unencrypted_uploads: true,
+            kms_context: BTreeMap::from([
+                ("team".to_string(), "analytics".to_string()),
+                ("env".to_string(), "prod".to_string()),
+            ]),
+        };
+        assert_eq!(BucketEncryptionPolicy::unmarshal(&policy.marshal().unwrap()).unwrap(), policy);
+
+        let mut enc = ObjectEncryption::from_bucket_default(&sse_config("aws:kms", Some("key-1"))).unwrap();
+        enc.kms_context.insert("env".to_string(), "dev".to_string());
+        enc.apply_bucket_context(&policy);
+        assert_eq!(enc.kms_context["env"], "dev");
+        assert_eq!(enc.kms_context["team"], "analytics");
+
+        let mut metadata = HashMap::new();
+        enc.write_metadata(&mut metadata).unwrap();
+        assert_eq!(metadata[&format!("{RESERVED_METADATA_PREFIX_LOWER}{SSE_TYPE_META}")], "aws:kms");
+        assert_eq!(metadata[&format!("{RESERVED_METADATA_PREFIX_LOWER}{SSE_KMS_KEY_ID_META}")], "key-1");
+
+        // The bucket context only applies to SSE-KMS
+        let mut s3 = ObjectEncryption::new(ServerSideEncryption::AES256);
+        s3.apply_bucket_context(&policy);
+        assert!(s3.kms_context.is_empty());
+    }
+}
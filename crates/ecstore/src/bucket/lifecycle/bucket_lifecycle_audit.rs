@@ -12,6 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Per rule metrics and audit trail of the lifecycle actions taken on objects.
+//!
+//! The object layer only reports the actions; the server installs a sink with [`set_audit_sink`]
+//! that writes them to its audit log. Actions taken before a sink is installed are only counted.
+
+use std::sync::OnceLock;
+
+use rustfs_common::metrics::{IlmAction, globalMetrics};
+use uuid::Uuid;
+
 use super::lifecycle;
 
 #[derive(Debug, Clone, Default)]
@@ -30,6 +40,24 @@ pub enum LcEventSrc {
     S3CompleteMultipartUpload,
 }
 
+impl LcEventSrc {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LcEventSrc::None => "None",
+            LcEventSrc::Heal => "Heal",
+            LcEventSrc::Scanner => "Scanner",
+            LcEventSrc::Decom => "Decom",
+            LcEventSrc::Rebal => "Rebal",
+            LcEventSrc::S3HeadObject => "s3:HeadObject",
+            LcEventSrc::S3GetObject => "s3:GetObject",
+            LcEventSrc::S3ListObjects => "s3:ListObjects",
+            LcEventSrc::S3PutObject => "s3:PutObject",
+            LcEventSrc::S3CopyObject => "s3:CopyObject",
+            LcEventSrc::S3CompleteMultipartUpload => "s3:CompleteMultipartUpload",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct LcAuditEvent {
     pub event: lifecycle::Event,
@@ -41,3 +69,39 @@ impl LcAuditEvent {
         Self { event, source }
     }
 }
+
+/// A lifecycle action taken on an object, for the audit log
+#[derive(Debug, Clone)]
+pub struct LcAuditEntry {
+    pub bucket: String,
+    pub object: String,
+    pub version_id: Option<Uuid>,
+    /// Upload the action aborted, for `AbortMultipartUploadAction`
+    pub upload_id: Option<String>,
+    pub rule_id: String,
+    pub action: IlmAction,
+    pub source: LcEventSrc,
+    /// Number of versions the action removed or transitioned
+    pub versions: u64,
+}
+
+type AuditSink = Box<dyn Fn(LcAuditEntry) + Send + Sync>;
+
+static AUDIT_SINK: OnceLock<AuditSink> = OnceLock::new();
+
+/// Install the sink lifecycle actions are reported to, false if one is installed already
+///
+/// The sink is called from the expiry and transition workers, it must hand the entry off without blocking.
+pub fn set_audit_sink(sink: impl Fn(LcAuditEntry) + Send + Sync + 'static) -> bool {
+    AUDIT_SINK.set(Box::new(sink)).is_ok()
+}
+
+/// Count a lifecycle action in the metrics of its rule and report it to the audit sink
+pub async fn audit_lifecycle_action(entry: LcAuditEntry) {
+    globalMetrics
+        .inc_ilm_rule(&entry.bucket, &entry.rule_id, entry.action, entry.versions)
+        .await;
+    if let Some(sink) = AUDIT_SINK.get() {
+        sink(entry);
+    }
+}
@@ -25,8 +25,10 @@ use lazy_static::lazy_static;
 use rustfs_common::data_usage::TierStats;
 use rustfs_common::heal_channel::rep_has_active_rules;
 use rustfs_common::metrics::{IlmAction, Metrics};
+use rustfs_filemeta::headers::{AMZ_RESTORE_EXPIRY_DAYS, AMZ_RESTORE_REQUEST_DATE};
 use rustfs_utils::path::encode_dir_object;
 use s3s::Body;
+use s3s::header::X_AMZ_RESTORE;
 use sha2::{Digest, Sha256};
 use std::any::Any;
 use std::collections::HashMap;
@@ -35,23 +37,27 @@ use std::io::Write;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::{Arc, Mutex};
-use time::OffsetDateTime;
+use time::format_description::FormatItem;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
 use tokio::select;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::{RwLock, mpsc};
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 use xxhash_rust::xxh64;
 
 //use rustfs_notify::{BucketNotificationConfig, Event, EventName, LogLevel, NotificationError, init_logger};
 //use rustfs_notify::{initialize, notification_system};
-use super::bucket_lifecycle_audit::{LcAuditEvent, LcEventSrc};
+use super::bucket_lifecycle_audit::{LcAuditEntry, LcAuditEvent, LcEventSrc, audit_lifecycle_action};
 use super::lifecycle::{self, ExpirationOptions, Lifecycle, TransitionOptions};
 use super::tier_last_day_stats::{DailyAllTierStats, LastDayTierStats};
 use super::tier_sweeper::{Jentry, delete_object_from_remote_tier};
+use crate::bucket::object_lock::ObjectLockApi;
 use crate::bucket::object_lock::objectlock_sys::enforce_retention_for_deletion;
 use crate::bucket::{metadata_sys::get_lifecycle_config, versioning_sys::BucketVersioningSys};
-use crate::client::object_api_utils::new_getobjectreader;
+use crate::client::transition_api::ReadCloser;
 use crate::error::Error;
 use crate::error::{error_resp_to_object_err, is_err_object_not_found, is_err_version_not_found, is_network_or_host_down};
 use crate::event::name::EventName;
@@ -62,7 +68,7 @@ use crate::store::ECStore;
 use crate::store_api::StorageAPI;
 use crate::store_api::{GetObjectReader, HTTPRangeSpec, ObjectInfo, ObjectOptions, ObjectToDelete};
 use crate::tier::warm_backend::WarmBackendGetOpts;
-use s3s::dto::{BucketLifecycleConfiguration, DefaultRetention, ReplicationConfiguration};
+use s3s::dto::{BucketLifecycleConfiguration, DefaultRetention, ObjectLockConfiguration, ReplicationConfiguration};
 
 pub type TimeFn = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync + 'static>;
 pub type TraceFn =
@@ -377,8 +383,8 @@ impl ExpiryState {
                         }
                     }
                     else if v.as_any().is::<NewerNoncurrentTask>() {
-                        let _v = v.as_any().downcast_ref::<NewerNoncurrentTask>().expect("err!");
-                        //delete_object_versions(api, &v.bucket, &v.versions, v.event).await;
+                        let v = v.as_any().downcast_ref::<NewerNoncurrentTask>().expect("err!");
+                        delete_object_versions(api.clone(), &v.bucket, &v.versions, &v.event).await;
                     }
                     else if v.as_any().is::<Jentry>() {
                         //transitionLogIf(es.ctx, deleteObjectFromRemoteTier(es.ctx, v.ObjName, v.VersionID, v.TierName))
@@ -663,7 +669,9 @@ pub async fn expire_transitioned_object(
         opts.version_id = oi.version_id.map(|id| id.to_string());
     }
     //let tags = LcAuditEvent::new(src, lcEvent).Tags();
-    if lc_event.action == IlmAction::DeleteRestoredAction {
+    if matches!(lc_event.action, IlmAction::DeleteRestoredAction | IlmAction::DeleteRestoredVersionAction) {
+        // only the restored copy goes away, the version itself stays on the tier
+        opts.version_id = oi.version_id.map(|id| id.to_string());
         opts.transition.expire_restored = true;
         match api.delete_object(&oi.bucket, &oi.name, opts).await {
             Ok(dobj) => {
@@ -734,6 +742,16 @@ pub fn gen_transition_objname(bucket: &str) -> Result<String, Error> {
 
 pub async fn transition_object(api: Arc<ECStore>, oi: &ObjectInfo, lae: LcAuditEvent) -> Result<(), Error> {
     let time_ilm = Metrics::time_ilm(lae.event.action);
+    let entry = LcAuditEntry {
+        bucket: oi.bucket.clone(),
+        object: oi.name.clone(),
+        version_id: oi.version_id,
+        upload_id: None,
+        rule_id: lae.event.rule_id.clone(),
+        action: lae.event.action,
+        source: lae.source.clone(),
+        versions: 1,
+    };
 
     let opts = ObjectOptions {
         transition: TransitionOptions {
@@ -749,54 +767,82 @@ pub async fn transition_object(api: Arc<ECStore>, oi: &ObjectInfo, lae: LcAuditE
         mod_time: oi.mod_time,
         ..Default::default()
     };
-    time_ilm(1);
-    api.transition_object(&oi.bucket, &oi.name, &opts).await
+    api.transition_object(&oi.bucket, &oi.name, &opts).await?;
+    time_ilm(1)();
+    audit_lifecycle_action(entry).await;
+    Ok(())
 }
 
 pub fn audit_tier_actions(_api: ECStore, _tier: &str, _bytes: i64) -> TimeFn {
     todo!();
 }
 
-pub async fn get_transitioned_object_reader(
-    bucket: &str,
-    object: &str,
-    rs: HTTPRangeSpec,
-    h: HeaderMap,
-    oi: ObjectInfo,
-    opts: &ObjectOptions,
-) -> Result<GetObjectReader, std::io::Error> {
+/// Fetches the stored bytes of a transitioned object from its tier, `length` < 0 reads to the end.
+pub async fn get_transitioned_object_data(oi: &ObjectInfo, offset: i64, length: i64) -> Result<ReadCloser, std::io::Error> {
     let mut tier_config_mgr = GLOBAL_TierConfigMgr.write().await;
-    let tgt_client = match tier_config_mgr.get_driver(&oi.transitioned_object.tier).await {
-        Ok(d) => d,
-        Err(err) => return Err(std::io::Error::other(err)),
-    };
+    let tgt_client = tier_config_mgr
+        .get_driver(&oi.transitioned_object.tier)
+        .await
+        .map_err(std::io::Error::other)?;
 
-    let ret = new_getobjectreader(rs, &oi, opts, &h);
-    if let Err(err) = ret {
-        return Err(error_resp_to_object_err(err, vec![bucket, object]));
-    }
-    let (get_fn, off, length) = ret.expect("err");
     let mut gopts = WarmBackendGetOpts::default();
-
-    if off >= 0 && length >= 0 {
-        gopts.start_offset = off;
+    if offset >= 0 && length >= 0 {
+        gopts.start_offset = offset;
         gopts.length = length;
     }
 
-    //return Ok(HttpFileReader::new(rs, &oi, opts, &h));
-    //timeTierAction := auditTierActions(oi.transitioned_object.Tier, length)
-    let reader = tgt_client
+    tgt_client
         .get(&oi.transitioned_object.name, &oi.transitioned_object.version_id, gopts)
-        .await?;
-    Ok(get_fn(reader, h))
+        .await
 }
 
-pub fn post_restore_opts(_r: http::Request<Body>, _bucket: &str, _object: &str) -> Result<ObjectOptions, std::io::Error> {
-    todo!();
+pub async fn get_transitioned_object_reader(
+    bucket: &str,
+    object: &str,
+    rs: Option<HTTPRangeSpec>,
+    h: &HeaderMap,
+    oi: &ObjectInfo,
+    opts: &ObjectOptions,
+) -> Result<GetObjectReader, std::io::Error> {
+    let (rd, mut wd) = tokio::io::duplex(crate::set_disk::DEFAULT_READ_BUFFER_SIZE);
+
+    let (reader, offset, length) = GetObjectReader::new(Box::new(rd), rs, oi, opts, h)
+        .await
+        .map_err(|err| std::io::Error::from(crate::error::to_object_err(err, vec![bucket, object])))?;
+
+    //timeTierAction := auditTierActions(oi.transitioned_object.Tier, length)
+    let mut data = get_transitioned_object_data(oi, offset as i64, length).await?;
+
+    let (bucket, object) = (bucket.to_owned(), object.to_owned());
+    tokio::spawn(async move {
+        if let Err(err) = tokio::io::copy(&mut data, &mut wd).await {
+            error!("get_transitioned_object_reader {}/{} err {:?}", bucket, object, err);
+        }
+    });
+
+    Ok(reader)
 }
 
-pub fn put_restore_opts(_bucket: &str, _object: &str, _rreq: &RestoreObjectRequest, _oi: &ObjectInfo) -> ObjectOptions {
-    todo!();
+/// Options to write back the rehydrated copy of a transitioned object: the version keeps
+/// its id, etag and mod time, and records until when the restored copy is kept.
+pub fn put_restore_opts(rreq: &RestoreObjectRequest, restore_expiry: OffsetDateTime, oi: &ObjectInfo) -> ObjectOptions {
+    let mut user_defined = oi.user_defined.clone();
+    user_defined.insert(X_AMZ_RESTORE.as_str().to_string(), completed_restore_obj(restore_expiry).to_string());
+    user_defined.insert(AMZ_RESTORE_EXPIRY_DAYS.to_string(), rreq.days.to_string());
+    user_defined.insert(
+        AMZ_RESTORE_REQUEST_DATE.to_string(),
+        OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+    );
+
+    let version_id = oi.version_id.filter(|vid| !vid.is_nil()).map(|vid| vid.to_string());
+    ObjectOptions {
+        versioned: version_id.is_some(),
+        version_id,
+        mod_time: oi.mod_time,
+        preserve_etag: oi.etag.clone(),
+        user_defined,
+        ..Default::default()
+    }
 }
 
 pub trait LifecycleOps {
@@ -848,18 +894,87 @@ pub struct RestoreObjectRequest {
 
 const _MAX_RESTORE_OBJECT_REQUEST_SIZE: i64 = 2 << 20;
 
+const RESTORE_EXPIRY_FORMAT: &[FormatItem<'static>] =
+    format_description!("[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT");
+
+/// State of a restore request as reported in the `x-amz-restore` header.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RestoreObjStatus {
+    pub ongoing: bool,
+    pub expiry: Option<OffsetDateTime>,
+}
+
+impl std::fmt::Display for RestoreObjStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ongoing {
+            return write!(f, "ongoing-request=\"true\"");
+        }
+        match self
+            .expiry
+            .and_then(|expiry| expiry.to_offset(UtcOffset::UTC).format(RESTORE_EXPIRY_FORMAT).ok())
+        {
+            Some(expiry) => write!(f, "ongoing-request=\"false\", expiry-date=\"{expiry}\""),
+            None => write!(f, "ongoing-request=\"false\""),
+        }
+    }
+}
+
+impl RestoreObjStatus {
+    pub fn on_disk(&self) -> bool {
+        !self.ongoing && self.expiry.is_some_and(|expiry| OffsetDateTime::now_utc() < expiry)
+    }
+}
+
+pub fn ongoing_restore_obj() -> RestoreObjStatus {
+    RestoreObjStatus {
+        ongoing: true,
+        expiry: None,
+    }
+}
+
+pub fn completed_restore_obj(expiry: OffsetDateTime) -> RestoreObjStatus {
+    RestoreObjStatus {
+        ongoing: false,
+        expiry: Some(expiry),
+    }
+}
+
+/// Parses the value of the `x-amz-restore` header.
+pub fn parse_restore_obj_status(restore_hdr: &str) -> Option<RestoreObjStatus> {
+    let mut tokens = restore_hdr.splitn(2, ',');
+    let ongoing = match tokens.next()?.trim() {
+        "ongoing-request=\"true\"" => return tokens.next().is_none().then(ongoing_restore_obj),
+        "ongoing-request=\"false\"" => false,
+        _ => return None,
+    };
+
+    let Some(expiry) = tokens.next() else {
+        return Some(RestoreObjStatus { ongoing, expiry: None });
+    };
+    let expiry = expiry.trim().strip_prefix("expiry-date=\"")?.strip_suffix('"')?;
+    let expiry = PrimitiveDateTime::parse(expiry, RESTORE_EXPIRY_FORMAT).ok()?.assume_utc();
+    Some(completed_restore_obj(expiry))
+}
+
+/// Whether a restored copy of a transitioned object is available locally.
+pub fn is_restored_object_on_disk(meta: &HashMap<String, String>) -> bool {
+    meta.get(X_AMZ_RESTORE.as_str())
+        .and_then(|hdr| parse_restore_obj_status(hdr))
+        .is_some_and(|status| status.on_disk())
+}
+
 pub async fn eval_action_from_lifecycle(
     lc: &BucketLifecycleConfiguration,
-    lr: Option<DefaultRetention>,
+    lr: Option<&ObjectLockConfiguration>,
     rcfg: Option<(ReplicationConfiguration, OffsetDateTime)>,
     oi: &ObjectInfo,
 ) -> lifecycle::Event {
     let event = lc.eval(&oi.to_lifecycle_opts()).await;
     //if serverDebugLog {
-    info!("lifecycle: Secondary scan: {}", event.action);
+    debug!("lifecycle: Secondary scan: {}", event.action);
     //}
 
-    let lock_enabled = if let Some(lr) = lr { lr.mode.is_some() } else { false };
+    let lock_enabled = lr.is_some_and(|lr| lr.enabled());
 
     match event.action {
         lifecycle::IlmAction::DeleteAllVersionsAction | lifecycle::IlmAction::DelMarkerDeleteAllVersionsAction => {
@@ -911,11 +1026,23 @@ pub async fn apply_expiry_on_transitioned_object(
     lc_event: &lifecycle::Event,
     src: &LcEventSrc,
 ) -> bool {
-    // let time_ilm = ScannerMetrics::time_ilm(lc_event.action.clone());
-    if let Err(_err) = expire_transitioned_object(api, oi, lc_event, src).await {
+    let time_ilm = Metrics::time_ilm(lc_event.action);
+    if let Err(err) = expire_transitioned_object(api, oi, lc_event, src).await {
+        warn!("lifecycle: expiring transitioned {}/{} failed: {}", oi.bucket, oi.name, err);
         return false;
     }
-    // let _ = time_ilm(1);
+    time_ilm(1)();
+    audit_lifecycle_action(LcAuditEntry {
+        bucket: oi.bucket.clone(),
+        object: oi.name.clone(),
+        version_id: oi.version_id,
+        upload_id: None,
+        rule_id: lc_event.rule_id.clone(),
+        action: lc_event.action,
+        source: src.clone(),
+        versions: 1,
+    })
+    .await;
 
     true
 }
@@ -924,7 +1051,7 @@ pub async fn apply_expiry_on_non_transitioned_objects(
     api: Arc<ECStore>,
     oi: &ObjectInfo,
     lc_event: &lifecycle::Event,
-    _src: &LcEventSrc,
+    src: &LcEventSrc,
 ) -> bool {
     let mut opts = ObjectOptions {
         expiration: ExpirationOptions { expire: true },
@@ -943,12 +1070,15 @@ pub async fn apply_expiry_on_non_transitioned_objects(
         opts.delete_prefix_object = true;
     }
 
-    // let time_ilm = ScannerMetrics::time_ilm(lc_event.action.clone());
+    let time_ilm = Metrics::time_ilm(lc_event.action);
 
-    let mut dobj = api
-        .delete_object(&oi.bucket, &encode_dir_object(&oi.name), opts)
-        .await
-        .unwrap();
+    let mut dobj = match api.delete_object(&oi.bucket, &encode_dir_object(&oi.name), opts).await {
+        Ok(dobj) => dobj,
+        Err(err) => {
+            warn!("lifecycle: expiring {}/{} failed: {}", oi.bucket, oi.name, err);
+            return false;
+        }
+    };
     if dobj.name.is_empty() {
         dobj = oi.clone();
     }
@@ -975,16 +1105,87 @@ pub async fn apply_expiry_on_non_transitioned_objects(
     });
 
     if lc_event.action != lifecycle::IlmAction::NoneAction {
-        // let mut num_versions = 1_u64;
-        // if lc_event.action.delete_all() {
-        //     num_versions = oi.num_versions as u64;
-        // }
-        // let _ = time_ilm(num_versions);
+        let mut num_versions = 1_u64;
+        if lc_event.action.delete_all() {
+            num_versions = oi.num_versions as u64;
+        }
+        time_ilm(num_versions)();
+        audit_lifecycle_action(LcAuditEntry {
+            bucket: oi.bucket.clone(),
+            object: oi.name.clone(),
+            version_id: oi.version_id,
+            upload_id: None,
+            rule_id: lc_event.rule_id.clone(),
+            action: lc_event.action,
+            source: src.clone(),
+            versions: num_versions,
+        })
+        .await;
     }
 
     true
 }
 
+/// Delete the noncurrent versions the scanner found beyond the newer ones a rule keeps
+async fn delete_object_versions(api: Arc<ECStore>, bucket: &str, versions: &[ObjectToDelete], lc_event: &lifecycle::Event) {
+    let time_ilm = Metrics::time_ilm(lc_event.action);
+    let opts = ObjectOptions {
+        versioned: BucketVersioningSys::prefix_enabled(bucket, &versions[0].object_name).await,
+        version_suspended: BucketVersioningSys::prefix_suspended(bucket, &versions[0].object_name).await,
+        expiration: ExpirationOptions { expire: true },
+        ..Default::default()
+    };
+    let (deleted, errs) = match api.delete_objects(bucket, versions.to_vec(), opts).await {
+        Ok(res) => res,
+        Err(err) => {
+            warn!(
+                "lifecycle: expiring noncurrent versions of {}/{} failed: {}",
+                bucket, versions[0].object_name, err
+            );
+            return;
+        }
+    };
+
+    let mut removed = 0;
+    for (version, err) in versions.iter().zip(errs.iter()) {
+        if let Some(err) = err {
+            warn!(
+                "lifecycle: expiring {}/{} version {:?} failed: {}",
+                bucket, version.object_name, version.version_id, err
+            );
+            continue;
+        }
+        removed += 1;
+        audit_lifecycle_action(LcAuditEntry {
+            bucket: bucket.to_string(),
+            object: version.object_name.clone(),
+            version_id: version.version_id,
+            upload_id: None,
+            rule_id: lc_event.rule_id.clone(),
+            action: lc_event.action,
+            source: LcEventSrc::Scanner,
+            versions: 1,
+        })
+        .await;
+    }
+    for dobj in deleted.into_iter().filter(|dobj| !dobj.object_name.is_empty()) {
+        send_event(EventArgs {
+            event_name: EventName::ObjectRemovedDelete.as_ref().to_string(),
+            bucket_name: bucket.to_string(),
+            object: ObjectInfo {
+                bucket: bucket.to_string(),
+                name: dobj.object_name,
+                version_id: dobj.version_id.as_deref().and_then(|v| Uuid::parse_str(v).ok()),
+                ..Default::default()
+            },
+            user_agent: "Internal: [ILM-Expiry]".to_string(),
+            host: GLOBAL_LocalNodeName.to_string(),
+            ..Default::default()
+        });
+    }
+    time_ilm(removed)();
+}
+
 async fn apply_expiry_rule(event: &lifecycle::Event, src: &LcEventSrc, oi: &ObjectInfo) -> bool {
     let mut expiry_state = GLOBAL_ExpiryState.write().await;
     expiry_state.enqueue_by_days(oi, event, src).await;
@@ -1009,3 +1210,43 @@ pub async fn apply_lifecycle_action(event: &lifecycle::Event, src: &LcEventSrc,
     }
     success
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_restore_obj_status() {
+        assert_eq!(ongoing_restore_obj().to_string(), "ongoing-request=\"true\"");
+        assert_eq!(RestoreObjStatus::default().to_string(), "ongoing-request=\"false\"");
+
+        let expiry = datetime!(2024-03-01 00:00:00 UTC);
+        let completed = completed_restore_obj(expiry).to_string();
+        assert_eq!(completed, "ongoing-request=\"false\", expiry-date=\"Fri, 01 Mar 2024 00:00:00 GMT\"");
+
+        assert_eq!(parse_restore_obj_status(&completed), Some(completed_restore_obj(expiry)));
+        assert_eq!(parse_restore_obj_status("ongoing-request=\"true\""), Some(ongoing_restore_obj()));
+        assert_eq!(parse_restore_obj_status("ongoing-request=\"false\""), Some(RestoreObjStatus::default()));
+        assert_eq!(parse_restore_obj_status("ongoing-request=\"true\", expiry-date=\"x\""), None);
+        assert_eq!(parse_restore_obj_status("ongoing-request=\"false\", expiry-date=\"tomorrow\""), None);
+        assert_eq!(parse_restore_obj_status(""), None);
+    }
+
+    #[test]
+    fn test_is_restored_object_on_disk() {
+        let mut meta = HashMap::new();
+        assert!(!is_restored_object_on_disk(&meta));
+
+        meta.insert(X_AMZ_RESTORE.as_str().to_string(), ongoing_restore_obj().to_string());
+        assert!(!is_restored_object_on_disk(&meta));
+
+        let expiry = OffsetDateTime::now_utc() + time::Duration::days(1);
+        meta.insert(X_AMZ_RESTORE.as_str().to_string(), completed_restore_obj(expiry).to_string());
+        assert!(is_restored_object_on_disk(&meta));
+
+        let expiry = OffsetDateTime::now_utc() - time::Duration::days(1);
+        meta.insert(X_AMZ_RESTORE.as_str().to_string(), completed_restore_obj(expiry).to_string());
+        assert!(!is_restored_object_on_disk(&meta));
+    }
+}
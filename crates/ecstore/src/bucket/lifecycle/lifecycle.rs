@@ -27,9 +27,9 @@ use std::env;
 use std::fmt::Display;
 use time::macros::{datetime, offset};
 use time::{self, Duration, OffsetDateTime};
-use tracing::info;
+use tracing::debug;
 
-use crate::bucket::lifecycle::rule::TransitionOps;
+use crate::bucket::lifecycle::rule::{Filter, TransitionOps};
 
 use super::bucket_lifecycle_ops::RestoreObjectRequest;
 
@@ -39,93 +39,130 @@ pub const TRANSITION_PENDING: &str = "pending";
 const ERR_LIFECYCLE_TOO_MANY_RULES: &str = "Lifecycle configuration allows a maximum of 1000 rules";
 const ERR_LIFECYCLE_NO_RULE: &str = "Lifecycle configuration should have at least one rule";
 const ERR_LIFECYCLE_DUPLICATE_ID: &str = "Rule ID must be unique. Found same ID for more than one rule";
-const _ERR_XML_NOT_WELL_FORMED: &str =
-    "The XML you provided was not well-formed or did not validate against our published schema";
+const ERR_XML_NOT_WELL_FORMED: &str = "The XML you provided was not well-formed or did not validate against our published schema";
+const ERR_INVALID_RULE_ID: &str = "ID length is limited to 255 characters";
+const ERR_EMPTY_RULE_STATUS: &str = "Status should not be empty";
+const ERR_INVALID_RULE_STATUS: &str = "Status must be set to either Enabled or Disabled";
+const ERR_LIFECYCLE_NO_ACTION: &str = "At least one action needs to be specified in a rule";
+const ERR_LIFECYCLE_INVALID_DAYS: &str = "Days must be positive integer when used with Expiration";
+const ERR_LIFECYCLE_INVALID_EXPIRATION: &str =
+    "Exactly one of Days (positive integer) or Date (positive ISO 8601 format) should be present inside Expiration.";
+const ERR_LIFECYCLE_INVALID_DELETE_MARKER: &str =
+    "Delete marker cannot be specified with Days or Date in a Lifecycle Expiration Policy";
+const ERR_LIFECYCLE_DELETE_MARKER_WITH_TAGS: &str = "ExpiredObjectDeleteMarker cannot be specified with tag filters";
+const ERR_LIFECYCLE_INVALID_NONCURRENT_EXPIRATION: &str =
+    "NoncurrentDays or NewerNoncurrentVersions must be a positive integer in NoncurrentVersionExpiration";
+const ERR_LIFECYCLE_INVALID_ABORT_DAYS: &str = "DaysAfterInitiation must be a positive integer";
+const ERR_LIFECYCLE_ABORT_WITH_TAGS: &str = "AbortIncompleteMultipartUpload cannot be specified with tag filters";
 const ERR_LIFECYCLE_BUCKET_LOCKED: &str =
     "ExpiredObjectAllVersions element and DelMarkerExpiration action cannot be used on an retention bucket";
 
 pub use rustfs_common::metrics::IlmAction;
 
+/// Why a lifecycle configuration is refused
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LifecycleConfigError {
+    /// The configuration does not follow the schema of lifecycle configurations
+    #[error("{}", ERR_XML_NOT_WELL_FORMED)]
+    MalformedXml,
+    /// A rule of the configuration, or their number, is invalid
+    #[error("{0}")]
+    Invalid(&'static str),
+}
+
 #[async_trait::async_trait]
 pub trait RuleValidate {
-    fn validate(&self) -> Result<(), std::io::Error>;
+    fn validate(&self) -> Result<(), LifecycleConfigError>;
 }
 
 #[async_trait::async_trait]
 impl RuleValidate for LifecycleRule {
-    /*fn validate_id(&self) -> Result<()> {
-        if self.id.len() > 255 {
-            return errInvalidRuleID;
+    fn validate(&self) -> Result<(), LifecycleConfigError> {
+        if self.id.as_ref().is_some_and(|id| id.len() > 255) {
+            return Err(LifecycleConfigError::Invalid(ERR_INVALID_RULE_ID));
+        }
+        match self.status.as_str() {
+            "" => return Err(LifecycleConfigError::Invalid(ERR_EMPTY_RULE_STATUS)),
+            ExpirationStatus::ENABLED | ExpirationStatus::DISABLED => (),
+            _ => return Err(LifecycleConfigError::Invalid(ERR_INVALID_RULE_STATUS)),
+        }
+
+        if self.prefix.is_some() && self.filter.is_some() {
+            return Err(LifecycleConfigError::MalformedXml);
+        }
+        if let Some(filter) = &self.filter {
+            filter.validate()?;
+        }
+        let tagged = self.filter.as_ref().is_some_and(|filter| filter.has_tags());
+
+        if let Some(expiration) = &self.expiration {
+            let set = expiration.days.is_some() as u8
+                + expiration.date.is_some() as u8
+                + expiration.expired_object_delete_marker.is_some() as u8;
+            if set != 1 {
+                return Err(LifecycleConfigError::Invalid(if expiration.expired_object_delete_marker.is_some() {
+                    ERR_LIFECYCLE_INVALID_DELETE_MARKER
+                } else {
+                    ERR_LIFECYCLE_INVALID_EXPIRATION
+                }));
+            }
+            if expiration.days.is_some_and(|days| days <= 0) {
+                return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_INVALID_DAYS));
+            }
+            if expiration.expired_object_delete_marker == Some(true) && tagged {
+                return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_DELETE_MARKER_WITH_TAGS));
+            }
         }
-        Ok(())
-    }
 
-    fn validate_status(&self) -> Result<()> {
-        if self.status.len() == 0 {
-            return ErrEmptyRuleStatus;
+        if let Some(expiration) = &self.noncurrent_version_expiration {
+            let days = expiration.noncurrent_days.unwrap_or_default();
+            let newer = expiration.newer_noncurrent_versions.unwrap_or_default();
+            if days < 0 || newer < 0 || (days == 0 && newer == 0) {
+                return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_INVALID_NONCURRENT_EXPIRATION));
+            }
         }
 
-        if self.status != Enabled && self.status != Disabled {
-            return ErrInvalidRuleStatus;
+        if let Some(abort) = &self.abort_incomplete_multipart_upload {
+            if abort.days_after_initiation.is_none_or(|days| days <= 0) {
+                return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_INVALID_ABORT_DAYS));
+            }
+            if tagged {
+                return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_ABORT_WITH_TAGS));
+            }
         }
-        Ok(())
-    }
-
-    fn validate_expiration(&self) -> Result<()> {
-        self.expiration.validate();
-    }
-
-    fn validate_noncurrent_expiration(&self) -> Result<()> {
-        self.noncurrent_version_expiration.validate()
-    }
 
-    fn validate_prefix_and_filter(&self) -> Result<()> {
-        if !self.prefix.set && self.Filter.isempty() || self.prefix.set && !self.filter.isempty() {
-            return ErrXMLNotWellFormed;
+        for transition in self.transitions.iter().flatten() {
+            transition.validate()?;
         }
-        if !self.prefix.set {
-            return self.filter.validate();
+        for transition in self.noncurrent_version_transitions.iter().flatten() {
+            if transition.storage_class.is_none() || transition.noncurrent_days.is_some_and(|days| days < 0) {
+                return Err(LifecycleConfigError::MalformedXml);
+            }
         }
-        Ok(())
-    }
 
-    fn validate_transition(&self) -> Result<()> {
-        self.Transition.Validate()
+        if self.expiration.is_none()
+            && self.noncurrent_version_expiration.is_none()
+            && self.abort_incomplete_multipart_upload.is_none()
+            && self.transitions.as_ref().is_none_or(|t| t.is_empty())
+            && self.noncurrent_version_transitions.as_ref().is_none_or(|t| t.is_empty())
+        {
+            return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_NO_ACTION));
+        }
+        Ok(())
     }
+}
 
-    fn validate_noncurrent_transition(&self) -> Result<()> {
-        self.NoncurrentVersionTransition.Validate()
+/// Key prefix the objects of a rule start with, from its filter or the legacy prefix element
+pub fn rule_prefix(rule: &LifecycleRule) -> &str {
+    match (&rule.prefix, &rule.filter) {
+        (Some(prefix), _) => prefix,
+        (None, Some(filter)) => filter.prefix(),
+        (None, None) => "",
     }
+}
 
-    fn get_prefix(&self) -> String {
-        if p := self.Prefix.String(); p != "" {
-          return p
-        }
-        if p := self.Filter.Prefix.String(); p != "" {
-          return p
-        }
-        if p := self.Filter.And.Prefix.String(); p != "" {
-          return p
-        }
-        "".to_string()
-    }*/
-
-    fn validate(&self) -> Result<(), std::io::Error> {
-        /*self.validate_id()?;
-        self.validate_status()?;
-        self.validate_expiration()?;
-        self.validate_noncurrent_expiration()?;
-        self.validate_prefix_and_filter()?;
-        self.validate_transition()?;
-        self.validate_noncurrent_transition()?;
-        if (!self.Filter.Tag.IsEmpty() || len(self.Filter.And.Tags) != 0) && !self.delmarker_expiration.Empty() {
-          return errInvalidRuleDelMarkerExpiration
-        }
-        if !self.expiration.set && !self.transition.set && !self.noncurrent_version_expiration.set && !self.noncurrent_version_transitions.unwrap()[0].set && self.delmarker_expiration.Empty() {
-          return errXMLNotWellFormed
-        }*/
-        Ok(())
-    }
+fn rule_enabled(rule: &LifecycleRule) -> bool {
+    rule.status.as_str() == ExpirationStatus::ENABLED
 }
 
 #[async_trait::async_trait]
@@ -133,12 +170,15 @@ pub trait Lifecycle {
     async fn has_transition(&self) -> bool;
     fn has_expiry(&self) -> bool;
     async fn has_active_rules(&self, prefix: &str) -> bool;
-    async fn validate(&self, lr: &ObjectLockConfiguration) -> Result<(), std::io::Error>;
+    async fn validate(&self, lr: &ObjectLockConfiguration) -> Result<(), LifecycleConfigError>;
     async fn filter_rules(&self, obj: &ObjectOpts) -> Option<Vec<LifecycleRule>>;
     async fn eval(&self, obj: &ObjectOpts) -> Event;
     async fn eval_inner(&self, obj: &ObjectOpts, now: OffsetDateTime) -> Event;
     //fn set_prediction_headers(&self, w: http.ResponseWriter, obj: ObjectOpts);
     async fn noncurrent_versions_expiration_limit(&self, obj: &ObjectOpts) -> Event;
+    /// Abort action of the first rule whose AbortIncompleteMultipartUpload is due for an upload
+    /// of `object` initiated at `initiated`
+    fn eval_abort_multipart(&self, object: &str, initiated: OffsetDateTime, now: OffsetDateTime) -> Event;
 }
 
 #[async_trait::async_trait]
@@ -162,64 +202,50 @@ impl Lifecycle for BucketLifecycleConfiguration {
     }
 
     async fn has_active_rules(&self, prefix: &str) -> bool {
-        if self.rules.len() == 0 {
-            return false;
-        }
-        for rule in self.rules.iter() {
-            if rule.status.as_str() == ExpirationStatus::DISABLED {
-                continue;
-            }
-
-            let rule_prefix = rule.prefix.as_ref().expect("err!");
-            if prefix.len() > 0 && rule_prefix.len() > 0 && !prefix.starts_with(rule_prefix) && !rule_prefix.starts_with(&prefix)
+        let now = OffsetDateTime::now_utc();
+        for rule in self.rules.iter().filter(|rule| rule_enabled(rule)) {
+            let rule_prefix = rule_prefix(rule);
+            if !prefix.is_empty()
+                && !rule_prefix.is_empty()
+                && !prefix.starts_with(rule_prefix)
+                && !rule_prefix.starts_with(prefix)
             {
                 continue;
             }
 
-            let rule_noncurrent_version_expiration = rule.noncurrent_version_expiration.as_ref().expect("err!");
-            if rule_noncurrent_version_expiration.noncurrent_days.expect("err!") > 0 {
-                return true;
-            }
-            if rule_noncurrent_version_expiration.newer_noncurrent_versions.expect("err!") > 0 {
-                return true;
-            }
-            if !rule.noncurrent_version_transitions.is_none() {
-                return true;
-            }
-            let rule_expiration = rule.expiration.as_ref().expect("err!");
-            if !rule_expiration.date.is_none()
-                && OffsetDateTime::from(rule_expiration.date.clone().expect("err!")).unix_timestamp()
-                    < OffsetDateTime::now_utc().unix_timestamp()
-            {
-                return true;
-            }
-            if !rule_expiration.date.is_none() {
-                return true;
+            if let Some(expiration) = &rule.noncurrent_version_expiration {
+                if expiration.noncurrent_days.unwrap_or_default() > 0
+                    || expiration.newer_noncurrent_versions.unwrap_or_default() > 0
+                {
+                    return true;
+                }
             }
-            if rule_expiration.expired_object_delete_marker.expect("err!") {
+            if rule.noncurrent_version_transitions.as_ref().is_some_and(|t| !t.is_empty()) {
                 return true;
             }
-            let rule_transitions: &[Transition] = &rule.transitions.as_ref().expect("err!");
-            let rule_transitions_0 = rule_transitions[0].clone();
-            if !rule_transitions_0.date.is_none()
-                && OffsetDateTime::from(rule_transitions_0.date.expect("err!")).unix_timestamp()
-                    < OffsetDateTime::now_utc().unix_timestamp()
-            {
-                return true;
+            if let Some(expiration) = &rule.expiration {
+                if expiration.date.clone().is_some_and(|date| OffsetDateTime::from(date) < now) {
+                    return true;
+                }
+                if expiration.days.is_some() || expiration.expired_object_delete_marker == Some(true) {
+                    return true;
+                }
             }
-            if !rule.transitions.is_none() {
-                return true;
+            for transition in rule.transitions.iter().flatten() {
+                if transition.days.is_some() || transition.date.clone().is_some_and(|date| OffsetDateTime::from(date) < now) {
+                    return true;
+                }
             }
         }
         false
     }
 
-    async fn validate(&self, lr: &ObjectLockConfiguration) -> Result<(), std::io::Error> {
+    async fn validate(&self, lr: &ObjectLockConfiguration) -> Result<(), LifecycleConfigError> {
         if self.rules.len() > 1000 {
-            return Err(std::io::Error::other(ERR_LIFECYCLE_TOO_MANY_RULES));
+            return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_TOO_MANY_RULES));
         }
         if self.rules.len() == 0 {
-            return Err(std::io::Error::other(ERR_LIFECYCLE_NO_RULE));
+            return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_NO_RULE));
         }
 
         for r in &self.rules {
@@ -228,7 +254,7 @@ impl Lifecycle for BucketLifecycleConfiguration {
                 if let Some(expiration) = r.expiration.as_ref() {
                     if let Some(expired_object_delete_marker) = expiration.expired_object_delete_marker {
                         if object_lock_enabled.as_str() == ObjectLockEnabled::ENABLED && (expired_object_delete_marker) {
-                            return Err(std::io::Error::other(ERR_LIFECYCLE_BUCKET_LOCKED));
+                            return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_BUCKET_LOCKED));
                         }
                     }
                         }
@@ -241,7 +267,7 @@ impl Lifecycle for BucketLifecycleConfiguration {
             let other_rules = &self.rules[i + 1..];
             for other_rule in other_rules {
                 if self.rules[i].id == other_rule.id {
-                    return Err(std::io::Error::other(ERR_LIFECYCLE_DUPLICATE_ID));
+                    return Err(LifecycleConfigError::Invalid(ERR_LIFECYCLE_DUPLICATE_ID));
                 }
             }
         }
@@ -249,25 +275,21 @@ impl Lifecycle for BucketLifecycleConfiguration {
     }
 
     async fn filter_rules(&self, obj: &ObjectOpts) -> Option<Vec<LifecycleRule>> {
-        if obj.name == "" {
+        if obj.name.is_empty() {
             return None;
         }
         let mut rules = Vec::<LifecycleRule>::new();
-        for rule in self.rules.iter() {
-            if rule.status.as_str() == ExpirationStatus::DISABLED {
+        for rule in self.rules.iter().filter(|rule| rule_enabled(rule)) {
+            if !obj.name.starts_with(rule_prefix(rule)) {
                 continue;
             }
-            if let Some(prefix) = rule.prefix.clone() {
-                if !obj.name.starts_with(prefix.as_str()) {
+            if let Some(filter) = &rule.filter {
+                if !Filter::test_tags(filter, &obj.user_tags) {
+                    continue;
+                }
+                if !obj.delete_marker && !filter.by_size(obj.size as i64) {
                     continue;
                 }
-            }
-            /*if !rule.filter.test_tags(obj.user_tags) {
-                continue;
-            }*/
-            //if !obj.delete_marker && !rule.filter.BySize(obj.size) {
-            if !obj.delete_marker && false {
-                continue;
             }
             rules.push(rule.clone());
         }
@@ -280,17 +302,17 @@ impl Lifecycle for BucketLifecycleConfiguration {
 
     async fn eval_inner(&self, obj: &ObjectOpts, now: OffsetDateTime) -> Event {
         let mut events = Vec::<Event>::new();
-        info!(
+        debug!(
             "eval_inner: object={}, mod_time={:?}, now={:?}, is_latest={}, delete_marker={}",
             obj.name, obj.mod_time, now, obj.is_latest, obj.delete_marker
         );
-        if obj.mod_time.expect("err").unix_timestamp() == 0 {
-            info!("eval_inner: mod_time is 0, returning default event");
+        let Some(mod_time) = obj.mod_time.filter(|t| t.unix_timestamp() != 0) else {
+            debug!("eval_inner: mod_time is 0, returning default event");
             return Event::default();
-        }
+        };
 
         if let Some(restore_expires) = obj.restore_expires {
-            if !restore_expires.unix_timestamp() == 0 && now.unix_timestamp() > restore_expires.unix_timestamp() {
+            if restore_expires.unix_timestamp() != 0 && now.unix_timestamp() > restore_expires.unix_timestamp() {
                 let mut action = IlmAction::DeleteRestoredAction;
                 if !obj.is_latest {
                     action = IlmAction::DeleteRestoredVersionAction;
@@ -309,12 +331,13 @@ impl Lifecycle for BucketLifecycleConfiguration {
 
         if let Some(ref lc_rules) = self.filter_rules(obj).await {
             for rule in lc_rules.iter() {
+                let rule_id = rule.id.clone().unwrap_or_default();
                 if obj.expired_object_deletemarker() {
                     if let Some(expiration) = rule.expiration.as_ref() {
-                        if let Some(expired_object_delete_marker) = expiration.expired_object_delete_marker {
+                        if expiration.expired_object_delete_marker == Some(true) {
                             events.push(Event {
                                 action: IlmAction::DeleteVersionAction,
-                                rule_id: rule.id.clone().expect("err!"),
+                                rule_id: rule_id.clone(),
                                 due: Some(now),
                                 noncurrent_days: 0,
                                 newer_noncurrent_versions: 0,
@@ -322,15 +345,13 @@ impl Lifecycle for BucketLifecycleConfiguration {
                             });
                             break;
                         }
-                    }
 
-                    if let Some(expiration) = rule.expiration.as_ref() {
                         if let Some(days) = expiration.days {
-                            let expected_expiry = expected_expiry_time(obj.mod_time.expect("err!"), days /*, date*/);
+                            let expected_expiry = expected_expiry_time(mod_time, days);
                             if now.unix_timestamp() == 0 || now.unix_timestamp() > expected_expiry.unix_timestamp() {
                                 events.push(Event {
                                     action: IlmAction::DeleteVersionAction,
-                                    rule_id: rule.id.clone().expect("err!"),
+                                    rule_id: rule_id.clone(),
                                     due: Some(expected_expiry),
                                     noncurrent_days: 0,
                                     newer_noncurrent_versions: 0,
@@ -342,80 +363,42 @@ impl Lifecycle for BucketLifecycleConfiguration {
                     }
                 }
 
-                if obj.is_latest {
-                    if let Some(ref expiration) = rule.expiration {
-                        if let Some(expired_object_delete_marker) = expiration.expired_object_delete_marker {
-                            if obj.delete_marker && expired_object_delete_marker {
-                                let due = expiration.next_due(obj);
-                                if let Some(due) = due {
-                                    if now.unix_timestamp() == 0 || now.unix_timestamp() > due.unix_timestamp() {
-                                        events.push(Event {
-                                            action: IlmAction::DelMarkerDeleteAllVersionsAction,
-                                            rule_id: rule.id.clone().expect("err!"),
-                                            due: Some(due),
-                                            noncurrent_days: 0,
-                                            newer_noncurrent_versions: 0,
-                                            storage_class: "".into(),
-                                        });
-                                    }
-                                }
-                                continue;
-                            }
-                        }
-                    }
-                }
-
                 if !obj.is_latest {
                     if let Some(ref noncurrent_version_expiration) = rule.noncurrent_version_expiration {
-                        if let Some(newer_noncurrent_versions) = noncurrent_version_expiration.newer_noncurrent_versions {
-                            if newer_noncurrent_versions > 0 {
-                                continue;
-                            }
+                        // Versions beyond the newer ones to keep are expired by the scanner, see
+                        // noncurrent_versions_expiration_limit
+                        if noncurrent_version_expiration.newer_noncurrent_versions.unwrap_or_default() > 0 {
+                            continue;
                         }
-                    }
-                }
 
-                if !obj.is_latest {
-                    if let Some(ref noncurrent_version_expiration) = rule.noncurrent_version_expiration {
-                        if let Some(noncurrent_days) = noncurrent_version_expiration.noncurrent_days {
-                            if noncurrent_days != 0 {
-                                if let Some(successor_mod_time) = obj.successor_mod_time {
-                                    let expected_expiry = expected_expiry_time(successor_mod_time, noncurrent_days);
-                                    if now.unix_timestamp() == 0 || now.unix_timestamp() > expected_expiry.unix_timestamp() {
-                                        events.push(Event {
-                                            action: IlmAction::DeleteVersionAction,
-                                            rule_id: rule.id.clone().expect("err!"),
-                                            due: Some(expected_expiry),
-                                            noncurrent_days: 0,
-                                            newer_noncurrent_versions: 0,
-                                            storage_class: "".into(),
-                                        });
-                                    }
+                        let noncurrent_days = noncurrent_version_expiration.noncurrent_days.unwrap_or_default();
+                        if noncurrent_days != 0 {
+                            if let Some(successor_mod_time) = obj.successor_mod_time {
+                                let expected_expiry = expected_expiry_time(successor_mod_time, noncurrent_days);
+                                if now.unix_timestamp() == 0 || now.unix_timestamp() > expected_expiry.unix_timestamp() {
+                                    events.push(Event {
+                                        action: IlmAction::DeleteVersionAction,
+                                        rule_id: rule_id.clone(),
+                                        due: Some(expected_expiry),
+                                        noncurrent_days: 0,
+                                        newer_noncurrent_versions: 0,
+                                        storage_class: "".into(),
+                                    });
                                 }
                             }
                         }
                     }
-                }
 
-                if !obj.is_latest {
-                    if let Some(ref noncurrent_version_transitions) = rule.noncurrent_version_transitions {
-                        if let Some(ref storage_class) = noncurrent_version_transitions[0].storage_class {
-                            if storage_class.as_str() != "" && !obj.delete_marker && obj.transition_status != TRANSITION_COMPLETE
-                            {
-                                let due = rule.noncurrent_version_transitions.as_ref().unwrap()[0].next_due(obj);
-                                if due.is_some()
-                                    && (now.unix_timestamp() == 0 || now.unix_timestamp() > due.unwrap().unix_timestamp())
-                                {
+                    if let Some(transition) = rule.noncurrent_version_transitions.as_ref().and_then(|t| t.first()) {
+                        let storage_class = transition.storage_class.as_ref().map(|sc| sc.as_str()).unwrap_or_default();
+                        if !storage_class.is_empty() && !obj.delete_marker && obj.transition_status != TRANSITION_COMPLETE {
+                            if let Some(due) = transition.next_due(obj) {
+                                if now.unix_timestamp() == 0 || now.unix_timestamp() > due.unix_timestamp() {
                                     events.push(Event {
                                         action: IlmAction::TransitionVersionAction,
-                                        rule_id: rule.id.clone().expect("err!"),
-                                        due,
-                                        storage_class: rule.noncurrent_version_transitions.as_ref().unwrap()[0]
-                                            .storage_class
-                                            .clone()
-                                            .unwrap()
-                                            .as_str()
-                                            .to_string(),
+                                        rule_id: rule_id.clone(),
+                                        due: Some(due),
+                                        storage_class: storage_class.to_string(),
                                         ..Default::default()
                                     });
                                 }
@@ -424,16 +407,8 @@ impl Lifecycle for BucketLifecycleConfiguration {
                     }
                 }
 
-                info!(
-                    "eval_inner: checking expiration condition - is_latest={}, delete_marker={}, version_id={:?}, condition_met={}",
-                    obj.is_latest,
-                    obj.delete_marker,
-                    obj.version_id,
-                    (obj.is_latest || obj.version_id.is_empty()) && !obj.delete_marker
-                );
                 // Allow expiration for latest objects OR non-versioned objects (empty version_id)
                 if (obj.is_latest || obj.version_id.is_empty()) && !obj.delete_marker {
-                    info!("eval_inner: entering expiration check");
                     if let Some(ref expiration) = rule.expiration {
                         if let Some(ref date) = expiration.date {
                             let date0 = OffsetDateTime::from(date.clone());
@@ -442,7 +417,7 @@ impl Lifecycle for BucketLifecycleConfiguration {
                             {
                                 events.push(Event {
                                     action: IlmAction::DeleteAction,
-                                    rule_id: rule.id.clone().expect("err!"),
+                                    rule_id: rule_id.clone(),
                                     due: Some(date0),
                                     noncurrent_days: 0,
                                     newer_noncurrent_versions: 0,
@@ -450,45 +425,39 @@ impl Lifecycle for BucketLifecycleConfiguration {
                                 });
                             }
                         } else if let Some(days) = expiration.days {
-                            let expected_expiry: OffsetDateTime = expected_expiry_time(obj.mod_time.expect("err!"), days);
-                            info!(
-                                "eval_inner: expiration check - days={}, obj_time={:?}, expiry_time={:?}, now={:?}, should_expire={}",
-                                days,
-                                obj.mod_time.expect("err!"),
-                                expected_expiry,
-                                now,
-                                now.unix_timestamp() > expected_expiry.unix_timestamp()
+                            let expected_expiry: OffsetDateTime = expected_expiry_time(mod_time, days);
+                            debug!(
+                                "eval_inner: expiration check - days={}, obj_time={:?}, expiry_time={:?}, now={:?}",
+                                days, mod_time, expected_expiry, now
                             );
                             if now.unix_timestamp() == 0 || now.unix_timestamp() > expected_expiry.unix_timestamp() {
-                                info!("eval_inner: object should expire, adding DeleteAction");
-                                let mut event = Event {
+                                events.push(Event {
                                     action: IlmAction::DeleteAction,
-                                    rule_id: rule.id.clone().expect("err!"),
+                                    rule_id: rule_id.clone(),
                                     due: Some(expected_expiry),
                                     noncurrent_days: 0,
                                     newer_noncurrent_versions: 0,
                                     storage_class: "".into(),
-                                };
-                                /*if rule.expiration.expect("err!").delete_all.val {
-                                    event.action = IlmAction::DeleteAllVersionsAction
-                                }*/
-                                events.push(event);
+                                });
                             }
                         }
                     }
 
                     if obj.transition_status != TRANSITION_COMPLETE {
-                        if let Some(ref transitions) = rule.transitions {
-                            let due = transitions[0].next_due(obj);
-                            if let Some(due) = due {
+                        if let Some(transition) = rule.transitions.as_ref().and_then(|t| t.first()) {
+                            if let Some(due) = transition.next_due(obj) {
                                 if due.unix_timestamp() > 0
                                     && (now.unix_timestamp() == 0 || now.unix_timestamp() > due.unix_timestamp())
                                 {
                                     events.push(Event {
                                         action: IlmAction::TransitionAction,
-                                        rule_id: rule.id.clone().expect("err!"),
+                                        rule_id: rule_id.clone(),
                                         due: Some(due),
-                                        storage_class: transitions[0].storage_class.clone().expect("err!").as_str().to_string(),
+                                        storage_class: transition
+                                            .storage_class
+                                            .as_ref()
+                                            .map(|sc| sc.as_str().to_string())
+                                            .unwrap_or_default(),
                                         noncurrent_days: 0,
                                         newer_noncurrent_versions: 0,
                                     });
@@ -500,75 +469,64 @@ impl Lifecycle for BucketLifecycleConfiguration {
             }
         }
 
-        if events.len() > 0 {
-            events.sort_by(|a, b| {
-                if now.unix_timestamp() > a.due.expect("err!").unix_timestamp()
-                    && now.unix_timestamp() > b.due.expect("err").unix_timestamp()
-                    || a.due.expect("err").unix_timestamp() == b.due.expect("err").unix_timestamp()
-                {
-                    match a.action {
-                        IlmAction::DeleteAllVersionsAction
-                        | IlmAction::DelMarkerDeleteAllVersionsAction
-                        | IlmAction::DeleteAction
-                        | IlmAction::DeleteVersionAction => {
-                            return Ordering::Less;
-                        }
-                        _ => (),
-                    }
-                    match b.action {
-                        IlmAction::DeleteAllVersionsAction
-                        | IlmAction::DelMarkerDeleteAllVersionsAction
-                        | IlmAction::DeleteAction
-                        | IlmAction::DeleteVersionAction => {
-                            return Ordering::Greater;
-                        }
-                        _ => (),
-                    }
-                    return Ordering::Less;
-                }
-
-                if a.due.expect("err").unix_timestamp() < b.due.expect("err").unix_timestamp() {
-                    return Ordering::Less;
-                }
-                return Ordering::Greater;
-            });
-            return events[0].clone();
-        }
-
-        Event::default()
+        // Actions due at the same time, or both overdue, delete first, else the earliest one wins
+        events.sort_by(|a, b| {
+            let a_due = a.due.unwrap_or(OffsetDateTime::UNIX_EPOCH);
+            let b_due = b.due.unwrap_or(OffsetDateTime::UNIX_EPOCH);
+            if (now > a_due && now > b_due) || a_due == b_due {
+                return b.action.delete().cmp(&a.action.delete());
+            }
+            a_due.cmp(&b_due)
+        });
+        events.into_iter().next().unwrap_or_default()
     }
 
     async fn noncurrent_versions_expiration_limit(&self, obj: &ObjectOpts) -> Event {
         if let Some(filter_rules) = self.filter_rules(obj).await {
             for rule in filter_rules.iter() {
                 if let Some(ref noncurrent_version_expiration) = rule.noncurrent_version_expiration {
-                    if let Some(newer_noncurrent_versions) = noncurrent_version_expiration.newer_noncurrent_versions {
-                        if newer_noncurrent_versions == 0 {
-                            continue;
-                        }
-                        return Event {
-                            action: IlmAction::DeleteVersionAction,
-                            rule_id: rule.id.clone().expect("err"),
-                            noncurrent_days: noncurrent_version_expiration.noncurrent_days.expect("noncurrent_days err.") as u32,
-                            newer_noncurrent_versions: newer_noncurrent_versions as usize,
-                            due: Some(OffsetDateTime::UNIX_EPOCH),
-                            storage_class: "".into(),
-                        };
-                    } else {
-                        return Event {
-                            action: IlmAction::DeleteVersionAction,
-                            rule_id: rule.id.clone().expect("err"),
-                            noncurrent_days: noncurrent_version_expiration.noncurrent_days.expect("noncurrent_days err.") as u32,
-                            newer_noncurrent_versions: 0,
-                            due: Some(OffsetDateTime::UNIX_EPOCH),
-                            storage_class: "".into(),
-                        };
+                    let newer_noncurrent_versions = noncurrent_version_expiration.newer_noncurrent_versions.unwrap_or_default();
+                    if newer_noncurrent_versions <= 0 {
+                        continue;
                     }
+                    return Event {
+                        action: IlmAction::DeleteVersionAction,
+                        rule_id: rule.id.clone().unwrap_or_default(),
+                        noncurrent_days: noncurrent_version_expiration.noncurrent_days.unwrap_or_default().max(0) as u32,
+                        newer_noncurrent_versions: newer_noncurrent_versions as usize,
+                        due: Some(OffsetDateTime::UNIX_EPOCH),
+                        storage_class: "".into(),
+                    };
                 }
             }
         }
         Event::default()
     }
+
+    fn eval_abort_multipart(&self, object: &str, initiated: OffsetDateTime, now: OffsetDateTime) -> Event {
+        for rule in self.rules.iter().filter(|rule| rule_enabled(rule)) {
+            let Some(days) = rule
+                .abort_incomplete_multipart_upload
+                .as_ref()
+                .and_then(|abort| abort.days_after_initiation)
+            else {
+                continue;
+            };
+            if !object.starts_with(rule_prefix(rule)) {
+                continue;
+            }
+            let due = expected_expiry_time(initiated, days);
+            if now > due {
+                return Event {
+                    action: IlmAction::AbortMultipartUploadAction,
+                    rule_id: rule.id.clone().unwrap_or_default(),
+                    due: Some(due),
+                    ..Default::default()
+                };
+            }
+        }
+        Event::default()
+    }
 }
 
 #[async_trait::async_trait]
@@ -583,7 +541,7 @@ impl LifecycleCalculate for LifecycleExpiration {
             return None;
         }
 
-        Some(expected_expiry_time(obj.mod_time.unwrap(), self.days.unwrap()))
+        Some(expected_expiry_time(obj.mod_time?, self.days?))
     }
 }
 
@@ -593,17 +551,17 @@ impl LifecycleCalculate for NoncurrentVersionTransition {
         if obj.is_latest || self.storage_class.is_none() {
             return None;
         }
-        if self.noncurrent_days.is_none() {
-            return obj.successor_mod_time;
+        match self.noncurrent_days {
+            Some(days) => Some(expected_expiry_time(obj.successor_mod_time?, days)),
+            None => obj.successor_mod_time,
         }
-        Some(expected_expiry_time(obj.successor_mod_time.unwrap(), self.noncurrent_days.unwrap()))
     }
 }
 
 #[async_trait::async_trait]
 impl LifecycleCalculate for Transition {
     fn next_due(&self, obj: &ObjectOpts) -> Option<OffsetDateTime> {
-        if !obj.is_latest || self.days.is_none() {
+        if !obj.is_latest {
             return None;
         }
 
@@ -611,10 +569,7 @@ impl LifecycleCalculate for Transition {
             return Some(date.into());
         }
 
-        if self.days.is_none() {
-            return obj.mod_time;
-        }
-        Some(expected_expiry_time(obj.mod_time.unwrap(), self.days.unwrap()))
+        Some(expected_expiry_time(obj.mod_time?, self.days?))
     }
 }
 
@@ -709,3 +664,203 @@ impl Default for TransitionOptions {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::dto::{AbortIncompleteMultipartUpload, LifecycleRuleFilter, NoncurrentVersionExpiration, Tag};
+
+    fn rule(id: &str, prefix: &str) -> LifecycleRule {
+        LifecycleRule {
+            abort_incomplete_multipart_upload: None,
+            expiration: None,
+            filter: Some(LifecycleRuleFilter {
+                prefix: Some(prefix.to_string()),
+                ..Default::default()
+            }),
+            id: Some(id.to_string()),
+            noncurrent_version_expiration: None,
+            noncurrent_version_transitions: None,
+            prefix: None,
+            status: ExpirationStatus::from_static(ExpirationStatus::ENABLED),
+            transitions: None,
+        }
+    }
+
+    fn expire_after(days: i32) -> Option<LifecycleExpiration> {
+        Some(LifecycleExpiration {
+            days: Some(days),
+            ..Default::default()
+        })
+    }
+
+    fn config(rules: Vec<LifecycleRule>) -> BucketLifecycleConfiguration {
+        BucketLifecycleConfiguration { rules }
+    }
+
+    #[tokio::test]
+    async fn test_validate_rules() {
+        let lock = ObjectLockConfiguration::default();
+        let mut valid = rule("logs", "logs/");
+        valid.expiration = expire_after(30);
+        assert!(config(vec![valid.clone()]).validate(&lock).await.is_ok());
+
+        let no_action = rule("empty", "");
+        assert!(no_action.validate().is_err());
+
+        let mut zero_days = rule("zero", "");
+        zero_days.expiration = expire_after(0);
+        assert!(zero_days.validate().is_err());
+
+        let mut both = rule("both", "");
+        both.expiration = Some(LifecycleExpiration {
+            days: Some(1),
+            expired_object_delete_marker: Some(true),
+            ..Default::default()
+        });
+        assert!(both.validate().is_err());
+
+        let mut bad_status = valid.clone();
+        bad_status.status = ExpirationStatus::from("enabled".to_string());
+        assert!(bad_status.validate().is_err());
+
+        let mut legacy_and_filter = valid.clone();
+        legacy_and_filter.prefix = Some("logs/".to_string());
+        assert!(legacy_and_filter.validate().is_err());
+
+        let mut noncurrent = rule("noncurrent", "");
+        noncurrent.noncurrent_version_expiration = Some(NoncurrentVersionExpiration {
+            newer_noncurrent_versions: None,
+            noncurrent_days: Some(0),
+        });
+        assert!(noncurrent.validate().is_err());
+
+        let mut abort_tagged = rule("abort", "");
+        abort_tagged.abort_incomplete_multipart_upload = Some(AbortIncompleteMultipartUpload {
+            days_after_initiation: Some(7),
+        });
+        assert!(abort_tagged.validate().is_ok());
+        abort_tagged.filter = Some(LifecycleRuleFilter {
+            tag: Some(Tag {
+                key: Some("env".to_string()),
+                value: Some("dev".to_string()),
+            }),
+            ..Default::default()
+        });
+        assert!(abort_tagged.validate().is_err());
+
+        assert!(config(vec![valid.clone(), valid]).validate(&lock).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_eval_expiration_and_filters() {
+        let now = OffsetDateTime::now_utc();
+        let mut tagged = rule("tagged", "logs/");
+        tagged.expiration = expire_after(1);
+        tagged.filter = Some(LifecycleRuleFilter {
+            and: Some(s3s::dto::LifecycleRuleAndOperator {
+                prefix: Some("logs/".to_string()),
+                tags: Some(vec![Tag {
+                    key: Some("temp".to_string()),
+                    value: Some("yes".to_string()),
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let lc = config(vec![tagged]);
+
+        let mut obj = ObjectOpts {
+            name: "logs/a.txt".to_string(),
+            mod_time: Some(now - Duration::days(2)),
+            is_latest: true,
+            user_tags: "temp=yes".to_string(),
+            ..Default::default()
+        };
+        let event = lc.eval_inner(&obj, now).await;
+        assert_eq!(event.action, IlmAction::DeleteAction);
+        assert_eq!(event.rule_id, "tagged");
+
+        obj.user_tags = "temp=no".to_string();
+        assert_eq!(lc.eval_inner(&obj, now).await.action, IlmAction::NoneAction);
+
+        obj.user_tags = "temp=yes".to_string();
+        obj.mod_time = Some(now);
+        assert_eq!(lc.eval_inner(&obj, now).await.action, IlmAction::NoneAction);
+    }
+
+    #[tokio::test]
+    async fn test_eval_noncurrent_versions() {
+        let now = OffsetDateTime::now_utc();
+        let mut noncurrent = rule("noncurrent", "");
+        noncurrent.noncurrent_version_expiration = Some(NoncurrentVersionExpiration {
+            newer_noncurrent_versions: None,
+            noncurrent_days: Some(3),
+        });
+        let lc = config(vec![noncurrent]);
+
+        let mut obj = ObjectOpts {
+            name: "a".to_string(),
+            version_id: uuid::Uuid::new_v4().to_string(),
+            mod_time: Some(now - Duration::days(10)),
+            successor_mod_time: Some(now - Duration::days(4)),
+            num_versions: 2,
+            ..Default::default()
+        };
+        assert_eq!(lc.eval_inner(&obj, now).await.action, IlmAction::DeleteVersionAction);
+
+        obj.successor_mod_time = Some(now - Duration::days(1));
+        assert_eq!(lc.eval_inner(&obj, now).await.action, IlmAction::NoneAction);
+
+        // The latest version is kept whatever its age
+        obj.is_latest = true;
+        assert_eq!(lc.eval_inner(&obj, now).await.action, IlmAction::NoneAction);
+    }
+
+    #[tokio::test]
+    async fn test_eval_expired_delete_marker() {
+        let now = OffsetDateTime::now_utc();
+        let mut markers = rule("markers", "");
+        markers.expiration = Some(LifecycleExpiration {
+            expired_object_delete_marker: Some(true),
+            ..Default::default()
+        });
+        let lc = config(vec![markers]);
+
+        let mut obj = ObjectOpts {
+            name: "a".to_string(),
+            version_id: uuid::Uuid::new_v4().to_string(),
+            mod_time: Some(now),
+            is_latest: true,
+            delete_marker: true,
+            num_versions: 1,
+            ..Default::default()
+        };
+        assert_eq!(lc.eval_inner(&obj, now).await.action, IlmAction::DeleteVersionAction);
+
+        obj.num_versions = 2;
+        assert_eq!(lc.eval_inner(&obj, now).await.action, IlmAction::NoneAction);
+    }
+
+    #[test]
+    fn test_eval_abort_multipart() {
+        let now = OffsetDateTime::now_utc();
+        let mut abort = rule("abort", "uploads/");
+        abort.abort_incomplete_multipart_upload = Some(AbortIncompleteMultipartUpload {
+            days_after_initiation: Some(2),
+        });
+        let lc = config(vec![abort]);
+
+        let event = lc.eval_abort_multipart("uploads/big", now - Duration::days(3), now);
+        assert_eq!(event.action, IlmAction::AbortMultipartUploadAction);
+        assert_eq!(event.rule_id, "abort");
+        assert_eq!(
+            lc.eval_abort_multipart("uploads/big", now - Duration::days(1), now).action,
+            IlmAction::NoneAction
+        );
+        assert_eq!(
+            lc.eval_abort_multipart("other/big", now - Duration::days(3), now).action,
+            IlmAction::NoneAction
+        );
+    }
+}
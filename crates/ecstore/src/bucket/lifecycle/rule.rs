@@ -18,41 +18,108 @@
 #![allow(unused_must_use)]
 #![allow(clippy::all)]
 
-use s3s::dto::{LifecycleRuleFilter, Transition};
+use s3s::dto::{LifecycleRuleFilter, Tag, Transition};
+
+use super::lifecycle::LifecycleConfigError;
+use crate::bucket::tagging::decode_tags;
 
 const _ERR_TRANSITION_INVALID_DAYS: &str = "Days must be 0 or greater when used with Transition";
 const _ERR_TRANSITION_INVALID_DATE: &str = "Date must be provided in ISO 8601 format";
 const ERR_TRANSITION_INVALID: &str =
     "Exactly one of Days (0 or greater) or Date (positive ISO 8601 format) should be present in Transition.";
 const _ERR_TRANSITION_DATE_NOT_MIDNIGHT: &str = "'Date' must be at midnight GMT";
+const ERR_FILTER_INVALID: &str = "Filter must have exactly one of Prefix, Tag, ObjectSize or And";
+const ERR_FILTER_INVALID_TAG: &str = "Tag key and value must be set in a lifecycle rule filter";
+const ERR_FILTER_INVALID_SIZE: &str = "ObjectSizeLessThan must be greater than ObjectSizeGreaterThan";
 
 pub trait Filter {
+    fn validate(&self) -> Result<(), LifecycleConfigError>;
+    fn prefix(&self) -> &str;
+    fn has_tags(&self) -> bool;
     fn test_tags(&self, user_tags: &str) -> bool;
     fn by_size(&self, sz: i64) -> bool;
 }
 
 impl Filter for LifecycleRuleFilter {
+    fn validate(&self) -> Result<(), LifecycleConfigError> {
+        let size_set = self.object_size_greater_than.is_some() || self.object_size_less_than.is_some();
+        if let Some(and) = &self.and {
+            if self.prefix.is_some() || self.tag.is_some() || size_set {
+                return Err(LifecycleConfigError::Invalid(ERR_FILTER_INVALID));
+            }
+            if and.tags.iter().flatten().any(|tag| !tag_valid(tag)) {
+                return Err(LifecycleConfigError::Invalid(ERR_FILTER_INVALID_TAG));
+            }
+            return validate_size(and.object_size_greater_than, and.object_size_less_than);
+        }
+        if self.prefix.is_some() as u8 + self.tag.is_some() as u8 + size_set as u8 > 1 {
+            return Err(LifecycleConfigError::Invalid(ERR_FILTER_INVALID));
+        }
+        if self.tag.as_ref().is_some_and(|tag| !tag_valid(tag)) {
+            return Err(LifecycleConfigError::Invalid(ERR_FILTER_INVALID_TAG));
+        }
+        validate_size(self.object_size_greater_than, self.object_size_less_than)
+    }
+
+    fn prefix(&self) -> &str {
+        self.prefix
+            .as_deref()
+            .or_else(|| self.and.as_ref().and_then(|and| and.prefix.as_deref()))
+            .unwrap_or_default()
+    }
+
+    fn has_tags(&self) -> bool {
+        self.tag.is_some()
+            || self
+                .and
+                .as_ref()
+                .is_some_and(|and| and.tags.as_ref().is_some_and(|tags| !tags.is_empty()))
+    }
+
+    /// Whether the object tags, URL encoded as stored, include every tag of the filter
     fn test_tags(&self, user_tags: &str) -> bool {
-        true
+        let and_tags = self.and.as_ref().and_then(|and| and.tags.as_ref());
+        let mut wanted = self.tag.iter().chain(and_tags.into_iter().flatten()).peekable();
+        if wanted.peek().is_none() {
+            return true;
+        }
+        let object_tags = decode_tags(user_tags);
+        wanted.all(|tag| object_tags.iter().any(|object_tag| object_tag == tag))
     }
 
     fn by_size(&self, sz: i64) -> bool {
-        true
+        let (greater_than, less_than) = match &self.and {
+            Some(and) => (and.object_size_greater_than, and.object_size_less_than),
+            None => (self.object_size_greater_than, self.object_size_less_than),
+        };
+        greater_than.is_none_or(|min| sz > min) && less_than.is_none_or(|max| sz < max)
+    }
+}
+
+fn tag_valid(tag: &Tag) -> bool {
+    tag.key.as_ref().is_some_and(|key| !key.is_empty()) && tag.value.is_some()
+}
+
+fn validate_size(greater_than: Option<i64>, less_than: Option<i64>) -> Result<(), LifecycleConfigError> {
+    match (greater_than, less_than) {
+        (Some(min), _) | (_, Some(min)) if min < 0 => Err(LifecycleConfigError::Invalid(ERR_FILTER_INVALID_SIZE)),
+        (Some(min), Some(max)) if max <= min => Err(LifecycleConfigError::Invalid(ERR_FILTER_INVALID_SIZE)),
+        _ => Ok(()),
     }
 }
 
 pub trait TransitionOps {
-    fn validate(&self) -> Result<(), std::io::Error>;
+    fn validate(&self) -> Result<(), LifecycleConfigError>;
 }
 
 impl TransitionOps for Transition {
-    fn validate(&self) -> Result<(), std::io::Error> {
-        if !self.date.is_none() && self.days.expect("err!") > 0 {
-            return Err(std::io::Error::other(ERR_TRANSITION_INVALID));
+    fn validate(&self) -> Result<(), LifecycleConfigError> {
+        if self.date.is_some() == self.days.is_some() || self.days.is_some_and(|days| days < 0) {
+            return Err(LifecycleConfigError::Invalid(ERR_TRANSITION_INVALID));
         }
 
         if self.storage_class.is_none() {
-            return Err(std::io::Error::other("ERR_XML_NOT_WELL_FORMED"));
+            return Err(LifecycleConfigError::MalformedXml);
         }
         Ok(())
     }
@@ -62,8 +129,53 @@ impl TransitionOps for Transition {
 mod test {
     use super::*;
 
+    use s3s::dto::LifecycleRuleAndOperator;
+
+    fn tag(key: &str, value: &str) -> Tag {
+        Tag {
+            key: Some(key.to_string()),
+            value: Some(value.to_string()),
+        }
+    }
+
     #[tokio::test]
     async fn test_rule() {
         //assert!(skip_access_checks(p.to_str().unwrap()));
     }
+
+    #[test]
+    fn test_filter_tags_and_size() {
+        let filter = LifecycleRuleFilter {
+            and: Some(LifecycleRuleAndOperator {
+                object_size_greater_than: Some(100),
+                object_size_less_than: Some(1000),
+                prefix: Some("logs/".to_string()),
+                tags: Some(vec![tag("env", "dev"), tag("team", "a b")]),
+            }),
+            ..Default::default()
+        };
+        assert!(filter.validate().is_ok());
+        assert_eq!(filter.prefix(), "logs/");
+        assert!(filter.has_tags());
+        assert!(Filter::test_tags(&filter, "env=dev&team=a+b&other=1"));
+        assert!(!Filter::test_tags(&filter, "env=dev"));
+        assert!(!Filter::test_tags(&filter, ""));
+        assert!(filter.by_size(500));
+        assert!(!filter.by_size(100));
+        assert!(!filter.by_size(1000));
+
+        let untagged = LifecycleRuleFilter {
+            prefix: Some("a/".to_string()),
+            ..Default::default()
+        };
+        assert!(Filter::test_tags(&untagged, ""));
+        assert!(untagged.by_size(0));
+
+        let ambiguous = LifecycleRuleFilter {
+            prefix: Some("a/".to_string()),
+            tag: Some(tag("env", "dev")),
+            ..Default::default()
+        };
+        assert!(ambiguous.validate().is_err());
+    }
 }
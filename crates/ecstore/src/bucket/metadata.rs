@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{quota::BucketQuota, target::BucketTargets};
+use super::{acl::BucketAcl, encryption::BucketEncryptionPolicy, quota::BucketQuota, target::BucketTargets};
 
 use super::object_lock::ObjectLockApi;
 use super::versioning::VersioningApi;
@@ -20,8 +20,9 @@ use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use rmp_serde::Serializer as rmpSerializer;
 use rustfs_policy::policy::BucketPolicy;
 use s3s::dto::{
-    BucketLifecycleConfiguration, NotificationConfiguration, ObjectLockConfiguration, ReplicationConfiguration,
-    ServerSideEncryptionConfiguration, Tagging, VersioningConfiguration,
+    BucketLifecycleConfiguration, CORSConfiguration, NotificationConfiguration, ObjectLockConfiguration,
+    PublicAccessBlockConfiguration, ReplicationConfiguration, ServerSideEncryptionConfiguration, Tagging,
+    VersioningConfiguration, WebsiteConfiguration,
 };
 use serde::Serializer;
 use serde::{Deserialize, Serialize};
@@ -47,12 +48,17 @@ pub const BUCKET_POLICY_CONFIG: &str = "policy.json";
 pub const BUCKET_NOTIFICATION_CONFIG: &str = "notification.xml";
 pub const BUCKET_LIFECYCLE_CONFIG: &str = "lifecycle.xml";
 pub const BUCKET_SSECONFIG: &str = "bucket-encryption.xml";
+pub const BUCKET_ENCRYPTION_POLICY_FILE: &str = "encryption-policy.json";
 pub const BUCKET_TAGGING_CONFIG: &str = "tagging.xml";
 pub const BUCKET_QUOTA_CONFIG_FILE: &str = "quota.json";
 pub const OBJECT_LOCK_CONFIG: &str = "object-lock.xml";
 pub const BUCKET_VERSIONING_CONFIG: &str = "versioning.xml";
 pub const BUCKET_REPLICATION_CONFIG: &str = "replication.xml";
 pub const BUCKET_TARGETS_FILE: &str = "bucket-targets.json";
+pub const BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG: &str = "public-access-block.xml";
+pub const BUCKET_CORS_CONFIG: &str = "cors.xml";
+pub const BUCKET_WEBSITE_CONFIG: &str = "website.xml";
+pub const BUCKET_ACL_CONFIG: &str = "acl.json";
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "PascalCase", default)]
@@ -66,15 +72,21 @@ pub struct BucketMetadata {
     pub object_lock_config_xml: Vec<u8>,
     pub versioning_config_xml: Vec<u8>,
     pub encryption_config_xml: Vec<u8>,
+    pub encryption_policy_json: Vec<u8>,
     pub tagging_config_xml: Vec<u8>,
     pub quota_config_json: Vec<u8>,
     pub replication_config_xml: Vec<u8>,
     pub bucket_targets_config_json: Vec<u8>,
     pub bucket_targets_config_meta_json: Vec<u8>,
+    pub public_access_block_config_xml: Vec<u8>,
+    pub cors_config_xml: Vec<u8>,
+    pub website_config_xml: Vec<u8>,
+    pub acl_config_json: Vec<u8>,
 
     pub policy_config_updated_at: OffsetDateTime,
     pub object_lock_config_updated_at: OffsetDateTime,
     pub encryption_config_updated_at: OffsetDateTime,
+    pub encryption_policy_updated_at: OffsetDateTime,
     pub tagging_config_updated_at: OffsetDateTime,
     pub quota_config_updated_at: OffsetDateTime,
     pub replication_config_updated_at: OffsetDateTime,
@@ -83,6 +95,10 @@ pub struct BucketMetadata {
     pub notification_config_updated_at: OffsetDateTime,
     pub bucket_targets_config_updated_at: OffsetDateTime,
     pub bucket_targets_config_meta_updated_at: OffsetDateTime,
+    pub public_access_block_config_updated_at: OffsetDateTime,
+    pub cors_config_updated_at: OffsetDateTime,
+    pub website_config_updated_at: OffsetDateTime,
+    pub acl_config_updated_at: OffsetDateTime,
 
     #[serde(skip)]
     pub new_field_updated_at: OffsetDateTime,
@@ -100,6 +116,8 @@ pub struct BucketMetadata {
     #[serde(skip)]
     pub sse_config: Option<ServerSideEncryptionConfiguration>,
     #[serde(skip)]
+    pub encryption_policy: Option<BucketEncryptionPolicy>,
+    #[serde(skip)]
     pub tagging_config: Option<Tagging>,
     #[serde(skip)]
     pub quota_config: Option<BucketQuota>,
@@ -109,6 +127,14 @@ pub struct BucketMetadata {
     pub bucket_target_config: Option<BucketTargets>,
     #[serde(skip)]
     pub bucket_target_config_meta: Option<HashMap<String, String>>,
+    #[serde(skip)]
+    pub public_access_block_config: Option<PublicAccessBlockConfiguration>,
+    #[serde(skip)]
+    pub cors_config: Option<CORSConfiguration>,
+    #[serde(skip)]
+    pub website_config: Option<WebsiteConfiguration>,
+    #[serde(skip)]
+    pub acl_config: Option<BucketAcl>,
 }
 
 impl Default for BucketMetadata {
@@ -123,14 +149,20 @@ impl Default for BucketMetadata {
             object_lock_config_xml: Default::default(),
             versioning_config_xml: Default::default(),
             encryption_config_xml: Default::default(),
+            encryption_policy_json: Default::default(),
             tagging_config_xml: Default::default(),
             quota_config_json: Default::default(),
             replication_config_xml: Default::default(),
             bucket_targets_config_json: Default::default(),
             bucket_targets_config_meta_json: Default::default(),
+            public_access_block_config_xml: Default::default(),
+            cors_config_xml: Default::default(),
+            website_config_xml: Default::default(),
+            acl_config_json: Default::default(),
             policy_config_updated_at: OffsetDateTime::UNIX_EPOCH,
             object_lock_config_updated_at: OffsetDateTime::UNIX_EPOCH,
             encryption_config_updated_at: OffsetDateTime::UNIX_EPOCH,
+            encryption_policy_updated_at: OffsetDateTime::UNIX_EPOCH,
             tagging_config_updated_at: OffsetDateTime::UNIX_EPOCH,
             quota_config_updated_at: OffsetDateTime::UNIX_EPOCH,
             replication_config_updated_at: OffsetDateTime::UNIX_EPOCH,
@@ -139,6 +171,10 @@ impl Default for BucketMetadata {
             notification_config_updated_at: OffsetDateTime::UNIX_EPOCH,
             bucket_targets_config_updated_at: OffsetDateTime::UNIX_EPOCH,
             bucket_targets_config_meta_updated_at: OffsetDateTime::UNIX_EPOCH,
+            public_access_block_config_updated_at: OffsetDateTime::UNIX_EPOCH,
+            cors_config_updated_at: OffsetDateTime::UNIX_EPOCH,
+            website_config_updated_at: OffsetDateTime::UNIX_EPOCH,
+            acl_config_updated_at: OffsetDateTime::UNIX_EPOCH,
             new_field_updated_at: OffsetDateTime::UNIX_EPOCH,
             policy_config: Default::default(),
             notification_config: Default::default(),
@@ -146,11 +182,16 @@ impl Default for BucketMetadata {
             object_lock_config: Default::default(),
             versioning_config: Default::default(),
             sse_config: Default::default(),
+            encryption_policy: Default::default(),
             tagging_config: Default::default(),
             quota_config: Default::default(),
             replication_config: Default::default(),
             bucket_target_config: Default::default(),
             bucket_target_config_meta: Default::default(),
+            public_access_block_config: Default::default(),
+            cors_config: Default::default(),
+            website_config: Default::default(),
+            acl_config: Default::default(),
         }
     }
 }
@@ -218,6 +259,9 @@ impl BucketMetadata {
         if self.encryption_config_updated_at == OffsetDateTime::UNIX_EPOCH {
             self.encryption_config_updated_at = self.created
         }
+        if self.encryption_policy_updated_at == OffsetDateTime::UNIX_EPOCH {
+            self.encryption_policy_updated_at = self.created
+        }
 
         if self.tagging_config_updated_at == OffsetDateTime::UNIX_EPOCH {
             self.tagging_config_updated_at = self.created
@@ -250,6 +294,18 @@ impl BucketMetadata {
         if self.bucket_targets_config_meta_updated_at == OffsetDateTime::UNIX_EPOCH {
             self.bucket_targets_config_meta_updated_at = self.created
         }
+        if self.public_access_block_config_updated_at == OffsetDateTime::UNIX_EPOCH {
+            self.public_access_block_config_updated_at = self.created
+        }
+        if self.cors_config_updated_at == OffsetDateTime::UNIX_EPOCH {
+            self.cors_config_updated_at = self.created
+        }
+        if self.website_config_updated_at == OffsetDateTime::UNIX_EPOCH {
+            self.website_config_updated_at = self.created
+        }
+        if self.acl_config_updated_at == OffsetDateTime::UNIX_EPOCH {
+            self.acl_config_updated_at = self.created
+        }
     }
 
     pub fn update_config(&mut self, config_file: &str, data: Vec<u8>) -> Result<OffsetDateTime> {
@@ -272,6 +328,10 @@ impl BucketMetadata {
                 self.encryption_config_xml = data;
                 self.encryption_config_updated_at = updated;
             }
+            BUCKET_ENCRYPTION_POLICY_FILE => {
+                self.encryption_policy_json = data;
+                self.encryption_policy_updated_at = updated;
+            }
             BUCKET_TAGGING_CONFIG => {
                 self.tagging_config_xml = data;
                 self.tagging_config_updated_at = updated;
@@ -299,6 +359,22 @@ impl BucketMetadata {
                 self.bucket_targets_config_json = data.clone();
                 self.bucket_targets_config_updated_at = updated;
             }
+            BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG => {
+                self.public_access_block_config_xml = data;
+                self.public_access_block_config_updated_at = updated;
+            }
+            BUCKET_CORS_CONFIG => {
+                self.cors_config_xml = data;
+                self.cors_config_updated_at = updated;
+            }
+            BUCKET_WEBSITE_CONFIG => {
+                self.website_config_xml = data;
+                self.website_config_updated_at = updated;
+            }
+            BUCKET_ACL_CONFIG => {
+                self.acl_config_json = data;
+                self.acl_config_updated_at = updated;
+            }
             _ => return Err(Error::other(format!("config file not found : {config_file}"))),
         }
 
@@ -351,6 +427,9 @@ impl BucketMetadata {
         if !self.encryption_config_xml.is_empty() {
             self.sse_config = Some(deserialize::<ServerSideEncryptionConfiguration>(&self.encryption_config_xml)?);
         }
+        if !self.encryption_policy_json.is_empty() {
+            self.encryption_policy = Some(BucketEncryptionPolicy::unmarshal(&self.encryption_policy_json)?);
+        }
         if !self.tagging_config_xml.is_empty() {
             self.tagging_config = Some(deserialize::<Tagging>(&self.tagging_config_xml)?);
         }
@@ -360,6 +439,19 @@ impl BucketMetadata {
         if !self.replication_config_xml.is_empty() {
             self.replication_config = Some(deserialize::<ReplicationConfiguration>(&self.replication_config_xml)?);
         }
+        if !self.public_access_block_config_xml.is_empty() {
+            self.public_access_block_config =
+                Some(deserialize::<PublicAccessBlockConfiguration>(&self.public_access_block_config_xml)?);
+        }
+        if !self.cors_config_xml.is_empty() {
+            self.cors_config = Some(deserialize::<CORSConfiguration>(&self.cors_config_xml)?);
+        }
+        if !self.website_config_xml.is_empty() {
+            self.website_config = Some(deserialize::<WebsiteConfiguration>(&self.website_config_xml)?);
+        }
+        if !self.acl_config_json.is_empty() {
+            self.acl_config = Some(BucketAcl::unmarshal(&self.acl_config_json)?);
+        }
         //let temp = self.bucket_targets_config_json.clone();
         if !self.bucket_targets_config_json.is_empty() {
             let arr: Vec<BucketTarget> = serde_json::from_slice(&self.bucket_targets_config_json)?;
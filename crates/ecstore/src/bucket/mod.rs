@@ -12,12 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod acl;
+pub mod cors;
+pub mod effective_policy;
+pub mod encryption;
 pub mod error;
 pub mod lifecycle;
 pub mod metadata;
 pub mod metadata_sys;
 pub mod object_lock;
 pub mod policy_sys;
+pub mod public_access;
 pub mod quota;
 pub mod replication;
 pub mod tagging;
@@ -25,3 +30,4 @@ pub mod target;
 pub mod utils;
 pub mod versioning;
 pub mod versioning_sys;
+pub mod website;
@@ -13,82 +13,242 @@
 // limitations under the License.
 
 use std::collections::HashMap;
-use time::{OffsetDateTime, format_description};
+use time::{Duration, OffsetDateTime, format_description};
 
-use s3s::dto::{Date, ObjectLockLegalHold, ObjectLockLegalHoldStatus, ObjectLockRetention, ObjectLockRetentionMode};
+use s3s::dto::{
+    Date, DefaultRetention, ObjectLockConfiguration, ObjectLockLegalHold, ObjectLockLegalHoldStatus, ObjectLockRetention,
+    ObjectLockRetentionMode,
+};
 use s3s::header::{X_AMZ_OBJECT_LOCK_LEGAL_HOLD, X_AMZ_OBJECT_LOCK_MODE, X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE};
 
-const _ERR_MALFORMED_BUCKET_OBJECT_CONFIG: &str = "invalid bucket object lock config";
-const _ERR_INVALID_RETENTION_DATE: &str = "date must be provided in ISO 8601 format";
-const _ERR_PAST_OBJECTLOCK_RETAIN_DATE: &str = "the retain until date must be in the future";
-const _ERR_UNKNOWN_WORMMODE_DIRECTIVE: &str = "unknown WORM mode directive";
+use super::ObjectLockApi;
+
+pub const ERR_MALFORMED_BUCKET_OBJECT_CONFIG: &str = "invalid bucket object lock config";
+pub const ERR_INVALID_RETENTION_DATE: &str = "date must be provided in ISO 8601 format";
+pub const ERR_PAST_OBJECTLOCK_RETAIN_DATE: &str = "the retain until date must be in the future";
+pub const ERR_UNKNOWN_WORMMODE_DIRECTIVE: &str = "unknown WORM mode directive";
+pub const ERR_UNKNOWN_LEGAL_HOLD_STATUS: &str = "legal hold status must be ON or OFF";
 const _ERR_OBJECTLOCK_MISSING_CONTENT_MD5: &str =
     "content-MD5 HTTP header is required for Put Object requests with Object Lock parameters";
-const _ERR_OBJECTLOCK_INVALID_HEADERS: &str =
+pub const ERR_OBJECTLOCK_INVALID_HEADERS: &str =
     "x-amz-object-lock-retain-until-date and x-amz-object-lock-mode must both be supplied";
 const _ERR_MALFORMED_XML: &str = "the XML you provided was not well-formed or did not validate against our published schema";
 
+/// Longest default retention a bucket may set, as S3 allows
+const MAX_DEFAULT_RETENTION_DAYS: i64 = 36500;
+
+/// Why an object lock request is refused
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ObjectLockError {
+    /// The object version is under retention or legal hold
+    #[error("Object is WORM protected and cannot be overwritten or deleted")]
+    Protected,
+    /// The bucket was not created with, or never got, object lock enabled
+    #[error("Bucket is missing Object Lock Configuration")]
+    NotEnabled,
+    /// The lock headers or retention of a request are invalid
+    #[error("{0}")]
+    InvalidRequest(&'static str),
+    /// The bucket object lock configuration is invalid
+    #[error("{0}")]
+    MalformedConfig(&'static str),
+}
+
 pub fn utc_now_ntp() -> OffsetDateTime {
     OffsetDateTime::now_utc()
 }
 
-pub fn get_object_retention_meta(meta: HashMap<String, String>) -> ObjectLockRetention {
-    let mut retain_until_date: Date = Date::from(OffsetDateTime::UNIX_EPOCH);
+/// Retention of an object version, as kept in its metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retention {
+    pub mode: ObjectLockRetentionMode,
+    pub retain_until: OffsetDateTime,
+}
 
-    let mut mode_str = meta.get(X_AMZ_OBJECT_LOCK_MODE.as_str().to_lowercase().as_str());
-    if mode_str.is_none() {
-        mode_str = Some(&meta[X_AMZ_OBJECT_LOCK_MODE.as_str()]);
+impl Retention {
+    /// Retention recorded in the metadata of an object version, if any
+    pub fn from_meta(meta: &HashMap<String, String>) -> Option<Self> {
+        let mode = parse_ret_mode(meta_value(meta, X_AMZ_OBJECT_LOCK_MODE.as_str())?)?;
+        let retain_until = parse_retain_until_date(meta_value(meta, X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE.as_str())?)?;
+        Some(Self { mode, retain_until })
     }
-    let mode = if let Some(mode_str) = mode_str {
-        parse_ret_mode(mode_str.as_str())
-    } else {
-        return ObjectLockRetention {
-            mode: None,
-            retain_until_date: None,
+
+    /// Retention of a new object under the bucket default `retention`, counted from `now`
+    pub fn from_default(retention: &DefaultRetention, now: OffsetDateTime) -> Option<Self> {
+        let mode = retention.mode.clone()?;
+        let days = match (retention.days, retention.years) {
+            (Some(days), None) => i64::from(days),
+            (None, Some(years)) => i64::from(years) * 365,
+            _ => return None,
         };
-    };
+        Some(Self {
+            mode,
+            retain_until: now + Duration::days(days),
+        })
+    }
 
-    let mut till_str = meta.get(X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE.as_str().to_lowercase().as_str());
-    if till_str.is_none() {
-        till_str = Some(&meta[X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE.as_str()]);
+    pub fn is_compliance(&self) -> bool {
+        self.mode.as_str() == ObjectLockRetentionMode::COMPLIANCE
     }
-    if let Some(till_str) = till_str {
-        let t = OffsetDateTime::parse(till_str, &format_description::well_known::Iso8601::DEFAULT);
-        if let Ok(parsed_time) = t {
-            retain_until_date = Date::from(parsed_time);
-        }
+
+    /// Whether the version is still retained at `now`
+    pub fn is_active(&self, now: OffsetDateTime) -> bool {
+        self.retain_until > now
     }
-    ObjectLockRetention {
-        mode: Some(mode),
-        retain_until_date: Some(retain_until_date),
+
+    /// Record the retention in the metadata of an object version
+    pub fn write_meta(&self, meta: &mut HashMap<String, String>) {
+        meta.insert(X_AMZ_OBJECT_LOCK_MODE.as_str().to_string(), self.mode.as_str().to_string());
+        meta.insert(
+            X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE.as_str().to_string(),
+            format_retain_until_date(self.retain_until),
+        );
     }
 }
 
-pub fn get_object_legalhold_meta(meta: HashMap<String, String>) -> ObjectLockLegalHold {
-    let mut hold_str = meta.get(X_AMZ_OBJECT_LOCK_LEGAL_HOLD.as_str().to_lowercase().as_str());
-    if hold_str.is_none() {
-        hold_str = Some(&meta[X_AMZ_OBJECT_LOCK_LEGAL_HOLD.as_str()]);
+/// Whether the metadata of an object version puts it under legal hold
+pub fn legal_hold_on(meta: &HashMap<String, String>) -> bool {
+    meta_value(meta, X_AMZ_OBJECT_LOCK_LEGAL_HOLD.as_str())
+        .and_then(parse_legalhold_status)
+        .is_some_and(|status| status.as_str() == ObjectLockLegalHoldStatus::ON)
+}
+
+fn meta_value<'a>(meta: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    meta.get(key)
+        .or_else(|| meta.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v))
+        .map(String::as_str)
+}
+
+pub fn get_object_retention_meta(meta: &HashMap<String, String>) -> ObjectLockRetention {
+    match Retention::from_meta(meta) {
+        Some(retention) => ObjectLockRetention {
+            mode: Some(retention.mode),
+            retain_until_date: Some(Date::from(retention.retain_until)),
+        },
+        None => ObjectLockRetention {
+            mode: None,
+            retain_until_date: None,
+        },
     }
-    if let Some(hold_str) = hold_str {
-        return ObjectLockLegalHold {
-            status: Some(parse_legalhold_status(hold_str)),
-        };
+}
+
+pub fn get_object_legalhold_meta(meta: &HashMap<String, String>) -> ObjectLockLegalHold {
+    ObjectLockLegalHold {
+        status: meta_value(meta, X_AMZ_OBJECT_LOCK_LEGAL_HOLD.as_str()).and_then(parse_legalhold_status),
     }
-    ObjectLockLegalHold { status: None }
 }
 
-pub fn parse_ret_mode(mode_str: &str) -> ObjectLockRetentionMode {
+pub fn parse_ret_mode(mode_str: &str) -> Option<ObjectLockRetentionMode> {
     match mode_str.to_uppercase().as_str() {
-        "GOVERNANCE" => ObjectLockRetentionMode::from_static(ObjectLockRetentionMode::GOVERNANCE),
-        "COMPLIANCE" => ObjectLockRetentionMode::from_static(ObjectLockRetentionMode::COMPLIANCE),
-        _ => unreachable!(),
+        "GOVERNANCE" => Some(ObjectLockRetentionMode::from_static(ObjectLockRetentionMode::GOVERNANCE)),
+        "COMPLIANCE" => Some(ObjectLockRetentionMode::from_static(ObjectLockRetentionMode::COMPLIANCE)),
+        _ => None,
     }
 }
 
-pub fn parse_legalhold_status(hold_str: &str) -> ObjectLockLegalHoldStatus {
+pub fn parse_legalhold_status(hold_str: &str) -> Option<ObjectLockLegalHoldStatus> {
     match hold_str {
-        "ON" => ObjectLockLegalHoldStatus::from_static(ObjectLockLegalHoldStatus::ON),
-        "OFF" => ObjectLockLegalHoldStatus::from_static(ObjectLockLegalHoldStatus::OFF),
-        _ => unreachable!(),
+        "ON" => Some(ObjectLockLegalHoldStatus::from_static(ObjectLockLegalHoldStatus::ON)),
+        "OFF" => Some(ObjectLockLegalHoldStatus::from_static(ObjectLockLegalHoldStatus::OFF)),
+        _ => None,
+    }
+}
+
+/// Parse a retain until date, RFC 3339 as stored or any ISO 8601 date time a client sends
+pub fn parse_retain_until_date(date: &str) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(date, &format_description::well_known::Rfc3339)
+        .or_else(|_| OffsetDateTime::parse(date, &format_description::well_known::Iso8601::DEFAULT))
+        .ok()
+}
+
+pub fn format_retain_until_date(date: OffsetDateTime) -> String {
+    date.format(&format_description::well_known::Rfc3339).unwrap_or_default()
+}
+
+/// Check an object lock configuration before it is stored for a bucket
+pub fn validate_object_lock_config(config: &ObjectLockConfiguration) -> Result<(), ObjectLockError> {
+    if !config.enabled() {
+        return Err(ObjectLockError::MalformedConfig(ERR_MALFORMED_BUCKET_OBJECT_CONFIG));
+    }
+    let Some(rule) = &config.rule else {
+        return Ok(());
+    };
+    let Some(retention) = &rule.default_retention else {
+        return Err(ObjectLockError::MalformedConfig(ERR_MALFORMED_BUCKET_OBJECT_CONFIG));
+    };
+    if retention
+        .mode
+        .as_ref()
+        .and_then(|mode| parse_ret_mode(mode.as_str()))
+        .is_none()
+    {
+        return Err(ObjectLockError::MalformedConfig(ERR_UNKNOWN_WORMMODE_DIRECTIVE));
+    }
+    let days = match (retention.days, retention.years) {
+        (Some(days), None) => i64::from(days),
+        (None, Some(years)) => i64::from(years) * 365,
+        _ => return Err(ObjectLockError::MalformedConfig(ERR_MALFORMED_BUCKET_OBJECT_CONFIG)),
+    };
+    if days <= 0 || days > MAX_DEFAULT_RETENTION_DAYS {
+        return Err(ObjectLockError::MalformedConfig(
+            "default retention period must be between 1 day and 100 years",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::dto::{ObjectLockEnabled, ObjectLockRule};
+
+    fn config(mode: &str, days: Option<i32>, years: Option<i32>) -> ObjectLockConfiguration {
+        ObjectLockConfiguration {
+            object_lock_enabled: Some(ObjectLockEnabled::from_static(ObjectLockEnabled::ENABLED)),
+            rule: Some(ObjectLockRule {
+                default_retention: Some(DefaultRetention {
+                    mode: Some(ObjectLockRetentionMode::from(mode.to_string())),
+                    days,
+                    years,
+                }),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_validate_object_lock_config() {
+        assert!(validate_object_lock_config(&config("GOVERNANCE", Some(30), None)).is_ok());
+        assert!(validate_object_lock_config(&config("COMPLIANCE", None, Some(7))).is_ok());
+        assert!(validate_object_lock_config(&config("COMPLIANCE", Some(1), Some(1))).is_err());
+        assert!(validate_object_lock_config(&config("COMPLIANCE", Some(0), None)).is_err());
+        assert!(validate_object_lock_config(&config("FOREVER", Some(1), None)).is_err());
+
+        let disabled = ObjectLockConfiguration {
+            object_lock_enabled: None,
+            rule: None,
+        };
+        assert_eq!(
+            validate_object_lock_config(&disabled),
+            Err(ObjectLockError::MalformedConfig(ERR_MALFORMED_BUCKET_OBJECT_CONFIG))
+        );
+    }
+
+    #[test]
+    fn test_retention_meta_round_trip() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let default = config("COMPLIANCE", None, Some(1)).rule.unwrap().default_retention.unwrap();
+        let retention = Retention::from_default(&default, now).unwrap();
+        assert_eq!(retention.retain_until, now + Duration::days(365));
+        assert!(retention.is_compliance());
+
+        let mut meta = HashMap::new();
+        assert_eq!(Retention::from_meta(&meta), None);
+        assert_eq!(get_object_retention_meta(&meta).mode, None);
+        retention.write_meta(&mut meta);
+        assert_eq!(Retention::from_meta(&meta), Some(retention));
+
+        assert!(!legal_hold_on(&meta));
+        meta.insert("X-Amz-Object-Lock-Legal-Hold".to_string(), "ON".to_string());
+        assert!(legal_hold_on(&meta));
     }
 }
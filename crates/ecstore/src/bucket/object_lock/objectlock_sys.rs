@@ -15,12 +15,13 @@
 use std::sync::Arc;
 use time::OffsetDateTime;
 
-use s3s::dto::{DefaultRetention, ObjectLockLegalHoldStatus, ObjectLockRetentionMode};
+use s3s::dto::DefaultRetention;
 
 use crate::bucket::metadata_sys::get_object_lock_config;
 use crate::store_api::ObjectInfo;
 
-use super::objectlock;
+use super::ObjectLockApi;
+use super::objectlock::{self, ObjectLockError, Retention};
 
 pub struct BucketObjectLockSys {}
 
@@ -38,30 +39,146 @@ impl BucketObjectLockSys {
         }
         None
     }
+
+    /// Whether object lock is enabled for the bucket
+    pub async fn enabled(bucket: &str) -> bool {
+        get_object_lock_config(bucket).await.is_ok_and(|(config, _)| config.enabled())
+    }
 }
 
-pub fn enforce_retention_for_deletion(obj_info: &ObjectInfo) -> bool {
+/// Check that the version in `obj_info` may be deleted or overwritten
+///
+/// Legal holds and compliance retention always protect a version, governance retention only
+/// unless `bypass_governance` is set by a caller allowed to bypass it.
+pub fn check_retention_for_deletion(
+    obj_info: &ObjectInfo,
+    bypass_governance: bool,
+    now: OffsetDateTime,
+) -> Result<(), ObjectLockError> {
     if obj_info.delete_marker {
-        return false;
+        return Ok(());
+    }
+    if objectlock::legal_hold_on(&obj_info.user_defined) {
+        return Err(ObjectLockError::Protected);
     }
+    match Retention::from_meta(&obj_info.user_defined) {
+        Some(retention) if retention.is_active(now) && (retention.is_compliance() || !bypass_governance) => {
+            Err(ObjectLockError::Protected)
+        }
+        _ => Ok(()),
+    }
+}
 
-    let lhold = objectlock::get_object_legalhold_meta(obj_info.user_defined.clone());
-    match lhold.status {
-        Some(st) if st.as_str() == ObjectLockLegalHoldStatus::ON => {
-            return true;
+pub fn enforce_retention_for_deletion(obj_info: &ObjectInfo) -> bool {
+    check_retention_for_deletion(obj_info, false, objectlock::utc_now_ntp()).is_err()
+}
+
+/// Check that the retention of `obj_info` may be replaced by `retention`, or removed when `None`
+///
+/// Compliance retention can only be extended. Governance retention can be extended or turned into
+/// compliance, shortening or removing it takes `bypass_governance`.
+pub fn check_retention_update(
+    obj_info: &ObjectInfo,
+    retention: Option<&Retention>,
+    bypass_governance: bool,
+    now: OffsetDateTime,
+) -> Result<(), ObjectLockError> {
+    if retention.is_some_and(|retention| !retention.is_active(now)) {
+        return Err(ObjectLockError::InvalidRequest(objectlock::ERR_PAST_OBJECTLOCK_RETAIN_DATE));
+    }
+    let Some(current) = Retention::from_meta(&obj_info.user_defined).filter(|current| current.is_active(now)) else {
+        return Ok(());
+    };
+
+    let extends = retention.is_some_and(|retention| retention.retain_until >= current.retain_until);
+    if current.is_compliance() {
+        return match retention {
+            Some(retention) if extends && retention.is_compliance() => Ok(()),
+            _ => Err(ObjectLockError::Protected),
+        };
+    }
+    if extends || bypass_governance {
+        Ok(())
+    } else {
+        Err(ObjectLockError::Protected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::dto::ObjectLockRetentionMode;
+    use std::collections::HashMap;
+    use time::Duration;
+
+    fn retention(mode: &str, retain_until: OffsetDateTime) -> Retention {
+        Retention {
+            mode: ObjectLockRetentionMode::from(mode.to_string()),
+            retain_until,
         }
-        _ => (),
     }
 
-    let ret = objectlock::get_object_retention_meta(obj_info.user_defined.clone());
-    match ret.mode {
-        Some(r) if (r.as_str() == ObjectLockRetentionMode::COMPLIANCE || r.as_str() == ObjectLockRetentionMode::GOVERNANCE) => {
-            let t = objectlock::utc_now_ntp();
-            if OffsetDateTime::from(ret.retain_until_date.expect("err!")).unix_timestamp() > t.unix_timestamp() {
-                return true;
-            }
+    fn object(retention: Option<&Retention>, legal_hold: bool) -> ObjectInfo {
+        let mut user_defined = HashMap::new();
+        if let Some(retention) = retention {
+            retention.write_meta(&mut user_defined);
+        }
+        if legal_hold {
+            user_defined.insert("x-amz-object-lock-legal-hold".to_string(), "ON".to_string());
         }
-        _ => (),
+        ObjectInfo {
+            user_defined,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_retention_for_deletion() {
+        let now = OffsetDateTime::now_utc();
+        let governance = retention("GOVERNANCE", now + Duration::days(1));
+        let compliance = retention("COMPLIANCE", now + Duration::days(1));
+        let expired = retention("COMPLIANCE", now - Duration::days(1));
+
+        assert!(check_retention_for_deletion(&object(None, false), false, now).is_ok());
+        assert!(check_retention_for_deletion(&object(Some(&expired), false), false, now).is_ok());
+        assert!(check_retention_for_deletion(&object(None, true), true, now).is_err());
+        assert!(check_retention_for_deletion(&object(Some(&governance), false), false, now).is_err());
+        assert!(check_retention_for_deletion(&object(Some(&governance), false), true, now).is_ok());
+        assert_eq!(
+            check_retention_for_deletion(&object(Some(&compliance), false), true, now),
+            Err(ObjectLockError::Protected)
+        );
+
+        let mut marker = object(Some(&compliance), true);
+        marker.delete_marker = true;
+        assert!(check_retention_for_deletion(&marker, false, now).is_ok());
+    }
+
+    #[test]
+    fn test_check_retention_update() {
+        let now = OffsetDateTime::now_utc();
+        let day = retention("GOVERNANCE", now + Duration::days(1));
+        let week = retention("GOVERNANCE", now + Duration::days(7));
+        let compliance_day = retention("COMPLIANCE", now + Duration::days(1));
+        let compliance_week = retention("COMPLIANCE", now + Duration::days(7));
+        let past = retention("GOVERNANCE", now - Duration::days(1));
+
+        assert!(matches!(
+            check_retention_update(&object(None, false), Some(&past), true, now),
+            Err(ObjectLockError::InvalidRequest(_))
+        ));
+        assert!(check_retention_update(&object(None, false), Some(&day), false, now).is_ok());
+
+        let governed = object(Some(&week), false);
+        assert!(check_retention_update(&governed, Some(&compliance_week), false, now).is_ok());
+        assert!(check_retention_update(&governed, Some(&day), false, now).is_err());
+        assert!(check_retention_update(&governed, Some(&day), true, now).is_ok());
+        assert!(check_retention_update(&governed, None, false, now).is_err());
+        assert!(check_retention_update(&governed, None, true, now).is_ok());
+
+        let complied = object(Some(&compliance_day), false);
+        assert!(check_retention_update(&complied, Some(&compliance_week), false, now).is_ok());
+        assert!(check_retention_update(&complied, Some(&week), true, now).is_err());
+        assert!(check_retention_update(&complied, None, true, now).is_err());
     }
-    false
 }
@@ -12,17 +12,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{error::BucketMetadataError, metadata_sys::get_bucket_metadata_sys};
-use crate::error::Result;
-use rustfs_policy::policy::{BucketPolicy, BucketPolicyArgs};
+use super::{
+    acl::BucketAcl, error::BucketMetadataError, metadata_sys::get_bucket_metadata_sys, public_access::PublicAccessBlock,
+};
+use crate::error::{Error, Result};
+use rustfs_policy::policy::{BucketPolicy, BucketPolicyArgs, DEFAULT_VERSION};
 use tracing::warn;
 
 pub struct PolicySys {}
 
 impl PolicySys {
     pub async fn is_allowed(args: &BucketPolicyArgs<'_>) -> bool {
-        match Self::get(args.bucket).await {
-            Ok(cfg) => return cfg.is_allowed(args),
+        let block = match PublicAccessBlock::get(args.bucket).await {
+            Ok(block) => block,
+            Err(err) => {
+                warn!("public access block get err {:?}", err);
+                return false;
+            }
+        };
+
+        match Self::get_effective(args.bucket, &block).await {
+            Ok(Some(cfg)) => {
+                // Anonymous callers are only denied by a public policy of a restricted bucket
+                if block.restrict_public_buckets && args.account.is_empty() && !args.deny_only && cfg.is_public() {
+                    return false;
+                }
+                return cfg.is_allowed(args);
+            }
+            Ok(None) => {}
             Err(err) => {
                 let berr: BucketMetadataError = err.into();
                 if berr != BucketMetadataError::BucketPolicyNotFound {
@@ -31,7 +48,32 @@ impl PolicySys {
             }
         }
 
-        args.is_owner
+        // Without a policy nothing is denied, and only the owner is allowed
+        args.is_owner || args.deny_only
+    }
+
+    /// Policy requests to `bucket` are evaluated against: its bucket policy and the statements of its
+    /// canned ACL, unless `block` ignores public ACLs; none when neither has a statement
+    pub async fn get_effective(bucket: &str, block: &PublicAccessBlock) -> Result<Option<BucketPolicy>> {
+        let mut cfg = match Self::get(bucket).await {
+            Ok(cfg) => Some(cfg),
+            Err(Error::ConfigNotFound) => None,
+            Err(err) => return Err(err),
+        };
+
+        if !block.ignore_public_acls {
+            let statements = BucketAcl::get(bucket).await?.statements(bucket);
+            if !statements.is_empty() {
+                cfg.get_or_insert_with(|| BucketPolicy {
+                    version: DEFAULT_VERSION.to_owned(),
+                    ..Default::default()
+                })
+                .statements
+                .extend(statements);
+            }
+        }
+
+        Ok(cfg)
     }
     pub async fn get(bucket: &str) -> Result<BucketPolicy> {
         let bucket_meta_sys_lock = get_bucket_metadata_sys()?;
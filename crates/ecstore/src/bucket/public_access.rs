@@ -0,0 +1,122 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Public access block of a bucket
+//!
+//! The block restricts what the bucket policy and the canned ACLs of a bucket may open to everyone:
+//! public ACLs can be refused or ignored, public policies refused, and the access a public policy gives
+//! anonymous callers taken back.
+
+use rustfs_policy::policy::BucketPolicy;
+use s3s::dto::PublicAccessBlockConfiguration;
+use serde::Serialize;
+
+use super::metadata_sys;
+use super::policy_sys::PolicySys;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicAccessBlock {
+    /// Requests giving the bucket or its objects a public ACL are refused
+    pub block_public_acls: bool,
+    /// Public ACLs of the bucket and its objects grant nothing
+    pub ignore_public_acls: bool,
+    /// Bucket policies allowing everyone in are refused
+    pub block_public_policy: bool,
+    /// Anonymous callers get nothing from a public bucket policy
+    pub restrict_public_buckets: bool,
+}
+
+impl From<&PublicAccessBlockConfiguration> for PublicAccessBlock {
+    fn from(config: &PublicAccessBlockConfiguration) -> Self {
+        Self {
+            block_public_acls: config.block_public_acls.unwrap_or_default(),
+            ignore_public_acls: config.ignore_public_acls.unwrap_or_default(),
+            block_public_policy: config.block_public_policy.unwrap_or_default(),
+            restrict_public_buckets: config.restrict_public_buckets.unwrap_or_default(),
+        }
+    }
+}
+
+impl PublicAccessBlock {
+    /// Public access block of `bucket`, nothing blocked when it has none
+    pub async fn get(bucket: &str) -> Result<Self> {
+        match metadata_sys::get_public_access_block_config(bucket).await {
+            Ok((config, _)) => Ok(Self::from(&config)),
+            Err(Error::ConfigNotFound) => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Whether anonymous callers may be let in by `policy`, the policy a bucket is evaluated against
+    /// under the block
+    pub fn is_public(&self, policy: &BucketPolicy) -> bool {
+        !self.restrict_public_buckets && policy.is_public()
+    }
+}
+
+/// Whether the bucket policy or the canned ACL of `bucket` let anonymous callers in, under the public
+/// access block of the bucket
+pub async fn is_bucket_public(bucket: &str) -> Result<bool> {
+    let block = PublicAccessBlock::get(bucket).await?;
+    Ok(PolicySys::get_effective(bucket, &block)
+        .await?
+        .is_some_and(|policy| block.is_public(&policy)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> BucketPolicy {
+        let data = r#"
+{
+  "Version": "2012-10-17",
+  "Statement": [
+    {
+      "Effect": "Allow",
+      "Principal": {"AWS": ["*"]},
+      "Action": ["s3:GetObject"],
+      "Resource": ["arn:aws:s3:::mybucket/*"]
+    },
+    {
+      "Effect": "Allow",
+      "Principal": {"AWS": ["alice"]},
+      "Action": ["s3:PutObject"],
+      "Resource": ["arn:aws:s3:::mybucket/*"]
+    }
+  ]
+}
+"#;
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[test]
+    fn test_public_access_block() {
+        let none = PublicAccessBlock::default();
+        assert!(none.is_public(&policy()));
+
+        let mut private = policy();
+        private.statements.remove(0);
+        assert!(!none.is_public(&private));
+
+        let restrict = PublicAccessBlock::from(&PublicAccessBlockConfiguration {
+            restrict_public_buckets: Some(true),
+            ..Default::default()
+        });
+        assert!(restrict.restrict_public_buckets && !restrict.block_public_acls);
+        assert!(!restrict.is_public(&policy()));
+    }
+}
@@ -12,41 +12,261 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::error::Result;
-use rmp_serde::Serializer as rmpSerializer;
+//! Size and object count quotas of buckets
+//!
+//! A write is checked against the usage of its bucket the scanner last saved, plus the writes this server
+//! made since, so no write has to stat the whole bucket. The usage is only as fresh as the last scan: an
+//! overwrite counts as new data until then, and deletes only lower it once the scanner saw them.
+
+use super::metadata_sys;
+use crate::data_usage::load_data_usage_from_backend;
+use crate::error::{Error, Result};
+use crate::new_object_layer_fn;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{error, warn};
+
+/// How long the usage the scanner saved is used before it is read again
+const USAGE_TTL: Duration = Duration::from_secs(10);
 
-// 定义 QuotaType 枚举类型
+/// Kind of a bucket quota: a hard quota fails the writes that would exceed it, a soft one only warns
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum QuotaType {
+    #[serde(alias = "Hard")]
     Hard,
+    #[serde(alias = "Soft")]
+    Soft,
 }
 
-// 定义 BucketQuota 结构体
-#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+/// Quota of a bucket, a limit of 0 is no limit
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(default)]
 pub struct BucketQuota {
-    quota: Option<u64>, // 使用 Option 来表示可能不存在的字段
+    /// Size limit of the configs written before `size`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota: Option<u64>,
 
-    size: u64,
+    /// Most bytes the bucket may hold
+    pub size: u64,
 
-    rate: u64,
+    pub rate: u64,
 
-    requests: u64,
+    pub requests: u64,
 
-    quota_type: Option<QuotaType>,
+    /// Most objects the bucket may hold
+    pub objects: u64,
+
+    /// Hard unless set otherwise
+    #[serde(rename = "quotatype", alias = "quota_type", skip_serializing_if = "Option::is_none")]
+    pub quota_type: Option<QuotaType>,
 }
 
 impl BucketQuota {
-    pub fn marshal_msg(&self) -> Result<Vec<u8>> {
-        let mut buf = Vec::new();
+    pub fn marshal(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
 
-        self.serialize(&mut rmpSerializer::new(&mut buf).with_struct_map())?;
+    pub fn unmarshal(buf: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(buf)?)
+    }
 
-        Ok(buf)
+    pub fn size_limit(&self) -> u64 {
+        if self.size > 0 {
+            self.size
+        } else {
+            self.quota.unwrap_or_default()
+        }
     }
 
-    pub fn unmarshal(buf: &[u8]) -> Result<Self> {
-        let t: BucketQuota = rmp_serde::from_slice(buf)?;
-        Ok(t)
+    /// Whether the quota limits anything
+    pub fn is_set(&self) -> bool {
+        self.size_limit() > 0 || self.objects > 0
+    }
+
+    pub fn is_hard(&self) -> bool {
+        self.quota_type != Some(QuotaType::Soft)
+    }
+
+    /// Whether `size` more bytes and `objects` more objects take a bucket using `usage` over the quota
+    pub fn exceeded_by(&self, usage: BucketUsage, size: u64, objects: u64) -> bool {
+        let size_limit = self.size_limit();
+        (size_limit > 0 && usage.size.saturating_add(size) > size_limit)
+            || (self.objects > 0 && usage.objects.saturating_add(objects) > self.objects)
+    }
+}
+
+/// Bytes and objects a bucket holds
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BucketUsage {
+    pub size: u64,
+    pub objects: u64,
+}
+
+#[derive(Default)]
+struct UsageCache {
+    loaded_at: Option<Instant>,
+    /// When the scanner saved the usage in `buckets`
+    last_update: Option<SystemTime>,
+    buckets: HashMap<String, BucketUsage>,
+    /// Usage added by the writes made since the scanner saved `buckets`
+    written: HashMap<String, BucketUsage>,
+}
+
+impl UsageCache {
+    fn usage(&self, bucket: &str) -> BucketUsage {
+        let saved = self.buckets.get(bucket).copied().unwrap_or_default();
+        let written = self.written.get(bucket).copied().unwrap_or_default();
+        BucketUsage {
+            size: saved.size.saturating_add(written.size),
+            objects: saved.objects.saturating_add(written.objects),
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        self.loaded_at.is_none_or(|at| at.elapsed() >= USAGE_TTL)
+    }
+
+    fn replace(&mut self, last_update: Option<SystemTime>, buckets: HashMap<String, BucketUsage>) {
+        // The writes made before the scanner saved its usage are counted in it
+        if last_update != self.last_update {
+            self.written.clear();
+        }
+        self.loaded_at = Some(Instant::now());
+        self.last_update = last_update;
+        self.buckets = buckets;
+    }
+}
+
+static USAGE: LazyLock<Mutex<UsageCache>> = LazyLock::new(|| Mutex::new(UsageCache::default()));
+
+fn usage_cache() -> std::sync::MutexGuard<'static, UsageCache> {
+    USAGE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Usage of `bucket`, as the scanner last saved it plus the writes made since
+pub async fn bucket_usage(bucket: &str) -> BucketUsage {
+    if usage_cache().is_stale() {
+        if let Some(store) = new_object_layer_fn() {
+            match load_data_usage_from_backend(store).await {
+                Ok(info) => {
+                    let buckets = info
+                        .buckets_usage
+                        .iter()
+                        .map(|(name, usage)| {
+                            (
+                                name.clone(),
+                                BucketUsage {
+                                    size: usage.size,
+                                    objects: usage.objects_count,
+                                },
+                            )
+                        })
+                        .collect();
+                    usage_cache().replace(info.last_update, buckets);
+                }
+                Err(err) => error!("load bucket usage for quotas failed: {}", err),
+            }
+        }
+    }
+
+    usage_cache().usage(bucket)
+}
+
+/// Counts a write of `size` bytes and `objects` objects to `bucket` until the scanner saves its usage again
+pub fn record_bucket_usage(bucket: &str, size: u64, objects: u64) {
+    let mut cache = usage_cache();
+    let written = cache.written.entry(bucket.to_owned()).or_default();
+    written.size = written.size.saturating_add(size);
+    written.objects = written.objects.saturating_add(objects);
+}
+
+/// Whether `bucket` has a quota limiting anything
+pub async fn has_bucket_quota(bucket: &str) -> bool {
+    metadata_sys::get_quota_config(bucket)
+        .await
+        .is_ok_and(|(quota, _)| quota.is_set())
+}
+
+/// Fails a write of `size` bytes and `objects` objects that would take `bucket` over its hard quota
+pub async fn check_bucket_quota(bucket: &str, size: u64, objects: u64) -> Result<()> {
+    let quota = match metadata_sys::get_quota_config(bucket).await {
+        Ok((quota, _)) => quota,
+        Err(Error::ConfigNotFound) => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    if !quota.is_set() {
+        return Ok(());
+    }
+
+    if !quota.exceeded_by(bucket_usage(bucket).await, size, objects) {
+        return Ok(());
+    }
+
+    if !quota.is_hard() {
+        warn!("bucket {} is over its soft quota", bucket);
+        return Ok(());
+    }
+
+    Err(Error::BucketQuotaExceeded(bucket.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_quota_unmarshal() {
+        let quota = BucketQuota::unmarshal(br#"{"quota":100,"quotatype":"hard"}"#).unwrap();
+        assert_eq!(quota.size_limit(), 100);
+        assert!(quota.is_hard());
+
+        let quota = BucketQuota::unmarshal(br#"{"size":200,"quota":100,"objects":3,"quotatype":"soft"}"#).unwrap();
+        assert_eq!(quota.size_limit(), 200);
+        assert_eq!(quota.objects, 3);
+        assert!(!quota.is_hard());
+
+        assert_eq!(BucketQuota::unmarshal(&quota.marshal().unwrap()).unwrap(), quota);
+        assert!(!BucketQuota::unmarshal(b"{}").unwrap().is_set());
+    }
+
+    #[test]
+    fn test_bucket_quota_exceeded_by() {
+        let quota = BucketQuota {
+            size: 100,
+            objects: 2,
+            ..Default::default()
+        };
+        let usage = BucketUsage { size: 60, objects: 1 };
+
+        assert!(!quota.exceeded_by(usage, 40, 1));
+        assert!(quota.exceeded_by(usage, 41, 1));
+        assert!(quota.exceeded_by(usage, 0, 2));
+
+        let unlimited = BucketQuota::default();
+        assert!(!unlimited.exceeded_by(usage, u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_usage_cache_written() {
+        let mut cache = UsageCache::default();
+        let scanned = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        cache.replace(Some(scanned), HashMap::from([("b".to_owned(), BucketUsage { size: 10, objects: 1 })]));
+        cache.written.insert("b".to_owned(), BucketUsage { size: 5, objects: 1 });
+        assert_eq!(cache.usage("b"), BucketUsage { size: 15, objects: 2 });
+
+        // The same scan read again keeps the writes made since
+        cache.replace(Some(scanned), HashMap::from([("b".to_owned(), BucketUsage { size: 10, objects: 1 })]));
+        assert_eq!(cache.usage("b"), BucketUsage { size: 15, objects: 2 });
+
+        // A newer scan counts them
+        cache.replace(
+            Some(scanned + Duration::from_secs(1)),
+            HashMap::from([("b".to_owned(), BucketUsage { size: 15, objects: 2 })]),
+        );
+        assert_eq!(cache.usage("b"), BucketUsage { size: 15, objects: 2 });
+        assert!(!cache.is_stale());
     }
 }
@@ -13,15 +13,30 @@
 // limitations under the License.
 
 use s3s::dto::Tag;
+use std::collections::HashSet;
 use url::form_urlencoded;
 
+/// The most tags an object can carry
+pub const MAX_OBJECT_TAGS: usize = 10;
+/// The most tags a bucket can carry
+pub const MAX_BUCKET_TAGS: usize = 50;
+
+const MAX_TAG_KEY_LENGTH: usize = 128;
+const MAX_TAG_VALUE_LENGTH: usize = 256;
+
+const ERR_TOO_MANY_TAGS: &str = "The TagSet exceeds the maximum number of tags";
+const ERR_INVALID_TAG_KEY: &str = "The TagKey you have provided is invalid";
+const ERR_INVALID_TAG_VALUE: &str = "The TagValue you have provided is invalid";
+const ERR_DUPLICATE_TAG_KEY: &str = "Cannot provide multiple Tags with the same key";
+
 pub fn decode_tags(tags: &str) -> Vec<Tag> {
     let values = form_urlencoded::parse(tags.as_bytes());
 
     let mut list = Vec::new();
 
     for (k, v) in values {
-        if k.is_empty() || v.is_empty() {
+        // Tag values may be empty, keys may not
+        if k.is_empty() {
             continue;
         }
 
@@ -46,3 +61,58 @@ pub fn encode_tags(tags: Vec<Tag>) -> String {
 
     encoded.finish()
 }
+
+/// Checks a tag set against the S3 limits: at most `max_tags` tags, keys of 1 to 128 and values
+/// of up to 256 characters, and no key given twice
+pub fn validate_tags(tags: &[Tag], max_tags: usize) -> Result<(), std::io::Error> {
+    if tags.len() > max_tags {
+        return Err(std::io::Error::other(ERR_TOO_MANY_TAGS));
+    }
+
+    let mut keys = HashSet::with_capacity(tags.len());
+    for tag in tags {
+        let key = tag.key.as_deref().unwrap_or_default();
+        if key.is_empty() || key.chars().count() > MAX_TAG_KEY_LENGTH {
+            return Err(std::io::Error::other(ERR_INVALID_TAG_KEY));
+        }
+        if tag.value.as_deref().unwrap_or_default().chars().count() > MAX_TAG_VALUE_LENGTH {
+            return Err(std::io::Error::other(ERR_INVALID_TAG_VALUE));
+        }
+        if !keys.insert(key) {
+            return Err(std::io::Error::other(ERR_DUPLICATE_TAG_KEY));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(key: &str, value: &str) -> Tag {
+        Tag {
+            key: Some(key.to_string()),
+            value: Some(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_decode_tags_keeps_empty_values() {
+        let tags = decode_tags("project=web&archived=&=orphan");
+        assert_eq!(tags, vec![tag("project", "web"), tag("archived", "")]);
+        assert_eq!(encode_tags(tags), "project=web&archived=");
+    }
+
+    #[test]
+    fn test_validate_tags() {
+        assert!(validate_tags(&[tag("project", "web"), tag("archived", "")], MAX_OBJECT_TAGS).is_ok());
+        assert!(validate_tags(&[tag("", "web")], MAX_OBJECT_TAGS).is_err());
+        assert!(validate_tags(&[tag(&"k".repeat(129), "v")], MAX_OBJECT_TAGS).is_err());
+        assert!(validate_tags(&[tag("k", &"v".repeat(257))], MAX_OBJECT_TAGS).is_err());
+        assert!(validate_tags(&[tag("k", "a"), tag("k", "b")], MAX_OBJECT_TAGS).is_err());
+
+        let many: Vec<Tag> = (0..=MAX_OBJECT_TAGS).map(|i| tag(&format!("k{i}"), "v")).collect();
+        assert!(validate_tags(&many, MAX_OBJECT_TAGS).is_err());
+        assert!(validate_tags(&many, MAX_BUCKET_TAGS).is_ok());
+    }
+}
@@ -28,6 +28,10 @@ lazy_static::lazy_static! {
     static ref IP_ADDRESS: Regex = Regex::new(r"^(\d+\.){3}\d+$").unwrap();
 }
 
+/// Prefixes and suffixes S3 keeps for its own bucket names
+const RESERVED_BUCKET_PREFIXES: &[&str] = &["xn--", "sthree-", "amzn-s3-demo-"];
+const RESERVED_BUCKET_SUFFIXES: &[&str] = &["-s3alias", "--ol-s3", ".mrap", "--x-s3", "--table-s3"];
+
 pub fn check_bucket_name_common(bucket_name: &str, strict: bool) -> Result<()> {
     let bucket_name_trimmed = bucket_name.trim();
 
@@ -52,7 +56,7 @@ pub fn check_bucket_name_common(bucket_name: &str, strict: bool) -> Result<()> {
         return Err(Error::other("Bucket name contains invalid characters"));
     }
     if strict {
-        if !VALID_BUCKET_NAME_STRICT.is_match(bucket_name_trimmed) {
+        if !VALID_BUCKET_NAME_STRICT.is_match(bucket_name) {
             return Err(Error::other("Bucket name contains invalid characters"));
         }
     } else if !VALID_BUCKET_NAME.is_match(bucket_name_trimmed) {
@@ -69,6 +73,18 @@ pub fn check_valid_bucket_name_strict(bucket_name: &str) -> Result<()> {
     check_bucket_name_common(bucket_name, true)
 }
 
+/// Checks the name of a bucket about to be made, which may not use the names S3 keeps for itself either
+pub fn check_new_bucket_name(bucket_name: &str) -> Result<()> {
+    check_valid_bucket_name_strict(bucket_name)?;
+    if let Some(prefix) = RESERVED_BUCKET_PREFIXES.iter().find(|p| bucket_name.starts_with(*p)) {
+        return Err(Error::other(format!("Bucket name cannot start with {prefix}")));
+    }
+    if let Some(suffix) = RESERVED_BUCKET_SUFFIXES.iter().find(|s| bucket_name.ends_with(*s)) {
+        return Err(Error::other(format!("Bucket name cannot end with {suffix}")));
+    }
+    Ok(())
+}
+
 pub fn check_valid_object_name_prefix(object_name: &str) -> Result<()> {
     if object_name.len() > 1024 {
         return Err(Error::other("Object name cannot be longer than 1024 characters"));
@@ -113,3 +129,37 @@ pub fn serialize<T: xml::Serialize>(val: &T) -> xml::SerResult<Vec<u8>> {
     }
     Ok(buf)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_new_bucket_name() {
+        for name in ["abc", "my-bucket.2024", "a".repeat(63).as_str()] {
+            assert!(check_new_bucket_name(name).is_ok(), "{name}");
+        }
+        for name in [
+            "ab",
+            "Bucket",
+            "my_bucket",
+            " abc",
+            "abc-",
+            "a..b",
+            "192.168.1.1",
+            "xn--abc",
+            "sthree-abc",
+            "abc-s3alias",
+            "abc--ol-s3",
+            "abc--x-s3",
+            "rustfs",
+            "a".repeat(64).as_str(),
+        ] {
+            assert!(check_new_bucket_name(name).is_err(), "{name}");
+        }
+
+        // Buckets already made with a reserved name can still be used
+        assert!(check_valid_bucket_name_strict("xn--abc").is_ok());
+        assert!(check_valid_bucket_name("My_Bucket").is_ok());
+    }
+}
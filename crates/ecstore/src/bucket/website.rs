@@ -0,0 +1,192 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Website configuration of a bucket: what S3 accepts in one, and how a request to the website is routed
+
+use s3s::dto::{RoutingRule, WebsiteConfiguration};
+
+use crate::error::{Error, Result};
+
+/// Most routing rules a website configuration may have
+pub const MAX_ROUTING_RULES: usize = 50;
+
+pub fn validate_website_config(config: &WebsiteConfiguration) -> Result<()> {
+    if config.redirect_all_requests_to.is_some() {
+        if config.index_document.is_some() || config.error_document.is_some() || config.routing_rules.is_some() {
+            return Err(Error::other(
+                "RedirectAllRequestsTo cannot be provided in conjunction with other Routing Rules",
+            ));
+        }
+        return Ok(());
+    }
+
+    let Some(index) = &config.index_document else {
+        return Err(Error::other(
+            "A value for IndexDocument Suffix must be provided if RedirectAllRequestsTo is empty",
+        ));
+    };
+    if index.suffix.is_empty() || index.suffix.contains('/') {
+        return Err(Error::other("The IndexDocument Suffix is not well formed"));
+    }
+    if config.error_document.as_ref().is_some_and(|doc| doc.key.is_empty()) {
+        return Err(Error::other("The ErrorDocument Key is not well formed"));
+    }
+
+    let rules = config.routing_rules.as_deref().unwrap_or_default();
+    if rules.len() > MAX_ROUTING_RULES {
+        return Err(Error::other(format!(
+            "a website configuration can have at most {MAX_ROUTING_RULES} routing rules"
+        )));
+    }
+    for rule in rules {
+        if let Some(condition) = &rule.condition {
+            if condition.key_prefix_equals.is_none() && condition.http_error_code_returned_equals.is_none() {
+                return Err(Error::other("Condition cannot be empty. To redirect all requests, remove the Condition"));
+            }
+            if let Some(code) = &condition.http_error_code_returned_equals {
+                if !code.parse::<u16>().is_ok_and(|code| (400..600).contains(&code)) {
+                    return Err(Error::other(format!("The provided HTTP error code ({code}) is not valid")));
+                }
+            }
+        }
+        let redirect = &rule.redirect;
+        if redirect.replace_key_with.is_some() && redirect.replace_key_prefix_with.is_some() {
+            return Err(Error::other("You can only define ReplaceKeyPrefix or ReplaceKey but not both"));
+        }
+        if let Some(code) = &redirect.http_redirect_code {
+            if !code.parse::<u16>().is_ok_and(|code| (300..400).contains(&code)) {
+                return Err(Error::other(format!("The provided HTTP redirect code ({code}) is not valid")));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Key the website serves for `key`: the index document of the folder when `key` names one
+pub fn index_key(config: &WebsiteConfiguration, key: &str) -> Option<String> {
+    let suffix = &config.index_document.as_ref()?.suffix;
+    (key.is_empty() || key.ends_with('/')).then(|| format!("{key}{suffix}"))
+}
+
+/// First routing rule of `config` applying to a request for `key`
+///
+/// Without a `status`, these are the rules applying before the object is read; with one, the rules
+/// applying to a request answered with that error.
+pub fn find_routing_rule<'a>(config: &'a WebsiteConfiguration, key: &str, status: Option<u16>) -> Option<&'a RoutingRule> {
+    config.routing_rules.as_deref()?.iter().find(|rule| {
+        let Some(condition) = &rule.condition else {
+            return status.is_none();
+        };
+        let prefix_matches = condition
+            .key_prefix_equals
+            .as_ref()
+            .is_none_or(|prefix| key.starts_with(prefix.as_str()));
+        let status_matches = match (&condition.http_error_code_returned_equals, status) {
+            (Some(code), Some(status)) => code.parse() == Ok(status),
+            (None, None) => true,
+            _ => false,
+        };
+        prefix_matches && status_matches
+    })
+}
+
+/// Key a request for `key` is redirected to by `rule`
+pub fn redirect_key(rule: &RoutingRule, key: &str) -> String {
+    if let Some(replace) = &rule.redirect.replace_key_with {
+        return replace.clone();
+    }
+    match (
+        &rule.redirect.replace_key_prefix_with,
+        rule.condition.as_ref().and_then(|c| c.key_prefix_equals.as_ref()),
+    ) {
+        (Some(replace), Some(prefix)) => format!("{replace}{}", key.strip_prefix(prefix.as_str()).unwrap_or(key)),
+        (Some(replace), None) => format!("{replace}{key}"),
+        (None, _) => key.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::dto::{Condition, ErrorDocument, IndexDocument, Redirect, RedirectAllRequestsTo};
+
+    fn rule(prefix: Option<&str>, code: Option<&str>, replace_prefix: Option<&str>) -> RoutingRule {
+        RoutingRule {
+            condition: (prefix.is_some() || code.is_some()).then(|| Condition {
+                key_prefix_equals: prefix.map(str::to_owned),
+                http_error_code_returned_equals: code.map(str::to_owned),
+            }),
+            redirect: Redirect {
+                host_name: None,
+                http_redirect_code: None,
+                protocol: None,
+                replace_key_prefix_with: replace_prefix.map(str::to_owned),
+                replace_key_with: None,
+            },
+        }
+    }
+
+    fn config(rules: Vec<RoutingRule>) -> WebsiteConfiguration {
+        WebsiteConfiguration {
+            error_document: Some(ErrorDocument {
+                key: "error.html".to_string(),
+            }),
+            index_document: Some(IndexDocument {
+                suffix: "index.html".to_string(),
+            }),
+            redirect_all_requests_to: None,
+            routing_rules: (!rules.is_empty()).then_some(rules),
+        }
+    }
+
+    #[test]
+    fn test_validate_website_config() {
+        assert!(validate_website_config(&config(vec![rule(Some("docs/"), None, Some("documents/"))])).is_ok());
+        assert!(validate_website_config(&config(vec![rule(None, Some("200"), None)])).is_err());
+
+        let mut no_index = config(vec![]);
+        no_index.index_document = None;
+        assert!(validate_website_config(&no_index).is_err());
+
+        let mut redirect_all = config(vec![]);
+        redirect_all.redirect_all_requests_to = Some(RedirectAllRequestsTo {
+            host_name: "example.com".to_string(),
+            protocol: None,
+        });
+        assert!(validate_website_config(&redirect_all).is_err());
+        redirect_all.index_document = None;
+        redirect_all.error_document = None;
+        assert!(validate_website_config(&redirect_all).is_ok());
+    }
+
+    #[test]
+    fn test_routing() {
+        let config = config(vec![
+            rule(Some("docs/"), None, Some("documents/")),
+            rule(None, Some("404"), Some("#!/")),
+        ]);
+        assert_eq!(index_key(&config, "").as_deref(), Some("index.html"));
+        assert_eq!(index_key(&config, "blog/").as_deref(), Some("blog/index.html"));
+        assert_eq!(index_key(&config, "blog"), None);
+
+        let docs = find_routing_rule(&config, "docs/a.html", None).unwrap();
+        assert_eq!(redirect_key(docs, "docs/a.html"), "documents/a.html");
+        assert!(find_routing_rule(&config, "app/route", None).is_none());
+
+        let missing = find_routing_rule(&config, "app/route", Some(404)).unwrap();
+        assert_eq!(redirect_key(missing, "app/route"), "#!/app/route");
+        assert!(find_routing_rule(&config, "app/route", Some(403)).is_none());
+    }
+}
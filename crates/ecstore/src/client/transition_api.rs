@@ -62,12 +62,11 @@ use crate::client::{
     credentials::{CredContext, Credentials, SignatureType, Static},
 };
 use crate::{checksum::ChecksumMode, store_api::GetObjectReader};
+use rustfs_retry::{Backoff, RetryPolicy};
 use rustfs_rio::HashReader;
 use rustfs_utils::{
     net::get_endpoint_url,
-    retry::{
-        DEFAULT_RETRY_CAP, DEFAULT_RETRY_UNIT, MAX_JITTER, MAX_RETRY, RetryTimer, is_http_status_retryable, is_s3code_retryable,
-    },
+    retry::{DEFAULT_RETRY_CAP, DEFAULT_RETRY_UNIT, MAX_JITTER, MAX_RETRY, is_http_status_retryable, is_s3code_retryable},
 };
 use s3s::S3ErrorCode;
 use s3s::dto::ReplicationStatus;
@@ -75,6 +74,9 @@ use s3s::{Body, dto::Owner};
 
 const C_USER_AGENT: &str = "RustFS (linux; x86)";
 
+/// Retry budget shared by the requests to every tier
+const RETRY_SUBSYSTEM: &str = "tier";
+
 const SUCCESS_STATUS: [StatusCode; 3] = [StatusCode::OK, StatusCode::NO_CONTENT, StatusCode::PARTIAL_CONTENT];
 
 const C_UNKNOWN: i32 = -1;
@@ -316,14 +318,27 @@ impl TransitionClient {
         }
         //}
 
-        let mut retry_timer = RetryTimer::new(req_retry, DEFAULT_RETRY_UNIT, DEFAULT_RETRY_CAP, MAX_JITTER, self.random);
-        while let Some(v) = retry_timer.next().await {
+        let policy = RetryPolicy::new(
+            req_retry.try_into().unwrap_or(1),
+            Backoff::new(DEFAULT_RETRY_UNIT, DEFAULT_RETRY_CAP).with_jitter(MAX_JITTER),
+        )
+        .with_budget(rustfs_retry::budget(RETRY_SUBSYSTEM))
+        .with_breaker(rustfs_retry::breaker(&format!("{RETRY_SUBSYSTEM}:{}", self.endpoint_url)));
+        let mut attempts = policy.attempts();
+        while attempts.next().await.is_ok() {
             let req = self.new_request(&method, metadata).await?;
 
-            resp = self.doit(req).await?;
+            resp = match self.doit(req).await {
+                Ok(resp) => resp,
+                Err(e) => {
+                    attempts.record_failure();
+                    return Err(e);
+                }
+            };
 
             for http_status in SUCCESS_STATUS {
                 if http_status == resp.status() {
+                    attempts.record_success();
                     return Ok(resp);
                 }
             }
@@ -332,6 +347,7 @@ impl TransitionClient {
             let err_response = http_resp_to_error_response(&resp, b.clone(), &metadata.bucket_name, &metadata.object_name);
 
             if self.region == "" {
+                attempts.record_success();
                 match err_response.code {
                     S3ErrorCode::AuthorizationHeaderMalformed | S3ErrorCode::InvalidArgument /*S3ErrorCode::InvalidRegion*/ => {
                         //break;
@@ -360,14 +376,12 @@ impl TransitionClient {
                 }
             }
 
-            if is_s3code_retryable(err_response.code.as_str()) {
-                continue;
-            }
-
-            if is_http_status_retryable(&resp.status()) {
+            if is_s3code_retryable(err_response.code.as_str()) || is_http_status_retryable(&resp.status()) {
+                attempts.record_failure();
                 continue;
             }
 
+            attempts.record_success();
             break;
         }
 
@@ -16,8 +16,10 @@
 // use error::Error;
 use crate::StorageAPI;
 use crate::bucket::metadata_sys::get_replication_config;
+use crate::bucket::object_lock::objectlock::{Retention, get_object_legalhold_meta};
 use crate::bucket::versioning_sys::BucketVersioningSys;
-use crate::error::Error;
+use crate::config::com::{read_config, save_config};
+use crate::error::{Error, is_err_object_not_found, is_err_version_not_found};
 use crate::new_object_layer_fn;
 use crate::rpc::RemotePeerS3Client;
 use crate::store;
@@ -37,18 +39,29 @@ use chrono::Utc;
 use futures::StreamExt;
 use futures::stream::FuturesUnordered;
 use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
 use http::Method;
 use lazy_static::lazy_static;
 // use std::time::SystemTime;
 use once_cell::sync::Lazy;
-use regex::Regex;
+use rustfs_common::globals::GLOBAL_Local_Node_Name;
+use rustfs_filemeta::headers::{RUSTFS_SOURCE_DELETE_MARKER, RUSTFS_SOURCE_MTIME, RUSTFS_SOURCE_REPLICATION_REQUEST};
+use rustfs_retry::{Backoff, RetryPolicy};
 use rustfs_rsc::Minio;
+use rustfs_rsc::client::KeyArgs;
 use rustfs_rsc::provider::StaticProvider;
+use rustfs_rsc::time::UtcTime;
+use rustfs_utils::path::SLASH_SEPARATOR;
+use rustfs_utils::retry::is_s3code_retryable;
 use s3s::dto::DeleteMarkerReplicationStatus;
 use s3s::dto::DeleteReplicationStatus;
 use s3s::dto::ExistingObjectReplicationStatus;
+use s3s::dto::ObjectLockLegalHoldStatus;
+use s3s::dto::ObjectLockRetentionMode;
 use s3s::dto::ReplicaModificationsStatus;
 use s3s::dto::ReplicationRuleStatus;
+use s3s::header::X_AMZ_BYPASS_GOVERNANCE_RETENTION;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
 use std::collections::HashMap;
@@ -57,10 +70,13 @@ use std::fmt;
 use std::iter::Iterator;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::OnceLock;
 use std::sync::atomic::AtomicI32;
 use std::sync::atomic::Ordering;
 use std::vec;
 use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 use tokio::sync::mpsc::{Receiver, Sender};
@@ -71,22 +87,41 @@ use xxhash_rust::xxh3::xxh3_64;
 // use bucket_targets::{self, GLOBAL_Bucket_Target_Sys};
 use crate::bucket::lifecycle::bucket_lifecycle_ops::TransitionedObject;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct MRFReplicateEntry {
-    #[serde(rename = "bucket")]
-    bucket: String,
-
-    #[serde(rename = "object")]
-    object: String,
-
-    #[serde(skip_serializing, skip_deserializing)]
-    version_id: String,
-
-    #[serde(rename = "retryCount")]
-    retry_count: i32,
-
-    #[serde(skip_serializing, skip_deserializing)]
-    sz: i64,
+/// A replication given up on, kept in the failed backlog until the heal pass queues it again
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum MRFReplicateEntry {
+    /// A version, healed from its replication status once it is read again
+    Object {
+        bucket: String,
+        object: String,
+        #[serde(rename = "versionId")]
+        version_id: String,
+    },
+    /// A delete, replicated again as it was
+    Delete(DeletedObjectReplicationInfo),
+}
+
+impl MRFReplicateEntry {
+    fn key(&self) -> String {
+        match self {
+            Self::Object {
+                bucket,
+                object,
+                version_id,
+            } => format!("{bucket}/{object}/{version_id}"),
+            Self::Delete(dv) => format!(
+                "{}/{}/{}/delete",
+                dv.bucket,
+                dv.deleted_object.object_name.as_deref().unwrap_or_default(),
+                dv.deleted_object
+                    .version_id
+                    .as_deref()
+                    .or(dv.deleted_object.delete_marker_version_id.as_deref())
+                    .unwrap_or_default()
+            ),
+        }
+    }
 }
 
 trait ReplicationWorkerOperation: Any + Send + Sync {
@@ -94,6 +129,30 @@ trait ReplicationWorkerOperation: Any + Send + Sync {
     fn as_any(&self) -> &dyn Any;
 }
 
+/// Times a failed replication is retried from the failed queue before it goes to the failed backlog
+pub const MRF_MAX_RETRY_COUNT: u32 = 3;
+
+/// Wait before a retry from the failed queue, multiplied by the number of the retry
+const MRF_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Replications waiting in the failed queue at most, the ones it drops go to the failed backlog
+const MRF_QUEUE_SIZE: usize = 100_000;
+
+/// Replications kept in the failed backlog at most
+const MRF_BACKLOG_SIZE: usize = 100_000;
+
+/// How often the failed backlog is healed and saved
+const MRF_HEAL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+const MRF_BACKLOG_DIR: &str = ".replication";
+
+/// Replications dropped by a full queue or failed on every retry, by [`MRFReplicateEntry::key`].
+/// Each node saves its own under the meta bucket, so that they outlive a restart
+static MRF_BACKLOG: LazyLock<std::sync::Mutex<HashMap<String, MRFReplicateEntry>>> = LazyLock::new(Default::default);
+
+/// Sender of the failed queue, for replications to requeue themselves without the pool lock
+static GLOBAL_MRF_SENDER: OnceLock<Sender<Box<dyn ReplicationWorkerOperation>>> = OnceLock::new();
+
 // WorkerMaxLimit max number of workers per node for "fast" mode
 pub const WORKER_MAX_LIMIT: usize = 50;
 
@@ -145,7 +204,7 @@ pub struct ReplicationPool {
     // MRF
     //mrf_worker_kill_ch: Option<Sender<()>>,
     mrf_replica_ch_sender: Sender<Box<dyn ReplicationWorkerOperation>>,
-    mrf_replica_ch_receiver: Receiver<Box<dyn ReplicationWorkerOperation>>,
+    mrf_replica_ch_receiver: Arc<Mutex<Receiver<Box<dyn ReplicationWorkerOperation>>>>,
     //mrf_save_ch: Sender<MRFReplicateEntry>,
     //mrf_stop_ch: Sender<()>,
     mrf_worker_size: usize,
@@ -192,6 +251,7 @@ impl ReplicationType {
             ReplicationType::ObjectReplicationType
                 | ReplicationType::HealReplicationType
                 | ReplicationType::ExistingObjectReplicationType
+                | ReplicationType::ResyncReplicationType
         )
     }
 }
@@ -200,6 +260,29 @@ const SYSTEM_XML_OBJECT: &str = ".system-d26a9498-cb7c-4a87-a44a-8ae204f5ba6c/sy
 const CAPACITY_XML_OBJECT: &str = ".system-d26a9498-cb7c-4a87-a44a-8ae204f5ba6c/capacity.xml";
 const VEEAM_AGENT_SUBSTR: &str = "APN/1.0 Veeam/1.0";
 
+/// Retry budget shared by the replication to every target
+const REPLICATION_RETRY_SUBSYSTEM: &str = "replication";
+const REPLICATION_MAX_ATTEMPTS: u32 = 3;
+const REPLICATION_BACKOFF: Backoff = Backoff::new(std::time::Duration::from_millis(500), std::time::Duration::from_secs(5));
+
+/// Retries of the requests to the replication target `arn`, which stop while the target keeps failing
+fn replication_retry_policy(arn: &str) -> RetryPolicy {
+    RetryPolicy::new(REPLICATION_MAX_ATTEMPTS, REPLICATION_BACKOFF.with_jitter(0.5))
+        .with_budget(rustfs_retry::budget(REPLICATION_RETRY_SUBSYSTEM))
+        .with_breaker(rustfs_retry::breaker(&format!("{REPLICATION_RETRY_SUBSYSTEM}:{arn}")))
+}
+
+/// Whether a failed request to a replication target may succeed when retried
+fn is_replication_retryable(err: &rustfs_rsc::error::Error) -> bool {
+    match err {
+        rustfs_rsc::error::Error::RequestError(_)
+        | rustfs_rsc::error::Error::HttpError(_)
+        | rustfs_rsc::error::Error::IoError(_) => true,
+        rustfs_rsc::error::Error::S3Error(e) => is_s3code_retryable(&e.code),
+        _ => false,
+    }
+}
+
 fn is_veeam_sos_api_object(object: &str) -> bool {
     matches!(object, SYSTEM_XML_OBJECT | CAPACITY_XML_OBJECT)
 }
@@ -255,23 +338,24 @@ pub async fn queue_replication_heal(
             event_type: "".to_string(),
             reset_id: "".to_string(),
             target_arn: "".to_string(),
+            retry_count: 0,
         };
 
         if matches!(roi.replication_status, ReplicationStatusType::Pending | ReplicationStatusType::Failed)
             || matches!(roi.version_purge_status, VersionPurgeStatusType::Failed | VersionPurgeStatusType::Pending)
         {
-            let mut pool = GLOBAL_REPLICATION_POOL.write().await;
-            pool.as_mut().unwrap().queue_replica_task(roi).await;
-            //GLOBAL_REPLICATION_POOL().queue_replica_delete_task(dv);
+            if let Some(pool) = GLOBAL_REPLICATION_POOL.read().await.as_ref() {
+                pool.queue_replica_delete_task(dv);
+            }
             return None;
         }
 
         if roi.existing_obj_resync.must_resync()
             && (roi.replication_status == ReplicationStatusType::Completed || roi.replication_status.is_empty())
         {
-            //queue_replicate_deletes_wrapper(dv, &roi.existing_obj_resync);
-            let mut pool = GLOBAL_REPLICATION_POOL.write().await;
-            pool.as_mut().unwrap().queue_replica_task(roi).await;
+            if let Some(pool) = GLOBAL_REPLICATION_POOL.read().await.as_ref() {
+                pool.queue_replica_delete_task(dv);
+            }
             return None;
         }
 
@@ -318,14 +402,13 @@ pub async fn check_replicate_delete(
     del_opts: &ObjectOptions,
     gerr: Option<&Error>,
 ) -> ReplicateDecision {
-    error!("check_replicate_delete");
     let mut dsc = ReplicateDecision::default();
 
     let rcfg = match get_replication_config(bucket).await {
         Ok((cfg, mod_time)) => cfg,
         Err(e) => {
             //repl_log_once_if(ctx, None, bucket); // 你需要实现这个日志函数
-            error!("get replication config err:");
+            debug!("get replication config err: {}", e);
             return dsc;
         }
     };
@@ -343,8 +426,7 @@ pub async fn check_replicate_delete(
         ssec: false,
         user_tags: Some(oi.user_tags.clone()),
         delete_marker: oi.delete_marker,
-        //version_id: dobj.version_id.clone().map(|v| v.to_string()),
-        version_id: oi.version_id.map(|uuid| uuid.to_string()).unwrap_or_default(),
+        version_id: dobj.version_id.map(|uuid| uuid.to_string()).unwrap_or_default(),
         op_type: ReplicationType::DeleteReplicationType,
         target_arn: None,
         replica: true,
@@ -449,9 +531,10 @@ pub async fn get_heal_replicate_object_info(
     let dsc = if oi.delete_marker {
         check_replicate_delete(
             &oi.bucket,
+            // a delete marker is replicated as the delete that created it
             &ObjectToDelete {
                 object_name: oi.name.clone(),
-                version_id: oi.version_id,
+                version_id: None,
             },
             oi,
             &ObjectOptions {
@@ -629,7 +712,8 @@ impl ReplicationPool {
         let mut max_workers = WORKER_MAX_LIMIT;
         warn!("init_bucket_replication_pool {} {} {} {}", workers, failed_workers, priority, max_workers);
 
-        let (sender, receiver) = mpsc::channel::<Box<dyn ReplicationWorkerOperation>>(10);
+        let (sender, receiver) = mpsc::channel::<Box<dyn ReplicationWorkerOperation>>(MRF_QUEUE_SIZE);
+        let _ = GLOBAL_MRF_SENDER.set(sender.clone());
 
         // Self {
         //     mrf_replica_ch_sender: sender,
@@ -671,7 +755,6 @@ impl ReplicationPool {
         };
 
         // 初始化通道
-        let (mrf_replica_tx, _) = mpsc::channel::<u32>(100_000);
         let (mrf_worker_kill_tx, _) = mpsc::channel::<u32>(failed_workers);
         let (mrf_save_tx, _) = mpsc::channel::<u32>(100_000);
         let (mrf_stop_tx, _) = mpsc::channel::<u32>(1);
@@ -687,8 +770,8 @@ impl ReplicationPool {
             max_lworkers: max_l_workers,
             //mrf_worker_kill_ch: None,
             mrf_replica_ch_sender: sender,
-            mrf_replica_ch_receiver: receiver,
-            mrf_worker_size: workers,
+            mrf_replica_ch_receiver: Arc::new(Mutex::new(receiver)),
+            mrf_worker_size: 0,
             priority,
             max_workers,
             obj_layer,
@@ -702,18 +785,11 @@ impl ReplicationPool {
 
         // 启动后台任务
         let resyncer = Arc::new(RwLock::new(ReplicationResyncer::new()));
-        let x = Arc::new(RwLock::new(&pool));
         // tokio::spawn(async move {
         //     resyncer.lock().await.persist_to_disk(ctx_clone, obj_layer_clone).await;
         // });
 
-        tokio::spawn(async move {
-            //pool4.process_mrf().await
-        });
-        let pool5 = Arc::clone(&x);
-        tokio::spawn(async move {
-            //pool5.persist_mrf().await
-        });
+        tokio::spawn(heal_mrf_backlog(obj_layer_clone));
 
         let mut global_pool = GLOBAL_REPLICATION_POOL.write().await;
         global_pool.replace(pool);
@@ -819,8 +895,34 @@ impl ReplicationPool {
         // warn!("self sender size is {:?}", self.workers_sender.len());
     }
 
-    async fn resize_failed_workers(&self, _count: usize) {
-        // 实现失败 worker 的初始化逻辑
+    async fn resize_failed_workers(&mut self, n: usize) {
+        // The failed workers share the queue, they only stop with the pool
+        while self.mrf_worker_size < n {
+            let receiver = Arc::clone(&self.mrf_replica_ch_receiver);
+            let active_workers = Arc::clone(&self.active_mrf_workers);
+            let layer = Arc::clone(&self.obj_layer);
+            tokio::spawn(async move {
+                loop {
+                    let Some(operation) = receiver.lock().await.recv().await else {
+                        break;
+                    };
+                    active_workers.fetch_add(1, Ordering::SeqCst);
+
+                    if let Some(info) = operation.as_any().downcast_ref::<ReplicateObjectInfo>() {
+                        tokio::time::sleep(MRF_RETRY_DELAY * info.retry_count).await;
+                        replicate_object(info.clone(), Arc::clone(&layer)).await;
+                    } else if let Some(info) = operation.as_any().downcast_ref::<DeletedObjectReplicationInfo>() {
+                        tokio::time::sleep(MRF_RETRY_DELAY * info.retry_count).await;
+                        replicate_delete(info, Arc::clone(&layer)).await;
+                    } else {
+                        error!("unknown replication type");
+                    }
+
+                    active_workers.fetch_sub(1, Ordering::SeqCst);
+                }
+            });
+            self.mrf_worker_size += 1;
+        }
     }
 
     // async fn process_mrf(&self) {
@@ -846,6 +948,24 @@ impl ReplicationPool {
         Some(&workers[index]) // 返回对应的 Sender
     }
 
+    fn queue_replica_delete_task(&self, doi: DeletedObjectReplicationInfo) {
+        let name = doi.deleted_object.object_name.clone().unwrap_or_default();
+        let ch = if doi.op_type == ReplicationType::HealReplicationType {
+            Some(&self.mrf_replica_ch_sender)
+        } else {
+            self.get_worker_ch(&doi.bucket, &name, 0)
+        };
+        let Some(ch) = ch else {
+            error!("no replication worker for the delete of {}/{}", doi.bucket, name);
+            record_mrf(MRFReplicateEntry::Delete(doi));
+            return;
+        };
+        if ch.try_send(Box::new(doi.clone())).is_err() {
+            warn!("replication queue full, keeping the delete of {} in the failed backlog", name);
+            record_mrf(MRFReplicateEntry::Delete(doi));
+        }
+    }
+
     async fn queue_replica_task(&mut self, ri: ReplicateObjectInfo) {
         if ri.size >= MIN_LARGE_OBJSIZE as i64 {
             let h = xxh3_64(format!("{}{}", ri.bucket, ri.name).as_bytes());
@@ -859,9 +979,8 @@ impl ReplicationPool {
                 match sender.try_send(Box::new(ri.clone())) {
                     Ok(_) => return,
                     Err(_) => {
-                        // 任务队列满了，执行 MRF 处理
-                        //println!("Queue full, saving to MRF: {}", ri.to_mrf_entry());
-                        println!("Queue full, saving to MRF");
+                        warn!("replication queue full, keeping {}/{} in the failed backlog", ri.bucket, ri.name);
+                        record_mrf(ri.to_mrf_entry());
                     }
                 }
             }
@@ -892,6 +1011,7 @@ impl ReplicationPool {
 
         if ch.is_none() && heal_ch.is_none() {
             error!("replicste chan empty");
+            record_mrf(ri.to_mrf_entry());
             return;
         }
 
@@ -917,8 +1037,7 @@ impl ReplicationPool {
         }
 
         if !sent {
-            //todo!
-            //self.queue_mrf_save(ri).await;
+            record_mrf(ri.to_mrf_entry());
             let max_workers = self.max_workers;
 
             match self.priority.as_str() {
@@ -1021,7 +1140,7 @@ impl ReplicationStatusType {
 
     // Checks if the status is empty (not set)
     pub fn is_empty(&self) -> bool {
-        matches!(self, ReplicationStatusType::Pending) // Adjust logic if needed
+        matches!(self, ReplicationStatusType::Unknown)
     }
 
     // 从字符串构造 ReplicationStatusType 枚举
@@ -1426,6 +1545,30 @@ pub struct DeletedObjectReplicationInfo {
     pub op_type: ReplicationType, // 假设 `replication.Type` 是 `ReplicationType` 枚举
     pub reset_id: String,
     pub target_arn: String,
+    #[serde(default)]
+    pub retry_count: u32,
+}
+
+impl DeletedObjectReplicationInfo {
+    /// Replication of a delete the object layer has done
+    pub fn new(bucket: &str, dobj: &crate::store_api::DeletedObject) -> Self {
+        Self {
+            deleted_object: DeletedObject {
+                delete_marker: Some(dobj.delete_marker),
+                delete_marker_version_id: dobj.delete_marker_version_id.clone(),
+                object_name: Some(dobj.object_name.clone()),
+                version_id: dobj.version_id.clone(),
+                delete_marker_mtime: convert_offsetdatetime_to_chrono(dobj.delete_marker_mtime).unwrap_or_else(Utc::now),
+                replication_state: ReplicationState::default(),
+            },
+            bucket: bucket.to_string(),
+            event_type: String::new(),
+            op_type: ReplicationType::DeleteReplicationType,
+            reset_id: String::new(),
+            target_arn: String::new(),
+            retry_count: 0,
+        }
+    }
 }
 
 pub fn get_composite_replication_status(m: &HashMap<String, ReplicationStatusType>) -> ReplicationStatusType {
@@ -1556,7 +1699,7 @@ impl ConfigProcess for s3s::dto::ReplicationConfiguration {
                         return false;
                     }
                     rule.delete_replication.unwrap().status
-                        == DeleteReplicationStatus::from_static(DeleteReplicationStatus::DISABLED)
+                        == DeleteReplicationStatus::from_static(DeleteReplicationStatus::ENABLED)
                 } else {
                     if rule.delete_marker_replication.is_none() {
                         warn!("need replicate failed");
@@ -1567,7 +1710,7 @@ impl ConfigProcess for s3s::dto::ReplicationConfiguration {
                         return false;
                     }
                     rule.delete_marker_replication.as_ref().unwrap().status.clone().unwrap()
-                        == DeleteMarkerReplicationStatus::from_static(DeleteMarkerReplicationStatus::DISABLED)
+                        == DeleteMarkerReplicationStatus::from_static(DeleteMarkerReplicationStatus::ENABLED)
                 };
             }
             // 处理常规对象/元数据复制
@@ -1647,32 +1790,45 @@ impl ConfigProcess for s3s::dto::ReplicationConfiguration {
     }
 }
 
-fn replication_statuses_map(s: &str) -> HashMap<String, ReplicationStatusType> {
-    let mut targets = HashMap::new();
-    let repl_status_regex = Regex::new(r"(\w+):([\w-]+)").unwrap();
-
-    for cap in repl_status_regex.captures_iter(s) {
-        if let (Some(target), Some(status)) = (cap.get(1), cap.get(2)) {
-            let tp = ReplicationStatusType::from(status.as_str());
-            targets.insert(target.as_str().to_string(), tp);
-        }
-    }
+/// Target ARNs and statuses of an `arn=STATUS;` list, the format of the replication statuses of objects
+fn target_statuses(s: &str) -> impl Iterator<Item = (&str, &str)> {
+    s.split(';')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(arn, status)| (arn.trim(), status.trim()))
+        .filter(|(arn, _)| !arn.is_empty())
+}
 
-    targets
+pub(crate) fn replication_statuses_map(s: &str) -> HashMap<String, ReplicationStatusType> {
+    target_statuses(s)
+        .map(|(arn, status)| (arn.to_string(), ReplicationStatusType::from(status)))
+        .collect()
 }
 
 fn version_purge_statuses_map(s: &str) -> HashMap<String, VersionPurgeStatusType> {
-    let mut targets = HashMap::new();
-    let repl_status_regex = Regex::new(r"(\w+):([\w-]+)").unwrap();
-
-    for cap in repl_status_regex.captures_iter(s) {
-        if let (Some(target), Some(status)) = (cap.get(1), cap.get(2)) {
-            let ptp = VersionPurgeStatusType::from(status.as_str());
-            targets.insert(target.as_str().to_string(), ptp);
-        }
-    }
-
-    targets
+    target_statuses(s)
+        .map(|(arn, status)| (arn.to_string(), VersionPurgeStatusType::from(status)))
+        .collect()
+}
+
+/// Replication statuses of an object version from its metadata, per target and as S3 shows it
+///
+/// Replicas are `REPLICA`, sources get the composite status of their targets and versions
+/// that are not replicated `Unknown`.
+pub fn replication_status_from_metadata(metadata: &HashMap<String, String>) -> (String, ReplicationStatusType) {
+    let internal = metadata
+        .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}{REPLICATION_STATUS}"))
+        .cloned()
+        .unwrap_or_default();
+    let replica = metadata
+        .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}{REPLICA_STATUS}"))
+        .is_some_and(|v| ReplicationStatusType::from(v) == ReplicationStatusType::Replica);
+
+    let status = if replica {
+        ReplicationStatusType::Replica
+    } else {
+        get_composite_replication_status(&replication_statuses_map(&internal))
+    };
+    (internal, status)
 }
 
 pub trait TraitForObjectInfo {
@@ -1696,6 +1852,18 @@ pub const OBJECT_LOCK_RETENTION_TIMESTAMP: &str = "objectlock-retention-timestam
 pub const OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP: &str = "objectlock-legalhold-timestamp";
 pub const REPLICATION_SSEC_CHECKSUM_HEADER: &str = "X-Rustfs-Replication-Ssec-Crc";
 
+/// Records in the reserved metadata when the tags, retention or legal hold of a version were set, the
+/// change made last winning when two sites replicate to each other
+pub fn set_metadata_timestamp(meta: &mut HashMap<String, String>, name: &str, at: OffsetDateTime) {
+    meta.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}{name}"), at.format(&Rfc3339).unwrap_or_default());
+}
+
+/// When the tags, retention or legal hold of a version were set, see [`set_metadata_timestamp`]
+pub fn metadata_timestamp(meta: &HashMap<String, String>, name: &str) -> Option<OffsetDateTime> {
+    meta.get(&format!("{RESERVED_METADATA_PREFIX_LOWER}{name}"))
+        .and_then(|v| OffsetDateTime::parse(v, &Rfc3339).ok())
+}
+
 impl TraitForObjectInfo for ObjectInfo {
     fn replication_state(&self) -> ReplicationState {
         let mut rs = ReplicationState {
@@ -1731,15 +1899,30 @@ impl TraitForObjectInfo for ObjectInfo {
 }
 
 fn convert_offsetdatetime_to_chrono(offset_dt: Option<OffsetDateTime>) -> Option<DateTime<Utc>> {
-    //offset_dt.map(|odt| {
-    let tm = offset_dt.unwrap().unix_timestamp();
-    //let naive = NaiveDateTime::from_timestamp_opt(tm, 0).expect("Invalid timestamp");
-    DateTime::<Utc>::from_timestamp(tm, 0)
-    //DateTime::from_naive_utc_and_offset(naive, Utc) // Convert to Utc first
-    //})
+    offset_dt.and_then(|odt| DateTime::<Utc>::from_timestamp(odt.unix_timestamp(), odt.nanosecond()))
+}
+
+fn convert_chrono_to_offsetdatetime(dt: DateTime<Utc>) -> Option<OffsetDateTime> {
+    dt.timestamp_nanos_opt()
+        .and_then(|nanos| OffsetDateTime::from_unix_timestamp_nanos(nanos.into()).ok())
 }
 
 pub async fn schedule_replication(oi: ObjectInfo, o: Arc<store::ECStore>, dsc: ReplicateDecision, op_type: i32) {
+    let ri = replicate_object_info(oi, dsc.clone(), op_type);
+
+    if dsc.synchronous() {
+        warn!("object sync replication");
+        replicate_object(ri, o).await;
+    } else {
+        warn!("object need async replication");
+        //GLOBAL_REPLICATION_POOL.lock().unwrap().queue_replica_task(ri);
+        let mut pool = GLOBAL_REPLICATION_POOL.write().await;
+        pool.as_mut().unwrap().queue_replica_task(ri).await;
+    }
+}
+
+/// Replication task of a version
+fn replicate_object_info(oi: ObjectInfo, dsc: ReplicateDecision, op_type: i32) -> ReplicateObjectInfo {
     let tgt_statuses = replication_statuses_map(&oi.replication_status_internal);
     // //let purge_statuses = version_purge_statuses_map(&oi.);
     let replication_timestamp = Utc::now(); // Placeholder for timestamp parsing
@@ -1749,7 +1932,7 @@ pub async fn schedule_replication(oi: ObjectInfo, o: Arc<store::ECStore>, dsc: R
     //let ssec = oi.user_defined.contains_key("ssec");
     let ssec = false;
 
-    let ri = ReplicateObjectInfo {
+    ReplicateObjectInfo {
         name: oi.name,
         size: oi.size,
         bucket: oi.bucket,
@@ -1766,7 +1949,7 @@ pub async fn schedule_replication(oi: ObjectInfo, o: Arc<store::ECStore>, dsc: R
         version_purge_status: oi.version_purge_status,
         replication_state,
         op_type,
-        dsc: dsc.clone(),
+        dsc,
         target_statuses: tgt_statuses,
         target_purge_statuses: Default::default(),
         replication_timestamp,
@@ -1779,16 +1962,74 @@ pub async fn schedule_replication(oi: ObjectInfo, o: Arc<store::ECStore>, dsc: R
         existing_obj_resync: Default::default(),
         target_arn: "".to_string(),
         actual_size: 0,
-    };
+    }
+}
 
-    if dsc.synchronous() {
-        warn!("object sync replication");
-        replicate_object(ri, o).await;
-    } else {
-        warn!("object need async replication");
-        //GLOBAL_REPLICATION_POOL.lock().unwrap().queue_replica_task(ri);
-        let mut pool = GLOBAL_REPLICATION_POOL.write().await;
-        pool.as_mut().unwrap().queue_replica_task(ri).await;
+/// Versions listed per page while resyncing a target
+const RESYNC_PAGE_SIZE: i32 = 1000;
+
+/// Replicates every version and delete marker of `bucket` to the target `arn` again, healing a site
+/// that missed changes while it was down, and returns how many were sent
+///
+/// The target keeps the versions it already holds, their modification time being no newer than its own.
+pub async fn resync_bucket_target(bucket: &str, arn: &str, store: Arc<store::ECStore>) -> Result<usize, Error> {
+    let (cfg, _) = get_replication_config(bucket).await?;
+
+    let mut marker = None;
+    let mut version_marker = None;
+    let mut count = 0;
+    loop {
+        let page = store
+            .clone()
+            .list_object_versions(bucket, "", marker, version_marker, None, RESYNC_PAGE_SIZE)
+            .await?;
+
+        for oi in page.objects {
+            let opts = ReplicationObjectOpts {
+                name: oi.name.clone(),
+                user_tags: Some(oi.user_tags.clone()),
+                version_id: oi.version_id.map(|v| v.to_string()).unwrap_or_default(),
+                delete_marker: oi.delete_marker,
+                ssec: false,
+                op_type: if oi.delete_marker {
+                    ReplicationType::DeleteReplicationType
+                } else {
+                    ReplicationType::ObjectReplicationType
+                },
+                replica: oi.replication_status == ReplicationStatusType::Replica,
+                existing_object: false,
+                target_arn: Some(arn.to_string()),
+            };
+            if !cfg.replicate(&opts) {
+                continue;
+            }
+
+            if oi.delete_marker {
+                let deleted = crate::store_api::DeletedObject {
+                    delete_marker: true,
+                    delete_marker_version_id: oi.version_id.map(|v| v.to_string()),
+                    object_name: oi.name.clone(),
+                    version_id: None,
+                    delete_marker_mtime: oi.mod_time,
+                };
+                let mut dv = DeletedObjectReplicationInfo::new(bucket, &deleted);
+                dv.op_type = ReplicationType::ResyncReplicationType;
+                dv.target_arn = arn.to_string();
+                replicate_delete(&dv, store.clone()).await;
+            } else {
+                let mut ri =
+                    replicate_object_info(oi, ReplicateDecision::default(), ReplicationType::ResyncReplicationType as i32);
+                ri.target_arn = arn.to_string();
+                replicate_object(ri, store.clone()).await;
+            }
+            count += 1;
+        }
+
+        if !page.is_truncated {
+            return Ok(count);
+        }
+        marker = page.next_marker;
+        version_marker = page.next_version_idmarker;
     }
 }
 
@@ -1934,9 +2175,6 @@ impl ReplicationState {
     }
 }
 
-lazy_static! {
-    static ref REPL_STATUS_REGEX: Regex = Regex::new(r"([^=].*?)=([^,].*?);").unwrap();
-}
 pub trait ObjectInfoExt {
     fn target_replication_status(&self, arn: String) -> ReplicationStatusType;
     fn is_multipart(&self) -> bool;
@@ -1944,18 +2182,10 @@ pub trait ObjectInfoExt {
 
 impl ObjectInfoExt for ObjectInfo {
     fn target_replication_status(&self, arn: String) -> ReplicationStatusType {
-        let rep_stat_matches = REPL_STATUS_REGEX.captures_iter(&self.replication_status_internal);
-        for matched in rep_stat_matches {
-            if let Some(arn_match) = matched.get(1) {
-                if arn_match.as_str() == arn {
-                    if let Some(status_match) = matched.get(2) {
-                        return ReplicationStatusType::from(status_match.as_str());
-                    }
-                }
-            }
-        }
-        /* `ReplicationStatusType` value */
-        ReplicationStatusType::Unknown
+        target_statuses(&self.replication_status_internal)
+            .find(|(target, _)| *target == arn)
+            .map(|(_, status)| ReplicationStatusType::from(status))
+            .unwrap_or(ReplicationStatusType::Unknown)
     }
     fn is_multipart(&self) -> bool {
         match &self.etag {
@@ -2067,12 +2297,10 @@ pub struct DeleteMarkerMTime {
 
 impl ReplicationWorkerOperation for ReplicateObjectInfo {
     fn to_mrf_entry(&self) -> MRFReplicateEntry {
-        MRFReplicateEntry {
+        MRFReplicateEntry::Object {
             bucket: self.bucket.clone(),
             object: self.name.clone(),
-            version_id: self.version_id.clone(), // 直接使用计算后的 version_id
-            retry_count: 0,
-            sz: self.size,
+            version_id: self.version_id.clone(),
         }
     }
     fn as_any(&self) -> &dyn Any {
@@ -2082,13 +2310,7 @@ impl ReplicationWorkerOperation for ReplicateObjectInfo {
 
 impl ReplicationWorkerOperation for DeletedObjectReplicationInfo {
     fn to_mrf_entry(&self) -> MRFReplicateEntry {
-        MRFReplicateEntry {
-            bucket: self.bucket.clone(),
-            object: self.deleted_object.object_name.clone().unwrap().clone(),
-            version_id: self.deleted_object.delete_marker_version_id.clone().unwrap_or_default(),
-            retry_count: 0,
-            sz: 0,
-        }
+        MRFReplicateEntry::Delete(self.clone())
     }
     fn as_any(&self) -> &dyn Any {
         self
@@ -2111,6 +2333,202 @@ pub fn get_s3client_from_para(ak: &str, sk: &str, url: &str, _region: &str) -> R
 // use hyper::body::Body;
 // use s3s::Body;
 
+/// Standard headers kept in the metadata of objects, written again on their replicas
+const REPLICATED_STANDARD_HEADERS: [&str; 10] = [
+    "content-type",
+    "cache-control",
+    "content-language",
+    "content-encoding",
+    "content-disposition",
+    "expires",
+    "x-amz-storage-class",
+    "x-amz-object-lock-mode",
+    "x-amz-object-lock-retain-until-date",
+    "x-amz-object-lock-legal-hold",
+];
+
+/// Headers of the requests writing a replica: the replication marker and modification time, then
+/// the standard headers, user metadata and tags of the source version
+fn replication_put_headers(oi: &ObjectInfo) -> HeaderMap {
+    let mut headers = replication_mtime_headers(oi.mod_time);
+    for (k, v) in &oi.user_defined {
+        let key = k.to_lowercase();
+        let name = if REPLICATED_STANDARD_HEADERS.contains(&key.as_str()) {
+            key
+        } else if key.starts_with("x-amz-") || key.starts_with("x-rustfs-") || key == "etag" {
+            continue;
+        } else {
+            format!("x-amz-meta-{key}")
+        };
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(v)) {
+            headers.insert(name, value);
+        }
+    }
+    if let Ok(tags) = HeaderValue::from_str(&oi.user_tags) {
+        if !oi.user_tags.is_empty() {
+            headers.insert(HeaderName::from_static("x-amz-tagging"), tags);
+        }
+    }
+    headers
+}
+
+/// Headers marking a request as sent by a replication source
+fn replication_request_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(name) = HeaderName::from_bytes(RUSTFS_SOURCE_REPLICATION_REQUEST.as_bytes()) {
+        headers.insert(name, HeaderValue::from_static("true"));
+    }
+    headers
+}
+
+/// Headers of a replication request carrying the time of the change it replicates, which decides
+/// between the changes of two sites
+fn replication_mtime_headers(mtime: Option<OffsetDateTime>) -> HeaderMap {
+    let mut headers = replication_request_headers();
+    let mtime = mtime.and_then(|mtime| mtime.format(&Rfc3339).ok());
+    if let (Some(mtime), Ok(name)) = (mtime, HeaderName::from_bytes(RUSTFS_SOURCE_MTIME.as_bytes())) {
+        if let Ok(value) = HeaderValue::from_str(&mtime) {
+            headers.insert(name, value);
+        }
+    }
+    headers
+}
+
+/// Queues a replication that failed for a retry by the failed workers, once the queue is full
+/// it goes to the failed backlog
+fn queue_mrf(operation: Box<dyn ReplicationWorkerOperation>) {
+    let Some(sender) = GLOBAL_MRF_SENDER.get() else {
+        record_mrf(operation.to_mrf_entry());
+        return;
+    };
+    if let Err(err) = sender.try_send(operation) {
+        warn!("replication failed queue full, keeping the replication in the failed backlog");
+        let operation = match err {
+            mpsc::error::TrySendError::Full(operation) | mpsc::error::TrySendError::Closed(operation) => operation,
+        };
+        record_mrf(operation.to_mrf_entry());
+    }
+}
+
+/// Keeps a replication given up on in the failed backlog, for the heal pass to queue it again
+fn record_mrf(entry: MRFReplicateEntry) {
+    let mut backlog = MRF_BACKLOG.lock().unwrap();
+    if backlog.len() >= MRF_BACKLOG_SIZE {
+        error!("replication failed backlog full, dropping {}", entry.key());
+        return;
+    }
+    backlog.insert(entry.key(), entry);
+}
+
+fn mrf_backlog_path(node: &str) -> String {
+    // Node names are host:port, keep them a single path element
+    let node = node.replace([':', '/'], "_");
+    format!(
+        "{}{}{}{}mrf-{}.json",
+        crate::disk::BUCKET_META_PREFIX,
+        SLASH_SEPARATOR,
+        MRF_BACKLOG_DIR,
+        SLASH_SEPARATOR,
+        node
+    )
+}
+
+async fn save_mrf_backlog(store: Arc<store::ECStore>) -> Result<(), Error> {
+    let node = GLOBAL_Local_Node_Name.read().await.clone();
+    let entries: Vec<_> = MRF_BACKLOG.lock().unwrap().values().cloned().collect();
+    let data = serde_json::to_vec(&entries).map_err(|e| Error::other(format!("serialize replication backlog: {e}")))?;
+    save_config(store, &mrf_backlog_path(&node), data).await
+}
+
+async fn load_mrf_backlog(store: Arc<store::ECStore>) -> Result<(), Error> {
+    let node = GLOBAL_Local_Node_Name.read().await.clone();
+    let data = match read_config(store, &mrf_backlog_path(&node)).await {
+        Ok(data) => data,
+        Err(Error::ConfigNotFound) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let entries: Vec<MRFReplicateEntry> =
+        serde_json::from_slice(&data).map_err(|e| Error::other(format!("deserialize replication backlog: {e}")))?;
+    for entry in entries {
+        record_mrf(entry);
+    }
+    Ok(())
+}
+
+/// Queues a replication of the failed backlog again. Versions are read again and healed while
+/// their replication is pending or failed, deletes are replicated again as they were
+async fn heal_mrf_entry(store: Arc<store::ECStore>, entry: MRFReplicateEntry) {
+    match entry {
+        MRFReplicateEntry::Object {
+            bucket,
+            object,
+            version_id,
+        } => {
+            let opts = ObjectOptions {
+                version_id: Some(version_id.clone()).filter(|v| !v.is_empty()),
+                versioned: BucketVersioningSys::prefix_enabled(&bucket, &object).await,
+                ..Default::default()
+            };
+            let oi = match store.get_object_info(&bucket, &object, &opts).await {
+                Ok(oi) => oi,
+                Err(err) if is_err_object_not_found(&err) || is_err_version_not_found(&err) => return,
+                Err(err) => {
+                    warn!("heal replication of {}/{} failed: {}", bucket, object, err);
+                    record_mrf(MRFReplicateEntry::Object {
+                        bucket,
+                        object,
+                        version_id,
+                    });
+                    return;
+                }
+            };
+            if !matches!(oi.replication_status, ReplicationStatusType::Pending | ReplicationStatusType::Failed) {
+                return;
+            }
+            match get_replication_config(&bucket).await {
+                Ok((cfg, _)) => {
+                    queue_replication_heal(&bucket, &oi, &cfg, 0).await;
+                }
+                Err(err) => warn!("heal replication of {}/{}: no replication config: {}", bucket, object, err),
+            }
+        }
+        MRFReplicateEntry::Delete(mut dv) => {
+            dv.op_type = ReplicationType::HealReplicationType;
+            dv.retry_count = 0;
+            match GLOBAL_REPLICATION_POOL.read().await.as_ref() {
+                Some(pool) => pool.queue_replica_delete_task(dv),
+                None => record_mrf(MRFReplicateEntry::Delete(dv)),
+            }
+        }
+    }
+}
+
+/// Restores the failed backlog of this node, then periodically queues it again and saves what is
+/// left of it
+async fn heal_mrf_backlog(store: Arc<store::ECStore>) {
+    if let Err(err) = load_mrf_backlog(store.clone()).await {
+        error!("load replication failed backlog: {}", err);
+    }
+
+    let mut interval = tokio::time::interval(MRF_HEAL_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+
+        let entries: Vec<_> = MRF_BACKLOG.lock().unwrap().drain().map(|(_, entry)| entry).collect();
+        if !entries.is_empty() {
+            info!("healing {} replications of the failed backlog", entries.len());
+        }
+        for entry in entries {
+            heal_mrf_entry(store.clone(), entry).await;
+        }
+
+        if let Err(err) = save_mrf_backlog(store.clone()).await {
+            error!("save replication failed backlog: {}", err);
+        }
+    }
+}
+
 async fn replicate_object_with_multipart(
     rep_obj: &ReplicateObjectInfo,
     local_obj_info: &ObjectInfo,
@@ -2122,12 +2540,13 @@ async fn replicate_object_with_multipart(
     let rustfs_cli = Minio::builder()
         .endpoint(target_info.endpoint.clone())
         .provider(provider)
-        .secure(false)
+        .secure(tgt_cli.secure)
         .build()
         .map_err(|e| Error::other(format!("build rustfs client failed: {e}")))?;
 
+    let key = KeyArgs::from(local_obj_info.name.clone()).extra_headers(Some(replication_put_headers(local_obj_info)));
     let ret = rustfs_cli
-        .create_multipart_upload_with_versionid(tgt_cli.bucket.clone(), local_obj_info.name.clone(), rep_obj.version_id.clone())
+        .create_multipart_upload_with_versionid(tgt_cli.bucket.clone(), key, rep_obj.version_id.clone())
         .await;
     match ret {
         Ok(task) => {
@@ -2204,7 +2623,9 @@ async fn replicate_object_with_multipart(
 
             let parts: Vec<_> = part_results.into_iter().flatten().collect();
 
-            let ret = rustfs_cli.complete_multipart_upload(&task, parts, None).await;
+            let ret = rustfs_cli
+                .complete_multipart_upload(&task, parts, Some(replication_mtime_headers(local_obj_info.mod_time)))
+                .await;
             match ret {
                 Ok(res) => {
                     warn!("finish upload suc:{:?} version_id={:?}", res, local_obj_info.version_id);
@@ -2224,21 +2645,10 @@ async fn replicate_object_with_multipart(
 
 impl ReplicateObjectInfo {
     fn target_replication_status(&self, arn: &str) -> ReplicationStatusType {
-        // 定义正则表达式，匹配类似 `arn;status` 格式
-        let repl_status_regex = Regex::new(r"(\w+);(\w+)").expect("Invalid regex");
-
-        // 遍历正则表达式的匹配项
-        for caps in repl_status_regex.captures_iter(&self.replication_status_internal) {
-            if let (Some(matched_arn), Some(matched_status)) = (caps.get(1), caps.get(2)) {
-                // 如果 ARN 匹配，返回对应的状态
-                if matched_arn.as_str() == arn {
-                    return ReplicationStatusType::from(matched_status.as_str());
-                }
-            }
-        }
-
-        // 如果没有匹配到，返回默认的 `Unknown` 状态
-        ReplicationStatusType::Unknown
+        target_statuses(&self.replication_status_internal)
+            .find(|(target, _)| *target == arn)
+            .map(|(_, status)| ReplicationStatusType::from(status))
+            .unwrap_or(ReplicationStatusType::Unknown)
     }
 
     async fn replicate_object(&self, target: &TargetClient, _arn: String) -> ReplicatedTargetInfo {
@@ -2262,9 +2672,10 @@ impl ReplicateObjectInfo {
             version_purge_status: VersionPurgeStatusType::Pending,
         };
 
+        // retries from the failed queue only replicate to the targets that failed
         if self.target_replication_status(&_arn) == ReplicationStatusType::Completed
-            && !self.existing_obj_resync.is_empty()
-            && !self.existing_obj_resync.must_resync_target(&_arn)
+            && (self.retry_count > 0
+                || (!self.existing_obj_resync.is_empty() && !self.existing_obj_resync.must_resync_target(&_arn)))
         {
             warn!("replication return");
             rinfo.replication_status = ReplicationStatusType::Completed;
@@ -2350,21 +2761,28 @@ impl ReplicateObjectInfo {
                     let res = reader.read_all().await;
                     match res {
                         Ok(ret) => {
-                            let body = rustfs_rsc::Data::from(ret);
+                            let body = Bytes::from(ret);
                             let rustfs_cli = Minio::builder()
                                 .endpoint(rinfo.endpoint.clone())
                                 .provider(provider)
-                                .secure(false)
+                                .secure(target.secure)
                                 .build()
                                 .unwrap();
 
-                            let ex = rustfs_cli.executor(Method::PUT);
-                            let ret = ex
-                                .bucket_name(target.bucket.clone())
-                                .object_name(self.name.clone())
-                                .body(body)
-                                .query("versionId", get_opts.version_id.clone().unwrap())
-                                .send_ok()
+                            let ret = replication_retry_policy(&target.arn)
+                                .retry(
+                                    || {
+                                        rustfs_cli
+                                            .executor(Method::PUT)
+                                            .bucket_name(target.bucket.clone())
+                                            .object_name(self.name.clone())
+                                            .headers_merge(replication_put_headers(&object_info))
+                                            .body(rustfs_rsc::Data::from(body.clone()))
+                                            .query("versionId", get_opts.version_id.clone().unwrap())
+                                            .send_ok()
+                                    },
+                                    is_replication_retryable,
+                                )
                                 .await;
                             match ret {
                                 Ok(_res) => {
@@ -2393,6 +2811,48 @@ impl ReplicateObjectInfo {
         rinfo
     }
 
+    /// Replicates the tags, retention and legal hold of the version to a target
+    async fn replicate_metadata(&self, target: &TargetClient, arn: String) -> ReplicatedTargetInfo {
+        let mut rinfo = ReplicatedTargetInfo {
+            size: self.actual_size,
+            arn: arn.clone(),
+            prev_replication_status: self.target_replication_status(&arn),
+            replication_status: ReplicationStatusType::Failed,
+            op_type: self.op_type,
+            replication_action: ReplicationAction::ReplicateMetadata,
+            endpoint: target.endpoint.clone(),
+            secure: target.endpoint.contains("https://"),
+            resync_timestamp: Utc::now().to_string(),
+            replication_resynced: false,
+            duration: Duration::default(),
+            err: None,
+            version_purge_status: VersionPurgeStatusType::Pending,
+        };
+
+        let opts = ObjectOptions {
+            version_id: Some(self.version_id.clone()),
+            versioned: true,
+            ..Default::default()
+        };
+        let object_info = match self.get_object_info(opts).await {
+            Ok(info) => info,
+            Err(err) => {
+                error!("replicate metadata {}/{}: get object info err: {}", self.bucket, self.name, err);
+                rinfo.err = Some(err.to_string());
+                return rinfo;
+            }
+        };
+
+        match replicate_metadata_to_target(target, &object_info).await {
+            Ok(()) => rinfo.replication_status = ReplicationStatusType::Completed,
+            Err(err) => {
+                error!("replicate metadata {}/{} to {}: {}", self.bucket, self.name, arn, err);
+                rinfo.err = Some(err.to_string());
+            }
+        }
+        rinfo
+    }
+
     fn is_target_offline(&self, endpoint: &str) -> bool {
         // 模拟检查目标是否离线
         warn!("Checking if target {} is offline", endpoint);
@@ -2433,7 +2893,276 @@ impl ReplicateObjectInfo {
 // arns
 //}
 
-pub async fn replicate_delete(_ri: &DeletedObjectReplicationInfo, object_api: Arc<store::ECStore>) {}
+/// Body of a retention request removing the retention of a version
+const EMPTY_RETENTION_XML: &str = r#"<Retention xmlns="http://s3.amazonaws.com/doc/2006-03-01/"></Retention>"#;
+
+/// Sets the tags, retention and legal hold of a version on a replication target
+///
+/// Each one goes with the time it was set, the target keeping it only when it is the latest change
+/// of the two sites.
+async fn replicate_metadata_to_target(target: &TargetClient, oi: &ObjectInfo) -> Result<(), Error> {
+    let provider = StaticProvider::new(&target.ak, &target.sk, None);
+    let client = Minio::builder()
+        .endpoint(target.endpoint.clone())
+        .provider(provider)
+        .secure(target.secure)
+        .build()
+        .map_err(|e| Error::other(format!("build rustfs client failed: {e}")))?;
+    let version_id = oi.version_id.map(|v| v.to_string());
+    let headers = |timestamp: &str| replication_mtime_headers(metadata_timestamp(&oi.user_defined, timestamp).or(oi.mod_time));
+    // The target checks retention changes like any other, governance retention is shortened only when the
+    // replication user may bypass it there
+    let retention_headers = || {
+        let mut headers = headers(OBJECT_LOCK_RETENTION_TIMESTAMP);
+        headers.insert(X_AMZ_BYPASS_GOVERNANCE_RETENTION, HeaderValue::from_static("true"));
+        headers
+    };
+    let key = |timestamp: &str| {
+        KeyArgs::from(oi.name.clone())
+            .version_id(version_id.clone())
+            .extra_headers(Some(headers(timestamp)))
+    };
+    let policy = replication_retry_policy(&target.arn);
+
+    let tags: HashMap<String, String> = url::form_urlencoded::parse(oi.user_tags.as_bytes()).into_owned().collect();
+    if tags.is_empty() {
+        policy
+            .retry(
+                || client.del_object_tags(target.bucket.clone(), key(TAGGING_TIMESTAMP)),
+                is_replication_retryable,
+            )
+            .await
+    } else {
+        policy
+            .retry(
+                || client.set_object_tags(target.bucket.clone(), key(TAGGING_TIMESTAMP), tags.clone()),
+                is_replication_retryable,
+            )
+            .await
+    }
+    .map_err(|e| Error::other(format!("replicate tags: {e}")))?;
+
+    if let Some(retention) = Retention::from_meta(&oi.user_defined) {
+        let retention = rustfs_rsc::datatype::Retention {
+            mode: if retention.mode.as_str() == ObjectLockRetentionMode::COMPLIANCE {
+                rustfs_rsc::datatype::RetentionMode::COMPLIANCE
+            } else {
+                rustfs_rsc::datatype::RetentionMode::GOVERNANCE
+            },
+            retain_until_date: UtcTime::new(
+                DateTime::<Utc>::from_timestamp(retention.retain_until.unix_timestamp(), retention.retain_until.nanosecond())
+                    .unwrap_or_default(),
+            ),
+        };
+        policy
+            .retry(
+                || {
+                    let key = KeyArgs::from(oi.name.clone())
+                        .version_id(version_id.clone())
+                        .extra_headers(Some(retention_headers()));
+                    client.set_object_retention(target.bucket.clone(), key, retention.clone())
+                },
+                is_replication_retryable,
+            )
+            .await
+            .map_err(|e| Error::other(format!("replicate retention: {e}")))?;
+    } else if metadata_timestamp(&oi.user_defined, OBJECT_LOCK_RETENTION_TIMESTAMP).is_some() {
+        // the retention was removed
+        policy
+            .retry(
+                || {
+                    let executor = client
+                        .executor(Method::PUT)
+                        .bucket_name(target.bucket.clone())
+                        .object_name(oi.name.clone())
+                        .query("retention", "")
+                        .headers_merge(retention_headers())
+                        .body(rustfs_rsc::Data::from(Bytes::from_static(EMPTY_RETENTION_XML.as_bytes())));
+                    match &version_id {
+                        Some(version_id) => executor.query("versionId", version_id.clone()).send_ok(),
+                        None => executor.send_ok(),
+                    }
+                },
+                is_replication_retryable,
+            )
+            .await
+            .map_err(|e| Error::other(format!("replicate retention: {e}")))?;
+    }
+
+    if let Some(status) = get_object_legalhold_meta(&oi.user_defined).status {
+        let on = status.as_str() == ObjectLockLegalHoldStatus::ON;
+        policy
+            .retry(
+                || {
+                    let key = key(OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP);
+                    let bucket = target.bucket.clone();
+                    let client = &client;
+                    async move {
+                        if on {
+                            client.enable_object_legal_hold_enabled(bucket, key).await
+                        } else {
+                            client.disable_object_legal_hold_enabled(bucket, key).await
+                        }
+                    }
+                },
+                is_replication_retryable,
+            )
+            .await
+            .map_err(|e| Error::other(format!("replicate legal hold: {e}")))?;
+    }
+
+    Ok(())
+}
+
+/// Replicates a delete marker or the delete of a version to the targets of the bucket
+///
+/// Delete markers have no metadata to keep the statuses of their targets, so the statuses
+/// travel with the retries of the failed queue.
+pub async fn replicate_delete(dobj: &DeletedObjectReplicationInfo, object_api: Arc<store::ECStore>) {
+    let bucket = dobj.bucket.as_str();
+    let name = dobj.deleted_object.object_name.clone().unwrap_or_default();
+    let rcfg = match get_replication_config(bucket).await {
+        Ok((cfg, _)) => cfg,
+        Err(err) => {
+            error!("replicate delete {}/{}: get replication config err: {}", bucket, name, err);
+            return;
+        }
+    };
+
+    // a delete marker names the version it created, the delete of a version the deleted version
+    let version_id = dobj.deleted_object.version_id.clone().unwrap_or_default();
+    let version_purge = dobj
+        .deleted_object
+        .delete_marker_version_id
+        .as_ref()
+        .is_none_or(|v| v.is_empty());
+
+    let mut opts = ReplicationObjectOpts {
+        name: name.clone(),
+        user_tags: None,
+        version_id: if version_purge { version_id.clone() } else { String::new() },
+        delete_marker: !version_purge,
+        ssec: false,
+        op_type: ReplicationType::DeleteReplicationType,
+        replica: false,
+        existing_object: false,
+        target_arn: (!dobj.target_arn.is_empty()).then(|| dobj.target_arn.clone()),
+    };
+
+    // a delete marker keeps its version id and time on the targets, which then order it like the source
+    let (target_version_id, headers) = if version_purge {
+        (Some(version_id.clone()), replication_request_headers())
+    } else {
+        let mut headers = replication_mtime_headers(convert_chrono_to_offsetdatetime(dobj.deleted_object.delete_marker_mtime));
+        if let Ok(name) = HeaderName::from_bytes(RUSTFS_SOURCE_DELETE_MARKER.as_bytes()) {
+            headers.insert(name, HeaderValue::from_static("true"));
+        }
+        (dobj.deleted_object.delete_marker_version_id.clone(), headers)
+    };
+
+    let mut state = dobj.deleted_object.replication_state.clone();
+    let mut failed = false;
+    for arn in rcfg.filter_target_arns(&opts) {
+        opts.target_arn = Some(arn.clone());
+        if !rcfg.replicate(&opts) {
+            continue;
+        }
+        let done = if version_purge {
+            state.purge_targets.get(&arn) == Some(&VersionPurgeStatusType::Complete)
+        } else {
+            state.targets.get(&arn) == Some(&ReplicationStatusType::Completed)
+        };
+        if done {
+            continue;
+        }
+
+        let replicated = match bucket_targets::get_bucket_target_client(bucket, &arn).await {
+            Ok(target) => replicate_delete_to_target(&target, &name, target_version_id.as_deref(), &headers)
+                .await
+                .inspect_err(|err| error!("replicate delete {}/{} to {}: {}", bucket, name, arn, err))
+                .is_ok(),
+            Err(err) => {
+                error!("replicate delete {}/{}: get target {} err: {}", bucket, name, arn, err);
+                false
+            }
+        };
+        failed |= !replicated;
+
+        if version_purge {
+            let status = if replicated {
+                VersionPurgeStatusType::Complete
+            } else {
+                VersionPurgeStatusType::Failed
+            };
+            state.purge_targets.insert(arn, status);
+        } else {
+            let status = if replicated {
+                ReplicationStatusType::Completed
+            } else {
+                ReplicationStatusType::Failed
+            };
+            state.targets.insert(arn, status);
+        }
+    }
+
+    if failed {
+        let mut retry = dobj.clone();
+        retry.deleted_object.replication_state = state;
+        if dobj.retry_count < MRF_MAX_RETRY_COUNT {
+            retry.event_type = "ReplicateMRF".into();
+            retry.retry_count += 1;
+            queue_mrf(Box::new(retry));
+        } else {
+            record_mrf(MRFReplicateEntry::Delete(retry));
+        }
+    }
+}
+
+/// Deletes an object, or one of its versions, on a replication target
+async fn replicate_delete_to_target(
+    target: &TargetClient,
+    name: &str,
+    version_id: Option<&str>,
+    headers: &HeaderMap,
+) -> Result<(), Error> {
+    let provider = StaticProvider::new(&target.ak, &target.sk, None);
+    let client = Minio::builder()
+        .endpoint(target.endpoint.clone())
+        .provider(provider)
+        .secure(target.secure)
+        .build()
+        .map_err(|e| Error::other(format!("build rustfs client failed: {e}")))?;
+
+    replication_retry_policy(&target.arn)
+        .retry(
+            || {
+                let executor = client
+                    .executor(Method::DELETE)
+                    .bucket_name(target.bucket.clone())
+                    .object_name(name)
+                    .headers_merge(headers.clone());
+                match version_id {
+                    Some(version_id) => executor.query("versionId", version_id).send_ok(),
+                    None => executor.send_ok(),
+                }
+            },
+            is_replication_retryable,
+        )
+        .await
+        .map(|_| ())
+        .map_err(|e| Error::other(e.to_string()))
+}
+
+/// Replicates a delete, on its own task unless a target replicates synchronously
+pub async fn schedule_replication_delete(dv: DeletedObjectReplicationInfo, o: Arc<store::ECStore>, dsc: ReplicateDecision) {
+    if dsc.synchronous() {
+        replicate_delete(&dv, o).await;
+    } else if let Some(pool) = GLOBAL_REPLICATION_POOL.read().await.as_ref() {
+        pool.queue_replica_delete_task(dv);
+    } else {
+        record_mrf(MRFReplicateEntry::Delete(dv));
+    }
+}
 
 pub fn clone_mss(v: &HashMap<String, String>) -> HashMap<String, String> {
     let mut r = HashMap::with_capacity(v.len());
@@ -2620,11 +3349,14 @@ pub async fn replicate_object(ri: ReplicateObjectInfo, object_api: Arc<store::EC
                 let task = task::spawn(async move {
                     warn!("async task");
                     let mut tgt_info: ReplicatedTargetInfo = Default::default();
-                    if lcri.op_type as u8 == ReplicationType::ObjectReplicationType.as_u8() {
+                    let op_type = ReplicationType::from_u8(lcri.op_type as u8);
+                    if op_type.is_some_and(ReplicationType::is_data_replication) {
                         warn!("object replication and arn is {}", tgt.arn.clone());
                         // all incoming calls go through optimized path.`o`
 
                         tgt_info = lcri.replicate_object(&tgt, tgt.arn.clone()).await;
+                    } else if op_type == Some(ReplicationType::MetadataReplicationType) {
+                        tgt_info = lcri.replicate_metadata(&tgt, tgt.arn.clone()).await;
                     } else {
                         warn!("async task");
                         // tgt_info = ri.replicate_all(object_api, &tgt).await;
@@ -2721,16 +3453,84 @@ pub async fn replicate_object(ri: ReplicateObjectInfo, object_api: Arc<store::EC
             // });
 
             // 失败重试
-            // if rs.replication_status() != ReplicationStatusType::Completed {
-            //     //ri.op_type = "HealReplicationType".into();
-            //     ri.event_type = "ReplicateMRF".into();
-            //     //ri.replication_status_internal = rinfos.replication_status_internal();
-            //     ri.retry_count += 1;
-            //     // global_replication_pool.get().queue_mrf_save(ri.to_mrf_entry());
-            // }
+            if replication_status == ReplicationStatusType::Failed {
+                if ri.retry_count < MRF_MAX_RETRY_COUNT {
+                    let mut retry = ri.clone();
+                    retry.event_type = "ReplicateMRF".into();
+                    retry.replication_status_internal = new_repl_status_internal;
+                    retry.retry_count += 1;
+                    queue_mrf(Box::new(retry));
+                } else {
+                    record_mrf(ri.to_mrf_entry());
+                }
+            }
         }
         Err(err) => {
             println!("Failed to get replication config: {err:?}");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replication_status_from_metadata() {
+        let key = format!("{RESERVED_METADATA_PREFIX_LOWER}{REPLICATION_STATUS}");
+        let mut metadata = HashMap::new();
+        assert_eq!(replication_status_from_metadata(&metadata).1, ReplicationStatusType::Unknown);
+
+        metadata.insert(key.clone(), "arn:a=COMPLETED;arn:b=PENDING;".to_string());
+        let (internal, status) = replication_status_from_metadata(&metadata);
+        assert_eq!(status, ReplicationStatusType::Pending);
+        assert_eq!(replication_statuses_map(&internal).get("arn:a"), Some(&ReplicationStatusType::Completed));
+
+        metadata.insert(key, "arn:a=COMPLETED;arn:b=COMPLETED;".to_string());
+        assert_eq!(replication_status_from_metadata(&metadata).1, ReplicationStatusType::Completed);
+
+        metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}{REPLICA_STATUS}"), "REPLICA".to_string());
+        assert_eq!(replication_status_from_metadata(&metadata).1, ReplicationStatusType::Replica);
+    }
+
+    #[test]
+    fn test_metadata_timestamp() {
+        let mut meta = HashMap::new();
+        assert!(metadata_timestamp(&meta, TAGGING_TIMESTAMP).is_none());
+
+        let at = OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_123_456_789).unwrap();
+        set_metadata_timestamp(&mut meta, TAGGING_TIMESTAMP, at);
+        assert_eq!(metadata_timestamp(&meta, TAGGING_TIMESTAMP), Some(at));
+        assert!(metadata_timestamp(&meta, OBJECT_LOCK_RETENTION_TIMESTAMP).is_none());
+        assert_eq!(
+            convert_offsetdatetime_to_chrono(Some(at)).and_then(convert_chrono_to_offsetdatetime),
+            Some(at)
+        );
+    }
+
+    #[test]
+    fn test_replication_put_headers() {
+        let oi = ObjectInfo {
+            user_defined: HashMap::from([
+                ("content-type".to_string(), "text/plain".to_string()),
+                ("color".to_string(), "blue".to_string()),
+                (
+                    format!("{RESERVED_METADATA_PREFIX_LOWER}{REPLICATION_STATUS}"),
+                    "arn:a=PENDING;".to_string(),
+                ),
+                ("x-amz-object-lock-legal-hold".to_string(), "ON".to_string()),
+            ]),
+            user_tags: "k=v".to_string(),
+            mod_time: Some(OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap()),
+            ..Default::default()
+        };
+        let headers = replication_put_headers(&oi);
+        assert_eq!(headers.get(RUSTFS_SOURCE_REPLICATION_REQUEST).unwrap(), "true");
+        assert_eq!(headers.get(RUSTFS_SOURCE_MTIME).unwrap(), "2023-11-14T22:13:20Z");
+        assert_eq!(headers.get("content-type").unwrap(), "text/plain");
+        assert_eq!(headers.get("x-amz-meta-color").unwrap(), "blue");
+        assert_eq!(headers.get("x-amz-object-lock-legal-hold").unwrap(), "ON");
+        assert_eq!(headers.get("x-amz-tagging").unwrap(), "k=v");
+        assert_eq!(headers.len(), 6);
+    }
+}
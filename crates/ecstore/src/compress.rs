@@ -12,16 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use rustfs_utils::CompressionAlgorithm;
 use rustfs_utils::string::has_pattern;
 use rustfs_utils::string::has_string_suffix_in_slice;
 use std::env;
-use tracing::error;
+use std::str::FromStr as _;
+use tracing::{error, warn};
 
 pub const MIN_COMPRESSIBLE_SIZE: usize = 4096;
 
 // 环境变量名称，用于控制是否启用压缩
 pub const ENV_COMPRESSION_ENABLED: &str = "RUSTFS_COMPRESSION_ENABLED";
 
+// Algorithm used to compress new objects: zstd, lz4 or snappy
+pub const ENV_COMPRESSION_ALGORITHM: &str = "RUSTFS_COMPRESSION_ALGORITHM";
+
+// Comma separated extensions and content-types to compress, everything not excluded when neither is set
+pub const ENV_COMPRESSION_EXTENSIONS: &str = "RUSTFS_COMPRESSION_EXTENSIONS";
+pub const ENV_COMPRESSION_MIME_TYPES: &str = "RUSTFS_COMPRESSION_MIME_TYPES";
+
 // Some standard object extensions which we strictly dis-allow for compression.
 pub const STANDARD_EXCLUDE_COMPRESS_EXTENSIONS: &[&str] = &[
     ".gz", ".bz2", ".rar", ".zip", ".7z", ".xz", ".mp4", ".mkv", ".mov", ".jpg", ".png", ".gif",
@@ -38,15 +47,37 @@ pub const STANDARD_EXCLUDE_COMPRESS_CONTENT_TYPES: &[&str] = &[
     "application/x-spoon",
 ];
 
+/// Whether compression is turned on, it is off unless the environment enables it
+pub fn compression_enabled() -> bool {
+    env::var(ENV_COMPRESSION_ENABLED).is_ok_and(|v| v.to_lowercase() == "true")
+}
+
+/// Algorithm new objects are compressed with, lz4 unless the environment picks another one
+pub fn compression_algorithm() -> CompressionAlgorithm {
+    let Ok(v) = env::var(ENV_COMPRESSION_ALGORITHM) else {
+        return CompressionAlgorithm::default();
+    };
+    match CompressionAlgorithm::from_str(v.trim()) {
+        Ok(algorithm @ (CompressionAlgorithm::Zstd | CompressionAlgorithm::Lz4 | CompressionAlgorithm::Snappy)) => algorithm,
+        _ => {
+            warn!("unsupported compression algorithm {}, using {}", v, CompressionAlgorithm::default());
+            CompressionAlgorithm::default()
+        }
+    }
+}
+
+fn env_list(key: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
 pub fn is_compressible(headers: &http::HeaderMap, object_name: &str) -> bool {
     // 检查环境变量是否启用压缩，默认关闭
-    if let Ok(compression_enabled) = env::var(ENV_COMPRESSION_ENABLED) {
-        if compression_enabled.to_lowercase() != "true" {
-            error!("Compression is disabled by environment variable");
-            return false;
-        }
-    } else {
-        // 环境变量未设置时默认关闭
+    if !compression_enabled() {
         return false;
     }
 
@@ -63,9 +94,17 @@ pub fn is_compressible(headers: &http::HeaderMap, object_name: &str) -> bool {
         error!("content_type: {} is not compressible", content_type);
         return false;
     }
-    true
 
-    // TODO: check from config
+    // With allowlists only the objects they name are compressed
+    let extensions = env_list(ENV_COMPRESSION_EXTENSIONS);
+    let mime_types = env_list(ENV_COMPRESSION_MIME_TYPES);
+    if !extensions.is_empty() || !mime_types.is_empty() {
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        let mime_types: Vec<&str> = mime_types.iter().map(String::as_str).collect();
+        return has_string_suffix_in_slice(object_name, &extensions)
+            || (!content_type.is_empty() && has_pattern(&mime_types, content_type));
+    }
+    true
 }
 
 #[cfg(test)]
@@ -126,4 +165,49 @@ mod tests {
             assert!(is_compressible(&headers, "file.json"));
         });
     }
+
+    #[test]
+    fn test_is_compressible_allowlist() {
+        use http::HeaderMap;
+
+        temp_env::with_vars(
+            [
+                (ENV_COMPRESSION_ENABLED, Some("true")),
+                (ENV_COMPRESSION_EXTENSIONS, Some(".txt, .log")),
+                (ENV_COMPRESSION_MIME_TYPES, Some("text/*,application/json")),
+            ],
+            || {
+                let mut headers = HeaderMap::new();
+                assert!(is_compressible(&headers, "file.txt"));
+                assert!(is_compressible(&headers, "FILE.LOG"));
+                assert!(!is_compressible(&headers, "file.bin"));
+
+                headers.insert("content-type", "text/csv".parse().unwrap());
+                assert!(is_compressible(&headers, "file.bin"));
+                headers.insert("content-type", "application/json".parse().unwrap());
+                assert!(is_compressible(&headers, "file.bin"));
+                headers.insert("content-type", "application/octet-stream".parse().unwrap());
+                assert!(!is_compressible(&headers, "file.bin"));
+
+                // The standard exclusions still win
+                assert!(!is_compressible(&headers, "file.txt.gz"));
+            },
+        );
+    }
+
+    #[test]
+    fn test_compression_algorithm() {
+        temp_env::with_var_unset(ENV_COMPRESSION_ALGORITHM, || {
+            assert_eq!(compression_algorithm(), CompressionAlgorithm::Lz4);
+        });
+        temp_env::with_var(ENV_COMPRESSION_ALGORITHM, Some("ZSTD"), || {
+            assert_eq!(compression_algorithm(), CompressionAlgorithm::Zstd);
+        });
+        temp_env::with_var(ENV_COMPRESSION_ALGORITHM, Some("snappy"), || {
+            assert_eq!(compression_algorithm(), CompressionAlgorithm::Snappy);
+        });
+        temp_env::with_var(ENV_COMPRESSION_ALGORITHM, Some("gzip"), || {
+            assert_eq!(compression_algorithm(), CompressionAlgorithm::Lz4);
+        });
+    }
 }
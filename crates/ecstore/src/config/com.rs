@@ -166,8 +166,7 @@ async fn read_server_config<S: StorageAPI>(api: Arc<S>, data: &[u8]) -> Result<C
         // Try to read the configuration again
         match read_config(api.clone(), &config_file).await {
             Ok(cfg_data) => {
-                // TODO: decrypt
-                let cfg = Config::unmarshal(&cfg_data)?;
+                let cfg = Config::unmarshal(&cfg_data)?.open_secrets()?;
                 return Ok(cfg.merge());
             }
             Err(Error::ConfigNotFound) => return handle_missing_config(api, "Read alternate configuration").await,
@@ -176,12 +175,12 @@ async fn read_server_config<S: StorageAPI>(api: Arc<S>, data: &[u8]) -> Result<C
     }
 
     // Process non-empty configuration data
-    let cfg = Config::unmarshal(data)?;
+    let cfg = Config::unmarshal(data)?.open_secrets()?;
     Ok(cfg.merge())
 }
 
 pub async fn save_server_config<S: StorageAPI>(api: Arc<S>, cfg: &Config) -> Result<()> {
-    let data = cfg.marshal()?;
+    let data = cfg.seal_secrets()?.marshal()?;
 
     let config_file = get_config_file();
 
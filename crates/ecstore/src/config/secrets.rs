@@ -0,0 +1,189 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Credentials of external targets sealed at rest.
+//!
+//! Webhook tokens, broker passwords and remote tier keys are only kept in plaintext in memory.
+//! Before a config is written they are encrypted with a key derived from the root credentials for
+//! this use only, and stored as `sealed:<base64>`. Without root credentials nothing is sealed or
+//! opened. Values written before sealing was introduced are read as they are, and values sealed
+//! with the root secret key itself are still opened; both are sealed again on the next save.
+
+use super::{Config, KV, KVS};
+use crate::error::{Error, Result};
+use crate::global::get_global_action_cred;
+use base64::Engine as _;
+use base64::engine::general_purpose;
+use rustfs_config::notify::{
+    AMQP_URL, KAFKA_SASL_PASSWORD, MQTT_PASSWORD, NATS_PASSWORD, NATS_TOKEN, NOTIFY_AMQP_SUB_SYS, NOTIFY_KAFKA_SUB_SYS,
+    NOTIFY_MQTT_SUB_SYS, NOTIFY_NATS_SUB_SYS, NOTIFY_WEBHOOK_SUB_SYS, WEBHOOK_AUTH_TOKEN,
+};
+use rustfs_utils::crypto::hkdf_sha256;
+use std::sync::OnceLock;
+
+const SEALED_PREFIX: &str = "sealed:";
+
+/// Config keys holding credentials, per sub-system
+const SECRET_KEYS: &[(&str, &str)] = &[
+    (NOTIFY_WEBHOOK_SUB_SYS, WEBHOOK_AUTH_TOKEN),
+    (NOTIFY_MQTT_SUB_SYS, MQTT_PASSWORD),
+    (NOTIFY_KAFKA_SUB_SYS, KAFKA_SASL_PASSWORD),
+    (NOTIFY_NATS_SUB_SYS, NATS_PASSWORD),
+    (NOTIFY_NATS_SUB_SYS, NATS_TOKEN),
+    (NOTIFY_AMQP_SUB_SYS, AMQP_URL),
+];
+
+/// Whether `key` of `sub_sys` holds a credential
+pub fn is_secret_key(sub_sys: &str, key: &str) -> bool {
+    SECRET_KEYS.iter().any(|(s, k)| *s == sub_sys && *k == key)
+}
+
+static SEALING_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+/// The root secret key, which sealed credentials before they got a key of their own
+fn root_secret() -> Result<String> {
+    get_global_action_cred()
+        .map(|cred| cred.secret_key)
+        .filter(|secret| !secret.is_empty())
+        .ok_or_else(|| Error::other("config credentials can not be sealed without root credentials"))
+}
+
+fn sealing_key() -> Result<[u8; 32]> {
+    if let Some(key) = SEALING_KEY.get() {
+        return Ok(*key);
+    }
+    let secret = root_secret()?;
+    Ok(*SEALING_KEY.get_or_init(|| hkdf_sha256("rustfs", secret, "config-secrets")))
+}
+
+fn seal_with(key: &[u8], value: &str) -> Result<String> {
+    if value.is_empty() || value.starts_with(SEALED_PREFIX) {
+        return Ok(value.to_string());
+    }
+    let data = rustfs_crypto::encrypt_data(key, value.as_bytes()).map_err(Error::other)?;
+    Ok(format!("{SEALED_PREFIX}{}", general_purpose::STANDARD.encode(data)))
+}
+
+fn open_with(key: &[u8], value: &str) -> Result<String> {
+    let Some(sealed) = value.strip_prefix(SEALED_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let data = general_purpose::STANDARD.decode(sealed).map_err(Error::other)?;
+    let data = rustfs_crypto::decrypt_data(key, &data).map_err(Error::other)?;
+    String::from_utf8(data).map_err(Error::other)
+}
+
+/// Encrypt a credential for storage
+pub fn seal(value: &str) -> Result<String> {
+    seal_with(&sealing_key()?, value)
+}
+
+/// Decrypt a stored credential, values that were never sealed are returned unchanged
+pub fn open(value: &str) -> Result<String> {
+    if !value.starts_with(SEALED_PREFIX) {
+        return Ok(value.to_string());
+    }
+    open_with(&sealing_key()?, value).or_else(|err| open_with(root_secret()?.as_bytes(), value).map_err(|_| err))
+}
+
+fn map_secrets(cfg: &Config, f: impl Fn(&str) -> Result<String>) -> Result<Config> {
+    let mut out = cfg.clone();
+    for (sub_sys, targets) in out.0.iter_mut() {
+        for kvs in targets.values_mut() {
+            for KV { key, value, .. } in kvs.0.iter_mut() {
+                if is_secret_key(sub_sys, key) {
+                    *value = f(value)?;
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+impl Config {
+    /// Copy of the config with its credentials sealed, as it is written to disk
+    pub fn seal_secrets(&self) -> Result<Config> {
+        map_secrets(self, seal)
+    }
+
+    /// Copy of the config with its sealed credentials decrypted
+    pub fn open_secrets(&self) -> Result<Config> {
+        map_secrets(self, open)
+    }
+}
+
+impl KVS {
+    /// Overlay `updates` on the credentials of a target of `sub_sys`, rejecting other keys
+    pub fn with_secrets(&self, sub_sys: &str, updates: &[(String, String)]) -> Result<KVS> {
+        let mut out = self.clone();
+        for (key, value) in updates {
+            if !is_secret_key(sub_sys, key) {
+                return Err(Error::other(format!("{key} is not a credential of {sub_sys}")));
+            }
+            out.insert(key.clone(), value.clone());
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let key = b"root-secret";
+        let sealed = seal_with(key, "token").unwrap();
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert_ne!(sealed, seal_with(key, "token").unwrap(), "sealing is randomized");
+        assert_eq!(open_with(key, &sealed).unwrap(), "token");
+        assert!(open_with(b"other-secret", &sealed).is_err());
+
+        // Already sealed, empty and legacy plaintext values pass through
+        assert_eq!(seal_with(key, &sealed).unwrap(), sealed);
+        assert_eq!(seal_with(key, "").unwrap(), "");
+        assert_eq!(open_with(key, "plain").unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_seal_config_secrets_only() {
+        let mut kvs = KVS::new();
+        kvs.insert(WEBHOOK_AUTH_TOKEN.to_string(), "token".to_string());
+        kvs.insert("endpoint".to_string(), "http://hook".to_string());
+        let cfg = Config(HashMap::from([(
+            NOTIFY_WEBHOOK_SUB_SYS.to_string(),
+            HashMap::from([("1".to_string(), kvs)]),
+        )]));
+
+        let sealed = cfg.seal_secrets().unwrap();
+        let target = sealed.get_value(NOTIFY_WEBHOOK_SUB_SYS, "1").unwrap();
+        assert!(target.lookup(WEBHOOK_AUTH_TOKEN).unwrap().starts_with(SEALED_PREFIX));
+        assert_eq!(target.lookup("endpoint").unwrap(), "http://hook");
+
+        let opened = sealed.open_secrets().unwrap();
+        let target = opened.get_value(NOTIFY_WEBHOOK_SUB_SYS, "1").unwrap();
+        assert_eq!(target.lookup(WEBHOOK_AUTH_TOKEN).unwrap(), "token");
+
+        assert!(
+            target
+                .with_secrets(NOTIFY_WEBHOOK_SUB_SYS, &[("endpoint".into(), "x".into())])
+                .is_err()
+        );
+        let rotated = target
+            .with_secrets(NOTIFY_WEBHOOK_SUB_SYS, &[(WEBHOOK_AUTH_TOKEN.into(), "new".into())])
+            .unwrap();
+        assert_eq!(rotated.lookup(WEBHOOK_AUTH_TOKEN).unwrap(), "new");
+    }
+}
@@ -15,6 +15,7 @@
 use super::KVS;
 use crate::config::KV;
 use crate::error::{Error, Result};
+use rustfs_utils::HashAlgorithm;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::sync::LazyLock;
@@ -41,6 +42,8 @@ pub const CLASS_STANDARD: &str = "standard";
 pub const CLASS_RRS: &str = "rrs";
 pub const OPTIMIZE: &str = "optimize";
 pub const INLINE_BLOCK: &str = "inline_block";
+pub const CLASS_STANDARD_BITROT: &str = "standard_bitrot";
+pub const CLASS_RRS_BITROT: &str = "rrs_bitrot";
 
 // Reduced redundancy storage class environment variable
 pub const RRS_ENV: &str = "RUSTFS_STORAGE_CLASS_RRS";
@@ -50,6 +53,10 @@ pub const STANDARD_ENV: &str = "RUSTFS_STORAGE_CLASS_STANDARD";
 pub const OPTIMIZE_ENV: &str = "RUSTFS_STORAGE_CLASS_OPTIMIZE";
 // Inline block indicates the size of the shard that is considered for inlining
 pub const INLINE_BLOCK_ENV: &str = "RUSTFS_STORAGE_CLASS_INLINE_BLOCK";
+// Bitrot algorithm of the standard storage class, one of highwayhash, blake3 or crc32c
+pub const STANDARD_BITROT_ENV: &str = "RUSTFS_STORAGE_CLASS_STANDARD_BITROT";
+// Bitrot algorithm of the reduced redundancy storage class
+pub const RRS_BITROT_ENV: &str = "RUSTFS_STORAGE_CLASS_RRS_BITROT";
 
 // Supported storage class scheme is EC
 pub const SCHEME_PREFIX: &str = "EC";
@@ -62,6 +69,9 @@ pub const DEFAULT_RRS_PARITY: usize = 1;
 
 pub static DEFAULT_INLINE_BLOCK: usize = 128 * 1024;
 
+// Bitrot algorithm used when none is configured
+pub const DEFAULT_BITROT_ALGORITHM: HashAlgorithm = HashAlgorithm::HighwayHash256;
+
 pub static DEFAULT_KVS: LazyLock<KVS> = LazyLock::new(|| {
     let kvs = vec![
         KV {
@@ -84,6 +94,16 @@ pub static DEFAULT_KVS: LazyLock<KVS> = LazyLock::new(|| {
             value: "".to_owned(),
             hidden_if_empty: true,
         },
+        KV {
+            key: CLASS_STANDARD_BITROT.to_owned(),
+            value: "".to_owned(),
+            hidden_if_empty: true,
+        },
+        KV {
+            key: CLASS_RRS_BITROT.to_owned(),
+            value: "".to_owned(),
+            hidden_if_empty: true,
+        },
     ];
 
     KVS(kvs)
@@ -93,6 +113,7 @@ pub static DEFAULT_KVS: LazyLock<KVS> = LazyLock::new(|| {
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct StorageClass {
     parity: usize,
+    bitrot: HashAlgorithm,
 }
 
 // Config storage class configuration
@@ -125,6 +146,19 @@ impl Config {
         }
     }
 
+    /// Bitrot algorithm for new objects of the storage class, existing objects keep the one
+    /// recorded in their metadata until a re-encode job rewrites them.
+    pub fn get_bitrot_algo_for_sc(&self, sc: &str) -> HashAlgorithm {
+        if !self.initialized {
+            return DEFAULT_BITROT_ALGORITHM;
+        }
+
+        match sc.trim() {
+            RRS => self.rrs.bitrot.clone(),
+            _ => self.standard.bitrot.clone(),
+        }
+    }
+
     pub fn should_inline(&self, shard_size: i64, versioned: bool) -> bool {
         if shard_size < 0 {
             return false;
@@ -162,7 +196,7 @@ impl Config {
 }
 
 pub fn lookup_config(kvs: &KVS, set_drive_count: usize) -> Result<Config> {
-    let standard = {
+    let mut standard = {
         let ssc_str = {
             if let Ok(ssc_str) = env::var(STANDARD_ENV) {
                 ssc_str
@@ -176,11 +210,12 @@ pub fn lookup_config(kvs: &KVS, set_drive_count: usize) -> Result<Config> {
         } else {
             StorageClass {
                 parity: default_parity_count(set_drive_count),
+                bitrot: DEFAULT_BITROT_ALGORITHM,
             }
         }
     };
 
-    let rrs = {
+    let mut rrs = {
         let ssc_str = {
             if let Ok(ssc_str) = env::var(RRS_ENV) {
                 ssc_str
@@ -194,16 +229,22 @@ pub fn lookup_config(kvs: &KVS, set_drive_count: usize) -> Result<Config> {
         } else {
             StorageClass {
                 parity: { if set_drive_count == 1 { 0 } else { DEFAULT_RRS_PARITY } },
+                bitrot: DEFAULT_BITROT_ALGORITHM,
             }
         }
     };
 
     validate_parity_inner(standard.parity, rrs.parity, set_drive_count)?;
 
+    standard.bitrot = parse_bitrot_algorithm(&env::var(STANDARD_BITROT_ENV).unwrap_or_else(|_| kvs.get(CLASS_STANDARD_BITROT)))?;
+    rrs.bitrot = parse_bitrot_algorithm(&env::var(RRS_BITROT_ENV).unwrap_or_else(|_| kvs.get(CLASS_RRS_BITROT)))?;
+
     let optimize = { env::var(OPTIMIZE_ENV).ok() };
 
+    // Objects whose shards are at most this size are kept in xl.meta rather than in part files
     let inline_block = {
-        if let Ok(ev) = env::var(INLINE_BLOCK_ENV) {
+        let ev = env::var(INLINE_BLOCK_ENV).unwrap_or_else(|_| kvs.get(INLINE_BLOCK));
+        if !ev.is_empty() {
             if let Ok(block) = ev.parse::<bytesize::ByteSize>() {
                 if block.as_u64() as usize > DEFAULT_INLINE_BLOCK {
                     warn!(
@@ -213,7 +254,7 @@ pub fn lookup_config(kvs: &KVS, set_drive_count: usize) -> Result<Config> {
                 }
                 block.as_u64() as usize
             } else {
-                return Err(Error::other(format!("parse {INLINE_BLOCK_ENV} format failed")));
+                return Err(Error::other(format!("parse {INLINE_BLOCK} value {ev} failed")));
             }
         } else {
             DEFAULT_INLINE_BLOCK
@@ -250,7 +291,31 @@ pub fn parse_storage_class(env: &str) -> Result<StorageClass> {
         Err(_) => return Err(Error::other(format!("Failed to parse parity value: {}.", s[1]))),
     };
 
-    Ok(StorageClass { parity: parity_drives })
+    Ok(StorageClass {
+        parity: parity_drives,
+        bitrot: DEFAULT_BITROT_ALGORITHM,
+    })
+}
+
+// Config name of a bitrot algorithm, the inverse of parse_bitrot_algorithm.
+pub fn bitrot_algorithm_name(algo: &HashAlgorithm) -> &'static str {
+    match algo {
+        HashAlgorithm::Blake3 => "blake3",
+        HashAlgorithm::Crc32c => "crc32c",
+        _ => "highwayhash",
+    }
+}
+
+// Parses the bitrot algorithm name of a storage class, empty means the default.
+pub fn parse_bitrot_algorithm(name: &str) -> Result<HashAlgorithm> {
+    match name.trim().to_lowercase().as_str() {
+        "" | "highwayhash" | "highwayhash256" => Ok(HashAlgorithm::HighwayHash256),
+        "blake3" => Ok(HashAlgorithm::Blake3),
+        "crc32c" => Ok(HashAlgorithm::Crc32c),
+        other => Err(Error::other(format!(
+            "Unsupported bitrot algorithm {other}. Supported algorithms are highwayhash, blake3 and crc32c."
+        ))),
+    }
 }
 
 // ValidateParity validates standard storage class parity.
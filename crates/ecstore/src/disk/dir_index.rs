@@ -0,0 +1,231 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sorted indexes of the directories of a drive, kept between listings
+//!
+//! A listing reads and sorts every entry of the directories it walks, whatever page of them it is
+//! asked for, so each page of a paginated listing of a large directory paid for the whole directory
+//! again. The index of a large directory is kept instead, for as long as the modification time of the
+//! directory shows no entry was added or removed, and the page a listing resumes at is found in it by
+//! binary search.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use rustfs_utils::path::{GLOBAL_DIR_SUFFIX_WITH_SLASH, SLASH_SEPARATOR};
+
+use super::STORAGE_FORMAT_FILE;
+
+/// Fewest entries of a directory worth keeping the index of
+pub const MIN_INDEXED_ENTRIES: usize = 1024;
+
+/// Most entries the indexes of a drive hold together
+pub const MAX_INDEXED_ENTRIES: usize = 8 << 20;
+
+/// How close a change can follow a read of a directory without changing its modification time, on
+/// file systems with coarse timestamps
+const MTIME_GRANULARITY: Duration = Duration::from_secs(2);
+
+/// Entries of a directory, the way a listing walks them
+#[derive(Debug, Default)]
+pub struct DirIndex {
+    /// Sub directories, sorted, without their trailing slash, except for directory objects which keep it
+    pub entries: Vec<String>,
+    /// Entries that are directory objects
+    pub dir_objs: HashSet<String>,
+    /// Metadata file of the directory, when it is an object itself
+    pub meta_file: Option<String>,
+}
+
+impl DirIndex {
+    /// Index of the entries `list_dir` returned for a directory
+    pub fn new(names: Vec<String>) -> Self {
+        let mut index = Self::default();
+        for name in names {
+            if let Some(dir) = name.strip_suffix(GLOBAL_DIR_SUFFIX_WITH_SLASH) {
+                let dir = format!("{dir}{SLASH_SEPARATOR}");
+                index.dir_objs.insert(dir.clone());
+                index.entries.push(dir);
+            } else if let Some(dir) = name.strip_suffix(SLASH_SEPARATOR) {
+                index.entries.push(dir.to_owned());
+            } else if name.ends_with(STORAGE_FORMAT_FILE) && index.meta_file.is_none() {
+                index.meta_file = Some(name);
+            }
+        }
+        index.entries.sort();
+        index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.meta_file.is_none()
+    }
+
+    /// Entries a walk of the entries starting with `filter_prefix` looks at, when it resumes at `forward`
+    ///
+    /// The entries before `forward` that may still be part of the walk are included, see [`Self::is_before`].
+    pub fn entries_from(&self, filter_prefix: Option<&str>, forward: Option<&str>) -> &[String] {
+        let mut start = filter_prefix.map_or(0, |prefix| self.entries.partition_point(|e| e.as_str() < prefix));
+        if let Some(forward) = forward {
+            // The names under a directory sort after its name followed by any character before the separator
+            let bound = forward.find(|c: char| c < '/').map_or(forward, |i| &forward[..i]);
+            start = start.max(self.entries.partition_point(|e| e.as_str() < bound));
+        }
+
+        let entries = &self.entries[start..];
+        match filter_prefix {
+            Some(prefix) => &entries[..entries.partition_point(|e| e.starts_with(prefix))],
+            None => entries,
+        }
+    }
+
+    /// Whether the walk resuming at `forward` skips `entry`, comparing the names the drive holds
+    pub fn is_before(&self, entry: &str, forward: &str) -> bool {
+        let name = match self.dir_objs.contains(entry) {
+            true => format!("{}{GLOBAL_DIR_SUFFIX_WITH_SLASH}", entry.trim_end_matches(SLASH_SEPARATOR)),
+            false => format!("{entry}{SLASH_SEPARATOR}"),
+        };
+        name.as_str() < forward
+    }
+}
+
+#[derive(Debug, Default)]
+struct CachedIndexes {
+    indexes: HashMap<PathBuf, (SystemTime, Arc<DirIndex>)>,
+    /// Paths of the indexes, the oldest first
+    order: VecDeque<PathBuf>,
+    entries: usize,
+}
+
+impl CachedIndexes {
+    fn remove(&mut self, path: &Path) {
+        if let Some((_, index)) = self.indexes.remove(path) {
+            self.entries -= index.entries.len();
+            self.order.retain(|p| p != path);
+        }
+    }
+}
+
+/// Indexes of the large directories of a drive
+#[derive(Debug, Default)]
+pub struct DirIndexCache {
+    cached: Mutex<CachedIndexes>,
+}
+
+impl DirIndexCache {
+    fn lock(&self) -> MutexGuard<'_, CachedIndexes> {
+        self.cached.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Index of the directory at `path`, unless it changed since it was kept
+    pub fn get(&self, path: &Path, mtime: SystemTime) -> Option<Arc<DirIndex>> {
+        let mut cached = self.lock();
+        let (kept_mtime, index) = cached.indexes.get(path)?;
+        if *kept_mtime == mtime {
+            return Some(index.clone());
+        }
+        cached.remove(path);
+        None
+    }
+
+    /// Keep the `index` of the directory at `path`, read at `read_at` when it was last modified at `mtime`
+    pub fn insert(&self, path: PathBuf, mtime: SystemTime, read_at: SystemTime, index: Arc<DirIndex>) {
+        let len = index.entries.len();
+        if !(MIN_INDEXED_ENTRIES..=MAX_INDEXED_ENTRIES).contains(&len) {
+            return;
+        }
+        // A change in the same tick of the modification time as the read would go unnoticed
+        if read_at.duration_since(mtime).map_or(true, |age| age < MTIME_GRANULARITY) {
+            return;
+        }
+
+        let mut cached = self.lock();
+        cached.remove(&path);
+        while cached.entries + len > MAX_INDEXED_ENTRIES {
+            let Some(oldest) = cached.order.pop_front() else {
+                break;
+            };
+            if let Some((_, index)) = cached.indexes.remove(&oldest) {
+                cached.entries -= index.entries.len();
+            }
+        }
+        cached.entries += len;
+        cached.order.push_back(path.clone());
+        cached.indexes.insert(path, (mtime, index));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_dir_index() {
+        let index = DirIndex::new(names(&["b/", "a-c/", "a/", "docs__XLDIR__/", "part.1", "c/"]));
+        assert_eq!(index.entries, names(&["a", "a-c", "b", "c", "docs/"]));
+        assert!(index.dir_objs.contains("docs/"));
+        assert!(index.meta_file.is_none());
+        assert!(DirIndex::new(names(&["xl.meta", "part.1"])).meta_file.is_some());
+    }
+
+    #[test]
+    fn test_entries_from() {
+        let index = DirIndex::new(names(&["a/", "a-c/", "ab/", "abc/", "b/", "docs__XLDIR__/"]));
+
+        assert_eq!(index.entries_from(Some("ab"), None), names(&["ab", "abc"]));
+        assert_eq!(index.entries_from(None, Some("b")), names(&["b", "docs/"]));
+        assert!(index.entries_from(Some("z"), None).is_empty());
+
+        // The keys under "a/" sort after "a-b", so "a" is still to walk when resuming there
+        let entries = index.entries_from(None, Some("a-b"));
+        assert_eq!(entries, names(&["a", "a-c", "ab", "abc", "b", "docs/"]));
+        let walked: Vec<_> = entries.iter().filter(|e| !index.is_before(e, "a-b")).collect();
+        assert_eq!(walked, ["a", "a-c", "ab", "abc", "b", "docs/"]);
+
+        // While they sort before "a0"
+        let walked: Vec<_> = index
+            .entries_from(None, Some("a0"))
+            .iter()
+            .filter(|e| !index.is_before(e, "a0"))
+            .collect();
+        assert_eq!(walked, ["ab", "abc", "b", "docs/"]);
+        assert!(index.is_before("docs/", "docs__z"));
+    }
+
+    #[test]
+    fn test_dir_index_cache() {
+        let cache = DirIndexCache::default();
+        let index = Arc::new(DirIndex::new((0..MIN_INDEXED_ENTRIES).map(|i| format!("{i:05}/")).collect()));
+        let path = PathBuf::from("/drive/bucket/logs");
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+
+        // Not kept when read right after a change
+        cache.insert(path.clone(), mtime, mtime + Duration::from_secs(1), index.clone());
+        assert!(cache.get(&path, mtime).is_none());
+
+        cache.insert(path.clone(), mtime, mtime + Duration::from_secs(10), index.clone());
+        assert!(cache.get(&path, mtime).is_some());
+        assert!(cache.get(&path, mtime + Duration::from_secs(20)).is_none());
+        assert!(cache.get(&path, mtime).is_none());
+
+        let small = Arc::new(DirIndex::new(names(&["a/"])));
+        cache.insert(path.clone(), mtime, mtime + Duration::from_secs(10), small);
+        assert!(cache.get(&path, mtime).is_none());
+    }
+}
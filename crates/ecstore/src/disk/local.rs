@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::dir_index::{DirIndex, DirIndexCache};
 use super::error::{Error, Result};
+use super::meta_cache::{Invalidation, META_CACHE_HIT_METRIC, META_CACHE_MISS_METRIC, MetaCache};
 use super::os::{is_root_disk, rename_all};
 use super::{
     BUCKET_META_PREFIX, CheckPartsResp, DeleteOptions, DiskAPI, DiskInfo, DiskInfoOptions, DiskLocation, DiskMetrics,
@@ -48,12 +50,11 @@ use rustfs_filemeta::{
 };
 use rustfs_utils::HashAlgorithm;
 use rustfs_utils::os::get_info;
-use std::collections::HashSet;
 use std::fmt::Debug;
 use std::io::SeekFrom;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use std::{
     fs::Metadata,
     path::{Path, PathBuf},
@@ -101,6 +102,8 @@ pub struct LocalDisk {
     pub major: u64,
     pub minor: u64,
     pub nrrequests: u64,
+    dir_indexes: DirIndexCache,
+    meta_cache: MetaCache,
     // pub id: Mutex<Option<Uuid>>,
     // pub format_data: Mutex<Vec<u8>>,
     // pub format_file_info: Mutex<Option<Metadata>>,
@@ -223,6 +226,8 @@ impl LocalDisk {
             minor: Default::default(),
             major: Default::default(),
             nrrequests: Default::default(),
+            dir_indexes: Default::default(),
+            meta_cache: MetaCache::from_env(),
             // // format_legacy,
             // format_file_info: Mutex::new(format_meta),
             // format_data: Mutex::new(format_data),
@@ -513,6 +518,15 @@ impl LocalDisk {
             return Err(DiskError::FileNotFound);
         }
 
+        // The metadata of the system buckets is not kept, it is not what HEAD requests read
+        let cached = !bucket.starts_with(RUSTFS_META_BUCKET);
+        if cached {
+            if let Some(res) = self.meta_cache.get(file_path.as_ref(), read_data) {
+                return Ok(res);
+            }
+        }
+        let generation = self.meta_cache.generation();
+
         let meta_path = file_path.as_ref().join(Path::new(STORAGE_FORMAT_FILE));
 
         let res = {
@@ -544,6 +558,11 @@ impl LocalDisk {
             return Err(DiskError::FileNotFound);
         }
 
+        if cached {
+            self.meta_cache
+                .insert(file_path.as_ref().to_path_buf(), read_data, &buf, mtime, generation);
+        }
+
         Ok((buf, mtime))
     }
 
@@ -629,6 +648,7 @@ impl LocalDisk {
     }
 
     async fn delete_versions_internal(&self, volume: &str, path: &str, fis: &Vec<FileInfo>) -> Result<()> {
+        let _invalidation = self.meta_invalidation(volume, path, false);
         let volume_dir = self.get_bucket_path(volume)?;
         let xlpath = self.get_object_path(volume, format!("{path}/{STORAGE_FORMAT_FILE}").as_str())?;
 
@@ -776,6 +796,16 @@ impl LocalDisk {
         Ok(f)
     }
 
+    /// Forgets the cached metadata of the objects a change to `path` of `volume` affects, once dropped
+    fn meta_invalidation(&self, volume: &str, path: &str, recursive: bool) -> Option<Invalidation<'_>> {
+        if volume.starts_with(RUSTFS_META_BUCKET) {
+            return None;
+        }
+        let volume_dir = self.get_bucket_path(volume).ok()?;
+        let path = self.get_object_path(volume, path).ok()?;
+        Some(self.meta_cache.invalidation(path, volume_dir, recursive))
+    }
+
     #[allow(dead_code)]
     fn get_metrics(&self) -> DiskMetrics {
         DiskMetrics::default()
@@ -803,6 +833,23 @@ impl LocalDisk {
         Ok(())
     }
 
+    /// Sorted entries of the directory `dir` of `bucket`, read from the drive unless the index of the
+    /// directory is kept and it has not changed since
+    async fn dir_index(&self, bucket: &str, dir: &str) -> Result<Arc<DirIndex>> {
+        let path = self.get_bucket_path(bucket)?.join(dir.trim_matches('/'));
+        let read_at = SystemTime::now();
+        let mtime = fs::metadata(&path).await.ok().and_then(|meta| meta.modified().ok());
+        if let Some(index) = mtime.and_then(|mtime| self.dir_indexes.get(&path, mtime)) {
+            return Ok(index);
+        }
+
+        let index = Arc::new(DirIndex::new(self.list_dir("", bucket, dir, -1).await?));
+        if let Some(mtime) = mtime {
+            self.dir_indexes.insert(path, mtime, read_at, index.clone());
+        }
+        Ok(index)
+    }
+
     async fn scan_dir<W: AsyncWrite + Unpin>(
         &self,
         current: &mut String,
@@ -840,7 +887,7 @@ impl LocalDisk {
             return Ok(());
         }
 
-        let mut entries = match self.list_dir("", &opts.bucket, current, -1).await {
+        let index = match self.dir_index(&opts.bucket, current).await {
             Ok(res) => res,
             Err(e) => {
                 if e != DiskError::VolumeNotFound && e != Error::FileNotFound {
@@ -855,7 +902,7 @@ impl LocalDisk {
             }
         };
 
-        if entries.is_empty() {
+        if index.is_empty() {
             return Ok(());
         }
 
@@ -864,46 +911,11 @@ impl LocalDisk {
 
         let bucket = opts.bucket.as_str();
 
-        let mut dir_objes = HashSet::new();
-
-        // 第一层过滤
-        for item in entries.iter_mut() {
-            let entry = item.clone();
-            // check limit
-            if opts.limit > 0 && *objs_returned >= opts.limit {
-                return Ok(());
-            }
-            // check prefix
-            if let Some(filter_prefix) = &opts.filter_prefix {
-                if !entry.starts_with(filter_prefix) {
-                    *item = "".to_owned();
-                    continue;
-                }
-            }
-
-            if let Some(forward) = &forward {
-                if &entry < forward {
-                    *item = "".to_owned();
-                    continue;
-                }
-            }
-
-            if entry.ends_with(SLASH_SEPARATOR) {
-                if entry.ends_with(GLOBAL_DIR_SUFFIX_WITH_SLASH) {
-                    let entry = format!("{}{}", entry.as_str().trim_end_matches(GLOBAL_DIR_SUFFIX_WITH_SLASH), SLASH_SEPARATOR);
-                    dir_objes.insert(entry.clone());
-                    *item = entry;
-                    continue;
-                }
-
-                *item = entry.trim_end_matches(SLASH_SEPARATOR).to_owned();
-                continue;
-            }
-
-            *item = "".to_owned();
-
-            if entry.ends_with(STORAGE_FORMAT_FILE) {
-                //
+        // The directory is an object itself
+        if let Some(entry) = &index.meta_file {
+            let filtered = opts.filter_prefix.as_ref().is_some_and(|p| !entry.starts_with(p.as_str()));
+            let skipped = forward.as_ref().is_some_and(|f| entry < f);
+            if !filtered && !skipped {
                 let metadata = self
                     .read_metadata(self.get_object_path(bucket, format!("{}/{}", &current, &entry).as_str())?)
                     .await?;
@@ -921,22 +933,11 @@ impl LocalDisk {
                 .await?;
                 *objs_returned += 1;
 
-                // warn!("scan list_dir {}, write_obj done, name: {:?}", &current, &name);
                 return Ok(());
             }
         }
 
-        entries.sort();
-
-        let mut entries = entries.as_slice();
-        if let Some(forward) = &forward {
-            for (i, entry) in entries.iter().enumerate() {
-                if entry >= forward || forward.starts_with(entry.as_str()) {
-                    entries = &entries[i..];
-                    break;
-                }
-            }
-        }
+        let entries = index.entries_from(opts.filter_prefix.as_deref(), forward.as_deref());
 
         let mut dir_stack: Vec<String> = Vec::with_capacity(5);
 
@@ -946,7 +947,7 @@ impl LocalDisk {
                 return Ok(());
             }
 
-            if entry.is_empty() {
+            if forward.as_ref().is_some_and(|f| index.is_before(entry, f)) {
                 continue;
             }
 
@@ -980,7 +981,7 @@ impl LocalDisk {
 
             let mut is_dir_obj = false;
 
-            if let Some(_dir) = dir_objes.get(entry) {
+            if index.dir_objs.contains(entry) {
                 is_dir_obj = true;
                 meta.name
                     .truncate(meta.name.len() - meta.name.chars().last().unwrap().len_utf8());
@@ -1232,11 +1233,13 @@ impl DiskAPI for LocalDisk {
 
     #[tracing::instrument(level = "debug", skip_all)]
     async fn write_all(&self, volume: &str, path: &str, data: Bytes) -> Result<()> {
+        let _invalidation = self.meta_invalidation(volume, path, false);
         self.write_all_public(volume, path, data).await
     }
 
     #[tracing::instrument(skip(self))]
     async fn delete(&self, volume: &str, path: &str, opt: DeleteOptions) -> Result<()> {
+        let _invalidation = self.meta_invalidation(volume, path, opt.recursive);
         let volume_dir = self.get_bucket_path(volume)?;
         if !skip_access_checks(volume) {
             if let Err(e) = access(&volume_dir).await {
@@ -1472,6 +1475,8 @@ impl DiskAPI for LocalDisk {
 
     #[tracing::instrument(skip(self))]
     async fn rename_file(&self, src_volume: &str, src_path: &str, dst_volume: &str, dst_path: &str) -> Result<()> {
+        let _src_invalidation = self.meta_invalidation(src_volume, src_path, true);
+        let _dst_invalidation = self.meta_invalidation(dst_volume, dst_path, true);
         let src_volume_dir = self.get_bucket_path(src_volume)?;
         let dst_volume_dir = self.get_bucket_path(dst_volume)?;
         if !skip_access_checks(src_volume) {
@@ -1717,6 +1722,7 @@ impl DiskAPI for LocalDisk {
         dst_volume: &str,
         dst_path: &str,
     ) -> Result<RenameDataResp> {
+        let _invalidation = self.meta_invalidation(dst_volume, dst_path, false);
         let src_volume_dir = self.get_bucket_path(src_volume)?;
         if !skip_access_checks(src_volume) {
             if let Err(e) = super::fs::access_std(&src_volume_dir) {
@@ -1805,8 +1811,11 @@ impl DiskAPI for LocalDisk {
                 if let Some(data_dir) = has_data_dir {
                     if xlmeta.shard_data_dir_count(&fi.version_id, &Some(data_dir)) == 0 {
                         // TODO: Healing
-                        // remove inlinedata\
-                        Some(data_dir)
+                        // The data of the version replaced goes, inline or not, so that an object
+                        // rewritten into part files leaves none of its former data in xl.meta
+                        let _ = xlmeta.data.remove(vec![fi.version_id.unwrap_or_default(), data_dir]);
+                        // Data kept inline has no directory to remove
+                        (!ver.uses_inline_data()).then_some(data_dir)
                     } else {
                         None
                     }
@@ -1963,6 +1972,7 @@ impl DiskAPI for LocalDisk {
         }
 
         for path in paths.iter() {
+            let _invalidation = self.meta_invalidation(volume, path, true);
             let file_path = volume_dir.join(Path::new(path));
 
             check_path_length(file_path.to_string_lossy().as_ref())?;
@@ -1975,6 +1985,7 @@ impl DiskAPI for LocalDisk {
 
     #[tracing::instrument(skip(self))]
     async fn update_metadata(&self, volume: &str, path: &str, fi: FileInfo, opts: &UpdateMetadataOpts) -> Result<()> {
+        let _invalidation = self.meta_invalidation(volume, path, false);
         if !fi.metadata.is_empty() {
             let volume_dir = self.get_bucket_path(volume)?;
             let file_path = volume_dir.join(Path::new(&path));
@@ -2012,6 +2023,7 @@ impl DiskAPI for LocalDisk {
 
     #[tracing::instrument(skip(self))]
     async fn write_metadata(&self, _org_volume: &str, volume: &str, path: &str, fi: FileInfo) -> Result<()> {
+        let _invalidation = self.meta_invalidation(volume, path, false);
         let p = self.get_object_path(volume, format!("{path}/{STORAGE_FORMAT_FILE}").as_str())?;
 
         let mut meta = FileMeta::new();
@@ -2074,6 +2086,7 @@ impl DiskAPI for LocalDisk {
         force_del_marker: bool,
         opts: DeleteOptions,
     ) -> Result<()> {
+        let _invalidation = self.meta_invalidation(volume, path, false);
         if path.starts_with(SLASH_SEPARATOR) {
             return self
                 .delete(
@@ -2236,6 +2249,7 @@ impl DiskAPI for LocalDisk {
 
     #[tracing::instrument(skip(self))]
     async fn delete_volume(&self, volume: &str) -> Result<()> {
+        let _invalidation = self.meta_invalidation(volume, "", true);
         let p = self.get_bucket_path(volume)?;
 
         // TODO: 不能用递归删除，如果目录下面有文件，返回 errVolumeNotEmpty
@@ -2259,6 +2273,12 @@ impl DiskAPI for LocalDisk {
         info.mount_path = self.path().to_str().unwrap().to_string();
         info.endpoint = self.endpoint.to_string();
         info.scanning = self.scanning.load(Ordering::SeqCst) == 1;
+        info.metrics
+            .api_calls
+            .insert(META_CACHE_HIT_METRIC.to_string(), self.meta_cache.hits());
+        info.metrics
+            .api_calls
+            .insert(META_CACHE_MISS_METRIC.to_string(), self.meta_cache.misses());
 
         Ok(info)
     }
@@ -2286,6 +2306,8 @@ async fn get_disk_info(drive_path: PathBuf) -> Result<(rustfs_utils::os::DiskInf
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::disk::RUSTFS_META_TMP_BUCKET;
+    use crate::disk::dir_index::MIN_INDEXED_ENTRIES;
 
     #[tokio::test]
     async fn test_skip_access_checks() {
@@ -2293,7 +2315,7 @@ mod test {
 
         let vols = [
             super::super::RUSTFS_META_TMP_DELETED_BUCKET,
-            super::super::RUSTFS_META_TMP_BUCKET,
+            RUSTFS_META_TMP_BUCKET,
             super::super::RUSTFS_META_MULTIPART_BUCKET,
             RUSTFS_META_BUCKET,
         ];
@@ -2502,7 +2524,7 @@ mod test {
         // It only checks length and platform-specific special characters
         // System volume names are valid according to the current implementation
         assert!(LocalDisk::is_valid_volname(RUSTFS_META_BUCKET));
-        assert!(LocalDisk::is_valid_volname(super::super::RUSTFS_META_TMP_BUCKET));
+        assert!(LocalDisk::is_valid_volname(RUSTFS_META_TMP_BUCKET));
 
         // Testing platform-specific behavior for special characters
         #[cfg(windows)]
@@ -2633,4 +2655,197 @@ mod test {
         #[cfg(not(windows))]
         assert!(!is_root_path("\\"));
     }
+
+    /// Names a walk of `opts` returns, after the marker it resumed at
+    async fn walk_names(disk: &LocalDisk, opts: WalkDirOptions) -> Vec<String> {
+        let marker = opts.forward_to.clone();
+        let mut buf = Vec::new();
+        disk.walk_dir(opts, &mut buf).await.unwrap();
+        // The walk leaves the stream open, so it ends with the data
+        let mut reader = rustfs_filemeta::MetacacheReader::new(std::io::Cursor::new(buf));
+        let mut names = Vec::new();
+        while let Ok(Some(entry)) = reader.peek().await {
+            if marker.as_ref().is_none_or(|marker| &entry.name > marker) {
+                names.push(entry.name);
+            }
+        }
+        names
+    }
+
+    #[tokio::test]
+    async fn test_walk_dir_large_directory() {
+        let test_dir = "./test_walk_dir_large_directory";
+        fs::create_dir_all(&test_dir).await.unwrap();
+        let endpoint = Endpoint::try_from(test_dir).unwrap();
+        let disk = LocalDisk::new(&endpoint, false).await.unwrap();
+        disk.make_volume("bucket").await.unwrap();
+
+        let mut keys: Vec<String> = (0..MIN_INDEXED_ENTRIES + 10).map(|i| format!("logs/{i:05}.log")).collect();
+        keys.extend(["logs/z/x.log".to_string(), "logs/z-x.log".to_string()]);
+        for key in &keys {
+            let fi = FileInfo {
+                volume: "bucket".to_string(),
+                name: key.clone(),
+                mod_time: Some(OffsetDateTime::now_utc()),
+                fresh: true,
+                ..Default::default()
+            };
+            disk.write_metadata("", "bucket", key, fi).await.unwrap();
+        }
+        keys.sort();
+
+        // Old enough for its index to be kept
+        let dir = disk.get_bucket_path("bucket").unwrap().join("logs");
+        let mtime = SystemTime::now() - Duration::from_secs(60);
+        std::fs::File::open(&dir).unwrap().set_modified(mtime).unwrap();
+
+        let opts = |forward_to: Option<&str>, limit| WalkDirOptions {
+            bucket: "bucket".to_string(),
+            base_dir: "logs/".to_string(),
+            recursive: true,
+            forward_to: forward_to.map(str::to_owned),
+            limit,
+            ..Default::default()
+        };
+
+        // Every page resumes where the previous one stopped, like a continuation token does
+        let mut walked = Vec::new();
+        let mut marker: Option<String> = None;
+        loop {
+            let page = walk_names(&disk, opts(marker.as_deref(), 100)).await;
+            let Some(last) = page.last().cloned() else {
+                break;
+            };
+            walked.extend(page.into_iter().filter(|name| !name.ends_with('/')));
+            marker = Some(last);
+        }
+        assert_eq!(walked, keys);
+        assert!(disk.dir_indexes.get(&dir, mtime).is_some());
+
+        // A start-after between the names of a directory and the keys under it
+        let page = walk_names(&disk, opts(Some("logs/z."), 0)).await;
+        assert_eq!(page, ["logs/z/", "logs/z/x.log"]);
+        let page = walk_names(&disk, opts(Some("logs/00500.log"), 3)).await;
+        assert_eq!(page, ["logs/00501.log", "logs/00502.log"]);
+
+        // A new object changes the directory, so its index is read again
+        let fi = FileInfo {
+            volume: "bucket".to_string(),
+            name: "logs/new.log".to_string(),
+            mod_time: Some(OffsetDateTime::now_utc()),
+            fresh: true,
+            ..Default::default()
+        };
+        disk.write_metadata("", "bucket", "logs/new.log", fi).await.unwrap();
+        let page = walk_names(&disk, opts(Some("logs/01033.log"), 0)).await;
+        assert_eq!(page, ["logs/new.log", "logs/z-x.log", "logs/z/", "logs/z/x.log"]);
+
+        let _ = fs::remove_dir_all(&test_dir).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_read_version_meta_cache() {
+        let test_dir = "./test_read_version_meta_cache";
+        fs::create_dir_all(&test_dir).await.unwrap();
+        let endpoint = Endpoint::try_from(test_dir).unwrap();
+        let disk = LocalDisk::new(&endpoint, false).await.unwrap();
+        disk.make_volume("bucket").await.unwrap();
+
+        let write = |size| FileInfo {
+            volume: "bucket".to_string(),
+            name: "object".to_string(),
+            size,
+            mod_time: Some(OffsetDateTime::now_utc()),
+            fresh: true,
+            ..Default::default()
+        };
+        let opts = ReadOptions::default();
+        let read = || disk.read_version("", "bucket", "object", "", &opts);
+
+        disk.write_metadata("", "bucket", "object", write(1)).await.unwrap();
+        assert_eq!(read().await.unwrap().size, 1);
+        assert_eq!(read().await.unwrap().size, 1);
+        assert_eq!((disk.meta_cache.hits(), disk.meta_cache.misses()), (1, 1));
+
+        // A write of the object is read back, not the metadata kept before it
+        disk.write_metadata("", "bucket", "object", write(2)).await.unwrap();
+        assert_eq!(read().await.unwrap().size, 2);
+
+        disk.delete(
+            "bucket",
+            "object",
+            DeleteOptions {
+                recursive: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(read().await.is_err());
+
+        let info = disk.disk_info(&DiskInfoOptions::default()).await.unwrap();
+        assert_eq!(info.metrics.api_calls[META_CACHE_HIT_METRIC], 1);
+
+        let _ = fs::remove_dir_all(&test_dir).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rename_data_inline_overwrite() {
+        let test_dir = "./test_rename_data_inline_overwrite";
+        fs::create_dir_all(&test_dir).await.unwrap();
+        let endpoint = Endpoint::try_from(test_dir).unwrap();
+        let disk = LocalDisk::new(&endpoint, false).await.unwrap();
+        disk.make_volume("bucket").await.unwrap();
+
+        let version = |inline: bool| {
+            let mut fi = FileInfo {
+                volume: "bucket".to_string(),
+                name: "object".to_string(),
+                size: 5,
+                data_dir: Some(Uuid::new_v4()),
+                mod_time: Some(OffsetDateTime::now_utc()),
+                ..Default::default()
+            };
+            if inline {
+                fi.data = Some(Bytes::from_static(b"small"));
+                fi.set_inline_data();
+            }
+            fi
+        };
+        let inline_data = || async {
+            let buf = disk.read_xl("bucket", "object", true).await.unwrap().buf;
+            FileMeta::load(&buf).unwrap().data.find(&Uuid::nil().to_string()).unwrap()
+        };
+
+        let inline = version(true);
+        let resp = disk
+            .rename_data(RUSTFS_META_TMP_BUCKET, "put-1", inline, "bucket", "object")
+            .await
+            .unwrap();
+        assert!(resp.old_data_dir.is_none());
+        assert_eq!(inline_data().await.as_deref(), Some(b"small".as_slice()));
+
+        // Rewritten into part files, the object keeps none of its inline data, and has no directory to remove
+        let parts = version(false);
+        let part = format!("put-2/{}/part.1", parts.data_dir.unwrap());
+        disk.write_all(RUSTFS_META_TMP_BUCKET, &part, Bytes::from_static(b"large"))
+            .await
+            .unwrap();
+        let resp = disk
+            .rename_data(RUSTFS_META_TMP_BUCKET, "put-2", parts.clone(), "bucket", "object")
+            .await
+            .unwrap();
+        assert!(resp.old_data_dir.is_none());
+        assert!(inline_data().await.is_none());
+
+        // Rewritten inline, the directory of its part files is to be removed
+        let resp = disk
+            .rename_data(RUSTFS_META_TMP_BUCKET, "put-3", version(true), "bucket", "object")
+            .await
+            .unwrap();
+        assert_eq!(resp.old_data_dir, parts.data_dir);
+        assert!(inline_data().await.is_some());
+
+        let _ = fs::remove_dir_all(&test_dir).await;
+    }
 }
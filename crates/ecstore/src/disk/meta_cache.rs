@@ -0,0 +1,297 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Metadata of the objects of a drive, kept in memory between reads
+//!
+//! Every read of an object, a HEAD included, reads its `xl.meta` on each drive of the set. The metadata
+//! read is kept by the drive instead, until the drive writes or deletes the object. Since every change
+//! to a drive goes through the drive, whichever node it comes from, this keeps the cache of each member
+//! of a set in step with what it holds; a time to live bounds how long a change made behind the back of
+//! the server goes unnoticed.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+use time::OffsetDateTime;
+
+/// Bytes of metadata a drive keeps, 0 to keep none
+pub const ENV_META_CACHE_SIZE: &str = "RUSTFS_META_CACHE_SIZE";
+
+/// Seconds the metadata of an object is kept for
+pub const ENV_META_CACHE_TTL: &str = "RUSTFS_META_CACHE_TTL";
+
+pub const DEFAULT_META_CACHE_SIZE: usize = 32 << 20;
+
+pub const DEFAULT_META_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Name of the count of reads answered from the cache in the metrics of a drive
+pub const META_CACHE_HIT_METRIC: &str = "MetaCacheHit";
+
+/// Name of the count of reads that went to the drive in the metrics of a drive
+pub const META_CACHE_MISS_METRIC: &str = "MetaCacheMiss";
+
+/// Largest share of the cache the metadata of a single object may take
+const MAX_ENTRY_SHARE: usize = 64;
+
+#[derive(Debug)]
+struct Entry {
+    buf: Vec<u8>,
+    mtime: Option<OffsetDateTime>,
+    /// Whether `buf` holds the inline data of the object
+    data: bool,
+    cached_at: Instant,
+}
+
+#[derive(Debug)]
+struct Entries {
+    lru: LruCache<PathBuf, Entry>,
+    size: usize,
+    /// Count of invalidations, to drop reads that raced one
+    generation: u64,
+}
+
+impl Entries {
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.lru.pop(path) {
+            self.size -= entry_size(path, &entry.buf);
+        }
+    }
+}
+
+fn entry_size(path: &Path, buf: &[u8]) -> usize {
+    path.as_os_str().len() + buf.len()
+}
+
+/// `xl.meta` of the objects of a drive, keyed by the directory of the object
+#[derive(Debug)]
+pub struct MetaCache {
+    entries: Mutex<Entries>,
+    max_size: usize,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl MetaCache {
+    pub fn new(max_size: usize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                lru: LruCache::unbounded(),
+                size: 0,
+                generation: 0,
+            }),
+            max_size,
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cache sized by `RUSTFS_META_CACHE_SIZE` and `RUSTFS_META_CACHE_TTL`
+    pub fn from_env() -> Self {
+        let max_size = env::var(ENV_META_CACHE_SIZE)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_META_CACHE_SIZE);
+        let ttl = env::var(ENV_META_CACHE_TTL)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map_or(DEFAULT_META_CACHE_TTL, Duration::from_secs);
+        Self::new(max_size, ttl)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_size > 0 && !self.ttl.is_zero()
+    }
+
+    /// Metadata of the object at `path`, read with its inline data when `data` is set
+    pub fn get(&self, path: &Path, data: bool) -> Option<(Vec<u8>, Option<OffsetDateTime>)> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let mut entries = self.lock();
+        let found = match entries.lru.get(path) {
+            Some(entry) if entry.cached_at.elapsed() >= self.ttl => {
+                entries.remove(path);
+                None
+            }
+            Some(entry) if entry.data == data => Some((entry.buf.clone(), entry.mtime)),
+            _ => None,
+        };
+        drop(entries);
+
+        match found {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        found
+    }
+
+    /// Count of invalidations so far, to pass to [`Self::insert`] for a read started now
+    pub fn generation(&self) -> u64 {
+        self.lock().generation
+    }
+
+    /// Keep the metadata read for the object at `path`, unless it was invalidated since `generation`
+    pub fn insert(&self, path: PathBuf, data: bool, buf: &[u8], mtime: Option<OffsetDateTime>, generation: u64) {
+        let size = entry_size(&path, buf);
+        if !self.is_enabled() || size > self.max_size / MAX_ENTRY_SHARE {
+            return;
+        }
+
+        let mut entries = self.lock();
+        if entries.generation != generation {
+            return;
+        }
+        entries.remove(&path);
+        while entries.size + size > self.max_size {
+            let Some((oldest, entry)) = entries.lru.pop_lru() else {
+                break;
+            };
+            entries.size -= entry_size(&oldest, &entry.buf);
+        }
+        entries.size += size;
+        entries.lru.put(
+            path,
+            Entry {
+                buf: buf.to_vec(),
+                mtime,
+                data,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Forget the metadata of the objects a change to `path` may affect: the objects `path` is part of, up to
+    /// `root`, and when `recursive` is set the objects under `path`
+    pub fn invalidate(&self, path: &Path, root: &Path, recursive: bool) {
+        let mut entries = self.lock();
+        entries.generation += 1;
+        if entries.lru.is_empty() {
+            return;
+        }
+        for ancestor in path.ancestors().take_while(|p| p.starts_with(root) && *p != root) {
+            entries.remove(ancestor);
+        }
+        if recursive {
+            let under: Vec<PathBuf> = entries
+                .lru
+                .iter()
+                .filter(|(key, _)| key.starts_with(path))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in under {
+                entries.remove(&key);
+            }
+        }
+    }
+
+    /// Guard calling [`Self::invalidate`] when dropped, so that the change it guards has been made, or
+    /// failed, whichever way the change returns
+    pub fn invalidation(&self, path: PathBuf, root: PathBuf, recursive: bool) -> Invalidation<'_> {
+        Invalidation {
+            cache: self,
+            path,
+            root,
+            recursive,
+        }
+    }
+
+    /// Reads answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Reads that went to the drive
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+pub struct Invalidation<'a> {
+    cache: &'a MetaCache,
+    path: PathBuf,
+    root: PathBuf,
+    recursive: bool,
+}
+
+impl Drop for Invalidation<'_> {
+    fn drop(&mut self) {
+        self.cache.invalidate(&self.path, &self.root, self.recursive);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_cache() {
+        let cache = MetaCache::new(64 << 10, Duration::from_secs(60));
+        let path = PathBuf::from("/drive/bucket/object");
+
+        assert!(cache.get(&path, false).is_none());
+        cache.insert(path.clone(), false, b"meta", None, cache.generation());
+        assert_eq!(cache.get(&path, false).unwrap().0, b"meta");
+        // A read of the inline data is not answered by metadata read without it
+        assert!(cache.get(&path, true).is_none());
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+
+        let root = Path::new("/drive/bucket");
+        cache.invalidate(&path.join("xl.meta"), root, false);
+        assert!(cache.get(&path, false).is_none());
+
+        // A read that raced a write is not kept
+        let generation = cache.generation();
+        drop(cache.invalidation(path.clone(), root.to_path_buf(), false));
+        cache.insert(path.clone(), false, b"stale", None, generation);
+        assert!(cache.get(&path, false).is_none());
+
+        cache.insert(path.clone(), false, b"meta", None, cache.generation());
+        cache.invalidate(Path::new("/drive/bucket/other"), root, true);
+        assert!(cache.get(&path, false).is_some());
+        cache.invalidate(root, root, true);
+        assert!(cache.get(&path, false).is_none());
+    }
+
+    #[test]
+    fn test_meta_cache_limits() {
+        let cache = MetaCache::new(64 * 1024, Duration::from_secs(60));
+        let buf = vec![0u8; 900];
+        for i in 0..100 {
+            cache.insert(PathBuf::from(format!("/drive/bucket/{i:03}")), false, &buf, None, cache.generation());
+        }
+        // The least recently used objects made room for the last ones
+        assert!(cache.get(Path::new("/drive/bucket/000"), false).is_none());
+        assert!(cache.get(Path::new("/drive/bucket/099"), false).is_some());
+        assert!(cache.lock().size <= 64 * 1024);
+
+        cache.insert(PathBuf::from("/drive/bucket/large"), false, &[0u8; 2048], None, cache.generation());
+        assert!(cache.get(Path::new("/drive/bucket/large"), false).is_none());
+
+        let expiring = MetaCache::new(64 * 1024, Duration::from_millis(10));
+        expiring.insert(PathBuf::from("/drive/bucket/a"), false, b"meta", None, expiring.generation());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(expiring.get(Path::new("/drive/bucket/a"), false).is_none());
+    }
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod dir_index;
 pub mod endpoint;
 pub mod error;
 pub mod error_conv;
@@ -19,6 +20,7 @@ pub mod error_reduce;
 pub mod format;
 pub mod fs;
 pub mod local;
+pub mod meta_cache;
 pub mod os;
 
 pub const RUSTFS_META_BUCKET: &str = ".rustfs.sys";
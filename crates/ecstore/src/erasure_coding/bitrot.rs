@@ -180,10 +180,8 @@ where
     }
 }
 
+/// Size of a shard file on disk, every shard is prefixed with its hash whatever the algorithm
 pub fn bitrot_shard_file_size(size: usize, shard_size: usize, algo: HashAlgorithm) -> usize {
-    if algo != HashAlgorithm::HighwayHash256S {
-        return size;
-    }
     size.div_ceil(shard_size) * algo.size() + size
 }
 
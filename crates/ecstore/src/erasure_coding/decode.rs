@@ -18,13 +18,58 @@ use crate::disk::error::Error;
 use crate::disk::error_reduce::reduce_errs;
 use futures::stream::{FuturesUnordered, StreamExt};
 use pin_project_lite::pin_project;
+use std::env;
 use std::io;
 use std::io::ErrorKind;
+use std::sync::{Arc, LazyLock};
 use tokio::io::AsyncRead;
 use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinHandle;
 use tracing::error;
 
+/// Most blocks a read decodes ahead of the one being written, 0 to read one block at a time
+pub const ENV_READ_AHEAD_BLOCKS: &str = "RUSTFS_READ_AHEAD_BLOCKS";
+
+/// Bytes a read of a part must span for blocks to be read ahead
+pub const ENV_READ_AHEAD_MIN_SIZE: &str = "RUSTFS_READ_AHEAD_MIN_SIZE";
+
+pub const DEFAULT_READ_AHEAD_BLOCKS: usize = 4;
+
+pub const DEFAULT_READ_AHEAD_MIN_SIZE: usize = 4 << 20;
+
+static READ_AHEAD: LazyLock<ReadAhead> = LazyLock::new(ReadAhead::from_env);
+
+/// How far the shards of a read are fetched and decoded ahead of the writer
+///
+/// The window starts at two blocks and grows by one, up to `max_blocks`, each time the writer has to wait
+/// for the drives, so a slow client holds little in memory while a fast one keeps every drive busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadAhead {
+    pub max_blocks: usize,
+    pub min_size: usize,
+}
+
+impl ReadAhead {
+    /// Read ahead set by `RUSTFS_READ_AHEAD_BLOCKS` and `RUSTFS_READ_AHEAD_MIN_SIZE`
+    pub fn from_env() -> Self {
+        let max_blocks = env::var(ENV_READ_AHEAD_BLOCKS)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_READ_AHEAD_BLOCKS);
+        let min_size = env::var(ENV_READ_AHEAD_MIN_SIZE)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_READ_AHEAD_MIN_SIZE);
+        Self { max_blocks, min_size }
+    }
+
+    fn applies(&self, length: usize, block_size: usize) -> bool {
+        self.max_blocks > 0 && length >= self.min_size && length > block_size
+    }
+}
+
 pin_project! {
 pub(crate) struct ParallelReader<R> {
     #[pin]
@@ -146,6 +191,116 @@ where
     }
 }
 
+/// Shards of a block as read, with the outcome of decoding them
+type DecodedBlock = (Vec<Option<Vec<u8>>>, Vec<Option<Error>>, io::Result<()>);
+
+async fn read_block<R>(reader: &mut ParallelReader<R>, erasure: &Erasure) -> DecodedBlock
+where
+    R: AsyncRead + Unpin + Send + Sync,
+{
+    let (mut shards, errs) = reader.read().await;
+
+    if !reader.can_decode(&shards) {
+        error!("erasure decode can_decode errs: {:?}", &errs);
+        return (shards, errs, Err(Error::ErasureReadQuorum.into()));
+    }
+
+    // Decode the shards
+    let decoded = erasure.decode_data(&mut shards);
+    if let Err(e) = &decoded {
+        error!("erasure decode decode_data err: {:?}", e);
+    }
+
+    (shards, errs, decoded)
+}
+
+/// Blocks decoded by a task of their own, ahead of the writer
+struct ReadAheadBlocks {
+    blocks: mpsc::UnboundedReceiver<DecodedBlock>,
+    window: Arc<Semaphore>,
+    size: usize,
+    max_size: usize,
+    task: JoinHandle<()>,
+}
+
+impl ReadAheadBlocks {
+    fn spawn<R>(mut reader: ParallelReader<R>, erasure: Erasure, count: usize, max_size: usize) -> Self
+    where
+        R: AsyncRead + Unpin + Send + Sync + 'static,
+    {
+        let size = max_size.min(2);
+        let window = Arc::new(Semaphore::new(size));
+        let (tx, blocks) = mpsc::unbounded_channel();
+
+        let permits = window.clone();
+        let task = tokio::spawn(async move {
+            for _ in 0..count {
+                // A permit is handed back once the writer takes the block
+                let Ok(permit) = permits.acquire().await else {
+                    return;
+                };
+                permit.forget();
+
+                let block = read_block(&mut reader, &erasure).await;
+                let failed = block.2.is_err();
+                if tx.send(block).is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            blocks,
+            window,
+            size,
+            max_size,
+            task,
+        }
+    }
+
+    async fn next(&mut self) -> Option<DecodedBlock> {
+        let block = match self.blocks.try_recv() {
+            Ok(block) => block,
+            Err(mpsc::error::TryRecvError::Empty) => {
+                // The writer caught up with the drives, read further ahead
+                if self.size < self.max_size {
+                    self.size += 1;
+                    self.window.add_permits(1);
+                }
+                self.blocks.recv().await?
+            }
+            Err(mpsc::error::TryRecvError::Disconnected) => return None,
+        };
+
+        self.window.add_permits(1);
+        Some(block)
+    }
+}
+
+impl Drop for ReadAheadBlocks {
+    fn drop(&mut self) {
+        self.window.close();
+        self.task.abort();
+    }
+}
+
+enum BlockReader<R> {
+    Inline(ParallelReader<R>),
+    Ahead(ReadAheadBlocks),
+}
+
+impl<R> BlockReader<R>
+where
+    R: AsyncRead + Unpin + Send + Sync,
+{
+    async fn next(&mut self, erasure: &Erasure) -> Option<DecodedBlock> {
+        match self {
+            BlockReader::Inline(reader) => Some(read_block(reader, erasure).await),
+            BlockReader::Ahead(blocks) => blocks.next().await,
+        }
+    }
+}
+
 /// 获取数据块总长度
 fn get_data_block_len(shards: &[Option<Vec<u8>>], data_blocks: usize) -> usize {
     let mut size = 0;
@@ -227,7 +382,24 @@ impl Erasure {
     ) -> (usize, Option<std::io::Error>)
     where
         W: AsyncWrite + Send + Sync + Unpin,
-        R: AsyncRead + Unpin + Send + Sync,
+        R: AsyncRead + Unpin + Send + Sync + 'static,
+    {
+        self.decode_with(writer, readers, offset, length, total_length, *READ_AHEAD)
+            .await
+    }
+
+    pub async fn decode_with<W, R>(
+        &self,
+        writer: &mut W,
+        readers: Vec<Option<BitrotReader<R>>>,
+        offset: usize,
+        length: usize,
+        total_length: usize,
+        read_ahead: ReadAhead,
+    ) -> (usize, Option<std::io::Error>)
+    where
+        W: AsyncWrite + Send + Sync + Unpin,
+        R: AsyncRead + Unpin + Send + Sync + 'static,
     {
         if readers.len() != self.data_shards + self.parity_shards {
             return (0, Some(io::Error::new(ErrorKind::InvalidInput, "Invalid number of readers")));
@@ -245,11 +417,10 @@ impl Erasure {
 
         let mut written = 0;
 
-        let mut reader = ParallelReader::new(readers, self.clone(), offset, total_length);
-
         let start = offset / self.block_size;
         let end = (offset + length) / self.block_size;
 
+        let mut blocks = Vec::with_capacity(end - start + 1);
         for i in start..=end {
             let (block_offset, block_length) = if start == end {
                 (offset % self.block_size, length)
@@ -266,7 +437,22 @@ impl Erasure {
                 break;
             }
 
-            let (mut shards, errs) = reader.read().await;
+            blocks.push((block_offset, block_length));
+        }
+
+        let reader = ParallelReader::new(readers, self.clone(), offset, total_length);
+        let mut reader = if read_ahead.applies(length, self.block_size) {
+            BlockReader::Ahead(ReadAheadBlocks::spawn(reader, self.clone(), blocks.len(), read_ahead.max_blocks))
+        } else {
+            BlockReader::Inline(reader)
+        };
+
+        for (block_offset, block_length) in blocks {
+            let Some((shards, errs, decoded)) = reader.next(self).await else {
+                error!("erasure decode read ahead stopped");
+                ret_err = Some(io::Error::other("erasure read ahead stopped"));
+                break;
+            };
 
             if ret_err.is_none() {
                 if let (_, Some(err)) = reduce_errs(&errs, &[]) {
@@ -276,15 +462,7 @@ impl Erasure {
                 }
             }
 
-            if !reader.can_decode(&shards) {
-                error!("erasure decode can_decode errs: {:?}", &errs);
-                ret_err = Some(Error::ErasureReadQuorum.into());
-                break;
-            }
-
-            // Decode the shards
-            if let Err(e) = self.decode_data(&mut shards) {
-                error!("erasure decode decode_data err: {:?}", e);
+            if let Err(e) = decoded {
                 ret_err = Some(e);
                 break;
             }
@@ -428,6 +606,74 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_decode_read_ahead() {
+        const BLOCK_SIZE: usize = 64;
+        const DATA_SHARDS: usize = 4;
+        const PARITY_SHARDS: usize = 2;
+
+        let erasure = Erasure::new(DATA_SHARDS, PARITY_SHARDS, BLOCK_SIZE);
+        let data: Vec<u8> = (0..BLOCK_SIZE * 9 + 17).map(|i| (i % 251) as u8).collect();
+        let shard_files = encode_shard_files(&erasure, &data).await;
+
+        let inline = ReadAhead {
+            max_blocks: 0,
+            min_size: 0,
+        };
+        let ahead = ReadAhead {
+            max_blocks: 3,
+            min_size: 0,
+        };
+
+        for (offset, length) in [
+            (0, data.len()),
+            (10, BLOCK_SIZE * 4),
+            (BLOCK_SIZE * 2 + 5, data.len() - BLOCK_SIZE * 2 - 5),
+        ] {
+            for offline in [0, PARITY_SHARDS] {
+                for read_ahead in [inline, ahead] {
+                    let readers = shard_files
+                        .iter()
+                        .enumerate()
+                        .map(|(i, file)| {
+                            (i >= offline).then(|| {
+                                let start = offset / BLOCK_SIZE * (HashAlgorithm::HighwayHash256.size() + erasure.shard_size());
+                                BitrotReader::new(
+                                    Cursor::new(file[start..].to_vec()),
+                                    erasure.shard_size(),
+                                    HashAlgorithm::HighwayHash256,
+                                )
+                            })
+                        })
+                        .collect();
+
+                    let mut out = Vec::new();
+                    let (written, err) = erasure
+                        .decode_with(&mut out, readers, offset, length, data.len(), read_ahead)
+                        .await;
+                    assert!(err.is_none(), "{offset} {length} {offline} {read_ahead:?}: {err:?}");
+                    assert_eq!(written, length);
+                    assert_eq!(out, &data[offset..offset + length]);
+                }
+            }
+        }
+    }
+
+    async fn encode_shard_files(erasure: &Erasure, data: &[u8]) -> Vec<Vec<u8>> {
+        let mut writers: Vec<_> = (0..erasure.total_shard_count())
+            .map(|_| BitrotWriter::new(Cursor::new(Vec::new()), erasure.shard_size(), HashAlgorithm::HighwayHash256))
+            .collect();
+
+        for block in data.chunks(erasure.block_size) {
+            let shards = erasure.encode_data(block).unwrap();
+            for (writer, shard) in writers.iter_mut().zip(shards) {
+                writer.write(&shard).await.unwrap();
+            }
+        }
+
+        writers.into_iter().map(|w| w.into_inner().into_inner()).collect()
+    }
+
     async fn create_reader(
         shard_size: usize,
         num_shards: usize,
@@ -124,6 +124,24 @@ pub enum StorageError {
     #[error("Prefix access is denied:{0}/{1}")]
     PrefixAccessDenied(String, String),
 
+    #[error("The SSE-C key does not match the key of {0}/{1}")]
+    InvalidSseCustomerKey(String, String),
+
+    #[error("The checksum sent for {0}/{1} does not match the checksum of the data")]
+    BadDigest(String, String),
+
+    #[error("At least one of the preconditions on {0}/{1} did not hold")]
+    PreconditionFailed(String, String),
+
+    #[error("The requested range is not satisfiable for an object of {0} bytes")]
+    InvalidRange(i64),
+
+    #[error("Bucket quota exceeded: {0}")]
+    BucketQuotaExceeded(String),
+
+    #[error("The write offset does not match the size {2} of {0}/{1}")]
+    InvalidWriteOffset(String, String, i64),
+
     #[error("Invalid UploadID KeyCombination: {0}/{1}")]
     InvalidUploadIDKeyCombination(String, String),
 
@@ -386,6 +404,12 @@ impl Clone for StorageError {
             StorageError::StorageFull => StorageError::StorageFull,
             StorageError::SlowDown => StorageError::SlowDown,
             StorageError::PrefixAccessDenied(a, b) => StorageError::PrefixAccessDenied(a.clone(), b.clone()),
+            StorageError::InvalidSseCustomerKey(a, b) => StorageError::InvalidSseCustomerKey(a.clone(), b.clone()),
+            StorageError::BadDigest(a, b) => StorageError::BadDigest(a.clone(), b.clone()),
+            StorageError::PreconditionFailed(a, b) => StorageError::PreconditionFailed(a.clone(), b.clone()),
+            StorageError::InvalidRange(a) => StorageError::InvalidRange(*a),
+            StorageError::BucketQuotaExceeded(a) => StorageError::BucketQuotaExceeded(a.clone()),
+            StorageError::InvalidWriteOffset(a, b, c) => StorageError::InvalidWriteOffset(a.clone(), b.clone(), *c),
             StorageError::InvalidUploadIDKeyCombination(a, b) => {
                 StorageError::InvalidUploadIDKeyCombination(a.clone(), b.clone())
             }
@@ -476,6 +500,12 @@ impl StorageError {
             StorageError::TooManyOpenFiles => 0x36,
             StorageError::NoHealRequired => 0x37,
             StorageError::Lock(_) => 0x38,
+            StorageError::InvalidSseCustomerKey(_, _) => 0x39,
+            StorageError::BadDigest(_, _) => 0x3A,
+            StorageError::PreconditionFailed(_, _) => 0x3B,
+            StorageError::InvalidRange(_) => 0x3C,
+            StorageError::BucketQuotaExceeded(_) => 0x3D,
+            StorageError::InvalidWriteOffset(_, _, _) => 0x3E,
         }
     }
 
@@ -541,6 +571,16 @@ impl StorageError {
             0x36 => Some(StorageError::TooManyOpenFiles),
             0x37 => Some(StorageError::NoHealRequired),
             0x38 => Some(StorageError::Lock(rustfs_lock::LockError::internal("Generic lock error".to_string()))),
+            0x39 => Some(StorageError::InvalidSseCustomerKey(Default::default(), Default::default())),
+            0x3A => Some(StorageError::BadDigest(Default::default(), Default::default())),
+            0x3B => Some(StorageError::PreconditionFailed(Default::default(), Default::default())),
+            0x3C => Some(StorageError::InvalidRange(Default::default())),
+            0x3D => Some(StorageError::BucketQuotaExceeded(Default::default())),
+            0x3E => Some(StorageError::InvalidWriteOffset(
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )),
             _ => None,
         }
     }
@@ -0,0 +1,412 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Object access heat map
+//!
+//! Every node counts the reads it serves per object. Counts decay each time the
+//! map is persisted, so the map follows what is hot now rather than what was hot
+//! once. Each node persists its own snapshot under the meta bucket; reports merge
+//! the live local map with the last snapshot of every other node.
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    hash::{BuildHasher, RandomState},
+    sync::{
+        Arc, LazyLock, RwLock,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rustfs_common::globals::GLOBAL_Local_Node_Name;
+use rustfs_utils::path::SLASH_SEPARATOR;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    config::com::{read_config, save_config},
+    error::{Error, Result},
+    global::get_global_endpoints,
+    store::ECStore,
+};
+
+/// How often each node persists (and decays) its heat map
+pub const HEAT_MAP_PERSIST_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Objects tracked per node; accesses to untracked objects are dropped until decay frees room
+const MAX_TRACKED_OBJECTS: usize = 100_000;
+
+/// Shards of the map, so concurrent reads of different objects rarely share a lock
+const HEAT_MAP_SHARDS: usize = 64;
+
+const HEAT_MAP_DIR: &str = ".heat";
+
+/// Access counters of one object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectHeat {
+    pub bucket: String,
+    pub object: String,
+    /// Decayed access count
+    pub hits: u64,
+    /// Unix time of the latest access, in seconds
+    #[serde(rename = "lastAccess")]
+    pub last_access: u64,
+}
+
+/// Access counters summed over objects sharing a prefix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrefixHeat {
+    pub bucket: String,
+    pub prefix: String,
+    pub hits: u64,
+    pub objects: usize,
+}
+
+/// Access counter of one object, keyed in its shard by the hash of the object name
+#[derive(Debug)]
+struct Counter {
+    bucket: String,
+    object: String,
+    hits: AtomicU64,
+    last_access: AtomicU64,
+}
+
+impl Counter {
+    fn new(bucket: String, object: String, hits: u64, last_access: u64) -> Self {
+        Self {
+            bucket,
+            object,
+            hits: AtomicU64::new(hits),
+            last_access: AtomicU64::new(last_access),
+        }
+    }
+
+    fn is(&self, bucket: &str, object: &str) -> bool {
+        self.bucket == bucket && self.object == object
+    }
+
+    fn add(&self, hits: u64, last_access: u64) {
+        self.hits.fetch_add(hits, Ordering::Relaxed);
+        self.last_access.fetch_max(last_access, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+pub struct HeatMap {
+    shards: Vec<RwLock<HashMap<u64, Counter>>>,
+    hasher: RandomState,
+    tracked: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+impl Default for HeatMap {
+    fn default() -> Self {
+        Self {
+            shards: (0..HEAT_MAP_SHARDS).map(|_| RwLock::default()).collect(),
+            hasher: RandomState::new(),
+            tracked: AtomicUsize::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+}
+
+static GLOBAL_HEAT_MAP: LazyLock<HeatMap> = LazyLock::new(HeatMap::default);
+
+pub fn global_heat_map() -> &'static HeatMap {
+    &GLOBAL_HEAT_MAP
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+impl HeatMap {
+    /// Count one access to `bucket/object`
+    pub fn record(&self, bucket: &str, object: &str) {
+        self.record_at(bucket, object, unix_now());
+    }
+
+    fn record_at(&self, bucket: &str, object: &str, now: u64) {
+        self.add(bucket, object, 1, now);
+    }
+
+    /// Add `hits` to the counter of `bucket/object`, tracking it if there is room
+    ///
+    /// Objects already tracked are counted under the read lock of their shard, without allocating.
+    fn add(&self, bucket: &str, object: &str, hits: u64, last_access: u64) {
+        let key = self.hasher.hash_one((bucket, object));
+        let shard = &self.shards[key as usize % HEAT_MAP_SHARDS];
+        if let Some(counter) = shard.read().unwrap().get(&key) {
+            self.add_to(counter, bucket, object, hits, last_access);
+            return;
+        }
+        if self.tracked.load(Ordering::Relaxed) >= MAX_TRACKED_OBJECTS {
+            self.dropped.fetch_add(hits, Ordering::Relaxed);
+            return;
+        }
+
+        match shard.write().unwrap().entry(key) {
+            Entry::Occupied(entry) => self.add_to(entry.get(), bucket, object, hits, last_access),
+            Entry::Vacant(entry) => {
+                entry.insert(Counter::new(bucket.to_string(), object.to_string(), hits, last_access));
+                self.tracked.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn add_to(&self, counter: &Counter, bucket: &str, object: &str, hits: u64, last_access: u64) {
+        if counter.is(bucket, object) {
+            counter.add(hits, last_access);
+        } else {
+            // Another object with the same hash holds the slot
+            self.dropped.fetch_add(hits, Ordering::Relaxed);
+        }
+    }
+
+    /// Accesses not counted because the map was full, or another object hashed to the same key
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracked.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn snapshot(&self) -> Vec<ObjectHeat> {
+        let mut heat = Vec::with_capacity(self.len());
+        for shard in &self.shards {
+            heat.extend(shard.read().unwrap().values().map(|counter| ObjectHeat {
+                bucket: counter.bucket.clone(),
+                object: counter.object.clone(),
+                hits: counter.hits.load(Ordering::Relaxed),
+                last_access: counter.last_access.load(Ordering::Relaxed),
+            }));
+        }
+        heat
+    }
+
+    /// Add persisted counters back into the map, as done on startup
+    pub fn merge(&self, heat: Vec<ObjectHeat>) {
+        for h in heat {
+            self.add(&h.bucket, &h.object, h.hits, h.last_access);
+        }
+    }
+
+    /// Drop an eighth of every count, forgetting objects that went cold
+    pub fn decay(&self) {
+        for shard in &self.shards {
+            let mut objects = shard.write().unwrap();
+            let before = objects.len();
+            objects.retain(|_, counter| {
+                let hits = counter.hits.get_mut();
+                *hits -= hits.div_ceil(8);
+                *hits > 0
+            });
+            self.tracked.fetch_sub(before - objects.len(), Ordering::Relaxed);
+        }
+    }
+}
+
+fn heat_map_path(node: &str) -> String {
+    // Node names are host:port, keep them a single path element
+    let node = node.replace([':', '/'], "_");
+    format!(
+        "{}{}{}{}{}.json",
+        crate::disk::BUCKET_META_PREFIX,
+        SLASH_SEPARATOR,
+        HEAT_MAP_DIR,
+        SLASH_SEPARATOR,
+        node
+    )
+}
+
+/// Persist this node's heat map, then decay it
+pub async fn save_heat_map(store: Arc<ECStore>) -> Result<()> {
+    let node = GLOBAL_Local_Node_Name.read().await.clone();
+    let heat_map = global_heat_map();
+    let data =
+        serde_json::to_vec(&heat_map.snapshot()).map_err(|e| Error::other(format!("Failed to serialize heat map: {e}")))?;
+    save_config(store, &heat_map_path(&node), data).await?;
+    heat_map.decay();
+    Ok(())
+}
+
+/// Load the heat map persisted by `node`, empty if it never saved one
+pub async fn load_heat_map(store: Arc<ECStore>, node: &str) -> Result<Vec<ObjectHeat>> {
+    let buf = match read_config(store, &heat_map_path(node)).await {
+        Ok(buf) => buf,
+        Err(Error::ConfigNotFound) => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    serde_json::from_slice(&buf).map_err(|e| Error::other(format!("Failed to deserialize heat map: {e}")))
+}
+
+/// Restore this node's persisted heat map into the live one
+pub async fn init_heat_map(store: Arc<ECStore>) -> Result<()> {
+    let node = GLOBAL_Local_Node_Name.read().await.clone();
+    global_heat_map().merge(load_heat_map(store, &node).await?);
+    Ok(())
+}
+
+/// Live heat of this node plus the latest snapshot of every peer
+pub async fn cluster_heat(store: Arc<ECStore>) -> Vec<ObjectHeat> {
+    let mut heat = global_heat_map().snapshot();
+    let (peers, local) = get_global_endpoints().peers();
+    for peer in peers.iter().filter(|peer| **peer != local) {
+        match load_heat_map(store.clone(), peer).await {
+            Ok(peer_heat) => heat.extend(peer_heat),
+            Err(e) => warn!("load heat map of {} failed: {}", peer, e),
+        }
+    }
+    heat
+}
+
+/// Merge counters of the same object and keep the `count` hottest
+pub fn top_objects(heat: Vec<ObjectHeat>, count: usize) -> Vec<ObjectHeat> {
+    let mut merged: HashMap<(String, String), ObjectHeat> = HashMap::new();
+    for h in heat {
+        merged
+            .entry((h.bucket.clone(), h.object.clone()))
+            .and_modify(|m| {
+                m.hits += h.hits;
+                m.last_access = m.last_access.max(h.last_access);
+            })
+            .or_insert(h);
+    }
+
+    let mut top: Vec<_> = merged.into_values().collect();
+    top.sort_by(|a, b| {
+        b.hits
+            .cmp(&a.hits)
+            .then_with(|| a.bucket.cmp(&b.bucket))
+            .then_with(|| a.object.cmp(&b.object))
+    });
+    top.truncate(count);
+    top
+}
+
+/// Sum counters by the first `depth` directories of each object and keep the `count` hottest
+pub fn top_prefixes(heat: Vec<ObjectHeat>, count: usize, depth: usize) -> Vec<PrefixHeat> {
+    let mut merged: HashMap<(String, String), (u64, Vec<String>)> = HashMap::new();
+    for h in heat {
+        let dirs: Vec<&str> = h.object.split(SLASH_SEPARATOR).collect();
+        let dirs = &dirs[..dirs.len() - 1];
+        let prefix = dirs[..depth.min(dirs.len())]
+            .iter()
+            .map(|dir| format!("{dir}{SLASH_SEPARATOR}"))
+            .collect::<String>();
+
+        let (hits, objects) = merged.entry((h.bucket, prefix)).or_default();
+        *hits += h.hits;
+        objects.push(h.object);
+    }
+
+    let mut top: Vec<_> = merged
+        .into_iter()
+        .map(|((bucket, prefix), (hits, mut objects))| {
+            objects.sort_unstable();
+            objects.dedup();
+            PrefixHeat {
+                bucket,
+                prefix,
+                hits,
+                objects: objects.len(),
+            }
+        })
+        .collect();
+    top.sort_by(|a, b| {
+        b.hits
+            .cmp(&a.hits)
+            .then_with(|| a.bucket.cmp(&b.bucket))
+            .then_with(|| a.prefix.cmp(&b.prefix))
+    });
+    top.truncate(count);
+    top
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heat(bucket: &str, object: &str, hits: u64) -> ObjectHeat {
+        ObjectHeat {
+            bucket: bucket.to_string(),
+            object: object.to_string(),
+            hits,
+            last_access: 0,
+        }
+    }
+
+    #[test]
+    fn test_heat_map_record_and_decay() {
+        let heat_map = HeatMap::default();
+        for _ in 0..16 {
+            heat_map.record_at("b", "hot", 10);
+        }
+        heat_map.record_at("b", "cold", 20);
+
+        heat_map.decay();
+        let mut snapshot = heat_map.snapshot();
+        snapshot.sort_by(|a, b| a.object.cmp(&b.object));
+        assert_eq!(
+            snapshot,
+            vec![ObjectHeat {
+                last_access: 10,
+                ..heat("b", "hot", 14)
+            }]
+        );
+
+        heat_map.merge(vec![heat("b", "hot", 1), heat("b", "new", 3)]);
+        assert_eq!(heat_map.len(), 2);
+        assert_eq!(
+            top_objects(heat_map.snapshot(), 1),
+            vec![ObjectHeat {
+                last_access: 10,
+                ..heat("b", "hot", 15)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_top_objects_and_prefixes() {
+        let reported = vec![
+            heat("b", "logs/2024/a", 5),
+            heat("b", "logs/2025/b", 7),
+            heat("b", "img/c", 3),
+            heat("b", "root", 1),
+            // Same object reported by another node
+            heat("b", "img/c", 10),
+        ];
+
+        let top = top_objects(reported.clone(), 2);
+        assert_eq!(top, vec![heat("b", "img/c", 13), heat("b", "logs/2025/b", 7)]);
+
+        let prefixes = top_prefixes(reported.clone(), 10, 1);
+        assert_eq!(
+            prefixes
+                .iter()
+                .map(|p| (p.prefix.as_str(), p.hits, p.objects))
+                .collect::<Vec<_>>(),
+            vec![("img/", 13, 1), ("logs/", 12, 2), ("", 1, 1)]
+        );
+
+        let prefixes = top_prefixes(reported, 1, 2);
+        assert_eq!(prefixes[0].prefix, "img/");
+        assert_eq!(prefixes[0].hits, 13);
+    }
+}
@@ -30,11 +30,15 @@ pub mod endpoints;
 pub mod erasure_coding;
 pub mod error;
 pub mod global;
+pub mod heat_map;
+pub mod list_token;
 pub mod lock_utils;
 pub mod metrics_realtime;
 pub mod notification_sys;
+pub mod object_ttl;
 pub mod pools;
 pub mod rebalance;
+pub mod reencode;
 pub mod rpc;
 pub mod set_disk;
 mod sets;
@@ -45,6 +49,7 @@ pub mod store_list_objects;
 pub mod store_utils;
 
 pub mod checksum;
+pub mod checksum_manifest;
 pub mod client;
 pub mod event;
 pub mod event_notification;
@@ -0,0 +1,198 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ListObjectsV2 continuation tokens.
+//!
+//! A token holds the listing position together with the bucket and prefix it was issued for. It
+//! is sealed with AES-256-GCM under a key derived from the root credentials every node shares, so
+//! any node can resume a listing another node started, and a token altered or replayed against
+//! another bucket or prefix is rejected. The key is derived once, sealing a page is a single AEAD
+//! operation.
+//!
+//! Tokens carry a version prefix; a node keeps decoding the versions older nodes issue. Tokens
+//! without a prefix were issued before tokens were sealed and are plain object names, so they are
+//! resumed from as-is, the same as a `start-after` the client chose.
+
+use crate::error::{Error, Result, StorageError};
+use crate::global::get_global_action_cred;
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use base64::Engine as _;
+use base64::engine::general_purpose;
+use rand::RngCore;
+use rustfs_utils::crypto::hkdf_sha256;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Tokens sealed with the root secret key itself by `rustfs_crypto`
+const TOKEN_PREFIX_V1: &str = "rfs1.";
+/// Tokens sealed with AES-256-GCM under the derived token key
+const TOKEN_PREFIX_V2: &str = "rfs2.";
+
+const NONCE_SIZE: usize = 12;
+
+static TOKEN_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ContinuationToken {
+    bucket: String,
+    prefix: String,
+    marker: String,
+}
+
+fn root_secret() -> Result<String> {
+    get_global_action_cred()
+        .map(|cred| cred.secret_key)
+        .filter(|secret| !secret.is_empty())
+        .ok_or_else(|| Error::other("continuation tokens can not be sealed without root credentials"))
+}
+
+fn token_key() -> Result<[u8; 32]> {
+    if let Some(key) = TOKEN_KEY.get() {
+        return Ok(*key);
+    }
+    let secret = root_secret()?;
+    Ok(*TOKEN_KEY.get_or_init(|| hkdf_sha256("rustfs", secret, "list-continuation-token")))
+}
+
+fn invalid_token(bucket: &str, prefix: &str) -> Error {
+    StorageError::InvalidArgument(
+        bucket.to_string(),
+        prefix.to_string(),
+        "The continuation token provided is incorrect".to_string(),
+    )
+}
+
+fn encode_with(key: &[u8; 32], bucket: &str, prefix: &str, marker: &str) -> Result<String> {
+    let token = ContinuationToken {
+        bucket: bucket.to_string(),
+        prefix: prefix.to_string(),
+        marker: marker.to_string(),
+    };
+    let data = serde_json::to_vec(&token).map_err(Error::other)?;
+
+    let mut nonce = [0u8; NONCE_SIZE];
+    rand::rng().fill_bytes(&mut nonce);
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(Error::other)?;
+    let sealed = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &data,
+                aad: TOKEN_PREFIX_V2.as_bytes(),
+            },
+        )
+        .map_err(|e| Error::other(format!("seal continuation token: {e}")))?;
+
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&sealed);
+    Ok(format!("{TOKEN_PREFIX_V2}{}", general_purpose::URL_SAFE_NO_PAD.encode(out)))
+}
+
+fn open_v2(key: &[u8; 32], data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < NONCE_SIZE {
+        return None;
+    }
+    let cipher = Aes256Gcm::new_from_slice(key).ok()?;
+    cipher
+        .decrypt(
+            Nonce::from_slice(&data[..NONCE_SIZE]),
+            Payload {
+                msg: &data[NONCE_SIZE..],
+                aad: TOKEN_PREFIX_V2.as_bytes(),
+            },
+        )
+        .ok()
+}
+
+/// Decode `token` with the token key, or the root secret key `legacy_key` for version 1 tokens
+fn decode_with(key: &[u8; 32], legacy_key: &[u8], bucket: &str, prefix: &str, token: &str) -> Result<String> {
+    let decoded = if let Some(sealed) = token.strip_prefix(TOKEN_PREFIX_V2) {
+        general_purpose::URL_SAFE_NO_PAD
+            .decode(sealed)
+            .ok()
+            .and_then(|data| open_v2(key, &data))
+    } else if let Some(sealed) = token.strip_prefix(TOKEN_PREFIX_V1) {
+        general_purpose::URL_SAFE_NO_PAD
+            .decode(sealed)
+            .ok()
+            .and_then(|data| rustfs_crypto::decrypt_data(legacy_key, &data).ok())
+    } else {
+        return Ok(token.to_string());
+    };
+    match decoded.and_then(|data| serde_json::from_slice::<ContinuationToken>(&data).ok()) {
+        Some(decoded) if decoded.bucket == bucket && decoded.prefix == prefix => Ok(decoded.marker),
+        _ => Err(invalid_token(bucket, prefix)),
+    }
+}
+
+/// Seal the position `marker` of a listing of `bucket` under `prefix` into a continuation token
+pub fn encode_continuation_token(bucket: &str, prefix: &str, marker: &str) -> Result<String> {
+    encode_with(&token_key()?, bucket, prefix, marker)
+}
+
+/// The listing position held by `token`, if it was issued for `bucket` and `prefix`
+pub fn decode_continuation_token(bucket: &str, prefix: &str, token: &str) -> Result<String> {
+    if !token.starts_with(TOKEN_PREFIX_V1) && !token.starts_with(TOKEN_PREFIX_V2) {
+        return Ok(token.to_string());
+    }
+    decode_with(&token_key()?, root_secret()?.as_bytes(), bucket, prefix, token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7u8; 32];
+    const LEGACY_KEY: &[u8] = b"test-secret-key";
+
+    #[test]
+    fn test_continuation_token_round_trip() {
+        let token = encode_with(&KEY, "bucket", "logs/", "logs/2025/a").unwrap();
+        assert!(token.starts_with(TOKEN_PREFIX_V2));
+        assert!(!token.contains("logs/2025/a"));
+        assert_eq!(decode_with(&KEY, LEGACY_KEY, "bucket", "logs/", &token).unwrap(), "logs/2025/a");
+
+        // Tokens issued before sealing are plain markers
+        assert_eq!(decode_with(&KEY, LEGACY_KEY, "bucket", "logs/", "logs/2024/z").unwrap(), "logs/2024/z");
+
+        // Version 1 tokens were sealed with the root secret key
+        let data = serde_json::to_vec(&ContinuationToken {
+            bucket: "bucket".to_string(),
+            prefix: "logs/".to_string(),
+            marker: "logs/2023/m".to_string(),
+        })
+        .unwrap();
+        let sealed = rustfs_crypto::encrypt_data(LEGACY_KEY, &data).unwrap();
+        let token = format!("{TOKEN_PREFIX_V1}{}", general_purpose::URL_SAFE_NO_PAD.encode(sealed));
+        assert_eq!(decode_with(&KEY, LEGACY_KEY, "bucket", "logs/", &token).unwrap(), "logs/2023/m");
+    }
+
+    #[test]
+    fn test_continuation_token_rejected() {
+        let token = encode_with(&KEY, "bucket", "logs/", "logs/2025/a").unwrap();
+
+        assert!(decode_with(&KEY, LEGACY_KEY, "other", "logs/", &token).is_err());
+        assert!(decode_with(&KEY, LEGACY_KEY, "bucket", "", &token).is_err());
+        assert!(decode_with(&[8u8; 32], LEGACY_KEY, "bucket", "logs/", &token).is_err());
+
+        let mut tampered = token.into_bytes();
+        let last = tampered.len() - 1;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        let tampered = String::from_utf8(tampered).unwrap();
+        assert!(decode_with(&KEY, LEGACY_KEY, "bucket", "logs/", &tampered).is_err());
+        assert!(decode_with(&KEY, LEGACY_KEY, "bucket", "logs/", &format!("{TOKEN_PREFIX_V2}!!")).is_err());
+        assert!(decode_with(&KEY, LEGACY_KEY, "bucket", "logs/", &format!("{TOKEN_PREFIX_V1}!!")).is_err());
+    }
+}
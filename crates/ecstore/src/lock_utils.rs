@@ -14,7 +14,7 @@
 
 use crate::disk::endpoint::Endpoint;
 use crate::error::Result;
-use rustfs_lock::client::{LockClient, local::LocalClient, remote::RemoteClient};
+use rustfs_lock::client::{LockClient, inprocess::InProcessClient, local::LocalClient, remote::RemoteClient};
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -22,6 +22,11 @@ use std::sync::Arc;
 /// This function creates one client per unique host:port combination
 /// to avoid duplicate connections to the same server
 pub async fn create_unique_clients(endpoints: &[Endpoint]) -> Result<Vec<Arc<dyn LockClient>>> {
+    // Nobody outside this process asks for the locks of a single node, keep them off the lock RPC server's map
+    if !crate::global::is_dist_erasure().await && endpoints.iter().all(|endpoint| endpoint.is_local) {
+        return Ok(vec![Arc::new(InProcessClient::new())]);
+    }
+
     let mut unique_endpoints: HashMap<String, &Endpoint> = HashMap::new();
 
     // Collect unique endpoints based on host:port
@@ -20,6 +20,7 @@ use crate::rpc::PeerRestClient;
 use crate::{endpoints::EndpointServerPools, new_object_layer_fn};
 use futures::future::join_all;
 use lazy_static::lazy_static;
+use rustfs_lock::{HeldLock, ResourceContention};
 use rustfs_madmin::{ItemState, ServerProperties};
 use std::sync::OnceLock;
 use std::time::SystemTime;
@@ -40,6 +41,28 @@ pub fn get_global_notification_sys() -> Option<&'static NotificationSys> {
     GLOBAL_NotificationSys.get()
 }
 
+type StsRevocationsReloader = Box<dyn Fn() + Send + Sync>;
+
+static STS_REVOCATIONS_RELOADER: OnceLock<StsRevocationsReloader> = OnceLock::new();
+
+/// Install how this node reloads the STS revocation list when a peer changed it, false if one is installed already
+///
+/// The object layer only forwards the request; the IAM system, which owns the list, installs the reloader.
+pub fn set_sts_revocations_reloader(reload: impl Fn() + Send + Sync + 'static) -> bool {
+    STS_REVOCATIONS_RELOADER.set(Box::new(reload)).is_ok()
+}
+
+/// Reload the STS revocation list of this node, false if no reloader is installed
+pub fn reload_sts_revocations_local() -> bool {
+    match STS_REVOCATIONS_RELOADER.get() {
+        Some(reload) => {
+            reload();
+            true
+        }
+        None => false,
+    }
+}
+
 pub struct NotificationSys {
     pub peer_clients: Vec<Option<PeerRestClient>>,
     #[allow(dead_code)]
@@ -140,6 +163,75 @@ impl NotificationSys {
         join_all(futures).await
     }
 
+    /// Locks held on this node and on every reachable peer
+    pub async fn list_locks(&self) -> Vec<HeldLock> {
+        let mut futures = Vec::with_capacity(self.peer_clients.len());
+        for client in self.peer_clients.iter().flatten() {
+            futures.push(client.list_locks());
+        }
+
+        let mut locks = rustfs_lock::list_local_locks().await;
+        for result in join_all(futures).await {
+            match result {
+                Ok(peer_locks) => locks.extend(peer_locks),
+                Err(err) => error!("notification list_locks err {:?}", err),
+            }
+        }
+        locks
+    }
+
+    /// The `count` most contended resources, merged across this node and every reachable peer
+    pub async fn lock_contention(&self, count: usize) -> Vec<ResourceContention> {
+        let mut futures = Vec::with_capacity(self.peer_clients.len());
+        for client in self.peer_clients.iter().flatten() {
+            futures.push(client.lock_contention(count));
+        }
+
+        let mut stats = rustfs_lock::top_contended_local(count);
+        for result in join_all(futures).await {
+            match result {
+                Ok(peer_stats) => stats.extend(peer_stats),
+                Err(err) => error!("notification lock_contention err {:?}", err),
+            }
+        }
+        rustfs_lock::contention::top_contended(stats, count)
+    }
+
+    /// Release the lock on `resource` on this node and every peer, whatever its holders
+    ///
+    /// Returns the number of nodes that held the lock.
+    pub async fn force_unlock(&self, resource: &str, owner: &str) -> usize {
+        let mut futures = Vec::with_capacity(self.peer_clients.len());
+        for client in self.peer_clients.iter().flatten() {
+            futures.push(client.force_unlock(resource, owner));
+        }
+
+        let lock_id = rustfs_lock::LockId::new_deterministic(resource);
+        let mut released = rustfs_lock::force_unlock_local(&lock_id).await as usize;
+        for result in join_all(futures).await {
+            match result {
+                Ok(held) => released += held as usize,
+                Err(err) => error!("notification force_unlock err {:?}", err),
+            }
+        }
+        released
+    }
+
+    /// Tell every peer to reload the STS revocation list
+    pub async fn reload_sts_revocations(&self) {
+        let mut futures = Vec::with_capacity(self.peer_clients.len());
+        for client in self.peer_clients.iter().flatten() {
+            futures.push(client.reload_sts_revocations());
+        }
+
+        let results = join_all(futures).await;
+        for result in results {
+            if let Err(err) = result {
+                error!("notification reload_sts_revocations err {:?}", err);
+            }
+        }
+    }
+
     pub async fn reload_pool_meta(&self) {
         let mut futures = Vec::with_capacity(self.peer_clients.len());
         for client in self.peer_clients.iter().flatten() {
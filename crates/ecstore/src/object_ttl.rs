@@ -0,0 +1,426 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Expiry of single objects, apart from lifecycle rules
+//!
+//! A version written with `X-Rustfs-Expire-After: <seconds>` keeps the time it expires at in its metadata.
+//! The node writing it schedules its deletion on a time wheel, which reaps it within a tick of that time
+//! instead of waiting for the scan applying lifecycle rules. The wheel only lives in memory: the scanner
+//! schedules again the expiring versions it comes across, so that a version scheduled on a node that
+//! stopped is reaped after the next scan of its set.
+//!
+//! The metadata is read again before a version is deleted, so an entry of the wheel that is stale, the
+//! version having been overwritten or given another expiry since, deletes nothing. The delete is made on
+//! the condition that the version is still the one read, which is checked under the object lock. Expired
+//! versions are deleted on the replication targets of the bucket like those a client deletes.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+
+use futures::{StreamExt, stream};
+use rustfs_filemeta::{MetaCacheEntry, headers::RESERVED_METADATA_PREFIX_LOWER};
+use time::{OffsetDateTime, UtcOffset, format_description::FormatItem, macros::format_description};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    bucket::{object_lock::objectlock_sys::enforce_retention_for_deletion, versioning_sys::BucketVersioningSys},
+    cmd::bucket_replication::{DeletedObjectReplicationInfo, check_replicate_delete, schedule_replication_delete},
+    error::{Error, is_err_object_not_found, is_err_version_not_found},
+    event::name::EventName,
+    event_notification::{EventArgs, send_event},
+    global::GLOBAL_LocalNodeName,
+    new_object_layer_fn,
+    store::ECStore,
+    store_api::{DeletedObject, ObjectInfo, ObjectOptions, ObjectToDelete, StorageAPI, WritePreconditions},
+};
+
+/// Rule id reported in the `x-amz-expiration` header of expiring objects
+pub const OBJECT_TTL_RULE_ID: &str = "object-ttl";
+
+/// Longest time to live an object may be given, ten years
+pub const MAX_OBJECT_TTL: Duration = Duration::from_secs(10 * 365 * 24 * 3600);
+
+/// Time the reaper advances the wheel by
+const TICK: Duration = Duration::from_secs(1);
+
+/// Slots of the wheel, one turn is an hour
+const WHEEL_SLOTS: usize = 3600;
+
+/// Expiries kept on a node, the scanner schedules those that did not fit when it comes across them
+const MAX_SCHEDULED: usize = 1_000_000;
+
+/// Versions deleted at once by the reaper
+const REAP_CONCURRENCY: usize = 8;
+
+const EXPIRATION_DATE_FORMAT: &[FormatItem<'static>] =
+    format_description!("[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT");
+
+fn expires_at_key() -> String {
+    format!("{RESERVED_METADATA_PREFIX_LOWER}expires-at")
+}
+
+/// Time to live of an `X-Rustfs-Expire-After` header, in whole seconds
+pub fn parse_expire_after(value: &str) -> Option<Duration> {
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+        .filter(|ttl| !ttl.is_zero() && *ttl <= MAX_OBJECT_TTL)
+}
+
+/// Time the version with `metadata` expires at, if it was given a time to live
+pub fn expires_at(metadata: &HashMap<String, String>) -> Option<OffsetDateTime> {
+    let secs = metadata.get(&expires_at_key())?.parse::<i64>().ok()?;
+    OffsetDateTime::from_unix_timestamp(secs).ok()
+}
+
+/// Make the version written with `metadata` expire at `at`
+pub fn set_expires_at(metadata: &mut HashMap<String, String>, at: OffsetDateTime) {
+    metadata.insert(expires_at_key(), at.unix_timestamp().to_string());
+}
+
+pub fn clear_expires_at(metadata: &mut HashMap<String, String>) {
+    metadata.remove(&expires_at_key());
+}
+
+/// `x-amz-expiration` of an object expiring at `at`
+pub fn expiration_header(at: OffsetDateTime) -> Option<String> {
+    let date = at.to_offset(UtcOffset::UTC).format(EXPIRATION_DATE_FORMAT).ok()?;
+    Some(format!("expiry-date=\"{date}\", rule-id=\"{OBJECT_TTL_RULE_ID}\""))
+}
+
+/// Wheel of slots a tick wide each, entries due a turn or more ahead wait in their slot for the turns between
+#[derive(Debug)]
+struct TimeWheel<T> {
+    slots: Vec<Vec<(u64, T)>>,
+    /// Tick the wheel was advanced to
+    current: u64,
+    len: usize,
+}
+
+impl<T> TimeWheel<T> {
+    fn new(slots: usize, current: u64) -> Self {
+        Self {
+            slots: (0..slots).map(|_| Vec::new()).collect(),
+            current,
+            len: 0,
+        }
+    }
+
+    /// Add `item` due at tick `due`, on the next advance when that has passed
+    fn insert(&mut self, due: u64, item: T) {
+        let due = due.max(self.current + 1);
+        let slot = (due % self.slots.len() as u64) as usize;
+        self.slots[slot].push((due, item));
+        self.len += 1;
+    }
+
+    /// Advance the wheel to tick `to`, taking out the entries due by then
+    fn advance(&mut self, to: u64) -> Vec<T> {
+        let mut due = Vec::new();
+        if to <= self.current {
+            return due;
+        }
+
+        let turn = self.slots.len() as u64;
+        // Past a whole turn every slot was visited once
+        for tick in self.current + 1..=self.current + (to - self.current).min(turn) {
+            let slot = &mut self.slots[(tick % turn) as usize];
+            let (ready, waiting): (Vec<_>, Vec<_>) = std::mem::take(slot).into_iter().partition(|(at, _)| *at <= to);
+            *slot = waiting;
+            due.extend(ready.into_iter().map(|(_, item)| item));
+        }
+        self.current = to;
+        self.len -= due.len();
+        due
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ExpiryKey {
+    bucket: String,
+    object: String,
+    version_id: Option<Uuid>,
+}
+
+struct Scheduler {
+    wheel: TimeWheel<ExpiryKey>,
+    /// Tick each version is due at, a wheel entry due at another tick is stale
+    due: HashMap<ExpiryKey, u64>,
+}
+
+impl Scheduler {
+    fn new(now: u64) -> Self {
+        Self {
+            wheel: TimeWheel::new(WHEEL_SLOTS, now),
+            due: HashMap::new(),
+        }
+    }
+
+    fn schedule(&mut self, key: ExpiryKey, at: u64) -> bool {
+        if self.due.get(&key) == Some(&at) {
+            return true;
+        }
+        if self.wheel.len() >= MAX_SCHEDULED {
+            return false;
+        }
+        self.due.insert(key.clone(), at);
+        self.wheel.insert(at, key);
+        true
+    }
+
+    fn advance(&mut self, now: u64) -> Vec<ExpiryKey> {
+        let mut due = self.wheel.advance(now);
+        due.retain(|key| match self.due.get(key) {
+            Some(&at) if at <= now => {
+                self.due.remove(key);
+                true
+            }
+            _ => false,
+        });
+        due
+    }
+}
+
+fn now_tick() -> u64 {
+    OffsetDateTime::now_utc().unix_timestamp().max(0) as u64 / TICK.as_secs()
+}
+
+fn tick_of(at: OffsetDateTime) -> u64 {
+    at.unix_timestamp().max(0) as u64 / TICK.as_secs()
+}
+
+static SCHEDULER: LazyLock<Mutex<Scheduler>> = LazyLock::new(|| Mutex::new(Scheduler::new(now_tick())));
+
+fn scheduler() -> std::sync::MutexGuard<'static, Scheduler> {
+    SCHEDULER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Schedule the deletion of the version of `bucket/object` with `version_id` at `at`
+pub fn schedule_expiry(bucket: &str, object: &str, version_id: Option<Uuid>, at: OffsetDateTime) {
+    let key = ExpiryKey {
+        bucket: bucket.to_owned(),
+        object: object.to_owned(),
+        version_id,
+    };
+    if !scheduler().schedule(key, tick_of(at)) {
+        debug!("object expiry wheel is full, {}/{} waits for the scanner", bucket, object);
+    }
+}
+
+/// Schedule the deletion of `info` when it was written with a time to live
+pub fn schedule_object_expiry(info: &ObjectInfo) {
+    if let Some(at) = expires_at(&info.user_defined) {
+        schedule_expiry(&info.bucket, &info.name, info.version_id, at);
+    }
+}
+
+/// Schedule the deletion of the versions of an object the scanner listed that were written with a time to live
+pub fn schedule_entry_expiry(bucket: &str, entry: &MetaCacheEntry) {
+    // Most objects have no time to live, look for the key before decoding their versions
+    const MARKER: &[u8] = b"expires-at";
+    if !entry.metadata.windows(MARKER.len()).any(|w| w == MARKER) {
+        return;
+    }
+    let Ok(versions) = entry.file_info_versions(bucket) else {
+        return;
+    };
+    for fi in versions.versions.iter().filter(|fi| !fi.deleted) {
+        if let Some(at) = expires_at(&fi.metadata) {
+            schedule_expiry(bucket, &entry.name, fi.version_id, at);
+        }
+    }
+}
+
+/// Start deleting the versions as they expire, until the task is aborted
+pub fn start_object_expiry() -> JoinHandle<()> {
+    info!("object expiry started");
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let due = scheduler().advance(now_tick());
+            if due.is_empty() {
+                continue;
+            }
+            let Some(store) = new_object_layer_fn() else {
+                continue;
+            };
+            stream::iter(due)
+                .for_each_concurrent(REAP_CONCURRENCY, |key| {
+                    let store = store.clone();
+                    async move { expire_version(store, key).await }
+                })
+                .await;
+        }
+    })
+}
+
+async fn expire_version(store: Arc<ECStore>, key: ExpiryKey) {
+    let version_id = key.version_id.map(|v| v.to_string());
+    let info_opts = ObjectOptions {
+        version_id: version_id.clone(),
+        ..Default::default()
+    };
+    let info = match store.get_object_info(&key.bucket, &key.object, &info_opts).await {
+        Ok(info) => info,
+        Err(err) if is_err_object_not_found(&err) || is_err_version_not_found(&err) => return,
+        Err(err) => {
+            warn!("object expiry: reading {}/{} failed: {}", key.bucket, key.object, err);
+            return;
+        }
+    };
+
+    // The version may have been written again since it was scheduled
+    let Some(at) = expires_at(&info.user_defined) else {
+        return;
+    };
+    if at > OffsetDateTime::now_utc() {
+        schedule_object_expiry(&info);
+        return;
+    }
+    if enforce_retention_for_deletion(&info) {
+        debug!("object expiry: {}/{} is retained, it is kept", key.bucket, key.object);
+        return;
+    }
+
+    let opts = ObjectOptions {
+        version_id: version_id.clone(),
+        versioned: BucketVersioningSys::prefix_enabled(&key.bucket, &key.object).await,
+        version_suspended: BucketVersioningSys::prefix_suspended(&key.bucket, &key.object).await,
+        // Only the version read is deleted, not one written since
+        write_preconditions: Some(WritePreconditions {
+            if_match: info.etag.clone(),
+            if_none_match: None,
+            mod_time: info.mod_time,
+        }),
+        ..Default::default()
+    };
+    let dobj = ObjectToDelete {
+        object_name: key.object.clone(),
+        version_id: key.version_id,
+    };
+    let dsc = check_replicate_delete(&key.bucket, &dobj, &info, &opts, None).await;
+
+    match store.delete_object(&key.bucket, &key.object, opts).await {
+        Ok(deleted) => {
+            debug!("object expiry: {}/{} expired", key.bucket, key.object);
+            if dsc.replicate_any() {
+                let dobj = if deleted.delete_marker {
+                    DeletedObject {
+                        delete_marker: true,
+                        delete_marker_version_id: deleted.version_id.map(|v| v.to_string()),
+                        object_name: key.object.clone(),
+                        version_id: None,
+                        delete_marker_mtime: deleted.mod_time,
+                    }
+                } else {
+                    DeletedObject {
+                        delete_marker: false,
+                        delete_marker_version_id: None,
+                        object_name: key.object.clone(),
+                        version_id,
+                        delete_marker_mtime: None,
+                    }
+                };
+                let dv = DeletedObjectReplicationInfo::new(&key.bucket, &dobj);
+                schedule_replication_delete(dv, store.clone(), dsc).await;
+            }
+            send_event(EventArgs {
+                event_name: EventName::ObjectRemovedDelete.as_ref().to_string(),
+                bucket_name: key.bucket,
+                object: deleted,
+                user_agent: "Internal: [Object-TTL]".to_string(),
+                host: GLOBAL_LocalNodeName.to_string(),
+                ..Default::default()
+            });
+        }
+        Err(err) if is_err_object_not_found(&err) || is_err_version_not_found(&err) => {}
+        Err(Error::PreconditionFailed(..)) => {
+            debug!("object expiry: {}/{} changed since it was read, it is kept", key.bucket, key.object);
+        }
+        Err(err) => warn!("object expiry: deleting {}/{} failed: {}", key.bucket, key.object, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_wheel() {
+        let mut wheel = TimeWheel::new(10, 100);
+        wheel.insert(103, "a");
+        wheel.insert(125, "b");
+        wheel.insert(50, "late");
+        assert_eq!(wheel.len(), 3);
+
+        assert_eq!(wheel.advance(101), vec!["late"]);
+        assert!(wheel.advance(102).is_empty());
+        assert_eq!(wheel.advance(104), vec!["a"]);
+        // "b" shares its slot with tick 115 but is a turn further
+        assert!(wheel.advance(115).is_empty());
+        assert_eq!(wheel.advance(200), vec!["b"]);
+        assert_eq!(wheel.len(), 0);
+    }
+
+    #[test]
+    fn test_scheduler_drops_stale_entries() {
+        let mut scheduler = Scheduler::new(0);
+        let key = ExpiryKey {
+            bucket: "tmp".to_owned(),
+            object: "staging/a".to_owned(),
+            version_id: None,
+        };
+        assert!(scheduler.schedule(key.clone(), 5));
+        assert!(scheduler.schedule(key.clone(), 5));
+        assert_eq!(scheduler.wheel.len(), 1);
+
+        // Given another expiry, the version is only reaped at the later one
+        assert!(scheduler.schedule(key.clone(), 8));
+        assert!(scheduler.advance(6).is_empty());
+        assert_eq!(scheduler.advance(8), vec![key]);
+        assert!(scheduler.due.is_empty());
+    }
+
+    #[test]
+    fn test_expiry_metadata() {
+        assert_eq!(parse_expire_after("600"), Some(Duration::from_secs(600)));
+        assert!(parse_expire_after("0").is_none());
+        assert!(parse_expire_after("-1").is_none());
+        assert!(parse_expire_after("1h").is_none());
+
+        let at = OffsetDateTime::from_unix_timestamp(1_356_220_800).unwrap();
+        let mut metadata = HashMap::new();
+        set_expires_at(&mut metadata, at);
+        assert_eq!(expires_at(&metadata), Some(at));
+        assert_eq!(
+            expiration_header(at).unwrap(),
+            "expiry-date=\"Sun, 23 Dec 2012 00:00:00 GMT\", rule-id=\"object-ttl\""
+        );
+        clear_expires_at(&mut metadata);
+        assert!(expires_at(&metadata).is_none());
+    }
+}
@@ -782,6 +782,7 @@ impl ECStore {
                         &ObjectOptions {
                             version_id: version_id.clone(),
                             no_lock: true,
+                            no_decryption: true,
                             ..Default::default()
                         },
                     )
@@ -1193,8 +1194,7 @@ impl ECStore {
         warn!("decommission_object: start {} {}", &bucket, &rd.object_info.name);
         let object_info = rd.object_info.clone();
 
-        // TODO: check : use size or actual_size ?
-        let _actual_size = object_info.get_actual_size()?;
+        let actual_size = object_info.get_actual_size()?;
 
         if object_info.is_multipart() {
             let res = match self
@@ -1236,7 +1236,11 @@ impl ECStore {
 
                 reader.read_exact(&mut chunk).await?;
 
-                let mut data = PutObjReader::from_vec(chunk);
+                let mut data = if object_info.is_encrypted() {
+                    PutObjReader::from_vec_with_actual_size(chunk, part.actual_size)
+                } else {
+                    PutObjReader::from_vec(chunk)
+                };
 
                 let pi = match self
                     .put_object_part(
@@ -1264,6 +1268,7 @@ impl ECStore {
                 parts[i] = CompletePart {
                     part_num: pi.part_num,
                     etag: pi.etag,
+                    ..Default::default()
                 };
             }
 
@@ -1291,7 +1296,7 @@ impl ECStore {
         }
 
         let reader = BufReader::new(rd.stream);
-        let hrd = HashReader::new(Box::new(WarpReader::new(reader)), object_info.size, object_info.size, None, false)?;
+        let hrd = HashReader::new(Box::new(WarpReader::new(reader)), object_info.size, actual_size, None, false)?;
         let mut data = PutObjReader::new(hrd);
 
         if let Err(err) = self
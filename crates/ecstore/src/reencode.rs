@@ -0,0 +1,572 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Re-encoding of existing objects to the parity and bitrot algorithm of a storage class.
+//!
+//! Objects keep the parity and bitrot algorithm they were written with when the storage class
+//! config changes, e.g. after drives were added. A re-encode job walks a bucket and rewrites every
+//! version whose parity or bitrot algorithm differs from the one of its target storage class, which
+//! is how objects are migrated to a new bitrot algorithm. Each version is rewritten in place,
+//! in the set it lives in, with its version id and modification time preserved; the new data
+//! only replaces the old one once it is committed on a write quorum of drives.
+
+use crate::StorageAPI;
+use crate::config::GLOBAL_STORAGE_CLASS;
+use crate::config::storageclass::{self, STANDARD};
+use crate::error::{Error, Result};
+use crate::error::{is_err_object_not_found, is_err_version_not_found};
+use crate::pools::ListCallback;
+use crate::set_disk::SetDisks;
+use crate::store::ECStore;
+use crate::store_api::{BucketOptions, CompletePart, GetObjectReader, ObjectIO, ObjectOptions, PutObjReader};
+use http::HeaderMap;
+use rustfs_filemeta::{FileInfo, MetaCacheEntry, headers::AMZ_STORAGE_CLASS};
+use rustfs_rio::{HashReader, WarpReader};
+use rustfs_utils::HashAlgorithm;
+use rustfs_utils::path::encode_dir_object;
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::task::{Context, Poll};
+use time::OffsetDateTime;
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader, ReadBuf};
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+static GLOBAL_REENCODE_JOB: LazyLock<Mutex<Option<Arc<ReencodeJob>>>> = LazyLock::new(|| Mutex::new(None));
+
+/// Objects to re-encode
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReencodeRequest {
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    /// Storage class to move the objects to, each object keeps its own when unset
+    #[serde(default)]
+    pub storage_class: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ReencodeStatus {
+    #[default]
+    Running,
+    Completed,
+    Canceled,
+    Failed,
+}
+
+/// Progress of a re-encode job, counted in object versions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReencodeProgress {
+    pub id: String,
+    pub request: ReencodeRequest,
+    pub status: ReencodeStatus,
+    pub scanned: u64,
+    pub reencoded: u64,
+    /// Versions already at their target parity, remote or changed while the job ran
+    pub skipped: u64,
+    pub failed: u64,
+    pub bytes: u64,
+    pub last_object: String,
+    pub last_error: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub started_at: OffsetDateTime,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub ended_at: Option<OffsetDateTime>,
+}
+
+struct ReencodeJob {
+    progress: Mutex<ReencodeProgress>,
+    canceled: AtomicBool,
+    cancel_tx: broadcast::Sender<bool>,
+}
+
+impl ReencodeJob {
+    fn update(&self, f: impl FnOnce(&mut ReencodeProgress)) {
+        f(&mut self.progress.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    fn progress(&self) -> ReencodeProgress {
+        self.progress.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn is_running(&self) -> bool {
+        self.progress().status == ReencodeStatus::Running
+    }
+}
+
+/// What to do with an object version
+#[derive(Debug, PartialEq, Eq)]
+enum Plan {
+    Skip,
+    Reencode { storage_class: String },
+}
+
+/// Decide whether `fi` needs to be rewritten to reach the parity and bitrot algorithm of its target storage class
+fn plan_version(
+    fi: &FileInfo,
+    requested_class: Option<&str>,
+    target_parity: impl Fn(&str) -> usize,
+    target_bitrot: impl Fn(&str) -> HashAlgorithm,
+) -> Plan {
+    if fi.deleted || fi.is_remote() {
+        return Plan::Skip;
+    }
+
+    let current_class = fi.metadata.get(AMZ_STORAGE_CLASS).map(String::as_str).unwrap_or(STANDARD);
+    let storage_class = requested_class.unwrap_or(current_class);
+    if storage_class == current_class
+        && fi.erasure.parity_blocks == target_parity(storage_class)
+        && fi.erasure.bitrot_algorithm() == target_bitrot(storage_class)
+    {
+        return Plan::Skip;
+    }
+
+    Plan::Reencode {
+        storage_class: storage_class.to_string(),
+    }
+}
+
+/// Progress of the current or last re-encode job on this node
+pub fn reencode_status() -> Option<ReencodeProgress> {
+    GLOBAL_REENCODE_JOB
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|job| job.progress())
+}
+
+/// Cancel the running re-encode job, returns whether one was running
+pub fn cancel_reencode() -> bool {
+    let job = GLOBAL_REENCODE_JOB.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    match job {
+        Some(job) if job.is_running() => {
+            job.canceled.store(true, Ordering::SeqCst);
+            let _ = job.cancel_tx.send(true);
+            true
+        }
+        _ => false,
+    }
+}
+
+impl ECStore {
+    /// Start re-encoding the objects selected by `req` in the background, returns the job id
+    ///
+    /// Only one job runs at a time on a node.
+    pub async fn start_reencode(self: &Arc<Self>, req: ReencodeRequest) -> Result<String> {
+        if let Some(sc) = req.storage_class.as_deref() {
+            if sc != STANDARD && sc != storageclass::RRS {
+                return Err(Error::other(format!("invalid storage class {sc}")));
+            }
+        }
+        self.get_bucket_info(&req.bucket, &BucketOptions::default()).await?;
+
+        let (cancel_tx, _) = broadcast::channel(1);
+        let job = Arc::new(ReencodeJob {
+            progress: Mutex::new(ReencodeProgress {
+                id: Uuid::new_v4().to_string(),
+                request: req,
+                status: ReencodeStatus::Running,
+                scanned: 0,
+                reencoded: 0,
+                skipped: 0,
+                failed: 0,
+                bytes: 0,
+                last_object: String::new(),
+                last_error: None,
+                started_at: OffsetDateTime::now_utc(),
+                ended_at: None,
+            }),
+            canceled: AtomicBool::new(false),
+            cancel_tx,
+        });
+
+        {
+            let mut current = GLOBAL_REENCODE_JOB.lock().unwrap_or_else(|e| e.into_inner());
+            if current.as_ref().is_some_and(|job| job.is_running()) {
+                return Err(Error::other("a re-encode job is already running"));
+            }
+            *current = Some(job.clone());
+        }
+
+        let id = job.progress().id;
+        let store = self.clone();
+        tokio::spawn(async move {
+            store.run_reencode(job).await;
+        });
+        Ok(id)
+    }
+
+    async fn run_reencode(self: Arc<Self>, job: Arc<ReencodeJob>) {
+        let ReencodeRequest { bucket, .. } = job.progress().request;
+        info!("reencode {}: start on bucket {}", job.progress().id, bucket);
+
+        let mut workers = Vec::new();
+        for pool in self.pools.iter() {
+            for set in pool.disk_set.iter() {
+                let set = set.clone();
+                let job = job.clone();
+                let bucket = bucket.clone();
+                let rx = job.cancel_tx.subscribe();
+                let cb: ListCallback = Arc::new({
+                    let set = set.clone();
+                    let job = job.clone();
+                    move |entry: MetaCacheEntry| {
+                        let set = set.clone();
+                        let job = job.clone();
+                        Box::pin(async move { reencode_entry(set, job, entry).await })
+                    }
+                });
+                workers.push(tokio::spawn(async move { set.list_objects_to_rebalance(rx, bucket, cb).await }));
+            }
+        }
+
+        let mut list_error = None;
+        for worker in workers {
+            match worker.await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => list_error = Some(err.to_string()),
+                Err(err) => list_error = Some(err.to_string()),
+            }
+        }
+
+        job.update(|p| {
+            p.status = if job.canceled.load(Ordering::SeqCst) {
+                ReencodeStatus::Canceled
+            } else if let Some(err) = list_error {
+                p.last_error = Some(err);
+                ReencodeStatus::Failed
+            } else {
+                ReencodeStatus::Completed
+            };
+            p.ended_at = Some(OffsetDateTime::now_utc());
+        });
+        let progress = job.progress();
+        info!(
+            "reencode {}: {:?}, {} re-encoded, {} skipped, {} failed",
+            progress.id, progress.status, progress.reencoded, progress.skipped, progress.failed
+        );
+    }
+}
+
+async fn reencode_entry(set: Arc<SetDisks>, job: Arc<ReencodeJob>, entry: MetaCacheEntry) {
+    let request = job.progress().request;
+    if entry.is_dir() || !entry.name.starts_with(&request.prefix) || job.canceled.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let fivs = match entry.file_info_versions(&request.bucket) {
+        Ok(fivs) => fivs,
+        Err(err) => {
+            error!("reencode_entry: file_info_versions {}/{} err {:?}", request.bucket, entry.name, err);
+            return;
+        }
+    };
+
+    let target_parity = |sc: &str| {
+        GLOBAL_STORAGE_CLASS
+            .get()
+            .and_then(|config| config.get_parity_for_sc(sc))
+            .unwrap_or(set.default_parity_count)
+    };
+    let target_bitrot = |sc: &str| {
+        GLOBAL_STORAGE_CLASS
+            .get()
+            .map(|config| config.get_bitrot_algo_for_sc(sc))
+            .unwrap_or(storageclass::DEFAULT_BITROT_ALGORITHM)
+    };
+
+    for version in fivs.versions.iter() {
+        job.update(|p| {
+            p.scanned += 1;
+            p.last_object = version.name.clone();
+        });
+
+        let plan = plan_version(version, request.storage_class.as_deref(), target_parity, target_bitrot);
+        let Plan::Reencode { storage_class } = plan else {
+            job.update(|p| p.skipped += 1);
+            continue;
+        };
+
+        if let Err(err) = storageclass::validate_parity(target_parity(&storage_class), set.set_drive_count) {
+            job.update(|p| {
+                p.failed += 1;
+                p.last_error = Some(err.to_string());
+            });
+            continue;
+        }
+
+        match reencode_version(&set, &request.bucket, version, &storage_class).await {
+            Ok(true) => job.update(|p| {
+                p.reencoded += 1;
+                p.bytes += version.size.max(0) as u64;
+            }),
+            Ok(false) => job.update(|p| p.skipped += 1),
+            Err(err) => {
+                warn!(
+                    "reencode_entry: {}/{} {:?} err {:?}",
+                    request.bucket, version.name, version.version_id, err
+                );
+                job.update(|p| {
+                    p.failed += 1;
+                    p.last_error = Some(format!("{}: {}", version.name, err));
+                });
+            }
+        }
+    }
+}
+
+/// Rewrite one object version with the parity and bitrot algorithm of `storage_class`
+///
+/// Returns `false` when the version was deleted or overwritten since it was listed. The object stays write
+/// locked from the read to the rewrite, so that no overwrite in between is replaced by the old data.
+async fn reencode_version(set: &Arc<SetDisks>, bucket: &str, version: &FileInfo, storage_class: &str) -> Result<bool> {
+    let object = encode_dir_object(&version.name);
+    let lock = set.lock_paths(&[object.clone()], &ObjectOptions::default()).await?;
+    let result = reencode_version_locked(set, bucket, &object, version, storage_class).await;
+    if let Some(lock) = lock {
+        lock.release().await;
+    }
+    result
+}
+
+async fn reencode_version_locked(
+    set: &Arc<SetDisks>,
+    bucket: &str,
+    object: &str,
+    version: &FileInfo,
+    storage_class: &str,
+) -> Result<bool> {
+    let version_id = version.version_id.map(|v| v.to_string());
+    let rd = match set
+        .get_object_reader(
+            bucket,
+            object,
+            None,
+            HeaderMap::new(),
+            &ObjectOptions {
+                version_id: version_id.clone(),
+                no_lock: true,
+                no_decryption: true,
+                ..Default::default()
+            },
+        )
+        .await
+    {
+        Ok(rd) => rd,
+        Err(err) if is_err_object_not_found(&err) || is_err_version_not_found(&err) => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    if rd.object_info.mod_time != version.mod_time {
+        return Ok(false);
+    }
+
+    let mut user_defined = rd.object_info.user_defined.clone();
+    user_defined.insert(AMZ_STORAGE_CLASS.to_string(), storage_class.to_string());
+
+    if rd.object_info.is_multipart() {
+        reencode_multipart(set, bucket, rd, version_id, user_defined).await?;
+        return Ok(true);
+    }
+
+    let object_info = rd.object_info.clone();
+    let actual_size = object_info.get_actual_size()?;
+    let reader = BufReader::new(rd.stream);
+    let hrd = HashReader::new(Box::new(WarpReader::new(reader)), object_info.size, actual_size, None, false)?;
+    let mut data = PutObjReader::new(hrd);
+    set.put_object(
+        bucket,
+        &object_info.name,
+        &mut data,
+        &ObjectOptions {
+            version_id,
+            mod_time: object_info.mod_time,
+            user_defined,
+            preserve_etag: object_info.etag.clone(),
+            no_lock: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Multipart objects are rewritten part by part so their ETag and part layout are kept
+async fn reencode_multipart(
+    set: &Arc<SetDisks>,
+    bucket: &str,
+    rd: GetObjectReader,
+    version_id: Option<String>,
+    user_defined: std::collections::HashMap<String, String>,
+) -> Result<()> {
+    let object_info = rd.object_info.clone();
+    let res = set
+        .new_multipart_upload(
+            bucket,
+            &object_info.name,
+            &ObjectOptions {
+                version_id,
+                user_defined,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let result = async {
+        let mut parts = Vec::with_capacity(object_info.parts.len());
+        let reader = SharedStream(Arc::new(Mutex::new(rd.stream)));
+        for part in object_info.parts.iter() {
+            let size = part.size as i64;
+            let actual_size = if object_info.is_encrypted() { part.actual_size } else { size };
+            let chunk = reader.clone().take(part.size as u64);
+            let mut data = PutObjReader::new(HashReader::new(Box::new(WarpReader::new(chunk)), size, actual_size, None, false)?);
+
+            let pi = set
+                .put_object_part(
+                    bucket,
+                    &object_info.name,
+                    &res.upload_id,
+                    part.number,
+                    &mut data,
+                    &ObjectOptions {
+                        preserve_etag: Some(part.etag.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            parts.push(CompletePart {
+                part_num: pi.part_num,
+                etag: pi.etag,
+                ..Default::default()
+            });
+        }
+
+        set.clone()
+            .complete_multipart_upload(
+                bucket,
+                &object_info.name,
+                &res.upload_id,
+                parts,
+                &ObjectOptions {
+                    mod_time: object_info.mod_time,
+                    no_lock: true,
+                    ..Default::default()
+                },
+            )
+            .await
+    }
+    .await;
+
+    if let Err(err) = result {
+        if let Err(abort_err) = set
+            .abort_multipart_upload(bucket, &object_info.name, &res.upload_id, &ObjectOptions::default())
+            .await
+        {
+            error!("reencode_multipart: abort_multipart_upload err {:?}", abort_err);
+        }
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// The stream of a whole object, read part by part by the readers of the parts in turn
+#[derive(Clone)]
+struct SharedStream(Arc<Mutex<Box<dyn AsyncRead + Unpin + Send + Sync>>>);
+
+impl AsyncRead for SharedStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        let mut stream = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        Pin::new(&mut **stream).poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustfs_filemeta::{ChecksumInfo, ErasureInfo};
+
+    fn version(parity: usize, storage_class: Option<&str>) -> FileInfo {
+        let mut fi = FileInfo {
+            erasure: ErasureInfo {
+                data_blocks: 8 - parity,
+                parity_blocks: parity,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        if let Some(sc) = storage_class {
+            fi.metadata.insert(AMZ_STORAGE_CLASS.to_string(), sc.to_string());
+        }
+        fi
+    }
+
+    fn parity(sc: &str) -> usize {
+        if sc == storageclass::RRS { 2 } else { 4 }
+    }
+
+    fn bitrot(_sc: &str) -> HashAlgorithm {
+        storageclass::DEFAULT_BITROT_ALGORITHM
+    }
+
+    #[test]
+    fn test_plan_reencodes_parity_mismatch_only() {
+        assert_eq!(plan_version(&version(4, None), None, parity, bitrot), Plan::Skip);
+        assert_eq!(plan_version(&version(2, Some(storageclass::RRS)), None, parity, bitrot), Plan::Skip);
+        assert_eq!(
+            plan_version(&version(2, None), None, parity, bitrot),
+            Plan::Reencode {
+                storage_class: STANDARD.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_plan_moves_to_requested_class() {
+        assert_eq!(
+            plan_version(&version(4, None), Some(storageclass::RRS), parity, bitrot),
+            Plan::Reencode {
+                storage_class: storageclass::RRS.to_string()
+            }
+        );
+        assert_eq!(plan_version(&version(4, Some(STANDARD)), Some(STANDARD), parity, bitrot), Plan::Skip);
+
+        let mut deleted = version(2, None);
+        deleted.deleted = true;
+        assert_eq!(plan_version(&deleted, None, parity, bitrot), Plan::Skip);
+    }
+
+    #[test]
+    fn test_plan_migrates_bitrot_algorithm() {
+        let mut blake3 = version(4, None);
+        blake3.erasure.add_checksum_info(ChecksumInfo {
+            part_number: 1,
+            algorithm: HashAlgorithm::Blake3,
+            ..Default::default()
+        });
+        assert_eq!(
+            plan_version(&blake3, None, parity, bitrot),
+            Plan::Reencode {
+                storage_class: STANDARD.to_string()
+            }
+        );
+        assert_eq!(plan_version(&blake3, None, parity, |_| HashAlgorithm::Blake3), Plan::Skip);
+    }
+}
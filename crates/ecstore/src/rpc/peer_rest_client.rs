@@ -19,6 +19,7 @@ use crate::{
     metrics_realtime::{CollectMetricsOpts, MetricType},
 };
 use rmp_serde::{Deserializer, Serializer};
+use rustfs_lock::{HeldLock, LockRequest, LockType, ResourceContention};
 use rustfs_madmin::{
     ServerProperties,
     health::{Cpus, MemInfo, OsInfo, Partitions, ProcInfo, SysConfig, SysErrors, SysService},
@@ -28,12 +29,13 @@ use rustfs_madmin::{
 use rustfs_protos::{
     node_service_time_out_client,
     proto_gen::node_service::{
-        DeleteBucketMetadataRequest, DeletePolicyRequest, DeleteServiceAccountRequest, DeleteUserRequest, GetCpusRequest,
-        GetMemInfoRequest, GetMetricsRequest, GetNetInfoRequest, GetOsInfoRequest, GetPartitionsRequest, GetProcInfoRequest,
-        GetSeLinuxInfoRequest, GetSysConfigRequest, GetSysErrorsRequest, LoadBucketMetadataRequest, LoadGroupRequest,
-        LoadPolicyMappingRequest, LoadPolicyRequest, LoadRebalanceMetaRequest, LoadServiceAccountRequest,
-        LoadTransitionTierConfigRequest, LoadUserRequest, LocalStorageInfoRequest, Mss, ReloadPoolMetaRequest,
-        ReloadSiteReplicationConfigRequest, ServerInfoRequest, SignalServiceRequest, StartProfilingRequest, StopRebalanceRequest,
+        DeleteBucketMetadataRequest, DeletePolicyRequest, DeleteServiceAccountRequest, DeleteUserRequest, GenerallyLockRequest,
+        GetCpusRequest, GetMemInfoRequest, GetMetricsRequest, GetNetInfoRequest, GetOsInfoRequest, GetPartitionsRequest,
+        GetProcInfoRequest, GetSeLinuxInfoRequest, GetSysConfigRequest, GetSysErrorsRequest, ListLocksRequest,
+        LoadBucketMetadataRequest, LoadGroupRequest, LoadPolicyMappingRequest, LoadPolicyRequest, LoadRebalanceMetaRequest,
+        LoadServiceAccountRequest, LoadTransitionTierConfigRequest, LoadUserRequest, LocalStorageInfoRequest,
+        LockContentionRequest, Mss, ReloadPoolMetaRequest, ReloadSiteReplicationConfigRequest, ReloadStsRevocationsRequest,
+        ServerInfoRequest, SignalServiceRequest, StartProfilingRequest, StopRebalanceRequest,
     },
 };
 use rustfs_utils::XHost;
@@ -127,6 +129,69 @@ impl PeerRestClient {
         Ok(storage_properties)
     }
 
+    pub async fn list_locks(&self) -> Result<Vec<HeldLock>> {
+        let mut client = node_service_time_out_client(&self.grid_host)
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+        let request = Request::new(ListLocksRequest {});
+
+        let response = client.list_locks(request).await?.into_inner();
+        if !response.success {
+            if let Some(msg) = response.error_info {
+                return Err(Error::other(msg));
+            }
+            return Err(Error::other(""));
+        }
+        let data = response.locks;
+
+        let mut buf = Deserializer::new(Cursor::new(data));
+        let locks: Vec<HeldLock> = Deserialize::deserialize(&mut buf)?;
+
+        Ok(locks)
+    }
+
+    /// The `count` most contended resources of the peer's lock server
+    pub async fn lock_contention(&self, count: usize) -> Result<Vec<ResourceContention>> {
+        let mut client = node_service_time_out_client(&self.grid_host)
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+        let request = Request::new(LockContentionRequest {
+            count: count.try_into().unwrap_or(u32::MAX),
+        });
+
+        let response = client.lock_contention(request).await?.into_inner();
+        if !response.success {
+            if let Some(msg) = response.error_info {
+                return Err(Error::other(msg));
+            }
+            return Err(Error::other(""));
+        }
+        let data = response.stats;
+
+        let mut buf = Deserializer::new(Cursor::new(data));
+        let stats: Vec<ResourceContention> = Deserialize::deserialize(&mut buf)?;
+
+        Ok(stats)
+    }
+
+    /// Release the lock on `resource` whatever its holders, returns whether the peer held it
+    pub async fn force_unlock(&self, resource: &str, owner: &str) -> Result<bool> {
+        let mut client = node_service_time_out_client(&self.grid_host)
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+        let args = LockRequest::new(resource, LockType::Exclusive, owner);
+        let request = Request::new(GenerallyLockRequest {
+            args: serde_json::to_string(&args)?,
+            timeout_ms: None,
+        });
+
+        let response = client.force_un_lock(request).await?.into_inner();
+        if let Some(msg) = response.error_info {
+            return Err(Error::other(msg));
+        }
+        Ok(response.success)
+    }
+
     pub async fn get_cpus(&self) -> Result<Cpus> {
         let mut client = node_service_time_out_client(&self.grid_host)
             .await
@@ -613,6 +678,23 @@ impl PeerRestClient {
         todo!()
     }
 
+    pub async fn reload_sts_revocations(&self) -> Result<()> {
+        let mut client = node_service_time_out_client(&self.grid_host)
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+        let request = Request::new(ReloadStsRevocationsRequest {});
+
+        let response = client.reload_sts_revocations(request).await?.into_inner();
+        if !response.success {
+            if let Some(msg) = response.error_info {
+                return Err(Error::other(msg));
+            }
+            return Err(Error::other(""));
+        }
+
+        Ok(())
+    }
+
     pub async fn reload_pool_meta(&self) -> Result<()> {
         let mut client = node_service_time_out_client(&self.grid_host)
             .await
@@ -45,6 +45,9 @@ use tonic::Request;
 use tracing::info;
 use uuid::Uuid;
 
+/// Chunks of a remote shard read ahead of the erasure decoder
+const READ_STREAM_WINDOW: usize = 16;
+
 #[derive(Debug)]
 pub struct RemoteDisk {
     pub id: Mutex<Option<Uuid>>,
@@ -635,7 +638,8 @@ impl DiskAPI for RemoteDisk {
         let mut headers = HeaderMap::new();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
         build_auth_headers(&url, &Method::GET, &mut headers);
-        Ok(Box::new(HttpReader::new(url, Method::GET, headers, None).await?))
+        // Shard data flows from the drive's node straight into the decoder, a failed read is reported by the GET itself
+        Ok(Box::new(HttpReader::stream(url, headers, READ_STREAM_WINDOW).await?))
     }
 
     #[tracing::instrument(level = "debug", skip(self))]
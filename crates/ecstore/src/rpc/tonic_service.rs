@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{collections::HashMap, io::Cursor, pin::Pin, sync::Arc};
+use std::{collections::HashMap, io::Cursor, pin::Pin, sync::Arc, time::Duration};
 
 // use common::error::Error as EcsError;
 use crate::{
@@ -24,6 +24,7 @@ use crate::{
     },
     metrics_realtime::{CollectMetricsOpts, MetricType, collect_local_metrics},
     new_object_layer_fn,
+    notification_sys::reload_sts_revocations_local,
     rpc::{LocalPeerS3Client, PeerS3Client},
     store::{all_local_disk_path, find_local_disk},
     store_api::{BucketOptions, DeleteBucketOptions, MakeBucketOptions, StorageAPI},
@@ -36,7 +37,7 @@ use rustfs_common::{globals::GLOBAL_Local_Node_Name, heal_channel::HealOpts};
 use bytes::Bytes;
 use rmp_serde::{Deserializer, Serializer};
 use rustfs_filemeta::{FileInfo, MetacacheReader};
-use rustfs_lock::{LockClient, LockRequest};
+use rustfs_lock::{LockCapabilities, LockClient, LockRequest};
 use rustfs_madmin::health::{
     get_cpus, get_mem_info, get_os_info, get_partitions, get_proc_info, get_sys_config, get_sys_errors, get_sys_services,
 };
@@ -1438,7 +1439,7 @@ impl Node for NodeService {
     async fn lock(&self, request: Request<GenerallyLockRequest>) -> Result<Response<GenerallyLockResponse>, Status> {
         let request = request.into_inner();
         // Parse the request to extract resource and owner
-        let args: LockRequest = match serde_json::from_str(&request.args) {
+        let mut args: LockRequest = match serde_json::from_str(&request.args) {
             Ok(args) => args,
             Err(err) => {
                 return Ok(tonic::Response::new(GenerallyLockResponse {
@@ -1447,6 +1448,10 @@ impl Node for NodeService {
                 }));
             }
         };
+        // The caller stops waiting after timeout_ms, so do not hold a waiter past it
+        if let Some(timeout_ms) = request.timeout_ms {
+            args.acquire_timeout = args.acquire_timeout.min(Duration::from_millis(timeout_ms));
+        }
 
         match self.lock_manager.acquire_exclusive(&args).await {
             Ok(result) => Ok(tonic::Response::new(GenerallyLockResponse {
@@ -1492,7 +1497,7 @@ impl Node for NodeService {
 
     async fn r_lock(&self, request: Request<GenerallyLockRequest>) -> Result<Response<GenerallyLockResponse>, Status> {
         let request = request.into_inner();
-        let args: LockRequest = match serde_json::from_str(&request.args) {
+        let mut args: LockRequest = match serde_json::from_str(&request.args) {
             Ok(args) => args,
             Err(err) => {
                 return Ok(tonic::Response::new(GenerallyLockResponse {
@@ -1501,6 +1506,10 @@ impl Node for NodeService {
                 }));
             }
         };
+        // The caller stops waiting after timeout_ms, so do not hold a waiter past it
+        if let Some(timeout_ms) = request.timeout_ms {
+            args.acquire_timeout = args.acquire_timeout.min(Duration::from_millis(timeout_ms));
+        }
 
         match self.lock_manager.acquire_shared(&args).await {
             Ok(result) => Ok(tonic::Response::new(GenerallyLockResponse {
@@ -1556,9 +1565,9 @@ impl Node for NodeService {
             }
         };
 
-        match self.lock_manager.release(&args.lock_id).await {
-            Ok(_) => Ok(tonic::Response::new(GenerallyLockResponse {
-                success: true,
+        match self.lock_manager.force_release(&args.lock_id).await {
+            Ok(success) => Ok(tonic::Response::new(GenerallyLockResponse {
+                success,
                 error_info: None,
             })),
             Err(err) => Ok(tonic::Response::new(GenerallyLockResponse {
@@ -1573,7 +1582,7 @@ impl Node for NodeService {
 
     async fn refresh(&self, request: Request<GenerallyLockRequest>) -> Result<Response<GenerallyLockResponse>, Status> {
         let request = request.into_inner();
-        let _args: LockRequest = match serde_json::from_str(&request.args) {
+        let args: LockRequest = match serde_json::from_str(&request.args) {
             Ok(args) => args,
             Err(err) => {
                 return Ok(tonic::Response::new(GenerallyLockResponse {
@@ -1583,8 +1592,65 @@ impl Node for NodeService {
             }
         };
 
-        Ok(tonic::Response::new(GenerallyLockResponse {
+        match self.lock_manager.refresh(&args.lock_id).await {
+            Ok(success) => Ok(tonic::Response::new(GenerallyLockResponse {
+                success,
+                error_info: None,
+            })),
+            Err(err) => Ok(tonic::Response::new(GenerallyLockResponse {
+                success: false,
+                error_info: Some(format!(
+                    "can not refresh, resource: {0}, owner: {1}, err: {2}",
+                    args.resource, args.owner, err
+                )),
+            })),
+        }
+    }
+
+    async fn list_locks(&self, _request: Request<ListLocksRequest>) -> Result<Response<ListLocksResponse>, Status> {
+        let locks = self.lock_manager.get_lock_map().list_locks().await;
+        let mut buf = Vec::new();
+        if let Err(err) = locks.serialize(&mut Serializer::new(&mut buf)) {
+            return Ok(tonic::Response::new(ListLocksResponse {
+                success: false,
+                locks: Bytes::new(),
+                error_info: Some(err.to_string()),
+            }));
+        }
+        Ok(tonic::Response::new(ListLocksResponse {
+            success: true,
+            locks: buf.into(),
+            error_info: None,
+        }))
+    }
+
+    async fn hello(&self, request: Request<HelloRequest>) -> Result<Response<HelloResponse>, Status> {
+        let request = request.into_inner();
+        debug!(
+            "lock client speaks protocol version {} with features {:?}",
+            request.protocol_version, request.features
+        );
+        let capabilities = LockCapabilities::current();
+        Ok(tonic::Response::new(HelloResponse {
+            protocol_version: capabilities.protocol_version,
+            features: capabilities.features.into_iter().collect(),
+        }))
+    }
+
+    async fn lock_contention(&self, request: Request<LockContentionRequest>) -> Result<Response<LockContentionResponse>, Status> {
+        let count = request.into_inner().count as usize;
+        let stats = rustfs_lock::top_contended_local(count);
+        let mut buf = Vec::new();
+        if let Err(err) = stats.serialize(&mut Serializer::new(&mut buf)) {
+            return Ok(tonic::Response::new(LockContentionResponse {
+                success: false,
+                stats: Bytes::new(),
+                error_info: Some(err.to_string()),
+            }));
+        }
+        Ok(tonic::Response::new(LockContentionResponse {
             success: true,
+            stats: buf.into(),
             error_info: None,
         }))
     }
@@ -2119,6 +2185,22 @@ impl Node for NodeService {
         todo!()
     }
 
+    async fn reload_sts_revocations(
+        &self,
+        _request: Request<ReloadStsRevocationsRequest>,
+    ) -> Result<Response<ReloadStsRevocationsResponse>, Status> {
+        if !reload_sts_revocations_local() {
+            return Ok(tonic::Response::new(ReloadStsRevocationsResponse {
+                success: false,
+                error_info: Some("errServerNotInitialized".to_string()),
+            }));
+        }
+        Ok(tonic::Response::new(ReloadStsRevocationsResponse {
+            success: true,
+            error_info: None,
+        }))
+    }
+
     async fn reload_pool_meta(
         &self,
         _request: Request<ReloadPoolMetaRequest>,
@@ -3052,6 +3134,7 @@ mod tests {
 
         let request = Request::new(GenerallyLockRequest {
             args: "invalid json".to_string(),
+            timeout_ms: None,
         });
 
         let response = service.lock(request).await;
@@ -3062,12 +3145,33 @@ mod tests {
         assert!(lock_response.error_info.is_some());
     }
 
+    #[tokio::test]
+    async fn test_lock_gives_up_with_caller() {
+        let service = create_test_node_service();
+        let lock = |owner: &str, timeout_ms: Option<u64>| {
+            let args = LockRequest::new("test_lock_gives_up_with_caller", rustfs_lock::LockType::Exclusive, owner)
+                .with_acquire_timeout(Duration::from_secs(10));
+            Request::new(GenerallyLockRequest {
+                args: serde_json::to_string(&args).unwrap(),
+                timeout_ms,
+            })
+        };
+        assert!(service.lock(lock("holder", None)).await.unwrap().into_inner().success);
+
+        // The caller only waits 50ms more, the server must not hold the waiter for 10s
+        let start = std::time::Instant::now();
+        let response = service.lock(lock("waiter", Some(50))).await.unwrap().into_inner();
+        assert!(!response.success);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
     #[tokio::test]
     async fn test_un_lock_invalid_args() {
         let service = create_test_node_service();
 
         let request = Request::new(GenerallyLockRequest {
             args: "invalid json".to_string(),
+            timeout_ms: None,
         });
 
         let response = service.un_lock(request).await;
@@ -3084,6 +3188,7 @@ mod tests {
 
         let request = Request::new(GenerallyLockRequest {
             args: "invalid json".to_string(),
+            timeout_ms: None,
         });
 
         let response = service.r_lock(request).await;
@@ -3100,6 +3205,7 @@ mod tests {
 
         let request = Request::new(GenerallyLockRequest {
             args: "invalid json".to_string(),
+            timeout_ms: None,
         });
 
         let response = service.r_un_lock(request).await;
@@ -3116,6 +3222,7 @@ mod tests {
 
         let request = Request::new(GenerallyLockRequest {
             args: "invalid json".to_string(),
+            timeout_ms: None,
         });
 
         let response = service.force_un_lock(request).await;
@@ -3132,6 +3239,7 @@ mod tests {
 
         let request = Request::new(GenerallyLockRequest {
             args: "invalid json".to_string(),
+            timeout_ms: None,
         });
 
         let response = service.refresh(request).await;
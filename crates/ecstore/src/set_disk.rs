@@ -16,8 +16,14 @@
 #![allow(unused_variables)]
 
 use crate::bitrot::{create_bitrot_reader, create_bitrot_writer};
+use crate::bucket::encryption::keys::{SSE_PART_NONCES_META, is_encrypted};
 use crate::bucket::lifecycle::lifecycle::TRANSITION_COMPLETE;
+use crate::bucket::object_lock::objectlock::utc_now_ntp;
+use crate::bucket::object_lock::objectlock_sys::check_retention_for_deletion;
 use crate::client::{object_api_utils::extract_etag, transition_api::ReaderImpl};
+use crate::cmd::bucket_replication::{
+    OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP, OBJECT_LOCK_RETENTION_TIMESTAMP, TAGGING_TIMESTAMP, metadata_timestamp,
+};
 use crate::disk::STORAGE_FORMAT_FILE;
 use crate::disk::error_reduce::{OBJECT_OP_IGNORED_ERRS, reduce_read_quorum_errs, reduce_write_quorum_errs};
 use crate::disk::{
@@ -25,14 +31,18 @@ use crate::disk::{
     conv_part_err_to_int, has_part_err,
 };
 use crate::erasure_coding;
+use crate::erasure_coding::bitrot_shard_file_size;
 use crate::erasure_coding::bitrot_verify;
 use crate::error::{Error, Result};
-use crate::error::{ObjectApiError, is_err_object_not_found};
+use crate::error::{ObjectApiError, is_err_object_not_found, is_err_version_not_found};
 use crate::global::{GLOBAL_LocalNodeName, GLOBAL_TierConfigMgr};
 use crate::store_api::ListObjectVersionsInfo;
 use crate::store_api::{ListPartsInfo, ObjectToDelete};
 use crate::{
-    bucket::lifecycle::bucket_lifecycle_ops::{gen_transition_objname, get_transitioned_object_reader, put_restore_opts},
+    bucket::lifecycle::bucket_lifecycle_ops::{
+        RestoreObjStatus, gen_transition_objname, get_transitioned_object_data, get_transitioned_object_reader,
+        is_restored_object_on_disk, put_restore_opts,
+    },
     cache_value::metacache_set::{ListPathRawOptions, list_path_raw},
     config::{GLOBAL_STORAGE_CLASS, storageclass},
     disk::{
@@ -47,10 +57,12 @@ use crate::{
     store_api::{
         BucketInfo, BucketOptions, CompletePart, DeleteBucketOptions, DeletedObject, GetObjectReader, HTTPRangeSpec,
         ListMultipartsInfo, ListObjectsV2Info, MakeBucketOptions, MultipartInfo, MultipartUploadResult, ObjectIO, ObjectInfo,
-        ObjectOptions, PartInfo, PutObjReader, StorageAPI,
+        ObjectOptions, PartInfo, PutObjReader, StorageAPI, WritePreconditions,
     },
     store_init::load_format_erasure,
 };
+use base64::Engine as _;
+use base64::engine::general_purpose;
 use bytes::Bytes;
 use bytesize::ByteSize;
 use chrono::Utc;
@@ -62,12 +74,12 @@ use rand::{Rng, seq::SliceRandom};
 use rustfs_common::heal_channel::{DriveState, HealChannelPriority, HealItemType, HealOpts, HealScanMode, send_heal_disk};
 use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
 use rustfs_filemeta::{
-    FileInfo, FileMeta, FileMetaShallowVersion, MetaCacheEntries, MetaCacheEntry, MetadataResolutionParams, ObjectPartInfo,
-    RawFileInfo, file_info_from_raw,
+    ChecksumInfo, FileInfo, FileMeta, FileMetaShallowVersion, MetaCacheEntries, MetaCacheEntry, MetadataResolutionParams,
+    ObjectPartInfo, RawFileInfo, file_info_from_raw,
     headers::{AMZ_OBJECT_TAGGING, AMZ_STORAGE_CLASS},
     merge_file_meta_versions,
 };
-use rustfs_lock::NamespaceLockManager;
+use rustfs_lock::{LockGuard, LockType, NamespaceLockManager};
 use rustfs_madmin::heal_commands::{HealDriveInfo, HealResultItem};
 use rustfs_rio::{EtagResolvable, HashReader, TryGetIndex as _, WarpReader};
 use rustfs_utils::{
@@ -76,6 +88,8 @@ use rustfs_utils::{
     path::{SLASH_SEPARATOR, encode_dir_object, has_suffix, path_join_buf},
 };
 use rustfs_workers::workers::Workers;
+use s3s::checksum::ChecksumHasher;
+use s3s::dto::ChecksumAlgorithm;
 use s3s::header::X_AMZ_RESTORE;
 use sha2::{Digest, Sha256};
 use std::hash::Hash;
@@ -90,7 +104,7 @@ use std::{
 };
 use time::OffsetDateTime;
 use tokio::{
-    io::AsyncWrite,
+    io::{AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _},
     sync::{RwLock, broadcast},
 };
 use tokio::{
@@ -310,6 +324,83 @@ impl SetDisks {
         data_count
     }
 
+    /// Write lock `paths` unless `opts.no_lock`, released when the guard is released or dropped,
+    /// so error paths cannot leak the locks
+    pub(crate) async fn lock_paths(&self, paths: &[String], opts: &ObjectOptions) -> Result<Option<LockGuard>> {
+        if opts.no_lock {
+            return Ok(None);
+        }
+        let (timeout, ttl) = (opts.lock_timeout(Duration::from_secs(5)), Duration::from_secs(10));
+        match self
+            .namespace_lock
+            .get_lock(paths, &self.locker_owner, LockType::Exclusive, timeout, ttl, opts.cancel.clone())
+            .await?
+        {
+            Some(lock) => Ok(Some(lock)),
+            None => Err(Error::other("can not get lock. please retry".to_string())),
+        }
+    }
+
+    /// Version of this site at least as new as the one a replication request brings, which is kept instead,
+    /// called with the object locked so that the version compared is the one the write would replace
+    async fn newer_local_version(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<Option<ObjectInfo>> {
+        let Some(mtime) = opts.mod_time.filter(|_| opts.replication_request) else {
+            return Ok(None);
+        };
+        if opts.versioned && opts.version_id.is_none() {
+            return Ok(None);
+        }
+        let version_opts = ObjectOptions {
+            version_id: opts.version_id.clone(),
+            versioned: opts.versioned,
+            version_suspended: opts.version_suspended,
+            no_lock: true,
+            ..Default::default()
+        };
+        match self.get_object_info(bucket, object, &version_opts).await {
+            Ok(info) => Ok(info.mod_time.filter(|local| *local >= mtime).map(|_| info)),
+            Err(err) if is_err_object_not_found(&err) || is_err_version_not_found(&err) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Whether a replication request sets tags, retention or legal hold that `fi` got as late or later on
+    /// this site, the later change winning
+    fn replicated_metadata_outdated(fi: &FileInfo, opts: &ObjectOptions) -> bool {
+        let Some(eval_metadata) = opts.eval_metadata.as_ref().filter(|_| opts.replication_request) else {
+            return false;
+        };
+        [
+            TAGGING_TIMESTAMP,
+            OBJECT_LOCK_RETENTION_TIMESTAMP,
+            OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP,
+        ]
+        .iter()
+        .any(|name| {
+            metadata_timestamp(eval_metadata, name)
+                .zip(metadata_timestamp(&fi.metadata, name))
+                .is_some_and(|(at, local)| local >= at)
+        })
+    }
+
+    /// Fails a write whose preconditions the current version of the object breaks, called with the object
+    /// locked so that no other write comes in between
+    async fn check_write_preconditions(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()> {
+        let Some(preconditions) = &opts.write_preconditions else {
+            return Ok(());
+        };
+        let current_opts = ObjectOptions {
+            no_lock: true,
+            ..Default::default()
+        };
+        let current = match self.get_object_info(bucket, object, &current_opts).await {
+            Ok(info) => Some(info).filter(|info| !info.delete_marker),
+            Err(err) if is_err_object_not_found(&err) || is_err_version_not_found(&err) => None,
+            Err(err) => return Err(err),
+        };
+        preconditions.check(bucket, object, current.as_ref())
+    }
+
     #[tracing::instrument(level = "debug", skip(disks, file_infos))]
     #[allow(clippy::type_complexity)]
     async fn rename_data(
@@ -1954,6 +2045,431 @@ impl SetDisks {
         shuffled_parts_metadata
     }
 
+    /// Bitrot algorithm configured for the storage class requested in the object metadata
+    fn bitrot_algo_for_sc(user_defined: &HashMap<String, String>) -> HashAlgorithm {
+        GLOBAL_STORAGE_CLASS
+            .get()
+            .map(|sc| sc.get_bitrot_algo_for_sc(user_defined.get(AMZ_STORAGE_CLASS).map(String::as_str).unwrap_or_default()))
+            .unwrap_or(storageclass::DEFAULT_BITROT_ALGORITHM)
+    }
+
+    /// Appends to an object its metadata holds the data of by writing it again whole, its data being small
+    async fn append_inline_object(
+        &self,
+        bucket: &str,
+        object: &str,
+        fi: &FileInfo,
+        data: &mut PutObjReader,
+        opts: &ObjectOptions,
+    ) -> Result<ObjectInfo> {
+        let version_id = fi.version_id.map(|v| v.to_string());
+        let read_opts = ObjectOptions {
+            no_lock: true,
+            version_id: version_id.clone(),
+            ..Default::default()
+        };
+        let mut existing = Vec::with_capacity(fi.size.max(0) as usize);
+        let mut reader = self
+            .get_object_reader(bucket, object, None, HeaderMap::new(), &read_opts)
+            .await?;
+        reader.stream.read_to_end(&mut existing).await?;
+
+        let size = if data.size() < 0 {
+            -1
+        } else {
+            existing.len() as i64 + data.size()
+        };
+        let appended = mem::replace(
+            &mut data.stream,
+            HashReader::new(Box::new(WarpReader::new(Cursor::new(Vec::new()))), 0, 0, None, false)?,
+        );
+        let stream = HashReader::new(
+            Box::new(WarpReader::new(std::io::Cursor::new(existing).chain(appended))),
+            size,
+            size,
+            None,
+            false,
+        )?;
+
+        let mut user_defined = fi.metadata.clone();
+        user_defined.remove("etag");
+        for key in ["inline-data", "actual-size", "checksums", "part-checksums"] {
+            user_defined.remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}{key}"));
+        }
+        if let Some(append_metadata) = opts.append_metadata {
+            append_metadata(&mut user_defined);
+        }
+
+        let put_opts = ObjectOptions {
+            user_defined,
+            version_id,
+            // The object must be the one read until it is written again
+            write_preconditions: Some(WritePreconditions {
+                if_match: fi.metadata.get("etag").cloned(),
+                if_none_match: None,
+                mod_time: None,
+            }),
+            ..opts.clone()
+        };
+        self.put_object(bucket, object, &mut PutObjReader::new(stream), &put_opts)
+            .await
+    }
+
+    /// Update the metadata of the object version picked by `opts` while holding the lock of the object, failing
+    /// when the version breaks the write preconditions of `opts`
+    async fn put_object_metadata_locked(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<ObjectInfo> {
+        let disks = self.get_disks_internal().await;
+
+        let (metas, errs) = {
+            if opts.version_id.is_some() {
+                Self::read_all_fileinfo(
+                    &disks,
+                    "",
+                    bucket,
+                    object,
+                    opts.version_id.as_ref().unwrap().to_string().as_str(),
+                    false,
+                    false,
+                )
+                .await?
+            } else {
+                Self::read_all_xl(&disks, bucket, object, false, false).await
+            }
+        };
+
+        let read_quorum = match Self::object_quorum_from_meta(&metas, &errs, self.default_parity_count) {
+            Ok((res, _)) => res,
+            Err(mut err) => {
+                if err == DiskError::ErasureReadQuorum
+                    && !bucket.starts_with(RUSTFS_META_BUCKET)
+                    && self
+                        .delete_if_dang_ling(bucket, object, &metas, &errs, &HashMap::new(), opts.clone())
+                        .await
+                        .is_ok()
+                {
+                    if opts.version_id.is_some() {
+                        err = DiskError::FileVersionNotFound
+                    } else {
+                        err = DiskError::FileNotFound
+                    }
+                }
+                return Err(to_object_err(err.into(), vec![bucket, object]));
+            }
+        };
+
+        let read_quorum = read_quorum as usize;
+
+        let (online_disks, mod_time, etag) = Self::list_online_disks(&disks, &metas, &errs, read_quorum);
+
+        let mut fi = Self::pick_valid_fileinfo(&metas, mod_time, etag, read_quorum)
+            .map_err(|e| to_object_err(e.into(), vec![bucket, object]))?;
+
+        if fi.deleted {
+            return Err(to_object_err(Error::MethodNotAllowed, vec![bucket, object]));
+        }
+
+        let obj_info = ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended);
+        if let Some(preconditions) = &opts.write_preconditions {
+            preconditions.check(bucket, object, Some(&obj_info))?;
+        }
+        if Self::replicated_metadata_outdated(&fi, opts) {
+            return Ok(obj_info);
+        }
+
+        for (k, v) in obj_info.user_defined {
+            fi.metadata.insert(k, v);
+        }
+
+        if let Some(mt) = &opts.eval_metadata {
+            for (k, v) in mt {
+                fi.metadata.insert(k.clone(), v.clone());
+            }
+        }
+
+        if opts.mod_time.is_some() {
+            fi.mod_time = opts.mod_time;
+        }
+        if let Some(ref version_id) = opts.version_id {
+            fi.version_id = Uuid::parse_str(version_id).ok();
+        }
+
+        self.update_object_meta(bucket, object, fi.clone(), &online_disks)
+            .await
+            .map_err(|e| to_object_err(e.into(), vec![bucket, object]))?;
+
+        Ok(ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended))
+    }
+
+    /// Copies an object to another object of the set by copying the shards each drive holds, so its data is
+    /// neither decoded nor encoded again. None when the copy can't keep them: encrypted or transitioned data,
+    /// or a destination storage class with another parity or bitrot algorithm
+    #[allow(clippy::too_many_arguments)]
+    async fn copy_object_shards(
+        &self,
+        src_bucket: &str,
+        src_object: &str,
+        dst_bucket: &str,
+        dst_object: &str,
+        src_info: &ObjectInfo,
+        src_opts: &ObjectOptions,
+        dst_opts: &ObjectOptions,
+    ) -> Result<Option<ObjectInfo>> {
+        let disks = self.get_disks_internal().await;
+
+        let (metas, errs) = {
+            if let Some(vid) = &src_opts.version_id {
+                Self::read_all_fileinfo(&disks, "", src_bucket, src_object, vid, true, false).await?
+            } else {
+                Self::read_all_xl(&disks, src_bucket, src_object, true, false).await
+            }
+        };
+
+        let (read_quorum, write_quorum) = Self::object_quorum_from_meta(&metas, &errs, self.default_parity_count)
+            .map_err(|e| to_object_err(e.into(), vec![src_bucket, src_object]))?;
+        let (read_quorum, write_quorum) = (read_quorum as usize, write_quorum as usize);
+
+        let (online_disks, mod_time, etag) = Self::list_online_disks(&disks, &metas, &errs, read_quorum);
+
+        let fi = Self::pick_valid_fileinfo(&metas, mod_time, etag, read_quorum)
+            .map_err(|e| to_object_err(e.into(), vec![src_bucket, src_object]))?;
+
+        if fi.deleted {
+            if src_opts.version_id.is_none() {
+                return Err(to_object_err(Error::FileNotFound, vec![src_bucket, src_object]));
+            }
+            return Err(to_object_err(Error::MethodNotAllowed, vec![src_bucket, src_object]));
+        }
+
+        let parity = GLOBAL_STORAGE_CLASS
+            .get()
+            .and_then(|sc| {
+                sc.get_parity_for_sc(
+                    src_info
+                        .user_defined
+                        .get(AMZ_STORAGE_CLASS)
+                        .map(String::as_str)
+                        .unwrap_or_default(),
+                )
+            })
+            .unwrap_or(self.default_parity_count);
+        let bitrot_algo = Self::bitrot_algo_for_sc(&src_info.user_defined);
+        if fi.is_remote()
+            || is_encrypted(&fi.metadata)
+            || is_encrypted(&src_info.user_defined)
+            || parity != fi.erasure.parity_blocks
+            || fi
+                .parts
+                .iter()
+                .any(|part| fi.erasure.get_checksum_info(part.number).algorithm != bitrot_algo)
+        {
+            return Ok(None);
+        }
+
+        let lock = self.lock_paths(&[dst_object.to_string()], dst_opts).await?;
+        self.check_write_preconditions(dst_bucket, dst_object, dst_opts).await?;
+
+        // The shards hold the data of the source as it is stored, and so keep what describes it
+        let mut user_defined = src_info.user_defined.clone();
+        for key in [
+            "compression",
+            "compression-size",
+            "actual-size",
+            "checksums",
+            "part-checksums",
+        ] {
+            let key = format!("{RESERVED_METADATA_PREFIX_LOWER}{key}");
+            match fi.metadata.get(&key) {
+                Some(v) => user_defined.insert(key, v.clone()),
+                None => user_defined.remove(&key),
+            };
+        }
+        if let Some(etag) = fi.metadata.get("etag") {
+            user_defined.insert("etag".to_owned(), etag.clone());
+        }
+        if user_defined
+            .get(AMZ_STORAGE_CLASS)
+            .is_some_and(|sc| sc == storageclass::STANDARD)
+        {
+            user_defined.remove(AMZ_STORAGE_CLASS);
+        }
+
+        let version_id = match &dst_opts.version_id {
+            Some(vid) => Some(Uuid::parse_str(vid).map_err(Error::other)?),
+            None => dst_opts.versioned.then(Uuid::new_v4),
+        };
+        let data_dir = Uuid::new_v4();
+        let tmp_dir = Uuid::new_v4().to_string();
+        let mod_time = dst_opts.mod_time.unwrap_or(OffsetDateTime::now_utc());
+        let versioned = dst_opts.versioned || dst_opts.version_suspended;
+
+        let (user_defined, tmp_dir_ref) = (&user_defined, &tmp_dir);
+        let futures = online_disks.iter().zip(metas.iter()).map(|(disk, meta)| async move {
+            let Some(disk) = disk else {
+                return Err(DiskError::DiskNotFound);
+            };
+            if !meta.is_valid() {
+                return Err(DiskError::FileCorrupt);
+            }
+
+            let mut meta = meta.clone();
+            if !meta.inline_data() {
+                for part in meta.parts.iter() {
+                    let size = bitrot_shard_file_size(
+                        meta.erasure.shard_file_size(part.size as i64) as usize,
+                        meta.erasure.shard_size(),
+                        meta.erasure.get_checksum_info(part.number).algorithm,
+                    );
+                    let src_path = format!("{}/{}/part.{}", src_object, meta.data_dir.unwrap_or_default(), part.number);
+                    let dst_path = format!("{}/{}/part.{}", tmp_dir_ref, data_dir, part.number);
+
+                    let mut reader = disk.read_file_stream(src_bucket, &src_path, 0, size).await?;
+                    let mut writer = disk.create_file("", RUSTFS_META_TMP_BUCKET, &dst_path, size as i64).await?;
+                    tokio::io::copy(&mut reader, &mut writer).await?;
+                    writer.shutdown().await?;
+                }
+            }
+
+            meta.volume = dst_bucket.to_owned();
+            meta.name = dst_object.to_owned();
+            meta.metadata = user_defined.clone();
+            meta.version_id = version_id;
+            meta.data_dir = Some(data_dir);
+            meta.mod_time = Some(mod_time);
+            meta.versioned = versioned;
+            Ok(meta)
+        });
+
+        let mut dst_disks = vec![None; disks.len()];
+        let mut dst_metas = vec![FileInfo::default(); disks.len()];
+        let mut errs = Vec::with_capacity(disks.len());
+        for (i, result) in join_all(futures).await.into_iter().enumerate() {
+            match result {
+                Ok(meta) => {
+                    dst_disks[i].clone_from(&online_disks[i]);
+                    dst_metas[i] = meta;
+                    errs.push(None);
+                }
+                Err(e) => errs.push(Some(e)),
+            }
+        }
+
+        if let Some(err) = reduce_write_quorum_errs(&errs, OBJECT_OP_IGNORED_ERRS, write_quorum) {
+            error!("copy_object_shards not enough disks to write: {:?}", &errs);
+            let _ = self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await;
+            return Err(to_object_err(err.into(), vec![dst_bucket, dst_object]));
+        }
+
+        // Someone else may hold the lock once its lease is lost, leave the object to them
+        if let Some(lock) = &lock {
+            if let Err(err) = lock.check() {
+                let _ = self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await;
+                return Err(err.into());
+            }
+        }
+
+        let (online_disks, _, op_old_dir) = Self::rename_data(
+            &dst_disks,
+            RUSTFS_META_TMP_BUCKET,
+            tmp_dir.as_str(),
+            &dst_metas,
+            dst_bucket,
+            dst_object,
+            write_quorum,
+        )
+        .await?;
+
+        if let Some(old_dir) = op_old_dir {
+            self.commit_rename_data_dir(&online_disks, dst_bucket, dst_object, &old_dir.to_string(), write_quorum)
+                .await?;
+        }
+
+        self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await?;
+
+        if let Some(lock) = lock {
+            lock.release().await;
+        }
+
+        let mut fi = online_disks
+            .iter()
+            .zip(dst_metas)
+            .find_map(|(disk, meta)| disk.as_ref().map(|_| meta))
+            .unwrap_or_default();
+        fi.is_latest = true;
+
+        Ok(Some(ObjectInfo::from_file_info(&fi, dst_bucket, dst_object, versioned)))
+    }
+
+    /// Bitrot algorithm chosen when the multipart upload was created, uploads started before
+    /// the algorithm was recorded use the default
+    fn multipart_bitrot_algo(fi: &FileInfo) -> HashAlgorithm {
+        fi.metadata
+            .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}bitrot-algo"))
+            .and_then(|v| storageclass::parse_bitrot_algorithm(v).ok())
+            .unwrap_or(storageclass::DEFAULT_BITROT_ALGORITHM)
+    }
+
+    /// All multipart uploads in progress on the set, for lifecycle to abort the stale ones
+    ///
+    /// Uploads are found by the object recorded when they were created, older uploads that lack
+    /// it are left out.
+    pub async fn list_all_multipart_uploads(&self) -> Result<Vec<MultipartInfo>> {
+        let Some(disk) = self.get_online_disks().await.into_iter().flatten().next() else {
+            return Ok(Vec::new());
+        };
+
+        let sha_dirs = match disk.list_dir("", RUSTFS_META_MULTIPART_BUCKET, "", -1).await {
+            Ok(dirs) => dirs,
+            Err(DiskError::FileNotFound) | Err(DiskError::VolumeNotFound) => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut uploads = Vec::new();
+        for sha_dir in sha_dirs.iter() {
+            let sha_dir = sha_dir.trim_end_matches(SLASH_SEPARATOR);
+            let upload_uuids = match disk.list_dir("", RUSTFS_META_MULTIPART_BUCKET, sha_dir, -1).await {
+                Ok(uuids) => uuids,
+                Err(DiskError::FileNotFound) | Err(DiskError::VolumeNotFound) => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            for upload_uuid in upload_uuids.iter() {
+                let upload_uuid = upload_uuid.trim_end_matches(SLASH_SEPARATOR);
+                let fi = match disk
+                    .read_version(
+                        "",
+                        RUSTFS_META_MULTIPART_BUCKET,
+                        &format!("{sha_dir}/{upload_uuid}"),
+                        "",
+                        &ReadOptions::default(),
+                    )
+                    .await
+                {
+                    Ok(fi) => fi,
+                    Err(DiskError::FileNotFound) | Err(DiskError::FileVersionNotFound) => continue,
+                    Err(err) => return Err(err.into()),
+                };
+                let Some((bucket, object)) = fi
+                    .metadata
+                    .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}multipart-object"))
+                    .and_then(|path| path.split_once('/'))
+                else {
+                    continue;
+                };
+
+                uploads.push(MultipartInfo {
+                    bucket: bucket.to_owned(),
+                    object: object.to_owned(),
+                    upload_id: base64_encode(
+                        format!("{}.{}", get_global_deployment_id().unwrap_or_default(), upload_uuid).as_bytes(),
+                    ),
+                    initiated: fi.mod_time,
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(uploads)
+    }
+
     // shuffle_disks TODO: use origin value
     fn shuffle_disks(disks: &[Option<DiskStore>], distribution: &[usize]) -> Vec<Option<DiskStore>> {
         if distribution.is_empty() {
@@ -2098,6 +2614,8 @@ impl SetDisks {
 
             let read_offset = (part_offset / erasure.block_size) * erasure.shard_size();
 
+            let checksum_algo = fi.erasure.get_checksum_info(part_number).algorithm;
+
             let mut readers = Vec::with_capacity(disks.len());
             let mut errors = Vec::with_capacity(disks.len());
             for (idx, disk_op) in disks.iter().enumerate() {
@@ -2109,7 +2627,7 @@ impl SetDisks {
                     read_offset,
                     till_offset,
                     erasure.shard_size(),
-                    HashAlgorithm::HighwayHash256,
+                    checksum_algo.clone(),
                 )
                 .await
                 {
@@ -2648,7 +3166,7 @@ impl SetDisks {
                                         &format!("{}/{}/part.{}", tmp_id, dst_data_dir, part.number),
                                         erasure.shard_file_size(part.size as i64),
                                         erasure.shard_size(),
-                                        HashAlgorithm::HighwayHash256,
+                                        checksum_algo.clone(),
                                     )
                                     .await?;
                                     writers.push(Some(writer));
@@ -2729,6 +3247,11 @@ impl SetDisks {
                                         part.actual_size,
                                         part.index.clone(),
                                     );
+                                    parts_metadata[index].erasure.add_checksum_info(ChecksumInfo {
+                                        part_number: part.number,
+                                        algorithm: checksum_algo.clone(),
+                                        hash: Bytes::new(),
+                                    });
                                     if is_inline_buffer {
                                         if let Some(writer) = writers[index].take() {
                                             // if let Some(w) = writer.as_any().downcast_ref::<BitrotFileWriter>() {
@@ -3165,6 +3688,7 @@ impl SetDisks {
         Ok(())
     }
 
+    /// Marks a restore of the object as no longer in progress, keeping the version as it is.
     pub async fn update_restore_metadata(
         &self,
         bucket: &str,
@@ -3172,36 +3696,422 @@ impl SetDisks {
         obj_info: &ObjectInfo,
         opts: &ObjectOptions,
     ) -> Result<()> {
-        let mut oi = obj_info.clone();
-        oi.metadata_only = true;
+        let mut eval_metadata = HashMap::new();
+        eval_metadata.insert(X_AMZ_RESTORE.as_str().to_string(), RestoreObjStatus::default().to_string());
 
-        oi.user_defined.remove(X_AMZ_RESTORE.as_str());
-
-        let version_id = oi.version_id.map(|v| v.to_string());
-        let obj = self
-            .copy_object(
-                bucket,
-                object,
-                bucket,
-                object,
-                &mut oi,
-                &ObjectOptions {
-                    version_id: version_id.clone(),
-                    ..Default::default()
-                },
-                &ObjectOptions {
-                    version_id,
-                    ..Default::default()
-                },
-            )
-            .await;
-        obj?;
+        self.put_object_metadata(
+            bucket,
+            object,
+            &ObjectOptions {
+                version_id: obj_info.version_id.filter(|vid| !vid.is_nil()).map(|vid| vid.to_string()),
+                versioned: opts.versioned,
+                version_suspended: opts.version_suspended,
+                eval_metadata: Some(eval_metadata),
+                ..Default::default()
+            },
+        )
+        .await?;
         Ok(())
     }
-}
 
-#[async_trait::async_trait]
-impl ObjectIO for SetDisks {
+    /// Writes the data of a transitioned object back to this set, part by part for multipart objects
+    /// so the etag of the version does not change.
+    async fn restore_from_tier(self: Arc<Self>, bucket: &str, object: &str, oi: &ObjectInfo, opts: &ObjectOptions) -> Result<()> {
+        let ropts = put_restore_opts(&opts.transition.restore_request, opts.transition.restore_expiry, oi);
+        let mut data = get_transitioned_object_data(oi, 0, -1).await?;
+
+        if oi.parts.len() <= 1 {
+            let hrd = HashReader::new(Box::new(WarpReader::new(data)), oi.size, oi.get_actual_size()?, None, false)?;
+            self.put_object(bucket, object, &mut PutObjReader::new(hrd), &ropts).await?;
+            return Ok(());
+        }
+
+        let res = self.new_multipart_upload(bucket, object, &ropts).await?;
+        let mut uploaded_parts = Vec::with_capacity(oi.parts.len());
+        for part in oi.parts.iter() {
+            let mut chunk = vec![0u8; part.size];
+            let uploaded = match data.read_exact(&mut chunk).await {
+                Ok(_) => {
+                    let mut p_reader = PutObjReader::from_vec_with_actual_size(chunk, part.actual_size);
+                    self.put_object_part(
+                        bucket,
+                        object,
+                        &res.upload_id,
+                        part.number,
+                        &mut p_reader,
+                        &ObjectOptions {
+                            preserve_etag: Some(part.etag.clone()),
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                }
+                Err(err) => Err(err.into()),
+            };
+
+            match uploaded {
+                Ok(pi) => uploaded_parts.push(CompletePart {
+                    part_num: pi.part_num,
+                    etag: pi.etag,
+                    ..Default::default()
+                }),
+                Err(err) => {
+                    let _ = self.abort_multipart_upload(bucket, object, &res.upload_id, &ropts).await;
+                    return Err(err);
+                }
+            }
+        }
+
+        if let Err(err) = self
+            .clone()
+            .complete_multipart_upload(
+                bucket,
+                object,
+                &res.upload_id,
+                uploaded_parts,
+                &ObjectOptions {
+                    mod_time: oi.mod_time,
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            let _ = self.abort_multipart_upload(bucket, object, &res.upload_id, &ropts).await;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Complete a multipart upload while holding the locks of the object and the upload
+    async fn complete_multipart_upload_locked(
+        self: Arc<Self>,
+        bucket: &str,
+        object: &str,
+        upload_id: &str,
+        uploaded_parts: Vec<CompletePart>,
+        opts: &ObjectOptions,
+        lock: Option<&LockGuard>,
+    ) -> Result<ObjectInfo> {
+        self.check_write_preconditions(bucket, object, opts).await?;
+        let (mut fi, files_metas) = self.check_upload_id_exists(bucket, object, upload_id, true).await?;
+        let upload_id_path = Self::get_upload_id_dir(bucket, object, upload_id);
+        if let Some(local) = self.newer_local_version(bucket, object, opts).await? {
+            debug!("complete_multipart_upload {}/{} keeps the newer version of this site", bucket, object);
+            let _ = self.delete_all(RUSTFS_META_MULTIPART_BUCKET, &upload_id_path).await;
+            return Ok(local);
+        }
+
+        let write_quorum = fi.write_quorum(self.default_write_quorum());
+
+        let disks = self.disks.read().await;
+
+        let disks = disks.clone();
+        // let disks = Self::shuffle_disks(&disks, &fi.erasure.distribution);
+
+        let part_path = format!("{}/{}/", upload_id_path, fi.data_dir.unwrap_or(Uuid::nil()));
+
+        let part_meta_paths = uploaded_parts
+            .iter()
+            .map(|v| format!("{part_path}part.{0}.meta", v.part_num))
+            .collect::<Vec<String>>();
+
+        let part_numbers = uploaded_parts.iter().map(|v| v.part_num).collect::<Vec<usize>>();
+
+        let object_parts =
+            Self::read_parts(&disks, RUSTFS_META_MULTIPART_BUCKET, &part_meta_paths, &part_numbers, write_quorum).await?;
+
+        if object_parts.len() != uploaded_parts.len() {
+            return Err(Error::other("part result number err"));
+        }
+
+        for (i, part) in object_parts.iter().enumerate() {
+            if let Some(err) = &part.error {
+                error!("complete_multipart_upload part error: {:?}", &err);
+            }
+
+            if uploaded_parts[i].part_num != part.number {
+                error!(
+                    "complete_multipart_upload part_id err part_id != part_num {} != {}",
+                    uploaded_parts[i].part_num, part.number
+                );
+                return Err(Error::InvalidPart(uploaded_parts[i].part_num, bucket.to_owned(), object.to_owned()));
+            }
+
+            fi.add_object_part(
+                part.number,
+                part.etag.clone(),
+                part.size,
+                part.mod_time,
+                part.actual_size,
+                part.index.clone(),
+            );
+        }
+
+        let (shuffle_disks, mut parts_metadatas) = Self::shuffle_disks_and_parts_metadata_by_index(&disks, &files_metas, &fi);
+
+        let curr_fi = fi.clone();
+
+        fi.parts = Vec::with_capacity(uploaded_parts.len());
+
+        let mut object_size: usize = 0;
+        let mut object_actual_size: i64 = 0;
+
+        for (i, p) in uploaded_parts.iter().enumerate() {
+            let Some(ext_part) = curr_fi.parts.iter().find(|v| v.number == p.part_num) else {
+                error!(
+                    "complete_multipart_upload part not found, part_id={}, bucket={}, object={}",
+                    p.part_num, bucket, object
+                );
+                return Err(Error::InvalidPart(p.part_num, "".to_owned(), p.etag.clone().unwrap_or_default()));
+            };
+
+            if p.etag != Some(ext_part.etag.clone()) {
+                error!(
+                    "complete_multipart_upload etag err {:?}, part_id={}, bucket={}, object={}",
+                    p.etag, p.part_num, bucket, object
+                );
+                return Err(Error::InvalidPart(p.part_num, ext_part.etag.clone(), p.etag.clone().unwrap_or_default()));
+            }
+
+            // The checksums the client lists for a part are the ones it was uploaded with
+            let checksums = object_parts[i].checksums.clone();
+            if let Some((algorithm, checksum)) = p
+                .checksums
+                .iter()
+                .find(|(algorithm, checksum)| checksums.as_ref().and_then(|c| c.get(*algorithm)) != Some(*checksum))
+            {
+                error!(
+                    "complete_multipart_upload checksum err {} {}, part_id={}, bucket={}, object={}",
+                    algorithm, checksum, p.part_num, bucket, object
+                );
+                return Err(Error::InvalidPart(p.part_num, ext_part.etag.clone(), p.etag.clone().unwrap_or_default()));
+            }
+
+            if (i < uploaded_parts.len() - 1) && !is_min_allowed_part_size(ext_part.actual_size) {
+                error!(
+                    "complete_multipart_upload is_min_allowed_part_size err {:?}, part_id={}, bucket={}, object={}",
+                    ext_part.actual_size, p.part_num, bucket, object
+                );
+                return Err(Error::InvalidPart(p.part_num, ext_part.etag.clone(), p.etag.clone().unwrap_or_default()));
+            }
+
+            object_size += ext_part.size;
+            object_actual_size += ext_part.actual_size;
+
+            fi.parts.push(ObjectPartInfo {
+                etag: ext_part.etag.clone(),
+                number: p.part_num,
+                size: ext_part.size,
+                mod_time: ext_part.mod_time,
+                actual_size: ext_part.actual_size,
+                index: ext_part.index.clone(),
+                checksums,
+                nonce: object_parts[i].nonce.clone(),
+                ..Default::default()
+            });
+        }
+
+        let checksums = get_complete_multipart_checksums(&fi.parts);
+        // The checksums sent with the request are the composite ones, with or without the part count
+        if let Some((algorithm, checksum)) = opts.checksums.iter().flatten().find(|(algorithm, checksum)| {
+            checksums.get(*algorithm).and_then(|v| v.split('-').next()) != checksum.split('-').next()
+        }) {
+            error!(
+                "complete_multipart_upload checksum err {} {}, bucket={}, object={}",
+                algorithm, checksum, bucket, object
+            );
+            return Err(Error::BadDigest(bucket.to_owned(), object.to_owned()));
+        }
+
+        fi.size = object_size as i64;
+        fi.mod_time = opts.mod_time;
+        if fi.mod_time.is_none() {
+            fi.mod_time = Some(OffsetDateTime::now_utc());
+        }
+
+        if !checksums.is_empty() {
+            let part_checksums: HashMap<_, _> = fi
+                .parts
+                .iter()
+                .filter_map(|p| p.checksums.as_ref().map(|c| (p.number, c)))
+                .collect();
+            fi.metadata.insert(
+                format!("{RESERVED_METADATA_PREFIX_LOWER}part-checksums"),
+                serde_json::to_string(&part_checksums)?,
+            );
+            fi.metadata
+                .insert(format!("{RESERVED_METADATA_PREFIX_LOWER}checksums"), serde_json::to_string(&checksums)?);
+        }
+
+        // xl.meta does not keep the nonces of the parts, so they are kept in the metadata like their checksums
+        let part_nonces: HashMap<_, _> = fi
+            .parts
+            .iter()
+            .filter_map(|p| p.nonce.as_ref().map(|n| (p.number, base64_encode(n))))
+            .collect();
+        if !part_nonces.is_empty() {
+            fi.metadata.insert(
+                format!("{RESERVED_METADATA_PREFIX_LOWER}{SSE_PART_NONCES_META}"),
+                serde_json::to_string(&part_nonces)?,
+            );
+        }
+
+        // etag
+        let etag = {
+            if let Some(etag) = opts.user_defined.get("etag") {
+                etag.clone()
+            } else {
+                get_complete_multipart_md5(&uploaded_parts)
+            }
+        };
+
+        fi.metadata.insert("etag".to_owned(), etag);
+
+        fi.metadata
+            .insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size"), object_actual_size.to_string());
+
+        if fi.is_compressed() {
+            fi.metadata
+                .insert(format!("{RESERVED_METADATA_PREFIX_LOWER}compression-size"), object_size.to_string());
+        }
+
+        let bitrot_algo = Self::multipart_bitrot_algo(&fi);
+        fi.metadata.remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}bitrot-algo"));
+        fi.metadata
+            .remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}multipart-object"));
+        fi.metadata
+            .remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}checksum-algorithm"));
+
+        if opts.data_movement {
+            fi.set_data_moved();
+        }
+
+        // TODO: object_actual_size
+        let _ = object_actual_size;
+
+        for meta in parts_metadatas.iter_mut() {
+            if meta.is_valid() {
+                meta.size = fi.size;
+                meta.mod_time = fi.mod_time;
+                meta.parts.clone_from(&fi.parts);
+                meta.metadata = fi.metadata.clone();
+                meta.versioned = opts.versioned || opts.version_suspended;
+                meta.erasure.checksums = fi
+                    .parts
+                    .iter()
+                    .map(|p| ChecksumInfo {
+                        part_number: p.number,
+                        algorithm: bitrot_algo.clone(),
+                        hash: Bytes::new(),
+                    })
+                    .collect();
+            }
+        }
+
+        let mut parts = Vec::with_capacity(curr_fi.parts.len());
+        // TODO: 优化 cleanupMultipartPath
+        for p in curr_fi.parts.iter() {
+            parts.push(path_join_buf(&[
+                &upload_id_path,
+                curr_fi.data_dir.unwrap_or(Uuid::nil()).to_string().as_str(),
+                format!("part.{}.meta", p.number).as_str(),
+            ]));
+
+            if !fi.parts.iter().any(|v| v.number == p.number) {
+                parts.push(path_join_buf(&[
+                    &upload_id_path,
+                    curr_fi.data_dir.unwrap_or(Uuid::nil()).to_string().as_str(),
+                    format!("part.{}", p.number).as_str(),
+                ]));
+            }
+
+            // let _ = self
+            //     .remove_part_meta(
+            //         bucket,
+            //         object,
+            //         upload_id,
+            //         curr_fi.data_dir.unwrap_or(Uuid::nil()).to_string().as_str(),
+            //         p.number,
+            //     )
+            //     .await;
+
+            // if !fi.parts.iter().any(|v| v.number == p.number) {
+            //     let _ = self
+            //         .remove_object_part(
+            //             bucket,
+            //             object,
+            //             upload_id,
+            //             curr_fi.data_dir.unwrap_or(Uuid::nil()).to_string().as_str(),
+            //             p.number,
+            //         )
+            //         .await;
+            // }
+        }
+
+        // Someone else may hold the locks once their lease is lost, leave the upload to them
+        if let Some(lock) = lock {
+            lock.check()?;
+        }
+
+        {
+            let disks = self.get_disks_internal().await;
+            Self::cleanup_multipart_path(&disks, &parts).await;
+        }
+
+        let (online_disks, versions, op_old_dir) = Self::rename_data(
+            &shuffle_disks,
+            RUSTFS_META_MULTIPART_BUCKET,
+            &upload_id_path,
+            &parts_metadatas,
+            bucket,
+            object,
+            write_quorum,
+        )
+        .await?;
+
+        // debug!("complete fileinfo {:?}", &fi);
+
+        // TODO: reduce_common_data_dir
+        if let Some(old_dir) = op_old_dir {
+            self.commit_rename_data_dir(&shuffle_disks, bucket, object, &old_dir.to_string(), write_quorum)
+                .await?;
+        }
+        if let Some(versions) = versions {
+            let _ =
+                rustfs_common::heal_channel::send_heal_request(rustfs_common::heal_channel::create_heal_request_with_options(
+                    bucket.to_string(),
+                    Some(object.to_string()),
+                    false,
+                    Some(rustfs_common::heal_channel::HealChannelPriority::Normal),
+                    Some(self.pool_index),
+                    Some(self.set_index),
+                ))
+                .await;
+        }
+
+        let upload_id_path = upload_id_path.clone();
+        let store = self.clone();
+        let _cleanup_handle = tokio::spawn(async move {
+            let _ = store.delete_all(RUSTFS_META_MULTIPART_BUCKET, &upload_id_path).await;
+        });
+
+        for (i, op_disk) in online_disks.iter().enumerate() {
+            if let Some(disk) = op_disk {
+                if disk.is_online().await {
+                    fi = parts_metadatas[i].clone();
+                    break;
+                }
+            }
+        }
+
+        fi.is_latest = true;
+
+        Ok(ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended))
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectIO for SetDisks {
     #[tracing::instrument(level = "debug", skip(self))]
     async fn get_object_reader(
         &self,
@@ -3245,11 +4155,15 @@ impl ObjectIO for SetDisks {
             return Ok(reader);
         }
 
-        // TODO: remote
+        if fi.is_remote() && !is_restored_object_on_disk(&object_info.user_defined) {
+            return get_transitioned_object_reader(bucket, object, range, &h, &object_info, opts)
+                .await
+                .map_err(Error::from);
+        }
 
         let (rd, wd) = tokio::io::duplex(DEFAULT_READ_BUFFER_SIZE);
 
-        let (reader, offset, length) = GetObjectReader::new(Box::new(rd), range, &object_info, opts, &h)?;
+        let (reader, offset, length) = GetObjectReader::new(Box::new(rd), range, &object_info, opts, &h).await?;
 
         // let disks = disks.clone();
         let bucket = bucket.to_owned();
@@ -3284,16 +4198,11 @@ impl ObjectIO for SetDisks {
     async fn put_object(&self, bucket: &str, object: &str, data: &mut PutObjReader, opts: &ObjectOptions) -> Result<ObjectInfo> {
         let disks = self.disks.read().await;
 
-        if !opts.no_lock {
-            let paths = vec![object.to_string()];
-            let lock_acquired = self
-                .namespace_lock
-                .lock_batch(&paths, &self.locker_owner, Duration::from_secs(5), Duration::from_secs(10))
-                .await?;
-
-            if !lock_acquired {
-                return Err(Error::other("can not get lock. please retry".to_string()));
-            }
+        let lock = self.lock_paths(&[object.to_string()], opts).await?;
+        self.check_write_preconditions(bucket, object, opts).await?;
+        if let Some(local) = self.newer_local_version(bucket, object, opts).await? {
+            debug!("put_object {}/{} keeps the newer version of this site", bucket, object);
+            return Ok(local);
         }
 
         let mut user_defined = opts.user_defined.clone();
@@ -3306,6 +4215,8 @@ impl ObjectIO for SetDisks {
             }
         };
 
+        let bitrot_algo = Self::bitrot_algo_for_sc(&user_defined);
+
         let mut parity_drives = sc_parity_drives.unwrap_or(self.default_parity_count);
         if opts.max_parity {
             parity_drives = disks.len() / 2;
@@ -3362,7 +4273,7 @@ impl ObjectIO for SetDisks {
                     &tmp_object,
                     erasure.shard_file_size(data.size()),
                     erasure.shard_size(),
-                    HashAlgorithm::HighwayHash256,
+                    bitrot_algo.clone(),
                 )
                 .await?;
 
@@ -3436,7 +4347,11 @@ impl ObjectIO for SetDisks {
 
         //TODO: userDefined
 
-        let etag = data.stream.try_resolve_etag().unwrap_or_default();
+        let mut etag = data.stream.try_resolve_etag().unwrap_or_default();
+
+        if let Some(ref tag) = opts.preserve_etag {
+            etag = tag.clone();
+        }
 
         user_defined.insert("etag".to_owned(), etag.clone());
 
@@ -3458,7 +4373,8 @@ impl ObjectIO for SetDisks {
             }
         }
 
-        let now = OffsetDateTime::now_utc();
+        // Rewrites of existing objects keep their modification time
+        let now = opts.mod_time.unwrap_or(OffsetDateTime::now_utc());
 
         for (i, fi) in parts_metadatas.iter_mut().enumerate() {
             fi.metadata = user_defined.clone();
@@ -3474,6 +4390,11 @@ impl ObjectIO for SetDisks {
             fi.size = w_size as i64;
             fi.versioned = opts.versioned || opts.version_suspended;
             fi.add_object_part(1, etag.clone(), w_size, fi.mod_time, actual_size, index_op.clone());
+            fi.erasure.add_checksum_info(ChecksumInfo {
+                part_number: 1,
+                algorithm: bitrot_algo.clone(),
+                hash: Bytes::new(),
+            });
 
             if opts.data_movement {
                 fi.set_data_moved();
@@ -3482,6 +4403,14 @@ impl ObjectIO for SetDisks {
 
         drop(writers); // drop writers to close all files, this is to prevent FileAccessDenied errors when renaming data
 
+        // Someone else may hold the lock once its lease is lost, leave the object to them
+        if let Some(lock) = &lock {
+            if let Err(err) = lock.check() {
+                let _ = self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await;
+                return Err(err.into());
+            }
+        }
+
         let (online_disks, _, op_old_dir) = Self::rename_data(
             &shuffle_disks,
             RUSTFS_META_TMP_BUCKET,
@@ -3501,11 +4430,8 @@ impl ObjectIO for SetDisks {
         self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await?;
 
         // Release lock if it was acquired
-        if !opts.no_lock {
-            let paths = vec![object.to_string()];
-            if let Err(err) = self.namespace_lock.unlock_batch(&paths, &self.locker_owner).await {
-                error!("Failed to unlock object {}: {}", object, err);
-            }
+        if let Some(lock) = lock {
+            lock.release().await;
         }
 
         for (i, op_disk) in online_disks.iter().enumerate() {
@@ -3522,6 +4448,275 @@ impl ObjectIO for SetDisks {
         // TODO: version support
         Ok(ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended))
     }
+
+    /// Appends the data as the next part of the object, in the data directory of the object, so that the parts
+    /// it has are neither read nor written again
+    #[tracing::instrument(level = "debug", skip(self, data))]
+    async fn append_object(
+        &self,
+        bucket: &str,
+        object: &str,
+        offset: i64,
+        data: &mut PutObjReader,
+        opts: &ObjectOptions,
+    ) -> Result<ObjectInfo> {
+        let disks = self.get_disks_internal().await;
+
+        let lock = self.lock_paths(&[object.to_string()], opts).await?;
+        self.check_write_preconditions(bucket, object, opts).await?;
+
+        let (metas, errs) = Self::read_all_xl(&disks, bucket, object, false, false).await;
+        let current = match Self::object_quorum_from_meta(&metas, &errs, self.default_parity_count) {
+            Ok((read_quorum, write_quorum)) => {
+                let (read_quorum, write_quorum) = (read_quorum as usize, write_quorum as usize);
+                let (online_disks, mod_time, etag) = Self::list_online_disks(&disks, &metas, &errs, read_quorum);
+                let fi = Self::pick_valid_fileinfo(&metas, mod_time, etag, read_quorum)
+                    .map_err(|e| to_object_err(e.into(), vec![bucket, object]))?;
+                Some((fi, online_disks, write_quorum)).filter(|(fi, _, _)| !fi.deleted)
+            }
+            Err(err) => {
+                let err = to_object_err(err.into(), vec![bucket, object]);
+                if !is_err_object_not_found(&err) {
+                    return Err(err);
+                }
+                None
+            }
+        };
+
+        let Some((fi, online_disks, write_quorum)) = current else {
+            if let Some(lock) = lock {
+                lock.release().await;
+            }
+            if offset != 0 {
+                return Err(Error::InvalidWriteOffset(bucket.to_owned(), object.to_owned(), 0));
+            }
+            let put_opts = ObjectOptions {
+                write_preconditions: Some(WritePreconditions {
+                    if_match: None,
+                    if_none_match: Some("*".to_owned()),
+                    mod_time: None,
+                }),
+                ..opts.clone()
+            };
+            return self.put_object(bucket, object, data, &put_opts).await;
+        };
+
+        // Appending changes the version in place, which retention and legal holds forbid
+        let info = ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended);
+        if check_retention_for_deletion(&info, false, utc_now_ntp()).is_err() {
+            return Err(Error::PrefixAccessDenied(bucket.to_owned(), object.to_owned()));
+        }
+        if fi.size != offset {
+            return Err(Error::InvalidWriteOffset(bucket.to_owned(), object.to_owned(), fi.size));
+        }
+        if fi.is_remote() || fi.is_compressed() || is_encrypted(&fi.metadata) {
+            return Err(Error::InvalidArgument(
+                bucket.to_owned(),
+                object.to_owned(),
+                "can not append to a compressed, encrypted or transitioned object".to_owned(),
+            ));
+        }
+
+        let Some(last_part) = fi.parts.last().filter(|_| !fi.inline_data()) else {
+            if let Some(lock) = lock {
+                lock.release().await;
+            }
+            return self.append_inline_object(bucket, object, &fi, data, opts).await;
+        };
+        let part_number = last_part.number + 1;
+        if part_number > MAX_PARTS_COUNT {
+            return Err(Error::InvalidArgument(
+                bucket.to_owned(),
+                object.to_owned(),
+                format!("an object can not be appended to more than {MAX_PARTS_COUNT} times"),
+            ));
+        }
+        let bitrot_algo = fi.erasure.get_checksum_info(last_part.number).algorithm;
+
+        let shuffle_disks = Self::shuffle_disks(&online_disks, &fi.erasure.distribution);
+        let tmp_dir = Uuid::new_v4().to_string();
+        let tmp_part = format!("{tmp_dir}/part.{part_number}");
+
+        let erasure = erasure_coding::Erasure::new(fi.erasure.data_blocks, fi.erasure.parity_blocks, fi.erasure.block_size);
+
+        let mut writers = Vec::with_capacity(shuffle_disks.len());
+        let mut errors = Vec::with_capacity(shuffle_disks.len());
+        for disk_op in shuffle_disks.iter() {
+            if let Some(disk) = disk_op {
+                let writer = create_bitrot_writer(
+                    false,
+                    Some(disk),
+                    RUSTFS_META_TMP_BUCKET,
+                    &tmp_part,
+                    erasure.shard_file_size(data.size()),
+                    erasure.shard_size(),
+                    bitrot_algo.clone(),
+                )
+                .await?;
+                writers.push(Some(writer));
+                errors.push(None);
+            } else {
+                errors.push(Some(DiskError::DiskNotFound));
+                writers.push(None);
+            }
+        }
+
+        let nil_count = errors.iter().filter(|&e| e.is_none()).count();
+        if nil_count < write_quorum {
+            if let Some(write_err) = reduce_write_quorum_errs(&errors, OBJECT_OP_IGNORED_ERRS, write_quorum) {
+                return Err(to_object_err(write_err.into(), vec![bucket, object]));
+            }
+
+            return Err(Error::other(format!("not enough disks to write: {errors:?}")));
+        }
+
+        let stream = mem::replace(
+            &mut data.stream,
+            HashReader::new(Box::new(WarpReader::new(Cursor::new(Vec::new()))), 0, 0, None, false)?,
+        );
+
+        let (reader, w_size) = match Arc::new(erasure).encode(stream, &mut writers, write_quorum).await {
+            Ok(res) => res,
+            Err(e) => {
+                let _ = self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await;
+                return Err(e.into());
+            }
+        };
+        let _ = mem::replace(&mut data.stream, reader);
+
+        if (w_size as i64) < data.size() {
+            let _ = self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await;
+            return Err(Error::other("append_object write size < data.size()"));
+        }
+
+        let index = data.stream.try_get_index().map(|v| v.clone().into_vec());
+        let part_etag = data.stream.try_resolve_etag().unwrap_or_default();
+        let actual_size = if data.actual_size() < 0 {
+            w_size as i64
+        } else {
+            data.actual_size()
+        };
+
+        // Only the drives all of whose shard was written get the part
+        let written: Vec<bool> = (0..online_disks.len())
+            .map(|i| {
+                fi.erasure
+                    .distribution
+                    .get(i)
+                    .is_some_and(|&shard| writers[shard - 1].is_some())
+            })
+            .collect();
+        drop(writers);
+
+        let mut parts: Vec<CompletePart> = fi
+            .parts
+            .iter()
+            .map(|p| CompletePart {
+                part_num: p.number,
+                etag: Some(p.etag.clone()),
+                ..Default::default()
+            })
+            .collect();
+        parts.push(CompletePart {
+            part_num: part_number,
+            etag: Some(part_etag.clone()),
+            ..Default::default()
+        });
+
+        // The parts appended make up the object like the parts of a multipart upload, and so does its etag
+        let mut metadata = fi.metadata.clone();
+        metadata.insert("etag".to_owned(), get_complete_multipart_md5(&parts));
+        for key in ["checksums", "part-checksums"] {
+            metadata.remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}{key}"));
+        }
+        let actual_size_key = format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size");
+        if let Some(size) = metadata.get(&actual_size_key).and_then(|v| v.parse::<i64>().ok()) {
+            metadata.insert(actual_size_key, (size + actual_size).to_string());
+        }
+        if let Some(append_metadata) = opts.append_metadata {
+            append_metadata(&mut metadata);
+        }
+        let mod_time = Some(opts.mod_time.unwrap_or(OffsetDateTime::now_utc()));
+
+        // Someone else may hold the lock once its lease is lost, leave the object to them
+        if let Some(lock) = &lock {
+            if let Err(err) = lock.check() {
+                let _ = self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await;
+                return Err(err.into());
+            }
+        }
+
+        let (metadata, tmp_part, part_etag, index, bitrot_algo) = (&metadata, &tmp_part, &part_etag, &index, &bitrot_algo);
+        let futures = online_disks
+            .iter()
+            .zip(metas.iter())
+            .zip(written)
+            .map(|((disk, meta), written)| async move {
+                let Some(disk) = disk.as_ref().filter(|_| written) else {
+                    return Err(DiskError::DiskNotFound);
+                };
+
+                let part_path = format!("{}/{}/part.{}", object, meta.data_dir.unwrap_or_default(), part_number);
+                disk.rename_file(RUSTFS_META_TMP_BUCKET, tmp_part, bucket, &part_path).await?;
+
+                let mut meta = meta.clone();
+                meta.add_object_part(part_number, part_etag.clone(), w_size, mod_time, actual_size, index.clone());
+                meta.erasure.add_checksum_info(ChecksumInfo {
+                    part_number,
+                    algorithm: bitrot_algo.clone(),
+                    hash: Bytes::new(),
+                });
+                meta.size += w_size as i64;
+                meta.mod_time = mod_time;
+                meta.metadata = metadata.clone();
+                disk.write_metadata("", bucket, object, meta.clone()).await?;
+                Ok(meta)
+            });
+
+        let mut updated = None;
+        let mut errs = Vec::with_capacity(online_disks.len());
+        for result in join_all(futures).await {
+            match result {
+                Ok(meta) => {
+                    updated.get_or_insert(meta);
+                    errs.push(None);
+                }
+                Err(e) => errs.push(Some(e)),
+            }
+        }
+
+        let _ = self.delete_all(RUSTFS_META_TMP_BUCKET, &tmp_dir).await;
+
+        if let Some(err) = reduce_write_quorum_errs(&errs, OBJECT_OP_IGNORED_ERRS, write_quorum) {
+            error!("append_object not enough disks to write: {:?}", &errs);
+            // Put the drives that got the part back to the version they had, so that they all agree on its size
+            let rollbacks = online_disks
+                .iter()
+                .zip(metas.iter())
+                .zip(errs.iter())
+                .filter(|(_, err)| err.is_none())
+                .filter_map(|((disk, meta), _)| disk.as_ref().map(|disk| (disk, meta)))
+                .map(|(disk, meta)| async move {
+                    let part_path = format!("{}/{}/part.{}", object, meta.data_dir.unwrap_or_default(), part_number);
+                    if let Err(err) = disk.write_metadata("", bucket, object, meta.clone()).await {
+                        warn!("append_object rollback of {bucket}/{object} failed: {err:?}");
+                        return;
+                    }
+                    let _ = disk.delete(bucket, &part_path, DeleteOptions::default()).await;
+                });
+            join_all(rollbacks).await;
+            return Err(to_object_err(err.into(), vec![bucket, object]));
+        }
+
+        if let Some(lock) = lock {
+            lock.release().await;
+        }
+
+        let mut fi = updated.unwrap_or(fi);
+        fi.is_latest = true;
+
+        Ok(ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended))
+    }
 }
 
 #[async_trait::async_trait]
@@ -3571,16 +4766,36 @@ impl StorageAPI for SetDisks {
         &self,
         src_bucket: &str,
         src_object: &str,
-        _dst_bucket: &str,
-        _dst_object: &str,
+        dst_bucket: &str,
+        dst_object: &str,
         src_info: &mut ObjectInfo,
         src_opts: &ObjectOptions,
         dst_opts: &ObjectOptions,
     ) -> Result<ObjectInfo> {
-        // FIXME: TODO:
-
         if !src_info.metadata_only {
-            return Err(StorageError::NotImplemented);
+            if let Some(info) = self
+                .copy_object_shards(src_bucket, src_object, dst_bucket, dst_object, src_info, src_opts, dst_opts)
+                .await?
+            {
+                return Ok(info);
+            }
+
+            let put_opts = ObjectOptions {
+                user_defined: src_info.user_defined.clone(),
+                versioned: dst_opts.versioned,
+                version_id: dst_opts.version_id.clone(),
+                mod_time: dst_opts.mod_time,
+                ..Default::default()
+            };
+
+            let Some(put_object_reader) = src_info.put_object_reader.as_mut() else {
+                return Err(StorageError::InvalidArgument(
+                    src_bucket.to_owned(),
+                    src_object.to_owned(),
+                    "put_object_reader is none".to_owned(),
+                ));
+            };
+            return self.put_object(dst_bucket, dst_object, put_object_reader, &put_opts).await;
         }
 
         let disks = self.get_disks_internal().await;
@@ -3747,7 +4962,11 @@ impl StorageAPI for SetDisks {
             del_objects[i].object_name.clone_from(&vr.name);
             del_objects[i].version_id = vr.version_id.map(|v| v.to_string());
 
-            if del_objects[i].version_id.is_none() {
+            if opts.replication_request && opts.delete_marker && vr.version_id.is_some() {
+                // The delete marker of another site keeps its version id and modification time
+                vr.mod_time = Some(opts.mod_time.unwrap_or(OffsetDateTime::now_utc()));
+                vr.deleted = true;
+            } else if del_objects[i].version_id.is_none() {
                 let (suspended, versioned) = (opts.version_suspended, opts.versioned);
                 if suspended || versioned {
                     vr.mod_time = Some(OffsetDateTime::now_utc());
@@ -3838,6 +5057,28 @@ impl StorageAPI for SetDisks {
             return Ok(ObjectInfo::default());
         }
 
+        // A conditional delete checks the version it removes under the object lock
+        let _lock = match &opts.write_preconditions {
+            Some(preconditions) => {
+                let lock = self.lock_paths(&[object.to_string()], &opts).await?;
+                let version_opts = ObjectOptions {
+                    version_id: opts.version_id.clone(),
+                    versioned: opts.versioned,
+                    version_suspended: opts.version_suspended,
+                    no_lock: true,
+                    ..Default::default()
+                };
+                let current = match self.get_object_info(bucket, object, &version_opts).await {
+                    Ok(info) => Some(info).filter(|info| !info.delete_marker),
+                    Err(err) if is_err_object_not_found(&err) || is_err_version_not_found(&err) => None,
+                    Err(err) => return Err(err),
+                };
+                preconditions.check(bucket, object, current.as_ref())?;
+                lock
+            }
+            None => None,
+        };
+
         // Create a single object deletion request
         let mut vr = FileInfo {
             name: object.to_string(),
@@ -3847,7 +5088,12 @@ impl StorageAPI for SetDisks {
 
         // Handle versioning
         let (suspended, versioned) = (opts.version_suspended, opts.versioned);
-        if opts.version_id.is_none() && (suspended || versioned) {
+        if opts.transition.expire_restored {
+            vr.expire_restored = true;
+        } else if opts.replication_request && opts.delete_marker && vr.version_id.is_some() {
+            vr.mod_time = Some(opts.mod_time.unwrap_or(OffsetDateTime::now_utc()));
+            vr.deleted = true;
+        } else if opts.version_id.is_none() && (suspended || versioned) {
             vr.mod_time = Some(OffsetDateTime::now_utc());
             vr.deleted = true;
             if versioned {
@@ -4001,87 +5247,19 @@ impl StorageAPI for SetDisks {
             Some(HealChannelPriority::Normal),
             Some(self.pool_index),
             Some(self.set_index),
-        ))
-        .await;
-        Ok(())
-    }
-
-    #[tracing::instrument(skip(self))]
-    async fn put_object_metadata(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<ObjectInfo> {
-        // TODO: nslock
-
-        let disks = self.get_disks_internal().await;
-
-        let (metas, errs) = {
-            if opts.version_id.is_some() {
-                Self::read_all_fileinfo(
-                    &disks,
-                    "",
-                    bucket,
-                    object,
-                    opts.version_id.as_ref().unwrap().to_string().as_str(),
-                    false,
-                    false,
-                )
-                .await?
-            } else {
-                Self::read_all_xl(&disks, bucket, object, false, false).await
-            }
-        };
-
-        let read_quorum = match Self::object_quorum_from_meta(&metas, &errs, self.default_parity_count) {
-            Ok((res, _)) => res,
-            Err(mut err) => {
-                if err == DiskError::ErasureReadQuorum
-                    && !bucket.starts_with(RUSTFS_META_BUCKET)
-                    && self
-                        .delete_if_dang_ling(bucket, object, &metas, &errs, &HashMap::new(), opts.clone())
-                        .await
-                        .is_ok()
-                {
-                    if opts.version_id.is_some() {
-                        err = DiskError::FileVersionNotFound
-                    } else {
-                        err = DiskError::FileNotFound
-                    }
-                }
-                return Err(to_object_err(err.into(), vec![bucket, object]));
-            }
-        };
-
-        let read_quorum = read_quorum as usize;
-
-        let (online_disks, mod_time, etag) = Self::list_online_disks(&disks, &metas, &errs, read_quorum);
-
-        let mut fi = Self::pick_valid_fileinfo(&metas, mod_time, etag, read_quorum)
-            .map_err(|e| to_object_err(e.into(), vec![bucket, object]))?;
-
-        if fi.deleted {
-            return Err(to_object_err(Error::MethodNotAllowed, vec![bucket, object]));
-        }
-
-        let obj_info = ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended);
-
-        for (k, v) in obj_info.user_defined {
-            fi.metadata.insert(k, v);
-        }
-
-        if let Some(mt) = &opts.eval_metadata {
-            for (k, v) in mt {
-                fi.metadata.insert(k.clone(), v.clone());
-            }
-        }
-
-        fi.mod_time = opts.mod_time;
-        if let Some(ref version_id) = opts.version_id {
-            fi.version_id = Uuid::parse_str(version_id).ok();
-        }
-
-        self.update_object_meta(bucket, object, fi.clone(), &online_disks)
-            .await
-            .map_err(|e| to_object_err(e.into(), vec![bucket, object]))?;
+        ))
+        .await;
+        Ok(())
+    }
 
-        Ok(ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended))
+    #[tracing::instrument(skip(self))]
+    async fn put_object_metadata(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<ObjectInfo> {
+        let lock = self.lock_paths(&[object.to_string()], opts).await?;
+        let result = self.put_object_metadata_locked(bucket, object, opts).await;
+        if let Some(lock) = lock {
+            lock.release().await;
+        }
+        result
     }
 
     #[tracing::instrument(skip(self))]
@@ -4092,6 +5270,7 @@ impl StorageAPI for SetDisks {
 
     #[tracing::instrument(level = "debug", skip(self))]
     async fn transition_object(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()> {
+        let _lock = self.lock_paths(&[object.to_string()], opts).await?;
         let mut tier_config_mgr = GLOBAL_TierConfigMgr.write().await;
         let tgt_client = match tier_config_mgr.get_driver(&opts.transition.tier).await {
             Ok(client) => client,
@@ -4100,13 +5279,6 @@ impl StorageAPI for SetDisks {
             }
         };
 
-        /*if !opts.no_lock {
-            let lk = self.new_ns_lock(bucket, object);
-            let lkctx = lk.get_lock(globalDeleteOperationTimeout)?;
-            //ctx = lkctx.Context()
-            //defer lk.Unlock(lkctx)
-        }*/
-
         let (mut fi, meta_arr, online_disks) = self.get_object_fileinfo(bucket, object, opts, true).await?;
         /*if err != nil {
             return Err(to_object_err(err, vec![bucket, object]));
@@ -4222,85 +5394,43 @@ impl StorageAPI for SetDisks {
     }
 
     #[tracing::instrument(level = "debug", skip(self))]
-    async fn restore_transitioned_object(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()> {
-        let set_restore_header_fn = async move |oi: &mut ObjectInfo, rerr: Option<Error>| -> Result<()> {
-            if rerr.is_none() {
-                return Ok(());
-            }
-            self.update_restore_metadata(bucket, object, oi, opts).await?;
-            Err(rerr.unwrap())
-        };
-        let mut oi = ObjectInfo::default();
-        let fi = self.get_object_fileinfo(bucket, object, opts, true).await;
-        if let Err(err) = fi {
-            return set_restore_header_fn(&mut oi, Some(to_object_err(err, vec![bucket, object]))).await;
-        }
-        let (actual_fi, _, _) = fi.unwrap();
-
-        oi = ObjectInfo::from_file_info(&actual_fi, bucket, object, opts.versioned || opts.version_suspended);
-        let ropts = put_restore_opts(bucket, object, &opts.transition.restore_request, &oi);
-        /*if oi.parts.len() == 1 {
-            let mut rs: HTTPRangeSpec;
-            let gr = get_transitioned_object_reader(bucket, object, rs, HeaderMap::new(), oi, opts);
-            //if err != nil {
-            //    return set_restore_header_fn(&mut oi, Some(toObjectErr(err, bucket, object)));
-            //}
-            let hash_reader = HashReader::new(gr, gr.obj_info.size, "", "", gr.obj_info.size);
-            let p_reader = PutObjReader::new(StreamingBlob::from(Box::pin(hash_reader)), hash_reader.size());
-            if let Err(err) = self.put_object(bucket, object, &mut p_reader, &ropts).await {
-                return set_restore_header_fn(&mut oi, Some(to_object_err(err, vec![bucket, object])));
-            } else {
-                return Ok(());
+    async fn restore_transitioned_object(self: Arc<Self>, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()> {
+        let (fi, _, _) = self
+            .get_object_fileinfo(bucket, object, opts, true)
+            .await
+            .map_err(|err| to_object_err(err, vec![bucket, object]))?;
+        let oi = ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended);
+
+        if let Err(err) = self.clone().restore_from_tier(bucket, object, &oi, opts).await {
+            // Clear the ongoing status so the restore can be asked for again
+            if let Err(uerr) = self.update_restore_metadata(bucket, object, &oi, opts).await {
+                warn!(
+                    "restore_transitioned_object: reset restore status of {}/{} failed {:?}",
+                    bucket, object, uerr
+                );
             }
+            return Err(to_object_err(err, vec![bucket, object]));
         }
 
-        let res = self.new_multipart_upload(bucket, object, &ropts).await?;
-        //if err != nil {
-        //    return set_restore_header_fn(&mut oi, err);
-        //}
-
-        let mut uploaded_parts: Vec<CompletePart> = vec![];
-        let mut rs: HTTPRangeSpec;
-        let gr = get_transitioned_object_reader(bucket, object, rs, HeaderMap::new(), oi, opts).await?;
-        //if err != nil {
-        //    return set_restore_header_fn(&mut oi, err);
-        //}
-
-        for part_info in oi.parts {
-            //let hr = HashReader::new(LimitReader(gr, part_info.size), part_info.size, "", "", part_info.size);
-            let hr = HashReader::new(gr, part_info.size as i64, part_info.size as i64, None, false);
-            //if err != nil {
-            //    return set_restore_header_fn(&mut oi, err);
-            //}
-            let mut p_reader = PutObjReader::new(hr, hr.size());
-            let p_info = self.put_object_part(bucket, object, &res.upload_id, part_info.number, &mut p_reader, &ObjectOptions::default()).await?;
-            //if let Err(err) = p_info {
-            //    return set_restore_header_fn(&mut oi, err);
-            //}
-            if p_info.size != part_info.size {
-                return set_restore_header_fn(&mut oi, Some(Error::from(ObjectApiError::InvalidObjectState(GenericError{bucket: bucket.to_string(), object: object.to_string(), ..Default::default()}))));
-            }
-            uploaded_parts.push(CompletePart {
-                part_num: p_info.part_num,
-                etag: p_info.etag,
-            });
-        }
-        if let Err(err) = self.complete_multipart_upload(bucket, object, &res.upload_id, uploaded_parts, &ObjectOptions {
-            mod_time: oi.mod_time,
-            ..Default::default()
-        }).await {
-            set_restore_header_fn(&mut oi, Some(err));
-        }*/
         Ok(())
     }
 
     #[tracing::instrument(level = "debug", skip(self))]
     async fn put_object_tags(&self, bucket: &str, object: &str, tags: &str, opts: &ObjectOptions) -> Result<ObjectInfo> {
+        let _lock = self.lock_paths(&[object.to_string()], opts).await?;
         let (mut fi, _, disks) = self.get_object_fileinfo(bucket, object, opts, false).await?;
+        let current = ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended);
+        if let Some(preconditions) = &opts.write_preconditions {
+            preconditions.check(bucket, object, Some(&current))?;
+        }
+        if Self::replicated_metadata_outdated(&fi, opts) {
+            return Ok(current);
+        }
 
         fi.metadata.insert(AMZ_OBJECT_TAGGING.to_owned(), tags.to_owned());
-
-        // TODO: userdeefined
+        if let Some(eval_metadata) = &opts.eval_metadata {
+            fi.metadata.extend(eval_metadata.clone());
+        }
 
         self.update_object_meta(bucket, object, fi.clone(), disks.as_slice()).await?;
 
@@ -4352,6 +5482,8 @@ impl StorageAPI for SetDisks {
         let disks = disks.clone();
         let shuffle_disks = Self::shuffle_disks(&disks, &fi.erasure.distribution);
 
+        let bitrot_algo = Self::multipart_bitrot_algo(&fi);
+
         let part_suffix = format!("part.{part_id}");
         let tmp_part = format!("{}x{}", Uuid::new_v4(), OffsetDateTime::now_utc().unix_timestamp());
         let tmp_part_path = Arc::new(format!("{tmp_part}/{part_suffix}"));
@@ -4418,7 +5550,7 @@ impl StorageAPI for SetDisks {
                     &tmp_part_path,
                     erasure.shard_file_size(data.size()),
                     erasure.shard_size(),
-                    HashAlgorithm::HighwayHash256,
+                    bitrot_algo.clone(),
                 )
                 .await?;
 
@@ -4491,6 +5623,8 @@ impl StorageAPI for SetDisks {
             mod_time: Some(OffsetDateTime::now_utc()),
             actual_size,
             index: index_op,
+            checksums: opts.checksums.clone(),
+            nonce: opts.part_nonce.map(|n| Bytes::copy_from_slice(&n)),
             ..Default::default()
         };
 
@@ -4520,6 +5654,7 @@ impl StorageAPI for SetDisks {
             last_mod: Some(OffsetDateTime::now_utc()),
             size: w_size,
             actual_size,
+            checksums: part_info.checksums,
         };
 
         // error!("put_object_part ret {:?}", &ret);
@@ -4554,6 +5689,11 @@ impl StorageAPI for SetDisks {
             max_parts,
             part_number_marker,
             user_defined: fi.metadata.clone(),
+            checksum_algorithm: fi
+                .metadata
+                .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}checksum-algorithm"))
+                .cloned()
+                .unwrap_or_default(),
             ..Default::default()
         };
 
@@ -4585,21 +5725,12 @@ impl StorageAPI for SetDisks {
             }
         };
 
+        // Listing continues with the parts numbered after the marker, whether or not the marker was uploaded
+        part_numbers.sort_unstable();
+        part_numbers.retain(|&v| v > part_number_marker);
         if part_numbers.is_empty() {
             return Ok(ret);
         }
-        let start_op = part_numbers.iter().find(|&&v| v != 0 && v == part_number_marker);
-        if part_number_marker > 0 && start_op.is_none() {
-            return Ok(ret);
-        }
-
-        if let Some(start) = start_op {
-            if start + 1 > part_numbers.len() {
-                return Ok(ret);
-            }
-
-            part_numbers = part_numbers[start + 1..].to_vec();
-        }
 
         let mut parts = Vec::with_capacity(part_numbers.len());
 
@@ -4626,6 +5757,7 @@ impl StorageAPI for SetDisks {
                 last_mod: part.mod_time,
                 size: part.size,
                 actual_size: part.actual_size,
+                checksums: part.checksums.clone(),
             });
 
             count -= 1;
@@ -4790,383 +5922,164 @@ impl StorageAPI for SetDisks {
     }
 
     #[tracing::instrument(skip(self))]
-    async fn new_multipart_upload(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<MultipartUploadResult> {
-        let disks = self.disks.read().await;
-
-        let disks = disks.clone();
-
-        let mut user_defined = opts.user_defined.clone();
-
-        if let Some(ref etag) = opts.preserve_etag {
-            user_defined.insert("etag".to_owned(), etag.clone());
-        }
-
-        if let Some(sc) = user_defined.get(AMZ_STORAGE_CLASS) {
-            if sc == storageclass::STANDARD {
-                let _ = user_defined.remove(AMZ_STORAGE_CLASS);
-            }
-        }
-
-        let sc_parity_drives = {
-            if let Some(sc) = GLOBAL_STORAGE_CLASS.get() {
-                sc.get_parity_for_sc(user_defined.get(AMZ_STORAGE_CLASS).cloned().unwrap_or_default().as_str())
-            } else {
-                None
-            }
-        };
-
-        let mut parity_drives = sc_parity_drives.unwrap_or(self.default_parity_count);
-        if opts.max_parity {
-            parity_drives = disks.len() / 2;
-        }
-
-        let data_drives = disks.len() - parity_drives;
-        let mut write_quorum = data_drives;
-        if data_drives == parity_drives {
-            write_quorum += 1
-        }
-
-        let mut fi = FileInfo::new([bucket, object].join("/").as_str(), data_drives, parity_drives);
-
-        fi.version_id = if let Some(vid) = &opts.version_id {
-            Some(Uuid::parse_str(vid)?)
-        } else {
-            None
-        };
-
-        if opts.versioned && opts.version_id.is_none() {
-            fi.version_id = Some(Uuid::new_v4());
-        }
-
-        fi.data_dir = Some(Uuid::new_v4());
-        fi.fresh = true;
-
-        let parts_metadata = vec![fi.clone(); disks.len()];
-
-        if !user_defined.contains_key("content-type") {
-            // TODO: get content-type
-        }
-
-        if let Some(sc) = user_defined.get(AMZ_STORAGE_CLASS) {
-            if sc == storageclass::STANDARD {
-                let _ = user_defined.remove(AMZ_STORAGE_CLASS);
-            }
-        }
-
-        let (shuffle_disks, mut parts_metadatas) = Self::shuffle_disks_and_parts_metadata(&disks, &parts_metadata, &fi);
-
-        let mod_time = opts.mod_time.unwrap_or(OffsetDateTime::now_utc());
-
-        for fi in parts_metadatas.iter_mut() {
-            fi.metadata = user_defined.clone();
-            fi.mod_time = Some(mod_time);
-            fi.fresh = true;
-        }
-
-        // fi.mod_time = Some(now);
-
-        let upload_uuid = format!("{}x{}", Uuid::new_v4(), mod_time.unix_timestamp_nanos());
-
-        let upload_id = base64_encode(format!("{}.{}", get_global_deployment_id().unwrap_or_default(), upload_uuid).as_bytes());
-
-        let upload_path = Self::get_upload_id_dir(bucket, object, upload_uuid.as_str());
-
-        Self::write_unique_file_info(
-            &shuffle_disks,
-            bucket,
-            RUSTFS_META_MULTIPART_BUCKET,
-            upload_path.as_str(),
-            &parts_metadatas,
-            write_quorum,
-        )
-        .await
-        .map_err(|e| to_object_err(e.into(), vec![bucket, object]))?;
-
-        // evalDisks
-
-        Ok(MultipartUploadResult { upload_id })
-    }
-
-    #[tracing::instrument(skip(self))]
-    async fn get_multipart_info(
-        &self,
-        bucket: &str,
-        object: &str,
-        upload_id: &str,
-        _opts: &ObjectOptions,
-    ) -> Result<MultipartInfo> {
-        // TODO: nslock
-        let (fi, _) = self
-            .check_upload_id_exists(bucket, object, upload_id, false)
-            .await
-            .map_err(|e| to_object_err(e, vec![bucket, object, upload_id]))?;
-
-        Ok(MultipartInfo {
-            bucket: bucket.to_owned(),
-            object: object.to_owned(),
-            upload_id: upload_id.to_owned(),
-            user_defined: fi.metadata.clone(),
-            ..Default::default()
-        })
-    }
-
-    #[tracing::instrument(skip(self))]
-    async fn abort_multipart_upload(&self, bucket: &str, object: &str, upload_id: &str, _opts: &ObjectOptions) -> Result<()> {
-        self.check_upload_id_exists(bucket, object, upload_id, false).await?;
-        let upload_id_path = Self::get_upload_id_dir(bucket, object, upload_id);
-
-        self.delete_all(RUSTFS_META_MULTIPART_BUCKET, &upload_id_path).await
-    }
-    // complete_multipart_upload 完成
-    #[tracing::instrument(skip(self))]
-    async fn complete_multipart_upload(
-        self: Arc<Self>,
-        bucket: &str,
-        object: &str,
-        upload_id: &str,
-        uploaded_parts: Vec<CompletePart>,
-        opts: &ObjectOptions,
-    ) -> Result<ObjectInfo> {
-        let (mut fi, files_metas) = self.check_upload_id_exists(bucket, object, upload_id, true).await?;
-        let upload_id_path = Self::get_upload_id_dir(bucket, object, upload_id);
-
-        let write_quorum = fi.write_quorum(self.default_write_quorum());
-
-        let disks = self.disks.read().await;
-
-        let disks = disks.clone();
-        // let disks = Self::shuffle_disks(&disks, &fi.erasure.distribution);
-
-        let part_path = format!("{}/{}/", upload_id_path, fi.data_dir.unwrap_or(Uuid::nil()));
-
-        let part_meta_paths = uploaded_parts
-            .iter()
-            .map(|v| format!("{part_path}part.{0}.meta", v.part_num))
-            .collect::<Vec<String>>();
-
-        let part_numbers = uploaded_parts.iter().map(|v| v.part_num).collect::<Vec<usize>>();
-
-        let object_parts =
-            Self::read_parts(&disks, RUSTFS_META_MULTIPART_BUCKET, &part_meta_paths, &part_numbers, write_quorum).await?;
-
-        if object_parts.len() != uploaded_parts.len() {
-            return Err(Error::other("part result number err"));
-        }
-
-        for (i, part) in object_parts.iter().enumerate() {
-            if let Some(err) = &part.error {
-                error!("complete_multipart_upload part error: {:?}", &err);
-            }
-
-            if uploaded_parts[i].part_num != part.number {
-                error!(
-                    "complete_multipart_upload part_id err part_id != part_num {} != {}",
-                    uploaded_parts[i].part_num, part.number
-                );
-                return Err(Error::InvalidPart(uploaded_parts[i].part_num, bucket.to_owned(), object.to_owned()));
-            }
-
-            fi.add_object_part(
-                part.number,
-                part.etag.clone(),
-                part.size,
-                part.mod_time,
-                part.actual_size,
-                part.index.clone(),
-            );
-        }
-
-        let (shuffle_disks, mut parts_metadatas) = Self::shuffle_disks_and_parts_metadata_by_index(&disks, &files_metas, &fi);
-
-        let curr_fi = fi.clone();
-
-        fi.parts = Vec::with_capacity(uploaded_parts.len());
-
-        let mut object_size: usize = 0;
-        let mut object_actual_size: i64 = 0;
-
-        for (i, p) in uploaded_parts.iter().enumerate() {
-            let has_part = curr_fi.parts.iter().find(|v| v.number == p.part_num);
-            if has_part.is_none() {
-                error!(
-                    "complete_multipart_upload has_part.is_none() {:?}, part_id={}, bucket={}, object={}",
-                    has_part, p.part_num, bucket, object
-                );
-                return Err(Error::InvalidPart(p.part_num, "".to_owned(), p.etag.clone().unwrap_or_default()));
-            }
-
-            let ext_part = &curr_fi.parts[i];
-
-            if p.etag != Some(ext_part.etag.clone()) {
-                error!(
-                    "complete_multipart_upload etag err {:?}, part_id={}, bucket={}, object={}",
-                    p.etag, p.part_num, bucket, object
-                );
-                return Err(Error::InvalidPart(p.part_num, ext_part.etag.clone(), p.etag.clone().unwrap_or_default()));
-            }
-
-            // TODO: crypto
-
-            if (i < uploaded_parts.len() - 1) && !is_min_allowed_part_size(ext_part.actual_size) {
-                error!(
-                    "complete_multipart_upload is_min_allowed_part_size err {:?}, part_id={}, bucket={}, object={}",
-                    ext_part.actual_size, p.part_num, bucket, object
-                );
-                return Err(Error::InvalidPart(p.part_num, ext_part.etag.clone(), p.etag.clone().unwrap_or_default()));
-            }
+    async fn new_multipart_upload(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<MultipartUploadResult> {
+        let disks = self.disks.read().await;
 
-            object_size += ext_part.size;
-            object_actual_size += ext_part.actual_size;
+        let disks = disks.clone();
 
-            fi.parts.push(ObjectPartInfo {
-                etag: ext_part.etag.clone(),
-                number: p.part_num,
-                size: ext_part.size,
-                mod_time: ext_part.mod_time,
-                actual_size: ext_part.actual_size,
-                index: ext_part.index.clone(),
-                ..Default::default()
-            });
+        let mut user_defined = opts.user_defined.clone();
+
+        if let Some(ref etag) = opts.preserve_etag {
+            user_defined.insert("etag".to_owned(), etag.clone());
         }
 
-        fi.size = object_size as i64;
-        fi.mod_time = opts.mod_time;
-        if fi.mod_time.is_none() {
-            fi.mod_time = Some(OffsetDateTime::now_utc());
+        if let Some(sc) = user_defined.get(AMZ_STORAGE_CLASS) {
+            if sc == storageclass::STANDARD {
+                let _ = user_defined.remove(AMZ_STORAGE_CLASS);
+            }
         }
 
-        // etag
-        let etag = {
-            if let Some(etag) = opts.user_defined.get("etag") {
-                etag.clone()
+        let sc_parity_drives = {
+            if let Some(sc) = GLOBAL_STORAGE_CLASS.get() {
+                sc.get_parity_for_sc(user_defined.get(AMZ_STORAGE_CLASS).cloned().unwrap_or_default().as_str())
             } else {
-                get_complete_multipart_md5(&uploaded_parts)
+                None
             }
         };
 
-        fi.metadata.insert("etag".to_owned(), etag);
-
-        fi.metadata
-            .insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size"), object_actual_size.to_string());
+        let mut parity_drives = sc_parity_drives.unwrap_or(self.default_parity_count);
+        if opts.max_parity {
+            parity_drives = disks.len() / 2;
+        }
 
-        if fi.is_compressed() {
-            fi.metadata
-                .insert(format!("{RESERVED_METADATA_PREFIX_LOWER}compression-size"), object_size.to_string());
+        let data_drives = disks.len() - parity_drives;
+        let mut write_quorum = data_drives;
+        if data_drives == parity_drives {
+            write_quorum += 1
         }
 
-        if opts.data_movement {
-            fi.set_data_moved();
+        let mut fi = FileInfo::new([bucket, object].join("/").as_str(), data_drives, parity_drives);
+
+        fi.version_id = if let Some(vid) = &opts.version_id {
+            Some(Uuid::parse_str(vid)?)
+        } else {
+            None
+        };
+
+        if opts.versioned && opts.version_id.is_none() {
+            fi.version_id = Some(Uuid::new_v4());
         }
 
-        // TODO: object_actual_size
-        let _ = object_actual_size;
+        fi.data_dir = Some(Uuid::new_v4());
+        fi.fresh = true;
 
-        for meta in parts_metadatas.iter_mut() {
-            if meta.is_valid() {
-                meta.size = fi.size;
-                meta.mod_time = fi.mod_time;
-                meta.parts.clone_from(&fi.parts);
-                meta.metadata = fi.metadata.clone();
-                meta.versioned = opts.versioned || opts.version_suspended;
+        let parts_metadata = vec![fi.clone(); disks.len()];
+
+        if !user_defined.contains_key("content-type") {
+            // TODO: get content-type
+        }
 
-                // TODO: Checksum
+        if let Some(sc) = user_defined.get(AMZ_STORAGE_CLASS) {
+            if sc == storageclass::STANDARD {
+                let _ = user_defined.remove(AMZ_STORAGE_CLASS);
             }
         }
 
-        let mut parts = Vec::with_capacity(curr_fi.parts.len());
-        // TODO: 优化 cleanupMultipartPath
-        for p in curr_fi.parts.iter() {
-            parts.push(path_join_buf(&[
-                &upload_id_path,
-                curr_fi.data_dir.unwrap_or(Uuid::nil()).to_string().as_str(),
-                format!("part.{}.meta", p.number).as_str(),
-            ]));
+        // Parts must all be written with the same algorithm, pin it for the lifetime of the upload
+        user_defined.insert(
+            format!("{RESERVED_METADATA_PREFIX_LOWER}bitrot-algo"),
+            storageclass::bitrot_algorithm_name(&Self::bitrot_algo_for_sc(&user_defined)).to_owned(),
+        );
+        // The upload dir is named by a hash, keep the object so lifecycle can find stale uploads
+        user_defined.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}multipart-object"), format!("{bucket}/{object}"));
 
-            if !fi.parts.iter().any(|v| v.number == p.number) {
-                parts.push(path_join_buf(&[
-                    &upload_id_path,
-                    curr_fi.data_dir.unwrap_or(Uuid::nil()).to_string().as_str(),
-                    format!("part.{}", p.number).as_str(),
-                ]));
-            }
+        let (shuffle_disks, mut parts_metadatas) = Self::shuffle_disks_and_parts_metadata(&disks, &parts_metadata, &fi);
 
-            // let _ = self
-            //     .remove_part_meta(
-            //         bucket,
-            //         object,
-            //         upload_id,
-            //         curr_fi.data_dir.unwrap_or(Uuid::nil()).to_string().as_str(),
-            //         p.number,
-            //     )
-            //     .await;
+        let mod_time = opts.mod_time.unwrap_or(OffsetDateTime::now_utc());
 
-            // if !fi.parts.iter().any(|v| v.number == p.number) {
-            //     let _ = self
-            //         .remove_object_part(
-            //             bucket,
-            //             object,
-            //             upload_id,
-            //             curr_fi.data_dir.unwrap_or(Uuid::nil()).to_string().as_str(),
-            //             p.number,
-            //         )
-            //         .await;
-            // }
+        for fi in parts_metadatas.iter_mut() {
+            fi.metadata = user_defined.clone();
+            fi.mod_time = Some(mod_time);
+            fi.fresh = true;
         }
 
-        {
-            let disks = self.get_disks_internal().await;
-            Self::cleanup_multipart_path(&disks, &parts).await;
-        }
+        // fi.mod_time = Some(now);
 
-        let (online_disks, versions, op_old_dir) = Self::rename_data(
+        let upload_uuid = format!("{}x{}", Uuid::new_v4(), mod_time.unix_timestamp_nanos());
+
+        let upload_id = base64_encode(format!("{}.{}", get_global_deployment_id().unwrap_or_default(), upload_uuid).as_bytes());
+
+        let upload_path = Self::get_upload_id_dir(bucket, object, upload_uuid.as_str());
+
+        Self::write_unique_file_info(
             &shuffle_disks,
+            bucket,
             RUSTFS_META_MULTIPART_BUCKET,
-            &upload_id_path,
+            upload_path.as_str(),
             &parts_metadatas,
-            bucket,
-            object,
             write_quorum,
         )
-        .await?;
+        .await
+        .map_err(|e| to_object_err(e.into(), vec![bucket, object]))?;
 
-        // debug!("complete fileinfo {:?}", &fi);
+        // evalDisks
 
-        // TODO: reduce_common_data_dir
-        if let Some(old_dir) = op_old_dir {
-            self.commit_rename_data_dir(&shuffle_disks, bucket, object, &old_dir.to_string(), write_quorum)
-                .await?;
-        }
-        if let Some(versions) = versions {
-            let _ =
-                rustfs_common::heal_channel::send_heal_request(rustfs_common::heal_channel::create_heal_request_with_options(
-                    bucket.to_string(),
-                    Some(object.to_string()),
-                    false,
-                    Some(rustfs_common::heal_channel::HealChannelPriority::Normal),
-                    Some(self.pool_index),
-                    Some(self.set_index),
-                ))
-                .await;
-        }
+        Ok(MultipartUploadResult { upload_id })
+    }
 
-        let upload_id_path = upload_id_path.clone();
-        let store = self.clone();
-        let _cleanup_handle = tokio::spawn(async move {
-            let _ = store.delete_all(RUSTFS_META_MULTIPART_BUCKET, &upload_id_path).await;
-        });
+    #[tracing::instrument(skip(self))]
+    async fn get_multipart_info(
+        &self,
+        bucket: &str,
+        object: &str,
+        upload_id: &str,
+        _opts: &ObjectOptions,
+    ) -> Result<MultipartInfo> {
+        // TODO: nslock
+        let (fi, _) = self
+            .check_upload_id_exists(bucket, object, upload_id, false)
+            .await
+            .map_err(|e| to_object_err(e, vec![bucket, object, upload_id]))?;
 
-        for (i, op_disk) in online_disks.iter().enumerate() {
-            if let Some(disk) = op_disk {
-                if disk.is_online().await {
-                    fi = parts_metadatas[i].clone();
-                    break;
-                }
-            }
-        }
+        Ok(MultipartInfo {
+            bucket: bucket.to_owned(),
+            object: object.to_owned(),
+            upload_id: upload_id.to_owned(),
+            user_defined: fi.metadata.clone(),
+            ..Default::default()
+        })
+    }
 
-        fi.is_latest = true;
+    #[tracing::instrument(skip(self))]
+    async fn abort_multipart_upload(&self, bucket: &str, object: &str, upload_id: &str, _opts: &ObjectOptions) -> Result<()> {
+        self.check_upload_id_exists(bucket, object, upload_id, false).await?;
+        let upload_id_path = Self::get_upload_id_dir(bucket, object, upload_id);
 
-        Ok(ObjectInfo::from_file_info(&fi, bucket, object, opts.versioned || opts.version_suspended))
+        self.delete_all(RUSTFS_META_MULTIPART_BUCKET, &upload_id_path).await
+    }
+    // complete_multipart_upload 完成
+    #[tracing::instrument(skip(self))]
+    async fn complete_multipart_upload(
+        self: Arc<Self>,
+        bucket: &str,
+        object: &str,
+        upload_id: &str,
+        uploaded_parts: Vec<CompletePart>,
+        opts: &ObjectOptions,
+    ) -> Result<ObjectInfo> {
+        // The object and the upload are taken as one batch, so concurrent completes and puts all lock in key order
+        let paths = vec![object.to_string(), Self::get_upload_id_dir(bucket, object, upload_id)];
+        let lock = self.lock_paths(&paths, opts).await?;
+
+        let result = self
+            .clone()
+            .complete_multipart_upload_locked(bucket, object, upload_id, uploaded_parts, opts, lock.as_ref())
+            .await;
+
+        if let Some(lock) = lock {
+            lock.release().await;
+        }
+        result
     }
 
     #[tracing::instrument(skip(self))]
@@ -5664,6 +6577,7 @@ async fn get_disks_info(disks: &[Option<DiskStore>], eps: &[Endpoint]) -> Vec<ru
                     },
                     used_inodes: res.used_inodes,
                     free_inodes: res.free_inodes,
+                    metrics: Some(res.metrics.clone()),
                     ..Default::default()
                 }),
                 Err(err) => ret.push(rustfs_madmin::Disk {
@@ -5755,6 +6669,58 @@ fn get_complete_multipart_md5(parts: &[CompletePart]) -> String {
     format!("{:x}-{}", hasher.finalize(), parts.len())
 }
 
+/// Composite checksums of a multipart object for every algorithm all of its parts were uploaded with:
+/// the checksum of the concatenated checksums of the parts, followed by the number of parts
+fn get_complete_multipart_checksums(parts: &[ObjectPartInfo]) -> HashMap<String, String> {
+    let mut checksums = HashMap::new();
+    if parts.is_empty() {
+        return checksums;
+    }
+
+    // CRC64NVME checksums only describe whole objects, they can't be combined from those of the parts
+    for algorithm in [
+        ChecksumAlgorithm::CRC32,
+        ChecksumAlgorithm::CRC32C,
+        ChecksumAlgorithm::SHA1,
+        ChecksumAlgorithm::SHA256,
+    ] {
+        let mut hasher = ChecksumHasher::default();
+        match algorithm {
+            ChecksumAlgorithm::CRC32 => hasher.crc32 = Some(Default::default()),
+            ChecksumAlgorithm::CRC32C => hasher.crc32c = Some(Default::default()),
+            ChecksumAlgorithm::SHA1 => hasher.sha1 = Some(Default::default()),
+            _ => hasher.sha256 = Some(Default::default()),
+        }
+
+        let all_parts = parts.iter().all(|part| {
+            let checksum = part.checksums.as_ref().and_then(|c| c.get(algorithm));
+            match checksum.and_then(|v| general_purpose::STANDARD.decode(v).ok()) {
+                Some(raw) => {
+                    hasher.update(&raw);
+                    true
+                }
+                None => false,
+            }
+        });
+        if !all_parts {
+            continue;
+        }
+
+        let checksum = hasher.finalize();
+        let value = match algorithm {
+            ChecksumAlgorithm::CRC32 => checksum.checksum_crc32,
+            ChecksumAlgorithm::CRC32C => checksum.checksum_crc32c,
+            ChecksumAlgorithm::SHA1 => checksum.checksum_sha1,
+            _ => checksum.checksum_sha256,
+        };
+        if let Some(value) = value {
+            checksums.insert(algorithm.to_owned(), format!("{value}-{}", parts.len()));
+        }
+    }
+
+    checksums
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5787,6 +6753,27 @@ mod tests {
         assert!(is_min_allowed_part_size(100 * 1024 * 1024)); // 100MB - allowed
     }
 
+    #[test]
+    fn test_get_complete_multipart_checksums() {
+        let part = |number: usize, checksums: &[(&str, &str)]| ObjectPartInfo {
+            number,
+            checksums: Some(checksums.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()),
+            ..Default::default()
+        };
+
+        // CRC32 of "hello world" for both parts, only the first one has a SHA1
+        let parts = vec![
+            part(1, &[("CRC32", "DUoRhQ=="), ("SHA1", "Kq5sNclPz7QV2+lfQIuc6R7oRu0=")]),
+            part(2, &[("CRC32", "DUoRhQ==")]),
+        ];
+        let checksums = get_complete_multipart_checksums(&parts);
+        assert_eq!(checksums.len(), 1);
+        assert_eq!(checksums.get("CRC32").map(String::as_str), Some("7ryKJw==-2"));
+
+        assert!(get_complete_multipart_checksums(&[]).is_empty());
+        assert!(get_complete_multipart_checksums(&[ObjectPartInfo::default()]).is_empty());
+    }
+
     #[test]
     fn test_get_complete_multipart_md5() {
         // Test MD5 calculation for multipart upload
@@ -5794,10 +6781,12 @@ mod tests {
             CompletePart {
                 part_num: 1,
                 etag: Some("d41d8cd98f00b204e9800998ecf8427e".to_string()),
+                ..Default::default()
             },
             CompletePart {
                 part_num: 2,
                 etag: Some("098f6bcd4621d373cade4e832627b4f6".to_string()),
+                ..Default::default()
             },
         ];
 
@@ -5814,11 +6803,31 @@ mod tests {
         let single_part = vec![CompletePart {
             part_num: 1,
             etag: Some("d41d8cd98f00b204e9800998ecf8427e".to_string()),
+            ..Default::default()
         }];
         let single_result = get_complete_multipart_md5(&single_part);
         assert!(single_result.ends_with("-1"));
     }
 
+    #[test]
+    fn test_get_complete_multipart_md5_quoted_parts() {
+        // The composite ETag is the MD5 of the binary MD5s of the parts, whether the client quoted them or not
+        let parts: Vec<CompletePart> = ["\"d41d8cd98f00b204e9800998ecf8427e\"", "098f6bcd4621d373cade4e832627b4f6"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, etag)| {
+                CompletePart::from(s3s::dto::CompletedPart {
+                    part_number: Some(i as i32 + 1),
+                    e_tag: Some(etag.to_owned()),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        assert_eq!(parts[0].etag.as_deref(), Some("d41d8cd98f00b204e9800998ecf8427e"));
+        assert_eq!(get_complete_multipart_md5(&parts), "f666c301a43456b66b9f3a47f696d391-2");
+    }
+
     #[test]
     fn test_get_upload_id_dir() {
         // Test upload ID directory path generation
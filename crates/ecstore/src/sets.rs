@@ -361,6 +361,19 @@ impl ObjectIO for Sets {
     async fn put_object(&self, bucket: &str, object: &str, data: &mut PutObjReader, opts: &ObjectOptions) -> Result<ObjectInfo> {
         self.get_disks_by_key(object).put_object(bucket, object, data, opts).await
     }
+    #[tracing::instrument(level = "debug", skip(self, data))]
+    async fn append_object(
+        &self,
+        bucket: &str,
+        object: &str,
+        offset: i64,
+        data: &mut PutObjReader,
+        opts: &ObjectOptions,
+    ) -> Result<ObjectInfo> {
+        self.get_disks_by_key(object)
+            .append_object(bucket, object, offset, data, opts)
+            .await
+    }
 }
 
 #[async_trait::async_trait]
@@ -477,7 +490,7 @@ impl StorageAPI for Sets {
 
         let cp_src_dst_same = path_join_buf(&[src_bucket, src_object]) == path_join_buf(&[dst_bucket, dst_object]);
 
-        if cp_src_dst_same {
+        if cp_src_dst_same && src_info.metadata_only {
             if let (Some(src_vid), Some(dst_vid)) = (&src_opts.version_id, &dst_opts.version_id) {
                 if src_vid == dst_vid {
                     return src_set
@@ -500,8 +513,14 @@ impl StorageAPI for Sets {
             }
         }
 
+        if !cp_src_dst_same && src_set.set_index == dst_set.set_index {
+            return src_set
+                .copy_object(src_bucket, src_object, dst_bucket, dst_object, src_info, src_opts, dst_opts)
+                .await;
+        }
+
         let put_opts = ObjectOptions {
-            user_defined: dst_opts.user_defined.clone(),
+            user_defined: src_info.user_defined.clone(),
             versioned: dst_opts.versioned,
             version_id: dst_opts.version_id.clone(),
             mod_time: dst_opts.mod_time,
@@ -600,11 +619,18 @@ impl StorageAPI for Sets {
         //     del_errs.extend(errs);
         // }
 
-        // TODO: Implement concurrency
-        for (k, v) in set_obj_map {
-            let disks = self.get_disks(k);
-            let objs: Vec<ObjectToDelete> = v.iter().map(|v| v.obj.clone()).collect();
-            let (dobjects, errs) = disks.delete_objects(bucket, objs, opts.clone()).await?;
+        let results = join_all(set_obj_map.into_iter().map(|(k, v)| {
+            let opts = opts.clone();
+            async move {
+                let objs: Vec<ObjectToDelete> = v.iter().map(|v| v.obj.clone()).collect();
+                let res = self.get_disks(k).delete_objects(bucket, objs, opts).await;
+                (v, res)
+            }
+        }))
+        .await;
+
+        for (v, res) in results {
+            let (dobjects, errs) = res?;
 
             for (i, err) in errs.into_iter().enumerate() {
                 let obj = v.get(i).unwrap();
@@ -662,7 +688,7 @@ impl StorageAPI for Sets {
     }
 
     #[tracing::instrument(skip(self))]
-    async fn restore_transitioned_object(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()> {
+    async fn restore_transitioned_object(self: Arc<Self>, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()> {
         self.get_disks_by_key(object)
             .restore_transitioned_object(bucket, object, opts)
             .await
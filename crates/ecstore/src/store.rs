@@ -16,7 +16,7 @@
 
 use crate::bucket::lifecycle::bucket_lifecycle_ops::init_background_expiry;
 use crate::bucket::metadata_sys::{self, set_bucket_metadata};
-use crate::bucket::utils::{check_valid_bucket_name, check_valid_bucket_name_strict, is_meta_bucketname};
+use crate::bucket::utils::{check_new_bucket_name, check_valid_bucket_name, check_valid_bucket_name_strict, is_meta_bucketname};
 use crate::config::GLOBAL_STORAGE_CLASS;
 use crate::config::storageclass;
 use crate::disk::endpoint::{Endpoint, EndpointType};
@@ -56,6 +56,7 @@ use rand::Rng as _;
 use rustfs_common::globals::{GLOBAL_Local_Node_Name, GLOBAL_Rustfs_Host, GLOBAL_Rustfs_Port};
 use rustfs_common::heal_channel::{HealItemType, HealOpts};
 use rustfs_filemeta::FileInfo;
+use rustfs_lock::{LockGuard, LockType};
 use rustfs_madmin::heal_commands::HealResultItem;
 use rustfs_utils::crypto::base64_decode;
 use rustfs_utils::path::{SLASH_SEPARATOR, decode_dir_object, encode_dir_object, path_join_buf};
@@ -643,6 +644,32 @@ impl ECStore {
         Ok(idx)
     }
 
+    /// Write lock `bucket` across the cluster, so that making and deleting it do not race
+    async fn lock_bucket(&self, bucket: &str) -> Result<LockGuard> {
+        self.lock_meta(&format!("{bucket}.lck")).await
+    }
+
+    /// Write lock the config item at `path` across the cluster, so that updates reading, changing
+    /// and saving it back do not overwrite each other
+    pub async fn lock_config(&self, path: &str) -> Result<LockGuard> {
+        self.lock_meta(&format!("{path}.lck")).await
+    }
+
+    async fn lock_meta(&self, resource: &str) -> Result<LockGuard> {
+        let set = &self.pools[0].disk_set[0];
+        set.namespace_lock
+            .get_lock(
+                &[format!("{RUSTFS_META_BUCKET}/{resource}")],
+                &set.locker_owner,
+                LockType::Exclusive,
+                Duration::from_secs(5),
+                Duration::from_secs(10),
+                None,
+            )
+            .await?
+            .ok_or_else(|| Error::other("can not get lock. please retry".to_string()))
+    }
+
     async fn get_pool_idx_no_lock(&self, bucket: &str, object: &str, size: i64) -> Result<usize> {
         let idx = match self.get_pool_idx_existing_no_lock(bucket, object).await {
             Ok(res) => res,
@@ -1122,6 +1149,32 @@ impl ObjectIO for ECStore {
 
         self.pools[idx].put_object(bucket, &object, data, opts).await
     }
+    #[tracing::instrument(level = "debug", skip(self, data))]
+    async fn append_object(
+        &self,
+        bucket: &str,
+        object: &str,
+        offset: i64,
+        data: &mut PutObjReader,
+        opts: &ObjectOptions,
+    ) -> Result<ObjectInfo> {
+        check_put_object_args(bucket, object)?;
+
+        let object = encode_dir_object(object);
+
+        if self.single_pool() {
+            return self.pools[0].append_object(bucket, object.as_str(), offset, data, opts).await;
+        }
+
+        // The appended data goes to the pool holding the object, a new object to the pool a put would choose
+        let idx = match self.get_pool_idx_existing_with_opts(bucket, object.as_str(), opts).await {
+            Ok(idx) => idx,
+            Err(err) if is_err_object_not_found(&err) && offset == 0 => self.get_pool_idx(bucket, &object, data.size()).await?,
+            Err(err) => return Err(err),
+        };
+
+        self.pools[idx].append_object(bucket, &object, offset, data, opts).await
+    }
 }
 
 lazy_static! {
@@ -1212,12 +1265,20 @@ impl StorageAPI for ECStore {
 
     #[tracing::instrument(skip(self))]
     async fn make_bucket(&self, bucket: &str, opts: &MakeBucketOptions) -> Result<()> {
+        let mut _lock = None;
         if !is_meta_bucketname(bucket) {
-            if let Err(err) = check_valid_bucket_name_strict(bucket) {
+            if let Err(err) = check_new_bucket_name(bucket) {
                 return Err(StorageError::BucketNameInvalid(err.to_string()));
             }
 
-            // TODO: nslock
+            if !opts.no_lock {
+                _lock = Some(self.lock_bucket(bucket).await?);
+            }
+
+            // A bucket made again would get new metadata, dropping its configuration
+            if !opts.force_create && self.peer_sys.get_bucket_info(bucket, &BucketOptions::default()).await.is_ok() {
+                return Err(StorageError::BucketExists(bucket.to_string()));
+            }
         }
 
         if let Err(err) = self.peer_sys.make_bucket(bucket, opts).await {
@@ -1295,7 +1356,11 @@ impl StorageAPI for ECStore {
             return Err(StorageError::BucketNameInvalid(err.to_string()));
         }
 
-        // TODO: nslock
+        let _lock = if opts.no_lock {
+            None
+        } else {
+            Some(self.lock_bucket(bucket).await?)
+        };
 
         let mut opts = opts.clone();
         if !opts.force {
@@ -1390,7 +1455,8 @@ impl StorageAPI for ECStore {
 
         let pool_idx = self.get_pool_idx_no_lock(src_bucket, &src_object, src_info.size).await?;
 
-        if cp_src_dst_same {
+        // Copies that rewrite the data, e.g. to change its encryption, are put like new objects
+        if cp_src_dst_same && src_info.metadata_only {
             if let (Some(src_vid), Some(dst_vid)) = (&src_opts.version_id, &dst_opts.version_id) {
                 if src_vid == dst_vid {
                     return self.pools[pool_idx]
@@ -1413,6 +1479,19 @@ impl StorageAPI for ECStore {
             }
         }
 
+        let dst_pool_idx = if cp_src_dst_same {
+            pool_idx
+        } else {
+            self.get_pool_idx_no_lock(dst_bucket, &dst_object, src_info.size).await?
+        };
+
+        // Within a pool, the sets copy the objects they hold both of without going through the erasure code
+        if !cp_src_dst_same && dst_pool_idx == pool_idx {
+            return self.pools[pool_idx]
+                .copy_object(src_bucket, &src_object, dst_bucket, &dst_object, src_info, src_opts, dst_opts)
+                .await;
+        }
+
         let put_opts = ObjectOptions {
             user_defined: src_info.user_defined.clone(),
             versioned: dst_opts.versioned,
@@ -1423,7 +1502,7 @@ impl StorageAPI for ECStore {
         };
 
         if let Some(put_object_reader) = src_info.put_object_reader.as_mut() {
-            return self.pools[pool_idx]
+            return self.pools[dst_pool_idx]
                 .put_object(dst_bucket, &dst_object, put_object_reader, &put_opts)
                 .await;
         }
@@ -1608,7 +1687,18 @@ impl StorageAPI for ECStore {
                     }
                 }
                 Err(e) => {
-                    if !is_err_object_not_found(&e) && is_err_version_not_found(&e) {
+                    // A delete marker replicated from another site is kept even when the object never reached this one
+                    if opts.replication_request && opts.delete_marker && is_err_object_not_found(&e) {
+                        if let Some(obj) = objects.get(i) {
+                            if let Some(idx) = self.get_available_pool_idx(bucket, &obj.object_name, 0).await {
+                                pool_obj_idx_map.entry(idx).or_insert_with(Vec::new).push(obj.clone());
+                                orig_index_map.entry(idx).or_insert_with(Vec::new).push(i);
+                                continue;
+                            }
+                        }
+                    }
+
+                    if !is_err_object_not_found(&e) && !is_err_version_not_found(&e) {
                         del_errs[i] = Some(e)
                     }
 
@@ -1623,36 +1713,30 @@ impl StorageAPI for ECStore {
             }
         }
 
-        if !pool_obj_idx_map.is_empty() {
-            for (i, sets) in self.pools.iter().enumerate() {
-                //  取 pool idx 对应的 objects index
-                if let Some(objs) = pool_obj_idx_map.get(&i) {
-                    //  取对应 obj，理论上不会 none
-                    // let objs: Vec<ObjectToDelete> = obj_idxs.iter().filter_map(|&idx| objects.get(idx).cloned()).collect();
+        let results = join_all(pool_obj_idx_map.into_iter().map(|(i, objs)| {
+            let opts = opts.clone();
+            async move { (i, self.pools[i].delete_objects(bucket, objs, opts).await) }
+        }))
+        .await;
 
-                    if objs.is_empty() {
-                        continue;
-                    }
+        for (i, res) in results {
+            let (pdel_objs, perrs) = res?;
 
-                    let (pdel_objs, perrs) = sets.delete_objects(bucket, objs.clone(), opts.clone()).await?;
+            // 同时存入不可能为 none
+            let org_indexes = orig_index_map.get(&i).unwrap();
 
-                    // 同时存入不可能为 none
-                    let org_indexes = orig_index_map.get(&i).unwrap();
+            // perrs 的顺序理论上跟 obj_idxs 顺序一致
+            for (i, err) in perrs.into_iter().enumerate() {
+                let obj_idx = org_indexes[i];
 
-                    // perrs 的顺序理论上跟 obj_idxs 顺序一致
-                    for (i, err) in perrs.into_iter().enumerate() {
-                        let obj_idx = org_indexes[i];
-
-                        if err.is_some() {
-                            del_errs[obj_idx] = err;
-                        }
+                if err.is_some() {
+                    del_errs[obj_idx] = err;
+                }
 
-                        let mut dobj = pdel_objs.get(i).unwrap().clone();
-                        dobj.object_name = decode_dir_object(&dobj.object_name);
+                let mut dobj = pdel_objs.get(i).unwrap().clone();
+                dobj.object_name = decode_dir_object(&dobj.object_name);
 
-                        del_objects[obj_idx] = dobj;
-                    }
-                }
+                del_objects[obj_idx] = dobj;
             }
         }
 
@@ -1712,44 +1796,31 @@ impl StorageAPI for ECStore {
     ) -> Result<ListMultipartsInfo> {
         check_list_multipart_args(bucket, prefix, &key_marker, &upload_id_marker, &delimiter)?;
 
-        if prefix.is_empty() {
-            // TODO: return from cache
-        }
-
-        if self.single_pool() {
-            return self.pools[0]
-                .list_multipart_uploads(bucket, prefix, key_marker, upload_id_marker, delimiter, max_uploads)
-                .await;
-        }
-
+        // Upload directories are named after a hash of the object, so the uploads under a prefix are found from
+        // the object each one records
         let mut uploads = Vec::new();
-
         for pool in self.pools.iter() {
             if self.is_suspended(pool.pool_idx).await {
                 continue;
             }
-            let res = pool
-                .list_multipart_uploads(
-                    bucket,
-                    prefix,
-                    key_marker.clone(),
-                    upload_id_marker.clone(),
-                    delimiter.clone(),
-                    max_uploads,
-                )
-                .await?;
-            uploads.extend(res.uploads);
+            for set in pool.disk_set.iter() {
+                uploads.extend(
+                    set.list_all_multipart_uploads()
+                        .await?
+                        .into_iter()
+                        .filter(|u| u.bucket == bucket && u.object.starts_with(prefix)),
+                );
+            }
         }
 
-        Ok(ListMultipartsInfo {
+        Ok(page_multipart_uploads(
+            uploads,
+            prefix,
             key_marker,
             upload_id_marker,
+            delimiter,
             max_uploads,
-            uploads,
-            prefix: prefix.to_owned(),
-            delimiter: delimiter.to_owned(),
-            ..Default::default()
-        })
+        ))
     }
 
     #[tracing::instrument(skip(self))]
@@ -1814,17 +1885,20 @@ impl StorageAPI for ECStore {
     }
 
     #[tracing::instrument(skip(self))]
-    async fn restore_transitioned_object(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()> {
+    async fn restore_transitioned_object(self: Arc<Self>, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()> {
         let object = encode_dir_object(object);
         if self.single_pool() {
-            return self.pools[0].restore_transitioned_object(bucket, &object, opts).await;
+            return self.pools[0].clone().restore_transitioned_object(bucket, &object, opts).await;
         }
 
         //opts.skip_decommissioned = true;
         //opts.nolock = true;
         let idx = self.get_pool_idx_existing_with_opts(bucket, object.as_str(), opts).await?;
 
-        self.pools[idx].restore_transitioned_object(bucket, &object, opts).await
+        self.pools[idx]
+            .clone()
+            .restore_transitioned_object(bucket, &object, opts)
+            .await
     }
 
     #[tracing::instrument(skip(self))]
@@ -2378,6 +2452,85 @@ fn check_list_multipart_args(
     Ok(())
 }
 
+/// The page of `uploads` after the markers, in key and then initiation order, keys sharing a prefix up to the
+/// delimiter rolled up into a common prefix
+fn page_multipart_uploads(
+    mut uploads: Vec<MultipartInfo>,
+    prefix: &str,
+    key_marker: Option<String>,
+    upload_id_marker: Option<String>,
+    delimiter: Option<String>,
+    max_uploads: usize,
+) -> ListMultipartsInfo {
+    uploads.sort_by(|a, b| a.object.cmp(&b.object).then(a.initiated.cmp(&b.initiated)));
+
+    let mut ret = ListMultipartsInfo {
+        key_marker: key_marker.clone(),
+        upload_id_marker: upload_id_marker.clone(),
+        max_uploads,
+        prefix: prefix.to_owned(),
+        delimiter: delimiter.clone(),
+        ..Default::default()
+    };
+
+    let delimiter = delimiter.filter(|d| !d.is_empty());
+    let mut after_upload_id_marker = upload_id_marker.is_none();
+    for upload in uploads {
+        if let Some(key_marker) = &key_marker {
+            if upload.object < *key_marker {
+                continue;
+            }
+            // The uploads of the marker key follow the upload id marker, when there is one
+            if upload.object == *key_marker {
+                if !after_upload_id_marker {
+                    after_upload_id_marker = upload_id_marker.as_ref() == Some(&upload.upload_id);
+                    continue;
+                }
+                if upload_id_marker.is_none() {
+                    continue;
+                }
+            }
+        }
+
+        let common_prefix = delimiter.as_ref().and_then(|d| {
+            upload.object[prefix.len()..]
+                .find(d.as_str())
+                .map(|idx| upload.object[..prefix.len() + idx + d.len()].to_owned())
+        });
+
+        if let Some(common_prefix) = common_prefix {
+            if key_marker.as_ref().is_some_and(|m| m.starts_with(&common_prefix))
+                || ret.common_prefixes.last() == Some(&common_prefix)
+            {
+                continue;
+            }
+            if ret.uploads.len() + ret.common_prefixes.len() == max_uploads {
+                ret.is_truncated = true;
+                break;
+            }
+            ret.next_key_marker = Some(common_prefix.clone());
+            ret.next_upload_id_marker = None;
+            ret.common_prefixes.push(common_prefix);
+            continue;
+        }
+
+        if ret.uploads.len() + ret.common_prefixes.len() == max_uploads {
+            ret.is_truncated = true;
+            break;
+        }
+        ret.next_key_marker = Some(upload.object.clone());
+        ret.next_upload_id_marker = Some(upload.upload_id.clone());
+        ret.uploads.push(upload);
+    }
+
+    if !ret.is_truncated {
+        ret.next_key_marker = None;
+        ret.next_upload_id_marker = None;
+    }
+
+    ret
+}
+
 fn check_object_args(bucket: &str, object: &str) -> Result<()> {
     if !is_meta_bucketname(bucket) && check_valid_bucket_name_strict(bucket).is_err() {
         return Err(StorageError::BucketNameInvalid(bucket.to_string()));
@@ -2734,4 +2887,64 @@ mod tests {
         assert!(check_put_object_args("", "test-object").is_err());
         assert!(check_put_object_args("test-bucket", "").is_err());
     }
+
+    fn upload(object: &str, upload_id: &str, initiated: i64) -> MultipartInfo {
+        MultipartInfo {
+            bucket: "bucket".to_owned(),
+            object: object.to_owned(),
+            upload_id: upload_id.to_owned(),
+            initiated: Some(OffsetDateTime::from_unix_timestamp(initiated).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn uploads() -> Vec<MultipartInfo> {
+        vec![
+            upload("b", "b2", 2),
+            upload("a/x", "ax", 3),
+            upload("b", "b1", 1),
+            upload("a/y", "ay", 4),
+            upload("c", "c1", 5),
+        ]
+    }
+
+    #[test]
+    fn test_page_multipart_uploads_markers() {
+        let page = page_multipart_uploads(uploads(), "", None, None, None, 3);
+        let ids: Vec<_> = page.uploads.iter().map(|u| u.upload_id.as_str()).collect();
+        assert_eq!(ids, ["ax", "ay", "b1"]);
+        assert!(page.is_truncated);
+        assert_eq!(page.next_key_marker.as_deref(), Some("b"));
+        assert_eq!(page.next_upload_id_marker.as_deref(), Some("b1"));
+
+        let page = page_multipart_uploads(uploads(), "", page.next_key_marker, page.next_upload_id_marker, None, 3);
+        let ids: Vec<_> = page.uploads.iter().map(|u| u.upload_id.as_str()).collect();
+        assert_eq!(ids, ["b2", "c1"]);
+        assert!(!page.is_truncated);
+        assert_eq!(page.next_key_marker, None);
+
+        // Without an upload id marker all the uploads of the marker key are skipped
+        let page = page_multipart_uploads(uploads(), "", Some("b".to_owned()), None, None, 10);
+        let ids: Vec<_> = page.uploads.iter().map(|u| u.upload_id.as_str()).collect();
+        assert_eq!(ids, ["c1"]);
+    }
+
+    #[test]
+    fn test_page_multipart_uploads_delimiter() {
+        let page = page_multipart_uploads(uploads(), "", None, None, Some("/".to_owned()), 2);
+        assert_eq!(page.common_prefixes, ["a/"]);
+        let ids: Vec<_> = page.uploads.iter().map(|u| u.upload_id.as_str()).collect();
+        assert_eq!(ids, ["b1"]);
+        assert!(page.is_truncated);
+
+        let page = page_multipart_uploads(uploads(), "", Some("a/".to_owned()), None, Some("/".to_owned()), 10);
+        assert!(page.common_prefixes.is_empty());
+        let ids: Vec<_> = page.uploads.iter().map(|u| u.upload_id.as_str()).collect();
+        assert_eq!(ids, ["b1", "b2", "c1"]);
+
+        let a_uploads = uploads().into_iter().filter(|u| u.object.starts_with("a/")).collect();
+        let page = page_multipart_uploads(a_uploads, "a/", None, None, Some("/".to_owned()), 10);
+        assert!(page.common_prefixes.is_empty());
+        assert_eq!(page.uploads.len(), 2);
+    }
 }
@@ -12,9 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::bucket::encryption::keys;
+use crate::bucket::lifecycle::bucket_lifecycle_ops::parse_restore_obj_status;
+use crate::bucket::lifecycle::lifecycle::TRANSITION_COMPLETE;
 use crate::bucket::metadata_sys::get_versioning_config;
 use crate::bucket::versioning::VersioningApi as _;
-use crate::cmd::bucket_replication::{ReplicationStatusType, VersionPurgeStatusType};
+use crate::cmd::bucket_replication::{ReplicationStatusType, VersionPurgeStatusType, replication_status_from_metadata};
+use crate::config::storageclass::STANDARD;
 use crate::disk::DiskStore;
 use crate::error::{Error, Result};
 use crate::store_utils::clean_metadata;
@@ -23,22 +27,31 @@ use crate::{
     bucket::lifecycle::lifecycle::ExpirationOptions,
     bucket::lifecycle::{bucket_lifecycle_ops::TransitionedObject, lifecycle::TransitionOptions},
 };
+use bytes::Bytes;
 use http::{HeaderMap, HeaderValue};
 use rustfs_common::heal_channel::HealOpts;
 use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
-use rustfs_filemeta::{FileInfo, MetaCacheEntriesSorted, ObjectPartInfo, headers::AMZ_OBJECT_TAGGING};
+use rustfs_filemeta::{
+    FileInfo, MetaCacheEntriesSorted, NULL_VERSION_ID, ObjectPartInfo,
+    headers::{AMZ_OBJECT_TAGGING, AMZ_STORAGE_CLASS},
+};
 use rustfs_madmin::heal_commands::HealResultItem;
-use rustfs_rio::{DecompressReader, HashReader, LimitReader, WarpReader};
+use rustfs_rio::{DecompressReader, DecryptReader, HashReader, Index, LimitReader, WarpReader};
 use rustfs_utils::CompressionAlgorithm;
-use rustfs_utils::path::decode_dir_object;
+use rustfs_utils::crypto::base64_decode;
+use rustfs_utils::path::{decode_dir_object, trim_etag};
+use s3s::dto::ChecksumAlgorithm;
+use s3s::header::X_AMZ_RESTORE;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::io::Cursor;
 use std::str::FromStr as _;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use time::OffsetDateTime;
 use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::sync::CancellationToken;
 use tracing::warn;
 use uuid::Uuid;
 
@@ -93,6 +106,15 @@ impl PutObjReader {
         }
     }
 
+    /// Reader over stored data, e.g. ciphertext, of an object whose content is `actual_size` long
+    pub fn from_vec_with_actual_size(data: Vec<u8>, actual_size: i64) -> Self {
+        let content_length = data.len() as i64;
+        PutObjReader {
+            stream: HashReader::new(Box::new(WarpReader::new(Cursor::new(data))), content_length, actual_size, None, false)
+                .unwrap(),
+        }
+    }
+
     pub fn size(&self) -> i64 {
         self.stream.size()
     }
@@ -109,7 +131,7 @@ pub struct GetObjectReader {
 
 impl GetObjectReader {
     #[tracing::instrument(level = "debug", skip(reader))]
-    pub fn new(
+    pub async fn new(
         reader: Box<dyn AsyncRead + Unpin + Send + Sync>,
         rs: Option<HTTPRangeSpec>,
         oi: &ObjectInfo,
@@ -124,34 +146,59 @@ impl GetObjectReader {
             }
         }
 
-        // TODO:Encrypted
-
         let (algo, is_compressed) = oi.is_compressed_ok()?;
 
-        // TODO: check TRANSITION
+        if oi.is_encrypted() && !opts.no_decryption {
+            let key = keys::object_key(&oi.bucket, &oi.name, &oi.user_defined, opts.sse_customer_key.as_ref()).await?;
+            let nonces = if oi.parts.is_empty() {
+                vec![key.part_nonce(1)]
+            } else {
+                oi.parts.iter().map(|part| key.nonce_of(part)).collect::<Result<_>>()?
+            };
 
-        if is_compressed {
             let actual_size = oi.get_actual_size()?;
-            let (off, length) = (0, oi.size);
-            let (_dec_off, dec_length) = (0, actual_size);
-            if let Some(_rs) = rs {
-                // TODO: range spec is not supported for compressed object
-                return Err(Error::other("The requested range is not satisfiable"));
-                // let (off, length) = rs.get_offset_length(actual_size)?;
-            }
-
-            let dec_reader = DecompressReader::new(reader, algo);
+            let (dec_off, dec_length) = match rs {
+                Some(rs) => rs.get_offset_length(actual_size)?,
+                None => (0, actual_size),
+            };
 
-            let actual_size = if actual_size > 0 {
-                actual_size as usize
+            let dec_reader = DecryptReader::new_multipart(reader, key.key(), nonces);
+            let stream: Box<dyn AsyncRead + Unpin + Send + Sync> = if is_compressed {
+                // The sealed parts are read from the start, the range is cut out of the decompressed data
+                Box::new(LimitReader::new(
+                    DecompressReader::new(dec_reader, algo).with_offset(dec_off),
+                    dec_length as usize,
+                ))
             } else {
+                Box::new(LimitReader::new(dec_reader.with_offset(dec_off), dec_length as usize))
+            };
+
+            let length = oi.size;
+            let mut oi = oi.clone();
+            oi.size = actual_size;
+
+            return Ok((GetObjectReader { stream, object_info: oi }, 0, length));
+        }
+
+        if is_compressed {
+            let actual_size = oi.get_actual_size()?;
+            if actual_size <= 0 {
                 return Err(Error::other(format!("invalid decompressed size {actual_size}")));
+            }
+            let (dec_off, dec_length) = match rs {
+                Some(rs) => rs.get_offset_length(actual_size)?,
+                None => (0, actual_size),
             };
 
-            let dec_reader = LimitReader::new(dec_reader, actual_size);
+            // Reading starts at the block before the range, what comes before the range is skipped
+            let (off, skip) = oi.compressed_offset(dec_off as i64);
+            let length = oi.size - off as i64;
+
+            let dec_reader = DecompressReader::new(reader, algo).with_offset(skip as usize);
+            let dec_reader = LimitReader::new(dec_reader, dec_length as usize);
 
             let mut oi = oi.clone();
-            oi.size = dec_length;
+            oi.size = actual_size;
 
             return Ok((
                 GetObjectReader {
@@ -214,11 +261,17 @@ impl HTTPRangeSpec {
             return None;
         }
 
+        let encrypted = oi.is_encrypted();
         let mut start = 0i64;
         let mut end = -1i64;
         for i in 0..oi.parts.len().min(part_number) {
+            let part_size = if encrypted {
+                oi.parts[i].actual_size
+            } else {
+                oi.parts[i].size as i64
+            };
             start = end + 1;
-            end = start + (oi.parts[i].size as i64) - 1
+            end = start + part_size - 1
         }
 
         Some(HTTPRangeSpec {
@@ -233,21 +286,21 @@ impl HTTPRangeSpec {
 
         let mut start = self.start;
         if self.is_suffix_length {
-            start = res_size + self.start;
-
-            if start < 0 {
-                start = 0;
-            }
+            // `start` holds the length of a suffix range, the last `len` bytes
+            start = res_size - len;
         }
         Ok((start as usize, len))
     }
     pub fn get_length(&self, res_size: i64) -> Result<i64> {
         if res_size < 0 {
-            return Err(Error::other("The requested range is not satisfiable"));
+            return Err(Error::InvalidRange(res_size));
         }
 
         if self.is_suffix_length {
             let specified_len = self.start; // 假设 h.start 是一个 i64 类型
+            if specified_len <= 0 {
+                return Err(Error::InvalidRange(res_size));
+            }
             let mut range_length = specified_len;
 
             if specified_len > res_size {
@@ -258,7 +311,7 @@ impl HTTPRangeSpec {
         }
 
         if self.start >= res_size {
-            return Err(Error::other("The requested range is not satisfiable"));
+            return Err(Error::InvalidRange(res_size));
         }
 
         if self.end > -1 {
@@ -302,6 +355,10 @@ pub struct ObjectOptions {
     pub skip_rebalancing: bool,
 
     pub data_movement: bool,
+    /// Read encrypted objects as stored, used when moving them between pools
+    pub no_decryption: bool,
+    /// Key of the request to read SSE-C objects with
+    pub sse_customer_key: Option<keys::CustomerKey>,
     pub src_pool_idx: usize,
     pub user_defined: HashMap<String, String>,
     pub preserve_etag: Option<String>,
@@ -315,6 +372,79 @@ pub struct ObjectOptions {
     pub lifecycle_audit_event: LcAuditEvent,
 
     pub eval_metadata: Option<HashMap<String, String>>,
+
+    /// Rewrites the metadata an append carries over from the object it appends to
+    pub append_metadata: Option<fn(&mut HashMap<String, String>)>,
+
+    /// `x-amz-checksum-*` values of the data written by algorithm, verified by the caller as the data streams in
+    pub checksums: Option<HashMap<String, String>>,
+
+    /// Nonce the data of an encrypted part is sealed with, recorded with the part
+    pub part_nonce: Option<[u8; 12]>,
+
+    /// `If-Match`/`If-None-Match` conditions of a write on the current version, checked under the object lock
+    pub write_preconditions: Option<WritePreconditions>,
+
+    /// Fires when the caller gave up on the request, e.g. the client went away, to stop waiting for locks
+    pub cancel: Option<CancellationToken>,
+
+    /// When the caller stops waiting for the request, lock waits end by then
+    pub deadline: Option<Instant>,
+}
+
+/// Conditions a write puts on the current version of the object it replaces
+#[derive(Debug, Default, Clone)]
+pub struct WritePreconditions {
+    /// ETags one of which the current version must have, `*` for any existing object
+    pub if_match: Option<String>,
+    /// ETags none of which the current version may have, `*` for the object not to exist at all
+    pub if_none_match: Option<String>,
+    /// Modification time the current version must have, for internal rewrites of a version read before
+    pub mod_time: Option<OffsetDateTime>,
+}
+
+impl WritePreconditions {
+    /// Checks the conditions against the current version of `bucket/object`, none when there is no such object
+    pub fn check(&self, bucket: &str, object: &str, current: Option<&ObjectInfo>) -> Result<()> {
+        let etag = current.map(|info| info.etag.as_deref().unwrap_or_default());
+        if let Some(if_match) = &self.if_match {
+            let Some(etag) = etag else {
+                return Err(Error::ObjectNotFound(bucket.to_owned(), object.to_owned()));
+            };
+            if !etag_matches(if_match, etag) {
+                return Err(Error::PreconditionFailed(bucket.to_owned(), object.to_owned()));
+            }
+        }
+        if let (Some(if_none_match), Some(etag)) = (&self.if_none_match, etag) {
+            if etag_matches(if_none_match, etag) {
+                return Err(Error::PreconditionFailed(bucket.to_owned(), object.to_owned()));
+            }
+        }
+        if self.mod_time.is_some() && current.and_then(|info| info.mod_time) != self.mod_time {
+            return Err(Error::PreconditionFailed(bucket.to_owned(), object.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `etag` is one of the comma separated ETags of an `If-Match` or `If-None-Match` condition, which
+/// may be quoted or weak, `*` matching any ETag
+pub fn etag_matches(condition: &str, etag: &str) -> bool {
+    let etag = etag.trim_matches('"');
+    condition
+        .split(',')
+        .map(str::trim)
+        .any(|v| v == "*" || v.trim_start_matches("W/").trim_matches('"') == etag)
+}
+
+impl ObjectOptions {
+    /// How long to wait for the locks of the request: `timeout`, cut short by the deadline
+    pub fn lock_timeout(&self, timeout: Duration) -> Duration {
+        match self.deadline {
+            Some(deadline) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => timeout,
+        }
+    }
 }
 
 // impl Default for ObjectOptions {
@@ -355,19 +485,36 @@ pub struct PartInfo {
     pub size: usize,
     pub etag: Option<String>,
     pub actual_size: i64,
+    /// Checksums of the part by algorithm, when the client sent them with it
+    pub checksums: Option<HashMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct CompletePart {
     pub part_num: usize,
     pub etag: Option<String>,
+    /// Checksums the client expects the part to have, by algorithm
+    pub checksums: HashMap<String, String>,
 }
 
 impl From<s3s::dto::CompletedPart> for CompletePart {
     fn from(value: s3s::dto::CompletedPart) -> Self {
+        let checksums = [
+            (ChecksumAlgorithm::CRC32, value.checksum_crc32),
+            (ChecksumAlgorithm::CRC32C, value.checksum_crc32c),
+            (ChecksumAlgorithm::CRC64NVME, value.checksum_crc64nvme),
+            (ChecksumAlgorithm::SHA1, value.checksum_sha1),
+            (ChecksumAlgorithm::SHA256, value.checksum_sha256),
+        ]
+        .into_iter()
+        .filter_map(|(algorithm, checksum)| checksum.map(|checksum| (algorithm.to_owned(), checksum)))
+        .collect();
+
         Self {
             part_num: value.part_number.unwrap_or_default() as usize,
-            etag: value.e_tag,
+            // Clients send the part ETags back as they were returned, in double quotes
+            etag: value.e_tag.map(|etag| trim_etag(&etag)),
+            checksums,
         }
     }
 }
@@ -447,6 +594,53 @@ impl Clone for ObjectInfo {
 }
 
 impl ObjectInfo {
+    /// Version id as S3 shows it, `null` for the null version of a versioned bucket
+    pub fn version_id_str(&self) -> Option<String> {
+        self.version_id.map(|v| {
+            if v.is_nil() {
+                NULL_VERSION_ID.to_string()
+            } else {
+                v.to_string()
+            }
+        })
+    }
+
+    /// Storage class as S3 shows it, a transitioned object reports the tier it was moved to
+    pub fn storage_class(&self) -> String {
+        if self.transitioned_object.status == TRANSITION_COMPLETE {
+            return self.transitioned_object.tier.clone();
+        }
+        self.user_defined
+            .get(AMZ_STORAGE_CLASS)
+            .cloned()
+            .unwrap_or_else(|| STANDARD.to_string())
+    }
+
+    /// Where the stored data of a compressed object has to be read from for an offset into the
+    /// decompressed data, and how many decompressed bytes come before the offset from there on.
+    /// Parts start blocks of their own, the index of a part finds the block inside it.
+    pub fn compressed_offset(&self, offset: i64) -> (usize, i64) {
+        let (mut comp_off, mut uncomp_off) = (0, 0);
+        for part in self.parts.iter() {
+            if offset < uncomp_off + part.actual_size {
+                let in_part = offset - uncomp_off;
+                let (block_comp, block_uncomp) = part
+                    .index
+                    .as_ref()
+                    .and_then(|b| {
+                        let mut index = Index::new();
+                        index.load(b).ok()?;
+                        index.find(in_part).ok()
+                    })
+                    .unwrap_or_default();
+                return (comp_off + block_comp as usize, in_part - block_uncomp);
+            }
+            comp_off += part.size;
+            uncomp_off += part.actual_size;
+        }
+        (comp_off, offset - uncomp_off)
+    }
+
     pub fn is_compressed(&self) -> bool {
         self.user_defined
             .contains_key(&format!("{RESERVED_METADATA_PREFIX_LOWER}compression"))
@@ -466,16 +660,29 @@ impl ObjectInfo {
         }
     }
 
+    /// Whether the object is stored encrypted with a sealed data key
+    pub fn is_encrypted(&self) -> bool {
+        keys::is_encrypted(&self.user_defined)
+    }
+
     pub fn is_multipart(&self) -> bool {
         self.etag.as_ref().is_some_and(|v| v.len() != 32)
     }
 
+    /// Checksums the object was uploaded with by algorithm, the composite checksums of a
+    /// multipart object end with `-<number of parts>`
+    pub fn checksums(&self) -> Option<HashMap<String, String>> {
+        self.user_defined
+            .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}checksums"))
+            .and_then(|v| serde_json::from_str(v).ok())
+    }
+
     pub fn get_actual_size(&self) -> std::io::Result<i64> {
         if self.actual_size > 0 {
             return Ok(self.actual_size);
         }
 
-        if self.is_compressed() {
+        if self.is_compressed() || self.is_encrypted() {
             if let Some(size_str) = self.user_defined.get(&format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size")) {
                 if !size_str.is_empty() {
                     // Todo: deal with error
@@ -494,8 +701,6 @@ impl ObjectInfo {
             return Ok(actual_size);
         }
 
-        // TODO: IsEncrypted
-
         Ok(self.size)
     }
 
@@ -523,7 +728,7 @@ impl ObjectInfo {
         let inlined = fi.inline_data();
 
         // TODO:expires
-        // TODO:ReplicationState
+        let (replication_status_internal, replication_status) = replication_status_from_metadata(&fi.metadata);
 
         let transitioned_object = TransitionedObject {
             name: fi.transitioned_objname.clone(),
@@ -543,6 +748,28 @@ impl ObjectInfo {
             v
         };
 
+        let restore_status = fi
+            .metadata
+            .get(X_AMZ_RESTORE.as_str())
+            .and_then(|v| parse_restore_obj_status(v));
+
+        // The checksums of the parts are kept in the metadata, an object written in one go has those of its data
+        let part_checksums: Option<HashMap<usize, HashMap<String, String>>> = fi
+            .metadata
+            .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}part-checksums"))
+            .and_then(|v| serde_json::from_str(v).ok());
+        let object_checksums: Option<HashMap<String, String>> = fi
+            .metadata
+            .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}checksums"))
+            .filter(|_| part_checksums.is_none() && fi.parts.len() == 1)
+            .and_then(|v| serde_json::from_str(v).ok());
+
+        // Parts of encrypted multipart uploads are sealed with nonces of their own
+        let part_nonces: Option<HashMap<usize, String>> = fi
+            .metadata
+            .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}{}", keys::SSE_PART_NONCES_META))
+            .and_then(|v| serde_json::from_str(v).ok());
+
         // Convert parts from rustfs_filemeta::ObjectPartInfo to store_api::ObjectPartInfo
         let parts = fi
             .parts
@@ -553,9 +780,20 @@ impl ObjectInfo {
                 size: part.size,
                 actual_size: part.actual_size,
                 mod_time: part.mod_time,
-                checksums: part.checksums.clone(),
+                checksums: part
+                    .checksums
+                    .clone()
+                    .or_else(|| part_checksums.as_ref().and_then(|c| c.get(&part.number).cloned()))
+                    .or_else(|| object_checksums.clone()),
                 number: part.number,
                 error: part.error.clone(),
+                nonce: part.nonce.clone().or_else(|| {
+                    part_nonces
+                        .as_ref()
+                        .and_then(|n| n.get(&part.number))
+                        .and_then(|n| base64_decode(n.as_bytes()).ok())
+                        .map(Bytes::from)
+                }),
             })
             .collect();
 
@@ -580,6 +818,10 @@ impl ObjectInfo {
             inlined,
             user_defined: metadata,
             transitioned_object,
+            restore_ongoing: restore_status.as_ref().is_some_and(|status| status.ongoing),
+            restore_expires: restore_status.and_then(|status| status.expiry),
+            replication_status_internal,
+            replication_status,
             ..Default::default()
         }
     }
@@ -589,7 +831,7 @@ impl ObjectInfo {
         bucket: &str,
         prefix: &str,
         delimiter: Option<String>,
-        after_version_id: Option<Uuid>,
+        after_version: Option<(String, Uuid)>,
     ) -> Vec<ObjectInfo> {
         let vcfg = get_versioning_config(bucket).await.ok();
         let mut objects = Vec::with_capacity(entries.entries().len());
@@ -625,14 +867,13 @@ impl ObjectInfo {
                     }
                 };
 
-                let versions = if let Some(vid) = after_version_id {
-                    if let Some(idx) = file_infos.find_version_index(vid) {
-                        &file_infos.versions[idx + 1..]
-                    } else {
-                        &file_infos.versions
-                    }
-                } else {
-                    &file_infos.versions
+                // listing resumes after the version marker of the marker object
+                let versions = match &after_version {
+                    Some((marker, vid)) if *marker == entry.name => match file_infos.find_version_index(*vid) {
+                        Some(idx) => &file_infos.versions[idx + 1..],
+                        None => &file_infos.versions,
+                    },
+                    _ => &file_infos.versions,
                 };
 
                 for fi in versions.iter() {
@@ -933,6 +1174,16 @@ pub trait ObjectIO: Send + Sync + 'static {
     ) -> Result<GetObjectReader>;
     // PutObject
     async fn put_object(&self, bucket: &str, object: &str, data: &mut PutObjReader, opts: &ObjectOptions) -> Result<ObjectInfo>;
+    /// Appends `data` to the current version of the object, whose size `offset` must be; an offset of 0 on a
+    /// missing object creates it
+    async fn append_object(
+        &self,
+        bucket: &str,
+        object: &str,
+        offset: i64,
+        data: &mut PutObjReader,
+        opts: &ObjectOptions,
+    ) -> Result<ObjectInfo>;
 }
 
 #[async_trait::async_trait]
@@ -1064,7 +1315,7 @@ pub trait StorageAPI: ObjectIO {
     async fn get_object_tags(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<String>;
     async fn add_partial(&self, bucket: &str, object: &str, version_id: &str) -> Result<()>;
     async fn transition_object(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()>;
-    async fn restore_transitioned_object(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()>;
+    async fn restore_transitioned_object(self: Arc<Self>, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<()>;
     async fn put_object_tags(&self, bucket: &str, object: &str, tags: &str, opts: &ObjectOptions) -> Result<ObjectInfo>;
     async fn delete_object_tags(&self, bucket: &str, object: &str, opts: &ObjectOptions) -> Result<ObjectInfo>;
 
@@ -1082,3 +1333,71 @@ pub trait StorageAPI: ObjectIO {
     async fn get_pool_and_set(&self, id: &str) -> Result<(Option<usize>, Option<usize>, Option<usize>)>;
     async fn check_abandoned_parts(&self, bucket: &str, object: &str, opts: &HealOpts) -> Result<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustfs_rio::{CompressReader, TryGetIndex};
+
+    #[tokio::test]
+    async fn test_get_object_reader_compressed_range() {
+        // Two parts of text of a few blocks each, so the ranges start inside blocks and parts
+        let data: Vec<u8> = (0..5 * 1024 * 1024 + 333)
+            .map(|i| (i % 251) as u8 ^ (i / 4096) as u8)
+            .collect();
+        let split = 3 * 1024 * 1024 + 17;
+
+        let mut stored = Vec::new();
+        let mut parts = Vec::new();
+        for (number, chunk) in [&data[..split], &data[split..]].into_iter().enumerate() {
+            let mut compress = CompressReader::new(WarpReader::new(Cursor::new(chunk.to_vec())), CompressionAlgorithm::Zstd);
+            let mut compressed = Vec::new();
+            compress.read_to_end(&mut compressed).await.unwrap();
+            parts.push(ObjectPartInfo {
+                number: number + 1,
+                size: compressed.len(),
+                actual_size: chunk.len() as i64,
+                index: compress.try_get_index().map(|index| index.clone().into_vec()),
+                ..Default::default()
+            });
+            stored.extend_from_slice(&compressed);
+        }
+
+        let oi = ObjectInfo {
+            size: stored.len() as i64,
+            parts,
+            user_defined: HashMap::from([
+                (format!("{RESERVED_METADATA_PREFIX_LOWER}compression"), "zstd".to_string()),
+                (format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size"), data.len().to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        for (start, end) in [
+            (0, 99),
+            (1024 * 1024 + 5, 2 * 1024 * 1024),
+            (split as i64 - 10, split as i64 + 10),
+            (5 * 1024 * 1024, -1),
+        ] {
+            let rs = HTTPRangeSpec {
+                is_suffix_length: false,
+                start,
+                end,
+            };
+            let (opts, h) = (ObjectOptions::default(), HeaderMap::new());
+            // The stored data is read from the offset the reader asks for
+            let (_, off, length) = GetObjectReader::new(Box::new(Cursor::new(Vec::new())), Some(rs.clone()), &oi, &opts, &h)
+                .await
+                .unwrap();
+            assert_eq!(off as i64 + length, oi.size);
+            // Ranges past the first block do not read it
+            assert_eq!(off > 0, start >= 1024 * 1024);
+            let stream = Box::new(Cursor::new(stored[off..].to_vec()));
+            let (mut reader, _, _) = GetObjectReader::new(stream, Some(rs.clone()), &oi, &opts, &h).await.unwrap();
+            let got = reader.read_all().await.unwrap();
+
+            let (dec_off, dec_length) = rs.get_offset_length(data.len() as i64).unwrap();
+            assert_eq!(got, &data[dec_off..dec_off + dec_length as usize], "range {start}-{end}");
+        }
+    }
+}
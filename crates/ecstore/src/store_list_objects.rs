@@ -21,6 +21,7 @@ use crate::disk::{DiskInfo, DiskStore};
 use crate::error::{
     Error, Result, StorageError, is_all_not_found, is_all_volume_not_found, is_err_bucket_not_found, to_object_err,
 };
+use crate::list_token::{decode_continuation_token, encode_continuation_token};
 use crate::set_disk::SetDisks;
 use crate::store::check_list_objs_args;
 use crate::store_api::{ListObjectVersionsInfo, ListObjectsInfo, ObjectInfo, ObjectOptions};
@@ -30,7 +31,7 @@ use futures::future::join_all;
 use rand::seq::SliceRandom;
 use rustfs_filemeta::{
     FileInfo, MetaCacheEntries, MetaCacheEntriesSorted, MetaCacheEntriesSortedResult, MetaCacheEntry, MetadataResolutionParams,
-    merge_file_meta_versions,
+    NULL_VERSION_ID, merge_file_meta_versions,
 };
 use rustfs_utils::path::{self, SLASH_SEPARATOR, base_dir_from_prefix};
 use std::collections::HashMap;
@@ -209,7 +210,7 @@ impl ListPathOptions {
 
 impl ECStore {
     #[allow(clippy::too_many_arguments)]
-    // @continuation_token marker
+    // @continuation_token sealed marker, see list_token
     // @start_after as marker when continuation_token empty
     // @delimiter default="/", empty when recursive
     // @max_keys limit
@@ -223,19 +224,20 @@ impl ECStore {
         _fetch_owner: bool,
         start_after: Option<String>,
     ) -> Result<ListObjectsV2Info> {
-        let marker = {
-            if continuation_token.is_none() {
-                start_after
-            } else {
-                continuation_token.clone()
-            }
+        let marker = match &continuation_token {
+            Some(token) => Some(decode_continuation_token(bucket, prefix, token)?),
+            None => start_after,
         };
 
         let loi = self.list_objects_generic(bucket, prefix, marker, delimiter, max_keys).await?;
+        let next_continuation_token = loi
+            .next_marker
+            .map(|marker| encode_continuation_token(bucket, prefix, &marker))
+            .transpose()?;
         Ok(ListObjectsV2Info {
             is_truncated: loi.is_truncated,
             continuation_token,
-            next_continuation_token: loi.next_marker,
+            next_continuation_token,
             objects: loi.objects,
             prefixes: loi.prefixes,
         })
@@ -379,10 +381,10 @@ impl ECStore {
             return Err(StorageError::NotImplemented);
         }
 
-        let version_marker = if let Some(marker) = version_marker {
-            Some(Uuid::parse_str(&marker)?)
-        } else {
-            None
+        let version_marker = match version_marker.as_deref() {
+            None => None,
+            Some(NULL_VERSION_ID) => Some(Uuid::nil()),
+            Some(marker) => Some(Uuid::parse_str(marker)?),
         };
 
         // if marker set, limit +1
@@ -413,7 +415,12 @@ impl ECStore {
         }
 
         if let Some(result) = list_result.entries.as_mut() {
-            result.forward_past(opts.marker);
+            // the versions of the marker object past the version marker are still to list
+            if version_marker.is_some() {
+                result.forward_to(opts.marker.clone());
+            } else {
+                result.forward_past(opts.marker.clone());
+            }
         }
 
         let mut get_objects = ObjectInfo::from_meta_cache_entries_sorted_versions(
@@ -421,7 +428,7 @@ impl ECStore {
             bucket,
             prefix,
             delimiter.clone(),
-            version_marker,
+            opts.marker.zip(version_marker),
         )
         .await;
 
@@ -438,7 +445,7 @@ impl ECStore {
             if is_truncated {
                 get_objects
                     .last()
-                    .map(|last| (Some(last.name.clone()), last.version_id.map(|v| v.to_string())))
+                    .map(|last| (Some(last.name.clone()), last.version_id_str()))
                     .unwrap_or_default()
             } else {
                 (None, None)
@@ -18,6 +18,8 @@ pub mod tier_config;
 pub mod tier_gen;
 pub mod tier_handlers;
 pub mod warm_backend;
+pub mod warm_backend_azure;
+pub mod warm_backend_gcs;
 pub mod warm_backend_minio;
 pub mod warm_backend_rustfs;
 pub mod warm_backend_s3;
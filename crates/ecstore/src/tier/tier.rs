@@ -35,6 +35,7 @@ use tokio::{select, sync::RwLock, time::interval};
 use tracing::{debug, error, info, warn};
 
 use crate::client::admin_handler_utils::AdminError;
+use crate::config::secrets;
 use crate::error::{Error, Result, StorageError};
 use crate::new_object_layer_fn;
 use crate::tier::{
@@ -114,15 +115,28 @@ impl TierConfigMgr {
     }
 
     pub fn unmarshal(data: &[u8]) -> std::result::Result<TierConfigMgr, std::io::Error> {
-        let cfg: TierConfigMgr = serde_json::from_slice(data)?;
+        let mut cfg: TierConfigMgr = serde_json::from_slice(data)?;
+        for tier in cfg.tiers.values_mut() {
+            *tier = tier.map_secret_key(secrets::open).map_err(std::io::Error::other)?;
+        }
         Ok(cfg)
     }
 
+    /// Encode the tiers with their remote secret keys sealed
     pub fn marshal(&self) -> std::result::Result<Bytes, std::io::Error> {
-        let data = serde_json::to_vec(&self)?;
-        let mut data = Bytes::from(data);
+        let mut sealed = TierConfigMgr {
+            driver_cache: HashMap::new(),
+            tiers: HashMap::new(),
+            last_refreshed_at: self.last_refreshed_at,
+        };
+        for (name, tier) in &self.tiers {
+            sealed
+                .tiers
+                .insert(name.clone(), tier.map_secret_key(secrets::seal).map_err(std::io::Error::other)?);
+        }
+        let data = serde_json::to_vec(&sealed)?;
 
-        Ok(data)
+        Ok(Bytes::from(data))
     }
 
     pub fn refreshed_at(&self) -> OffsetDateTime {
@@ -276,6 +290,23 @@ impl TierConfigMgr {
                     s3.secret_key = creds.secret_key;
                 }
             }
+            TierType::Azure => {
+                let mut azure = cfg.azure.as_mut().expect("err");
+                if creds.secret_key == "" {
+                    return Err(ERR_TIER_MISSING_CREDENTIALS.clone());
+                }
+                if creds.access_key != "" {
+                    azure.account_name = creds.access_key;
+                }
+                azure.account_key = creds.secret_key;
+            }
+            TierType::GCS => {
+                let mut gcs = cfg.gcs.as_mut().expect("err");
+                if creds.creds_json == "" {
+                    return Err(ERR_TIER_MISSING_CREDENTIALS.clone());
+                }
+                gcs.creds = creds.creds_json;
+            }
             TierType::RustFS => {
                 let mut rustfs = cfg.rustfs.as_mut().expect("err");
                 if creds.access_key == "" || creds.secret_key == "" {
@@ -36,7 +36,7 @@ pub struct TierCreds {
     pub aws_role_arn: String,
 
     //azsp: ServicePrincipalAuth,
-
-    //#[serde(rename = "credsJson")]
-    pub creds_json: Vec<u8>,
+    /// GCS service account key file, base64 encoded
+    #[serde(rename = "credsJson")]
+    pub creds_json: String,
 }
@@ -42,6 +42,12 @@ impl Display for TierType {
             TierType::S3 => {
                 write!(f, "S3")
             }
+            TierType::Azure => {
+                write!(f, "Azure")
+            }
+            TierType::GCS => {
+                write!(f, "GCS")
+            }
             TierType::RustFS => {
                 write!(f, "RustFS")
             }
@@ -59,6 +65,8 @@ impl TierType {
     pub fn new(sc_type: &str) -> Self {
         match sc_type {
             "S3" => TierType::S3,
+            "Azure" => TierType::Azure,
+            "GCS" => TierType::GCS,
             "RustFS" => TierType::RustFS,
             "MinIO" => TierType::MinIO,
             _ => TierType::Unsupported,
@@ -68,6 +76,8 @@ impl TierType {
     pub fn as_lowercase(&self) -> String {
         match self {
             TierType::S3 => "s3".to_string(),
+            TierType::Azure => "azure".to_string(),
+            TierType::GCS => "gcs".to_string(),
             TierType::RustFS => "rustfs".to_string(),
             TierType::MinIO => "minio".to_string(),
             _ => "unsupported".to_string(),
@@ -86,8 +96,10 @@ pub struct TierConfig {
     pub name: String,
     #[serde(rename = "s3", skip_serializing_if = "Option::is_none")]
     pub s3: Option<TierS3>,
-    //TODO: azure: Option<TierAzure>,
-    //TODO: gcs: Option<TierGCS>,
+    #[serde(rename = "azure", skip_serializing_if = "Option::is_none")]
+    pub azure: Option<TierAzure>,
+    #[serde(rename = "gcs", skip_serializing_if = "Option::is_none")]
+    pub gcs: Option<TierGCS>,
     #[serde(rename = "rustfs", skip_serializing_if = "Option::is_none")]
     pub rustfs: Option<TierRustFS>,
     #[serde(rename = "minio", skip_serializing_if = "Option::is_none")]
@@ -97,8 +109,8 @@ pub struct TierConfig {
 impl Clone for TierConfig {
     fn clone(&self) -> TierConfig {
         let mut s3 = None;
-        //az  TierAzure
-        //gcs TierGCS
+        let mut az = None;
+        let mut gcs = None;
         let mut r = None;
         let mut m = None;
         match self.tier_type {
@@ -107,6 +119,16 @@ impl Clone for TierConfig {
                 s3_.secret_key = "REDACTED".to_string();
                 s3 = Some(s3_);
             }
+            TierType::Azure => {
+                let mut az_ = self.azure.as_ref().expect("err").clone();
+                az_.account_key = "REDACTED".to_string();
+                az = Some(az_);
+            }
+            TierType::GCS => {
+                let mut gcs_ = self.gcs.as_ref().expect("err").clone();
+                gcs_.creds = "REDACTED".to_string();
+                gcs = Some(gcs_);
+            }
             TierType::RustFS => {
                 let mut r_ = self.rustfs.as_ref().expect("err").clone();
                 r_.secret_key = "REDACTED".to_string();
@@ -124,17 +146,59 @@ impl Clone for TierConfig {
             tier_type: self.tier_type.clone(),
             name: self.name.clone(),
             s3,
+            azure: az,
+            gcs,
             rustfs: r,
             minio: m,
         }
     }
 }
 
+impl TierConfig {
+    /// Copy of the tier with `f` applied to its remote secret key
+    ///
+    /// Unlike `clone`, which redacts the secret key, the copy keeps the key returned by `f`.
+    pub fn map_secret_key<E>(&self, f: impl Fn(&str) -> std::result::Result<String, E>) -> std::result::Result<TierConfig, E> {
+        let mut s3 = self.s3.clone();
+        let mut azure = self.azure.clone();
+        let mut gcs = self.gcs.clone();
+        let mut rustfs = self.rustfs.clone();
+        let mut minio = self.minio.clone();
+        if let Some(s3) = s3.as_mut() {
+            s3.secret_key = f(&s3.secret_key)?;
+        }
+        if let Some(azure) = azure.as_mut() {
+            azure.account_key = f(&azure.account_key)?;
+        }
+        if let Some(gcs) = gcs.as_mut() {
+            gcs.creds = f(&gcs.creds)?;
+        }
+        if let Some(rustfs) = rustfs.as_mut() {
+            rustfs.secret_key = f(&rustfs.secret_key)?;
+        }
+        if let Some(minio) = minio.as_mut() {
+            minio.secret_key = f(&minio.secret_key)?;
+        }
+        Ok(TierConfig {
+            version: self.version.clone(),
+            tier_type: self.tier_type.clone(),
+            name: self.name.clone(),
+            s3,
+            azure,
+            gcs,
+            rustfs,
+            minio,
+        })
+    }
+}
+
 #[allow(dead_code)]
 impl TierConfig {
     fn endpoint(&self) -> String {
         match self.tier_type {
             TierType::S3 => self.s3.as_ref().expect("err").endpoint.clone(),
+            TierType::Azure => self.azure.as_ref().expect("err").endpoint.clone(),
+            TierType::GCS => self.gcs.as_ref().expect("err").endpoint.clone(),
             TierType::RustFS => self.rustfs.as_ref().expect("err").endpoint.clone(),
             TierType::MinIO => self.minio.as_ref().expect("err").endpoint.clone(),
             _ => {
@@ -147,6 +211,8 @@ impl TierConfig {
     fn bucket(&self) -> String {
         match self.tier_type {
             TierType::S3 => self.s3.as_ref().expect("err").bucket.clone(),
+            TierType::Azure => self.azure.as_ref().expect("err").bucket.clone(),
+            TierType::GCS => self.gcs.as_ref().expect("err").bucket.clone(),
             TierType::RustFS => self.rustfs.as_ref().expect("err").bucket.clone(),
             TierType::MinIO => self.minio.as_ref().expect("err").bucket.clone(),
             _ => {
@@ -159,6 +225,8 @@ impl TierConfig {
     fn prefix(&self) -> String {
         match self.tier_type {
             TierType::S3 => self.s3.as_ref().expect("err").prefix.clone(),
+            TierType::Azure => self.azure.as_ref().expect("err").prefix.clone(),
+            TierType::GCS => self.gcs.as_ref().expect("err").prefix.clone(),
             TierType::RustFS => self.rustfs.as_ref().expect("err").prefix.clone(),
             TierType::MinIO => self.minio.as_ref().expect("err").prefix.clone(),
             _ => {
@@ -171,6 +239,8 @@ impl TierConfig {
     fn region(&self) -> String {
         match self.tier_type {
             TierType::S3 => self.s3.as_ref().expect("err").region.clone(),
+            TierType::Azure => self.azure.as_ref().expect("err").region.clone(),
+            TierType::GCS => self.gcs.as_ref().expect("err").region.clone(),
             TierType::RustFS => self.rustfs.as_ref().expect("err").region.clone(),
             TierType::MinIO => self.minio.as_ref().expect("err").region.clone(),
             _ => {
@@ -250,6 +320,36 @@ impl TierS3 {
     }
 }
 
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct TierAzure {
+    pub name: String,
+    pub endpoint: String,
+    #[serde(rename = "accountName")]
+    pub account_name: String,
+    #[serde(rename = "accountKey")]
+    pub account_key: String,
+    pub bucket: String,
+    pub prefix: String,
+    pub region: String,
+    #[serde(rename = "storageClass")]
+    pub storage_class: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct TierGCS {
+    pub name: String,
+    pub endpoint: String,
+    /// Service account key file, base64 encoded
+    pub creds: String,
+    pub bucket: String,
+    pub prefix: String,
+    pub region: String,
+    #[serde(rename = "storageClass")]
+    pub storage_class: String,
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 #[serde(default)]
 pub struct TierRustFS {
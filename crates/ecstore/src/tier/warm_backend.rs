@@ -27,6 +27,8 @@ use crate::tier::{
     tier::ERR_TIER_TYPE_UNSUPPORTED,
     tier_config::{TierConfig, TierType},
     tier_handlers::{ERR_TIER_BUCKET_NOT_FOUND, ERR_TIER_PERM_ERR},
+    warm_backend_azure::WarmBackendAzure,
+    warm_backend_gcs::WarmBackendGCS,
     warm_backend_minio::WarmBackendMinIO,
     warm_backend_rustfs::WarmBackendRustFS,
     warm_backend_s3::WarmBackendS3,
@@ -34,6 +36,7 @@ use crate::tier::{
 use bytes::Bytes;
 use http::StatusCode;
 use std::collections::HashMap;
+use tokio::io::AsyncReadExt;
 use tracing::{info, warn};
 
 pub type WarmBackendImpl = Box<dyn WarmBackend + Send + Sync + 'static>;
@@ -61,6 +64,18 @@ pub trait WarmBackend {
     async fn in_use(&self) -> Result<bool, std::io::Error>;
 }
 
+/// Next part of `r`, up to `size` bytes, or an empty part at its end
+pub(crate) async fn read_part(r: &mut ReaderImpl, size: usize) -> Result<Bytes, std::io::Error> {
+    match r {
+        ReaderImpl::Body(body) => Ok(body.split_to(size.min(body.len()))),
+        ReaderImpl::ObjectBody(body) => {
+            let mut buf = Vec::with_capacity(size);
+            (&mut body.stream).take(size as u64).read_to_end(&mut buf).await?;
+            Ok(Bytes::from(buf))
+        }
+    }
+}
+
 pub async fn check_warm_backend(w: Option<&WarmBackendImpl>) -> Result<(), AdminError> {
     let w = w.expect("err");
     let remote_version_id = w
@@ -104,6 +119,30 @@ pub async fn new_warm_backend(tier: &TierConfig, probe: bool) -> Result<WarmBack
             }
             d = Some(Box::new(dd.expect("err")));
         }
+        TierType::Azure => {
+            let dd = WarmBackendAzure::new(tier.azure.as_ref().expect("err"), &tier.name).await;
+            if let Err(err) = dd {
+                warn!("{}", err);
+                return Err(AdminError {
+                    code: "XRustFSAdminTierInvalidConfig".to_string(),
+                    message: format!("Unable to setup remote tier, check tier configuration: {}", err.to_string()),
+                    status_code: StatusCode::BAD_REQUEST,
+                });
+            }
+            d = Some(Box::new(dd.expect("err")));
+        }
+        TierType::GCS => {
+            let dd = WarmBackendGCS::new(tier.gcs.as_ref().expect("err"), &tier.name).await;
+            if let Err(err) = dd {
+                warn!("{}", err);
+                return Err(AdminError {
+                    code: "XRustFSAdminTierInvalidConfig".to_string(),
+                    message: format!("Unable to setup remote tier, check tier configuration: {}", err.to_string()),
+                    status_code: StatusCode::BAD_REQUEST,
+                });
+            }
+            d = Some(Box::new(dd.expect("err")));
+        }
         TierType::RustFS => {
             let dd = WarmBackendRustFS::new(tier.rustfs.as_ref().expect("err"), &tier.name).await;
             if let Err(err) = dd {
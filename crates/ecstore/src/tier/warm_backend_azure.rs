@@ -0,0 +1,368 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Azure Blob Storage tier, over the Blob REST API with Shared Key authorization
+
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use http::header::{
+    AUTHORIZATION, CONTENT_ENCODING, CONTENT_LANGUAGE, CONTENT_LENGTH, CONTENT_TYPE, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    IF_UNMODIFIED_SINCE, RANGE,
+};
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+use sha2::Sha256;
+use time::OffsetDateTime;
+use time::macros::format_description;
+use tokio::io::BufReader;
+use url::Url;
+
+use crate::client::transition_api::{ReadCloser, ReaderImpl};
+use crate::tier::{
+    tier_config::TierAzure,
+    warm_backend::{WarmBackend, WarmBackendGetOpts, read_part},
+};
+
+const AZURE_API_VERSION: &str = "2021-08-06";
+
+const X_MS_DATE: &str = "x-ms-date";
+const X_MS_VERSION: &str = "x-ms-version";
+const X_MS_RANGE: &str = "x-ms-range";
+const X_MS_BLOB_TYPE: &str = "x-ms-blob-type";
+const X_MS_ACCESS_TIER: &str = "x-ms-access-tier";
+const X_MS_META_PREFIX: &str = "x-ms-meta-";
+
+/// Smallest block of a blob uploaded in blocks; larger blobs use larger blocks to stay within the block limit
+const MIN_BLOCK_SIZE: usize = 16 << 20;
+const MAX_BLOCKS: usize = 50_000;
+
+pub struct WarmBackendAzure {
+    pub client: reqwest::Client,
+    pub endpoint: String,
+    pub account_name: String,
+    account_key: Vec<u8>,
+    pub bucket: String,
+    pub prefix: String,
+    pub storage_class: String,
+}
+
+impl WarmBackendAzure {
+    pub async fn new(conf: &TierAzure, _tier: &str) -> Result<Self, std::io::Error> {
+        if conf.account_name.is_empty() || conf.account_key.is_empty() {
+            return Err(std::io::Error::other("both the account name and key are required"));
+        }
+        if conf.bucket.is_empty() {
+            return Err(std::io::Error::other("no bucket name was provided"));
+        }
+        let account_key = STANDARD
+            .decode(conf.account_key.trim())
+            .map_err(|err| std::io::Error::other(format!("invalid account key: {err}")))?;
+
+        let endpoint = if conf.endpoint.is_empty() {
+            format!("https://{}.blob.core.windows.net", conf.account_name)
+        } else {
+            Url::parse(&conf.endpoint).map_err(|err| std::io::Error::other(err.to_string()))?;
+            conf.endpoint.trim_end_matches('/').to_string()
+        };
+
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .build()
+            .map_err(std::io::Error::other)?;
+        Ok(Self {
+            client,
+            endpoint,
+            account_name: conf.account_name.clone(),
+            account_key,
+            bucket: conf.bucket.clone(),
+            prefix: conf.prefix.trim_matches('/').to_string(),
+            storage_class: conf.storage_class.clone(),
+        })
+    }
+
+    pub fn get_dest(&self, object: &str) -> String {
+        if self.prefix.is_empty() {
+            return object.to_string();
+        }
+        format!("{}/{}", self.prefix, object)
+    }
+
+    fn url(&self, blob: &str, query: &[(&str, &str)]) -> Result<Url, std::io::Error> {
+        let mut url = format!("{}/{}", self.endpoint, self.bucket);
+        if !blob.is_empty() {
+            let path: Vec<_> = blob.split('/').map(urlencoding::encode).collect();
+            url.push('/');
+            url.push_str(&path.join("/"));
+        }
+        let mut url = Url::parse(&url).map_err(|err| std::io::Error::other(err.to_string()))?;
+        if !query.is_empty() {
+            let query: Vec<_> = query
+                .iter()
+                .map(|(name, value)| format!("{name}={}", urlencoding::encode(value)))
+                .collect();
+            url.set_query(Some(&query.join("&")));
+        }
+        Ok(url)
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        url: Url,
+        mut headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<reqwest::Response, std::io::Error> {
+        let date = OffsetDateTime::now_utc()
+            .format(format_description!(
+                "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
+            ))
+            .map_err(std::io::Error::other)?;
+        headers.insert(X_MS_DATE, header_value(&date)?);
+        headers.insert(X_MS_VERSION, HeaderValue::from_static(AZURE_API_VERSION));
+        if method == Method::PUT {
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+        }
+        let signature = shared_key_signature(&self.account_key, &string_to_sign(&method, &url, &headers, &self.account_name));
+        headers.insert(AUTHORIZATION, header_value(&format!("SharedKey {}:{signature}", self.account_name))?);
+
+        let resp = self
+            .client
+            .request(method, url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .await
+            .map_err(std::io::Error::other)?;
+        if !resp.status().is_success() {
+            return Err(response_error(resp).await);
+        }
+        Ok(resp)
+    }
+
+    /// Headers of a new blob, for its metadata and access tier
+    fn blob_headers(&self, meta: &HashMap<String, String>) -> Result<HeaderMap, std::io::Error> {
+        let mut headers = HeaderMap::new();
+        for (k, v) in meta {
+            // Metadata names must be C# identifiers and values plain ASCII.
+            let name = format!("{X_MS_META_PREFIX}{}", k.to_lowercase().replace('-', "_"));
+            let name = http::HeaderName::from_bytes(name.as_bytes()).map_err(std::io::Error::other)?;
+            headers.insert(name, header_value(&urlencoding::encode(v))?);
+        }
+        if !self.storage_class.is_empty() {
+            headers.insert(X_MS_ACCESS_TIER, header_value(&self.storage_class)?);
+        }
+        Ok(headers)
+    }
+}
+
+#[async_trait::async_trait]
+impl WarmBackend for WarmBackendAzure {
+    async fn put_with_meta(
+        &self,
+        object: &str,
+        mut r: ReaderImpl,
+        length: i64,
+        meta: HashMap<String, String>,
+    ) -> Result<String, std::io::Error> {
+        let dest = self.get_dest(object);
+        let block_size = MIN_BLOCK_SIZE.max((length.max(0) as usize).div_ceil(MAX_BLOCKS));
+
+        if length >= 0 && length as usize <= block_size {
+            let body = read_part(&mut r, block_size).await?;
+            let mut headers = self.blob_headers(&meta)?;
+            headers.insert(X_MS_BLOB_TYPE, HeaderValue::from_static("BlockBlob"));
+            self.send(Method::PUT, self.url(&dest, &[])?, headers, body).await?;
+            // Version ids of the remote are not UUIDs; transitioned blobs have unique names instead.
+            return Ok(String::new());
+        }
+
+        let mut block_list = String::from(r#"<?xml version="1.0" encoding="utf-8"?><BlockList>"#);
+        for i in 0.. {
+            let body = read_part(&mut r, block_size).await?;
+            if body.is_empty() {
+                break;
+            }
+            let block_id = STANDARD.encode(format!("{i:08}"));
+            let url = self.url(&dest, &[("comp", "block"), ("blockid", &block_id)])?;
+            self.send(Method::PUT, url, HeaderMap::new(), body).await?;
+            block_list.push_str(&format!("<Latest>{block_id}</Latest>"));
+        }
+        block_list.push_str("</BlockList>");
+
+        let mut headers = self.blob_headers(&meta)?;
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/xml"));
+        let url = self.url(&dest, &[("comp", "blocklist")])?;
+        self.send(Method::PUT, url, headers, Bytes::from(block_list)).await?;
+        Ok(String::new())
+    }
+
+    async fn put(&self, object: &str, r: ReaderImpl, length: i64) -> Result<String, std::io::Error> {
+        self.put_with_meta(object, r, length, HashMap::new()).await
+    }
+
+    async fn get(&self, object: &str, rv: &str, opts: WarmBackendGetOpts) -> Result<ReadCloser, std::io::Error> {
+        let query: Vec<_> = if rv.is_empty() { vec![] } else { vec![("versionid", rv)] };
+        let mut headers = HeaderMap::new();
+        if opts.start_offset >= 0 && opts.length > 0 {
+            let range = format!("bytes={}-{}", opts.start_offset, opts.start_offset + opts.length - 1);
+            headers.insert(X_MS_RANGE, header_value(&range)?);
+        }
+        let url = self.url(&self.get_dest(object), &query)?;
+        let resp = self.send(Method::GET, url, headers, Bytes::new()).await?;
+        let body = resp.bytes().await.map_err(std::io::Error::other)?;
+        Ok(BufReader::new(Cursor::new(body.to_vec())))
+    }
+
+    async fn remove(&self, object: &str, rv: &str) -> Result<(), std::io::Error> {
+        let query: Vec<_> = if rv.is_empty() { vec![] } else { vec![("versionid", rv)] };
+        let url = self.url(&self.get_dest(object), &query)?;
+        match self.send(Method::DELETE, url, HeaderMap::new(), Bytes::new()).await {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    async fn in_use(&self) -> Result<bool, std::io::Error> {
+        let url = self.url(
+            "",
+            &[
+                ("restype", "container"),
+                ("comp", "list"),
+                ("prefix", &self.prefix),
+                ("delimiter", "/"),
+                ("maxresults", "1"),
+            ],
+        )?;
+        let resp = self.send(Method::GET, url, HeaderMap::new(), Bytes::new()).await?;
+        let body = resp.text().await.map_err(std::io::Error::other)?;
+        Ok(body.contains("<Blob>") || body.contains("<BlobPrefix>"))
+    }
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, std::io::Error> {
+    HeaderValue::from_str(value).map_err(std::io::Error::other)
+}
+
+/// String to sign of a request for Shared Key authorization
+fn string_to_sign(method: &Method, url: &Url, headers: &HeaderMap, account_name: &str) -> String {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let content_length = match header(CONTENT_LENGTH) {
+        "0" => "",
+        v => v,
+    };
+
+    let mut ans = format!(
+        "{method}\n{}\n{}\n{content_length}\n\n{}\n\n{}\n{}\n{}\n{}\n{}\n",
+        header(CONTENT_ENCODING),
+        header(CONTENT_LANGUAGE),
+        header(CONTENT_TYPE),
+        header(IF_MODIFIED_SINCE),
+        header(IF_MATCH),
+        header(IF_NONE_MATCH),
+        header(IF_UNMODIFIED_SINCE),
+        header(RANGE),
+    );
+
+    let mut ms_headers: Vec<_> = headers
+        .keys()
+        .filter(|name| name.as_str().starts_with("x-ms-"))
+        .map(|name| {
+            let values: Vec<_> = headers
+                .get_all(name)
+                .iter()
+                .map(|v| String::from_utf8_lossy(v.as_bytes()).trim().to_string())
+                .collect();
+            (name.as_str(), values.join(","))
+        })
+        .collect();
+    ms_headers.sort();
+    for (name, value) in ms_headers {
+        ans.push_str(&format!("{name}:{value}\n"));
+    }
+
+    ans.push_str(&format!("/{account_name}{}", url.path()));
+    let mut query: Vec<(String, String)> = url.query_pairs().map(|(k, v)| (k.to_lowercase(), v.into_owned())).collect();
+    query.sort();
+    let mut i = 0;
+    while i < query.len() {
+        let name = &query[i].0;
+        let values: Vec<_> = query[i..]
+            .iter()
+            .take_while(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+            .collect();
+        ans.push_str(&format!("\n{name}:{}", values.join(",")));
+        i += values.len();
+    }
+    ans
+}
+
+fn shared_key_signature(key: &[u8], string_to_sign: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(string_to_sign.as_bytes());
+    STANDARD.encode(mac.finalize().into_bytes())
+}
+
+async fn response_error(resp: reqwest::Response) -> std::io::Error {
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    let code = body
+        .split_once("<Code>")
+        .and_then(|(_, rest)| rest.split_once("</Code>"))
+        .map(|(code, _)| code.to_string())
+        .unwrap_or_default();
+    let kind = if status == StatusCode::NOT_FOUND {
+        std::io::ErrorKind::NotFound
+    } else {
+        std::io::ErrorKind::Other
+    };
+    std::io::Error::new(kind, format!("azure: {status}: {code}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_to_sign() {
+        let url = Url::parse("https://acct.blob.core.windows.net/bucket/a%20b?comp=block&blockid=MDA%3D").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(5));
+        headers.insert(X_MS_VERSION, HeaderValue::from_static(AZURE_API_VERSION));
+        headers.insert(X_MS_DATE, HeaderValue::from_static("Mon, 01 Jan 2025 00:00:00 GMT"));
+        assert_eq!(
+            string_to_sign(&Method::PUT, &url, &headers, "acct"),
+            "PUT\n\n\n5\n\n\n\n\n\n\n\n\nx-ms-date:Mon, 01 Jan 2025 00:00:00 GMT\nx-ms-version:2021-08-06\n\
+             /acct/bucket/a%20b\nblockid:MDA=\ncomp:block"
+        );
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(0));
+        let url = Url::parse("https://acct.blob.core.windows.net/bucket").unwrap();
+        assert!(string_to_sign(&Method::DELETE, &url, &headers, "acct").starts_with("DELETE\n\n\n\n"));
+    }
+
+    #[test]
+    fn test_shared_key_signature() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            shared_key_signature(b"Jefe", "what do ya want for nothing?"),
+            "W9zBRr9gdU5qBCQmCJV1x1oAPwidJzmDnexYuWTsOEM="
+        );
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Google Cloud Storage tier, over the JSON API with a service account
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use http::StatusCode;
+use http::header::{AUTHORIZATION, CONTENT_RANGE, LOCATION, RANGE};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tokio::io::BufReader;
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::client::transition_api::{ReadCloser, ReaderImpl};
+use crate::tier::{
+    tier_config::TierGCS,
+    warm_backend::{WarmBackend, WarmBackendGetOpts, read_part},
+};
+
+const GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+const GCS_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const GCS_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+const JWT_BEARER_GRANT: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+const X_UPLOAD_CONTENT_LENGTH: &str = "x-upload-content-length";
+
+/// Chunk of a resumable upload, a multiple of 256 KiB as the API requires
+const CHUNK_SIZE: usize = 16 << 20;
+/// Access tokens are renewed this long before they expire
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Service account key file, as downloaded from the console
+#[derive(Deserialize, Debug)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    #[serde(default)]
+    token_uri: String,
+}
+
+impl ServiceAccountKey {
+    /// Key file of the tier credentials, which are the file base64 encoded
+    fn parse(creds: &str) -> Result<Self, std::io::Error> {
+        let json = STANDARD
+            .decode(creds.trim())
+            .map_err(|err| std::io::Error::other(format!("invalid credentials: {err}")))?;
+        let mut key: ServiceAccountKey =
+            serde_json::from_slice(&json).map_err(|err| std::io::Error::other(format!("invalid credentials: {err}")))?;
+        if key.token_uri.is_empty() {
+            key.token_uri = GCS_TOKEN_URI.to_string();
+        }
+        Ok(key)
+    }
+
+    fn claims(&self, now: i64) -> Claims<'_> {
+        Claims {
+            iss: &self.client_email,
+            scope: GCS_SCOPE,
+            aud: &self.token_uri,
+            iat: now,
+            exp: now + 3600,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+struct AccessToken {
+    token: String,
+    expires_at: Instant,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ListObjectsResponse {
+    items: Vec<serde_json::Value>,
+    prefixes: Vec<String>,
+}
+
+pub struct WarmBackendGCS {
+    pub client: reqwest::Client,
+    pub endpoint: String,
+    key: ServiceAccountKey,
+    encoding_key: EncodingKey,
+    token: Mutex<Option<AccessToken>>,
+    pub bucket: String,
+    pub prefix: String,
+    pub storage_class: String,
+}
+
+impl WarmBackendGCS {
+    pub async fn new(conf: &TierGCS, _tier: &str) -> Result<Self, std::io::Error> {
+        if conf.creds.is_empty() {
+            return Err(std::io::Error::other("no credentials were provided"));
+        }
+        if conf.bucket.is_empty() {
+            return Err(std::io::Error::other("no bucket name was provided"));
+        }
+        let key = ServiceAccountKey::parse(&conf.creds)?;
+        let encoding_key = EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+            .map_err(|err| std::io::Error::other(format!("invalid private key: {err}")))?;
+
+        let endpoint = if conf.endpoint.is_empty() {
+            GCS_ENDPOINT.to_string()
+        } else {
+            Url::parse(&conf.endpoint).map_err(|err| std::io::Error::other(err.to_string()))?;
+            conf.endpoint.trim_end_matches('/').to_string()
+        };
+
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .build()
+            .map_err(std::io::Error::other)?;
+        Ok(Self {
+            client,
+            endpoint,
+            key,
+            encoding_key,
+            token: Mutex::new(None),
+            bucket: conf.bucket.clone(),
+            prefix: conf.prefix.trim_matches('/').to_string(),
+            storage_class: conf.storage_class.clone(),
+        })
+    }
+
+    pub fn get_dest(&self, object: &str) -> String {
+        if self.prefix.is_empty() {
+            return object.to_string();
+        }
+        format!("{}/{}", self.prefix, object)
+    }
+
+    fn object_url(&self, object: &str, query: &str) -> String {
+        let mut url = format!(
+            "{}/storage/v1/b/{}/o/{}",
+            self.endpoint,
+            urlencoding::encode(&self.bucket),
+            urlencoding::encode(&self.get_dest(object))
+        );
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(query);
+        }
+        url
+    }
+
+    /// Access token of the service account, exchanged for a signed JWT when the cached one is about to expire
+    async fn access_token(&self) -> Result<String, std::io::Error> {
+        let mut token = self.token.lock().await;
+        if let Some(token) = token.as_ref() {
+            if token.expires_at > Instant::now() + TOKEN_EXPIRY_MARGIN {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let claims = self.key.claims(OffsetDateTime::now_utc().unix_timestamp());
+        let assertion =
+            jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.encoding_key).map_err(std::io::Error::other)?;
+        let resp = self
+            .client
+            .post(&self.key.token_uri)
+            .form(&[("grant_type", JWT_BEARER_GRANT), ("assertion", &assertion)])
+            .send()
+            .await
+            .map_err(std::io::Error::other)?;
+        if !resp.status().is_success() {
+            return Err(response_error(resp).await);
+        }
+        let resp: TokenResponse = resp.json().await.map_err(std::io::Error::other)?;
+        *token = Some(AccessToken {
+            token: resp.access_token.clone(),
+            expires_at: Instant::now() + Duration::from_secs(resp.expires_in),
+        });
+        Ok(resp.access_token)
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<reqwest::Response, std::io::Error> {
+        let token = self.access_token().await?;
+        let resp = req
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .send()
+            .await
+            .map_err(std::io::Error::other)?;
+        if !resp.status().is_success() && resp.status() != StatusCode::PERMANENT_REDIRECT {
+            return Err(response_error(resp).await);
+        }
+        Ok(resp)
+    }
+}
+
+#[async_trait::async_trait]
+impl WarmBackend for WarmBackendGCS {
+    async fn put_with_meta(
+        &self,
+        object: &str,
+        mut r: ReaderImpl,
+        length: i64,
+        meta: HashMap<String, String>,
+    ) -> Result<String, std::io::Error> {
+        let mut resource = serde_json::json!({ "name": self.get_dest(object), "metadata": meta });
+        if !self.storage_class.is_empty() {
+            resource["storageClass"] = self.storage_class.clone().into();
+        }
+        let url = format!(
+            "{}/upload/storage/v1/b/{}/o?uploadType=resumable",
+            self.endpoint,
+            urlencoding::encode(&self.bucket)
+        );
+        let mut req = self.client.post(url).json(&resource);
+        if length >= 0 {
+            req = req.header(X_UPLOAD_CONTENT_LENGTH, length);
+        }
+        let resp = self.send(req).await?;
+        let session = resp
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| std::io::Error::other("gcs: no resumable upload session"))?
+            .to_string();
+
+        let mut offset = 0;
+        loop {
+            let body = read_part(&mut r, CHUNK_SIZE).await?;
+            let last = body.len() < CHUNK_SIZE || (length >= 0 && offset + body.len() as i64 >= length);
+            let range = content_range(offset, body.len(), last);
+            offset += body.len() as i64;
+            let resp = self
+                .send(self.client.put(&session).header(CONTENT_RANGE, range).body(body))
+                .await?;
+            if last {
+                if resp.status() == StatusCode::PERMANENT_REDIRECT {
+                    return Err(std::io::Error::other("gcs: upload is incomplete"));
+                }
+                break;
+            }
+        }
+        // Generations of the remote are not UUIDs; transitioned objects have unique names instead.
+        Ok(String::new())
+    }
+
+    async fn put(&self, object: &str, r: ReaderImpl, length: i64) -> Result<String, std::io::Error> {
+        self.put_with_meta(object, r, length, HashMap::new()).await
+    }
+
+    async fn get(&self, object: &str, rv: &str, opts: WarmBackendGetOpts) -> Result<ReadCloser, std::io::Error> {
+        let mut query = "alt=media".to_string();
+        if !rv.is_empty() {
+            query.push_str(&format!("&generation={}", urlencoding::encode(rv)));
+        }
+        let mut req = self.client.get(self.object_url(object, &query));
+        if opts.start_offset >= 0 && opts.length > 0 {
+            req = req.header(RANGE, format!("bytes={}-{}", opts.start_offset, opts.start_offset + opts.length - 1));
+        }
+        let resp = self.send(req).await?;
+        let body = resp.bytes().await.map_err(std::io::Error::other)?;
+        Ok(BufReader::new(Cursor::new(body.to_vec())))
+    }
+
+    async fn remove(&self, object: &str, rv: &str) -> Result<(), std::io::Error> {
+        let query = if rv.is_empty() {
+            String::new()
+        } else {
+            format!("generation={}", urlencoding::encode(rv))
+        };
+        match self.send(self.client.delete(self.object_url(object, &query))).await {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    async fn in_use(&self) -> Result<bool, std::io::Error> {
+        let url = format!(
+            "{}/storage/v1/b/{}/o?prefix={}&delimiter=%2F&maxResults=1",
+            self.endpoint,
+            urlencoding::encode(&self.bucket),
+            urlencoding::encode(&self.prefix)
+        );
+        let resp = self.send(self.client.get(url)).await?;
+        let list: ListObjectsResponse = resp.json().await.map_err(std::io::Error::other)?;
+        Ok(!list.items.is_empty() || !list.prefixes.is_empty())
+    }
+}
+
+/// `Content-Range` of a chunk of a resumable upload, with the total size once the chunk is the last one
+fn content_range(offset: i64, len: usize, last: bool) -> String {
+    let total = if last {
+        (offset + len as i64).to_string()
+    } else {
+        "*".to_string()
+    };
+    if len == 0 {
+        return format!("bytes */{total}");
+    }
+    format!("bytes {offset}-{}/{total}", offset + len as i64 - 1)
+}
+
+async fn response_error(resp: reqwest::Response) -> std::io::Error {
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    let message = body.pointer("/error/message").and_then(|v| v.as_str()).unwrap_or_default();
+    let kind = if status == StatusCode::NOT_FOUND {
+        std::io::ErrorKind::NotFound
+    } else {
+        std::io::ErrorKind::Other
+    };
+    std::io::Error::new(kind, format!("gcs: {status}: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_account_key() {
+        let creds = STANDARD
+            .encode(r#"{"type":"service_account","client_email":"tier@project.iam.gserviceaccount.com","private_key":"pem"}"#);
+        let key = ServiceAccountKey::parse(&creds).unwrap();
+        assert_eq!(key.token_uri, GCS_TOKEN_URI);
+        assert_eq!(
+            key.claims(1000),
+            Claims {
+                iss: "tier@project.iam.gserviceaccount.com",
+                scope: GCS_SCOPE,
+                aud: GCS_TOKEN_URI,
+                iat: 1000,
+                exp: 4600,
+            }
+        );
+        assert!(ServiceAccountKey::parse("not base64").is_err());
+    }
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(content_range(0, 10, true), "bytes 0-9/10");
+        assert_eq!(content_range(0, CHUNK_SIZE, false), format!("bytes 0-{}/*", CHUNK_SIZE - 1));
+        assert_eq!(content_range(10, 0, true), "bytes */10");
+    }
+}
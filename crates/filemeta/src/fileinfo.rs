@@ -47,6 +47,9 @@ pub struct ObjectPartInfo {
     // Checksums holds checksums of the part
     pub checksums: Option<HashMap<String, String>>,
     pub error: Option<String>,
+    // Nonce the part of an encrypted multipart upload was sealed with
+    #[serde(default)]
+    pub nonce: Option<Bytes>,
 }
 
 impl ObjectPartInfo {
@@ -142,6 +145,22 @@ impl ErasureInfo {
         }
     }
 
+    /// Record the bitrot checksum of a part, replacing any previous one
+    pub fn add_checksum_info(&mut self, info: ChecksumInfo) {
+        match self.checksums.iter_mut().find(|sum| sum.part_number == info.part_number) {
+            Some(sum) => *sum = info,
+            None => self.checksums.push(info),
+        }
+    }
+
+    /// Bitrot algorithm of the version, every part of a version is written with the same algorithm
+    pub fn bitrot_algorithm(&self) -> HashAlgorithm {
+        self.checksums
+            .first()
+            .map(|sum| sum.algorithm.clone())
+            .unwrap_or(HashAlgorithm::HighwayHash256)
+    }
+
     /// Calculate the size of each shard.
     pub fn shard_size(&self) -> usize {
         calc_shard_size(self.block_size, self.data_blocks)
@@ -302,6 +321,7 @@ impl FileInfo {
             index,
             checksums: None,
             error: None,
+            nonce: None,
         };
 
         for p in self.parts.iter_mut() {
@@ -491,7 +511,8 @@ pub struct FileInfoVersions {
 
 impl FileInfoVersions {
     pub fn find_version_index(&self, vid: Uuid) -> Option<usize> {
-        self.versions.iter().position(|v| v.version_id == Some(vid))
+        // the null version has no version id, `vid` is nil for it
+        self.versions.iter().position(|v| v.version_id.unwrap_or_default() == vid)
     }
 
     /// Calculate the total size of all versions for this object
@@ -13,14 +13,16 @@
 // limitations under the License.
 
 use crate::error::{Error, Result};
-use crate::fileinfo::{ErasureAlgo, ErasureInfo, FileInfo, FileInfoVersions, ObjectPartInfo, RawFileInfo};
+use crate::fileinfo::{ChecksumInfo, ErasureAlgo, ErasureInfo, FileInfo, FileInfoVersions, ObjectPartInfo, RawFileInfo};
 use crate::filemeta_inline::InlineData;
 use crate::headers::{
-    self, AMZ_META_UNENCRYPTED_CONTENT_LENGTH, AMZ_META_UNENCRYPTED_CONTENT_MD5, AMZ_STORAGE_CLASS, RESERVED_METADATA_PREFIX,
-    RESERVED_METADATA_PREFIX_LOWER, VERSION_PURGE_STATUS_KEY,
+    self, AMZ_META_UNENCRYPTED_CONTENT_LENGTH, AMZ_META_UNENCRYPTED_CONTENT_MD5, AMZ_RESTORE_EXPIRY_DAYS,
+    AMZ_RESTORE_REQUEST_DATE, AMZ_STORAGE_CLASS, RESERVED_METADATA_PREFIX, RESERVED_METADATA_PREFIX_LOWER,
+    VERSION_PURGE_STATUS_KEY,
 };
 use byteorder::ByteOrder;
 use bytes::Bytes;
+use rustfs_utils::HashAlgorithm;
 use s3s::header::X_AMZ_RESTORE;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
@@ -62,9 +64,6 @@ pub const TRANSITIONED_OBJECTNAME: &str = "transitioned-object";
 pub const TRANSITIONED_VERSION_ID: &str = "transitioned-versionID";
 pub const TRANSITION_TIER: &str = "transition-tier";
 
-const X_AMZ_RESTORE_EXPIRY_DAYS: &str = "X-Amz-Restore-Expiry-Days";
-const X_AMZ_RESTORE_REQUEST_DATE: &str = "X-Amz-Restore-Request-Date";
-
 // type ScanHeaderVersionFn = Box<dyn Fn(usize, &[u8], &[u8]) -> Result<()>>;
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -496,7 +495,9 @@ impl FileMeta {
     }
 
     pub fn add_version_filemata(&mut self, ver: FileMetaVersion) -> Result<()> {
-        let mod_time = ver.get_mod_time().unwrap().nanosecond();
+        let Some(mod_time) = ver.get_mod_time() else {
+            return Err(Error::other("attempted to add invalid version"));
+        };
         if !ver.valid() {
             return Err(Error::other("attempted to add invalid version"));
         }
@@ -518,7 +519,7 @@ impl FileMeta {
 
         let len = self.versions.len();
         for (i, existing) in self.versions.iter().enumerate() {
-            if existing.header.mod_time.unwrap().nanosecond() <= mod_time {
+            if existing.header.mod_time.is_none_or(|t| t <= mod_time) {
                 let vers = self.versions[i..len - 1].to_vec();
                 self.versions[i + 1..].clone_from_slice(vers.as_slice());
                 self.versions[i] = FileMetaShallowVersion {
@@ -532,12 +533,18 @@ impl FileMeta {
     }
 
     // delete_version deletes version, returns data_dir
+    //
+    // A deleted `fi` adds a delete marker, replacing the version of the same id: with a null version
+    // id, the null version of a bucket whose versioning is suspended.
     pub fn delete_version(&mut self, fi: &FileInfo) -> Result<Option<Uuid>> {
+        // the null version is kept without a version id
+        let vid = fi.version_id.filter(|v| !v.is_nil());
+
         let mut ventry = FileMetaVersion::default();
         if fi.deleted {
             ventry.version_type = VersionType::Delete;
             ventry.delete_marker = Some(MetaDeleteMarker {
-                version_id: fi.version_id,
+                version_id: vid,
                 mod_time: fi.mod_time,
                 ..Default::default()
             });
@@ -547,62 +554,56 @@ impl FileMeta {
             }
         }
 
-        for (i, ver) in self.versions.iter().enumerate() {
-            if ver.header.version_id != fi.version_id {
-                continue;
+        let Some(i) = self.versions.iter().position(|ver| ver.header.version_id == vid) else {
+            if fi.deleted {
+                self.add_version_filemata(ventry)?;
+                return Ok(None);
             }
+            return Err(Error::FileVersionNotFound);
+        };
 
-            match ver.header.version_type {
-                VersionType::Invalid | VersionType::Legacy => return Err(Error::other("invalid file meta version")),
-                VersionType::Delete => return Ok(None),
-                VersionType::Object => {
-                    let v = self.get_idx(i)?;
-
-                    self.versions.remove(i);
-
-                    let a = v.object.map(|v| v.data_dir).unwrap_or_default();
-                    return Ok(a);
+        match self.versions[i].header.version_type {
+            VersionType::Invalid | VersionType::Legacy => return Err(Error::other("invalid file meta version")),
+            VersionType::Delete => {
+                self.versions.remove(i);
+                if fi.deleted {
+                    self.add_version_filemata(ventry)?;
                 }
+                return Ok(None);
             }
+            VersionType::Object => {}
         }
 
-        for (i, version) in self.versions.iter().enumerate() {
-            if version.header.version_type != VersionType::Object || version.header.version_id != fi.version_id {
-                continue;
-            }
-
-            let mut ver = self.get_idx(i)?;
-
-            if fi.expire_restored {
-                ver.object.as_mut().unwrap().remove_restore_hdrs();
-                let _ = self.set_idx(i, ver.clone());
-            } else if fi.transition_status == TRANSITION_COMPLETE {
-                ver.object.as_mut().unwrap().set_transition(fi);
-                ver.object.as_mut().unwrap().reset_inline_data();
-                self.set_idx(i, ver.clone())?;
-            } else {
-                let vers = self.versions[i + 1..].to_vec();
-                self.versions.extend(vers.iter().cloned());
-                let (free_version, to_free) = ver.object.as_ref().unwrap().init_free_version(fi);
-                if to_free {
-                    self.add_version_filemata(free_version)?;
-                }
-            }
+        let mut ver = self.get_idx(i)?;
+        let Some(obj) = ver.object.as_mut() else {
+            return Err(Error::other("invalid file meta version"));
+        };
 
-            if fi.deleted {
-                self.add_version_filemata(ventry)?;
-            }
-            if self.shared_data_dir_count(ver.object.as_ref().unwrap().version_id, ver.object.as_ref().unwrap().data_dir) > 0 {
-                return Ok(None);
+        if fi.expire_restored {
+            obj.remove_restore_hdrs();
+            self.set_idx(i, ver.clone())?;
+        } else if fi.transition_status == TRANSITION_COMPLETE {
+            obj.set_transition(fi);
+            obj.reset_inline_data();
+            self.set_idx(i, ver.clone())?;
+        } else {
+            self.versions.remove(i);
+            let (free_version, to_free) = obj.init_free_version(fi);
+            if to_free {
+                self.add_version_filemata(free_version)?;
             }
-            return Ok(ver.object.as_ref().unwrap().data_dir);
         }
 
         if fi.deleted {
             self.add_version_filemata(ventry)?;
         }
-
-        Err(Error::FileVersionNotFound)
+        let Some(obj) = ver.object.as_ref() else {
+            return Ok(None);
+        };
+        if self.shared_data_dir_count(obj.version_id, obj.data_dir) > 0 {
+            return Ok(None);
+        }
+        Ok(obj.data_dir)
     }
 
     pub fn into_fileinfo(
@@ -961,7 +962,8 @@ impl FileMetaVersion {
 
     pub fn get_version_id(&self) -> Option<Uuid> {
         match self.version_type {
-            VersionType::Object | VersionType::Delete => self.object.as_ref().map(|v| v.version_id).unwrap_or_default(),
+            VersionType::Object => self.object.as_ref().map(|v| v.version_id).unwrap_or_default(),
+            VersionType::Delete => self.delete_marker.as_ref().map(|v| v.version_id).unwrap_or_default(),
             _ => None,
         }
     }
@@ -1473,6 +1475,7 @@ impl MetaObject {
 
         // todo: ReplicationState,Delete
 
+        let bitrot_algo = self.bitrot_checksum_algo.to_hash_algorithm();
         let erasure = ErasureInfo {
             algorithm: self.erasure_algorithm.to_string(),
             data_blocks: self.erasure_m,
@@ -1480,7 +1483,27 @@ impl MetaObject {
             block_size: self.erasure_block_size,
             index: self.erasure_index,
             distribution: self.erasure_dist.iter().map(|&v| v as usize).collect(),
-            ..Default::default()
+            checksums: self
+                .part_numbers
+                .iter()
+                .map(|&part_number| ChecksumInfo {
+                    part_number,
+                    algorithm: bitrot_algo.clone(),
+                    hash: Bytes::new(),
+                })
+                .collect(),
+        };
+
+        let sys = |name: &str| {
+            self.meta_sys
+                .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}{name}"))
+                .map(|v| v.as_slice())
+                .unwrap_or_default()
+        };
+        // Older versions kept the remote version id as raw bytes
+        let transition_version_id = match sys(TRANSITIONED_VERSION_ID) {
+            v if v.len() == 16 => Uuid::from_slice(v).ok(),
+            v => Uuid::parse_str(&String::from_utf8_lossy(v)).ok(),
         };
 
         FileInfo {
@@ -1493,6 +1516,10 @@ impl MetaObject {
             volume: volume.to_string(),
             parts,
             metadata,
+            transition_status: String::from_utf8_lossy(sys(TRANSITION_STATUS)).into_owned(),
+            transitioned_objname: String::from_utf8_lossy(sys(TRANSITIONED_OBJECTNAME)).into_owned(),
+            transition_version_id,
+            transition_tier: String::from_utf8_lossy(sys(TRANSITION_TIER)).into_owned(),
             ..Default::default()
         }
     }
@@ -1508,7 +1535,9 @@ impl MetaObject {
         );
         self.meta_sys.insert(
             format!("{RESERVED_METADATA_PREFIX_LOWER}{TRANSITIONED_VERSION_ID}"),
-            fi.transition_version_id.unwrap().as_bytes().to_vec(),
+            fi.transition_version_id
+                .map(|vid| vid.to_string().into_bytes())
+                .unwrap_or_default(),
         );
         self.meta_sys.insert(
             format!("{RESERVED_METADATA_PREFIX_LOWER}{TRANSITION_TIER}"),
@@ -1518,8 +1547,8 @@ impl MetaObject {
 
     pub fn remove_restore_hdrs(&mut self) {
         self.meta_user.remove(X_AMZ_RESTORE.as_str());
-        self.meta_user.remove(X_AMZ_RESTORE_EXPIRY_DAYS);
-        self.meta_user.remove(X_AMZ_RESTORE_REQUEST_DATE);
+        self.meta_user.remove(AMZ_RESTORE_EXPIRY_DAYS);
+        self.meta_user.remove(AMZ_RESTORE_REQUEST_DATE);
     }
 
     pub fn uses_data_dir(&self) -> bool {
@@ -1653,7 +1682,7 @@ impl From<FileInfo> for MetaObject {
             erasure_block_size: value.erasure.block_size,
             erasure_index: value.erasure.index,
             erasure_dist: value.erasure.distribution.iter().map(|x| *x as u8).collect(),
-            bitrot_checksum_algo: ChecksumAlgo::HighwayHash,
+            bitrot_checksum_algo: ChecksumAlgo::from_hash_algorithm(&value.erasure.bitrot_algorithm()),
             part_numbers: value.parts.iter().map(|v| v.number).collect(),
             part_etags,
             part_sizes: value.parts.iter().map(|v| v.size).collect(),
@@ -1873,6 +1902,8 @@ pub enum ChecksumAlgo {
     #[default]
     Invalid = 0,
     HighwayHash = 1,
+    Blake3 = 2,
+    Crc32c = 3,
 }
 
 impl ChecksumAlgo {
@@ -1883,14 +1914,37 @@ impl ChecksumAlgo {
         match self {
             ChecksumAlgo::Invalid => 0,
             ChecksumAlgo::HighwayHash => 1,
+            ChecksumAlgo::Blake3 => 2,
+            ChecksumAlgo::Crc32c => 3,
         }
     }
     pub fn from_u8(u: u8) -> Self {
         match u {
             1 => ChecksumAlgo::HighwayHash,
+            2 => ChecksumAlgo::Blake3,
+            3 => ChecksumAlgo::Crc32c,
             _ => ChecksumAlgo::Invalid,
         }
     }
+
+    /// Hash algorithm used to verify the shards of this version
+    pub fn to_hash_algorithm(&self) -> HashAlgorithm {
+        match self {
+            ChecksumAlgo::Blake3 => HashAlgorithm::Blake3,
+            ChecksumAlgo::Crc32c => HashAlgorithm::Crc32c,
+            // Versions written before the algorithm was recorded always use HighwayHash
+            ChecksumAlgo::Invalid | ChecksumAlgo::HighwayHash => HashAlgorithm::HighwayHash256,
+        }
+    }
+
+    /// Only bitrot algorithms are stored, anything else is recorded as HighwayHash
+    pub fn from_hash_algorithm(algo: &HashAlgorithm) -> Self {
+        match algo {
+            HashAlgorithm::Blake3 => ChecksumAlgo::Blake3,
+            HashAlgorithm::Crc32c => ChecksumAlgo::Crc32c,
+            _ => ChecksumAlgo::HighwayHash,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Default, Clone)]
@@ -2236,6 +2290,36 @@ mod test {
         assert_eq!(obj.data_dir, obj2.data_dir);
     }
 
+    #[test]
+    fn test_metaobject_transition() {
+        let mut obj = MetaObject {
+            data_dir: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+        let fi = FileInfo {
+            transition_status: TRANSITION_COMPLETE.to_string(),
+            transitioned_objname: "ab/cd/remote".to_string(),
+            transition_version_id: Some(Uuid::new_v4()),
+            transition_tier: "WARM".to_string(),
+            ..Default::default()
+        };
+        obj.set_transition(&fi);
+
+        let got = obj.into_fileinfo("bucket", "object", false);
+        assert!(got.is_remote());
+        assert_eq!(got.transition_status, fi.transition_status);
+        assert_eq!(got.transitioned_objname, fi.transitioned_objname);
+        assert_eq!(got.transition_version_id, fi.transition_version_id);
+        assert_eq!(got.transition_tier, fi.transition_tier);
+
+        // Remote objects without versioning have no remote version id
+        obj.set_transition(&FileInfo {
+            transition_version_id: None,
+            ..fi
+        });
+        assert_eq!(obj.into_fileinfo("bucket", "object", false).transition_version_id, None);
+    }
+
     #[test]
     fn test_marshal_metadeletemarker() {
         let obj = MetaDeleteMarker {
@@ -2631,7 +2715,12 @@ mod test {
     #[test]
     fn test_checksum_algorithms() {
         // 测试不同的校验和算法
-        let algorithms = vec![ChecksumAlgo::Invalid, ChecksumAlgo::HighwayHash];
+        let algorithms = vec![
+            ChecksumAlgo::Invalid,
+            ChecksumAlgo::HighwayHash,
+            ChecksumAlgo::Blake3,
+            ChecksumAlgo::Crc32c,
+        ];
 
         for algo in algorithms {
             let obj = MetaObject {
@@ -2642,7 +2731,12 @@ mod test {
             // 验证算法的有效性检查
             match algo {
                 ChecksumAlgo::Invalid => assert!(!algo.valid()),
-                ChecksumAlgo::HighwayHash => assert!(algo.valid()),
+                _ => assert!(algo.valid()),
+            }
+
+            // 验证与 bitrot 算法的映射
+            if algo.valid() {
+                assert_eq!(ChecksumAlgo::from_hash_algorithm(&algo.to_hash_algorithm()), algo);
             }
 
             // 验证序列化和反序列化
@@ -3012,6 +3106,59 @@ mod test {
             assert_eq!(obj2.meta_user.get(key), Some(&expected_value.to_string()));
         }
     }
+
+    #[test]
+    fn test_delete_version_markers() {
+        let now = OffsetDateTime::now_utc();
+        let object = |version_id: Option<Uuid>, mod_time: OffsetDateTime| {
+            let mut fi = FileInfo::new("obj", 2, 1);
+            fi.version_id = version_id;
+            fi.data_dir = Some(Uuid::new_v4());
+            fi.mod_time = Some(mod_time);
+            fi
+        };
+        let marker = |version_id: Option<Uuid>, mod_time: OffsetDateTime| FileInfo {
+            version_id,
+            deleted: true,
+            mod_time: Some(mod_time),
+            ..Default::default()
+        };
+        let headers = |fm: &FileMeta| {
+            fm.versions
+                .iter()
+                .map(|v| (v.header.version_id, v.header.version_type.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let v1 = Uuid::new_v4();
+        let mut fm = FileMeta::new();
+        fm.add_version(object(Some(v1), now - time::Duration::hours(2))).unwrap();
+        fm.add_version(object(None, now - time::Duration::hours(1))).unwrap();
+
+        // suspended versioning: the null version becomes a null delete marker
+        assert!(fm.delete_version(&marker(None, now)).unwrap().is_some());
+        assert_eq!(headers(&fm), vec![(None, VersionType::Delete), (Some(v1), VersionType::Object)]);
+
+        // a delete marker is removed by its version id
+        let m = Uuid::new_v4();
+        fm.delete_version(&marker(Some(m), now + time::Duration::seconds(1))).unwrap();
+        assert_eq!(fm.versions[0].header.version_id, Some(m));
+        let removed = FileInfo {
+            version_id: Some(m),
+            ..Default::default()
+        };
+        assert_eq!(fm.delete_version(&removed).unwrap(), None);
+        assert_eq!(headers(&fm), vec![(None, VersionType::Delete), (Some(v1), VersionType::Object)]);
+
+        // the null version is addressed by the nil version id
+        let null = FileInfo {
+            version_id: Some(Uuid::nil()),
+            ..Default::default()
+        };
+        fm.delete_version(&null).unwrap();
+        assert_eq!(headers(&fm), vec![(Some(v1), VersionType::Object)]);
+        assert!(matches!(fm.delete_version(&null), Err(Error::FileVersionNotFound)));
+    }
 }
 
 #[tokio::test]
@@ -17,6 +17,10 @@ pub const AMZ_META_UNENCRYPTED_CONTENT_MD5: &str = "X-Amz-Meta-X-Amz-Unencrypted
 
 pub const AMZ_STORAGE_CLASS: &str = "x-amz-storage-class";
 
+/// Days a restored copy of a transitioned object is kept, and when it was asked for
+pub const AMZ_RESTORE_EXPIRY_DAYS: &str = "X-Amz-Restore-Expiry-Days";
+pub const AMZ_RESTORE_REQUEST_DATE: &str = "X-Amz-Restore-Request-Date";
+
 pub const RESERVED_METADATA_PREFIX: &str = "X-RustFS-Internal-";
 pub const RESERVED_METADATA_PREFIX_LOWER: &str = "x-rustfs-internal-";
 
@@ -32,6 +36,16 @@ pub const X_RUSTFS_DATA_MOV: &str = "X-Rustfs-Internal-data-mov";
 
 pub const AMZ_OBJECT_TAGGING: &str = "X-Amz-Tagging";
 pub const AMZ_BUCKET_REPLICATION_STATUS: &str = "X-Amz-Replication-Status";
+/// Header of the requests a replication source sends to its targets
+pub const RUSTFS_SOURCE_REPLICATION_REQUEST: &str = "X-Rustfs-Source-Replication-Request";
+/// Header carrying the modification time of the source version, which the replica keeps
+pub const RUSTFS_SOURCE_MTIME: &str = "X-Rustfs-Source-Mtime";
+/// Header marking a replicated delete as the delete marker of the source
+pub const RUSTFS_SOURCE_DELETE_MARKER: &str = "X-Rustfs-Source-DeleteMarker";
 pub const AMZ_DECODED_CONTENT_LENGTH: &str = "X-Amz-Decoded-Content-Length";
+/// Header of a PutObject appending to the object, the size the object has to have
+pub const AMZ_WRITE_OFFSET_BYTES: &str = "X-Amz-Write-Offset-Bytes";
+/// Header giving the object written a time to live, in seconds
+pub const RUSTFS_EXPIRE_AFTER: &str = "X-Rustfs-Expire-After";
 
 pub const RUSTFS_DATA_MOVE: &str = "X-Rustfs-Internal-data-mov";
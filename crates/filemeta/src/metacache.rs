@@ -442,11 +442,17 @@ impl MetaCacheEntriesSorted {
         entries
     }
 
+    /// Drop the entries up to and including `marker`
     pub fn forward_past(&mut self, marker: Option<String>) {
         if let Some(val) = marker {
-            if let Some(idx) = self.o.0.iter().flatten().position(|v| v.name > val) {
-                self.o.0 = self.o.0.split_off(idx);
-            }
+            self.o.0.retain(|v| v.as_ref().is_some_and(|v| v.name > val));
+        }
+    }
+
+    /// Drop the entries before `marker`
+    pub fn forward_to(&mut self, marker: Option<String>) {
+        if let Some(val) = marker {
+            self.o.0.retain(|v| v.as_ref().is_some_and(|v| v.name >= val));
         }
     }
 }
@@ -0,0 +1,177 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Time-boxed bucket access grants.
+//!
+//! A grant is enforced through a managed policy named `rustfs-grant-<id>` that is attached to the
+//! grantee like any other policy. The grants themselves are kept next to the other IAM config
+//! items in [`BUCKET_GRANTS_FILE`], keyed by the name of their policy, so that they survive
+//! restarts and every node loads them with the rest of IAM.
+
+use crate::error::{Error, Result};
+use rustfs_policy::policy::Policy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use time::OffsetDateTime;
+
+pub const BUCKET_GRANT_POLICY_PREFIX: &str = "rustfs-grant-";
+
+pub const BUCKET_GRANTS_FILE: &str = "bucket-grants.json";
+
+/// Actions granted when the request does not name any
+pub const DEFAULT_BUCKET_GRANT_ACTIONS: [&str; 2] = ["s3:GetObject", "s3:ListBucket"];
+
+/// Actions on the bucket that list its keys, limited to the grant prefix by an `s3:prefix` condition
+const BUCKET_GRANT_LIST_ACTIONS: [&str; 3] = ["s3:ListBucket", "s3:ListBucketVersions", "s3:ListBucketMultipartUploads"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketAccessGrant {
+    #[serde(default)]
+    pub policy_name: String,
+    pub identity: String,
+    #[serde(default)]
+    pub is_group: bool,
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub actions: Vec<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expiration: OffsetDateTime,
+    #[serde(default)]
+    pub granted_by: String,
+}
+
+impl BucketAccessGrant {
+    pub fn is_grant_policy(name: &str) -> bool {
+        name.starts_with(BUCKET_GRANT_POLICY_PREFIX)
+    }
+
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expiration <= now
+    }
+
+    pub fn validate(&self, now: OffsetDateTime) -> Result<()> {
+        if self.identity.is_empty() || self.bucket.is_empty() {
+            return Err(Error::InvalidArgument);
+        }
+
+        if self.is_expired(now) {
+            return Err(Error::other("grant expiration must be in the future"));
+        }
+
+        if self.actions.iter().any(|a| !a.starts_with("s3:")) {
+            return Err(Error::other("only s3 actions can be granted on a bucket"));
+        }
+
+        Ok(())
+    }
+
+    /// Build the managed policy backing this grant
+    ///
+    /// Listing is allowed on the bucket for keys under the prefix only, the other actions on the
+    /// objects under the prefix, and on the bucket itself when the grant covers all of it.
+    pub fn to_policy(&self) -> Result<Policy> {
+        let actions: Vec<&str> = if self.actions.is_empty() {
+            DEFAULT_BUCKET_GRANT_ACTIONS.to_vec()
+        } else {
+            self.actions.iter().map(String::as_str).collect()
+        };
+        let (list_actions, other_actions): (Vec<&str>, Vec<&str>) =
+            actions.into_iter().partition(|a| BUCKET_GRANT_LIST_ACTIONS.contains(a));
+
+        let bucket_arn = format!("arn:aws:s3:::{}", self.bucket);
+        let mut statements = Vec::new();
+        if !other_actions.is_empty() {
+            let mut resources = vec![format!("{}/{}*", bucket_arn, self.prefix)];
+            if self.prefix.is_empty() {
+                resources.push(bucket_arn.clone());
+            }
+            statements.push(json!({
+                "Effect": "Allow",
+                "Action": other_actions,
+                "Resource": resources,
+            }));
+        }
+        if !list_actions.is_empty() {
+            let mut statement = json!({
+                "Effect": "Allow",
+                "Action": list_actions,
+                "Resource": [bucket_arn],
+            });
+            if !self.prefix.is_empty() {
+                statement["Condition"] = json!({ "StringLike": { "s3:prefix": [format!("{}*", self.prefix)] } });
+            }
+            statements.push(statement);
+        }
+
+        let doc = json!({
+            "Version": "2012-10-17",
+            "Statement": Value::Array(statements),
+        });
+
+        Policy::parse_config(doc.to_string().as_bytes()).map_err(|e| Error::other(format!("invalid grant policy: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Duration;
+
+    fn sample_grant(expiration: OffsetDateTime) -> BucketAccessGrant {
+        BucketAccessGrant {
+            policy_name: format!("{BUCKET_GRANT_POLICY_PREFIX}test"),
+            identity: "alice".to_string(),
+            is_group: false,
+            bucket: "reports".to_string(),
+            prefix: "2024/".to_string(),
+            actions: vec!["s3:GetObject".to_string()],
+            expiration,
+            granted_by: "admin".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_grant_policy() {
+        let mut grant = sample_grant(OffsetDateTime::now_utc() + Duration::hours(1));
+        grant.actions = vec!["s3:GetObject".to_string(), "s3:ListBucket".to_string()];
+
+        let policy = grant.to_policy().unwrap();
+        assert_eq!(policy.statements.len(), 2);
+        assert!(policy.statements.iter().all(|s| s.sid.is_empty()));
+        // Listing is limited to the prefix
+        assert!(policy.statements[0].conditions.is_empty());
+        assert!(!policy.statements[1].conditions.is_empty());
+
+        grant.prefix.clear();
+        let policy = grant.to_policy().unwrap();
+        assert!(policy.statements[1].conditions.is_empty());
+
+        grant.actions = vec!["s3:GetObject".to_string()];
+        assert_eq!(grant.to_policy().unwrap().statements.len(), 1);
+    }
+
+    #[test]
+    fn test_grant_validate() {
+        let now = OffsetDateTime::now_utc();
+        assert!(sample_grant(now + Duration::minutes(5)).validate(now).is_ok());
+        assert!(sample_grant(now - Duration::minutes(5)).validate(now).is_err());
+
+        let mut grant = sample_grant(now + Duration::minutes(5));
+        grant.actions = vec!["admin:ServerInfo".to_string()];
+        assert!(grant.validate(now).is_err());
+    }
+}
@@ -22,7 +22,9 @@ use tracing::{debug, instrument};
 
 pub mod cache;
 pub mod error;
+pub mod grant;
 pub mod manager;
+pub mod revoke;
 pub mod store;
 pub mod utils;
 
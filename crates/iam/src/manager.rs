@@ -17,13 +17,17 @@ use crate::sys::get_claims_from_token_with_secret;
 use crate::{
     cache::{Cache, CacheEntity},
     error::{Error as IamError, is_err_no_such_group, is_err_no_such_policy, is_err_no_such_user},
+    grant::{BUCKET_GRANTS_FILE, BucketAccessGrant},
+    revoke::{ISSUED_AT_CLAIM, RevocationList, STS_REVOCATIONS_FILE, SessionRevocation, prune_revocations},
     store::{GroupInfo, MappedPolicy, Store, UserType, object::IAM_CONFIG_PREFIX},
     sys::{
         MAX_SVCSESSION_POLICY_SIZE, SESSION_POLICY_NAME, SESSION_POLICY_NAME_EXTRACTED, STATUS_DISABLED, STATUS_ENABLED,
         UpdateServiceAccountOpts,
     },
 };
+use arc_swap::ArcSwap;
 use rustfs_ecstore::global::get_global_action_cred;
+use rustfs_ecstore::notification_sys::{get_global_notification_sys, set_sts_revocations_reloader};
 use rustfs_madmin::{AccountStatus, AddOrUpdateUserReq, GroupDesc};
 use rustfs_policy::{
     arn::ARN,
@@ -76,6 +80,14 @@ fn get_iam_format_file_path() -> String {
     path_join_buf(&[&IAM_CONFIG_PREFIX, IAM_FORMAT_FILE])
 }
 
+fn get_sts_revocations_file_path() -> String {
+    path_join_buf(&[&IAM_CONFIG_PREFIX, STS_REVOCATIONS_FILE])
+}
+
+fn get_bucket_grants_file_path() -> String {
+    path_join_buf(&[&IAM_CONFIG_PREFIX, BUCKET_GRANTS_FILE])
+}
+
 pub struct IamCache<T> {
     pub cache: Cache,
     pub api: T,
@@ -83,6 +95,9 @@ pub struct IamCache<T> {
     pub roles: HashMap<ARN, Vec<String>>,
     pub send_chan: Sender<i64>,
     pub last_timestamp: AtomicI64,
+    pub revocations: RevocationList,
+    /// Bucket access grants by the name of their policy
+    pub bucket_grants: ArcSwap<HashMap<String, BucketAccessGrant>>,
 }
 
 impl<T> IamCache<T>
@@ -99,6 +114,8 @@ where
             send_chan: sender,
             roles: HashMap::new(),
             last_timestamp: AtomicI64::new(0),
+            revocations: RevocationList::default(),
+            bucket_grants: ArcSwap::default(),
         });
 
         sys.clone().init(receiver).await.unwrap();
@@ -109,6 +126,18 @@ where
         self.clone().save_iam_formatter().await?;
         self.clone().load().await?;
 
+        let cache = Arc::downgrade(&self);
+        set_sts_revocations_reloader(move || {
+            let Some(cache) = cache.upgrade() else {
+                return;
+            };
+            tokio::spawn(async move {
+                if let Err(err) = cache.reload_sts_revocations().await {
+                    error!("reload sts revocations err {:?}", err);
+                }
+            });
+        });
+
         // 检查环境变量是否设置
         let skip_background_task = std::env::var("RUSTFS_SKIP_BACKGROUND_TASK").is_ok();
 
@@ -159,11 +188,115 @@ where
     async fn load(self: Arc<Self>) -> Result<()> {
         // debug!("load iam to cache");
         self.api.load_all(&self.cache).await?;
+        self.bucket_grants.store(Arc::new(self.load_bucket_grants().await?));
+        if self.reload_sts_revocations().await? {
+            // Keep the persisted list from growing, whichever node gets here first saves it pruned
+            if let Err(err) = self.update_sts_revocations(|_| {}).await {
+                warn!("prune sts revocations failed: {:?}", err);
+            }
+        }
         self.last_timestamp
             .store(OffsetDateTime::now_utc().unix_timestamp(), Ordering::Relaxed);
         Ok(())
     }
 
+    async fn load_sts_revocations(&self) -> Result<Vec<SessionRevocation>> {
+        match self.api.load_iam_config(get_sts_revocations_file_path()).await {
+            Ok(entries) => Ok(entries),
+            Err(err) if is_err_config_not_found(&err) => Ok(Vec::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Reload the revocation list, without the revocations that no longer cover a session
+    ///
+    /// Returns whether the persisted list has revocations to prune.
+    async fn reload_sts_revocations(&self) -> Result<bool> {
+        let mut entries = self.load_sts_revocations().await?;
+        let pruned = self.prune_sts_revocations(&mut entries);
+        self.revocations.replace(entries);
+        Ok(pruned)
+    }
+
+    fn prune_sts_revocations(&self, entries: &mut Vec<SessionRevocation>) -> bool {
+        let sessions = self.cache.sts_accounts.load();
+        prune_revocations(entries, OffsetDateTime::now_utc(), |r| {
+            sessions
+                .values()
+                .filter(|u| !u.credentials.is_expired())
+                .any(|u| r.matches(&u.credentials.parent_user, session_issued_at(u)))
+        })
+    }
+
+    /// Change the persisted revocation list under the cluster-wide lock of its file, pruning it,
+    /// and tell the peers to reload it if it changed
+    async fn update_sts_revocations(&self, update: impl FnOnce(&mut Vec<SessionRevocation>)) -> Result<()> {
+        let path = get_sts_revocations_file_path();
+        let guard = self.api.lock_iam_config(&path).await?;
+
+        let mut entries = self.load_sts_revocations().await?;
+        let pruned = self.prune_sts_revocations(&mut entries);
+        let len = entries.len();
+        update(&mut entries);
+        let changed = pruned || entries.len() != len;
+        if changed {
+            self.api.save_iam_config(&entries, &path).await?;
+        }
+        guard.release().await;
+
+        self.revocations.replace(entries);
+        if let Some(notification_sys) = get_global_notification_sys().filter(|_| changed) {
+            notification_sys.reload_sts_revocations().await;
+        }
+        Ok(())
+    }
+
+    /// Add a revocation, merging with revocations persisted by other nodes since the last load
+    pub async fn revoke_sts_sessions(&self, revocation: SessionRevocation) -> Result<()> {
+        revocation.validate()?;
+        self.update_sts_revocations(|entries| entries.push(revocation)).await
+    }
+
+    async fn load_bucket_grants(&self) -> Result<HashMap<String, BucketAccessGrant>> {
+        match self.api.load_iam_config(get_bucket_grants_file_path()).await {
+            Ok(grants) => Ok(grants),
+            Err(err) if is_err_config_not_found(&err) => Ok(HashMap::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Bucket access grants as persisted, which includes the ones other nodes made since the last load
+    pub async fn list_bucket_grants(&self) -> Result<Vec<BucketAccessGrant>> {
+        Ok(self.load_bucket_grants().await?.into_values().collect())
+    }
+
+    /// Change the persisted bucket access grants under the cluster-wide lock of their file
+    pub async fn update_bucket_grants<R>(
+        &self,
+        update: impl FnOnce(&mut HashMap<String, BucketAccessGrant>) -> Result<R>,
+    ) -> Result<R> {
+        let path = get_bucket_grants_file_path();
+        let guard = self.api.lock_iam_config(&path).await?;
+
+        let mut grants = self.load_bucket_grants().await?;
+        let res = update(&mut grants)?;
+        self.api.save_iam_config(&grants, &path).await?;
+        guard.release().await;
+
+        self.bucket_grants.store(Arc::new(grants));
+        Ok(res)
+    }
+
+    /// Whether `u` is an STS session covered by the revocation list
+    pub fn is_sts_session_revoked(&self, u: &UserIdentity) -> bool {
+        let cred = &u.credentials;
+        if self.revocations.is_empty() || !cred.is_temp() || cred.is_service_account() {
+            return false;
+        }
+
+        self.revocations.is_revoked(&cred.parent_user, session_issued_at(u))
+    }
+
     // TODO: Check if exists, whether retry is possible
     #[tracing::instrument(level = "debug", skip(self))]
     async fn save_iam_formatter(self: Arc<Self>) -> Result<()> {
@@ -1604,6 +1737,14 @@ pub fn get_token_signing_key() -> Option<String> {
     }
 }
 
+/// Issue time of an STS session, from its `iat` claim
+fn session_issued_at(u: &UserIdentity) -> Option<OffsetDateTime> {
+    extract_jwt_claims(u)
+        .ok()
+        .and_then(|claims| claims.get(ISSUED_AT_CLAIM).and_then(Value::as_i64))
+        .and_then(|t| OffsetDateTime::from_unix_timestamp(t).ok())
+}
+
 pub fn extract_jwt_claims(u: &UserIdentity) -> Result<HashMap<String, Value>> {
     let Some(sys_key) = get_token_signing_key() else {
         return Err(Error::other("global active sk not init"));
@@ -0,0 +1,199 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Revocation list for STS sessions.
+//!
+//! Revocations are kept in memory for the credential check and persisted next to the other IAM
+//! config items. The node taking a revocation tells its peers to reload the list, the others pick
+//! it up on their next IAM reload.
+//!
+//! A revocation is dropped once its window has ended for [`REVOCATION_GRACE`] and no stored STS
+//! session it covers is left.
+
+use crate::error::{Error, Result};
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use time::{Duration, OffsetDateTime};
+
+pub const STS_REVOCATIONS_FILE: &str = "sts-revocations.json";
+
+/// How long a revocation is kept after its window ends, covering sessions issued on other nodes
+/// that this node has not loaded yet
+pub const REVOCATION_GRACE: Duration = Duration::hours(1);
+
+/// Claim holding the issue time of a session token, in seconds since the Unix epoch
+pub const ISSUED_AT_CLAIM: &str = "iat";
+
+/// Revocation of the STS sessions of one or all users, optionally limited to an issue-time window
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRevocation {
+    /// Parent user of the revoked sessions, every user when empty
+    #[serde(default)]
+    pub user: String,
+    /// Sessions issued before this time are kept
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub issued_after: Option<OffsetDateTime>,
+    /// Sessions issued after this time are kept, defaults to the revocation time
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub issued_before: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub revoked_at: OffsetDateTime,
+    #[serde(default)]
+    pub revoked_by: String,
+}
+
+impl SessionRevocation {
+    pub fn validate(&self) -> Result<()> {
+        if let (Some(after), Some(before)) = (self.issued_after, self.issued_before) {
+            if after > before {
+                return Err(Error::other("issuedAfter must not be later than issuedBefore"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a session of `parent_user` issued at `issued_at` is revoked
+    ///
+    /// Sessions without a known issue time predate the `iat` claim and are treated as matching any window.
+    pub fn matches(&self, parent_user: &str, issued_at: Option<OffsetDateTime>) -> bool {
+        if !self.user.is_empty() && self.user != parent_user {
+            return false;
+        }
+
+        let Some(issued_at) = issued_at else {
+            return true;
+        };
+
+        if self.issued_after.is_some_and(|after| issued_at < after) {
+            return false;
+        }
+        issued_at <= self.window_end()
+    }
+
+    /// Sessions issued after this time are kept
+    pub fn window_end(&self) -> OffsetDateTime {
+        self.issued_before.unwrap_or(self.revoked_at)
+    }
+}
+
+/// Drop the revocations that can no longer match a session: their window ended more than
+/// [`REVOCATION_GRACE`] before `now` and `covers_session` finds no stored session they match
+///
+/// Returns whether any revocation was dropped.
+pub fn prune_revocations(
+    entries: &mut Vec<SessionRevocation>,
+    now: OffsetDateTime,
+    covers_session: impl Fn(&SessionRevocation) -> bool,
+) -> bool {
+    let len = entries.len();
+    entries.retain(|r| now < r.window_end() + REVOCATION_GRACE || covers_session(r));
+    entries.len() < len
+}
+
+/// In-memory revocation list, cheap to check when empty
+#[derive(Default)]
+pub struct RevocationList {
+    entries: ArcSwap<Vec<SessionRevocation>>,
+}
+
+impl RevocationList {
+    pub fn is_empty(&self) -> bool {
+        self.entries.load().is_empty()
+    }
+
+    pub fn entries(&self) -> Vec<SessionRevocation> {
+        self.entries.load().as_ref().clone()
+    }
+
+    pub fn replace(&self, entries: Vec<SessionRevocation>) {
+        self.entries.store(Arc::new(entries));
+    }
+
+    pub fn is_revoked(&self, parent_user: &str, issued_at: Option<OffsetDateTime>) -> bool {
+        self.entries.load().iter().any(|r| r.matches(parent_user, issued_at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revocation(user: &str, after: Option<i64>, before: Option<i64>) -> SessionRevocation {
+        SessionRevocation {
+            user: user.to_string(),
+            issued_after: after.map(|t| OffsetDateTime::from_unix_timestamp(t).unwrap()),
+            issued_before: before.map(|t| OffsetDateTime::from_unix_timestamp(t).unwrap()),
+            revoked_at: OffsetDateTime::from_unix_timestamp(2_000).unwrap(),
+            revoked_by: "admin".to_string(),
+        }
+    }
+
+    fn at(t: i64) -> Option<OffsetDateTime> {
+        Some(OffsetDateTime::from_unix_timestamp(t).unwrap())
+    }
+
+    #[test]
+    fn test_revocation_matches_user_and_window() {
+        let by_user = revocation("alice", None, None);
+        assert!(by_user.matches("alice", at(1_000)));
+        assert!(!by_user.matches("bob", at(1_000)));
+        // Sessions issued after the revocation are unaffected
+        assert!(!by_user.matches("alice", at(3_000)));
+
+        let window = revocation("", at(500).map(|t| t.unix_timestamp()), Some(1_500));
+        assert!(window.matches("bob", at(1_000)));
+        assert!(!window.matches("bob", at(100)));
+        assert!(!window.matches("bob", at(1_600)));
+        assert!(window.matches("bob", None));
+    }
+
+    #[test]
+    fn test_revocation_list() {
+        let list = RevocationList::default();
+        assert!(list.is_empty());
+        assert!(!list.is_revoked("alice", at(1_000)));
+
+        list.replace(vec![revocation("alice", None, None)]);
+        assert!(list.is_revoked("alice", at(1_000)));
+        assert!(!list.is_revoked("bob", at(1_000)));
+    }
+
+    #[test]
+    fn test_prune_revocations() {
+        let revoked_at = OffsetDateTime::from_unix_timestamp(2_000).unwrap();
+        let mut entries = vec![revocation("alice", None, None), revocation("bob", None, None)];
+
+        // Within the grace period nothing is dropped
+        assert!(!prune_revocations(&mut entries, revoked_at + Duration::minutes(30), |_| false));
+        assert_eq!(entries.len(), 2);
+
+        // Afterwards only the revocations still covering a stored session are kept
+        assert!(prune_revocations(&mut entries, revoked_at + Duration::hours(2), |r| r.user == "bob"));
+        assert_eq!(entries, vec![revocation("bob", None, None)]);
+
+        // A window ending in the future keeps the revocation
+        let mut entries = vec![revocation("", None, Some(10_000))];
+        assert!(!prune_revocations(&mut entries, revoked_at + Duration::hours(2), |_| false));
+    }
+
+    #[test]
+    fn test_validate_rejects_inverted_window() {
+        let mut r = revocation("", Some(1_000), Some(500));
+        assert!(r.validate().is_err());
+        r.issued_before = r.issued_after.map(|t| t + Duration::seconds(1));
+        assert!(r.validate().is_ok());
+    }
+}
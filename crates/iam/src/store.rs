@@ -16,6 +16,7 @@ pub mod object;
 
 use crate::cache::Cache;
 use crate::error::Result;
+use rustfs_lock::LockGuard;
 use rustfs_policy::{auth::UserIdentity, policy::PolicyDoc};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use std::collections::{HashMap, HashSet};
@@ -26,6 +27,8 @@ pub trait Store: Clone + Send + Sync + 'static {
     async fn save_iam_config<Item: Serialize + Send>(&self, item: Item, path: impl AsRef<str> + Send) -> Result<()>;
     async fn load_iam_config<Item: DeserializeOwned>(&self, path: impl AsRef<str> + Send) -> Result<Item>;
     async fn delete_iam_config(&self, path: impl AsRef<str> + Send) -> Result<()>;
+    /// Write lock the config item at `path` across the cluster, for updates that read it first
+    async fn lock_iam_config(&self, path: impl AsRef<str> + Send) -> Result<LockGuard>;
 
     async fn save_user_identity(&self, name: &str, user_type: UserType, item: UserIdentity, ttl: Option<usize>) -> Result<()>;
     async fn delete_user_identity(&self, name: &str, user_type: UserType) -> Result<()>;
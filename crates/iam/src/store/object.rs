@@ -30,6 +30,7 @@ use rustfs_ecstore::{
     store_api::{ObjectInfo, ObjectOptions},
     store_list_objects::{ObjectInfoOrErr, WalkOptions},
 };
+use rustfs_lock::LockGuard;
 use rustfs_policy::{auth::UserIdentity, policy::PolicyDoc};
 use rustfs_utils::path::{SLASH_SEPARATOR, path_join_buf};
 use serde::{Serialize, de::DeserializeOwned};
@@ -396,6 +397,9 @@ impl Store for ObjectStore {
         delete_config(self.object_api.clone(), path.as_ref()).await?;
         Ok(())
     }
+    async fn lock_iam_config(&self, path: impl AsRef<str> + Send) -> Result<LockGuard> {
+        Ok(self.object_api.lock_config(path.as_ref()).await?)
+    }
 
     async fn save_user_identity(
         &self,
@@ -14,11 +14,14 @@
 
 use crate::error::Error as IamError;
 use crate::error::is_err_no_such_account;
+use crate::error::is_err_no_such_policy;
 use crate::error::is_err_no_such_temp_account;
 use crate::error::{Error, Result};
+use crate::grant::{BUCKET_GRANT_POLICY_PREFIX, BucketAccessGrant};
 use crate::manager::IamCache;
 use crate::manager::extract_jwt_claims;
 use crate::manager::get_default_policyes;
+use crate::revoke::SessionRevocation;
 use crate::store::GroupInfo;
 use crate::store::MappedPolicy;
 use crate::store::Store;
@@ -41,6 +44,7 @@ use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
 use time::OffsetDateTime;
+use tracing::warn;
 
 pub const MAX_SVCSESSION_POLICY_SIZE: usize = 4096;
 
@@ -476,7 +480,7 @@ impl<T: Store> IamSys<T> {
 
         match self.store.get_user(access_key).await {
             Some(res) => {
-                let ok = res.credentials.is_valid();
+                let ok = res.credentials.is_valid() && !self.store.is_sts_session_revoked(&res);
 
                 Ok((Some(res), ok))
             }
@@ -484,6 +488,15 @@ impl<T: Store> IamSys<T> {
         }
     }
 
+    /// Revoke outstanding STS sessions, see [`SessionRevocation`]
+    pub async fn revoke_sts_sessions(&self, revocation: SessionRevocation) -> Result<()> {
+        self.store.revoke_sts_sessions(revocation).await
+    }
+
+    pub fn list_sts_revocations(&self) -> Vec<SessionRevocation> {
+        self.store.revocations.entries()
+    }
+
     pub async fn get_user(&self, access_key: &str) -> Option<UserIdentity> {
         match self.check_key(access_key).await {
             Ok((u, _)) => u,
@@ -631,10 +644,165 @@ impl<T: Store> IamSys<T> {
         is_owner || combined_policy.is_allowed(&parent_args)
     }
 
+    /// Whether the policy of the credentials `args.account` themselves allows the request: the session
+    /// policy of temporary credentials or the inline policy of a service account. Grants made to
+    /// their parent user outside of IAM, e.g. by a bucket policy, reach them only as far as it allows.
+    /// Credentials without a policy of their own are not limited by it
+    pub async fn is_allowed_by_credential_policy(&self, args: &Args<'_>) -> bool {
+        let Ok((is_temp, _)) = self.is_temp_user(args.account).await else { return false };
+        if is_temp {
+            let (has_session_policy, is_allowed_sp) = is_allowed_by_session_policy(args);
+            return !has_session_policy || is_allowed_sp;
+        }
+
+        let Ok((is_svc, _)) = self.is_service_account(args.account).await else { return false };
+        if is_svc {
+            if args
+                .claims
+                .get(&iam_policy_claim_name_sa())
+                .and_then(|sa| sa.as_str())
+                .is_some_and(|sa| sa == INHERITED_POLICY_TYPE)
+            {
+                return true;
+            }
+            let (has_session_policy, is_allowed_sp) = is_allowed_by_session_policy_for_service_account(args);
+            return !has_session_policy || is_allowed_sp;
+        }
+
+        true
+    }
+
     pub async fn get_combined_policy(&self, policies: &[String]) -> Policy {
         self.store.merge_policies(&policies.join(",")).await.1
     }
 
+    /// Grant an identity time-limited access to a bucket/prefix through a managed policy
+    pub async fn grant_bucket_access(&self, mut grant: BucketAccessGrant) -> Result<BucketAccessGrant> {
+        grant.validate(OffsetDateTime::now_utc())?;
+
+        if grant.is_group {
+            self.store.get_group_description(&grant.identity).await?;
+        } else if self.store.get_user(&grant.identity).await.is_none() {
+            return Err(Error::NoSuchUser(grant.identity.clone()));
+        }
+
+        grant.policy_name = format!("{}{:016x}", BUCKET_GRANT_POLICY_PREFIX, rand::random::<u64>());
+        self.set_policy(&grant.policy_name, grant.to_policy()?).await?;
+        if let Err(err) = self
+            .store
+            .update_bucket_grants(|grants| {
+                grants.insert(grant.policy_name.clone(), grant.clone());
+                Ok(())
+            })
+            .await
+        {
+            let _ = self.store.delete_policy(&grant.policy_name, true).await;
+            return Err(err);
+        }
+
+        let mut policies = self
+            .store
+            .get_mapped_policy(&grant.identity, grant.is_group)
+            .await
+            .map(|mp| mp.to_slice())
+            .unwrap_or_default();
+        policies.push(grant.policy_name.clone());
+
+        if let Err(err) = self
+            .policy_db_set(&grant.identity, UserType::Reg, grant.is_group, &policies.join(","))
+            .await
+        {
+            let _ = self
+                .store
+                .update_bucket_grants(|grants| {
+                    grants.remove(&grant.policy_name);
+                    Ok(())
+                })
+                .await;
+            let _ = self.store.delete_policy(&grant.policy_name, true).await;
+            return Err(err);
+        }
+
+        Ok(grant)
+    }
+
+    /// List the bucket access grants, all buckets when `bucket` is empty
+    pub async fn list_bucket_access_grants(&self, bucket: &str) -> Result<Vec<BucketAccessGrant>> {
+        let mut grants: Vec<BucketAccessGrant> = self
+            .store
+            .list_bucket_grants()
+            .await?
+            .into_iter()
+            .filter(|g| bucket.is_empty() || g.bucket == bucket)
+            .collect();
+        grants.sort_by(|a, b| a.expiration.cmp(&b.expiration));
+
+        Ok(grants)
+    }
+
+    /// Detach the grant from its identity and remove the backing policy
+    pub async fn revoke_bucket_access_grant(&self, policy_name: &str) -> Result<BucketAccessGrant> {
+        let Some(grant) = self
+            .store
+            .list_bucket_grants()
+            .await?
+            .into_iter()
+            .find(|g| g.policy_name == policy_name)
+        else {
+            return Err(Error::NoSuchPolicy);
+        };
+
+        if let Some(mp) = self.store.get_mapped_policy(&grant.identity, grant.is_group).await {
+            let remaining: Vec<String> = mp.to_slice().into_iter().filter(|p| p != policy_name).collect();
+            self.policy_db_set(&grant.identity, UserType::Reg, grant.is_group, &remaining.join(","))
+                .await?;
+        }
+
+        match self.store.delete_policy(policy_name, true).await {
+            Ok(()) => {}
+            Err(err) if is_err_no_such_policy(&err) => {}
+            Err(err) => return Err(err),
+        }
+        self.store
+            .update_bucket_grants(|grants| {
+                grants.remove(policy_name);
+                Ok(())
+            })
+            .await?;
+
+        Ok(grant)
+    }
+
+    /// Revoke every grant whose expiration has passed, returning the revoked grants
+    pub async fn purge_expired_bucket_access_grants(&self) -> Result<Vec<BucketAccessGrant>> {
+        let now = OffsetDateTime::now_utc();
+        let mut purged = Vec::new();
+
+        for grant in self.list_bucket_access_grants("").await? {
+            if !grant.is_expired(now) {
+                continue;
+            }
+
+            match self.revoke_bucket_access_grant(&grant.policy_name).await {
+                Ok(g) => purged.push(g),
+                Err(e) => warn!("revoke expired bucket grant {} failed: {}", grant.policy_name, e),
+            }
+        }
+
+        Ok(purged)
+    }
+
+    /// Drop grant policies that already expired but haven't been purged yet, or whose grant is unknown
+    fn filter_expired_grants(&self, policies: Vec<String>) -> Vec<String> {
+        let now = OffsetDateTime::now_utc();
+        let grants = self.store.bucket_grants.load();
+
+        policies
+            .into_iter()
+            .filter(|name| !BucketAccessGrant::is_grant_policy(name) || grants.get(name).is_some_and(|g| !g.is_expired(now)))
+            .collect()
+    }
+
     pub async fn is_allowed(&self, args: &Args<'_>) -> bool {
         if args.is_owner {
             return true;
@@ -654,6 +822,7 @@ impl<T: Store> IamSys<T> {
 
         let Ok(policies) = self.policy_db_get(args.account, args.groups).await else { return false };
 
+        let policies = self.filter_expired_grants(policies);
         if policies.is_empty() {
             return false;
         }
@@ -0,0 +1,128 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Audit trail of the lock events that block or interrupt writes.
+//!
+//! Forced unlocks are not among them, the admin API that forces them audits who did.
+//!
+//! The lock crate only reports the events; the server installs a sink with [`set_audit_sink`]
+//! that writes them to its audit log. Events raised before a sink is installed are dropped.
+
+use std::sync::OnceLock;
+
+/// What happened to a lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockAuditKind {
+    /// Nobody renewed the lease of a held lock, its holders lost it
+    LeaseExpired,
+    /// The lease of a lock held through a namespace lock could not be renewed on a majority
+    LeaseLost,
+    /// Some nodes granted the lock, too few of them for the quorum
+    QuorumFailure,
+}
+
+impl LockAuditKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockAuditKind::LeaseExpired => "lock:LeaseExpired",
+            LockAuditKind::LeaseLost => "lock:LeaseLost",
+            LockAuditKind::QuorumFailure => "lock:QuorumFailure",
+        }
+    }
+}
+
+/// A lock event for the audit log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockAuditEvent {
+    pub kind: LockAuditKind,
+    pub resource: String,
+    /// Owners holding or requesting the lock
+    pub owners: Vec<String>,
+    /// Nodes the event concerns, empty for this node
+    pub nodes: Vec<String>,
+    pub detail: String,
+}
+
+type AuditSink = Box<dyn Fn(LockAuditEvent) + Send + Sync>;
+
+static AUDIT_SINK: OnceLock<AuditSink> = OnceLock::new();
+
+/// Install the sink lock events are reported to, false if one is installed already
+///
+/// The sink is called while lock maps are locked, it must hand the event off without blocking.
+pub fn set_audit_sink(sink: impl Fn(LockAuditEvent) + Send + Sync + 'static) -> bool {
+    AUDIT_SINK.set(Box::new(sink)).is_ok()
+}
+
+pub(crate) fn audit(kind: LockAuditKind, resource: &str, owners: Vec<String>, nodes: Vec<String>, detail: String) {
+    tracing::info!(
+        "{} on '{}', owners: {:?}, nodes: {:?}: {}",
+        kind.as_str(),
+        resource,
+        owners,
+        nodes,
+        detail
+    );
+    if let Some(sink) = AUDIT_SINK.get() {
+        sink(LockAuditEvent {
+            kind,
+            resource: resource.to_string(),
+            owners,
+            nodes,
+            detail,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LocalLockMap, LockRequest, LockType};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    static EVENTS: Mutex<Vec<LockAuditEvent>> = Mutex::new(Vec::new());
+
+    fn events_of(resource: &str) -> Vec<LockAuditEvent> {
+        EVENTS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.resource == resource)
+            .cloned()
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_audit_expiry() {
+        set_audit_sink(|event| EVENTS.lock().unwrap().push(event));
+        let lock_map = LocalLockMap::new();
+
+        let forced = LockRequest::new("audited-forced", LockType::Exclusive, "holder").with_ttl(Duration::from_secs(5));
+        assert!(lock_map.lock_with_ttl_id(&forced).await.unwrap());
+        assert!(lock_map.force_unlock_by_id(&forced.lock_id).await);
+        assert!(events_of("audited-forced").is_empty());
+
+        let expired = LockRequest::new("audited-expired", LockType::Shared, "reader").with_ttl(Duration::from_millis(50));
+        assert!(lock_map.rlock_with_ttl_id(&expired).await.unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let writer = LockRequest::new("audited-expired", LockType::Exclusive, "writer").with_ttl(Duration::from_secs(5));
+        assert!(lock_map.lock_with_ttl_id(&writer).await.unwrap());
+        let events = events_of("audited-expired");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, LockAuditKind::LeaseExpired);
+        assert_eq!(events[0].owners, vec!["reader".to_string()]);
+        lock_map.shutdown().await;
+    }
+}
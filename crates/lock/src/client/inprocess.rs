@@ -0,0 +1,143 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    client::LockClient,
+    error::{LockError, Result},
+    inprocess::InProcessLockMap,
+    types::{LockId, LockInfo, LockRequest, LockResponse, LockStats, LockStatus},
+};
+
+/// In-process lock client for single node deployments
+///
+/// Uses the global InProcessLockMap, locks taken through it are invisible to the lock RPC server
+#[derive(Debug, Clone)]
+pub struct InProcessClient;
+
+impl InProcessClient {
+    /// Create new in-process client
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Get global in-process lock map instance
+    pub fn get_lock_map(&self) -> Arc<InProcessLockMap> {
+        crate::get_global_inprocess_lock_map()
+    }
+}
+
+impl Default for InProcessClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl LockClient for InProcessClient {
+    async fn acquire_exclusive(&self, request: &LockRequest) -> Result<LockResponse> {
+        self.acquire_lock(request).await
+    }
+
+    async fn acquire_shared(&self, request: &LockRequest) -> Result<LockResponse> {
+        self.acquire_lock(request).await
+    }
+
+    async fn acquire_lock(&self, request: &LockRequest) -> Result<LockResponse> {
+        if !self.get_lock_map().acquire(request).await? {
+            return Ok(LockResponse::failure("Lock acquisition failed".to_string(), Duration::ZERO));
+        }
+        let now = SystemTime::now();
+        let lock_info = LockInfo {
+            id: request.lock_id.clone(),
+            resource: request.resource.clone(),
+            lock_type: request.lock_type,
+            status: LockStatus::Acquired,
+            owner: request.owner.clone(),
+            acquired_at: now,
+            expires_at: now + request.ttl,
+            last_refreshed: now,
+            metadata: request.metadata.clone(),
+            priority: request.priority,
+            wait_start_time: None,
+        };
+        Ok(LockResponse::success(lock_info, Duration::ZERO))
+    }
+
+    async fn release(&self, lock_id: &LockId) -> Result<bool> {
+        if self.get_lock_map().release(lock_id) {
+            Ok(true)
+        } else {
+            Err(LockError::internal("Lock ID not found".to_string()))
+        }
+    }
+
+    async fn refresh(&self, lock_id: &LockId) -> Result<bool> {
+        Ok(self.get_lock_map().refresh(lock_id))
+    }
+
+    async fn force_release(&self, lock_id: &LockId) -> Result<bool> {
+        Ok(self.get_lock_map().force_release(lock_id))
+    }
+
+    async fn check_status(&self, lock_id: &LockId) -> Result<Option<LockInfo>> {
+        Ok(self.get_lock_map().lock_info(lock_id))
+    }
+
+    async fn get_stats(&self) -> Result<LockStats> {
+        Ok(LockStats::default())
+    }
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn is_online(&self) -> bool {
+        true
+    }
+
+    async fn is_local(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::LockType;
+
+    #[tokio::test]
+    async fn test_inprocess_client_lock_status() {
+        let client = InProcessClient::new();
+        let resource_name = format!("test-inprocess-{}", uuid::Uuid::new_v4());
+        let request =
+            LockRequest::new(&resource_name, LockType::Shared, "reader").with_acquire_timeout(Duration::from_millis(10));
+
+        let response = client.acquire_shared(&request).await.unwrap();
+        let lock_info = response.lock_info().unwrap();
+        let status = client.check_status(&lock_info.id).await.unwrap().unwrap();
+        assert_eq!(status.lock_type, LockType::Shared);
+        assert_eq!(status.owner, "reader");
+
+        let writer =
+            LockRequest::new(&resource_name, LockType::Exclusive, "writer").with_acquire_timeout(Duration::from_millis(10));
+        assert!(!client.acquire_exclusive(&writer).await.unwrap().is_success());
+
+        assert!(client.release(&lock_info.id).await.unwrap());
+        assert!(client.check_status(&lock_info.id).await.unwrap().is_none());
+        assert!(client.acquire_exclusive(&writer).await.unwrap().is_success());
+    }
+}
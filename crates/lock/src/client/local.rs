@@ -49,10 +49,10 @@ impl Default for LocalClient {
 impl LockClient for LocalClient {
     async fn acquire_exclusive(&self, request: &LockRequest) -> Result<LockResponse> {
         let lock_map = self.get_lock_map();
-        let success = lock_map
-            .lock_with_ttl_id(request)
-            .await
-            .map_err(|e| crate::error::LockError::internal(format!("Lock acquisition failed: {e}")))?;
+        let success = lock_map.lock_with_ttl_id(request).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::Interrupted => crate::error::LockError::canceled(&request.resource),
+            _ => crate::error::LockError::internal(format!("Lock acquisition failed: {e}")),
+        })?;
         if success {
             let lock_info = LockInfo {
                 id: crate::types::LockId::new_deterministic(&request.resource),
@@ -75,10 +75,10 @@ impl LockClient for LocalClient {
 
     async fn acquire_shared(&self, request: &LockRequest) -> Result<LockResponse> {
         let lock_map = self.get_lock_map();
-        let success = lock_map
-            .rlock_with_ttl_id(request)
-            .await
-            .map_err(|e| crate::error::LockError::internal(format!("Shared lock acquisition failed: {e}")))?;
+        let success = lock_map.rlock_with_ttl_id(request).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::Interrupted => crate::error::LockError::canceled(&request.resource),
+            _ => crate::error::LockError::internal(format!("Shared lock acquisition failed: {e}")),
+        })?;
         if success {
             let lock_info = LockInfo {
                 id: crate::types::LockId::new_deterministic(&request.resource),
@@ -116,21 +116,19 @@ impl LockClient for LocalClient {
         }
     }
 
-    async fn refresh(&self, _lock_id: &LockId) -> Result<bool> {
-        // For local locks, refresh is not needed as they don't expire automatically
-        Ok(true)
+    async fn refresh(&self, lock_id: &LockId) -> Result<bool> {
+        Ok(self.get_lock_map().refresh_by_id(lock_id).await)
     }
 
     async fn force_release(&self, lock_id: &LockId) -> Result<bool> {
-        self.release(lock_id).await
+        Ok(self.get_lock_map().force_unlock_by_id(lock_id).await)
     }
 
     async fn check_status(&self, lock_id: &LockId) -> Result<Option<LockInfo>> {
         let lock_map = self.get_lock_map();
 
         // Check if the lock exists in our locks map
-        let locks_guard = lock_map.locks.read().await;
-        if let Some(entry) = locks_guard.get(lock_id) {
+        if let Some(entry) = lock_map.entry(lock_id).await {
             let entry_guard = entry.read().await;
 
             // Determine lock type and owner based on the entry
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod inprocess;
 pub mod local;
 pub mod remote;
 
@@ -20,6 +21,7 @@ use std::sync::Arc;
 
 use crate::{
     error::Result,
+    protocol::LockCapabilities,
     types::{LockId, LockInfo, LockRequest, LockResponse, LockStats},
 };
 
@@ -63,6 +65,16 @@ pub trait LockClient: Send + Sync + std::fmt::Debug {
 
     /// Check if client is local
     async fn is_local(&self) -> bool;
+
+    /// Address of the lock server, `None` for locks held in this process
+    fn endpoint(&self) -> Option<String> {
+        None
+    }
+
+    /// Lock protocol capabilities both this node and the lock server support
+    async fn capabilities(&self) -> LockCapabilities {
+        LockCapabilities::current()
+    }
 }
 
 /// Client factory
@@ -74,6 +86,11 @@ impl ClientFactory {
         Arc::new(local::LocalClient::new())
     }
 
+    /// Create in-process client
+    pub fn create_in_process() -> Arc<dyn LockClient> {
+        Arc::new(inprocess::InProcessClient::new())
+    }
+
     /// Create remote client
     pub fn create_remote(endpoint: String) -> Arc<dyn LockClient> {
         Arc::new(remote::RemoteClient::new(endpoint))
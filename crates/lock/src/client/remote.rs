@@ -15,27 +15,39 @@
 use async_trait::async_trait;
 use rustfs_protos::{
     node_service_time_out_client,
-    proto_gen::node_service::{GenerallyLockRequest, PingRequest},
+    proto_gen::node_service::{GenerallyLockRequest, HelloRequest, PingRequest},
 };
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tonic::Request;
-use tracing::info;
+use tonic::{Code, Request};
+use tracing::{debug, info};
 
 use crate::{
     error::{LockError, Result},
+    protocol::LockCapabilities,
     types::{LockId, LockInfo, LockRequest, LockResponse, LockStats},
 };
 
 use super::LockClient;
 
+/// How long negotiated capabilities are trusted, a peer upgraded in the meantime is asked again
+const CAPABILITIES_TTL: Duration = Duration::from_secs(60);
+
+/// Milliseconds the server may wait for `request`, so it stops waiting when the caller does
+fn wait_budget_ms(request: &LockRequest) -> u64 {
+    u64::try_from(request.wait_budget().as_millis()).unwrap_or(u64::MAX)
+}
+
 /// Remote lock client implementation
 #[derive(Debug)]
 pub struct RemoteClient {
     addr: String,
     // Track active locks with their original owner information
     active_locks: Arc<RwLock<HashMap<LockId, String>>>, // lock_id -> owner
+    // Capabilities negotiated with the server and when
+    capabilities: Arc<RwLock<Option<(Instant, LockCapabilities)>>>,
 }
 
 impl Clone for RemoteClient {
@@ -43,6 +55,7 @@ impl Clone for RemoteClient {
         Self {
             addr: self.addr.clone(),
             active_locks: self.active_locks.clone(),
+            capabilities: self.capabilities.clone(),
         }
     }
 }
@@ -52,13 +65,32 @@ impl RemoteClient {
         Self {
             addr: endpoint,
             active_locks: Arc::new(RwLock::new(HashMap::new())),
+            capabilities: Arc::new(RwLock::new(None)),
         }
     }
 
     pub fn from_url(url: url::Url) -> Self {
-        Self {
-            addr: url.to_string(),
-            active_locks: Arc::new(RwLock::new(HashMap::new())),
+        Self::new(url.to_string())
+    }
+
+    /// Exchange capabilities with the server through the Hello RPC
+    async fn hello(&self) -> Result<LockCapabilities> {
+        let own = LockCapabilities::current();
+        let mut client = node_service_time_out_client(&self.addr)
+            .await
+            .map_err(|err| LockError::internal(format!("can not get client, err: {err}")))?;
+        let req = Request::new(HelloRequest {
+            protocol_version: own.protocol_version,
+            features: own.features.iter().cloned().collect(),
+        });
+        match client.hello(req).await {
+            Ok(resp) => {
+                let resp = resp.into_inner();
+                Ok(own.common(&LockCapabilities::new(resp.protocol_version, resp.features)))
+            }
+            // Servers predating Hello
+            Err(status) if status.code() == Code::Unimplemented => Ok(LockCapabilities::legacy()),
+            Err(status) => Err(LockError::internal(status.to_string())),
         }
     }
 
@@ -74,6 +106,8 @@ impl RemoteClient {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         }
     }
 }
@@ -88,6 +122,7 @@ impl LockClient for RemoteClient {
         let req = Request::new(GenerallyLockRequest {
             args: serde_json::to_string(&request)
                 .map_err(|e| LockError::internal(format!("Failed to serialize request: {e}")))?,
+            timeout_ms: Some(wait_budget_ms(request)),
         });
         let resp = client
             .lock(req)
@@ -139,6 +174,7 @@ impl LockClient for RemoteClient {
         let req = Request::new(GenerallyLockRequest {
             args: serde_json::to_string(&request)
                 .map_err(|e| LockError::internal(format!("Failed to serialize request: {e}")))?,
+            timeout_ms: Some(wait_budget_ms(request)),
         });
         let resp = client
             .r_lock(req)
@@ -202,12 +238,16 @@ impl LockClient for RemoteClient {
         // Try UnLock first (for exclusive locks)
         let req = Request::new(GenerallyLockRequest {
             args: request_string.clone(),
+            timeout_ms: None,
         });
         let resp = client.un_lock(req).await;
 
         let success = if resp.is_err() {
             // If that fails, try RUnLock (for shared locks)
-            let req = Request::new(GenerallyLockRequest { args: request_string });
+            let req = Request::new(GenerallyLockRequest {
+                args: request_string,
+                timeout_ms: None,
+            });
             let resp = client
                 .r_un_lock(req)
                 .await
@@ -244,6 +284,7 @@ impl LockClient for RemoteClient {
         let req = Request::new(GenerallyLockRequest {
             args: serde_json::to_string(&refresh_request)
                 .map_err(|e| LockError::internal(format!("Failed to serialize request: {e}")))?,
+            timeout_ms: None,
         });
         let resp = client
             .refresh(req)
@@ -265,6 +306,7 @@ impl LockClient for RemoteClient {
         let req = Request::new(GenerallyLockRequest {
             args: serde_json::to_string(&force_request)
                 .map_err(|e| LockError::internal(format!("Failed to serialize request: {e}")))?,
+            timeout_ms: None,
         });
         let resp = client
             .force_un_lock(req)
@@ -291,6 +333,7 @@ impl LockClient for RemoteClient {
         let req = Request::new(GenerallyLockRequest {
             args: serde_json::to_string(&status_request)
                 .map_err(|e| LockError::internal(format!("Failed to serialize request: {e}")))?,
+            timeout_ms: None,
         });
 
         // Try exclusive lock first with very short timeout
@@ -305,6 +348,7 @@ impl LockClient for RemoteClient {
                     let release_req = Request::new(GenerallyLockRequest {
                         args: serde_json::to_string(&status_request)
                             .map_err(|e| LockError::internal(format!("Failed to serialize request: {e}")))?,
+                        timeout_ms: None,
                     });
                     let _ = client.un_lock(release_req).await; // Best effort release
 
@@ -400,4 +444,35 @@ impl LockClient for RemoteClient {
     async fn is_local(&self) -> bool {
         false
     }
+
+    fn endpoint(&self) -> Option<String> {
+        Some(self.addr.clone())
+    }
+
+    async fn capabilities(&self) -> LockCapabilities {
+        let known = self.capabilities.read().await.clone();
+        if let Some((negotiated_at, capabilities)) = &known {
+            if negotiated_at.elapsed() < CAPABILITIES_TTL {
+                return capabilities.clone();
+            }
+        }
+
+        match self.hello().await {
+            Ok(capabilities) => {
+                if known.as_ref().is_none_or(|(_, known)| *known != capabilities) {
+                    info!(
+                        "lock server {} speaks protocol version {} with features {:?}",
+                        self.addr, capabilities.protocol_version, capabilities.features
+                    );
+                }
+                *self.capabilities.write().await = Some((Instant::now(), capabilities.clone()));
+                capabilities
+            }
+            // An unreachable server is assumed unchanged until it answers again
+            Err(err) => {
+                debug!("lock server {} hello failed: {}", self.addr, err);
+                known.map_or_else(LockCapabilities::current, |(_, capabilities)| capabilities)
+            }
+        }
+    }
 }
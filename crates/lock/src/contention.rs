@@ -0,0 +1,327 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lock contention statistics, per resource, of the lock server
+//!
+//! Lock maps record how long every request waited for its resource and whether it gave up. A
+//! resource is only tracked once some request had to wait for it, so uncontended objects cost a
+//! lookup and no memory. Stats cover the current window and the one before it, so the most
+//! contended resources listed reflect the last minutes rather than the whole uptime.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Length of a stats window, resources are listed with the stats of up to two of them
+pub const CONTENTION_WINDOW: Duration = Duration::from_secs(300);
+
+/// Waits at least this long make a resource tracked
+const CONTENDED_WAIT: Duration = Duration::from_millis(1);
+
+/// Number of shards of the stats, so recording rarely contends across resources
+const SHARDS: usize = 64;
+
+/// Resources tracked per shard, the least contended one makes room for a new one
+const MAX_RESOURCES_PER_SHARD: usize = 64;
+
+/// Wait buckets: under 1ms, under 2ms, under 4ms, ... under 2^16 ms, and longer
+const WAIT_BUCKETS: usize = 18;
+
+/// Upper bound of wait bucket `i`, `None` for the last one
+fn bucket_bound(i: usize) -> Option<Duration> {
+    (i < WAIT_BUCKETS - 1).then(|| Duration::from_millis(1 << i))
+}
+
+fn bucket_of(wait: Duration) -> usize {
+    (0..WAIT_BUCKETS - 1)
+        .find(|&i| bucket_bound(i).is_some_and(|bound| wait < bound))
+        .unwrap_or(WAIT_BUCKETS - 1)
+}
+
+/// Wait and timeout stats of one resource
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceContention {
+    /// Locked resource name
+    pub resource: String,
+    /// Requests granted the lock
+    pub acquired: u64,
+    /// Requests that gave up waiting for the lock
+    pub timeouts: u64,
+    /// Time all requests waited, timed out ones included
+    pub total_wait: Duration,
+    /// Longest wait of a request
+    pub max_wait: Duration,
+    /// Number of waits per bucket, doubling from under 1ms
+    pub wait_buckets: Vec<u64>,
+}
+
+impl ResourceContention {
+    pub fn new(resource: &str) -> Self {
+        Self {
+            resource: resource.to_string(),
+            acquired: 0,
+            timeouts: 0,
+            total_wait: Duration::ZERO,
+            max_wait: Duration::ZERO,
+            wait_buckets: vec![0; WAIT_BUCKETS],
+        }
+    }
+
+    fn record(&mut self, wait: Duration, timed_out: bool) {
+        if timed_out {
+            self.timeouts += 1;
+        } else {
+            self.acquired += 1;
+        }
+        self.total_wait += wait;
+        self.max_wait = self.max_wait.max(wait);
+        self.wait_buckets[bucket_of(wait)] += 1;
+    }
+
+    /// Add the stats of `other`, e.g. the same resource on another node
+    pub fn merge(&mut self, other: &ResourceContention) {
+        self.acquired += other.acquired;
+        self.timeouts += other.timeouts;
+        self.total_wait += other.total_wait;
+        self.max_wait = self.max_wait.max(other.max_wait);
+        if self.wait_buckets.len() < other.wait_buckets.len() {
+            self.wait_buckets.resize(other.wait_buckets.len(), 0);
+        }
+        for (count, other) in self.wait_buckets.iter_mut().zip(&other.wait_buckets) {
+            *count += other;
+        }
+    }
+
+    /// Number of requests recorded
+    pub fn requests(&self) -> u64 {
+        self.acquired + self.timeouts
+    }
+
+    /// Share of the requests that timed out
+    pub fn timeout_rate(&self) -> f64 {
+        match self.requests() {
+            0 => 0.0,
+            requests => self.timeouts as f64 / requests as f64,
+        }
+    }
+
+    /// Wait that a `quantile` of the requests stayed under, as the upper bound of its bucket
+    pub fn wait_quantile(&self, quantile: f64) -> Duration {
+        let requests: u64 = self.wait_buckets.iter().sum();
+        if requests == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((requests as f64 * quantile).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in self.wait_buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return bucket_bound(i).map_or(self.max_wait, |bound| bound.min(self.max_wait));
+            }
+        }
+        self.max_wait
+    }
+}
+
+/// Most contended resources of `stats`, merging the ones of the same resource, at most `count`
+pub fn top_contended(stats: impl IntoIterator<Item = ResourceContention>, count: usize) -> Vec<ResourceContention> {
+    let mut merged: HashMap<String, ResourceContention> = HashMap::new();
+    for stat in stats {
+        match merged.get_mut(&stat.resource) {
+            Some(existing) => existing.merge(&stat),
+            None => {
+                merged.insert(stat.resource.clone(), stat);
+            }
+        }
+    }
+
+    let mut top: Vec<_> = merged.into_values().collect();
+    top.sort_by(|a, b| {
+        b.total_wait
+            .cmp(&a.total_wait)
+            .then(b.timeouts.cmp(&a.timeouts))
+            .then_with(|| a.resource.cmp(&b.resource))
+    });
+    top.truncate(count);
+    top
+}
+
+#[derive(Debug)]
+struct Window {
+    current: HashMap<String, ResourceContention>,
+    previous: HashMap<String, ResourceContention>,
+    started: Instant,
+}
+
+impl Window {
+    fn rotate(&mut self, now: Instant, length: Duration) {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed < length {
+            return;
+        }
+        self.previous = std::mem::take(&mut self.current);
+        if elapsed >= length * 2 {
+            self.previous.clear();
+        }
+        self.started = now;
+    }
+
+    /// Make room for one more resource, dropping the one that waited least
+    fn evict(&mut self) {
+        if self.current.len() < MAX_RESOURCES_PER_SHARD {
+            return;
+        }
+        let least = self
+            .current
+            .values()
+            .min_by_key(|stat| (stat.total_wait, stat.timeouts))
+            .map(|stat| stat.resource.clone());
+        if let Some(resource) = least {
+            self.current.remove(&resource);
+        }
+    }
+}
+
+/// Lock contention stats of a lock map
+#[derive(Debug)]
+pub struct ContentionStats {
+    shards: Box<[Mutex<Window>]>,
+    window: Duration,
+}
+
+impl Default for ContentionStats {
+    fn default() -> Self {
+        Self::new(CONTENTION_WINDOW)
+    }
+}
+
+impl ContentionStats {
+    /// Stats listing resources by their last one to two windows of `window`
+    pub fn new(window: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            shards: (0..SHARDS)
+                .map(|_| {
+                    Mutex::new(Window {
+                        current: HashMap::new(),
+                        previous: HashMap::new(),
+                        started: now,
+                    })
+                })
+                .collect(),
+            window,
+        }
+    }
+
+    fn shard(&self, resource: &str) -> &Mutex<Window> {
+        let mut hasher = DefaultHasher::new();
+        resource.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    /// Record a request for `resource` that waited `wait`, then got the lock or timed out
+    pub fn record(&self, resource: &str, wait: Duration, timed_out: bool) {
+        let mut window = self.shard(resource).lock().unwrap_or_else(|e| e.into_inner());
+        window.rotate(Instant::now(), self.window);
+        if let Some(stat) = window.current.get_mut(resource) {
+            stat.record(wait, timed_out);
+            return;
+        }
+        if !timed_out && wait < CONTENDED_WAIT && !window.previous.contains_key(resource) {
+            return;
+        }
+        window.evict();
+        let mut stat = ResourceContention::new(resource);
+        stat.record(wait, timed_out);
+        window.current.insert(resource.to_string(), stat);
+    }
+
+    /// The `count` most contended resources, those whose requests waited longest in total first
+    pub fn top(&self, count: usize) -> Vec<ResourceContention> {
+        let now = Instant::now();
+        let mut stats = Vec::new();
+        for shard in self.shards.iter() {
+            let mut window = shard.lock().unwrap_or_else(|e| e.into_inner());
+            window.rotate(now, self.window);
+            stats.extend(window.previous.values().cloned());
+            stats.extend(window.current.values().cloned());
+        }
+        top_contended(stats, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_contended_resources_are_tracked() {
+        let stats = ContentionStats::default();
+        stats.record("quiet", Duration::ZERO, false);
+        assert!(stats.top(10).is_empty());
+
+        stats.record("busy", Duration::from_millis(30), false);
+        stats.record("busy", Duration::ZERO, false);
+        stats.record("busy", Duration::from_millis(500), true);
+        let top = stats.top(10);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].resource, "busy");
+        assert_eq!((top[0].acquired, top[0].timeouts), (2, 1));
+        assert_eq!(top[0].total_wait, Duration::from_millis(530));
+        assert!((top[0].timeout_rate() - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_wait_quantiles() {
+        let mut stat = ResourceContention::new("bucket");
+        for _ in 0..98 {
+            stat.record(Duration::from_micros(1500), false);
+        }
+        stat.record(Duration::from_millis(100), false);
+        stat.record(Duration::from_secs(120), true);
+        assert_eq!(stat.wait_quantile(0.5), Duration::from_millis(2));
+        assert_eq!(stat.wait_quantile(0.99), Duration::from_millis(128));
+        assert_eq!(stat.wait_quantile(1.0), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_top_merges_and_ranks_by_total_wait() {
+        let mut a = ResourceContention::new("a");
+        a.record(Duration::from_millis(40), false);
+        let mut b = ResourceContention::new("b");
+        b.record(Duration::from_millis(30), false);
+        let mut b2 = ResourceContention::new("b");
+        b2.record(Duration::from_millis(30), true);
+
+        let top = top_contended([a, b, b2], 1);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].resource, "b");
+        assert_eq!(top[0].requests(), 2);
+        assert_eq!(top[0].wait_buckets.iter().sum::<u64>(), 2);
+    }
+
+    #[test]
+    fn test_old_windows_are_dropped() {
+        let stats = ContentionStats::new(Duration::from_millis(20));
+        stats.record("old", Duration::from_millis(5), false);
+        std::thread::sleep(Duration::from_millis(25));
+        // still listed from the previous window
+        assert_eq!(stats.top(10).len(), 1);
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(stats.top(10).is_empty());
+    }
+}
@@ -86,6 +86,14 @@ pub enum LockError {
     /// Not the lock owner
     #[error("Not the lock owner: lock_id {lock_id}, owner {owner}")]
     NotOwner { lock_id: LockId, owner: String },
+
+    /// Lock acquisition canceled by its caller
+    #[error("Lock acquisition for resource '{resource}' was canceled")]
+    Canceled { resource: String },
+
+    /// Lease of a held lock could not be renewed on a quorum
+    #[error("Lost the lease of the lock on resource '{resource}'")]
+    LeaseLost { resource: String },
 }
 
 impl Clone for LockError {
@@ -139,6 +147,12 @@ impl Clone for LockError {
                 lock_id: lock_id.clone(),
                 owner: owner.clone(),
             },
+            LockError::Canceled { resource } => LockError::Canceled {
+                resource: resource.clone(),
+            },
+            LockError::LeaseLost { resource } => LockError::LeaseLost {
+                resource: resource.clone(),
+            },
         }
     }
 }
@@ -213,6 +227,20 @@ impl LockError {
         }
     }
 
+    /// Create canceled error
+    pub fn canceled(resource: impl Into<String>) -> Self {
+        Self::Canceled {
+            resource: resource.into(),
+        }
+    }
+
+    /// Create lease lost error
+    pub fn lease_lost(resource: impl Into<String>) -> Self {
+        Self::LeaseLost {
+            resource: resource.into(),
+        }
+    }
+
     /// Check if it is a retryable error
     pub fn is_retryable(&self) -> bool {
         matches!(self, Self::Timeout { .. } | Self::Network { .. } | Self::Internal { .. })
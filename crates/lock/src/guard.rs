@@ -0,0 +1,196 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::{LockError, Result},
+    namespace::{NamespaceLock, NamespaceLockManager},
+    types::LockType,
+};
+
+/// Locks of a batch of resources held through a [`NamespaceLock`]
+///
+/// The locks are released by [`LockGuard::release`], or in the background when the guard is
+/// dropped. While they are held their leases are renewed; once one of them cannot be renewed on a
+/// quorum the lock may be granted to someone else, which the guard reports through
+/// [`LockGuard::is_lost`], [`LockGuard::lost`] and the callbacks registered with
+/// [`LockGuard::on_lost`].
+#[derive(Debug)]
+pub struct LockGuard {
+    namespace_lock: Arc<NamespaceLock>,
+    resources: Vec<String>,
+    owner: String,
+    lock_type: LockType,
+    /// Namespaced key of each leased lock with the token canceled when its lease is lost
+    leases: Vec<(String, CancellationToken)>,
+    /// Canceled once the locks are released
+    released: CancellationToken,
+}
+
+impl LockGuard {
+    pub(crate) fn new(
+        namespace_lock: Arc<NamespaceLock>,
+        resources: Vec<String>,
+        owner: &str,
+        lock_type: LockType,
+        leases: Vec<(String, CancellationToken)>,
+    ) -> Self {
+        Self {
+            namespace_lock,
+            resources,
+            owner: owner.to_string(),
+            lock_type,
+            leases,
+            released: CancellationToken::new(),
+        }
+    }
+
+    pub fn resources(&self) -> &[String] {
+        &self.resources
+    }
+
+    pub fn lock_type(&self) -> LockType {
+        self.lock_type
+    }
+
+    /// Whether the lease of any of the locks was lost
+    pub fn is_lost(&self) -> bool {
+        self.leases.iter().any(|(_, lost)| lost.is_cancelled())
+    }
+
+    /// `LockError::LeaseLost` when the lease of any of the locks was lost
+    pub fn check(&self) -> Result<()> {
+        match self.leases.iter().find(|(_, lost)| lost.is_cancelled()) {
+            Some((resource, _)) => Err(LockError::lease_lost(resource)),
+            None => Ok(()),
+        }
+    }
+
+    /// Complete once the lease of any of the locks is lost, never for locks without lease
+    pub async fn lost(&self) {
+        any_lost(&self.leases).await
+    }
+
+    /// Call `callback` once the lease of any of the locks is lost, unless they are released first
+    pub fn on_lost(&self, callback: impl FnOnce() + Send + 'static) {
+        let leases = self.leases.clone();
+        let released = self.released.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = any_lost(&leases) => callback(),
+                _ = released.cancelled() => {}
+            }
+        });
+    }
+
+    /// Release the locks
+    pub async fn release(self) {
+        self.released.cancel();
+        unlock(&self.namespace_lock, &self.resources, &self.owner, self.lock_type).await;
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if self.released.is_cancelled() {
+            return;
+        }
+        self.released.cancel();
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            tracing::warn!(
+                "Dropped the locks of {:?} outside a runtime, they expire with their leases",
+                self.resources
+            );
+            return;
+        };
+        let namespace_lock = self.namespace_lock.clone();
+        let resources = std::mem::take(&mut self.resources);
+        let owner = std::mem::take(&mut self.owner);
+        let lock_type = self.lock_type;
+        handle.spawn(async move { unlock(&namespace_lock, &resources, &owner, lock_type).await });
+    }
+}
+
+async fn any_lost(leases: &[(String, CancellationToken)]) {
+    if leases.is_empty() {
+        return std::future::pending().await;
+    }
+    futures::future::select_all(leases.iter().map(|(_, lost)| Box::pin(lost.cancelled()))).await;
+}
+
+async fn unlock(namespace_lock: &NamespaceLock, resources: &[String], owner: &str, lock_type: LockType) {
+    let result = match lock_type {
+        LockType::Exclusive => namespace_lock.unlock_batch(resources, owner).await,
+        LockType::Shared => namespace_lock.runlock_batch(resources, owner).await,
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to release the locks of {:?}: {}", resources, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LocalClient, types::LockId};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_guard_reports_lost_lease() {
+        let ns_lock = Arc::new(NamespaceLock::with_client(Arc::new(LocalClient::new())));
+        let resources = vec!["guarded".to_string()];
+        let ttl = Duration::from_millis(300);
+        let guard = ns_lock
+            .get_lock(&resources, "owner1", LockType::Exclusive, Duration::from_millis(100), ttl, None)
+            .await
+            .unwrap()
+            .unwrap();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        guard.on_lost(move || {
+            let _ = tx.send(());
+        });
+        assert!(guard.check().is_ok());
+
+        // Someone else takes over the lock, so the next renewal fails
+        let lock_id = LockId::new_deterministic(&ns_lock.get_resource_key("guarded"));
+        assert!(crate::force_unlock_local(&lock_id).await);
+        tokio::time::timeout(ttl * 3, guard.lost()).await.unwrap();
+        assert!(guard.is_lost());
+        assert!(matches!(guard.check(), Err(LockError::LeaseLost { .. })));
+        tokio::time::timeout(ttl, rx).await.unwrap().unwrap();
+        guard.release().await;
+    }
+
+    #[tokio::test]
+    async fn test_dropped_guard_releases_locks() {
+        let ns_lock = Arc::new(NamespaceLock::with_client(Arc::new(LocalClient::new())));
+        let resources = vec!["guard-dropped".to_string()];
+        let (timeout, ttl) = (Duration::from_millis(100), Duration::from_secs(5));
+        let guard = ns_lock
+            .get_lock(&resources, "owner1", LockType::Exclusive, timeout, ttl, None)
+            .await
+            .unwrap();
+        assert!(guard.is_some());
+        drop(guard);
+
+        let guard = ns_lock
+            .get_lock(&resources, "owner2", LockType::Exclusive, Duration::from_secs(1), ttl, None)
+            .await
+            .unwrap()
+            .expect("dropped guard released its lock");
+        assert!(!guard.is_lost());
+        guard.release().await;
+    }
+}
@@ -0,0 +1,468 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-process lock map for single node deployments
+//!
+//! Nothing outside the process ever asks for these locks, so there is no need for the lock RPC
+//! server to see them. Locks live in shards guarded by `parking_lot` locks that are never held
+//! across an await, and waiters sleep until a release on their shard wakes them instead of
+//! polling.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+use parking_lot::RwLock;
+use tokio::sync::Notify;
+
+use crate::audit::{LockAuditKind, audit};
+use crate::contention::{ContentionStats, ResourceContention};
+use crate::error::{LockError, Result};
+use crate::types::{HeldLock, LockId, LockInfo, LockMetadata, LockPriority, LockRequest, LockStatus, LockType};
+
+const SHARDS: usize = 64;
+
+#[derive(Debug)]
+struct Entry {
+    writer: Option<String>,
+    readers: HashMap<String, usize>,
+    expires_at: Option<Instant>,
+    acquired_at: SystemTime,
+    ttl: Duration,
+    waiting_readers: usize,
+    /// Writers waiting for the lock, new readers queue behind them
+    waiting_writers: usize,
+}
+
+impl Entry {
+    fn new() -> Self {
+        Self {
+            writer: None,
+            readers: HashMap::new(),
+            expires_at: None,
+            acquired_at: SystemTime::now(),
+            ttl: Duration::ZERO,
+            waiting_readers: 0,
+            waiting_writers: 0,
+        }
+    }
+
+    fn is_held(&self) -> bool {
+        self.writer.is_some() || !self.readers.is_empty()
+    }
+
+    fn is_idle(&self) -> bool {
+        !self.is_held() && self.waiting_readers == 0 && self.waiting_writers == 0
+    }
+
+    /// Drop the holders of a lease nobody renewed, true if there were any
+    fn expire(&mut self, lock_id: &LockId, now: Instant) -> bool {
+        if self.expires_at.is_some_and(|exp| exp <= now) && self.is_held() {
+            audit(
+                LockAuditKind::LeaseExpired,
+                &lock_id.resource,
+                self.owners(),
+                Vec::new(),
+                format!("lease not renewed, held for {:?}", self.acquired_at.elapsed().unwrap_or_default()),
+            );
+            self.writer = None;
+            self.readers.clear();
+            self.expires_at = None;
+            return true;
+        }
+        false
+    }
+
+    fn owners(&self) -> Vec<String> {
+        self.holders().map(|(owner, _)| owner.clone()).collect()
+    }
+
+    fn holders(&self) -> impl Iterator<Item = (&String, LockType)> {
+        self.writer
+            .iter()
+            .map(|owner| (owner, LockType::Exclusive))
+            .chain(self.readers.keys().map(|owner| (owner, LockType::Shared)))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shard {
+    locks: RwLock<HashMap<LockId, Entry>>,
+    released: Notify,
+}
+
+/// Registers a request as waiting on its lock for as long as it lives
+struct Waiting<'a> {
+    shard: &'a Shard,
+    lock_id: &'a LockId,
+    lock_type: LockType,
+}
+
+impl<'a> Waiting<'a> {
+    fn new(shard: &'a Shard, request: &'a LockRequest) -> Self {
+        let mut locks = shard.locks.write();
+        let entry = locks.entry(request.lock_id.clone()).or_insert_with(Entry::new);
+        match request.lock_type {
+            LockType::Exclusive => entry.waiting_writers += 1,
+            LockType::Shared => entry.waiting_readers += 1,
+        }
+        Self {
+            shard,
+            lock_id: &request.lock_id,
+            lock_type: request.lock_type,
+        }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.shard.locks.write();
+        let Some(entry) = locks.get_mut(self.lock_id) else {
+            return;
+        };
+        match self.lock_type {
+            LockType::Exclusive => entry.waiting_writers -= 1,
+            LockType::Shared => entry.waiting_readers -= 1,
+        }
+        let idle = entry.is_idle();
+        let writers_gone = entry.waiting_writers == 0;
+        if idle {
+            locks.remove(self.lock_id);
+        }
+        drop(locks);
+        // Readers held back by the last waiting writer may go now
+        if writers_gone && self.lock_type == LockType::Exclusive {
+            self.shard.released.notify_waiters();
+        }
+    }
+}
+
+async fn canceled(request: &LockRequest) {
+    match &request.cancel {
+        Some(cancel) => cancel.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Sharded in-process lock map
+#[derive(Debug)]
+pub struct InProcessLockMap {
+    shards: Arc<[Shard]>,
+    shutdown: Arc<AtomicBool>,
+    contention: ContentionStats,
+}
+
+impl Default for InProcessLockMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InProcessLockMap {
+    pub fn new() -> Self {
+        let map = Self {
+            shards: (0..SHARDS).map(|_| Shard::default()).collect(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            contention: ContentionStats::default(),
+        };
+        map.spawn_expiry_task();
+        map
+    }
+
+    /// Forget leases nobody renewed, waking whoever waits for them
+    fn spawn_expiry_task(&self) {
+        let shards = self.shards.clone();
+        let shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            while !shutdown.load(Ordering::Relaxed) {
+                interval.tick().await;
+                let now = Instant::now();
+                for shard in shards.iter() {
+                    let mut expired = false;
+                    shard.locks.write().retain(|lock_id, entry| {
+                        expired |= entry.expire(lock_id, now);
+                        !entry.is_idle()
+                    });
+                    if expired {
+                        shard.released.notify_waiters();
+                    }
+                }
+            }
+        });
+    }
+
+    fn shard(&self, lock_id: &LockId) -> &Shard {
+        let mut hasher = DefaultHasher::new();
+        lock_id.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    /// Take the lock if it is free, otherwise tell when the lease in the way expires
+    fn try_acquire(&self, shard: &Shard, request: &LockRequest) -> std::result::Result<(), Option<Instant>> {
+        let now = Instant::now();
+        let mut locks = shard.locks.write();
+        let entry = locks.entry(request.lock_id.clone()).or_insert_with(Entry::new);
+        entry.expire(&request.lock_id, now);
+
+        let free = match request.lock_type {
+            LockType::Exclusive => !entry.is_held(),
+            // Owners already reading may re-enter past waiting writers
+            LockType::Shared => {
+                entry.writer.is_none() && (entry.waiting_writers == 0 || entry.readers.contains_key(&request.owner))
+            }
+        };
+        if !free {
+            let expires_at = entry.expires_at;
+            if entry.is_idle() {
+                locks.remove(&request.lock_id);
+            }
+            return Err(expires_at);
+        }
+
+        if !entry.is_held() {
+            entry.acquired_at = SystemTime::now();
+        }
+        match request.lock_type {
+            LockType::Exclusive => entry.writer = Some(request.owner.clone()),
+            LockType::Shared => *entry.readers.entry(request.owner.clone()).or_insert(0) += 1,
+        }
+        // Readers share one lease, it lasts as long as the longest one requested
+        let expires_at = now + request.ttl;
+        if entry.expires_at.is_none_or(|exp| exp < expires_at) {
+            entry.expires_at = Some(expires_at);
+        }
+        entry.ttl = entry.ttl.max(request.ttl);
+        Ok(())
+    }
+
+    /// Acquire the lock of `request`, false once its wait budget is spent
+    pub async fn acquire(&self, request: &LockRequest) -> Result<bool> {
+        let shard = self.shard(&request.lock_id);
+        let start = Instant::now();
+        let deadline = start + request.wait_budget();
+        let mut waiting = None;
+
+        loop {
+            // Listen before trying, so a release right after a failed attempt is not missed
+            let released = shard.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            let expires_at = match self.try_acquire(shard, request) {
+                Ok(()) => {
+                    self.contention.record(&request.resource, start.elapsed(), false);
+                    return Ok(true);
+                }
+                Err(expires_at) => expires_at,
+            };
+            if Instant::now() >= deadline {
+                self.contention.record(&request.resource, start.elapsed(), true);
+                return Ok(false);
+            }
+            waiting.get_or_insert_with(|| Waiting::new(shard, request));
+
+            let wake_at = expires_at.map_or(deadline, |exp| exp.min(deadline));
+            tokio::select! {
+                _ = released => {}
+                _ = tokio::time::sleep_until(wake_at.into()) => {}
+                _ = canceled(request) => return Err(LockError::canceled(&request.resource)),
+            }
+        }
+    }
+
+    /// Release the lock, the writer first, otherwise one reader
+    pub fn release(&self, lock_id: &LockId) -> bool {
+        self.update(lock_id, |entry| {
+            if entry.writer.take().is_some() {
+                return true;
+            }
+            let Some(owner) = entry.readers.keys().next().cloned() else {
+                return false;
+            };
+            let count = entry.readers.get_mut(&owner).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                entry.readers.remove(&owner);
+            }
+            true
+        })
+    }
+
+    /// Release the lock whatever its holders
+    pub fn force_release(&self, lock_id: &LockId) -> bool {
+        self.update(lock_id, |entry| {
+            let held = entry.is_held();
+            if held {
+                tracing::warn!(
+                    "Force unlocking '{}', writer: {:?}, readers: {:?}",
+                    lock_id.resource,
+                    entry.writer,
+                    entry.readers.keys().collect::<Vec<_>>()
+                );
+            }
+            entry.writer = None;
+            entry.readers.clear();
+            held
+        })
+    }
+
+    /// Apply a release to the lock, waking its waiters if anything was released
+    fn update(&self, lock_id: &LockId, release: impl FnOnce(&mut Entry) -> bool) -> bool {
+        let shard = self.shard(lock_id);
+        let mut locks = shard.locks.write();
+        let Some(entry) = locks.get_mut(lock_id) else {
+            return false;
+        };
+        let released = release(entry);
+        if !entry.is_held() {
+            entry.expires_at = None;
+        }
+        if entry.is_idle() {
+            locks.remove(lock_id);
+        }
+        drop(locks);
+        if released {
+            shard.released.notify_waiters();
+        }
+        released
+    }
+
+    /// Renew the lease of a held lock for the ttl it was acquired with
+    pub fn refresh(&self, lock_id: &LockId) -> bool {
+        let now = Instant::now();
+        let mut locks = self.shard(lock_id).locks.write();
+        let Some(entry) = locks.get_mut(lock_id) else {
+            return false;
+        };
+        if !entry.is_held() || entry.expires_at.is_some_and(|exp| exp <= now) {
+            return false;
+        }
+        entry.expires_at = Some(now + entry.ttl);
+        true
+    }
+
+    /// The lock as seen by its holder: its writer, otherwise one of its readers
+    pub fn lock_info(&self, lock_id: &LockId) -> Option<LockInfo> {
+        let now = Instant::now();
+        let locks = self.shard(lock_id).locks.read();
+        let entry = locks.get(lock_id)?;
+        let expires_at = entry.expires_at.filter(|exp| *exp > now)?;
+        let (owner, lock_type) = entry.holders().next()?;
+        Some(LockInfo {
+            id: lock_id.clone(),
+            resource: lock_id.resource.clone(),
+            lock_type,
+            status: LockStatus::Acquired,
+            owner: owner.clone(),
+            acquired_at: entry.acquired_at,
+            expires_at: SystemTime::now() + (expires_at - now),
+            last_refreshed: SystemTime::now(),
+            metadata: LockMetadata::default(),
+            priority: LockPriority::Normal,
+            wait_start_time: None,
+        })
+    }
+
+    /// Locks held in this process, one entry per holder
+    pub fn list_locks(&self) -> Vec<HeldLock> {
+        let now = Instant::now();
+        let mut held = Vec::new();
+        for shard in self.shards.iter() {
+            for (lock_id, entry) in shard.locks.read().iter() {
+                if entry.expires_at.is_some_and(|exp| exp <= now) {
+                    continue;
+                }
+                for (owner, lock_type) in entry.holders() {
+                    held.push(HeldLock {
+                        resource: lock_id.resource.clone(),
+                        uid: lock_id.uuid.clone(),
+                        owner: owner.clone(),
+                        lock_type,
+                        acquired_at: entry.acquired_at,
+                        waiters: entry.waiting_readers + entry.waiting_writers,
+                    });
+                }
+            }
+        }
+        held
+    }
+
+    /// The `count` most contended resources of the last windows, see [`ContentionStats`]
+    pub fn top_contended(&self, count: usize) -> Vec<ResourceContention> {
+        self.contention.top(count)
+    }
+
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::sync::CancellationToken;
+
+    fn request(resource: &str, lock_type: LockType, owner: &str, timeout: Duration) -> LockRequest {
+        LockRequest::new(resource, lock_type, owner).with_acquire_timeout(timeout)
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_and_shared() {
+        let map = InProcessLockMap::new();
+        let timeout = Duration::from_millis(50);
+        let write = request("res", LockType::Exclusive, "w", timeout);
+        let read = |owner| request("res", LockType::Shared, owner, timeout);
+
+        assert!(map.acquire(&write).await.unwrap());
+        assert!(!map.acquire(&read("r1")).await.unwrap());
+        assert_eq!(map.list_locks().len(), 1);
+
+        // A release wakes the waiting reader before its timeout
+        let lock_id = write.lock_id.clone();
+        let waiting = read("r1").with_acquire_timeout(Duration::from_secs(5));
+        let (acquired, _) = tokio::join!(map.acquire(&waiting), async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            assert!(map.release(&lock_id));
+        });
+        assert!(acquired.unwrap());
+        assert!(map.acquire(&read("r2")).await.unwrap());
+        assert!(!map.acquire(&write).await.unwrap());
+
+        assert!(map.force_release(&lock_id));
+        assert!(map.list_locks().is_empty());
+        assert!(map.acquire(&write).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_lease_and_cancel() {
+        let map = InProcessLockMap::new();
+        let holder = request("res", LockType::Exclusive, "holder", Duration::ZERO).with_ttl(Duration::from_millis(30));
+        assert!(map.acquire(&holder).await.unwrap());
+
+        // The waiter gets the lock once the unrenewed lease runs out
+        let waiter = request("res", LockType::Exclusive, "waiter", Duration::from_secs(5));
+        let start = Instant::now();
+        assert!(map.acquire(&waiter).await.unwrap());
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(map.refresh(&waiter.lock_id));
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let canceled = request("res", LockType::Shared, "reader", Duration::from_secs(5)).with_cancel(cancel);
+        assert!(matches!(map.acquire(&canceled).await, Err(LockError::Canceled { .. })));
+    }
+}
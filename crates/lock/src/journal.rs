@@ -0,0 +1,354 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Append-only journal of lock grants
+//!
+//! A lock server coming back with an empty lock map would hand out locks its peers still hold
+//! leases on. With a journal configured, every change to a lock is appended as the new state of
+//! that lock, and on startup the journal is replayed so leases that have not run out are held
+//! again until their holders release them or stop refreshing them. Records of expired or released
+//! locks are dropped by rewriting the journal once they make up most of it.
+//!
+//! Records are written by a background thread, which appends all the records pending at once and
+//! syncs them with a single fsync. Recording a change waits for the sync of its batch, so a grant is
+//! on disk before it is acknowledged while concurrent grants share one fsync.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::types::LockId;
+
+/// Environment variable holding the path of the lock journal, no journal is kept when unset
+pub const ENV_LOCK_JOURNAL: &str = "RUSTFS_LOCK_JOURNAL";
+
+/// Records below which the journal is never rewritten
+const COMPACT_MIN_RECORDS: usize = 1024;
+
+/// State of one lock after a change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalRecord {
+    pub id: LockId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub writer: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub readers: HashMap<String, usize>,
+    /// When the lease ends, unix milliseconds
+    #[serde(default)]
+    pub expires_at: u64,
+    /// When the lock went from free to held, unix milliseconds
+    #[serde(default)]
+    pub acquired_at: u64,
+    /// Lease length renewed on refresh, milliseconds
+    #[serde(default)]
+    pub ttl: u64,
+}
+
+impl JournalRecord {
+    pub fn is_held(&self) -> bool {
+        self.writer.is_some() || !self.readers.is_empty()
+    }
+
+    /// Whether the lock is held by a lease that has not run out at `now`
+    pub fn is_live(&self, now: SystemTime) -> bool {
+        self.is_held() && self.expires_at > unix_millis(now)
+    }
+
+    /// Time left on the lease at `now`
+    pub fn remaining(&self, now: SystemTime) -> Duration {
+        Duration::from_millis(self.expires_at.saturating_sub(unix_millis(now)))
+    }
+}
+
+pub fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[derive(Debug)]
+struct Journal {
+    /// Latest state of every lock held when it was recorded
+    live: HashMap<LockId, JournalRecord>,
+    /// Records in the file
+    records: usize,
+    /// Changes for the writer thread, in the order they were made
+    writes: Sender<JournalWrite>,
+}
+
+/// Change of the journal file made by the writer thread
+#[derive(Debug)]
+enum JournalWrite {
+    /// Append a serialized record, acknowledged once it is synced
+    Append(Vec<u8>, oneshot::Sender<()>),
+    /// Replace the journal with the given records
+    Rewrite(HashMap<LockId, JournalRecord>),
+}
+
+/// Lock journal backed by a file
+#[derive(Debug)]
+pub struct LockJournal {
+    inner: Mutex<Journal>,
+    /// Writer thread, joined on close once it wrote out every pending record
+    writer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl LockJournal {
+    /// Open the journal at `path`, returning the leases still live in it
+    ///
+    /// A torn record at the end, left by a crash in the middle of a write, is skipped.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<(Self, Vec<JournalRecord>)> {
+        let path = path.as_ref().to_path_buf();
+        let mut live = HashMap::new();
+        match File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    match serde_json::from_str::<JournalRecord>(&line) {
+                        Ok(record) => apply(&mut live, record),
+                        Err(e) => tracing::warn!("Skipping unreadable lock journal record in {:?}: {}", path, e),
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let now = SystemTime::now();
+        live.retain(|_, record| record.is_live(now));
+        let file = rewrite(&path, &live)?;
+        let (writes, pending) = channel();
+        let writer = std::thread::Builder::new()
+            .name("lock-journal".to_string())
+            .spawn(move || write_journal(path, file, pending))?;
+        let replayed = live.values().cloned().collect();
+        let records = live.len();
+        Ok((
+            Self {
+                inner: Mutex::new(Journal { live, records, writes }),
+                writer: Mutex::new(Some(writer)),
+            },
+            replayed,
+        ))
+    }
+
+    /// Append the new state of a lock, returning once it is synced to disk
+    pub async fn record(&self, record: JournalRecord) {
+        let line = match serde_json::to_vec(&record) {
+            Ok(mut line) => {
+                line.push(b'\n');
+                line
+            }
+            Err(e) => {
+                tracing::warn!("Failed to serialize lock journal record: {}", e);
+                return;
+            }
+        };
+        let (synced, ack) = oneshot::channel();
+        {
+            let mut journal = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            if journal.writes.send(JournalWrite::Append(line, synced)).is_err() {
+                tracing::warn!("Lock journal writer is gone, dropping a record");
+                return;
+            }
+            journal.records += 1;
+            apply(&mut journal.live, record);
+        }
+        let _ = ack.await;
+    }
+
+    /// Forget expired leases, rewriting the journal once they make up most of it
+    pub fn compact(&self) {
+        let mut journal = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now = SystemTime::now();
+        journal.live.retain(|_, record| record.is_live(now));
+        if journal.records < COMPACT_MIN_RECORDS || journal.records < journal.live.len() * 2 {
+            return;
+        }
+        tracing::debug!("Compacting lock journal from {} to {} records", journal.records, journal.live.len());
+        let live = journal.live.clone();
+        if journal.writes.send(JournalWrite::Rewrite(live)).is_ok() {
+            journal.records = journal.live.len();
+        }
+    }
+
+    /// Records in the journal file
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).records
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write out every pending record and stop the writer, later records are dropped
+    pub fn close(&self) {
+        // Closing the channel stops the writer once it wrote out what is pending
+        let (closed, _) = channel();
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).writes = closed;
+        let writer = self.writer.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(writer) = writer {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl Drop for LockJournal {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Write the changes sent to the journal at `path` until the journal is dropped
+///
+/// Every change pending when the thread wakes up goes out in one write and one sync, after which
+/// the records of the batch are acknowledged.
+fn write_journal(path: PathBuf, mut file: File, pending: Receiver<JournalWrite>) {
+    let mut batch = Vec::new();
+    let mut acks = Vec::new();
+    while let Ok(write) = pending.recv() {
+        for write in std::iter::once(write).chain(pending.try_iter()) {
+            match write {
+                JournalWrite::Append(line, synced) => {
+                    batch.extend_from_slice(&line);
+                    acks.push(synced);
+                }
+                JournalWrite::Rewrite(live) => {
+                    // Kept in the old journal should the rewrite fail
+                    append(&path, &mut file, &mut batch);
+                    match rewrite(&path, &live) {
+                        Ok(rewritten) => file = rewritten,
+                        Err(e) => tracing::warn!("Failed to compact lock journal {:?}: {}", path, e),
+                    }
+                }
+            }
+        }
+        append(&path, &mut file, &mut batch);
+        for synced in acks.drain(..) {
+            let _ = synced.send(());
+        }
+    }
+}
+
+/// Append and sync the records of `batch`, emptying it
+fn append(path: &Path, file: &mut File, batch: &mut Vec<u8>) {
+    if batch.is_empty() {
+        return;
+    }
+    if let Err(e) = file.write_all(batch).and_then(|_| file.sync_data()) {
+        tracing::warn!("Failed to append to lock journal {:?}: {}", path, e);
+    }
+    batch.clear();
+}
+
+fn apply(live: &mut HashMap<LockId, JournalRecord>, record: JournalRecord) {
+    if record.is_held() {
+        live.insert(record.id.clone(), record);
+    } else {
+        live.remove(&record.id);
+    }
+}
+
+/// Replace the journal with the given records, returning it opened for appending
+fn rewrite(path: &Path, live: &HashMap<LockId, JournalRecord>) -> io::Result<File> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    {
+        let mut file = File::create(&tmp)?;
+        for record in live.values() {
+            let mut line = serde_json::to_vec(record).map_err(io::Error::other)?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+        }
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+    OpenOptions::new().append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(resource: &str, writer: Option<&str>, expires_in: Duration) -> JournalRecord {
+        JournalRecord {
+            id: LockId::new_deterministic(resource),
+            writer: writer.map(str::to_string),
+            readers: HashMap::new(),
+            expires_at: unix_millis(SystemTime::now() + expires_in),
+            acquired_at: unix_millis(SystemTime::now()),
+            ttl: expires_in.as_millis() as u64,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_journal_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("locks.journal");
+        let hour = Duration::from_secs(3600);
+
+        {
+            let (journal, replayed) = LockJournal::open(&path).unwrap();
+            assert!(replayed.is_empty());
+            journal.record(record("held", Some("a"), hour)).await;
+            journal.record(record("released", Some("b"), hour)).await;
+            journal.record(record("released", None, hour)).await;
+            journal.record(record("expired", Some("c"), Duration::ZERO)).await;
+        }
+        // A write cut short by a crash
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"id\":")
+            .unwrap();
+
+        let (journal, replayed) = LockJournal::open(&path).unwrap();
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].id, LockId::new_deterministic("held"));
+        assert_eq!(replayed[0].writer.as_deref(), Some("a"));
+        assert!(replayed[0].remaining(SystemTime::now()) > Duration::from_secs(3500));
+        // Reopening rewrote the journal with the live lease only
+        assert_eq!(journal.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_journal_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("locks.journal");
+        let (journal, _) = LockJournal::open(&path).unwrap();
+
+        journal.record(record("held", Some("a"), Duration::from_secs(3600))).await;
+        for _ in 0..COMPACT_MIN_RECORDS {
+            journal.record(record("busy", Some("b"), Duration::from_secs(3600))).await;
+            journal.record(record("busy", None, Duration::ZERO)).await;
+        }
+        journal.compact();
+        assert_eq!(journal.len(), 1);
+
+        // Appends go to the rewritten journal
+        journal.record(record("more", Some("c"), Duration::from_secs(3600))).await;
+        drop(journal);
+        let (_, replayed) = LockJournal::open(&path).unwrap();
+        assert_eq!(replayed.len(), 2);
+    }
+}
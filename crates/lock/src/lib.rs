@@ -22,9 +22,26 @@ pub mod namespace;
 // Abstraction Layer Modules
 pub mod client;
 
+// Audit trail of lock events
+pub mod audit;
+
+// Guards of held locks
+pub mod guard;
+
 // Local Layer Modules
+pub mod inprocess;
+pub mod journal;
 pub mod local;
 
+// Lock acquisition metrics
+pub mod metrics;
+
+// Per resource lock contention stats
+pub mod contention;
+
+// Protocol versions and features negotiated between nodes
+pub mod protocol;
+
 // Core Modules
 pub mod error;
 pub mod types;
@@ -36,16 +53,20 @@ pub mod types;
 // Re-export main types for easy access
 pub use crate::{
     // Client interfaces
-    client::{LockClient, local::LocalClient, remote::RemoteClient},
+    client::{LockClient, inprocess::InProcessClient, local::LocalClient, remote::RemoteClient},
+    contention::ResourceContention,
     // Error types
     error::{LockError, Result},
-    local::LocalLockMap,
+    guard::LockGuard,
+    inprocess::InProcessLockMap,
+    local::{LocalLockMap, LockState},
     // Main components
     namespace::{NamespaceLock, NamespaceLockManager},
+    protocol::LockCapabilities,
     // Core types
     types::{
-        HealthInfo, HealthStatus, LockId, LockInfo, LockMetadata, LockPriority, LockRequest, LockResponse, LockStats, LockStatus,
-        LockType,
+        HealthInfo, HealthStatus, HeldLock, LockId, LockInfo, LockMetadata, LockPriority, LockRequest, LockResponse, LockStats,
+        LockStatus, LockType, QuorumPolicy,
     },
 };
 
@@ -77,6 +98,50 @@ pub fn get_global_lock_map() -> Arc<local::LocalLockMap> {
     GLOBAL_LOCK_MAP.get_or_init(|| Arc::new(local::LocalLockMap::new())).clone()
 }
 
+static GLOBAL_INPROCESS_LOCK_MAP: OnceCell<Arc<inprocess::InProcessLockMap>> = OnceCell::new();
+
+/// Get the global in-process lock map used by single node deployments
+pub fn get_global_inprocess_lock_map() -> Arc<inprocess::InProcessLockMap> {
+    GLOBAL_INPROCESS_LOCK_MAP
+        .get_or_init(|| Arc::new(inprocess::InProcessLockMap::new()))
+        .clone()
+}
+
+/// Locks held in this process, whichever local lock map holds them
+pub async fn list_local_locks() -> Vec<HeldLock> {
+    let mut locks = get_global_lock_map().list_locks().await;
+    if let Some(map) = GLOBAL_INPROCESS_LOCK_MAP.get() {
+        locks.extend(map.list_locks());
+    }
+    locks
+}
+
+/// The `count` most contended resources of this process, whichever local lock map they are in
+pub fn top_contended_local(count: usize) -> Vec<ResourceContention> {
+    let mut stats = get_global_lock_map().top_contended(count);
+    if let Some(map) = GLOBAL_INPROCESS_LOCK_MAP.get() {
+        stats.extend(map.top_contended(count));
+    }
+    contention::top_contended(stats, count)
+}
+
+/// Force release a lock held in this process, true if it was held
+pub async fn force_unlock_local(lock_id: &LockId) -> bool {
+    let released = get_global_lock_map().force_unlock_by_id(lock_id).await;
+    match GLOBAL_INPROCESS_LOCK_MAP.get() {
+        Some(map) => map.force_release(lock_id) || released,
+        None => released,
+    }
+}
+
+/// Stop the background tasks of the local lock maps
+pub async fn shutdown_local_locks() {
+    get_global_lock_map().shutdown().await;
+    if let Some(map) = GLOBAL_INPROCESS_LOCK_MAP.get() {
+        map.shutdown();
+    }
+}
+
 // ============================================================================
 // Convenience Functions
 // ============================================================================
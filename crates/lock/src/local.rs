@@ -12,13 +12,61 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use rustfs_retry::Backoff;
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::RwLock;
 
 use crate::LockRequest;
+use crate::audit::{LockAuditKind, audit};
+use crate::contention::{ContentionStats, ResourceContention};
+use crate::journal::{ENV_LOCK_JOURNAL, JournalRecord, LockJournal, unix_millis};
+use crate::types::{HeldLock, LockId, LockType};
+
+/// Environment variable holding how long, in milliseconds, a writer waits before new readers queue behind it
+pub const ENV_LOCK_WRITER_PRIORITY_AFTER: &str = "RUSTFS_LOCK_WRITER_PRIORITY_AFTER";
+
+/// Number of shards of the lock map, so operations on different resources rarely contend on it
+const SHARDS: usize = 64;
+
+/// One shard of the lock map, LockId to lock object
+type Shard = RwLock<HashMap<LockId, Arc<RwLock<LocalLockEntry>>>>;
+
+/// Default wait of a writer before new readers queue behind it
+pub const DEFAULT_WRITER_PRIORITY_AFTER: Duration = Duration::from_millis(100);
+
+/// A waiting writer that has not retried for this long is assumed gone, e.g. its request was canceled
+const WRITER_WAIT_STALE: Duration = Duration::from_millis(100);
+
+/// Delay between attempts on a held lock, capped well below `WRITER_WAIT_STALE`; jittered so
+/// waiters released together do not retry in lockstep
+const POLL_BACKOFF: Backoff = Backoff::new(Duration::from_millis(1), Duration::from_millis(10)).with_jitter(0.5);
+
+/// Sleep before the next attempt on a held lock, false if the request got canceled meanwhile
+async fn wait_for_retry(request: &LockRequest, polls: u32) -> bool {
+    let delay = POLL_BACKOFF.delay(polls);
+    match &request.cancel {
+        Some(cancel) => tokio::select! {
+            _ = tokio::time::sleep(delay) => true,
+            _ = cancel.cancelled() => false,
+        },
+        None => {
+            tokio::time::sleep(delay).await;
+            true
+        }
+    }
+}
+
+/// whether nobody holds a lock, false while its state is being changed
+fn is_idle(entry: &Arc<RwLock<LocalLockEntry>>) -> bool {
+    entry
+        .try_read()
+        .is_ok_and(|entry| entry.writer.is_none() && entry.readers.is_empty())
+}
 
 /// local lock entry
 #[derive(Debug)]
@@ -29,15 +77,148 @@ pub struct LocalLockEntry {
     pub readers: HashMap<String, usize>,
     /// lock expiration time
     pub expires_at: Option<Instant>,
+    /// when the lock went from free to held
+    pub acquired_at: Option<SystemTime>,
+    /// lease length granted on acquisition, used again on refresh
+    pub ttl: Duration,
+    /// since when writers have been waiting for the lock
+    pub writer_waiting_since: Option<Instant>,
+    /// last time a waiting writer retried
+    pub writer_last_retry: Option<Instant>,
+}
+
+impl LocalLockEntry {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            writer: None,
+            readers: HashMap::new(),
+            expires_at: None,
+            acquired_at: None,
+            ttl,
+            writer_waiting_since: None,
+            writer_last_retry: None,
+        }
+    }
+
+    /// owners holding the lock
+    fn holders(&self) -> Vec<String> {
+        self.writer.iter().chain(self.readers.keys()).cloned().collect()
+    }
+
+    /// drop the holders of a lease nobody renewed, true if the lease was over
+    fn expire(&mut self, lock_id: &LockId, now: Instant) -> bool {
+        if self.expires_at.is_none_or(|exp| exp > now) {
+            return false;
+        }
+        let holders = self.holders();
+        if !holders.is_empty() {
+            let held_for = self.acquired_at.and_then(|at| at.elapsed().ok()).unwrap_or_default();
+            audit(
+                LockAuditKind::LeaseExpired,
+                &lock_id.resource,
+                holders,
+                Vec::new(),
+                format!("lease of {:?} not renewed, held for {:?}", self.ttl, held_for),
+            );
+        }
+        self.writer = None;
+        self.readers.clear();
+        self.expires_at = None;
+        self.acquired_at = None;
+        true
+    }
+
+    /// record a failed write lock attempt
+    fn writer_waiting(&mut self, now: Instant) {
+        if self.writer_waiting_since.is_none() || self.writer_last_retry.is_none_or(|t| now - t > WRITER_WAIT_STALE) {
+            self.writer_waiting_since = Some(now);
+        }
+        self.writer_last_retry = Some(now);
+    }
+
+    /// the entry as recorded in the lock journal
+    fn journal_record(&self, lock_id: &LockId) -> JournalRecord {
+        let now = Instant::now();
+        JournalRecord {
+            id: lock_id.clone(),
+            writer: self.writer.clone(),
+            readers: self.readers.clone(),
+            expires_at: self
+                .expires_at
+                .map(|exp| unix_millis(SystemTime::now() + exp.saturating_duration_since(now)))
+                .unwrap_or_default(),
+            acquired_at: self.acquired_at.map(unix_millis).unwrap_or_default(),
+            ttl: self.ttl.as_millis() as u64,
+        }
+    }
+
+    /// whether a writer has been waiting long enough for new readers to queue behind it
+    fn writer_has_priority(&self, now: Instant, priority_after: Duration) -> bool {
+        match (self.writer_waiting_since, self.writer_last_retry) {
+            (Some(since), Some(last)) => now - last <= WRITER_WAIT_STALE && now - since >= priority_after,
+            _ => false,
+        }
+    }
+}
+
+/// Who holds the lock of a resource
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockState {
+    /// read locks held, counting each reentrant acquisition
+    pub readers: usize,
+    /// whether a write lock is held
+    pub writer: bool,
 }
 
 /// local lock map
 #[derive(Debug)]
 pub struct LocalLockMap {
-    /// LockId to lock object map
-    pub locks: Arc<RwLock<HashMap<crate::types::LockId, Arc<RwLock<LocalLockEntry>>>>>,
+    /// LockId to lock object map, sharded by LockId hash
+    shards: Arc<[Shard]>,
     /// Shutdown flag for background tasks
     shutdown: Arc<AtomicBool>,
+    /// how long a writer waits before new read locks are held back for it
+    writer_priority_after: Duration,
+    /// number of requests waiting per lock
+    waiters: Arc<Mutex<HashMap<LockId, usize>>>,
+    /// journal of lock grants, replayed on startup
+    journal: Option<Arc<LockJournal>>,
+    /// wait and timeout stats of contended resources
+    contention: ContentionStats,
+}
+
+/// Counts a request as waiting for a lock until dropped
+struct Waiter {
+    waiters: Arc<Mutex<HashMap<LockId, usize>>>,
+    lock_id: LockId,
+}
+
+impl Waiter {
+    fn new(waiters: &Arc<Mutex<HashMap<LockId, usize>>>, lock_id: &LockId) -> Self {
+        *waiters.lock().unwrap().entry(lock_id.clone()).or_insert(0) += 1;
+        Self {
+            waiters: waiters.clone(),
+            lock_id: lock_id.clone(),
+        }
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        let mut waiters = self.waiters.lock().unwrap();
+        if let Some(count) = waiters.get_mut(&self.lock_id) {
+            *count -= 1;
+            if *count == 0 {
+                waiters.remove(&self.lock_id);
+            }
+        }
+    }
+}
+
+fn shard_of<'a>(shards: &'a [Shard], lock_id: &LockId) -> &'a Shard {
+    let mut hasher = DefaultHasher::new();
+    lock_id.hash(&mut hasher);
+    &shards[hasher.finish() as usize % shards.len()]
 }
 
 impl Default for LocalLockMap {
@@ -47,20 +228,109 @@ impl Default for LocalLockMap {
 }
 
 impl LocalLockMap {
-    /// create new local lock map
+    /// create new local lock map, journaling its grants when `RUSTFS_LOCK_JOURNAL` is set
     pub fn new() -> Self {
+        let writer_priority_after = std::env::var(ENV_LOCK_WRITER_PRIORITY_AFTER)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_WRITER_PRIORITY_AFTER);
+        let journal = std::env::var(ENV_LOCK_JOURNAL).ok().filter(|path| !path.is_empty());
+        if let Some(path) = journal {
+            match Self::open_journal(&path) {
+                Ok(journal) => return Self::build(writer_priority_after, Some(journal)),
+                Err(e) => tracing::error!("Failed to open lock journal {}, locks will not survive a restart: {}", path, e),
+            }
+        }
+        Self::with_writer_priority_after(writer_priority_after)
+    }
+
+    /// create new local lock map where writers waiting longer than `writer_priority_after` block new readers
+    pub fn with_writer_priority_after(writer_priority_after: Duration) -> Self {
+        Self::build(writer_priority_after, None)
+    }
+
+    /// create new local lock map journaling its grants to `path`, holding again the leases still live in it
+    pub fn with_journal(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        Ok(Self::build(DEFAULT_WRITER_PRIORITY_AFTER, Some(Self::open_journal(path)?)))
+    }
+
+    fn open_journal(path: impl AsRef<std::path::Path>) -> std::io::Result<(Arc<LockJournal>, Vec<JournalRecord>)> {
+        let (journal, replayed) = LockJournal::open(path)?;
+        Ok((Arc::new(journal), replayed))
+    }
+
+    fn build(writer_priority_after: Duration, journal: Option<(Arc<LockJournal>, Vec<JournalRecord>)>) -> Self {
+        let shards: Arc<[Shard]> = (0..SHARDS).map(|_| Shard::default()).collect();
+        let journal = journal.map(|(journal, replayed)| {
+            let (now, sys_now) = (Instant::now(), SystemTime::now());
+            for record in replayed {
+                tracing::info!(
+                    "Holding journaled lock '{}' for {:?}, writer: {:?}, readers: {:?}",
+                    record.id.resource,
+                    record.remaining(sys_now),
+                    record.writer,
+                    record.readers.keys().collect::<Vec<_>>()
+                );
+                let entry = LocalLockEntry {
+                    writer: record.writer.clone(),
+                    readers: record.readers.clone(),
+                    expires_at: Some(now + record.remaining(sys_now)),
+                    acquired_at: Some(std::time::UNIX_EPOCH + Duration::from_millis(record.acquired_at)),
+                    ..LocalLockEntry::new(Duration::from_millis(record.ttl))
+                };
+                shard_of(&shards, &record.id)
+                    .try_write()
+                    .expect("lock map is not shared yet")
+                    .insert(record.id, Arc::new(RwLock::new(entry)));
+            }
+            journal
+        });
+
         let map = Self {
-            locks: Arc::new(RwLock::new(HashMap::new())),
+            shards,
             shutdown: Arc::new(AtomicBool::new(false)),
+            writer_priority_after,
+            waiters: Arc::new(Mutex::new(HashMap::new())),
+            journal,
+            contention: ContentionStats::default(),
         };
         map.spawn_expiry_task();
         map
     }
 
+    /// append the state of a lock to the journal, if one is kept, waiting until it is on disk
+    async fn journal(&self, lock_id: &LockId, entry: &LocalLockEntry) {
+        if let Some(journal) = &self.journal {
+            journal.record(entry.journal_record(lock_id)).await;
+        }
+    }
+
+    fn shard(&self, lock_id: &LockId) -> &Shard {
+        shard_of(&self.shards, lock_id)
+    }
+
+    /// drop the entry of a lock nobody holds
+    ///
+    /// Locks are only granted under the shard lock, so an entry found idle here cannot be granted
+    /// once removed; an entry granted again since it was released stays.
+    async fn remove_if_idle(&self, lock_id: &LockId) {
+        let mut locks_guard = self.shard(lock_id).write().await;
+        if locks_guard.get(lock_id).is_some_and(is_idle) {
+            locks_guard.remove(lock_id);
+        }
+    }
+
+    /// lock object of a LockId, if it is in the map
+    pub async fn entry(&self, lock_id: &LockId) -> Option<Arc<RwLock<LocalLockEntry>>> {
+        self.shard(lock_id).read().await.get(lock_id).cloned()
+    }
+
     /// spawn expiry task to clean up expired locks
     fn spawn_expiry_task(&self) {
-        let locks = self.locks.clone();
+        let shards = self.shards.clone();
         let shutdown = self.shutdown.clone();
+        let journal = self.journal.clone();
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(1));
             loop {
@@ -72,33 +342,35 @@ impl LocalLockMap {
                 }
 
                 let now = Instant::now();
-                let mut to_remove = Vec::new();
-
-                {
-                    let locks_guard = locks.read().await;
-                    for (key, entry) in locks_guard.iter() {
-                        if let Ok(mut entry_guard) = entry.try_write() {
-                            if let Some(exp) = entry_guard.expires_at {
-                                if exp <= now {
-                                    entry_guard.writer = None;
-                                    entry_guard.readers.clear();
-                                    entry_guard.expires_at = None;
-
-                                    if entry_guard.writer.is_none() && entry_guard.readers.is_empty() {
-                                        to_remove.push(key.clone());
-                                    }
+                for shard in shards.iter() {
+                    let mut to_remove = Vec::new();
+
+                    {
+                        let locks_guard = shard.read().await;
+                        for (key, entry) in locks_guard.iter() {
+                            if let Ok(mut entry_guard) = entry.try_write() {
+                                if entry_guard.expire(key, now) {
+                                    to_remove.push(key.clone());
                                 }
                             }
                         }
                     }
-                }
 
-                if !to_remove.is_empty() {
-                    let mut locks_guard = locks.write().await;
-                    for key in to_remove {
-                        locks_guard.remove(&key);
+                    if !to_remove.is_empty() {
+                        let mut locks_guard = shard.write().await;
+                        for key in to_remove {
+                            // granted again since it expired
+                            if locks_guard.get(&key).is_some_and(|entry| !is_idle(entry)) {
+                                continue;
+                            }
+                            locks_guard.remove(&key);
+                        }
                     }
                 }
+
+                if let Some(journal) = &journal {
+                    journal.compact();
+                }
             }
         });
     }
@@ -106,109 +378,137 @@ impl LocalLockMap {
     /// write lock with TTL, support timeout, use LockRequest
     pub async fn lock_with_ttl_id(&self, request: &LockRequest) -> std::io::Result<bool> {
         let start = Instant::now();
-        let expires_at = Some(Instant::now() + request.ttl);
+        let budget = request.wait_budget();
+        let mut waiter = None;
+        let mut polls = 0;
 
         loop {
-            // get or create lock entry
-            let entry = {
-                let mut locks_guard = self.locks.write().await;
-                locks_guard
+            {
+                // get or create lock entry, granting it under the shard lock so it cannot be removed meanwhile
+                let mut locks_guard = self.shard(&request.lock_id).write().await;
+                let entry = locks_guard
                     .entry(request.lock_id.clone())
-                    .or_insert_with(|| {
-                        Arc::new(RwLock::new(LocalLockEntry {
-                            writer: None,
-                            readers: HashMap::new(),
-                            expires_at: None,
-                        }))
-                    })
-                    .clone()
-            };
-
-            // try to get write lock to modify state
-            if let Ok(mut entry_guard) = entry.try_write() {
-                // check expired state
-                let now = Instant::now();
-                if let Some(exp) = entry_guard.expires_at {
-                    if exp <= now {
-                        entry_guard.writer = None;
-                        entry_guard.readers.clear();
-                        entry_guard.expires_at = None;
-                    }
-                }
+                    .or_insert_with(|| Arc::new(RwLock::new(LocalLockEntry::new(request.ttl))));
 
-                // check if can get write lock
-                if entry_guard.writer.is_none() && entry_guard.readers.is_empty() {
-                    entry_guard.writer = Some(request.owner.clone());
-                    entry_guard.expires_at = expires_at;
-                    tracing::debug!("Write lock acquired for resource '{}' by owner '{}'", request.resource, request.owner);
-                    return Ok(true);
+                // try to get write lock to modify state
+                if let Ok(mut entry_guard) = entry.try_write() {
+                    // check expired state
+                    let now = Instant::now();
+                    entry_guard.expire(&request.lock_id, now);
+
+                    // check if can get write lock
+                    if entry_guard.writer.is_none() && entry_guard.readers.is_empty() {
+                        entry_guard.writer = Some(request.owner.clone());
+                        // the lease starts with the grant, not with the wait for it
+                        entry_guard.expires_at = Some(now + request.ttl);
+                        entry_guard.acquired_at = Some(SystemTime::now());
+                        entry_guard.ttl = request.ttl;
+                        entry_guard.writer_waiting_since = None;
+                        entry_guard.writer_last_retry = None;
+                        self.journal(&request.lock_id, &entry_guard).await;
+                        self.contention.record(&request.resource, start.elapsed(), false);
+                        tracing::debug!("Write lock acquired for resource '{}' by owner '{}'", request.resource, request.owner);
+                        return Ok(true);
+                    }
+                    entry_guard.writer_waiting(now);
                 }
             }
 
-            if start.elapsed() >= request.acquire_timeout {
+            waiter.get_or_insert_with(|| Waiter::new(&self.waiters, &request.lock_id));
+            if start.elapsed() >= budget {
+                self.contention.record(&request.resource, start.elapsed(), true);
                 return Ok(false);
             }
-            tokio::time::sleep(Duration::from_millis(10)).await;
+            if !wait_for_retry(request, polls).await {
+                return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "lock wait canceled"));
+            }
+            polls += 1;
         }
     }
 
     /// read lock with TTL, support timeout, use LockRequest
     pub async fn rlock_with_ttl_id(&self, request: &LockRequest) -> std::io::Result<bool> {
         let start = Instant::now();
-        let expires_at = Some(Instant::now() + request.ttl);
+        let budget = request.wait_budget();
+        let mut waiter = None;
+        let mut polls = 0;
 
         loop {
-            // get or create lock entry
-            let entry = {
-                let mut locks_guard = self.locks.write().await;
-                locks_guard
+            {
+                // get or create lock entry, granting it under the shard lock so it cannot be removed meanwhile
+                let mut locks_guard = self.shard(&request.lock_id).write().await;
+                let entry = locks_guard
                     .entry(request.lock_id.clone())
-                    .or_insert_with(|| {
-                        Arc::new(RwLock::new(LocalLockEntry {
-                            writer: None,
-                            readers: HashMap::new(),
-                            expires_at: None,
-                        }))
-                    })
-                    .clone()
-            };
-
-            // try to get write lock to modify state
-            if let Ok(mut entry_guard) = entry.try_write() {
-                // check expired state
-                let now = Instant::now();
-                if let Some(exp) = entry_guard.expires_at {
-                    if exp <= now {
-                        entry_guard.writer = None;
-                        entry_guard.readers.clear();
-                        entry_guard.expires_at = None;
-                    }
-                }
+                    .or_insert_with(|| Arc::new(RwLock::new(LocalLockEntry::new(request.ttl))));
 
-                // check if can get read lock
-                if entry_guard.writer.is_none() {
-                    // increase read lock count
-                    *entry_guard.readers.entry(request.owner.clone()).or_insert(0) += 1;
-                    entry_guard.expires_at = expires_at;
-                    tracing::debug!("Read lock acquired for resource '{}' by owner '{}'", request.resource, request.owner);
-                    return Ok(true);
+                // try to get write lock to modify state
+                if let Ok(mut entry_guard) = entry.try_write() {
+                    // check expired state
+                    let now = Instant::now();
+                    entry_guard.expire(&request.lock_id, now);
+
+                    // new readers queue behind a writer that waited long enough, owners already reading may re-enter
+                    let writer_first = entry_guard.writer_has_priority(now, self.writer_priority_after)
+                        && !entry_guard.readers.contains_key(&request.owner);
+
+                    // check if can get read lock
+                    if entry_guard.writer.is_none() && !writer_first {
+                        if entry_guard.readers.is_empty() {
+                            entry_guard.acquired_at = Some(SystemTime::now());
+                        }
+                        // increase read lock count
+                        *entry_guard.readers.entry(request.owner.clone()).or_insert(0) += 1;
+                        // readers share one lease, it lasts as long as the longest one requested
+                        if entry_guard.expires_at.is_none_or(|exp| exp < now + request.ttl) {
+                            entry_guard.expires_at = Some(now + request.ttl);
+                        }
+                        entry_guard.ttl = entry_guard.ttl.max(request.ttl);
+                        self.journal(&request.lock_id, &entry_guard).await;
+                        self.contention.record(&request.resource, start.elapsed(), false);
+                        tracing::debug!("Read lock acquired for resource '{}' by owner '{}'", request.resource, request.owner);
+                        return Ok(true);
+                    }
                 }
             }
 
-            if start.elapsed() >= request.acquire_timeout {
+            waiter.get_or_insert_with(|| Waiter::new(&self.waiters, &request.lock_id));
+            if start.elapsed() >= budget {
+                self.contention.record(&request.resource, start.elapsed(), true);
                 return Ok(false);
             }
-            tokio::time::sleep(Duration::from_millis(10)).await;
+            if !wait_for_retry(request, polls).await {
+                return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "lock wait canceled"));
+            }
+            polls += 1;
         }
     }
 
+    /// renew the lease of a held lock by LockId for the ttl it was acquired with
+    ///
+    /// Returns false when the lock is no longer held, e.g. because its lease already expired.
+    pub async fn refresh_by_id(&self, lock_id: &crate::types::LockId) -> bool {
+        let locks_guard = self.shard(lock_id).read().await;
+        let Some(entry) = locks_guard.get(lock_id) else {
+            return false;
+        };
+        let mut entry_guard = entry.write().await;
+        let now = Instant::now();
+        let held = entry_guard.writer.is_some() || !entry_guard.readers.is_empty();
+        if !held || entry_guard.expires_at.is_some_and(|exp| exp <= now) {
+            return false;
+        }
+        entry_guard.expires_at = Some(now + entry_guard.ttl);
+        self.journal(lock_id, &entry_guard).await;
+        true
+    }
+
     /// unlock by LockId and owner - need to specify owner to correctly unlock
     pub async fn unlock_by_id_and_owner(&self, lock_id: &crate::types::LockId, owner: &str) -> std::io::Result<()> {
         println!("Unlocking lock_id: {lock_id:?}, owner: {owner}");
         let mut need_remove = false;
 
         {
-            let locks_guard = self.locks.read().await;
+            let locks_guard = self.shard(lock_id).read().await;
             if let Some(entry) = locks_guard.get(lock_id) {
                 println!("Found lock entry, attempting to acquire write lock...");
                 match entry.try_write() {
@@ -241,6 +541,7 @@ impl LocalLockMap {
                                 entry_guard.writer, entry_guard.readers
                             );
                         }
+                        self.journal(lock_id, &entry_guard).await;
                     }
                     Err(_) => {
                         println!("Failed to acquire write lock for unlock - this is the problem!");
@@ -258,22 +559,44 @@ impl LocalLockMap {
         // only here, entry's Ref is really dropped, can safely remove
         if need_remove {
             println!("Removing lock entry from map...");
-            let mut locks_guard = self.locks.write().await;
-            let removed = locks_guard.remove(lock_id);
-            println!("Lock entry removed: {:?}", removed.is_some());
+            self.remove_if_idle(lock_id).await;
         }
         println!("Unlock operation completed");
         Ok(())
     }
 
+    /// release a lock by LockId whatever its holders, for recovering orphaned locks
+    ///
+    /// Returns whether the lock was held.
+    pub async fn force_unlock_by_id(&self, lock_id: &crate::types::LockId) -> bool {
+        let Some(entry) = self.shard(lock_id).write().await.remove(lock_id) else {
+            return false;
+        };
+        let mut entry_guard = entry.write().await;
+        let held = entry_guard.writer.is_some() || !entry_guard.readers.is_empty();
+        tracing::warn!(
+            "Force unlocking '{}', writer: {:?}, readers: {:?}",
+            lock_id.resource,
+            entry_guard.writer,
+            entry_guard.readers.keys().collect::<Vec<_>>()
+        );
+        entry_guard.writer = None;
+        entry_guard.readers.clear();
+        entry_guard.expires_at = None;
+        entry_guard.acquired_at = None;
+        self.journal(lock_id, &entry_guard).await;
+        held
+    }
+
     /// unlock by LockId - smart release (compatible with old interface, but may be inaccurate)
     pub async fn unlock_by_id(&self, lock_id: &crate::types::LockId) -> std::io::Result<()> {
         let mut need_remove = false;
 
         {
-            let locks_guard = self.locks.read().await;
+            let locks_guard = self.shard(lock_id).read().await;
             if let Some(entry) = locks_guard.get(lock_id) {
-                if let Ok(mut entry_guard) = entry.try_write() {
+                {
+                    let mut entry_guard = entry.write().await;
                     // release write lock first
                     if entry_guard.writer.is_some() {
                         entry_guard.writer = None;
@@ -294,13 +617,13 @@ impl LocalLockMap {
                         entry_guard.expires_at = None;
                         need_remove = true;
                     }
+                    self.journal(lock_id, &entry_guard).await;
                 }
             }
         }
 
         if need_remove {
-            let mut locks_guard = self.locks.write().await;
-            locks_guard.remove(lock_id);
+            self.remove_if_idle(lock_id).await;
         }
         Ok(())
     }
@@ -310,9 +633,10 @@ impl LocalLockMap {
         let mut need_remove = false;
 
         {
-            let locks_guard = self.locks.read().await;
+            let locks_guard = self.shard(lock_id).read().await;
             if let Some(entry) = locks_guard.get(lock_id) {
-                if let Ok(mut entry_guard) = entry.try_write() {
+                {
+                    let mut entry_guard = entry.write().await;
                     // release read lock
                     if let Some(count) = entry_guard.readers.get_mut(owner) {
                         *count -= 1;
@@ -326,13 +650,13 @@ impl LocalLockMap {
                         entry_guard.expires_at = None;
                         need_remove = true;
                     }
+                    self.journal(lock_id, &entry_guard).await;
                 }
             }
         }
 
         if need_remove {
-            let mut locks_guard = self.locks.write().await;
-            locks_guard.remove(lock_id);
+            self.remove_if_idle(lock_id).await;
         }
         Ok(())
     }
@@ -342,9 +666,10 @@ impl LocalLockMap {
         let mut need_remove = false;
 
         {
-            let locks_guard = self.locks.read().await;
+            let locks_guard = self.shard(lock_id).read().await;
             if let Some(entry) = locks_guard.get(lock_id) {
-                if let Ok(mut entry_guard) = entry.try_write() {
+                {
+                    let mut entry_guard = entry.write().await;
                     // release first read lock
                     if let Some((owner, _)) = entry_guard.readers.iter().next() {
                         let owner = owner.clone();
@@ -361,13 +686,13 @@ impl LocalLockMap {
                         entry_guard.expires_at = None;
                         need_remove = true;
                     }
+                    self.journal(lock_id, &entry_guard).await;
                 }
             }
         }
 
         if need_remove {
-            let mut locks_guard = self.locks.write().await;
-            locks_guard.remove(lock_id);
+            self.remove_if_idle(lock_id).await;
         }
         Ok(())
     }
@@ -375,7 +700,7 @@ impl LocalLockMap {
     /// check if resource is locked
     pub async fn is_locked(&self, resource: &str) -> bool {
         let lock_id = crate::types::LockId::new_deterministic(resource);
-        let locks_guard = self.locks.read().await;
+        let locks_guard = self.shard(&lock_id).read().await;
         if let Some(entry) = locks_guard.get(&lock_id) {
             let entry_guard = entry.read().await;
             entry_guard.writer.is_some() || !entry_guard.readers.is_empty()
@@ -384,10 +709,26 @@ impl LocalLockMap {
         }
     }
 
+    /// readers and writer currently holding the lock of a resource
+    pub async fn lock_state(&self, resource: &str) -> LockState {
+        let lock_id = crate::types::LockId::new_deterministic(resource);
+        let locks_guard = self.shard(&lock_id).read().await;
+        match locks_guard.get(&lock_id) {
+            Some(entry) => {
+                let entry_guard = entry.read().await;
+                LockState {
+                    readers: entry_guard.readers.values().sum(),
+                    writer: entry_guard.writer.is_some(),
+                }
+            }
+            None => LockState::default(),
+        }
+    }
+
     /// get lock info for a resource
     pub async fn get_lock(&self, resource: &str) -> Option<crate::types::LockInfo> {
         let lock_id = crate::types::LockId::new_deterministic(resource);
-        let locks_guard = self.locks.read().await;
+        let locks_guard = self.shard(&lock_id).read().await;
         if let Some(entry) = locks_guard.get(&lock_id) {
             let entry_guard = entry.read().await;
 
@@ -428,26 +769,69 @@ impl LocalLockMap {
         }
     }
 
+    /// list held locks with their waiters, one entry per writer or reader
+    pub async fn list_locks(&self) -> Vec<HeldLock> {
+        let mut entries = Vec::new();
+        for shard in self.shards.iter() {
+            let locks_guard = shard.read().await;
+            entries.extend(locks_guard.iter().map(|(id, entry)| (id.clone(), entry.clone())));
+        }
+
+        let mut held = Vec::new();
+        for (lock_id, entry) in entries {
+            let entry_guard = entry.read().await;
+            if entry_guard.expires_at.is_some_and(|exp| exp <= Instant::now()) {
+                continue;
+            }
+            let waiters = self.waiters.lock().unwrap().get(&lock_id).copied().unwrap_or_default();
+            let holders = entry_guard
+                .writer
+                .iter()
+                .map(|owner| (owner, LockType::Exclusive))
+                .chain(entry_guard.readers.keys().map(|owner| (owner, LockType::Shared)));
+            for (owner, lock_type) in holders {
+                held.push(HeldLock {
+                    resource: lock_id.resource.clone(),
+                    uid: lock_id.uuid.clone(),
+                    owner: owner.clone(),
+                    lock_type,
+                    acquired_at: entry_guard.acquired_at.unwrap_or_else(SystemTime::now),
+                    waiters,
+                });
+            }
+        }
+        held
+    }
+
+    /// the `count` most contended resources of the last windows, see [`ContentionStats`]
+    pub fn top_contended(&self, count: usize) -> Vec<ResourceContention> {
+        self.contention.top(count)
+    }
+
     /// get statistics
     pub async fn get_stats(&self) -> crate::types::LockStats {
         let mut stats = crate::types::LockStats::default();
-        let locks_guard = self.locks.read().await;
-
-        for (_, entry) in locks_guard.iter() {
-            let entry_guard = entry.read().await;
-            if entry_guard.writer.is_some() {
-                stats.exclusive_locks += 1;
+        for shard in self.shards.iter() {
+            let locks_guard = shard.read().await;
+            for entry in locks_guard.values() {
+                let entry_guard = entry.read().await;
+                if entry_guard.writer.is_some() {
+                    stats.exclusive_locks += 1;
+                }
+                stats.shared_locks += entry_guard.readers.len();
             }
-            stats.shared_locks += entry_guard.readers.len();
         }
 
         stats.total_locks = stats.exclusive_locks + stats.shared_locks;
         stats
     }
 
-    /// shutdown background tasks
+    /// shutdown background tasks, writing out and closing the lock journal
     pub async fn shutdown(&self) {
         self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(journal) = &self.journal {
+            journal.close();
+        }
     }
 }
 
@@ -475,6 +859,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         // try to acquire lock
@@ -527,6 +913,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let ok = lock_map.rlock_with_ttl_id(&request).await.unwrap();
@@ -561,6 +949,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let ok = lock_map.lock_with_ttl_id(&request1).await.unwrap();
@@ -578,6 +968,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let request2_clone = request2.clone();
@@ -614,6 +1006,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let request2 = LockRequest {
@@ -626,6 +1020,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let request3 = LockRequest {
@@ -638,6 +1034,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let ok1 = lock_map.rlock_with_ttl_id(&request1).await.unwrap();
@@ -645,6 +1043,13 @@ mod tests {
         let ok3 = lock_map.rlock_with_ttl_id(&request3).await.unwrap();
         assert!(ok1 && ok2 && ok3, "All read locks should succeed");
         assert!(lock_map.is_locked("res_sharing_test").await, "Resource should be locked");
+        assert_eq!(
+            lock_map.lock_state("res_sharing_test").await,
+            LockState {
+                readers: 3,
+                writer: false
+            }
+        );
 
         // Release readers one by one
         lock_map.runlock_by_id_and_owner(&request1.lock_id, "reader1").await.unwrap();
@@ -661,6 +1066,7 @@ mod tests {
             !lock_map.is_locked("res_sharing_test").await,
             "Should be unlocked when all readers release"
         );
+        assert_eq!(lock_map.lock_state("res_sharing_test").await, LockState::default());
     }
 
     /// Test read-write lock exclusion
@@ -679,6 +1085,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let ok = lock_map.rlock_with_ttl_id(&read_request).await.unwrap();
@@ -695,6 +1103,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let result = timeout(Duration::from_millis(100), async {
@@ -722,6 +1132,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
         let ok = lock_map.lock_with_ttl_id(&write_request_long_ttl).await.unwrap();
         assert!(ok, "Write lock should succeed after read lock is released");
@@ -737,6 +1149,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let result = timeout(Duration::from_millis(100), async {
@@ -776,6 +1190,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let read_request1 = LockRequest {
@@ -788,6 +1204,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let read_request2 = LockRequest {
@@ -800,6 +1218,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         lock_map.lock_with_ttl_id(&write_request).await.unwrap();
@@ -855,6 +1275,8 @@ mod tests {
                         metadata: crate::types::LockMetadata::default(),
                         priority: crate::types::LockPriority::Normal,
                         deadlock_detection: false,
+                        cancel: None,
+                        deadline: None,
                     };
 
                     if request.lock_type == crate::types::LockType::Exclusive {
@@ -895,6 +1317,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
 
         let ok = lock_map.lock_with_ttl_id(&request).await.unwrap();
@@ -911,6 +1335,8 @@ mod tests {
             metadata: crate::types::LockMetadata::default(),
             priority: crate::types::LockPriority::Normal,
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         };
         let ok2 = lock_map.lock_with_ttl_id(&request2).await.unwrap();
         assert!(!ok2, "Second lock should fail before timeout");
@@ -922,4 +1348,201 @@ mod tests {
         let ok3 = lock_map.lock_with_ttl_id(&request2).await.unwrap();
         assert!(ok3, "Lock should succeed after timeout");
     }
+
+    #[tokio::test]
+    async fn test_refresh_extends_lease() {
+        let lock_map = LocalLockMap::new();
+        let request = LockRequest::new("refresh_resource", crate::types::LockType::Exclusive, "owner1")
+            .with_acquire_timeout(Duration::from_millis(50))
+            .with_ttl(Duration::from_millis(200));
+        assert!(lock_map.lock_with_ttl_id(&request).await.unwrap());
+
+        // Renewed halfway, the lease outlives its original ttl
+        sleep(Duration::from_millis(120)).await;
+        assert!(lock_map.refresh_by_id(&request.lock_id).await);
+        sleep(Duration::from_millis(120)).await;
+        let other = LockRequest::new("refresh_resource", crate::types::LockType::Exclusive, "owner2")
+            .with_acquire_timeout(Duration::from_millis(10))
+            .with_ttl(Duration::from_millis(200));
+        assert!(!lock_map.lock_with_ttl_id(&other).await.unwrap());
+
+        // An expired lease can not be renewed
+        sleep(Duration::from_millis(250)).await;
+        assert!(!lock_map.refresh_by_id(&request.lock_id).await);
+        assert!(lock_map.lock_with_ttl_id(&other).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_waiting_writer_blocks_new_readers() {
+        let lock_map = Arc::new(LocalLockMap::with_writer_priority_after(Duration::from_millis(50)));
+        let read = |owner: &str| {
+            LockRequest::new("hot_object", crate::types::LockType::Shared, owner)
+                .with_acquire_timeout(Duration::from_millis(30))
+                .with_ttl(Duration::from_secs(5))
+        };
+        let reader1 = read("reader1");
+        assert!(lock_map.rlock_with_ttl_id(&reader1).await.unwrap());
+
+        let writer = LockRequest::new("hot_object", crate::types::LockType::Exclusive, "writer")
+            .with_acquire_timeout(Duration::from_secs(2))
+            .with_ttl(Duration::from_secs(5));
+        let handle = {
+            let lock_map = lock_map.clone();
+            tokio::spawn(async move { lock_map.lock_with_ttl_id(&writer).await.unwrap() })
+        };
+
+        // Before the writer waited long enough readers are still admitted
+        sleep(Duration::from_millis(10)).await;
+        let early = read("early");
+        assert!(lock_map.rlock_with_ttl_id(&early).await.unwrap());
+        lock_map.unlock_by_id_and_owner(&early.lock_id, "early").await.unwrap();
+
+        // Then new readers queue behind it, while current readers may re-enter
+        sleep(Duration::from_millis(100)).await;
+        assert!(!lock_map.rlock_with_ttl_id(&read("late")).await.unwrap());
+        assert!(lock_map.rlock_with_ttl_id(&reader1).await.unwrap());
+
+        lock_map.unlock_by_id_and_owner(&reader1.lock_id, "reader1").await.unwrap();
+        lock_map.unlock_by_id_and_owner(&reader1.lock_id, "reader1").await.unwrap();
+        assert!(handle.await.unwrap(), "writer should get the lock once the readers left");
+    }
+
+    #[tokio::test]
+    async fn test_gone_writer_stops_blocking_readers() {
+        let lock_map = LocalLockMap::with_writer_priority_after(Duration::ZERO);
+        let reader = LockRequest::new("gone_writer", crate::types::LockType::Shared, "reader1")
+            .with_acquire_timeout(Duration::from_millis(30))
+            .with_ttl(Duration::from_secs(5));
+        assert!(lock_map.rlock_with_ttl_id(&reader).await.unwrap());
+
+        let writer = LockRequest::new("gone_writer", crate::types::LockType::Exclusive, "writer")
+            .with_acquire_timeout(Duration::from_millis(30))
+            .with_ttl(Duration::from_secs(5));
+        assert!(!lock_map.lock_with_ttl_id(&writer).await.unwrap());
+
+        let other = LockRequest::new("gone_writer", crate::types::LockType::Shared, "reader2")
+            .with_acquire_timeout(Duration::from_millis(30))
+            .with_ttl(Duration::from_secs(5));
+        assert!(!lock_map.rlock_with_ttl_id(&other).await.unwrap());
+
+        // The writer gave up, readers are admitted again once its wait went stale
+        sleep(WRITER_WAIT_STALE + Duration::from_millis(20)).await;
+        assert!(lock_map.rlock_with_ttl_id(&other).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_list_locks_with_waiters() {
+        let lock_map = Arc::new(LocalLockMap::new());
+        let writer = LockRequest::new("listed", crate::types::LockType::Exclusive, "writer").with_ttl(Duration::from_secs(5));
+        assert!(lock_map.lock_with_ttl_id(&writer).await.unwrap());
+
+        let waiting = {
+            let lock_map = lock_map.clone();
+            let reader = LockRequest::new("listed", crate::types::LockType::Shared, "reader")
+                .with_acquire_timeout(Duration::from_millis(500))
+                .with_ttl(Duration::from_secs(5));
+            tokio::spawn(async move { lock_map.rlock_with_ttl_id(&reader).await.unwrap() })
+        };
+        sleep(Duration::from_millis(100)).await;
+
+        let held = lock_map.list_locks().await;
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].resource, "listed");
+        assert_eq!(held[0].uid, writer.lock_id.uuid);
+        assert_eq!(held[0].owner, "writer");
+        assert_eq!(held[0].lock_type, crate::types::LockType::Exclusive);
+        assert_eq!(held[0].waiters, 1);
+
+        lock_map.unlock_by_id_and_owner(&writer.lock_id, "writer").await.unwrap();
+        assert!(waiting.await.unwrap());
+        let held = lock_map.list_locks().await;
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].owner, "reader");
+        assert_eq!(held[0].waiters, 0);
+    }
+
+    #[tokio::test]
+    async fn test_force_unlock_releases_all_holders() {
+        let lock_map = LocalLockMap::new();
+        let r1 = LockRequest::new("forced", crate::types::LockType::Shared, "reader1").with_ttl(Duration::from_secs(5));
+        let r2 = LockRequest::new("forced", crate::types::LockType::Shared, "reader2").with_ttl(Duration::from_secs(5));
+        assert!(lock_map.rlock_with_ttl_id(&r1).await.unwrap());
+        assert!(lock_map.rlock_with_ttl_id(&r2).await.unwrap());
+
+        assert!(lock_map.force_unlock_by_id(&r1.lock_id).await);
+        assert!(!lock_map.is_locked("forced").await);
+        assert!(!lock_map.force_unlock_by_id(&r1.lock_id).await);
+
+        let writer = LockRequest::new("forced", crate::types::LockType::Exclusive, "writer")
+            .with_acquire_timeout(Duration::from_millis(50))
+            .with_ttl(Duration::from_secs(5));
+        assert!(lock_map.lock_with_ttl_id(&writer).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_locks_spread_over_shards() {
+        let lock_map = Arc::new(LocalLockMap::new());
+        let requests: Vec<_> = (0..SHARDS * 4)
+            .map(|i| {
+                LockRequest::new(format!("sharded{i}"), crate::types::LockType::Exclusive, "owner")
+                    .with_ttl(Duration::from_secs(5))
+            })
+            .collect();
+
+        let handles: Vec<_> = requests
+            .iter()
+            .cloned()
+            .map(|request| {
+                let lock_map = lock_map.clone();
+                tokio::spawn(async move { lock_map.lock_with_ttl_id(&request).await.unwrap() })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.await.unwrap());
+        }
+
+        assert_eq!(lock_map.get_stats().await.exclusive_locks, requests.len());
+        assert_eq!(lock_map.list_locks().await.len(), requests.len());
+        let used = lock_map
+            .shards
+            .iter()
+            .filter(|shard| !shard.try_read().unwrap().is_empty())
+            .count();
+        assert!(used > SHARDS / 2, "only {used} shards in use");
+
+        for request in &requests {
+            lock_map.unlock_by_id_and_owner(&request.lock_id, "owner").await.unwrap();
+        }
+        assert_eq!(lock_map.get_stats().await.total_locks, 0);
+        assert!(lock_map.shards.iter().all(|shard| shard.try_read().unwrap().is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_journal_restores_leases_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("locks.journal");
+        let holder = LockRequest::new("journaled", crate::types::LockType::Exclusive, "holder").with_ttl(Duration::from_secs(30));
+        let reader =
+            LockRequest::new("journaled_read", crate::types::LockType::Shared, "reader").with_ttl(Duration::from_secs(30));
+        let other = LockRequest::new("journaled", crate::types::LockType::Exclusive, "other")
+            .with_acquire_timeout(Duration::from_millis(50))
+            .with_ttl(Duration::from_secs(30));
+
+        let lock_map = LocalLockMap::with_journal(&path).unwrap();
+        assert!(lock_map.lock_with_ttl_id(&holder).await.unwrap());
+        assert!(lock_map.rlock_with_ttl_id(&reader).await.unwrap());
+        lock_map.runlock_by_id_and_owner(&reader.lock_id, "reader").await.unwrap();
+        lock_map.shutdown().await;
+
+        // The restarted server still knows the lease it granted and does not grant a conflicting lock
+        let lock_map = LocalLockMap::with_journal(&path).unwrap();
+        assert!(!lock_map.lock_with_ttl_id(&other).await.unwrap());
+        assert!(!lock_map.is_locked("journaled_read").await);
+        assert!(lock_map.refresh_by_id(&holder.lock_id).await);
+        lock_map.unlock_by_id_and_owner(&holder.lock_id, "holder").await.unwrap();
+        lock_map.shutdown().await;
+
+        let lock_map = LocalLockMap::with_journal(&path).unwrap();
+        assert!(lock_map.lock_with_ttl_id(&other).await.unwrap());
+    }
 }
@@ -0,0 +1,127 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lock acquisition metrics.
+//!
+//! Wait times are recorded per lock type and outcome. Timeouts and quorum failures are also
+//! counted per resource, which points at the objects lock contention concentrates on; successful
+//! acquisitions carry no resource label to keep the number of series bounded.
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{KeyValue, global};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::types::LockType;
+
+pub const LOCK_ACQUIRE_WAIT: &str = "lock.acquire.wait";
+pub const LOCK_ACQUIRE_TIMEOUTS: &str = "lock.acquire.timeouts";
+pub const LOCK_ACQUIRE_QUORUM_FAILURES: &str = "lock.acquire.quorum_failures";
+
+/// Acquisitions waiting longer than this are logged as contended
+pub const SLOW_ACQUIRE_THRESHOLD: Duration = Duration::from_secs(1);
+
+const WAIT_BOUNDS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// How a lock acquisition ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcquireOutcome {
+    Acquired,
+    /// No locker granted the lock before the acquire timeout
+    Timeout,
+    /// Some lockers granted the lock, too few or not all of them for the namespace lock
+    QuorumFailure,
+    /// The caller stopped waiting, e.g. its request went away
+    Canceled,
+    Error,
+}
+
+impl AcquireOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AcquireOutcome::Acquired => "acquired",
+            AcquireOutcome::Timeout => "timeout",
+            AcquireOutcome::QuorumFailure => "quorum_failure",
+            AcquireOutcome::Canceled => "canceled",
+            AcquireOutcome::Error => "error",
+        }
+    }
+}
+
+struct LockMetrics {
+    wait: Histogram<f64>,
+    timeouts: Counter<u64>,
+    quorum_failures: Counter<u64>,
+}
+
+static METRICS: OnceLock<LockMetrics> = OnceLock::new();
+
+fn metrics() -> &'static LockMetrics {
+    METRICS.get_or_init(|| {
+        let meter = global::meter("lock");
+        LockMetrics {
+            wait: meter
+                .f64_histogram(LOCK_ACQUIRE_WAIT)
+                .with_description("Time spent acquiring namespace locks.")
+                .with_unit("s")
+                .with_boundaries(WAIT_BOUNDS.to_vec())
+                .build(),
+            timeouts: meter
+                .u64_counter(LOCK_ACQUIRE_TIMEOUTS)
+                .with_description("Namespace lock acquisitions that timed out, per resource.")
+                .build(),
+            quorum_failures: meter
+                .u64_counter(LOCK_ACQUIRE_QUORUM_FAILURES)
+                .with_description("Namespace lock acquisitions that failed to reach quorum, per resource.")
+                .build(),
+        }
+    })
+}
+
+fn lock_type_str(lock_type: LockType) -> &'static str {
+    match lock_type {
+        LockType::Exclusive => "write",
+        LockType::Shared => "read",
+    }
+}
+
+/// Record one lock acquisition of `resource`
+pub fn record_acquire(resource: &str, lock_type: LockType, outcome: AcquireOutcome, wait: Duration) {
+    let m = metrics();
+    let lock_type = lock_type_str(lock_type);
+    m.wait.record(
+        wait.as_secs_f64(),
+        &[KeyValue::new("type", lock_type), KeyValue::new("outcome", outcome.as_str())],
+    );
+
+    let per_resource = [
+        KeyValue::new("type", lock_type),
+        KeyValue::new("resource", resource.to_string()),
+    ];
+    match outcome {
+        AcquireOutcome::Timeout => m.timeouts.add(1, &per_resource),
+        AcquireOutcome::QuorumFailure => m.quorum_failures.add(1, &per_resource),
+        AcquireOutcome::Acquired | AcquireOutcome::Canceled | AcquireOutcome::Error => {}
+    }
+
+    if wait >= SLOW_ACQUIRE_THRESHOLD || !matches!(outcome, AcquireOutcome::Acquired | AcquireOutcome::Canceled) {
+        tracing::warn!(
+            resource,
+            lock_type,
+            outcome = outcome.as_str(),
+            wait_ms = wait.as_millis() as u64,
+            "contended lock acquisition"
+        );
+    }
+}
@@ -13,53 +13,286 @@
 // limitations under the License.
 
 use async_trait::async_trait;
-use std::sync::Arc;
-use std::time::Duration;
+use rustfs_retry::Backoff;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
+    audit::{LockAuditKind, audit},
     client::LockClient,
     error::{LockError, Result},
-    types::{LockId, LockInfo, LockRequest, LockResponse, LockStatus, LockType},
+    guard::LockGuard,
+    metrics::{AcquireOutcome, record_acquire},
+    protocol::FEATURE_LEASE,
+    types::{LockId, LockInfo, LockRequest, LockResponse, LockStatus, LockType, QuorumPolicy},
 };
 
+/// Longest the lease refresher sleeps before checking for new leases or shutdown
+const LEASE_REFRESHER_IDLE: Duration = Duration::from_secs(1);
+
+/// Delay between two-phase commit attempts; jittered so owners that split the clients between
+/// them do not retry in lockstep
+const ATTEMPT_BACKOFF: Backoff = Backoff::new(Duration::from_millis(5), Duration::from_millis(100)).with_jitter(0.5);
+
+/// Environment variable holding the quorum policy of distributed locks: `majority`, `all` or a count
+pub const ENV_LOCK_QUORUM: &str = "RUSTFS_LOCK_QUORUM";
+
+type Clients = Arc<Vec<Arc<dyn LockClient>>>;
+
+/// Node of a client for the audit log
+fn client_node(client: &Arc<dyn LockClient>) -> String {
+    client.endpoint().unwrap_or_else(|| "local".to_string())
+}
+
+/// Clients whose servers have leases; the others hold their locks until released and have
+/// nothing to renew
+async fn leased_clients(clients: &[Arc<dyn LockClient>]) -> Vec<Arc<dyn LockClient>> {
+    let capabilities = futures::future::join_all(clients.iter().map(|client| client.capabilities())).await;
+    clients
+        .iter()
+        .zip(capabilities)
+        .filter(|(_, capabilities)| capabilities.supports(FEATURE_LEASE))
+        .map(|(client, _)| client.clone())
+        .collect()
+}
+
+/// Quorum policy configured through `ENV_LOCK_QUORUM`, all clients when unset or invalid
+fn quorum_policy_from_env() -> QuorumPolicy {
+    let Ok(value) = std::env::var(ENV_LOCK_QUORUM) else {
+        return QuorumPolicy::default();
+    };
+    value.parse().unwrap_or_else(|e| {
+        tracing::warn!("{}, using {}", e, QuorumPolicy::default());
+        QuorumPolicy::default()
+    })
+}
+
+/// Lease of a lock held through a namespace lock
+#[derive(Debug, Clone)]
+struct Lease {
+    ttl: Duration,
+    renew_at: Instant,
+    /// Shared locks of the same resource share one lease
+    holders: usize,
+    /// Owner the lease was first granted to
+    owner: String,
+    /// Clients that granted the lock to its holders, the only ones it is renewed on
+    clients: Vec<Arc<dyn LockClient>>,
+    /// Canceled when the lease could not be renewed
+    lost: CancellationToken,
+}
+
+impl Lease {
+    fn new(ttl: Duration, owner: &str) -> Self {
+        Self {
+            ttl,
+            renew_at: Instant::now() + ttl / 3,
+            holders: 1,
+            owner: owner.to_string(),
+            clients: Vec::new(),
+            lost: CancellationToken::new(),
+        }
+    }
+
+    fn add_clients(&mut self, clients: &[Arc<dyn LockClient>]) {
+        for client in clients {
+            if !self.clients.iter().any(|known| Arc::ptr_eq(known, client)) {
+                self.clients.push(client.clone());
+            }
+        }
+    }
+}
+
+/// Leases of the locks held through a namespace lock
+#[derive(Debug, Default)]
+struct Leases {
+    held: Mutex<HashMap<LockId, Lease>>,
+    changed: Notify,
+}
+
+impl Leases {
+    fn insert(&self, lock_id: LockId, ttl: Duration, owner: &str, clients: &[Arc<dyn LockClient>]) {
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        held.entry(lock_id)
+            .and_modify(|lease| {
+                lease.holders += 1;
+                lease.ttl = lease.ttl.max(ttl);
+            })
+            .or_insert_with(|| Lease::new(ttl, owner))
+            .add_clients(clients);
+        drop(held);
+        self.changed.notify_one();
+    }
+
+    fn remove(&self, lock_id: &LockId) {
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(lease) = held.get_mut(lock_id) {
+            lease.holders -= 1;
+            if lease.holders == 0 {
+                held.remove(lock_id);
+            }
+        }
+    }
+
+    /// Leases due for renewal with the clients to renew them on
+    fn due(&self) -> Vec<(LockId, Vec<Arc<dyn LockClient>>)> {
+        let now = Instant::now();
+        self.held
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(_, lease)| lease.renew_at <= now)
+            .map(|(id, lease)| (id.clone(), lease.clients.clone()))
+            .collect()
+    }
+
+    /// When the next lease is due for renewal
+    fn next_renewal(&self) -> Option<Instant> {
+        self.held
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(|lease| lease.renew_at)
+            .min()
+    }
+
+    fn renewed(&self, lock_id: &LockId) {
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(lease) = held.get_mut(lock_id) {
+            lease.renew_at = Instant::now() + lease.ttl / 3;
+        }
+    }
+
+    /// Give up a lease, returning its owner
+    fn lost(&self, lock_id: &LockId) -> Option<String> {
+        let lease = self.held.lock().unwrap_or_else(|e| e.into_inner()).remove(lock_id)?;
+        lease.lost.cancel();
+        Some(lease.owner)
+    }
+
+    /// Token canceled once the lease of a held lock is lost
+    fn lost_token(&self, lock_id: &LockId) -> Option<CancellationToken> {
+        self.held
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(lock_id)
+            .map(|lease| lease.lost.clone())
+    }
+
+    fn is_held(&self, lock_id: &LockId) -> bool {
+        self.held.lock().unwrap_or_else(|e| e.into_inner()).contains_key(lock_id)
+    }
+}
+
+/// Clients that granted one acquisition of a lock
+type Granted = Vec<Arc<dyn LockClient>>;
+
+/// Clients that granted each owner its locks
+///
+/// Lockers release a lock by id whoever holds it, so a lock is only released on the clients that
+/// granted it to its owner; elsewhere the same lock may be held by someone else.
+#[derive(Debug, Default)]
+struct Grants {
+    /// One entry per acquisition, shared locks may be taken more than once by an owner
+    held: Mutex<HashMap<(LockId, String), Vec<Granted>>>,
+}
+
+impl Grants {
+    fn insert(&self, lock_id: LockId, owner: &str, clients: Vec<Arc<dyn LockClient>>) {
+        self.held
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry((lock_id, owner.to_string()))
+            .or_default()
+            .push(clients);
+    }
+
+    /// Clients that granted the last acquisition of a lock by `owner`, forgetting it
+    fn take(&self, lock_id: &LockId, owner: &str) -> Option<Vec<Arc<dyn LockClient>>> {
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (lock_id.clone(), owner.to_string());
+        let grants = held.get_mut(&key)?;
+        let clients = grants.pop();
+        if grants.is_empty() {
+            held.remove(&key);
+        }
+        clients
+    }
+}
+
 /// Namespace lock for managing locks by resource namespaces
+///
+/// A lock is granted once the number of clients required by the quorum policy granted it. The
+/// number is derived from the clients of the set, one per node holding drives of it.
+///
+/// Locks are acquired with a lease of `LockRequest::ttl`, so the locks of an owner that crashes
+/// expire on their own. While a lock is held here its lease is renewed in the background every
+/// third of its ttl; when renewal fails on a majority of clients the lease is given up and the
+/// lock expires. Locks taken through [`NamespaceLock::get_lock`] come with a [`LockGuard`] telling
+/// their holder when that happens, so it can stop writing data it no longer owns.
 #[derive(Debug)]
 pub struct NamespaceLock {
-    /// Lock clients for this namespace
-    clients: Vec<Arc<dyn LockClient>>,
+    /// Lock clients for this namespace, one per node
+    clients: Clients,
     /// Namespace identifier
     namespace: String,
-    /// Quorum size for operations (1 for local, majority for distributed)
-    quorum: usize,
+    /// Clients that must grant a lock
+    quorum_policy: QuorumPolicy,
+    /// Leases of the locks held through this namespace lock
+    leases: Arc<Leases>,
+    /// Clients that granted the locks held through this namespace lock
+    grants: Grants,
+    /// Set once the lease refresher task is running
+    refresher: OnceLock<()>,
 }
 
 impl NamespaceLock {
     /// Create new namespace lock
     pub fn new(namespace: String) -> Self {
         Self {
-            clients: Vec::new(),
+            clients: Arc::default(),
             namespace,
-            quorum: 1,
+            quorum_policy: quorum_policy_from_env(),
+            leases: Arc::default(),
+            grants: Grants::default(),
+            refresher: OnceLock::new(),
         }
     }
 
     /// Create namespace lock with clients
     pub fn with_clients(namespace: String, clients: Vec<Arc<dyn LockClient>>) -> Self {
-        let quorum = if clients.len() > 1 {
-            // For multiple clients (distributed mode), require majority
-            (clients.len() / 2) + 1
-        } else {
-            // For single client (local mode), only need 1
-            1
-        };
-
         Self {
-            clients,
+            clients: Arc::new(clients),
             namespace,
-            quorum,
+            quorum_policy: quorum_policy_from_env(),
+            leases: Arc::default(),
+            grants: Grants::default(),
+            refresher: OnceLock::new(),
         }
     }
 
+    /// Set how many clients must grant a lock
+    pub fn with_quorum_policy(mut self, quorum_policy: QuorumPolicy) -> Self {
+        self.quorum_policy = quorum_policy;
+        self
+    }
+
+    pub fn quorum_policy(&self) -> QuorumPolicy {
+        self.quorum_policy
+    }
+
+    /// Number of clients that must grant a lock with the current clients
+    pub fn quorum(&self) -> usize {
+        self.quorum_policy.required(self.clients().len())
+    }
+
+    fn clients(&self) -> Clients {
+        self.clients.clone()
+    }
+
     /// Create namespace lock with client (compatibility)
     pub fn with_client(client: Arc<dyn LockClient>) -> Self {
         Self::with_clients("default".to_string(), vec![client])
@@ -75,98 +308,238 @@ impl NamespaceLock {
         format!("{}:{}", self.namespace, resource)
     }
 
-    /// Acquire lock using clients with transactional semantics (all-or-nothing)
+    /// Acquire lock on a quorum of clients, rolling back a grant short of it
+    #[tracing::instrument(
+        name = "lock.acquire",
+        skip_all,
+        fields(resource = %request.resource, lock_type = ?request.lock_type, owner = %request.owner, outcome)
+    )]
     pub async fn acquire_lock(&self, request: &LockRequest) -> Result<LockResponse> {
-        if self.clients.is_empty() {
+        let clients = self.clients();
+        if clients.is_empty() {
             return Err(LockError::internal("No lock clients available"));
         }
 
-        // For single client, use it directly
-        if self.clients.len() == 1 {
-            return self.clients[0].acquire_lock(request).await;
+        let start = Instant::now();
+        let result = if clients.len() == 1 {
+            // For single client, use it directly
+            clients[0].acquire_lock(request).await.map(|response| {
+                let outcome = if response.success {
+                    AcquireOutcome::Acquired
+                } else {
+                    AcquireOutcome::Timeout
+                };
+                (response, outcome, vec![clients[0].clone()])
+            })
+        } else {
+            // Two-phase commit for distributed lock acquisition
+            self.acquire_lock_with_2pc(&clients, request).await
+        };
+        let outcome = match &result {
+            Ok((_, outcome, _)) => *outcome,
+            Err(LockError::Canceled { .. }) => AcquireOutcome::Canceled,
+            Err(_) => AcquireOutcome::Error,
+        };
+        tracing::Span::current().record("outcome", outcome.as_str());
+        record_acquire(&request.resource, request.lock_type, outcome, start.elapsed());
+        let (response, _, granted) = result?;
+
+        if response.success {
+            let lock_id = LockId::new_deterministic(&request.resource);
+            if !request.ttl.is_zero() {
+                self.leases.insert(lock_id.clone(), request.ttl, &request.owner, &granted);
+                self.refresher.get_or_init(|| self.spawn_lease_refresher());
+            }
+            self.grants.insert(lock_id, &request.owner, granted);
         }
+        Ok(response)
+    }
 
-        // Two-phase commit for distributed lock acquisition
-        self.acquire_lock_with_2pc(request).await
+    /// Whether the lease of a lock acquired through this namespace lock is still being renewed
+    pub fn is_lease_held(&self, lock_id: &LockId) -> bool {
+        self.leases.is_held(lock_id)
     }
 
-    /// Two-phase commit lock acquisition: all nodes must succeed or all fail
-    async fn acquire_lock_with_2pc(&self, request: &LockRequest) -> Result<LockResponse> {
-        // Phase 1: Prepare - try to acquire lock on all clients
-        let futures: Vec<_> = self
-            .clients
-            .iter()
-            .enumerate()
-            .map(|(idx, client)| async move {
-                let result = client.acquire_lock(request).await;
-                (idx, result)
-            })
-            .collect();
+    /// Renew the leases of held locks until this namespace lock is dropped
+    fn spawn_lease_refresher(&self) {
+        let leases = Arc::downgrade(&self.leases);
+        let clients = self.clients.clone();
+        tokio::spawn(async move {
+            while let Some(held) = Weak::upgrade(&leases) {
+                // A lease lives on while a majority renews it, whatever the quorum it was granted by
+                let quorum = QuorumPolicy::Majority.required(leased_clients(&clients).await.len());
+                for (lock_id, granted) in held.due() {
+                    // Renewing elsewhere would keep alive a grant someone failed to release there
+                    let clients = leased_clients(&granted).await;
+                    let results = futures::future::join_all(clients.iter().map(|client| client.refresh(&lock_id))).await;
+                    let renewed = results.iter().filter(|r| matches!(r, Ok(true))).count();
+                    if renewed >= quorum {
+                        held.renewed(&lock_id);
+                    } else {
+                        tracing::warn!("Lost lease of lock {}: renewed on {}/{} clients", lock_id, renewed, quorum);
+                        if let Some(owner) = held.lost(&lock_id) {
+                            let failed = clients
+                                .iter()
+                                .zip(&results)
+                                .filter(|(_, r)| !matches!(r, Ok(true)))
+                                .map(|(client, _)| client_node(client))
+                                .collect();
+                            audit(
+                                LockAuditKind::LeaseLost,
+                                &lock_id.resource,
+                                vec![owner],
+                                failed,
+                                format!("renewed on {renewed} of {} nodes, {quorum} required", clients.len()),
+                            );
+                        }
+                    }
+                }
 
-        let results = futures::future::join_all(futures).await;
-        let mut successful_clients = Vec::new();
-        let mut failed_clients = Vec::new();
+                let wait = held
+                    .next_renewal()
+                    .map(|at| at.saturating_duration_since(Instant::now()))
+                    .unwrap_or(LEASE_REFRESHER_IDLE)
+                    .min(LEASE_REFRESHER_IDLE);
+                let _ = tokio::time::timeout(wait, held.changed.notified()).await;
+            }
+        });
+    }
 
-        // Collect results
-        for (idx, result) in results {
-            match result {
-                Ok(response) if response.success => {
-                    successful_clients.push(idx);
-                }
-                _ => {
-                    failed_clients.push(idx);
+    /// Two-phase commit lock acquisition: a quorum of nodes must succeed or all fail
+    ///
+    /// Each attempt waits on the clients for at most a third of the ttl and rolls back a grant
+    /// short of the quorum before retrying, so a grant never outlives its lease unrenewed and two
+    /// owners holding part of the clients each do not block one another until the timeout. An
+    /// attempt still waits in the queue of every client, where writers get their priority.
+    async fn acquire_lock_with_2pc(
+        &self,
+        clients: &[Arc<dyn LockClient>],
+        request: &LockRequest,
+    ) -> Result<(LockResponse, AcquireOutcome, Vec<Arc<dyn LockClient>>)> {
+        let quorum = self.quorum_policy.required(clients.len());
+        let deadline = Instant::now() + request.wait_budget();
+        let mut attempts = 0;
+
+        loop {
+            let mut attempt_wait = deadline.saturating_duration_since(Instant::now());
+            if !request.ttl.is_zero() {
+                attempt_wait = attempt_wait.min(request.ttl / 3);
+            }
+            let mut attempt = request.clone().with_deadline(Instant::now() + attempt_wait);
+            attempt.acquire_timeout = attempt_wait;
+            let (successful_clients, failed_clients) = self.prepare_2pc(clients, &attempt).await;
+
+            // A canceled caller gets no lock, whatever the lockers granted in the meantime. A locker whose
+            // grant raced the cancellation keeps it until the lease expires, nobody renews it.
+            if request.is_canceled() {
+                if !successful_clients.is_empty() {
+                    self.rollback_acquisitions(clients, request, &successful_clients).await;
                 }
+                return Err(LockError::canceled(&request.resource));
             }
-        }
 
-        // Check if we have enough successful acquisitions for quorum
-        if successful_clients.len() >= self.quorum {
-            // Phase 2a: Commit - we have quorum, but need to ensure consistency
-            // If not all clients succeeded, we need to rollback for consistency
-            if successful_clients.len() < self.clients.len() {
-                // Rollback all successful acquisitions to maintain consistency
-                self.rollback_acquisitions(request, &successful_clients).await;
-                return Ok(LockResponse::failure(
-                    "Partial success detected, rolled back for consistency".to_string(),
+            // Check if we have enough successful acquisitions for quorum
+            if successful_clients.len() >= quorum {
+                // Phase 2a: Commit - the lock stays held on the clients that granted it
+                let response = LockResponse::success(
+                    LockInfo {
+                        id: LockId::new_deterministic(&request.resource),
+                        resource: request.resource.clone(),
+                        lock_type: request.lock_type,
+                        status: LockStatus::Acquired,
+                        owner: request.owner.clone(),
+                        acquired_at: std::time::SystemTime::now(),
+                        expires_at: std::time::SystemTime::now() + request.ttl,
+                        last_refreshed: std::time::SystemTime::now(),
+                        metadata: request.metadata.clone(),
+                        priority: request.priority,
+                        wait_start_time: None,
+                    },
                     Duration::ZERO,
-                ));
+                );
+                let granted = successful_clients.iter().map(|&idx| clients[idx].clone()).collect();
+                return Ok((response, AcquireOutcome::Acquired, granted));
             }
 
-            // All clients succeeded - lock acquired successfully
-            Ok(LockResponse::success(
-                LockInfo {
-                    id: LockId::new_deterministic(&request.resource),
-                    resource: request.resource.clone(),
-                    lock_type: request.lock_type,
-                    status: LockStatus::Acquired,
-                    owner: request.owner.clone(),
-                    acquired_at: std::time::SystemTime::now(),
-                    expires_at: std::time::SystemTime::now() + request.ttl,
-                    last_refreshed: std::time::SystemTime::now(),
-                    metadata: request.metadata.clone(),
-                    priority: request.priority,
-                    wait_start_time: None,
-                },
-                Duration::ZERO,
-            ))
-        } else {
             // Phase 2b: Abort - insufficient quorum, rollback any successful acquisitions
             if !successful_clients.is_empty() {
-                self.rollback_acquisitions(request, &successful_clients).await;
+                self.rollback_acquisitions(clients, request, &successful_clients).await;
+            }
+            if Instant::now() < deadline {
+                let delay = ATTEMPT_BACKOFF
+                    .delay(attempts)
+                    .min(deadline.saturating_duration_since(Instant::now()));
+                match &request.cancel {
+                    Some(cancel) => tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = cancel.cancelled() => return Err(LockError::canceled(&request.resource)),
+                    },
+                    None => tokio::time::sleep(delay).await,
+                }
+                attempts += 1;
+                continue;
+            }
+
+            // Nobody granted the lock when every locker timed out, otherwise the lockers disagree
+            let outcome = if successful_clients.is_empty() {
+                AcquireOutcome::Timeout
+            } else {
+                audit(
+                    LockAuditKind::QuorumFailure,
+                    &request.resource,
+                    vec![request.owner.clone()],
+                    failed_clients.iter().map(|&idx| client_node(&clients[idx])).collect(),
+                    format!("granted by {} of {} nodes, {quorum} required", successful_clients.len(), clients.len()),
+                );
+                AcquireOutcome::QuorumFailure
+            };
+            return Ok((
+                LockResponse::failure(
+                    format!("Failed to acquire quorum: {}/{} required", successful_clients.len(), quorum),
+                    Duration::ZERO,
+                ),
+                outcome,
+                Vec::new(),
+            ));
+        }
+    }
+
+    /// Phase 1 of the two-phase commit: try to acquire the lock on all clients, returning the
+    /// indices of the clients that granted it and of those that did not
+    async fn prepare_2pc(&self, clients: &[Arc<dyn LockClient>], request: &LockRequest) -> (Vec<usize>, Vec<usize>) {
+        let futures: Vec<_> = clients
+            .iter()
+            .enumerate()
+            .map(|(idx, client)| async move {
+                let result = match &request.cancel {
+                    // Remote lockers never see the token, stop waiting on them here instead
+                    Some(cancel) => tokio::select! {
+                        result = client.acquire_lock(request) => result,
+                        _ = cancel.cancelled() => Err(LockError::canceled(&request.resource)),
+                    },
+                    None => client.acquire_lock(request).await,
+                };
+                (idx, result)
+            })
+            .collect();
+
+        let mut successful_clients = Vec::new();
+        let mut failed_clients = Vec::new();
+        for (idx, result) in futures::future::join_all(futures).await {
+            match result {
+                Ok(response) if response.success => successful_clients.push(idx),
+                _ => failed_clients.push(idx),
             }
-            Ok(LockResponse::failure(
-                format!("Failed to acquire quorum: {}/{} required", successful_clients.len(), self.quorum),
-                Duration::ZERO,
-            ))
         }
+        (successful_clients, failed_clients)
     }
 
     /// Rollback lock acquisitions on specified clients
-    async fn rollback_acquisitions(&self, request: &LockRequest, client_indices: &[usize]) {
+    async fn rollback_acquisitions(&self, clients: &[Arc<dyn LockClient>], request: &LockRequest, client_indices: &[usize]) {
         let lock_id = LockId::new_deterministic(&request.resource);
         let rollback_futures: Vec<_> = client_indices
             .iter()
-            .filter_map(|&idx| self.clients.get(idx))
+            .filter_map(|&idx| clients.get(idx))
             .map(|client| async {
                 if let Err(e) = client.release(&lock_id).await {
                     tracing::warn!("Failed to rollback lock on client: {}", e);
@@ -184,18 +557,20 @@ impl NamespaceLock {
 
     /// Release lock using clients
     pub async fn release_lock(&self, lock_id: &LockId) -> Result<bool> {
-        if self.clients.is_empty() {
+        let clients = self.clients();
+        if clients.is_empty() {
             return Err(LockError::internal("No lock clients available"));
         }
 
+        self.leases.remove(lock_id);
+
         // For single client, use it directly
-        if self.clients.len() == 1 {
-            return self.clients[0].release(lock_id).await;
+        if clients.len() == 1 {
+            return clients[0].release(lock_id).await;
         }
 
         // For multiple clients, try to release from all clients
-        let futures: Vec<_> = self
-            .clients
+        let futures: Vec<_> = clients
             .iter()
             .map(|client| {
                 let id = lock_id.clone();
@@ -210,6 +585,19 @@ impl NamespaceLock {
         Ok(successful > 0)
     }
 
+    /// Release the lock `owner` acquired through this namespace lock on the clients that granted it
+    ///
+    /// Falls back to [`NamespaceLock::release_lock`] for locks this namespace lock did not grant.
+    pub async fn release_lock_of(&self, lock_id: &LockId, owner: &str) -> Result<bool> {
+        let Some(clients) = self.grants.take(lock_id, owner) else {
+            return self.release_lock(lock_id).await;
+        };
+        self.leases.remove(lock_id);
+
+        let results = futures::future::join_all(clients.iter().map(|client| client.release(lock_id))).await;
+        Ok(results.into_iter().any(|r| matches!(r, Ok(true))))
+    }
+
     /// Get health information
     pub async fn get_health(&self) -> crate::types::HealthInfo {
         let lock_stats = self.get_stats().await;
@@ -220,8 +608,9 @@ impl NamespaceLock {
         };
 
         // Check client status
+        let clients = self.clients();
         let mut connected_clients = 0;
-        for client in &self.clients {
+        for client in clients.iter() {
             if client.is_online().await {
                 connected_clients += 1;
             }
@@ -233,7 +622,7 @@ impl NamespaceLock {
             crate::types::HealthStatus::Degraded
         };
         health.connected_nodes = connected_clients;
-        health.total_nodes = self.clients.len();
+        health.total_nodes = clients.len();
 
         health
     }
@@ -243,7 +632,7 @@ impl NamespaceLock {
         let mut stats = crate::types::LockStats::default();
 
         // Try to get stats from clients
-        for client in &self.clients {
+        for client in self.clients().iter() {
             if let Ok(client_stats) = client.get_stats().await {
                 stats.successful_acquires += client_stats.successful_acquires;
                 stats.failed_acquires += client_stats.failed_acquires;
@@ -279,44 +668,23 @@ pub trait NamespaceLockManager: Send + Sync {
 #[async_trait]
 impl NamespaceLockManager for NamespaceLock {
     async fn lock_batch(&self, resources: &[String], owner: &str, timeout: Duration, ttl: Duration) -> Result<bool> {
-        if self.clients.is_empty() {
-            return Err(LockError::internal("No lock clients available"));
-        }
-
-        // Transactional batch lock: all resources must be locked or none
-        let mut acquired_resources = Vec::new();
-
-        for resource in resources {
-            let namespaced_resource = self.get_resource_key(resource);
-            let request = LockRequest::new(&namespaced_resource, LockType::Exclusive, owner)
-                .with_acquire_timeout(timeout)
-                .with_ttl(ttl);
-
-            let response = self.acquire_lock(&request).await?;
-            if response.success {
-                acquired_resources.push(namespaced_resource);
-            } else {
-                // Rollback all previously acquired locks
-                self.rollback_batch_locks(&acquired_resources, owner).await;
-                return Ok(false);
-            }
-        }
-        Ok(true)
+        self.acquire_batch(resources, owner, LockType::Exclusive, timeout, ttl, None)
+            .await
     }
 
-    async fn unlock_batch(&self, resources: &[String], _owner: &str) -> Result<()> {
-        if self.clients.is_empty() {
+    async fn unlock_batch(&self, resources: &[String], owner: &str) -> Result<()> {
+        if self.clients().is_empty() {
             return Err(LockError::internal("No lock clients available"));
         }
 
         // Release all locks (best effort)
-        let release_futures: Vec<_> = resources
-            .iter()
+        let release_futures: Vec<_> = self
+            .batch_keys(resources)
+            .into_iter()
             .map(|resource| {
-                let namespaced_resource = self.get_resource_key(resource);
-                let lock_id = LockId::new_deterministic(&namespaced_resource);
+                let lock_id = LockId::new_deterministic(&resource);
                 async move {
-                    if let Err(e) = self.release_lock(&lock_id).await {
+                    if let Err(e) = self.release_lock_of(&lock_id, owner).await {
                         tracing::warn!("Failed to release lock for resource {}: {}", resource, e);
                     }
                 }
@@ -328,44 +696,23 @@ impl NamespaceLockManager for NamespaceLock {
     }
 
     async fn rlock_batch(&self, resources: &[String], owner: &str, timeout: Duration, ttl: Duration) -> Result<bool> {
-        if self.clients.is_empty() {
-            return Err(LockError::internal("No lock clients available"));
-        }
-
-        // Transactional batch read lock: all resources must be locked or none
-        let mut acquired_resources = Vec::new();
-
-        for resource in resources {
-            let namespaced_resource = self.get_resource_key(resource);
-            let request = LockRequest::new(&namespaced_resource, LockType::Shared, owner)
-                .with_acquire_timeout(timeout)
-                .with_ttl(ttl);
-
-            let response = self.acquire_lock(&request).await?;
-            if response.success {
-                acquired_resources.push(namespaced_resource);
-            } else {
-                // Rollback all previously acquired read locks
-                self.rollback_batch_locks(&acquired_resources, owner).await;
-                return Ok(false);
-            }
-        }
-        Ok(true)
+        self.acquire_batch(resources, owner, LockType::Shared, timeout, ttl, None)
+            .await
     }
 
-    async fn runlock_batch(&self, resources: &[String], _owner: &str) -> Result<()> {
-        if self.clients.is_empty() {
+    async fn runlock_batch(&self, resources: &[String], owner: &str) -> Result<()> {
+        if self.clients().is_empty() {
             return Err(LockError::internal("No lock clients available"));
         }
 
         // Release all read locks (best effort)
-        let release_futures: Vec<_> = resources
-            .iter()
+        let release_futures: Vec<_> = self
+            .batch_keys(resources)
+            .into_iter()
             .map(|resource| {
-                let namespaced_resource = self.get_resource_key(resource);
-                let lock_id = LockId::new_deterministic(&namespaced_resource);
+                let lock_id = LockId::new_deterministic(&resource);
                 async move {
-                    if let Err(e) = self.release_lock(&lock_id).await {
+                    if let Err(e) = self.release_lock_of(&lock_id, owner).await {
                         tracing::warn!("Failed to release read lock for resource {}: {}", resource, e);
                     }
                 }
@@ -378,14 +725,115 @@ impl NamespaceLockManager for NamespaceLock {
 }
 
 impl NamespaceLock {
+    /// Batch get write lock, giving up with `LockError::Canceled` once `cancel` fires
+    pub async fn lock_batch_with_cancel(
+        &self,
+        resources: &[String],
+        owner: &str,
+        timeout: Duration,
+        ttl: Duration,
+        cancel: CancellationToken,
+    ) -> Result<bool> {
+        self.acquire_batch(resources, owner, LockType::Exclusive, timeout, ttl, Some(cancel))
+            .await
+    }
+
+    /// Lock a batch of resources, returning a guard that releases them once dropped, or `None`
+    /// when they could not be locked in time
+    pub async fn get_lock(
+        self: &Arc<Self>,
+        resources: &[String],
+        owner: &str,
+        lock_type: LockType,
+        timeout: Duration,
+        ttl: Duration,
+        cancel: Option<CancellationToken>,
+    ) -> Result<Option<LockGuard>> {
+        if !self.acquire_batch(resources, owner, lock_type, timeout, ttl, cancel).await? {
+            return Ok(None);
+        }
+        let leases = if ttl.is_zero() {
+            Vec::new()
+        } else {
+            self.batch_keys(resources)
+                .into_iter()
+                .map(|key| {
+                    let lock_id = LockId::new_deterministic(&key);
+                    // A lease gone already was lost between its grant and now
+                    let lost = self.leases.lost_token(&lock_id).unwrap_or_else(|| {
+                        let lost = CancellationToken::new();
+                        lost.cancel();
+                        lost
+                    });
+                    (key, lost)
+                })
+                .collect()
+        };
+        Ok(Some(LockGuard::new(self.clone(), resources.to_vec(), owner, lock_type, leases)))
+    }
+
+    /// Namespaced keys of a batch, sorted and deduplicated
+    ///
+    /// Every batch takes its locks in key order, so two batches sharing resources can never each
+    /// hold a lock the other one waits for.
+    fn batch_keys(&self, resources: &[String]) -> Vec<String> {
+        let mut keys: Vec<String> = resources.iter().map(|resource| self.get_resource_key(resource)).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+
+    /// Transactional batch lock: all resources must be locked or none
+    async fn acquire_batch(
+        &self,
+        resources: &[String],
+        owner: &str,
+        lock_type: LockType,
+        timeout: Duration,
+        ttl: Duration,
+        cancel: Option<CancellationToken>,
+    ) -> Result<bool> {
+        if self.clients().is_empty() {
+            return Err(LockError::internal("No lock clients available"));
+        }
+
+        let mut acquired_resources = Vec::new();
+        // The timeout covers the whole batch, later resources only get what the earlier ones left
+        let deadline = Instant::now() + timeout;
+
+        for namespaced_resource in self.batch_keys(resources) {
+            let mut request = LockRequest::new(&namespaced_resource, lock_type, owner)
+                .with_acquire_timeout(timeout)
+                .with_ttl(ttl)
+                .with_deadline(deadline);
+            request.cancel = cancel.clone();
+
+            let response = match self.acquire_lock(&request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    self.rollback_batch_locks(&acquired_resources, owner).await;
+                    return Err(e);
+                }
+            };
+            if response.success {
+                acquired_resources.push(namespaced_resource);
+            } else {
+                // Rollback all previously acquired locks
+                self.rollback_batch_locks(&acquired_resources, owner).await;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     /// Rollback batch lock acquisitions
-    async fn rollback_batch_locks(&self, acquired_resources: &[String], _owner: &str) {
+    async fn rollback_batch_locks(&self, acquired_resources: &[String], owner: &str) {
         let rollback_futures: Vec<_> = acquired_resources
             .iter()
             .map(|resource| {
                 let lock_id = LockId::new_deterministic(resource);
                 async move {
-                    if let Err(e) = self.release_lock(&lock_id).await {
+                    if let Err(e) = self.release_lock_of(&lock_id, owner).await {
                         tracing::warn!("Failed to rollback lock for resource {}: {}", resource, e);
                     }
                 }
@@ -420,6 +868,76 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_lock_batch_canceled() {
+        let ns_lock = NamespaceLock::with_client(Arc::new(LocalClient::new()));
+        let resources = vec!["test_lock_batch_canceled".to_string()];
+        assert!(
+            ns_lock
+                .lock_batch(&resources, "holder", Duration::from_millis(100), Duration::from_secs(10))
+                .await
+                .unwrap()
+        );
+
+        let cancel = CancellationToken::new();
+        let canceler = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceler.cancel();
+        });
+
+        let start = Instant::now();
+        let result = ns_lock
+            .lock_batch_with_cancel(&resources, "waiter", Duration::from_secs(10), Duration::from_secs(10), cancel)
+            .await;
+        assert!(matches!(result, Err(LockError::Canceled { .. })));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        ns_lock.unlock_batch(&resources, "holder").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lock_batch_opposite_orders() {
+        let ns_lock = NamespaceLock::with_client(Arc::new(LocalClient::new()));
+        let forward = vec!["test_batch_order_a".to_string(), "test_batch_order_b".to_string()];
+        let backward: Vec<String> = forward.iter().rev().cloned().collect();
+
+        // Both batches take their locks in the same order, so each one gets all of them in turn
+        let run = |resources: Vec<String>, owner: &'static str| {
+            let ns_lock = &ns_lock;
+            async move {
+                for _ in 0..20 {
+                    assert!(
+                        ns_lock
+                            .lock_batch(&resources, owner, Duration::from_secs(5), Duration::from_secs(10))
+                            .await
+                            .unwrap()
+                    );
+                    tokio::task::yield_now().await;
+                    ns_lock.unlock_batch(&resources, owner).await.unwrap();
+                }
+            }
+        };
+        tokio::join!(run(forward.clone(), "forward"), run(backward, "backward"));
+
+        // Duplicates are locked once
+        let duplicated = vec![forward[0].clone(), forward[0].clone()];
+        assert!(
+            ns_lock
+                .lock_batch(&duplicated, "dup", Duration::from_millis(100), Duration::from_secs(10))
+                .await
+                .unwrap()
+        );
+        ns_lock.unlock_batch(&duplicated, "dup").await.unwrap();
+        assert!(
+            ns_lock
+                .lock_batch(&forward, "after", Duration::from_millis(100), Duration::from_secs(10))
+                .await
+                .unwrap()
+        );
+        ns_lock.unlock_batch(&forward, "after").await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_connection_health() {
         let local_lock = NamespaceLock::new("test-namespace".to_string());
@@ -437,8 +955,8 @@ mod tests {
     async fn test_namespace_lock_new_local() {
         let ns_lock = NamespaceLock::with_client(Arc::new(LocalClient::new()));
         assert_eq!(ns_lock.namespace(), "default");
-        assert_eq!(ns_lock.clients.len(), 1);
-        assert!(ns_lock.clients[0].is_local().await);
+        assert_eq!(ns_lock.clients().len(), 1);
+        assert!(ns_lock.clients()[0].is_local().await);
 
         // Test that it can perform lock operations
         let resources = vec!["test-resource".to_string()];
@@ -497,9 +1015,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_distributed_lock_consistency() {
-        // Create a namespace with multiple local clients to simulate distributed scenario
+        // Create a namespace with clients of two lock maps to simulate distributed scenario
         let client1: Arc<dyn LockClient> = Arc::new(LocalClient::new());
-        let client2: Arc<dyn LockClient> = Arc::new(LocalClient::new());
+        let client2: Arc<dyn LockClient> = Arc::new(crate::InProcessClient::new());
         let clients = vec![client1, client2];
 
         let ns_lock = NamespaceLock::with_clients("test-namespace".to_string(), clients);
@@ -509,8 +1027,93 @@ mod tests {
         // This should succeed only if ALL clients can acquire the lock
         let response = ns_lock.acquire_lock(&request).await.unwrap();
 
-        // Since we're using separate LocalClient instances, they don't share state
-        // so this test demonstrates the consistency check
+        // The clients don't share state, so both grant it
         assert!(response.success); // Either all succeed or rollback happens
     }
+
+    #[tokio::test]
+    async fn test_quorum_of_clients() {
+        let local = |n: usize| {
+            (0..n)
+                .map(|_| Arc::new(LocalClient::new()) as Arc<dyn LockClient>)
+                .collect::<Vec<_>>()
+        };
+
+        let ns_lock =
+            NamespaceLock::with_clients("test-namespace".to_string(), local(3)).with_quorum_policy(QuorumPolicy::Majority);
+        assert_eq!(ns_lock.quorum(), 2);
+        let ns_lock = ns_lock.with_quorum_policy(QuorumPolicy::All);
+        assert_eq!(ns_lock.quorum(), 3);
+
+        let ns_lock =
+            NamespaceLock::with_clients("test-namespace".to_string(), local(4)).with_quorum_policy(QuorumPolicy::Majority);
+        assert_eq!(ns_lock.quorum(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_2pc_outcome_of_contended_lock() {
+        let holder = LockRequest::new("contended-outcome", LockType::Exclusive, "holder").with_ttl(Duration::from_secs(10));
+        assert!(crate::get_global_lock_map().lock_with_ttl_id(&holder).await.unwrap());
+
+        let clients: Vec<Arc<dyn LockClient>> = vec![Arc::new(LocalClient::new()), Arc::new(LocalClient::new())];
+        let ns_lock = NamespaceLock::with_clients("test-namespace".to_string(), clients);
+        let request = LockRequest::new("contended-outcome", LockType::Exclusive, "waiter")
+            .with_acquire_timeout(Duration::from_millis(50))
+            .with_ttl(Duration::from_secs(10));
+
+        let (response, outcome, _) = ns_lock.acquire_lock_with_2pc(&ns_lock.clients(), &request).await.unwrap();
+        assert!(!response.success);
+        assert_eq!(outcome, AcquireOutcome::Timeout);
+
+        crate::get_global_lock_map()
+            .unlock_by_id_and_owner(&holder.lock_id, "holder")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lease_is_renewed_while_held() {
+        let ns_lock = NamespaceLock::with_client(Arc::new(LocalClient::new()));
+        let resources = vec!["lease-renewed".to_string()];
+        let ttl = Duration::from_millis(300);
+        assert!(
+            ns_lock
+                .lock_batch(&resources, "owner1", Duration::from_millis(100), ttl)
+                .await
+                .unwrap()
+        );
+
+        tokio::time::sleep(ttl * 3).await;
+        let lock_id = LockId::new_deterministic(&ns_lock.get_resource_key("lease-renewed"));
+        assert!(ns_lock.is_lease_held(&lock_id));
+        assert!(
+            !ns_lock
+                .lock_batch(&resources, "owner2", Duration::from_millis(50), ttl)
+                .await
+                .unwrap()
+        );
+
+        ns_lock.unlock_batch(&resources, "owner1").await.unwrap();
+        assert!(!ns_lock.is_lease_held(&lock_id));
+    }
+
+    #[tokio::test]
+    async fn test_lease_expires_when_owner_is_gone() {
+        let resources = vec!["lease-crashed".to_string()];
+        let ttl = Duration::from_millis(300);
+
+        // The owner goes away without unlocking, as if its node crashed
+        let crashed = NamespaceLock::with_client(Arc::new(LocalClient::new()));
+        assert!(
+            crashed
+                .lock_batch(&resources, "owner1", Duration::from_millis(100), ttl)
+                .await
+                .unwrap()
+        );
+        drop(crashed);
+
+        let ns_lock = NamespaceLock::with_client(Arc::new(LocalClient::new()));
+        assert!(ns_lock.lock_batch(&resources, "owner2", ttl * 3, ttl).await.unwrap());
+        ns_lock.unlock_batch(&resources, "owner2").await.unwrap();
+    }
 }
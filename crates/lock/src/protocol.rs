@@ -0,0 +1,107 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versions and optional features of the lock protocol
+//!
+//! Nodes exchange their capabilities through the Hello RPC before relying on a lock semantic, so
+//! old and new nodes keep locking together while a cluster is upgraded one node at a time. A peer
+//! that does not know Hello speaks protocol version 1 and has no optional feature.
+
+use std::collections::BTreeSet;
+
+/// Protocol version of this build
+pub const LOCK_PROTOCOL_VERSION: u32 = 2;
+
+/// Protocol version of peers predating capability negotiation
+pub const LEGACY_LOCK_PROTOCOL_VERSION: u32 = 1;
+
+/// Locks are leases: they expire unless renewed through Refresh, and survive a restart of the
+/// lock server when it keeps a journal
+pub const FEATURE_LEASE: &str = "lease";
+
+/// Batches lock their resources in key order, so overlapping batches cannot deadlock
+pub const FEATURE_BATCH_LOCK: &str = "batch-lock";
+
+/// Protocol version and optional features of a lock server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockCapabilities {
+    pub protocol_version: u32,
+    pub features: BTreeSet<String>,
+}
+
+impl LockCapabilities {
+    /// Capabilities of this build
+    pub fn current() -> Self {
+        Self {
+            protocol_version: LOCK_PROTOCOL_VERSION,
+            features: [FEATURE_LEASE, FEATURE_BATCH_LOCK].into_iter().map(str::to_string).collect(),
+        }
+    }
+
+    /// Capabilities of a peer that does not know the Hello RPC
+    pub fn legacy() -> Self {
+        Self {
+            protocol_version: LEGACY_LOCK_PROTOCOL_VERSION,
+            features: BTreeSet::new(),
+        }
+    }
+
+    pub fn new(protocol_version: u32, features: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            protocol_version,
+            features: features.into_iter().collect(),
+        }
+    }
+
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// What both sides can rely on: the lower version and the features both support
+    pub fn common(&self, other: &Self) -> Self {
+        Self {
+            protocol_version: self.protocol_version.min(other.protocol_version),
+            features: self.features.intersection(&other.features).cloned().collect(),
+        }
+    }
+}
+
+impl Default for LockCapabilities {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_capabilities() {
+        let current = LockCapabilities::current();
+        assert!(current.supports(FEATURE_LEASE));
+        assert_eq!(current.common(&current), current);
+
+        let legacy = current.common(&LockCapabilities::legacy());
+        assert_eq!(legacy, LockCapabilities::legacy());
+        assert!(!legacy.supports(FEATURE_LEASE));
+
+        // A newer peer may know features this build does not
+        let newer = LockCapabilities::new(3, ["lease".to_string(), "fencing".to_string()]);
+        let common = current.common(&newer);
+        assert_eq!(common.protocol_version, LOCK_PROTOCOL_VERSION);
+        assert!(common.supports(FEATURE_LEASE));
+        assert!(!common.supports(FEATURE_BATCH_LOCK) && !common.supports("fencing"));
+    }
+}
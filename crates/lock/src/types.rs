@@ -13,7 +13,8 @@
 // limitations under the License.
 
 use serde::{Deserialize, Serialize};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 /// Lock type enumeration
@@ -50,6 +51,58 @@ pub enum LockPriority {
     Critical = 4,
 }
 
+/// How many lock clients of a namespace lock must grant a lock
+///
+/// The count is derived from the number of clients, one per node of the erasure set, rather than
+/// fixed when the namespace lock is built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum QuorumPolicy {
+    /// More than half of the nodes
+    Majority,
+    /// Every node, a partial grant is rolled back
+    #[default]
+    All,
+    /// A fixed number of nodes, never fewer than a majority nor more than all of them
+    Count(usize),
+}
+
+impl QuorumPolicy {
+    /// Number of the `nodes` clients that must grant a lock
+    pub fn required(&self, nodes: usize) -> usize {
+        let majority = nodes / 2 + 1;
+        match self {
+            QuorumPolicy::Majority => majority.min(nodes),
+            QuorumPolicy::All => nodes,
+            QuorumPolicy::Count(n) => (*n).clamp(majority.min(nodes), nodes),
+        }
+    }
+}
+
+impl std::fmt::Display for QuorumPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuorumPolicy::Majority => write!(f, "majority"),
+            QuorumPolicy::All => write!(f, "all"),
+            QuorumPolicy::Count(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+impl std::str::FromStr for QuorumPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "majority" => Ok(QuorumPolicy::Majority),
+            "all" => Ok(QuorumPolicy::All),
+            n => match n.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(QuorumPolicy::Count(n)),
+                _ => Err(format!("invalid lock quorum '{s}', expected majority, all or a positive count")),
+            },
+        }
+    }
+}
+
 /// Lock information structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LockInfo {
@@ -252,6 +305,13 @@ pub struct LockRequest {
     pub priority: LockPriority,
     /// Deadlock detection
     pub deadlock_detection: bool,
+    /// Aborts the wait for the lock, e.g. once the request needing it is gone; local only
+    #[serde(skip)]
+    pub cancel: Option<CancellationToken>,
+    /// When the caller stops waiting, whatever `acquire_timeout` allows; sent to remote lockers as
+    /// the time left
+    #[serde(skip)]
+    pub deadline: Option<Instant>,
 }
 
 impl LockRequest {
@@ -268,6 +328,8 @@ impl LockRequest {
             metadata: LockMetadata::default(),
             priority: LockPriority::default(),
             deadlock_detection: false,
+            cancel: None,
+            deadline: None,
         }
     }
 
@@ -300,6 +362,31 @@ impl LockRequest {
         self.deadlock_detection = enabled;
         self
     }
+
+    /// Give up waiting for the lock once `cancel` fires
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Stop waiting for the lock at `deadline`, e.g. when the request needing it times out
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// How long the lock may still be waited for: `acquire_timeout`, cut short by the deadline
+    pub fn wait_budget(&self) -> Duration {
+        match self.deadline {
+            Some(deadline) => self.acquire_timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => self.acquire_timeout,
+        }
+    }
+
+    /// Whether the wait for the lock was canceled
+    pub fn is_canceled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|cancel| cancel.is_cancelled())
+    }
 }
 
 /// Lock response structure
@@ -429,6 +516,23 @@ impl Default for LockStats {
     }
 }
 
+/// A lock held on a node, as listed for lock introspection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeldLock {
+    /// Locked resource name
+    pub resource: String,
+    /// Lock UID
+    pub uid: String,
+    /// Holder of the lock, one entry is listed per reader of a shared lock
+    pub owner: String,
+    /// Lock type
+    pub lock_type: LockType,
+    /// Time the lock was acquired, shared locks report when the first reader got it
+    pub acquired_at: SystemTime,
+    /// Number of lock requests waiting for the resource
+    pub waiters: usize,
+}
+
 /// Node information structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeInfo {
@@ -652,6 +756,19 @@ mod tests {
         assert!(request.deadlock_detection);
     }
 
+    #[test]
+    fn test_lock_request_wait_budget() {
+        let request =
+            LockRequest::new("test-resource", LockType::Exclusive, "test-owner").with_acquire_timeout(Duration::from_secs(60));
+        assert_eq!(request.wait_budget(), Duration::from_secs(60));
+
+        let request = request.with_deadline(Instant::now() + Duration::from_secs(1));
+        assert!(request.wait_budget() <= Duration::from_secs(1));
+
+        let request = request.with_deadline(Instant::now());
+        assert_eq!(request.wait_budget(), Duration::ZERO);
+    }
+
     #[test]
     fn test_lock_response() {
         let lock_info = LockInfo {
@@ -689,6 +806,22 @@ mod tests {
         assert!(diff < Duration::from_secs(1));
     }
 
+    #[test]
+    fn test_quorum_policy() {
+        assert_eq!("majority".parse::<QuorumPolicy>().unwrap().required(4), 3);
+        assert_eq!("ALL".parse::<QuorumPolicy>().unwrap().required(4), 4);
+        assert_eq!("3".parse::<QuorumPolicy>().unwrap(), QuorumPolicy::Count(3));
+        assert!("0".parse::<QuorumPolicy>().is_err());
+        assert!("most".parse::<QuorumPolicy>().is_err());
+
+        // Explicit counts follow the node count, bounded by majority and all
+        assert_eq!(QuorumPolicy::Count(3).required(5), 3);
+        assert_eq!(QuorumPolicy::Count(1).required(5), 3);
+        assert_eq!(QuorumPolicy::Count(3).required(2), 2);
+        assert_eq!(QuorumPolicy::Majority.required(1), 1);
+        assert_eq!(QuorumPolicy::Count(2).required(0), 0);
+    }
+
     #[test]
     fn test_serialization() {
         let request = LockRequest::new("test", LockType::Exclusive, "owner");
@@ -136,6 +136,8 @@ pub struct ScannerMetrics {
     pub life_time_ops: HashMap<String, u64>,
     #[serde(rename = "ilm_ops")]
     pub life_time_ilm: HashMap<String, u64>,
+    #[serde(rename = "ilm_rule_ops", default)]
+    pub life_time_ilm_rules: HashMap<String, HashMap<String, u64>>,
     #[serde(rename = "last_minute")]
     pub last_minute: LastMinute,
     #[serde(rename = "active")]
@@ -178,6 +180,13 @@ impl ScannerMetrics {
             *self.life_time_ilm.entry(k.clone()).or_default() += v;
         }
 
+        for (rule, actions) in other.life_time_ilm_rules.iter() {
+            let merged = self.life_time_ilm_rules.entry(rule.clone()).or_default();
+            for (k, v) in actions.iter() {
+                *merged.entry(k.clone()).or_default() += v;
+            }
+        }
+
         for (k, v) in other.last_minute.ilm.iter() {
             self.last_minute.ilm.entry(k.clone()).or_default().merge(v);
         }
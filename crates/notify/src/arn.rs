@@ -124,13 +124,13 @@ impl ARN {
     }
 
     /// Returns the string representation of ARN
-    /// Returns the ARN string in the format "{ARN_PREFIX}:{region}:{target_id}"
+    /// Returns the ARN string in the format "{ARN_PREFIX}{region}:{target_id}"
     #[allow(clippy::inherent_to_string)]
     pub fn to_arn_string(&self) -> String {
         if self.target_id.id.is_empty() && self.target_id.name.is_empty() && self.region.is_empty() {
             return String::new();
         }
-        format!("{}:{}:{}", ARN_PREFIX, self.region, self.target_id.to_id_string())
+        format!("{}{}:{}", ARN_PREFIX, self.region, self.target_id.to_id_string())
     }
 
     /// Parsing ARN from string
@@ -68,6 +68,9 @@ pub enum EventName {
     ScannerBigPrefix = 31,                   // PrefixManyFolders corresponding to Go
     LifecycleDelMarkerExpirationDelete = 32, // ILMDelMarkerExpirationDelete corresponding to Go
 
+    // Opt-in single event types, only delivered to configurations naming them
+    ObjectCopyProgress = 33, // Periodic progress of a long running server-side copy
+
     // Compound "All" event type (no sequential value for mask)
     ObjectAccessedAll,
     ObjectCreatedAll,
@@ -79,7 +82,7 @@ pub enum EventName {
     Everything,       // New, from Go
 }
 
-// Single event type sequential array for Everything.expand()
+// Single event type sequential array for Everything.expand(), opt-in types are left out
 const SINGLE_EVENT_NAMES_IN_ORDER: [EventName; 32] = [
     EventName::ObjectAccessedGet,
     EventName::ObjectAccessedGetRetention,
@@ -115,7 +118,7 @@ const SINGLE_EVENT_NAMES_IN_ORDER: [EventName; 32] = [
     EventName::LifecycleDelMarkerExpirationDelete,
 ];
 
-const LAST_SINGLE_TYPE_VALUE: u32 = EventName::LifecycleDelMarkerExpirationDelete as u32;
+const LAST_SINGLE_TYPE_VALUE: u32 = EventName::ObjectCopyProgress as u32;
 
 impl EventName {
     /// The parsed string is EventName.
@@ -138,6 +141,7 @@ impl EventName {
             "s3:ObjectCreated:PutLegalHold" => Ok(EventName::ObjectCreatedPutLegalHold),
             "s3:ObjectCreated:PutTagging" => Ok(EventName::ObjectCreatedPutTagging),
             "s3:ObjectCreated:DeleteTagging" => Ok(EventName::ObjectCreatedDeleteTagging),
+            "s3:ObjectCopy:Progress" => Ok(EventName::ObjectCopyProgress),
             "s3:ObjectRemoved:*" => Ok(EventName::ObjectRemovedAll),
             "s3:ObjectRemoved:Delete" => Ok(EventName::ObjectRemovedDelete),
             "s3:ObjectRemoved:DeleteMarkerCreated" => Ok(EventName::ObjectRemovedDeleteMarkerCreated),
@@ -184,6 +188,7 @@ impl EventName {
             EventName::ObjectCreatedDeleteTagging => "s3:ObjectCreated:DeleteTagging",
             EventName::ObjectCreatedPutRetention => "s3:ObjectCreated:PutRetention",
             EventName::ObjectCreatedPutLegalHold => "s3:ObjectCreated:PutLegalHold",
+            EventName::ObjectCopyProgress => "s3:ObjectCopy:Progress",
             EventName::ObjectRemovedAll => "s3:ObjectRemoved:*",
             EventName::ObjectRemovedDelete => "s3:ObjectRemoved:Delete",
             EventName::ObjectRemovedDeleteMarkerCreated => "s3:ObjectRemoved:DeleteMarkerCreated",
@@ -332,6 +337,9 @@ pub struct Object {
     pub version_id: Option<String>,
     /// A unique identifier for the event
     pub sequencer: String,
+    /// Object tags at event time, only used to route the event and never published
+    #[serde(skip)]
+    pub user_tags: HashMap<String, String>,
 }
 
 /// Metadata about the event
@@ -437,6 +445,7 @@ impl Event {
                     user_metadata: Some(user_metadata),
                     version_id: Some("1".to_string()),
                     sequencer: "0055AED6DCD90281E5".to_string(),
+                    user_tags: HashMap::new(),
                 },
             },
             source: Source {
@@ -484,6 +493,9 @@ impl Event {
                 key: key_name,
                 version_id,
                 sequencer: unique_id,
+                user_tags: form_urlencoded::parse(args.object.user_tags.as_bytes())
+                    .into_owned()
+                    .collect(),
                 ..Default::default()
             },
         };
@@ -14,15 +14,34 @@
 
 use crate::{
     error::TargetError,
-    target::{Target, mqtt::MQTTArgs, webhook::WebhookArgs},
+    target::{
+        Target,
+        mqtt::{MQTTArgs, MQTTVersion},
+        webhook::WebhookArgs,
+    },
 };
 use async_trait::async_trait;
 use rumqttc::QoS;
+#[cfg(feature = "amqp")]
+use rustfs_config::notify::{
+    AMQP_DELIVERY_MODE, AMQP_DURABLE, AMQP_EXCHANGE, AMQP_EXCHANGE_TYPE, AMQP_MANDATORY, AMQP_PUBLISHER_CONFIRMS, AMQP_QUEUE_DIR,
+    AMQP_QUEUE_LIMIT, AMQP_ROUTING_KEY, AMQP_TLS_CA, AMQP_URL, ENV_NOTIFY_AMQP_KEYS, NOTIFY_AMQP_KEYS,
+};
 use rustfs_config::notify::{
     DEFAULT_DIR, DEFAULT_LIMIT, ENV_NOTIFY_MQTT_KEYS, ENV_NOTIFY_WEBHOOK_KEYS, MQTT_BROKER, MQTT_KEEP_ALIVE_INTERVAL,
-    MQTT_PASSWORD, MQTT_QOS, MQTT_QUEUE_DIR, MQTT_QUEUE_LIMIT, MQTT_RECONNECT_INTERVAL, MQTT_TOPIC, MQTT_USERNAME,
-    NOTIFY_MQTT_KEYS, NOTIFY_WEBHOOK_KEYS, WEBHOOK_AUTH_TOKEN, WEBHOOK_CLIENT_CERT, WEBHOOK_CLIENT_KEY, WEBHOOK_ENDPOINT,
-    WEBHOOK_QUEUE_DIR, WEBHOOK_QUEUE_LIMIT,
+    MQTT_PASSWORD, MQTT_PROTOCOL_VERSION, MQTT_QOS, MQTT_QUEUE_DIR, MQTT_QUEUE_LIMIT, MQTT_RECONNECT_INTERVAL, MQTT_TLS_CA,
+    MQTT_TLS_CLIENT_CERT, MQTT_TLS_CLIENT_KEY, MQTT_TOPIC, MQTT_USERNAME, NOTIFY_MQTT_KEYS, NOTIFY_WEBHOOK_KEYS,
+    WEBHOOK_AUTH_TOKEN, WEBHOOK_CLIENT_CERT, WEBHOOK_CLIENT_KEY, WEBHOOK_ENDPOINT, WEBHOOK_QUEUE_DIR, WEBHOOK_QUEUE_LIMIT,
+};
+#[cfg(all(feature = "kafka", target_os = "linux"))]
+use rustfs_config::notify::{
+    ENV_NOTIFY_KAFKA_KEYS, KAFKA_BROKERS, KAFKA_QUEUE_DIR, KAFKA_QUEUE_LIMIT, KAFKA_SASL_MECHANISM, KAFKA_SASL_PASSWORD,
+    KAFKA_SASL_USERNAME, KAFKA_TLS, KAFKA_TLS_SKIP_VERIFY, KAFKA_TOPIC, NOTIFY_KAFKA_KEYS,
+};
+#[cfg(feature = "nats")]
+use rustfs_config::notify::{
+    ENV_NOTIFY_NATS_KEYS, NATS_ADDRESS, NATS_JETSTREAM, NATS_PASSWORD, NATS_QUEUE_DIR, NATS_QUEUE_LIMIT, NATS_SUBJECT, NATS_TLS,
+    NATS_TOKEN, NATS_USERNAME, NOTIFY_NATS_KEYS,
 };
 use rustfs_ecstore::config::KVS;
 use std::collections::HashSet;
@@ -156,6 +175,10 @@ impl TargetFactory for MQTTTargetFactory {
                 .and_then(|v| v.parse::<u64>().ok())
                 .map(Duration::from_secs)
                 .unwrap_or_else(|| Duration::from_secs(30)),
+            protocol_version: config.lookup(MQTT_PROTOCOL_VERSION).unwrap_or_default().parse()?,
+            tls_ca: config.lookup(MQTT_TLS_CA).unwrap_or_default(),
+            tls_client_cert: config.lookup(MQTT_TLS_CLIENT_CERT).unwrap_or_default(),
+            tls_client_key: config.lookup(MQTT_TLS_CLIENT_KEY).unwrap_or_default(),
             queue_dir: config.lookup(MQTT_QUEUE_DIR).unwrap_or(DEFAULT_DIR.to_string()),
             queue_limit: config
                 .lookup(MQTT_QUEUE_LIMIT)
@@ -194,6 +217,19 @@ impl TargetFactory for MQTTTargetFactory {
             }
         }
 
+        config
+            .lookup(MQTT_PROTOCOL_VERSION)
+            .unwrap_or_default()
+            .parse::<MQTTVersion>()?;
+
+        if config.lookup(MQTT_TLS_CLIENT_CERT).unwrap_or_default().is_empty()
+            != config.lookup(MQTT_TLS_CLIENT_KEY).unwrap_or_default().is_empty()
+        {
+            return Err(TargetError::Configuration(
+                "MQTT TLS client cert and key must be specified as a pair".to_string(),
+            ));
+        }
+
         let queue_dir = config.lookup(MQTT_QUEUE_DIR).unwrap_or_default();
         if !queue_dir.is_empty() {
             if !std::path::Path::new(&queue_dir).is_absolute() {
@@ -217,3 +253,190 @@ impl TargetFactory for MQTTTargetFactory {
         ENV_NOTIFY_MQTT_KEYS.iter().map(|s| s.to_string()).collect()
     }
 }
+
+/// Factory for creating Kafka targets
+#[cfg(all(feature = "kafka", target_os = "linux"))]
+pub struct KafkaTargetFactory;
+
+#[cfg(all(feature = "kafka", target_os = "linux"))]
+impl KafkaTargetFactory {
+    fn args(config: &KVS) -> Result<crate::target::kafka::KafkaArgs, TargetError> {
+        let brokers = config
+            .lookup(KAFKA_BROKERS)
+            .ok_or_else(|| TargetError::Configuration("Missing Kafka brokers".to_string()))?;
+        let flag = |key: &str| {
+            config
+                .lookup(key)
+                .filter(|v| !v.is_empty())
+                .map(|v| crate::target::parse_bool(&v))
+                .transpose()
+                .map(|v| v.unwrap_or(false))
+        };
+
+        Ok(crate::target::kafka::KafkaArgs {
+            enable: true, // Assumed enabled.
+            brokers: brokers
+                .split(',')
+                .map(str::trim)
+                .filter(|b| !b.is_empty())
+                .map(String::from)
+                .collect(),
+            topic: config.lookup(KAFKA_TOPIC).unwrap_or_default(),
+            sasl_mechanism: config.lookup(KAFKA_SASL_MECHANISM).unwrap_or_default(),
+            sasl_username: config.lookup(KAFKA_SASL_USERNAME).unwrap_or_default(),
+            sasl_password: config.lookup(KAFKA_SASL_PASSWORD).unwrap_or_default(),
+            tls: flag(KAFKA_TLS)?,
+            tls_skip_verify: flag(KAFKA_TLS_SKIP_VERIFY)?,
+            queue_dir: config.lookup(KAFKA_QUEUE_DIR).unwrap_or(DEFAULT_DIR.to_string()),
+            queue_limit: config
+                .lookup(KAFKA_QUEUE_LIMIT)
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_LIMIT),
+        })
+    }
+}
+
+#[cfg(all(feature = "kafka", target_os = "linux"))]
+#[async_trait]
+impl TargetFactory for KafkaTargetFactory {
+    async fn create_target(&self, id: String, config: &KVS) -> Result<Box<dyn Target + Send + Sync>, TargetError> {
+        let target = crate::target::kafka::KafkaTarget::new(id, Self::args(config)?)?;
+        Ok(Box::new(target))
+    }
+
+    fn validate_config(&self, _id: &str, config: &KVS) -> Result<(), TargetError> {
+        Self::args(config)?.validate()
+    }
+
+    fn get_valid_fields(&self) -> HashSet<String> {
+        NOTIFY_KAFKA_KEYS.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn get_valid_env_fields(&self) -> HashSet<String> {
+        ENV_NOTIFY_KAFKA_KEYS.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+/// Factory for creating NATS targets
+#[cfg(feature = "nats")]
+pub struct NATSTargetFactory;
+
+#[cfg(feature = "nats")]
+impl NATSTargetFactory {
+    fn args(config: &KVS) -> Result<crate::target::nats::NATSArgs, TargetError> {
+        let address = config
+            .lookup(NATS_ADDRESS)
+            .ok_or_else(|| TargetError::Configuration("Missing NATS address".to_string()))?;
+        let flag = |key: &str| {
+            config
+                .lookup(key)
+                .filter(|v| !v.is_empty())
+                .map(|v| crate::target::parse_bool(&v))
+                .transpose()
+                .map(|v| v.unwrap_or(false))
+        };
+
+        Ok(crate::target::nats::NATSArgs {
+            enable: true, // Assumed enabled.
+            address,
+            subject: config.lookup(NATS_SUBJECT).unwrap_or_default(),
+            username: config.lookup(NATS_USERNAME).unwrap_or_default(),
+            password: config.lookup(NATS_PASSWORD).unwrap_or_default(),
+            token: config.lookup(NATS_TOKEN).unwrap_or_default(),
+            tls: flag(NATS_TLS)?,
+            jetstream: flag(NATS_JETSTREAM)?,
+            queue_dir: config.lookup(NATS_QUEUE_DIR).unwrap_or(DEFAULT_DIR.to_string()),
+            queue_limit: config
+                .lookup(NATS_QUEUE_LIMIT)
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_LIMIT),
+        })
+    }
+}
+
+#[cfg(feature = "nats")]
+#[async_trait]
+impl TargetFactory for NATSTargetFactory {
+    async fn create_target(&self, id: String, config: &KVS) -> Result<Box<dyn Target + Send + Sync>, TargetError> {
+        let target = crate::target::nats::NATSTarget::new(id, Self::args(config)?)?;
+        Ok(Box::new(target))
+    }
+
+    fn validate_config(&self, _id: &str, config: &KVS) -> Result<(), TargetError> {
+        Self::args(config)?.validate()
+    }
+
+    fn get_valid_fields(&self) -> HashSet<String> {
+        NOTIFY_NATS_KEYS.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn get_valid_env_fields(&self) -> HashSet<String> {
+        ENV_NOTIFY_NATS_KEYS.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+/// Factory for creating AMQP targets
+#[cfg(feature = "amqp")]
+pub struct AMQPTargetFactory;
+
+#[cfg(feature = "amqp")]
+impl AMQPTargetFactory {
+    fn args(config: &KVS) -> Result<crate::target::amqp::AMQPArgs, TargetError> {
+        let url = config
+            .lookup(AMQP_URL)
+            .ok_or_else(|| TargetError::Configuration("Missing AMQP url".to_string()))?;
+        let flag = |key: &str| {
+            config
+                .lookup(key)
+                .filter(|v| !v.is_empty())
+                .map(|v| crate::target::parse_bool(&v))
+                .transpose()
+                .map(|v| v.unwrap_or(false))
+        };
+        let delivery_mode = match config.lookup(AMQP_DELIVERY_MODE).filter(|v| !v.is_empty()) {
+            Some(v) => v
+                .parse::<u8>()
+                .map_err(|_| TargetError::Configuration(format!("Invalid AMQP delivery mode: {v}")))?,
+            None => 0,
+        };
+
+        Ok(crate::target::amqp::AMQPArgs {
+            enable: true, // Assumed enabled.
+            url,
+            exchange: config.lookup(AMQP_EXCHANGE).unwrap_or_default(),
+            exchange_type: config.lookup(AMQP_EXCHANGE_TYPE).unwrap_or_default(),
+            routing_key: config.lookup(AMQP_ROUTING_KEY).unwrap_or_default(),
+            mandatory: flag(AMQP_MANDATORY)?,
+            durable: flag(AMQP_DURABLE)?,
+            delivery_mode,
+            publisher_confirms: flag(AMQP_PUBLISHER_CONFIRMS)?,
+            tls_ca: config.lookup(AMQP_TLS_CA).unwrap_or_default(),
+            queue_dir: config.lookup(AMQP_QUEUE_DIR).unwrap_or(DEFAULT_DIR.to_string()),
+            queue_limit: config
+                .lookup(AMQP_QUEUE_LIMIT)
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_LIMIT),
+        })
+    }
+}
+
+#[cfg(feature = "amqp")]
+#[async_trait]
+impl TargetFactory for AMQPTargetFactory {
+    async fn create_target(&self, id: String, config: &KVS) -> Result<Box<dyn Target + Send + Sync>, TargetError> {
+        let target = crate::target::amqp::AMQPTarget::new(id, Self::args(config)?)?;
+        Ok(Box::new(target))
+    }
+
+    fn validate_config(&self, _id: &str, config: &KVS) -> Result<(), TargetError> {
+        Self::args(config)?.validate()
+    }
+
+    fn get_valid_fields(&self) -> HashSet<String> {
+        NOTIFY_AMQP_KEYS.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn get_valid_env_fields(&self) -> HashSet<String> {
+        ENV_NOTIFY_AMQP_KEYS.iter().map(|s| s.to_string()).collect()
+    }
+}
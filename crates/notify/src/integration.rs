@@ -18,6 +18,7 @@ use crate::{
     Event, EventName, StoreError, Target, error::NotificationError, notifier::EventNotifier, registry::TargetRegistry,
     rules::BucketNotificationConfig, stream,
 };
+use rustfs_config::notify::WEBHOOK_QUEUE_DIR;
 use rustfs_ecstore::config::{Config, KVS};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -126,7 +127,7 @@ impl NotificationSystem {
         info!("Initialize notification system...");
 
         let config = self.config.read().await;
-        debug!("Initializing notification system with config: {:?}", *config);
+        debug!("Initializing notification system");
         let targets: Vec<Box<dyn Target + Send + Sync>> = self.registry.create_targets_from_config(&config).await?;
 
         info!("{} notification targets were created", targets.len());
@@ -278,6 +279,48 @@ impl NotificationSystem {
         .await
     }
 
+    /// Rotates the credentials of a target.
+    ///
+    /// A probe target is built with the new credentials first, the configuration only switches
+    /// once the probe reaches the endpoint, so a bad rotation leaves the working target in place.
+    ///
+    /// # Arguments
+    /// * `target_type` - Target type, such as "notify_webhook" or "notify_mqtt".
+    /// * `target_name` - A unique name for a Target, such as "1".
+    /// * `secrets` - Credential keys and their new values, such as `auth_token` of a webhook.
+    pub async fn rotate_target_secrets(
+        &self,
+        target_type: &str,
+        target_name: &str,
+        secrets: &[(String, String)],
+    ) -> Result<(), NotificationError> {
+        info!("Rotating credentials of target {} of type {}", target_name, target_type);
+        let current = self
+            .config
+            .read()
+            .await
+            .get_value(target_type, target_name)
+            .ok_or_else(|| NotificationError::Configuration(format!("target {target_type}/{target_name} not found")))?;
+        let kvs = current
+            .with_secrets(target_type, secrets)
+            .map_err(|e| NotificationError::Configuration(e.to_string()))?;
+
+        // The probe must not share the queue of the running target, the key is the same for all types
+        let mut probe_kvs = kvs.clone();
+        probe_kvs.insert(WEBHOOK_QUEUE_DIR.to_string(), String::new());
+        let probe = self
+            .registry
+            .create_target(target_type, target_name.to_string(), &probe_kvs)
+            .await?;
+        let reachable = probe_target(probe.as_ref()).await;
+        if let Err(e) = probe.close().await {
+            warn!("Failed to close probe of target {}: {}", target_name, e);
+        }
+        reachable?;
+
+        self.set_target_config(target_type, target_name, kvs).await
+    }
+
     /// Removes all notification configurations for a bucket.
     pub async fn remove_bucket_notification_config(&self, bucket_name: &str) {
         self.notifier.remove_rules_map(bucket_name).await;
@@ -492,3 +535,23 @@ pub async fn load_config_from_file(path: &str, system: &NotificationSystem) -> R
         .map_err(|e| NotificationError::Configuration(format!("Failed to parse config: {e}")))?;
     system.reload_config(config).await
 }
+
+/// How long a probe target gets to reach its endpoint
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wait for a freshly created target to reach its endpoint
+async fn probe_target(target: &(dyn Target + Send + Sync)) -> Result<(), NotificationError> {
+    target.init().await?;
+    let deadline = Instant::now() + PROBE_TIMEOUT;
+    loop {
+        let err = match target.is_active().await {
+            Ok(true) => return Ok(()),
+            Ok(false) => NotificationError::Configuration(format!("target {} is not reachable", target.id())),
+            Err(e) => e.into(),
+        };
+        if Instant::now() >= deadline {
+            return Err(err);
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
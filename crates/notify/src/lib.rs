@@ -16,7 +16,8 @@
 //!
 //! This library provides a Rust implementation of a storage bucket notification system.
 //! It supports sending events to various targets
-//! (like Webhook and MQTT) and includes features like event persistence and retry on failure.
+//! (Webhook and MQTT, Kafka, NATS and AMQP behind the `kafka`, `nats` and `amqp` features) and includes
+//! features like event persistence and retry on failure.
 
 pub mod arn;
 pub mod error;
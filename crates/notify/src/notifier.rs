@@ -127,7 +127,7 @@ impl EventNotifier {
         let object_key = &event.s3.object.key;
         let event_name = event.event_name;
         if let Some(rules) = self.bucket_rules_map.get(bucket_name) {
-            let target_ids = rules.match_rules(event_name, object_key);
+            let target_ids = rules.match_rules_with_tags(event_name, object_key, &event.s3.object.user_tags);
             if target_ids.is_empty() {
                 debug!("No matching targets for event in bucket: {}", bucket_name);
                 return;
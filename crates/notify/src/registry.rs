@@ -46,6 +46,12 @@ impl TargetRegistry {
         // Register built-in factories
         registry.register(ChannelTargetType::Webhook.as_str(), Box::new(WebhookTargetFactory));
         registry.register(ChannelTargetType::Mqtt.as_str(), Box::new(MQTTTargetFactory));
+        #[cfg(all(feature = "kafka", target_os = "linux"))]
+        registry.register(ChannelTargetType::Kafka.as_str(), Box::new(crate::factory::KafkaTargetFactory));
+        #[cfg(feature = "nats")]
+        registry.register(ChannelTargetType::Nats.as_str(), Box::new(crate::factory::NATSTargetFactory));
+        #[cfg(feature = "amqp")]
+        registry.register(ChannelTargetType::Amqp.as_str(), Box::new(crate::factory::AMQPTargetFactory));
 
         registry
     }
@@ -20,7 +20,7 @@ use crate::rules::NotificationConfiguration;
 use crate::rules::pattern_rules;
 use crate::rules::target_id_set;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Read;
 
 /// Configuration for bucket notifications.
@@ -50,6 +50,17 @@ impl BucketNotificationConfig {
         self.rules.add_rule_config(event_names, pattern, target_id);
     }
 
+    /// Adds a rule that only matches objects carrying all of the given tags.
+    pub fn add_tag_rule(
+        &mut self,
+        event_names: &[EventName],
+        pattern: String,
+        tags: BTreeMap<String, String>,
+        target_id: TargetID,
+    ) {
+        self.rules.add_tag_rule_config(event_names, pattern, tags, target_id);
+    }
+
     /// Parses notification configuration from XML.
     /// `arn_list` is a list of valid ARN strings for validation.
     pub fn from_xml<R: Read + std::io::BufRead>(
@@ -71,7 +82,8 @@ impl BucketNotificationConfig {
             // Ensure TargetID can be cloned or extracted correctly.
             let target_id = queue_conf.arn.target_id.clone();
             let pattern_str = queue_conf.filter.filter_rule_list.pattern();
-            rules_map.add_rule_config(&queue_conf.events, pattern_str, target_id);
+            let tags = queue_conf.filter.tag_conditions();
+            rules_map.add_tag_rule_config(&queue_conf.events, pattern_str, tags, target_id);
         }
 
         Ok(BucketNotificationConfig {
@@ -95,15 +107,18 @@ impl BucketNotificationConfig {
 
         // Iterate through the rules in self.rules and validate their TargetIDs against arn_list
         // This requires RulesMap to expose its internal structure or provide an iterator
-        for (_event_name, pattern_rules) in self.rules.inner().iter() {
-            for (_pattern, target_id_set) in pattern_rules.inner().iter() {
-                // Assuming PatternRules has inner()
-                for target_id in target_id_set {
-                    // Construct the ARN string for this target_id and self.region
-                    let arn_to_check = target_id.to_arn(&self.region); // Assuming TargetID has to_arn
-                    if !arn_list.contains(&arn_to_check.to_arn_string()) {
-                        return Err(BucketNotificationConfigError::ArnNotFound(arn_to_check.to_arn_string()));
-                    }
+        let pattern_targets = self.rules.inner().values().flat_map(|pr| pr.inner().values());
+        let tag_targets = self
+            .rules
+            .tag_inner()
+            .values()
+            .flat_map(|tr| tr.inner().iter().map(|r| &r.targets));
+        for target_id_set in pattern_targets.chain(tag_targets) {
+            for target_id in target_id_set {
+                // Construct the ARN string for this target_id and self.region
+                let arn_to_check = target_id.to_arn(&self.region); // Assuming TargetID has to_arn
+                if !arn_list.contains(&arn_to_check.to_arn_string()) {
+                    return Err(BucketNotificationConfigError::ArnNotFound(arn_to_check.to_arn_string()));
                 }
             }
         }
@@ -127,3 +142,26 @@ impl pattern_rules::PatternRules {
         &self.rules
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_xml_s3_key_filter() {
+        let xml = r#"<NotificationConfiguration><QueueConfiguration><Queue>arn:rustfs:sqs:us-east-1:1:kafka</Queue><Event>s3:ObjectCreated:*</Event><Filter><S3Key><FilterRule><Name>prefix</Name><Value>images/</Value></FilterRule></S3Key></Filter></QueueConfiguration></NotificationConfiguration>"#;
+        let arn_list = vec!["arn:rustfs:sqs:us-east-1:1:kafka".to_string()];
+        let config = BucketNotificationConfig::from_xml(xml.as_bytes(), "us-east-1", &arn_list).unwrap();
+
+        assert_eq!(config.rules.match_rules(EventName::ObjectCreatedPut, "images/a.png").len(), 1);
+        assert!(config.rules.match_rules(EventName::ObjectCreatedPut, "docs/a.txt").is_empty());
+        assert!(
+            config
+                .rules
+                .match_rules(EventName::ObjectRemovedDelete, "images/a.png")
+                .is_empty()
+        );
+
+        assert!(BucketNotificationConfig::from_xml(xml.as_bytes(), "us-east-1", &[]).is_err());
+    }
+}
@@ -15,6 +15,7 @@
 pub mod pattern;
 pub mod pattern_rules;
 pub mod rules_map;
+pub mod tag_rules;
 pub mod target_id_set;
 pub mod xml_config; // For XML structure definition and parsing
 
@@ -29,5 +30,6 @@ pub use xml_config::ParseConfigError as BucketNotificationConfigError;
 
 pub use pattern_rules::PatternRules;
 pub use rules_map::RulesMap;
+pub use tag_rules::{TagRule, TagRules};
 pub use target_id_set::TargetIdSet;
 pub use xml_config::{NotificationConfiguration, ParseConfigError};
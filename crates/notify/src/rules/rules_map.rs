@@ -13,17 +13,21 @@
 // limitations under the License.
 
 use super::pattern_rules::PatternRules;
+use super::tag_rules::TagRules;
 use super::target_id_set::TargetIdSet;
 use crate::arn::TargetID;
 use crate::event::EventName;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// RulesMap - Rule mapping organized by event name。
 /// `event.RulesMap` (map[Name]Rules) in the corresponding Go code
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RulesMap {
     map: HashMap<EventName, PatternRules>,
+    /// Rules that additionally filter on object tags, evaluated at event time.
+    #[serde(default)]
+    tag_map: HashMap<EventName, TagRules>,
     /// A bitmask that represents the union of all event types in this map.
     /// Used for quick checks in `has_subscriber`.
     total_events_mask: u64,
@@ -65,6 +69,35 @@ impl RulesMap {
         }
     }
 
+    /// Add a rule configuration that only matches objects carrying all of `tags`.
+    ///
+    /// Falls back to [`RulesMap::add_rule_config`] when `tags` is empty.
+    pub fn add_tag_rule_config(
+        &mut self,
+        event_names: &[EventName],
+        pattern: String,
+        tags: BTreeMap<String, String>,
+        target_id: TargetID,
+    ) {
+        if tags.is_empty() {
+            self.add_rule_config(event_names, pattern, target_id);
+            return;
+        }
+
+        let effective_pattern = if pattern.is_empty() { "*".to_string() } else { pattern };
+
+        for event_name_spec in event_names {
+            for expanded_event_name in event_name_spec.expand() {
+                self.tag_map.entry(expanded_event_name).or_default().add(
+                    effective_pattern.clone(),
+                    tags.clone(),
+                    target_id.clone(),
+                );
+                self.total_events_mask |= expanded_event_name.mask();
+            }
+        }
+    }
+
     /// Merge another RulesMap.
     /// `RulesMap.Add(rulesMap2 RulesMap) corresponding to Go
     pub fn add_map(&mut self, other_map: &Self) {
@@ -74,6 +107,10 @@ impl RulesMap {
             let merged_rules = self_pattern_rules.union(other_pattern_rules);
             *self_pattern_rules = merged_rules;
         }
+        for (event_name, other_tag_rules) in &other_map.tag_map {
+            let self_tag_rules = self.tag_map.entry(*event_name).or_default();
+            *self_tag_rules = self_tag_rules.union(other_tag_rules);
+        }
         // Directly merge two masks.
         self.total_events_mask |= other_map.total_events_mask;
     }
@@ -94,6 +131,12 @@ impl RulesMap {
         for event_name in events_to_remove {
             self.map.remove(&event_name);
         }
+        self.tag_map.retain(|event_name, self_tag_rules| {
+            if let Some(other_tag_rules) = other_map.tag_map.get(event_name) {
+                *self_tag_rules = self_tag_rules.difference(other_tag_rules);
+            }
+            !self_tag_rules.is_empty()
+        });
         // After removing the rule, recalculate total_events_mask.
         self.recalculate_mask();
     }
@@ -109,6 +152,8 @@ impl RulesMap {
 
     /// Rules matching the given event and object keys and return all matching target IDs.
     ///
+    /// Tag-conditioned rules never match here, use [`RulesMap::match_rules_with_tags`] for those.
+    ///
     /// # Notice
     /// The `event_name` parameter should be a specific, non-compound event type.
     /// Because this is taken from the `Event` object that actually occurs.
@@ -139,9 +184,24 @@ impl RulesMap {
             .map_or_else(TargetIdSet::new, |pr| pr.match_targets(object_key))
     }
 
+    /// Like [`RulesMap::match_rules`], but also evaluates tag-conditioned rules against the
+    /// tags the object carries when the event occurs.
+    pub fn match_rules_with_tags(
+        &self,
+        event_name: EventName,
+        object_key: &str,
+        object_tags: &HashMap<String, String>,
+    ) -> TargetIdSet {
+        let mut targets = self.match_rules(event_name, object_key);
+        if let Some(tag_rules) = self.tag_map.get(&event_name) {
+            targets.extend(tag_rules.match_targets(object_key, object_tags));
+        }
+        targets
+    }
+
     /// Check if RulesMap is empty.
     pub fn is_empty(&self) -> bool {
-        self.map.is_empty()
+        self.map.is_empty() && self.tag_map.is_empty()
     }
 
     /// Returns a clone of internal rules for use in scenarios such as BucketNotificationConfig::validate.
@@ -149,11 +209,16 @@ impl RulesMap {
         &self.map
     }
 
+    /// Returns the tag-conditioned rules, keyed by event name.
+    pub fn tag_inner(&self) -> &HashMap<EventName, TagRules> {
+        &self.tag_map
+    }
+
     /// A private helper function that recalculates `total_events_mask` based on the content of the current `map`.
     /// Called after the removal operation to ensure the accuracy of the mask.
     fn recalculate_mask(&mut self) {
         let mut new_mask = 0u64;
-        for event_name in self.map.keys() {
+        for event_name in self.map.keys().chain(self.tag_map.keys()) {
             new_mask |= event_name.mask();
         }
         self.total_events_mask = new_mask;
@@ -176,6 +241,7 @@ impl RulesMap {
     pub fn remove_rules(&mut self, event_names: &[EventName]) {
         for event_name in event_names {
             self.map.remove(event_name);
+            self.tag_map.remove(event_name);
         }
         self.recalculate_mask(); // Unified calculation of mask after batch processing
     }
@@ -0,0 +1,141 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::pattern;
+use super::target_id_set::TargetIdSet;
+use crate::arn::TargetID;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// TagRule - A pattern rule that additionally requires the object to carry all of the given tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagRule {
+    pub pattern: String,
+    pub tags: BTreeMap<String, String>,
+    pub targets: TargetIdSet,
+}
+
+impl TagRule {
+    /// Checks whether the object name and tags satisfy this rule.
+    pub fn matches(&self, object_name: &str, object_tags: &HashMap<String, String>) -> bool {
+        pattern::match_simple(&self.pattern, object_name)
+            && self.tags.iter().all(|(k, v)| object_tags.get(k).is_some_and(|tv| tv == v))
+    }
+}
+
+/// TagRules - Tag-conditioned rules of a single event type.
+/// Rules with the same pattern and tag set share one TargetIdSet.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagRules {
+    pub(crate) rules: Vec<TagRule>,
+}
+
+impl TagRules {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add rules: Pattern, tag conditions and Target ID.
+    pub fn add(&mut self, pattern: String, tags: BTreeMap<String, String>, target_id: TargetID) {
+        match self.rules.iter_mut().find(|r| r.pattern == pattern && r.tags == tags) {
+            Some(rule) => {
+                rule.targets.insert(target_id);
+            }
+            None => {
+                let mut targets = TargetIdSet::new();
+                targets.insert(target_id);
+                self.rules.push(TagRule { pattern, tags, targets });
+            }
+        }
+    }
+
+    /// Returns all TargetIDs whose rules match the object name and tags.
+    pub fn match_targets(&self, object_name: &str, object_tags: &HashMap<String, String>) -> TargetIdSet {
+        let mut matched_targets = TargetIdSet::new();
+        for rule in self.rules.iter().filter(|r| r.matches(object_name, object_tags)) {
+            matched_targets.extend(rule.targets.iter().cloned());
+        }
+        matched_targets
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn inner(&self) -> &[TagRule] {
+        &self.rules
+    }
+
+    /// Merge another TagRules.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut new_rules = self.clone();
+        for rule in &other.rules {
+            for target_id in &rule.targets {
+                new_rules.add(rule.pattern.clone(), rule.tags.clone(), target_id.clone());
+            }
+        }
+        new_rules
+    }
+
+    /// Calculate the difference from another TagRules.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result_rules = Vec::new();
+        for rule in &self.rules {
+            let targets: TargetIdSet = match other.rules.iter().find(|r| r.pattern == rule.pattern && r.tags == rule.tags) {
+                Some(other_rule) => rule.targets.difference(&other_rule.targets).cloned().collect(),
+                None => rule.targets.clone(),
+            };
+            if !targets.is_empty() {
+                result_rules.push(TagRule {
+                    pattern: rule.pattern.clone(),
+                    tags: rule.tags.clone(),
+                    targets,
+                });
+            }
+        }
+        TagRules { rules: result_rules }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(id: &str) -> TargetID {
+        TargetID::new(id.to_string(), "webhook".to_string())
+    }
+
+    #[test]
+    fn test_tag_rules_match_targets() {
+        let mut rules = TagRules::new();
+        let phi = BTreeMap::from([("classification".to_string(), "phi".to_string())]);
+        rules.add("*".to_string(), phi.clone(), target("compliance"));
+        rules.add("reports/*".to_string(), phi, target("reports"));
+
+        let tags = HashMap::from([
+            ("classification".to_string(), "phi".to_string()),
+            ("owner".to_string(), "billing".to_string()),
+        ]);
+        let matched = rules.match_targets("reports/2024.csv", &tags);
+        assert_eq!(matched.len(), 2);
+
+        let matched = rules.match_targets("images/a.png", &tags);
+        assert_eq!(matched.len(), 1);
+        assert!(matched.contains(&target("compliance")));
+
+        let public = HashMap::from([("classification".to_string(), "public".to_string())]);
+        assert!(rules.match_targets("reports/2024.csv", &public).is_empty());
+        assert!(rules.match_targets("reports/2024.csv", &HashMap::new()).is_empty());
+    }
+}
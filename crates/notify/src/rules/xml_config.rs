@@ -15,8 +15,8 @@
 use super::pattern;
 use crate::arn::{ARN, ArnError, TargetIDError};
 use crate::event::EventName;
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashSet};
 use std::io::Read;
 use thiserror::Error;
 
@@ -32,6 +32,10 @@ pub enum ParseConfigError {
     DuplicatePrefixFilter,
     #[error("There can only be one 'suffix' in the filter rule")]
     DuplicateSuffixFilter,
+    #[error("Invalid filter tag:{0}")]
+    InvalidFilterTag(String),
+    #[error("Duplicate filter tag key:{0}")]
+    DuplicateFilterTag(String),
     #[error("Missing event name")]
     MissingEventName,
     #[error("Duplicate event name:{0}")]
@@ -129,10 +133,55 @@ impl FilterRuleList {
     }
 }
 
+/// Object tag condition of a notification filter, the object must carry this exact tag
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FilterTag {
+    #[serde(rename = "Key")]
+    pub key: String,
+    #[serde(rename = "Value")]
+    pub value: String,
+}
+
+impl FilterTag {
+    fn validate(&self) -> Result<(), ParseConfigError> {
+        // Same limits as S3 object tags
+        if self.key.is_empty() || self.key.chars().count() > 128 || self.value.chars().count() > 256 {
+            return Err(ParseConfigError::InvalidFilterTag(format!("{}={}", self.key, self.value)));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
 pub struct S3KeyFilter {
-    #[serde(rename = "FilterRuleList", default, skip_serializing_if = "FilterRuleList::is_empty")]
+    #[serde(
+        rename = "S3Key",
+        alias = "FilterRuleList",
+        default,
+        skip_serializing_if = "FilterRuleList::is_empty"
+    )]
     pub filter_rule_list: FilterRuleList,
+    #[serde(rename = "Tag", default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<FilterTag>,
+}
+
+impl S3KeyFilter {
+    pub fn validate(&self) -> Result<(), ParseConfigError> {
+        self.filter_rule_list.validate()?;
+        let mut keys = HashSet::new();
+        for tag in &self.tags {
+            tag.validate()?;
+            if !keys.insert(tag.key.as_str()) {
+                return Err(ParseConfigError::DuplicateFilterTag(tag.key.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Tag conditions of the filter, all of them must match
+    pub fn tag_conditions(&self) -> BTreeMap<String, String> {
+        self.tags.iter().map(|t| (t.key.clone(), t.value.clone())).collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -141,14 +190,30 @@ pub struct QueueConfig {
     pub id: Option<String>,
     #[serde(rename = "Queue")] // This is ARN in XML
     pub arn: ARN,
-    #[serde(rename = "Event", default)] // XML has multiple <Event> tags
-    pub events: Vec<EventName>, // EventName needs to handle XML (de)serialization if not string
+    #[serde(
+        rename = "Event",
+        default,
+        serialize_with = "serialize_event_names",
+        deserialize_with = "deserialize_event_names"
+    )] // XML has multiple <Event> tags
+    pub events: Vec<EventName>, // Written as `s3:ObjectCreated:*` and the like in XML
     #[serde(rename = "Filter", default, skip_serializing_if = "s3key_filter_is_empty")]
     pub filter: S3KeyFilter,
 }
 
+fn serialize_event_names<S: Serializer>(events: &[EventName], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(events.iter().map(|e| e.as_str()))
+}
+
+fn deserialize_event_names<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<EventName>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|name| EventName::parse(name.trim()).map_err(serde::de::Error::custom))
+        .collect()
+}
+
 fn s3key_filter_is_empty(f: &S3KeyFilter) -> bool {
-    f.filter_rule_list.is_empty()
+    f.filter_rule_list.is_empty() && f.tags.is_empty()
 }
 
 impl QueueConfig {
@@ -163,7 +228,7 @@ impl QueueConfig {
                 return Err(ParseConfigError::DuplicateEventName(event.to_string()));
             }
         }
-        self.filter.filter_rule_list.validate()?;
+        self.filter.validate()?;
 
         // Validate ARN (similar to Go's Queue.Validate)
         // The Go code checks targetList.Exists(q.ARN.TargetID)
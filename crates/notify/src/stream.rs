@@ -19,12 +19,30 @@ use crate::{
     store::{Key, Store},
     target::Target,
 };
+use rustfs_retry::{Backoff, RetryError, RetryPolicy, StopReason};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{Semaphore, mpsc};
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
+/// Retry budget shared by the deliveries to every target
+const RETRY_SUBSYSTEM: &str = "notify";
+/// Longest wait between two deliveries of an event
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Whether a failed delivery may succeed when retried
+fn is_retryable(e: &TargetError) -> bool {
+    matches!(e, TargetError::NotConnected | TargetError::Timeout(_))
+}
+
+/// Retries of the deliveries to `target`, which stop while the target keeps failing
+fn retry_policy(target: &dyn Target, max_attempts: u32, base_delay: Duration) -> RetryPolicy {
+    RetryPolicy::new(max_attempts, Backoff::new(base_delay, MAX_RETRY_DELAY).with_jitter(0.5))
+        .with_budget(rustfs_retry::budget(RETRY_SUBSYSTEM))
+        .with_breaker(rustfs_retry::breaker(&format!("{RETRY_SUBSYSTEM}:{}", target.name())))
+}
+
 /// Streams events from the store to the target
 pub async fn stream_events(
     store: &mut (dyn Store<Event, Error = StoreError, Key = Key> + Send),
@@ -34,8 +52,9 @@ pub async fn stream_events(
     info!("Starting event stream for target: {}", target.name());
 
     // Retry configuration
-    const MAX_RETRIES: usize = 5;
+    const MAX_ATTEMPTS: u32 = 5;
     const RETRY_DELAY: Duration = Duration::from_secs(5);
+    let policy = retry_policy(target, MAX_ATTEMPTS, RETRY_DELAY);
 
     loop {
         // Check for cancellation signal
@@ -60,42 +79,17 @@ pub async fn stream_events(
                 return;
             }
 
-            let mut retry_count = 0;
-            let mut success = false;
-
-            // Retry logic
-            while retry_count < MAX_RETRIES && !success {
-                match target.send_from_store(key.clone()).await {
-                    Ok(_) => {
-                        info!("Successfully sent event for target: {}", target.name());
-                        success = true;
-                    }
-                    Err(e) => {
-                        // Handle specific errors
-                        match &e {
-                            TargetError::NotConnected => {
-                                warn!("Target {} not connected, retrying...", target.name());
-                                retry_count += 1;
-                                sleep(RETRY_DELAY).await;
-                            }
-                            TargetError::Timeout(_) => {
-                                warn!("Timeout for target {}, retrying...", target.name());
-                                retry_count += 1;
-                                sleep(Duration::from_secs((retry_count * 5) as u64)).await; // Exponential backoff
-                            }
-                            _ => {
-                                // Permanent error, skip this event
-                                error!("Permanent error for target {}: {}", target.name(), e);
-                                break;
-                            }
-                        }
-                    }
+            match policy.retry(|| target.send_from_store(key.clone()), is_retryable).await {
+                Ok(_) => info!("Successfully sent event for target: {}", target.name()),
+                Err(RetryError::Permanent(e)) => {
+                    // Permanent error, skip this event
+                    error!("Permanent error for target {}: {}", target.name(), e);
                 }
-            }
-
-            // Remove event from store if successfully sent
-            if retry_count >= MAX_RETRIES && !success {
-                warn!("Max retries exceeded for event {}, target: {}, skipping", key.to_string(), target.name());
+                Err(RetryError::Stopped {
+                    reason: StopReason::CircuitOpen,
+                    ..
+                }) => debug!("Target {} keeps failing, holding back event {}", target.name(), key.to_string()),
+                Err(e) => warn!("Giving up on event {} for target {}: {}", key.to_string(), target.name(), e),
             }
         }
 
@@ -153,8 +147,9 @@ pub async fn stream_events_with_batching(
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(DEFAULT_BATCH_SIZE);
     const BATCH_TIMEOUT: Duration = Duration::from_secs(5);
-    const MAX_RETRIES: usize = 5;
+    const MAX_ATTEMPTS: u32 = 5;
     const BASE_RETRY_DELAY: Duration = Duration::from_secs(2);
+    let policy = retry_policy(target, MAX_ATTEMPTS, BASE_RETRY_DELAY);
 
     let mut batch = Vec::with_capacity(batch_size);
     let mut batch_keys = Vec::with_capacity(batch_size);
@@ -173,7 +168,7 @@ pub async fn stream_events_with_batching(
         if keys.is_empty() {
             // If there is data in the batch and timeout, refresh the batch
             if !batch.is_empty() && last_flush.elapsed() >= BATCH_TIMEOUT {
-                process_batch(&mut batch, &mut batch_keys, target, MAX_RETRIES, BASE_RETRY_DELAY, &metrics, &semaphore).await;
+                process_batch(&mut batch, &mut batch_keys, target, &policy, &metrics, &semaphore).await;
                 last_flush = Instant::now();
             }
 
@@ -190,7 +185,7 @@ pub async fn stream_events_with_batching(
 
                 // Processing collected batches before exiting
                 if !batch.is_empty() {
-                    process_batch(&mut batch, &mut batch_keys, target, MAX_RETRIES, BASE_RETRY_DELAY, &metrics, &semaphore).await;
+                    process_batch(&mut batch, &mut batch_keys, target, &policy, &metrics, &semaphore).await;
                 }
                 return;
             }
@@ -205,8 +200,7 @@ pub async fn stream_events_with_batching(
 
                     // If the batch is full or enough time has passed since the last refresh, the batch will be processed
                     if batch.len() >= batch_size || last_flush.elapsed() >= BATCH_TIMEOUT {
-                        process_batch(&mut batch, &mut batch_keys, target, MAX_RETRIES, BASE_RETRY_DELAY, &metrics, &semaphore)
-                            .await;
+                        process_batch(&mut batch, &mut batch_keys, target, &policy, &metrics, &semaphore).await;
                         last_flush = Instant::now();
                     }
                 }
@@ -237,8 +231,7 @@ async fn process_batch(
     batch: &mut Vec<Event>,
     batch_keys: &mut Vec<Key>,
     target: &dyn Target,
-    max_retries: usize,
-    base_delay: Duration,
+    policy: &RetryPolicy,
     metrics: &Arc<NotificationMetrics>,
     semaphore: &Arc<Semaphore>,
 ) {
@@ -258,45 +251,27 @@ async fn process_batch(
 
     // Handle every event in the batch
     for (_event, key) in batch.iter().zip(batch_keys.iter()) {
-        let mut retry_count = 0;
-        let mut success = false;
-
-        // Retry logic
-        while retry_count < max_retries && !success {
-            match target.send_from_store(key.clone()).await {
-                Ok(_) => {
-                    info!("Successfully sent event for target: {}, Key: {}", target.name(), key.to_string());
-                    success = true;
-                    metrics.increment_processed();
-                }
-                Err(e) => {
-                    // Different retry strategies are adopted according to the error type
-                    match &e {
-                        TargetError::NotConnected => {
-                            warn!("Target {} not connected, retrying...", target.name());
-                            retry_count += 1;
-                            tokio::time::sleep(base_delay * (1 << retry_count)).await; // Exponential backoff
-                        }
-                        TargetError::Timeout(_) => {
-                            warn!("Timeout for target {}, retrying...", target.name());
-                            retry_count += 1;
-                            tokio::time::sleep(base_delay * (1 << retry_count)).await;
-                        }
-                        _ => {
-                            // Permanent error, skip this event
-                            error!("Permanent error for target {}: {}", target.name(), e);
-                            metrics.increment_failed();
-                            break;
-                        }
-                    }
-                }
+        match policy.retry(|| target.send_from_store(key.clone()), is_retryable).await {
+            Ok(_) => {
+                info!("Successfully sent event for target: {}, Key: {}", target.name(), key.to_string());
+                metrics.increment_processed();
+            }
+            Err(RetryError::Permanent(e)) => {
+                // Permanent error, skip this event
+                error!("Permanent error for target {}: {}", target.name(), e);
+                metrics.increment_failed();
+            }
+            Err(RetryError::Stopped {
+                reason: StopReason::CircuitOpen,
+                ..
+            }) => {
+                // Left in the store for a later round
+                debug!("Target {} keeps failing, holding back event {}", target.name(), key.to_string());
+            }
+            Err(e) => {
+                warn!("Giving up on event {} for target {}: {}", key.to_string(), target.name(), e);
+                metrics.increment_failed();
             }
-        }
-
-        // Handle the situation where the maximum number of retry exhaustion is exhausted
-        if retry_count >= max_retries && !success {
-            warn!("Max retries exceeded for event {}, target: {}, skipping", key.to_string(), target.name());
-            metrics.increment_failed();
         }
     }
 
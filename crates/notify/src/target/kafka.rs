@@ -0,0 +1,369 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::target::{ChannelTargetType, event_log_data};
+use crate::{
+    StoreError, Target,
+    arn::TargetID,
+    error::TargetError,
+    event::Event,
+    store::{Key, Store},
+};
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rustfs_config::notify::STORE_EXTENSION;
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tracing::{debug, error, info, instrument};
+
+/// How long the producer keeps trying to deliver an event before reporting a failure
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(10);
+const METADATA_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// SASL mechanisms accepted by the target
+const SASL_MECHANISMS: &[&str] = &["PLAIN", "SCRAM-SHA-256", "SCRAM-SHA-512"];
+
+/// Arguments for configuring a Kafka target
+#[derive(Debug, Clone)]
+pub struct KafkaArgs {
+    /// Whether the target is enabled
+    pub enable: bool,
+    /// The brokers to bootstrap from, such as `localhost:9092`
+    pub brokers: Vec<String>,
+    /// The topic to produce to
+    pub topic: String,
+    /// The SASL mechanism, `PLAIN` when empty and a username is set
+    pub sasl_mechanism: String,
+    /// The SASL username, SASL is disabled when empty
+    pub sasl_username: String,
+    /// The SASL password
+    pub sasl_password: String,
+    /// Whether to connect to the brokers with TLS
+    pub tls: bool,
+    /// Whether to skip the verification of the broker certificates
+    pub tls_skip_verify: bool,
+    /// The directory to store events in case of failure
+    pub queue_dir: String,
+    /// The maximum number of events to store
+    pub queue_limit: u64,
+}
+
+impl KafkaArgs {
+    /// KafkaArgs verification method
+    pub fn validate(&self) -> Result<(), TargetError> {
+        if !self.enable {
+            return Ok(());
+        }
+
+        if self.brokers.is_empty() {
+            return Err(TargetError::Configuration("no broker address found".to_string()));
+        }
+        for broker in &self.brokers {
+            match broker.rsplit_once(':') {
+                Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+                _ => return Err(TargetError::Configuration(format!("invalid broker address: {broker}"))),
+            }
+        }
+
+        if self.topic.is_empty() {
+            return Err(TargetError::Configuration("topic empty".to_string()));
+        }
+
+        if !self.sasl_mechanism.is_empty() && !SASL_MECHANISMS.contains(&self.sasl_mechanism.to_uppercase().as_str()) {
+            return Err(TargetError::Configuration(format!("unsupported sasl mechanism: {}", self.sasl_mechanism)));
+        }
+
+        if !self.queue_dir.is_empty() {
+            let path = std::path::Path::new(&self.queue_dir);
+            if !path.is_absolute() {
+                return Err(TargetError::Configuration("kafka queueDir path should be absolute".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn client_config(&self) -> ClientConfig {
+        let sasl = !self.sasl_username.is_empty();
+        let protocol = match (self.tls, sasl) {
+            (true, true) => "sasl_ssl",
+            (true, false) => "ssl",
+            (false, true) => "sasl_plaintext",
+            (false, false) => "plaintext",
+        };
+
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", self.brokers.join(","))
+            .set("message.timeout.ms", MESSAGE_TIMEOUT.as_millis().to_string())
+            .set("security.protocol", protocol);
+        if sasl {
+            let mechanism = if self.sasl_mechanism.is_empty() {
+                "PLAIN".to_string()
+            } else {
+                self.sasl_mechanism.to_uppercase()
+            };
+            config
+                .set("sasl.mechanisms", mechanism)
+                .set("sasl.username", &self.sasl_username)
+                .set("sasl.password", &self.sasl_password);
+        }
+        if self.tls && self.tls_skip_verify {
+            config.set("enable.ssl.certificate.verification", "false");
+        }
+        config
+    }
+}
+
+/// A target that produces events to a Kafka topic
+pub struct KafkaTarget {
+    id: TargetID,
+    args: KafkaArgs,
+    producer: FutureProducer,
+    store: Option<Box<dyn Store<Event, Error = StoreError, Key = Key> + Send + Sync>>,
+}
+
+impl KafkaTarget {
+    /// Creates a new KafkaTarget
+    #[instrument(skip(args), fields(target_id = %id))]
+    pub fn new(id: String, args: KafkaArgs) -> Result<Self, TargetError> {
+        args.validate()?;
+        let target_id = TargetID::new(id, ChannelTargetType::Kafka.as_str().to_string());
+
+        // The producer connects in the background and reconnects by itself
+        let producer: FutureProducer = args
+            .client_config()
+            .create()
+            .map_err(|e| TargetError::Configuration(format!("Failed to create Kafka producer: {e}")))?;
+
+        let queue_store = if !args.queue_dir.is_empty() {
+            let queue_dir =
+                PathBuf::from(&args.queue_dir).join(format!("rustfs-{}-{}", ChannelTargetType::Kafka.as_str(), target_id.id));
+            let store = crate::store::QueueStore::<Event>::new(queue_dir, args.queue_limit, STORE_EXTENSION);
+
+            if let Err(e) = store.open() {
+                error!("Failed to open store for Kafka target {}: {}", target_id.id, e);
+                return Err(TargetError::Storage(format!("{e}")));
+            }
+
+            Some(Box::new(store) as Box<dyn Store<Event, Error = StoreError, Key = Key> + Send + Sync>)
+        } else {
+            None
+        };
+
+        info!(target_id = %target_id.id, "Kafka target created");
+        Ok(KafkaTarget {
+            id: target_id,
+            args,
+            producer,
+            store: queue_store,
+        })
+    }
+
+    /// Clones the KafkaTarget, sharing its producer
+    pub fn clone_box(&self) -> Box<dyn Target + Send + Sync> {
+        Box::new(KafkaTarget {
+            id: self.id.clone(),
+            args: self.args.clone(),
+            producer: self.producer.clone(),
+            store: self.store.as_ref().map(|s| s.boxed_clone()),
+        })
+    }
+
+    async fn send(&self, event: &Event) -> Result<(), TargetError> {
+        let data = event_log_data(event)?;
+        // Keyed by object so the events of an object stay ordered within a partition
+        let key = format!("{}/{}", event.s3.bucket.name, event.s3.object.key);
+        debug!("Sending event to Kafka target: {}, topic: {}", self.id, self.args.topic);
+
+        self.producer
+            .send(FutureRecord::to(&self.args.topic).key(&key).payload(&data), Duration::from_secs(0))
+            .await
+            .map_err(|(e, _)| delivery_error(e))?;
+
+        debug!("Event produced to Kafka target: {}", self.id);
+        Ok(())
+    }
+}
+
+/// Connection failures keep the event queued for the next attempt
+fn delivery_error(err: KafkaError) -> TargetError {
+    match err.rdkafka_error_code() {
+        Some(
+            RDKafkaErrorCode::AllBrokersDown
+            | RDKafkaErrorCode::BrokerTransportFailure
+            | RDKafkaErrorCode::MessageTimedOut
+            | RDKafkaErrorCode::QueueFull,
+        ) => TargetError::NotConnected,
+        Some(RDKafkaErrorCode::TopicAuthorizationFailed | RDKafkaErrorCode::SaslAuthenticationFailed) => {
+            TargetError::Authentication(format!("Kafka rejected the event: {err}"))
+        }
+        _ => TargetError::Request(format!("Failed to produce message: {err}")),
+    }
+}
+
+#[async_trait]
+impl Target for KafkaTarget {
+    fn id(&self) -> TargetID {
+        self.id.clone()
+    }
+
+    async fn is_active(&self) -> Result<bool, TargetError> {
+        // Fetching metadata blocks until a broker answers
+        let producer = self.producer.clone();
+        let topic = self.args.topic.clone();
+        let metadata = tokio::task::spawn_blocking(move || producer.client().fetch_metadata(Some(&topic), METADATA_TIMEOUT))
+            .await
+            .map_err(|e| TargetError::Unknown(format!("Kafka metadata task failed: {e}")))?;
+        match metadata {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                debug!("Kafka target {} is not reachable: {}", self.id, e);
+                Err(TargetError::NotConnected)
+            }
+        }
+    }
+
+    async fn save(&self, event: Arc<Event>) -> Result<(), TargetError> {
+        if let Some(store) = &self.store {
+            store
+                .put(event)
+                .map_err(|e| TargetError::Storage(format!("Failed to save event to store: {e}")))?;
+            debug!("Event saved to store for target: {}", self.id);
+            Ok(())
+        } else {
+            self.send(&event).await
+        }
+    }
+
+    async fn send_from_store(&self, key: Key) -> Result<(), TargetError> {
+        debug!("Sending event from store for target: {}", self.id);
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| TargetError::Configuration("No store configured".to_string()))?;
+
+        let event = match store.get(&key) {
+            Ok(event) => event,
+            Err(StoreError::NotFound) => return Ok(()),
+            Err(e) => {
+                return Err(TargetError::Storage(format!("Failed to get event from store: {e}")));
+            }
+        };
+
+        self.send(&event).await?;
+
+        match store.del(&key) {
+            Ok(_) => debug!("Event deleted from store for target: {}, key:{}", self.id, key.to_string()),
+            Err(e) => {
+                error!("Failed to delete event from store: {}", e);
+                return Err(TargetError::Storage(format!("Failed to delete event from store: {e}")));
+            }
+        }
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), TargetError> {
+        // Give the events still in flight a chance to be delivered
+        let producer = self.producer.clone();
+        let _ = tokio::task::spawn_blocking(move || producer.flush(METADATA_TIMEOUT)).await;
+        info!("Kafka target closed: {}", self.id);
+        Ok(())
+    }
+
+    fn store(&self) -> Option<&(dyn Store<Event, Error = StoreError, Key = Key> + Send + Sync)> {
+        self.store.as_deref()
+    }
+
+    fn clone_dyn(&self) -> Box<dyn Target + Send + Sync> {
+        self.clone_box()
+    }
+
+    async fn init(&self) -> Result<(), TargetError> {
+        if !self.is_enabled() {
+            debug!("Kafka target {} is disabled, skipping initialization", self.id);
+            return Ok(());
+        }
+        self.is_active().await.map(|_| ())
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.args.enable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> KafkaArgs {
+        KafkaArgs {
+            enable: true,
+            brokers: vec!["localhost:9092".to_string()],
+            topic: "bucketevents".to_string(),
+            sasl_mechanism: String::new(),
+            sasl_username: String::new(),
+            sasl_password: String::new(),
+            tls: false,
+            tls_skip_verify: false,
+            queue_dir: String::new(),
+            queue_limit: 0,
+        }
+    }
+
+    #[test]
+    fn test_kafka_args_validate() {
+        assert!(args().validate().is_ok());
+        assert!(
+            KafkaArgs {
+                brokers: vec![],
+                ..args()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            KafkaArgs {
+                brokers: vec!["localhost".to_string()],
+                ..args()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            KafkaArgs {
+                sasl_mechanism: "GSSAPI".to_string(),
+                ..args()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_kafka_client_config() {
+        let config = KafkaArgs {
+            sasl_username: "user".to_string(),
+            sasl_password: "pass".to_string(),
+            sasl_mechanism: "scram-sha-512".to_string(),
+            tls: true,
+            ..args()
+        }
+        .client_config();
+        assert_eq!(config.get("security.protocol"), Some("sasl_ssl"));
+        assert_eq!(config.get("sasl.mechanisms"), Some("SCRAM-SHA-512"));
+        assert_eq!(args().client_config().get("security.protocol"), Some("plaintext"));
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::target::{ChannelTargetType, event_log_data};
+use crate::{
+    StoreError, Target,
+    arn::TargetID,
+    error::TargetError,
+    event::Event,
+    store::{Key, Store},
+};
+use async_nats::connection::State;
+use async_nats::{Client, ConnectOptions};
+use async_trait::async_trait;
+use rustfs_config::notify::STORE_EXTENSION;
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::sync::OnceCell;
+use tracing::{debug, error, info, instrument};
+
+const DEFAULT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Arguments for configuring a NATS target
+#[derive(Debug, Clone)]
+pub struct NATSArgs {
+    /// Whether the target is enabled
+    pub enable: bool,
+    /// The server address, such as `nats://127.0.0.1:4222`
+    pub address: String,
+    /// The subject to publish to
+    pub subject: String,
+    /// The username for the server
+    pub username: String,
+    /// The password for the server
+    pub password: String,
+    /// The authentication token for the server
+    pub token: String,
+    /// Whether TLS is required
+    pub tls: bool,
+    /// Whether to publish to a JetStream stream and wait for its ack
+    pub jetstream: bool,
+    /// The directory to store events in case of failure
+    pub queue_dir: String,
+    /// The maximum number of events to store
+    pub queue_limit: u64,
+}
+
+impl NATSArgs {
+    /// NATSArgs verification method
+    pub fn validate(&self) -> Result<(), TargetError> {
+        if !self.enable {
+            return Ok(());
+        }
+
+        if self.address.is_empty() {
+            return Err(TargetError::Configuration("address empty".to_string()));
+        }
+
+        if self.subject.is_empty() {
+            return Err(TargetError::Configuration("subject empty".to_string()));
+        }
+
+        if !self.token.is_empty() && !self.username.is_empty() {
+            return Err(TargetError::Configuration(
+                "token and username/password cannot be specified together".to_string(),
+            ));
+        }
+
+        if self.username.is_empty() != self.password.is_empty() {
+            return Err(TargetError::Configuration(
+                "username and password must be specified as a pair".to_string(),
+            ));
+        }
+
+        if !self.queue_dir.is_empty() {
+            let path = std::path::Path::new(&self.queue_dir);
+            if !path.is_absolute() {
+                return Err(TargetError::Configuration("nats queueDir path should be absolute".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A target that publishes events to a NATS subject
+pub struct NATSTarget {
+    id: TargetID,
+    args: NATSArgs,
+    // The client reconnects by itself once connected, so it is shared by all clones
+    client: Arc<OnceCell<Client>>,
+    store: Option<Box<dyn Store<Event, Error = StoreError, Key = Key> + Send + Sync>>,
+}
+
+impl NATSTarget {
+    /// Creates a new NATSTarget
+    #[instrument(skip(args), fields(target_id = %id))]
+    pub fn new(id: String, args: NATSArgs) -> Result<Self, TargetError> {
+        args.validate()?;
+        let target_id = TargetID::new(id, ChannelTargetType::Nats.as_str().to_string());
+
+        let queue_store = if !args.queue_dir.is_empty() {
+            let queue_dir =
+                PathBuf::from(&args.queue_dir).join(format!("rustfs-{}-{}", ChannelTargetType::Nats.as_str(), target_id.id));
+            let store = crate::store::QueueStore::<Event>::new(queue_dir, args.queue_limit, STORE_EXTENSION);
+
+            if let Err(e) = store.open() {
+                error!("Failed to open store for NATS target {}: {}", target_id.id, e);
+                return Err(TargetError::Storage(format!("{e}")));
+            }
+
+            Some(Box::new(store) as Box<dyn Store<Event, Error = StoreError, Key = Key> + Send + Sync>)
+        } else {
+            None
+        };
+
+        info!(target_id = %target_id.id, "NATS target created");
+        Ok(NATSTarget {
+            id: target_id,
+            args,
+            client: Arc::new(OnceCell::new()),
+            store: queue_store,
+        })
+    }
+
+    /// Clones the NATSTarget, sharing its connection
+    pub fn clone_box(&self) -> Box<dyn Target + Send + Sync> {
+        Box::new(NATSTarget {
+            id: self.id.clone(),
+            args: self.args.clone(),
+            client: Arc::clone(&self.client),
+            store: self.store.as_ref().map(|s| s.boxed_clone()),
+        })
+    }
+
+    fn connect_options(&self) -> ConnectOptions {
+        let mut options = ConnectOptions::new()
+            .name(format!("rustfs-{}", self.id.id))
+            .connection_timeout(DEFAULT_CONNECTION_TIMEOUT)
+            .require_tls(self.args.tls);
+        if !self.args.token.is_empty() {
+            options = options.token(self.args.token.clone());
+        } else if !self.args.username.is_empty() {
+            options = options.user_and_password(self.args.username.clone(), self.args.password.clone());
+        }
+        options
+    }
+
+    /// Connects on first use, a failed attempt is retried by the next call
+    async fn client(&self) -> Result<&Client, TargetError> {
+        self.client
+            .get_or_try_init(|| async {
+                let client = self
+                    .connect_options()
+                    .connect(self.args.address.as_str())
+                    .await
+                    .map_err(|e| {
+                        debug!("Connection to NATS {} failed: {}", self.args.address, e);
+                        TargetError::NotConnected
+                    })?;
+                info!("NATS target {} connected to {}", self.id, self.args.address);
+                Ok(client)
+            })
+            .await
+    }
+
+    async fn send(&self, event: &Event) -> Result<(), TargetError> {
+        let client = self.client().await?;
+        if client.connection_state() != State::Connected {
+            return Err(TargetError::NotConnected);
+        }
+
+        let data = event_log_data(event)?;
+        debug!("Sending event to NATS target: {}, subject: {}", self.id, self.args.subject);
+
+        if self.args.jetstream {
+            let ack = async_nats::jetstream::new(client.clone())
+                .publish(self.args.subject.clone(), data.into())
+                .await
+                .map_err(|e| TargetError::Request(format!("Failed to publish to JetStream: {e}")))?;
+            ack.await
+                .map_err(|e| TargetError::Request(format!("JetStream did not acknowledge the event: {e}")))?;
+        } else {
+            client
+                .publish(self.args.subject.clone(), data.into())
+                .await
+                .map_err(|e| TargetError::Request(format!("Failed to publish message: {e}")))?;
+            // Publishing only buffers the message, flushing makes sure the server got it
+            client.flush().await.map_err(|_| TargetError::NotConnected)?;
+        }
+
+        debug!("Event published to NATS target: {}", self.id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Target for NATSTarget {
+    fn id(&self) -> TargetID {
+        self.id.clone()
+    }
+
+    async fn is_active(&self) -> Result<bool, TargetError> {
+        let client = self.client().await?;
+        match client.connection_state() {
+            State::Connected => Ok(true),
+            _ => Err(TargetError::NotConnected),
+        }
+    }
+
+    async fn save(&self, event: Arc<Event>) -> Result<(), TargetError> {
+        if let Some(store) = &self.store {
+            store
+                .put(event)
+                .map_err(|e| TargetError::Storage(format!("Failed to save event to store: {e}")))?;
+            debug!("Event saved to store for target: {}", self.id);
+            Ok(())
+        } else {
+            self.send(&event).await
+        }
+    }
+
+    async fn send_from_store(&self, key: Key) -> Result<(), TargetError> {
+        debug!("Sending event from store for target: {}", self.id);
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| TargetError::Configuration("No store configured".to_string()))?;
+
+        let event = match store.get(&key) {
+            Ok(event) => event,
+            Err(StoreError::NotFound) => return Ok(()),
+            Err(e) => {
+                return Err(TargetError::Storage(format!("Failed to get event from store: {e}")));
+            }
+        };
+
+        self.send(&event).await?;
+
+        match store.del(&key) {
+            Ok(_) => debug!("Event deleted from store for target: {}, key:{}", self.id, key.to_string()),
+            Err(e) => {
+                error!("Failed to delete event from store: {}", e);
+                return Err(TargetError::Storage(format!("Failed to delete event from store: {e}")));
+            }
+        }
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), TargetError> {
+        if let Some(client) = self.client.get() {
+            if let Err(e) = client.drain().await {
+                debug!("Failed to drain NATS connection of target {}: {}", self.id, e);
+            }
+        }
+        info!("NATS target closed: {}", self.id);
+        Ok(())
+    }
+
+    fn store(&self) -> Option<&(dyn Store<Event, Error = StoreError, Key = Key> + Send + Sync)> {
+        self.store.as_deref()
+    }
+
+    fn clone_dyn(&self) -> Box<dyn Target + Send + Sync> {
+        self.clone_box()
+    }
+
+    async fn init(&self) -> Result<(), TargetError> {
+        if !self.is_enabled() {
+            debug!("NATS target {} is disabled, skipping initialization", self.id);
+            return Ok(());
+        }
+        self.is_active().await.map(|_| ())
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.args.enable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> NATSArgs {
+        NATSArgs {
+            enable: true,
+            address: "nats://127.0.0.1:4222".to_string(),
+            subject: "bucketevents".to_string(),
+            username: String::new(),
+            password: String::new(),
+            token: String::new(),
+            tls: false,
+            jetstream: false,
+            queue_dir: String::new(),
+            queue_limit: 0,
+        }
+    }
+
+    #[test]
+    fn test_nats_args_validate() {
+        assert!(args().validate().is_ok());
+        assert!(
+            NATSArgs {
+                subject: String::new(),
+                ..args()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            NATSArgs {
+                username: "user".to_string(),
+                ..args()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            NATSArgs {
+                token: "token".to_string(),
+                username: "user".to_string(),
+                password: "pass".to_string(),
+                ..args()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            NATSArgs {
+                queue_dir: "events".to_string(),
+                ..args()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
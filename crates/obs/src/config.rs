@@ -13,45 +13,52 @@
 // limitations under the License.
 
 use rustfs_config::observability::{
-    DEFAULT_AUDIT_LOGGER_QUEUE_CAPACITY, DEFAULT_SINKS_FILE_BUFFER_SIZE, DEFAULT_SINKS_FILE_FLUSH_INTERVAL_MS,
-    DEFAULT_SINKS_FILE_FLUSH_THRESHOLD, DEFAULT_SINKS_KAFKA_BATCH_SIZE, DEFAULT_SINKS_KAFKA_BATCH_TIMEOUT_MS,
-    DEFAULT_SINKS_KAFKA_BROKERS, DEFAULT_SINKS_KAFKA_TOPIC, DEFAULT_SINKS_WEBHOOK_AUTH_TOKEN, DEFAULT_SINKS_WEBHOOK_ENDPOINT,
-    DEFAULT_SINKS_WEBHOOK_MAX_RETRIES, DEFAULT_SINKS_WEBHOOK_RETRY_DELAY_MS, ENV_AUDIT_LOGGER_QUEUE_CAPACITY, ENV_OBS_ENDPOINT,
-    ENV_OBS_ENVIRONMENT, ENV_OBS_LOCAL_LOGGING_ENABLED, ENV_OBS_LOG_FILENAME, ENV_OBS_LOG_KEEP_FILES,
-    ENV_OBS_LOG_ROTATION_SIZE_MB, ENV_OBS_LOG_ROTATION_TIME, ENV_OBS_LOGGER_LEVEL, ENV_OBS_METER_INTERVAL, ENV_OBS_SAMPLE_RATIO,
-    ENV_OBS_SERVICE_NAME, ENV_OBS_SERVICE_VERSION, ENV_SINKS_FILE_BUFFER_SIZE, ENV_SINKS_FILE_FLUSH_INTERVAL_MS,
-    ENV_SINKS_FILE_FLUSH_THRESHOLD, ENV_SINKS_FILE_PATH, ENV_SINKS_KAFKA_BATCH_SIZE, ENV_SINKS_KAFKA_BATCH_TIMEOUT_MS,
-    ENV_SINKS_KAFKA_BROKERS, ENV_SINKS_KAFKA_TOPIC, ENV_SINKS_WEBHOOK_AUTH_TOKEN, ENV_SINKS_WEBHOOK_ENDPOINT,
-    ENV_SINKS_WEBHOOK_MAX_RETRIES, ENV_SINKS_WEBHOOK_RETRY_DELAY_MS,
+    DEFAULT_AUDIT_LOGGER_QUEUE_CAPACITY, DEFAULT_OBS_SAMPLE_PARENT_BASED, DEFAULT_SINKS_FILE_BUFFER_SIZE,
+    DEFAULT_SINKS_FILE_FLUSH_INTERVAL_MS, DEFAULT_SINKS_FILE_FLUSH_THRESHOLD, DEFAULT_SINKS_KAFKA_BATCH_SIZE,
+    DEFAULT_SINKS_KAFKA_BATCH_TIMEOUT_MS, DEFAULT_SINKS_KAFKA_BROKERS, DEFAULT_SINKS_KAFKA_TOPIC,
+    DEFAULT_SINKS_WEBHOOK_AUTH_TOKEN, DEFAULT_SINKS_WEBHOOK_ENDPOINT, DEFAULT_SINKS_WEBHOOK_MAX_RETRIES,
+    DEFAULT_SINKS_WEBHOOK_RETRY_DELAY_MS, ENV_AUDIT_LOGGER_QUEUE_CAPACITY, ENV_OBS_ENDPOINT, ENV_OBS_ENVIRONMENT,
+    ENV_OBS_LOCAL_LOGGING_ENABLED, ENV_OBS_LOG_FILENAME, ENV_OBS_LOG_KEEP_FILES, ENV_OBS_LOG_ROTATION_SIZE_MB,
+    ENV_OBS_LOG_ROTATION_TIME, ENV_OBS_LOGGER_LEVEL, ENV_OBS_METER_INTERVAL, ENV_OBS_SAMPLE_ALWAYS_TARGETS,
+    ENV_OBS_SAMPLE_PARENT_BASED, ENV_OBS_SAMPLE_RATIO, ENV_OBS_SERVICE_NAME, ENV_OBS_SERVICE_VERSION, ENV_SINKS_FILE_BUFFER_SIZE,
+    ENV_SINKS_FILE_FLUSH_INTERVAL_MS, ENV_SINKS_FILE_FLUSH_THRESHOLD, ENV_SINKS_FILE_PATH, ENV_SINKS_KAFKA_BATCH_SIZE,
+    ENV_SINKS_KAFKA_BATCH_TIMEOUT_MS, ENV_SINKS_KAFKA_BROKERS, ENV_SINKS_KAFKA_TOPIC, ENV_SINKS_WEBHOOK_AUTH_TOKEN,
+    ENV_SINKS_WEBHOOK_ENDPOINT, ENV_SINKS_WEBHOOK_MAX_RETRIES, ENV_SINKS_WEBHOOK_RETRY_DELAY_MS,
 };
-use rustfs_config::observability::{ENV_OBS_LOG_DIRECTORY, ENV_OBS_USE_STDOUT};
+use rustfs_config::observability::{ENV_AUDIT_API_FILTER, ENV_OBS_LOG_DIRECTORY, ENV_OBS_RESOURCE_DETECTORS, ENV_OBS_USE_STDOUT};
 use rustfs_config::{
     APP_NAME, DEFAULT_LOG_KEEP_FILES, DEFAULT_LOG_LEVEL, DEFAULT_LOG_ROTATION_SIZE_MB, DEFAULT_LOG_ROTATION_TIME,
     DEFAULT_OBS_LOG_FILENAME, ENVIRONMENT, METER_INTERVAL, SAMPLE_RATIO, SERVICE_VERSION, USE_STDOUT,
 };
 use rustfs_utils::dirs::get_log_directory_to_string;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 
 /// OpenTelemetry Configuration
 /// Add service name, service version, environment
 /// Add interval time for metric collection
 /// Add sample ratio for trace sampling
+/// Add always-sample targets and parent-based toggle for trace sampling
+/// Add resource detectors for host, Kubernetes and cloud attributes
 /// Add endpoint for metric collection
 /// Add use_stdout for output to stdout
 /// Add logger level for log level
 /// Add local_logging_enabled for local logging enabled
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct OtelConfig {
-    pub endpoint: String,                    // Endpoint for metric collection
-    pub use_stdout: Option<bool>,            // Output to stdout
-    pub sample_ratio: Option<f64>,           // Trace sampling ratio
-    pub meter_interval: Option<u64>,         // Metric collection interval
-    pub service_name: Option<String>,        // Service name
-    pub service_version: Option<String>,     // Service version
-    pub environment: Option<String>,         // Environment
-    pub logger_level: Option<String>,        // Logger level
-    pub local_logging_enabled: Option<bool>, // Local logging enabled
+    pub endpoint: String,                           // Endpoint for metric collection
+    pub use_stdout: Option<bool>,                   // Output to stdout
+    pub sample_ratio: Option<f64>,                  // Trace sampling ratio
+    pub sample_always_targets: Option<Vec<String>>, // Span targets sampled regardless of ratio
+    pub sample_parent_based: Option<bool>,          // Follow the sampling decision of the parent span
+    pub resource_detectors: Option<Vec<String>>,    // Resource detectors, all when unset
+    pub meter_interval: Option<u64>,                // Metric collection interval
+    pub service_name: Option<String>,               // Service name
+    pub service_version: Option<String>,            // Service version
+    pub environment: Option<String>,                // Environment
+    pub logger_level: Option<String>,               // Logger level
+    pub local_logging_enabled: Option<bool>,        // Local logging enabled
     // Added flexi_logger related configurations
     pub log_directory: Option<String>,     // LOG FILE DIRECTORY
     pub log_filename: Option<String>,      // The name of the log file
@@ -87,6 +94,16 @@ impl OtelConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .or(Some(SAMPLE_RATIO)),
+            sample_always_targets: env::var(ENV_OBS_SAMPLE_ALWAYS_TARGETS)
+                .ok()
+                .map(|v| v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()),
+            sample_parent_based: env::var(ENV_OBS_SAMPLE_PARENT_BASED)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .or(Some(DEFAULT_OBS_SAMPLE_PARENT_BASED)),
+            resource_detectors: env::var(ENV_OBS_RESOURCE_DETECTORS)
+                .ok()
+                .map(|v| v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()),
             meter_interval: env::var(ENV_OBS_METER_INTERVAL)
                 .ok()
                 .and_then(|v| v.parse().ok())
@@ -147,7 +164,7 @@ impl Default for OtelConfig {
 }
 
 /// Kafka Sink Configuration - Add batch parameters
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct KafkaSinkConfig {
     pub brokers: String,
     pub topic: String,
@@ -185,7 +202,7 @@ impl Default for KafkaSinkConfig {
 }
 
 /// Webhook Sink Configuration - Add Retry Parameters
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct WebhookSinkConfig {
     pub endpoint: String,
     pub auth_token: String,
@@ -223,7 +240,7 @@ impl Default for WebhookSinkConfig {
 }
 
 /// File Sink Configuration - Add buffering parameters
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct FileSinkConfig {
     pub path: String,
     pub buffer_size: Option<usize>,     // Write buffer size, default 8192
@@ -258,7 +275,7 @@ impl Default for FileSinkConfig {
 }
 
 /// Sink configuration collection
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type")]
 pub enum SinkConfig {
     File(FileSinkConfig),
@@ -278,10 +295,100 @@ impl Default for SinkConfig {
     }
 }
 
+/// Audit generation mode of an API
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditMode {
+    /// Audit every call
+    #[default]
+    On,
+    /// Never audit
+    Off,
+    /// Only audit calls that failed
+    Failures,
+}
+
+impl std::str::FromStr for AuditMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "on" | "all" => Ok(AuditMode::On),
+            "off" | "none" => Ok(AuditMode::Off),
+            "failures" | "errors" => Ok(AuditMode::Failures),
+            other => Err(format!("invalid audit mode: {other}")),
+        }
+    }
+}
+
+/// Per-API audit enable/disable matrix
+///
+/// APIs not listed use `default_mode`. The textual form is a comma separated list of
+/// `Api=mode` pairs where `*` sets the default, e.g. `HeadObject=off,ListObjectsV2=failures`.
+///
+/// # Example
+/// ```
+/// use rustfs_obs::{AuditFilter, AuditMode};
+///
+/// let filter: AuditFilter = "HeadObject=off,ListObjectsV2=failures".parse().unwrap();
+/// assert!(!filter.should_audit("HeadObject", Some(500)));
+/// assert!(!filter.should_audit("ListObjectsV2", Some(200)));
+/// assert!(filter.should_audit("ListObjectsV2", Some(404)));
+/// assert_eq!(filter.mode("PutObject"), AuditMode::On);
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    #[serde(default)]
+    pub default_mode: AuditMode,
+    #[serde(default)]
+    pub apis: HashMap<String, AuditMode>,
+}
+
+impl AuditFilter {
+    /// Mode applied to the given API
+    pub fn mode(&self, api: &str) -> AuditMode {
+        self.apis.get(api).copied().unwrap_or(self.default_mode)
+    }
+
+    /// Whether a call of `api` that finished with `status_code` should produce an audit entry
+    ///
+    /// A call without a status code is treated as failed.
+    pub fn should_audit(&self, api: &str, status_code: Option<i32>) -> bool {
+        match self.mode(api) {
+            AuditMode::On => true,
+            AuditMode::Off => false,
+            AuditMode::Failures => status_code.is_none_or(|code| code >= 400),
+        }
+    }
+}
+
+impl std::str::FromStr for AuditFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = AuditFilter::default();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((api, mode)) = pair.split_once('=') else {
+                return Err(format!("invalid audit filter entry: {pair}"));
+            };
+            let mode = mode.parse()?;
+            match api.trim() {
+                "" => return Err(format!("invalid audit filter entry: {pair}")),
+                "*" => filter.default_mode = mode,
+                api => {
+                    filter.apis.insert(api.to_string(), mode);
+                }
+            }
+        }
+        Ok(filter)
+    }
+}
+
 ///Logger Configuration
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct LoggerConfig {
     pub queue_capacity: Option<usize>,
+    pub audit_filter: Option<AuditFilter>, // Per-API audit matrix, audit everything when unset
 }
 
 impl LoggerConfig {
@@ -291,6 +398,13 @@ impl LoggerConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .or(Some(DEFAULT_AUDIT_LOGGER_QUEUE_CAPACITY)),
+            audit_filter: env::var(ENV_AUDIT_API_FILTER).ok().and_then(|v| match v.parse() {
+                Ok(filter) => Some(filter),
+                Err(e) => {
+                    eprintln!("Ignoring {ENV_AUDIT_API_FILTER}: {e}");
+                    None
+                }
+            }),
         }
     }
 }
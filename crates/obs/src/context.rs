@@ -0,0 +1,49 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Request context carried in task-local storage.
+//!
+//! The HTTP layer runs every request inside [`scope_request_id`], so log calls made while the
+//! request is handled pick up its id without it being passed down explicitly.
+
+use std::future::Future;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Run `f` with `request_id` as the id of the current request
+pub async fn scope_request_id<F: Future>(request_id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(request_id, f).await
+}
+
+/// Id of the request the current task is handling, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_id_is_scoped_to_the_task() {
+        assert_eq!(current_request_id(), None);
+
+        let id = scope_request_id("01J0000000000000000000000".to_string(), async { current_request_id() }).await;
+        assert_eq!(id.as_deref(), Some("01J0000000000000000000000"));
+
+        assert_eq!(current_request_id(), None);
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Crash capture
+//!
+//! Panics are recorded as a final `ServerLogEntry` in an emergency file that is written
+//! synchronously, because the async logger worker may never run again once the process is going
+//! down.
+//!
+//! On Linux, fatal signals are recorded too. Formatting a record or capturing a backtrace is not
+//! async-signal-safe, so the signal handler only `write(2)`s a fixed record and the signal number
+//! to the pre-opened emergency file, then lets the default disposition dump core.
+
+use crate::{BaseLogEntry, GlobalError};
+use crate::{LogRecord, OtelConfig, ServerLogEntry};
+use rustfs_config::DEFAULT_CRASH_LOG_FILE;
+use rustfs_config::observability::ENV_OBS_LOG_DIRECTORY;
+use std::backtrace::Backtrace;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing_core::Level;
+
+/// Emergency sink, opened once at install time so that crash paths never allocate a file handle
+static CRASH_SINK: OnceLock<CrashSink> = OnceLock::new();
+
+/// Synchronous append-only sink for crash records
+#[derive(Debug)]
+pub(crate) struct CrashSink {
+    path: PathBuf,
+    file: File,
+}
+
+impl CrashSink {
+    pub(crate) fn open(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    /// Write the raw bytes and flush them to disk
+    pub(crate) fn write_raw(&self, data: &[u8]) {
+        let mut file = &self.file;
+        let _ = file.write_all(data);
+        let _ = file.sync_data();
+    }
+
+    /// Serialize the entry as a JSON line and flush it to disk
+    pub(crate) fn write_entry(&self, entry: &ServerLogEntry) {
+        let mut line = entry.to_json();
+        line.push('\n');
+        self.write_raw(line.as_bytes());
+    }
+}
+
+/// Build the crash record for a panic
+pub(crate) fn panic_entry(info: &PanicHookInfo<'_>, backtrace: &Backtrace) -> ServerLogEntry {
+    let payload = if let Some(s) = info.payload().downcast_ref::<&str>() {
+        (*s).to_string()
+    } else if let Some(s) = info.payload().downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    };
+
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let thread = std::thread::current();
+    let base = BaseLogEntry::new().message(Some(format!("panic: {payload}")));
+
+    ServerLogEntry::new(Level::ERROR, "crash_handler".to_string())
+        .with_base(base)
+        .add_field("kind".to_string(), "panic".to_string())
+        .add_field("location".to_string(), location)
+        .add_field("thread".to_string(), thread.name().unwrap_or("unnamed").to_string())
+        .add_field("backtrace".to_string(), backtrace.to_string())
+}
+
+/// Install the panic hook and, on Linux, the fatal signal handlers
+///
+/// The crash file is `<log_directory>/rustfs-crash.log`. Calling this more than once keeps the
+/// first installation.
+///
+/// # Example
+/// ```no_run
+/// use rustfs_obs::{OtelConfig, install_crash_handler};
+///
+/// let config = OtelConfig::default();
+/// let _ = install_crash_handler(&config);
+/// ```
+pub fn install_crash_handler(config: &OtelConfig) -> Result<PathBuf, GlobalError> {
+    if let Some(sink) = CRASH_SINK.get() {
+        return Ok(sink.path.clone());
+    }
+
+    let default_log_directory = rustfs_utils::dirs::get_log_directory_to_string(ENV_OBS_LOG_DIRECTORY);
+    let log_directory = config.log_directory.as_deref().unwrap_or(default_log_directory.as_str());
+    let path = Path::new(log_directory).join(DEFAULT_CRASH_LOG_FILE);
+
+    let sink = CrashSink::open(&path).map_err(|e| GlobalError::CrashHandlerError(e.to_string()))?;
+    let _ = CRASH_SINK.set(sink);
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(sink) = CRASH_SINK.get() {
+            let backtrace = Backtrace::force_capture();
+            sink.write_entry(&panic_entry(info, &backtrace));
+        }
+        previous(info);
+    }));
+
+    #[cfg(target_os = "linux")]
+    if let Some(sink) = CRASH_SINK.get() {
+        use std::os::fd::AsRawFd;
+        signal::install(sink.file.as_raw_fd())?;
+    }
+
+    Ok(path)
+}
+
+#[cfg(target_os = "linux")]
+mod signal {
+    use crate::GlobalError;
+    use nix::libc::{c_int, c_void};
+    use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction};
+    use std::os::fd::RawFd;
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    const FATAL_SIGNALS: [Signal; 4] = [Signal::SIGSEGV, Signal::SIGABRT, Signal::SIGBUS, Signal::SIGILL];
+
+    /// Record written for a fatal signal, completed by the signal number and `SIGNAL_RECORD_END`
+    const SIGNAL_RECORD: &[u8] = b"{\"level\":\"ERROR\",\"source\":\"crash_handler\",\"kind\":\"signal\",\"signal\":";
+    const SIGNAL_RECORD_END: &[u8] = b"}\n";
+
+    /// Emergency file the handler writes to, -1 until the handlers are installed
+    static CRASH_FD: AtomicI32 = AtomicI32::new(-1);
+
+    pub(super) fn install(fd: RawFd) -> Result<(), GlobalError> {
+        CRASH_FD.store(fd, Ordering::Relaxed);
+        let action = SigAction::new(
+            SigHandler::Handler(handle_fatal_signal),
+            SaFlags::SA_RESETHAND | SaFlags::SA_ONSTACK,
+            SigSet::empty(),
+        );
+
+        for sig in FATAL_SIGNALS {
+            // SAFETY: the handler only calls write(2), fdatasync(2) and raise(3), which are
+            // async-signal-safe, and re-raises the signal with the default disposition restored by
+            // SA_RESETHAND.
+            #[allow(unsafe_code)]
+            unsafe { sigaction(sig, &action) }.map_err(|e| GlobalError::CrashHandlerError(format!("{sig}: {e}")))?;
+        }
+
+        Ok(())
+    }
+
+    extern "C" fn handle_fatal_signal(signum: c_int) {
+        let fd = CRASH_FD.load(Ordering::Relaxed);
+        if fd >= 0 {
+            let mut digits = [0u8; 10];
+            let mut start = digits.len();
+            let mut n = signum.unsigned_abs();
+            loop {
+                start -= 1;
+                digits[start] = b'0' + (n % 10) as u8;
+                n /= 10;
+                if n == 0 {
+                    break;
+                }
+            }
+
+            write_all(fd, SIGNAL_RECORD);
+            write_all(fd, &digits[start..]);
+            write_all(fd, SIGNAL_RECORD_END);
+            // SAFETY: fdatasync on a file descriptor that stays open for the life of the process
+            #[allow(unsafe_code)]
+            unsafe {
+                nix::libc::fdatasync(fd);
+            }
+        }
+
+        // SAFETY: raise is async-signal-safe, the default disposition is back thanks to SA_RESETHAND
+        #[allow(unsafe_code)]
+        unsafe {
+            nix::libc::raise(signum);
+        }
+    }
+
+    /// Async-signal-safe write of the whole buffer, giving up on the first error
+    fn write_all(fd: RawFd, mut data: &[u8]) {
+        while !data.is_empty() {
+            // SAFETY: the pointer and length come from a live slice
+            #[allow(unsafe_code)]
+            let written = unsafe { nix::libc::write(fd, data.as_ptr() as *const c_void, data.len()) };
+            if written <= 0 {
+                return;
+            }
+            data = &data[written as usize..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_sink_writes_json_lines() {
+        let dir = std::env::temp_dir().join(format!("rustfs-obs-crash-{}", std::process::id()));
+        let path = dir.join(DEFAULT_CRASH_LOG_FILE);
+        let sink = CrashSink::open(&path).unwrap();
+
+        let entry = ServerLogEntry::new(Level::ERROR, "crash_handler".to_string())
+            .with_base(BaseLogEntry::new().message(Some("panic: boom".to_string())));
+        sink.write_entry(&entry);
+        sink.write_entry(&entry);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let parsed: ServerLogEntry = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(parsed.source, "crash_handler");
+        assert_eq!(parsed.base.message.as_deref(), Some("panic: boom"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_fatal_signal_is_recorded() {
+        use nix::sys::signal::{Signal, raise};
+        use nix::sys::wait::{WaitStatus, waitpid};
+        use nix::unistd::{ForkResult, fork};
+        use std::os::fd::AsRawFd;
+
+        let dir = std::env::temp_dir().join(format!("rustfs-obs-signal-{}", std::process::id()));
+        let path = dir.join(DEFAULT_CRASH_LOG_FILE);
+        let sink = CrashSink::open(&path).unwrap();
+
+        // SAFETY: the child only installs the handlers and raises the signal before it dies
+        #[allow(unsafe_code)]
+        match unsafe { fork() }.unwrap() {
+            ForkResult::Child => {
+                let _ = signal::install(sink.file.as_raw_fd());
+                let _ = raise(Signal::SIGABRT);
+                #[allow(unsafe_code)]
+                unsafe {
+                    nix::libc::_exit(0)
+                };
+            }
+            ForkResult::Parent { child } => {
+                assert!(matches!(waitpid(child, None).unwrap(), WaitStatus::Signaled(_, Signal::SIGABRT, _)));
+            }
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let record: serde_json::Value = serde_json::from_str(content.trim_end()).unwrap();
+        assert_eq!(record["kind"], "signal");
+        assert_eq!(record["signal"], Signal::SIGABRT as i32);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
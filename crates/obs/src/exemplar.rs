@@ -0,0 +1,289 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Latency histograms carrying trace-id exemplars.
+//!
+//! The OpenTelemetry SDK does not populate exemplars yet, so latency observations are
+//! additionally aggregated here, keeping the most recent sampled trace per bucket, and
+//! rendered in the OpenMetrics text format where Grafana can link a bucket to its trace.
+
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::{KeyValue, global, metrics::Histogram};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Content type of [`LatencyHistogram::render_openmetrics`] output
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Bucket upper bounds in seconds, from 1ms to 60s
+pub const DEFAULT_LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+static HTTP_REQUEST_LATENCY: LazyLock<LatencyHistogram> = LazyLock::new(|| {
+    LatencyHistogram::new(
+        "rustfs_http_request_duration_seconds",
+        "http.server.request.duration",
+        "Time taken to serve an HTTP request",
+        "code",
+    )
+});
+
+/// Histogram of HTTP request latency, labelled by response status code
+pub fn http_request_latency() -> &'static LatencyHistogram {
+    &HTTP_REQUEST_LATENCY
+}
+
+/// A sampled observation linked to the trace that produced it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exemplar {
+    pub trace_id: String,
+    pub span_id: String,
+    pub value: f64,
+    /// Seconds since the Unix epoch
+    pub timestamp: f64,
+}
+
+#[derive(Debug)]
+struct Series {
+    /// Non-cumulative counts, one per bound plus the `+Inf` bucket
+    counts: Vec<u64>,
+    exemplars: Vec<Option<Exemplar>>,
+    sum: f64,
+    count: u64,
+}
+
+impl Series {
+    fn new(buckets: usize) -> Self {
+        Self {
+            counts: vec![0; buckets + 1],
+            exemplars: vec![None; buckets + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+/// Latency histogram with a single label dimension and per-bucket exemplars
+///
+/// Every observation is also recorded on an OpenTelemetry histogram so OTLP export keeps working.
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    name: &'static str,
+    otel_name: &'static str,
+    help: &'static str,
+    label: &'static str,
+    bounds: &'static [f64],
+    series: RwLock<HashMap<String, Arc<Mutex<Series>>>>,
+    otel: OnceLock<Histogram<f64>>,
+}
+
+impl LatencyHistogram {
+    pub fn new(name: &'static str, otel_name: &'static str, help: &'static str, label: &'static str) -> Self {
+        Self::with_buckets(name, otel_name, help, label, DEFAULT_LATENCY_BUCKETS)
+    }
+
+    pub fn with_buckets(
+        name: &'static str,
+        otel_name: &'static str,
+        help: &'static str,
+        label: &'static str,
+        bounds: &'static [f64],
+    ) -> Self {
+        Self {
+            name,
+            otel_name,
+            help,
+            label,
+            bounds,
+            series: RwLock::new(HashMap::new()),
+            otel: OnceLock::new(),
+        }
+    }
+
+    /// Record `latency`, attaching the trace of `span` as exemplar when that trace is sampled
+    pub fn observe(&self, label_value: &str, latency: Duration, span: &Span) {
+        let value = latency.as_secs_f64();
+
+        self.otel
+            .get_or_init(|| {
+                global::meter("rustfs")
+                    .f64_histogram(self.otel_name)
+                    .with_description(self.help)
+                    .with_unit("s")
+                    .with_boundaries(self.bounds.to_vec())
+                    .build()
+            })
+            .record(value, &[KeyValue::new(self.label, label_value.to_string())]);
+
+        let exemplar = span_exemplar(span, value);
+        let bucket = self.bounds.iter().position(|b| value <= *b).unwrap_or(self.bounds.len());
+
+        let series = self.series_for(label_value);
+        let mut series = series.lock().unwrap_or_else(|e| e.into_inner());
+        series.counts[bucket] += 1;
+        series.sum += value;
+        series.count += 1;
+        if exemplar.is_some() {
+            series.exemplars[bucket] = exemplar;
+        }
+    }
+
+    /// Most recent exemplar of every bucket of the series, `+Inf` last
+    pub fn exemplars(&self, label_value: &str) -> Vec<Option<Exemplar>> {
+        let series = self.series.read().unwrap_or_else(|e| e.into_inner());
+        match series.get(label_value) {
+            Some(s) => s.lock().unwrap_or_else(|e| e.into_inner()).exemplars.clone(),
+            None => vec![None; self.bounds.len() + 1],
+        }
+    }
+
+    /// Render all series in the OpenMetrics text exposition format, terminated by `# EOF`
+    pub fn render_openmetrics(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE {} histogram", self.name);
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# UNIT {} seconds", self.name);
+
+        let series = self.series.read().unwrap_or_else(|e| e.into_inner());
+        let mut labels: Vec<&String> = series.keys().collect();
+        labels.sort();
+
+        for label_value in labels {
+            let s = series[label_value].lock().unwrap_or_else(|e| e.into_inner());
+            let label_value = escape_label_value(label_value);
+            let mut cumulative = 0;
+            for (i, count) in s.counts.iter().enumerate() {
+                cumulative += count;
+                let le = match self.bounds.get(i) {
+                    Some(b) => b.to_string(),
+                    None => "+Inf".to_string(),
+                };
+                let _ = write!(
+                    out,
+                    "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}",
+                    self.name, self.label, label_value, le, cumulative
+                );
+                if let Some(e) = &s.exemplars[i] {
+                    let _ = write!(
+                        out,
+                        " # {{trace_id=\"{}\",span_id=\"{}\"}} {} {:.3}",
+                        e.trace_id, e.span_id, e.value, e.timestamp
+                    );
+                }
+                out.push('\n');
+            }
+            let _ = writeln!(out, "{}_sum{{{}=\"{}\"}} {}", self.name, self.label, label_value, s.sum);
+            let _ = writeln!(out, "{}_count{{{}=\"{}\"}} {}", self.name, self.label, label_value, s.count);
+        }
+
+        out.push_str("# EOF\n");
+        out
+    }
+
+    fn series_for(&self, label_value: &str) -> Arc<Mutex<Series>> {
+        if let Some(s) = self.series.read().unwrap_or_else(|e| e.into_inner()).get(label_value) {
+            return s.clone();
+        }
+        self.series
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(label_value.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(Series::new(self.bounds.len()))))
+            .clone()
+    }
+}
+
+/// Exemplar for `span`, only when it belongs to a valid, sampled trace
+fn span_exemplar(span: &Span, value: f64) -> Option<Exemplar> {
+    let context = span.context();
+    let span_ref = context.span();
+    let span_context = span_ref.span_context();
+    if !span_context.is_valid() || !span_context.is_sampled() {
+        return None;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default();
+
+    Some(Exemplar {
+        trace_id: span_context.trace_id().to_string(),
+        span_id: span_context.span_id().to_string(),
+        value,
+        timestamp,
+    })
+}
+
+/// `value` escaped to be quoted as the value of a label in the OpenMetrics text format
+pub fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn histogram() -> LatencyHistogram {
+        LatencyHistogram::with_buckets("test_latency_seconds", "test.latency", "Test latency", "method", &[0.1, 1.0])
+    }
+
+    #[test]
+    fn test_observe_without_trace_has_no_exemplar() {
+        let h = histogram();
+        h.observe("GET", Duration::from_millis(50), &Span::none());
+        h.observe("GET", Duration::from_secs(2), &Span::none());
+
+        assert_eq!(h.exemplars("GET"), vec![None, None, None]);
+
+        let text = h.render_openmetrics();
+        assert!(text.contains("test_latency_seconds_bucket{method=\"GET\",le=\"0.1\"} 1\n"));
+        assert!(text.contains("test_latency_seconds_bucket{method=\"GET\",le=\"1\"} 1\n"));
+        assert!(text.contains("test_latency_seconds_bucket{method=\"GET\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("test_latency_seconds_count{method=\"GET\"} 2\n"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_observe_in_sampled_span_attaches_exemplar() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let h = histogram();
+        let trace_id = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("http-request");
+            h.observe("PUT", Duration::from_millis(500), &span);
+            span.context().span().span_context().trace_id().to_string()
+        });
+
+        let exemplars = h.exemplars("PUT");
+        assert!(exemplars[0].is_none());
+        let exemplar = exemplars[1].as_ref().expect("exemplar in the 1s bucket");
+        assert_eq!(exemplar.trace_id, trace_id);
+        assert_eq!(exemplar.value, 0.5);
+
+        let text = h.render_openmetrics();
+        assert!(text.contains(&format!(
+            "test_latency_seconds_bucket{{method=\"PUT\",le=\"1\"}} 1 # {{trace_id=\"{trace_id}\""
+        )));
+    }
+}
@@ -12,10 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::crash::install_crash_handler;
 use crate::logger::InitLogStatus;
+use crate::reload::{load_config, spawn_config_watcher};
 use crate::telemetry::{OtelGuard, init_telemetry};
-use crate::{AppConfig, Logger, get_global_logger, init_global_logger};
+use crate::{Logger, get_global_logger, init_global_logger};
+use serde::Serialize;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::{OnceCell, SetError};
 use tracing::{error, info};
 
@@ -41,10 +45,111 @@ pub enum GlobalError {
     GpuInitError(String),
     #[error("GPU device not found: {0}")]
     GpuDeviceError(String),
-    #[error("Failed to send log: {0}")]
-    SendFailed(&'static str),
-    #[error("Operation timed out: {0}")]
-    Timeout(&'static str),
+    #[error("Log queue is closed")]
+    QueueClosed,
+    #[error("Log queue full, gave up after {0:?}")]
+    QueueTimeout(Duration),
+    #[error(transparent)]
+    Sink(#[from] SinkError),
+    #[error("Failed to install crash handler: {0}")]
+    CrashHandlerError(String),
+}
+
+impl GlobalError {
+    /// Stable identifier of the error kind, suitable for metrics and API responses
+    pub fn code(&self) -> &'static str {
+        match self {
+            GlobalError::SetError(_) => "GuardAlreadySet",
+            GlobalError::NotInitialized => "GuardNotInitialized",
+            GlobalError::MetricsError(_) => "MetricsError",
+            GlobalError::PidError(_) => "PidError",
+            GlobalError::ProcessNotFound(_) => "ProcessNotFound",
+            GlobalError::CoreCountError => "CoreCountError",
+            GlobalError::GpuInitError(_) => "GpuInitError",
+            GlobalError::GpuDeviceError(_) => "GpuDeviceError",
+            GlobalError::QueueClosed => "QueueClosed",
+            GlobalError::QueueTimeout(_) => "QueueTimeout",
+            GlobalError::Sink(e) => e.kind.code(),
+            GlobalError::CrashHandlerError(_) => "CrashHandlerError",
+        }
+    }
+
+    /// Whether the same operation may succeed later, e.g. once queue pressure or a sink outage is over
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GlobalError::QueueTimeout(_) => true,
+            GlobalError::Sink(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
+/// Classification of a sink delivery failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SinkErrorKind {
+    /// The destination could not be reached or is overloaded
+    Unavailable,
+    /// The destination refused the entry, e.g. an authentication or validation error
+    Rejected,
+    /// Local I/O failed
+    Io,
+    /// The sink configuration can never work, e.g. an invalid header or a missing permission
+    Misconfigured,
+}
+
+impl SinkErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SinkErrorKind::Unavailable => "SinkUnavailable",
+            SinkErrorKind::Rejected => "SinkRejected",
+            SinkErrorKind::Io => "SinkIo",
+            SinkErrorKind::Misconfigured => "SinkMisconfigured",
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SinkErrorKind::Unavailable | SinkErrorKind::Io)
+    }
+}
+
+/// Failure of a sink to deliver a log entry
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{sink} sink failed ({}): {message}", kind.code())]
+pub struct SinkError {
+    pub sink: &'static str,
+    pub kind: SinkErrorKind,
+    pub message: String,
+    /// Entries the failure lost, more than one when a sink reports failures of earlier batches
+    pub entries: u64,
+}
+
+impl SinkError {
+    pub fn new(sink: &'static str, kind: SinkErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            sink,
+            kind,
+            message: message.into(),
+            entries: 1,
+        }
+    }
+
+    pub fn with_entries(mut self, entries: u64) -> Self {
+        self.entries = entries;
+        self
+    }
+
+    /// Classify a local I/O error, permission and missing path errors need an operator to fix them
+    pub fn from_io(sink: &'static str, err: &std::io::Error) -> Self {
+        let kind = match err.kind() {
+            std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::NotFound => SinkErrorKind::Misconfigured,
+            _ => SinkErrorKind::Io,
+        };
+        Self::new(sink, kind, err.to_string())
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.kind.is_retryable()
+    }
 }
 
 /// Initialize the observability module
@@ -66,11 +171,18 @@ pub enum GlobalError {
 /// ```
 pub async fn init_obs(endpoint: Option<String>) -> (Arc<tokio::sync::Mutex<Logger>>, OtelGuard) {
     // Load the configuration file
-    let config = AppConfig::new_with_endpoint(endpoint);
+    let config = load_config(endpoint.clone());
 
     let guard = init_telemetry(&config.observability);
 
+    match install_crash_handler(&config.observability) {
+        Ok(path) => info!("Crash handler installed, crash log: {}", path.display()),
+        Err(e) => error!("Failed to install crash handler: {}", e),
+    }
+
     let logger = init_global_logger(&config).await;
+    logger.lock().await.register_metrics(&opentelemetry::global::meter("logger"));
+    spawn_config_watcher(logger.clone(), endpoint, config.clone());
     let obs_config = config.observability.clone();
     tokio::spawn(async move {
         let result = InitLogStatus::init_start_log(&obs_config).await;
@@ -165,4 +277,19 @@ mod tests {
         let result = get_global_guard();
         assert!(matches!(result, Err(GlobalError::NotInitialized)));
     }
+
+    #[test]
+    fn test_error_retryability() {
+        assert!(GlobalError::QueueTimeout(Duration::from_millis(500)).is_retryable());
+        assert!(!GlobalError::QueueClosed.is_retryable());
+
+        let unavailable = GlobalError::from(SinkError::new("webhook", SinkErrorKind::Unavailable, "503"));
+        assert!(unavailable.is_retryable());
+        assert_eq!(unavailable.code(), "SinkUnavailable");
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let misconfigured = GlobalError::from(SinkError::from_io("file", &denied));
+        assert!(!misconfigured.is_retryable());
+        assert_eq!(misconfigured.code(), "SinkMisconfigured");
+    }
 }
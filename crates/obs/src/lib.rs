@@ -45,23 +45,36 @@
 /// # }
 /// ```
 mod config;
+mod context;
+mod crash;
 mod entry;
+mod exemplar;
 mod global;
 mod logger;
 mod metrics;
+mod reload;
+pub mod replay;
+mod resource;
+mod sampler;
 mod sinks;
 mod system;
 mod telemetry;
 mod worker;
 
-pub use config::{AppConfig, LoggerConfig, OtelConfig, SinkConfig};
+pub use config::{AppConfig, AuditFilter, AuditMode, LoggerConfig, OtelConfig, SinkConfig};
+pub use context::{current_request_id, scope_request_id};
+pub use crash::install_crash_handler;
 pub use entry::args::Args;
 pub use entry::audit::{ApiDetails, AuditLogEntry};
 pub use entry::base::BaseLogEntry;
 pub use entry::unified::{ConsoleLogEntry, ServerLogEntry, UnifiedLogEntry};
 pub use entry::{LogKind, LogRecord, ObjectVersion, SerializableLevel};
+pub use exemplar::{
+    DEFAULT_LATENCY_BUCKETS, Exemplar, LatencyHistogram, OPENMETRICS_CONTENT_TYPE, escape_label_value, http_request_latency,
+};
 pub use global::*;
-pub use logger::Logger;
+pub use logger::{Logger, LoggerStats};
 pub use logger::{get_global_logger, init_global_logger, start_logger};
-pub use logger::{log_debug, log_error, log_info, log_trace, log_warn, log_with_context};
+pub use logger::{log_audit_event, log_debug, log_error, log_info, log_trace, log_warn, log_with_context};
+pub use sinks::Sink;
 pub use system::SystemObserver;
@@ -12,25 +12,94 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::context::current_request_id;
+use crate::resource::detect_resource_attributes;
 use crate::sinks::Sink;
 use crate::{
-    AppConfig, AuditLogEntry, BaseLogEntry, ConsoleLogEntry, GlobalError, OtelConfig, ServerLogEntry, UnifiedLogEntry, sinks,
+    ApiDetails, AppConfig, AuditFilter, AuditLogEntry, BaseLogEntry, ConsoleLogEntry, GlobalError, OtelConfig, ServerLogEntry,
+    SinkError, SinkErrorKind, UnifiedLogEntry, sinks,
 };
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::Meter;
 use rustfs_config::{APP_NAME, ENVIRONMENT, SERVICE_VERSION};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::SystemTime;
 use tokio::sync::mpsc::{self, Receiver, Sender};
-use tokio::sync::{Mutex, OnceCell};
+use tokio::sync::{Mutex, OnceCell, broadcast, watch};
 use tracing_core::Level;
 
 // Add the global instance at the module level
 static GLOBAL_LOGGER: OnceCell<Arc<Mutex<Logger>>> = OnceCell::const_new();
 
+/// Capacity of the console broadcast channel, slow subscribers lag behind and skip entries
+const CONSOLE_BROADCAST_CAPACITY: usize = 1024;
+
+pub const LOGGER_QUEUE_LENGTH: &str = "logger.queue.length";
+pub const LOGGER_QUEUE_CAPACITY: &str = "logger.queue.capacity";
+pub const LOGGER_DROPPED_ENTRIES: &str = "logger.dropped.entries";
+pub const LOGGER_SINK_FAILURES: &str = "logger.sink.failures";
+
+/// How long `log_entry` waits for room in a full queue before dropping the entry
+const QUEUE_BACKPRESSURE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Counters shared between the logger and its worker
+#[derive(Debug, Default)]
+pub(crate) struct LoggerCounters {
+    pub(crate) dropped_by_policy: AtomicU64,
+    pub(crate) sink_failures: AtomicU64,
+    pub(crate) permanent_sink_failures: AtomicU64,
+    pub(crate) last_sink_error: std::sync::Mutex<Option<SinkErrorKind>>,
+}
+
+impl LoggerCounters {
+    pub(crate) fn record_sink_failure(&self, err: &SinkError) {
+        self.sink_failures.fetch_add(err.entries, Ordering::Relaxed);
+        if !err.is_retryable() {
+            self.permanent_sink_failures.fetch_add(err.entries, Ordering::Relaxed);
+        }
+        *self.last_sink_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(err.kind);
+    }
+}
+
+/// Point-in-time view of the logger pipeline, used to size `queue_capacity`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LoggerStats {
+    /// Entries waiting for the worker
+    pub queue_length: usize,
+    pub queue_capacity: usize,
+    /// Entries suppressed by the audit filter or lost after the queue full backpressure timeout
+    pub dropped_by_policy: u64,
+    /// Entries a sink failed to deliver
+    pub sink_failures: u64,
+    /// Sink failures that need the sink to be reconfigured, included in `sink_failures`
+    pub permanent_sink_failures: u64,
+    /// Kind of the most recent sink failure
+    pub last_sink_error: Option<SinkErrorKind>,
+}
+
 /// Server log processor
-#[derive(Debug)]
 pub struct Logger {
     sender: Sender<UnifiedLogEntry>, // Log sending channel
     queue_capacity: usize,
+    console_tx: broadcast::Sender<ConsoleLogEntry>, // Live console log fan-out
+    audit_filter: AuditFilter,                      // Per-API audit matrix
+    counters: Arc<LoggerCounters>,                  // Pipeline self-metrics
+    sinks_tx: watch::Sender<Vec<Arc<dyn Sink>>>,    // Sinks used by the worker, replaced on reload
+    resource_tags: HashMap<String, Value>,          // Detected resource attributes added to every entry
+}
+
+impl std::fmt::Debug for Logger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Logger")
+            .field("queue_capacity", &self.queue_capacity)
+            .field("audit_filter", &self.audit_filter)
+            .field("sinks", &self.sinks_tx.borrow().len())
+            .finish_non_exhaustive()
+    }
 }
 
 impl Logger {
@@ -40,7 +109,27 @@ impl Logger {
         // Get queue capacity from configuration, or use default values 10000
         let queue_capacity = config.logger.as_ref().and_then(|l| l.queue_capacity).unwrap_or(10000);
         let (sender, receiver) = mpsc::channel(queue_capacity);
-        (Logger { sender, queue_capacity }, receiver)
+        let (console_tx, _) = broadcast::channel(CONSOLE_BROADCAST_CAPACITY);
+        let audit_filter = config
+            .logger
+            .as_ref()
+            .and_then(|l| l.audit_filter.clone())
+            .unwrap_or_default();
+        (
+            Logger {
+                sender,
+                queue_capacity,
+                console_tx,
+                audit_filter,
+                counters: Arc::new(LoggerCounters::default()),
+                sinks_tx: watch::Sender::new(Vec::new()),
+                resource_tags: detect_resource_attributes(&config.observability)
+                    .into_iter()
+                    .map(|kv| (kv.key.to_string(), Value::String(kv.value.to_string())))
+                    .collect(),
+            },
+            receiver,
+        )
     }
 
     /// get the queue capacity
@@ -58,15 +147,142 @@ impl Logger {
         self.queue_capacity
     }
 
+    /// Snapshot of the queue usage and drop counters
+    ///
+    /// # Example
+    /// ```
+    /// use rustfs_obs::Logger;
+    ///
+    /// fn example(logger: &Logger) {
+    ///     let stats = logger.stats();
+    ///     println!("{}/{} queued, {} dropped", stats.queue_length, stats.queue_capacity, stats.dropped_by_policy);
+    /// }
+    /// ```
+    pub fn stats(&self) -> LoggerStats {
+        LoggerStats {
+            queue_length: self.sender.max_capacity() - self.sender.capacity(),
+            queue_capacity: self.queue_capacity,
+            dropped_by_policy: self.counters.dropped_by_policy.load(Ordering::Relaxed),
+            sink_failures: self.counters.sink_failures.load(Ordering::Relaxed),
+            permanent_sink_failures: self.counters.permanent_sink_failures.load(Ordering::Relaxed),
+            last_sink_error: *self.counters.last_sink_error.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    /// Export the logger stats as observable instruments of `meter`
+    /// The instruments hold a weak handle on the queue and stop reporting its length once the logger shuts down.
+    pub fn register_metrics(&self, meter: &Meter) {
+        let sender = self.sender.downgrade();
+        meter
+            .u64_observable_gauge(LOGGER_QUEUE_LENGTH)
+            .with_description("Log entries waiting to be written to the sinks.")
+            .with_callback(move |observer| {
+                if let Some(sender) = sender.upgrade() {
+                    observer.observe((sender.max_capacity() - sender.capacity()) as u64, &[]);
+                }
+            })
+            .build();
+
+        let capacity = self.queue_capacity as u64;
+        meter
+            .u64_observable_gauge(LOGGER_QUEUE_CAPACITY)
+            .with_description("Capacity of the log queue.")
+            .with_callback(move |observer| observer.observe(capacity, &[]))
+            .build();
+
+        let counters = self.counters.clone();
+        meter
+            .u64_observable_counter(LOGGER_DROPPED_ENTRIES)
+            .with_description("Log entries dropped by the audit filter or a full queue.")
+            .with_callback(move |observer| observer.observe(counters.dropped_by_policy.load(Ordering::Relaxed), &[]))
+            .build();
+
+        let counters = self.counters.clone();
+        meter
+            .u64_observable_counter(LOGGER_SINK_FAILURES)
+            .with_description("Log entries that a sink failed to deliver.")
+            .with_callback(move |observer| {
+                let total = counters.sink_failures.load(Ordering::Relaxed);
+                let permanent = counters.permanent_sink_failures.load(Ordering::Relaxed);
+                observer.observe(total.saturating_sub(permanent), &[KeyValue::new("retryable", true)]);
+                observer.observe(permanent, &[KeyValue::new("retryable", false)]);
+            })
+            .build();
+    }
+
+    /// Apply a changed logger section of `config` to the running pipeline
+    /// The worker switches to `sinks` before its next entry; queued entries are kept and written to the new sinks.
+    /// The queue capacity is fixed at startup, a different `queue_capacity` only takes effect after a restart.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rustfs_obs::{AppConfig, Logger};
+    ///
+    /// fn example(logger: &mut Logger, config: &AppConfig) {
+    ///     logger.reload(config, vec![]);
+    /// }
+    /// ```
+    pub fn reload(&mut self, config: &AppConfig, sinks: Vec<Arc<dyn Sink>>) {
+        let logger_config = config.logger.as_ref();
+        if let Some(capacity) = logger_config.and_then(|l| l.queue_capacity) {
+            if capacity != self.queue_capacity {
+                tracing::warn!("Logger queue capacity change {} -> {} requires a restart", self.queue_capacity, capacity);
+            }
+        }
+        self.audit_filter = logger_config.and_then(|l| l.audit_filter.clone()).unwrap_or_default();
+        self.sinks_tx.send_replace(sinks);
+    }
+
+    /// Subscribe to the live console log stream
+    /// Every console entry logged after the call is delivered to the returned receiver.
+    /// A subscriber that falls behind receives `RecvError::Lagged` and continues with newer entries.
+    ///
+    /// # Example
+    /// ```
+    /// use rustfs_obs::Logger;
+    ///
+    /// async fn example(logger: &Logger) {
+    ///     let mut rx = logger.subscribe_console();
+    ///     while let Ok(entry) = rx.recv().await {
+    ///         println!("{}", entry.console_msg);
+    ///     }
+    /// }
+    /// ```
+    pub fn subscribe_console(&self) -> broadcast::Receiver<ConsoleLogEntry> {
+        self.console_tx.subscribe()
+    }
+
     /// Log a server entry
     #[tracing::instrument(skip(self), fields(log_source = "logger_server"))]
     pub async fn log_server_entry(&self, entry: ServerLogEntry) -> Result<(), GlobalError> {
         self.log_entry(UnifiedLogEntry::Server(entry)).await
     }
 
+    /// Whether a call of `api` that finished with `status_code` should be audited
+    /// Callers on hot paths can check this before building the audit entry.
+    ///
+    /// # Example
+    /// ```
+    /// use rustfs_obs::Logger;
+    ///
+    /// fn example(logger: &Logger) {
+    ///     if logger.should_audit("HeadObject", Some(200)) {
+    ///         // build and log the audit entry
+    ///     }
+    /// }
+    /// ```
+    pub fn should_audit(&self, api: &str, status_code: Option<i32>) -> bool {
+        self.audit_filter.should_audit(api, status_code)
+    }
+
     /// Log an audit entry
+    /// Entries suppressed by the audit filter are dropped silently.
     #[tracing::instrument(skip(self), fields(log_source = "logger_audit"))]
     pub async fn log_audit_entry(&self, entry: AuditLogEntry) -> Result<(), GlobalError> {
+        if !self.should_audit(entry.api.name.as_deref().unwrap_or_default(), entry.api.status_code) {
+            self.counters.dropped_by_policy.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
         self.log_entry(UnifiedLogEntry::Audit(Box::new(entry))).await
     }
 
@@ -78,7 +294,9 @@ impl Logger {
 
     /// Asynchronous logging of unified log entries
     #[tracing::instrument(skip_all, fields(log_source = "logger"))]
-    pub async fn log_entry(&self, entry: UnifiedLogEntry) -> Result<(), GlobalError> {
+    pub async fn log_entry(&self, mut entry: UnifiedLogEntry) -> Result<(), GlobalError> {
+        self.add_resource_tags(&mut entry);
+
         // Extract information for tracing based on entry type
         match &entry {
             UnifiedLogEntry::Server(server) => {
@@ -128,6 +346,9 @@ impl Logger {
                     node = %console.node_name,
                     message = %console.console_msg
                 );
+
+                // No subscribers is the common case, ignore the send error
+                let _ = self.console_tx.send(console.clone());
             }
         }
 
@@ -137,13 +358,32 @@ impl Logger {
             Err(mpsc::error::TrySendError::Full(entry)) => {
                 // Processing strategy when queue is full
                 tracing::warn!("Log queue full, applying backpressure");
-                match tokio::time::timeout(std::time::Duration::from_millis(500), self.sender.send(entry)).await {
+                match tokio::time::timeout(QUEUE_BACKPRESSURE_TIMEOUT, self.sender.send(entry)).await {
                     Ok(Ok(_)) => Ok(()),
-                    Ok(Err(_)) => Err(GlobalError::SendFailed("Channel closed")),
-                    Err(_) => Err(GlobalError::Timeout("Queue backpressure timeout")),
+                    Ok(Err(_)) => Err(GlobalError::QueueClosed),
+                    Err(_) => {
+                        self.counters.dropped_by_policy.fetch_add(1, Ordering::Relaxed);
+                        Err(GlobalError::QueueTimeout(QUEUE_BACKPRESSURE_TIMEOUT))
+                    }
                 }
             }
-            Err(mpsc::error::TrySendError::Closed(_)) => Err(GlobalError::SendFailed("Logger channel closed")),
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(GlobalError::QueueClosed),
+        }
+    }
+
+    /// Tag the entry with the detected resource attributes, keeping tags set by the caller
+    fn add_resource_tags(&self, entry: &mut UnifiedLogEntry) {
+        if self.resource_tags.is_empty() {
+            return;
+        }
+        let base = match entry {
+            UnifiedLogEntry::Server(server) => &mut server.base,
+            UnifiedLogEntry::Audit(audit) => &mut audit.base,
+            UnifiedLogEntry::Console(console) => &mut console.base,
+        };
+        let tags = base.tags.get_or_insert_with(HashMap::new);
+        for (key, value) in &self.resource_tags {
+            tags.entry(key.clone()).or_insert_with(|| value.clone());
         }
     }
 
@@ -153,7 +393,7 @@ impl Logger {
     /// # Parameters
     /// - `message`: Message to be logged
     /// - `source`: Source of the log
-    /// - `request_id`: Request ID
+    /// - `request_id`: Request ID, defaults to the id of the request handled by the current task
     /// - `user_id`: User ID
     /// - `fields`: Additional fields
     ///
@@ -177,6 +417,8 @@ impl Logger {
         user_id: Option<String>,
         fields: Vec<(String, String)>,
     ) -> Result<(), GlobalError> {
+        // Fall back to the id of the request being handled by this task
+        let request_id = request_id.or_else(current_request_id);
         let base = BaseLogEntry::new().message(Some(message.to_string())).request_id(request_id);
 
         let server_entry = ServerLogEntry::new(level, source.to_string())
@@ -248,7 +490,12 @@ impl Logger {
 /// ```
 pub fn start_logger(config: &AppConfig, sinks: Vec<Arc<dyn Sink>>) -> Logger {
     let (logger, receiver) = Logger::new(config);
-    tokio::spawn(crate::worker::start_worker(receiver, sinks));
+    logger.sinks_tx.send_replace(sinks);
+    tokio::spawn(crate::worker::start_worker(
+        receiver,
+        logger.sinks_tx.subscribe(),
+        logger.counters.clone(),
+    ));
     logger
 }
 
@@ -401,7 +648,7 @@ pub async fn log_trace(message: &str, source: &str) -> Result<(), GlobalError> {
 /// - `message`: Message to be logged
 /// - `source`: Source of the log
 /// - `level`: Log level
-/// - `request_id`: Request ID
+/// - `request_id`: Request ID, defaults to the id of the request handled by the current task
 /// - `user_id`: User ID
 /// - `fields`: Additional fields
 /// # Returns
@@ -430,6 +677,33 @@ pub async fn log_with_context(
         .await
 }
 
+/// Log an audit entry for an event the server raised outside of an S3 request
+///
+/// The entry is written from a spawned task, so callers holding locks are not held up by the sinks,
+/// and a failure to write it is only logged.
+///
+/// # Parameters
+/// - `event`: Name of the event, also set as the name of the API
+/// - `entry_type`: Type of the entry, such as `admin` or `lock`
+/// - `api`: Details of the API the event concerns
+/// - `base`: Message and tags of the entry
+/// - `access_key`: Access key of the user who caused the event, if any
+pub fn log_audit_event(event: String, entry_type: &str, api: ApiDetails, base: BaseLogEntry, access_key: Option<String>) {
+    let entry = AuditLogEntry::new()
+        .with_base(base)
+        .set_version("1".to_string())
+        .set_event(event.clone())
+        .set_entry_type(Some(entry_type.to_string()))
+        .set_api(api.set_name(Some(event.clone())))
+        .set_access_key(access_key);
+
+    tokio::spawn(async move {
+        if let Err(e) = get_global_logger().lock().await.log_audit_entry(entry).await {
+            tracing::warn!("audit {} failed: {}", event, e);
+        }
+    });
+}
+
 /// Log initialization status
 #[derive(Debug)]
 pub(crate) struct InitLogStatus {
@@ -488,3 +762,148 @@ async fn log_init_state(status: Option<InitLogStatus>) -> Result<(), GlobalError
     get_global_logger().lock().await.log_server_entry(server_entry).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApiDetails, LoggerConfig};
+
+    #[tokio::test]
+    async fn test_subscribe_console_receives_entries() {
+        let (logger, _receiver) = Logger::new(&AppConfig::default());
+        let mut rx = logger.subscribe_console();
+
+        let entry = ConsoleLogEntry::new_with_console_msg("hello".to_string(), "node-1".to_string());
+        logger.log_console_entry(entry).await.unwrap();
+
+        let received = rx.recv().await.unwrap();
+        assert_eq!(received.console_msg, "hello");
+        assert_eq!(received.node_name, "node-1");
+    }
+
+    #[tokio::test]
+    async fn test_audit_filter_suppresses_entries() {
+        let config = AppConfig {
+            logger: Some(LoggerConfig {
+                queue_capacity: Some(16),
+                audit_filter: Some("HeadObject=off,ListObjectsV2=failures".parse().unwrap()),
+            }),
+            ..AppConfig::default()
+        };
+        let (logger, mut receiver) = Logger::new(&config);
+
+        let audit = |name: &str, code: i32| {
+            AuditLogEntry::new().set_api(ApiDetails::new().set_name(Some(name.to_string())).set_status_code(Some(code)))
+        };
+        logger.log_audit_entry(audit("HeadObject", 500)).await.unwrap();
+        logger.log_audit_entry(audit("ListObjectsV2", 200)).await.unwrap();
+        logger.log_audit_entry(audit("ListObjectsV2", 403)).await.unwrap();
+        logger.log_audit_entry(audit("PutObject", 200)).await.unwrap();
+
+        let stats = logger.stats();
+        assert_eq!(stats.queue_length, 2);
+        assert_eq!(stats.queue_capacity, 16);
+        assert_eq!(stats.dropped_by_policy, 2);
+
+        let mut logged = Vec::new();
+        while let Ok(UnifiedLogEntry::Audit(entry)) = receiver.try_recv() {
+            assert!(entry.base.tags.as_ref().is_some_and(|tags| tags.contains_key("os.type")));
+            logged.push((entry.api.name.unwrap_or_default(), entry.api.status_code.unwrap_or_default()));
+        }
+        assert_eq!(logged, vec![("ListObjectsV2".to_string(), 403), ("PutObject".to_string(), 200)]);
+    }
+
+    #[derive(Default)]
+    struct CollectSink(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait::async_trait]
+    impl Sink for CollectSink {
+        async fn write(&self, entry: &UnifiedLogEntry) -> Result<(), SinkError> {
+            if let UnifiedLogEntry::Console(console) = entry {
+                self.0.lock().unwrap().push(console.console_msg.clone());
+            }
+            Ok(())
+        }
+    }
+
+    async fn wait_for(sink: &CollectSink, len: usize) -> Vec<String> {
+        for _ in 0..100 {
+            if sink.0.lock().unwrap().len() >= len {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        sink.0.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_reload_switches_sinks() {
+        let console = |msg: &str| ConsoleLogEntry::new_with_console_msg(msg.to_string(), "node-1".to_string());
+        let before = Arc::new(CollectSink::default());
+        let after = Arc::new(CollectSink::default());
+
+        let mut logger = start_logger(&AppConfig::default(), vec![before.clone()]);
+        logger.log_console_entry(console("one")).await.unwrap();
+        assert_eq!(wait_for(&before, 1).await, vec!["one"]);
+
+        let config = AppConfig {
+            logger: Some(LoggerConfig {
+                queue_capacity: None,
+                audit_filter: Some("*=off".parse().unwrap()),
+            }),
+            ..AppConfig::default()
+        };
+        logger.reload(&config, vec![after.clone()]);
+        assert!(!logger.should_audit("PutObject", Some(200)));
+
+        logger.log_console_entry(console("two")).await.unwrap();
+        logger.log_console_entry(console("three")).await.unwrap();
+        assert_eq!(wait_for(&after, 2).await, vec!["two", "three"]);
+        assert_eq!(wait_for(&before, 1).await, vec!["one"]);
+    }
+
+    struct FailingSink(SinkErrorKind);
+
+    #[async_trait::async_trait]
+    impl Sink for FailingSink {
+        async fn write(&self, _entry: &UnifiedLogEntry) -> Result<(), SinkError> {
+            Err(SinkError::new("test", self.0, "failed"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sink_failures_are_classified() {
+        let logger = start_logger(
+            &AppConfig::default(),
+            vec![
+                Arc::new(FailingSink(SinkErrorKind::Unavailable)),
+                Arc::new(FailingSink(SinkErrorKind::Misconfigured)),
+            ],
+        );
+        logger
+            .log_console_entry(ConsoleLogEntry::new_with_console_msg("one".to_string(), "node-1".to_string()))
+            .await
+            .unwrap();
+
+        let mut stats = logger.stats();
+        for _ in 0..100 {
+            if stats.sink_failures >= 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            stats = logger.stats();
+        }
+        assert_eq!(stats.sink_failures, 2);
+        assert_eq!(stats.permanent_sink_failures, 1);
+        assert_eq!(stats.last_sink_error, Some(SinkErrorKind::Misconfigured));
+    }
+
+    #[test]
+    fn test_sink_failure_counts_every_lost_entry() {
+        let counters = LoggerCounters::default();
+        counters.record_sink_failure(&SinkError::new("kafka", SinkErrorKind::Unavailable, "lost").with_entries(5));
+        counters.record_sink_failure(&SinkError::new("file", SinkErrorKind::Misconfigured, "denied"));
+        assert_eq!(counters.sink_failures.load(Ordering::Relaxed), 6);
+        assert_eq!(counters.permanent_sink_failures.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{AppConfig, Logger, LoggerConfig, SinkConfig, sinks};
+use rustfs_config::observability::{DEFAULT_OBS_CONFIG_RELOAD_INTERVAL, ENV_OBS_CONFIG_FILE, ENV_OBS_CONFIG_RELOAD_INTERVAL};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Sections of the config file that override the environment
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    sinks: Option<Vec<SinkConfig>>,
+    logger: Option<LoggerConfig>,
+}
+
+/// Load the application configuration from the environment and the optional `RUSTFS_OBS_CONFIG_FILE`
+pub(crate) fn load_config(endpoint: Option<String>) -> AppConfig {
+    let path = std::env::var(ENV_OBS_CONFIG_FILE)
+        .ok()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from);
+    load_config_from(endpoint, path.as_deref())
+}
+
+fn load_config_from(endpoint: Option<String>, path: Option<&Path>) -> AppConfig {
+    let mut config = AppConfig::new_with_endpoint(endpoint);
+    let Some(path) = path else {
+        return config;
+    };
+
+    let file = std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_slice::<ConfigFile>(&data).map_err(|e| e.to_string()));
+    match file {
+        Ok(file) => {
+            if let Some(sinks) = file.sinks {
+                config.sinks = sinks;
+            }
+            if file.logger.is_some() {
+                config.logger = file.logger;
+            }
+        }
+        Err(e) => warn!("Ignoring observability config file {}: {}", path.display(), e),
+    }
+    config
+}
+
+/// Periodically reload the configuration and apply changes of its `sinks` or `logger` sections to `logger`
+///
+/// Disabled when `RUSTFS_OBS_CONFIG_RELOAD_INTERVAL` is `0`.
+pub(crate) fn spawn_config_watcher(logger: Arc<Mutex<Logger>>, endpoint: Option<String>, applied: AppConfig) {
+    let interval = std::env::var(ENV_OBS_CONFIG_RELOAD_INTERVAL)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_OBS_CONFIG_RELOAD_INTERVAL);
+    if interval == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut applied = applied;
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+
+            let config = load_config(endpoint.clone());
+            if config.sinks == applied.sinks && config.logger == applied.logger {
+                continue;
+            }
+
+            // Build the sinks before taking the lock so logging is not blocked while they connect
+            let sinks = sinks::create_sinks(&config).await;
+            info!("Logger configuration changed, reloading {} sinks", sinks.len());
+            logger.lock().await.reload(&config, sinks);
+            applied = config;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WebhookSinkConfig;
+
+    #[test]
+    fn test_config_file_overrides_sinks_and_logger() {
+        let path = std::env::temp_dir().join(format!("rustfs-obs-config-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{
+                "sinks": [{ "type": "Webhook", "endpoint": "http://audit:8080", "auth_token": "token" }],
+                "logger": { "queue_capacity": 64 }
+            }"#,
+        )
+        .unwrap();
+
+        let config = load_config_from(None, Some(&path));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            config.sinks,
+            vec![SinkConfig::Webhook(WebhookSinkConfig {
+                endpoint: "http://audit:8080".to_string(),
+                auth_token: "token".to_string(),
+                max_retries: None,
+                retry_delay_ms: None,
+            })]
+        );
+        assert_eq!(config.logger.and_then(|l| l.queue_capacity), Some(64));
+    }
+
+    #[test]
+    fn test_unreadable_config_file_keeps_environment() {
+        let config = load_config_from(None, Some(Path::new("/nonexistent/rustfs-obs.json")));
+        assert_eq!(config.sinks, AppConfig::new_with_endpoint(None).sinks);
+    }
+}
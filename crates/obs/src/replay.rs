@@ -0,0 +1,292 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replay of log entries persisted by the file sink into another sink.
+//!
+//! The file sink writes one JSON entry per line. When a remote sink such as Elasticsearch was
+//! unavailable, the entries it missed can be re-emitted from the local files once it is back:
+//!
+//! ```no_run
+//! use rustfs_obs::replay::{ReplayOptions, replay};
+//! use rustfs_obs::{AppConfig, SinkConfig};
+//!
+//! # async fn example() {
+//! let config = AppConfig::new_with_endpoint(None);
+//! let target = config.sinks.iter().find(|s| matches!(s, SinkConfig::Webhook(_))).unwrap();
+//! match replay("/var/logs/rustfs".as_ref(), target, &ReplayOptions::default()).await {
+//!     Ok(stats) => println!("replayed {} entries, skipped {}", stats.replayed, stats.skipped),
+//!     Err(e) => println!("replayed {} entries before failing: {}", e.stats.replayed, e.source),
+//! }
+//! # }
+//! ```
+
+use crate::sinks::{self, Sink};
+use crate::{AppConfig, LogRecord, SinkConfig, SinkError, SinkErrorKind, UnifiedLogEntry};
+use chrono::{DateTime, Utc};
+use rustfs_config::DEFAULT_SINK_FILE_LOG_FILE;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Which entries to replay and how hard to try delivering them
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    /// Only replay entries logged at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only replay entries logged before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Attempts per entry while the sink reports a retryable error, the sink itself does not retry
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every further attempt
+    pub retry_delay: Duration,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            since: None,
+            until: None,
+            max_attempts: 5,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl ReplayOptions {
+    fn in_window(&self, timestamp: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| timestamp >= since) && self.until.is_none_or(|until| timestamp < until)
+    }
+}
+
+/// Outcome of a replay run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Files read, oldest first
+    pub files: Vec<PathBuf>,
+    /// Entries delivered to the sink
+    pub replayed: u64,
+    /// Entries outside the time window
+    pub filtered: u64,
+    /// Lines that are not a JSON log entry, e.g. written by an older version of the file sink
+    pub skipped: u64,
+}
+
+/// A replay run that stopped before the end, with what it did until then
+#[derive(Debug, thiserror::Error)]
+#[error("replay stopped after {} entries: {source}", .stats.replayed)]
+pub struct ReplayError {
+    pub stats: ReplayStats,
+    pub source: SinkError,
+}
+
+impl ReplayError {
+    fn new(stats: &ReplayStats, source: SinkError) -> Self {
+        Self {
+            stats: stats.clone(),
+            source,
+        }
+    }
+}
+
+/// Replay the entries found at `path` into a sink built from `target`
+pub async fn replay(path: &Path, target: &SinkConfig, options: &ReplayOptions) -> Result<ReplayStats, ReplayError> {
+    let mut target = target.clone();
+    // Retries are up to the replay, a webhook makes a single attempt per entry
+    if let SinkConfig::Webhook(webhook) = &mut target {
+        webhook.max_retries = Some(1);
+    }
+    let config = AppConfig {
+        sinks: vec![target],
+        ..AppConfig::default()
+    };
+    let Some(sink) = sinks::create_sinks(&config).await.pop() else {
+        let err = SinkError::new("replay", SinkErrorKind::Misconfigured, "the target sink could not be created");
+        return Err(ReplayError::new(&ReplayStats::default(), err));
+    };
+    replay_into(path, sink, options).await
+}
+
+/// Replay the entries found at `path` into `sink`
+///
+/// Delivery stops at the first permanent sink error, or when a retryable error persists for
+/// `max_attempts` attempts. The error carries the stats of the entries handled until then. Entries
+/// are replayed in order, so a later run with `since` set to the timestamp of the last delivered
+/// entry picks up where the failed run stopped.
+pub async fn replay_into(path: &Path, sink: Arc<dyn Sink>, options: &ReplayOptions) -> Result<ReplayStats, ReplayError> {
+    let mut stats = ReplayStats::default();
+    let io_error = |stats: &ReplayStats, e: std::io::Error| ReplayError::new(stats, SinkError::from_io("replay", &e));
+    stats.files = replay_files(path).await.map_err(|e| io_error(&stats, e))?;
+
+    for file in stats.files.clone() {
+        let reader = tokio::fs::File::open(&file).await.map_err(|e| io_error(&stats, e))?;
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Some(line) = lines.next_line().await.map_err(|e| io_error(&stats, e))? {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Ok(entry) = serde_json::from_str::<UnifiedLogEntry>(line) else {
+                stats.skipped += 1;
+                continue;
+            };
+            if !options.in_window(entry.get_timestamp()) {
+                stats.filtered += 1;
+                continue;
+            }
+
+            write_with_retry(sink.as_ref(), &entry, options)
+                .await
+                .map_err(|e| ReplayError::new(&stats, e))?;
+            stats.replayed += 1;
+        }
+    }
+
+    Ok(stats)
+}
+
+async fn write_with_retry(sink: &dyn Sink, entry: &UnifiedLogEntry, options: &ReplayOptions) -> Result<(), SinkError> {
+    let mut delay = options.retry_delay;
+    let mut attempt = 1;
+    loop {
+        match sink.write(entry).await {
+            Ok(()) => return Ok(()),
+            Err(e) if e.is_retryable() && attempt < options.max_attempts => {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Files to replay for `path`, oldest first
+///
+/// A file is replayed as is. For a directory, the file sink output and its rotated copies
+/// (`rustfs-sink.log.1`, `rustfs-sink.log.2024-06-01`, ...) are replayed in modification order.
+pub async fn replay_files(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if tokio::fs::metadata(path).await?.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    let mut entries = tokio::fs::read_dir(path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        if !name.to_string_lossy().starts_with(DEFAULT_SINK_FILE_LOG_FILE) || !entry.file_type().await?.is_file() {
+            continue;
+        }
+        files.push((entry.metadata().await?.modified()?, entry.path()));
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuditLogEntry, BaseLogEntry};
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FlakySink {
+        failures_left: Mutex<u32>,
+        kind: Option<SinkErrorKind>,
+        events: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Sink for FlakySink {
+        async fn write(&self, entry: &UnifiedLogEntry) -> Result<(), SinkError> {
+            let mut failures_left = self.failures_left.lock().unwrap();
+            if let Some(kind) = self.kind.filter(|_| *failures_left > 0) {
+                *failures_left -= 1;
+                return Err(SinkError::new("test", kind, "down"));
+            }
+            if let UnifiedLogEntry::Audit(audit) = entry {
+                self.events.lock().unwrap().push(audit.event.clone());
+            }
+            Ok(())
+        }
+    }
+
+    fn audit_line(event: &str, hour: u32) -> String {
+        let timestamp = Utc.with_ymd_and_hms(2024, 6, 1, hour, 0, 0).unwrap();
+        let entry = AuditLogEntry::new()
+            .with_base(BaseLogEntry::new().timestamp(timestamp))
+            .set_event(event.to_string());
+        serde_json::to_string(&UnifiedLogEntry::Audit(Box::new(entry))).unwrap()
+    }
+
+    fn write_log(name: &str, lines: &[String]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rustfs-obs-replay-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(DEFAULT_SINK_FILE_LOG_FILE), lines.join("\n")).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_replay_filters_window_and_skips_garbage() {
+        let dir = write_log(
+            "window",
+            &[
+                audit_line("PutObject", 1),
+                "UnifiedLogEntry::Audit(..)".to_string(),
+                audit_line("GetObject", 2),
+                audit_line("DeleteObject", 3),
+            ],
+        );
+        let sink = Arc::new(FlakySink::default());
+        let options = ReplayOptions {
+            since: Some(Utc.with_ymd_and_hms(2024, 6, 1, 2, 0, 0).unwrap()),
+            ..ReplayOptions::default()
+        };
+
+        let stats = replay_into(&dir, sink.clone(), &options).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(stats.files.len(), 1);
+        assert_eq!((stats.replayed, stats.filtered, stats.skipped), (2, 1, 1));
+        assert_eq!(*sink.events.lock().unwrap(), vec!["GetObject", "DeleteObject"]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_retries_transient_errors_only() {
+        let dir = write_log("retry", &[audit_line("PutObject", 1)]);
+        let options = ReplayOptions {
+            retry_delay: Duration::from_millis(1),
+            ..ReplayOptions::default()
+        };
+
+        let flaky = Arc::new(FlakySink {
+            failures_left: Mutex::new(2),
+            kind: Some(SinkErrorKind::Unavailable),
+            ..FlakySink::default()
+        });
+        assert_eq!(replay_into(&dir, flaky.clone(), &options).await.unwrap().replayed, 1);
+
+        let rejecting = Arc::new(FlakySink {
+            failures_left: Mutex::new(1),
+            kind: Some(SinkErrorKind::Rejected),
+            ..FlakySink::default()
+        });
+        let err = replay_into(&dir, rejecting, &options).await.unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!err.source.is_retryable());
+        assert_eq!((err.stats.files.len(), err.stats.replayed), (1, 0));
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of the environment rustfs runs in, reported as OpenTelemetry resource attributes.
+//!
+//! Detectors only read environment variables and local files, so startup never waits on a
+//! metadata service. Kubernetes attributes rely on the downward API variables below.
+
+use crate::OtelConfig;
+use opentelemetry::KeyValue;
+use opentelemetry_semantic_conventions::attribute::{
+    CLOUD_PROVIDER, CLOUD_REGION, HOST_ARCH, HOST_NAME, K8S_NAMESPACE_NAME, K8S_NODE_NAME, K8S_POD_NAME, K8S_POD_UID, OS_TYPE,
+    OS_VERSION,
+};
+use rustfs_config::observability::DEFAULT_OBS_RESOURCE_DETECTORS;
+use std::env;
+use std::fs;
+use std::str::FromStr;
+
+const DETECTOR_HOST: &str = "host";
+const DETECTOR_K8S: &str = "k8s";
+const DETECTOR_CLOUD: &str = "cloud";
+
+const K8S_SERVICE_HOST_ENV: &str = "KUBERNETES_SERVICE_HOST";
+const K8S_POD_NAME_ENV: &str = "POD_NAME";
+const K8S_POD_UID_ENV: &str = "POD_UID";
+const K8S_POD_NAMESPACE_ENV: &str = "POD_NAMESPACE";
+const K8S_NODE_NAME_ENV: &str = "NODE_NAME";
+const K8S_NAMESPACE_FILE: &str = "/var/run/secrets/kubernetes.io/serviceaccount/namespace";
+const DMI_SYS_VENDOR_FILE: &str = "/sys/class/dmi/id/sys_vendor";
+
+/// Source of resource attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResourceDetector {
+    /// Hostname, OS and architecture
+    Host,
+    /// Pod, namespace and node when running in Kubernetes
+    K8s,
+    /// Cloud provider and region
+    Cloud,
+}
+
+impl FromStr for ResourceDetector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            DETECTOR_HOST => Ok(Self::Host),
+            DETECTOR_K8S | "kubernetes" => Ok(Self::K8s),
+            DETECTOR_CLOUD => Ok(Self::Cloud),
+            other => Err(format!("unknown resource detector: {other}")),
+        }
+    }
+}
+
+impl ResourceDetector {
+    fn detect(self, attributes: &mut Vec<KeyValue>) {
+        match self {
+            Self::Host => detect_host(attributes),
+            Self::K8s => detect_k8s(attributes),
+            Self::Cloud => detect_cloud(attributes),
+        }
+    }
+}
+
+/// Resource attributes of all detectors enabled in `config`
+pub(crate) fn detect_resource_attributes(config: &OtelConfig) -> Vec<KeyValue> {
+    let names = match &config.resource_detectors {
+        Some(names) => names.clone(),
+        None => DEFAULT_OBS_RESOURCE_DETECTORS.split(',').map(str::to_string).collect(),
+    };
+
+    let mut attributes = Vec::new();
+    for name in names.iter().filter(|n| !n.trim().is_empty()) {
+        match name.parse::<ResourceDetector>() {
+            Ok(detector) => detector.detect(&mut attributes),
+            Err(e) => eprintln!("Ignoring resource detector: {e}"),
+        }
+    }
+    attributes
+}
+
+fn detect_host(attributes: &mut Vec<KeyValue>) {
+    if let Some(host_name) = sysinfo::System::host_name() {
+        attributes.push(KeyValue::new(HOST_NAME, host_name));
+    }
+    let arch = match env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => other,
+    };
+    attributes.push(KeyValue::new(HOST_ARCH, arch));
+    let os_type = match env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    };
+    attributes.push(KeyValue::new(OS_TYPE, os_type));
+    if let Some(os_version) = sysinfo::System::os_version() {
+        attributes.push(KeyValue::new(OS_VERSION, os_version));
+    }
+}
+
+fn detect_k8s(attributes: &mut Vec<KeyValue>) {
+    if env_value(K8S_SERVICE_HOST_ENV).is_none() {
+        return;
+    }
+
+    // The pod hostname defaults to the pod name
+    if let Some(pod_name) = env_value(K8S_POD_NAME_ENV).or_else(|| env_value("HOSTNAME")) {
+        attributes.push(KeyValue::new(K8S_POD_NAME, pod_name));
+    }
+    if let Some(pod_uid) = env_value(K8S_POD_UID_ENV) {
+        attributes.push(KeyValue::new(K8S_POD_UID, pod_uid));
+    }
+    let namespace = env_value(K8S_POD_NAMESPACE_ENV).or_else(|| {
+        fs::read_to_string(K8S_NAMESPACE_FILE)
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    });
+    if let Some(namespace) = namespace {
+        attributes.push(KeyValue::new(K8S_NAMESPACE_NAME, namespace));
+    }
+    if let Some(node_name) = env_value(K8S_NODE_NAME_ENV) {
+        attributes.push(KeyValue::new(K8S_NODE_NAME, node_name));
+    }
+}
+
+fn detect_cloud(attributes: &mut Vec<KeyValue>) {
+    let vendor = fs::read_to_string(DMI_SYS_VENDOR_FILE).unwrap_or_default();
+    let provider = cloud_provider(&vendor).or_else(|| {
+        if env_value("AWS_REGION").is_some() || env_value("AWS_EXECUTION_ENV").is_some() {
+            Some("aws")
+        } else if env_value("GOOGLE_CLOUD_PROJECT").is_some() {
+            Some("gcp")
+        } else {
+            None
+        }
+    });
+    let Some(provider) = provider else {
+        return;
+    };
+    attributes.push(KeyValue::new(CLOUD_PROVIDER, provider));
+
+    let region = match provider {
+        "aws" => env_value("AWS_REGION").or_else(|| env_value("AWS_DEFAULT_REGION")),
+        "gcp" => env_value("GOOGLE_CLOUD_REGION"),
+        "azure" => env_value("AZURE_REGION"),
+        _ => None,
+    };
+    if let Some(region) = region {
+        attributes.push(KeyValue::new(CLOUD_REGION, region));
+    }
+}
+
+/// Map the DMI system vendor to the `cloud.provider` value
+fn cloud_provider(sys_vendor: &str) -> Option<&'static str> {
+    let vendor = sys_vendor.trim();
+    if vendor.starts_with("Amazon") {
+        Some("aws")
+    } else if vendor.starts_with("Google") {
+        Some("gcp")
+    } else if vendor.starts_with("Microsoft") {
+        Some("azure")
+    } else if vendor.starts_with("Alibaba") {
+        Some("alibaba_cloud")
+    } else {
+        None
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_detectors() {
+        assert_eq!("host".parse::<ResourceDetector>(), Ok(ResourceDetector::Host));
+        assert_eq!(" Kubernetes".parse::<ResourceDetector>(), Ok(ResourceDetector::K8s));
+        assert_eq!("cloud".parse::<ResourceDetector>(), Ok(ResourceDetector::Cloud));
+        assert!("gpu".parse::<ResourceDetector>().is_err());
+    }
+
+    #[test]
+    fn test_cloud_provider_from_dmi_vendor() {
+        assert_eq!(cloud_provider("Amazon EC2\n"), Some("aws"));
+        assert_eq!(cloud_provider("Google"), Some("gcp"));
+        assert_eq!(cloud_provider("Microsoft Corporation"), Some("azure"));
+        assert_eq!(cloud_provider("Dell Inc."), None);
+    }
+
+    #[test]
+    fn test_detectors_follow_config() {
+        let config = |detectors: &[&str]| OtelConfig {
+            resource_detectors: Some(detectors.iter().map(|d| d.to_string()).collect()),
+            ..OtelConfig::default()
+        };
+
+        assert!(detect_resource_attributes(&config(&[])).is_empty());
+
+        let host = detect_resource_attributes(&config(&["host"]));
+        assert!(host.iter().any(|kv| kv.key.as_str() == OS_TYPE));
+        assert!(host.iter().any(|kv| kv.key.as_str() == HOST_ARCH));
+    }
+}
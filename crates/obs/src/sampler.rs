@@ -0,0 +1,185 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::OtelConfig;
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceContextExt, TraceId};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+use rustfs_config::SAMPLE_RATIO;
+use rustfs_config::observability::DEFAULT_OBS_SAMPLE_PARENT_BASED;
+
+/// Span attribute carrying the module path, set by `tracing-opentelemetry`
+const CODE_NAMESPACE: &str = "code.namespace";
+/// Prefix of workspace crates, optional in always-sample targets
+const CRATE_PREFIX: &str = "rustfs_";
+
+/// Build the trace sampler described by the sampling options of `config`
+///
+/// Spans matching `sample_always_targets` are always recorded; all others go through the
+/// head-based ratio sampler, wrapped in a parent-based sampler unless disabled. As before the
+/// sampler existed, a ratio outside of `(0, 1)`, zero included, samples every trace.
+pub(crate) fn build_sampler(config: &OtelConfig) -> TargetSampler {
+    let sample_ratio = config.sample_ratio.unwrap_or(SAMPLE_RATIO);
+    let ratio_sampler = if sample_ratio > 0.0 && sample_ratio < 1.0 {
+        Sampler::TraceIdRatioBased(sample_ratio)
+    } else {
+        Sampler::AlwaysOn
+    };
+
+    let fallback = if config.sample_parent_based.unwrap_or(DEFAULT_OBS_SAMPLE_PARENT_BASED) {
+        Sampler::ParentBased(Box::new(ratio_sampler))
+    } else {
+        ratio_sampler
+    };
+
+    TargetSampler {
+        always_targets: config.sample_always_targets.clone().unwrap_or_default(),
+        fallback,
+    }
+}
+
+/// Sampler that always records spans of selected targets and defers the rest to `fallback`
+///
+/// A target matches a span when it names the span, or is a module path prefix of the span's
+/// `code.namespace`; the `rustfs_` crate prefix may be omitted, so `lock` matches `rustfs_lock::*`.
+#[derive(Clone, Debug)]
+pub(crate) struct TargetSampler {
+    always_targets: Vec<String>,
+    fallback: Sampler,
+}
+
+impl TargetSampler {
+    fn is_always_sampled(&self, name: &str, attributes: &[KeyValue]) -> bool {
+        if self.always_targets.is_empty() {
+            return false;
+        }
+
+        let namespace = attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == CODE_NAMESPACE)
+            .map(|kv| kv.value.as_str());
+
+        self.always_targets.iter().any(|target| {
+            if target == name {
+                return true;
+            }
+            let Some(namespace) = namespace.as_deref() else {
+                return false;
+            };
+            let namespace = namespace.strip_prefix(CRATE_PREFIX).unwrap_or(namespace);
+            let target = target.strip_prefix(CRATE_PREFIX).unwrap_or(target);
+            namespace == target || namespace.strip_prefix(target).is_some_and(|rest| rest.starts_with("::"))
+        })
+    }
+}
+
+impl ShouldSample for TargetSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        if self.is_always_sampled(name, attributes) {
+            return SamplingResult {
+                decision: SamplingDecision::RecordAndSample,
+                attributes: Vec::new(),
+                trace_state: parent_context
+                    .map(|cx| cx.span().span_context().trace_state().clone())
+                    .unwrap_or_default(),
+            };
+        }
+
+        self.fallback
+            .should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(ratio: f64, targets: &[&str], parent_based: bool) -> OtelConfig {
+        OtelConfig {
+            sample_ratio: Some(ratio),
+            sample_always_targets: Some(targets.iter().map(|t| t.to_string()).collect()),
+            sample_parent_based: Some(parent_based),
+            ..OtelConfig::default()
+        }
+    }
+
+    fn decide(sampler: &TargetSampler, parent: Option<&Context>, name: &str, namespace: &str) -> SamplingDecision {
+        sampler
+            .should_sample(
+                parent,
+                // Above any ratio below 1
+                TraceId::from_bytes(u128::MAX.to_be_bytes()),
+                name,
+                &SpanKind::Internal,
+                &[KeyValue::new(CODE_NAMESPACE, namespace.to_string())],
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn test_always_sample_targets_bypass_ratio() {
+        let sampler = build_sampler(&config(0.5, &["lock", "rustfs_ecstore::set_disk"], false));
+
+        assert_eq!(
+            decide(&sampler, None, "acquire", "rustfs_lock::namespace_lock"),
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(
+            decide(&sampler, None, "put", "rustfs_ecstore::set_disk"),
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(decide(&sampler, None, "get", "rustfs_ecstore::store"), SamplingDecision::Drop);
+        assert_eq!(decide(&sampler, None, "get", "rustfs_locks::client"), SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn test_parent_based_follows_parent_decision() {
+        use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceState};
+
+        let sampled_parent = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_bytes(42u128.to_be_bytes()),
+            SpanId::from_bytes(7u64.to_be_bytes()),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        ));
+
+        let parent_based = build_sampler(&config(0.5, &[], true));
+        assert_eq!(
+            decide(&parent_based, Some(&sampled_parent), "get", "rustfs::storage"),
+            SamplingDecision::RecordAndSample
+        );
+
+        let head_only = build_sampler(&config(0.5, &[], false));
+        assert_eq!(
+            decide(&head_only, Some(&sampled_parent), "get", "rustfs::storage"),
+            SamplingDecision::Drop
+        );
+    }
+
+    #[test]
+    fn test_zero_ratio_samples_everything() {
+        let sampler = build_sampler(&config(0.0, &[], false));
+        assert_eq!(decide(&sampler, None, "get", "rustfs::storage"), SamplingDecision::RecordAndSample);
+    }
+}
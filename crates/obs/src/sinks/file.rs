@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::sinks::Sink;
-use crate::{LogRecord, UnifiedLogEntry};
+use crate::{LogRecord, SinkError, SinkErrorKind, UnifiedLogEntry};
 use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::fs::OpenOptions;
@@ -121,8 +121,11 @@ impl FileSink {
 
 #[async_trait]
 impl Sink for FileSink {
-    async fn write(&self, entry: &UnifiedLogEntry) {
-        let line = format!("{entry:?}\n");
+    async fn write(&self, entry: &UnifiedLogEntry) -> Result<(), SinkError> {
+        // One JSON entry per line, so the file can be replayed into another sink
+        let mut line =
+            serde_json::to_string(entry).map_err(|e| SinkError::new("file", SinkErrorKind::Rejected, e.to_string()))?;
+        line.push('\n');
         let mut writer = self.writer.lock().await;
 
         if let Err(e) = writer.write_all(line.as_bytes()).await {
@@ -132,7 +135,7 @@ impl Sink for FileSink {
                 e,
                 entry.get_timestamp()
             );
-            return;
+            return Err(SinkError::from_io("file", &e));
         }
 
         // Only flush periodically to improve performance
@@ -144,7 +147,7 @@ impl Sink for FileSink {
         if self.should_flush() {
             if let Err(e) = writer.flush().await {
                 eprintln!("Failed to flush log file {}: {}", self.path, e);
-                return;
+                return Err(SinkError::from_io("file", &e));
             }
 
             // Reset counters
@@ -157,6 +160,8 @@ impl Sink for FileSink {
 
             self.last_flush.store(now, std::sync::atomic::Ordering::Relaxed);
         }
+
+        Ok(())
     }
 }
 
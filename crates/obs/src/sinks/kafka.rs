@@ -13,9 +13,10 @@
 // limitations under the License.
 
 use crate::sinks::Sink;
-use crate::{LogRecord, UnifiedLogEntry};
+use crate::{LogRecord, SinkError, SinkErrorKind, UnifiedLogEntry};
 use async_trait::async_trait;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Kafka Sink Implementation
 pub struct KafkaSink {
@@ -25,6 +26,8 @@ pub struct KafkaSink {
     batch_timeout_ms: u64,
     entries: Arc<tokio::sync::Mutex<Vec<UnifiedLogEntry>>>,
     last_flush: Arc<std::sync::atomic::AtomicU64>,
+    // Deliveries failed in background batches, reported on the next write
+    failed: Arc<AtomicU64>,
 }
 
 impl KafkaSink {
@@ -38,6 +41,7 @@ impl KafkaSink {
                 .unwrap()
                 .as_millis() as u64,
         ));
+        let failed = Arc::new(AtomicU64::new(0));
         let sink = KafkaSink {
             producer: producer.clone(),
             topic: topic.clone(),
@@ -45,10 +49,11 @@ impl KafkaSink {
             batch_timeout_ms,
             entries: entries.clone(),
             last_flush: last_flush.clone(),
+            failed: failed.clone(),
         };
 
         // Start background flusher
-        tokio::spawn(Self::periodic_flush(producer, topic, entries, last_flush, batch_timeout_ms));
+        tokio::spawn(Self::periodic_flush(producer, topic, entries, last_flush, failed, batch_timeout_ms));
 
         sink
     }
@@ -70,6 +75,7 @@ impl KafkaSink {
         topic: String,
         entries: Arc<tokio::sync::Mutex<Vec<UnifiedLogEntry>>>,
         last_flush: Arc<std::sync::atomic::AtomicU64>,
+        failed: Arc<AtomicU64>,
         timeout_ms: u64,
     ) {
         loop {
@@ -85,38 +91,48 @@ impl KafkaSink {
             if now - last >= timeout_ms {
                 let mut batch = entries.lock().await;
                 if !batch.is_empty() {
-                    Self::send_batch(&producer, &topic, batch.drain(..).collect()).await;
+                    Self::send_batch(&producer, &topic, batch.drain(..).collect(), &failed).await;
                     last_flush.store(now, std::sync::atomic::Ordering::Relaxed);
                 }
             }
         }
     }
 
-    async fn send_batch(producer: &rdkafka::producer::FutureProducer, topic: &str, entries: Vec<UnifiedLogEntry>) {
+    async fn send_batch(
+        producer: &rdkafka::producer::FutureProducer,
+        topic: &str,
+        entries: Vec<UnifiedLogEntry>,
+        failed: &AtomicU64,
+    ) {
         for entry in entries {
             let payload = match serde_json::to_string(&entry) {
                 Ok(p) => p,
                 Err(e) => {
                     eprintln!("Failed to serialize log entry: {e}");
+                    failed.fetch_add(1, Ordering::Relaxed);
                     continue;
                 }
             };
 
             let span_id = entry.get_timestamp().to_rfc3339();
 
-            let _ = producer
+            if producer
                 .send(
                     rdkafka::producer::FutureRecord::to(topic).payload(&payload).key(&span_id),
                     std::time::Duration::from_secs(5),
                 )
-                .await;
+                .await
+                .is_err()
+            {
+                failed.fetch_add(1, Ordering::Relaxed);
+            }
         }
     }
 }
 
 #[async_trait]
 impl Sink for KafkaSink {
-    async fn write(&self, entry: &UnifiedLogEntry) {
+    async fn write(&self, entry: &UnifiedLogEntry) -> Result<(), SinkError> {
         let mut batch = self.entries.lock().await;
         batch.push(entry.clone());
 
@@ -135,6 +151,7 @@ impl Sink for KafkaSink {
             let entries_to_send: Vec<UnifiedLogEntry> = batch.drain(..).collect();
             let producer = self.producer.clone();
             let topic = self.topic.clone();
+            let failed = self.failed.clone();
 
             self.last_flush.store(
                 std::time::SystemTime::now()
@@ -145,9 +162,20 @@ impl Sink for KafkaSink {
             );
 
             tokio::spawn(async move {
-                KafkaSink::send_batch(&producer, &topic, entries_to_send).await;
+                KafkaSink::send_batch(&producer, &topic, entries_to_send, &failed).await;
             });
         }
+
+        let failed = self.failed.swap(0, Ordering::Relaxed);
+        if failed > 0 {
+            return Err(SinkError::new(
+                "kafka",
+                SinkErrorKind::Unavailable,
+                format!("{failed} entries of earlier batches were not delivered"),
+            )
+            .with_entries(failed));
+        }
+        Ok(())
     }
 }
 
@@ -159,11 +187,12 @@ impl Drop for KafkaSink {
         let topic = self.topic.clone();
         let entries = self.entries.clone();
         let last_flush = self.last_flush.clone();
+        let failed = self.failed.clone();
 
         tokio::spawn(async move {
             let mut batch = entries.lock().await;
             if !batch.is_empty() {
-                KafkaSink::send_batch(&producer, &topic, batch.drain(..).collect()).await;
+                KafkaSink::send_batch(&producer, &topic, batch.drain(..).collect(), &failed).await;
                 last_flush.store(
                     std::time::SystemTime::now()
                         .duration_since(std::time::UNIX_EPOCH)
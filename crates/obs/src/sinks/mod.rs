@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{AppConfig, SinkConfig, UnifiedLogEntry};
+use crate::{AppConfig, SinkConfig, SinkError, UnifiedLogEntry};
 use async_trait::async_trait;
 use std::sync::Arc;
 
@@ -24,9 +24,11 @@ mod kafka;
 mod webhook;
 
 /// Sink Trait definition, asynchronously write logs
+/// An error means the entry was lost, the worker counts it as a sink failure and keeps its kind
+/// so callers can tell a transient outage from a sink that needs reconfiguring.
 #[async_trait]
 pub trait Sink: Send + Sync {
-    async fn write(&self, entry: &UnifiedLogEntry);
+    async fn write(&self, entry: &UnifiedLogEntry) -> Result<(), SinkError>;
 }
 
 /// Create a list of Sink instances
@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::UnifiedLogEntry;
 use crate::sinks::Sink;
+use crate::{SinkError, SinkErrorKind, UnifiedLogEntry};
 use async_trait::async_trait;
 
 /// Webhook Sink Implementation
@@ -42,11 +42,13 @@ impl WebhookSink {
 
 #[async_trait]
 impl Sink for WebhookSink {
-    async fn write(&self, entry: &UnifiedLogEntry) {
+    async fn write(&self, entry: &UnifiedLogEntry) -> Result<(), SinkError> {
         let mut retries = 0;
         let url = self.endpoint.clone();
         let entry_clone = entry.clone();
-        let auth_value = reqwest::header::HeaderValue::from_str(format!("Bearer {}", self.auth_token.clone()).as_str()).unwrap();
+        let auth_value = reqwest::header::HeaderValue::from_str(format!("Bearer {}", self.auth_token.clone()).as_str())
+            .map_err(|e| SinkError::new("webhook", SinkErrorKind::Misconfigured, format!("invalid auth token: {e}")))?;
+        let mut last_error = SinkError::new("webhook", SinkErrorKind::Unavailable, "no delivery attempted");
         while retries < self.max_retries {
             match self
                 .client
@@ -57,9 +59,34 @@ impl Sink for WebhookSink {
                 .await
             {
                 Ok(response) if response.status().is_success() => {
-                    return;
+                    return Ok(());
                 }
-                _ => {
+                // Client errors other than timeouts and throttling won't go away by retrying
+                Ok(response)
+                    if response.status().is_client_error()
+                        && response.status() != reqwest::StatusCode::REQUEST_TIMEOUT
+                        && response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    eprintln!("Webhook rejected log entry with status {}", response.status());
+                    return Err(SinkError::new(
+                        "webhook",
+                        SinkErrorKind::Rejected,
+                        format!("endpoint responded {}", response.status()),
+                    ));
+                }
+                result => {
+                    last_error = match result {
+                        Ok(response) => SinkError::new(
+                            "webhook",
+                            SinkErrorKind::Unavailable,
+                            format!("endpoint responded {}", response.status()),
+                        ),
+                        Err(e) if e.is_builder() => SinkError::new("webhook", SinkErrorKind::Misconfigured, e.to_string()),
+                        Err(e) => SinkError::new("webhook", SinkErrorKind::Unavailable, e.to_string()),
+                    };
+                    if !last_error.is_retryable() {
+                        break;
+                    }
                     retries += 1;
                     if retries < self.max_retries {
                         tokio::time::sleep(tokio::time::Duration::from_millis(
@@ -71,7 +98,8 @@ impl Sink for WebhookSink {
             }
         }
 
-        eprintln!("Failed to send log to webhook after {0} retries", self.max_retries);
+        eprintln!("Failed to send log to webhook after {0} retries: {1}", retries, last_error);
+        Err(last_error)
     }
 }
 
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use crate::OtelConfig;
+use crate::resource::detect_resource_attributes;
+use crate::sampler::build_sampler;
 use flexi_logger::{Age, Cleanup, Criterion, DeferredNow, FileSpec, LogSpecification, Naming, Record, WriteMode, style};
 use nu_ansi_term::Color;
 use opentelemetry::trace::TracerProvider;
@@ -23,7 +25,7 @@ use opentelemetry_sdk::logs::SdkLoggerProvider;
 use opentelemetry_sdk::{
     Resource,
     metrics::{MeterProviderBuilder, PeriodicReader, SdkMeterProvider},
-    trace::{RandomIdGenerator, Sampler, SdkTracerProvider},
+    trace::{RandomIdGenerator, SdkTracerProvider},
 };
 use opentelemetry_semantic_conventions::{
     SCHEMA_URL,
@@ -31,7 +33,7 @@ use opentelemetry_semantic_conventions::{
 };
 use rustfs_config::observability::ENV_OBS_LOG_DIRECTORY;
 use rustfs_config::{
-    APP_NAME, DEFAULT_LOG_KEEP_FILES, DEFAULT_LOG_LEVEL, ENVIRONMENT, METER_INTERVAL, SAMPLE_RATIO, SERVICE_VERSION, USE_STDOUT,
+    APP_NAME, DEFAULT_LOG_KEEP_FILES, DEFAULT_LOG_LEVEL, ENVIRONMENT, METER_INTERVAL, SERVICE_VERSION, USE_STDOUT,
 };
 use rustfs_utils::get_local_ip_with_default;
 use smallvec::SmallVec;
@@ -76,8 +78,11 @@ impl std::fmt::Debug for OtelGuard {
     }
 }
 
-impl Drop for OtelGuard {
-    fn drop(&mut self) {
+impl OtelGuard {
+    /// Flush and shut down the providers, later calls do nothing
+    ///
+    /// The global guard lives in a static and is never dropped, so the server calls this on shutdown.
+    pub fn shutdown(&mut self) {
         if let Some(provider) = self.tracer_provider.take() {
             if let Err(err) = provider.shutdown() {
                 eprintln!("Tracer shutdown error: {err:?}");
@@ -97,6 +102,12 @@ impl Drop for OtelGuard {
     }
 }
 
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
 /// create OpenTelemetry Resource
 fn resource(config: &OtelConfig) -> Resource {
     Resource::builder()
@@ -112,7 +123,9 @@ fn resource(config: &OtelConfig) -> Resource {
                     Cow::Borrowed(config.environment.as_deref().unwrap_or(ENVIRONMENT)).to_string(),
                 ),
                 KeyValue::new(NETWORK_LOCAL_ADDRESS, get_local_ip_with_default()),
-            ],
+            ]
+            .into_iter()
+            .chain(detect_resource_attributes(config)),
             SCHEMA_URL,
         )
         .build()
@@ -143,15 +156,8 @@ pub(crate) fn init_telemetry(config: &OtelConfig) -> OtelGuard {
 
         // initialize tracer provider
         let tracer_provider = {
-            let sample_ratio = config.sample_ratio.unwrap_or(SAMPLE_RATIO);
-            let sampler = if sample_ratio > 0.0 && sample_ratio < 1.0 {
-                Sampler::TraceIdRatioBased(sample_ratio)
-            } else {
-                Sampler::AlwaysOn
-            };
-
             let builder = SdkTracerProvider::builder()
-                .with_sampler(sampler)
+                .with_sampler(build_sampler(config))
                 .with_id_generator(RandomIdGenerator::default())
                 .with_resource(res.clone());
 
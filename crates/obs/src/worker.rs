@@ -12,16 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::logger::LoggerCounters;
 use crate::{UnifiedLogEntry, sinks::Sink};
 use std::sync::Arc;
 use tokio::sync::mpsc::Receiver;
+use tokio::sync::watch;
 
 /// Start the log processing worker thread
-pub(crate) async fn start_worker(receiver: Receiver<UnifiedLogEntry>, sinks: Vec<Arc<dyn Sink>>) {
+/// Sink changes published on `sinks` are picked up between entries, so nothing queued is lost on reload.
+pub(crate) async fn start_worker(
+    receiver: Receiver<UnifiedLogEntry>,
+    mut sinks: watch::Receiver<Vec<Arc<dyn Sink>>>,
+    counters: Arc<LoggerCounters>,
+) {
     let mut receiver = receiver;
+    let mut current = sinks.borrow_and_update().clone();
     while let Some(entry) = receiver.recv().await {
-        for sink in &sinks {
-            sink.write(&entry).await;
+        if sinks.has_changed().unwrap_or(false) {
+            current = sinks.borrow_and_update().clone();
+        }
+        for sink in &current {
+            if let Err(e) = sink.write(&entry).await {
+                counters.record_sink_failure(&e);
+            }
         }
     }
 }
@@ -180,10 +180,18 @@ pub enum S3Action {
     PutBucketNotificationAction,
     #[strum(serialize = "s3:PutBucketPolicy")]
     PutBucketPolicyAction,
+    #[strum(serialize = "s3:GetBucketAcl")]
+    GetBucketAclAction,
+    #[strum(serialize = "s3:PutBucketAcl")]
+    PutBucketAclAction,
     #[strum(serialize = "s3:PutBucketCors")]
     PutBucketCorsAction,
     #[strum(serialize = "s3:PutObject")]
     PutObjectAction,
+    #[strum(serialize = "s3:GetObjectAcl")]
+    GetObjectAclAction,
+    #[strum(serialize = "s3:PutObjectAcl")]
+    PutObjectAclAction,
     #[strum(serialize = "s3:DeleteObjectVersion")]
     DeleteObjectVersionAction,
     #[strum(serialize = "s3:DeleteObjectVersionTagging")]
@@ -224,6 +232,16 @@ pub enum S3Action {
     PutBucketEncryptionAction,
     #[strum(serialize = "s3:GetBucketEncryption")]
     GetBucketEncryptionAction,
+    #[strum(serialize = "s3:PutBucketPublicAccessBlock")]
+    PutBucketPublicAccessBlockAction,
+    #[strum(serialize = "s3:GetBucketPublicAccessBlock")]
+    GetBucketPublicAccessBlockAction,
+    #[strum(serialize = "s3:PutBucketWebsite")]
+    PutBucketWebsiteAction,
+    #[strum(serialize = "s3:GetBucketWebsite")]
+    GetBucketWebsiteAction,
+    #[strum(serialize = "s3:DeleteBucketWebsite")]
+    DeleteBucketWebsiteAction,
     #[strum(serialize = "s3:PutBucketVersioning")]
     PutBucketVersioningAction,
     #[strum(serialize = "s3:GetBucketVersioning")]
@@ -363,6 +381,8 @@ pub enum AdminAction {
     ListServiceAccountsAdminAction,
     #[strum(serialize = "admin:ListTemporaryAccounts")]
     ListTemporaryAccountsAdminAction,
+    #[strum(serialize = "admin:RevokeTemporaryAccounts")]
+    RevokeTemporaryAccountsAdminAction,
     #[strum(serialize = "admin:AddUserToGroup")]
     AddUserToGroupAdminAction,
     #[strum(serialize = "admin:RemoveUserFromGroup")]
@@ -466,6 +486,7 @@ impl AdminAction {
                 | AdminAction::RemoveServiceAccountAdminAction
                 | AdminAction::ListServiceAccountsAdminAction
                 | AdminAction::ListTemporaryAccountsAdminAction
+                | AdminAction::RevokeTemporaryAccountsAdminAction
                 | AdminAction::AddUserToGroupAdminAction
                 | AdminAction::RemoveUserFromGroupAdminAction
                 | AdminAction::GetGroupAdminAction
@@ -65,11 +65,13 @@ impl Condition {
             "NumericEquals" => Self::NumericEquals(d.next_value()?),
             "NumericNotEquals" => Self::NumericNotEquals(d.next_value()?),
             "NumericLessThan" => Self::NumericLessThan(d.next_value()?),
+            "NumericLessThanEquals" => Self::NumericLessThanEquals(d.next_value()?),
             "NumericGreaterThan" => Self::NumericGreaterThan(d.next_value()?),
             "NumericGreaterThanIfExists" => Self::NumericGreaterThanIfExists(d.next_value()?),
             "NumericGreaterThanEquals" => Self::NumericGreaterThanEquals(d.next_value()?),
             "DateEquals" => Self::DateEquals(d.next_value()?),
             "DateNotEquals" => Self::DateNotEquals(d.next_value()?),
+            "DateLessThan" => Self::DateLessThan(d.next_value()?),
             "DateLessThanEquals" => Self::DateLessThanEquals(d.next_value()?),
             "DateGreaterThan" => Self::DateGreaterThan(d.next_value()?),
             "DateGreaterThanEquals" => Self::DateGreaterThanEquals(d.next_value()?),
@@ -126,7 +128,7 @@ impl Condition {
             NumericLessThan(s) => s.evaluate(i64::lt, false, values),
             NumericLessThanEquals(s) => s.evaluate(i64::le, false, values),
             NumericGreaterThan(s) => s.evaluate(i64::gt, false, values),
-            NumericGreaterThanIfExists(s) => s.evaluate(i64::ge, true, values),
+            NumericGreaterThanIfExists(s) => s.evaluate(i64::gt, true, values),
             NumericGreaterThanEquals(s) => s.evaluate(i64::ge, false, values),
             DateEquals(s) => s.evaluate(OffsetDateTime::eq, values),
             DateNotEquals(s) => s.evaluate(OffsetDateTime::ne, values),
@@ -176,6 +176,8 @@ pub struct BucketPolicyArgs<'a> {
     pub conditions: &'a HashMap<String, Vec<String>>,
     pub is_owner: bool,
     pub object: &'a str,
+    /// Only the `Deny` statements are evaluated, the request is allowed unless one of them matches
+    pub deny_only: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
@@ -196,7 +198,7 @@ impl BucketPolicy {
             }
         }
 
-        if args.is_owner {
+        if args.is_owner || args.deny_only {
             return true;
         }
 
@@ -210,6 +212,25 @@ impl BucketPolicy {
     }
 }
 
+impl BucketPolicy {
+    /// Checks the policy is valid and only grants access to `bucket` and its objects
+    pub fn validate(&self, bucket: &str) -> Result<()> {
+        self.is_valid()?;
+
+        for statement in self.statements.iter() {
+            statement.resources.validate_bucket(bucket)?;
+            statement.not_resources.validate_bucket(bucket)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether one of the statements allows everyone in, see [`BPStatement::is_public`]
+    pub fn is_public(&self) -> bool {
+        self.statements.iter().any(BPStatement::is_public)
+    }
+}
+
 impl Validator for BucketPolicy {
     type Error = Error;
 
@@ -464,6 +485,7 @@ pub mod default {
 mod test {
     use super::*;
     use crate::error::Result;
+    use crate::policy::action::S3Action;
 
     #[tokio::test]
     async fn test_parse_policy() -> Result<()> {
@@ -531,4 +553,123 @@ mod test {
         // assert_eq!(p, p2);
         Ok(())
     }
+
+    #[test]
+    fn test_bucket_policy_is_public() -> Result<()> {
+        let data = r#"
+{
+  "Version": "2012-10-17",
+  "Statement": [
+    {
+      "Effect": "Deny",
+      "Principal": {"AWS": ["*"]},
+      "Action": ["s3:DeleteObject"],
+      "Resource": ["arn:aws:s3:::mybucket/*"]
+    },
+    {
+      "Effect": "Allow",
+      "Principal": {"AWS": ["*"]},
+      "Action": ["s3:GetObject"],
+      "Resource": ["arn:aws:s3:::mybucket/*"]
+    }
+  ]
+}
+"#;
+        let mut policy: BucketPolicy = serde_json::from_str(data)?;
+        assert!(policy.is_public());
+
+        policy.statements.pop();
+        assert!(!policy.is_public());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucket_policy_conditions() -> Result<()> {
+        let data = r#"
+{
+  "Version": "2012-10-17",
+  "Statement": [
+    {
+      "Effect": "Allow",
+      "Principal": {"AWS": ["*"]},
+      "Action": ["s3:ListBucket"],
+      "Resource": ["arn:aws:s3:::mybucket"],
+      "Condition": {
+        "StringLike": {"s3:prefix": ["public/*"]},
+        "NumericLessThanEquals": {"s3:max-keys": "100"}
+      }
+    },
+    {
+      "Effect": "Allow",
+      "Principal": {"AWS": ["alice"]},
+      "Action": ["s3:GetObject"],
+      "Resource": ["arn:aws:s3:::mybucket/*"]
+    },
+    {
+      "Effect": "Deny",
+      "Principal": {"AWS": ["*"]},
+      "Action": ["s3:*"],
+      "Resource": ["arn:aws:s3:::mybucket", "arn:aws:s3:::mybucket/*"],
+      "Condition": {
+        "Bool": {"aws:SecureTransport": ["false"]}
+      }
+    },
+    {
+      "Effect": "Deny",
+      "Principal": {"AWS": ["*"]},
+      "Action": ["s3:GetObject"],
+      "Resource": ["arn:aws:s3:::mybucket/*"],
+      "Condition": {
+        "NotIpAddress": {"aws:SourceIp": ["192.168.0.0/16"]}
+      }
+    }
+  ]
+}
+"#;
+        let policy: BucketPolicy = serde_json::from_str(data)?;
+        policy.validate("mybucket")?;
+        assert!(policy.validate("otherbucket").is_err());
+        // The statement for everyone is conditional, the others are not for everyone
+        assert!(!policy.is_public());
+
+        let conditions = |pairs: &[(&str, &str)]| -> HashMap<String, Vec<String>> {
+            pairs.iter().map(|(k, v)| (k.to_string(), vec![v.to_string()])).collect()
+        };
+        let is_allowed = |account: &str, action: S3Action, object: &str, conditions: &HashMap<String, Vec<String>>, deny_only| {
+            policy.is_allowed(&BucketPolicyArgs {
+                account,
+                groups: &None,
+                action: Action::S3Action(action),
+                bucket: "mybucket",
+                conditions,
+                is_owner: false,
+                object,
+                deny_only,
+            })
+        };
+
+        let secure_inside = conditions(&[("SecureTransport", "true"), ("SourceIp", "192.168.1.7")]);
+        let secure_outside = conditions(&[("SecureTransport", "true"), ("SourceIp", "10.0.0.1")]);
+        let insecure = conditions(&[("SecureTransport", "false"), ("SourceIp", "192.168.1.7")]);
+
+        assert!(is_allowed("alice", S3Action::GetObjectAction, "a.txt", &secure_inside, false));
+        assert!(!is_allowed("bob", S3Action::GetObjectAction, "a.txt", &secure_inside, false));
+        // An explicit deny wins over the statement allowing alice
+        assert!(!is_allowed("alice", S3Action::GetObjectAction, "a.txt", &secure_outside, false));
+        assert!(!is_allowed("alice", S3Action::GetObjectAction, "a.txt", &insecure, false));
+
+        // Only the deny statements count when checking for an explicit deny
+        assert!(is_allowed("bob", S3Action::GetObjectAction, "a.txt", &secure_inside, true));
+        assert!(!is_allowed("bob", S3Action::GetObjectAction, "a.txt", &insecure, true));
+
+        let mut list = conditions(&[("SecureTransport", "true"), ("prefix", "public/docs"), ("max-keys", "50")]);
+        assert!(is_allowed("", S3Action::ListBucketAction, "", &list, false));
+        list.insert("prefix".to_string(), vec!["private/".to_string()]);
+        assert!(!is_allowed("", S3Action::ListBucketAction, "", &list, false));
+        list.insert("prefix".to_string(), vec!["public/".to_string()]);
+        list.insert("max-keys".to_string(), vec!["1000".to_string()]);
+        assert!(!is_allowed("", S3Action::ListBucketAction, "", &list, false));
+
+        Ok(())
+    }
 }
@@ -33,6 +33,11 @@ impl Principal {
         }
         false
     }
+
+    /// Whether the principal is everyone, anonymous callers included
+    pub fn is_wildcard(&self) -> bool {
+        self.aws.contains("*")
+    }
 }
 
 impl Validator for Principal {
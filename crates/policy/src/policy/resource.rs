@@ -49,6 +49,15 @@ impl ResourceSet {
 
         false
     }
+
+    /// Checks every resource of the set is `bucket` or an object of it
+    pub fn validate_bucket(&self, bucket: &str) -> Result<()> {
+        for resource in self.0.iter() {
+            resource.validate_bucket(bucket)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Deref for ResourceSet {
@@ -111,6 +120,19 @@ impl Resource {
     pub fn match_resource(&self, resource: &str) -> bool {
         self.is_match(resource, &HashMap::new())
     }
+
+    /// Checks the resource is `bucket` or an object of it, as the resources of a bucket policy must be
+    pub fn validate_bucket(&self, bucket: &str) -> Result<()> {
+        let Resource::S3(pattern) = self else {
+            return Err(IamError::InvalidResource("kms".into(), bucket.into()).into());
+        };
+
+        if !wildcard::is_match(pattern, bucket) && !wildcard::is_match_as_pattern_prefix(pattern, format!("{bucket}/")) {
+            return Err(IamError::InvalidResource("s3".into(), pattern.into()).into());
+        }
+
+        Ok(())
+    }
 }
 
 impl TryFrom<&str> for Resource {
@@ -199,4 +221,16 @@ mod tests {
         let resource: Resource = resource.try_into().unwrap();
         resource.is_match(object, &HashMap::new())
     }
+
+    #[test_case("arn:aws:s3:::mybucket","mybucket" => true; "1")]
+    #[test_case("arn:aws:s3:::mybucket/*","mybucket" => true; "2")]
+    #[test_case("arn:aws:s3:::mybucket/photos/*","mybucket" => true; "3")]
+    #[test_case("arn:aws:s3:::*","mybucket" => true; "4")]
+    #[test_case("arn:aws:s3:::my*/*","mybucket" => true; "5")]
+    #[test_case("arn:aws:s3:::otherbucket/*","mybucket" => false; "6")]
+    #[test_case("arn:aws:s3:::mybucket10/*","mybucket" => false; "7")]
+    fn test_resource_validate_bucket(resource: &str, bucket: &str) -> bool {
+        let resource: Resource = resource.try_into().unwrap();
+        resource.validate_bucket(bucket).is_ok()
+    }
 }
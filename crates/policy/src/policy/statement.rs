@@ -189,6 +189,13 @@ impl BPStatement {
 
         self.effect.is_allowed(check)
     }
+
+    /// Whether the statement allows everyone in, whatever the request
+    ///
+    /// A statement with conditions is not public, as they may restrict it to known callers.
+    pub fn is_public(&self) -> bool {
+        self.effect == Effect::Allow && self.principal.is_wildcard() && self.conditions.is_empty()
+    }
 }
 
 impl Validator for BPStatement {
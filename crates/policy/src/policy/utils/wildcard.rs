@@ -29,7 +29,6 @@ where
     inner_match(pattern, name, false)
 }
 
-#[allow(dead_code)]
 pub fn is_match_as_pattern_prefix<P, N>(pattern: P, text: N) -> bool
 where
     P: AsRef<str>,
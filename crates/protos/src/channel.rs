@@ -0,0 +1,311 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pooled gRPC channels to peer nodes
+//!
+//! All RPCs to a peer share one channel. The channel keeps its connection alive with HTTP/2 pings
+//! and reconnects by itself once the connection drops. A peer whose requests keep failing at the
+//! transport level has its circuit opened: RPCs to it fail right away instead of each waiting out
+//! the connect timeout, and once the backoff has passed a single request probes the peer again.
+//! After a node restarts it gets one reconnect per peer, not one per waiting RPC.
+//!
+//! With node TLS enabled, channels to `https` peers are dialed with the node certificates as
+//! they are at the time of each connect, so reconnects pick up rotated certificates.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use std::{fmt, io};
+
+use hyper_util::rt::TokioIo;
+use rustfs_utils::node_tls::node_tls;
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tonic::body::Body;
+use tonic::codegen::{BoxFuture, Context, Poll, Service, StdError, http};
+use tonic::transport::{Channel, Endpoint, Uri};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+const KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// Consecutive transport failures opening the circuit of a peer
+const FAILURE_THRESHOLD: u32 = 3;
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+static PEERS: LazyLock<Mutex<HashMap<String, Arc<Peer>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Returned instead of a channel while the circuit of a peer is open
+#[derive(Debug)]
+pub struct CircuitOpen {
+    pub addr: String,
+    pub retry_in: Duration,
+}
+
+impl fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "peer {} is unreachable, retrying in {:?}", self.addr, self.retry_in)
+    }
+}
+
+impl Error for CircuitOpen {}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl Breaker {
+    /// Whether a request may go out at `now`, or how long until one may
+    fn admit(&mut self, now: Instant) -> Result<(), Duration> {
+        match self.open_until {
+            None => Ok(()),
+            Some(until) if now < until => Err(until - now),
+            Some(_) => {
+                // Let this request probe the peer, the others keep failing fast until it reports back
+                self.open_until = Some(now + CONNECT_TIMEOUT);
+                Ok(())
+            }
+        }
+    }
+
+    fn success(&mut self) {
+        self.failures = 0;
+        self.open_until = None;
+    }
+
+    fn failure(&mut self, now: Instant) {
+        self.failures = self.failures.saturating_add(1);
+        if self.failures >= FAILURE_THRESHOLD {
+            let backoff = MIN_BACKOFF
+                .saturating_mul(1 << (self.failures - FAILURE_THRESHOLD).min(16))
+                .min(MAX_BACKOFF);
+            self.open_until = Some(now + backoff);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Peer {
+    #[cfg(feature = "fault-injection")]
+    addr: String,
+    channel: Channel,
+    breaker: Mutex<Breaker>,
+}
+
+impl Peer {
+    fn success(&self) {
+        self.breaker.lock().unwrap_or_else(|e| e.into_inner()).success();
+    }
+
+    fn failure(&self) {
+        self.breaker.lock().unwrap_or_else(|e| e.into_inner()).failure(Instant::now());
+    }
+}
+
+/// Channel to a peer, reporting the outcome of every request to the peer's circuit
+#[derive(Debug, Clone)]
+pub struct PeerChannel {
+    channel: Channel,
+    peer: Arc<Peer>,
+}
+
+impl Service<http::Request<Body>> for PeerChannel {
+    type Response = http::Response<Body>;
+    type Error = StdError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let ready = self.channel.poll_ready(cx);
+        if let Poll::Ready(Err(_)) = &ready {
+            self.peer.failure();
+        }
+        ready.map_err(Into::into)
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        #[cfg(feature = "fault-injection")]
+        if let Some((delay, fault)) = crate::faults::plan(&self.peer.addr, request.uri().path()) {
+            return self.call_with_fault(request, delay, fault);
+        }
+
+        let response = self.channel.call(request);
+        let peer = self.peer.clone();
+        Box::pin(async move {
+            let response = response.await;
+            match &response {
+                Ok(_) => peer.success(),
+                Err(_) => peer.failure(),
+            }
+            response.map_err(Into::into)
+        })
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+impl PeerChannel {
+    fn call_with_fault(
+        &mut self,
+        request: http::Request<Body>,
+        delay: Duration,
+        fault: crate::faults::Fault,
+    ) -> BoxFuture<http::Response<Body>, StdError> {
+        use crate::faults::Fault;
+
+        // The request goes out after the delay on a channel of its own, this one stays ready for the next
+        let mut channel = self.channel.clone();
+        let peer = self.peer.clone();
+        Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            if matches!(fault, Fault::Partitioned | Fault::DropRequest) {
+                peer.failure();
+                return Err(fault.error(&peer.addr).into());
+            }
+            std::future::poll_fn(|cx| channel.poll_ready(cx)).await?;
+            let response = channel.call(request).await;
+            if fault == Fault::DropResponse || response.is_err() {
+                peer.failure();
+                return Err(match response {
+                    Ok(_) => fault.error(&peer.addr).into(),
+                    Err(e) => e.into(),
+                });
+            }
+            peer.success();
+            response.map_err(Into::into)
+        })
+    }
+}
+
+/// Dials peers over TLS, authenticating with the node certificate
+#[derive(Debug, Clone, Copy)]
+struct NodeTlsConnector;
+
+impl Service<Uri> for NodeTlsConnector {
+    type Response = TokioIo<TlsStream<TcpStream>>;
+    type Error = io::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        Box::pin(async move {
+            let tls = node_tls().ok_or_else(|| io::Error::other("node TLS is not enabled"))?;
+            let host = uri
+                .host()
+                .map(|host| host.trim_start_matches('[').trim_end_matches(']').to_string())
+                .ok_or_else(|| io::Error::other(format!("no host in peer address {uri}")))?;
+            let port = uri.port_u16().unwrap_or(443);
+            let server_name = ServerName::try_from(host.clone()).map_err(io::Error::other)?;
+
+            let stream = TcpStream::connect((host.as_str(), port)).await?;
+            stream.set_nodelay(true)?;
+            let mut config = (*tls.client_config()).clone();
+            config.alpn_protocols = vec![b"h2".to_vec()];
+            let stream = TlsConnector::from(Arc::new(config)).connect(server_name, stream).await?;
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+/// The pooled channel to `addr`, unless its circuit is open
+pub fn peer_channel(addr: &str) -> Result<PeerChannel, Box<dyn Error>> {
+    let peer = {
+        let mut peers = PEERS.lock().unwrap_or_else(|e| e.into_inner());
+        match peers.get(addr) {
+            Some(peer) => peer.clone(),
+            None => {
+                let endpoint = Endpoint::from_shared(addr.to_string())?
+                    .connect_timeout(CONNECT_TIMEOUT)
+                    .http2_keep_alive_interval(KEEPALIVE_INTERVAL)
+                    .keep_alive_timeout(KEEPALIVE_TIMEOUT)
+                    .keep_alive_while_idle(true)
+                    .tcp_keepalive(Some(TCP_KEEPALIVE))
+                    .tcp_nodelay(true);
+                let channel = if endpoint.uri().scheme_str() == Some("https") && node_tls().is_some() {
+                    endpoint.connect_with_connector_lazy(NodeTlsConnector)
+                } else {
+                    endpoint.connect_lazy()
+                };
+                let peer = Arc::new(Peer {
+                    #[cfg(feature = "fault-injection")]
+                    addr: addr.to_string(),
+                    channel,
+                    breaker: Mutex::new(Breaker::default()),
+                });
+                peers.insert(addr.to_string(), peer.clone());
+                peer
+            }
+        }
+    };
+
+    peer.breaker
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .admit(Instant::now())
+        .map_err(|retry_in| CircuitOpen {
+            addr: addr.to_string(),
+            retry_in,
+        })?;
+
+    Ok(PeerChannel {
+        channel: peer.channel.clone(),
+        peer,
+    })
+}
+
+/// Close the circuit of the peer at `addr`
+#[cfg(feature = "fault-injection")]
+pub(crate) fn reset_peer(addr: &str) {
+    if let Some(peer) = PEERS.lock().unwrap_or_else(|e| e.into_inner()).get(addr) {
+        peer.success();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_and_probes() {
+        let mut breaker = Breaker::default();
+        let now = Instant::now();
+
+        for _ in 1..FAILURE_THRESHOLD {
+            breaker.failure(now);
+            assert!(breaker.admit(now).is_ok());
+        }
+        breaker.failure(now);
+        assert_eq!(breaker.admit(now), Err(MIN_BACKOFF));
+
+        // One probe once the backoff has passed, the others wait for it
+        let later = now + MIN_BACKOFF;
+        assert!(breaker.admit(later).is_ok());
+        assert!(breaker.admit(later).is_err());
+
+        // A failed probe doubles the backoff
+        breaker.failure(later);
+        assert_eq!(breaker.admit(later), Err(MIN_BACKOFF * 2));
+
+        breaker.success();
+        assert!(breaker.admit(later).is_ok());
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Network faults injected into the RPCs to peer nodes, for tests
+//!
+//! Tests set the faults of a peer by its address, the one RPC clients are created with. Requests
+//! on the channel to that peer are then delayed, fail before reaching it, lose their response
+//! after it handled them, or all fail as if the peer were partitioned away. Faults injected into a
+//! request count as transport failures for the circuit of the peer, like real ones would.
+//!
+//! Only built with the `fault-injection` feature, servers never set any fault.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+static FAULTS: LazyLock<RwLock<HashMap<String, PeerFaults>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Faults of the requests to a peer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeerFaults {
+    /// Delay before every request is sent
+    pub latency: Duration,
+    /// Up to this much more delay, picked at random for every request
+    pub jitter: Duration,
+    /// Share of the requests failing before they reach the peer
+    pub drop_request: f64,
+    /// Share of the requests the peer handles whose response is lost
+    pub drop_response: f64,
+    /// Every request fails right away, as if the peer were unreachable
+    pub partitioned: bool,
+    /// gRPC methods the faults apply to, e.g. `Lock` or `UnLock`, all of them when empty
+    pub methods: Vec<String>,
+}
+
+impl PeerFaults {
+    /// Faults cutting the peer off
+    pub fn partition() -> Self {
+        Self {
+            partitioned: true,
+            ..Default::default()
+        }
+    }
+
+    /// Apply the faults to these gRPC methods only
+    pub fn for_methods(mut self, methods: &[&str]) -> Self {
+        self.methods = methods.iter().map(|method| method.to_string()).collect();
+        self
+    }
+
+    fn applies_to(&self, path: &str) -> bool {
+        let method = path.rsplit('/').next().unwrap_or(path);
+        self.methods.is_empty() || self.methods.iter().any(|m| m == method)
+    }
+}
+
+/// Error of a request failed by an injected fault
+#[derive(Debug)]
+pub struct InjectedFault {
+    pub addr: String,
+    pub fault: &'static str,
+}
+
+impl fmt::Display for InjectedFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "injected fault on peer {}: {}", self.addr, self.fault)
+    }
+}
+
+impl Error for InjectedFault {}
+
+/// Inject `faults` into the requests to the peer at `addr`, replacing its earlier ones
+pub fn set_peer_faults(addr: &str, faults: PeerFaults) {
+    FAULTS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(addr.to_string(), faults);
+}
+
+/// Cut the peer at `addr` off
+pub fn partition(addr: &str) {
+    set_peer_faults(addr, PeerFaults::partition());
+}
+
+/// Remove the faults of the peer at `addr` and close its circuit, so requests reach it right away
+pub fn heal(addr: &str) {
+    FAULTS.write().unwrap_or_else(|e| e.into_inner()).remove(addr);
+    crate::channel::reset_peer(addr);
+}
+
+/// Remove the faults of all peers
+pub fn heal_all() {
+    let addrs: Vec<String> = FAULTS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain()
+        .map(|(addr, _)| addr)
+        .collect();
+    for addr in addrs {
+        crate::channel::reset_peer(&addr);
+    }
+}
+
+/// What happens to a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Fault {
+    None,
+    Partitioned,
+    DropRequest,
+    DropResponse,
+}
+
+impl Fault {
+    pub(crate) fn error(self, addr: &str) -> InjectedFault {
+        let fault = match self {
+            Fault::None => "none",
+            Fault::Partitioned => "partitioned",
+            Fault::DropRequest => "request dropped",
+            Fault::DropResponse => "response dropped",
+        };
+        InjectedFault {
+            addr: addr.to_string(),
+            fault,
+        }
+    }
+}
+
+/// Delay and fault of a request to `addr` on the gRPC `path`, `None` when it goes through untouched
+pub(crate) fn plan(addr: &str, path: &str) -> Option<(Duration, Fault)> {
+    let faults = FAULTS.read().unwrap_or_else(|e| e.into_inner());
+    let peer = faults.get(addr).filter(|peer| peer.applies_to(path))?;
+    if peer.partitioned {
+        return Some((Duration::ZERO, Fault::Partitioned));
+    }
+
+    let delay = peer.latency + peer.jitter.mul_f64(rand::random::<f64>());
+    let fault = if rand::random::<f64>() < peer.drop_request {
+        Fault::DropRequest
+    } else if rand::random::<f64>() < peer.drop_response {
+        Fault::DropResponse
+    } else {
+        Fault::None
+    };
+    Some((delay, fault))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_service_time_out_client;
+    use crate::proto_gen::node_service::PingRequest;
+    use std::time::Instant;
+
+    #[test]
+    fn test_faults_apply_to_their_methods() {
+        let addr = "http://faults-methods:9000";
+        assert_eq!(plan(addr, "/node_service.NodeService/Lock"), None);
+
+        set_peer_faults(
+            addr,
+            PeerFaults {
+                drop_request: 1.0,
+                ..Default::default()
+            }
+            .for_methods(&["Lock", "UnLock"]),
+        );
+        assert_eq!(plan(addr, "/node_service.NodeService/Lock"), Some((Duration::ZERO, Fault::DropRequest)));
+        assert_eq!(plan(addr, "/node_service.NodeService/Ping"), None);
+
+        partition(addr);
+        assert_eq!(plan(addr, "/node_service.NodeService/Ping"), Some((Duration::ZERO, Fault::Partitioned)));
+        heal(addr);
+        assert_eq!(plan(addr, "/node_service.NodeService/Ping"), None);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_peer_fails_fast() {
+        // Nothing listens there, the partition fails the request before any connect attempt
+        let addr = "http://127.0.0.1:9";
+        partition(addr);
+        let mut client = node_service_time_out_client(addr).await.unwrap();
+        let start = Instant::now();
+        let err = client.ping(PingRequest::default()).await.unwrap_err();
+        assert!(err.to_string().contains("partitioned"), "unexpected error: {err}");
+        assert!(start.elapsed() < Duration::from_secs(1));
+        heal(addr);
+    }
+}
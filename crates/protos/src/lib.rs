@@ -15,47 +15,32 @@
 #[allow(unsafe_code)]
 mod generated;
 
-use std::{error::Error, time::Duration};
+pub mod channel;
+#[cfg(feature = "fault-injection")]
+pub mod faults;
 
+use std::error::Error;
+
+pub use channel::{CircuitOpen, PeerChannel};
 pub use generated::*;
 use proto_gen::node_service::node_service_client::NodeServiceClient;
-use rustfs_common::globals::GLOBAL_Conn_Map;
-use tonic::{
-    Request, Status,
-    metadata::MetadataValue,
-    service::interceptor::InterceptedService,
-    transport::{Channel, Endpoint},
-};
+use tonic::{Request, Status, metadata::MetadataValue, service::interceptor::InterceptedService};
 
 // Default 100 MB
 pub const DEFAULT_GRPC_SERVER_MESSAGE_LEN: usize = 100 * 1024 * 1024;
 
 pub async fn node_service_time_out_client(
-    addr: &String,
+    addr: &str,
 ) -> Result<
     NodeServiceClient<
-        InterceptedService<Channel, Box<dyn Fn(Request<()>) -> Result<Request<()>, Status> + Send + Sync + 'static>>,
+        InterceptedService<PeerChannel, Box<dyn Fn(Request<()>) -> Result<Request<()>, Status> + Send + Sync + 'static>>,
     >,
     Box<dyn Error>,
 > {
     let token: MetadataValue<_> = "rustfs rpc".parse()?;
 
-    let channel = { GLOBAL_Conn_Map.read().await.get(addr).cloned() };
-
-    let channel = match channel {
-        Some(channel) => channel,
-        None => {
-            let connector = Endpoint::from_shared(addr.to_string())?.connect_timeout(Duration::from_secs(60));
-            let channel = connector.connect().await?;
-
-            {
-                GLOBAL_Conn_Map.write().await.insert(addr.to_string(), channel.clone());
-            }
-            channel
-        }
-    };
+    let channel = channel::peer_channel(addr)?;
 
-    // let timeout_channel = Timeout::new(channel, Duration::from_secs(60));
     Ok(NodeServiceClient::with_interceptor(
         channel,
         Box::new(move |mut req: Request<()>| {
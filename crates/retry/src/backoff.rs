@@ -0,0 +1,72 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+/// Capped exponential backoff
+///
+/// The delay before retry `n` is `base * 2^n`, at most `cap`. With jitter the delay is reduced by
+/// a random share of up to `jitter` of it, so callers failing together spread out their retries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    base: Duration,
+    cap: Duration,
+    jitter: f64,
+}
+
+impl Backoff {
+    pub const fn new(base: Duration, cap: Duration) -> Self {
+        Self { base, cap, jitter: 0.0 }
+    }
+
+    /// Set the share of a delay that may be dropped at random, clamped to 0..=1
+    pub const fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Delay before retry `retry`, counting from 0
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self
+            .base
+            .checked_mul(1u32.checked_shl(retry).unwrap_or(u32::MAX))
+            .map_or(self.cap, |d| d.min(self.cap));
+        if self.jitter > 0.0 {
+            delay.mul_f64(1.0 - self.jitter * rand::random::<f64>())
+        } else {
+            delay
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_doubles_up_to_cap() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(1), Duration::from_millis(200));
+        assert_eq!(backoff.delay(3), Duration::from_millis(800));
+        assert_eq!(backoff.delay(4), Duration::from_secs(1));
+        assert_eq!(backoff.delay(64), Duration::from_secs(1));
+
+        let jittered = backoff.with_jitter(0.5);
+        for retry in 0..8 {
+            let delay = jittered.delay(retry);
+            assert!(delay <= backoff.delay(retry) && delay >= backoff.delay(retry) / 2);
+        }
+    }
+}
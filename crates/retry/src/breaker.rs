@@ -0,0 +1,138 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Consecutive failures that open a breaker by default
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+/// Time an open breaker refuses calls by default
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls go through
+    Closed,
+    /// Calls are refused until the cooldown passed
+    Open,
+    /// One probe call is let through, its result closes or reopens the breaker
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: BreakerState,
+    failures: u32,
+    /// When the breaker opened, or when the half open probe was let through
+    since: Instant,
+}
+
+/// Stops calling a remote after consecutive failures
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                failures: 0,
+                since: Instant::now(),
+            }),
+        }
+    }
+
+    /// Whether a call may go through now
+    ///
+    /// Once the cooldown of an open breaker passed a single probe is allowed; should the probe
+    /// never report back, another one is allowed after a further cooldown.
+    pub fn allow(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            BreakerState::Closed => true,
+            BreakerState::Open | BreakerState::HalfOpen if inner.since.elapsed() >= self.cooldown => {
+                inner.state = BreakerState::HalfOpen;
+                inner.since = Instant::now();
+                true
+            }
+            BreakerState::Open | BreakerState::HalfOpen => false,
+        }
+    }
+
+    /// Report a call the remote answered, closing the breaker
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state = BreakerState::Closed;
+        inner.failures = 0;
+    }
+
+    /// Report a call that failed to reach the remote or timed out
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures = inner.failures.saturating_add(1);
+        if inner.state == BreakerState::HalfOpen || inner.failures >= self.failure_threshold {
+            if inner.state == BreakerState::Closed {
+                tracing::warn!("circuit breaker opened after {} consecutive failures", inner.failures);
+            }
+            inner.state = BreakerState::Open;
+            inner.since = Instant::now();
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().unwrap().state
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURE_THRESHOLD, DEFAULT_COOLDOWN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_and_probes() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+        breaker.record_failure();
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(!breaker.allow());
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(breaker.allow());
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(!breaker.allow());
+
+        // A failed probe opens the breaker again at once
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(breaker.allow());
+        breaker.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.allow());
+    }
+}
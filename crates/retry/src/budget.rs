@@ -0,0 +1,110 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Mutex;
+
+/// Tokens a budget starts with and never exceeds by default
+pub const DEFAULT_BUDGET_TOKENS: f64 = 100.0;
+/// Tokens a successful call returns to a budget by default, allowing one retry per ten successes
+pub const DEFAULT_BUDGET_DEPOSIT: f64 = 0.1;
+
+/// Retries of one subsystem, limited to a share of its successful calls
+///
+/// Every retry withdraws a token, every successful call deposits a fraction of one. A healthy
+/// subsystem keeps its budget full; once a remote degrades, the budget drains and further
+/// failures are given up on at once instead of multiplying the load on the remote.
+#[derive(Debug)]
+pub struct RetryBudget {
+    max_tokens: f64,
+    deposit: f64,
+    state: Mutex<BudgetStats>,
+}
+
+/// Point in time view of a retry budget
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BudgetStats {
+    /// Retries still allowed
+    pub tokens: f64,
+    /// Retries taken from the budget
+    pub retries: u64,
+    /// Retries refused because the budget ran out
+    pub rejected: u64,
+}
+
+impl RetryBudget {
+    pub fn new(max_tokens: f64, deposit: f64) -> Self {
+        Self {
+            max_tokens,
+            deposit,
+            state: Mutex::new(BudgetStats {
+                tokens: max_tokens,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Take the token of one retry, false when the budget is exhausted
+    pub fn try_withdraw(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.tokens < 1.0 {
+            state.rejected += 1;
+            return false;
+        }
+        state.tokens -= 1.0;
+        state.retries += 1;
+        true
+    }
+
+    /// Credit a successful call
+    pub fn deposit(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.tokens = (state.tokens + self.deposit).min(self.max_tokens);
+    }
+
+    pub fn stats(&self) -> BudgetStats {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUDGET_TOKENS, DEFAULT_BUDGET_DEPOSIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_refills_from_successes() {
+        let budget = RetryBudget::new(2.0, 0.5);
+        assert!(budget.try_withdraw());
+        assert!(budget.try_withdraw());
+        assert!(!budget.try_withdraw());
+
+        budget.deposit();
+        assert!(!budget.try_withdraw());
+        budget.deposit();
+        assert!(budget.try_withdraw());
+
+        for _ in 0..10 {
+            budget.deposit();
+        }
+        let stats = budget.stats();
+        assert_eq!(stats.tokens, 2.0);
+        assert_eq!(stats.retries, 3);
+        assert_eq!(stats.rejected, 2);
+    }
+}
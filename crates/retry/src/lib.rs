@@ -0,0 +1,63 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Retry helpers shared by the subsystems talking to peers and remote targets.
+//!
+//! A [`RetryPolicy`] combines a capped exponential [`Backoff`] with an optional [`RetryBudget`],
+//! shared by every caller of one subsystem, and an optional [`CircuitBreaker`], shared by every
+//! caller of one remote. The budget keeps retries a small share of the traffic of a subsystem
+//! once a remote degrades, the breaker stops calling a remote that keeps failing until a
+//! cooldown passed.
+
+mod backoff;
+mod breaker;
+mod budget;
+mod policy;
+
+pub use backoff::Backoff;
+pub use breaker::{BreakerState, CircuitBreaker};
+pub use budget::{BudgetStats, RetryBudget};
+pub use policy::{Attempts, RetryError, RetryPolicy, StopReason};
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+static BUDGETS: LazyLock<Mutex<HashMap<String, Arc<RetryBudget>>>> = LazyLock::new(Default::default);
+static BREAKERS: LazyLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> = LazyLock::new(Default::default);
+
+/// Retry budget of `subsystem`, created with the default size on first use
+pub fn budget(subsystem: &str) -> Arc<RetryBudget> {
+    let mut budgets = BUDGETS.lock().unwrap();
+    budgets
+        .entry(subsystem.to_string())
+        .or_insert_with(|| Arc::new(RetryBudget::default()))
+        .clone()
+}
+
+/// Circuit breaker of the remote identified by `key`, created with the default thresholds on first use
+pub fn breaker(key: &str) -> Arc<CircuitBreaker> {
+    let mut breakers = BREAKERS.lock().unwrap();
+    breakers
+        .entry(key.to_string())
+        .or_insert_with(|| Arc::new(CircuitBreaker::default()))
+        .clone()
+}
+
+/// Stats of every retry budget in use, by subsystem
+pub fn budget_stats() -> Vec<(String, BudgetStats)> {
+    let budgets = BUDGETS.lock().unwrap();
+    let mut stats: Vec<_> = budgets.iter().map(|(name, b)| (name.clone(), b.stats())).collect();
+    stats.sort_by(|a, b| a.0.cmp(&b.0));
+    stats
+}
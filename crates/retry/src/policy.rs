@@ -0,0 +1,260 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Backoff, CircuitBreaker, RetryBudget};
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Why a policy gave up retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The policy allows no further attempts
+    Exhausted,
+    /// The retry budget of the subsystem ran out
+    BudgetExhausted,
+    /// The breaker of the remote refuses calls
+    CircuitOpen,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Exhausted => write!(f, "attempts exhausted"),
+            StopReason::BudgetExhausted => write!(f, "retry budget exhausted"),
+            StopReason::CircuitOpen => write!(f, "circuit open"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RetryError<E> {
+    /// The operation failed with an error retrying would not fix
+    Permanent(E),
+    /// Retrying stopped, `last` is the error of the last attempt if one was made
+    Stopped { reason: StopReason, last: Option<E> },
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetryError::Permanent(e) => write!(f, "{e}"),
+            RetryError::Stopped { reason, last: Some(e) } => write!(f, "{reason}: {e}"),
+            RetryError::Stopped { reason, last: None } => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for RetryError<E> {}
+
+impl<E> RetryError<E> {
+    /// Error of the last attempt made
+    pub fn into_last(self) -> Option<E> {
+        match self {
+            RetryError::Permanent(e) => Some(e),
+            RetryError::Stopped { last, .. } => last,
+        }
+    }
+}
+
+/// How often and how fast an operation is retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Backoff,
+    budget: Option<Arc<RetryBudget>>,
+    breaker: Option<Arc<CircuitBreaker>>,
+}
+
+impl RetryPolicy {
+    /// Allow `max_attempts` attempts, the first one included, spaced by `backoff`
+    pub fn new(max_attempts: u32, backoff: Backoff) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff,
+            budget: None,
+            breaker: None,
+        }
+    }
+
+    /// Take every retry from `budget`
+    pub fn with_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Only attempt while `breaker` allows calls, and report every attempt to it
+    pub fn with_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Start counting the attempts of one operation, for callers driving the loop themselves
+    pub fn attempts(&self) -> Attempts<'_> {
+        Attempts { policy: self, made: 0 }
+    }
+
+    /// Run `op` until it succeeds, fails with an error `is_retryable` rejects, or the policy stops
+    pub async fn retry<T, E, F, Fut>(&self, mut op: F, is_retryable: impl Fn(&E) -> bool) -> Result<T, RetryError<E>>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempts = self.attempts();
+        let mut last = None;
+        loop {
+            if let Err(reason) = attempts.next().await {
+                return Err(RetryError::Stopped { reason, last });
+            }
+            match op().await {
+                Ok(v) => {
+                    attempts.record_success();
+                    return Ok(v);
+                }
+                Err(e) if is_retryable(&e) => {
+                    attempts.record_failure();
+                    last = Some(e);
+                }
+                Err(e) => {
+                    // The remote answered, it only refused the request
+                    attempts.record_success();
+                    return Err(RetryError::Permanent(e));
+                }
+            }
+        }
+    }
+}
+
+/// Attempts of one operation under a [`RetryPolicy`]
+pub struct Attempts<'a> {
+    policy: &'a RetryPolicy,
+    made: u32,
+}
+
+impl Attempts<'_> {
+    /// Wait until the next attempt may be made, returning its number counting from 1
+    ///
+    /// The first attempt is only subject to the breaker. Later ones also need a retry from the
+    /// budget and wait for the backoff delay first.
+    pub async fn next(&mut self) -> Result<u32, StopReason> {
+        if self.made > 0 {
+            if self.made >= self.policy.max_attempts {
+                return Err(StopReason::Exhausted);
+            }
+            if self.policy.budget.as_ref().is_some_and(|b| !b.try_withdraw()) {
+                return Err(StopReason::BudgetExhausted);
+            }
+            tokio::time::sleep(self.policy.backoff.delay(self.made - 1)).await;
+        }
+        if self.policy.breaker.as_ref().is_some_and(|b| !b.allow()) {
+            return Err(StopReason::CircuitOpen);
+        }
+        self.made += 1;
+        Ok(self.made)
+    }
+
+    /// Report that the last attempt succeeded
+    pub fn record_success(&self) {
+        if let Some(breaker) = &self.policy.breaker {
+            breaker.record_success();
+        }
+        if let Some(budget) = &self.policy.budget {
+            budget.deposit();
+        }
+    }
+
+    /// Report that the last attempt failed in a way worth retrying
+    pub fn record_failure(&self) {
+        if let Some(breaker) = &self.policy.breaker {
+            breaker.record_failure();
+        }
+    }
+
+    /// Attempts made so far
+    pub fn made(&self) -> u32 {
+        self.made
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BreakerState;
+    use std::time::Duration;
+
+    const BACKOFF: Backoff = Backoff::new(Duration::from_millis(1), Duration::from_millis(2));
+
+    #[tokio::test]
+    async fn test_retry_until_success_or_permanent() {
+        let mut calls = 0;
+        let res: Result<u32, RetryError<&str>> = RetryPolicy::new(5, BACKOFF)
+            .retry(
+                || {
+                    calls += 1;
+                    let n = calls;
+                    async move { if n < 3 { Err("busy") } else { Ok(n) } }
+                },
+                |e| *e == "busy",
+            )
+            .await;
+        assert_eq!(res.unwrap(), 3);
+
+        let res: Result<(), _> = RetryPolicy::new(5, BACKOFF)
+            .retry(|| async { Err("denied") }, |e| *e == "busy")
+            .await;
+        assert!(matches!(res, Err(RetryError::Permanent("denied"))));
+
+        let res: Result<(), _> = RetryPolicy::new(3, BACKOFF).retry(|| async { Err("busy") }, |_| true).await;
+        assert!(matches!(
+            res,
+            Err(RetryError::Stopped {
+                reason: StopReason::Exhausted,
+                last: Some("busy")
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_budget_and_breaker() {
+        let budget = Arc::new(RetryBudget::new(1.0, 0.1));
+        let breaker = Arc::new(CircuitBreaker::new(3, Duration::from_secs(60)));
+        let policy = RetryPolicy::new(10, BACKOFF)
+            .with_budget(budget.clone())
+            .with_breaker(breaker.clone());
+
+        let res: Result<(), _> = policy.retry(|| async { Err("busy") }, |_| true).await;
+        assert!(matches!(
+            res,
+            Err(RetryError::Stopped {
+                reason: StopReason::BudgetExhausted,
+                ..
+            })
+        ));
+        assert_eq!(budget.stats().retries, 1);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        let res: Result<(), _> = policy.retry(|| async { Err("busy") }, |_| true).await;
+        assert!(matches!(res, Err(RetryError::Stopped { .. })));
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        let res: Result<(), RetryError<&str>> = policy.retry(|| async { Ok(()) }, |_| true).await;
+        assert!(matches!(
+            res,
+            Err(RetryError::Stopped {
+                reason: StopReason::CircuitOpen,
+                last: None
+            })
+        ));
+    }
+}
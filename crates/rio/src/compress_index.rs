@@ -18,6 +18,7 @@ use std::io::{self, Read, Seek, SeekFrom};
 
 const S2_INDEX_HEADER: &[u8] = b"s2idx\x00";
 const S2_INDEX_TRAILER: &[u8] = b"\x00xdi2s";
+const CHUNK_TYPE_INDEX: u8 = 0x99;
 const MAX_INDEX_ENTRIES: usize = 1 << 16;
 const MIN_INDEX_DIST: i64 = 1 << 20;
 // const MIN_INDEX_DIST: i64 = 0;
@@ -75,7 +76,13 @@ impl Index {
         if n > MAX_INDEX_ENTRIES {
             panic!("n > MAX_INDEX_ENTRIES");
         }
-        self.info = Vec::with_capacity(n);
+        self.info = vec![
+            IndexInfo {
+                compressed_offset: 0,
+                uncompressed_offset: 0,
+            };
+            n
+        ];
     }
 
     pub fn add(&mut self, compressed_offset: i64, uncompressed_offset: i64) -> io::Result<()> {
@@ -214,7 +221,7 @@ impl Index {
         let init_size = b.len();
 
         // Add skippable header
-        b.extend_from_slice(&[0x50, 0x2A, 0x4D, 0x18]); // ChunkTypeIndex
+        b.push(CHUNK_TYPE_INDEX);
         b.extend_from_slice(&[0, 0, 0]); // Placeholder for chunk length
 
         // Add header
@@ -291,7 +298,7 @@ impl Index {
             return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "buffer too small"));
         }
 
-        if b[0] != 0x50 || b[1] != 0x2A || b[2] != 0x4D || b[3] != 0x18 {
+        if b[0] != CHUNK_TYPE_INDEX {
             return Err(io::Error::other("invalid chunk type"));
         }
 
@@ -382,9 +389,10 @@ impl Index {
             b = &b[n..];
 
             if idx > 0 {
-                c_predict += c_off / 2;
+                let c_predict_new = c_predict + c_off / 2;
                 let prev = self.info[idx - 1].compressed_offset;
                 c_off += prev + c_predict;
+                c_predict = c_predict_new;
                 if c_off <= prev {
                     return Err(io::Error::other("invalid offset"));
                 }
@@ -683,4 +691,36 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_index_load() -> io::Result<()> {
+        let mut index = Index::new();
+        for i in 1..=5 {
+            index.add(i * 400_000 + i * 7, i * MIN_INDEX_DIST)?;
+        }
+        index.total_uncompressed = 5 * MIN_INDEX_DIST + 100;
+        index.total_compressed = 2_000_100;
+        let expected = index.clone();
+
+        let b = index.into_vec();
+        let mut loaded = Index::new();
+        let rest = loaded.load(&b)?;
+        assert!(rest.is_empty());
+
+        assert_eq!(loaded.total_uncompressed, expected.total_uncompressed);
+        assert_eq!(loaded.total_compressed, expected.total_compressed);
+        assert_eq!(loaded.len(), expected.len());
+        for offset in [
+            0,
+            MIN_INDEX_DIST - 1,
+            MIN_INDEX_DIST,
+            3 * MIN_INDEX_DIST + 5,
+            5 * MIN_INDEX_DIST + 100,
+        ] {
+            assert_eq!(loaded.find(offset)?, expected.find(offset)?);
+        }
+
+        assert!(Index::new().load(&b[..b.len() - 1]).is_err());
+        Ok(())
+    }
 }
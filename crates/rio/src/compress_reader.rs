@@ -134,6 +134,9 @@ where
                     let n = temp_buf.filled().len();
                     if n == 0 {
                         if this.temp_buffer.is_empty() {
+                            // The totals let readers find offsets past the last index entry
+                            this.index.total_compressed = *this.written as i64;
+                            this.index.total_uncompressed = *this.uncomp_written as i64;
                             return Poll::Ready(Ok(()));
                         }
                         break;
@@ -217,6 +220,7 @@ pin_project! {
         compressed_read: usize,
         compressed_len: usize,
         compression_algorithm: CompressionAlgorithm,
+        skip: usize,
     }
 }
 
@@ -237,8 +241,15 @@ where
             compressed_read: 0,
             compressed_len: 0,
             compression_algorithm,
+            skip: 0,
         }
     }
+
+    /// Skip the first `offset` bytes of the decompressed data, to start reading inside a block
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.skip = offset;
+        self
+    }
 }
 
 impl<R> AsyncRead for DecompressReader<R>
@@ -321,7 +332,7 @@ where
                 }
             }
         }
-        let (uncompress_len, uvarint) = uvarint(&compressed_buf[0..16]);
+        let (uncompress_len, uvarint) = uvarint(&compressed_buf[0..min(16, compressed_buf.len())]);
         let compressed_data = &compressed_buf[uvarint as usize..];
         let decompressed = if typ == COMPRESS_TYPE_COMPRESSED {
             match decompress_block(compressed_data, *this.compression_algorithm) {
@@ -370,6 +381,16 @@ where
         *this.compressed_read = 0;
         *this.compressed_len = 0;
         *this.header_done = false;
+        if *this.skip > 0 {
+            let skipped = min(*this.skip, this.buffer.len());
+            *this.skip -= skipped;
+            if skipped == this.buffer.len() {
+                this.buffer.clear();
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            this.buffer.drain(..skipped);
+        }
         let to_copy = min(buf.remaining(), this.buffer.len());
         buf.put_slice(&this.buffer[..to_copy]);
         *this.buffer_pos += to_copy;
@@ -521,4 +542,36 @@ mod tests {
 
         assert_eq!(&decompressed, &data);
     }
+
+    #[tokio::test]
+    async fn test_decompress_reader_with_index_offset() {
+        use rand::Rng;
+        // Blocks of random bytes do not compress, runs of one byte do
+        let mut data = vec![0u8; 1024 * 1024 * 5 + 777];
+        for (i, chunk) in data.chunks_mut(64 * 1024).enumerate() {
+            if i % 2 == 0 {
+                rand::rng().fill(chunk);
+            }
+        }
+        let mut compress_reader = CompressReader::new(WarpReader::new(Cursor::new(data.clone())), CompressionAlgorithm::Zstd);
+        let mut compressed = Vec::new();
+        compress_reader.read_to_end(&mut compressed).await.unwrap();
+
+        let mut index = Index::new();
+        index
+            .load(&compress_reader.try_get_index().unwrap().clone().into_vec())
+            .unwrap();
+        assert_eq!(index.total_uncompressed, data.len() as i64);
+        assert_eq!(index.total_compressed, compressed.len() as i64);
+
+        for offset in [0, 10, 1024 * 1024, 1024 * 1024 * 3 + 5, data.len() - 1] {
+            let (comp_off, uncomp_off) = index.find(offset as i64).unwrap();
+            let reader = Cursor::new(compressed[comp_off as usize..].to_vec());
+            let mut decompress_reader =
+                DecompressReader::new(reader, CompressionAlgorithm::Zstd).with_offset(offset - uncomp_off as usize);
+            let mut decompressed = Vec::new();
+            decompress_reader.read_to_end(&mut decompressed).await.unwrap();
+            assert_eq!(decompressed, &data[offset..], "offset {offset}");
+        }
+    }
 }
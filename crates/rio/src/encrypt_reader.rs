@@ -12,6 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Streaming AES-256-GCM encryption of object data
+//!
+//! The plaintext is sealed in blocks of up to [`BLOCK_SIZE`] bytes. Each block is framed by an
+//! 8 byte header: its type (0 for data, 0xFF for the end of the stream), the length of the frame
+//! body as a little endian u24 and the CRC32 of the plaintext. The body holds the plaintext
+//! length as a uvarint and the ciphertext with its tag. Block `i` of a stream is sealed with the
+//! stream nonce XORed with `i`, so no two blocks share a nonce and blocks cannot be reordered.
+//! Parts of a multipart object are streams of their own, each sealed with a nonce of its own.
+
 use crate::HashReaderDetector;
 use crate::HashReaderMut;
 use crate::compress_index::{Index, TryGetIndex};
@@ -20,19 +29,57 @@ use aes_gcm::aead::Aead;
 use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
 use pin_project_lite::pin_project;
 use rustfs_utils::{put_uvarint, put_uvarint_len};
+use std::io::{Error, ErrorKind};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use tokio::io::{AsyncRead, ReadBuf};
 
+/// Largest plaintext sealed in one block
+pub const BLOCK_SIZE: usize = 64 * 1024;
+
+const HEADER_SIZE: usize = 8;
+const BLOCK_DATA: u8 = 0x00;
+const BLOCK_END: u8 = 0xFF;
+
+/// Nonce of the stream of part `part_number` of an object encrypted with the base `nonce`
+///
+/// Objects written in one piece are encrypted as part 1. Parts of multipart uploads must not use it,
+/// a part number can be uploaded more than once and would reuse the nonce.
+pub fn part_nonce(nonce: [u8; 12], part_number: usize) -> [u8; 12] {
+    let mut out = nonce;
+    for (b, p) in out[..4].iter_mut().zip((part_number as u32).to_be_bytes()) {
+        *b ^= p;
+    }
+    out
+}
+
+fn block_nonce(nonce: &[u8; 12], block: u64) -> [u8; 12] {
+    let mut out = *nonce;
+    for (b, c) in out[4..].iter_mut().zip(block.to_be_bytes()) {
+        *b ^= c;
+    }
+    out
+}
+
+fn copy_buffered(buffer: &mut Vec<u8>, buffer_pos: &mut usize, buf: &mut ReadBuf<'_>) {
+    let to_copy = std::cmp::min(buf.remaining(), buffer.len() - *buffer_pos);
+    buf.put_slice(&buffer[*buffer_pos..*buffer_pos + to_copy]);
+    *buffer_pos += to_copy;
+    if *buffer_pos == buffer.len() {
+        buffer.clear();
+        *buffer_pos = 0;
+    }
+}
+
 pin_project! {
     /// A reader wrapper that encrypts data on the fly using AES-256-GCM.
-    /// This is a demonstration. For production, use a secure and audited crypto library.
     #[derive(Debug)]
     pub struct EncryptReader<R> {
         #[pin]
         pub inner: R,
         key: [u8; 32],   // AES-256-GCM key
-        nonce: [u8; 12], // 96-bit nonce for GCM
+        nonce: [u8; 12], // 96-bit nonce of the stream
+        block: u64,
         buffer: Vec<u8>,
         buffer_pos: usize,
         finished: bool,
@@ -48,6 +95,7 @@ where
             inner,
             key,
             nonce,
+            block: 0,
             buffer: Vec::new(),
             buffer_pos: 0,
             finished: false,
@@ -63,78 +111,53 @@ where
         let mut this = self.project();
         // Serve from buffer if any
         if *this.buffer_pos < this.buffer.len() {
-            let to_copy = std::cmp::min(buf.remaining(), this.buffer.len() - *this.buffer_pos);
-            buf.put_slice(&this.buffer[*this.buffer_pos..*this.buffer_pos + to_copy]);
-            *this.buffer_pos += to_copy;
-            if *this.buffer_pos == this.buffer.len() {
-                this.buffer.clear();
-                *this.buffer_pos = 0;
-            }
+            copy_buffered(this.buffer, this.buffer_pos, buf);
             return Poll::Ready(Ok(()));
         }
         if *this.finished {
             return Poll::Ready(Ok(()));
         }
-        // Read a fixed block size from inner
-        let block_size = 8 * 1024;
-        let mut temp = vec![0u8; block_size];
+
+        let mut temp = vec![0u8; BLOCK_SIZE];
         let mut temp_buf = ReadBuf::new(&mut temp);
         match this.inner.as_mut().poll_read(cx, &mut temp_buf) {
-            Poll::Pending => Poll::Pending,
-            Poll::Ready(Ok(())) => {
-                let n = temp_buf.filled().len();
-                if n == 0 {
-                    // EOF, write end header
-                    let mut header = [0u8; 8];
-                    header[0] = 0xFF; // type: end
-                    *this.buffer = header.to_vec();
-                    *this.buffer_pos = 0;
-                    *this.finished = true;
-                    let to_copy = std::cmp::min(buf.remaining(), this.buffer.len());
-                    buf.put_slice(&this.buffer[..to_copy]);
-                    *this.buffer_pos += to_copy;
-                    Poll::Ready(Ok(()))
-                } else {
-                    // Encrypt the chunk
-                    let cipher = Aes256Gcm::new_from_slice(this.key).expect("key");
-                    let nonce = Nonce::from_slice(this.nonce);
-                    let plaintext = &temp_buf.filled()[..n];
-                    let plaintext_len = plaintext.len();
-                    let crc = crc32fast::hash(plaintext);
-                    let ciphertext = cipher
-                        .encrypt(nonce, plaintext)
-                        .map_err(|e| std::io::Error::other(format!("encrypt error: {e}")))?;
-                    let int_len = put_uvarint_len(plaintext_len as u64);
-                    let clen = int_len + ciphertext.len() + 4;
-                    // Header: 8 bytes
-                    // 0: type (0 = encrypted, 0xFF = end)
-                    // 1-3: length (little endian u24, ciphertext length)
-                    // 4-7: CRC32 of ciphertext (little endian u32)
-                    let mut header = [0u8; 8];
-                    header[0] = 0x00; // 0 = encrypted
-                    header[1] = (clen & 0xFF) as u8;
-                    header[2] = ((clen >> 8) & 0xFF) as u8;
-                    header[3] = ((clen >> 16) & 0xFF) as u8;
-                    header[4] = (crc & 0xFF) as u8;
-                    header[5] = ((crc >> 8) & 0xFF) as u8;
-                    header[6] = ((crc >> 16) & 0xFF) as u8;
-                    header[7] = ((crc >> 24) & 0xFF) as u8;
-                    let mut out = Vec::with_capacity(8 + int_len + ciphertext.len());
-                    out.extend_from_slice(&header);
-                    let mut plaintext_len_buf = vec![0u8; int_len];
-                    put_uvarint(&mut plaintext_len_buf, plaintext_len as u64);
-                    out.extend_from_slice(&plaintext_len_buf);
-                    out.extend_from_slice(&ciphertext);
-                    *this.buffer = out;
-                    *this.buffer_pos = 0;
-                    let to_copy = std::cmp::min(buf.remaining(), this.buffer.len());
-                    buf.put_slice(&this.buffer[..to_copy]);
-                    *this.buffer_pos += to_copy;
-                    Poll::Ready(Ok(()))
-                }
-            }
-            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Ready(Ok(())) => {}
+        }
+
+        let plaintext = temp_buf.filled();
+        if plaintext.is_empty() {
+            // EOF, write end header
+            let mut header = [0u8; HEADER_SIZE];
+            header[0] = BLOCK_END;
+            *this.buffer = header.to_vec();
+            *this.finished = true;
+        } else {
+            let cipher = Aes256Gcm::new_from_slice(this.key).map_err(|e| Error::other(format!("encrypt error: {e}")))?;
+            let nonce = block_nonce(this.nonce, *this.block);
+            let ciphertext = cipher
+                .encrypt(Nonce::from_slice(&nonce), plaintext)
+                .map_err(|e| Error::other(format!("encrypt error: {e}")))?;
+            *this.block += 1;
+
+            let int_len = put_uvarint_len(plaintext.len() as u64);
+            let body_len = int_len + ciphertext.len();
+            let crc = crc32fast::hash(plaintext);
+
+            let mut out = Vec::with_capacity(HEADER_SIZE + body_len);
+            out.push(BLOCK_DATA);
+            out.extend_from_slice(&(body_len as u32).to_le_bytes()[..3]);
+            out.extend_from_slice(&crc.to_le_bytes());
+            let mut plaintext_len_buf = vec![0u8; int_len];
+            put_uvarint(&mut plaintext_len_buf, plaintext.len() as u64);
+            out.extend_from_slice(&plaintext_len_buf);
+            out.extend_from_slice(&ciphertext);
+            *this.buffer = out;
         }
+        *this.buffer_pos = 0;
+        copy_buffered(this.buffer, this.buffer_pos, buf);
+        Poll::Ready(Ok(()))
     }
 }
 
@@ -171,46 +194,55 @@ where
 
 pin_project! {
     /// A reader wrapper that decrypts data on the fly using AES-256-GCM.
-    /// This is a demonstration. For production, use a secure and audited crypto library.
-#[derive(Debug)]
+    ///
+    /// Reads one stream per nonce, one after the other, as multipart objects store them.
+    #[derive(Debug)]
     pub struct DecryptReader<R> {
         #[pin]
         pub inner: R,
-        key: [u8; 32],   // AES-256-GCM key
-        nonce: [u8; 12], // 96-bit nonce for GCM
+        key: [u8; 32],        // AES-256-GCM key
+        nonces: Vec<[u8; 12]>, // 96-bit nonce of every stream
+        stream: usize,
+        block: u64,
+        skip: usize,
         buffer: Vec<u8>,
         buffer_pos: usize,
         finished: bool,
-        // For block framing
-        header_buf: [u8; 8],
-        header_read: usize,
-        header_done: bool,
-        ciphertext_buf: Option<Vec<u8>>,
-        ciphertext_read: usize,
-        ciphertext_len: usize,
+        frame: Vec<u8>,
+        frame_read: usize,
     }
 }
 
 impl<R> DecryptReader<R>
 where
-    R: Reader,
+    R: AsyncRead + Unpin + Send + Sync,
 {
     pub fn new(inner: R, key: [u8; 32], nonce: [u8; 12]) -> Self {
+        Self::new_multipart(inner, key, vec![nonce])
+    }
+
+    /// Decrypt the streams of consecutive parts, each sealed with its nonce
+    pub fn new_multipart(inner: R, key: [u8; 32], nonces: Vec<[u8; 12]>) -> Self {
         Self {
             inner,
             key,
-            nonce,
+            finished: nonces.is_empty(),
+            nonces,
+            stream: 0,
+            block: 0,
+            skip: 0,
             buffer: Vec::new(),
             buffer_pos: 0,
-            finished: false,
-            header_buf: [0u8; 8],
-            header_read: 0,
-            header_done: false,
-            ciphertext_buf: None,
-            ciphertext_read: 0,
-            ciphertext_len: 0,
+            frame: vec![0u8; HEADER_SIZE],
+            frame_read: 0,
         }
     }
+
+    /// Drop the first `offset` bytes of the plaintext, to serve a range of the object
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.skip = offset;
+        self
+    }
 }
 
 impl<R> AsyncRead for DecryptReader<R>
@@ -219,121 +251,79 @@ where
 {
     fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
         let mut this = self.project();
-        // Serve from buffer if any
-        if *this.buffer_pos < this.buffer.len() {
-            let to_copy = std::cmp::min(buf.remaining(), this.buffer.len() - *this.buffer_pos);
-            buf.put_slice(&this.buffer[*this.buffer_pos..*this.buffer_pos + to_copy]);
-            *this.buffer_pos += to_copy;
-            if *this.buffer_pos == this.buffer.len() {
-                this.buffer.clear();
-                *this.buffer_pos = 0;
-            }
-            return Poll::Ready(Ok(()));
-        }
-        if *this.finished {
-            return Poll::Ready(Ok(()));
-        }
-        // Read header (8 bytes), support partial header read
-        while !*this.header_done && *this.header_read < 8 {
-            let mut temp = [0u8; 8];
-            let mut temp_buf = ReadBuf::new(&mut temp[0..8 - *this.header_read]);
-            match this.inner.as_mut().poll_read(cx, &mut temp_buf) {
-                Poll::Pending => return Poll::Pending,
-                Poll::Ready(Ok(())) => {
-                    let n = temp_buf.filled().len();
-                    if n == 0 {
-                        break;
-                    }
-                    this.header_buf[*this.header_read..*this.header_read + n].copy_from_slice(&temp_buf.filled()[..n]);
-                    *this.header_read += n;
-                }
-                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+        loop {
+            if *this.buffer_pos < this.buffer.len() {
+                copy_buffered(this.buffer, this.buffer_pos, buf);
+                return Poll::Ready(Ok(()));
             }
-            if *this.header_read < 8 {
-                return Poll::Pending;
+            if *this.finished {
+                return Poll::Ready(Ok(()));
             }
-        }
-        if !*this.header_done && *this.header_read == 8 {
-            *this.header_done = true;
-        }
-        if !*this.header_done {
-            return Poll::Pending;
-        }
-        let typ = this.header_buf[0];
-        let len = (this.header_buf[1] as usize) | ((this.header_buf[2] as usize) << 8) | ((this.header_buf[3] as usize) << 16);
-        let crc = (this.header_buf[4] as u32)
-            | ((this.header_buf[5] as u32) << 8)
-            | ((this.header_buf[6] as u32) << 16)
-            | ((this.header_buf[7] as u32) << 24);
-        *this.header_read = 0;
-        *this.header_done = false;
-        if typ == 0xFF {
-            *this.finished = true;
-            return Poll::Ready(Ok(()));
-        }
-        // Read ciphertext block (len bytes), support partial read
-        if this.ciphertext_buf.is_none() {
-            *this.ciphertext_len = len - 4; // 4 bytes for CRC32
-            *this.ciphertext_buf = Some(vec![0u8; *this.ciphertext_len]);
-            *this.ciphertext_read = 0;
-        }
-        let ciphertext_buf = this.ciphertext_buf.as_mut().unwrap();
-        while *this.ciphertext_read < *this.ciphertext_len {
-            let mut temp_buf = ReadBuf::new(&mut ciphertext_buf[*this.ciphertext_read..]);
-            match this.inner.as_mut().poll_read(cx, &mut temp_buf) {
-                Poll::Pending => return Poll::Pending,
-                Poll::Ready(Ok(())) => {
-                    let n = temp_buf.filled().len();
-                    if n == 0 {
-                        break;
-                    }
-                    *this.ciphertext_read += n;
+
+            // Read the header, then the body of the frame
+            let frame_len = if *this.frame_read < HEADER_SIZE {
+                HEADER_SIZE
+            } else {
+                HEADER_SIZE + (u32::from_le_bytes([this.frame[1], this.frame[2], this.frame[3], 0]) as usize)
+            };
+            if *this.frame_read < frame_len {
+                if this.frame.len() < frame_len {
+                    this.frame.resize(frame_len, 0);
                 }
-                Poll::Ready(Err(e)) => {
-                    this.ciphertext_buf.take();
-                    *this.ciphertext_read = 0;
-                    *this.ciphertext_len = 0;
-                    return Poll::Ready(Err(e));
+                let mut temp_buf = ReadBuf::new(&mut this.frame[*this.frame_read..frame_len]);
+                match this.inner.as_mut().poll_read(cx, &mut temp_buf) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Ready(Ok(())) => {}
+                }
+                let n = temp_buf.filled().len();
+                if n == 0 {
+                    return Poll::Ready(Err(Error::new(ErrorKind::UnexpectedEof, "encrypted stream is truncated")));
                 }
+                *this.frame_read += n;
+                continue;
+            }
+
+            // A whole frame is read
+            *this.frame_read = 0;
+            if this.frame[0] == BLOCK_END {
+                *this.stream += 1;
+                *this.block = 0;
+                *this.finished = *this.stream == this.nonces.len();
+                continue;
+            }
+            if this.frame[0] != BLOCK_DATA || frame_len == HEADER_SIZE {
+                return Poll::Ready(Err(Error::new(ErrorKind::InvalidData, "invalid encrypted block")));
+            }
+            let crc = u32::from_le_bytes([this.frame[4], this.frame[5], this.frame[6], this.frame[7]]);
+            let body = &this.frame[HEADER_SIZE..frame_len];
+            let (plaintext_len, uvarint_len) = rustfs_utils::uvarint(body);
+            if uvarint_len <= 0 {
+                return Poll::Ready(Err(Error::new(ErrorKind::InvalidData, "invalid encrypted block length")));
+            }
+
+            let cipher = Aes256Gcm::new_from_slice(this.key).map_err(|e| Error::other(format!("decrypt error: {e}")))?;
+            let nonce = block_nonce(&this.nonces[*this.stream], *this.block);
+            let plaintext = cipher
+                .decrypt(Nonce::from_slice(&nonce), &body[uvarint_len as usize..])
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("decrypt error: {e}")))?;
+            *this.block += 1;
+            if plaintext.len() != plaintext_len as usize {
+                return Poll::Ready(Err(Error::new(ErrorKind::InvalidData, "Plaintext length mismatch")));
+            }
+            if crc32fast::hash(&plaintext) != crc {
+                return Poll::Ready(Err(Error::new(ErrorKind::InvalidData, "CRC32 mismatch")));
+            }
+
+            let skipped = std::cmp::min(*this.skip, plaintext.len());
+            *this.skip -= skipped;
+            *this.buffer = plaintext;
+            *this.buffer_pos = skipped;
+            if *this.buffer_pos == this.buffer.len() {
+                this.buffer.clear();
+                *this.buffer_pos = 0;
             }
         }
-        if *this.ciphertext_read < *this.ciphertext_len {
-            return Poll::Pending;
-        }
-        // Parse uvarint for plaintext length
-        let (plaintext_len, uvarint_len) = rustfs_utils::uvarint(&ciphertext_buf[0..16]);
-        let ciphertext = &ciphertext_buf[uvarint_len as usize..];
-
-        // Decrypt
-        let cipher = Aes256Gcm::new_from_slice(this.key).expect("key");
-        let nonce = Nonce::from_slice(this.nonce);
-        let plaintext = cipher
-            .decrypt(nonce, ciphertext)
-            .map_err(|e| std::io::Error::other(format!("decrypt error: {e}")))?;
-        if plaintext.len() != plaintext_len as usize {
-            this.ciphertext_buf.take();
-            *this.ciphertext_read = 0;
-            *this.ciphertext_len = 0;
-            return Poll::Ready(Err(std::io::Error::other("Plaintext length mismatch")));
-        }
-        // CRC32 check
-        let actual_crc = crc32fast::hash(&plaintext);
-        if actual_crc != crc {
-            this.ciphertext_buf.take();
-            *this.ciphertext_read = 0;
-            *this.ciphertext_len = 0;
-            return Poll::Ready(Err(std::io::Error::other("CRC32 mismatch")));
-        }
-        *this.buffer = plaintext;
-        *this.buffer_pos = 0;
-        // Clear block state for next block
-        this.ciphertext_buf.take();
-        *this.ciphertext_read = 0;
-        *this.ciphertext_len = 0;
-        let to_copy = std::cmp::min(buf.remaining(), this.buffer.len());
-        buf.put_slice(&this.buffer[..to_copy]);
-        *this.buffer_pos += to_copy;
-        Poll::Ready(Ok(()))
     }
 }
 
@@ -369,82 +359,104 @@ mod tests {
     use rand::RngCore;
     use tokio::io::{AsyncReadExt, BufReader};
 
-    #[tokio::test]
-    async fn test_encrypt_decrypt_reader_aes256gcm() {
-        let data = b"hello sse encrypt";
+    fn random_key() -> ([u8; 32], [u8; 12]) {
         let mut key = [0u8; 32];
         let mut nonce = [0u8; 12];
         rand::rng().fill_bytes(&mut key);
         rand::rng().fill_bytes(&mut nonce);
+        (key, nonce)
+    }
 
-        let reader = BufReader::new(&data[..]);
-        let encrypt_reader = EncryptReader::new(WarpReader::new(reader), key, nonce);
-
-        // Encrypt
-        let mut encrypt_reader = encrypt_reader;
+    async fn encrypt(data: &[u8], key: [u8; 32], nonce: [u8; 12]) -> Vec<u8> {
+        let mut encrypt_reader = EncryptReader::new(WarpReader::new(Cursor::new(data.to_vec())), key, nonce);
         let mut encrypted = Vec::new();
         encrypt_reader.read_to_end(&mut encrypted).await.unwrap();
-
-        // Decrypt using DecryptReader
-        let reader = Cursor::new(encrypted.clone());
-        let decrypt_reader = DecryptReader::new(WarpReader::new(reader), key, nonce);
-        let mut decrypt_reader = decrypt_reader;
-        let mut decrypted = Vec::new();
-        decrypt_reader.read_to_end(&mut decrypted).await.unwrap();
-
-        assert_eq!(&decrypted, data);
+        encrypted
     }
 
     #[tokio::test]
-    async fn test_decrypt_reader_only() {
-        // Encrypt some data first
-        let data = b"test decrypt only";
-        let mut key = [0u8; 32];
-        let mut nonce = [0u8; 12];
-        rand::rng().fill_bytes(&mut key);
-        rand::rng().fill_bytes(&mut nonce);
+    async fn test_encrypt_decrypt_reader_aes256gcm() {
+        let data = b"hello sse encrypt";
+        let (key, nonce) = random_key();
 
-        // Encrypt
         let reader = BufReader::new(&data[..]);
-        let encrypt_reader = EncryptReader::new(WarpReader::new(reader), key, nonce);
-        let mut encrypt_reader = encrypt_reader;
+        let mut encrypt_reader = EncryptReader::new(WarpReader::new(reader), key, nonce);
         let mut encrypted = Vec::new();
         encrypt_reader.read_to_end(&mut encrypted).await.unwrap();
 
-        // Now test DecryptReader
-
-        let reader = Cursor::new(encrypted.clone());
-        let decrypt_reader = DecryptReader::new(WarpReader::new(reader), key, nonce);
-        let mut decrypt_reader = decrypt_reader;
+        let mut decrypt_reader = DecryptReader::new(WarpReader::new(Cursor::new(encrypted)), key, nonce);
         let mut decrypted = Vec::new();
         decrypt_reader.read_to_end(&mut decrypted).await.unwrap();
 
         assert_eq!(&decrypted, data);
     }
 
+    #[tokio::test]
+    async fn test_decrypt_reader_rejects_tampering() {
+        let (key, nonce) = random_key();
+        let encrypted = encrypt(b"test decrypt only", key, nonce).await;
+
+        let mut wrong_key = key;
+        wrong_key[0] ^= 1;
+        let mut decrypted = Vec::new();
+        let mut reader = DecryptReader::new(Cursor::new(encrypted.clone()), wrong_key, nonce);
+        assert!(reader.read_to_end(&mut decrypted).await.is_err());
+
+        let mut flipped = encrypted.clone();
+        let last = flipped.len() - HEADER_SIZE - 1;
+        flipped[last] ^= 1;
+        let mut reader = DecryptReader::new(Cursor::new(flipped), key, nonce);
+        assert!(reader.read_to_end(&mut Vec::new()).await.is_err());
+
+        // Without its end block the stream is truncated
+        let truncated = encrypted[..encrypted.len() - HEADER_SIZE].to_vec();
+        let mut reader = DecryptReader::new(Cursor::new(truncated), key, nonce);
+        let err = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
     #[tokio::test]
     async fn test_encrypt_decrypt_reader_large() {
         use rand::Rng;
-        let size = 1024 * 1024;
+        let size = 1024 * 1024 + 17;
         let mut data = vec![0u8; size];
         rand::rng().fill(&mut data[..]);
-        let mut key = [0u8; 32];
-        let mut nonce = [0u8; 12];
-        rand::rng().fill_bytes(&mut key);
-        rand::rng().fill_bytes(&mut nonce);
+        let (key, nonce) = random_key();
 
-        let reader = std::io::Cursor::new(data.clone());
-        let encrypt_reader = EncryptReader::new(WarpReader::new(reader), key, nonce);
-        let mut encrypt_reader = encrypt_reader;
-        let mut encrypted = Vec::new();
-        encrypt_reader.read_to_end(&mut encrypted).await.unwrap();
+        let encrypted = encrypt(&data, key, nonce).await;
 
-        let reader = std::io::Cursor::new(encrypted.clone());
-        let decrypt_reader = DecryptReader::new(WarpReader::new(reader), key, nonce);
-        let mut decrypt_reader = decrypt_reader;
+        let mut decrypt_reader = DecryptReader::new(WarpReader::new(Cursor::new(encrypted.clone())), key, nonce);
         let mut decrypted = Vec::new();
         decrypt_reader.read_to_end(&mut decrypted).await.unwrap();
-
         assert_eq!(&decrypted, &data);
+
+        let offset = BLOCK_SIZE + 100;
+        let mut decrypt_reader = DecryptReader::new(Cursor::new(encrypted), key, nonce).with_offset(offset);
+        let mut decrypted = Vec::new();
+        decrypt_reader.read_to_end(&mut decrypted).await.unwrap();
+        assert_eq!(&decrypted, &data[offset..]);
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_multipart_streams() {
+        let (key, nonce) = random_key();
+        let parts: [&[u8]; 3] = [b"first part", b"", b"third part"];
+
+        let mut stored = Vec::new();
+        let mut nonces = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            nonces.push(part_nonce(nonce, i + 1));
+            stored.extend(encrypt(part, key, part_nonce(nonce, i + 1)).await);
+        }
+
+        let mut decrypted = Vec::new();
+        let mut reader = DecryptReader::new_multipart(Cursor::new(stored.clone()), key, nonces.clone());
+        reader.read_to_end(&mut decrypted).await.unwrap();
+        assert_eq!(decrypted, b"first partthird part");
+
+        // Parts decrypted with the nonce of another part fail
+        nonces.swap(0, 2);
+        let mut reader = DecryptReader::new_multipart(Cursor::new(stored), key, nonces);
+        assert!(reader.read_to_end(&mut Vec::new()).await.is_err());
     }
 }
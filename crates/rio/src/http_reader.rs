@@ -13,15 +13,16 @@
 // limitations under the License.
 
 use bytes::Bytes;
-use futures::{Stream, TryStreamExt as _};
+use futures::{Stream, StreamExt as _, TryStreamExt as _};
 use http::HeaderMap;
 use pin_project_lite::pin_project;
 use reqwest::{Client, Method, RequestBuilder};
+use rustfs_utils::node_tls::node_tls;
 use std::error::Error as _;
 use std::io::{self, Error};
 use std::ops::Not as _;
 use std::pin::Pin;
-use std::sync::LazyLock;
+use std::sync::Mutex;
 use std::task::{Context, Poll};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::sync::mpsc;
@@ -32,8 +33,26 @@ use crate::{EtagResolvable, HashReaderDetector, HashReaderMut};
 fn get_http_client() -> Client {
     // Reuse the HTTP connection pool in the global `reqwest::Client` instance
     // TODO: interact with load balancing?
-    static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
-    CLIENT.clone()
+    // The client presents the node certificate and is rebuilt whenever the certificates are reloaded
+    static CLIENT: Mutex<Option<(Option<u64>, Client)>> = Mutex::new(None);
+    let tls = node_tls();
+    let generation = tls.as_ref().map(|tls| tls.generation());
+    let mut client = CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((current, client)) = client.as_ref() {
+        if *current == generation {
+            return client.clone();
+        }
+    }
+
+    let built = match tls {
+        Some(tls) => Client::builder()
+            .use_preconfigured_tls((*tls.client_config()).clone())
+            .build()
+            .unwrap_or_else(|_| Client::new()),
+        None => Client::new(),
+    };
+    *client = Some((generation, built.clone()));
+    built
 }
 
 static HTTP_DEBUG_LOG: bool = false;
@@ -121,6 +140,55 @@ impl HttpReader {
             headers,
         })
     }
+
+    /// Stream the response of a GET to `url` without probing it first
+    ///
+    /// The body is pulled off the connection ahead of the caller by up to `window` chunks, so the
+    /// network transfer overlaps with the consumer instead of waiting on it, while the memory held
+    /// per stream stays bounded. Chunks are handed through as received, without being copied.
+    pub async fn stream(url: String, headers: HeaderMap, window: usize) -> io::Result<Self> {
+        let resp = get_http_client()
+            .get(url.clone())
+            .headers(headers.clone())
+            .send()
+            .await
+            .map_err(|e| Error::other(format!("HttpReader HTTP request error: {e}")))?;
+
+        if resp.status().is_success().not() {
+            return Err(Error::other(format!(
+                "HttpReader HTTP request failed with non-200 status {}",
+                resp.status()
+            )));
+        }
+
+        let (tx, rx) = mpsc::channel(window.max(1));
+        tokio::spawn(async move {
+            let mut body = resp.bytes_stream();
+            loop {
+                let chunk = tokio::select! {
+                    chunk = body.next() => chunk,
+                    // The reader is gone, stop pulling the body
+                    _ = tx.closed() => return,
+                };
+                let Some(chunk) = chunk else {
+                    return;
+                };
+                let chunk = chunk.map_err(|e| Error::other(format!("HttpReader stream error: {e}")));
+                let failed = chunk.is_err();
+                if tx.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Self {
+            inner: StreamReader::new(Box::pin(WindowStream { receiver: rx })),
+            url,
+            method: Method::GET,
+            headers,
+        })
+    }
+
     pub fn url(&self) -> &str {
         &self.url
     }
@@ -164,6 +232,18 @@ impl HashReaderDetector for HttpReader {
     }
 }
 
+/// Chunks read ahead by the task pulling a response body
+struct WindowStream {
+    receiver: mpsc::Receiver<io::Result<Bytes>>,
+}
+
+impl Stream for WindowStream {
+    type Item = io::Result<Bytes>;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
 struct ReceiverStream {
     receiver: mpsc::Receiver<Option<Bytes>>,
 }
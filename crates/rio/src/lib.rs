@@ -24,11 +24,14 @@ mod compress_reader;
 pub use compress_reader::{CompressReader, DecompressReader};
 
 mod encrypt_reader;
-pub use encrypt_reader::{DecryptReader, EncryptReader};
+pub use encrypt_reader::{DecryptReader, EncryptReader, part_nonce};
 
 mod hardlimit_reader;
 pub use hardlimit_reader::HardLimitReader;
 
+mod throttle_reader;
+pub use throttle_reader::{BandwidthLimiter, ThrottleReader};
+
 mod hash_reader;
 pub use hash_reader::*;
 
@@ -41,7 +44,7 @@ pub use writer::*;
 mod http_reader;
 pub use http_reader::*;
 
-pub use compress_index::TryGetIndex;
+pub use compress_index::{Index, TryGetIndex};
 
 mod etag;
 
@@ -0,0 +1,192 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ThrottleReader: paces an AsyncRead to a bandwidth shared with other readers and counts the
+//! bytes read through it.
+
+use pin_project_lite::pin_project;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::Sleep;
+
+use crate::compress_index::{Index, TryGetIndex};
+use crate::{EtagResolvable, HashReaderDetector, HashReaderMut, Reader};
+
+/// Bandwidth shared by any number of readers
+///
+/// Unused bandwidth accumulates for up to one second, so short bursts go through unpaced.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    bytes_per_sec: u64,
+    /// When the bandwidth handed out so far is used up
+    next: Mutex<Instant>,
+}
+
+impl BandwidthLimiter {
+    /// Limiter allowing `bytes_per_sec`, which must not be zero
+    pub fn new(bytes_per_sec: u64) -> Self {
+        assert!(bytes_per_sec > 0, "bandwidth limit must not be zero");
+        Self {
+            bytes_per_sec,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// Take `n` bytes of bandwidth, returning how long to wait before using it
+    pub fn reserve(&self, n: usize) -> Duration {
+        let now = Instant::now();
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let floor = now.checked_sub(Duration::from_secs(1)).unwrap_or(now);
+        let start = (*next).max(floor);
+        *next = start + Duration::from_secs_f64(n as f64 / self.bytes_per_sec as f64);
+        next.saturating_duration_since(now)
+    }
+}
+
+pin_project! {
+    pub struct ThrottleReader<R> {
+        #[pin]
+        inner: R,
+        limiter: Option<Arc<BandwidthLimiter>>,
+        read: Arc<AtomicU64>,
+        delay: Option<Pin<Box<Sleep>>>,
+    }
+}
+
+impl<R> ThrottleReader<R>
+where
+    R: AsyncRead + Unpin + Send + Sync,
+{
+    /// Wrap `inner`, pacing it to `limiter` if any
+    pub fn new(inner: R, limiter: Option<Arc<BandwidthLimiter>>) -> Self {
+        Self {
+            inner,
+            limiter,
+            read: Arc::new(AtomicU64::new(0)),
+            delay: None,
+        }
+    }
+
+    /// Counter of the bytes read so far, readable while the reader is in use
+    pub fn progress(&self) -> Arc<AtomicU64> {
+        self.read.clone()
+    }
+}
+
+impl<R> AsyncRead for ThrottleReader<R>
+where
+    R: AsyncRead + Unpin + Send + Sync,
+{
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        let this = self.project();
+        if let Some(delay) = this.delay.as_mut() {
+            if delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            *this.delay = None;
+        }
+
+        let before = buf.filled().len();
+        let poll = this.inner.poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = &poll {
+            let n = buf.filled().len() - before;
+            this.read.fetch_add(n as u64, Ordering::Relaxed);
+            // The bytes go out now, the next read waits for the bandwidth they used
+            if let Some(limiter) = this.limiter {
+                let wait = limiter.reserve(n);
+                if !wait.is_zero() {
+                    *this.delay = Some(Box::pin(tokio::time::sleep(wait)));
+                }
+            }
+        }
+        poll
+    }
+}
+
+impl<R> EtagResolvable for ThrottleReader<R>
+where
+    R: EtagResolvable,
+{
+    fn try_resolve_etag(&mut self) -> Option<String> {
+        self.inner.try_resolve_etag()
+    }
+}
+
+impl<R> HashReaderDetector for ThrottleReader<R>
+where
+    R: HashReaderDetector,
+{
+    fn is_hash_reader(&self) -> bool {
+        self.inner.is_hash_reader()
+    }
+    fn as_hash_reader_mut(&mut self) -> Option<&mut dyn HashReaderMut> {
+        self.inner.as_hash_reader_mut()
+    }
+}
+
+impl<R> TryGetIndex for ThrottleReader<R>
+where
+    R: TryGetIndex,
+{
+    fn try_get_index(&self) -> Option<&Index> {
+        self.inner.try_get_index()
+    }
+}
+
+impl<R> Reader for ThrottleReader<R> where R: Reader {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_throttle_reader_paces_shared_bandwidth() {
+        let limiter = Arc::new(BandwidthLimiter::new(64 * 1024));
+        let data = vec![7u8; 64 * 1024];
+        let mut first = ThrottleReader::new(&data[..], Some(limiter.clone()));
+        let mut second = ThrottleReader::new(&data[..], Some(limiter));
+        let (first_read, second_read) = (first.progress(), second.progress());
+
+        // The first second of bandwidth is a free burst, the second reader waits out the rest
+        let start = Instant::now();
+        let mut buf = Vec::new();
+        first.read_to_end(&mut buf).await.unwrap();
+        second.read_to_end(&mut buf).await.unwrap();
+        let elapsed = start.elapsed();
+
+        assert_eq!(buf.len(), 2 * data.len());
+        assert_eq!(first_read.load(Ordering::Relaxed), data.len() as u64);
+        assert_eq!(second_read.load(Ordering::Relaxed), data.len() as u64);
+        assert!(elapsed >= Duration::from_millis(900), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(3), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_throttle_reader_unlimited() {
+        let data = vec![1u8; 1024 * 1024];
+        let mut reader = ThrottleReader::new(&data[..], None);
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(reader.progress().load(Ordering::Relaxed), data.len() as u64);
+    }
+}
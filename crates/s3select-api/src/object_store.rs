@@ -21,6 +21,7 @@ use futures_core::stream::BoxStream;
 use http::HeaderMap;
 use object_store::Attributes;
 use object_store::GetOptions;
+use object_store::GetRange;
 use object_store::GetResult;
 use object_store::ListResult;
 use object_store::MultipartUpload;
@@ -38,12 +39,12 @@ use rustfs_ecstore::StorageAPI;
 use rustfs_ecstore::new_object_layer_fn;
 use rustfs_ecstore::set_disk::DEFAULT_READ_BUFFER_SIZE;
 use rustfs_ecstore::store::ECStore;
+use rustfs_ecstore::store_api::HTTPRangeSpec;
 use rustfs_ecstore::store_api::ObjectIO;
 use rustfs_ecstore::store_api::ObjectOptions;
 use s3s::S3Result;
 use s3s::dto::SelectObjectContentInput;
 use s3s::s3_error;
-use std::ops::Range;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::Poll;
@@ -106,23 +107,34 @@ impl ObjectStore for EcObjectStore {
         unimplemented!()
     }
 
-    async fn get_opts(&self, location: &Path, _options: GetOptions) -> Result<GetResult> {
-        info!("{:?}", location);
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        info!("{:?} {:?}", location, options.range);
         let opts = ObjectOptions::default();
         let h = HeaderMap::new();
+        // Parquet readers fetch the footer and the selected row groups by range
+        let range = options.range.as_ref().map(range_spec);
         let reader = self
             .store
-            .get_object_reader(&self.input.bucket, &self.input.key, None, h, &opts)
+            .get_object_reader(&self.input.bucket, &self.input.key, range.clone(), h, &opts)
             .await
             .map_err(|_| o_Error::NotFound {
                 path: format!("{}/{}", self.input.bucket, self.input.key),
                 source: "can not get object info".into(),
             })?;
 
+        let size = reader.object_info.size;
+        let (offset, length) = match range {
+            Some(rs) => rs.get_offset_length(size).map_err(|e| o_Error::Generic {
+                store: "EcObjectStore",
+                source: Box::new(e),
+            })?,
+            None => (0, size),
+        };
+
         let meta = ObjectMeta {
             location: location.clone(),
             last_modified: Utc::now(),
-            size: reader.object_info.size as usize,
+            size: size as usize,
             e_tag: reader.object_info.etag,
             version: None,
         };
@@ -135,31 +147,23 @@ impl ObjectStore for EcObjectStore {
                         ConvertStream::new(reader.stream, self.delimiter.clone()),
                         DEFAULT_READ_BUFFER_SIZE,
                     ),
-                    reader.object_info.size as usize,
+                    length as usize,
                 )
                 .boxed(),
             )
         } else {
             object_store::GetResultPayload::Stream(
-                bytes_stream(
-                    ReaderStream::with_capacity(reader.stream, DEFAULT_READ_BUFFER_SIZE),
-                    reader.object_info.size as usize,
-                )
-                .boxed(),
+                bytes_stream(ReaderStream::with_capacity(reader.stream, DEFAULT_READ_BUFFER_SIZE), length as usize).boxed(),
             )
         };
         Ok(GetResult {
             payload,
             meta,
-            range: 0..reader.object_info.size as usize,
+            range: offset..offset + length as usize,
             attributes,
         })
     }
 
-    async fn get_ranges(&self, _location: &Path, _ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
-        unimplemented!()
-    }
-
     async fn head(&self, location: &Path) -> Result<ObjectMeta> {
         info!("{:?}", location);
         let opts = ObjectOptions::default();
@@ -202,6 +206,26 @@ impl ObjectStore for EcObjectStore {
     }
 }
 
+fn range_spec(range: &GetRange) -> HTTPRangeSpec {
+    match range {
+        GetRange::Bounded(r) => HTTPRangeSpec {
+            is_suffix_length: false,
+            start: r.start as i64,
+            end: r.end as i64 - 1,
+        },
+        GetRange::Offset(offset) => HTTPRangeSpec {
+            is_suffix_length: false,
+            start: *offset as i64,
+            end: -1,
+        },
+        GetRange::Suffix(length) => HTTPRangeSpec {
+            is_suffix_length: true,
+            start: *length as i64,
+            end: -1,
+        },
+    }
+}
+
 pin_project! {
     struct ConvertStream<R> {
         inner: R,
@@ -274,7 +298,17 @@ where
 
 #[cfg(test)]
 mod test {
-    use super::replace_symbol;
+    use super::{range_spec, replace_symbol};
+    use object_store::GetRange;
+
+    #[test]
+    fn test_range_spec() {
+        // Footer and row group reads of a 1000 byte Parquet object
+        assert_eq!(range_spec(&GetRange::Suffix(8)).get_offset_length(1000).unwrap(), (992, 8));
+        assert_eq!(range_spec(&GetRange::Bounded(4..104)).get_offset_length(1000).unwrap(), (4, 100));
+        assert_eq!(range_spec(&GetRange::Offset(900)).get_offset_length(1000).unwrap(), (900, 100));
+        assert!(range_spec(&GetRange::Offset(1000)).get_offset_length(1000).is_err());
+    }
 
     #[test]
     fn test_replace() {
@@ -25,6 +25,7 @@ use datafusion::{
         datatypes::{Schema, SchemaRef},
         record_batch::RecordBatch,
     },
+    config::TableParquetOptions,
     datasource::{
         file_format::{csv::CsvFormat, json::JsonFormat, parquet::ParquetFormat},
         listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl},
@@ -214,7 +215,11 @@ impl SimpleQueryDispatcher {
                     need_ignore_volume_name,
                 )
             } else if self.input.request.input_serialization.parquet.is_some() {
-                let file_format = ParquetFormat::new();
+                // Row groups are pruned with their statistics, filters are also applied while decoding
+                let mut options = TableParquetOptions::default();
+                options.global.pushdown_filters = true;
+                options.global.reorder_filters = true;
+                let file_format = ParquetFormat::new().with_options(options);
                 (ListingOptions::new(Arc::new(file_format)).with_file_extension(".parquet"), false, false)
             } else if self.input.request.input_serialization.json.is_some() {
                 let file_format = JsonFormat::default();
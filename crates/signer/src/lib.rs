@@ -13,12 +13,14 @@
 // limitations under the License.
 
 pub mod constants;
+pub mod presign_constraints;
 pub mod request_signature_streaming;
 pub mod request_signature_streaming_unsigned_trailer;
 pub mod request_signature_v2;
 pub mod request_signature_v4;
 pub mod utils;
 
+pub use presign_constraints::{PresignConstraints, pre_sign_v4_with_constraints};
 pub use request_signature_streaming::streaming_sign_v4;
 pub use request_signature_v2::pre_sign_v2;
 pub use request_signature_v2::sign_v2;
@@ -0,0 +1,222 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Constraints of a pre-signed PUT URL.
+//!
+//! The constraints travel as query parameters of the URL. The query is part of the canonical
+//! request, so whoever holds the URL cannot loosen them without breaking the signature, and the
+//! server rejects uploads that do not meet them.
+
+use http::{Uri, request};
+use s3s::Body;
+use time::OffsetDateTime;
+
+use super::request_signature_v4::pre_sign_v4;
+
+/// Query parameter holding the only content type the upload may have
+pub const QUERY_CONTENT_TYPE: &str = "X-Rustfs-Content-Type";
+/// Query parameter holding the allowed content lengths of the upload, as `min,max` in bytes
+pub const QUERY_CONTENT_LENGTH_RANGE: &str = "X-Rustfs-Content-Length-Range";
+/// Query parameter holding the prefix the object key must start with
+pub const QUERY_KEY_PREFIX: &str = "X-Rustfs-Key-Prefix";
+
+/// Conditions an upload through a pre-signed URL must meet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PresignConstraints {
+    pub content_type: Option<String>,
+    /// Inclusive bounds of the content length
+    pub content_length_range: Option<(u64, u64)>,
+    pub key_prefix: Option<String>,
+}
+
+/// Why an upload was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstraintViolation {
+    Malformed(String),
+    ContentType,
+    MissingContentLength,
+    TooSmall,
+    TooLarge,
+    KeyPrefix,
+}
+
+impl std::fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(param) => write!(f, "malformed pre-signed URL constraint {param}"),
+            Self::ContentType => write!(f, "content type is not allowed by the pre-signed URL"),
+            Self::MissingContentLength => write!(f, "the pre-signed URL requires a content length"),
+            Self::TooSmall => write!(f, "content is smaller than the pre-signed URL allows"),
+            Self::TooLarge => write!(f, "content is larger than the pre-signed URL allows"),
+            Self::KeyPrefix => write!(f, "object key is outside the prefix of the pre-signed URL"),
+        }
+    }
+}
+
+impl PresignConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.content_type.is_none() && self.content_length_range.is_none() && self.key_prefix.is_none()
+    }
+
+    fn query_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if let Some(content_type) = &self.content_type {
+            params.push((QUERY_CONTENT_TYPE, content_type.clone()));
+        }
+        if let Some((min, max)) = self.content_length_range {
+            params.push((QUERY_CONTENT_LENGTH_RANGE, format!("{min},{max}")));
+        }
+        if let Some(prefix) = &self.key_prefix {
+            params.push((QUERY_KEY_PREFIX, prefix.clone()));
+        }
+        params
+    }
+
+    /// Read the constraints from the query of a request
+    pub fn from_query(query: Option<&str>) -> Result<Self, ConstraintViolation> {
+        let mut constraints = Self::default();
+        let pairs = serde_urlencoded::from_str::<Vec<(String, String)>>(query.unwrap_or_default())
+            .map_err(|_| ConstraintViolation::Malformed("query".to_string()))?;
+        for (key, value) in pairs {
+            if key.eq_ignore_ascii_case(QUERY_CONTENT_TYPE) {
+                constraints.content_type = Some(value);
+            } else if key.eq_ignore_ascii_case(QUERY_CONTENT_LENGTH_RANGE) {
+                let range = value
+                    .split_once(',')
+                    .and_then(|(min, max)| Some((min.trim().parse().ok()?, max.trim().parse().ok()?)))
+                    .filter(|(min, max)| min <= max)
+                    .ok_or_else(|| ConstraintViolation::Malformed(QUERY_CONTENT_LENGTH_RANGE.to_string()))?;
+                constraints.content_length_range = Some(range);
+            } else if key.eq_ignore_ascii_case(QUERY_KEY_PREFIX) {
+                constraints.key_prefix = Some(value);
+            }
+        }
+        Ok(constraints)
+    }
+
+    /// Check an upload of `content_length` bytes of `content_type` to `key`
+    pub fn check(&self, key: &str, content_type: Option<&str>, content_length: Option<u64>) -> Result<(), ConstraintViolation> {
+        if let Some(expected) = &self.content_type {
+            if content_type.is_none_or(|content_type| !content_type.eq_ignore_ascii_case(expected)) {
+                return Err(ConstraintViolation::ContentType);
+            }
+        }
+        if let Some((min, max)) = self.content_length_range {
+            let length = content_length.ok_or(ConstraintViolation::MissingContentLength)?;
+            if length < min {
+                return Err(ConstraintViolation::TooSmall);
+            }
+            if length > max {
+                return Err(ConstraintViolation::TooLarge);
+            }
+        }
+        if let Some(prefix) = &self.key_prefix {
+            if !key.starts_with(prefix.as_str()) {
+                return Err(ConstraintViolation::KeyPrefix);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the constraints to the query of `req`, before it is signed
+    pub fn apply(&self, mut req: request::Request<Body>) -> request::Request<Body> {
+        if self.is_empty() {
+            return req;
+        }
+        let mut query = req
+            .uri()
+            .query()
+            .and_then(|q| serde_urlencoded::from_str::<Vec<(String, String)>>(q).ok())
+            .unwrap_or_default();
+        query.extend(self.query_params().into_iter().map(|(k, v)| (k.to_string(), v)));
+
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = Some(
+            format!("{}?{}", req.uri().path(), serde_urlencoded::to_string(&query).unwrap())
+                .parse()
+                .unwrap(),
+        );
+        *req.uri_mut() = Uri::from_parts(parts).unwrap();
+        req
+    }
+}
+
+/// Pre-sign a PUT request whose upload must meet `constraints`
+#[allow(clippy::too_many_arguments)]
+pub fn pre_sign_v4_with_constraints(
+    req: request::Request<Body>,
+    access_key_id: &str,
+    secret_access_key: &str,
+    session_token: &str,
+    location: &str,
+    expires: i64,
+    t: OffsetDateTime,
+    constraints: &PresignConstraints,
+) -> request::Request<Body> {
+    pre_sign_v4(
+        constraints.apply(req),
+        access_key_id,
+        secret_access_key,
+        session_token,
+        location,
+        expires,
+        t,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presign_constraints() {
+        let constraints = PresignConstraints {
+            content_type: Some("image/png".to_string()),
+            content_length_range: Some((1, 1024)),
+            key_prefix: Some("uploads/".to_string()),
+        };
+        let req = request::Request::builder()
+            .method(http::Method::PUT)
+            .uri("http://localhost:9000/bucket/uploads/a.png?x-id=PutObject")
+            .body(Body::empty())
+            .unwrap();
+        let req = constraints.apply(req);
+        assert_eq!(PresignConstraints::from_query(req.uri().query()), Ok(constraints.clone()));
+
+        assert_eq!(constraints.check("uploads/a.png", Some("image/png"), Some(10)), Ok(()));
+        assert_eq!(
+            constraints.check("uploads/a.png", Some("text/html"), Some(10)),
+            Err(ConstraintViolation::ContentType)
+        );
+        assert_eq!(
+            constraints.check("uploads/a.png", Some("image/png"), None),
+            Err(ConstraintViolation::MissingContentLength)
+        );
+        assert_eq!(
+            constraints.check("uploads/a.png", Some("image/png"), Some(0)),
+            Err(ConstraintViolation::TooSmall)
+        );
+        assert_eq!(
+            constraints.check("uploads/a.png", Some("image/png"), Some(2048)),
+            Err(ConstraintViolation::TooLarge)
+        );
+        assert_eq!(
+            constraints.check("other/a.png", Some("image/png"), Some(10)),
+            Err(ConstraintViolation::KeyPrefix)
+        );
+
+        assert!(PresignConstraints::from_query(None).unwrap().is_empty());
+        assert!(PresignConstraints::from_query(Some("X-Rustfs-Content-Length-Range=10,1")).is_err());
+    }
+}
@@ -56,6 +56,14 @@ pub fn hmac_sha256(key: impl AsRef<[u8]>, data: impl AsRef<[u8]>) -> [u8; 32] {
     m.finalize().into_bytes().into()
 }
 
+/// HKDF-SHA256 (RFC 5869) of `ikm` with `salt`, the 32 byte key for the use named by `info`
+pub fn hkdf_sha256(salt: impl AsRef<[u8]>, ikm: impl AsRef<[u8]>, info: impl AsRef<[u8]>) -> [u8; 32] {
+    let prk = hmac_sha256(salt, ikm);
+    let mut block = info.as_ref().to_vec();
+    block.push(1);
+    hmac_sha256(prk, block)
+}
+
 /// `f(hex(src))`
 fn hex_bytes32<R>(src: impl AsRef<[u8]>, f: impl FnOnce(&str) -> R) -> R {
     let buf: &mut [_] = &mut [MaybeUninit::uninit(); 64];
@@ -36,6 +36,10 @@ pub enum HashAlgorithm {
     Md5,
     /// No hash (for testing or unprotected data)
     None,
+    /// BLAKE3 (256-bit)
+    Blake3,
+    /// CRC32C (Castagnoli), detects corruption only, for low-power edge devices
+    Crc32c,
 }
 
 enum HashEncoded {
@@ -44,6 +48,8 @@ enum HashEncoded {
     HighwayHash256([u8; 32]),
     HighwayHash256S([u8; 32]),
     Blake2b512(blake3::Hash),
+    Blake3(blake3::Hash),
+    Crc32c([u8; 4]),
     None,
 }
 
@@ -56,6 +62,8 @@ impl AsRef<[u8]> for HashEncoded {
             HashEncoded::HighwayHash256(hash) => hash.as_ref(),
             HashEncoded::HighwayHash256S(hash) => hash.as_ref(),
             HashEncoded::Blake2b512(hash) => hash.as_bytes(),
+            HashEncoded::Blake3(hash) => hash.as_bytes(),
+            HashEncoded::Crc32c(hash) => hash.as_ref(),
             HashEncoded::None => &[],
         }
     }
@@ -87,6 +95,11 @@ impl HashAlgorithm {
                 HashEncoded::HighwayHash256S(u8x32_from_u64x4(hasher.finalize256()))
             }
             HashAlgorithm::BLAKE2b512 => HashEncoded::Blake2b512(blake3::hash(data)),
+            HashAlgorithm::Blake3 => HashEncoded::Blake3(blake3::hash(data)),
+            HashAlgorithm::Crc32c => {
+                let crc = crc_fast::checksum(crc_fast::CrcAlgorithm::Crc32Iscsi, data) as u32;
+                HashEncoded::Crc32c(crc.to_be_bytes())
+            }
             HashAlgorithm::None => HashEncoded::None,
         }
     }
@@ -100,6 +113,8 @@ impl HashAlgorithm {
             HashAlgorithm::BLAKE2b512 => 32, // blake3 outputs 32 bytes by default
             HashAlgorithm::Md5 => 16,
             HashAlgorithm::None => 0,
+            HashAlgorithm::Blake3 => 32,
+            HashAlgorithm::Crc32c => 4,
         }
     }
 }
@@ -140,6 +155,15 @@ mod tests {
         assert_eq!(HashAlgorithm::SHA256.size(), 32);
         assert_eq!(HashAlgorithm::BLAKE2b512.size(), 32);
         assert_eq!(HashAlgorithm::None.size(), 0);
+        assert_eq!(HashAlgorithm::Blake3.size(), 32);
+        assert_eq!(HashAlgorithm::Crc32c.size(), 4);
+    }
+
+    #[test]
+    fn test_hash_encode_crc32c() {
+        // Standard CRC32C check value
+        let hash = HashAlgorithm::Crc32c.hash_encode(b"123456789");
+        assert_eq!(hash.as_ref(), &[0xe3, 0x06, 0x92, 0x83]);
     }
 
     #[test]
@@ -18,6 +18,8 @@ pub mod certs;
 pub mod ip;
 #[cfg(feature = "net")]
 pub mod net;
+#[cfg(feature = "tls")]
+pub mod node_tls;
 
 #[cfg(feature = "net")]
 pub use net::*;
@@ -0,0 +1,201 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mutual TLS between the nodes of a cluster.
+//!
+//! When the TLS directory holds a `node_ca.pem`, nodes authenticate each other on their RPC
+//! connections. A node dials its peers presenting `node_cert.pem` and only trusts servers whose
+//! certificate is signed by the node CA and valid for the host it dials. It accepts lock and
+//! storage RPCs only from clients presenting a certificate signed by the node CA and valid for
+//! one of the cluster hosts. The files are checked for changes at most every
+//! `RELOAD_CHECK_INTERVAL` and reloaded, so certificates rotate without a restart; established
+//! connections keep the certificates they were set up with.
+
+use crate::{certs_error, load_certs, load_private_key};
+use rustfs_config::{RUSTFS_TLS_NODE_CA, RUSTFS_TLS_NODE_CERT, RUSTFS_TLS_NODE_KEY};
+use rustls::crypto::CryptoProvider;
+use rustls::server::danger::ClientCertVerifier;
+use rustls::server::{ParsedCertificate, WebPkiClientVerifier};
+use rustls::{ClientConfig, RootCertStore};
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+use std::{fs, io};
+use tracing::{info, warn};
+
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+const NODE_TLS_FILES: [&str; 3] = [RUSTFS_TLS_NODE_CA, RUSTFS_TLS_NODE_CERT, RUSTFS_TLS_NODE_KEY];
+
+static NODE_TLS: Mutex<Option<NodeTlsState>> = Mutex::new(None);
+
+/// Certificates this node authenticates itself and its peers with
+#[derive(Debug)]
+pub struct NodeTls {
+    client_config: Arc<ClientConfig>,
+    verifier: Arc<dyn ClientCertVerifier>,
+    hosts: Vec<String>,
+    generation: u64,
+}
+
+struct NodeTlsState {
+    dir: PathBuf,
+    modified: Vec<Option<SystemTime>>,
+    checked: Instant,
+    current: Arc<NodeTls>,
+}
+
+impl NodeTls {
+    fn load(dir: &Path, hosts: Vec<String>, generation: u64) -> io::Result<Self> {
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+
+        let mut roots = RootCertStore::empty();
+        for cert in load_certs(&path(RUSTFS_TLS_NODE_CA))? {
+            roots.add(cert).map_err(|e| certs_error(e.to_string()))?;
+        }
+        let roots = Arc::new(roots);
+        let certs = load_certs(&path(RUSTFS_TLS_NODE_CERT))?;
+        let key = load_private_key(&path(RUSTFS_TLS_NODE_KEY))?;
+
+        let client_config = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| certs_error(e.to_string()))?
+            .with_root_certificates(roots.clone())
+            .with_client_auth_cert(certs, key)
+            .map_err(|e| certs_error(e.to_string()))?;
+        // S3 clients share the listener with the peers and present no certificate
+        let verifier = WebPkiClientVerifier::builder_with_provider(roots, provider)
+            .allow_unauthenticated()
+            .build()
+            .map_err(|e| certs_error(e.to_string()))?;
+
+        Ok(Self {
+            client_config: Arc::new(client_config),
+            verifier,
+            hosts,
+            generation,
+        })
+    }
+
+    /// Configuration of connections to peers, without ALPN protocols
+    pub fn client_config(&self) -> Arc<ClientConfig> {
+        self.client_config.clone()
+    }
+
+    /// Verifier accepting client certificates signed by the node CA, as well as clients without one
+    pub fn client_verifier(&self) -> Arc<dyn ClientCertVerifier> {
+        self.verifier.clone()
+    }
+
+    /// Incremented every time the certificates are reloaded
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether `certs` is signed by the node CA and its leaf is valid for one of the cluster hosts
+    pub fn is_node_certificate(&self, certs: &[CertificateDer<'_>]) -> bool {
+        let Some((leaf, intermediates)) = certs.split_first() else {
+            return false;
+        };
+        if self
+            .verifier
+            .verify_client_cert(leaf, intermediates, UnixTime::now())
+            .is_err()
+        {
+            return false;
+        }
+        let Ok(leaf) = ParsedCertificate::try_from(leaf) else {
+            return false;
+        };
+        self.hosts.iter().any(|host| {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            ServerName::try_from(host).is_ok_and(|name| rustls::client::verify_server_name(&leaf, &name).is_ok())
+        })
+    }
+}
+
+fn lock() -> MutexGuard<'static, Option<NodeTlsState>> {
+    NODE_TLS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn modified(dir: &Path) -> Vec<Option<SystemTime>> {
+    NODE_TLS_FILES
+        .iter()
+        .map(|name| fs::metadata(dir.join(name)).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Load the node certificates from the TLS directory, for a cluster made of `hosts`
+///
+/// Returns whether node TLS is enabled, which it is when the directory holds a node CA.
+pub fn init_node_tls(tls_path: &str, hosts: Vec<String>) -> io::Result<bool> {
+    let dir = Path::new(tls_path);
+    if tls_path.is_empty() || !dir.join(RUSTFS_TLS_NODE_CA).exists() {
+        return Ok(false);
+    }
+
+    let _ = CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider());
+    let current = Arc::new(NodeTls::load(dir, hosts, 0)?);
+    *lock() = Some(NodeTlsState {
+        dir: dir.to_path_buf(),
+        modified: modified(dir),
+        checked: Instant::now(),
+        current,
+    });
+    info!("node TLS enabled, peers authenticate with certificates signed by {}", RUSTFS_TLS_NODE_CA);
+    Ok(true)
+}
+
+/// The node certificates, reloaded first if their files changed, or `None` when node TLS is disabled
+pub fn node_tls() -> Option<Arc<NodeTls>> {
+    let mut guard = lock();
+    let state = guard.as_mut()?;
+    if state.checked.elapsed() >= RELOAD_CHECK_INTERVAL {
+        state.checked = Instant::now();
+        let modified = modified(&state.dir);
+        if modified != state.modified {
+            // A rotation caught halfway through fails to load and is retried at the next check
+            match NodeTls::load(&state.dir, state.current.hosts.clone(), state.current.generation + 1) {
+                Ok(tls) => {
+                    info!("reloaded node certificates from {}", state.dir.display());
+                    state.current = Arc::new(tls);
+                    state.modified = modified;
+                }
+                Err(e) => warn!("failed to reload node certificates, keeping the previous ones: {}", e),
+            }
+        }
+    }
+    Some(state.current.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_init_node_tls() {
+        let dir = TempDir::new().unwrap();
+        let tls_path = dir.path().to_str().unwrap();
+        assert!(!init_node_tls("", Vec::new()).unwrap());
+        assert!(!init_node_tls(tls_path, Vec::new()).unwrap());
+
+        // A node CA without a usable node certificate is a configuration error
+        fs::write(dir.path().join(RUSTFS_TLS_NODE_CA), "not a certificate").unwrap();
+        assert!(init_node_tls(tls_path, Vec::new()).is_err());
+        assert!(node_tls().is_none());
+    }
+}
@@ -12,71 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use futures::Stream;
 use hyper::http;
-use std::{
-    pin::Pin,
-    sync::LazyLock,
-    task::{Context, Poll},
-    time::Duration,
-};
-use tokio::time::interval;
+use std::{sync::LazyLock, time::Duration};
 
 pub const MAX_RETRY: i64 = 10;
 pub const MAX_JITTER: f64 = 1.0;
-pub const NO_JITTER: f64 = 0.0;
 
 pub const DEFAULT_RETRY_UNIT: Duration = Duration::from_millis(200);
 pub const DEFAULT_RETRY_CAP: Duration = Duration::from_secs(1);
 
-pub struct RetryTimer {
-    base_sleep: Duration,
-    max_sleep: Duration,
-    jitter: f64,
-    random: u64,
-    rem: i64,
-}
-
-impl RetryTimer {
-    pub fn new(max_retry: i64, base_sleep: Duration, max_sleep: Duration, jitter: f64, random: u64) -> Self {
-        Self {
-            base_sleep,
-            max_sleep,
-            jitter,
-            random,
-            rem: max_retry,
-        }
-    }
-}
-
-impl Stream for RetryTimer {
-    type Item = ();
-
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
-        let jitter = self.jitter.clamp(NO_JITTER, MAX_JITTER);
-
-        let attempt = MAX_RETRY - self.rem;
-        let mut sleep = self.base_sleep * (1 << attempt);
-        if sleep > self.max_sleep {
-            sleep = self.max_sleep;
-        }
-        if (jitter - NO_JITTER).abs() > 1e-9 {
-            sleep -= sleep * self.random as u32 * jitter as u32;
-        }
-
-        if self.rem == 0 {
-            return Poll::Ready(None);
-        }
-
-        self.rem -= 1;
-        let mut t = interval(sleep);
-        match t.poll_tick(cx) {
-            Poll::Ready(_) => Poll::Ready(Some(())),
-            Poll::Pending => Poll::Pending,
-        }
-    }
-}
-
 static RETRYABLE_S3CODES: LazyLock<Vec<String>> = LazyLock::new(|| {
     vec![
         "RequestError".to_string(),
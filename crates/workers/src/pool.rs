@@ -0,0 +1,232 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Task pools separating background subsystems from request handling.
+//!
+//! Requests are served by the runtime the server starts on, the foreground pool. Scanner, heal
+//! and replication run on a runtime of their own with a bounded number of worker threads, so a
+//! burst of background work queues behind those threads instead of delaying requests. Tasks a
+//! subsystem spawns with `tokio::spawn` stay on the pool it was started on.
+
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::Meter;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::runtime::{Builder, Handle, Runtime};
+use tokio::task::{JoinError, JoinHandle};
+use tracing::info;
+
+/// Environment variable holding the number of worker threads of the background pool
+pub const ENV_BACKGROUND_WORKERS: &str = "RUSTFS_BACKGROUND_WORKERS";
+
+pub const TASK_POOL_WORKERS: &str = "task_pool.workers";
+pub const TASK_POOL_ALIVE_TASKS: &str = "task_pool.alive_tasks";
+pub const TASK_POOL_QUEUE_DEPTH: &str = "task_pool.queue_depth";
+pub const TASK_POOL_SPAWNED_TASKS: &str = "task_pool.spawned_tasks";
+
+static FOREGROUND: OnceLock<TaskPool> = OnceLock::new();
+static BACKGROUND: OnceLock<TaskPool> = OnceLock::new();
+
+/// Point in time view of a task pool
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub name: &'static str,
+    /// Worker threads of the pool
+    pub workers: usize,
+    /// Tasks spawned on the pool that have not completed yet
+    pub alive_tasks: usize,
+    /// Tasks waiting in the shared queue for a worker
+    pub queue_depth: usize,
+    /// Tasks spawned through the pool since it started
+    pub spawned: u64,
+}
+
+/// A tokio runtime that tasks of one class are spawned on
+pub struct TaskPool {
+    name: &'static str,
+    handle: Handle,
+    spawned: AtomicU64,
+    // Owned runtimes live in statics and are never dropped, a foreground pool borrows the server's
+    _runtime: Option<Runtime>,
+}
+
+impl TaskPool {
+    fn new(name: &'static str, handle: Handle, runtime: Option<Runtime>) -> Self {
+        Self {
+            name,
+            handle,
+            spawned: AtomicU64::new(0),
+            _runtime: runtime,
+        }
+    }
+
+    /// Build a pool on a runtime of its own with `workers` threads
+    pub fn with_workers(name: &'static str, workers: usize) -> std::io::Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(workers.max(1))
+            .thread_name(format!("rustfs-{name}"))
+            .enable_all()
+            .build()?;
+        Ok(Self::new(name, runtime.handle().clone(), Some(runtime)))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Spawn a task on the pool
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawned.fetch_add(1, Ordering::Relaxed);
+        self.handle.spawn(future)
+    }
+
+    /// Run `future` on the pool and wait for its output
+    ///
+    /// Tasks the future spawns with `tokio::spawn` run on the pool as well, which is how a
+    /// subsystem is started on it.
+    pub async fn run<F>(&self, future: F) -> Result<F::Output, JoinError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn(future).await
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let metrics = self.handle.metrics();
+        PoolStats {
+            name: self.name,
+            workers: metrics.num_workers(),
+            alive_tasks: metrics.num_alive_tasks(),
+            queue_depth: metrics.global_queue_depth(),
+            spawned: self.spawned.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn default_background_workers() -> usize {
+    std::env::var(ENV_BACKGROUND_WORKERS)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or_else(|| (num_cpus() / 4).max(2))
+}
+
+fn num_cpus() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Register the runtime of the calling task as the foreground pool
+///
+/// Must be called from within the runtime serving requests, later calls are ignored.
+pub fn init_foreground() -> &'static TaskPool {
+    FOREGROUND.get_or_init(|| TaskPool::new("foreground", Handle::current(), None))
+}
+
+/// Pool of the background subsystems, built on first use
+pub fn background() -> &'static TaskPool {
+    BACKGROUND.get_or_init(|| {
+        let workers = default_background_workers();
+        info!("starting background task pool with {} workers", workers);
+        TaskPool::with_workers("background", workers).expect("failed to build background task pool")
+    })
+}
+
+/// Stats of every pool started so far
+pub fn pool_stats() -> Vec<PoolStats> {
+    [FOREGROUND.get(), BACKGROUND.get()]
+        .into_iter()
+        .flatten()
+        .map(TaskPool::stats)
+        .collect()
+}
+
+/// Export the stats of the task pools as gauges and counters labeled by pool
+pub fn register_metrics(meter: &Meter) {
+    meter
+        .u64_observable_gauge(TASK_POOL_WORKERS)
+        .with_description("Worker threads of the task pool.")
+        .with_callback(|observer| {
+            for stats in pool_stats() {
+                observer.observe(stats.workers as u64, &[KeyValue::new("pool", stats.name)]);
+            }
+        })
+        .build();
+
+    meter
+        .u64_observable_gauge(TASK_POOL_ALIVE_TASKS)
+        .with_description("Tasks of the task pool that have not completed.")
+        .with_callback(|observer| {
+            for stats in pool_stats() {
+                observer.observe(stats.alive_tasks as u64, &[KeyValue::new("pool", stats.name)]);
+            }
+        })
+        .build();
+
+    meter
+        .u64_observable_gauge(TASK_POOL_QUEUE_DEPTH)
+        .with_description("Tasks of the task pool waiting for a worker.")
+        .with_callback(|observer| {
+            for stats in pool_stats() {
+                observer.observe(stats.queue_depth as u64, &[KeyValue::new("pool", stats.name)]);
+            }
+        })
+        .build();
+
+    meter
+        .u64_observable_counter(TASK_POOL_SPAWNED_TASKS)
+        .with_description("Tasks spawned through the task pool.")
+        .with_callback(|observer| {
+            for stats in pool_stats() {
+                observer.observe(stats.spawned, &[KeyValue::new("pool", stats.name)]);
+            }
+        })
+        .build();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_keeps_spawned_tasks_on_pool() {
+        let pool = TaskPool::with_workers("test", 1).unwrap();
+        let thread = pool
+            .run(async {
+                tokio::spawn(async { std::thread::current().name().map(str::to_string) })
+                    .await
+                    .unwrap()
+            })
+            .await
+            .unwrap();
+        assert_eq!(thread.as_deref(), Some("rustfs-test"));
+
+        let stats = pool.stats();
+        assert_eq!(stats.name, "test");
+        assert_eq!(stats.workers, 1);
+        assert_eq!(stats.spawned, 1);
+
+        // Owned runtimes must not be dropped from async context
+        std::mem::forget(pool);
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Authorization of admin API requests
+
+use std::collections::HashMap;
+
+use rustfs_policy::auth::Credentials;
+use rustfs_policy::policy::Args;
+use rustfs_policy::policy::action::{Action, AdminAction};
+use s3s::{Body, S3Request, S3Result, s3_error};
+
+use crate::auth::{check_key_valid, get_condition_values, get_session_token};
+
+/// Credentials `req` was signed with, and whether they are those of the owner
+pub(crate) async fn request_cred(req: &S3Request<Body>) -> S3Result<(Credentials, bool)> {
+    let Some(input_cred) = &req.credentials else {
+        return Err(s3_error!(InvalidRequest, "get cred failed"));
+    };
+
+    check_key_valid(get_session_token(&req.uri, &req.headers).unwrap_or_default(), &input_cred.access_key).await
+}
+
+/// Check that the caller of `req` may perform `action` on `bucket`, an empty bucket for none,
+/// returning the access key it called with
+pub(crate) async fn authorize_action(req: &S3Request<Body>, action: Action, bucket: &str) -> S3Result<String> {
+    let (cred, owner) = request_cred(req).await?;
+
+    let Ok(iam_store) = rustfs_iam::get() else {
+        return Err(s3_error!(InvalidRequest, "iam not init"));
+    };
+
+    let conditions = get_condition_values(&req.headers, &cred);
+    if !iam_store
+        .is_allowed(&Args {
+            account: &cred.access_key,
+            groups: &cred.groups,
+            action,
+            bucket,
+            conditions: &conditions,
+            is_owner: owner,
+            object: "",
+            claims: cred.claims.as_ref().unwrap_or(&HashMap::new()),
+            deny_only: false,
+        })
+        .await
+    {
+        return Err(s3_error!(AccessDenied, "access denied"));
+    }
+
+    Ok(cred.access_key)
+}
+
+/// Check that the caller of `req` may perform the admin `action`, returning the access key it called with
+pub(crate) async fn authorize(req: &S3Request<Body>, action: AdminAction) -> S3Result<String> {
+    authorize_action(req, Action::AdminAction(action), "").await
+}
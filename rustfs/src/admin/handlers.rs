@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::auth::authorize;
 use super::router::Operation;
 use crate::auth::check_key_valid;
 use crate::auth::get_condition_values;
@@ -45,6 +46,7 @@ use rustfs_madmin::utils::parse_duration;
 use rustfs_policy::policy::Args;
 use rustfs_policy::policy::BucketPolicy;
 use rustfs_policy::policy::action::Action;
+use rustfs_policy::policy::action::AdminAction;
 use rustfs_policy::policy::action::S3Action;
 use rustfs_policy::policy::default::DEFAULT_POLICIES;
 use rustfs_utils::path::path_join;
@@ -68,12 +70,25 @@ use tracing::debug;
 use tracing::{error, info, warn};
 // use url::UrlQuery;
 
+pub mod access_check;
+pub mod bucket_encryption;
+pub mod bucket_grant;
 pub mod bucket_meta;
+pub mod bucket_quota;
+pub mod bucket_usage;
+pub mod checksum_manifest;
+pub mod console_log;
+pub mod effective_policy;
 pub mod event;
 pub mod group;
+pub mod heat;
+pub mod kms;
+pub mod locks;
 pub mod policies;
 pub mod pools;
 pub mod rebalance;
+pub mod reencode;
+pub mod replication;
 pub mod service_account;
 pub mod sts;
 pub mod tier;
@@ -599,6 +614,31 @@ impl Operation for MetricsHandler {
     }
 }
 
+/// Serves latency histograms in the OpenMetrics format, with trace-id exemplars, for Prometheus scraping
+pub struct PrometheusMetricsHandler {}
+
+#[async_trait::async_trait]
+impl Operation for PrometheusMetricsHandler {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::PrometheusAdminAction).await?;
+
+        let mut data = rustfs_obs::http_request_latency().render_openmetrics();
+        // The usage gauges go before the `# EOF` closing the latency histograms
+        match bucket_usage::usage_report("", "").await {
+            Ok(report) => {
+                data.truncate(data.trim_end_matches("# EOF\n").len());
+                bucket_usage::render_usage_openmetrics(&report, &mut data);
+                data.push_str("# EOF\n");
+            }
+            Err(e) => warn!("PrometheusMetricsHandler: bucket usage unavailable: {}", e),
+        }
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, rustfs_obs::OPENMETRICS_CONTENT_TYPE.parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct HealInitParams {
     bucket: String,
@@ -0,0 +1,143 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    admin::{auth::request_cred, router::Operation},
+    auth::get_condition_values,
+};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::{StorageAPI, new_object_layer_fn, store_api::BucketOptions};
+use rustfs_policy::policy::{Args, action::Action};
+use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::warn;
+
+/// Most bucket and action pairs one request may check
+const MAX_ACCESS_CHECKS: usize = 10_000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckAccessReq {
+    pub buckets: Vec<String>,
+    /// Actions such as `s3:GetObject`
+    pub actions: Vec<String>,
+    /// Object the object actions are checked on, the whole bucket when empty
+    #[serde(default)]
+    pub object: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketAccess {
+    pub bucket: String,
+    pub exists: bool,
+    /// Whether the caller may perform each action on the bucket
+    pub actions: BTreeMap<String, bool>,
+}
+
+pub struct CheckAccess {}
+
+#[async_trait::async_trait]
+impl Operation for CheckAccess {
+    // POST <endpoint>/<admin-API>/check-access
+    //
+    // Reports, in one round trip, which buckets exist and which of the actions the caller may
+    // perform on each of them, so consoles need not probe every bucket. Needs no admin
+    // permission: callers only learn about their own access.
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        let (cred, owner) = request_cred(&req).await?;
+
+        let mut input = req.input;
+        let body = match input.store_all_unlimited().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("get body failed, e: {:?}", e);
+                return Err(s3_error!(InvalidRequest, "get body failed"));
+            }
+        };
+        let args: CheckAccessReq = serde_json::from_slice(&body)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidArgument, format!("unmarshal body err {e}")))?;
+        if args.buckets.len().saturating_mul(args.actions.len()) > MAX_ACCESS_CHECKS {
+            return Err(s3_error!(
+                InvalidArgument,
+                "at most {} bucket and action pairs can be checked",
+                MAX_ACCESS_CHECKS
+            ));
+        }
+        let actions = args
+            .actions
+            .iter()
+            .map(|name| {
+                Action::try_from(name.as_str())
+                    .map(|action| (name, action))
+                    .map_err(|_| s3_error!(InvalidArgument, "unknown action {}", name))
+            })
+            .collect::<S3Result<Vec<_>>>()?;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+        let existing: HashSet<String> = store
+            .list_bucket(&BucketOptions::default())
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, e.to_string()))?
+            .into_iter()
+            .map(|bucket| bucket.name)
+            .collect();
+
+        let Ok(iam_store) = rustfs_iam::get() else {
+            return Err(s3_error!(InvalidRequest, "iam not init"));
+        };
+        let default_claims = HashMap::new();
+        let claims = cred.claims.as_ref().unwrap_or(&default_claims);
+        let conditions = get_condition_values(&req.headers, &cred);
+
+        let mut result = Vec::with_capacity(args.buckets.len());
+        for bucket in args.buckets {
+            let mut allowed = BTreeMap::new();
+            for (name, action) in &actions {
+                let is_allowed = iam_store
+                    .is_allowed(&Args {
+                        account: &cred.access_key,
+                        groups: &cred.groups,
+                        action: *action,
+                        bucket: &bucket,
+                        conditions: &conditions,
+                        is_owner: owner,
+                        object: &args.object,
+                        claims,
+                        deny_only: false,
+                    })
+                    .await;
+                allowed.insert(name.to_string(), is_allowed);
+            }
+            result.push(BucketAccess {
+                exists: existing.contains(&bucket),
+                bucket,
+                actions: allowed,
+            });
+        }
+
+        let data = serde_json::to_vec(&result)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal body err {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
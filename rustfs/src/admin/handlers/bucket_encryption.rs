@@ -0,0 +1,121 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize_action, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::{
+    StorageAPI,
+    bucket::{encryption::BucketEncryptionPolicy, metadata::BUCKET_ENCRYPTION_POLICY_FILE, metadata_sys},
+    error::StorageError,
+    new_object_layer_fn,
+    store_api::BucketOptions,
+};
+use rustfs_policy::policy::action::{Action, S3Action};
+use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::Deserialize;
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct BucketEncryptionPolicyQuery {
+    #[serde(default)]
+    pub bucket: String,
+}
+
+fn parse_query(req: &S3Request<Body>) -> S3Result<BucketEncryptionPolicyQuery> {
+    let query: BucketEncryptionPolicyQuery = match req.uri.query() {
+        Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+        None => BucketEncryptionPolicyQuery::default(),
+    };
+    if query.bucket.is_empty() {
+        return Err(s3_error!(InvalidArgument, "bucket is required"));
+    }
+    Ok(query)
+}
+
+/// The encryption policy is governed by the same permission as the bucket default encryption
+async fn check_bucket_encryption_access(req: &S3Request<Body>, bucket: &str, action: S3Action) -> S3Result<()> {
+    authorize_action(req, Action::S3Action(action), bucket).await?;
+
+    let Some(store) = new_object_layer_fn() else {
+        return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+    };
+    store
+        .get_bucket_info(bucket, &BucketOptions::default())
+        .await
+        .map_err(|e| S3Error::with_message(S3ErrorCode::NoSuchBucket, e.to_string()))?;
+
+    Ok(())
+}
+
+pub struct SetBucketEncryptionPolicy {}
+#[async_trait::async_trait]
+impl Operation for SetBucketEncryptionPolicy {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle SetBucketEncryptionPolicy");
+
+        let query = parse_query(&req)?;
+        check_bucket_encryption_access(&req, &query.bucket, S3Action::PutBucketEncryptionAction).await?;
+
+        let mut input = req.input;
+        let body = match input.store_all_unlimited().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("get body failed, e: {:?}", e);
+                return Err(s3_error!(InvalidRequest, "get body failed"));
+            }
+        };
+
+        let policy = BucketEncryptionPolicy::unmarshal(&body)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidRequest, format!("unmarshal body err {e}")))?;
+        policy
+            .validate()
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidArgument, e.to_string()))?;
+
+        let data = policy
+            .marshal()
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal policy err {e}")))?;
+        metadata_sys::update(&query.bucket, BUCKET_ENCRYPTION_POLICY_FILE, data)
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, e.to_string()))?;
+
+        Ok(S3Response::new((StatusCode::OK, Body::empty())))
+    }
+}
+
+pub struct GetBucketEncryptionPolicy {}
+#[async_trait::async_trait]
+impl Operation for GetBucketEncryptionPolicy {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle GetBucketEncryptionPolicy");
+
+        let query = parse_query(&req)?;
+        check_bucket_encryption_access(&req, &query.bucket, S3Action::GetBucketEncryptionAction).await?;
+
+        let policy = match metadata_sys::get_encryption_policy(&query.bucket).await {
+            Ok((policy, _)) => policy,
+            Err(StorageError::ConfigNotFound) => BucketEncryptionPolicy::default(),
+            Err(e) => return Err(S3Error::with_message(S3ErrorCode::InternalError, e.to_string())),
+        };
+
+        let data = policy
+            .marshal()
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal body err {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
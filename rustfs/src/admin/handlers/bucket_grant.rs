@@ -0,0 +1,236 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_iam::grant::BucketAccessGrant;
+use rustfs_obs::{ApiDetails, AuditLogEntry, BaseLogEntry, get_logger};
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{
+    Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    s3_error,
+};
+use serde::Deserialize;
+use serde_urlencoded::from_bytes;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+/// How often expired grants are purged from the IAM store
+const GRANT_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize, Default)]
+pub struct BucketGrantQuery {
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Request body of `add-bucket-grant`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddBucketGrantReq {
+    pub identity: String,
+    #[serde(default)]
+    pub is_group: bool,
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub actions: Vec<String>,
+    /// Grant lifetime in seconds
+    pub duration_seconds: i64,
+}
+
+/// Record a grant lifecycle event in the audit log
+async fn audit_grant_event(event: &str, grant: &BucketAccessGrant, access_key: Option<String>) {
+    let api = ApiDetails::new()
+        .set_name(Some(event.to_string()))
+        .set_bucket(Some(grant.bucket.clone()))
+        .set_object(Some(grant.prefix.clone()))
+        .set_status(Some("OK".to_string()))
+        .set_status_code(Some(200));
+
+    let message = format!(
+        "{} {} access to {}/{} until {} via {}",
+        if grant.is_group { "group" } else { "user" },
+        grant.identity,
+        grant.bucket,
+        grant.prefix,
+        grant.expiration,
+        grant.policy_name
+    );
+
+    let entry = AuditLogEntry::new()
+        .with_base(BaseLogEntry::new().message(Some(message)))
+        .set_version("1".to_string())
+        .set_event(event.to_string())
+        .set_entry_type(Some("admin".to_string()))
+        .set_api(api)
+        .set_access_key(access_key);
+
+    if let Err(e) = get_logger().lock().await.log_audit_entry(entry).await {
+        warn!("audit bucket grant event failed: {}", e);
+    }
+}
+
+pub struct AddBucketGrant {}
+#[async_trait::async_trait]
+impl Operation for AddBucketGrant {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle AddBucketGrant");
+
+        let access_key = authorize(&req, AdminAction::AttachPolicyAdminAction).await?;
+
+        let mut input = req.input;
+        let body = match input.store_all_unlimited().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("get body failed, e: {:?}", e);
+                return Err(s3_error!(InvalidRequest, "get body failed"));
+            }
+        };
+
+        let args: AddBucketGrantReq = serde_json::from_slice(&body)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidArgument, format!("unmarshal body err {e}")))?;
+
+        if args.duration_seconds <= 0 {
+            return Err(s3_error!(InvalidArgument, "durationSeconds must be positive"));
+        }
+
+        let Ok(iam_store) = rustfs_iam::get() else { return Err(s3_error!(InternalError, "iam not init")) };
+
+        let grant = BucketAccessGrant {
+            policy_name: String::new(),
+            identity: args.identity,
+            is_group: args.is_group,
+            bucket: args.bucket,
+            prefix: args.prefix,
+            actions: args.actions,
+            expiration: OffsetDateTime::now_utc() + time::Duration::seconds(args.duration_seconds),
+            granted_by: access_key.clone(),
+        };
+
+        let grant = iam_store.grant_bucket_access(grant).await.map_err(|e| {
+            warn!("grant bucket access failed, e: {:?}", e);
+            S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
+        })?;
+
+        audit_grant_event("admin:AddBucketGrant", &grant, Some(access_key)).await;
+
+        let data = serde_json::to_vec(&grant)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal body err {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+pub struct ListBucketGrants {}
+#[async_trait::async_trait]
+impl Operation for ListBucketGrants {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle ListBucketGrants");
+
+        let query = {
+            if let Some(query) = req.uri.query() {
+                let input: BucketGrantQuery =
+                    from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?;
+                input
+            } else {
+                BucketGrantQuery::default()
+            }
+        };
+
+        authorize(&req, AdminAction::ListUserPoliciesAdminAction).await?;
+
+        let Ok(iam_store) = rustfs_iam::get() else { return Err(s3_error!(InternalError, "iam not init")) };
+
+        let grants = iam_store.list_bucket_access_grants(&query.bucket).await.map_err(|e| {
+            warn!("list bucket grants failed, e: {:?}", e);
+            S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
+        })?;
+
+        let data = serde_json::to_vec(&grants)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal body err {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+pub struct RevokeBucketGrant {}
+#[async_trait::async_trait]
+impl Operation for RevokeBucketGrant {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle RevokeBucketGrant");
+
+        let query = {
+            if let Some(query) = req.uri.query() {
+                let input: BucketGrantQuery =
+                    from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?;
+                input
+            } else {
+                BucketGrantQuery::default()
+            }
+        };
+
+        if !BucketAccessGrant::is_grant_policy(&query.name) {
+            return Err(s3_error!(InvalidArgument, "invalid grant name"));
+        }
+
+        let access_key = authorize(&req, AdminAction::DeletePolicyAdminAction).await?;
+
+        let Ok(iam_store) = rustfs_iam::get() else { return Err(s3_error!(InternalError, "iam not init")) };
+
+        let grant = iam_store.revoke_bucket_access_grant(&query.name).await.map_err(|e| {
+            warn!("revoke bucket grant failed, e: {:?}", e);
+            S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
+        })?;
+
+        audit_grant_event("admin:RevokeBucketGrant", &grant, Some(access_key)).await;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        header.insert(CONTENT_LENGTH, "0".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::empty()), header))
+    }
+}
+
+/// Periodically revoke expired bucket grants and audit each expiry
+pub fn start_bucket_grant_expiry() {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(GRANT_EXPIRY_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Ok(iam_store) = rustfs_iam::get() else { continue };
+
+            match iam_store.purge_expired_bucket_access_grants().await {
+                Ok(expired) => {
+                    for grant in expired.iter() {
+                        info!("bucket grant {} expired", grant.policy_name);
+                        audit_grant_event("admin:ExpireBucketGrant", grant, None).await;
+                    }
+                }
+                Err(e) => warn!("purge expired bucket grants failed: {}", e),
+            }
+        }
+    });
+}
@@ -27,10 +27,13 @@ use matchit::Params;
 use rustfs_ecstore::{
     StorageAPI,
     bucket::{
+        acl::BucketAcl,
+        encryption::BucketEncryptionPolicy,
         metadata::{
-            BUCKET_LIFECYCLE_CONFIG, BUCKET_NOTIFICATION_CONFIG, BUCKET_POLICY_CONFIG, BUCKET_QUOTA_CONFIG_FILE,
+            BUCKET_ACL_CONFIG, BUCKET_CORS_CONFIG, BUCKET_ENCRYPTION_POLICY_FILE, BUCKET_LIFECYCLE_CONFIG,
+            BUCKET_NOTIFICATION_CONFIG, BUCKET_POLICY_CONFIG, BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG, BUCKET_QUOTA_CONFIG_FILE,
             BUCKET_REPLICATION_CONFIG, BUCKET_SSECONFIG, BUCKET_TAGGING_CONFIG, BUCKET_TARGETS_FILE, BUCKET_VERSIONING_CONFIG,
-            BucketMetadata, OBJECT_LOCK_CONFIG,
+            BUCKET_WEBSITE_CONFIG, BucketMetadata, OBJECT_LOCK_CONFIG,
         },
         metadata_sys,
         quota::BucketQuota,
@@ -49,8 +52,8 @@ use rustfs_utils::path::{SLASH_SEPARATOR, path_join_buf};
 use s3s::{
     Body, S3Request, S3Response, S3Result,
     dto::{
-        BucketLifecycleConfiguration, ObjectLockConfiguration, ReplicationConfiguration, ServerSideEncryptionConfiguration,
-        Tagging, VersioningConfiguration,
+        BucketLifecycleConfiguration, CORSConfiguration, ObjectLockConfiguration, PublicAccessBlockConfiguration,
+        ReplicationConfiguration, ServerSideEncryptionConfiguration, Tagging, VersioningConfiguration, WebsiteConfiguration,
     },
     header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE},
     s3_error,
@@ -112,12 +115,17 @@ impl Operation for ExportBucketMetadata {
             BUCKET_NOTIFICATION_CONFIG,
             BUCKET_LIFECYCLE_CONFIG,
             BUCKET_SSECONFIG,
+            BUCKET_ENCRYPTION_POLICY_FILE,
             BUCKET_TAGGING_CONFIG,
             BUCKET_QUOTA_CONFIG_FILE,
             OBJECT_LOCK_CONFIG,
             BUCKET_VERSIONING_CONFIG,
             BUCKET_REPLICATION_CONFIG,
             BUCKET_TARGETS_FILE,
+            BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG,
+            BUCKET_CORS_CONFIG,
+            BUCKET_WEBSITE_CONFIG,
+            BUCKET_ACL_CONFIG,
         ];
 
         for bucket in buckets {
@@ -207,6 +215,27 @@ impl Operation for ExportBucketMetadata {
                             .write_all(&config_xml)
                             .map_err(|e| s3_error!(InternalError, "write file failed: {e}"))?;
                     }
+                    BUCKET_ENCRYPTION_POLICY_FILE => {
+                        let config = match metadata_sys::get_encryption_policy(&bucket.name).await {
+                            Ok((res, _)) => res,
+                            Err(e) => {
+                                if e == StorageError::ConfigNotFound {
+                                    continue;
+                                }
+                                return Err(s3_error!(InternalError, "get bucket metadata failed: {e}"));
+                            }
+                        };
+                        let config_json = config
+                            .marshal()
+                            .map_err(|e| s3_error!(InternalError, "serialize config failed: {e}"))?;
+
+                        zip_writer
+                            .start_file(conf_path, SimpleFileOptions::default())
+                            .map_err(|e| s3_error!(InternalError, "start file failed: {e}"))?;
+                        zip_writer
+                            .write_all(&config_json)
+                            .map_err(|e| s3_error!(InternalError, "write file failed: {e}"))?;
+                    }
                     BUCKET_QUOTA_CONFIG_FILE => {
                         let config: BucketQuota = match metadata_sys::get_quota_config(&bucket.name).await {
                             Ok((res, _)) => res,
@@ -227,6 +256,27 @@ impl Operation for ExportBucketMetadata {
                             .write_all(&config_json)
                             .map_err(|e| s3_error!(InternalError, "write file failed: {e}"))?;
                     }
+                    BUCKET_ACL_CONFIG => {
+                        let config: BucketAcl = match metadata_sys::get_bucket_acl_config(&bucket.name).await {
+                            Ok((res, _)) => res,
+                            Err(e) => {
+                                if e == StorageError::ConfigNotFound {
+                                    continue;
+                                }
+                                return Err(s3_error!(InternalError, "get bucket metadata failed: {e}"));
+                            }
+                        };
+                        let config_json = config
+                            .marshal()
+                            .map_err(|e| s3_error!(InternalError, "serialize config failed: {e}"))?;
+
+                        zip_writer
+                            .start_file(conf_path, SimpleFileOptions::default())
+                            .map_err(|e| s3_error!(InternalError, "start file failed: {e}"))?;
+                        zip_writer
+                            .write_all(&config_json)
+                            .map_err(|e| s3_error!(InternalError, "write file failed: {e}"))?;
+                    }
                     OBJECT_LOCK_CONFIG => {
                         let config = match metadata_sys::get_object_lock_config(&bucket.name).await {
                             Ok((res, _)) => res,
@@ -328,6 +378,66 @@ impl Operation for ExportBucketMetadata {
                             .write_all(&config_json)
                             .map_err(|e| s3_error!(InternalError, "write file failed: {e}"))?;
                     }
+                    BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG => {
+                        let config = match metadata_sys::get_public_access_block_config(&bucket.name).await {
+                            Ok((res, _)) => res,
+                            Err(e) => {
+                                if e == StorageError::ConfigNotFound {
+                                    continue;
+                                }
+                                return Err(s3_error!(InternalError, "get bucket metadata failed: {e}"));
+                            }
+                        };
+                        let config_xml =
+                            serialize(&config).map_err(|e| s3_error!(InternalError, "serialize config failed: {e}"))?;
+
+                        zip_writer
+                            .start_file(conf_path, SimpleFileOptions::default())
+                            .map_err(|e| s3_error!(InternalError, "start file failed: {e}"))?;
+                        zip_writer
+                            .write_all(&config_xml)
+                            .map_err(|e| s3_error!(InternalError, "write file failed: {e}"))?;
+                    }
+                    BUCKET_CORS_CONFIG => {
+                        let config = match metadata_sys::get_cors_config(&bucket.name).await {
+                            Ok((res, _)) => res,
+                            Err(e) => {
+                                if e == StorageError::ConfigNotFound {
+                                    continue;
+                                }
+                                return Err(s3_error!(InternalError, "get bucket metadata failed: {e}"));
+                            }
+                        };
+                        let config_xml =
+                            serialize(&config).map_err(|e| s3_error!(InternalError, "serialize config failed: {e}"))?;
+
+                        zip_writer
+                            .start_file(conf_path, SimpleFileOptions::default())
+                            .map_err(|e| s3_error!(InternalError, "start file failed: {e}"))?;
+                        zip_writer
+                            .write_all(&config_xml)
+                            .map_err(|e| s3_error!(InternalError, "write file failed: {e}"))?;
+                    }
+                    BUCKET_WEBSITE_CONFIG => {
+                        let config = match metadata_sys::get_website_config(&bucket.name).await {
+                            Ok((res, _)) => res,
+                            Err(e) => {
+                                if e == StorageError::ConfigNotFound {
+                                    continue;
+                                }
+                                return Err(s3_error!(InternalError, "get bucket metadata failed: {e}"));
+                            }
+                        };
+                        let config_xml =
+                            serialize(&config).map_err(|e| s3_error!(InternalError, "serialize config failed: {e}"))?;
+
+                        zip_writer
+                            .start_file(conf_path, SimpleFileOptions::default())
+                            .map_err(|e| s3_error!(InternalError, "start file failed: {e}"))?;
+                        zip_writer
+                            .write_all(&config_xml)
+                            .map_err(|e| s3_error!(InternalError, "write file failed: {e}"))?;
+                    }
                     _ => {}
                 }
             }
@@ -522,6 +632,17 @@ impl Operation for ImportBucketMetadata {
                     metadata.encryption_config_updated_at = update_at;
                 }
 
+                BUCKET_ENCRYPTION_POLICY_FILE => {
+                    if let Err(e) = BucketEncryptionPolicy::unmarshal(&content) {
+                        warn!("deserialize config failed: {e}");
+                        continue;
+                    }
+
+                    let metadata = bucket_metadatas.get_mut(bucket_name).unwrap();
+                    metadata.encryption_policy_json = content;
+                    metadata.encryption_policy_updated_at = update_at;
+                }
+
                 BUCKET_TAGGING_CONFIG => {
                     if let Err(e) = deserialize::<Tagging>(&content) {
                         warn!("deserialize config failed: {e}");
@@ -544,6 +665,17 @@ impl Operation for ImportBucketMetadata {
                     metadata.quota_config_updated_at = update_at;
                 }
 
+                BUCKET_ACL_CONFIG => {
+                    if let Err(e) = BucketAcl::unmarshal(&content) {
+                        warn!("deserialize config failed: {e}");
+                        continue;
+                    }
+
+                    let metadata = bucket_metadatas.get_mut(bucket_name).unwrap();
+                    metadata.acl_config_json = content;
+                    metadata.acl_config_updated_at = update_at;
+                }
+
                 OBJECT_LOCK_CONFIG => {
                     if let Err(e) = deserialize::<ObjectLockConfiguration>(&content) {
                         warn!("deserialize config failed: {e}");
@@ -588,6 +720,39 @@ impl Operation for ImportBucketMetadata {
                     metadata.bucket_targets_config_updated_at = update_at;
                 }
 
+                BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG => {
+                    if let Err(e) = deserialize::<PublicAccessBlockConfiguration>(&content) {
+                        warn!("deserialize config failed: {e}");
+                        continue;
+                    }
+
+                    let metadata = bucket_metadatas.get_mut(bucket_name).unwrap();
+                    metadata.public_access_block_config_xml = content;
+                    metadata.public_access_block_config_updated_at = update_at;
+                }
+
+                BUCKET_CORS_CONFIG => {
+                    if let Err(e) = deserialize::<CORSConfiguration>(&content) {
+                        warn!("deserialize config failed: {e}");
+                        continue;
+                    }
+
+                    let metadata = bucket_metadatas.get_mut(bucket_name).unwrap();
+                    metadata.cors_config_xml = content;
+                    metadata.cors_config_updated_at = update_at;
+                }
+
+                BUCKET_WEBSITE_CONFIG => {
+                    if let Err(e) = deserialize::<WebsiteConfiguration>(&content) {
+                        warn!("deserialize config failed: {e}");
+                        continue;
+                    }
+
+                    let metadata = bucket_metadatas.get_mut(bucket_name).unwrap();
+                    metadata.website_config_xml = content;
+                    metadata.website_config_updated_at = update_at;
+                }
+
                 _ => {}
             }
         }
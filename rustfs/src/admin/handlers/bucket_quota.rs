@@ -0,0 +1,126 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::{
+    StorageAPI,
+    bucket::{metadata::BUCKET_QUOTA_CONFIG_FILE, metadata_sys, quota::BucketQuota},
+    error::StorageError,
+    new_object_layer_fn,
+    store_api::BucketOptions,
+};
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::Deserialize;
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct BucketQuotaQuery {
+    #[serde(default)]
+    pub bucket: String,
+}
+
+fn parse_query(req: &S3Request<Body>) -> S3Result<BucketQuotaQuery> {
+    let query: BucketQuotaQuery = match req.uri.query() {
+        Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+        None => BucketQuotaQuery::default(),
+    };
+    if query.bucket.is_empty() {
+        return Err(s3_error!(InvalidArgument, "bucket is required"));
+    }
+    Ok(query)
+}
+
+async fn check_bucket_exists(bucket: &str) -> S3Result<()> {
+    let Some(store) = new_object_layer_fn() else {
+        return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+    };
+    store
+        .get_bucket_info(bucket, &BucketOptions::default())
+        .await
+        .map_err(|e| S3Error::with_message(S3ErrorCode::NoSuchBucket, e.to_string()))?;
+
+    Ok(())
+}
+
+pub struct SetBucketQuota {}
+#[async_trait::async_trait]
+impl Operation for SetBucketQuota {
+    // PUT <endpoint>/<admin-API>/set-bucket-quota?bucket=<bucket>, a quota limiting nothing removes it
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle SetBucketQuota");
+
+        let query = parse_query(&req)?;
+        authorize(&req, AdminAction::SetBucketQuotaAdminAction).await?;
+        check_bucket_exists(&query.bucket).await?;
+
+        let mut input = req.input;
+        let body = match input.store_all_unlimited().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("get body failed, e: {:?}", e);
+                return Err(s3_error!(InvalidRequest, "get body failed"));
+            }
+        };
+
+        let quota = BucketQuota::unmarshal(&body)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidRequest, format!("unmarshal body err {e}")))?;
+
+        if !quota.is_set() {
+            metadata_sys::delete(&query.bucket, BUCKET_QUOTA_CONFIG_FILE)
+                .await
+                .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, e.to_string()))?;
+            return Ok(S3Response::new((StatusCode::OK, Body::empty())));
+        }
+
+        let data = quota
+            .marshal()
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal quota err {e}")))?;
+        metadata_sys::update(&query.bucket, BUCKET_QUOTA_CONFIG_FILE, data)
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, e.to_string()))?;
+
+        Ok(S3Response::new((StatusCode::OK, Body::empty())))
+    }
+}
+
+pub struct GetBucketQuota {}
+#[async_trait::async_trait]
+impl Operation for GetBucketQuota {
+    // GET <endpoint>/<admin-API>/get-bucket-quota?bucket=<bucket>
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle GetBucketQuota");
+
+        let query = parse_query(&req)?;
+        authorize(&req, AdminAction::GetBucketQuotaAdminAction).await?;
+        check_bucket_exists(&query.bucket).await?;
+
+        let quota = match metadata_sys::get_quota_config(&query.bucket).await {
+            Ok((quota, _)) => quota,
+            Err(StorageError::ConfigNotFound) => BucketQuota::default(),
+            Err(e) => return Err(S3Error::with_message(S3ErrorCode::InternalError, e.to_string())),
+        };
+
+        let data = quota
+            .marshal()
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal quota err {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Usage of buckets and of their prefixes, as the scanner last counted it
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_common::data_usage::PrefixUsageInfo;
+use rustfs_ecstore::{
+    StorageAPI,
+    bucket::{metadata_sys, quota::BucketQuota},
+    data_usage::load_data_usage_from_backend,
+    new_object_layer_fn,
+    store_api::BucketOptions,
+};
+use rustfs_obs::escape_label_value;
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{Body, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::{Deserialize, Serialize};
+use serde_urlencoded::from_bytes;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::SystemTime;
+use tracing::{error, warn};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BucketUsageQuery {
+    /// Bucket to report, all of them when empty
+    pub bucket: String,
+    /// Only report the prefixes starting with it
+    pub prefix: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrefixUsage {
+    pub prefix: String,
+    #[serde(flatten)]
+    pub usage: PrefixUsageInfo,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BucketUsageReport {
+    pub bucket: String,
+    pub size: u64,
+    pub objects_count: u64,
+    pub versions_count: u64,
+    pub delete_markers_count: u64,
+    pub object_size_histogram: HashMap<String, u64>,
+    pub object_versions_histogram: HashMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota: Option<BucketQuota>,
+    /// Largest prefixes first
+    pub prefixes: Vec<PrefixUsage>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct UsageReport {
+    /// When the scanner counted the usage
+    pub last_update: Option<SystemTime>,
+    pub buckets: Vec<BucketUsageReport>,
+}
+
+/// Report of the usage of `bucket`, or of every bucket when empty, with the prefixes starting with `prefix`
+pub async fn usage_report(bucket: &str, prefix: &str) -> S3Result<UsageReport> {
+    let Some(store) = new_object_layer_fn() else {
+        return Err(s3_error!(InternalError, "Not init"));
+    };
+
+    let buckets: Vec<String> = if bucket.is_empty() {
+        store
+            .list_bucket(&BucketOptions::default())
+            .await
+            .map_err(|e| s3_error!(InternalError, "list buckets failed: {e}"))?
+            .into_iter()
+            .map(|b| b.name)
+            .collect()
+    } else {
+        store
+            .get_bucket_info(bucket, &BucketOptions::default())
+            .await
+            .map_err(|e| s3_error!(NoSuchBucket, "{e}"))?;
+        vec![bucket.to_owned()]
+    };
+
+    let mut info = load_data_usage_from_backend(store).await.map_err(|e| {
+        error!("load_data_usage_from_backend failed {:?}", e);
+        s3_error!(InternalError, "load_data_usage_from_backend failed")
+    })?;
+
+    let mut report = UsageReport {
+        last_update: info.last_update,
+        buckets: Vec::with_capacity(buckets.len()),
+    };
+    for name in buckets {
+        let usage = info.buckets_usage.remove(&name).unwrap_or_default();
+        let mut prefixes: Vec<PrefixUsage> = usage
+            .prefixes_usage
+            .into_iter()
+            .filter(|(p, _)| p.starts_with(prefix))
+            .map(|(prefix, usage)| PrefixUsage { prefix, usage })
+            .collect();
+        prefixes.sort_by(|a, b| b.usage.size.cmp(&a.usage.size).then_with(|| a.prefix.cmp(&b.prefix)));
+
+        let quota = metadata_sys::get_quota_config(&name)
+            .await
+            .ok()
+            .map(|(quota, _)| quota)
+            .filter(BucketQuota::is_set);
+
+        report.buckets.push(BucketUsageReport {
+            bucket: name,
+            size: usage.size,
+            objects_count: usage.objects_count,
+            versions_count: usage.versions_count,
+            delete_markers_count: usage.delete_markers_count,
+            object_size_histogram: usage.object_size_histogram,
+            object_versions_histogram: usage.object_versions_histogram,
+            quota,
+            prefixes,
+        });
+    }
+
+    Ok(report)
+}
+
+fn write_gauge<'a>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    labels: &[&str],
+    samples: impl IntoIterator<Item = (Vec<&'a str>, u64)>,
+) {
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    let _ = writeln!(out, "# HELP {name} {help}");
+    for (values, value) in samples {
+        let labels: Vec<String> = labels
+            .iter()
+            .zip(values)
+            .map(|(label, v)| format!("{label}=\"{}\"", escape_label_value(v)))
+            .collect();
+        let _ = writeln!(out, "{name}{{{}}} {value}", labels.join(","));
+    }
+}
+
+/// Renders `report` as gauges in the OpenMetrics text format, without the closing `# EOF`
+pub fn render_usage_openmetrics(report: &UsageReport, out: &mut String) {
+    let since_update = report
+        .last_update
+        .and_then(|t| SystemTime::now().duration_since(t).ok())
+        .map_or(0, |d| d.as_secs());
+    let _ = writeln!(out, "# TYPE rustfs_cluster_usage_objects_since_last_update_seconds gauge");
+    let _ = writeln!(
+        out,
+        "# HELP rustfs_cluster_usage_objects_since_last_update_seconds Time since the scanner last counted the usage"
+    );
+    let _ = writeln!(out, "rustfs_cluster_usage_objects_since_last_update_seconds {since_update}");
+
+    let buckets = &report.buckets;
+    let by_bucket = |f: fn(&BucketUsageReport) -> u64| buckets.iter().map(move |b| (vec![b.bucket.as_str()], f(b)));
+    write_gauge(
+        out,
+        "rustfs_cluster_usage_buckets_total_bytes",
+        "Total bucket size in bytes",
+        &["bucket"],
+        by_bucket(|b| b.size),
+    );
+    write_gauge(
+        out,
+        "rustfs_cluster_usage_buckets_objects_count",
+        "Total objects count in bucket",
+        &["bucket"],
+        by_bucket(|b| b.objects_count),
+    );
+    write_gauge(
+        out,
+        "rustfs_cluster_usage_buckets_versions_count",
+        "Total object versions count in bucket",
+        &["bucket"],
+        by_bucket(|b| b.versions_count),
+    );
+    write_gauge(
+        out,
+        "rustfs_cluster_usage_buckets_delete_markers_count",
+        "Total delete markers count in bucket",
+        &["bucket"],
+        by_bucket(|b| b.delete_markers_count),
+    );
+    write_gauge(
+        out,
+        "rustfs_cluster_usage_buckets_quota_total_bytes",
+        "Total bucket quota in bytes",
+        &["bucket"],
+        buckets
+            .iter()
+            .filter_map(|b| Some((vec![b.bucket.as_str()], b.quota.as_ref()?.size_limit())))
+            .filter(|(_, limit)| *limit > 0),
+    );
+
+    let by_prefix = |f: fn(&PrefixUsageInfo) -> u64| {
+        buckets.iter().flat_map(move |b| {
+            b.prefixes
+                .iter()
+                .map(move |p| (vec![b.bucket.as_str(), p.prefix.as_str()], f(&p.usage)))
+        })
+    };
+    write_gauge(
+        out,
+        "rustfs_cluster_usage_prefixes_total_bytes",
+        "Total size in bytes of the objects under a prefix",
+        &["bucket", "prefix"],
+        by_prefix(|p| p.size),
+    );
+    write_gauge(
+        out,
+        "rustfs_cluster_usage_prefixes_objects_count",
+        "Total objects count under a prefix",
+        &["bucket", "prefix"],
+        by_prefix(|p| p.objects_count),
+    );
+}
+
+pub struct BucketUsage {}
+
+#[async_trait::async_trait]
+impl Operation for BucketUsage {
+    // GET <endpoint>/<admin-API>/bucket-usage?bucket=<bucket>&prefix=<prefix>
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle BucketUsage");
+
+        let query: BucketUsageQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => BucketUsageQuery::default(),
+        };
+
+        authorize(&req, AdminAction::DataUsageInfoAdminAction).await?;
+
+        let report = usage_report(&query.bucket, &query.prefix).await?;
+        let data = serde_json::to_vec(&report).map_err(|e| s3_error!(InternalError, "marshal bucket usage failed: {e}"))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_usage_openmetrics() {
+        let report = UsageReport {
+            last_update: Some(SystemTime::now()),
+            buckets: vec![BucketUsageReport {
+                bucket: "photos".to_owned(),
+                size: 30,
+                objects_count: 3,
+                quota: Some(BucketQuota {
+                    size: 100,
+                    ..Default::default()
+                }),
+                prefixes: vec![PrefixUsage {
+                    prefix: "2024/".to_owned(),
+                    usage: PrefixUsageInfo {
+                        size: 20,
+                        objects_count: 2,
+                        ..Default::default()
+                    },
+                }],
+                ..Default::default()
+            }],
+        };
+
+        let mut out = String::new();
+        render_usage_openmetrics(&report, &mut out);
+
+        assert!(out.contains("rustfs_cluster_usage_buckets_total_bytes{bucket=\"photos\"} 30\n"));
+        assert!(out.contains("rustfs_cluster_usage_buckets_quota_total_bytes{bucket=\"photos\"} 100\n"));
+        assert!(out.contains("rustfs_cluster_usage_prefixes_objects_count{bucket=\"photos\",prefix=\"2024/\"} 2\n"));
+        assert!(out.contains("rustfs_cluster_usage_objects_since_last_update_seconds 0\n"));
+        assert!(!out.contains("# EOF"));
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize_action, router::Operation};
+use datafusion::arrow::array::{ArrayRef, Int64Array, StringArray, UInt64Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::ArrowWriter;
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::{
+    checksum_manifest::{ChecksumRecord, sign_manifest},
+    new_object_layer_fn,
+    store_api::{BucketOptions, StorageAPI},
+};
+use rustfs_policy::policy::action::{Action, AdminAction};
+use s3s::{
+    Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
+    header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE},
+    s3_error,
+};
+use serde_urlencoded::from_bytes;
+use std::sync::Arc;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tracing::warn;
+
+const MANIFEST_SHA256_HEADER: &str = "x-rustfs-manifest-sha256";
+const MANIFEST_SIGNATURE_HEADER: &str = "x-rustfs-manifest-signature";
+const MANIFEST_CREATED_HEADER: &str = "x-rustfs-manifest-created";
+const MANIFEST_OBJECTS_HEADER: &str = "x-rustfs-manifest-objects";
+
+const MANIFEST_COLUMNS: [&str; 7] = ["name", "version_id", "size", "mod_time", "etag", "parts", "checksums"];
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ExportChecksumsQuery {
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    /// `csv` (default) or `parquet`
+    #[serde(default)]
+    pub format: String,
+}
+
+fn format_time(t: Option<OffsetDateTime>) -> String {
+    t.and_then(|t| t.format(&Rfc3339).ok()).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn encode_csv(records: &[ChecksumRecord]) -> Vec<u8> {
+    let mut out = MANIFEST_COLUMNS.join(",");
+    out.push('\n');
+    for r in records {
+        let row = [
+            csv_field(&r.name),
+            r.version_id.clone().unwrap_or_default(),
+            r.size.to_string(),
+            format_time(r.mod_time),
+            csv_field(&r.etag),
+            r.parts.to_string(),
+            csv_field(&r.checksums),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out.into_bytes()
+}
+
+fn encode_parquet(records: &[ChecksumRecord]) -> S3Result<Vec<u8>> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(MANIFEST_COLUMNS[0], DataType::Utf8, false),
+        Field::new(MANIFEST_COLUMNS[1], DataType::Utf8, true),
+        Field::new(MANIFEST_COLUMNS[2], DataType::Int64, false),
+        Field::new(MANIFEST_COLUMNS[3], DataType::Utf8, true),
+        Field::new(MANIFEST_COLUMNS[4], DataType::Utf8, false),
+        Field::new(MANIFEST_COLUMNS[5], DataType::UInt64, false),
+        Field::new(MANIFEST_COLUMNS[6], DataType::Utf8, false),
+    ]));
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(records.iter().map(|r| r.name.as_str()))),
+        Arc::new(StringArray::from_iter(records.iter().map(|r| r.version_id.as_deref()))),
+        Arc::new(Int64Array::from_iter_values(records.iter().map(|r| r.size))),
+        Arc::new(StringArray::from_iter(records.iter().map(|r| r.mod_time.map(|t| format_time(Some(t)))))),
+        Arc::new(StringArray::from_iter_values(records.iter().map(|r| r.etag.as_str()))),
+        Arc::new(UInt64Array::from_iter_values(records.iter().map(|r| r.parts as u64))),
+        Arc::new(StringArray::from_iter_values(records.iter().map(|r| r.checksums.as_str()))),
+    ];
+
+    let batch = RecordBatch::try_new(schema.clone(), columns)
+        .map_err(|e| s3_error!(InternalError, "build checksum manifest failed: {e}"))?;
+
+    let mut out = Vec::new();
+    let mut writer =
+        ArrowWriter::try_new(&mut out, schema, None).map_err(|e| s3_error!(InternalError, "write parquet failed: {e}"))?;
+    writer
+        .write(&batch)
+        .map_err(|e| s3_error!(InternalError, "write parquet failed: {e}"))?;
+    writer
+        .close()
+        .map_err(|e| s3_error!(InternalError, "write parquet failed: {e}"))?;
+    Ok(out)
+}
+
+pub struct ExportChecksums {}
+
+#[async_trait::async_trait]
+impl Operation for ExportChecksums {
+    // GET <endpoint>/<admin-API>/export-checksums?bucket=<bucket>&prefix=<prefix>&format=<csv|parquet>
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle ExportChecksums");
+
+        let query: ExportChecksumsQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => ExportChecksumsQuery::default(),
+        };
+        if query.bucket.is_empty() {
+            return Err(s3_error!(InvalidArgument, "bucket is required"));
+        }
+        let parquet = match query.format.as_str() {
+            "" | "csv" => false,
+            "parquet" => true,
+            other => return Err(s3_error!(InvalidArgument, "unsupported manifest format: {}", other)),
+        };
+
+        authorize_action(&req, Action::AdminAction(AdminAction::ExportBucketMetadataAction), &query.bucket).await?;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&query.bucket, &BucketOptions::default())
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::NoSuchBucket, e.to_string()))?;
+
+        let records = store
+            .checksum_manifest(&query.bucket, &query.prefix)
+            .await
+            .map_err(|e| s3_error!(InternalError, "list objects failed: {e}"))?;
+
+        let (data, content_type, ext) = if parquet {
+            (encode_parquet(&records)?, "application/vnd.apache.parquet", "parquet")
+        } else {
+            (encode_csv(&records), "text/csv", "csv")
+        };
+
+        let Some(sig) = sign_manifest(&query.bucket, &data) else {
+            return Err(s3_error!(InternalError, "root credentials not initialized"));
+        };
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, content_type.parse().unwrap());
+        header.insert(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}-checksums.{ext}\"", query.bucket)
+                .parse()
+                .map_err(|_e| s3_error!(InternalError, "invalid bucket name"))?,
+        );
+        header.insert(CONTENT_LENGTH, data.len().to_string().parse().unwrap());
+        header.insert(MANIFEST_SHA256_HEADER, sig.sha256.parse().unwrap());
+        header.insert(MANIFEST_SIGNATURE_HEADER, sig.signature.parse().unwrap());
+        header.insert(MANIFEST_CREATED_HEADER, sig.created.to_string().parse().unwrap());
+        header.insert(MANIFEST_OBJECTS_HEADER, records.len().to_string().parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_csv_quotes_fields() {
+        let records = vec![ChecksumRecord {
+            name: "a,\"b\"".to_string(),
+            size: 3,
+            etag: "e".to_string(),
+            parts: 1,
+            checksums: "CRC32=c".to_string(),
+            ..Default::default()
+        }];
+        let csv = String::from_utf8(encode_csv(&records)).unwrap();
+        assert_eq!(
+            csv,
+            "name,version_id,size,mod_time,etag,parts,checksums\n\"a,\"\"b\"\"\",,3,,e,1,CRC32=c\n"
+        );
+    }
+}
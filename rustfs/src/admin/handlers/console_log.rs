@@ -0,0 +1,144 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use matchit::Params;
+use rustfs_obs::{ConsoleLogEntry, LogRecord, get_logger};
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{
+    Body, S3Request, S3Response, S3Result, StdError,
+    header::{CACHE_CONTROL, CONTENT_TYPE},
+    s3_error,
+    stream::{ByteStream, DynByteStream},
+};
+use serde::Deserialize;
+use serde_urlencoded::from_bytes;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ConsoleLogQuery {
+    /// Only stream entries produced by this node
+    #[serde(default)]
+    pub node: Option<String>,
+    /// Stop after this many entries, 0 means unlimited
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+/// Encode a console entry as a server-sent event frame
+fn encode_sse_event(entry: &ConsoleLogEntry) -> Bytes {
+    Bytes::from(format!("event: console\ndata: {}\n\n", entry.to_json()))
+}
+
+struct ConsoleLogStream {
+    inner: ReceiverStream<Result<Bytes, StdError>>,
+}
+
+impl Stream for ConsoleLogStream {
+    type Item = Result<Bytes, StdError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = Pin::into_inner(self);
+        this.inner.poll_next_unpin(cx)
+    }
+}
+
+impl ByteStream for ConsoleLogStream {}
+
+/// Tail the node console log as a `text/event-stream`
+pub struct ConsoleLog {}
+
+#[async_trait::async_trait]
+impl Operation for ConsoleLog {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        debug!("handle ConsoleLog");
+
+        let query = {
+            if let Some(query) = req.uri.query() {
+                let input: ConsoleLogQuery =
+                    from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?;
+                input
+            } else {
+                ConsoleLogQuery::default()
+            }
+        };
+
+        authorize(&req, AdminAction::ConsoleLogAdminAction).await?;
+
+        let mut rx = get_logger().lock().await.subscribe_console();
+        let node = query.node.filter(|n| !n.is_empty());
+        let mut remaining = match query.limit.unwrap_or_default() {
+            0 => u64::MAX,
+            n => n,
+        };
+
+        let (tx, body_rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            while remaining > 0 {
+                let entry = tokio::select! {
+                    _ = tx.closed() => return,
+                    entry = rx.recv() => entry,
+                };
+
+                match entry {
+                    Ok(entry) => {
+                        if node.as_ref().is_some_and(|n| *n != entry.node_name) {
+                            continue;
+                        }
+                        if tx.send(Ok(encode_sse_event(&entry))).await.is_err() {
+                            return;
+                        }
+                        remaining -= 1;
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("console log subscriber lagged, skipped {} entries", skipped);
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+
+        let in_stream: DynByteStream = Box::pin(ConsoleLogStream {
+            inner: ReceiverStream::new(body_rx),
+        });
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+        header.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(in_stream)), header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_sse_event() {
+        let entry = ConsoleLogEntry::new_with_console_msg("disk online".to_string(), "node-1".to_string());
+        let frame = encode_sse_event(&entry);
+        let frame = std::str::from_utf8(&frame).unwrap();
+
+        assert!(frame.starts_with("event: console\ndata: {"));
+        assert!(frame.ends_with("}\n\n"));
+        assert!(frame.contains("disk online"));
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::{
+    StorageAPI, bucket::effective_policy::resolve_effective_policy, new_object_layer_fn, store_api::BucketOptions,
+};
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::Deserialize;
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct EffectivePolicyQuery {
+    pub bucket: String,
+    pub prefix: String,
+}
+
+pub struct GetEffectivePolicy {}
+
+#[async_trait::async_trait]
+impl Operation for GetEffectivePolicy {
+    // GET <endpoint>/<admin-API>/effective-policy?bucket=<bucket>&prefix=<prefix>
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle GetEffectivePolicy");
+
+        let query: EffectivePolicyQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => EffectivePolicyQuery::default(),
+        };
+        if query.bucket.is_empty() {
+            return Err(s3_error!(InvalidArgument, "bucket is required"));
+        }
+
+        authorize(&req, AdminAction::ServerInfoAdminAction).await?;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+        store
+            .get_bucket_info(&query.bucket, &BucketOptions::default())
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::NoSuchBucket, e.to_string()))?;
+
+        let policy = resolve_effective_policy(&query.bucket, &query.prefix)
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, e.to_string()))?;
+
+        let data = serde_json::to_vec(&policy).map_err(|e| s3_error!(InternalError, "marshal effective policy failed: {e}"))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
@@ -14,13 +14,15 @@
 
 #![allow(dead_code)]
 
+use crate::admin::auth::authorize;
 use crate::admin::router::Operation;
 use crate::auth::{check_key_valid, get_session_token};
 use http::{HeaderMap, StatusCode};
 use matchit::Params;
-use rustfs_config::notify::{ENABLE_KEY, ENABLE_ON, NOTIFY_MQTT_SUB_SYS, NOTIFY_WEBHOOK_SUB_SYS};
+use rustfs_config::notify::{ENABLE_KEY, ENABLE_ON, NOTIFY_SUB_SYSTEMS};
 use rustfs_notify::EventName;
-use rustfs_notify::rules::{BucketNotificationConfig, PatternRules};
+use rustfs_notify::rules::{BucketNotificationConfig, PatternRules, TagRules};
+use rustfs_policy::policy::action::AdminAction;
 use s3s::header::CONTENT_LENGTH;
 use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
 use serde::{Deserialize, Serialize};
@@ -52,7 +54,7 @@ impl Operation for SetNotificationTarget {
             .map_err(|e| s3_error!(InvalidArgument, "invalid query parameters: {}", e))?;
 
         let target_type = query.target_type.to_lowercase();
-        if target_type != *NOTIFY_WEBHOOK_SUB_SYS && target_type != *NOTIFY_MQTT_SUB_SYS {
+        if !NOTIFY_SUB_SYSTEMS.contains(&target_type.as_str()) {
             return Err(s3_error!(InvalidArgument, "unsupported target type: {}", query.target_type));
         }
 
@@ -108,6 +110,53 @@ impl Operation for SetNotificationTarget {
     }
 }
 
+/// Rotate the credentials of a notification target, switching only once the new ones connect
+pub struct RotateTargetSecrets {}
+#[async_trait::async_trait]
+impl Operation for RotateTargetSecrets {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        let query: TargetQuery = from_bytes(req.uri.query().unwrap_or("").as_bytes())
+            .map_err(|e| s3_error!(InvalidArgument, "invalid query parameters: {}", e))?;
+
+        let target_type = query.target_type.to_lowercase();
+        if !NOTIFY_SUB_SYSTEMS.contains(&target_type.as_str()) {
+            return Err(s3_error!(InvalidArgument, "unsupported target type: {}", query.target_type));
+        }
+
+        authorize(&req, AdminAction::ConfigUpdateAdminAction).await?;
+
+        let Some(ns) = rustfs_notify::global::notification_system() else {
+            return Err(s3_error!(InternalError, "notification system not initialized"));
+        };
+
+        // The body maps credential keys, such as `auth_token` or `password`, to their new values
+        let mut input = req.input;
+        let body = input.store_all_unlimited().await.map_err(|e| {
+            warn!("failed to read request body: {:?}", e);
+            s3_error!(InvalidRequest, "failed to read request body")
+        })?;
+        let secrets: HashMap<String, String> =
+            serde_json::from_slice(&body).map_err(|e| s3_error!(InvalidArgument, "invalid json body for secrets: {}", e))?;
+        if secrets.is_empty() {
+            return Err(s3_error!(InvalidArgument, "no secrets to rotate"));
+        }
+        let secrets: Vec<(String, String)> = secrets.into_iter().collect();
+
+        info!("Rotating secrets of target type '{}', name '{}'", &target_type, &query.target_name);
+        ns.rotate_target_secrets(&target_type, &query.target_name, &secrets)
+            .await
+            .map_err(|e| {
+                error!("failed to rotate target secrets: {}", e);
+                S3Error::with_message(S3ErrorCode::InvalidRequest, format!("failed to rotate target secrets: {e}"))
+            })?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        header.insert(CONTENT_LENGTH, "0".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::empty()), header))
+    }
+}
+
 /// Get a list of notification targets for all activities
 pub struct ListNotificationTargets {}
 #[async_trait::async_trait]
@@ -237,6 +286,8 @@ impl Operation for SetBucketNotification {
 #[derive(Serialize)]
 struct BucketRulesResponse {
     rules: HashMap<EventName, PatternRules>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    tag_rules: HashMap<EventName, TagRules>,
 }
 pub struct GetBucketNotification {}
 #[async_trait::async_trait]
@@ -255,9 +306,10 @@ impl Operation for GetBucketNotification {
             return Err(s3_error!(InternalError, "notification system not initialized"));
         };
 
-        let rules_map = ns.notifier.get_rules_map(&query.bucket_name);
+        let rules_map = ns.notifier.get_rules_map(&query.bucket_name).unwrap_or_default();
         let response = BucketRulesResponse {
-            rules: rules_map.unwrap_or_default().inner().clone(),
+            rules: rules_map.inner().clone(),
+            tag_rules: rules_map.tag_inner().clone(),
         };
 
         let data = serde_json::to_vec(&response)
@@ -0,0 +1,78 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::heat_map::{ObjectHeat, PrefixHeat, cluster_heat, top_objects, top_prefixes};
+use rustfs_ecstore::new_object_layer_fn;
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{Body, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::{Deserialize, Serialize};
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+const DEFAULT_TOP_HOT: usize = 10;
+const DEFAULT_PREFIX_DEPTH: usize = 1;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TopHotQuery {
+    pub count: Option<usize>,
+    /// Directories of the object key making up a prefix
+    #[serde(rename = "prefix-depth")]
+    pub prefix_depth: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HotReport {
+    pub objects: Vec<ObjectHeat>,
+    pub prefixes: Vec<PrefixHeat>,
+}
+
+pub struct TopHotObjects {}
+
+#[async_trait::async_trait]
+impl Operation for TopHotObjects {
+    // GET <endpoint>/<admin-API>/top/hot-objects?count=<count>&prefix-depth=<depth>
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle TopHotObjects");
+
+        let query: TopHotQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => TopHotQuery::default(),
+        };
+
+        authorize(&req, AdminAction::DataUsageInfoAdminAction).await?;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(s3_error!(InternalError, "Not init"));
+        };
+
+        let count = query.count.unwrap_or(DEFAULT_TOP_HOT);
+        let heat = cluster_heat(store).await;
+        let report = HotReport {
+            objects: top_objects(heat.clone(), count),
+            prefixes: top_prefixes(heat, count, query.prefix_depth.unwrap_or(DEFAULT_PREFIX_DEPTH)),
+        };
+
+        let data = serde_json::to_vec(&report).map_err(|e| s3_error!(InternalError, "marshal hot objects failed: {e}"))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::{
+    bucket::encryption::kms::{ERR_KMS_NOT_CONFIGURED, kms, kms_key_rotation_status, start_kms_key_rotation},
+    new_object_layer_fn,
+};
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::{Deserialize, Serialize};
+use serde_urlencoded::from_bytes;
+use tracing::warn;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct KmsKeyRotateQuery {
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    /// KMS key to re-wrap the data keys with, the default key of the KMS if empty
+    #[serde(default, rename = "key-id")]
+    pub key_id: String,
+}
+
+fn json_response<T: Serialize>(value: &T) -> S3Result<S3Response<(StatusCode, Body)>> {
+    let data = serde_json::to_vec(value).map_err(|e| s3_error!(InternalError, "Failed to serialize response: {}", e))?;
+
+    let mut header = HeaderMap::new();
+    header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+}
+
+pub struct KmsKeyRotate {}
+
+#[async_trait::async_trait]
+impl Operation for KmsKeyRotate {
+    // POST <endpoint>/<admin-API>/kms/key/rotate?bucket=xxx&prefix=xxx&key-id=xxx
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle KmsKeyRotate");
+
+        authorize(&req, AdminAction::KMSCreateKeyAdminAction).await?;
+
+        let query: KmsKeyRotateQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => KmsKeyRotateQuery::default(),
+        };
+        if query.bucket.is_empty() {
+            return Err(s3_error!(InvalidArgument, "bucket is required"));
+        }
+
+        let Some(kms) = kms() else {
+            return Err(S3Error::with_message(S3ErrorCode::NotImplemented, ERR_KMS_NOT_CONFIGURED.to_string()));
+        };
+        let key_id = if query.key_id.is_empty() {
+            kms.default_key_id().to_string()
+        } else {
+            query.key_id
+        };
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        start_kms_key_rotation(store, &query.bucket, &query.prefix, &key_id)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidRequest, e.to_string()))?;
+
+        warn!("KMS key rotation of bucket {} to {} started", query.bucket, key_id);
+        json_response(&kms_key_rotation_status())
+    }
+}
+
+pub struct KmsKeyRotateStatus {}
+
+#[async_trait::async_trait]
+impl Operation for KmsKeyRotateStatus {
+    // GET <endpoint>/<admin-API>/kms/key/rotate/status
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        authorize(&req, AdminAction::KMSKeyStatusAdminAction).await?;
+
+        let Some(status) = kms_key_rotation_status() else {
+            return Err(s3_error!(InvalidRequest, "no KMS key rotation has run on this node"));
+        };
+        json_response(&status)
+    }
+}
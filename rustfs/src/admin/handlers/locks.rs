@@ -0,0 +1,354 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_common::globals::GLOBAL_Local_Node_Name;
+use rustfs_ecstore::notification_sys::get_global_notification_sys;
+use rustfs_lock::{HeldLock, LockId, LockType, ResourceContention, audit::LockAuditEvent};
+use rustfs_obs::{ApiDetails, BaseLogEntry, log_audit_event};
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{Body, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::{Deserialize, Serialize};
+use serde_urlencoded::from_bytes;
+use std::collections::HashMap;
+use std::time::SystemTime;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tracing::warn;
+
+const DEFAULT_TOP_LOCKS: usize = 10;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TopLocksQuery {
+    pub count: Option<usize>,
+}
+
+/// A lock as reported by `top/locks`, merged across the nodes holding it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TopLock {
+    pub resource: String,
+    pub uid: String,
+    pub owner: String,
+    #[serde(rename = "type")]
+    pub lock_type: String,
+    /// RFC 3339 time of the earliest acquisition on any node
+    pub acquired: String,
+    #[serde(rename = "elapsedSecs")]
+    pub elapsed_secs: u64,
+    /// Most waiters seen on a single node
+    pub waiters: usize,
+    /// Number of nodes the lock is held on
+    pub nodes: usize,
+}
+
+/// Merge the locks listed by every node, oldest first, keeping at most `count`
+fn top_locks(locks: Vec<HeldLock>, count: usize, now: SystemTime) -> Vec<TopLock> {
+    let mut merged: HashMap<(String, String, String, bool), (HeldLock, usize)> = HashMap::new();
+    for lock in locks {
+        let key = (
+            lock.resource.clone(),
+            lock.uid.clone(),
+            lock.owner.clone(),
+            lock.lock_type == LockType::Exclusive,
+        );
+        merged
+            .entry(key)
+            .and_modify(|(held, nodes)| {
+                held.acquired_at = held.acquired_at.min(lock.acquired_at);
+                held.waiters = held.waiters.max(lock.waiters);
+                *nodes += 1;
+            })
+            .or_insert((lock, 1));
+    }
+
+    let mut held: Vec<_> = merged.into_values().collect();
+    held.sort_by(|(a, _), (b, _)| a.acquired_at.cmp(&b.acquired_at).then_with(|| a.resource.cmp(&b.resource)));
+    held.truncate(count);
+
+    held.into_iter()
+        .map(|(lock, nodes)| TopLock {
+            acquired: OffsetDateTime::from(lock.acquired_at).format(&Rfc3339).unwrap_or_default(),
+            elapsed_secs: now.duration_since(lock.acquired_at).unwrap_or_default().as_secs(),
+            lock_type: match lock.lock_type {
+                LockType::Exclusive => "WRITE",
+                LockType::Shared => "READ",
+            }
+            .to_string(),
+            resource: lock.resource,
+            uid: lock.uid,
+            owner: lock.owner,
+            waiters: lock.waiters,
+            nodes,
+        })
+        .collect()
+}
+
+/// Check the caller may perform `action`, returning its access key
+/// A contended resource as reported by `top/lock-contention`, with the stats of all nodes summed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LockHotspot {
+    pub resource: String,
+    /// Lock requests, each node a namespace lock asks counts one
+    pub requests: u64,
+    pub timeouts: u64,
+    #[serde(rename = "timeoutRate")]
+    pub timeout_rate: f64,
+    #[serde(rename = "p50WaitMs")]
+    pub p50_wait_ms: u64,
+    #[serde(rename = "p99WaitMs")]
+    pub p99_wait_ms: u64,
+    #[serde(rename = "maxWaitMs")]
+    pub max_wait_ms: u64,
+    #[serde(rename = "totalWaitMs")]
+    pub total_wait_ms: u64,
+}
+
+impl From<ResourceContention> for LockHotspot {
+    fn from(stat: ResourceContention) -> Self {
+        Self {
+            requests: stat.requests(),
+            timeouts: stat.timeouts,
+            timeout_rate: stat.timeout_rate(),
+            p50_wait_ms: stat.wait_quantile(0.5).as_millis() as u64,
+            p99_wait_ms: stat.wait_quantile(0.99).as_millis() as u64,
+            max_wait_ms: stat.max_wait.as_millis() as u64,
+            total_wait_ms: stat.total_wait.as_millis() as u64,
+            resource: stat.resource,
+        }
+    }
+}
+
+/// Record a forced unlock in the audit log
+fn audit_force_unlock(resource: &str, released: usize, access_key: &str) {
+    let api = ApiDetails::new()
+        .set_object(Some(resource.to_string()))
+        .set_status(Some("OK".to_string()))
+        .set_status_code(Some(200));
+    let message = format!("lock {resource} forced open on {released} nodes by {access_key}");
+
+    log_audit_event(
+        "admin:ForceUnlock".to_string(),
+        "admin",
+        api,
+        BaseLogEntry::new().message(Some(message)),
+        Some(access_key.to_string()),
+    );
+}
+
+/// Record a lock event that blocked or interrupted writes in the audit log
+pub(crate) fn audit_lock_event(event: LockAuditEvent) {
+    let nodes = if event.nodes.is_empty() {
+        // The node name is only written at startup, before any lock is taken
+        vec![GLOBAL_Local_Node_Name.try_read().map(|node| node.clone()).unwrap_or_default()]
+    } else {
+        event.nodes
+    };
+
+    let api = ApiDetails::new().set_object(Some(event.resource.clone()));
+    let tags = HashMap::from([
+        ("resource".to_string(), serde_json::Value::from(event.resource)),
+        ("owners".to_string(), serde_json::Value::from(event.owners)),
+        ("nodes".to_string(), serde_json::Value::from(nodes)),
+    ]);
+
+    log_audit_event(
+        event.kind.as_str().to_string(),
+        "lock",
+        api,
+        BaseLogEntry::new().message(Some(event.detail)).tags(Some(tags)),
+        None,
+    );
+}
+
+pub struct TopLocks {}
+
+#[async_trait::async_trait]
+impl Operation for TopLocks {
+    // GET <endpoint>/<admin-API>/top/locks?count=<count>
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle TopLocks");
+
+        let query: TopLocksQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => TopLocksQuery::default(),
+        };
+
+        authorize(&req, AdminAction::TopLocksAdminAction).await?;
+
+        let locks = match get_global_notification_sys() {
+            Some(sys) => sys.list_locks().await,
+            None => rustfs_lock::list_local_locks().await,
+        };
+        let top = top_locks(locks, query.count.unwrap_or(DEFAULT_TOP_LOCKS), SystemTime::now());
+
+        let data = serde_json::to_vec(&top).map_err(|e| s3_error!(InternalError, "marshal top locks failed: {e}"))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+pub struct TopLockContention {}
+
+#[async_trait::async_trait]
+impl Operation for TopLockContention {
+    // GET <endpoint>/<admin-API>/top/lock-contention?count=<count>
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle TopLockContention");
+
+        let query: TopLocksQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => TopLocksQuery::default(),
+        };
+
+        authorize(&req, AdminAction::TopLocksAdminAction).await?;
+
+        let count = query.count.unwrap_or(DEFAULT_TOP_LOCKS);
+        let stats = match get_global_notification_sys() {
+            Some(sys) => sys.lock_contention(count).await,
+            None => rustfs_lock::top_contended_local(count),
+        };
+        let hotspots: Vec<LockHotspot> = stats.into_iter().map(LockHotspot::from).collect();
+
+        let data = serde_json::to_vec(&hotspots).map_err(|e| s3_error!(InternalError, "marshal lock contention failed: {e}"))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ForceUnlockQuery {
+    /// Comma separated lock resources, as listed by `top/locks`
+    pub paths: String,
+}
+
+/// Result of forcing a lock open
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ForcedLock {
+    pub resource: String,
+    /// Number of nodes that held the lock
+    pub released: usize,
+}
+
+pub struct ForceUnlock {}
+
+#[async_trait::async_trait]
+impl Operation for ForceUnlock {
+    // POST <endpoint>/<admin-API>/force-unlock?paths=<resource>,<resource>
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle ForceUnlock");
+
+        let query: ForceUnlockQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => ForceUnlockQuery::default(),
+        };
+        let paths: Vec<&str> = query.paths.split(',').map(str::trim).filter(|p| !p.is_empty()).collect();
+        if paths.is_empty() {
+            return Err(s3_error!(InvalidArgument, "paths is required"));
+        }
+
+        let access_key = authorize(&req, AdminAction::ForceUnlockAdminAction).await?;
+        let owner = format!("force-unlock:{access_key}");
+
+        let mut forced = Vec::with_capacity(paths.len());
+        for resource in paths {
+            let released = match get_global_notification_sys() {
+                Some(sys) => sys.force_unlock(resource, &owner).await,
+                None => rustfs_lock::force_unlock_local(&LockId::new_deterministic(resource)).await as usize,
+            };
+            audit_force_unlock(resource, released, &access_key);
+            forced.push(ForcedLock {
+                resource: resource.to_string(),
+                released,
+            });
+        }
+
+        let data = serde_json::to_vec(&forced).map_err(|e| s3_error!(InternalError, "marshal force unlock failed: {e}"))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn held(resource: &str, owner: &str, lock_type: LockType, acquired_at: SystemTime, waiters: usize) -> HeldLock {
+        HeldLock {
+            resource: resource.to_string(),
+            uid: format!("{resource}-uid"),
+            owner: owner.to_string(),
+            lock_type,
+            acquired_at,
+            waiters,
+        }
+    }
+
+    #[test]
+    fn test_top_locks_merges_nodes() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let locks = vec![
+            held("bucket/a", "n1", LockType::Exclusive, now - Duration::from_secs(5), 0),
+            held("bucket/a", "n1", LockType::Exclusive, now - Duration::from_secs(7), 2),
+            held("bucket/b", "n2", LockType::Shared, now - Duration::from_secs(60), 0),
+            held("bucket/c", "n3", LockType::Shared, now - Duration::from_secs(1), 0),
+        ];
+
+        let top = top_locks(locks, 2, now);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].resource, "bucket/b");
+        assert_eq!(top[0].lock_type, "READ");
+        assert_eq!(top[0].elapsed_secs, 60);
+        assert_eq!(top[1].resource, "bucket/a");
+        assert_eq!(top[1].lock_type, "WRITE");
+        assert_eq!(top[1].elapsed_secs, 7);
+        assert_eq!(top[1].waiters, 2);
+        assert_eq!(top[1].nodes, 2);
+    }
+
+    #[test]
+    fn test_lock_hotspot_reports_wait_quantiles() {
+        let mut stat = ResourceContention::new("bucket/a");
+        stat.acquired = 3;
+        stat.timeouts = 1;
+        stat.total_wait = Duration::from_millis(1_300);
+        stat.max_wait = Duration::from_millis(1_000);
+        // two waits under 1ms, one under 512ms, one timed out after a second
+        stat.wait_buckets[0] = 2;
+        stat.wait_buckets[9] = 1;
+        stat.wait_buckets[10] = 1;
+
+        let hotspot = LockHotspot::from(stat);
+        assert_eq!(hotspot.requests, 4);
+        assert_eq!(hotspot.timeout_rate, 0.25);
+        assert_eq!(hotspot.p50_wait_ms, 1);
+        assert_eq!(hotspot.p99_wait_ms, 1_000);
+        assert_eq!(hotspot.total_wait_ms, 1_300);
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::{
+    new_object_layer_fn,
+    reencode::{ReencodeRequest, cancel_reencode, reencode_status},
+};
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::Serialize;
+use tracing::warn;
+
+#[derive(Debug, Serialize)]
+pub struct ReencodeResp {
+    pub id: String,
+}
+
+fn json_response<T: Serialize>(value: &T) -> S3Result<S3Response<(StatusCode, Body)>> {
+    let data = serde_json::to_vec(value).map_err(|e| s3_error!(InternalError, "Failed to serialize response: {}", e))?;
+
+    let mut header = HeaderMap::new();
+    header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+}
+
+pub struct ReencodeStart {}
+
+#[async_trait::async_trait]
+impl Operation for ReencodeStart {
+    // POST <endpoint>/<admin-API>/reencode/start
+    // @body: ReencodeRequest
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle ReencodeStart");
+
+        authorize(&req, AdminAction::RebalanceAdminAction).await?;
+
+        let mut input = req.input;
+        let body = match input.store_all_unlimited().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("get body failed, e: {:?}", e);
+                return Err(s3_error!(InvalidRequest, "get body failed"));
+            }
+        };
+
+        let request: ReencodeRequest = serde_json::from_slice(&body)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidRequest, format!("unmarshal body err {e}")))?;
+        if request.bucket.is_empty() {
+            return Err(s3_error!(InvalidArgument, "bucket is required"));
+        }
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        if store.is_decommission_running().await || store.is_rebalance_started().await {
+            return Err(s3_error!(
+                InvalidRequest,
+                "Re-encode cannot be started while a decommission or rebalance is in progress"
+            ));
+        }
+
+        let id = store
+            .start_reencode(request)
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidRequest, e.to_string()))?;
+
+        warn!("Re-encode started with id: {}", id);
+        json_response(&ReencodeResp { id })
+    }
+}
+
+pub struct ReencodeStatus {}
+
+#[async_trait::async_trait]
+impl Operation for ReencodeStatus {
+    // GET <endpoint>/<admin-API>/reencode/status
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle ReencodeStatus");
+
+        authorize(&req, AdminAction::RebalanceAdminAction).await?;
+
+        let Some(progress) = reencode_status() else {
+            return Err(s3_error!(NoSuchKey, "no re-encode job found"));
+        };
+        json_response(&progress)
+    }
+}
+
+pub struct ReencodeCancel {}
+
+#[async_trait::async_trait]
+impl Operation for ReencodeCancel {
+    // POST <endpoint>/<admin-API>/reencode/cancel
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle ReencodeCancel");
+
+        authorize(&req, AdminAction::RebalanceAdminAction).await?;
+
+        if !cancel_reencode() {
+            return Err(s3_error!(InvalidRequest, "no re-encode job is running"));
+        }
+        Ok(S3Response::new((StatusCode::OK, Body::empty())))
+    }
+}
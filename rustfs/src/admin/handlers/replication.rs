@@ -0,0 +1,91 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::{auth::authorize, router::Operation};
+use http::{HeaderMap, StatusCode};
+use matchit::Params;
+use rustfs_ecstore::bucket::metadata_sys::get_replication_config;
+use rustfs_ecstore::cmd::bucket_replication::resync_bucket_target;
+use rustfs_ecstore::cmd::bucket_targets::get_bucket_target_client;
+use rustfs_ecstore::new_object_layer_fn;
+use rustfs_policy::policy::action::AdminAction;
+use s3s::{Body, S3Request, S3Response, S3Result, header::CONTENT_TYPE, s3_error};
+use serde::{Deserialize, Serialize};
+use serde_urlencoded::from_bytes;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ResyncQuery {
+    pub bucket: String,
+    pub arn: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResyncStarted {
+    pub bucket: String,
+    pub arn: String,
+}
+
+pub struct ResyncBucketTarget {}
+
+#[async_trait::async_trait]
+impl Operation for ResyncBucketTarget {
+    // POST <endpoint>/<admin-API>/v3/replication/resync?bucket=<bucket>&arn=<target-arn>
+    #[tracing::instrument(skip_all)]
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle ResyncBucketTarget");
+
+        let query: ResyncQuery = match req.uri.query() {
+            Some(query) => from_bytes(query.as_bytes()).map_err(|_e| s3_error!(InvalidArgument, "get query failed"))?,
+            None => ResyncQuery::default(),
+        };
+        if query.bucket.is_empty() || query.arn.is_empty() {
+            return Err(s3_error!(InvalidArgument, "bucket and arn are required"));
+        }
+
+        authorize(&req, AdminAction::SiteReplicationResyncAction).await?;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(s3_error!(InternalError, "Not init"));
+        };
+
+        get_replication_config(&query.bucket)
+            .await
+            .map_err(|e| s3_error!(InvalidRequest, "bucket {} has no replication config: {e}", query.bucket))?;
+        get_bucket_target_client(&query.bucket, &query.arn)
+            .await
+            .map_err(|e| s3_error!(InvalidArgument, "unknown replication target {}: {e}", query.arn))?;
+
+        // The resync walks the whole bucket, the request only starts it
+        let (bucket, arn) = (query.bucket.clone(), query.arn.clone());
+        tokio::spawn(async move {
+            match resync_bucket_target(&bucket, &arn, store).await {
+                Ok(count) => info!("resync of {} to {} replicated {} versions", bucket, arn, count),
+                Err(err) => error!("resync of {} to {} failed: {}", bucket, arn, err),
+            }
+        });
+
+        let data = serde_json::to_vec(&ResyncStarted {
+            bucket: query.bucket,
+            arn: query.arn,
+        })
+        .map_err(|e| s3_error!(InternalError, "marshal resync failed: {e}"))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+        Ok(S3Response::with_headers((StatusCode::ACCEPTED, Body::from(data)), header))
+    }
+}
@@ -13,18 +13,26 @@
 // limitations under the License.
 
 use crate::{
-    admin::router::Operation,
+    admin::{auth::authorize, router::Operation},
     auth::{check_key_valid, get_session_token},
 };
-use http::StatusCode;
+use http::{HeaderMap, StatusCode};
 use matchit::Params;
 use rustfs_ecstore::bucket::utils::serialize;
-use rustfs_iam::{manager::get_token_signing_key, sys::SESSION_POLICY_NAME};
-use rustfs_policy::{auth::get_new_credentials_with_metadata, policy::Policy};
+use rustfs_iam::{
+    manager::get_token_signing_key,
+    revoke::{ISSUED_AT_CLAIM, SessionRevocation},
+    sys::SESSION_POLICY_NAME,
+};
+use rustfs_policy::{
+    auth::get_new_credentials_with_metadata,
+    policy::{Policy, action::AdminAction},
+};
 use rustfs_utils::crypto::base64_encode;
 use s3s::{
     Body, S3Error, S3ErrorCode, S3Request, S3Response, S3Result,
     dto::{AssumeRoleOutput, Credentials, Timestamp},
+    header::CONTENT_TYPE,
     s3_error,
 };
 use serde::Deserialize;
@@ -108,6 +116,10 @@ impl Operation for AssumeRoleHandle {
         );
 
         claims.insert("parent".to_string(), serde_json::Value::String(cred.access_key.clone()));
+        claims.insert(
+            ISSUED_AT_CLAIM.to_string(),
+            serde_json::Value::Number(serde_json::Number::from(OffsetDateTime::now_utc().unix_timestamp())),
+        );
 
         // warn!("AssumeRole get cred {:?}", &user);
         // warn!("AssumeRole get body {:?}", &body);
@@ -160,6 +172,102 @@ impl Operation for AssumeRoleHandle {
     }
 }
 
+/// Request body of `revoke-sts-sessions`
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RevokeStsSessionsReq {
+    /// Parent user whose sessions are revoked
+    pub user: String,
+    /// Revoke the sessions of every user, required when `user` is empty
+    pub all: bool,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub issued_after: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub issued_before: Option<OffsetDateTime>,
+}
+
+pub struct RevokeStsSessions {}
+#[async_trait::async_trait]
+impl Operation for RevokeStsSessions {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle RevokeStsSessions");
+
+        let access_key = authorize(&req, AdminAction::RevokeTemporaryAccountsAdminAction).await?;
+
+        let mut input = req.input;
+        let body = match input.store_all_unlimited().await {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("get body failed, e: {:?}", e);
+                return Err(s3_error!(InvalidRequest, "get body failed"));
+            }
+        };
+
+        let body: RevokeStsSessionsReq = serde_json::from_slice(&body)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidRequest, format!("unmarshal body err {e}")))?;
+
+        if body.user.is_empty() && !body.all {
+            return Err(s3_error!(InvalidArgument, "user is required unless all is set"));
+        }
+
+        let revocation = SessionRevocation {
+            user: body.user,
+            issued_after: body.issued_after,
+            issued_before: body.issued_before,
+            revoked_at: OffsetDateTime::now_utc(),
+            revoked_by: access_key,
+        };
+        revocation
+            .validate()
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidArgument, e.to_string()))?;
+
+        let Ok(iam_store) = rustfs_iam::get() else { return Err(s3_error!(InternalError, "iam not init")) };
+
+        iam_store.revoke_sts_sessions(revocation.clone()).await.map_err(|e| {
+            warn!("revoke sts sessions failed, e: {:?}", e);
+            S3Error::with_message(S3ErrorCode::InternalError, e.to_string())
+        })?;
+
+        info!(
+            "revoked sts sessions of {} issued between {:?} and {:?} by {}",
+            if revocation.user.is_empty() {
+                "all users"
+            } else {
+                &revocation.user
+            },
+            revocation.issued_after,
+            revocation.issued_before.unwrap_or(revocation.revoked_at),
+            revocation.revoked_by
+        );
+
+        let data = serde_json::to_vec(&revocation)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal body err {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
+pub struct ListStsRevocations {}
+#[async_trait::async_trait]
+impl Operation for ListStsRevocations {
+    async fn call(&self, req: S3Request<Body>, _params: Params<'_, '_>) -> S3Result<S3Response<(StatusCode, Body)>> {
+        warn!("handle ListStsRevocations");
+
+        authorize(&req, AdminAction::ListTemporaryAccountsAdminAction).await?;
+
+        let Ok(iam_store) = rustfs_iam::get() else { return Err(s3_error!(InternalError, "iam not init")) };
+
+        let data = serde_json::to_vec(&iam_store.list_sts_revocations())
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InternalError, format!("marshal body err {e}")))?;
+
+        let mut header = HeaderMap::new();
+        header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        Ok(S3Response::with_headers((StatusCode::OK, Body::from(data)), header))
+    }
+}
+
 pub fn populate_session_policy(claims: &mut HashMap<String, Value>, policy: &str) -> S3Result<()> {
     if !policy.is_empty() {
         let session_policy = Policy::parse_config(policy.as_bytes())
@@ -107,6 +107,12 @@ impl Operation for AddTier {
             TierType::S3 => {
                 args.name = args.s3.clone().unwrap().name;
             }
+            TierType::Azure => {
+                args.name = args.azure.clone().unwrap().name;
+            }
+            TierType::GCS => {
+                args.name = args.gcs.clone().unwrap().name;
+            }
             TierType::RustFS => {
                 args.name = args.rustfs.clone().unwrap().name;
             }
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod auth;
 pub mod console;
 pub mod handlers;
 pub mod router;
@@ -20,12 +21,15 @@ pub mod utils;
 
 // use ecstore::global::{is_dist_erasure, is_erasure};
 use handlers::{
-    bucket_meta, group, policies, pools, rebalance,
+    access_check, bucket_encryption, bucket_grant, bucket_meta, bucket_quota, bucket_usage, checksum_manifest, console_log,
+    effective_policy, group, heat, kms, locks, policies, pools, rebalance, reencode, replication,
     service_account::{AddServiceAccount, DeleteServiceAccount, InfoServiceAccount, ListServiceAccount, UpdateServiceAccount},
     sts, tier, user,
 };
 
-use crate::admin::handlers::event::{ListNotificationTargets, RemoveNotificationTarget, SetNotificationTarget};
+use crate::admin::handlers::event::{
+    ListNotificationTargets, RemoveNotificationTarget, RotateTargetSecrets, SetNotificationTarget,
+};
 use handlers::{GetReplicationMetricsHandler, ListRemoteTargetHandler, RemoveRemoteTargetHandler, SetRemoteTargetHandler};
 use hyper::Method;
 use router::{AdminOperation, S3Router};
@@ -55,6 +59,26 @@ pub fn make_admin_route(console_enabled: bool) -> std::io::Result<impl S3Route>
         format!("{}{}", ADMIN_PREFIX, "/v3/info").as_str(),
         AdminOperation(&handlers::ServerInfoHandler {}),
     )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/top/locks").as_str(),
+        AdminOperation(&locks::TopLocks {}),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/top/lock-contention").as_str(),
+        AdminOperation(&locks::TopLockContention {}),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/top/hot-objects").as_str(),
+        AdminOperation(&heat::TopHotObjects {}),
+    )?;
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/force-unlock").as_str(),
+        AdminOperation(&locks::ForceUnlock {}),
+    )?;
     r.insert(
         Method::GET,
         format!("{}{}", ADMIN_PREFIX, "/v3/inspect-data").as_str(),
@@ -82,6 +106,23 @@ pub fn make_admin_route(console_enabled: bool) -> std::io::Result<impl S3Route>
         format!("{}{}", ADMIN_PREFIX, "/v3/metrics").as_str(),
         AdminOperation(&handlers::MetricsHandler {}),
     )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/metrics/prometheus").as_str(),
+        AdminOperation(&handlers::PrometheusMetricsHandler {}),
+    )?;
+    // bucket-usage?[bucket=xxx]&[prefix=xxx]
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/bucket-usage").as_str(),
+        AdminOperation(&bucket_usage::BucketUsage {}),
+    )?;
+    // ?[node=xxx]&[limit=xxx]
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/log").as_str(),
+        AdminOperation(&console_log::ConsoleLog {}),
+    )?;
 
     // 1
     r.insert(
@@ -124,6 +165,35 @@ pub fn make_admin_route(console_enabled: bool) -> std::io::Result<impl S3Route>
         AdminOperation(&rebalance::RebalanceStop {}),
     )?;
 
+    // @body: ReencodeRequest
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/reencode/start").as_str(),
+        AdminOperation(&reencode::ReencodeStart {}),
+    )?;
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/reencode/status").as_str(),
+        AdminOperation(&reencode::ReencodeStatus {}),
+    )?;
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/reencode/cancel").as_str(),
+        AdminOperation(&reencode::ReencodeCancel {}),
+    )?;
+
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/rotate").as_str(),
+        AdminOperation(&kms::KmsKeyRotate {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/kms/key/rotate/status").as_str(),
+        AdminOperation(&kms::KmsKeyRotateStatus {}),
+    )?;
+
     // Some APIs are only available in EC mode
     // if is_dist_erasure().await || is_erasure().await {
     r.insert(
@@ -181,6 +251,12 @@ pub fn make_admin_route(console_enabled: bool) -> std::io::Result<impl S3Route>
         AdminOperation(&bucket_meta::ExportBucketMetadata {}),
     )?;
 
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/export-checksums").as_str(),
+        AdminOperation(&checksum_manifest::ExportChecksums {}),
+    )?;
+
     r.insert(
         Method::PUT,
         format!("{}{}", ADMIN_PREFIX, "/import-bucket-metadata").as_str(),
@@ -199,6 +275,13 @@ pub fn make_admin_route(console_enabled: bool) -> std::io::Result<impl S3Route>
         AdminOperation(&GetReplicationMetricsHandler {}),
     )?;
 
+    // ?bucket=xxx&arn=xxx
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/replication/resync").as_str(),
+        AdminOperation(&replication::ResyncBucketTarget {}),
+    )?;
+
     r.insert(
         Method::PUT,
         format!("{}{}", ADMIN_PREFIX, "/v3/set-remote-target").as_str(),
@@ -223,6 +306,13 @@ fn register_user_route(r: &mut S3Router<AdminOperation>) -> std::io::Result<()>
         AdminOperation(&handlers::AccountInfoHandler {}),
     )?;
 
+    // @body: CheckAccessReq
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/check-access").as_str(),
+        AdminOperation(&access_check::CheckAccess {}),
+    )?;
+
     // ?[bucket=xxx]
     r.insert(
         Method::GET,
@@ -366,6 +456,77 @@ fn register_user_route(r: &mut S3Router<AdminOperation>) -> std::io::Result<()>
         AdminOperation(&policies::SetPolicyForUserOrGroup {}),
     )?;
 
+    // set-bucket-encryption-policy?bucket=xxx
+    // @body: BucketEncryptionPolicy
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/set-bucket-encryption-policy").as_str(),
+        AdminOperation(&bucket_encryption::SetBucketEncryptionPolicy {}),
+    )?;
+
+    // get-bucket-encryption-policy?bucket=xxx
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/get-bucket-encryption-policy").as_str(),
+        AdminOperation(&bucket_encryption::GetBucketEncryptionPolicy {}),
+    )?;
+
+    // set-bucket-quota?bucket=xxx
+    // @body: BucketQuota
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/set-bucket-quota").as_str(),
+        AdminOperation(&bucket_quota::SetBucketQuota {}),
+    )?;
+
+    // get-bucket-quota?bucket=xxx
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/get-bucket-quota").as_str(),
+        AdminOperation(&bucket_quota::GetBucketQuota {}),
+    )?;
+
+    // effective-policy?bucket=xxx&prefix=xxx
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/effective-policy").as_str(),
+        AdminOperation(&effective_policy::GetEffectivePolicy {}),
+    )?;
+
+    // @body: AddBucketGrantReq
+    r.insert(
+        Method::PUT,
+        format!("{}{}", ADMIN_PREFIX, "/v3/add-bucket-grant").as_str(),
+        AdminOperation(&bucket_grant::AddBucketGrant {}),
+    )?;
+
+    // list-bucket-grants?[bucket=xxx]
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/list-bucket-grants").as_str(),
+        AdminOperation(&bucket_grant::ListBucketGrants {}),
+    )?;
+
+    // revoke-bucket-grant?name=xxx
+    r.insert(
+        Method::DELETE,
+        format!("{}{}", ADMIN_PREFIX, "/v3/revoke-bucket-grant").as_str(),
+        AdminOperation(&bucket_grant::RevokeBucketGrant {}),
+    )?;
+
+    // @body: RevokeStsSessionsReq
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/revoke-sts-sessions").as_str(),
+        AdminOperation(&sts::RevokeStsSessions {}),
+    )?;
+
+    r.insert(
+        Method::GET,
+        format!("{}{}", ADMIN_PREFIX, "/v3/list-sts-revocations").as_str(),
+        AdminOperation(&sts::ListStsRevocations {}),
+    )?;
+
     r.insert(
         Method::GET,
         format!("{}{}", ADMIN_PREFIX, "/v3/target-list").as_str(),
@@ -378,6 +539,15 @@ fn register_user_route(r: &mut S3Router<AdminOperation>) -> std::io::Result<()>
         AdminOperation(&SetNotificationTarget {}),
     )?;
 
+    // Rotate the credentials of a notification target
+    // target-rotate-secrets?targetType=xxx&targetName=xxx, body {"auth_token": "..."}
+    // The new credentials are probed against the endpoint before the target switches to them.
+    r.insert(
+        Method::POST,
+        format!("{}{}", ADMIN_PREFIX, "/v3/target-rotate-secrets").as_str(),
+        AdminOperation(&RotateTargetSecrets {}),
+    )?;
+
     // Remove notification target
     // This endpoint removes a notification target based on its type and name.
     // target-remove?target_type=xxx&target_name=xxx
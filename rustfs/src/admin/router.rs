@@ -34,6 +34,7 @@ use tracing::error;
 use crate::admin::ADMIN_PREFIX;
 use crate::admin::console;
 use crate::admin::rpc::RPC_PREFIX;
+use crate::server::{NodePeer, node_rpc_allowed};
 
 const CONSOLE_PREFIX: &str = "/rustfs/console";
 
@@ -142,6 +143,9 @@ where
         if req.uri.path().starts_with(RPC_PREFIX) {
             // Skip signature verification for HEAD requests (health checks)
             if req.method != Method::HEAD {
+                if !node_rpc_allowed(req.extensions.get::<NodePeer>()) {
+                    return Err(s3_error!(AccessDenied, "Node certificate required"));
+                }
                 verify_rpc_signature(&req.uri.to_string(), &req.method, &req.headers).map_err(|e| {
                     error!("RPC signature verification failed: {}", e);
                     s3_error!(AccessDenied, "{}", e)
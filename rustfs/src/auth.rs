@@ -12,8 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::server::ConnectionInfo;
 use http::HeaderMap;
 use http::Uri;
+use rustfs_ecstore::bucket::tagging::decode_tags;
 use rustfs_ecstore::global::get_global_action_cred;
 use rustfs_iam::error::Error as IamError;
 use rustfs_iam::sys::SESSION_POLICY_NAME;
@@ -201,20 +203,16 @@ pub fn get_condition_values(header: &HeaderMap, cred: &auth::Credentials) -> Has
         clone_header.remove("x-amz-signature-age");
     }
 
-    // TODO: parse_object_tags
-    // if let Some(_user_tags) = clone_header.get("x-amz-tagging") {
-    // TODO: parse_object_tags
-    // if let Ok(tag) = tags::parse_object_tags(user_tags.to_str().unwrap_or("")) {
-    //     let tag_map = tag.to_map();
-    //     let mut keys = Vec::new();
-    //     for (k, v) in tag_map {
-    //         args.insert(format!("ExistingObjectTag/{}", k), vec![v.clone()]);
-    //         args.insert(format!("RequestObjectTag/{}", k), vec![v.clone()]);
-    //         keys.push(k);
-    //     }
-    //     args.insert("RequestObjectTagKeys".to_string(), keys);
-    // }
-    // }
+    if let Some(tagging) = clone_header.get("x-amz-tagging").and_then(|v| v.to_str().ok()) {
+        let mut keys = Vec::new();
+        for tag in decode_tags(tagging) {
+            if let (Some(key), Some(value)) = (tag.key, tag.value) {
+                args.insert(format!("RequestObjectTag/{key}"), vec![value]);
+                keys.push(key);
+            }
+        }
+        args.insert("RequestObjectTagKeys".to_string(), keys);
+    }
 
     for obj_lock in &[
         "x-amz-object-lock-mode",
@@ -310,6 +308,32 @@ pub fn get_condition_values(header: &HeaderMap, cred: &auth::Credentials) -> Has
     args
 }
 
+/// Query parameters of a listing that policies can test with `s3:prefix`, `s3:delimiter` and `s3:max-keys`
+const LIST_CONDITION_PARAMS: &[&str] = &["prefix", "delimiter", "max-keys"];
+
+/// Adds the condition values of a request that do not come from its headers: `aws:SourceIp` and
+/// `aws:SecureTransport` from its connection, and the listing parameters of its query
+///
+/// They replace any header of the same name, which the client could otherwise set to satisfy a condition.
+/// The source address is that of the connection, a proxy in front of the server is the source.
+pub fn add_request_conditions(conditions: &mut HashMap<String, Vec<String>>, uri: &Uri, conn: Option<&ConnectionInfo>) {
+    if let Some(conn) = conn {
+        if let Some(addr) = conn.remote_addr {
+            conditions.insert("SourceIp".to_string(), vec![addr.ip().to_canonical().to_string()]);
+        }
+        conditions.insert("SecureTransport".to_string(), vec![conn.secure.to_string()]);
+    }
+
+    for param in LIST_CONDITION_PARAMS {
+        conditions.remove(*param);
+    }
+    for (key, value) in form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes()) {
+        if LIST_CONDITION_PARAMS.contains(&key.as_ref()) {
+            conditions.entry(key.into_owned()).or_default().push(value.into_owned());
+        }
+    }
+}
+
 pub fn get_query_param<'a>(query: &'a str, param_name: &str) -> Option<&'a str> {
     let param_name = param_name.to_lowercase();
 
@@ -597,6 +621,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_condition_values_with_request_object_tags() {
+        let cred = create_test_credentials();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-amz-tagging", HeaderValue::from_static("project=web&stage=prod"));
+
+        let conditions = get_condition_values(&headers, &cred);
+
+        assert_eq!(conditions.get("RequestObjectTag/project"), Some(&vec!["web".to_string()]));
+        assert_eq!(conditions.get("RequestObjectTag/stage"), Some(&vec!["prod".to_string()]));
+        assert_eq!(
+            conditions.get("RequestObjectTagKeys"),
+            Some(&vec!["project".to_string(), "stage".to_string()])
+        );
+        assert!(!conditions.contains_key("ExistingObjectTag/project"));
+        assert!(!conditions.contains_key("x-amz-tagging"));
+    }
+
     #[test]
     fn test_get_condition_values_with_signature_age() {
         let cred = create_test_credentials();
@@ -758,4 +800,29 @@ mod tests {
 
         assert!(!cred.is_service_account());
     }
+
+    #[test]
+    fn test_add_request_conditions() {
+        let mut headers = HeaderMap::new();
+        headers.insert("prefix", HeaderValue::from_static("spoofed/"));
+        headers.insert("sourceip", HeaderValue::from_static("10.0.0.1"));
+        let mut conditions = get_condition_values(&headers, &create_test_credentials());
+
+        let uri: Uri = "/bucket?list-type=2&prefix=photos%2F2024&max-keys=10".parse().unwrap();
+        let conn = ConnectionInfo {
+            remote_addr: Some("[::ffff:192.168.1.7]:9000".parse().unwrap()),
+            secure: true,
+        };
+        add_request_conditions(&mut conditions, &uri, Some(&conn));
+
+        assert_eq!(conditions.get("prefix"), Some(&vec!["photos/2024".to_string()]));
+        assert_eq!(conditions.get("max-keys"), Some(&vec!["10".to_string()]));
+        assert_eq!(conditions.get("delimiter"), None);
+        assert_eq!(conditions.get("SourceIp"), Some(&vec!["192.168.1.7".to_string()]));
+        assert_eq!(conditions.get("SecureTransport"), Some(&vec!["true".to_string()]));
+
+        let mut conditions = HashMap::new();
+        add_request_conditions(&mut conditions, &"/bucket".parse().unwrap(), None);
+        assert!(conditions.is_empty());
+    }
 }
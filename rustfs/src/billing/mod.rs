@@ -0,0 +1,479 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Usage records for billing
+//!
+//! Every node counts the S3 requests it serves per bucket together with the bytes uploaded to and
+//! downloaded from it. Once per `RUSTFS_BILLING_INTERVAL` each node sends one record per bucket
+//! with the counts of the period to the billing webhook (`RUSTFS_BILLING_WEBHOOK_ENDPOINT`) and/or
+//! Kafka topic (`RUSTFS_BILLING_KAFKA_BROKERS`, `RUSTFS_BILLING_KAFKA_TOPIC`, needs the `kafka`
+//! feature). Stored bytes come from the latest data usage scan and are only reported by the node
+//! owning the first endpoint, so summing the records of all nodes never counts storage twice.
+//!
+//! A bucket belongs to the tenant named by its `RUSTFS_BILLING_TENANT_TAG` tag (`tenant` by
+//! default); the field is left out for untagged buckets.
+//!
+//! Records, version 1 (the webhook receives a JSON array of them per node and period, Kafka one
+//! message per record keyed by bucket):
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "node": "node1:9000",
+//!   "bucket": "photos",
+//!   "tenant": "acme",
+//!   "periodStart": "2025-01-01T00:00:00+00:00",
+//!   "periodEnd": "2025-01-01T01:00:00+00:00",
+//!   "requests": 1200,
+//!   "bytesIn": 52428800,
+//!   "bytesOut": 1073741824,
+//!   "storedBytes": 10737418240,
+//!   "objects": 5321,
+//!   "byteHours": 10737418240.0
+//! }
+//! ```
+//!
+//! `storedBytes`, `objects` and `byteHours` (stored bytes times the hours of the period) are only
+//! present in the records of the node reporting storage. Nodes skip buckets without requests and
+//! buckets that no longer exist. Requests are counted once they are authorized, downloads by the
+//! bytes actually sent. Records a sink fails to take are sent to it again with the next period, up
+//! to [`MAX_PENDING_RECORDS`]; counts of a node that crashes before reporting are lost.
+
+mod sink;
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use pin_project_lite::pin_project;
+use rustfs_ecstore::bucket::metadata_sys;
+use rustfs_ecstore::data_usage::load_data_usage_from_backend;
+use rustfs_ecstore::global::get_global_endpoints;
+use rustfs_ecstore::store::ECStore;
+use serde::Serialize;
+use tracing::warn;
+
+pub use sink::UsageSink;
+
+/// Environment variable holding the seconds between usage records
+pub const ENV_BILLING_INTERVAL: &str = "RUSTFS_BILLING_INTERVAL";
+/// Environment variable holding the URL usage records are posted to
+pub const ENV_BILLING_WEBHOOK_ENDPOINT: &str = "RUSTFS_BILLING_WEBHOOK_ENDPOINT";
+/// Environment variable holding the bearer token sent to the billing webhook
+pub const ENV_BILLING_WEBHOOK_AUTH_TOKEN: &str = "RUSTFS_BILLING_WEBHOOK_AUTH_TOKEN";
+/// Environment variable holding the Kafka brokers usage records are produced to
+pub const ENV_BILLING_KAFKA_BROKERS: &str = "RUSTFS_BILLING_KAFKA_BROKERS";
+/// Environment variable holding the Kafka topic of usage records
+pub const ENV_BILLING_KAFKA_TOPIC: &str = "RUSTFS_BILLING_KAFKA_TOPIC";
+/// Environment variable holding the bucket tag naming the tenant of a bucket
+pub const ENV_BILLING_TENANT_TAG: &str = "RUSTFS_BILLING_TENANT_TAG";
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(3600);
+const DEFAULT_TENANT_TAG: &str = "tenant";
+const DEFAULT_KAFKA_TOPIC: &str = "rustfs-usage";
+
+/// Version of the usage record schema
+pub const USAGE_RECORD_VERSION: u32 = 1;
+
+/// Buckets counted per node; requests to other buckets are dropped until the next report
+const MAX_METERED_BUCKETS: usize = 10_000;
+
+/// Records kept per sink for a retry with the next period; the oldest are dropped beyond that
+pub const MAX_PENDING_RECORDS: usize = 100_000;
+
+/// Billing settings, read from the environment
+#[derive(Debug, Clone)]
+pub struct BillingConfig {
+    pub interval: Duration,
+    pub tenant_tag: String,
+    pub webhook_endpoint: Option<String>,
+    pub webhook_auth_token: String,
+    pub kafka_brokers: Option<String>,
+    pub kafka_topic: String,
+}
+
+impl BillingConfig {
+    /// The billing settings, if a webhook or Kafka brokers are configured
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let webhook_endpoint = var(ENV_BILLING_WEBHOOK_ENDPOINT);
+        let kafka_brokers = var(ENV_BILLING_KAFKA_BROKERS);
+        if webhook_endpoint.is_none() && kafka_brokers.is_none() {
+            return None;
+        }
+        Some(Self {
+            interval: var(ENV_BILLING_INTERVAL)
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .filter(|secs| *secs > 0)
+                .map_or(DEFAULT_INTERVAL, Duration::from_secs),
+            tenant_tag: var(ENV_BILLING_TENANT_TAG).unwrap_or_else(|| DEFAULT_TENANT_TAG.to_string()),
+            webhook_endpoint,
+            webhook_auth_token: var(ENV_BILLING_WEBHOOK_AUTH_TOKEN).unwrap_or_default(),
+            kafka_brokers,
+            kafka_topic: var(ENV_BILLING_KAFKA_TOPIC).unwrap_or_else(|| DEFAULT_KAFKA_TOPIC.to_string()),
+        })
+    }
+}
+
+static BILLING_CONFIG: LazyLock<Option<BillingConfig>> = LazyLock::new(BillingConfig::from_env);
+
+pub fn billing_config() -> Option<&'static BillingConfig> {
+    BILLING_CONFIG.as_ref()
+}
+
+/// Traffic of one bucket over a period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BucketTraffic {
+    pub requests: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+/// Per bucket traffic counters of this node
+#[derive(Debug, Default)]
+pub struct UsageMeter {
+    buckets: Mutex<HashMap<String, BucketTraffic>>,
+}
+
+static USAGE_METER: LazyLock<UsageMeter> = LazyLock::new(UsageMeter::default);
+
+impl UsageMeter {
+    fn update(&self, bucket: &str, f: impl FnOnce(&mut BucketTraffic)) {
+        if bucket.is_empty() {
+            return;
+        }
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(traffic) = buckets.get_mut(bucket) {
+            f(traffic);
+        } else if buckets.len() < MAX_METERED_BUCKETS {
+            f(buckets.entry(bucket.to_string()).or_default());
+        }
+    }
+
+    /// The counts since the last call
+    pub fn take(&self) -> HashMap<String, BucketTraffic> {
+        std::mem::take(&mut *self.buckets.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Count a request to `bucket`
+pub fn record_request(bucket: &str) {
+    if billing_config().is_some() {
+        USAGE_METER.update(bucket, |t| t.requests += 1);
+    }
+}
+
+/// Count `bytes` uploaded to `bucket`
+pub fn record_bytes_in(bucket: &str, bytes: i64) {
+    if billing_config().is_some() && bytes > 0 {
+        USAGE_METER.update(bucket, |t| t.bytes_in += bytes as u64);
+    }
+}
+
+/// Count `bytes` downloaded from `bucket`
+pub fn record_bytes_out(bucket: &str, bytes: i64) {
+    if billing_config().is_some() && bytes > 0 {
+        USAGE_METER.update(bucket, |t| t.bytes_out += bytes as u64);
+    }
+}
+
+pin_project! {
+    /// Body of a download from `bucket`, counting the bytes it sends once it is done or dropped, so
+    /// that an aborted download counts what it got
+    pub struct BilledBody<S> {
+        #[pin]
+        inner: S,
+        bucket: String,
+        bytes: i64,
+    }
+
+    impl<S> PinnedDrop for BilledBody<S> {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            record_bytes_out(this.bucket, *this.bytes);
+        }
+    }
+}
+
+impl<S> BilledBody<S> {
+    pub fn new(inner: S, bucket: &str) -> Self {
+        Self {
+            inner,
+            bucket: bucket.to_string(),
+            bytes: 0,
+        }
+    }
+}
+
+impl<S, E> Stream for BilledBody<S>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    type Item = Result<Bytes, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = this.inner.poll_next(cx);
+        if let Poll::Ready(Some(Ok(bytes))) = &item {
+            *this.bytes += bytes.len() as i64;
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Usage of one bucket over one period, as reported by one node
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRecord {
+    pub version: u32,
+    pub node: String,
+    pub bucket: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub requests: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stored_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub objects: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub byte_hours: Option<f64>,
+}
+
+/// Stored bytes and objects of a bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BucketStorage {
+    pub bytes: u64,
+    pub objects: u64,
+}
+
+/// Records of a period from the traffic of this node and, if it reports storage, the stored data
+fn build_records(
+    node: &str,
+    period: (DateTime<Utc>, DateTime<Utc>),
+    traffic: HashMap<String, BucketTraffic>,
+    storage: Option<HashMap<String, BucketStorage>>,
+    tenants: &HashMap<String, String>,
+) -> Vec<UsageRecord> {
+    let (start, end) = period;
+    let hours = (end - start).num_milliseconds().max(0) as f64 / 3_600_000.0;
+    let mut buckets: Vec<&String> = traffic.keys().chain(storage.iter().flat_map(|s| s.keys())).collect();
+    buckets.sort();
+    buckets.dedup();
+
+    buckets
+        .into_iter()
+        .map(|bucket| {
+            let t = traffic.get(bucket).copied().unwrap_or_default();
+            let stored = storage.as_ref().map(|s| s.get(bucket).copied().unwrap_or_default());
+            UsageRecord {
+                version: USAGE_RECORD_VERSION,
+                node: node.to_string(),
+                bucket: bucket.clone(),
+                tenant: tenants.get(bucket).cloned(),
+                period_start: start,
+                period_end: end,
+                requests: t.requests,
+                bytes_in: t.bytes_in,
+                bytes_out: t.bytes_out,
+                stored_bytes: stored.map(|s| s.bytes),
+                objects: stored.map(|s| s.objects),
+                byte_hours: stored.map(|s| s.bytes as f64 * hours),
+            }
+        })
+        .collect()
+}
+
+async fn bucket_tenant(bucket: &str, tag: &str) -> Option<String> {
+    let (tagging, _) = metadata_sys::get_tagging_config(bucket).await.ok()?;
+    tagging
+        .tag_set
+        .into_iter()
+        .find(|t| t.key.as_deref() == Some(tag))
+        .and_then(|t| t.value)
+}
+
+/// Records a sink failed to take added to those of the period, keeping at most [`MAX_PENDING_RECORDS`]
+fn with_pending(pending: &mut Vec<UsageRecord>, records: &[UsageRecord]) -> Vec<UsageRecord> {
+    let mut batch = std::mem::take(pending);
+    batch.extend_from_slice(records);
+    if batch.len() > MAX_PENDING_RECORDS {
+        let dropped = batch.len() - MAX_PENDING_RECORDS;
+        warn!("dropping {} usage records a billing sink failed to take", dropped);
+        batch.drain(..dropped);
+    }
+    batch
+}
+
+/// Sends the usage records of this node once per period
+pub struct UsageReporter {
+    config: BillingConfig,
+    sinks: Vec<UsageSink>,
+    period_start: tokio::sync::Mutex<DateTime<Utc>>,
+    /// Records each sink failed to take, by the index of the sink
+    pending: tokio::sync::Mutex<Vec<Vec<UsageRecord>>>,
+}
+
+impl UsageReporter {
+    pub fn new(config: BillingConfig) -> Arc<Self> {
+        let sinks = UsageSink::from_config(&config);
+        let pending = sinks.iter().map(|_| Vec::new()).collect();
+        Arc::new(Self {
+            config,
+            sinks,
+            period_start: tokio::sync::Mutex::new(Utc::now()),
+            pending: tokio::sync::Mutex::new(pending),
+        })
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    /// Send the records of the period ending now
+    pub async fn report(&self, store: Arc<ECStore>) {
+        let mut period_start = self.period_start.lock().await;
+        let end = Utc::now();
+        let mut traffic = USAGE_METER.take();
+
+        // Requests are counted by the name the client sent, only buckets that exist are billed
+        let mut missing = Vec::new();
+        for bucket in traffic.keys() {
+            if metadata_sys::get(bucket).await.is_err() {
+                missing.push(bucket.clone());
+            }
+        }
+        for bucket in missing {
+            traffic.remove(&bucket);
+        }
+
+        let endpoints = get_global_endpoints();
+        let storage = if endpoints.first_local() {
+            match load_data_usage_from_backend(store).await {
+                Ok(usage) => Some(
+                    usage
+                        .buckets_usage
+                        .into_iter()
+                        .map(|(bucket, u)| {
+                            (
+                                bucket,
+                                BucketStorage {
+                                    bytes: u.size,
+                                    objects: u.objects_count,
+                                },
+                            )
+                        })
+                        .collect(),
+                ),
+                Err(e) => {
+                    warn!("load data usage for billing failed: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut tenants = HashMap::new();
+        for bucket in traffic.keys().chain(storage.iter().flat_map(|s: &HashMap<_, _>| s.keys())) {
+            if tenants.contains_key(bucket) {
+                continue;
+            }
+            if let Some(tenant) = bucket_tenant(bucket, &self.config.tenant_tag).await {
+                tenants.insert(bucket.clone(), tenant);
+            }
+        }
+
+        let (_, node) = endpoints.peers();
+        let records = build_records(&node, (*period_start, end), traffic, storage, &tenants);
+        *period_start = end;
+
+        let mut pending = self.pending.lock().await;
+        for (sink, pending) in self.sinks.iter().zip(pending.iter_mut()) {
+            let batch = with_pending(pending, &records);
+            if batch.is_empty() {
+                continue;
+            }
+            if let Err(e) = sink.send(&batch).await {
+                warn!("send {} usage records failed, retrying with the next period: {}", batch.len(), e);
+                *pending = batch;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_records() {
+        let start = Utc::now();
+        let end = start + chrono::Duration::minutes(30);
+        let traffic = HashMap::from([(
+            "logs".to_string(),
+            BucketTraffic {
+                requests: 3,
+                bytes_in: 10,
+                bytes_out: 20,
+            },
+        )]);
+        let storage = HashMap::from([("photos".to_string(), BucketStorage { bytes: 1000, objects: 2 })]);
+        let tenants = HashMap::from([("photos".to_string(), "acme".to_string())]);
+
+        let records = build_records("node1", (start, end), traffic.clone(), Some(storage), &tenants);
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].bucket.as_str(), records[0].requests), ("logs", 3));
+        assert_eq!(records[0].stored_bytes, Some(0));
+        assert_eq!(records[1].tenant.as_deref(), Some("acme"));
+        assert_eq!(records[1].byte_hours, Some(500.0));
+
+        // Nodes not reporting storage leave the storage fields out
+        let records = build_records("node2", (start, end), traffic, None, &tenants);
+        let json = serde_json::to_value(&records[0]).unwrap();
+        assert_eq!(json["bytesOut"], 20);
+        assert!(json.get("storedBytes").is_none());
+        assert!(json.get("tenant").is_none());
+    }
+
+    #[test]
+    fn test_with_pending() {
+        let start = Utc::now();
+        let traffic = |bucket: &str| HashMap::from([(bucket.to_string(), BucketTraffic::default())]);
+        let old = build_records("node1", (start, start), traffic("old"), None, &HashMap::new());
+        let new = build_records("node1", (start, start), traffic("new"), None, &HashMap::new());
+
+        let mut pending = old.clone();
+        let batch = with_pending(&mut pending, &new);
+        assert!(pending.is_empty());
+        assert_eq!(batch.iter().map(|r| r.bucket.as_str()).collect::<Vec<_>>(), vec!["old", "new"]);
+
+        let mut pending = vec![old[0].clone(); MAX_PENDING_RECORDS];
+        let batch = with_pending(&mut pending, &new);
+        assert_eq!(batch.len(), MAX_PENDING_RECORDS);
+        assert_eq!(batch.last().unwrap().bucket, "new");
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use super::{BillingConfig, UsageRecord};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+const WEBHOOK_MAX_RETRIES: u32 = 3;
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Destination of usage records
+pub enum UsageSink {
+    Webhook {
+        client: reqwest::Client,
+        endpoint: String,
+        auth_token: String,
+    },
+    #[cfg(all(feature = "kafka", target_os = "linux"))]
+    Kafka {
+        producer: rdkafka::producer::FutureProducer,
+        topic: String,
+    },
+}
+
+impl UsageSink {
+    pub fn from_config(config: &BillingConfig) -> Vec<Self> {
+        let mut sinks = Vec::new();
+        if let Some(endpoint) = &config.webhook_endpoint {
+            sinks.push(UsageSink::Webhook {
+                client: reqwest::Client::builder()
+                    .timeout(WEBHOOK_TIMEOUT)
+                    .build()
+                    .unwrap_or_else(|_| reqwest::Client::new()),
+                endpoint: endpoint.clone(),
+                auth_token: config.webhook_auth_token.clone(),
+            });
+            info!("Billing webhook sink created for endpoint: {}", endpoint);
+        }
+        if let Some(brokers) = &config.kafka_brokers {
+            #[cfg(all(feature = "kafka", target_os = "linux"))]
+            match rdkafka::config::ClientConfig::new()
+                .set("bootstrap.servers", brokers)
+                .set("message.timeout.ms", "5000")
+                .create()
+            {
+                Ok(producer) => {
+                    sinks.push(UsageSink::Kafka {
+                        producer,
+                        topic: config.kafka_topic.clone(),
+                    });
+                    info!("Billing Kafka sink created for topic: {}", config.kafka_topic);
+                }
+                Err(e) => warn!("Failed to create billing Kafka producer: {}", e),
+            }
+            #[cfg(not(all(feature = "kafka", target_os = "linux")))]
+            warn!(
+                "Billing Kafka brokers {} for topic {} are configured but the 'kafka' feature is not enabled",
+                brokers, config.kafka_topic
+            );
+        }
+        sinks
+    }
+
+    /// Deliver the records of one period
+    pub async fn send(&self, records: &[UsageRecord]) -> Result<(), String> {
+        match self {
+            UsageSink::Webhook {
+                client,
+                endpoint,
+                auth_token,
+            } => {
+                let mut last_error = String::new();
+                for attempt in 0..WEBHOOK_MAX_RETRIES {
+                    if attempt > 0 {
+                        tokio::time::sleep(WEBHOOK_RETRY_DELAY * (1 << attempt)).await;
+                    }
+                    let mut request = client.post(endpoint).json(records);
+                    if !auth_token.is_empty() {
+                        request = request.bearer_auth(auth_token);
+                    }
+                    match request.send().await {
+                        Ok(resp) if resp.status().is_success() => return Ok(()),
+                        // Rejected records won't be accepted by sending them again
+                        Ok(resp) if resp.status().is_client_error() => {
+                            return Err(format!("webhook responded {}", resp.status()));
+                        }
+                        Ok(resp) => last_error = format!("webhook responded {}", resp.status()),
+                        Err(e) => last_error = e.to_string(),
+                    }
+                }
+                Err(last_error)
+            }
+            #[cfg(all(feature = "kafka", target_os = "linux"))]
+            UsageSink::Kafka { producer, topic } => {
+                for record in records {
+                    let payload = serde_json::to_vec(record).map_err(|e| e.to_string())?;
+                    producer
+                        .send(
+                            rdkafka::producer::FutureRecord::to(topic)
+                                .payload(&payload)
+                                .key(&record.bucket),
+                            Duration::from_secs(5),
+                        )
+                        .await
+                        .map_err(|(e, _)| e.to_string())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
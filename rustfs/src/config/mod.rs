@@ -56,6 +56,10 @@ pub struct Opt {
     #[arg(long, env = "RUSTFS_SERVER_DOMAINS")]
     pub server_domains: Vec<String>,
 
+    /// Domain name under which buckets with a website configuration are served as `<bucket>.<domain>`.
+    #[arg(long, env = "RUSTFS_WEBSITE_DOMAINS")]
+    pub website_domains: Vec<String>,
+
     /// Access key used for authentication.
     #[arg(long, default_value_t = rustfs_config::DEFAULT_ACCESS_KEY.to_string(), env = "RUSTFS_ACCESS_KEY")]
     pub access_key: String,
@@ -81,6 +85,30 @@ pub struct Opt {
 
     #[arg(long, env = "RUSTFS_REGION")]
     pub region: Option<String>,
+
+    /// Master key sealing the data keys of SSE-S3 objects, as <key-id>:<base64 of 32 bytes>.
+    #[arg(long, env = "RUSTFS_KMS_SECRET_KEY")]
+    pub kms_secret_key: Option<String>,
+
+    /// Endpoint of the KES server generating the data keys of SSE-KMS objects.
+    #[arg(long, env = "RUSTFS_KMS_KES_ENDPOINT")]
+    pub kms_kes_endpoint: Option<String>,
+
+    /// KES key used for SSE-KMS when neither the request nor the bucket names one.
+    #[arg(long, env = "RUSTFS_KMS_KES_KEY_NAME")]
+    pub kms_kes_key_name: Option<String>,
+
+    /// PEM client certificate authenticating to the KES server.
+    #[arg(long, env = "RUSTFS_KMS_KES_CERT_FILE")]
+    pub kms_kes_cert_file: Option<String>,
+
+    /// PEM private key of the KES client certificate.
+    #[arg(long, env = "RUSTFS_KMS_KES_KEY_FILE")]
+    pub kms_kes_key_file: Option<String>,
+
+    /// PEM CA certificate the KES server certificate is verified with.
+    #[arg(long, env = "RUSTFS_KMS_KES_CA_FILE")]
+    pub kms_kes_ca_file: Option<String>,
 }
 
 // lazy_static::lazy_static! {
@@ -0,0 +1,428 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content scanning of uploads, e.g. by an antivirus
+//!
+//! With `RUSTFS_SCAN_WEBHOOK_ENDPOINT` set, every object uploaded through PutObject or a multipart
+//! upload is tagged `scan-status=pending-scan` and queued for the scanner. The scanner receives the
+//! content of the object as the body of a POST, along with its `X-Rustfs-Bucket`, `X-Rustfs-Object`
+//! and `X-Rustfs-Version-Id` headers, and answers with its verdict:
+//!
+//! ```json
+//! { "verdict": "clean" }
+//! { "verdict": "infected", "threat": "EICAR-Test-File" }
+//! ```
+//!
+//! A clean object is tagged `scan-status=clean`. An infected one is tagged `scan-status=infected`,
+//! or moved under `RUSTFS_SCAN_QUARANTINE_PREFIX` in its bucket when that is set. Objects that are
+//! not clean cannot be downloaded unless `RUSTFS_SCAN_BLOCK_UNSCANNED` is `false`. Clients cannot
+//! set or remove the `scan-status` tag. An object whose scan failed, or was queued on a node that
+//! stopped, stays pending until a periodic sweep queues it again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use http::HeaderMap;
+use rustfs_ecstore::StorageAPI;
+use rustfs_ecstore::bucket::encryption::clear_encryption_metadata;
+use rustfs_ecstore::error::StorageError;
+use rustfs_ecstore::new_object_layer_fn;
+use rustfs_ecstore::store::ECStore;
+use rustfs_ecstore::store_api::{BucketOptions, ObjectIO, ObjectInfo, ObjectOptions, PutObjReader, WritePreconditions};
+use rustfs_filemeta::headers::{AMZ_OBJECT_TAGGING, RESERVED_METADATA_PREFIX_LOWER};
+use rustfs_rio::{HashReader, WarpReader};
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinHandle;
+use tokio_util::io::ReaderStream;
+use tracing::{debug, info, warn};
+
+use crate::storage::options::{del_opts, put_opts};
+use crate::storage::sse::resolve_object_encryption;
+
+/// Environment variable holding the URL objects are posted to for scanning
+pub const ENV_SCAN_WEBHOOK_ENDPOINT: &str = "RUSTFS_SCAN_WEBHOOK_ENDPOINT";
+/// Environment variable holding the bearer token sent to the scanner
+pub const ENV_SCAN_WEBHOOK_AUTH_TOKEN: &str = "RUSTFS_SCAN_WEBHOOK_AUTH_TOKEN";
+/// Environment variable holding the prefix infected objects are moved under, they are only tagged when unset
+pub const ENV_SCAN_QUARANTINE_PREFIX: &str = "RUSTFS_SCAN_QUARANTINE_PREFIX";
+/// Environment variable turning off the blocking of downloads of objects that are not clean
+pub const ENV_SCAN_BLOCK_UNSCANNED: &str = "RUSTFS_SCAN_BLOCK_UNSCANNED";
+
+/// Object tag holding the scan status
+pub const SCAN_STATUS_TAG: &str = "scan-status";
+pub const SCAN_STATUS_PENDING: &str = "pending-scan";
+pub const SCAN_STATUS_CLEAN: &str = "clean";
+pub const SCAN_STATUS_INFECTED: &str = "infected";
+
+const SCAN_QUEUE_CAPACITY: usize = 10_000;
+const SCAN_CONCURRENCY: usize = 4;
+const SCAN_TIMEOUT: Duration = Duration::from_secs(300);
+const SCAN_MAX_RETRIES: u32 = 3;
+const SCAN_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// How often the objects left pending are queued again
+const SCAN_SWEEP_INTERVAL: Duration = Duration::from_secs(3600);
+/// How long an object stays pending before a sweep queues it again, which leaves its own job time to run
+const SCAN_SWEEP_MIN_AGE: time::Duration = time::Duration::minutes(30);
+
+/// Content scanning settings, read from the environment
+#[derive(Debug, Clone)]
+pub struct ScanConfig {
+    pub webhook_endpoint: String,
+    pub webhook_auth_token: String,
+    pub quarantine_prefix: Option<String>,
+    pub block_unscanned: bool,
+}
+
+impl ScanConfig {
+    /// The scanning settings, if a scanner is configured
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        Some(Self {
+            webhook_endpoint: var(ENV_SCAN_WEBHOOK_ENDPOINT)?,
+            webhook_auth_token: var(ENV_SCAN_WEBHOOK_AUTH_TOKEN).unwrap_or_default(),
+            quarantine_prefix: var(ENV_SCAN_QUARANTINE_PREFIX),
+            block_unscanned: var(ENV_SCAN_BLOCK_UNSCANNED).is_none_or(|v| !v.trim().eq_ignore_ascii_case("false")),
+        })
+    }
+}
+
+static SCAN_CONFIG: LazyLock<Option<ScanConfig>> = LazyLock::new(ScanConfig::from_env);
+
+pub fn scan_config() -> Option<&'static ScanConfig> {
+    SCAN_CONFIG.as_ref()
+}
+
+/// The scan status in the encoded tags of an object
+pub fn scan_status(tags: &str) -> Option<String> {
+    form_urlencoded::parse(tags.as_bytes())
+        .find(|(key, _)| key == SCAN_STATUS_TAG)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// `tags` with its scan status replaced by `status`, or removed when `None`
+pub fn with_scan_status(tags: &str, status: Option<&str>) -> String {
+    let mut encoded = form_urlencoded::Serializer::new(String::new());
+    for (key, value) in form_urlencoded::parse(tags.as_bytes()) {
+        if key != SCAN_STATUS_TAG {
+            encoded.append_pair(&key, &value);
+        }
+    }
+    if let Some(status) = status {
+        encoded.append_pair(SCAN_STATUS_TAG, status);
+    }
+    encoded.finish()
+}
+
+/// Tag the metadata of an upload as waiting for its scan, whatever status the client tagged it with
+pub fn mark_pending(metadata: &mut HashMap<String, String>) {
+    if scan_config().is_some() {
+        let tags = metadata.get(AMZ_OBJECT_TAGGING).map(String::as_str).unwrap_or_default();
+        let tags = with_scan_status(tags, Some(SCAN_STATUS_PENDING));
+        metadata.insert(AMZ_OBJECT_TAGGING.to_string(), tags);
+    }
+}
+
+/// Whether an object with these tags may be downloaded
+pub fn download_allowed(tags: &str) -> bool {
+    scan_config().is_none_or(|config| !config.block_unscanned || scan_status(tags).as_deref() == Some(SCAN_STATUS_CLEAN))
+}
+
+struct ScanJob {
+    bucket: String,
+    object: String,
+    version_id: Option<String>,
+}
+
+static SCAN_QUEUE: Mutex<Option<mpsc::Sender<ScanJob>>> = Mutex::new(None);
+
+/// Queue an uploaded object for scanning
+pub fn submit(bucket: &str, object: &str, version_id: Option<String>) {
+    if !enqueue(bucket, object, version_id) {
+        warn!("content scan queue is full, {}/{} stays pending until the next sweep", bucket, object);
+    }
+}
+
+/// Queue an object for scanning, false when the queue is full
+fn enqueue(bucket: &str, object: &str, version_id: Option<String>) -> bool {
+    let queue = SCAN_QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(queue) = queue.as_ref() else {
+        return true;
+    };
+    let job = ScanJob {
+        bucket: bucket.to_string(),
+        object: object.to_string(),
+        version_id,
+    };
+    queue.try_send(job).is_ok()
+}
+
+/// Queue again the object versions left pending for longer than [`SCAN_SWEEP_MIN_AGE`], whose job was
+/// dropped by a full queue, failed, or was lost when a node stopped
+async fn sweep_pending() {
+    let Some(store) = new_object_layer_fn() else {
+        return;
+    };
+    let buckets = match store.list_bucket(&BucketOptions::default()).await {
+        Ok(buckets) => buckets,
+        Err(e) => {
+            warn!("content scan sweep can not list buckets: {}", e);
+            return;
+        }
+    };
+    let cutoff = OffsetDateTime::now_utc() - SCAN_SWEEP_MIN_AGE;
+
+    for bucket in buckets {
+        let (mut marker, mut version_marker) = (None, None);
+        loop {
+            let page = match store
+                .clone()
+                .list_object_versions(&bucket.name, "", marker, version_marker, None, 1000)
+                .await
+            {
+                Ok(page) => page,
+                Err(e) => {
+                    warn!("content scan sweep of {} failed: {}", bucket.name, e);
+                    break;
+                }
+            };
+            let pending = page.objects.iter().filter(|oi| {
+                !oi.delete_marker
+                    && oi.mod_time.is_some_and(|mod_time| mod_time < cutoff)
+                    && scan_status(&oi.user_tags).as_deref() == Some(SCAN_STATUS_PENDING)
+            });
+            for oi in pending {
+                let version_id = oi.version_id.filter(|v| !v.is_nil()).map(|v| v.to_string());
+                if !enqueue(&bucket.name, &oi.name, version_id) {
+                    debug!("content scan queue is full, the sweep goes on next time");
+                    return;
+                }
+            }
+            if !page.is_truncated {
+                break;
+            }
+            marker = page.next_marker;
+            version_marker = page.next_version_idmarker;
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScanVerdict {
+    verdict: String,
+    #[serde(default)]
+    threat: String,
+}
+
+/// Start scanning the queued uploads, if a scanner is configured
+pub fn start_scanning() -> Option<JoinHandle<()>> {
+    let config = scan_config()?;
+    let (tx, mut rx) = mpsc::channel(SCAN_QUEUE_CAPACITY);
+    *SCAN_QUEUE.lock().unwrap_or_else(|e| e.into_inner()) = Some(tx);
+    let client = reqwest::Client::builder()
+        .timeout(SCAN_TIMEOUT)
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new());
+    info!("content scanning of uploads enabled, scanner: {}", config.webhook_endpoint);
+
+    let scanning = async move {
+        let permits = Arc::new(Semaphore::new(SCAN_CONCURRENCY));
+        while let Some(job) = rx.recv().await {
+            let Ok(permit) = permits.clone().acquire_owned().await else {
+                break;
+            };
+            let client = client.clone();
+            tokio::spawn(async move {
+                if let Err(e) = scan(&client, config, &job).await {
+                    warn!("content scan of {}/{} failed, it stays pending: {}", job.bucket, job.object, e);
+                }
+                drop(permit);
+            });
+        }
+    };
+    let sweeping = async {
+        let mut interval = tokio::time::interval(SCAN_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            sweep_pending().await;
+        }
+    };
+    Some(tokio::spawn(async move {
+        tokio::select! {
+            _ = scanning => {}
+            _ = sweeping => {}
+        }
+    }))
+}
+
+/// Stop taking uploads for scanning, those queued are left pending
+pub fn stop_scanning() {
+    SCAN_QUEUE.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+fn object_opts(job: &ScanJob) -> ObjectOptions {
+    ObjectOptions {
+        version_id: job.version_id.clone(),
+        ..Default::default()
+    }
+}
+
+async fn scan(client: &reqwest::Client, config: &ScanConfig, job: &ScanJob) -> Result<(), String> {
+    let store = new_object_layer_fn().ok_or("object layer not initialized")?;
+
+    let mut last_error = String::new();
+    let mut verdict = None;
+    for attempt in 0..SCAN_MAX_RETRIES {
+        if attempt > 0 {
+            tokio::time::sleep(SCAN_RETRY_DELAY * (1 << attempt)).await;
+        }
+        // Every attempt sends the content again, it cannot be replayed from a previous one
+        let reader = store
+            .get_object_reader(&job.bucket, &job.object, None, HeaderMap::new(), &object_opts(job))
+            .await
+            .map_err(|e| e.to_string())?;
+        let scanned = reader.object_info.clone();
+        let mut request = client
+            .post(&config.webhook_endpoint)
+            .header("X-Rustfs-Bucket", &job.bucket)
+            .header("X-Rustfs-Object", &job.object)
+            .header("X-Rustfs-Version-Id", job.version_id.as_deref().unwrap_or_default())
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(reader.stream)));
+        if !config.webhook_auth_token.is_empty() {
+            request = request.bearer_auth(&config.webhook_auth_token);
+        }
+        match request.send().await {
+            Ok(resp) if resp.status().is_success() => {
+                verdict = Some((resp.json::<ScanVerdict>().await.map_err(|e| e.to_string())?, scanned));
+                break;
+            }
+            Ok(resp) if resp.status().is_client_error() => return Err(format!("scanner responded {}", resp.status())),
+            Ok(resp) => last_error = format!("scanner responded {}", resp.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    let (verdict, scanned) = verdict.ok_or(last_error)?;
+
+    let result = match verdict.verdict.as_str() {
+        SCAN_STATUS_CLEAN => set_scan_status(&store, job, &scanned, SCAN_STATUS_CLEAN).await,
+        SCAN_STATUS_INFECTED => {
+            warn!("content scan found {} in {}/{}", verdict.threat, job.bucket, job.object);
+            match &config.quarantine_prefix {
+                Some(prefix) => return quarantine(&store, job, &scanned, prefix).await,
+                None => set_scan_status(&store, job, &scanned, SCAN_STATUS_INFECTED).await,
+            }
+        }
+        other => return Err(format!("unknown verdict {other}")),
+    };
+    match result {
+        // The upload that replaced the scanned one is queued on its own
+        Err(StorageError::PreconditionFailed(_, _)) => {
+            debug!("{}/{} changed while it was scanned, the verdict is dropped", job.bucket, job.object);
+            Ok(())
+        }
+        result => result.map_err(|e| e.to_string()),
+    }
+}
+
+/// Options picking the version that was scanned, as long as it has not been replaced since
+fn scanned_opts(job: &ScanJob, scanned: &ObjectInfo) -> ObjectOptions {
+    ObjectOptions {
+        write_preconditions: Some(WritePreconditions {
+            if_match: Some(scanned.etag.clone().unwrap_or_default()),
+            if_none_match: None,
+            mod_time: scanned.mod_time,
+        }),
+        ..object_opts(job)
+    }
+}
+
+async fn set_scan_status(store: &ECStore, job: &ScanJob, scanned: &ObjectInfo, status: &str) -> Result<(), StorageError> {
+    let opts = scanned_opts(job, scanned);
+    let tags = store.get_object_tags(&job.bucket, &job.object, &opts).await?;
+    store
+        .put_object_tags(&job.bucket, &job.object, &with_scan_status(&tags, Some(status)), &opts)
+        .await?;
+    Ok(())
+}
+
+/// Move an infected object under the quarantine prefix of its bucket
+async fn quarantine(store: &Arc<ECStore>, job: &ScanJob, scanned: &ObjectInfo, prefix: &str) -> Result<(), String> {
+    let reader = store
+        .get_object_reader(&job.bucket, &job.object, None, HeaderMap::new(), &object_opts(job))
+        .await
+        .map_err(|e| e.to_string())?;
+    let info = reader.object_info;
+    if info.etag != scanned.etag || info.mod_time != scanned.mod_time {
+        debug!("{}/{} changed while it was scanned, it is not quarantined", job.bucket, job.object);
+        return Ok(());
+    }
+    let size = info.get_actual_size().map_err(|e| e.to_string())?;
+
+    // The content is stored again uncompressed, and encrypted per the bucket defaults
+    let mut metadata = info.user_defined.clone();
+    for key in ["compression", "actual-size", "compression-size"] {
+        metadata.remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}{key}"));
+    }
+    clear_encryption_metadata(&mut metadata);
+    if let Some(encryption) = resolve_object_encryption(&job.bucket, &HeaderMap::new())
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        encryption.write_metadata(&mut metadata).map_err(|e| e.to_string())?;
+    }
+    metadata.insert(
+        AMZ_OBJECT_TAGGING.to_string(),
+        with_scan_status(&info.user_tags, Some(SCAN_STATUS_INFECTED)),
+    );
+
+    let target = format!("{prefix}{}", job.object);
+    let opts = put_opts(&job.bucket, &target, None, &HeaderMap::new(), metadata)
+        .await
+        .map_err(|e| e.to_string())?;
+    let hash_reader =
+        HashReader::new(Box::new(WarpReader::new(reader.stream)), size, size, None, false).map_err(|e| e.to_string())?;
+    store
+        .put_object(&job.bucket, &target, &mut PutObjReader::new(hash_reader), &opts)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut opts = del_opts(&job.bucket, &job.object, job.version_id.clone(), &HeaderMap::new(), HashMap::new())
+        .await
+        .map_err(|e| e.to_string())?;
+    opts.write_preconditions = scanned_opts(job, scanned).write_preconditions;
+    store
+        .delete_object(&job.bucket, &job.object, opts)
+        .await
+        .map_err(|e| e.to_string())?;
+    info!("quarantined {}/{} to {}", job.bucket, job.object, target);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_scan_status() {
+        let tags = with_scan_status("project=web&scan-status=clean", Some(SCAN_STATUS_PENDING));
+        assert_eq!(scan_status(&tags).as_deref(), Some(SCAN_STATUS_PENDING));
+        assert!(tags.starts_with("project=web&"));
+
+        assert_eq!(with_scan_status(&tags, None), "project=web");
+        assert_eq!(scan_status(""), None);
+        assert_eq!(with_scan_status("", Some(SCAN_STATUS_CLEAN)), "scan-status=clean");
+    }
+}
@@ -15,6 +15,21 @@
 use rustfs_ecstore::error::StorageError;
 use s3s::{S3Error, S3ErrorCode};
 
+/// Code of the error a write over the hard quota of its bucket fails with
+pub const QUOTA_EXCEEDED: &str = "QuotaExceeded";
+
+/// Code of the error an append fails with when the object does not have as many bytes as its offset
+pub const INVALID_WRITE_OFFSET: &str = "InvalidWriteOffset";
+
+/// Status of the errors with a code of ours, which s3s does not know
+fn custom_status_code(code: &S3ErrorCode) -> Option<http::StatusCode> {
+    match code {
+        S3ErrorCode::Custom(code) if code == QUOTA_EXCEEDED => Some(http::StatusCode::FORBIDDEN),
+        S3ErrorCode::Custom(code) if code == INVALID_WRITE_OFFSET => Some(http::StatusCode::BAD_REQUEST),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 pub struct ApiError {
     pub code: S3ErrorCode,
@@ -45,7 +60,11 @@ impl ApiError {
 
 impl From<ApiError> for S3Error {
     fn from(err: ApiError) -> Self {
+        let status_code = custom_status_code(&err.code);
         let mut s3e = S3Error::with_message(err.code, err.message);
+        if let Some(status_code) = status_code {
+            s3e.set_status_code(status_code);
+        }
         if let Some(source) = err.source {
             s3e.set_source(source);
         }
@@ -67,6 +86,12 @@ impl From<StorageError> for ApiError {
             StorageError::StorageFull => S3ErrorCode::ServiceUnavailable,
             StorageError::SlowDown => S3ErrorCode::SlowDown,
             StorageError::PrefixAccessDenied(_, _) => S3ErrorCode::AccessDenied,
+            StorageError::InvalidSseCustomerKey(_, _) => S3ErrorCode::AccessDenied,
+            StorageError::BadDigest(_, _) => S3ErrorCode::BadDigest,
+            StorageError::PreconditionFailed(_, _) => S3ErrorCode::PreconditionFailed,
+            StorageError::InvalidRange(_) => S3ErrorCode::InvalidRange,
+            StorageError::BucketQuotaExceeded(_) => S3ErrorCode::Custom(QUOTA_EXCEEDED.into()),
+            StorageError::InvalidWriteOffset(_, _, _) => S3ErrorCode::Custom(INVALID_WRITE_OFFSET.into()),
             StorageError::InvalidUploadIDKeyCombination(_, _) => S3ErrorCode::InvalidArgument,
             StorageError::ObjectNameTooLong(_, _) => S3ErrorCode::InvalidArgument,
             StorageError::ObjectNamePrefixAsSlash(_, _) => S3ErrorCode::InvalidArgument,
@@ -212,6 +237,10 @@ mod tests {
             (StorageError::StorageFull, S3ErrorCode::ServiceUnavailable),
             (StorageError::SlowDown, S3ErrorCode::SlowDown),
             (StorageError::PrefixAccessDenied("test".into(), "test".into()), S3ErrorCode::AccessDenied),
+            (
+                StorageError::InvalidSseCustomerKey("test".into(), "test".into()),
+                S3ErrorCode::AccessDenied,
+            ),
             (StorageError::ObjectNotFound("test".into(), "test".into()), S3ErrorCode::NoSuchKey),
             (StorageError::ConfigNotFound, S3ErrorCode::NoSuchKey),
             (StorageError::VolumeNotFound, S3ErrorCode::NoSuchBucket),
@@ -250,6 +279,21 @@ mod tests {
         assert!(s3_error.source().is_some());
     }
 
+    #[test]
+    fn test_quota_exceeded_to_s3_error() {
+        let s3_error: S3Error = ApiError::from(StorageError::BucketQuotaExceeded("test".into())).into();
+        assert_eq!(*s3_error.code(), S3ErrorCode::Custom(QUOTA_EXCEEDED.into()));
+        assert_eq!(s3_error.status_code(), Some(http::StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn test_invalid_write_offset_to_s3_error() {
+        let s3_error: S3Error = ApiError::from(StorageError::InvalidWriteOffset("b".into(), "o".into(), 10)).into();
+        assert_eq!(*s3_error.code(), S3ErrorCode::Custom(INVALID_WRITE_OFFSET.into()));
+        assert_eq!(s3_error.status_code(), Some(http::StatusCode::BAD_REQUEST));
+        assert!(s3_error.message().unwrap_or("").contains("size 10"));
+    }
+
     #[test]
     fn test_api_error_to_s3_error_without_source() {
         let api_error = ApiError {
@@ -14,7 +14,9 @@
 
 mod admin;
 mod auth;
+mod billing;
 mod config;
+mod content_scan;
 mod error;
 // mod grpc;
 pub mod license;
@@ -24,7 +26,10 @@ mod update;
 mod version;
 
 // Ensure the correct path for parse_license is imported
-use crate::server::{SHUTDOWN_TIMEOUT, ServiceState, ServiceStateManager, ShutdownSignal, start_http_server, wait_for_shutdown};
+use crate::server::{
+    LifecycleManager, SHUTDOWN_TIMEOUT, ServiceState, ServiceStateManager, ShutdownSignal, Subsystem, start_http_server,
+    wait_for_shutdown,
+};
 use chrono::Datelike;
 use clap::Parser;
 use license::init_license;
@@ -34,16 +39,21 @@ use rustfs_ahm::{
 };
 use rustfs_common::globals::set_global_addr;
 use rustfs_config::DEFAULT_DELIMITER;
+use rustfs_ecstore::bucket::encryption::keys::{MasterKey, master_key, set_master_key};
+use rustfs_ecstore::bucket::encryption::kms::{KesClient, set_kms};
 use rustfs_ecstore::bucket::metadata_sys::init_bucket_metadata_sys;
 use rustfs_ecstore::cmd::bucket_replication::init_bucket_replication_pool;
 use rustfs_ecstore::config as ecconfig;
 use rustfs_ecstore::config::GLOBAL_CONFIG_SYS;
 use rustfs_ecstore::config::GLOBAL_SERVER_CONFIG;
+use rustfs_ecstore::heat_map::{HEAT_MAP_PERSIST_INTERVAL, init_heat_map, save_heat_map};
+use rustfs_ecstore::object_ttl::start_object_expiry;
 use rustfs_ecstore::store_api::BucketOptions;
 use rustfs_ecstore::{
     StorageAPI,
-    endpoints::EndpointServerPools,
+    endpoints::{EndpointServerPools, SetupType},
     global::{set_global_rustfs_port, shutdown_background_services},
+    new_object_layer_fn,
     notification_sys::new_global_notification_sys,
     set_global_endpoints,
     store::ECStore,
@@ -53,8 +63,12 @@ use rustfs_ecstore::{
 use rustfs_iam::init_iam_sys;
 use rustfs_obs::{init_obs, set_global_guard};
 use rustfs_utils::net::parse_and_resolve_address;
+use rustfs_utils::node_tls::init_node_tls;
+use rustfs_workers::pool;
 use std::io::{Error, Result};
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 use tracing::{debug, error, info, instrument, warn};
 
 #[cfg(all(target_os = "linux", target_env = "gnu"))]
@@ -87,10 +101,47 @@ async fn main() -> Result<()> {
     // Store in global storage
     set_global_guard(guard).map_err(Error::other)?;
 
+    // Requests are served by this runtime, background subsystems get a pool of their own
+    pool::init_foreground();
+    pool::register_metrics(&opentelemetry::global::meter("system"));
+
     // Run parameters
     run(opt).await
 }
 
+/// Install the master key of SSE-S3 and the KMS of SSE-KMS, the master key doubles as KMS when no
+/// KES server is configured
+fn init_kms(opt: &config::Opt) -> Result<()> {
+    if let Some(kms_secret_key) = &opt.kms_secret_key {
+        let master_key = MasterKey::parse(kms_secret_key).map_err(Error::other)?;
+        info!("SSE-S3 master key {} installed", master_key.id());
+        set_master_key(master_key);
+    }
+
+    if let Some(endpoint) = &opt.kms_kes_endpoint {
+        let Some(key_name) = &opt.kms_kes_key_name else {
+            return Err(Error::other("RUSTFS_KMS_KES_KEY_NAME is required with RUSTFS_KMS_KES_ENDPOINT"));
+        };
+        let identity = match (&opt.kms_kes_cert_file, &opt.kms_kes_key_file) {
+            (Some(cert), Some(key)) => {
+                let mut pem = std::fs::read(cert)?;
+                pem.extend(std::fs::read(key)?);
+                Some(pem)
+            }
+            (None, None) => None,
+            _ => return Err(Error::other("the KES client certificate and key must be given together")),
+        };
+        let ca = opt.kms_kes_ca_file.as_ref().map(std::fs::read).transpose()?;
+
+        let kes = KesClient::new(endpoint, key_name, identity.as_deref(), ca.as_deref()).map_err(Error::other)?;
+        info!("SSE-KMS uses the KES server {} with default key {}", endpoint, key_name);
+        set_kms(Arc::new(kes));
+    } else if let Some(master_key) = master_key() {
+        set_kms(Arc::new(master_key.clone()));
+    }
+    Ok(())
+}
+
 #[instrument(skip(opt))]
 async fn run(opt: config::Opt) -> Result<()> {
     debug!("opt: {:?}", &opt);
@@ -99,6 +150,8 @@ async fn run(opt: config::Opt) -> Result<()> {
         rustfs_ecstore::global::set_global_region(region.clone());
     }
 
+    init_kms(&opt)?;
+
     let server_addr = parse_and_resolve_address(opt.address.as_str()).map_err(Error::other)?;
     let server_port = server_addr.port();
     let server_address = server_addr.to_string();
@@ -140,136 +193,352 @@ async fn run(opt: config::Opt) -> Result<()> {
         }
     }
 
+    // Peers authenticate each other from the first RPC on, before any listener or client starts
+    let node_hosts = endpoint_pools
+        .get_nodes()
+        .iter()
+        .filter_map(|node| node.url.host_str().map(str::to_string))
+        .collect();
+    init_node_tls(opt.tls_path.as_deref().unwrap_or_default(), node_hosts)?;
+
     let state_manager = ServiceStateManager::new();
     // Update service status to Starting
     state_manager.update(ServiceState::Starting);
 
-    let shutdown_tx = start_http_server(&opt, state_manager.clone()).await?;
-
-    set_global_endpoints(endpoint_pools.as_ref().clone());
-    update_erasure_type(setup_type).await;
-
-    // Initialize the local disk
-    init_local_disks(endpoint_pools.clone()).await.map_err(Error::other)?;
+    let mut lifecycle = LifecycleManager::new();
+    lifecycle
+        .register(
+            // Telemetry is flushed last, once everything else had its say; exporters flushing
+            // their pending batches bound every export on their own
+            Subsystem::new("obs").stop_timeout(Duration::from_secs(30)).on_stop(|| async {
+                if let Some(guard) = rustfs_obs::try_get_global_guard() {
+                    tokio::task::spawn_blocking(move || guard.lock().unwrap().shutdown())
+                        .await
+                        .map_err(Error::other)?;
+                }
+                Ok(())
+            }),
+        )
+        .register(
+            Subsystem::new("lock")
+                .depends_on(&["obs"])
+                .on_start(|| async {
+                    rustfs_lock::audit::set_audit_sink(admin::handlers::locks::audit_lock_event);
+                    Ok(())
+                })
+                .on_stop(|| async {
+                    rustfs_lock::shutdown_local_locks().await;
+                    Ok(())
+                }),
+        )
+        .register(http_subsystem(opt.clone(), state_manager.clone()))
+        .register(storage_subsystem(server_addr, endpoint_pools, setup_type))
+        .register(
+            Subsystem::new("notify")
+                .depends_on(&["storage"])
+                .on_start(|| async {
+                    storage::copy_progress::spawn_progress_log();
+                    init_event_notifier().await;
+                    Ok(())
+                })
+                .on_stop(|| async {
+                    shutdown_event_notifier().await;
+                    Ok(())
+                }),
+        )
+        .register(scanner_subsystem())
+        .register(heat_map_subsystem())
+        .register(billing_subsystem())
+        .register(content_scan_subsystem())
+        .register(object_expiry_subsystem())
+        .register(
+            // Replication runs on the background pool, along with every task it spawns
+            Subsystem::new("replication").depends_on(&["storage"]).on_start(|| async {
+                pool::background()
+                    .run(init_bucket_replication_pool())
+                    .await
+                    .map_err(Error::other)
+            }),
+        )
+        .register(update_check_subsystem());
+
+    lifecycle.start_all().await?;
+    print_server_info();
 
-    // init store
-    let store = ECStore::new(server_addr, endpoint_pools.clone()).await.inspect_err(|err| {
-        error!("ECStore::new {:?}", err);
-    })?;
+    // Perform hibernation for 1 second
+    tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
+    // listen to the shutdown signal
+    match wait_for_shutdown().await {
+        #[cfg(unix)]
+        ShutdownSignal::CtrlC | ShutdownSignal::Sigint | ShutdownSignal::Sigterm => {
+            handle_shutdown(&state_manager, &mut lifecycle).await;
+        }
+        #[cfg(not(unix))]
+        ShutdownSignal::CtrlC => {
+            handle_shutdown(&state_manager, &mut lifecycle).await;
+        }
+    }
 
-    ecconfig::init();
-    // config system configuration
-    GLOBAL_CONFIG_SYS.init(store.clone()).await?;
+    info!("server is stopped state: {:?}", state_manager.current_state());
+    Ok(())
+}
 
-    // Initialize event notifier
-    init_event_notifier().await;
+/// HTTP and RPC listeners, serving while the storage below them still initializes
+fn http_subsystem(opt: config::Opt, state_manager: ServiceStateManager) -> Subsystem {
+    let shutdown_tx = Arc::new(OnceLock::new());
+    let stop_tx = shutdown_tx.clone();
+    Subsystem::new("http")
+        .depends_on(&["obs", "lock"])
+        .on_start(move || async move {
+            let _ = shutdown_tx.set(start_http_server(&opt, state_manager).await?);
+            Ok(())
+        })
+        .on_stop(move || async move {
+            if let Some(tx) = stop_tx.get() {
+                let _ = tx.send(());
+            }
+            // Wait for the worker thread to complete the cleaning work
+            tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
+            Ok(())
+        })
+}
 
-    let buckets_list = store
-        .list_bucket(&BucketOptions {
-            no_metadata: true,
-            ..Default::default()
+/// Drives, object layer, configuration, bucket metadata, IAM and peer notification
+fn storage_subsystem(server_addr: SocketAddr, endpoint_pools: EndpointServerPools, setup_type: SetupType) -> Subsystem {
+    // Waits for the drives of the other nodes for as long as they take to come up
+    Subsystem::new("storage")
+        .depends_on(&["http"])
+        .start_timeout(None)
+        .on_start(move || async move {
+            set_global_endpoints(endpoint_pools.as_ref().clone());
+            update_erasure_type(setup_type).await;
+
+            // Initialize the local disk
+            init_local_disks(endpoint_pools.clone()).await.map_err(Error::other)?;
+
+            // init store
+            let store = ECStore::new(server_addr, endpoint_pools.clone()).await.inspect_err(|err| {
+                error!("ECStore::new {:?}", err);
+            })?;
+
+            ecconfig::init();
+            // config system configuration
+            GLOBAL_CONFIG_SYS.init(store.clone()).await?;
+
+            let buckets_list = store
+                .list_bucket(&BucketOptions {
+                    no_metadata: true,
+                    ..Default::default()
+                })
+                .await
+                .map_err(Error::other)?;
+
+            let buckets = buckets_list.into_iter().map(|v| v.name).collect();
+
+            init_bucket_metadata_sys(store.clone(), buckets).await;
+
+            init_iam_sys(store.clone()).await?;
+            admin::handlers::bucket_grant::start_bucket_grant_expiry();
+
+            new_global_notification_sys(endpoint_pools.clone()).await.map_err(|err| {
+                error!("new_global_notification_sys failed {:?}", &err);
+                Error::other(err)
+            })
         })
-        .await
-        .map_err(Error::other)?;
+}
 
-    let buckets = buckets_list.into_iter().map(|v| v.name).collect();
+/// Scanner and auto heal, on the background pool along with every task they spawn
+fn scanner_subsystem() -> Subsystem {
+    Subsystem::new("scanner")
+        .depends_on(&["storage"])
+        .on_start(|| async {
+            let store = new_object_layer_fn().ok_or_else(|| Error::other("object layer not initialized"))?;
+            let _ = create_ahm_services_cancel_token();
+            rustfs_ecstore::bucket::lifecycle::bucket_lifecycle_audit::set_audit_sink(storage::lifecycle::audit_lifecycle_event);
+            let background = pool::background();
+
+            // Initialize heal manager with channel processor
+            let heal_storage = Arc::new(ECStoreHealStorage::new(store));
+            let heal_manager = background
+                .run(init_heal_manager(heal_storage, None))
+                .await
+                .map_err(Error::other)??;
+
+            let scanner = Scanner::new(Some(ScannerConfig::default()), Some(heal_manager));
+            background
+                .run(async move { scanner.start().await })
+                .await
+                .map_err(Error::other)??;
+            Ok(())
+        })
+        .on_stop(|| async {
+            // Stop background services (data scanner and auto heal) gracefully
+            shutdown_background_services();
+            shutdown_ahm_services();
+            Ok(())
+        })
+}
 
-    init_bucket_metadata_sys(store.clone(), buckets).await;
+/// Object access heat map, restored on start, persisted periodically and once more on stop
+fn heat_map_subsystem() -> Subsystem {
+    let task = Arc::new(Mutex::new(None));
+    let abort = task.clone();
+    Subsystem::new("heat-map")
+        .depends_on(&["storage"])
+        .on_start(move || async move {
+            let store = new_object_layer_fn().ok_or_else(|| Error::other("object layer not initialized"))?;
+            if let Err(e) = init_heat_map(store.clone()).await {
+                warn!("load heat map failed: {}", e);
+            }
+            *task.lock().unwrap() = Some(tokio::spawn(async move {
+                let mut interval = tokio::time::interval(HEAT_MAP_PERSIST_INTERVAL);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = save_heat_map(store.clone()).await {
+                        warn!("save heat map failed: {}", e);
+                    }
+                }
+            }));
+            Ok(())
+        })
+        .on_stop(move || async move {
+            if let Some(task) = abort.lock().unwrap().take() {
+                task.abort();
+            }
+            let store = new_object_layer_fn().ok_or_else(|| Error::other("object layer not initialized"))?;
+            save_heat_map(store).await.map_err(Error::other)
+        })
+}
 
-    init_iam_sys(store.clone()).await?;
+/// Periodic usage records for billing, the last period is sent on shutdown
+fn billing_subsystem() -> Subsystem {
+    let reporter = billing::billing_config().cloned().map(billing::UsageReporter::new);
+    let task = Arc::new(Mutex::new(None));
+    let abort = task.clone();
+    let stop_reporter = reporter.clone();
+    Subsystem::new("billing")
+        .depends_on(&["storage"])
+        .on_start(move || async move {
+            let Some(reporter) = reporter else {
+                return Ok(());
+            };
+            let store = new_object_layer_fn().ok_or_else(|| Error::other("object layer not initialized"))?;
+            *task.lock().unwrap() = Some(tokio::spawn(async move {
+                let mut interval = tokio::time::interval(reporter.interval());
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    reporter.report(store.clone()).await;
+                }
+            }));
+            Ok(())
+        })
+        .on_stop(move || async move {
+            if let Some(task) = abort.lock().unwrap().take() {
+                task.abort();
+            }
+            if let (Some(reporter), Some(store)) = (stop_reporter, new_object_layer_fn()) {
+                reporter.report(store).await;
+            }
+            Ok(())
+        })
+}
 
-    new_global_notification_sys(endpoint_pools.clone()).await.map_err(|err| {
-        error!("new_global_notification_sys failed {:?}", &err);
-        Error::other(err)
-    })?;
+/// Content scanning of uploads, uploads still queued on shutdown stay pending
+fn content_scan_subsystem() -> Subsystem {
+    let task = Arc::new(Mutex::new(None));
+    let abort = task.clone();
+    Subsystem::new("content-scan")
+        .depends_on(&["storage"])
+        .on_start(move || async move {
+            *task.lock().unwrap() = content_scan::start_scanning();
+            Ok(())
+        })
+        .on_stop(move || async move {
+            content_scan::stop_scanning();
+            if let Some(task) = abort.lock().unwrap().take() {
+                task.abort();
+            }
+            Ok(())
+        })
+}
 
-    // init scanner and auto heal with unified cancellation token
-    // let _background_services_cancel_token = create_background_services_cancel_token();
-    // init_data_scanner().await;
-    // init_auto_heal().await;
-    let _ = create_ahm_services_cancel_token();
+/// Deletion of the objects written with a time to live as they expire
+fn object_expiry_subsystem() -> Subsystem {
+    let task = Arc::new(Mutex::new(None));
+    let abort = task.clone();
+    Subsystem::new("object-expiry")
+        .depends_on(&["storage"])
+        .on_start(move || async move {
+            *task.lock().unwrap() = Some(start_object_expiry());
+            Ok(())
+        })
+        .on_stop(move || async move {
+            if let Some(task) = abort.lock().unwrap().take() {
+                task.abort();
+            }
+            Ok(())
+        })
+}
 
-    // Initialize heal manager with channel processor
-    let heal_storage = Arc::new(ECStoreHealStorage::new(store.clone()));
-    let heal_manager = init_heal_manager(heal_storage, None).await?;
+/// Optional check for a newer release, aborted on shutdown if still running
+fn update_check_subsystem() -> Subsystem {
+    let task = Arc::new(Mutex::new(None));
+    let abort = task.clone();
+    Subsystem::new("update-check")
+        .depends_on(&["obs"])
+        .on_start(move || async move {
+            *task.lock().unwrap() = Some(tokio::spawn(check_for_updates()));
+            Ok(())
+        })
+        .on_stop(move || async move {
+            if let Some(task) = abort.lock().unwrap().take() {
+                task.abort();
+            }
+            Ok(())
+        })
+}
 
-    let scanner = Scanner::new(Some(ScannerConfig::default()), Some(heal_manager));
-    scanner.start().await?;
-    print_server_info();
-    init_bucket_replication_pool().await;
-
-    // Async update check (optional)
-    tokio::spawn(async {
-        use crate::update::{UpdateCheckError, check_updates};
-
-        match check_updates().await {
-            Ok(result) => {
-                if result.update_available {
-                    if let Some(latest) = &result.latest_version {
-                        info!(
-                            "🚀 Version check: New version available: {} -> {} (current: {})",
-                            result.current_version, latest.version, result.current_version
-                        );
-                        if let Some(notes) = &latest.release_notes {
-                            info!("📝 Release notes: {}", notes);
-                        }
-                        if let Some(url) = &latest.download_url {
-                            info!("🔗 Download URL: {}", url);
-                        }
+async fn check_for_updates() {
+    use crate::update::{UpdateCheckError, check_updates};
+
+    match check_updates().await {
+        Ok(result) => {
+            if result.update_available {
+                if let Some(latest) = &result.latest_version {
+                    info!(
+                        "🚀 Version check: New version available: {} -> {} (current: {})",
+                        result.current_version, latest.version, result.current_version
+                    );
+                    if let Some(notes) = &latest.release_notes {
+                        info!("📝 Release notes: {}", notes);
+                    }
+                    if let Some(url) = &latest.download_url {
+                        info!("🔗 Download URL: {}", url);
                     }
-                } else {
-                    debug!("✅ Version check: Current version is up to date: {}", result.current_version);
                 }
-            }
-            Err(UpdateCheckError::HttpError(e)) => {
-                debug!("Version check: network error (this is normal): {}", e);
-            }
-            Err(e) => {
-                debug!("Version check: failed (this is normal): {}", e);
+            } else {
+                debug!("✅ Version check: Current version is up to date: {}", result.current_version);
             }
         }
-    });
-
-    // Perform hibernation for 1 second
-    tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
-    // listen to the shutdown signal
-    match wait_for_shutdown().await {
-        #[cfg(unix)]
-        ShutdownSignal::CtrlC | ShutdownSignal::Sigint | ShutdownSignal::Sigterm => {
-            handle_shutdown(&state_manager, &shutdown_tx).await;
+        Err(UpdateCheckError::HttpError(e)) => {
+            debug!("Version check: network error (this is normal): {}", e);
         }
-        #[cfg(not(unix))]
-        ShutdownSignal::CtrlC => {
-            handle_shutdown(&state_manager, &shutdown_tx).await;
+        Err(e) => {
+            debug!("Version check: failed (this is normal): {}", e);
         }
     }
-
-    info!("server is stopped state: {:?}", state_manager.current_state());
-    Ok(())
 }
 
 /// Handles the shutdown process of the server
-async fn handle_shutdown(state_manager: &ServiceStateManager, shutdown_tx: &tokio::sync::broadcast::Sender<()>) {
+async fn handle_shutdown(state_manager: &ServiceStateManager, lifecycle: &mut LifecycleManager) {
     info!("Shutdown signal received in main thread");
     // update the status to stopping first
     state_manager.update(ServiceState::Stopping);
 
-    // Stop background services (data scanner and auto heal) gracefully
-    info!("Stopping background services (data scanner and auto heal)...");
-    shutdown_background_services();
-
-    // Stop AHM services gracefully
-    info!("Stopping AHM services...");
-    shutdown_ahm_services();
-
-    // Stop the notification system
-    shutdown_event_notifier().await;
-
     info!("Server is stopping...");
-    let _ = shutdown_tx.send(());
-
-    // Wait for the worker thread to complete the cleaning work
-    tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
+    lifecycle.stop_all().await;
 
     // the last updated status is stopped
     state_manager.update(ServiceState::Stopped);
@@ -289,14 +558,11 @@ pub(crate) async fn init_event_notifier() {
         }
     };
 
-    info!("Global server configuration loaded successfully. config: {:?}", server_config);
+    info!("Global server configuration loaded successfully.");
     // 2. Check if the notify subsystem exists in the configuration, and skip initialization if it doesn't
-    if server_config
-        .get_value(rustfs_config::notify::NOTIFY_MQTT_SUB_SYS, DEFAULT_DELIMITER)
-        .is_none()
-        || server_config
-            .get_value(rustfs_config::notify::NOTIFY_WEBHOOK_SUB_SYS, DEFAULT_DELIMITER)
-            .is_none()
+    if rustfs_config::notify::NOTIFY_SUB_SYSTEMS
+        .iter()
+        .all(|sub_sys| server_config.get_value(sub_sys, DEFAULT_DELIMITER).is_none())
     {
         info!("'notify' subsystem not configured, skipping event notifier initialization.");
         return;
@@ -311,6 +577,9 @@ pub(crate) async fn init_event_notifier() {
             error!("Failed to initialize event notifier system: {}", e);
         } else {
             info!("Event notifier system initialized successfully.");
+            if let Some(store) = new_object_layer_fn() {
+                storage::notification::load_all_bucket_notifications(store).await;
+            }
         }
     });
 }
@@ -0,0 +1,257 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Identities of clients authenticating with a TLS client certificate.
+//!
+//! When the TLS directory holds a `client_ca.pem`, the S3 listener asks clients for a certificate
+//! signed by it. Clients without one still connect and sign their requests with access keys. A
+//! verified certificate is matched against the rules of `client_identity.json`, and the IAM user
+//! named by the first matching rule is the identity of the unsigned requests on that connection.
+//! The rules are reloaded when the file changes.
+
+use rustfs_config::{RUSTFS_TLS_CLIENT_CA, RUSTFS_TLS_CLIENT_IDENTITY};
+use rustfs_utils::string::match_pattern;
+use rustls::RootCertStore;
+use rustls::pki_types::CertificateDer;
+use rustls::server::WebPkiClientVerifier;
+use rustls::server::danger::ClientCertVerifier;
+use serde::Deserialize;
+use std::io::Result;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// DER encoding of the id-at-commonName attribute type (2.5.4.3)
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// How often `client_identity.json` is checked for changes
+const CLIENT_IDENTITY_RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+static CLIENT_CERT_RULES: LazyLock<RwLock<Arc<Vec<CertIdentityRule>>>> = LazyLock::new(Default::default);
+
+/// Maps client certificates to an IAM user
+///
+/// `subject` is matched against the subject common name and `san` against the DNS subject
+/// alternative names, both with `*` and `?` wildcards. A rule matches when all of its patterns do.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CertIdentityRule {
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub san: Option<String>,
+    /// Access key of the IAM user the certificate authenticates as
+    pub user: String,
+}
+
+impl CertIdentityRule {
+    fn matches(&self, identity: &CertIdentity) -> bool {
+        if self.subject.is_none() && self.san.is_none() {
+            return false;
+        }
+        let subject_ok = self
+            .subject
+            .as_deref()
+            .is_none_or(|pattern| identity.common_name.as_deref().is_some_and(|cn| match_pattern(pattern, cn)));
+        let san_ok = self
+            .san
+            .as_deref()
+            .is_none_or(|pattern| identity.dns_names.iter().any(|name| match_pattern(pattern, name)));
+        subject_ok && san_ok
+    }
+}
+
+/// Names presented by a client certificate
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CertIdentity {
+    pub common_name: Option<String>,
+    pub dns_names: Vec<String>,
+}
+
+impl CertIdentity {
+    pub fn from_der(cert: &CertificateDer<'_>) -> Option<Self> {
+        let cert = webpki::EndEntityCert::try_from(cert).ok()?;
+        Some(Self {
+            common_name: common_name(cert.subject()),
+            dns_names: cert.valid_dns_names().map(str::to_owned).collect(),
+        })
+    }
+}
+
+/// IAM user of a connection authenticated with a client certificate, set as a request extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertUser(pub String);
+
+/// Split one DER element off `input`, returning its tag, its contents and the remaining input
+fn der_next(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    let (len, rest) = if len < 0x80 {
+        (len as usize, rest)
+    } else {
+        let n = (len & 0x7f) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return None;
+        }
+        let (len, rest) = rest.split_at(n);
+        (len.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize), rest)
+    };
+    if rest.len() < len {
+        return None;
+    }
+    let (value, rest) = rest.split_at(len);
+    Some((tag, value, rest))
+}
+
+/// First common name of a DER encoded X.501 name
+fn common_name(mut name: &[u8]) -> Option<String> {
+    // webpki hands out the contents of the name sequence, accept the full encoding as well
+    if let Some((0x30, inner, [])) = der_next(name) {
+        name = inner;
+    }
+    while let Some((0x31, mut rdn, rest)) = der_next(name) {
+        while let Some((0x30, attr, next)) = der_next(rdn) {
+            if let Some((0x06, OID_COMMON_NAME, value)) = der_next(attr) {
+                let (_, value, _) = der_next(value)?;
+                return String::from_utf8(value.to_vec()).ok();
+            }
+            rdn = next;
+        }
+        name = rest;
+    }
+    None
+}
+
+/// Load the client CA and identity rules from the TLS directory
+///
+/// Returns the verifier to configure the listener with, or `None` when no client CA is present.
+pub fn load_client_cert_verifier(tls_path: &str) -> Result<Option<Arc<dyn ClientCertVerifier>>> {
+    let ca_path = Path::new(tls_path).join(RUSTFS_TLS_CLIENT_CA);
+    if !ca_path.exists() {
+        return Ok(None);
+    }
+
+    let mut roots = RootCertStore::empty();
+    for cert in rustfs_utils::load_certs(&ca_path.to_string_lossy())? {
+        roots.add(cert).map_err(|e| rustfs_utils::certs_error(e.to_string()))?;
+    }
+    let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+        .allow_unauthenticated()
+        .build()
+        .map_err(|e| rustfs_utils::certs_error(e.to_string()))?;
+
+    let rules_path = Path::new(tls_path).join(RUSTFS_TLS_CLIENT_IDENTITY);
+    if !rules_path.exists() {
+        warn!(
+            "client CA found without {}, client certificates map to no identity until it is added",
+            RUSTFS_TLS_CLIENT_IDENTITY
+        );
+    }
+    let mut modified = load_client_cert_rules(&rules_path)?;
+
+    // Rules are picked up again when the file changes, a broken edit keeps the rules loaded before
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CLIENT_IDENTITY_RELOAD_INTERVAL);
+        loop {
+            interval.tick().await;
+            let current = std::fs::metadata(&rules_path).and_then(|m| m.modified()).ok();
+            if current == modified {
+                continue;
+            }
+            match load_client_cert_rules(&rules_path) {
+                Ok(loaded) => modified = loaded,
+                Err(e) => warn!("reload {} failed, keeping the previous rules: {e}", rules_path.display()),
+            }
+        }
+    });
+
+    Ok(Some(verifier))
+}
+
+/// Replace the identity rules with those of `path`, none when it does not exist, returning its modification time
+fn load_client_cert_rules(path: &Path) -> Result<Option<SystemTime>> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let rules: Vec<CertIdentityRule> = if path.exists() {
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data).map_err(|e| rustfs_utils::certs_error(format!("invalid {}: {e}", path.display())))?
+    } else {
+        Vec::new()
+    };
+    debug!("loaded {} client certificate identity rules", rules.len());
+    *CLIENT_CERT_RULES.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(rules);
+    Ok(modified)
+}
+
+fn resolve<'a>(rules: &'a [CertIdentityRule], identity: &CertIdentity) -> Option<&'a str> {
+    rules
+        .iter()
+        .find(|rule| rule.matches(identity))
+        .map(|rule| rule.user.as_str())
+}
+
+/// IAM user of the leaf certificate a client presented, if a rule maps it
+pub fn client_cert_user(certs: Option<&[CertificateDer<'_>]>) -> Option<ClientCertUser> {
+    let rules = CLIENT_CERT_RULES.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let identity = CertIdentity::from_der(certs?.first()?)?;
+    let user = resolve(&rules, &identity);
+    debug!(?identity, ?user, "client certificate identity");
+    user.map(|user| ClientCertUser(user.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(subject: Option<&str>, san: Option<&str>, user: &str) -> CertIdentityRule {
+        CertIdentityRule {
+            subject: subject.map(str::to_string),
+            san: san.map(str::to_string),
+            user: user.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_common_name() {
+        // SEQUENCE { SET { SEQUENCE { O = "acme" } }, SET { SEQUENCE { CN = "svc" } } }
+        let name = [
+            0x30, 0x1d, 0x31, 0x0d, 0x30, 0x0b, 0x06, 0x03, 0x55, 0x04, 0x0a, 0x0c, 0x04, b'a', b'c', b'm', b'e', 0x31, 0x0c,
+            0x30, 0x0a, 0x06, 0x03, 0x55, 0x04, 0x03, 0x0c, 0x03, b's', b'v', b'c',
+        ];
+        assert_eq!(common_name(&name).as_deref(), Some("svc"));
+        assert_eq!(common_name(&name[2..]).as_deref(), Some("svc"));
+        assert_eq!(common_name(&name[2..17]), None);
+        assert_eq!(common_name(&[0x30, 0x05]), None);
+    }
+
+    #[test]
+    fn test_resolve_rules() {
+        let rules = vec![
+            rule(Some("backup-*"), None, "backup"),
+            rule(None, Some("*.ingest.internal"), "ingest"),
+            rule(Some("etl"), Some("etl.internal"), "etl"),
+            rule(None, None, "everyone"),
+        ];
+
+        let identity = |cn: &str, dns: &[&str]| CertIdentity {
+            common_name: Some(cn.to_string()),
+            dns_names: dns.iter().map(|d| d.to_string()).collect(),
+        };
+
+        assert_eq!(resolve(&rules, &identity("backup-eu", &[])), Some("backup"));
+        assert_eq!(resolve(&rules, &identity("node", &["a.ingest.internal"])), Some("ingest"));
+        assert_eq!(resolve(&rules, &identity("etl", &["etl.internal"])), Some("etl"));
+        assert_eq!(resolve(&rules, &identity("etl", &["other.internal"])), None);
+        assert_eq!(resolve(&rules, &identity("unknown", &[])), None);
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CORS of the S3 API by the configuration of the bucket a request is for
+//!
+//! Cross-origin requests for a bucket with a CORS configuration follow its rules: preflight requests are
+//! answered here, and the response to an actual request carries the headers of the rule it matches, none
+//! when it matches no rule. Requests for buckets without a configuration, and for the admin API, RPC and
+//! console, are left to the permissive CORS layer underneath.
+
+use crate::server::hybrid::HybridBody;
+use crate::server::presign::request_bucket;
+use bytes::Bytes;
+use http::header::{
+    ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+    CONTENT_TYPE, ORIGIN, VARY,
+};
+use http::{HeaderMap, HeaderValue, Method, Request as HttpRequest, Response, StatusCode};
+use rustfs_ecstore::bucket::cors::find_cors_rule;
+use rustfs_ecstore::bucket::metadata_sys;
+use s3s::dto::CORSRule;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Paths of the admin API, RPC and console, which bucket configurations do not apply to
+const RUSTFS_PREFIX: &str = "/rustfs/";
+
+/// Answer to a preflight request no rule of the bucket allows
+const CORS_FORBIDDEN: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error><Code>AccessForbidden</Code>\
+<Message>CORSResponse: This CORS request is not allowed. This is usually because the evalution of Origin, \
+request method / Access-Control-Request-Method or Access-Control-Request-Headers are not whitelisted by the \
+resource's CORS spec.</Message></Error>";
+
+#[derive(Clone)]
+pub struct BucketCorsLayer {
+    domains: Arc<Vec<String>>,
+}
+
+impl BucketCorsLayer {
+    /// Layer for a server answering virtual-hosted-style requests for the `domains`
+    pub fn new(domains: Arc<Vec<String>>) -> Self {
+        Self { domains }
+    }
+}
+
+impl<S> Layer<S> for BucketCorsLayer {
+    type Service = BucketCorsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BucketCorsService {
+            inner,
+            domains: self.domains.clone(),
+        }
+    }
+}
+
+/// Service implementation for the CORS configurations of buckets
+#[derive(Clone)]
+pub struct BucketCorsService<S> {
+    inner: S,
+    domains: Arc<Vec<String>>,
+}
+
+impl<S, ReqBody, RestBody, GrpcBody> Service<HttpRequest<ReqBody>> for BucketCorsService<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = Response<HybridBody<RestBody, GrpcBody>>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    RestBody: From<Bytes> + Send + 'static,
+    GrpcBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: HttpRequest<ReqBody>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let origin = req.headers().get(ORIGIN).and_then(|v| v.to_str().ok()).map(str::to_owned);
+        let (Some(origin), false) = (origin, req.uri().path().starts_with(RUSTFS_PREFIX)) else {
+            return Box::pin(inner.call(req));
+        };
+        let bucket = request_bucket(req.uri(), req.headers(), &self.domains);
+
+        Box::pin(async move {
+            let config = match bucket.is_empty() {
+                true => None,
+                false => metadata_sys::get_cors_config(&bucket).await.ok().map(|(config, _)| config),
+            };
+            let Some(config) = config else {
+                return inner.call(req).await;
+            };
+
+            if let Some(method) = preflight_method(&req) {
+                let requested = requested_headers(req.headers());
+                let headers: Vec<&str> = requested.iter().map(String::as_str).collect();
+                return Ok(match find_cors_rule(&config, &origin, &method, &headers) {
+                    Some(rule) => {
+                        let mut response = Response::new(HybridBody::Rest {
+                            rest_body: RestBody::from(Bytes::new()),
+                        });
+                        set_cors_headers(response.headers_mut(), rule, &origin);
+                        set_preflight_headers(response.headers_mut(), rule, &headers);
+                        response
+                    }
+                    None => {
+                        let mut response = Response::new(HybridBody::Rest {
+                            rest_body: RestBody::from(Bytes::from_static(CORS_FORBIDDEN.as_bytes())),
+                        });
+                        *response.status_mut() = StatusCode::FORBIDDEN;
+                        response
+                            .headers_mut()
+                            .insert(CONTENT_TYPE, HeaderValue::from_static("application/xml"));
+                        response
+                    }
+                });
+            }
+
+            let method = req.method().as_str().to_owned();
+            let mut response = inner.call(req).await?;
+            let headers = response.headers_mut();
+            // The rules of the bucket replace whatever the permissive layer underneath allowed
+            let permissive: Vec<_> = headers
+                .keys()
+                .filter(|name| name.as_str().starts_with("access-control-"))
+                .cloned()
+                .collect();
+            for name in permissive {
+                headers.remove(name);
+            }
+            match find_cors_rule(&config, &origin, &method, &[]) {
+                Some(rule) => set_cors_headers(headers, rule, &origin),
+                None => {
+                    headers.append(VARY, HeaderValue::from_static("Origin"));
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Method a preflight request asks about, `None` for other requests
+fn preflight_method<B>(req: &HttpRequest<B>) -> Option<String> {
+    if req.method() != Method::OPTIONS {
+        return None;
+    }
+    let method = req.headers().get(ACCESS_CONTROL_REQUEST_METHOD)?.to_str().ok()?;
+    Some(method.to_owned())
+}
+
+/// Headers a preflight request asks about in `Access-Control-Request-Headers`
+fn requested_headers(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all(ACCESS_CONTROL_REQUEST_HEADERS)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Headers allowing the request from `origin` the `rule` matched
+fn set_cors_headers(headers: &mut HeaderMap, rule: &CORSRule, origin: &str) {
+    // Like S3, a rule for every origin allows it without credentials
+    if rule.allowed_origins.iter().any(|pattern| pattern == "*") {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    } else if let Ok(origin) = HeaderValue::from_str(origin) {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+    }
+    if let Ok(methods) = HeaderValue::from_str(&rule.allowed_methods.join(", ")) {
+        headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+    }
+    if let Some(expose) = rule.expose_headers.as_ref().filter(|expose| !expose.is_empty()) {
+        if let Ok(expose) = HeaderValue::from_str(&expose.join(", ")) {
+            headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, expose);
+        }
+    }
+    headers.append(VARY, HeaderValue::from_static("Origin"));
+}
+
+/// Headers answering a preflight request asking for the `requested` headers
+fn set_preflight_headers(headers: &mut HeaderMap, rule: &CORSRule, requested: &[&str]) {
+    if !requested.is_empty() {
+        if let Ok(allowed) = HeaderValue::from_str(&requested.join(", ")) {
+            headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allowed);
+        }
+    }
+    if let Some(max_age) = rule.max_age_seconds {
+        headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(max_age));
+    }
+    headers.append(VARY, HeaderValue::from_static("Access-Control-Request-Headers"));
+    headers.append(VARY, HeaderValue::from_static("Access-Control-Request-Method"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(origins: &[&str]) -> CORSRule {
+        CORSRule {
+            allowed_origins: origins.iter().map(|s| s.to_string()).collect(),
+            allowed_methods: vec!["GET".to_string(), "PUT".to_string()],
+            allowed_headers: Some(vec!["*".to_string()]),
+            expose_headers: Some(vec!["ETag".to_string()]),
+            id: None,
+            max_age_seconds: Some(3000),
+        }
+    }
+
+    #[test]
+    fn test_set_cors_headers() {
+        let mut headers = HeaderMap::new();
+        set_cors_headers(&mut headers, &rule(&["https://*.example.com"]), "https://app.example.com");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "GET, PUT");
+        assert_eq!(headers[ACCESS_CONTROL_EXPOSE_HEADERS], "ETag");
+
+        let mut headers = HeaderMap::new();
+        set_cors_headers(&mut headers, &rule(&["*"]), "https://app.example.com");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert!(!headers.contains_key(ACCESS_CONTROL_ALLOW_CREDENTIALS));
+    }
+
+    #[test]
+    fn test_preflight() {
+        let req = HttpRequest::builder()
+            .method(Method::OPTIONS)
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .header(ACCESS_CONTROL_REQUEST_HEADERS, "content-type, x-amz-date")
+            .body(())
+            .unwrap();
+        assert_eq!(preflight_method(&req).as_deref(), Some("PUT"));
+        assert_eq!(requested_headers(req.headers()), vec!["content-type", "x-amz-date"]);
+
+        let mut headers = HeaderMap::new();
+        set_preflight_headers(&mut headers, &rule(&["*"]), &["content-type", "x-amz-date"]);
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_HEADERS], "content-type, x-amz-date");
+        assert_eq!(headers[ACCESS_CONTROL_MAX_AGE], "3000");
+
+        let req = HttpRequest::builder().method(Method::GET).body(()).unwrap();
+        assert!(preflight_method(&req).is_none());
+    }
+}
@@ -16,8 +16,14 @@
 use crate::admin;
 use crate::auth::IAMAuth;
 use crate::config;
+use crate::server::client_cert::{ClientCertUser, client_cert_user, load_client_cert_verifier};
+use crate::server::cors::BucketCorsLayer;
 use crate::server::hybrid::hybrid;
-use crate::server::layer::RedirectLayer;
+use crate::server::layer::{
+    ByteRangesLayer, ClientCertLayer, ConnectionInfoLayer, REQUEST_ID_HEADER, RedirectLayer, RequestIdLayer,
+};
+use crate::server::node_cert::{NodeClientCertVerifier, NodePeer, node_peer, node_rpc_allowed};
+use crate::server::presign::PresignedRequestService;
 use crate::server::{ServiceState, ServiceStateManager};
 use crate::storage;
 use bytes::Bytes;
@@ -34,7 +40,7 @@ use rustfs_obs::SystemObserver;
 use rustfs_protos::proto_gen::node_service::node_service_server::NodeServiceServer;
 use rustfs_utils::net::parse_and_resolve_address;
 use rustls::ServerConfig;
-use s3s::service::S3Service;
+use rustls::server::danger::ClientCertVerifier;
 use s3s::{host::MultiDomain, service::S3ServiceBuilder};
 use socket2::SockRef;
 use std::io::{Error, Result};
@@ -141,7 +147,11 @@ pub async fn start_http_server(
             b.set_host(MultiDomain::new(&opt.server_domains).map_err(Error::other)?);
         }
 
-        b.build()
+        if !opt.website_domains.is_empty() {
+            info!("static website hosting is enabled use domain_name {:?}", &opt.website_domains);
+        }
+
+        PresignedRequestService::new(b.build(), opt.server_domains.clone(), opt.website_domains.clone())
     };
 
     tokio::spawn(async move {
@@ -290,6 +300,17 @@ async fn setup_tls_acceptor(tls_path: &str) -> Result<Option<TlsAcceptor>> {
     // Make sure to use a modern encryption suite
     let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
 
+    // Client certificates are only requested when a client CA is configured, or peers present node certificates
+    let client_verifier = load_client_cert_verifier(tls_path)?;
+    let client_verifier = match rustfs_utils::node_tls::node_tls() {
+        Some(_) => Some(Arc::new(NodeClientCertVerifier::new(client_verifier)) as Arc<dyn ClientCertVerifier>),
+        None => client_verifier,
+    };
+    let config_builder = || match &client_verifier {
+        Some(verifier) => ServerConfig::builder().with_client_cert_verifier(verifier.clone()),
+        None => ServerConfig::builder().with_no_client_auth(),
+    };
+
     // 1. Attempt to load all certificates in the directory (multi-certificate support, for SNI)
     if let Ok(cert_key_pairs) = rustfs_utils::load_all_certs_from_directory(tls_path) {
         if !cert_key_pairs.is_empty() {
@@ -299,9 +320,7 @@ async fn setup_tls_acceptor(tls_path: &str) -> Result<Option<TlsAcceptor>> {
             let resolver = rustfs_utils::create_multi_cert_resolver(cert_key_pairs)?;
 
             // Configure the server to enable SNI support
-            let mut server_config = ServerConfig::builder()
-                .with_no_client_auth()
-                .with_cert_resolver(Arc::new(resolver));
+            let mut server_config = config_builder().with_cert_resolver(Arc::new(resolver));
 
             // Configure ALPN protocol priority
             server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec(), b"http/1.0".to_vec()];
@@ -323,8 +342,7 @@ async fn setup_tls_acceptor(tls_path: &str) -> Result<Option<TlsAcceptor>> {
         let certs = rustfs_utils::load_certs(&cert_path).map_err(|e| rustfs_utils::certs_error(e.to_string()))?;
         let key = rustfs_utils::load_private_key(&key_path).map_err(|e| rustfs_utils::certs_error(e.to_string()))?;
 
-        let mut server_config = ServerConfig::builder()
-            .with_no_client_auth()
+        let mut server_config = config_builder()
             .with_single_cert(certs, key)
             .map_err(|e| rustfs_utils::certs_error(e.to_string()))?;
 
@@ -355,63 +373,81 @@ fn process_connection(
     socket: TcpStream,
     tls_acceptor: Option<Arc<TlsAcceptor>>,
     http_server: Arc<ConnBuilder<TokioExecutor>>,
-    s3_service: S3Service,
+    s3_service: PresignedRequestService,
     graceful: Arc<GracefulShutdown>,
 ) {
     tokio::spawn(async move {
         // Build services inside each connected task to avoid passing complex service types across tasks,
         // It also ensures that each connection has an independent service instance.
-        let rpc_service = NodeServiceServer::with_interceptor(make_server(), check_auth);
-        let service = hybrid(s3_service, rpc_service);
-
-        let hybrid_service = ServiceBuilder::new()
-            .layer(CatchPanicLayer::new())
-            .layer(
-                TraceLayer::new_for_http()
-                    .make_span_with(|request: &HttpRequest<_>| {
-                        let span = tracing::info_span!("http-request",
-                            status_code = tracing::field::Empty,
-                            method = %request.method(),
-                            uri = %request.uri(),
-                            version = ?request.version(),
-                        );
-                        for (header_name, header_value) in request.headers() {
-                            if header_name == "user-agent" || header_name == "content-type" || header_name == "content-length" {
-                                span.record(header_name.as_str(), header_value.to_str().unwrap_or("invalid"));
+        // The identity of a client certificate is only known after the TLS handshake
+        let remote_addr = socket.peer_addr().ok();
+        let secure = tls_acceptor.is_some();
+        let build_service = move |cert_user: Option<ClientCertUser>, node_peer: Option<NodePeer>| {
+            let rpc_service = NodeServiceServer::with_interceptor(make_server(), check_auth);
+            let domains = s3_service.domains();
+            let service = hybrid(s3_service, rpc_service);
+
+            let hybrid_service = ServiceBuilder::new()
+                .layer(CatchPanicLayer::new())
+                .layer(RequestIdLayer)
+                .layer(ClientCertLayer::new(cert_user, node_peer))
+                .layer(ConnectionInfoLayer::new(remote_addr, secure))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(|request: &HttpRequest<_>| {
+                            let span = tracing::info_span!("http-request",
+                                status_code = tracing::field::Empty,
+                                method = %request.method(),
+                                uri = %request.uri(),
+                                version = ?request.version(),
+                                request_id = request
+                                    .headers()
+                                    .get(REQUEST_ID_HEADER)
+                                    .and_then(|v| v.to_str().ok())
+                                    .unwrap_or_default(),
+                            );
+                            for (header_name, header_value) in request.headers() {
+                                if header_name == "user-agent" || header_name == "content-type" || header_name == "content-length"
+                                {
+                                    span.record(header_name.as_str(), header_value.to_str().unwrap_or("invalid"));
+                                }
                             }
-                        }
 
-                        span
-                    })
-                    .on_request(|request: &HttpRequest<_>, _span: &Span| {
-                        info!(
-                            counter.rustfs_api_requests_total = 1_u64,
-                            key_request_method = %request.method().to_string(),
-                            key_request_uri_path = %request.uri().path().to_owned(),
-                            "handle request api total",
-                        );
-                        debug!("http started method: {}, url path: {}", request.method(), request.uri().path())
-                    })
-                    .on_response(|response: &Response<_>, latency: Duration, _span: &Span| {
-                        _span.record("http response status_code", tracing::field::display(response.status()));
-                        debug!("http response generated in {:?}", latency)
-                    })
-                    .on_body_chunk(|chunk: &Bytes, latency: Duration, _span: &Span| {
-                        info!(histogram.request.body.len = chunk.len(), "histogram request body length",);
-                        debug!("http body sending {} bytes in {:?}", chunk.len(), latency)
-                    })
-                    .on_eos(|_trailers: Option<&HeaderMap>, stream_duration: Duration, _span: &Span| {
-                        debug!("http stream closed after {:?}", stream_duration)
-                    })
-                    .on_failure(|_error, latency: Duration, _span: &Span| {
-                        info!(counter.rustfs_api_requests_failure_total = 1_u64, "handle request api failure total");
-                        debug!("http request failure error: {:?} in {:?}", _error, latency)
-                    }),
-            )
-            .layer(CorsLayer::permissive())
-            .layer(RedirectLayer)
-            .service(service);
-        let hybrid_service = TowerToHyperService::new(hybrid_service);
+                            span
+                        })
+                        .on_request(|request: &HttpRequest<_>, _span: &Span| {
+                            info!(
+                                counter.rustfs_api_requests_total = 1_u64,
+                                key_request_method = %request.method().to_string(),
+                                key_request_uri_path = %request.uri().path().to_owned(),
+                                "handle request api total",
+                            );
+                            debug!("http started method: {}, url path: {}", request.method(), request.uri().path())
+                        })
+                        .on_response(|response: &Response<_>, latency: Duration, _span: &Span| {
+                            _span.record("http response status_code", tracing::field::display(response.status()));
+                            rustfs_obs::http_request_latency().observe(response.status().as_str(), latency, _span);
+                            debug!("http response generated in {:?}", latency)
+                        })
+                        .on_body_chunk(|chunk: &Bytes, latency: Duration, _span: &Span| {
+                            info!(histogram.request.body.len = chunk.len(), "histogram request body length",);
+                            debug!("http body sending {} bytes in {:?}", chunk.len(), latency)
+                        })
+                        .on_eos(|_trailers: Option<&HeaderMap>, stream_duration: Duration, _span: &Span| {
+                            debug!("http stream closed after {:?}", stream_duration)
+                        })
+                        .on_failure(|_error, latency: Duration, _span: &Span| {
+                            info!(counter.rustfs_api_requests_failure_total = 1_u64, "handle request api failure total");
+                            debug!("http request failure error: {:?} in {:?}", _error, latency)
+                        }),
+                )
+                .layer(BucketCorsLayer::new(domains))
+                .layer(CorsLayer::permissive())
+                .layer(ByteRangesLayer)
+                .layer(RedirectLayer)
+                .service(service);
+            TowerToHyperService::new(hybrid_service)
+        };
 
         // Decide whether to handle HTTPS or HTTP connections based on the existence of TLS Acceptor
         if let Some(acceptor) = tls_acceptor {
@@ -423,8 +459,10 @@ fn process_connection(
             match acceptor.accept(socket).await {
                 Ok(tls_socket) => {
                     debug!("TLS handshake successful");
+                    let peer_certs = tls_socket.get_ref().1.peer_certificates();
+                    let (cert_user, node_peer) = (client_cert_user(peer_certs), node_peer(peer_certs));
                     let stream = TokioIo::new(tls_socket);
-                    let conn = http_server.serve_connection(stream, hybrid_service);
+                    let conn = http_server.serve_connection(stream, build_service(cert_user, node_peer));
                     if let Err(err) = graceful.watch(conn).await {
                         handle_connection_error(&*err);
                     }
@@ -474,7 +512,7 @@ fn process_connection(
         } else {
             debug!("Http handshake start");
             let stream = TokioIo::new(socket);
-            let conn = http_server.serve_connection(stream, hybrid_service);
+            let conn = http_server.serve_connection(stream, build_service(None, None));
             if let Err(err) = graceful.watch(conn).await {
                 handle_connection_error(&*err);
             }
@@ -508,6 +546,10 @@ fn handle_connection_error(err: &(dyn std::error::Error + 'static)) {
 
 #[allow(clippy::result_large_err)]
 fn check_auth(req: Request<()>) -> std::result::Result<Request<()>, Status> {
+    if !node_rpc_allowed(req.extensions().get::<NodePeer>()) {
+        return Err(Status::unauthenticated("Node certificate required"));
+    }
+
     let token: MetadataValue<_> = "rustfs rpc".parse().unwrap();
 
     match req.metadata().get("authorization") {
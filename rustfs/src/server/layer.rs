@@ -12,10 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::server::client_cert::ClientCertUser;
 use crate::server::hybrid::HybridBody;
-use http::{Request as HttpRequest, Response, StatusCode};
+use crate::server::node_cert::NodePeer;
+use http::{HeaderMap, HeaderValue, Request as HttpRequest, Response, StatusCode};
 use hyper::body::Incoming;
+use s3s::dto::Range;
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use tower::{Layer, Service};
@@ -89,3 +93,336 @@ where
         Box::pin(async move { inner.call(req).await.map_err(Into::into) })
     }
 }
+
+/// Header carrying the request id, accepted from clients and echoed in every response
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Layer that assigns every request an id, generating a ULID when the client sent none
+///
+/// The id is set on the request and response headers and scoped to the task handling the
+/// request, where `rustfs_obs` log calls pick it up.
+#[derive(Clone)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdService { inner }
+    }
+}
+
+/// Service implementation for request id propagation
+#[derive(Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<HttpRequest<ReqBody>> for RequestIdService<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: HttpRequest<ReqBody>) -> Self::Future {
+        let request_id = request_id_of(req.headers());
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            req.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(rustfs_obs::scope_request_id(request_id.clone(), async move {
+            let mut response = inner.call(req).await?;
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                response.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+            Ok(response)
+        }))
+    }
+}
+
+/// Layer that tags every request of a connection with the IAM user its client certificate maps to,
+/// and with whether the certificate is that of a peer node
+#[derive(Clone)]
+pub struct ClientCertLayer {
+    user: Option<ClientCertUser>,
+    node_peer: Option<NodePeer>,
+}
+
+impl ClientCertLayer {
+    pub fn new(user: Option<ClientCertUser>, node_peer: Option<NodePeer>) -> Self {
+        Self { user, node_peer }
+    }
+}
+
+impl<S> Layer<S> for ClientCertLayer {
+    type Service = ClientCertService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientCertService {
+            inner,
+            user: self.user.clone(),
+            node_peer: self.node_peer,
+        }
+    }
+}
+
+/// Service implementation for client certificate identities
+#[derive(Clone)]
+pub struct ClientCertService<S> {
+    inner: S,
+    user: Option<ClientCertUser>,
+    node_peer: Option<NodePeer>,
+}
+
+impl<S, ReqBody> Service<HttpRequest<ReqBody>> for ClientCertService<S>
+where
+    S: Service<HttpRequest<ReqBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: HttpRequest<ReqBody>) -> Self::Future {
+        if let Some(user) = &self.user {
+            req.extensions_mut().insert(user.clone());
+        }
+        if let Some(node_peer) = self.node_peer {
+            req.extensions_mut().insert(node_peer);
+        }
+        self.inner.call(req)
+    }
+}
+
+/// Where a request comes from and whether it came over TLS, the `aws:SourceIp` and `aws:SecureTransport`
+/// of the policies evaluated for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    pub remote_addr: Option<SocketAddr>,
+    pub secure: bool,
+}
+
+/// Layer that tags every request of a connection with its [`ConnectionInfo`]
+#[derive(Clone)]
+pub struct ConnectionInfoLayer {
+    info: ConnectionInfo,
+}
+
+impl ConnectionInfoLayer {
+    pub fn new(remote_addr: Option<SocketAddr>, secure: bool) -> Self {
+        Self {
+            info: ConnectionInfo { remote_addr, secure },
+        }
+    }
+}
+
+impl<S> Layer<S> for ConnectionInfoLayer {
+    type Service = ConnectionInfoService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectionInfoService { inner, info: self.info }
+    }
+}
+
+/// Service implementation for connection information
+#[derive(Clone)]
+pub struct ConnectionInfoService<S> {
+    inner: S,
+    info: ConnectionInfo,
+}
+
+impl<S, ReqBody> Service<HttpRequest<ReqBody>> for ConnectionInfoService<S>
+where
+    S: Service<HttpRequest<ReqBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: HttpRequest<ReqBody>) -> Self::Future {
+        req.extensions_mut().insert(self.info);
+        self.inner.call(req)
+    }
+}
+
+/// Ranges of a GET asking for several ranges of an object, answered with a `multipart/byteranges` body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteRanges(pub Vec<Range>);
+
+/// Content type of a response made of several ranges of an object
+pub const MULTIPART_BYTERANGES: &str = "multipart/byteranges";
+
+/// Layer that takes the `Range` header of a GET asking for several ranges off the request, which the S3
+/// layer only parses with a single range, and hands the ranges to the handler as [`ByteRanges`]
+///
+/// The header can only be taken off when the request signature does not cover it, as it does for
+/// presigned URLs and anonymous requests; signed multi-range requests are left to fail as before.
+#[derive(Clone)]
+pub struct ByteRangesLayer;
+
+impl<S> Layer<S> for ByteRangesLayer {
+    type Service = ByteRangesService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ByteRangesService { inner }
+    }
+}
+
+/// Service implementation for multi-range requests
+#[derive(Clone)]
+pub struct ByteRangesService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<HttpRequest<ReqBody>> for ByteRangesService<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: HttpRequest<ReqBody>) -> Self::Future {
+        let ranges = (req.method() == http::Method::GET)
+            .then(|| byte_ranges_of(req.uri(), req.headers()))
+            .flatten();
+        if let Some(ranges) = ranges {
+            req.headers_mut().remove(http::header::RANGE);
+            req.extensions_mut().insert(ranges);
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(async move {
+            let mut response = inner.call(req).await?;
+            // The handler can't answer 206 without a Content-Range, which a multipart body does not have
+            let multipart = response
+                .headers()
+                .get(http::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.starts_with(MULTIPART_BYTERANGES));
+            if multipart && response.status() == StatusCode::OK {
+                *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Ranges of a `Range` header asking for more than one range, none when it asks for one, is malformed or
+/// is covered by the request signature
+fn byte_ranges_of(uri: &http::Uri, headers: &HeaderMap) -> Option<ByteRanges> {
+    let range = headers.get(http::header::RANGE)?.to_str().ok()?;
+    let specs = range.strip_prefix("bytes=")?;
+    if !specs.contains(',') {
+        return None;
+    }
+
+    let signed_headers = headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split("SignedHeaders=").nth(1))
+        .map(|v| v.split(',').next().unwrap_or_default().to_owned())
+        .or_else(|| {
+            uri.query()?
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("X-Amz-SignedHeaders="))
+                .map(|v| v.replace("%3B", ";").replace("%3b", ";"))
+        });
+    if signed_headers.is_some_and(|v| v.split(';').any(|h| h.trim().eq_ignore_ascii_case("range"))) {
+        return None;
+    }
+
+    let ranges = specs
+        .split(',')
+        .map(|spec| Range::parse(&format!("bytes={}", spec.trim())).ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some(ByteRanges(ranges))
+}
+
+/// Request id sent by the client, or a new ULID when missing or not a valid header value
+fn request_id_of(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| ulid::Ulid::new().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_id_of() {
+        let mut headers = HeaderMap::new();
+        let generated = request_id_of(&headers);
+        assert!(ulid::Ulid::from_string(&generated).is_ok());
+
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static(" client-id "));
+        assert_eq!(request_id_of(&headers), "client-id");
+
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_str(&"x".repeat(200)).unwrap());
+        assert_ne!(request_id_of(&headers), "x".repeat(200));
+    }
+
+    #[test]
+    fn test_byte_ranges_of() {
+        let uri: http::Uri = "/bucket/key".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(byte_ranges_of(&uri, &headers), None);
+
+        headers.insert(http::header::RANGE, HeaderValue::from_static("bytes=0-9"));
+        assert_eq!(byte_ranges_of(&uri, &headers), None);
+
+        headers.insert(http::header::RANGE, HeaderValue::from_static("bytes=0-9, 20-, -5"));
+        assert_eq!(
+            byte_ranges_of(&uri, &headers),
+            Some(ByteRanges(vec![
+                Range::Int { first: 0, last: Some(9) },
+                Range::Int { first: 20, last: None },
+                Range::Suffix { length: 5 },
+            ]))
+        );
+
+        headers.insert(http::header::RANGE, HeaderValue::from_static("bytes=0-9,x-y"));
+        assert_eq!(byte_ranges_of(&uri, &headers), None);
+
+        // A signed Range header must reach the S3 layer as it was sent
+        headers.insert(http::header::RANGE, HeaderValue::from_static("bytes=0-9,20-29"));
+        headers.insert(
+            http::header::AUTHORIZATION,
+            HeaderValue::from_static(
+                "AWS4-HMAC-SHA256 Credential=a/20250101/us-east-1/s3/aws4_request, SignedHeaders=host;range;x-amz-date, Signature=0",
+            ),
+        );
+        assert_eq!(byte_ranges_of(&uri, &headers), None);
+
+        headers.remove(http::header::AUTHORIZATION);
+        let presigned: http::Uri = "/bucket/key?X-Amz-SignedHeaders=host&X-Amz-Signature=0".parse().unwrap();
+        assert_eq!(byte_ranges_of(&presigned, &headers).map(|r| r.0.len()), Some(2));
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ordered startup and shutdown of the server subsystems.
+//!
+//! Subsystems are registered with the names of the subsystems they depend on. They start in
+//! dependency order, registration order breaking ties, and stop in the reverse of the order they
+//! started in, so a subsystem never outlives what it depends on. Every stage is bounded by a
+//! timeout and logged as it progresses.
+
+use futures::future::BoxFuture;
+use std::collections::HashSet;
+use std::future::Future;
+use std::io::{Error, Result};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Default bound of the start of one subsystem
+pub(crate) const DEFAULT_START_TIMEOUT: Duration = Duration::from_secs(60);
+/// Default bound of the stop of one subsystem
+pub(crate) const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Hook = Box<dyn FnOnce() -> BoxFuture<'static, Result<()>> + Send>;
+
+/// A part of the server with a start and a stop stage
+pub(crate) struct Subsystem {
+    name: &'static str,
+    depends_on: Vec<&'static str>,
+    start: Option<Hook>,
+    stop: Option<Hook>,
+    start_timeout: Option<Duration>,
+    stop_timeout: Duration,
+}
+
+impl Subsystem {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            depends_on: Vec::new(),
+            start: None,
+            stop: None,
+            start_timeout: Some(DEFAULT_START_TIMEOUT),
+            stop_timeout: DEFAULT_STOP_TIMEOUT,
+        }
+    }
+
+    /// Start after, and stop before, the subsystems named
+    pub(crate) fn depends_on(mut self, names: &[&'static str]) -> Self {
+        self.depends_on.extend_from_slice(names);
+        self
+    }
+
+    pub(crate) fn on_start<F, Fut>(mut self, f: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.start = Some(Box::new(move || Box::pin(f())));
+        self
+    }
+
+    pub(crate) fn on_stop<F, Fut>(mut self, f: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.stop = Some(Box::new(move || Box::pin(f())));
+        self
+    }
+
+    /// Bound the start stage, `None` waits for as long as it takes
+    pub(crate) fn start_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.start_timeout = timeout;
+        self
+    }
+
+    pub(crate) fn stop_timeout(mut self, timeout: Duration) -> Self {
+        self.stop_timeout = timeout;
+        self
+    }
+}
+
+/// Owner of the subsystems of the server
+#[derive(Default)]
+pub(crate) struct LifecycleManager {
+    registered: Vec<Subsystem>,
+    /// Subsystems started, in the order they started
+    started: Vec<Subsystem>,
+}
+
+impl LifecycleManager {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn register(&mut self, subsystem: Subsystem) -> &mut Self {
+        self.registered.push(subsystem);
+        self
+    }
+
+    /// Order the registered subsystems so each comes after its dependencies
+    fn startup_order(&mut self) -> Result<Vec<Subsystem>> {
+        let mut pending = std::mem::take(&mut self.registered);
+        let names: HashSet<_> = pending
+            .iter()
+            .map(|s| s.name)
+            .chain(self.started.iter().map(|s| s.name))
+            .collect();
+        for subsystem in &pending {
+            if let Some(missing) = subsystem.depends_on.iter().find(|d| !names.contains(*d)) {
+                return Err(Error::other(format!(
+                    "subsystem {} depends on unknown subsystem {}",
+                    subsystem.name, missing
+                )));
+            }
+        }
+
+        let mut ready: HashSet<_> = self.started.iter().map(|s| s.name).collect();
+        let mut order = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let Some(idx) = pending.iter().position(|s| s.depends_on.iter().all(|d| ready.contains(d))) else {
+                let names: Vec<_> = pending.iter().map(|s| s.name).collect();
+                return Err(Error::other(format!("dependency cycle between subsystems {names:?}")));
+            };
+            let subsystem = pending.remove(idx);
+            ready.insert(subsystem.name);
+            order.push(subsystem);
+        }
+        Ok(order)
+    }
+
+    /// Start the registered subsystems in dependency order
+    ///
+    /// When a subsystem fails to start, the ones already started are stopped again and the
+    /// error is returned.
+    pub(crate) async fn start_all(&mut self) -> Result<()> {
+        let order = self.startup_order()?;
+        let total = order.len();
+        for (i, mut subsystem) in order.into_iter().enumerate() {
+            info!("starting subsystem {} ({}/{})", subsystem.name, i + 1, total);
+            let begin = Instant::now();
+            let result = match subsystem.start.take() {
+                Some(start) => match subsystem.start_timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, start())
+                        .await
+                        .unwrap_or_else(|_| Err(Error::other(format!("timed out after {timeout:?}")))),
+                    None => start().await,
+                },
+                None => Ok(()),
+            };
+
+            if let Err(err) = result {
+                error!("subsystem {} failed to start: {}", subsystem.name, err);
+                let name = subsystem.name;
+                self.stop_all().await;
+                return Err(Error::other(format!("start subsystem {name}: {err}")));
+            }
+            info!("subsystem {} started in {:?}", subsystem.name, begin.elapsed());
+            self.started.push(subsystem);
+        }
+        Ok(())
+    }
+
+    /// Stop the started subsystems in the reverse of the order they started in
+    ///
+    /// A subsystem that fails or times out to stop is logged and skipped, the others still stop.
+    pub(crate) async fn stop_all(&mut self) {
+        let total = self.started.len();
+        let mut stopped = 0;
+        while let Some(mut subsystem) = self.started.pop() {
+            stopped += 1;
+            let Some(stop) = subsystem.stop.take() else {
+                continue;
+            };
+            info!("stopping subsystem {} ({}/{})", subsystem.name, stopped, total);
+            let begin = Instant::now();
+            match tokio::time::timeout(subsystem.stop_timeout, stop()).await {
+                Ok(Ok(())) => info!("subsystem {} stopped in {:?}", subsystem.name, begin.elapsed()),
+                Ok(Err(err)) => error!("subsystem {} failed to stop: {}", subsystem.name, err),
+                Err(_) => warn!("subsystem {} did not stop within {:?}", subsystem.name, subsystem.stop_timeout),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn recorded(log: &Arc<Mutex<Vec<String>>>, name: &'static str, deps: &[&'static str]) -> Subsystem {
+        let (start_log, stop_log) = (log.clone(), log.clone());
+        Subsystem::new(name)
+            .depends_on(deps)
+            .on_start(move || async move {
+                start_log.lock().unwrap().push(format!("start {name}"));
+                Ok(())
+            })
+            .on_stop(move || async move {
+                stop_log.lock().unwrap().push(format!("stop {name}"));
+                Ok(())
+            })
+    }
+
+    #[tokio::test]
+    async fn test_start_in_dependency_order_and_stop_in_reverse() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = LifecycleManager::new();
+        manager
+            .register(recorded(&log, "scanner", &["storage"]))
+            .register(recorded(&log, "obs", &[]))
+            .register(recorded(&log, "storage", &["obs"]))
+            .register(
+                Subsystem::new("hung")
+                    .depends_on(&["obs"])
+                    .on_stop(std::future::pending)
+                    .stop_timeout(Duration::from_millis(10)),
+            );
+
+        manager.start_all().await.unwrap();
+        manager.stop_all().await;
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "start obs",
+                "start storage",
+                "start scanner",
+                "stop scanner",
+                "stop storage",
+                "stop obs"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_start_stops_started_subsystems() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = LifecycleManager::new();
+        manager
+            .register(recorded(&log, "obs", &[]))
+            .register(Subsystem::new("storage").on_start(|| async { Err(Error::other("no drives")) }))
+            .register(recorded(&log, "scanner", &["storage"]));
+
+        assert!(manager.start_all().await.is_err());
+        assert_eq!(*log.lock().unwrap(), ["start obs", "stop obs"]);
+
+        let mut manager = LifecycleManager::new();
+        manager
+            .register(Subsystem::new("a").depends_on(&["b"]))
+            .register(Subsystem::new("b").depends_on(&["a"]));
+        assert!(manager.start_all().await.is_err());
+    }
+}
@@ -12,11 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod aws_chunked;
+mod client_cert;
+mod cors;
 mod http;
 mod hybrid;
 mod layer;
+mod lifecycle;
+mod node_cert;
+mod presign;
 mod service_state;
+mod sig_v4;
+mod sig_v4a;
+mod website;
+pub(crate) use aws_chunked::TrailerMismatch;
+pub(crate) use client_cert::ClientCertUser;
 pub(crate) use http::start_http_server;
+pub(crate) use layer::{ByteRanges, ConnectionInfo, MULTIPART_BYTERANGES};
+pub(crate) use lifecycle::{LifecycleManager, Subsystem};
+pub(crate) use node_cert::{NodePeer, node_rpc_allowed};
 pub(crate) use service_state::SHUTDOWN_TIMEOUT;
 pub(crate) use service_state::ServiceState;
 pub(crate) use service_state::ServiceStateManager;
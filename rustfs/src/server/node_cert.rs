@@ -0,0 +1,117 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Peers authenticating with a node certificate.
+//!
+//! With node TLS enabled, the listener also accepts client certificates signed by the node CA,
+//! whichever version of it is loaded at handshake time. A connection whose certificate is valid
+//! for a cluster host is a peer node, and only peer nodes may call the lock and storage RPCs.
+
+use rustfs_utils::node_tls::node_tls;
+use rustls::client::danger::HandshakeSignatureValid;
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::{CertificateDer, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::{DigitallySignedStruct, DistinguishedName, SignatureScheme};
+use std::sync::Arc;
+
+/// Marks the requests of a connection authenticated with a node certificate, set as a request extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodePeer;
+
+/// Whether the node RPCs of a request may be served
+pub fn node_rpc_allowed(peer: Option<&NodePeer>) -> bool {
+    peer.is_some() || node_tls().is_none()
+}
+
+/// Whether the certificates a client presented make it a peer node
+pub fn node_peer(certs: Option<&[CertificateDer<'_>]>) -> Option<NodePeer> {
+    node_tls()?.is_node_certificate(certs?).then_some(NodePeer)
+}
+
+/// Accepts client certificates signed by the node CA or by the client CA, if any
+#[derive(Debug)]
+pub struct NodeClientCertVerifier {
+    client: Option<Arc<dyn ClientCertVerifier>>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl NodeClientCertVerifier {
+    pub fn new(client: Option<Arc<dyn ClientCertVerifier>>) -> Self {
+        Self {
+            client,
+            algorithms: rustls::crypto::aws_lc_rs::default_provider().signature_verification_algorithms,
+        }
+    }
+}
+
+impl ClientCertVerifier for NodeClientCertVerifier {
+    fn client_auth_mandatory(&self) -> bool {
+        false
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        self.client.as_ref().map_or(&[], |client| client.root_hint_subjects())
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        let node = node_tls().map(|tls| tls.client_verifier().verify_client_cert(end_entity, intermediates, now));
+        match (node, &self.client) {
+            (Some(Ok(verified)), _) => Ok(verified),
+            (_, Some(client)) => client.verify_client_cert(end_entity, intermediates, now),
+            (Some(Err(e)), None) => Err(e),
+            (None, None) => Err(rustls::Error::General("no client certificates are accepted".to_string())),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_rpc_allowed_without_node_tls() {
+        // Without node TLS every connection may call the node RPCs, as before
+        assert!(node_rpc_allowed(None));
+        assert!(node_rpc_allowed(Some(&NodePeer)));
+        assert_eq!(node_peer(None), None);
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::server::{ConnectionInfo, aws_chunked, sig_v4a, website};
+use crate::storage::post_policy::{PostForm, PostPolicy, parse_post_form};
+use futures::future::BoxFuture;
+use http::header::{CONTENT_TYPE, ETAG, HOST, LOCATION};
+use http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use hyper::body::Incoming;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use s3s::service::S3Service;
+use s3s::{Body, HttpError, HttpRequest, HttpResponse, S3Error, S3ErrorCode, S3Result, s3_error};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use time::OffsetDateTime;
+use tower::Service;
+
+/// Longest validity of a presigned URL, one week like S3
+const MAX_PRESIGNED_EXPIRES: u64 = 7 * 24 * 3600;
+
+/// Characters of an object key escaped in the `Location` of a POST upload
+const KEY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'/');
+
+/// S3 service checking what s3s leaves to the server on presigned requests: the longest
+/// validity of presigned URLs and the policy conditions of browser POST uploads, which it also
+/// answers the way their form asks
+///
+/// Requests signed with SigV4A are signed again by [`sig_v4a`] first and `aws-chunked` uploads with a trailing
+/// checksum are decoded by [`aws_chunked`], both of which the S3 layer turns away. Requests to the website
+/// endpoints of buckets are answered by [`website::serve`] instead.
+#[derive(Clone)]
+pub struct PresignedRequestService {
+    inner: S3Service,
+    domains: Arc<Vec<String>>,
+    website_domains: Arc<Vec<String>>,
+}
+
+impl PresignedRequestService {
+    pub fn new(inner: S3Service, domains: Vec<String>, website_domains: Vec<String>) -> Self {
+        Self {
+            inner,
+            domains: Arc::new(domains),
+            website_domains: Arc::new(website_domains),
+        }
+    }
+
+    /// Domains virtual-hosted-style requests are answered for
+    pub(super) fn domains(&self) -> Arc<Vec<String>> {
+        self.domains.clone()
+    }
+}
+
+impl Service<HttpRequest<Incoming>> for PresignedRequestService {
+    type Response = HttpResponse;
+    type Error = HttpError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: HttpRequest<Incoming>) -> Self::Future {
+        let inner = self.inner.clone();
+        let domains = self.domains.clone();
+        if let Some(bucket) = website::website_bucket(req.headers(), &self.website_domains) {
+            return Box::pin(async move { website::serve(&inner, req.map(Body::from), bucket).await });
+        }
+        Box::pin(async move {
+            let (req, post_upload) = match check_presigned_request(req.map(Body::from), &domains).await {
+                Ok(checked) => checked,
+                Err(err) => return err.to_http_response().map_err(|e| HttpError::new(Box::new(e))),
+            };
+            let res = inner.call(req).await?;
+            Ok(match post_upload {
+                Some(upload) if res.status().is_success() => upload.response(res.headers().get(ETAG)),
+                _ => res,
+            })
+        })
+    }
+}
+
+/// POST upload, answered as its `success_action_redirect` or `success_action_status` fields ask once the
+/// object is stored, where the S3 layer answers it like a PutObject
+#[derive(Debug)]
+struct PostUpload {
+    /// URL of the object uploaded
+    location: String,
+    bucket: String,
+    key: String,
+    redirect: Option<String>,
+    status: Option<String>,
+}
+
+impl PostUpload {
+    fn new(form: &PostForm, bucket: String, scheme: &str, host: &str) -> Self {
+        let key = form.field("key").unwrap_or_default().to_string();
+        Self {
+            location: format!("{scheme}://{host}/{bucket}/{}", utf8_percent_encode(&key, KEY_ENCODE_SET)),
+            bucket,
+            key,
+            redirect: form
+                .field("success_action_redirect")
+                .or_else(|| form.field("redirect"))
+                .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+                .map(str::to_string),
+            status: form.field("success_action_status").map(str::to_string),
+        }
+    }
+
+    fn response(self, etag: Option<&HeaderValue>) -> HttpResponse {
+        let etag = etag.and_then(|v| v.to_str().ok()).unwrap_or_default();
+
+        if let Some(redirect) = &self.redirect {
+            let query = form_urlencoded::Serializer::new(String::new())
+                .append_pair("bucket", &self.bucket)
+                .append_pair("key", &self.key)
+                .append_pair("etag", etag)
+                .finish();
+            let separator = if redirect.contains('?') { '&' } else { '?' };
+            if let Ok(location) = HeaderValue::from_str(&format!("{redirect}{separator}{query}")) {
+                let mut res = HttpResponse::new(Body::empty());
+                *res.status_mut() = StatusCode::SEE_OTHER;
+                res.headers_mut().insert(LOCATION, location);
+                return res;
+            }
+        }
+
+        // Statuses other than these are answered with 204, like S3
+        let (status, body) = match self.status.as_deref() {
+            Some("200") => (StatusCode::OK, None),
+            Some("201") => (
+                StatusCode::CREATED,
+                Some(format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<PostResponse><Location>{}</Location><Bucket>{}</Bucket><Key>{}</Key><ETag>{}</ETag></PostResponse>",
+                    escape_xml(&self.location),
+                    escape_xml(&self.bucket),
+                    escape_xml(&self.key),
+                    escape_xml(etag)
+                )),
+            ),
+            _ => (StatusCode::NO_CONTENT, None),
+        };
+
+        let mut res = HttpResponse::new(body.map_or_else(Body::empty, Body::from));
+        *res.status_mut() = status;
+        if let Ok(location) = HeaderValue::from_str(&self.location) {
+            res.headers_mut().insert(LOCATION, location);
+        }
+        if let Ok(etag) = HeaderValue::from_str(etag) {
+            res.headers_mut().insert(ETAG, etag);
+        }
+        if status == StatusCode::CREATED {
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/xml"));
+        }
+        res
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+async fn check_presigned_request(req: HttpRequest, domains: &[String]) -> S3Result<(HttpRequest, Option<PostUpload>)> {
+    check_presigned_expires(req.uri())?;
+    let req = sig_v4a::convert_sig_v4a_request(req).await?;
+    let req = aws_chunked::decode_trailer_request(req).await?;
+
+    let Some(boundary) = form_boundary(req.method(), req.headers()) else {
+        return Ok((req, None));
+    };
+    let bucket = request_bucket(req.uri(), req.headers(), domains);
+
+    let (parts, mut body) = req.into_parts();
+    let data = body
+        .store_all_unlimited()
+        .await
+        .map_err(|e| S3Error::with_source(S3ErrorCode::MalformedPOSTRequest, e))?;
+
+    let form = parse_post_form(&data, &boundary)?;
+    if let Some(policy) = form.field("policy") {
+        PostPolicy::parse(policy)?.check(&bucket, &form, OffsetDateTime::now_utc())?;
+    }
+
+    let scheme = match parts.extensions.get::<ConnectionInfo>() {
+        Some(info) if info.secure => "https",
+        _ => "http",
+    };
+    let host = parts.headers.get(HOST).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let upload = PostUpload::new(&form, bucket, scheme, host);
+
+    Ok((HttpRequest::from_parts(parts, Body::from(data)), Some(upload)))
+}
+
+fn check_presigned_expires(uri: &Uri) -> S3Result<()> {
+    let Some(expires) = uri.query().and_then(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(k, _)| k == "X-Amz-Expires")
+            .map(|(_, v)| v)
+    }) else {
+        return Ok(());
+    };
+
+    match expires.parse::<u64>() {
+        Ok(expires) if expires <= MAX_PRESIGNED_EXPIRES => Ok(()),
+        _ => Err(s3_error!(
+            AuthorizationQueryParametersError,
+            "X-Amz-Expires must be less than a week (in seconds) that is 604800"
+        )),
+    }
+}
+
+/// Boundary of a POST upload, `None` for other requests
+fn form_boundary(method: &Method, headers: &HeaderMap) -> Option<String> {
+    if method != Method::POST {
+        return None;
+    }
+    let content_type = headers.get(http::header::CONTENT_TYPE)?.to_str().ok()?;
+    let (mime, params) = content_type.split_once(';')?;
+    if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params.split(';').find_map(|param| {
+        let (name, value) = param.trim().split_once('=')?;
+        name.eq_ignore_ascii_case("boundary")
+            .then(|| value.trim_matches('"').to_string())
+    })
+}
+
+/// Bucket of a path-style or virtual-hosted-style request
+pub(super) fn request_bucket(uri: &Uri, headers: &HeaderMap, domains: &[String]) -> String {
+    let host = headers
+        .get(http::header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .map(|host| host.split(':').next().unwrap_or(host))
+        .unwrap_or_default();
+    for domain in domains {
+        let domain = domain.split(':').next().unwrap_or(domain);
+        if let Some(bucket) = host.strip_suffix(domain).and_then(|b| b.strip_suffix('.')) {
+            return bucket.to_string();
+        }
+    }
+
+    let path = uri.path().trim_start_matches('/');
+    let bucket = path.split('/').next().unwrap_or_default();
+    percent_encoding::percent_decode_str(bucket).decode_utf8_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_presigned_expires() {
+        let uri = |q: &str| format!("/bucket/key?{q}").parse::<Uri>().unwrap();
+        assert!(check_presigned_expires(&uri("X-Amz-Expires=604800")).is_ok());
+        assert!(check_presigned_expires(&uri("versionId=1")).is_ok());
+        let err = check_presigned_expires(&uri("X-Amz-Expires=604801")).unwrap_err();
+        assert_eq!(*err.code(), S3ErrorCode::AuthorizationQueryParametersError);
+        assert!(check_presigned_expires(&uri("X-Amz-Expires=-1")).is_err());
+    }
+
+    #[test]
+    fn test_request_bucket() {
+        let mut headers = HeaderMap::new();
+        headers.insert(http::header::HOST, "photos.s3.example.com:9000".parse().unwrap());
+        let domains = vec!["s3.example.com".to_string()];
+        assert_eq!(request_bucket(&"/".parse().unwrap(), &headers, &domains), "photos");
+        assert_eq!(request_bucket(&"/docs/".parse().unwrap(), &headers, &[]), "docs");
+
+        headers.insert(http::header::CONTENT_TYPE, "multipart/form-data; boundary=\"XyZ\"".parse().unwrap());
+        assert_eq!(form_boundary(&Method::POST, &headers).as_deref(), Some("XyZ"));
+        assert!(form_boundary(&Method::PUT, &headers).is_none());
+    }
+
+    #[test]
+    fn test_post_upload_response() {
+        let form = |fields: &[(&str, &str)]| PostForm {
+            fields: fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            file_size: 0,
+        };
+        let upload = |fields: &[(&str, &str)]| PostUpload::new(&form(fields), "photos".to_string(), "https", "s3.example.com");
+        let etag = HeaderValue::from_static("\"abc\"");
+
+        let res = upload(&[("key", "a b.txt")]).response(Some(&etag));
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(res.headers()[LOCATION], "https://s3.example.com/photos/a%20b.txt");
+
+        let res = upload(&[("key", "a.txt"), ("success_action_status", "201")]).response(Some(&etag));
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[CONTENT_TYPE], "application/xml");
+
+        let res = upload(&[("key", "a.txt"), ("success_action_status", "200")]).response(Some(&etag));
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = upload(&[
+            ("key", "a.txt"),
+            ("success_action_redirect", "https://app.example.com/done?x=1"),
+        ])
+        .response(Some(&etag));
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            res.headers()[LOCATION],
+            "https://app.example.com/done?x=1&bucket=photos&key=a.txt&etag=%22abc%22"
+        );
+
+        // A redirect that is not a URL falls back to the status
+        let res = upload(&[("key", "a.txt"), ("success_action_redirect", "javascript:alert(1)")]).response(Some(&etag));
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+        assert_eq!(escape_xml("a<&>\"'"), "a&lt;&amp;&gt;&quot;&apos;");
+    }
+}
@@ -0,0 +1,244 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signature V4 of the requests rewritten before the S3 layer
+//!
+//! The S3 layer checks the signature of requests itself. Requests it cannot take as they come are checked
+//! first, rewritten, and signed again with the key of the client, so that the S3 layer checks them as if the
+//! client had sent them that way. The canonical request is built the way the S3 layer builds it.
+
+use crate::auth::IAMAuth;
+use http::header::{AUTHORIZATION, HOST};
+use http::request::Parts;
+use http::{HeaderMap, HeaderValue, Method, Uri};
+use rustfs_ecstore::global::get_global_action_cred;
+use rustfs_signer::request_signature_v4::{get_signature, get_signing_key};
+use rustfs_utils::crypto::hex_sha256;
+use s3s::auth::S3Auth;
+use s3s::{S3Result, s3_error};
+use time::macros::format_description;
+use time::{Date, PrimitiveDateTime};
+
+pub(super) const SIGN_V4_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+pub(super) const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+pub(super) const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+pub(super) const X_AMZ_CONTENT_SHA256: &str = "x-amz-content-sha256";
+pub(super) const X_AMZ_DATE: &str = "x-amz-date";
+
+/// `Authorization` header of a request signed with signature V4
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct Authorization {
+    pub(super) access_key: String,
+    pub(super) date: String,
+    pub(super) region: String,
+    pub(super) service: String,
+    pub(super) signed_headers: Vec<String>,
+    pub(super) signature: String,
+}
+
+impl Authorization {
+    pub(super) fn parse(header: &str) -> Option<Self> {
+        let (credential, signed_headers, signature) = auth_params(header.strip_prefix(SIGN_V4_ALGORITHM)?)?;
+        let mut scope = credential.rsplitn(5, '/');
+        if scope.next()? != "aws4_request" {
+            return None;
+        }
+        let service = scope.next()?.to_owned();
+        let region = scope.next()?.to_owned();
+        let date = scope.next()?.to_owned();
+        let access_key = scope.next()?.to_owned();
+        Some(Self {
+            access_key,
+            date,
+            region,
+            service,
+            signed_headers,
+            signature,
+        })
+    }
+
+    pub(super) fn scope(&self) -> String {
+        format!("{}/{}/{}/aws4_request", self.date, self.region, self.service)
+    }
+
+    pub(super) fn header(&self) -> String {
+        format!(
+            "{SIGN_V4_ALGORITHM} Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key,
+            self.scope(),
+            self.signed_headers.join(";"),
+            self.signature
+        )
+    }
+
+    pub(super) fn signing_key(&self, secret_key: &str) -> Option<[u8; 32]> {
+        let date = Date::parse(&self.date, format_description!("[year][month][day]")).ok()?;
+        Some(get_signing_key(secret_key, &self.region, date.midnight().assume_utc(), &self.service))
+    }
+}
+
+/// `Credential`, `SignedHeaders` and `Signature` of an `Authorization` header, after its algorithm
+pub(super) fn auth_params(params: &str) -> Option<(&str, Vec<String>, String)> {
+    let (mut credential, mut signed_headers, mut signature) = (None, None, None);
+    for param in params.split(',') {
+        match param.trim().split_once('=')? {
+            ("Credential", v) => credential = Some(v),
+            ("SignedHeaders", v) => signed_headers = Some(v.split(';').map(str::to_owned).collect()),
+            ("Signature", v) => signature = Some(v.to_owned()),
+            _ => {}
+        }
+    }
+    Some((credential?, signed_headers?, signature?))
+}
+
+/// Percent-encode a URI component the way signature V4 does
+fn uri_encode(output: &mut String, input: &str, encode_slash: bool) {
+    for &byte in input.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'~' | b'.' => output.push(byte as char),
+            b'/' if !encode_slash => output.push('/'),
+            _ => output.push_str(&format!("%{byte:02X}")),
+        }
+    }
+}
+
+/// Canonical request of signature V4, for the headers named in `signed_headers` and the given payload hash
+pub(super) fn canonical_request(
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    signed_headers: &[String],
+    payload: &str,
+) -> String {
+    let mut ans = String::with_capacity(256);
+    ans.push_str(method.as_str());
+    ans.push('\n');
+
+    let path = urlencoding::decode(uri.path()).unwrap_or_else(|_| uri.path().into());
+    uri_encode(&mut ans, &path, false);
+    ans.push('\n');
+
+    let query: Vec<(String, String)> = serde_urlencoded::from_str(uri.query().unwrap_or_default()).unwrap_or_default();
+    let mut query: Vec<(String, String)> = query
+        .iter()
+        .filter(|(name, _)| name != "X-Amz-Signature")
+        .map(|(name, value)| {
+            let (mut name_enc, mut value_enc) = (String::new(), String::new());
+            uri_encode(&mut name_enc, name, true);
+            uri_encode(&mut value_enc, value, true);
+            (name_enc, value_enc)
+        })
+        .collect();
+    query.sort_by(|a, b| a.0.cmp(&b.0));
+    let query: Vec<String> = query.into_iter().map(|(name, value)| format!("{name}={value}")).collect();
+    ans.push_str(&query.join("&"));
+    ans.push('\n');
+
+    for name in signed_headers {
+        let mut values = headers.get_all(name.as_str()).iter().peekable();
+        if values.peek().is_none() && name == HOST.as_str() {
+            if let Some(authority) = uri.authority() {
+                ans.push_str(&format!("{name}:{}\n", authority.as_str()));
+            }
+        }
+        for value in values {
+            ans.push_str(&format!("{name}:{}\n", String::from_utf8_lossy(value.as_bytes()).trim()));
+        }
+    }
+    ans.push('\n');
+
+    ans.push_str(&signed_headers.join(";"));
+    ans.push('\n');
+    ans.push_str(payload);
+    ans
+}
+
+/// String to sign of a request, for the algorithm it is signed with
+pub(super) fn string_to_sign(algorithm: &str, amz_date: &str, scope: &str, canonical_request: &str) -> String {
+    let hash = hex_sha256(canonical_request.as_bytes(), str::to_owned);
+    format!("{algorithm}\n{amz_date}\n{scope}\n{hash}")
+}
+
+pub(super) fn request_signature(key: [u8; 32], amz_date: &str, scope: &str, canonical_request: &str) -> String {
+    get_signature(key, &string_to_sign(SIGN_V4_ALGORITHM, amz_date, scope, canonical_request))
+}
+
+/// `X-Amz-Date` of a request, checked to be a signature timestamp
+pub(super) fn amz_date(value: Option<&str>) -> S3Result<String> {
+    let value = value.unwrap_or_default();
+    PrimitiveDateTime::parse(value, format_description!("[year][month][day]T[hour][minute][second]Z"))
+        .map_err(|_| s3_error!(AccessDenied, "invalid header: {X_AMZ_DATE}"))?;
+    Ok(value.to_owned())
+}
+
+/// Secret key of a client, looked up like the S3 layer does
+pub(super) async fn secret_key(access_key: &str) -> S3Result<String> {
+    let Some(cred) = get_global_action_cred() else {
+        return Err(s3_error!(InternalError, "credentials are not initialized"));
+    };
+    let auth = IAMAuth::new(cred.access_key, cred.secret_key);
+    Ok(auth.get_secret_key(access_key).await?.expose().to_owned())
+}
+
+/// Sign a rewritten request again, for its headers as they are now and the given payload hash
+///
+/// Signed headers the rewrite took off the request are left out of the new signature.
+pub(super) fn sign_again(
+    parts: &mut Parts,
+    mut authorization: Authorization,
+    key: [u8; 32],
+    amz_date: &str,
+    payload: &str,
+) -> S3Result<()> {
+    authorization
+        .signed_headers
+        .retain(|name| name == HOST.as_str() || parts.headers.contains_key(name.as_str()));
+    let canonical = canonical_request(&parts.method, &parts.uri, &parts.headers, &authorization.signed_headers, payload);
+    authorization.signature = request_signature(key, amz_date, &authorization.scope(), &canonical);
+    let value = HeaderValue::from_str(&authorization.header()).map_err(|_| s3_error!(AuthorizationHeaderMalformed))?;
+    parts.headers.insert(AUTHORIZATION, value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_authorization() {
+        let header = "AWS4-HMAC-SHA256 Credential=AKIA/20250101/us-east-1/s3/aws4_request, \
+                      SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature=abc";
+        let auth = Authorization::parse(header).unwrap();
+        assert_eq!(auth.access_key, "AKIA");
+        assert_eq!(auth.scope(), "20250101/us-east-1/s3/aws4_request");
+        assert_eq!(auth.signed_headers, ["host", "x-amz-content-sha256", "x-amz-date"]);
+        assert_eq!(Authorization::parse(&auth.header()), Some(auth));
+        assert!(Authorization::parse("AWS4-HMAC-SHA256 Credential=AKIA/20250101/us-east-1/s3").is_none());
+    }
+
+    #[test]
+    fn test_canonical_request() {
+        let uri: Uri = "/bucket/a%20b?uploadId=1&partNumber=2&X-Amz-Signature=x".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, "localhost:9000".parse().unwrap());
+        headers.insert(X_AMZ_DATE, " 20250101T000000Z ".parse().unwrap());
+        let signed = vec![HOST.to_string(), X_AMZ_DATE.to_owned()];
+        assert_eq!(
+            canonical_request(&Method::PUT, &uri, &headers, &signed, UNSIGNED_PAYLOAD),
+            "PUT\n/bucket/a%20b\npartNumber=2&uploadId=1\nhost:localhost:9000\nx-amz-date:20250101T000000Z\n\n\
+             host;x-amz-date\nUNSIGNED-PAYLOAD"
+        );
+    }
+}
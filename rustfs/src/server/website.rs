@@ -0,0 +1,230 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Static website endpoints of buckets
+//!
+//! A request for `<bucket>.<website domain>` is answered by the website configuration of the bucket: routing
+//! rules and redirects first, then the object, the index document for folders, and the error document for
+//! client errors. Objects are read with anonymous GetObject requests to the S3 service, so that a website
+//! only serves what the bucket policy lets everyone read, like on S3.
+
+use crate::error::ApiError;
+use crate::server::ConnectionInfo;
+use http::header::{CONTENT_TYPE, HOST, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE, LOCATION, RANGE};
+use http::request::Parts;
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+use percent_encoding::{AsciiSet, CONTROLS, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use rustfs_ecstore::bucket::metadata_sys;
+use rustfs_ecstore::bucket::website::{find_routing_rule, index_key, redirect_key};
+use rustfs_ecstore::error::StorageError;
+use s3s::dto::RoutingRule;
+use s3s::service::S3Service;
+use s3s::{Body, HttpError, HttpRequest, HttpResponse, S3Error, s3_error};
+
+/// Characters of an object key escaped in the path of the requests reading it
+const KEY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'/');
+
+/// Characters of a redirect target escaped in the `Location` header
+const LOCATION_ENCODE_SET: &AsciiSet = &CONTROLS.add(b' ').add(b'"');
+
+/// Headers of the browser request that apply to the object served
+const FORWARDED_HEADERS: [http::HeaderName; 5] = [RANGE, IF_MATCH, IF_NONE_MATCH, IF_MODIFIED_SINCE, IF_UNMODIFIED_SINCE];
+
+/// Bucket a request to a website endpoint is for, `None` for requests to the S3 API
+pub(super) fn website_bucket(headers: &HeaderMap, domains: &[String]) -> Option<String> {
+    let host = headers.get(HOST)?.to_str().ok()?;
+    let host = host.split(':').next().unwrap_or(host);
+    domains.iter().find_map(|domain| {
+        let domain = domain.split(':').next().unwrap_or(domain);
+        let bucket = host.strip_suffix(domain)?.strip_suffix('.')?;
+        (!bucket.is_empty()).then(|| bucket.to_string())
+    })
+}
+
+/// Answer of the website of `bucket` to `req`
+pub(super) async fn serve(inner: &S3Service, req: HttpRequest, bucket: String) -> Result<HttpResponse, HttpError> {
+    let (parts, _) = req.into_parts();
+    if parts.method != Method::GET && parts.method != Method::HEAD {
+        return error_response(s3_error!(MethodNotAllowed, "The specified method is not allowed against this resource"));
+    }
+
+    let config = match metadata_sys::get_website_config(&bucket).await {
+        Ok((config, _)) => config,
+        Err(StorageError::ConfigNotFound) => {
+            return error_response(s3_error!(
+                NoSuchWebsiteConfiguration,
+                "The specified bucket does not have a website configuration"
+            ));
+        }
+        Err(err) => return error_response(ApiError::from(err).into()),
+    };
+
+    let scheme = match parts.extensions.get::<ConnectionInfo>() {
+        Some(info) if info.secure => "https",
+        _ => "http",
+    };
+    let host = parts.headers.get(HOST).and_then(|v| v.to_str().ok()).unwrap_or_default();
+
+    if let Some(to) = &config.redirect_all_requests_to {
+        let protocol = to.protocol.as_ref().map_or(scheme, |p| p.as_str());
+        let target = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+        return redirect(StatusCode::MOVED_PERMANENTLY, &format!("{protocol}://{}{target}", to.host_name));
+    }
+
+    let key = percent_decode_str(parts.uri.path().trim_start_matches('/'))
+        .decode_utf8_lossy()
+        .into_owned();
+    if let Some(rule) = find_routing_rule(&config, &key, None) {
+        return routing_redirect(rule, &key, scheme, host);
+    }
+
+    let object = index_key(&config, &key).unwrap_or_else(|| key.clone());
+    let response = get_object(inner, &parts, parts.method.clone(), &parts.headers, &bucket, &object).await?;
+    let status = response.status();
+    if status.is_success() || status.is_redirection() {
+        return Ok(with_content_type(response, &object));
+    }
+
+    // A folder asked for without its trailing slash is redirected to it, when it has an index document
+    if status == StatusCode::NOT_FOUND && !key.is_empty() && !key.ends_with('/') {
+        if let Some(index) = index_key(&config, &format!("{key}/")) {
+            let head = get_object(inner, &parts, Method::HEAD, &HeaderMap::new(), &bucket, &index).await?;
+            if head.status().is_success() {
+                return redirect(StatusCode::FOUND, &format!("{}/", parts.uri.path()));
+            }
+        }
+    }
+
+    if let Some(rule) = find_routing_rule(&config, &key, Some(status.as_u16())) {
+        return routing_redirect(rule, &key, scheme, host);
+    }
+
+    if let (true, Some(document)) = (status.is_client_error(), &config.error_document) {
+        let error = get_object(inner, &parts, parts.method.clone(), &HeaderMap::new(), &bucket, &document.key).await?;
+        if error.status().is_success() {
+            let mut error = with_content_type(error, &document.key);
+            *error.status_mut() = status;
+            return Ok(error);
+        }
+    }
+
+    Ok(response)
+}
+
+/// Anonymous read of `bucket/key`, with the `headers` of the browser request that apply to it
+async fn get_object(
+    inner: &S3Service,
+    parts: &Parts,
+    method: Method,
+    headers: &HeaderMap,
+    bucket: &str,
+    key: &str,
+) -> Result<HttpResponse, HttpError> {
+    let uri = format!("/{bucket}/{}", utf8_percent_encode(key, KEY_ENCODE_SET));
+    let mut req = HttpRequest::new(Body::empty());
+    *req.method_mut() = method;
+    *req.uri_mut() = match uri.parse() {
+        Ok(uri) => uri,
+        Err(_) => return error_response(s3_error!(InvalidURI, "Couldn't parse the specified URI")),
+    };
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = headers.get(&name) {
+            req.headers_mut().insert(name, value.clone());
+        }
+    }
+    *req.extensions_mut() = parts.extensions.clone();
+
+    inner.call(req).await
+}
+
+/// Response with the content type guessed from the extension of `key`, when the object has none of its own
+fn with_content_type(mut response: HttpResponse, key: &str) -> HttpResponse {
+    let generic = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_none_or(|v| v == "application/octet-stream" || v == "binary/octet-stream");
+    if generic {
+        if let Some(mime) = mime_guess::from_path(key).first() {
+            if let Ok(value) = HeaderValue::from_str(mime.essence_str()) {
+                response.headers_mut().insert(CONTENT_TYPE, value);
+            }
+        }
+    }
+    response
+}
+
+fn routing_redirect(rule: &RoutingRule, key: &str, scheme: &str, host: &str) -> Result<HttpResponse, HttpError> {
+    let target = &rule.redirect;
+    let protocol = target.protocol.as_ref().map_or(scheme, |p| p.as_str());
+    let host = target.host_name.as_deref().unwrap_or(host);
+    let status = target
+        .http_redirect_code
+        .as_deref()
+        .and_then(|code| StatusCode::from_bytes(code.as_bytes()).ok())
+        .unwrap_or(StatusCode::MOVED_PERMANENTLY);
+    let key = redirect_key(rule, key);
+    redirect(status, &format!("{protocol}://{host}/{}", utf8_percent_encode(&key, LOCATION_ENCODE_SET)))
+}
+
+fn redirect(status: StatusCode, location: &str) -> Result<HttpResponse, HttpError> {
+    let Ok(location) = HeaderValue::from_str(location) else {
+        return error_response(s3_error!(InvalidRequest, "The website redirect location is not valid"));
+    };
+    let mut response = HttpResponse::new(Body::empty());
+    *response.status_mut() = status;
+    response.headers_mut().insert(LOCATION, location);
+    Ok(response)
+}
+
+fn error_response(err: S3Error) -> Result<HttpResponse, HttpError> {
+    err.to_http_response().map_err(|e| HttpError::new(Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_website_bucket() {
+        let domains = vec!["web.example.com:9000".to_string()];
+        let headers = |host: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(HOST, host.parse().unwrap());
+            headers
+        };
+        assert_eq!(website_bucket(&headers("docs.web.example.com:9000"), &domains).as_deref(), Some("docs"));
+        assert_eq!(website_bucket(&headers("docs.web.example.com"), &domains).as_deref(), Some("docs"));
+        assert!(website_bucket(&headers("web.example.com"), &domains).is_none());
+        assert!(website_bucket(&headers("docs.s3.example.com"), &domains).is_none());
+        assert!(website_bucket(&HeaderMap::new(), &domains).is_none());
+    }
+
+    #[test]
+    fn test_with_content_type() {
+        let response = with_content_type(HttpResponse::new(Body::empty()), "assets/app.js");
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/javascript");
+
+        let mut response = HttpResponse::new(Body::empty());
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        let response = with_content_type(response, "index.html");
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/plain");
+    }
+}
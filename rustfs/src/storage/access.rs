@@ -12,14 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::acl::object_acl;
 use super::ecfs::FS;
-use crate::auth::{check_key_valid, get_condition_values, get_session_token};
+use super::sse::check_customer_key_transport;
+use crate::auth::{add_request_conditions, check_key_valid, get_condition_values, get_session_token};
+use crate::billing;
 use crate::license::license_check;
+use crate::server::{ClientCertUser, ConnectionInfo};
 use rustfs_ecstore::bucket::policy_sys::PolicySys;
+use rustfs_ecstore::bucket::public_access::PublicAccessBlock;
+use rustfs_ecstore::bucket::tagging::decode_tags;
+use rustfs_ecstore::new_object_layer_fn;
+use rustfs_ecstore::store_api::{ObjectOptions, StorageAPI};
+use rustfs_filemeta::headers::{AMZ_DECODED_CONTENT_LENGTH, RUSTFS_SOURCE_REPLICATION_REQUEST};
 use rustfs_iam::error::Error as IamError;
 use rustfs_policy::auth;
 use rustfs_policy::policy::action::{Action, S3Action};
 use rustfs_policy::policy::{Args, BucketPolicyArgs};
+use rustfs_signer::presign_constraints::{ConstraintViolation, PresignConstraints};
 use s3s::access::{S3Access, S3AccessContext};
 use s3s::{S3Error, S3ErrorCode, S3Request, S3Result, dto::*, s3_error};
 use std::collections::HashMap;
@@ -32,11 +42,176 @@ pub(crate) struct ReqInfo {
     pub bucket: Option<String>,
     pub object: Option<String>,
     pub version_id: Option<String>,
+    /// Bucket of the request path, counted for billing once the request is let through
+    pub billing_bucket: Option<String>,
+}
+
+/// Count the request for billing, once, now that it is let through
+pub(crate) fn bill_request(req_info: &mut ReqInfo) {
+    if let Some(bucket) = req_info.billing_bucket.take() {
+        billing::record_request(&bucket);
+    }
+}
+
+/// Credentials of the IAM user a client certificate maps to
+///
+/// Only regular IAM users can be mapped: a certificate must never act as the root user, nor as a service
+/// account or temporary credentials, whose policies are tied to the parent that signs for them.
+async fn client_cert_cred(user: &str) -> S3Result<auth::Credentials> {
+    let (cred, is_owner) = check_key_valid("", user).await?;
+    if is_owner || cred.is_temp() || cred.is_service_account() {
+        return Err(s3_error!(AccessDenied, "client certificates can only map to IAM users"));
+    }
+    Ok(cred)
+}
+
+/// Clear the governance bypass a request asks for unless its caller may bypass governance retention
+///
+/// Without the permission the request goes on, with governance retention enforced.
+async fn drop_unauthorized_bypass<T>(req: &mut S3Request<T>, bypass: impl Fn(&mut T) -> &mut Option<bool>) {
+    if *bypass(&mut req.input) == Some(true)
+        && authorize_request(req, Action::S3Action(S3Action::BypassGovernanceRetentionAction))
+            .await
+            .is_err()
+    {
+        *bypass(&mut req.input) = None;
+    }
+}
+
+/// Clear the replication marker of a request unless its caller may replicate into the bucket
+///
+/// Without the permission the request goes on as a regular one, replicated in turn.
+async fn drop_unauthorized_replication<T>(req: &mut S3Request<T>, action: S3Action) {
+    if req.headers.contains_key(RUSTFS_SOURCE_REPLICATION_REQUEST)
+        && authorize_request(req, Action::S3Action(action)).await.is_err()
+    {
+        req.headers.remove(RUSTFS_SOURCE_REPLICATION_REQUEST);
+    }
+}
+
+/// Actions whose policies may test the tags of the object they act on with `s3:ExistingObjectTag`
+const EXISTING_OBJECT_TAG_ACTIONS: &[S3Action] = &[
+    S3Action::GetObjectAction,
+    S3Action::GetObjectVersionAction,
+    S3Action::GetObjectTaggingAction,
+    S3Action::GetObjectVersionTaggingAction,
+    S3Action::PutObjectTaggingAction,
+    S3Action::PutObjectVersionTaggingAction,
+    S3Action::DeleteObjectTaggingAction,
+    S3Action::DeleteObjectVersionTaggingAction,
+    S3Action::GetObjectRetentionAction,
+    S3Action::PutObjectRetentionAction,
+    S3Action::GetObjectLegalHoldAction,
+    S3Action::PutObjectLegalHoldAction,
+];
+
+/// Authorizes the ACL a request gives the bucket or object it creates, which takes the permission of
+/// setting that ACL afterwards
+async fn authorize_acl<T>(req: &mut S3Request<T>, acl: Option<&str>) -> S3Result<()> {
+    if matches!(acl, None | Some(ObjectCannedACL::PRIVATE)) {
+        return Ok(());
+    }
+    let req_info = req.extensions.get::<ReqInfo>().expect("ReqInfo not found");
+    let action = if req_info.object.is_some() {
+        S3Action::PutObjectAclAction
+    } else {
+        S3Action::PutBucketAclAction
+    };
+    authorize_request(req, Action::S3Action(action)).await
+}
+
+/// Adds the `ExistingObjectTag/<key>` condition values of the object the request acts on
+async fn add_existing_object_tags(conditions: &mut HashMap<String, Vec<String>>, req_info: &ReqInfo, action: Action) {
+    let Action::S3Action(s3_action) = action else {
+        return;
+    };
+    if !EXISTING_OBJECT_TAG_ACTIONS.contains(&s3_action) {
+        return;
+    }
+    let (Some(bucket), Some(object)) = (req_info.bucket.as_deref(), req_info.object.as_deref()) else {
+        return;
+    };
+    let Some(store) = new_object_layer_fn() else {
+        return;
+    };
+
+    let opts = ObjectOptions {
+        version_id: req_info.version_id.clone(),
+        ..Default::default()
+    };
+    // A missing object has no tags, the handler reports it once the request is allowed
+    let Ok(tags) = store.get_object_tags(bucket, object, &opts).await else {
+        return;
+    };
+    for tag in decode_tags(&tags) {
+        if let (Some(key), Some(value)) = (tag.key, tag.value) {
+            conditions.insert(format!("ExistingObjectTag/{key}"), vec![value]);
+        }
+    }
+}
+
+/// Whether the ACL of the object the request reads lets its caller read it
+///
+/// The ACL of an object only ever grants reads, and the deny statements of the bucket policy still apply.
+/// Object ACLs grant nothing in a bucket whose public access block ignores public ACLs.
+async fn object_acl_allows(args: &BucketPolicyArgs<'_>, version_id: Option<&str>) -> bool {
+    if args.action != Action::S3Action(S3Action::GetObjectAction) || args.object.is_empty() {
+        return false;
+    }
+    if PublicAccessBlock::get(args.bucket)
+        .await
+        .map_or(true, |block| block.ignore_public_acls)
+    {
+        return false;
+    }
+    let Some(store) = new_object_layer_fn() else {
+        return false;
+    };
+
+    let opts = ObjectOptions {
+        version_id: version_id.map(str::to_owned),
+        ..Default::default()
+    };
+    // A missing object is reported by the handler, once the request is allowed
+    let Ok(info) = store.get_object_info(args.bucket, args.object, &opts).await else {
+        return false;
+    };
+    let Some(statement) = object_acl(&info.user_defined).object_statement(args.bucket, args.object) else {
+        return false;
+    };
+
+    statement.is_allowed(args)
+        && PolicySys::is_allowed(&BucketPolicyArgs {
+            deny_only: true,
+            ..args.clone()
+        })
+        .await
 }
 
 /// Authorizes the request based on the action and credentials.
 pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3Result<()> {
-    let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+    check_request_access(req, action).await?;
+    bill_request(req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found"));
+    Ok(())
+}
+
+/// Authorizes `action` on one key of a request acting on several, as a request naming only that key would be
+pub(crate) async fn authorize_key<T>(req: &S3Request<T>, key: &str, version_id: Option<&str>, action: Action) -> S3Result<()> {
+    let req_info = ReqInfo {
+        object: Some(key.to_owned()),
+        version_id: version_id.map(str::to_owned),
+        ..req.extensions.get::<ReqInfo>().expect("ReqInfo not found").clone()
+    };
+    check_access(req, &req_info, action).await
+}
+
+async fn check_request_access<T>(req: &S3Request<T>, action: Action) -> S3Result<()> {
+    let req_info = req.extensions.get::<ReqInfo>().expect("ReqInfo not found");
+    check_access(req, req_info, action).await
+}
+
+async fn check_access<T>(req: &S3Request<T>, req_info: &ReqInfo, action: Action) -> S3Result<()> {
+    let conn = req.extensions.get::<ConnectionInfo>().copied();
 
     if let Some(cred) = &req_info.cred {
         let Ok(iam_store) = rustfs_iam::get() else {
@@ -48,9 +223,36 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
 
         let default_claims = HashMap::new();
         let claims = cred.claims.as_ref().unwrap_or(&default_claims);
-        let conditions = get_condition_values(&req.headers, cred);
+        let mut conditions = get_condition_values(&req.headers, cred);
+        add_request_conditions(&mut conditions, &req.uri, conn.as_ref());
+        add_existing_object_tags(&mut conditions, req_info, action).await;
 
+        // The bucket policy names users, whatever credentials of theirs they sign with
+        let account = if cred.is_temp() || cred.is_service_account() {
+            cred.parent_user.as_str()
+        } else {
+            cred.access_key.as_str()
+        };
+        let bucket_policy_args = |action, deny_only| BucketPolicyArgs {
+            bucket: req_info.bucket.as_deref().unwrap_or(""),
+            action,
+            is_owner: false,
+            account,
+            groups: &cred.groups,
+            conditions: &conditions,
+            object: req_info.object.as_deref().unwrap_or(""),
+            deny_only,
+        };
+
+        // An explicit deny of the bucket policy wins over anything the user policies allow, except for
+        // the owner, who can always fix the policy of a bucket
+        if !req_info.is_owner && req_info.bucket.is_some() && !PolicySys::is_allowed(&bucket_policy_args(action, true)).await {
+            return Err(s3_error!(AccessDenied, "Access Denied"));
+        }
+
+        // Bypassing governance retention takes its own permission, whatever the version
         if action != Action::S3Action(S3Action::DeleteObjectAction)
+            && action != Action::S3Action(S3Action::BypassGovernanceRetentionAction)
             && req_info.version_id.is_some()
             && iam_store
                 .is_allowed(&Args {
@@ -103,11 +305,64 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
         {
             return Ok(());
         }
+
+        // What the user policies do not allow, the bucket policy may. Temporary credentials and service
+        // accounts are named by their parent user there, but get no more than their own policy allows
+        if req_info.bucket.is_some() && action != Action::S3Action(S3Action::ListAllMyBucketsAction) {
+            let credential_args = |action, object| Args {
+                account: &cred.access_key,
+                groups: &cred.groups,
+                action,
+                bucket: req_info.bucket.as_deref().unwrap_or(""),
+                conditions: &conditions,
+                is_owner: false,
+                object,
+                claims,
+                deny_only: false,
+            };
+            let object = req_info.object.as_deref().unwrap_or("");
+            let own_policy = cred.is_temp() || cred.is_service_account();
+
+            if PolicySys::is_allowed(&bucket_policy_args(action, false)).await
+                && (!own_policy
+                    || iam_store
+                        .is_allowed_by_credential_policy(&credential_args(action, object))
+                        .await)
+            {
+                return Ok(());
+            }
+
+            let list_bucket = Action::S3Action(S3Action::ListBucketAction);
+            if action == Action::S3Action(S3Action::ListBucketVersionsAction)
+                && PolicySys::is_allowed(&BucketPolicyArgs {
+                    object: "",
+                    ..bucket_policy_args(list_bucket, false)
+                })
+                .await
+                && (!own_policy
+                    || iam_store
+                        .is_allowed_by_credential_policy(&credential_args(list_bucket, ""))
+                        .await)
+            {
+                return Ok(());
+            }
+
+            if object_acl_allows(&bucket_policy_args(action, false), req_info.version_id.as_deref()).await
+                && (!own_policy
+                    || iam_store
+                        .is_allowed_by_credential_policy(&credential_args(action, object))
+                        .await)
+            {
+                return Ok(());
+            }
+        }
     } else {
-        let conditions = get_condition_values(&req.headers, &auth::Credentials::default());
+        let mut conditions = get_condition_values(&req.headers, &auth::Credentials::default());
+        add_request_conditions(&mut conditions, &req.uri, conn.as_ref());
+        add_existing_object_tags(&mut conditions, req_info, action).await;
 
         if action != Action::S3Action(S3Action::ListAllMyBucketsAction) {
-            if PolicySys::is_allowed(&BucketPolicyArgs {
+            let args = BucketPolicyArgs {
                 bucket: req_info.bucket.as_deref().unwrap_or(""),
                 action,
                 is_owner: false,
@@ -115,9 +370,13 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
                 groups: &None,
                 conditions: &conditions,
                 object: req_info.object.as_deref().unwrap_or(""),
-            })
-            .await
-            {
+                deny_only: false,
+            };
+            if PolicySys::is_allowed(&args).await {
+                return Ok(());
+            }
+
+            if object_acl_allows(&args, req_info.version_id.as_deref()).await {
                 return Ok(());
             }
 
@@ -130,6 +389,7 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
                     groups: &None,
                     conditions: &conditions,
                     object: "",
+                    deny_only: false,
                 })
                 .await
             {
@@ -141,6 +401,37 @@ pub async fn authorize_request<T>(req: &mut S3Request<T>, action: Action) -> S3R
     Err(s3_error!(AccessDenied, "Access Denied"))
 }
 
+/// Refuses uploads the constraints of a pre-signed URL do not allow
+///
+/// The constraints are query parameters covered by the signature, see [`PresignConstraints`].
+fn check_presign_constraints(req: &S3Request<PutObjectInput>) -> S3Result<()> {
+    let constraints = PresignConstraints::from_query(req.uri.query()).map_err(|e| s3_error!(InvalidArgument, "{}", e))?;
+    if constraints.is_empty() {
+        return Ok(());
+    }
+
+    let content_length = req
+        .input
+        .content_length
+        .or_else(|| {
+            req.headers
+                .get(AMZ_DECODED_CONTENT_LENGTH)
+                .and_then(|v| atoi::atoi::<i64>(v.as_bytes()))
+        })
+        .and_then(|length| u64::try_from(length).ok());
+    let content_type = req.input.content_type.as_ref().map(|content_type| content_type.as_ref());
+
+    constraints
+        .check(&req.input.key, content_type, content_length)
+        .map_err(|e| match e {
+            ConstraintViolation::MissingContentLength => s3_error!(MissingContentLength, "{}", e),
+            ConstraintViolation::TooSmall => s3_error!(EntityTooSmall, "{}", e),
+            ConstraintViolation::TooLarge => s3_error!(EntityTooLarge, "{}", e),
+            ConstraintViolation::Malformed(_) => s3_error!(InvalidArgument, "{}", e),
+            ConstraintViolation::ContentType | ConstraintViolation::KeyPrefix => s3_error!(AccessDenied, "{}", e),
+        })
+}
+
 #[async_trait::async_trait]
 impl S3Access for FS {
     // /// Checks whether the current request has accesses to the resources.
@@ -170,10 +461,16 @@ impl S3Access for FS {
         //     // cx.extensions_mut(),
         // );
 
+        let conn = cx.extensions_mut().get::<ConnectionInfo>().copied();
+        check_customer_key_transport(cx.headers(), conn.as_ref())?;
+
         let (cred, is_owner) = if let Some(input_cred) = cx.credentials() {
             let (cred, is_owner) =
                 check_key_valid(get_session_token(cx.uri(), cx.headers()).unwrap_or_default(), &input_cred.access_key).await?;
             (Some(cred), is_owner)
+        } else if let Some(ClientCertUser(user)) = cx.extensions_mut().get::<ClientCertUser>().cloned() {
+            // Unsigned requests on a connection with a mapped client certificate act as its user
+            (Some(client_cert_cred(&user).await?), false)
         } else {
             (None, false)
         };
@@ -181,6 +478,7 @@ impl S3Access for FS {
         let req_info = ReqInfo {
             cred,
             is_owner,
+            billing_bucket: cx.s3_path().get_bucket_name().map(str::to_string),
             ..Default::default()
         };
 
@@ -202,6 +500,8 @@ impl S3Access for FS {
         req_info.bucket = Some(req.input.bucket.clone());
 
         authorize_request(req, Action::S3Action(S3Action::CreateBucketAction)).await?;
+        let acl = req.input.acl.clone();
+        authorize_acl(req, acl.as_ref().map(BucketCannedACL::as_str)).await?;
 
         if req.input.object_lock_enabled_for_bucket.is_some_and(|v| v) {
             authorize_request(req, Action::S3Action(S3Action::PutBucketObjectLockConfigurationAction)).await?;
@@ -213,14 +513,24 @@ impl S3Access for FS {
     /// Checks whether the AbortMultipartUpload request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn abort_multipart_upload(&self, _req: &mut S3Request<AbortMultipartUploadInput>) -> S3Result<()> {
-        Ok(())
+    async fn abort_multipart_upload(&self, req: &mut S3Request<AbortMultipartUploadInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+        req_info.object = Some(req.input.key.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::AbortMultipartUploadAction)).await
     }
 
     /// Checks whether the CompleteMultipartUpload request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn complete_multipart_upload(&self, _req: &mut S3Request<CompleteMultipartUploadInput>) -> S3Result<()> {
+    async fn complete_multipart_upload(&self, req: &mut S3Request<CompleteMultipartUploadInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+        req_info.object = Some(req.input.key.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::PutObjectAction)).await?;
+        drop_unauthorized_replication(req, S3Action::ReplicateObjectAction).await;
         Ok(())
     }
 
@@ -250,14 +560,25 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::PutObjectAction)).await
+        authorize_request(req, Action::S3Action(S3Action::PutObjectAction)).await?;
+        let acl = req.input.acl.clone();
+        authorize_acl(req, acl.as_ref().map(ObjectCannedACL::as_str)).await
     }
 
     /// Checks whether the CreateMultipartUpload request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn create_multipart_upload(&self, _req: &mut S3Request<CreateMultipartUploadInput>) -> S3Result<()> {
+    async fn create_multipart_upload(&self, req: &mut S3Request<CreateMultipartUploadInput>) -> S3Result<()> {
         license_check().map_err(|er| s3_error!(AccessDenied, "{:?}", er.to_string()))?;
+
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+        req_info.object = Some(req.input.key.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::PutObjectAction)).await?;
+        let acl = req.input.acl.clone();
+        authorize_acl(req, acl.as_ref().map(ObjectCannedACL::as_str)).await?;
+        drop_unauthorized_replication(req, S3Action::ReplicateObjectAction).await;
         Ok(())
     }
 
@@ -386,8 +707,11 @@ impl S3Access for FS {
     /// Checks whether the DeleteBucketWebsite request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn delete_bucket_website(&self, _req: &mut S3Request<DeleteBucketWebsiteInput>) -> S3Result<()> {
-        Ok(())
+    async fn delete_bucket_website(&self, req: &mut S3Request<DeleteBucketWebsiteInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::DeleteBucketWebsiteAction)).await
     }
 
     /// Checks whether the DeleteObject request has accesses to the resources.
@@ -399,7 +723,10 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::DeleteObjectAction)).await
+        authorize_request(req, Action::S3Action(S3Action::DeleteObjectAction)).await?;
+        drop_unauthorized_bypass(req, |input| &mut input.bypass_governance_retention).await;
+        drop_unauthorized_replication(req, S3Action::ReplicateDeleteAction).await;
+        Ok(())
     }
 
     /// Checks whether the DeleteObjectTagging request has accesses to the resources.
@@ -411,21 +738,31 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::DeleteObjectTaggingAction)).await
+        authorize_request(req, Action::S3Action(S3Action::DeleteObjectTaggingAction)).await?;
+        drop_unauthorized_replication(req, S3Action::ReplicateTagsAction).await;
+        Ok(())
     }
 
     /// Checks whether the DeleteObjects request has accesses to the resources.
     ///
-    /// This method returns `Ok(())` by default.
-    async fn delete_objects(&self, _req: &mut S3Request<DeleteObjectsInput>) -> S3Result<()> {
+    /// Each key is authorized by the handler, which reports the keys the caller may not delete.
+    async fn delete_objects(&self, req: &mut S3Request<DeleteObjectsInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+        if req.input.bypass_governance_retention == Some(true) {
+            drop_unauthorized_bypass(req, |input| &mut input.bypass_governance_retention).await;
+        }
         Ok(())
     }
 
     /// Checks whether the DeletePublicAccessBlock request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn delete_public_access_block(&self, _req: &mut S3Request<DeletePublicAccessBlockInput>) -> S3Result<()> {
-        Ok(())
+    async fn delete_public_access_block(&self, req: &mut S3Request<DeletePublicAccessBlockInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::PutBucketPublicAccessBlockAction)).await
     }
 
     /// Checks whether the GetBucketAccelerateConfiguration request has accesses to the resources.
@@ -445,7 +782,7 @@ impl S3Access for FS {
         let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
         req_info.bucket = Some(req.input.bucket.clone());
 
-        authorize_request(req, Action::S3Action(S3Action::GetBucketPolicyAction)).await
+        authorize_request(req, Action::S3Action(S3Action::GetBucketAclAction)).await
     }
 
     /// Checks whether the GetBucketAnalyticsConfiguration request has accesses to the resources.
@@ -615,8 +952,11 @@ impl S3Access for FS {
     /// Checks whether the GetBucketWebsite request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn get_bucket_website(&self, _req: &mut S3Request<GetBucketWebsiteInput>) -> S3Result<()> {
-        Ok(())
+    async fn get_bucket_website(&self, req: &mut S3Request<GetBucketWebsiteInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::GetBucketWebsiteAction)).await
     }
 
     /// Checks whether the GetObject request has accesses to the resources.
@@ -640,7 +980,7 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::GetBucketPolicyAction)).await
+        authorize_request(req, Action::S3Action(S3Action::GetObjectAclAction)).await
     }
 
     /// Checks whether the GetObjectAttributes request has accesses to the resources.
@@ -719,8 +1059,11 @@ impl S3Access for FS {
     /// Checks whether the GetPublicAccessBlock request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn get_public_access_block(&self, _req: &mut S3Request<GetPublicAccessBlockInput>) -> S3Result<()> {
-        Ok(())
+    async fn get_public_access_block(&self, req: &mut S3Request<GetPublicAccessBlockInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::GetBucketPublicAccessBlockAction)).await
     }
 
     /// Checks whether the HeadBucket request has accesses to the resources.
@@ -833,8 +1176,12 @@ impl S3Access for FS {
     /// Checks whether the ListParts request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn list_parts(&self, _req: &mut S3Request<ListPartsInput>) -> S3Result<()> {
-        Ok(())
+    async fn list_parts(&self, req: &mut S3Request<ListPartsInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+        req_info.object = Some(req.input.key.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::ListMultipartUploadPartsAction)).await
     }
 
     /// Checks whether the PutBucketAccelerateConfiguration request has accesses to the resources.
@@ -854,7 +1201,7 @@ impl S3Access for FS {
         let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
         req_info.bucket = Some(req.input.bucket.clone());
 
-        authorize_request(req, Action::S3Action(S3Action::PutBucketPolicyAction)).await
+        authorize_request(req, Action::S3Action(S3Action::PutBucketAclAction)).await
     }
 
     /// Checks whether the PutBucketAnalyticsConfiguration request has accesses to the resources.
@@ -1004,8 +1351,11 @@ impl S3Access for FS {
     /// Checks whether the PutBucketWebsite request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn put_bucket_website(&self, _req: &mut S3Request<PutBucketWebsiteInput>) -> S3Result<()> {
-        Ok(())
+    async fn put_bucket_website(&self, req: &mut S3Request<PutBucketWebsiteInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::PutBucketWebsiteAction)).await
     }
 
     /// Checks whether the PutObject request has accesses to the resources.
@@ -1019,7 +1369,12 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::PutObjectAction)).await
+        check_presign_constraints(req)?;
+        authorize_request(req, Action::S3Action(S3Action::PutObjectAction)).await?;
+        let acl = req.input.acl.clone();
+        authorize_acl(req, acl.as_ref().map(ObjectCannedACL::as_str)).await?;
+        drop_unauthorized_replication(req, S3Action::ReplicateObjectAction).await;
+        Ok(())
     }
 
     /// Checks whether the PutObjectAcl request has accesses to the resources.
@@ -1031,7 +1386,7 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::PutBucketPolicyAction)).await
+        authorize_request(req, Action::S3Action(S3Action::PutObjectAclAction)).await
     }
 
     /// Checks whether the PutObjectLegalHold request has accesses to the resources.
@@ -1043,7 +1398,9 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::PutObjectLegalHoldAction)).await
+        authorize_request(req, Action::S3Action(S3Action::PutObjectLegalHoldAction)).await?;
+        drop_unauthorized_replication(req, S3Action::ReplicateObjectAction).await;
+        Ok(())
     }
 
     /// Checks whether the PutObjectLockConfiguration request has accesses to the resources.
@@ -1065,7 +1422,10 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::PutObjectRetentionAction)).await
+        authorize_request(req, Action::S3Action(S3Action::PutObjectRetentionAction)).await?;
+        drop_unauthorized_bypass(req, |input| &mut input.bypass_governance_retention).await;
+        drop_unauthorized_replication(req, S3Action::ReplicateObjectAction).await;
+        Ok(())
     }
 
     /// Checks whether the PutObjectTagging request has accesses to the resources.
@@ -1077,14 +1437,19 @@ impl S3Access for FS {
         req_info.object = Some(req.input.key.clone());
         req_info.version_id = req.input.version_id.clone();
 
-        authorize_request(req, Action::S3Action(S3Action::PutObjectTaggingAction)).await
+        authorize_request(req, Action::S3Action(S3Action::PutObjectTaggingAction)).await?;
+        drop_unauthorized_replication(req, S3Action::ReplicateTagsAction).await;
+        Ok(())
     }
 
     /// Checks whether the PutPublicAccessBlock request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn put_public_access_block(&self, _req: &mut S3Request<PutPublicAccessBlockInput>) -> S3Result<()> {
-        Ok(())
+    async fn put_public_access_block(&self, req: &mut S3Request<PutPublicAccessBlockInput>) -> S3Result<()> {
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+        req_info.bucket = Some(req.input.bucket.clone());
+
+        authorize_request(req, Action::S3Action(S3Action::PutBucketPublicAccessBlockAction)).await
     }
 
     /// Checks whether the RestoreObject request has accesses to the resources.
@@ -1124,8 +1489,30 @@ impl S3Access for FS {
     /// Checks whether the UploadPartCopy request has accesses to the resources.
     ///
     /// This method returns `Ok(())` by default.
-    async fn upload_part_copy(&self, _req: &mut S3Request<UploadPartCopyInput>) -> S3Result<()> {
-        Ok(())
+    async fn upload_part_copy(&self, req: &mut S3Request<UploadPartCopyInput>) -> S3Result<()> {
+        {
+            let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+            let (src_bucket, src_key, version_id) = match &req.input.copy_source {
+                CopySource::AccessPoint { .. } => return Err(s3_error!(NotImplemented)),
+                CopySource::Bucket { bucket, key, version_id } => {
+                    (bucket.to_string(), key.to_string(), version_id.as_ref().map(|v| v.to_string()))
+                }
+            };
+
+            req_info.bucket = Some(src_bucket);
+            req_info.object = Some(src_key);
+            req_info.version_id = version_id;
+
+            authorize_request(req, Action::S3Action(S3Action::GetObjectAction)).await?;
+        }
+
+        let req_info = req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found");
+
+        req_info.bucket = Some(req.input.bucket.clone());
+        req_info.object = Some(req.input.key.clone());
+        req_info.version_id = None;
+
+        authorize_request(req, Action::S3Action(S3Action::PutObjectAction)).await
     }
 
     /// Checks whether the WriteGetObjectResponse request has accesses to the resources.
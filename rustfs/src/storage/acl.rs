@@ -0,0 +1,278 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Canned ACLs, for the clients that only speak ACLs
+//!
+//! The ACL of a bucket is kept in its metadata, see [`BucketAcl`] for how it is evaluated. The ACL of an
+//! object is kept in the object metadata and turned into a statement for that object when a request
+//! reading it is authorized. Only the `private`, `public-read` and `public-read-write` canned ACLs, and
+//! the grants they amount to, are supported.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use rustfs_ecstore::bucket::acl::{BucketAcl, public_object_statement};
+use rustfs_ecstore::bucket::metadata::BUCKET_ACL_CONFIG;
+use rustfs_ecstore::bucket::metadata_sys;
+use rustfs_ecstore::bucket::public_access::PublicAccessBlock;
+use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
+use rustfs_policy::policy::statement::BPStatement;
+use s3s::dto::{AccessControlPolicy, BucketCannedACL, Grant, Grantee, Owner, Permission, Type};
+use s3s::{S3Result, s3_error};
+
+use crate::error::ApiError;
+
+/// Group of the grants made to everyone
+pub const ALL_USERS_URI: &str = "http://acs.amazonaws.com/groups/global/AllUsers";
+
+/// Metadata key the canned ACL of an object is kept under
+pub static OBJECT_ACL_KEY: LazyLock<String> = LazyLock::new(|| format!("{RESERVED_METADATA_PREFIX_LOWER}acl"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CannedAcl {
+    Private,
+    PublicRead,
+    PublicReadWrite,
+}
+
+impl CannedAcl {
+    /// ACL of an `x-amz-acl` header, the canned ACLs without a policy equivalent are not implemented
+    pub fn parse(acl: &str) -> S3Result<Self> {
+        match acl {
+            BucketCannedACL::PRIVATE => Ok(Self::Private),
+            BucketCannedACL::PUBLIC_READ => Ok(Self::PublicRead),
+            BucketCannedACL::PUBLIC_READ_WRITE => Ok(Self::PublicReadWrite),
+            _ => Err(s3_error!(NotImplemented, "The canned ACL {} is not supported", acl)),
+        }
+    }
+
+    /// ACL of an `AccessControlPolicy` document, whose grants must be those of a supported canned ACL
+    pub fn from_policy(policy: &AccessControlPolicy) -> S3Result<Self> {
+        let (mut read, mut write) = (false, false);
+        for grant in policy.grants.iter().flatten() {
+            let all_users = grant
+                .grantee
+                .as_ref()
+                .is_some_and(|grantee| grantee.uri.as_deref() == Some(ALL_USERS_URI));
+            match (all_users, grant.permission.as_ref().map(Permission::as_str)) {
+                (false, Some(Permission::FULL_CONTROL)) => {}
+                (true, Some(Permission::READ)) => read = true,
+                (true, Some(Permission::WRITE)) => write = true,
+                _ => {
+                    return Err(s3_error!(
+                        NotImplemented,
+                        "Only the grants of the private, public-read and public-read-write canned ACLs are supported"
+                    ));
+                }
+            }
+        }
+
+        match (read, write) {
+            (false, false) => Ok(Self::Private),
+            (true, false) => Ok(Self::PublicRead),
+            (true, true) => Ok(Self::PublicReadWrite),
+            (false, true) => Err(s3_error!(NotImplemented, "A write grant to everyone needs a read grant")),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Private => BucketCannedACL::PRIVATE,
+            Self::PublicRead => BucketCannedACL::PUBLIC_READ,
+            Self::PublicReadWrite => BucketCannedACL::PUBLIC_READ_WRITE,
+        }
+    }
+
+    /// Grants of the ACL, full control to `owner` and the permissions it gives everyone
+    pub fn grants(self, owner: &Owner) -> Vec<Grant> {
+        let mut grants = vec![Grant {
+            grantee: Some(Grantee {
+                type_: Type::from_static(Type::CANONICAL_USER),
+                display_name: owner.display_name.clone(),
+                email_address: None,
+                id: owner.id.clone(),
+                uri: None,
+            }),
+            permission: Some(Permission::from_static(Permission::FULL_CONTROL)),
+        }];
+
+        let public = match self {
+            Self::Private => &[][..],
+            Self::PublicRead => &[Permission::READ][..],
+            Self::PublicReadWrite => &[Permission::READ, Permission::WRITE][..],
+        };
+        grants.extend(public.iter().map(|permission| Grant {
+            grantee: Some(Grantee {
+                type_: Type::from_static(Type::GROUP),
+                display_name: None,
+                email_address: None,
+                id: None,
+                uri: Some(ALL_USERS_URI.to_owned()),
+            }),
+            permission: Some(Permission::from_static(permission)),
+        }));
+
+        grants
+    }
+
+    /// Statement the ACL of an object is evaluated as, none for a private object
+    pub fn object_statement(self, bucket: &str, object: &str) -> Option<BPStatement> {
+        (self != Self::Private).then(|| public_object_statement(bucket, object))
+    }
+}
+
+/// ACL of `bucket`, private unless it has one
+pub async fn bucket_acl(bucket: &str) -> S3Result<CannedAcl> {
+    let acl = BucketAcl::get(bucket).await.map_err(ApiError::from)?;
+    Ok(CannedAcl::parse(&acl.canned).unwrap_or(CannedAcl::Private))
+}
+
+/// ACL of an object, private unless its metadata says otherwise
+pub fn object_acl(metadata: &HashMap<String, String>) -> CannedAcl {
+    metadata
+        .get(OBJECT_ACL_KEY.as_str())
+        .and_then(|acl| CannedAcl::parse(acl).ok())
+        .unwrap_or(CannedAcl::Private)
+}
+
+/// Records the ACL a write asks for in the metadata of the object, replacing the one it may have copied
+pub fn set_object_acl(metadata: &mut HashMap<String, String>, acl: Option<&str>) -> S3Result<()> {
+    metadata.remove(OBJECT_ACL_KEY.as_str());
+    if let Some(acl) = acl {
+        let acl = CannedAcl::parse(acl)?;
+        if acl != CannedAcl::Private {
+            metadata.insert(OBJECT_ACL_KEY.clone(), acl.as_str().to_owned());
+        }
+    }
+    Ok(())
+}
+
+/// Refuses a public ACL for `bucket` or its objects when the public access block of the bucket blocks them
+pub async fn check_acl_allowed(bucket: &str, acl: CannedAcl) -> S3Result<()> {
+    if acl == CannedAcl::Private {
+        return Ok(());
+    }
+
+    let block = PublicAccessBlock::get(bucket).await.map_err(ApiError::from)?;
+    if block.block_public_acls {
+        return Err(s3_error!(AccessDenied, "Public ACLs are blocked for this bucket"));
+    }
+    Ok(())
+}
+
+/// Sets the ACL of `bucket` in its metadata, a private bucket has none
+pub async fn put_bucket_acl(bucket: &str, acl: CannedAcl) -> S3Result<()> {
+    if acl == CannedAcl::Private {
+        metadata_sys::delete(bucket, BUCKET_ACL_CONFIG)
+            .await
+            .map_err(ApiError::from)?;
+        return Ok(());
+    }
+
+    let data = BucketAcl::new(acl.as_str()).marshal().map_err(ApiError::from)?;
+    metadata_sys::update(bucket, BUCKET_ACL_CONFIG, data)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustfs_policy::policy::action::{Action, S3Action};
+    use rustfs_policy::policy::{BucketPolicy, BucketPolicyArgs, DEFAULT_VERSION};
+
+    fn anonymous(policy: &BucketPolicy, action: S3Action, object: &str) -> bool {
+        policy.is_allowed(&BucketPolicyArgs {
+            account: "",
+            groups: &None,
+            action: Action::S3Action(action),
+            bucket: "mybucket",
+            conditions: &HashMap::new(),
+            is_owner: false,
+            object,
+            deny_only: false,
+        })
+    }
+
+    fn acl_policy(acl: CannedAcl) -> BucketPolicy {
+        BucketPolicy {
+            version: DEFAULT_VERSION.to_owned(),
+            statements: BucketAcl::new(acl.as_str()).statements("mybucket"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bucket_acl_statements() {
+        let policy = acl_policy(CannedAcl::PublicReadWrite);
+        assert!(anonymous(&policy, S3Action::ListBucketAction, ""));
+        assert!(anonymous(&policy, S3Action::PutObjectAction, "a.txt"));
+
+        let policy = acl_policy(CannedAcl::PublicRead);
+        assert!(anonymous(&policy, S3Action::GetObjectAction, "a.txt"));
+        assert!(!anonymous(&policy, S3Action::PutObjectAction, "a.txt"));
+
+        assert!(acl_policy(CannedAcl::Private).statements.is_empty());
+    }
+
+    #[test]
+    fn test_from_policy() {
+        let owner = Owner::default();
+        for acl in [CannedAcl::Private, CannedAcl::PublicRead, CannedAcl::PublicReadWrite] {
+            let policy = AccessControlPolicy {
+                grants: Some(acl.grants(&owner)),
+                owner: None,
+            };
+            assert_eq!(CannedAcl::from_policy(&policy).unwrap(), acl);
+        }
+
+        let policy = AccessControlPolicy {
+            grants: Some(vec![Grant {
+                grantee: Some(Grantee {
+                    type_: Type::from_static(Type::CANONICAL_USER),
+                    display_name: None,
+                    email_address: None,
+                    id: Some("someone-else".to_owned()),
+                    uri: None,
+                }),
+                permission: Some(Permission::from_static(Permission::READ)),
+            }]),
+            owner: None,
+        };
+        assert!(CannedAcl::from_policy(&policy).is_err());
+        assert!(CannedAcl::parse("authenticated-read").is_err());
+    }
+
+    #[test]
+    fn test_object_acl() {
+        let mut metadata = HashMap::new();
+        set_object_acl(&mut metadata, Some("public-read")).unwrap();
+        assert_eq!(object_acl(&metadata), CannedAcl::PublicRead);
+
+        let statement = object_acl(&metadata).object_statement("mybucket", "a.txt").unwrap();
+        let policy = BucketPolicy {
+            version: DEFAULT_VERSION.to_owned(),
+            statements: vec![statement],
+            ..Default::default()
+        };
+        assert!(anonymous(&policy, S3Action::GetObjectAction, "a.txt"));
+        assert!(!anonymous(&policy, S3Action::GetObjectAction, "b.txt"));
+        assert!(!anonymous(&policy, S3Action::PutObjectAction, "a.txt"));
+
+        set_object_acl(&mut metadata, None).unwrap();
+        assert_eq!(object_acl(&metadata), CannedAcl::Private);
+        assert!(CannedAcl::Private.object_statement("mybucket", "a.txt").is_none());
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Range reads of objects: the `multipart/byteranges` answer to a GET asking for several ranges, and the
+//! 416 answer to a range outside the object
+//!
+//! Each range of a multi-range answer is read from the drives on its own when the body gets to it, so
+//! that the parts of the object between the ranges are never read.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::StreamExt;
+use http::{HeaderMap, HeaderValue};
+use rustfs_ecstore::set_disk::DEFAULT_READ_BUFFER_SIZE;
+use rustfs_ecstore::store::ECStore;
+use rustfs_ecstore::store_api::{HTTPRangeSpec, ObjectIO, ObjectOptions};
+use s3s::dto::Range;
+use s3s::{S3Error, s3_error};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::server::MULTIPART_BYTERANGES;
+
+/// Range spec of the object layer for a `Range` header value
+pub fn range_spec(range: &Range) -> HTTPRangeSpec {
+    match *range {
+        Range::Int { first, last } => HTTPRangeSpec {
+            is_suffix_length: false,
+            start: first as i64,
+            end: last.map_or(-1, |last| last as i64),
+        },
+        Range::Suffix { length } => HTTPRangeSpec {
+            is_suffix_length: true,
+            start: length as i64,
+            end: -1,
+        },
+    }
+}
+
+/// 416 answer to a range outside an object of `size` bytes, with the `Content-Range` telling the client the size
+pub fn range_not_satisfiable(size: i64) -> S3Error {
+    let mut err = s3_error!(InvalidRange, "The requested range is not satisfiable");
+    if let Ok(value) = HeaderValue::from_str(&format!("bytes */{size}")) {
+        let mut headers = HeaderMap::new();
+        headers.insert(http::header::CONTENT_RANGE, value);
+        err.set_headers(headers);
+    }
+    err
+}
+
+/// `multipart/byteranges` body made of ranges of an object, each preceded by its own part headers
+pub struct MultipartRanges {
+    boundary: String,
+    /// Offset and length of every range in the object, with the part headers that precede it
+    parts: Vec<(Bytes, usize, i64)>,
+    tail: Bytes,
+}
+
+impl MultipartRanges {
+    /// Body of the `(offset, length)` ranges of an object of `size` bytes and the given content type
+    pub fn new(ranges: &[(usize, i64)], size: i64, content_type: &str) -> Self {
+        let boundary = Uuid::new_v4().simple().to_string();
+        let parts = ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &(offset, length))| {
+                let delimiter = if i == 0 { "" } else { "\r\n" };
+                let head = format!(
+                    "{delimiter}--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: bytes {}-{}/{size}\r\n\r\n",
+                    offset,
+                    offset as i64 + length - 1
+                );
+                (Bytes::from(head), offset, length)
+            })
+            .collect();
+        let tail = Bytes::from(format!("\r\n--{boundary}--\r\n"));
+        Self { boundary, parts, tail }
+    }
+
+    /// `Content-Type` of the body, which carries its boundary
+    pub fn content_type(&self) -> String {
+        format!("{MULTIPART_BYTERANGES}; boundary={}", self.boundary)
+    }
+
+    pub fn content_length(&self) -> i64 {
+        let parts: i64 = self.parts.iter().map(|(head, _, length)| head.len() as i64 + length).sum();
+        parts + self.tail.len() as i64
+    }
+
+    /// Stream of the body, reading each range of `bucket/key` when the previous one has been sent
+    ///
+    /// The ranges are read by a task of their own, as the readers of the object layer cannot be shared
+    /// between threads the way a response body has to be.
+    pub fn into_stream(
+        self,
+        store: Arc<ECStore>,
+        bucket: String,
+        key: String,
+        opts: ObjectOptions,
+    ) -> ReceiverStream<std::io::Result<Bytes>> {
+        let Self { parts, tail, .. } = self;
+        let (tx, rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            for (head, offset, length) in parts {
+                if tx.send(Ok(head)).await.is_err() {
+                    return;
+                }
+                let rs = HTTPRangeSpec {
+                    is_suffix_length: false,
+                    start: offset as i64,
+                    end: offset as i64 + length - 1,
+                };
+                let reader = match store
+                    .get_object_reader(&bucket, &key, Some(rs), HeaderMap::new(), &opts)
+                    .await
+                {
+                    Ok(reader) => reader,
+                    Err(err) => {
+                        let _ = tx.send(Err(std::io::Error::other(err))).await;
+                        return;
+                    }
+                };
+                let mut data = ReaderStream::with_capacity(reader.stream, DEFAULT_READ_BUFFER_SIZE);
+                while let Some(chunk) = data.next().await {
+                    let failed = chunk.is_err();
+                    if tx.send(chunk).await.is_err() || failed {
+                        return;
+                    }
+                }
+            }
+            let _ = tx.send(Ok(tail)).await;
+        });
+        ReceiverStream::new(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_spec() {
+        let rs = range_spec(&Range::Int { first: 5, last: None });
+        assert_eq!((rs.is_suffix_length, rs.start, rs.end), (false, 5, -1));
+        let rs = range_spec(&Range::Suffix { length: 10 });
+        assert_eq!(rs.get_offset_length(100).unwrap(), (90, 10));
+        assert!(range_spec(&Range::Suffix { length: 0 }).get_offset_length(100).is_err());
+        assert!(
+            range_spec(&Range::Int { first: 100, last: None })
+                .get_offset_length(100)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_range_not_satisfiable() {
+        let err = range_not_satisfiable(42);
+        assert_eq!(err.code(), &s3s::S3ErrorCode::InvalidRange);
+        assert_eq!(err.headers().unwrap().get(http::header::CONTENT_RANGE).unwrap(), "bytes */42");
+    }
+
+    #[test]
+    fn test_multipart_ranges() {
+        let body = MultipartRanges::new(&[(0, 10), (90, 10)], 100, "text/plain");
+        assert!(body.content_type().starts_with("multipart/byteranges; boundary="));
+
+        let (first, second) = (&body.parts[0].0, &body.parts[1].0);
+        let boundary = &body.boundary;
+        assert_eq!(
+            first,
+            &format!("--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-9/100\r\n\r\n")
+        );
+        assert_eq!(
+            second,
+            &format!("\r\n--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 90-99/100\r\n\r\n")
+        );
+        assert_eq!(body.content_length(), (first.len() + second.len() + body.tail.len() + 20) as i64);
+    }
+}
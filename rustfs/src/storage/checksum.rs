@@ -0,0 +1,231 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `x-amz-checksum-*` values clients send with the data they upload
+//!
+//! The data of a request is checked against them while it streams to the drives, and the checksums of a part
+//! are kept with it, keyed by algorithm, for `CompleteMultipartUpload` and `ListParts`. Objects keep theirs in
+//! their metadata and send them back to clients that ask for them with `x-amz-checksum-mode: ENABLED`.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, ready};
+
+use bytes::Bytes;
+use futures::Stream;
+use pin_project_lite::pin_project;
+use rustfs_ecstore::store_api::ObjectInfo;
+use s3s::checksum::ChecksumHasher;
+use s3s::dto::{Checksum, ChecksumAlgorithm, ChecksumMode, ChecksumType};
+
+/// Checksums by algorithm, none when the request has none
+pub fn checksum_map(checksum: Checksum) -> Option<HashMap<String, String>> {
+    let checksums: HashMap<_, _> = [
+        (ChecksumAlgorithm::CRC32, checksum.checksum_crc32),
+        (ChecksumAlgorithm::CRC32C, checksum.checksum_crc32c),
+        (ChecksumAlgorithm::CRC64NVME, checksum.checksum_crc64nvme),
+        (ChecksumAlgorithm::SHA1, checksum.checksum_sha1),
+        (ChecksumAlgorithm::SHA256, checksum.checksum_sha256),
+    ]
+    .into_iter()
+    .filter_map(|(algorithm, value)| value.map(|value| (algorithm.to_owned(), value)))
+    .collect();
+
+    (!checksums.is_empty()).then_some(checksums)
+}
+
+/// `x-amz-checksum-*` values of checksums kept by algorithm, composite ones end with the number of parts
+pub fn checksum_dto(checksums: Option<&HashMap<String, String>>) -> Checksum {
+    let get = |algorithm: &str| checksums.and_then(|c| c.get(algorithm).cloned());
+    let checksum_type = checksums.filter(|c| !c.is_empty()).map(|c| {
+        if c.values().any(|v| v.contains('-')) {
+            ChecksumType::from_static(ChecksumType::COMPOSITE)
+        } else {
+            ChecksumType::from_static(ChecksumType::FULL_OBJECT)
+        }
+    });
+    Checksum {
+        checksum_crc32: get(ChecksumAlgorithm::CRC32),
+        checksum_crc32c: get(ChecksumAlgorithm::CRC32C),
+        checksum_crc64nvme: get(ChecksumAlgorithm::CRC64NVME),
+        checksum_sha1: get(ChecksumAlgorithm::SHA1),
+        checksum_sha256: get(ChecksumAlgorithm::SHA256),
+        checksum_type,
+    }
+}
+
+/// Checksums sent back with an object when the client enables the checksum mode: those of the requested
+/// part of a multipart object, or else those of the whole object
+pub fn object_checksum(info: &ObjectInfo, mode: Option<&ChecksumMode>, part_number: Option<usize>) -> Checksum {
+    if mode.is_none_or(|mode| mode.as_str() != ChecksumMode::ENABLED) {
+        return Checksum::default();
+    }
+    match part_number {
+        Some(part_number) if info.parts.len() > 1 => {
+            let part = info.parts.iter().find(|p| p.number == part_number);
+            checksum_dto(part.and_then(|p| p.checksums.as_ref()))
+        }
+        _ => checksum_dto(info.checksums().as_ref()),
+    }
+}
+
+pin_project! {
+    /// Stream of uploaded data that fails at its end when the data does not match the checksums sent with it
+    pub struct ChecksumStream<S> {
+        #[pin]
+        inner: S,
+        hasher: Option<ChecksumHasher>,
+        expected: Checksum,
+        mismatch: Arc<AtomicBool>,
+    }
+}
+
+impl<S> ChecksumStream<S> {
+    pub fn new(inner: S, checksums: Option<&HashMap<String, String>>) -> Self {
+        let expected = checksum_dto(checksums);
+        let hasher = checksums.map(|_| ChecksumHasher {
+            crc32: expected.checksum_crc32.as_ref().map(|_| Default::default()),
+            crc32c: expected.checksum_crc32c.as_ref().map(|_| Default::default()),
+            sha1: expected.checksum_sha1.as_ref().map(|_| Default::default()),
+            sha256: expected.checksum_sha256.as_ref().map(|_| Default::default()),
+            crc64nvme: expected.checksum_crc64nvme.as_ref().map(|_| Default::default()),
+        });
+        Self {
+            inner,
+            hasher,
+            expected,
+            mismatch: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Flag raised when the data turns out not to match, which tells a failed write apart from other errors
+    pub fn mismatch(&self) -> Arc<AtomicBool> {
+        self.mismatch.clone()
+    }
+}
+
+impl<S> Stream for ChecksumStream<S>
+where
+    S: Stream<Item = std::io::Result<Bytes>>,
+{
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        match ready!(this.inner.poll_next(cx)) {
+            Some(Ok(data)) => {
+                if let Some(hasher) = this.hasher.as_mut() {
+                    hasher.update(&data);
+                }
+                Poll::Ready(Some(Ok(data)))
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(err))),
+            None => {
+                let Some(hasher) = this.hasher.take() else {
+                    return Poll::Ready(None);
+                };
+                let actual = hasher.finalize();
+                let expected = &*this.expected;
+                if actual.checksum_crc32 != expected.checksum_crc32
+                    || actual.checksum_crc32c != expected.checksum_crc32c
+                    || actual.checksum_crc64nvme != expected.checksum_crc64nvme
+                    || actual.checksum_sha1 != expected.checksum_sha1
+                    || actual.checksum_sha256 != expected.checksum_sha256
+                {
+                    this.mismatch.store(true, Ordering::SeqCst);
+                    return Poll::Ready(Some(Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "the data does not match the checksum sent with it",
+                    ))));
+                }
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    fn crc32(value: &str) -> Checksum {
+        Checksum {
+            checksum_crc32: Some(value.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    async fn check(data: &'static [u8], checksum: Checksum) -> (Vec<std::io::Result<Bytes>>, bool) {
+        let checksums = checksum_map(checksum);
+        let stream = ChecksumStream::new(futures::stream::iter([Ok(Bytes::from_static(data))]), checksums.as_ref());
+        let mismatch = stream.mismatch();
+        let items = stream.collect::<Vec<_>>().await;
+        (items, mismatch.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn test_checksum_map() {
+        assert!(checksum_map(Checksum::default()).is_none());
+
+        let checksums = checksum_map(crc32("DUoRhQ==")).unwrap();
+        assert_eq!(checksums.get(ChecksumAlgorithm::CRC32).map(String::as_str), Some("DUoRhQ=="));
+        assert_eq!(checksum_dto(Some(&checksums)).checksum_crc32.as_deref(), Some("DUoRhQ=="));
+        assert!(checksum_dto(None).checksum_crc32.is_none());
+    }
+
+    #[test]
+    fn test_object_checksum() {
+        let enabled = ChecksumMode::from_static(ChecksumMode::ENABLED);
+        let mut info = ObjectInfo::default();
+        info.user_defined
+            .insert("x-rustfs-internal-checksums".to_owned(), r#"{"CRC32":"7ryKJw==-2"}"#.to_owned());
+        info.parts = (1..=2)
+            .map(|number| rustfs_filemeta::ObjectPartInfo {
+                number,
+                checksums: checksum_map(crc32("DUoRhQ==")),
+                ..Default::default()
+            })
+            .collect();
+
+        assert!(object_checksum(&info, None, None).checksum_crc32.is_none());
+
+        let checksum = object_checksum(&info, Some(&enabled), None);
+        assert_eq!(checksum.checksum_crc32.as_deref(), Some("7ryKJw==-2"));
+        assert_eq!(checksum.checksum_type.as_ref().map(|t| t.as_str()), Some(ChecksumType::COMPOSITE));
+
+        let checksum = object_checksum(&info, Some(&enabled), Some(2));
+        assert_eq!(checksum.checksum_crc32.as_deref(), Some("DUoRhQ=="));
+        assert_eq!(checksum.checksum_type.as_ref().map(|t| t.as_str()), Some(ChecksumType::FULL_OBJECT));
+    }
+
+    #[tokio::test]
+    async fn test_checksum_stream() {
+        // CRC32 of "hello world"
+        let (items, mismatch) = check(b"hello world", crc32("DUoRhQ==")).await;
+        assert_eq!(items.len(), 1);
+        assert!(!mismatch);
+
+        let (items, mismatch) = check(b"hello world!", crc32("DUoRhQ==")).await;
+        assert_eq!(items.len(), 2);
+        assert!(items[1].is_err());
+        assert!(mismatch);
+
+        let (items, mismatch) = check(b"anything", Checksum::default()).await;
+        assert_eq!(items.len(), 1);
+        assert!(!mismatch);
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bandwidth and progress reporting of server-side copies
+//!
+//! All server-side copies on a node share the bandwidth set by `RUSTFS_COPY_BANDWIDTH`, so a few
+//! large copies cannot saturate the drives serving regular traffic. While a copy runs, its
+//! progress is published on an in-process channel every `RUSTFS_COPY_PROGRESS_INTERVAL` and sent
+//! as an `s3:ObjectCopy:Progress` event to the notification targets of buckets subscribed to it.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use http::HeaderMap;
+use rustfs_ecstore::store_api::ObjectInfo;
+use rustfs_notify::EventName;
+use rustfs_rio::BandwidthLimiter;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+/// Environment variable holding the bandwidth of server-side copies in bytes per second, unlimited when unset or 0
+pub const ENV_COPY_BANDWIDTH: &str = "RUSTFS_COPY_BANDWIDTH";
+
+/// Environment variable holding the seconds between progress reports of a copy, 0 disables them
+pub const ENV_COPY_PROGRESS_INTERVAL: &str = "RUSTFS_COPY_PROGRESS_INTERVAL";
+
+const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+const PROGRESS_CHANNEL_CAPACITY: usize = 256;
+
+static COPY_LIMITER: LazyLock<Option<Arc<BandwidthLimiter>>> = LazyLock::new(|| {
+    let bytes_per_sec = std::env::var(ENV_COPY_BANDWIDTH)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    (bytes_per_sec > 0).then(|| Arc::new(BandwidthLimiter::new(bytes_per_sec)))
+});
+
+static PROGRESS_INTERVAL: LazyLock<Option<Duration>> = LazyLock::new(|| {
+    let interval = std::env::var(ENV_COPY_PROGRESS_INTERVAL)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map_or(DEFAULT_PROGRESS_INTERVAL, Duration::from_secs);
+    (!interval.is_zero()).then_some(interval)
+});
+
+static PROGRESS_TX: LazyLock<broadcast::Sender<CopyProgress>> = LazyLock::new(|| broadcast::channel(PROGRESS_CHANNEL_CAPACITY).0);
+
+/// Bandwidth shared by the server-side copies of this node
+pub fn copy_limiter() -> Option<Arc<BandwidthLimiter>> {
+    COPY_LIMITER.clone()
+}
+
+/// Receive the progress of the server-side copies of this node
+pub fn subscribe_copy_progress() -> broadcast::Receiver<CopyProgress> {
+    PROGRESS_TX.subscribe()
+}
+
+/// Log the progress reports of server-side copies
+pub fn spawn_progress_log() {
+    let mut rx = subscribe_copy_progress();
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(p) if p.done => debug!(
+                    "Copy {}/{} -> {}/{} finished, {} bytes in {:?}",
+                    p.src_bucket, p.src_object, p.bucket, p.object, p.bytes_copied, p.elapsed
+                ),
+                Ok(p) => info!(
+                    "Copy {}/{} -> {}/{} in progress, {} of {} bytes in {:?}",
+                    p.src_bucket, p.src_object, p.bucket, p.object, p.bytes_copied, p.total_size, p.elapsed
+                ),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Progress of one server-side copy
+#[derive(Debug, Clone)]
+pub struct CopyProgress {
+    pub src_bucket: String,
+    pub src_object: String,
+    pub bucket: String,
+    pub object: String,
+    pub bytes_copied: u64,
+    /// Bytes to copy, -1 when unknown
+    pub total_size: i64,
+    pub elapsed: Duration,
+    /// Whether this is the last report of the copy
+    pub done: bool,
+}
+
+/// Copy whose progress is reported
+#[derive(Debug, Clone)]
+pub struct CopyTarget {
+    pub src_bucket: String,
+    pub src_object: String,
+    pub bucket: String,
+    pub object: String,
+    pub total_size: i64,
+    pub headers: HeaderMap,
+}
+
+/// Reports the progress of a copy until dropped
+pub struct CopyProgressTracker {
+    target: Arc<CopyTarget>,
+    copied: Arc<AtomicU64>,
+    started: Instant,
+    ticker: Option<JoinHandle<()>>,
+}
+
+impl CopyProgressTracker {
+    /// Start reporting `copied`, the bytes of `target` read so far
+    pub fn start(target: CopyTarget, copied: Arc<AtomicU64>) -> Self {
+        let target = Arc::new(target);
+        let started = Instant::now();
+        let ticker = PROGRESS_INTERVAL.map(|interval| {
+            let (target, copied) = (target.clone(), copied.clone());
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+                loop {
+                    ticker.tick().await;
+                    let progress = progress(&target, &copied, started, false);
+                    notify_progress(&target, &progress).await;
+                    let _ = PROGRESS_TX.send(progress);
+                }
+            })
+        });
+        Self {
+            target,
+            copied,
+            started,
+            ticker,
+        }
+    }
+}
+
+impl Drop for CopyProgressTracker {
+    fn drop(&mut self) {
+        if let Some(ticker) = self.ticker.take() {
+            ticker.abort();
+        }
+        let _ = PROGRESS_TX.send(progress(&self.target, &self.copied, self.started, true));
+    }
+}
+
+fn progress(target: &CopyTarget, copied: &AtomicU64, started: Instant, done: bool) -> CopyProgress {
+    CopyProgress {
+        src_bucket: target.src_bucket.clone(),
+        src_object: target.src_object.clone(),
+        bucket: target.bucket.clone(),
+        object: target.object.clone(),
+        bytes_copied: copied.load(Ordering::Relaxed),
+        total_size: target.total_size,
+        elapsed: started.elapsed(),
+        done,
+    }
+}
+
+async fn notify_progress(target: &CopyTarget, progress: &CopyProgress) {
+    if !rustfs_notify::is_notification_system_initialized() {
+        return;
+    }
+
+    let mut resp_elements = HashMap::new();
+    resp_elements.insert(
+        "x-rustfs-copy-source".to_string(),
+        format!("{}/{}", progress.src_bucket, progress.src_object),
+    );
+    resp_elements.insert("x-rustfs-copy-bytes-copied".to_string(), progress.bytes_copied.to_string());
+    resp_elements.insert("x-rustfs-copy-total-size".to_string(), progress.total_size.to_string());
+    resp_elements.insert("x-rustfs-copy-elapsed-ms".to_string(), progress.elapsed.as_millis().to_string());
+
+    let event_args = rustfs_notify::event::EventArgs {
+        event_name: EventName::ObjectCopyProgress,
+        bucket_name: target.bucket.clone(),
+        object: ObjectInfo {
+            bucket: target.bucket.clone(),
+            name: target.object.clone(),
+            size: target.total_size,
+            ..Default::default()
+        },
+        req_params: rustfs_utils::extract_req_params_header(&target.headers),
+        resp_elements,
+        version_id: String::new(),
+        host: rustfs_utils::get_request_host(&target.headers),
+        user_agent: rustfs_utils::get_request_user_agent(&target.headers),
+    };
+    rustfs_notify::global::notifier_instance().notify(event_args).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_copy_progress_reports_done_on_drop() {
+        let mut rx = subscribe_copy_progress();
+        let copied = Arc::new(AtomicU64::new(0));
+        let tracker = CopyProgressTracker::start(
+            CopyTarget {
+                src_bucket: "src".to_string(),
+                src_object: "a".to_string(),
+                bucket: "dst".to_string(),
+                object: "b".to_string(),
+                total_size: 42,
+                headers: HeaderMap::new(),
+            },
+            copied.clone(),
+        );
+        copied.fetch_add(42, Ordering::Relaxed);
+        drop(tracker);
+
+        let progress = rx.recv().await.unwrap();
+        assert!(progress.done);
+        assert_eq!(progress.bytes_copied, 42);
+        assert_eq!((progress.bucket.as_str(), progress.object.as_str()), ("dst", "b"));
+    }
+}
@@ -12,22 +12,40 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::access::authorize_request;
+use super::access::{authorize_key, authorize_request, bill_request};
+use super::acl::{self, CannedAcl, OBJECT_ACL_KEY, bucket_acl, object_acl, set_object_acl};
+use super::byte_ranges::{MultipartRanges, range_not_satisfiable, range_spec};
+use super::checksum::{ChecksumStream, checksum_dto, checksum_map, object_checksum};
+use super::copy_progress::{CopyProgressTracker, CopyTarget, copy_limiter};
+use super::lifecycle::lifecycle_config_error;
+use super::object_lock::{
+    bucket_object_lock, check_version_unprotected, clear_object_lock_metadata, object_lock_error, resolve_object_lock,
+};
 use super::options::del_opts;
 use super::options::extract_metadata;
 use super::options::put_opts;
-use crate::auth::get_condition_values;
+use super::options::{parse_version_id, s3_version_id};
+use super::precondition::{Preconditions, write_preconditions};
+use super::sse::{
+    check_customer_key, copy_source_customer_key, customer_key, customer_key_response, encrypt_reader, encryption_response,
+    new_object_key, reseal_customer_key, resolve_object_encryption, rewrap_object_key,
+};
+use crate::billing::{self, BilledBody};
+use crate::content_scan;
 use crate::error::ApiError;
+use crate::server::{ByteRanges, TrailerMismatch};
 use crate::storage::access::ReqInfo;
+use crate::storage::notification::load_bucket_notification;
 use crate::storage::options::copy_dst_opts;
 use crate::storage::options::copy_src_opts;
-use crate::storage::options::{extract_metadata_from_mime, get_opts};
+use crate::storage::options::{extract_metadata_from_mime, get_opts, is_replication_request, request_deadline, source_mtime};
 use bytes::Bytes;
 use chrono::DateTime;
 use chrono::Utc;
 use datafusion::arrow::csv::WriterBuilder as CsvWriterBuilder;
 use datafusion::arrow::json::WriterBuilder as JsonWriterBuilder;
 use datafusion::arrow::json::writer::JsonArray;
+use rustfs_ecstore::config::storageclass;
 use rustfs_ecstore::set_disk::MAX_PARTS_COUNT;
 use rustfs_s3select_api::object_store::bytes_stream;
 use rustfs_s3select_api::query::Context;
@@ -36,55 +54,86 @@ use rustfs_s3select_query::get_global_db;
 
 // use rustfs_ecstore::store_api::RESERVED_METADATA_PREFIX;
 use futures::StreamExt;
-use http::HeaderMap;
-use rustfs_ecstore::bucket::lifecycle::bucket_lifecycle_ops::validate_transition_tier;
+use http::{HeaderMap, HeaderValue};
+use rustfs_ecstore::bucket::cors::validate_cors_config;
+use rustfs_ecstore::bucket::encryption::keys::{CustomerKey, ObjectKey, is_encrypted, object_key};
+use rustfs_ecstore::bucket::encryption::{SSE_TYPE_META, clear_encryption_metadata, validate_sse_config};
+use rustfs_ecstore::bucket::lifecycle::bucket_lifecycle_ops::{
+    RestoreObjectRequest, completed_restore_obj, ongoing_restore_obj, validate_transition_tier,
+};
 use rustfs_ecstore::bucket::lifecycle::lifecycle::Lifecycle;
+use rustfs_ecstore::bucket::lifecycle::lifecycle::{self, TRANSITION_COMPLETE, TransitionOptions};
+use rustfs_ecstore::bucket::metadata::BUCKET_CORS_CONFIG;
 use rustfs_ecstore::bucket::metadata::BUCKET_LIFECYCLE_CONFIG;
 use rustfs_ecstore::bucket::metadata::BUCKET_NOTIFICATION_CONFIG;
 use rustfs_ecstore::bucket::metadata::BUCKET_POLICY_CONFIG;
+use rustfs_ecstore::bucket::metadata::BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG;
 use rustfs_ecstore::bucket::metadata::BUCKET_REPLICATION_CONFIG;
 use rustfs_ecstore::bucket::metadata::BUCKET_SSECONFIG;
 use rustfs_ecstore::bucket::metadata::BUCKET_TAGGING_CONFIG;
 use rustfs_ecstore::bucket::metadata::BUCKET_VERSIONING_CONFIG;
+use rustfs_ecstore::bucket::metadata::BUCKET_WEBSITE_CONFIG;
 use rustfs_ecstore::bucket::metadata::OBJECT_LOCK_CONFIG;
 use rustfs_ecstore::bucket::metadata_sys;
+use rustfs_ecstore::bucket::object_lock::objectlock::{self, ObjectLockError, Retention, validate_object_lock_config};
+use rustfs_ecstore::bucket::object_lock::objectlock_sys::check_retention_update;
 use rustfs_ecstore::bucket::policy_sys::PolicySys;
-use rustfs_ecstore::bucket::tagging::decode_tags;
-use rustfs_ecstore::bucket::tagging::encode_tags;
+use rustfs_ecstore::bucket::public_access::{PublicAccessBlock, is_bucket_public};
+use rustfs_ecstore::bucket::quota::{check_bucket_quota, has_bucket_quota, record_bucket_usage};
+use rustfs_ecstore::bucket::tagging::{MAX_BUCKET_TAGS, MAX_OBJECT_TAGS, decode_tags, encode_tags, validate_tags};
 use rustfs_ecstore::bucket::utils::serialize;
+use rustfs_ecstore::bucket::versioning::VersioningApi;
 use rustfs_ecstore::bucket::versioning_sys::BucketVersioningSys;
+use rustfs_ecstore::bucket::website::validate_website_config;
 use rustfs_ecstore::cmd::bucket_replication::ReplicationStatusType;
 use rustfs_ecstore::cmd::bucket_replication::ReplicationType;
 use rustfs_ecstore::cmd::bucket_replication::get_must_replicate_options;
 use rustfs_ecstore::cmd::bucket_replication::must_replicate;
 use rustfs_ecstore::cmd::bucket_replication::schedule_replication;
+use rustfs_ecstore::cmd::bucket_replication::{
+    DeletedObjectReplicationInfo, ReplicateDecision, check_replicate_delete, schedule_replication_delete,
+};
+use rustfs_ecstore::cmd::bucket_replication::{
+    OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP, OBJECT_LOCK_RETENTION_TIMESTAMP, TAGGING_TIMESTAMP, metadata_timestamp,
+    set_metadata_timestamp,
+};
 use rustfs_ecstore::compress::MIN_COMPRESSIBLE_SIZE;
-use rustfs_ecstore::compress::is_compressible;
+use rustfs_ecstore::compress::{compression_algorithm, is_compressible};
 use rustfs_ecstore::error::StorageError;
+use rustfs_ecstore::heat_map::global_heat_map;
 use rustfs_ecstore::new_object_layer_fn;
+use rustfs_ecstore::object_ttl::{self, schedule_object_expiry};
 use rustfs_ecstore::set_disk::DEFAULT_READ_BUFFER_SIZE;
+use rustfs_ecstore::store::ECStore;
 use rustfs_ecstore::store_api::BucketOptions;
 use rustfs_ecstore::store_api::CompletePart;
 use rustfs_ecstore::store_api::DeleteBucketOptions;
 use rustfs_ecstore::store_api::HTTPRangeSpec;
+use rustfs_ecstore::store_api::ListObjectsInfo;
 use rustfs_ecstore::store_api::MakeBucketOptions;
+use rustfs_ecstore::store_api::MultipartInfo;
 use rustfs_ecstore::store_api::MultipartUploadResult;
 use rustfs_ecstore::store_api::ObjectIO;
+use rustfs_ecstore::store_api::ObjectInfo;
 use rustfs_ecstore::store_api::ObjectOptions;
 use rustfs_ecstore::store_api::ObjectToDelete;
 use rustfs_ecstore::store_api::PutObjReader;
 use rustfs_ecstore::store_api::StorageAPI;
+use rustfs_filemeta::ObjectPartInfo;
 use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
-use rustfs_filemeta::headers::{AMZ_DECODED_CONTENT_LENGTH, AMZ_OBJECT_TAGGING};
+use rustfs_filemeta::headers::{
+    AMZ_DECODED_CONTENT_LENGTH, AMZ_OBJECT_TAGGING, AMZ_RESTORE_EXPIRY_DAYS, AMZ_RESTORE_REQUEST_DATE, AMZ_STORAGE_CLASS,
+    AMZ_WRITE_OFFSET_BYTES, RUSTFS_EXPIRE_AFTER,
+};
 use rustfs_notify::EventName;
-use rustfs_policy::auth;
+use rustfs_policy::policy::BucketPolicy;
 use rustfs_policy::policy::action::Action;
 use rustfs_policy::policy::action::S3Action;
-use rustfs_policy::policy::{BucketPolicy, BucketPolicyArgs, Validator};
 use rustfs_rio::CompressReader;
 use rustfs_rio::EtagReader;
 use rustfs_rio::HashReader;
 use rustfs_rio::Reader;
+use rustfs_rio::ThrottleReader;
 use rustfs_rio::WarpReader;
 use rustfs_utils::CompressionAlgorithm;
 use rustfs_utils::path::path_join_buf;
@@ -94,6 +143,7 @@ use s3s::S3Error;
 use s3s::S3ErrorCode;
 use s3s::S3Result;
 use s3s::dto::*;
+use s3s::header::{X_AMZ_OBJECT_LOCK_LEGAL_HOLD, X_AMZ_OBJECT_LOCK_MODE, X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE, X_AMZ_RESTORE};
 use s3s::s3_error;
 use s3s::{S3Request, S3Response};
 use std::collections::HashMap;
@@ -102,6 +152,7 @@ use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::LazyLock;
+use std::sync::atomic::Ordering;
 use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
 use tokio::sync::mpsc;
@@ -109,6 +160,7 @@ use tokio_stream::wrappers::ReceiverStream;
 use tokio_tar::Archive;
 use tokio_util::io::ReaderStream;
 use tokio_util::io::StreamReader;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
@@ -179,6 +231,10 @@ impl FS {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
+        // Every extracted object is encrypted like an object put on its own
+        let encryption = resolve_object_encryption(&bucket, &req.headers).await?;
+        let lock_metadata = resolve_object_lock(&bucket, &req.headers).await?;
+
         let prefix = req
             .headers
             .get("X-Amz-Meta-Rustfs-Snowball-Prefix")
@@ -219,27 +275,49 @@ impl FS {
                 let actual_size = size;
 
                 if is_compressible(&HeaderMap::new(), &fpath) && size > MIN_COMPRESSIBLE_SIZE as i64 {
-                    metadata.insert(
-                        format!("{RESERVED_METADATA_PREFIX_LOWER}compression"),
-                        CompressionAlgorithm::default().to_string(),
-                    );
+                    let algorithm = compression_algorithm();
+                    metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}compression"), algorithm.to_string());
                     metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size",), size.to_string());
 
                     let hrd = HashReader::new(reader, size, actual_size, None, false).map_err(ApiError::from)?;
 
-                    reader = Box::new(CompressReader::new(hrd, CompressionAlgorithm::default()));
+                    reader = Box::new(CompressReader::new(hrd, algorithm));
                     size = -1;
                 }
 
+                if let Some(encryption) = &encryption {
+                    encryption.write_metadata(&mut metadata).map_err(ApiError::from)?;
+                }
+                if let Some(object_key) = new_object_key(encryption.as_ref(), &bucket, &fpath, &mut metadata).await? {
+                    metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size",), actual_size.to_string());
+                    reader = encrypt_reader(reader, &mut size, actual_size, &object_key, object_key.part_nonce(1))?;
+                }
+
                 let hrd = HashReader::new(reader, size, actual_size, None, false).map_err(ApiError::from)?;
                 let mut reader = PutObjReader::new(hrd);
 
+                let mut opts = ObjectOptions {
+                    user_defined: metadata,
+                    ..Default::default()
+                };
+                content_scan::mark_pending(&mut opts.user_defined);
+                opts.user_defined.extend(lock_metadata.clone());
+                set_object_ttl(&req.headers, &mut opts.user_defined)?;
+                check_version_unprotected(&bucket, &fpath, &opts, false).await?;
+
+                // Each extracted object counts against the quota of the bucket like an object put on its own
+                let quota_size = u64::try_from(actual_size).unwrap_or_default();
+                check_bucket_quota(&bucket, quota_size, 1).await.map_err(ApiError::from)?;
+
                 let _obj_info = store
-                    .put_object(&bucket, &fpath, &mut reader, &ObjectOptions::default())
+                    .put_object(&bucket, &fpath, &mut reader, &opts)
                     .await
                     .map_err(ApiError::from)?;
+                record_bucket_usage(&bucket, quota_size, 1);
+                content_scan::submit(&bucket, &fpath, _obj_info.version_id.map(|v| v.to_string()));
+                schedule_object_expiry(&_obj_info);
 
-                let e_tag = _obj_info.clone().etag;
+                let e_tag = etag_response(_obj_info.etag.clone());
 
                 // // store.put_object(bucket, object, data, opts);
 
@@ -288,6 +366,110 @@ impl FS {
         };
         Ok(S3Response::new(output))
     }
+
+    /// PutObject with `x-amz-write-offset-bytes`, appending the body to the object when the object has as
+    /// many bytes as the offset, or creating it when the offset is 0 and there is none
+    async fn append_object(&self, req: S3Request<PutObjectInput>, offset: &HeaderValue) -> S3Result<S3Response<PutObjectOutput>> {
+        let offset = offset
+            .to_str()
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v >= 0)
+            .ok_or_else(|| s3_error!(InvalidArgument, "invalid {}", AMZ_WRITE_OFFSET_BYTES))?;
+
+        let PutObjectInput {
+            body,
+            bucket,
+            key,
+            content_length,
+            tagging,
+            metadata,
+            acl,
+            ..
+        } = req.input;
+        let Some(body) = body else { return Err(s3_error!(IncompleteBody)) };
+        let size = match content_length {
+            Some(c) => c,
+            None => req
+                .headers
+                .get(AMZ_DECODED_CONTENT_LENGTH)
+                .and_then(|v| atoi::atoi::<i64>(v.as_bytes()))
+                .ok_or_else(|| s3_error!(UnexpectedContent))?,
+        };
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        // The parts appended are stored as sent, so that none of the parts before has to be read again
+        if resolve_object_encryption(&bucket, &req.headers).await?.is_some() {
+            return Err(s3_error!(NotImplemented, "appending to encrypted objects is not supported"));
+        }
+
+        let mut metadata = metadata.unwrap_or_default();
+        extract_metadata_from_mime(&req.headers, &mut metadata);
+        set_object_acl(&mut metadata, acl.as_ref().map(ObjectCannedACL::as_str))?;
+        acl::check_acl_allowed(&bucket, object_acl(&metadata)).await?;
+        metadata.extend(resolve_object_lock(&bucket, &req.headers).await?);
+        if let Some(tags) = tagging {
+            check_header_tags(&tags)?;
+            metadata.insert(AMZ_OBJECT_TAGGING.to_owned(), tags);
+        }
+        set_object_ttl(&req.headers, &mut metadata)?;
+        content_scan::mark_pending(&mut metadata);
+
+        let body = StreamReader::new(body.map(|f| f.map_err(|e| std::io::Error::other(e.to_string()))));
+        let reader = HashReader::new(Box::new(WarpReader::new(body)), size, size, None, false).map_err(ApiError::from)?;
+        let mut reader = PutObjReader::new(reader);
+
+        let mut opts = put_opts(&bucket, &key, None, &req.headers, metadata)
+            .await
+            .map_err(ApiError::from)?;
+        // The bytes appended are not scanned yet, whatever status the object had
+        opts.append_metadata = Some(content_scan::mark_pending);
+        let cancel = CancellationToken::new();
+        let _cancel_on_drop = cancel.clone().drop_guard();
+        opts.cancel = Some(cancel);
+        opts.deadline = request_deadline();
+        check_version_unprotected(&bucket, &key, &opts, false).await?;
+
+        let quota_size = u64::try_from(size).unwrap_or_default();
+        let new_objects = u64::from(offset == 0);
+        check_bucket_quota(&bucket, quota_size, new_objects)
+            .await
+            .map_err(ApiError::from)?;
+
+        let obj_info = store
+            .append_object(&bucket, &key, offset, &mut reader, &opts)
+            .await
+            .map_err(ApiError::from)?;
+        billing::record_bytes_in(&bucket, size);
+        record_bucket_usage(&bucket, quota_size, new_objects);
+        content_scan::submit(&bucket, &key, obj_info.version_id.map(|v| v.to_string()));
+        schedule_object_expiry(&obj_info);
+
+        let output = PutObjectOutput {
+            e_tag: etag_response(obj_info.etag.clone()),
+            version_id: obj_info.version_id_str(),
+            ..Default::default()
+        };
+
+        let event_args = rustfs_notify::event::EventArgs {
+            event_name: EventName::ObjectCreatedPut,
+            bucket_name: bucket,
+            version_id: obj_info.version_id_str().unwrap_or_default(),
+            object: obj_info,
+            req_params: rustfs_utils::extract_req_params_header(&req.headers),
+            resp_elements: rustfs_utils::extract_resp_elements(&S3Response::new(output.clone())),
+            host: rustfs_utils::get_request_host(&req.headers),
+            user_agent: rustfs_utils::get_request_user_agent(&req.headers),
+        };
+        tokio::spawn(async move {
+            rustfs_notify::global::notifier_instance().notify(event_args).await;
+        });
+
+        Ok(S3Response::new(output))
+    }
 }
 #[async_trait::async_trait]
 impl S3 for FS {
@@ -299,10 +481,15 @@ impl S3 for FS {
     async fn create_bucket(&self, req: S3Request<CreateBucketInput>) -> S3Result<S3Response<CreateBucketOutput>> {
         let CreateBucketInput {
             bucket,
+            acl,
+            create_bucket_configuration,
             object_lock_enabled_for_bucket,
             ..
         } = req.input;
 
+        let acl = acl.map(|acl| CannedAcl::parse(acl.as_str())).transpose()?;
+        check_location_constraint(create_bucket_configuration.as_ref())?;
+
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
@@ -311,13 +498,20 @@ impl S3 for FS {
             .make_bucket(
                 &bucket,
                 &MakeBucketOptions {
-                    force_create: true,
                     lock_enabled: object_lock_enabled_for_bucket.is_some_and(|v| v),
                     ..Default::default()
                 },
             )
             .await
-            .map_err(ApiError::from)?;
+            .map_err(|err| match err {
+                // Every bucket belongs to this deployment, and clients retrying a create take this as done
+                StorageError::BucketExists(_) => s3_error!(BucketAlreadyOwnedByYou),
+                err => ApiError::from(err).into(),
+            })?;
+
+        if let Some(acl) = acl {
+            acl::put_bucket_acl(&bucket, acl).await?;
+        }
 
         let output = CreateBucketOutput::default();
 
@@ -347,6 +541,10 @@ impl S3 for FS {
             copy_source,
             bucket,
             key,
+            copy_source_if_match,
+            copy_source_if_none_match,
+            copy_source_if_modified_since,
+            copy_source_if_unmodified_since,
             ..
         } = req.input.clone();
         let (src_bucket, src_key, version_id) = match copy_source {
@@ -362,18 +560,32 @@ impl S3 for FS {
 
         let mut src_opts = copy_src_opts(&src_bucket, &src_key, &req.headers).map_err(ApiError::from)?;
 
-        src_opts.version_id = version_id.clone();
+        let src_version = get_opts(&src_bucket, &src_key, version_id, None, &req.headers)
+            .await
+            .map_err(ApiError::from)?;
+        src_opts.version_id = src_version.version_id;
+        src_opts.versioned = src_version.versioned;
+        src_opts.version_suspended = src_version.version_suspended;
 
+        let src_customer_key = copy_source_customer_key(&req.headers)?;
         let mut get_opts = ObjectOptions {
             version_id: src_opts.version_id.clone(),
             versioned: src_opts.versioned,
             version_suspended: src_opts.version_suspended,
+            sse_customer_key: src_customer_key,
             ..Default::default()
         };
 
-        let dst_opts = copy_dst_opts(&bucket, &key, version_id, &req.headers, HashMap::new())
+        // the copy is a new version of the destination
+        let mut dst_opts = copy_dst_opts(&bucket, &key, None, &req.headers, HashMap::new())
             .await
             .map_err(ApiError::from)?;
+        let cancel = CancellationToken::new();
+        let _cancel_on_drop = cancel.clone().drop_guard();
+        get_opts.cancel = Some(cancel.clone());
+        get_opts.deadline = request_deadline();
+        dst_opts.cancel = Some(cancel);
+        dst_opts.deadline = get_opts.deadline;
 
         let cp_src_dst_same = path_join_buf(&[&src_bucket, &src_key]) == path_join_buf(&[&bucket, &key]);
 
@@ -393,31 +605,140 @@ impl S3 for FS {
             .map_err(ApiError::from)?;
 
         let mut src_info = gr.object_info.clone();
+        check_customer_key(&src_bucket, &src_key, &src_info.user_defined, src_customer_key.as_ref())?;
+        Preconditions {
+            if_match: copy_source_if_match,
+            if_none_match: copy_source_if_none_match,
+            if_modified_since: copy_source_if_modified_since,
+            if_unmodified_since: copy_source_if_unmodified_since,
+        }
+        .check_copy_source(&src_info)?;
+        if !content_scan::download_allowed(&src_info.user_tags) {
+            return Err(s3_error!(AccessDenied, "object has not passed content scanning"));
+        }
+
+        // The copy is encrypted per the destination request and bucket, not like its source
+        let encryption = resolve_object_encryption(&bucket, &req.headers).await?;
 
         if cp_src_dst_same {
-            src_info.metadata_only = true;
+            // Changing the encryption of an object in place rewrites its data
+            let src_sse_type = src_info
+                .user_defined
+                .get(&format!("{RESERVED_METADATA_PREFIX_LOWER}{SSE_TYPE_META}"));
+            src_info.metadata_only = encryption.as_ref().map(|e| &e.sse_type) == src_sse_type;
+        }
+
+        let replace_metadata = req
+            .input
+            .metadata_directive
+            .as_ref()
+            .is_some_and(|directive| directive.as_str() == MetadataDirective::REPLACE);
+        let replace_tags = req
+            .input
+            .tagging_directive
+            .as_ref()
+            .is_some_and(|directive| directive.as_str() == TaggingDirective::REPLACE);
+
+        // Like on S3, a copy onto itself has to change something about the object
+        if cp_src_dst_same
+            && src_info.metadata_only
+            && src_opts.version_id.is_none()
+            && !replace_metadata
+            && !replace_tags
+            && req.input.server_side_encryption.is_none()
+            && req.input.sse_customer_algorithm.is_none()
+            && req
+                .input
+                .storage_class
+                .as_ref()
+                .is_none_or(|sc| sc.as_str() == src_info.storage_class())
+        {
+            return Err(s3_error!(
+                InvalidRequest,
+                "This copy request is illegal because it is trying to copy an object to itself without changing the object's metadata, storage class, website redirect location or encryption attributes."
+            ));
+        }
+
+        let dst_customer_key = encryption.as_ref().and_then(|e| e.customer_key.as_ref());
+        let mut object_key = None;
+        if src_info.metadata_only {
+            if let Some(encryption) = encryption.as_ref().filter(|e| e.is_kms()) {
+                if is_encrypted(&src_info.user_defined) {
+                    rewrap_object_key(encryption, &bucket, &key, &mut src_info.user_defined).await?;
+                }
+            }
+            if let (Some(src_customer_key), Some(dst_customer_key)) = (&src_customer_key, dst_customer_key) {
+                reseal_customer_key(src_customer_key, dst_customer_key, &bucket, &key, &mut src_info.user_defined)?;
+            }
+        } else {
+            // The copy is written in one go, so only the checksums of the whole data still describe it
+            src_info
+                .user_defined
+                .remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}part-checksums"));
+            if src_info.checksums().is_some_and(|c| c.values().any(|v| v.contains('-'))) {
+                src_info
+                    .user_defined
+                    .remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}checksums"));
+            }
+            clear_encryption_metadata(&mut src_info.user_defined);
+            if let Some(encryption) = &encryption {
+                encryption
+                    .write_metadata(&mut src_info.user_defined)
+                    .map_err(ApiError::from)?;
+            }
+            object_key = new_object_key(encryption.as_ref(), &bucket, &key, &mut src_info.user_defined).await?;
         }
+        // The copy keeps the metadata of its source unless the request replaces it
+        if replace_metadata {
+            replace_user_metadata(&mut src_info.user_defined, extract_metadata(&req.headers));
+        } else if let Some(storage_class) = &req.input.storage_class {
+            src_info
+                .user_defined
+                .insert(AMZ_STORAGE_CLASS.to_owned(), storage_class.as_str().to_owned());
+        }
+
+        // A copy only expires when the request gives it a time to live of its own
+        object_ttl::clear_expires_at(&mut src_info.user_defined);
+        set_object_ttl(&req.headers, &mut src_info.user_defined)?;
 
-        let mut reader: Box<dyn Reader> = Box::new(WarpReader::new(gr.stream));
+        // Like on S3, a copy is private unless the request gives it an ACL
+        set_object_acl(&mut src_info.user_defined, req.input.acl.as_ref().map(ObjectCannedACL::as_str))?;
+        acl::check_acl_allowed(&bucket, object_acl(&src_info.user_defined)).await?;
 
         let actual_size = src_info.get_actual_size().map_err(ApiError::from)?;
 
+        let throttled = ThrottleReader::new(WarpReader::new(gr.stream), copy_limiter());
+        let progress = (!src_info.metadata_only).then(|| {
+            CopyProgressTracker::start(
+                CopyTarget {
+                    src_bucket: src_bucket.clone(),
+                    src_object: src_key.clone(),
+                    bucket: bucket.clone(),
+                    object: key.clone(),
+                    total_size: actual_size,
+                    headers: req.headers.clone(),
+                },
+                throttled.progress(),
+            )
+        });
+        let mut reader: Box<dyn Reader> = Box::new(throttled);
+
         let mut length = actual_size;
 
         let mut compress_metadata = HashMap::new();
 
-        if is_compressible(&req.headers, &key) && actual_size > MIN_COMPRESSIBLE_SIZE as i64 {
-            compress_metadata.insert(
-                format!("{RESERVED_METADATA_PREFIX_LOWER}compression"),
-                CompressionAlgorithm::default().to_string(),
-            );
+        if src_info.metadata_only {
+            // The stored data and the metadata describing it stay as they are
+        } else if is_compressible(&req.headers, &key) && actual_size > MIN_COMPRESSIBLE_SIZE as i64 {
+            let algorithm = compression_algorithm();
+            compress_metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}compression"), algorithm.to_string());
             compress_metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size",), actual_size.to_string());
 
             let hrd = EtagReader::new(reader, None);
 
             // let hrd = HashReader::new(reader, length, actual_size, None, false).map_err(ApiError::from)?;
 
-            reader = Box::new(CompressReader::new(hrd, CompressionAlgorithm::default()));
+            reader = Box::new(CompressReader::new(hrd, algorithm));
             length = -1;
         } else {
             src_info
@@ -431,34 +752,82 @@ impl S3 for FS {
                 .remove(&format!("{RESERVED_METADATA_PREFIX_LOWER}compression-size"));
         }
 
+        if let Some(object_key) = &object_key {
+            compress_metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size",), actual_size.to_string());
+            reader = encrypt_reader(reader, &mut length, actual_size, object_key, object_key.part_nonce(1))?;
+        }
+
         let hrd = HashReader::new(reader, length, actual_size, None, false).map_err(ApiError::from)?;
 
         src_info.put_object_reader = Some(PutObjReader::new(hrd));
 
-        // check quota
-        // TODO: src metadada
+        // Rewriting the metadata of an object in place adds nothing to its bucket
+        let quota_size = (!src_info.metadata_only).then(|| u64::try_from(actual_size).unwrap_or_default());
+        if let Some(size) = quota_size {
+            check_bucket_quota(&bucket, size, 1).await.map_err(ApiError::from)?;
+        }
 
         for (k, v) in compress_metadata {
             src_info.user_defined.insert(k, v);
         }
 
-        // TODO: src tags
+        // Like encryption, the lock of the copy comes from the destination request and bucket
+        clear_object_lock_metadata(&mut src_info.user_defined);
+        src_info
+            .user_defined
+            .extend(resolve_object_lock(&bucket, &req.headers).await?);
+        let dst_version = ObjectOptions {
+            versioned: dst_opts.versioned,
+            version_suspended: dst_opts.version_suspended,
+            ..Default::default()
+        };
+        check_version_unprotected(&bucket, &key, &dst_version, false).await?;
+
+        // The copy keeps the source tags unless the request replaces them
+        let tags = if replace_tags {
+            let tags = req.input.tagging.clone().unwrap_or_default();
+            check_header_tags(&tags)?;
+            // Only the content scanner sets the scan status
+            content_scan::with_scan_status(&tags, None)
+        } else {
+            src_info.user_tags.clone()
+        };
+        if tags.is_empty() {
+            src_info.user_defined.remove(AMZ_OBJECT_TAGGING);
+        } else {
+            src_info.user_defined.insert(AMZ_OBJECT_TAGGING.to_owned(), tags);
+        }
+        content_scan::mark_pending(&mut src_info.user_defined);
 
         let oi = store
             .copy_object(&src_bucket, &src_key, &bucket, &key, &mut src_info, &src_opts, &dst_opts)
             .await
             .map_err(ApiError::from)?;
+        drop(progress);
+        if let Some(size) = quota_size {
+            record_bucket_usage(&bucket, size, 1);
+        }
+        content_scan::submit(&bucket, &key, oi.version_id.map(|v| v.to_string()));
+        schedule_object_expiry(&oi);
 
         // warn!("copy_object oi {:?}", &oi);
         let object_info = oi.clone();
         let copy_object_result = CopyObjectResult {
-            e_tag: oi.etag,
+            e_tag: etag_response(oi.etag),
             last_modified: oi.mod_time.map(Timestamp::from),
             ..Default::default()
         };
 
+        let (server_side_encryption, ssekms_key_id) = encryption_response(&src_info.user_defined);
+        let (sse_customer_algorithm, sse_customer_key_md5) = customer_key_response(dst_customer_key);
         let output = CopyObjectOutput {
             copy_object_result: Some(copy_object_result),
+            copy_source_version_id: src_info.version_id_str(),
+            version_id: object_info.version_id_str(),
+            server_side_encryption,
+            ssekms_key_id,
+            sse_customer_algorithm,
+            sse_customer_key_md5,
             ..Default::default()
         };
 
@@ -486,165 +855,122 @@ impl S3 for FS {
         Ok(S3Response::new(output))
     }
 
-    async fn restore_object(&self, _req: S3Request<RestoreObjectInput>) -> S3Result<S3Response<RestoreObjectOutput>> {
-        Err(s3_error!(NotImplemented, "RestoreObject is not implemented yet"))
-        /*
-        let bucket = params.bucket;
-        if let Err(e) = un_escape_path(params.object) {
-            warn!("post restore object failed, e: {:?}", e);
-            return Err(S3Error::with_message(S3ErrorCode::Custom("PostRestoreObjectFailed".into()), "post restore object failed"));
-        }
+    /// Rehydrate a transitioned object from its tier for a number of days
+    #[tracing::instrument(level = "debug", skip(self, req))]
+    async fn restore_object(&self, req: S3Request<RestoreObjectInput>) -> S3Result<S3Response<RestoreObjectOutput>> {
+        let RestoreObjectInput {
+            bucket,
+            key,
+            restore_request,
+            version_id,
+            ..
+        } = req.input.clone();
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        if Err(err) = check_request_auth_type(req, policy::RestoreObjectAction, bucket, object) {
-            return Err(S3Error::with_message(S3ErrorCode::Custom("PostRestoreObjectFailed".into()), "post restore object failed"));
+        let Some(restore_request) = restore_request else {
+            return Err(s3_error!(MalformedXML, "missing RestoreRequest"));
+        };
+        if restore_request
+            .type_
+            .as_ref()
+            .is_some_and(|t| t.as_str() == RestoreRequestType::SELECT)
+        {
+            return Err(s3_error!(NotImplemented, "SELECT restore requests are not supported"));
         }
-
-        if req.content_length <= 0 {
-            return Err(S3Error::with_message(S3ErrorCode::Custom("ErrEmptyRequestBody".into()), "post restore object failed"));
+        let days = restore_request.days.unwrap_or_default();
+        if days <= 0 {
+            return Err(s3_error!(InvalidArgument, "Days must be a positive number"));
         }
-        let Some(opts) = post_restore_opts(req, bucket, object) else {
-            return Err(S3Error::with_message(S3ErrorCode::Custom("ErrEmptyRequestBody".into()), "post restore object failed"));
-        };
 
-        let Some(obj_info) = store.get_object_info(bucket, object, opts) else {
-            return Err(S3Error::with_message(S3ErrorCode::Custom("ErrEmptyRequestBody".into()), "post restore object failed"));
-        };
+        let opts: ObjectOptions = get_opts(&bucket, &key, version_id, None, &req.headers)
+            .await
+            .map_err(ApiError::from)?;
+        let obj_info = store.get_object_info(&bucket, &key, &opts).await.map_err(ApiError::from)?;
 
-        if obj_info.transitioned_object.status != lifecycle::TRANSITION_COMPLETE {
-            return Err(S3Error::with_message(S3ErrorCode::Custom("ErrEmptyRequestBody".into()), "post restore object failed"));
+        if obj_info.transitioned_object.status != TRANSITION_COMPLETE {
+            return Err(s3_error!(InvalidObjectState, "object is not in a remote tier"));
         }
-
-        let mut api_err;
-        let Some(rreq) = parse_restore_request(req.body(), req.content_length) else {
-            let api_err = errorCodes.ToAPIErr(ErrMalformedXML);
-            api_err.description = err.Error()
-            return Err(S3Error::with_message(S3ErrorCode::Custom("ErrEmptyRequestBody".into()), "post restore object failed"));
-        };
-        let mut status_code = http::StatusCode::OK;
-        let mut already_restored = false;
-        if Err(err) = rreq.validate(store) {
-            api_err = errorCodes.ToAPIErr(ErrMalformedXML)
-            api_err.description = err.Error()
-            return Err(S3Error::with_message(S3ErrorCode::Custom("ErrEmptyRequestBody".into()), "post restore object failed"));
-        } else {
-            if obj_info.restore_ongoing && rreq.Type != "SELECT" {
-                return Err(S3Error::with_message(S3ErrorCode::Custom("ErrObjectRestoreAlreadyInProgress".into()), "post restore object failed"));
-            }
-            if !obj_info.restore_ongoing && !obj_info.restore_expires.unix_timestamp() == 0 {
-                status_code = http::StatusCode::Accepted;
-                already_restored = true;
-            }
+        if obj_info.restore_ongoing {
+            return Err(s3_error!(RestoreAlreadyInProgress));
         }
-        let restore_expiry = lifecycle::expected_expiry_time(OffsetDateTime::now_utc(), rreq.days);
-        let mut metadata = clone_mss(obj_info.user_defined);
 
-        if rreq.type != "SELECT" {
-            obj_info.metadataOnly = true;
-            metadata[xhttp.AmzRestoreExpiryDays] = rreq.days;
-            metadata[xhttp.AmzRestoreRequestDate] = OffsetDateTime::now_utc().format(http::TimeFormat);
+        let now = OffsetDateTime::now_utc();
+        let restore_expiry = lifecycle::expected_expiry_time(now, days);
+        let already_restored = obj_info.restore_expires.is_some_and(|expiry| now < expiry);
+
+        let mut eval_metadata = HashMap::new();
+        eval_metadata.insert(
+            X_AMZ_RESTORE.as_str().to_string(),
             if already_restored {
-                metadata[AmzRestore] = completed_restore_obj(restore_expiry).String()
+                completed_restore_obj(restore_expiry).to_string()
             } else {
-                metadata[AmzRestore] = ongoing_restore_obj().to_string()
-            }
-            obj_info.user_defined = metadata;
-            if let Err(err) = store.copy_object(bucket, object, bucket, object, obj_info, ObjectOptions {
-                version_id: obj_info.version_id,
-            }, ObjectOptions {
-                version_id: obj_info.version_id,
-                m_time:     obj_info.mod_time,
-            }) {
-                return Err(S3Error::with_message(S3ErrorCode::Custom("ErrInvalidObjectState".into()), "post restore object failed"));
-            }
-            if already_restored {
-                return Ok(());
-            }
-        }
+                ongoing_restore_obj().to_string()
+            },
+        );
+        eval_metadata.insert(AMZ_RESTORE_EXPIRY_DAYS.to_string(), days.to_string());
+        eval_metadata.insert(AMZ_RESTORE_REQUEST_DATE.to_string(), now.format(&Rfc3339).unwrap_or_default());
+
+        let popts = ObjectOptions {
+            version_id: opts.version_id.clone(),
+            versioned: opts.versioned,
+            version_suspended: opts.version_suspended,
+            eval_metadata: Some(eval_metadata),
+            ..Default::default()
+        };
+        store
+            .put_object_metadata(&bucket, &key, &popts)
+            .await
+            .map_err(ApiError::from)?;
 
-        let restore_object = must_get_uuid();
-        if rreq.output_location.s3.bucket_name != "" {
-            w.Header()[AmzRestoreOutputPath] = []string{pathJoin(rreq.OutputLocation.S3.BucketName, rreq.OutputLocation.S3.Prefix, restore_object)}
+        // A restored copy only has its lifetime extended
+        if already_restored {
+            return Ok(S3Response::new(RestoreObjectOutput::default()));
         }
-        w.WriteHeader(status_code)
-        send_event(EventArgs {
-            event_name:  event::ObjectRestorePost,
-            bucket_name: bucket,
-            object:      obj_info,
-            req_params:  extract_req_params(r),
-            user_agent:  req.user_agent(),
-            host:        handlers::get_source_ip(r),
-        });
+
+        let output = RestoreObjectOutput::default();
+        let event_args = rustfs_notify::event::EventArgs {
+            event_name: EventName::ObjectRestorePost,
+            bucket_name: bucket.clone(),
+            object: obj_info.clone(),
+            req_params: rustfs_utils::extract_req_params_header(&req.headers),
+            resp_elements: rustfs_utils::extract_resp_elements(&S3Response::new(output.clone())),
+            version_id: obj_info.version_id_str().unwrap_or_default(),
+            host: rustfs_utils::get_request_host(&req.headers),
+            user_agent: rustfs_utils::get_request_user_agent(&req.headers),
+        };
+        let mut completed_args = event_args.clone();
+        completed_args.event_name = EventName::ObjectRestoreCompleted;
         tokio::spawn(async move {
-            if !rreq.SelectParameters.IsEmpty() {
-                let actual_size = obj_info.get_actual_size();
-                if actual_size.is_err() {
-                    return Err(S3Error::with_message(S3ErrorCode::Custom("ErrInvalidObjectState".into()), "post restore object failed"));
-                }
+            rustfs_notify::global::notifier_instance().notify(event_args).await;
+        });
 
-                let object_rsc = s3select.NewObjectReadSeekCloser(
-                    |offset int64| -> (io.ReadCloser, error) {
-                        rs := &HTTPRangeSpec{
-                          IsSuffixLength: false,
-                          Start:          offset,
-                          End:            -1,
-                        }
-                        return getTransitionedObjectReader(bucket, object, rs, r.Header,
-                          obj_info, ObjectOptions {version_id: obj_info.version_id});
-                    },
-                    actual_size.unwrap(),
-                );
-                if err = rreq.SelectParameters.Open(objectRSC); err != nil {
-                    if serr, ok := err.(s3select.SelectError); ok {
-                        let encoded_error_response = encodeResponse(APIErrorResponse {
-                            code:       serr.ErrorCode(),
-                            message:    serr.ErrorMessage(),
-                            bucket_name: bucket,
-                            key:        object,
-                            resource:   r.URL.Path,
-                            request_id:  w.Header().Get(xhttp.AmzRequestID),
-                            host_id:     globalDeploymentID(),
-                        });
-                        //writeResponse(w, serr.HTTPStatusCode(), encodedErrorResponse, mimeXML)
-                        Ok(S3Response::with_headers((StatusCode::OK, Body::empty()), header));
-                    } else {
-                        return Err(S3Error::with_message(S3ErrorCode::Custom("ErrInvalidObjectState".into()), "post restore object failed"));
-                    }
-                    return Ok(());
-                }
-                let nr = httptest.NewRecorder();
-                let rw = xhttp.NewResponseRecorder(nr);
-                rw.log_err_body = true;
-                rw.log_all_body = true;
-                rreq.select_parameters.evaluate(rw);
-                rreq.select_parameters.Close();
-                return Ok(S3Response::with_headers((StatusCode::OK, Body::empty()), header));
-            }
-            let opts = ObjectOptions {
-                transition: TransitionOptions {
-                    restore_request: rreq,
-                    restore_expiry:  restore_expiry,
+        let ropts = ObjectOptions {
+            transition: TransitionOptions {
+                restore_request: RestoreObjectRequest {
+                    days: days as i64,
+                    tier: restore_request.tier.map(|t| t.as_str().to_string()).unwrap_or_default(),
+                    description: restore_request.description.unwrap_or_default(),
+                    ..Default::default()
                 },
-                version_id: objInfo.version_id,
-            }
-            if Err(err) = store.restore_transitioned_object(bucket, object, opts) {
-                format!(format!("unable to restore transitioned bucket/object {}/{}: {}", bucket, object, err.to_string()));
-                return Ok(S3Response::with_headers((StatusCode::OK, Body::empty()), header));
+                restore_expiry,
+                ..Default::default()
+            },
+            ..opts
+        };
+        tokio::spawn(async move {
+            if let Err(err) = store.restore_transitioned_object(&bucket, &key, &ropts).await {
+                error!("unable to restore transitioned object {}/{}: {}", bucket, key, err);
+                return;
             }
-
-            send_event(EventArgs {
-                EventName:  event.ObjectRestoreCompleted,
-                BucketName: bucket,
-                Object:     objInfo,
-                ReqParams:  extractReqParams(r),
-                UserAgent:  r.UserAgent(),
-                Host:       handlers.GetSourceIP(r),
-            });
+            rustfs_notify::global::notifier_instance().notify(completed_args).await;
         });
-        */
+
+        let mut resp = S3Response::new(output);
+        resp.status = Some(http::StatusCode::ACCEPTED);
+        Ok(resp)
     }
 
     /// Delete a bucket
@@ -667,9 +993,10 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        let bucket = input.bucket;
         let event_args = rustfs_notify::event::EventArgs {
             event_name: EventName::BucketRemoved,
-            bucket_name: input.bucket,
+            bucket_name: bucket.clone(),
             object: rustfs_ecstore::store_api::ObjectInfo { ..Default::default() },
             req_params: rustfs_utils::extract_req_params_header(&req.headers),
             resp_elements: rustfs_utils::extract_resp_elements(&S3Response::new(DeleteBucketOutput {})),
@@ -681,6 +1008,10 @@ impl S3 for FS {
         // Asynchronous call will not block the response of the current request
         tokio::spawn(async move {
             rustfs_notify::global::notifier_instance().notify(event_args).await;
+            // The rules of the bucket go once its removal has been notified
+            if let Some(ns) = rustfs_notify::global::notification_system() {
+                ns.remove_bucket_notification_config(&bucket).await;
+            }
         });
 
         Ok(S3Response::new(DeleteBucketOutput {}))
@@ -690,14 +1021,23 @@ impl S3 for FS {
     #[tracing::instrument(level = "debug", skip(self, req))]
     async fn delete_object(&self, req: S3Request<DeleteObjectInput>) -> S3Result<S3Response<DeleteObjectOutput>> {
         let DeleteObjectInput {
-            bucket, key, version_id, ..
+            bucket,
+            key,
+            version_id,
+            bypass_governance_retention,
+            ..
         } = req.input.clone();
 
         let metadata = extract_metadata(&req.headers);
 
-        let opts: ObjectOptions = del_opts(&bucket, &key, version_id, &req.headers, metadata)
+        let mut opts: ObjectOptions = del_opts(&bucket, &key, version_id, &req.headers, metadata)
             .await
             .map_err(ApiError::from)?;
+        let cancel = CancellationToken::new();
+        let _cancel_on_drop = cancel.clone().drop_guard();
+        opts.cancel = Some(cancel);
+        opts.deadline = request_deadline();
+        check_version_unprotected(&bucket, &key, &opts, bypass_governance_retention.unwrap_or_default()).await?;
 
         let version_id = opts.version_id.as_ref().map(|v| Uuid::parse_str(v).ok()).unwrap_or_default();
         let dobj = ObjectToDelete {
@@ -705,12 +1045,20 @@ impl S3 for FS {
             version_id,
         };
 
-        let objects: Vec<ObjectToDelete> = vec![dobj];
-
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
-        let (dobjs, _errs) = store.delete_objects(&bucket, objects, opts).await.map_err(ApiError::from)?;
+        let dsc = delete_replication_decision(&store, &bucket, &dobj, &opts).await;
+
+        let objects: Vec<ObjectToDelete> = vec![dobj];
+        let (dobjs, errs) = store.delete_objects(&bucket, objects, opts).await.map_err(ApiError::from)?;
+
+        if dsc.replicate_any() {
+            for (dobj, _) in dobjs.iter().zip(errs.iter()).filter(|(_, err)| err.is_none()) {
+                let dv = DeletedObjectReplicationInfo::new(&bucket, dobj);
+                schedule_replication_delete(dv, store.clone(), dsc.clone()).await;
+            }
+        }
 
         // TODO: let errors;
 
@@ -720,7 +1068,12 @@ impl S3 for FS {
                 .map(|v| {
                     let delete_marker = { if v.delete_marker { Some(true) } else { None } };
 
-                    let version_id = v.version_id.clone();
+                    // a new delete marker is the version the delete created
+                    let version_id = if v.delete_marker {
+                        s3_version_id(v.delete_marker_version_id.clone())
+                    } else {
+                        s3_version_id(v.version_id.clone())
+                    };
 
                     (delete_marker, version_id)
                 })
@@ -732,6 +1085,11 @@ impl S3 for FS {
             }
         };
         let del_version_id = version_id.as_ref().map(|v| v.to_string()).unwrap_or_default();
+        let event_name = if delete_marker.unwrap_or_default() {
+            EventName::ObjectRemovedDeleteMarkerCreated
+        } else {
+            EventName::ObjectRemovedDelete
+        };
         let output = DeleteObjectOutput {
             delete_marker,
             version_id,
@@ -739,7 +1097,7 @@ impl S3 for FS {
         };
 
         let event_args = rustfs_notify::event::EventArgs {
-            event_name: EventName::ObjectRemovedDelete,
+            event_name,
             bucket_name: bucket.clone(),
             object: rustfs_ecstore::store_api::ObjectInfo {
                 name: key,
@@ -763,22 +1121,15 @@ impl S3 for FS {
 
     /// Delete multiple objects
     #[tracing::instrument(level = "debug", skip(self, req))]
-    async fn delete_objects(&self, req: S3Request<DeleteObjectsInput>) -> S3Result<S3Response<DeleteObjectsOutput>> {
+    async fn delete_objects(&self, mut req: S3Request<DeleteObjectsInput>) -> S3Result<S3Response<DeleteObjectsOutput>> {
         // info!("delete_objects args {:?}", req.input);
 
-        let DeleteObjectsInput { bucket, delete, .. } = req.input;
-
-        let objects: Vec<ObjectToDelete> = delete
-            .objects
-            .iter()
-            .map(|v| {
-                let version_id = v.version_id.as_ref().map(|v| Uuid::parse_str(v).ok()).unwrap_or_default();
-                ObjectToDelete {
-                    object_name: v.key.clone(),
-                    version_id,
-                }
-            })
-            .collect();
+        let DeleteObjectsInput {
+            bucket,
+            delete,
+            bypass_governance_retention,
+            ..
+        } = req.input.clone();
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
@@ -790,31 +1141,132 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        let (dobjs, errs) = store.delete_objects(&bucket, objects, opts).await.map_err(ApiError::from)?;
+        if delete.objects.is_empty() || delete.objects.len() > MAX_DELETE_OBJECTS {
+            return Err(s3_error!(
+                MalformedXML,
+                "A delete request must list between 1 and {} keys",
+                MAX_DELETE_OBJECTS
+            ));
+        }
 
-        let deleted = dobjs
+        // WORM protected versions are reported as errors, the others are still deleted
+        let locked = bucket_object_lock(&bucket).await.is_some();
+        let bypass_governance = bypass_governance_retention.unwrap_or_default();
+        // Collected first, a stream holding the closure is not Send
+        let checks: Vec<_> = delete
+            .objects
             .iter()
-            .map(|v| DeletedObject {
-                delete_marker: { if v.delete_marker { Some(true) } else { None } },
-                delete_marker_version_id: v.delete_marker_version_id.clone(),
-                key: Some(v.object_name.clone()),
-                version_id: v.version_id.clone(),
+            .map(|v| {
+                let (req, store, bucket, opts) = (&req, &store, &bucket, &opts);
+                async move {
+                    // Each key takes the permission a DeleteObject request for it would
+                    let action = if v.version_id.is_some() {
+                        S3Action::DeleteObjectVersionAction
+                    } else {
+                        S3Action::DeleteObjectAction
+                    };
+                    if let Err(err) = authorize_key(req, &v.key, v.version_id.as_deref(), Action::S3Action(action)).await {
+                        return Err(delete_error(&v.key, v.version_id.clone(), &err));
+                    }
+                    let version_id = match v.version_id.as_deref() {
+                        None => None,
+                        Some(vid) => match parse_version_id(vid) {
+                            Some(id) => Some(id),
+                            None => {
+                                return Err(Error {
+                                    code: Some(S3ErrorCode::NoSuchVersion.as_str().to_string()),
+                                    key: Some(v.key.clone()),
+                                    message: Some("The specified version does not exist.".to_string()),
+                                    version_id: v.version_id.clone(),
+                                });
+                            }
+                        },
+                    };
+                    if locked {
+                        let version_opts = ObjectOptions {
+                            version_id: version_id.map(|v| v.to_string()),
+                            versioned: opts.versioned,
+                            version_suspended: opts.version_suspended,
+                            ..Default::default()
+                        };
+                        if let Err(err) = check_version_unprotected(bucket, &v.key, &version_opts, bypass_governance).await {
+                            return Err(delete_error(&v.key, v.version_id.clone(), &err));
+                        }
+                    }
+                    let dobj = ObjectToDelete {
+                        object_name: v.key.clone(),
+                        version_id,
+                    };
+                    let dsc = delete_replication_decision(store, bucket, &dobj, opts).await;
+                    Ok((dobj, dsc))
+                }
             })
             .collect();
+        // In order, the response lists the keys as the request did
+        let checks: Vec<_> = futures::stream::iter(checks)
+            .buffered(DELETE_OBJECTS_CHECK_CONCURRENCY)
+            .collect()
+            .await;
+
+        // Billed once the caller may delete at least one of the keys
+        let access_denied = Some(S3ErrorCode::AccessDenied.as_str());
+        if checks
+            .iter()
+            .any(|check| check.as_ref().err().is_none_or(|err| err.code.as_deref() != access_denied))
+        {
+            bill_request(req.extensions.get_mut::<ReqInfo>().expect("ReqInfo not found"));
+        }
 
-        // TODO: let errors;
-        for err in errs.iter().flatten() {
-            warn!("delete_objects err  {:?}", err);
+        let mut errors = Vec::new();
+        let mut objects: Vec<ObjectToDelete> = Vec::with_capacity(checks.len());
+        let mut replicate_decisions = Vec::with_capacity(checks.len());
+        for check in checks {
+            match check {
+                Ok((dobj, dsc)) => {
+                    objects.push(dobj);
+                    replicate_decisions.push(dsc);
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+
+        let (dobjs, errs) = store.delete_objects(&bucket, objects, opts).await.map_err(ApiError::from)?;
+
+        let mut deleted = Vec::with_capacity(dobjs.len());
+        let mut removed = Vec::with_capacity(dobjs.len());
+        for ((dobj, err), dsc) in dobjs.into_iter().zip(errs).zip(replicate_decisions) {
+            if let Some(err) = err {
+                warn!("delete_objects {} err {:?}", dobj.object_name, err);
+                let version_id = dobj.version_id.map(|v| v.to_string());
+                errors.push(delete_error(&dobj.object_name, version_id, &ApiError::from(err).into()));
+                continue;
+            }
+
+            if dsc.replicate_any() {
+                let dv = DeletedObjectReplicationInfo::new(&bucket, &dobj);
+                schedule_replication_delete(dv, store.clone(), dsc).await;
+            }
+
+            // Quiet mode only reports the keys that could not be deleted
+            if !delete.quiet.unwrap_or_default() {
+                deleted.push(DeletedObject {
+                    delete_marker: { if dobj.delete_marker { Some(true) } else { None } },
+                    delete_marker_version_id: s3_version_id(dobj.delete_marker_version_id.clone()),
+                    key: Some(dobj.object_name.clone()),
+                    version_id: s3_version_id(dobj.version_id.clone()),
+                });
+            }
+            removed.push(dobj);
         }
 
         let output = DeleteObjectsOutput {
-            deleted: Some(deleted),
-            // errors,
+            deleted: (!deleted.is_empty()).then_some(deleted),
+            errors: (!errors.is_empty()).then_some(errors),
             ..Default::default()
         };
         // Asynchronous call will not block the response of the current request
         tokio::spawn(async move {
-            for dobj in dobjs {
+            for dobj in removed {
                 let version_id = match dobj.version_id {
                     None => String::new(),
                     Some(v) => v.to_string(),
@@ -887,6 +1339,11 @@ impl S3 for FS {
             version_id,
             part_number,
             range,
+            checksum_mode,
+            if_match,
+            if_none_match,
+            if_modified_since,
+            if_unmodified_since,
             ..
         } = req.input.clone();
 
@@ -904,37 +1361,55 @@ impl S3 for FS {
             }
         }
 
-        let rs = range.map(|v| match v {
-            Range::Int { first, last } => HTTPRangeSpec {
-                is_suffix_length: false,
-                start: first as i64,
-                end: if let Some(last) = last { last as i64 } else { -1 },
-            },
-            Range::Suffix { length } => HTTPRangeSpec {
-                is_suffix_length: true,
-                start: length as i64,
-                end: -1,
-            },
-        });
+        let rs = range.as_ref().map(range_spec);
+        // Several ranges are taken off the request by the ByteRangesLayer, s3s only parses one
+        let byte_ranges = req.extensions.get::<ByteRanges>().map(|ranges| ranges.0.clone());
 
-        if rs.is_some() && part_number.is_some() {
+        if (rs.is_some() || byte_ranges.is_some()) && part_number.is_some() {
             return Err(s3_error!(InvalidArgument, "range and part_number invalid"));
         }
 
-        let opts: ObjectOptions = get_opts(&bucket, &key, version_id, part_number, &req.headers)
+        let mut opts: ObjectOptions = get_opts(&bucket, &key, version_id, part_number, &req.headers)
             .await
             .map_err(ApiError::from)?;
+        opts.sse_customer_key = customer_key(&req.headers)?;
+        // Only lock waits watch the token, the body streamed after the handler returns is not cut off
+        let cancel = CancellationToken::new();
+        let _cancel_on_drop = cancel.clone().drop_guard();
+        opts.cancel = Some(cancel);
+        opts.deadline = request_deadline();
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        let reader = store
-            .get_object_reader(bucket.as_str(), key.as_str(), rs.clone(), h, &opts)
-            .await
-            .map_err(ApiError::from)?;
+        // The ranges of a multi-range read are only known to be satisfiable once the object size is
+        let (info, stream) = if byte_ranges.is_some() {
+            let info = store.get_object_info(&bucket, &key, &opts).await.map_err(ApiError::from)?;
+            (info, None)
+        } else {
+            let reader = store
+                .get_object_reader(bucket.as_str(), key.as_str(), rs.clone(), h, &opts)
+                .await
+                .map_err(|err| match err {
+                    StorageError::InvalidRange(size) => range_not_satisfiable(size),
+                    err => ApiError::from(err).into(),
+                })?;
+            (reader.object_info, Some(reader.stream))
+        };
+        global_heat_map().record(&bucket, &key);
 
-        let info = reader.object_info;
+        Preconditions {
+            if_match,
+            if_none_match,
+            if_modified_since,
+            if_unmodified_since,
+        }
+        .check_read(&info)?;
+        check_customer_key(&bucket, &key, &info.user_defined, opts.sse_customer_key.as_ref())?;
+        if !content_scan::download_allowed(&info.user_tags) {
+            return Err(s3_error!(AccessDenied, "object has not passed content scanning"));
+        }
         let event_info = info.clone();
         let content_type = {
             if let Some(content_type) = &info.content_type {
@@ -952,6 +1427,13 @@ impl S3 for FS {
         };
         let last_modified = info.mod_time.map(Timestamp::from);
 
+        // A range has no checksum of its own, only the object and its parts do
+        let checksum = if rs.is_none() && byte_ranges.is_none() {
+            object_checksum(&info, checksum_mode.as_ref(), part_number)
+        } else {
+            Checksum::default()
+        };
+
         let mut rs = rs;
 
         if let Some(part_number) = part_number {
@@ -961,21 +1443,54 @@ impl S3 for FS {
         }
 
         let mut content_length = info.size as i64;
-
-        let content_range = if let Some(rs) = rs {
-            let total_size = info.get_actual_size().map_err(ApiError::from)?;
-            let (start, length) = rs.get_offset_length(total_size as i64).map_err(ApiError::from)?;
-            content_length = length;
-            Some(format!("bytes {}-{}/{}", start, start as i64 + length - 1, total_size))
+        let mut content_type = content_type;
+
+        let (content_range, body) = if let Some(stream) = stream {
+            let content_range = if let Some(rs) = rs {
+                let total_size = info.get_actual_size().map_err(ApiError::from)?;
+                let (start, length) = rs
+                    .get_offset_length(total_size)
+                    .map_err(|_| range_not_satisfiable(total_size))?;
+                content_length = length;
+                Some(format!("bytes {}-{}/{}", start, start as i64 + length - 1, total_size))
+            } else {
+                None
+            };
+            let body = StreamingBlob::wrap(BilledBody::new(
+                bytes_stream(ReaderStream::with_capacity(stream, DEFAULT_READ_BUFFER_SIZE), content_length as usize),
+                &bucket,
+            ));
+            (content_range, body)
         } else {
-            None
-        };
-
-        let body = Some(StreamingBlob::wrap(bytes_stream(
-            ReaderStream::with_capacity(reader.stream, DEFAULT_READ_BUFFER_SIZE),
-            content_length as usize,
-        )));
+            // Ranges outside the object are left out, the request only fails when none is left
+            let total_size = info.get_actual_size().map_err(ApiError::from)?;
+            let ranges: Vec<_> = byte_ranges
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|range| range_spec(range).get_offset_length(total_size).ok())
+                .collect();
+            if ranges.is_empty() {
+                return Err(range_not_satisfiable(total_size));
+            }
 
+            let part_type = info.content_type.as_deref().unwrap_or("application/octet-stream");
+            let multipart = MultipartRanges::new(&ranges, total_size, part_type);
+            content_length = multipart.content_length();
+            content_type = ContentType::from_str(&multipart.content_type()).ok();
+            let stream = multipart.into_stream(store.clone(), bucket.clone(), key.clone(), opts.clone());
+            (
+                None,
+                StreamingBlob::wrap(BilledBody::new(bytes_stream(stream, content_length as usize), &bucket)),
+            )
+        };
+        let body = Some(body);
+
+        let (server_side_encryption, ssekms_key_id) = encryption_response(&info.user_defined);
+        let (sse_customer_algorithm, sse_customer_key_md5) = customer_key_response(opts.sse_customer_key.as_ref());
+        let replication_status = replication_status_response(&info.replication_status);
+        let storage_class = storage_class_response(&info);
+        let restore = restore_response(&info);
+        let expiration = expiration_response(&info);
         let output = GetObjectOutput {
             body,
             content_length: Some(content_length),
@@ -983,7 +1498,22 @@ impl S3 for FS {
             content_type,
             accept_ranges: Some("bytes".to_string()),
             content_range,
-            e_tag: info.etag,
+            version_id: info.version_id_str(),
+            e_tag: etag_response(info.etag),
+            checksum_crc32: checksum.checksum_crc32,
+            checksum_crc32c: checksum.checksum_crc32c,
+            checksum_crc64nvme: checksum.checksum_crc64nvme,
+            checksum_sha1: checksum.checksum_sha1,
+            checksum_sha256: checksum.checksum_sha256,
+            checksum_type: checksum.checksum_type,
+            server_side_encryption,
+            ssekms_key_id,
+            sse_customer_algorithm,
+            sse_customer_key_md5,
+            replication_status,
+            storage_class,
+            restore,
+            expiration,
             ..Default::default()
         };
 
@@ -1036,6 +1566,11 @@ impl S3 for FS {
             version_id,
             part_number,
             range,
+            checksum_mode,
+            if_match,
+            if_none_match,
+            if_modified_since,
+            if_unmodified_since,
             ..
         } = req.input.clone();
 
@@ -1047,18 +1582,7 @@ impl S3 for FS {
             }
         }
 
-        let rs = range.map(|v| match v {
-            Range::Int { first, last } => HTTPRangeSpec {
-                is_suffix_length: false,
-                start: first as i64,
-                end: if let Some(last) = last { last as i64 } else { -1 },
-            },
-            Range::Suffix { length } => HTTPRangeSpec {
-                is_suffix_length: true,
-                start: length as i64,
-                end: -1,
-            },
-        });
+        let rs = range.as_ref().map(range_spec);
 
         if rs.is_some() && part_number.is_some() {
             return Err(s3_error!(InvalidArgument, "range and part_number invalid"));
@@ -1073,6 +1597,16 @@ impl S3 for FS {
         };
 
         let info = store.get_object_info(&bucket, &key, &opts).await.map_err(ApiError::from)?;
+        let customer_key = customer_key(&req.headers)?;
+        check_customer_key(&bucket, &key, &info.user_defined, customer_key.as_ref())?;
+        Preconditions {
+            if_match,
+            if_none_match,
+            if_modified_since,
+            if_unmodified_since,
+        }
+        .check_read(&info)?;
+        global_heat_map().record(&bucket, &key);
 
         // warn!("head_object info {:?}", &info);
         let event_info = info.clone();
@@ -1096,16 +1630,53 @@ impl S3 for FS {
 
         let content_length = info.get_actual_size().map_err(ApiError::from)?;
 
-        let metadata = info.user_defined;
+        // A range has no checksum of its own, only the object and its parts do
+        let checksum = if rs.is_none() {
+            object_checksum(&info, checksum_mode.as_ref(), part_number)
+        } else {
+            Checksum::default()
+        };
+        let version_id = info.version_id_str();
+        let (server_side_encryption, ssekms_key_id) = encryption_response(&info.user_defined);
+        let (sse_customer_algorithm, sse_customer_key_md5) = customer_key_response(customer_key.as_ref());
+        let replication_status = replication_status_response(&info.replication_status);
+        let storage_class = storage_class_response(&info);
+        let restore = restore_response(&info);
+        let expiration = expiration_response(&info);
+        let mut metadata = info.user_defined;
+        // Internal metadata, e.g. the sealed data key of encrypted objects, stays on the server
+        metadata.retain(|k, _| !k.starts_with(RESERVED_METADATA_PREFIX_LOWER));
+        // The storage class and the restore status have headers of their own
+        for k in [
+            AMZ_STORAGE_CLASS,
+            X_AMZ_RESTORE.as_str(),
+            AMZ_RESTORE_EXPIRY_DAYS,
+            AMZ_RESTORE_REQUEST_DATE,
+        ] {
+            metadata.remove(k);
+        }
 
         let output = HeadObjectOutput {
             content_length: Some(content_length),
             content_type,
             last_modified,
-            e_tag: info.etag,
+            e_tag: etag_response(info.etag),
+            checksum_crc32: checksum.checksum_crc32,
+            checksum_crc32c: checksum.checksum_crc32c,
+            checksum_crc64nvme: checksum.checksum_crc64nvme,
+            checksum_sha1: checksum.checksum_sha1,
+            checksum_sha256: checksum.checksum_sha256,
+            checksum_type: checksum.checksum_type,
             metadata: Some(metadata),
-            version_id: info.version_id.map(|v| v.to_string()),
-            // metadata: object_metadata,
+            version_id,
+            server_side_encryption,
+            ssekms_key_id,
+            sse_customer_algorithm,
+            sse_customer_key_md5,
+            replication_status,
+            storage_class,
+            restore,
+            expiration,
             ..Default::default()
         };
 
@@ -1183,17 +1754,50 @@ impl S3 for FS {
 
     #[tracing::instrument(level = "debug", skip(self, req))]
     async fn list_objects(&self, req: S3Request<ListObjectsInput>) -> S3Result<S3Response<ListObjectsOutput>> {
-        let v2_resp = self.list_objects_v2(req.map_input(Into::into)).await?;
-
-        Ok(v2_resp.map_output(|v2| ListObjectsOutput {
-            contents: v2.contents,
-            delimiter: v2.delimiter,
-            encoding_type: v2.encoding_type,
-            name: v2.name,
-            prefix: v2.prefix,
-            max_keys: v2.max_keys,
-            ..Default::default()
-        }))
+        let ListObjectsInput {
+            bucket,
+            delimiter,
+            marker,
+            max_keys,
+            prefix,
+            ..
+        } = req.input;
+
+        let prefix = prefix.unwrap_or_default();
+        let max_keys = max_keys.unwrap_or(1000);
+        if max_keys < 0 {
+            return Err(s3_error!(InvalidArgument, "max-keys cannot be negative"));
+        }
+
+        let delimiter = delimiter.filter(|v| !v.is_empty());
+        let marker = marker.filter(|v| !v.is_empty());
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        // The marker of a V1 list is the last key of the previous page, not a sealed token
+        let object_infos = if max_keys == 0 {
+            store
+                .get_bucket_info(&bucket, &BucketOptions::default())
+                .await
+                .map_err(ApiError::from)?;
+            ListObjectsInfo::default()
+        } else {
+            store
+                .list_objects_generic(&bucket, &prefix, marker.clone(), delimiter.clone(), max_keys)
+                .await
+                .map_err(ApiError::from)?
+        };
+
+        Ok(S3Response::new(list_objects_response(
+            bucket,
+            prefix,
+            delimiter,
+            marker,
+            max_keys,
+            object_infos,
+        )))
     }
 
     #[tracing::instrument(level = "debug", skip(self, req))]
@@ -1240,23 +1844,7 @@ impl S3 for FS {
             .objects
             .iter()
             .filter(|v| !v.name.is_empty())
-            .map(|v| {
-                let mut obj = Object {
-                    key: Some(v.name.to_owned()),
-                    last_modified: v.mod_time.map(Timestamp::from),
-                    size: Some(v.get_actual_size().unwrap_or_default()),
-                    e_tag: v.etag.clone(),
-                    ..Default::default()
-                };
-
-                if fetch_owner.is_some_and(|v| v) {
-                    obj.owner = Some(Owner {
-                        display_name: Some("rustfs".to_owned()),
-                        id: Some("v0.1".to_owned()),
-                    });
-                }
-                obj
-            })
+            .map(|v| object_response(v, fetch_owner.is_some_and(|v| v)))
             .collect();
 
         let key_count = objects.len() as i32;
@@ -1311,28 +1899,41 @@ impl S3 for FS {
         };
 
         let object_infos = store
-            .list_object_versions(&bucket, &prefix, key_marker, version_id_marker, delimiter.clone(), max_keys)
+            .list_object_versions(
+                &bucket,
+                &prefix,
+                key_marker.clone(),
+                version_id_marker.clone(),
+                delimiter.clone(),
+                max_keys,
+            )
             .await
             .map_err(ApiError::from)?;
 
-        let objects: Vec<ObjectVersion> = object_infos
-            .objects
-            .iter()
-            .filter(|v| !v.name.is_empty())
-            .map(|v| {
-                ObjectVersion {
+        let mut versions = Vec::new();
+        let mut delete_markers = Vec::new();
+        for v in object_infos.objects.iter().filter(|v| !v.name.is_empty()) {
+            if v.delete_marker {
+                delete_markers.push(DeleteMarkerEntry {
                     key: Some(v.name.to_owned()),
                     last_modified: v.mod_time.map(Timestamp::from),
-                    size: Some(v.size),
-                    version_id: v.version_id.map(|v| v.to_string()),
+                    version_id: v.version_id_str(),
                     is_latest: Some(v.is_latest),
-                    e_tag: v.etag.clone(),
-                    ..Default::default() // TODO: another fields
-                }
-            })
-            .collect();
-
-        let key_count = objects.len() as i32;
+                    ..Default::default()
+                });
+                continue;
+            }
+            versions.push(ObjectVersion {
+                key: Some(v.name.to_owned()),
+                last_modified: v.mod_time.map(Timestamp::from),
+                size: Some(v.size),
+                version_id: v.version_id_str(),
+                is_latest: Some(v.is_latest),
+                e_tag: etag_response(v.etag.clone()),
+                storage_class: Some(ObjectVersionStorageClass::from(v.storage_class())),
+                ..Default::default() // TODO: another fields
+            });
+        }
 
         let common_prefixes = object_infos
             .prefixes
@@ -1341,13 +1942,18 @@ impl S3 for FS {
             .collect();
 
         let output = ListObjectVersionsOutput {
-            // is_truncated: Some(object_infos.is_truncated),
-            max_keys: Some(key_count),
+            is_truncated: Some(object_infos.is_truncated),
+            key_marker,
+            version_id_marker,
+            next_key_marker: object_infos.next_marker,
+            next_version_id_marker: object_infos.next_version_idmarker,
+            max_keys: Some(max_keys),
             delimiter,
             name: Some(bucket),
             prefix: Some(prefix),
             common_prefixes: Some(common_prefixes),
-            versions: Some(objects),
+            versions: Some(versions),
+            delete_markers: Some(delete_markers),
             ..Default::default()
         };
 
@@ -1364,6 +1970,10 @@ impl S3 for FS {
             return self.put_object_extract(req).await;
         }
 
+        if let Some(offset) = req.headers.get(AMZ_WRITE_OFFSET_BYTES).cloned() {
+            return self.append_object(req, &offset).await;
+        }
+
         let input = req.input;
 
         if let Some(ref storage_class) = input.storage_class {
@@ -1381,6 +1991,14 @@ impl S3 for FS {
             tagging,
             metadata,
             version_id,
+            acl,
+            checksum_crc32,
+            checksum_crc32c,
+            checksum_crc64nvme,
+            checksum_sha1,
+            checksum_sha256,
+            if_match,
+            if_none_match,
             ..
         } = input;
 
@@ -1400,7 +2018,18 @@ impl S3 for FS {
             }
         };
 
-        let body = StreamReader::new(body.map(|f| f.map_err(|e| std::io::Error::other(e.to_string()))));
+        let checksums = checksum_map(Checksum {
+            checksum_crc32,
+            checksum_crc32c,
+            checksum_crc64nvme,
+            checksum_sha1,
+            checksum_sha256,
+            ..Default::default()
+        });
+        let body = ChecksumStream::new(body.map(|f| f.map_err(|e| std::io::Error::other(e.to_string()))), checksums.as_ref());
+        let checksum_mismatch = body.mismatch();
+        let trailer_mismatch = req.extensions.get::<TrailerMismatch>().cloned().unwrap_or_default();
+        let body = StreamReader::new(body);
 
         // let body = Box::new(StreamReader::new(body.map(|f| f.map_err(|e| std::io::Error::other(e.to_string())))));
 
@@ -1413,28 +2042,50 @@ impl S3 for FS {
         let mut metadata = metadata.unwrap_or_default();
 
         extract_metadata_from_mime(&req.headers, &mut metadata);
+        set_object_acl(&mut metadata, acl.as_ref().map(ObjectCannedACL::as_str))?;
+        acl::check_acl_allowed(&bucket, object_acl(&metadata)).await?;
+
+        let encryption = resolve_object_encryption(&bucket, &req.headers).await?;
+        if let Some(encryption) = &encryption {
+            encryption.write_metadata(&mut metadata).map_err(ApiError::from)?;
+        }
+        let object_key = new_object_key(encryption.as_ref(), &bucket, &key, &mut metadata).await?;
+
+        metadata.extend(resolve_object_lock(&bucket, &req.headers).await?);
 
         if let Some(tags) = tagging {
+            check_header_tags(&tags)?;
             metadata.insert(AMZ_OBJECT_TAGGING.to_owned(), tags);
         }
+        content_scan::mark_pending(&mut metadata);
+        set_object_ttl(&req.headers, &mut metadata)?;
+        if let Some(checksums) = &checksums {
+            metadata.insert(
+                format!("{RESERVED_METADATA_PREFIX_LOWER}checksums"),
+                try_!(serde_json::to_string(checksums)),
+            );
+        }
 
         let mut reader: Box<dyn Reader> = Box::new(WarpReader::new(body));
 
         let actual_size = size;
 
         if is_compressible(&req.headers, &key) && size > MIN_COMPRESSIBLE_SIZE as i64 {
-            metadata.insert(
-                format!("{RESERVED_METADATA_PREFIX_LOWER}compression"),
-                CompressionAlgorithm::default().to_string(),
-            );
+            let algorithm = compression_algorithm();
+            metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}compression"), algorithm.to_string());
             metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size",), size.to_string());
 
             let hrd = HashReader::new(reader, size as i64, size as i64, None, false).map_err(ApiError::from)?;
 
-            reader = Box::new(CompressReader::new(hrd, CompressionAlgorithm::default()));
+            reader = Box::new(CompressReader::new(hrd, algorithm));
             size = -1;
         }
 
+        if let Some(object_key) = &object_key {
+            metadata.insert(format!("{RESERVED_METADATA_PREFIX_LOWER}actual-size",), actual_size.to_string());
+            reader = encrypt_reader(reader, &mut size, actual_size, object_key, object_key.part_nonce(1))?;
+        }
+
         // TODO: md5 check
         let reader = HashReader::new(reader, size, actual_size, None, false).map_err(ApiError::from)?;
 
@@ -1446,6 +2097,25 @@ impl S3 for FS {
         let mut opts: ObjectOptions = put_opts(&bucket, &key, version_id, &req.headers, mt)
             .await
             .map_err(ApiError::from)?;
+        // The token fires once this handler is dropped, as hyper does when the client disconnects
+        let cancel = CancellationToken::new();
+        let _cancel_on_drop = cancel.clone().drop_guard();
+        opts.cancel = Some(cancel);
+        opts.deadline = request_deadline();
+        opts.write_preconditions = write_preconditions(if_match, if_none_match);
+        check_version_unprotected(&bucket, &key, &opts, false).await?;
+
+        let quota_size = u64::try_from(actual_size).unwrap_or_default();
+        check_bucket_quota(&bucket, quota_size, 1).await.map_err(ApiError::from)?;
+
+        if let Some(local) = newer_local_version(&store, &bucket, &key, &opts).await {
+            debug!("put_object {}/{} keeps the newer version of this site", bucket, key);
+            return Ok(S3Response::new(PutObjectOutput {
+                e_tag: etag_response(local.etag.clone()),
+                version_id: local.version_id_str(),
+                ..Default::default()
+            }));
+        }
 
         let repoptions =
             get_must_replicate_options(&mt2, "", ReplicationStatusType::Unknown, ReplicationType::ObjectReplicationType, &opts);
@@ -1461,12 +2131,20 @@ impl S3 for FS {
             opts.user_defined.insert(k, dsc.pending_status());
         }
 
-        let obj_info = store
-            .put_object(&bucket, &key, &mut reader, &opts)
-            .await
-            .map_err(ApiError::from)?;
+        let obj_info = store.put_object(&bucket, &key, &mut reader, &opts).await.map_err(|e| {
+            if checksum_mismatch.load(Ordering::SeqCst) || trailer_mismatch.is_raised() {
+                s3_error!(BadDigest, "The object does not match the checksum sent with it")
+            } else {
+                ApiError::from(e).into()
+            }
+        })?;
+        billing::record_bytes_in(&bucket, actual_size);
+        record_bucket_usage(&bucket, quota_size, 1);
+        content_scan::submit(&bucket, &key, obj_info.version_id.map(|v| v.to_string()));
+        schedule_object_expiry(&obj_info);
         let event_info = obj_info.clone();
-        let e_tag = obj_info.etag.clone();
+        let e_tag = etag_response(obj_info.etag.clone());
+        let put_version_id = obj_info.version_id_str();
 
         let repoptions =
             get_must_replicate_options(&mt2, "", ReplicationStatusType::Unknown, ReplicationType::ObjectReplicationType, &opts);
@@ -1478,8 +2156,22 @@ impl S3 for FS {
             schedule_replication(obj_info, objectlayer.unwrap(), dsc, 1).await;
         }
 
+        let (server_side_encryption, ssekms_key_id) = encryption_response(&mt2);
+        let (sse_customer_algorithm, sse_customer_key_md5) =
+            customer_key_response(encryption.as_ref().and_then(|e| e.customer_key.as_ref()));
+        let checksum = checksum_dto(checksums.as_ref());
         let output = PutObjectOutput {
             e_tag,
+            version_id: put_version_id,
+            checksum_crc32: checksum.checksum_crc32,
+            checksum_crc32c: checksum.checksum_crc32c,
+            checksum_crc64nvme: checksum.checksum_crc64nvme,
+            checksum_sha1: checksum.checksum_sha1,
+            checksum_sha256: checksum.checksum_sha256,
+            server_side_encryption,
+            ssekms_key_id,
+            sse_customer_algorithm,
+            sse_customer_key_md5,
             ..Default::default()
         };
 
@@ -1512,6 +2204,8 @@ impl S3 for FS {
             key,
             tagging,
             version_id,
+            checksum_algorithm,
+            acl,
             ..
         } = req.input.clone();
 
@@ -1524,21 +2218,46 @@ impl S3 for FS {
         };
 
         let mut metadata = extract_metadata(&req.headers);
+        set_object_acl(&mut metadata, acl.as_ref().map(ObjectCannedACL::as_str))?;
+        acl::check_acl_allowed(&bucket, object_acl(&metadata)).await?;
 
         if let Some(tags) = tagging {
+            check_header_tags(&tags)?;
             metadata.insert(AMZ_OBJECT_TAGGING.to_owned(), tags);
         }
+        content_scan::mark_pending(&mut metadata);
+        set_object_ttl(&req.headers, &mut metadata)?;
+
+        let encryption = resolve_object_encryption(&bucket, &req.headers).await?;
+        if let Some(encryption) = &encryption {
+            encryption.write_metadata(&mut metadata).map_err(ApiError::from)?;
+        }
+        // Every part is encrypted with the data key of the upload
+        new_object_key(encryption.as_ref(), &bucket, &key, &mut metadata).await?;
+        let (server_side_encryption, ssekms_key_id) = encryption_response(&metadata);
+        let (sse_customer_algorithm, sse_customer_key_md5) =
+            customer_key_response(encryption.as_ref().and_then(|e| e.customer_key.as_ref()));
+        metadata.extend(resolve_object_lock(&bucket, &req.headers).await?);
+
+        // The parts are expected to be sent with checksums of this algorithm
+        if let Some(algorithm) = &checksum_algorithm {
+            metadata.insert(
+                format!("{RESERVED_METADATA_PREFIX_LOWER}checksum-algorithm"),
+                algorithm.as_str().to_owned(),
+            );
+        }
 
         if is_compressible(&req.headers, &key) {
             metadata.insert(
                 format!("{RESERVED_METADATA_PREFIX_LOWER}compression"),
-                CompressionAlgorithm::default().to_string(),
+                compression_algorithm().to_string(),
             );
         }
 
         let opts: ObjectOptions = put_opts(&bucket, &key, version_id, &req.headers, metadata)
             .await
             .map_err(ApiError::from)?;
+        check_version_unprotected(&bucket, &key, &opts, false).await?;
 
         let MultipartUploadResult { upload_id, .. } = store
             .new_multipart_upload(&bucket, &key, &opts)
@@ -1550,6 +2269,11 @@ impl S3 for FS {
             bucket: Some(bucket),
             key: Some(key),
             upload_id: Some(upload_id),
+            server_side_encryption,
+            sse_customer_algorithm,
+            sse_customer_key_md5,
+            ssekms_key_id,
+            checksum_algorithm,
             ..Default::default()
         };
 
@@ -1589,6 +2313,11 @@ impl S3 for FS {
             upload_id,
             part_number,
             content_length,
+            checksum_crc32,
+            checksum_crc32c,
+            checksum_crc64nvme,
+            checksum_sha1,
+            checksum_sha256,
             // content_md5,
             ..
         } = req.input;
@@ -1598,7 +2327,7 @@ impl S3 for FS {
         // let upload_id =
 
         let body = body.ok_or_else(|| s3_error!(IncompleteBody))?;
-        let mut size = match content_length {
+        let size = match content_length {
             Some(c) => c,
             None => {
                 if let Some(val) = req.headers.get(AMZ_DECODED_CONTENT_LENGTH) {
@@ -1612,11 +2341,25 @@ impl S3 for FS {
             }
         };
 
-        let body = StreamReader::new(body.map(|f| f.map_err(|e| std::io::Error::other(e.to_string()))));
+        let checksums = checksum_map(Checksum {
+            checksum_crc32,
+            checksum_crc32c,
+            checksum_crc64nvme,
+            checksum_sha1,
+            checksum_sha256,
+            ..Default::default()
+        });
+        let body = ChecksumStream::new(body.map(|f| f.map_err(|e| std::io::Error::other(e.to_string()))), checksums.as_ref());
+        let checksum_mismatch = body.mismatch();
+        let trailer_mismatch = req.extensions.get::<TrailerMismatch>().cloned().unwrap_or_default();
+        let body = StreamReader::new(body);
 
         // mc cp step 4
 
-        let opts = ObjectOptions::default();
+        let mut opts = ObjectOptions {
+            checksums,
+            ..Default::default()
+        };
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
@@ -1627,48 +2370,164 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        let is_compressible = fi
-            .user_defined
-            .contains_key(format!("{RESERVED_METADATA_PREFIX_LOWER}compression").as_str());
+        // Parts of SSE-C uploads need the key the upload was created with
+        let customer_key = customer_key(&req.headers)?;
+        check_customer_key(&bucket, &key, &fi.user_defined, customer_key.as_ref())?;
+
+        let mut reader = part_reader(
+            &bucket,
+            &key,
+            &fi,
+            customer_key.as_ref(),
+            &mut opts,
+            Box::new(WarpReader::new(body)),
+            size,
+        )
+        .await?;
 
-        let mut reader: Box<dyn Reader> = Box::new(WarpReader::new(body));
+        let info = store
+            .put_object_part(&bucket, &key, &upload_id, part_id, &mut reader, &opts)
+            .await
+            .map_err(|e| {
+                if checksum_mismatch.load(Ordering::SeqCst) || trailer_mismatch.is_raised() {
+                    s3_error!(BadDigest, "The part does not match the checksum sent with it")
+                } else {
+                    ApiError::from(e).into()
+                }
+            })?;
+        billing::record_bytes_in(&bucket, info.actual_size);
 
-        let actual_size = size;
+        let (server_side_encryption, ssekms_key_id) = encryption_response(&fi.user_defined);
+        let (sse_customer_algorithm, sse_customer_key_md5) = customer_key_response(customer_key.as_ref());
+        let checksum = checksum_dto(info.checksums.as_ref());
+        let output = UploadPartOutput {
+            e_tag: etag_response(info.etag),
+            checksum_crc32: checksum.checksum_crc32,
+            checksum_crc32c: checksum.checksum_crc32c,
+            checksum_crc64nvme: checksum.checksum_crc64nvme,
+            checksum_sha1: checksum.checksum_sha1,
+            checksum_sha256: checksum.checksum_sha256,
+            server_side_encryption,
+            ssekms_key_id,
+            sse_customer_algorithm,
+            sse_customer_key_md5,
+            ..Default::default()
+        };
 
-        if is_compressible {
-            let hrd = HashReader::new(reader, size, actual_size, None, false).map_err(ApiError::from)?;
+        Ok(S3Response::new(output))
+    }
 
-            reader = Box::new(CompressReader::new(hrd, CompressionAlgorithm::default()));
-            size = -1;
+    #[tracing::instrument(level = "debug", skip(self, req))]
+    async fn upload_part_copy(&self, req: S3Request<UploadPartCopyInput>) -> S3Result<S3Response<UploadPartCopyOutput>> {
+        let UploadPartCopyInput {
+            bucket,
+            key,
+            copy_source,
+            copy_source_range,
+            part_number,
+            upload_id,
+            copy_source_if_match,
+            copy_source_if_none_match,
+            copy_source_if_modified_since,
+            copy_source_if_unmodified_since,
+            ..
+        } = req.input;
+
+        let (src_bucket, src_key, version_id) = match copy_source {
+            CopySource::AccessPoint { .. } => return Err(s3_error!(NotImplemented)),
+            CopySource::Bucket { bucket, key, version_id } => {
+                (bucket.to_string(), key.to_string(), version_id.map(|v| v.to_string()))
+            }
+        };
+
+        let part_id = part_number as usize;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        let fi = store
+            .get_multipart_info(&bucket, &key, &upload_id, &ObjectOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        let customer_key = customer_key(&req.headers)?;
+        check_customer_key(&bucket, &key, &fi.user_defined, customer_key.as_ref())?;
+
+        let src_version = get_opts(&src_bucket, &src_key, version_id, None, &req.headers)
+            .await
+            .map_err(ApiError::from)?;
+        let src_customer_key = copy_source_customer_key(&req.headers)?;
+        let src_opts = ObjectOptions {
+            version_id: src_version.version_id,
+            versioned: src_version.versioned,
+            version_suspended: src_version.version_suspended,
+            sse_customer_key: src_customer_key,
+            ..Default::default()
+        };
+
+        let src_info = store
+            .get_object_info(&src_bucket, &src_key, &src_opts)
+            .await
+            .map_err(ApiError::from)?;
+        check_customer_key(&src_bucket, &src_key, &src_info.user_defined, src_customer_key.as_ref())?;
+        Preconditions {
+            if_match: copy_source_if_match,
+            if_none_match: copy_source_if_none_match,
+            if_modified_since: copy_source_if_modified_since,
+            if_unmodified_since: copy_source_if_unmodified_since,
+        }
+        .check_copy_source(&src_info)?;
+        if !content_scan::download_allowed(&src_info.user_tags) {
+            return Err(s3_error!(AccessDenied, "object has not passed content scanning"));
         }
 
-        // TODO: md5 check
-        let reader = HashReader::new(reader, size, actual_size, None, false).map_err(ApiError::from)?;
+        let src_size = src_info.get_actual_size().map_err(ApiError::from)?;
+        let rs = copy_source_range
+            .map(|range| parse_copy_source_range(&range, src_size))
+            .transpose()?;
+        let length = rs.as_ref().map_or(src_size, |rs| rs.end - rs.start + 1);
+        if length > MAX_COPY_PART_SIZE {
+            return Err(s3_error!(
+                InvalidRequest,
+                "The specified copy source is larger than the maximum allowable size for a copy source: {}",
+                MAX_COPY_PART_SIZE
+            ));
+        }
 
-        let mut reader = PutObjReader::new(reader);
+        let gr = store
+            .get_object_reader(&src_bucket, &src_key, rs, HeaderMap::new(), &src_opts)
+            .await
+            .map_err(ApiError::from)?;
+
+        let reader = Box::new(ThrottleReader::new(WarpReader::new(gr.stream), copy_limiter()));
+        let mut opts = ObjectOptions::default();
+        let mut reader = part_reader(&bucket, &key, &fi, customer_key.as_ref(), &mut opts, reader, length).await?;
 
         let info = store
             .put_object_part(&bucket, &key, &upload_id, part_id, &mut reader, &opts)
             .await
             .map_err(ApiError::from)?;
 
-        let output = UploadPartOutput {
-            e_tag: info.etag,
+        let (server_side_encryption, ssekms_key_id) = encryption_response(&fi.user_defined);
+        let (sse_customer_algorithm, sse_customer_key_md5) = customer_key_response(customer_key.as_ref());
+        let output = UploadPartCopyOutput {
+            copy_part_result: Some(CopyPartResult {
+                e_tag: etag_response(info.etag),
+                last_modified: info.last_mod.map(Timestamp::from),
+                ..Default::default()
+            }),
+            copy_source_version_id: src_info.version_id_str(),
+            server_side_encryption,
+            ssekms_key_id,
+            sse_customer_algorithm,
+            sse_customer_key_md5,
             ..Default::default()
         };
 
         Ok(S3Response::new(output))
     }
 
-    #[tracing::instrument(level = "debug", skip(self, req))]
-    async fn upload_part_copy(&self, req: S3Request<UploadPartCopyInput>) -> S3Result<S3Response<UploadPartCopyOutput>> {
-        let _input = req.input;
-
-        let _output = UploadPartCopyOutput { ..Default::default() };
-
-        unimplemented!("upload_part_copy");
-    }
-
     #[tracing::instrument(level = "debug", skip(self, req))]
     async fn list_parts(&self, req: S3Request<ListPartsInput>) -> S3Result<S3Response<ListPartsOutput>> {
         let ListPartsInput {
@@ -1696,18 +2555,35 @@ impl S3 for FS {
             bucket: Some(res.bucket),
             key: Some(res.object),
             upload_id: Some(res.upload_id),
+            part_number_marker: Some(res.part_number_marker as i32),
+            next_part_number_marker: res.is_truncated.then_some(res.next_part_number_marker as i32),
+            max_parts: Some(res.max_parts as i32),
+            is_truncated: Some(res.is_truncated),
             parts: Some(
                 res.parts
                     .into_iter()
-                    .map(|p| Part {
-                        e_tag: p.etag,
-                        last_modified: p.last_mod.map(Timestamp::from),
-                        part_number: Some(p.part_num as i32),
-                        size: Some(p.size as i64),
-                        ..Default::default()
+                    .map(|p| {
+                        let checksum = checksum_dto(p.checksums.as_ref());
+                        Part {
+                            e_tag: etag_response(p.etag),
+                            last_modified: p.last_mod.map(Timestamp::from),
+                            part_number: Some(p.part_num as i32),
+                            size: Some(p.size as i64),
+                            checksum_crc32: checksum.checksum_crc32,
+                            checksum_crc32c: checksum.checksum_crc32c,
+                            checksum_crc64nvme: checksum.checksum_crc64nvme,
+                            checksum_sha1: checksum.checksum_sha1,
+                            checksum_sha256: checksum.checksum_sha256,
+                        }
                     })
                     .collect(),
             ),
+            checksum_algorithm: (!res.checksum_algorithm.is_empty()).then(|| res.checksum_algorithm.into()),
+            owner: Some(RUSTFS_OWNER.to_owned()),
+            initiator: Some(Initiator {
+                display_name: RUSTFS_OWNER.display_name.clone(),
+                id: RUSTFS_OWNER.id.clone(),
+            }),
             ..Default::default()
         };
         Ok(S3Response::new(output))
@@ -1735,12 +2611,6 @@ impl S3 for FS {
 
         let max_uploads = max_uploads.map(|x| x as usize).unwrap_or(MAX_PARTS_COUNT);
 
-        if let Some(key_marker) = &key_marker {
-            if !key_marker.starts_with(prefix.as_str()) {
-                return Err(s3_error!(NotImplemented, "Invalid key marker"));
-            }
-        }
-
         let result = store
             .list_multipart_uploads(&bucket, &prefix, delimiter, key_marker, upload_id_marker, max_uploads)
             .await
@@ -1752,6 +2622,8 @@ impl S3 for FS {
             delimiter: result.delimiter,
             key_marker: result.key_marker,
             upload_id_marker: result.upload_id_marker,
+            next_key_marker: result.next_key_marker,
+            next_upload_id_marker: result.next_upload_id_marker,
             max_uploads: Some(result.max_uploads as i32),
             is_truncated: Some(result.is_truncated),
             uploads: Some(
@@ -1790,6 +2662,13 @@ impl S3 for FS {
             bucket,
             key,
             upload_id,
+            checksum_crc32,
+            checksum_crc32c,
+            checksum_crc64nvme,
+            checksum_sha1,
+            checksum_sha256,
+            if_match,
+            if_none_match,
             ..
         } = req.input;
 
@@ -1798,7 +2677,25 @@ impl S3 for FS {
 
         let Some(multipart_upload) = multipart_upload else { return Err(s3_error!(InvalidPart)) };
 
-        let opts = &ObjectOptions::default();
+        let cancel = CancellationToken::new();
+        let _cancel_on_drop = cancel.clone().drop_guard();
+        let opts = &ObjectOptions {
+            cancel: Some(cancel),
+            deadline: request_deadline(),
+            replication_request: is_replication_request(&req.headers),
+            mod_time: source_mtime(&req.headers),
+            versioned: BucketVersioningSys::prefix_enabled(&bucket, &key).await,
+            checksums: checksum_map(Checksum {
+                checksum_crc32,
+                checksum_crc32c,
+                checksum_crc64nvme,
+                checksum_sha1,
+                checksum_sha256,
+                ..Default::default()
+            }),
+            write_preconditions: write_preconditions(if_match, if_none_match),
+            ..Default::default()
+        };
 
         let mut uploaded_parts = Vec::new();
 
@@ -1810,16 +2707,59 @@ impl S3 for FS {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
+        if let Some(local) = newer_local_version(&store, &bucket, &key, opts).await {
+            debug!("complete_multipart_upload {}/{} keeps the newer version of this site", bucket, key);
+            store
+                .abort_multipart_upload(&bucket, &key, &upload_id, opts)
+                .await
+                .map_err(ApiError::from)?;
+            return Ok(S3Response::new(CompleteMultipartUploadOutput {
+                bucket: Some(bucket),
+                key: Some(key),
+                e_tag: etag_response(local.etag.clone()),
+                version_id: local.version_id_str(),
+                ..Default::default()
+            }));
+        }
+
+        // Only the parts the request names make up the object
+        let mut quota_size = 0;
+        if has_bucket_quota(&bucket).await {
+            let parts = store
+                .list_object_parts(&bucket, &key, &upload_id, None, MAX_PARTS_COUNT, opts)
+                .await
+                .map_err(ApiError::from)?;
+            quota_size = parts
+                .parts
+                .iter()
+                .filter(|p| uploaded_parts.iter().any(|u| u.part_num == p.part_num))
+                .map(|p| u64::try_from(p.actual_size).unwrap_or_default())
+                .sum();
+            check_bucket_quota(&bucket, quota_size, 1).await.map_err(ApiError::from)?;
+        }
+
         let obj_info = store
             .complete_multipart_upload(&bucket, &key, &upload_id, uploaded_parts, opts)
             .await
             .map_err(ApiError::from)?;
+        record_bucket_usage(&bucket, quota_size, 1);
+        content_scan::submit(&bucket, &key, obj_info.version_id.map(|v| v.to_string()));
+        schedule_object_expiry(&obj_info);
 
+        let (server_side_encryption, ssekms_key_id) = encryption_response(&obj_info.user_defined);
+        let checksum = checksum_dto(obj_info.checksums().as_ref());
         let output = CompleteMultipartUploadOutput {
+            checksum_crc32: checksum.checksum_crc32,
+            checksum_crc32c: checksum.checksum_crc32c,
+            checksum_sha1: checksum.checksum_sha1,
+            checksum_sha256: checksum.checksum_sha256,
             bucket: Some(bucket.clone()),
             key: Some(key.clone()),
-            e_tag: obj_info.etag.clone(),
+            e_tag: etag_response(obj_info.etag.clone()),
             location: Some("us-east-1".to_string()),
+            version_id: obj_info.version_id_str(),
+            server_side_encryption,
+            ssekms_key_id,
             ..Default::default()
         };
 
@@ -1885,6 +2825,7 @@ impl S3 for FS {
     #[tracing::instrument(level = "debug", skip(self))]
     async fn put_bucket_tagging(&self, req: S3Request<PutBucketTaggingInput>) -> S3Result<S3Response<PutBucketTaggingOutput>> {
         let PutBucketTaggingInput { bucket, tagging, .. } = req.input;
+        validate_tags(&tagging.tag_set, MAX_BUCKET_TAGS).map_err(|e| s3_error!(InvalidTag, "{}", e))?;
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
@@ -1924,6 +2865,7 @@ impl S3 for FS {
             bucket,
             key: object,
             tagging,
+            version_id,
             ..
         } = req.input.clone();
 
@@ -1931,15 +2873,31 @@ impl S3 for FS {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        let tags = encode_tags(tagging.tag_set);
+        let mut opts = get_opts(&bucket, &object, version_id, None, &req.headers)
+            .await
+            .map_err(ApiError::from)?;
+        let now = source_mtime(&req.headers).unwrap_or_else(OffsetDateTime::now_utc);
+        if newer_local_metadata(&store, &bucket, &object, &opts, TAGGING_TIMESTAMP, now).await? {
+            return Ok(S3Response::new(PutObjectTaggingOutput { version_id: None }));
+        }
 
-        // TODO: getOpts
-        // TODO: Replicate
+        validate_tags(&tagging.tag_set, MAX_OBJECT_TAGS).map_err(|e| s3_error!(InvalidTag, "{}", e))?;
+        let mut tags = encode_tags(tagging.tag_set);
+        // Only the content scanner sets the scan status
+        if content_scan::scan_config().is_some() {
+            let current = store.get_object_tags(&bucket, &object, &opts).await.map_err(ApiError::from)?;
+            tags = content_scan::with_scan_status(&tags, content_scan::scan_status(&current).as_deref());
+        }
 
-        store
-            .put_object_tags(&bucket, &object, &tags, &ObjectOptions::default())
+        let mut eval_metadata = HashMap::new();
+        set_metadata_timestamp(&mut eval_metadata, TAGGING_TIMESTAMP, now);
+        opts.eval_metadata = Some(eval_metadata);
+
+        let info = store
+            .put_object_tags(&bucket, &object, &tags, &opts)
             .await
             .map_err(ApiError::from)?;
+        schedule_metadata_replication(store, info, &opts).await;
 
         let version_id = match req.input.version_id {
             Some(v) => v.to_string(),
@@ -1970,17 +2928,21 @@ impl S3 for FS {
 
     #[tracing::instrument(level = "debug", skip(self))]
     async fn get_object_tagging(&self, req: S3Request<GetObjectTaggingInput>) -> S3Result<S3Response<GetObjectTaggingOutput>> {
-        let GetObjectTaggingInput { bucket, key: object, .. } = req.input;
+        let GetObjectTaggingInput {
+            bucket,
+            key: object,
+            version_id,
+            ..
+        } = req.input;
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        // TODO: version
-        let tags = store
-            .get_object_tags(&bucket, &object, &ObjectOptions::default())
+        let opts = get_opts(&bucket, &object, version_id, None, &req.headers)
             .await
             .map_err(ApiError::from)?;
+        let tags = store.get_object_tags(&bucket, &object, &opts).await.map_err(ApiError::from)?;
 
         let tag_set = decode_tags(tags.as_str());
 
@@ -1995,18 +2957,48 @@ impl S3 for FS {
         &self,
         req: S3Request<DeleteObjectTaggingInput>,
     ) -> S3Result<S3Response<DeleteObjectTaggingOutput>> {
-        let DeleteObjectTaggingInput { bucket, key: object, .. } = req.input.clone();
+        let DeleteObjectTaggingInput {
+            bucket,
+            key: object,
+            version_id,
+            ..
+        } = req.input.clone();
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        // TODO: Replicate
-        // TODO: version
-        store
-            .delete_object_tags(&bucket, &object, &ObjectOptions::default())
+        let mut opts = get_opts(&bucket, &object, version_id, None, &req.headers)
             .await
             .map_err(ApiError::from)?;
+        let now = source_mtime(&req.headers).unwrap_or_else(OffsetDateTime::now_utc);
+        if newer_local_metadata(&store, &bucket, &object, &opts, TAGGING_TIMESTAMP, now).await? {
+            return Ok(S3Response::new(DeleteObjectTaggingOutput { version_id: None }));
+        }
+        let mut eval_metadata = HashMap::new();
+        set_metadata_timestamp(&mut eval_metadata, TAGGING_TIMESTAMP, now);
+        opts.eval_metadata = Some(eval_metadata);
+
+        // Only the content scanner sets the scan status, it outlives the other tags
+        let scan_status = match content_scan::scan_config() {
+            Some(_) => store
+                .get_object_tags(&bucket, &object, &opts)
+                .await
+                .ok()
+                .and_then(|tags| content_scan::scan_status(&tags)),
+            None => None,
+        };
+        let info = match scan_status {
+            Some(status) => store
+                .put_object_tags(&bucket, &object, &content_scan::with_scan_status("", Some(&status)), &opts)
+                .await
+                .map_err(ApiError::from)?,
+            None => store
+                .delete_object_tags(&bucket, &object, &opts)
+                .await
+                .map_err(ApiError::from)?,
+        };
+        schedule_metadata_replication(store, info, &opts).await;
 
         let version_id = match req.input.version_id {
             Some(v) => v.to_string(),
@@ -2071,9 +3063,15 @@ impl S3 for FS {
 
         // TODO: check other sys
         // check site replication enable
-        // check bucket object lock enable
         // check replication suspended
 
+        if !versioning_configuration.enabled() && bucket_object_lock(&bucket).await.is_some() {
+            return Err(s3_error!(
+                InvalidBucketState,
+                "An Object Lock configuration is present on this bucket, so the versioning state cannot be changed"
+            ));
+        }
+
         let data = try_!(serialize(&versioning_configuration));
 
         metadata_sys::update(&bucket, BUCKET_VERSIONING_CONFIG, data)
@@ -2100,31 +3098,7 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        let conditions = get_condition_values(&req.headers, &auth::Credentials::default());
-
-        let read_only = PolicySys::is_allowed(&BucketPolicyArgs {
-            bucket: &bucket,
-            action: Action::S3Action(S3Action::ListBucketAction),
-            is_owner: false,
-            account: "",
-            groups: &None,
-            conditions: &conditions,
-            object: "",
-        })
-        .await;
-
-        let write_only = PolicySys::is_allowed(&BucketPolicyArgs {
-            bucket: &bucket,
-            action: Action::S3Action(S3Action::PutObjectAction),
-            is_owner: false,
-            account: "",
-            groups: &None,
-            conditions: &conditions,
-            object: "",
-        })
-        .await;
-
-        let is_public = read_only && write_only;
+        let is_public = is_bucket_public(&bucket).await.map_err(ApiError::from)?;
 
         let output = GetBucketPolicyStatusOutput {
             policy_status: Some(PolicyStatus {
@@ -2174,14 +3148,24 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        // warn!("input policy {}", &policy);
+        if policy.len() > MAX_BUCKET_POLICY_SIZE {
+            return Err(s3_error!(
+                EntityTooLarge,
+                "Policy exceeds the maximum allowed document size of {} bytes",
+                MAX_BUCKET_POLICY_SIZE
+            ));
+        }
 
-        let cfg: BucketPolicy =
-            serde_json::from_str(&policy).map_err(|e| s3_error!(InvalidArgument, "parse policy failed {:?}", e))?;
+        let cfg: BucketPolicy = serde_json::from_str(&policy).map_err(|e| s3_error!(MalformedPolicy, "{}", e))?;
 
-        if let Err(err) = cfg.is_valid() {
+        if let Err(err) = cfg.validate(&bucket) {
             warn!("put_bucket_policy err input {:?}, {:?}", &policy, err);
-            return Err(s3_error!(InvalidPolicyDocument));
+            return Err(s3_error!(MalformedPolicy, "{}", err));
+        }
+
+        let block = PublicAccessBlock::get(&bucket).await.map_err(ApiError::from)?;
+        if block.block_public_policy && cfg.is_public() {
+            return Err(s3_error!(AccessDenied, "Public policies are blocked for this bucket"));
         }
 
         let data = serde_json::to_vec(&cfg).map_err(|e| s3_error!(InternalError, "parse policy failed {:?}", e))?;
@@ -2261,33 +3245,262 @@ impl S3 for FS {
 
         let Some(input_cfg) = lifecycle_configuration else { return Err(s3_error!(InvalidArgument)) };
 
-        let rcfg = metadata_sys::get_object_lock_config(&bucket).await;
-        if let Ok(rcfg) = rcfg {
-            if let Err(err) = input_cfg.validate(&rcfg.0).await {
-                //return Err(S3Error::with_message(S3ErrorCode::Custom("BucketLockValidateFailed".into()), err.to_string()));
-                return Err(S3Error::with_message(S3ErrorCode::Custom("ValidateFailed".into()), err.to_string()));
-            }
-        }
+        let rcfg = metadata_sys::get_object_lock_config(&bucket)
+            .await
+            .map(|(rcfg, _)| rcfg)
+            .unwrap_or_default();
+        input_cfg.validate(&rcfg).await.map_err(lifecycle_config_error)?;
+
+        if let Err(err) = validate_transition_tier(&input_cfg).await {
+            //warn!("lifecycle_configuration add failed, err: {:?}", err);
+            return Err(S3Error::with_message(S3ErrorCode::Custom("CustomError".into()), err.to_string()));
+        }
+
+        let data = try_!(serialize(&input_cfg));
+        metadata_sys::update(&bucket, BUCKET_LIFECYCLE_CONFIG, data)
+            .await
+            .map_err(ApiError::from)?;
+
+        Ok(S3Response::new(PutBucketLifecycleConfigurationOutput::default()))
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    async fn delete_bucket_lifecycle(
+        &self,
+        req: S3Request<DeleteBucketLifecycleInput>,
+    ) -> S3Result<S3Response<DeleteBucketLifecycleOutput>> {
+        let DeleteBucketLifecycleInput { bucket, .. } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        metadata_sys::delete(&bucket, BUCKET_LIFECYCLE_CONFIG)
+            .await
+            .map_err(ApiError::from)?;
+
+        Ok(S3Response::new(DeleteBucketLifecycleOutput::default()))
+    }
+
+    async fn get_bucket_encryption(
+        &self,
+        req: S3Request<GetBucketEncryptionInput>,
+    ) -> S3Result<S3Response<GetBucketEncryptionOutput>> {
+        let GetBucketEncryptionInput { bucket, .. } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        let server_side_encryption_configuration = match metadata_sys::get_sse_config(&bucket).await {
+            Ok((cfg, _)) => Some(cfg),
+            Err(StorageError::ConfigNotFound) => return Err(s3_error!(ServerSideEncryptionConfigurationNotFoundError)),
+            Err(err) => {
+                warn!("get_sse_config err {:?}", err);
+                return Err(ApiError::from(err).into());
+            }
+        };
+
+        Ok(S3Response::new(GetBucketEncryptionOutput {
+            server_side_encryption_configuration,
+        }))
+    }
+
+    async fn put_bucket_encryption(
+        &self,
+        req: S3Request<PutBucketEncryptionInput>,
+    ) -> S3Result<S3Response<PutBucketEncryptionOutput>> {
+        let PutBucketEncryptionInput {
+            bucket,
+            server_side_encryption_configuration,
+            ..
+        } = req.input;
+
+        info!("sse_config {:?}", &server_side_encryption_configuration);
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        // TODO: check kms
+        validate_sse_config(&server_side_encryption_configuration)
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidArgument, e.to_string()))?;
+
+        let data = try_!(serialize(&server_side_encryption_configuration));
+        metadata_sys::update(&bucket, BUCKET_SSECONFIG, data)
+            .await
+            .map_err(ApiError::from)?;
+        Ok(S3Response::new(PutBucketEncryptionOutput::default()))
+    }
+
+    async fn delete_bucket_encryption(
+        &self,
+        req: S3Request<DeleteBucketEncryptionInput>,
+    ) -> S3Result<S3Response<DeleteBucketEncryptionOutput>> {
+        let DeleteBucketEncryptionInput { bucket, .. } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+        metadata_sys::delete(&bucket, BUCKET_SSECONFIG)
+            .await
+            .map_err(ApiError::from)?;
+
+        Ok(S3Response::new(DeleteBucketEncryptionOutput::default()))
+    }
+
+    async fn get_public_access_block(
+        &self,
+        req: S3Request<GetPublicAccessBlockInput>,
+    ) -> S3Result<S3Response<GetPublicAccessBlockOutput>> {
+        let GetPublicAccessBlockInput { bucket, .. } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        let public_access_block_configuration = match metadata_sys::get_public_access_block_config(&bucket).await {
+            Ok((cfg, _)) => Some(cfg),
+            Err(StorageError::ConfigNotFound) => {
+                let mut err = S3Error::with_message(
+                    S3ErrorCode::Custom("NoSuchPublicAccessBlockConfiguration".into()),
+                    "The public access block configuration was not found",
+                );
+                err.set_status_code(http::StatusCode::NOT_FOUND);
+                return Err(err);
+            }
+            Err(err) => return Err(ApiError::from(err).into()),
+        };
+
+        Ok(S3Response::new(GetPublicAccessBlockOutput {
+            public_access_block_configuration,
+        }))
+    }
+
+    async fn put_public_access_block(
+        &self,
+        req: S3Request<PutPublicAccessBlockInput>,
+    ) -> S3Result<S3Response<PutPublicAccessBlockOutput>> {
+        let PutPublicAccessBlockInput {
+            bucket,
+            public_access_block_configuration,
+            ..
+        } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        let data = try_!(serialize(&public_access_block_configuration));
+        metadata_sys::update(&bucket, BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG, data)
+            .await
+            .map_err(ApiError::from)?;
+
+        Ok(S3Response::new(PutPublicAccessBlockOutput::default()))
+    }
+
+    async fn delete_public_access_block(
+        &self,
+        req: S3Request<DeletePublicAccessBlockInput>,
+    ) -> S3Result<S3Response<DeletePublicAccessBlockOutput>> {
+        let DeletePublicAccessBlockInput { bucket, .. } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+        metadata_sys::delete(&bucket, BUCKET_PUBLIC_ACCESS_BLOCK_CONFIG)
+            .await
+            .map_err(ApiError::from)?;
+
+        Ok(S3Response::new(DeletePublicAccessBlockOutput::default()))
+    }
+
+    async fn get_bucket_cors(&self, req: S3Request<GetBucketCorsInput>) -> S3Result<S3Response<GetBucketCorsOutput>> {
+        let GetBucketCorsInput { bucket, .. } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
+
+        let cors_rules = match metadata_sys::get_cors_config(&bucket).await {
+            Ok((cfg, _)) => Some(cfg.cors_rules),
+            Err(StorageError::ConfigNotFound) => {
+                return Err(s3_error!(NoSuchCORSConfiguration, "The CORS configuration does not exist"));
+            }
+            Err(err) => return Err(ApiError::from(err).into()),
+        };
+
+        Ok(S3Response::new(GetBucketCorsOutput { cors_rules }))
+    }
+
+    async fn put_bucket_cors(&self, req: S3Request<PutBucketCorsInput>) -> S3Result<S3Response<PutBucketCorsOutput>> {
+        let PutBucketCorsInput {
+            bucket,
+            cors_configuration,
+            ..
+        } = req.input;
+
+        let Some(store) = new_object_layer_fn() else {
+            return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+        };
+
+        store
+            .get_bucket_info(&bucket, &BucketOptions::default())
+            .await
+            .map_err(ApiError::from)?;
 
-        if let Err(err) = validate_transition_tier(&input_cfg).await {
-            //warn!("lifecycle_configuration add failed, err: {:?}", err);
-            return Err(S3Error::with_message(S3ErrorCode::Custom("CustomError".into()), err.to_string()));
-        }
+        validate_cors_config(&cors_configuration).map_err(|e| s3_error!(InvalidRequest, "{}", e))?;
 
-        let data = try_!(serialize(&input_cfg));
-        metadata_sys::update(&bucket, BUCKET_LIFECYCLE_CONFIG, data)
+        let data = try_!(serialize(&cors_configuration));
+        metadata_sys::update(&bucket, BUCKET_CORS_CONFIG, data)
             .await
             .map_err(ApiError::from)?;
 
-        Ok(S3Response::new(PutBucketLifecycleConfigurationOutput::default()))
+        Ok(S3Response::new(PutBucketCorsOutput::default()))
     }
 
-    #[tracing::instrument(level = "debug", skip(self))]
-    async fn delete_bucket_lifecycle(
-        &self,
-        req: S3Request<DeleteBucketLifecycleInput>,
-    ) -> S3Result<S3Response<DeleteBucketLifecycleOutput>> {
-        let DeleteBucketLifecycleInput { bucket, .. } = req.input;
+    async fn delete_bucket_cors(&self, req: S3Request<DeleteBucketCorsInput>) -> S3Result<S3Response<DeleteBucketCorsOutput>> {
+        let DeleteBucketCorsInput { bucket, .. } = req.input;
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
@@ -2297,19 +3510,15 @@ impl S3 for FS {
             .get_bucket_info(&bucket, &BucketOptions::default())
             .await
             .map_err(ApiError::from)?;
-
-        metadata_sys::delete(&bucket, BUCKET_LIFECYCLE_CONFIG)
+        metadata_sys::delete(&bucket, BUCKET_CORS_CONFIG)
             .await
             .map_err(ApiError::from)?;
 
-        Ok(S3Response::new(DeleteBucketLifecycleOutput::default()))
+        Ok(S3Response::new(DeleteBucketCorsOutput::default()))
     }
 
-    async fn get_bucket_encryption(
-        &self,
-        req: S3Request<GetBucketEncryptionInput>,
-    ) -> S3Result<S3Response<GetBucketEncryptionOutput>> {
-        let GetBucketEncryptionInput { bucket, .. } = req.input;
+    async fn get_bucket_website(&self, req: S3Request<GetBucketWebsiteInput>) -> S3Result<S3Response<GetBucketWebsiteOutput>> {
+        let GetBucketWebsiteInput { bucket, .. } = req.input;
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
@@ -2320,34 +3529,32 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        let server_side_encryption_configuration = match metadata_sys::get_sse_config(&bucket).await {
-            Ok((cfg, _)) => Some(cfg),
-            Err(err) => {
-                // if BucketMetadataError::BucketLifecycleNotFound.is(&err) {
-                //     return Err(s3_error!(ErrNoSuchBucketSSEConfig));
-                // }
-                warn!("get_sse_config err {:?}", err);
-                None
+        let cfg = match metadata_sys::get_website_config(&bucket).await {
+            Ok((cfg, _)) => cfg,
+            Err(StorageError::ConfigNotFound) => {
+                return Err(s3_error!(
+                    NoSuchWebsiteConfiguration,
+                    "The specified bucket does not have a website configuration"
+                ));
             }
+            Err(err) => return Err(ApiError::from(err).into()),
         };
 
-        Ok(S3Response::new(GetBucketEncryptionOutput {
-            server_side_encryption_configuration,
+        Ok(S3Response::new(GetBucketWebsiteOutput {
+            error_document: cfg.error_document,
+            index_document: cfg.index_document,
+            redirect_all_requests_to: cfg.redirect_all_requests_to,
+            routing_rules: cfg.routing_rules,
         }))
     }
 
-    async fn put_bucket_encryption(
-        &self,
-        req: S3Request<PutBucketEncryptionInput>,
-    ) -> S3Result<S3Response<PutBucketEncryptionOutput>> {
-        let PutBucketEncryptionInput {
+    async fn put_bucket_website(&self, req: S3Request<PutBucketWebsiteInput>) -> S3Result<S3Response<PutBucketWebsiteOutput>> {
+        let PutBucketWebsiteInput {
             bucket,
-            server_side_encryption_configuration,
+            website_configuration,
             ..
         } = req.input;
 
-        info!("sse_config {:?}", &server_side_encryption_configuration);
-
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
@@ -2357,20 +3564,21 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        // TODO: check kms
+        validate_website_config(&website_configuration).map_err(|e| s3_error!(InvalidRequest, "{}", e))?;
 
-        let data = try_!(serialize(&server_side_encryption_configuration));
-        metadata_sys::update(&bucket, BUCKET_SSECONFIG, data)
+        let data = try_!(serialize(&website_configuration));
+        metadata_sys::update(&bucket, BUCKET_WEBSITE_CONFIG, data)
             .await
             .map_err(ApiError::from)?;
-        Ok(S3Response::new(PutBucketEncryptionOutput::default()))
+
+        Ok(S3Response::new(PutBucketWebsiteOutput::default()))
     }
 
-    async fn delete_bucket_encryption(
+    async fn delete_bucket_website(
         &self,
-        req: S3Request<DeleteBucketEncryptionInput>,
-    ) -> S3Result<S3Response<DeleteBucketEncryptionOutput>> {
-        let DeleteBucketEncryptionInput { bucket, .. } = req.input;
+        req: S3Request<DeleteBucketWebsiteInput>,
+    ) -> S3Result<S3Response<DeleteBucketWebsiteOutput>> {
+        let DeleteBucketWebsiteInput { bucket, .. } = req.input;
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
@@ -2380,11 +3588,11 @@ impl S3 for FS {
             .get_bucket_info(&bucket, &BucketOptions::default())
             .await
             .map_err(ApiError::from)?;
-        metadata_sys::delete(&bucket, BUCKET_SSECONFIG)
+        metadata_sys::delete(&bucket, BUCKET_WEBSITE_CONFIG)
             .await
             .map_err(ApiError::from)?;
 
-        Ok(S3Response::new(DeleteBucketEncryptionOutput::default()))
+        Ok(S3Response::new(DeleteBucketWebsiteOutput::default()))
     }
 
     #[tracing::instrument(level = "debug", skip(self))]
@@ -2421,6 +3629,7 @@ impl S3 for FS {
         } = req.input;
 
         let Some(input_cfg) = object_lock_configuration else { return Err(s3_error!(InvalidArgument)) };
+        validate_object_lock_config(&input_cfg).map_err(object_lock_error)?;
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
@@ -2431,6 +3640,14 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        // Object lock needs every write to keep the earlier versions
+        if bucket_object_lock(&bucket).await.is_none() && !BucketVersioningSys::enabled(&bucket).await {
+            return Err(s3_error!(
+                InvalidBucketState,
+                "Versioning must be enabled on the bucket to apply an Object Lock configuration"
+            ));
+        }
+
         let data = try_!(serialize(&input_cfg));
 
         metadata_sys::update(&bucket, OBJECT_LOCK_CONFIG, data)
@@ -2611,14 +3828,15 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
+        // Unknown target ARNs are rejected before the configuration is stored
+        load_bucket_notification(&bucket, &notification_configuration).await?;
+
         let data = try_!(serialize(&notification_configuration));
 
         metadata_sys::update(&bucket, BUCKET_NOTIFICATION_CONFIG, data)
             .await
             .map_err(ApiError::from)?;
 
-        // TODO: event notice add rule
-
         Ok(S3Response::new(PutBucketNotificationConfigurationOutput::default()))
     }
 
@@ -2634,16 +3852,7 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        let grants = vec![Grant {
-            grantee: Some(Grantee {
-                type_: Type::from_static(Type::CANONICAL_USER),
-                display_name: None,
-                email_address: None,
-                id: None,
-                uri: None,
-            }),
-            permission: Some(Permission::from_static(Permission::FULL_CONTROL)),
-        }];
+        let grants = bucket_acl(&bucket).await?.grants(&RUSTFS_OWNER);
 
         Ok(S3Response::new(GetBucketAclOutput {
             grants: Some(grants),
@@ -2670,51 +3879,28 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        if let Some(canned_acl) = acl {
-            if canned_acl.as_str() != BucketCannedACL::PRIVATE {
-                return Err(s3_error!(NotImplemented));
-            }
-        } else {
-            let is_full_control = access_control_policy.is_some_and(|v| {
-                v.grants.is_some_and(|gs| {
-                    //
-                    !gs.is_empty()
-                        && gs.first().is_some_and(|g| {
-                            g.to_owned()
-                                .permission
-                                .is_some_and(|p| p.as_str() == Permission::FULL_CONTROL)
-                        })
-                })
-            });
+        let acl = requested_acl(acl.as_ref().map(BucketCannedACL::as_str), access_control_policy.as_ref())?;
+        acl::check_acl_allowed(&bucket, acl).await?;
+        acl::put_bucket_acl(&bucket, acl).await?;
 
-            if !is_full_control {
-                return Err(s3_error!(NotImplemented));
-            }
-        }
         Ok(S3Response::new(PutBucketAclOutput::default()))
     }
 
     async fn get_object_acl(&self, req: S3Request<GetObjectAclInput>) -> S3Result<S3Response<GetObjectAclOutput>> {
-        let GetObjectAclInput { bucket, key, .. } = req.input;
+        let GetObjectAclInput {
+            bucket, key, version_id, ..
+        } = req.input;
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        if let Err(e) = store.get_object_info(&bucket, &key, &ObjectOptions::default()).await {
-            return Err(S3Error::with_message(S3ErrorCode::InternalError, format!("{e}")));
-        }
+        let opts: ObjectOptions = get_opts(&bucket, &key, version_id, None, &req.headers)
+            .await
+            .map_err(ApiError::from)?;
+        let info = store.get_object_info(&bucket, &key, &opts).await.map_err(ApiError::from)?;
 
-        let grants = vec![Grant {
-            grantee: Some(Grantee {
-                type_: Type::from_static(Type::CANONICAL_USER),
-                display_name: None,
-                email_address: None,
-                id: None,
-                uri: None,
-            }),
-            permission: Some(Permission::from_static(Permission::FULL_CONTROL)),
-        }];
+        let grants = object_acl(&info.user_defined).grants(&RUSTFS_OWNER);
 
         Ok(S3Response::new(GetObjectAclOutput {
             grants: Some(grants),
@@ -2727,22 +3913,55 @@ impl S3 for FS {
         &self,
         req: S3Request<GetObjectAttributesInput>,
     ) -> S3Result<S3Response<GetObjectAttributesOutput>> {
-        let GetObjectAttributesInput { bucket, key, .. } = req.input.clone();
+        let GetObjectAttributesInput {
+            bucket,
+            key,
+            version_id,
+            object_attributes,
+            part_number_marker,
+            max_parts,
+            ..
+        } = req.input.clone();
 
         let Some(store) = new_object_layer_fn() else {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        if let Err(e) = store
-            .get_object_reader(&bucket, &key, None, HeaderMap::new(), &ObjectOptions::default())
+        let opts: ObjectOptions = get_opts(&bucket, &key, version_id, None, &req.headers)
             .await
-        {
-            return Err(S3Error::with_message(S3ErrorCode::InternalError, format!("{e}")));
-        }
+            .map_err(ApiError::from)?;
+        let info = store.get_object_info(&bucket, &key, &opts).await.map_err(ApiError::from)?;
+        let customer_key = customer_key(&req.headers)?;
+        check_customer_key(&bucket, &key, &info.user_defined, customer_key.as_ref())?;
+
+        let wants = |attribute: &str| object_attributes.iter().any(|a| a.as_str() == attribute);
+
+        let checksum = checksum_dto(info.checksums().as_ref());
+        let object_parts = (info.is_multipart() && wants(ObjectAttributes::OBJECT_PARTS)).then(|| {
+            page_object_parts(
+                &info.parts,
+                part_number_marker.unwrap_or_default().max(0) as usize,
+                max_parts.map(|v| v.max(0) as usize).unwrap_or(MAX_PARTS_COUNT),
+            )
+        });
+        let storage_class = info.storage_class();
 
         let output = GetObjectAttributesOutput {
-            delete_marker: None,
-            object_parts: None,
+            e_tag: info.etag.clone().filter(|_| wants(ObjectAttributes::ETAG)),
+            checksum: checksum
+                .checksum_type
+                .is_some()
+                .then_some(checksum)
+                .filter(|_| wants(ObjectAttributes::CHECKSUM)),
+            object_parts,
+            object_size: if wants(ObjectAttributes::OBJECT_SIZE) {
+                Some(info.get_actual_size().map_err(ApiError::from)?)
+            } else {
+                None
+            },
+            storage_class: wants(ObjectAttributes::STORAGE_CLASS).then(|| StorageClass::from(storage_class)),
+            last_modified: info.mod_time.map(Timestamp::from),
+            version_id: info.version_id_str(),
             ..Default::default()
         };
         let version_id = match req.input.version_id {
@@ -2778,6 +3997,7 @@ impl S3 for FS {
             key,
             acl,
             access_control_policy,
+            version_id,
             ..
         } = req.input;
 
@@ -2785,31 +4005,23 @@ impl S3 for FS {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        if let Err(e) = store.get_object_info(&bucket, &key, &ObjectOptions::default()).await {
-            return Err(S3Error::with_message(S3ErrorCode::InternalError, format!("{e}")));
-        }
+        let acl = requested_acl(acl.as_ref().map(ObjectCannedACL::as_str), access_control_policy.as_ref())?;
+        acl::check_acl_allowed(&bucket, acl).await?;
 
-        if let Some(canned_acl) = acl {
-            if canned_acl.as_str() != BucketCannedACL::PRIVATE {
-                return Err(s3_error!(NotImplemented));
-            }
-        } else {
-            let is_full_control = access_control_policy.is_some_and(|v| {
-                v.grants.is_some_and(|gs| {
-                    //
-                    !gs.is_empty()
-                        && gs.first().is_some_and(|g| {
-                            g.to_owned()
-                                .permission
-                                .is_some_and(|p| p.as_str() == Permission::FULL_CONTROL)
-                        })
-                })
-            });
+        let opts: ObjectOptions = get_opts(&bucket, &key, version_id, None, &req.headers)
+            .await
+            .map_err(ApiError::from)?;
+        let popts = ObjectOptions {
+            version_id: opts.version_id.clone(),
+            eval_metadata: Some(HashMap::from([(OBJECT_ACL_KEY.clone(), acl.as_str().to_owned())])),
+            ..Default::default()
+        };
+        let info = store
+            .put_object_metadata(&bucket, &key, &popts)
+            .await
+            .map_err(ApiError::from)?;
+        schedule_metadata_replication(store, info, &opts).await;
 
-            if !is_full_control {
-                return Err(s3_error!(NotImplemented));
-            }
-        }
         Ok(S3Response::new(PutObjectAclOutput::default()))
     }
 
@@ -2822,6 +4034,19 @@ impl S3 for FS {
         let input = Arc::new(req.input);
         info!("{:?}", input);
 
+        if content_scan::scan_config().is_some() {
+            let Some(store) = new_object_layer_fn() else {
+                return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+            };
+            let info = store
+                .get_object_info(&input.bucket, &input.key, &ObjectOptions::default())
+                .await
+                .map_err(ApiError::from)?;
+            if !content_scan::download_allowed(&info.user_tags) {
+                return Err(s3_error!(AccessDenied, "object has not passed content scanning"));
+            }
+        }
+
         let db = get_global_db((*input).clone(), false).await.map_err(|e| {
             error!("get global db failed, {}", e.to_string());
             s3_error!(InternalError, "{}", e.to_string())
@@ -2971,8 +4196,9 @@ impl S3 for FS {
             .await
             .map_err(ApiError::from)?;
 
-        // check object lock
-        let _ = metadata_sys::get_object_lock_config(&bucket).await.map_err(ApiError::from)?;
+        if bucket_object_lock(&bucket).await.is_none() {
+            return Err(object_lock_error(ObjectLockError::NotEnabled));
+        }
 
         let opts: ObjectOptions = get_opts(&bucket, &key, version_id, None, &req.headers)
             .await
@@ -2983,17 +4209,22 @@ impl S3 for FS {
             .map(|v| v.status.map(|v| v.as_str().to_string()))
             .unwrap_or_default()
             .unwrap_or("OFF".to_string());
+        if objectlock::parse_legalhold_status(&legal_hold).is_none() {
+            return Err(object_lock_error(ObjectLockError::InvalidRequest(
+                objectlock::ERR_UNKNOWN_LEGAL_HOLD_STATUS,
+            )));
+        }
 
-        let now = OffsetDateTime::now_utc();
-        eval_metadata.insert("x-amz-object-lock-legal-hold".to_string(), legal_hold);
-        eval_metadata.insert(
-            format!("{}{}", RESERVED_METADATA_PREFIX_LOWER, "objectlock-legalhold-timestamp"),
-            format!("{}.{:09}Z", now.format(&Rfc3339).unwrap(), now.nanosecond()),
-        );
+        let now = source_mtime(&req.headers).unwrap_or_else(OffsetDateTime::now_utc);
+        if newer_local_metadata(&store, &bucket, &key, &opts, OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP, now).await? {
+            return Ok(S3Response::new(PutObjectLegalHoldOutput::default()));
+        }
+        eval_metadata.insert(X_AMZ_OBJECT_LOCK_LEGAL_HOLD.as_str().to_string(), legal_hold);
+        set_metadata_timestamp(&mut eval_metadata, OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP, now);
 
         let popts = ObjectOptions {
             mod_time: opts.mod_time,
-            version_id: opts.version_id,
+            version_id: opts.version_id.clone(),
             eval_metadata: Some(eval_metadata),
             ..Default::default()
         };
@@ -3002,6 +4233,7 @@ impl S3 for FS {
             error!("put_object_metadata failed, {}", e.to_string());
             s3_error!(InternalError, "{}", e.to_string())
         })?;
+        schedule_metadata_replication(store, info.clone(), &opts).await;
 
         let output = PutObjectLegalHoldOutput {
             request_charged: Some(RequestCharged::from_static(RequestCharged::REQUESTER)),
@@ -3053,19 +4285,8 @@ impl S3 for FS {
             s3_error!(InternalError, "{}", e.to_string())
         })?;
 
-        let mode = object_info
-            .user_defined
-            .get("x-amz-object-lock-mode")
-            .map(|v| ObjectLockRetentionMode::from(v.as_str().to_string()));
-
-        let retain_until_date = object_info
-            .user_defined
-            .get("x-amz-object-lock-retain-until-date")
-            .and_then(|v| OffsetDateTime::parse(v.as_str(), &Rfc3339).ok())
-            .map(Timestamp::from);
-
         let output = GetObjectRetentionOutput {
-            retention: Some(ObjectLockRetention { mode, retain_until_date }),
+            retention: Some(objectlock::get_object_retention_meta(&object_info.user_defined)),
         };
         let version_id = match req.input.version_id {
             Some(v) => v.to_string(),
@@ -3099,6 +4320,7 @@ impl S3 for FS {
             key,
             retention,
             version_id,
+            bypass_governance_retention,
             ..
         } = req.input.clone();
 
@@ -3106,37 +4328,53 @@ impl S3 for FS {
             return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
         };
 
-        // check object lock
-        let _ = metadata_sys::get_object_lock_config(&bucket).await.map_err(ApiError::from)?;
-
-        // TODO: check allow
-
-        let mut eval_metadata = HashMap::new();
-
-        if let Some(v) = retention {
-            let mode = v.mode.map(|v| v.as_str().to_string()).unwrap_or_default();
-            let retain_until_date = v
-                .retain_until_date
-                .map(|v| OffsetDateTime::from(v).format(&Rfc3339).unwrap())
-                .unwrap_or_default();
-            let now = OffsetDateTime::now_utc();
-            eval_metadata.insert("x-amz-object-lock-mode".to_string(), mode);
-            eval_metadata.insert("x-amz-object-lock-retain-until-date".to_string(), retain_until_date);
-            eval_metadata.insert(
-                format!("{}{}", RESERVED_METADATA_PREFIX_LOWER, "objectlock-retention-timestamp"),
-                format!("{}.{:09}Z", now.format(&Rfc3339).unwrap(), now.nanosecond()),
-            );
+        if bucket_object_lock(&bucket).await.is_none() {
+            return Err(object_lock_error(ObjectLockError::NotEnabled));
         }
 
+        // An empty retention removes it, which governance retention only allows with a bypass
+        let retention = match retention.map(|v| (v.mode, v.retain_until_date)) {
+            Some((Some(mode), Some(retain_until_date))) => Some(Retention {
+                mode: objectlock::parse_ret_mode(mode.as_str()).ok_or_else(|| {
+                    object_lock_error(ObjectLockError::InvalidRequest(objectlock::ERR_UNKNOWN_WORMMODE_DIRECTIVE))
+                })?,
+                retain_until: OffsetDateTime::from(retain_until_date),
+            }),
+            None | Some((None, None)) => None,
+            Some(_) => return Err(s3_error!(MalformedXML, "retention needs both a mode and a retain until date")),
+        };
+
         let mut opts: ObjectOptions = get_opts(&bucket, &key, version_id, None, &req.headers)
             .await
             .map_err(ApiError::from)?;
+
+        let now = source_mtime(&req.headers).unwrap_or_else(OffsetDateTime::now_utc);
+        let current = store.get_object_info(&bucket, &key, &opts).await.map_err(ApiError::from)?;
+        // A replica keeps a retention it set later than the source, and checks any other like its own
+        if opts.replication_request
+            && metadata_timestamp(&current.user_defined, OBJECT_LOCK_RETENTION_TIMESTAMP).is_some_and(|local| local >= now)
+        {
+            return Ok(S3Response::new(PutObjectRetentionOutput::default()));
+        }
+        check_retention_update(&current, retention.as_ref(), bypass_governance_retention.unwrap_or_default(), now)
+            .map_err(object_lock_error)?;
+
+        let mut eval_metadata = HashMap::new();
+        match &retention {
+            Some(retention) => retention.write_meta(&mut eval_metadata),
+            None => {
+                eval_metadata.insert(X_AMZ_OBJECT_LOCK_MODE.as_str().to_string(), String::new());
+                eval_metadata.insert(X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE.as_str().to_string(), String::new());
+            }
+        }
+        set_metadata_timestamp(&mut eval_metadata, OBJECT_LOCK_RETENTION_TIMESTAMP, now);
         opts.eval_metadata = Some(eval_metadata);
 
         let object_info = store.put_object_metadata(&bucket, &key, &opts).await.map_err(|e| {
             error!("put_object_metadata failed, {}", e.to_string());
             s3_error!(InternalError, "{}", e.to_string())
         })?;
+        schedule_metadata_replication(store, object_info.clone(), &opts).await;
 
         let output = PutObjectRetentionOutput {
             request_charged: Some(RequestCharged::from_static(RequestCharged::REQUESTER)),
@@ -3166,6 +4404,350 @@ impl S3 for FS {
     }
 }
 
+/// Most keys a DeleteObjects request may list, as on S3
+const MAX_DELETE_OBJECTS: usize = 1000;
+
+/// Keys of a DeleteObjects request whose lock and replication checks run at once
+const DELETE_OBJECTS_CHECK_CONCURRENCY: usize = 32;
+
+/// Largest part an UploadPartCopy copies, larger sources are copied in ranges
+const MAX_COPY_PART_SIZE: i64 = 5 * 1024 * 1024 * 1024;
+
+/// Largest bucket policy document accepted, as on S3
+const MAX_BUCKET_POLICY_SIZE: usize = 20 * 1024;
+
+/// ACL a PutBucketAcl or PutObjectAcl sets, from its canned ACL or its `AccessControlPolicy` document
+fn requested_acl(canned: Option<&str>, policy: Option<&AccessControlPolicy>) -> S3Result<CannedAcl> {
+    match (canned, policy) {
+        (Some(acl), _) => CannedAcl::parse(acl),
+        (None, Some(policy)) => CannedAcl::from_policy(policy),
+        (None, None) => Err(s3_error!(NotImplemented, "Only canned ACLs and the grants they amount to are supported")),
+    }
+}
+
+/// Range of its source an UploadPartCopy copies, `bytes=first-last` within the `size` bytes of the source
+fn parse_copy_source_range(range: &str, size: i64) -> S3Result<HTTPRangeSpec> {
+    let invalid = || s3_error!(InvalidArgument, "Invalid x-amz-copy-source-range: {}", range);
+    let (first, last) = range
+        .strip_prefix("bytes=")
+        .and_then(|r| r.split_once('-'))
+        .ok_or_else(invalid)?;
+    let (Ok(first), Ok(last)) = (first.parse::<i64>(), last.parse::<i64>()) else {
+        return Err(invalid());
+    };
+    if first < 0 || first > last {
+        return Err(invalid());
+    }
+    if last >= size {
+        return Err(s3_error!(
+            InvalidRange,
+            "The x-amz-copy-source-range {} is outside the source object",
+            range
+        ));
+    }
+
+    Ok(HTTPRangeSpec {
+        is_suffix_length: false,
+        start: first,
+        end: last,
+    })
+}
+
+/// Reader of the data of a part, compressed and encrypted like the rest of its upload. Encrypted
+/// parts are sealed with a fresh nonce, which is set in `opts` to be recorded with the part
+async fn part_reader(
+    bucket: &str,
+    key: &str,
+    upload: &MultipartInfo,
+    customer_key: Option<&CustomerKey>,
+    opts: &mut ObjectOptions,
+    mut reader: Box<dyn Reader>,
+    mut size: i64,
+) -> S3Result<PutObjReader> {
+    let actual_size = size;
+
+    // Parts are compressed with the algorithm the upload was started with
+    if let Some(algorithm) = upload
+        .user_defined
+        .get(format!("{RESERVED_METADATA_PREFIX_LOWER}compression").as_str())
+    {
+        let algorithm = CompressionAlgorithm::from_str(algorithm).map_err(ApiError::from)?;
+        let hrd = HashReader::new(reader, size, actual_size, None, false).map_err(ApiError::from)?;
+
+        reader = Box::new(CompressReader::new(hrd, algorithm));
+        size = -1;
+    }
+
+    if is_encrypted(&upload.user_defined) {
+        let object_key = object_key(bucket, key, &upload.user_defined, customer_key)
+            .await
+            .map_err(ApiError::from)?;
+        let nonce = ObjectKey::random_part_nonce();
+        reader = encrypt_reader(reader, &mut size, actual_size, &object_key, nonce)?;
+        opts.part_nonce = Some(nonce);
+    }
+
+    // TODO: md5 check
+    let reader = HashReader::new(reader, size, actual_size, None, false).map_err(ApiError::from)?;
+
+    Ok(PutObjReader::new(reader))
+}
+
+/// One page of the parts of a multipart object for GetObjectAttributes, those after `marker`
+fn page_object_parts(parts: &[ObjectPartInfo], marker: usize, max_parts: usize) -> GetObjectAttributesParts {
+    let mut remaining = parts.iter().filter(|p| p.number > marker);
+    let page: Vec<_> = remaining.by_ref().take(max_parts).collect();
+    let is_truncated = remaining.next().is_some();
+
+    GetObjectAttributesParts {
+        total_parts_count: Some(parts.len() as i32),
+        part_number_marker: Some(marker as i32),
+        next_part_number_marker: page.last().filter(|_| is_truncated).map(|p| p.number as i32),
+        max_parts: Some(max_parts as i32),
+        is_truncated: Some(is_truncated),
+        parts: Some(
+            page.into_iter()
+                .map(|p| {
+                    let checksum = checksum_dto(p.checksums.as_ref());
+                    ObjectPart {
+                        part_number: Some(p.number as i32),
+                        size: Some(if p.actual_size > 0 { p.actual_size } else { p.size as i64 }),
+                        checksum_crc32: checksum.checksum_crc32,
+                        checksum_crc32c: checksum.checksum_crc32c,
+                        checksum_crc64nvme: checksum.checksum_crc64nvme,
+                        checksum_sha1: checksum.checksum_sha1,
+                        checksum_sha256: checksum.checksum_sha256,
+                    }
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Metadata of a copy made with the `REPLACE` directive: the user metadata of the request, over what the
+/// server keeps about the data, its encryption, lock and replication
+fn replace_user_metadata(user_defined: &mut HashMap<String, String>, metadata: HashMap<String, String>) {
+    user_defined.retain(|k, _| {
+        let k = k.to_lowercase();
+        k.starts_with(RESERVED_METADATA_PREFIX_LOWER) || k.starts_with("x-amz-") || k == "etag"
+    });
+    user_defined.extend(metadata);
+}
+
+/// Entry of the `Error` list of a DeleteObjects response for a key that was not deleted
+fn delete_error(key: &str, version_id: Option<String>, err: &S3Error) -> Error {
+    Error {
+        code: Some(err.code().as_str().to_string()),
+        key: Some(key.to_owned()),
+        message: err.message().map(str::to_string),
+        version_id,
+    }
+}
+
+/// Rejects an `x-amz-tagging` header whose tag set breaks the S3 limits
+fn check_header_tags(tags: &str) -> S3Result<()> {
+    validate_tags(&decode_tags(tags), MAX_OBJECT_TAGS).map_err(|e| s3_error!(InvalidTag, "{}", e))
+}
+
+/// Fails a bucket asked for in another region than the one of this server, `us-east-1` unless set
+fn check_location_constraint(config: Option<&CreateBucketConfiguration>) -> S3Result<()> {
+    let Some(location) = config
+        .and_then(|config| config.location_constraint.as_ref())
+        .map(|location| location.as_str())
+        .filter(|location| !location.is_empty())
+    else {
+        return Ok(());
+    };
+    let region = rustfs_ecstore::global::get_global_region().unwrap_or_else(|| "us-east-1".to_owned());
+    if location != region {
+        return Err(s3_error!(
+            IllegalLocationConstraintException,
+            "The {} location constraint is incompatible with the region of this server, {}",
+            location,
+            region
+        ));
+    }
+    Ok(())
+}
+
+/// Entry of an object in a list of objects, with its owner when `fetch_owner` is set
+fn object_response(info: &ObjectInfo, fetch_owner: bool) -> Object {
+    Object {
+        key: Some(info.name.to_owned()),
+        last_modified: info.mod_time.map(Timestamp::from),
+        size: Some(info.get_actual_size().unwrap_or_default()),
+        e_tag: etag_response(info.etag.clone()),
+        storage_class: Some(ObjectStorageClass::from(info.storage_class())),
+        owner: fetch_owner.then(|| Owner {
+            display_name: Some("rustfs".to_owned()),
+            id: Some("v0.1".to_owned()),
+        }),
+        ..Default::default()
+    }
+}
+
+/// ListObjects (V1) output of a page of objects, which always lists their owners and, when truncated,
+/// carries the marker to ask for the next page with whether or not a delimiter was given
+fn list_objects_response(
+    bucket: String,
+    prefix: String,
+    delimiter: Option<String>,
+    marker: Option<String>,
+    max_keys: i32,
+    info: ListObjectsInfo,
+) -> ListObjectsOutput {
+    let contents = info
+        .objects
+        .iter()
+        .filter(|v| !v.name.is_empty())
+        .map(|v| object_response(v, true))
+        .collect();
+    let common_prefixes = info
+        .prefixes
+        .into_iter()
+        .map(|prefix| CommonPrefix { prefix: Some(prefix) })
+        .collect();
+
+    ListObjectsOutput {
+        is_truncated: Some(info.is_truncated),
+        marker,
+        next_marker: info.next_marker.filter(|_| info.is_truncated),
+        contents: Some(contents),
+        common_prefixes: Some(common_prefixes),
+        delimiter,
+        max_keys: Some(max_keys),
+        name: Some(bucket),
+        prefix: Some(prefix),
+        ..Default::default()
+    }
+}
+
+/// ETag of an object or part as S3 returns it, in double quotes
+fn etag_response(etag: Option<String>) -> Option<ETag> {
+    etag.filter(|etag| !etag.is_empty())
+        .map(|etag| format!("\"{}\"", etag.trim_matches('"')))
+}
+
+/// `x-amz-replication-status` of an object version, none when it is not replicated
+fn replication_status_response(status: &ReplicationStatusType) -> Option<ReplicationStatus> {
+    (!status.is_empty()).then(|| ReplicationStatus::from(status.as_str().to_owned()))
+}
+
+/// `x-amz-storage-class` of an object version, none for the standard class
+fn storage_class_response(info: &ObjectInfo) -> Option<StorageClass> {
+    let storage_class = info.storage_class();
+    (storage_class != storageclass::STANDARD).then(|| StorageClass::from(storage_class))
+}
+
+/// Give the object written with `metadata` the time to live of its `X-Rustfs-Expire-After` header, if any
+fn set_object_ttl(headers: &HeaderMap, metadata: &mut HashMap<String, String>) -> S3Result<()> {
+    let Some(value) = headers.get(RUSTFS_EXPIRE_AFTER) else {
+        return Ok(());
+    };
+    let ttl = value
+        .to_str()
+        .ok()
+        .and_then(object_ttl::parse_expire_after)
+        .ok_or_else(|| s3_error!(InvalidArgument, "{} must be a number of seconds up to ten years", RUSTFS_EXPIRE_AFTER))?;
+    object_ttl::set_expires_at(metadata, OffsetDateTime::now_utc() + ttl);
+    Ok(())
+}
+
+/// `x-amz-expiration` of an object version given a time to live
+fn expiration_response(info: &ObjectInfo) -> Option<Expiration> {
+    object_ttl::expires_at(&info.user_defined).and_then(object_ttl::expiration_header)
+}
+
+/// `x-amz-restore` of a transitioned object version, none when no restore was asked for
+fn restore_response(info: &ObjectInfo) -> Option<Restore> {
+    if info.restore_ongoing {
+        return Some(ongoing_restore_obj().to_string());
+    }
+    info.restore_expires.map(|expiry| completed_restore_obj(expiry).to_string())
+}
+
+/// Replication of a delete, decided on the version it deletes before it is gone
+async fn delete_replication_decision(
+    store: &ECStore,
+    bucket: &str,
+    dobj: &ObjectToDelete,
+    opts: &ObjectOptions,
+) -> ReplicateDecision {
+    if opts.replication_request || metadata_sys::get_replication_config(bucket).await.is_err() {
+        return ReplicateDecision::default();
+    }
+
+    let version_opts = ObjectOptions {
+        version_id: dobj.version_id.map(|v| v.to_string()),
+        versioned: opts.versioned,
+        version_suspended: opts.version_suspended,
+        ..Default::default()
+    };
+    let goi = store.get_object_info(bucket, &dobj.object_name, &version_opts).await;
+    let oi = goi.as_ref().cloned().unwrap_or_default();
+    check_replicate_delete(bucket, dobj, &oi, opts, goi.as_ref().err()).await
+}
+
+/// Whether the tags, retention or legal hold a replication request sets, as of `at` on its source, were changed
+/// since on this site, the later change winning
+///
+/// Only spares the write, which the storage layer checks again under the object lock.
+async fn newer_local_metadata(
+    store: &ECStore,
+    bucket: &str,
+    key: &str,
+    opts: &ObjectOptions,
+    timestamp: &str,
+    at: OffsetDateTime,
+) -> S3Result<bool> {
+    if !opts.replication_request {
+        return Ok(false);
+    }
+    let info = store.get_object_info(bucket, key, opts).await.map_err(ApiError::from)?;
+    Ok(metadata_timestamp(&info.user_defined, timestamp).is_some_and(|local| local >= at))
+}
+
+/// Replicates a change of the tags, retention or legal hold of a version made on this site
+async fn schedule_metadata_replication(store: Arc<ECStore>, info: rustfs_ecstore::store_api::ObjectInfo, opts: &ObjectOptions) {
+    let repoptions = get_must_replicate_options(
+        &info.user_defined,
+        &info.user_tags,
+        info.replication_status.clone(),
+        ReplicationType::MetadataReplicationType,
+        opts,
+    );
+    let dsc = must_replicate(&info.bucket, &info.name, &repoptions).await;
+    if dsc.replicate_any() {
+        schedule_replication(info, store, dsc, ReplicationType::MetadataReplicationType as i32).await;
+    }
+}
+
+/// Version of this site newer than the one a replication request brings, which wins over it
+///
+/// Sites replicating to each other both write the same version ids with the modification time of the source, so
+/// versioned buckets converge on their own; the null version of an unversioned bucket keeps the last writer.
+/// Only spares the upload, the storage layer checks again under the object lock and keeps the local version.
+async fn newer_local_version(
+    store: &ECStore,
+    bucket: &str,
+    key: &str,
+    opts: &ObjectOptions,
+) -> Option<rustfs_ecstore::store_api::ObjectInfo> {
+    let mtime = opts.mod_time.filter(|_| opts.replication_request)?;
+    if opts.versioned && opts.version_id.is_none() {
+        return None;
+    }
+
+    let version_opts = ObjectOptions {
+        version_id: opts.version_id.clone(),
+        versioned: opts.versioned,
+        version_suspended: opts.version_suspended,
+        ..Default::default()
+    };
+    let info = store.get_object_info(bucket, key, &version_opts).await.ok()?;
+    info.mod_time.filter(|local| *local >= mtime).map(|_| info)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3228,6 +4810,141 @@ mod tests {
         assert_eq!(internal_error.code(), &S3ErrorCode::InternalError);
     }
 
+    #[test]
+    fn test_parse_copy_source_range() {
+        let rs = parse_copy_source_range("bytes=0-9", 100).unwrap();
+        assert_eq!((rs.start, rs.end, rs.is_suffix_length), (0, 9, false));
+        let rs = parse_copy_source_range("bytes=10-99", 100).unwrap();
+        assert_eq!((rs.start, rs.end), (10, 99));
+
+        for range in ["0-9", "bytes=9-0", "bytes=-9", "bytes=5-", "bytes=a-b"] {
+            let err = parse_copy_source_range(range, 100).unwrap_err();
+            assert_eq!(err.code(), &S3ErrorCode::InvalidArgument, "{range}");
+        }
+        let err = parse_copy_source_range("bytes=10-100", 100).unwrap_err();
+        assert_eq!(err.code(), &S3ErrorCode::InvalidRange);
+    }
+
+    #[test]
+    fn test_replace_user_metadata() {
+        let mut user_defined = HashMap::from([
+            ("content-type".to_owned(), "text/plain".to_owned()),
+            ("color".to_owned(), "red".to_owned()),
+            ("etag".to_owned(), "abc".to_owned()),
+            (AMZ_OBJECT_TAGGING.to_owned(), "a=b".to_owned()),
+            (format!("{RESERVED_METADATA_PREFIX_LOWER}compression"), "zstd".to_owned()),
+        ]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-amz-meta-shape", http::HeaderValue::from_static("round"));
+        replace_user_metadata(&mut user_defined, extract_metadata(&headers));
+
+        assert_eq!(user_defined.get("shape").map(String::as_str), Some("round"));
+        assert_eq!(user_defined.get("content-type").map(String::as_str), Some("binary/octet-stream"));
+        assert!(!user_defined.contains_key("color"));
+        assert_eq!(user_defined.get("etag").map(String::as_str), Some("abc"));
+        assert_eq!(user_defined.get(AMZ_OBJECT_TAGGING).map(String::as_str), Some("a=b"));
+        assert!(user_defined.contains_key(&format!("{RESERVED_METADATA_PREFIX_LOWER}compression")));
+    }
+
+    #[test]
+    fn test_delete_error() {
+        let err = delete_error("a/b", Some("v1".to_owned()), &s3_error!(AccessDenied, "object is WORM protected"));
+        assert_eq!(err.code.as_deref(), Some("AccessDenied"));
+        assert_eq!(err.key.as_deref(), Some("a/b"));
+        assert_eq!(err.message.as_deref(), Some("object is WORM protected"));
+        assert_eq!(err.version_id.as_deref(), Some("v1"));
+    }
+
+    #[test]
+    fn test_check_location_constraint() {
+        let config = |location: &str| CreateBucketConfiguration {
+            location_constraint: Some(BucketLocationConstraint::from(location.to_owned())),
+            ..Default::default()
+        };
+        assert!(check_location_constraint(None).is_ok());
+        assert!(check_location_constraint(Some(&config(""))).is_ok());
+        assert!(check_location_constraint(Some(&config("us-east-1"))).is_ok());
+        let err = check_location_constraint(Some(&config("eu-west-1"))).unwrap_err();
+        assert_eq!(*err.code(), S3ErrorCode::IllegalLocationConstraintException);
+    }
+
+    #[test]
+    fn test_list_objects_response() {
+        let object = |name: &str| ObjectInfo {
+            name: name.to_owned(),
+            etag: Some("abc".to_owned()),
+            ..Default::default()
+        };
+        let info = ListObjectsInfo {
+            is_truncated: true,
+            next_marker: Some("photos/b".to_owned()),
+            objects: vec![object("photos/a"), object("photos/b")],
+            prefixes: vec!["photos/2024/".to_owned()],
+        };
+
+        let output = list_objects_response(
+            "bucket".to_owned(),
+            "photos/".to_owned(),
+            Some("/".to_owned()),
+            Some("photos/0".to_owned()),
+            2,
+            info,
+        );
+        assert_eq!(output.is_truncated, Some(true));
+        assert_eq!(output.marker.as_deref(), Some("photos/0"));
+        assert_eq!(output.next_marker.as_deref(), Some("photos/b"));
+        assert_eq!(output.max_keys, Some(2));
+        let contents = output.contents.unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[0].e_tag.as_deref(), Some("\"abc\""));
+        assert!(contents[0].owner.is_some());
+        assert_eq!(output.common_prefixes.unwrap()[0].prefix.as_deref(), Some("photos/2024/"));
+
+        // The last page has no next marker
+        let last = ListObjectsInfo {
+            next_marker: Some("photos/b".to_owned()),
+            ..Default::default()
+        };
+        let output = list_objects_response("bucket".to_owned(), String::new(), None, None, 1000, last);
+        assert_eq!(output.is_truncated, Some(false));
+        assert!(output.next_marker.is_none());
+    }
+
+    #[test]
+    fn test_etag_response() {
+        assert_eq!(etag_response(Some("abc-2".to_owned())).as_deref(), Some("\"abc-2\""));
+        assert_eq!(etag_response(Some("\"abc\"".to_owned())).as_deref(), Some("\"abc\""));
+        assert_eq!(etag_response(Some(String::new())), None);
+        assert_eq!(etag_response(None), None);
+    }
+
+    #[test]
+    fn test_page_object_parts() {
+        let parts: Vec<_> = (1..=5)
+            .map(|number| ObjectPartInfo {
+                number,
+                size: 10,
+                actual_size: 8,
+                ..Default::default()
+            })
+            .collect();
+
+        let page = page_object_parts(&parts, 0, 2);
+        let numbers: Vec<_> = page.parts.unwrap().iter().map(|p| p.part_number.unwrap()).collect();
+        assert_eq!(numbers, vec![1, 2]);
+        assert_eq!(page.total_parts_count, Some(5));
+        assert_eq!(page.is_truncated, Some(true));
+        assert_eq!(page.next_part_number_marker, Some(2));
+
+        let page = page_object_parts(&parts, 3, 2);
+        let parts_page = page.parts.unwrap();
+        assert_eq!(parts_page.iter().map(|p| p.part_number.unwrap()).collect::<Vec<_>>(), vec![4, 5]);
+        assert_eq!(parts_page[0].size, Some(8));
+        assert_eq!(page.is_truncated, Some(false));
+        assert_eq!(page.next_part_number_marker, None);
+    }
+
     #[test]
     fn test_compression_format_usage() {
         // Test that compression format detection works for common file extensions
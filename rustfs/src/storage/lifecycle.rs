@@ -0,0 +1,65 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rustfs_ecstore::bucket::lifecycle::bucket_lifecycle_audit::LcAuditEntry;
+use rustfs_ecstore::bucket::lifecycle::lifecycle::LifecycleConfigError;
+use rustfs_obs::{ApiDetails, BaseLogEntry, log_audit_event};
+use s3s::{S3Error, S3ErrorCode};
+use std::collections::HashMap;
+
+/// S3 error of a lifecycle configuration refused by validation
+pub fn lifecycle_config_error(err: LifecycleConfigError) -> S3Error {
+    let code = match err {
+        LifecycleConfigError::MalformedXml => S3ErrorCode::MalformedXML,
+        LifecycleConfigError::Invalid(_) => S3ErrorCode::InvalidArgument,
+    };
+    S3Error::with_message(code, err.to_string())
+}
+
+/// Record an object expired, transitioned or upload aborted by a lifecycle rule in the audit log
+pub(crate) fn audit_lifecycle_event(entry: LcAuditEntry) {
+    let api = ApiDetails::new()
+        .set_bucket(Some(entry.bucket.clone()))
+        .set_object(Some(entry.object.clone()));
+    let mut tags = HashMap::from([
+        ("bucket".to_string(), serde_json::Value::from(entry.bucket)),
+        ("object".to_string(), serde_json::Value::from(entry.object)),
+        ("rule-id".to_string(), serde_json::Value::from(entry.rule_id)),
+        ("source".to_string(), serde_json::Value::from(entry.source.as_str())),
+        ("versions".to_string(), serde_json::Value::from(entry.versions)),
+    ]);
+    if let Some(version_id) = entry.version_id {
+        tags.insert("version-id".to_string(), serde_json::Value::from(version_id.to_string()));
+    }
+    if let Some(upload_id) = entry.upload_id {
+        tags.insert("upload-id".to_string(), serde_json::Value::from(upload_id));
+    }
+
+    log_audit_event(format!("ilm:{}", entry.action), "ilm", api, BaseLogEntry::new().tags(Some(tags)), None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_config_error() {
+        let err = lifecycle_config_error(LifecycleConfigError::MalformedXml);
+        assert_eq!(*err.code(), S3ErrorCode::MalformedXML);
+        let err =
+            lifecycle_config_error(LifecycleConfigError::Invalid("Days must be positive integer when used with Expiration"));
+        assert_eq!(*err.code(), S3ErrorCode::InvalidArgument);
+        assert_eq!(err.message(), Some("Days must be positive integer when used with Expiration"));
+    }
+}
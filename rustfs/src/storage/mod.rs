@@ -13,6 +13,16 @@
 // limitations under the License.
 
 pub mod access;
+pub mod acl;
+pub mod byte_ranges;
+pub mod checksum;
+pub mod copy_progress;
 pub mod ecfs;
+pub mod lifecycle;
+pub mod notification;
+pub mod object_lock;
 // pub mod error;
 pub mod options;
+pub mod post_policy;
+pub mod precondition;
+pub mod sse;
@@ -0,0 +1,117 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Glue between the bucket notification configurations stored in the bucket metadata and the
+//! rules of the event notifier.
+
+use rustfs_ecstore::bucket::metadata_sys;
+use rustfs_ecstore::bucket::utils::serialize;
+use rustfs_ecstore::global::get_global_region;
+use rustfs_ecstore::store::ECStore;
+use rustfs_ecstore::store_api::{BucketOptions, StorageAPI};
+use rustfs_notify::BucketNotificationConfig;
+use s3s::dto::NotificationConfiguration;
+use s3s::{S3Result, s3_error};
+use std::io::Cursor;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Region of the ARNs of the notification targets
+fn notification_region() -> String {
+    get_global_region().unwrap_or_default()
+}
+
+/// Checks a notification configuration against the registered targets and makes it the rules of
+/// the bucket, an empty configuration removes them.
+pub async fn load_bucket_notification(bucket: &str, cfg: &NotificationConfiguration) -> S3Result<()> {
+    let data = serialize(cfg).map_err(|e| s3_error!(InternalError, "serialize notification config failed: {e}"))?;
+
+    let ns = rustfs_notify::global::notification_system();
+    let region = notification_region();
+    let arn_list = match &ns {
+        Some(ns) => ns.notifier.get_arn_list(&region).await,
+        None => Vec::new(),
+    };
+
+    let config = BucketNotificationConfig::from_xml(Cursor::new(data), &region, &arn_list)
+        .map_err(|e| s3_error!(InvalidArgument, "invalid notification configuration: {e}"))?;
+
+    if let Some(ns) = ns {
+        ns.notifier.add_rules_map(bucket, config.rules).await;
+    }
+    Ok(())
+}
+
+/// Loads the notification rules of every bucket, run once the notification system is up
+pub async fn load_all_bucket_notifications(store: Arc<ECStore>) {
+    let buckets = match store.list_bucket(&BucketOptions::default()).await {
+        Ok(buckets) => buckets,
+        Err(err) => {
+            warn!("list buckets for notification rules failed: {}", err);
+            return;
+        }
+    };
+
+    for bucket in buckets {
+        let cfg = match metadata_sys::get_notification_config(&bucket.name).await {
+            Ok(Some(cfg)) => cfg,
+            Ok(None) => continue,
+            Err(err) => {
+                warn!("get notification config of {} failed: {:?}", bucket.name, err);
+                continue;
+            }
+        };
+        // A target removed since the configuration was written only disables that bucket
+        match load_bucket_notification(&bucket.name, &cfg).await {
+            Ok(()) => info!("loaded notification rules of bucket {}", bucket.name),
+            Err(err) => warn!("load notification rules of {} failed: {}", bucket.name, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::dto::{FilterRule, FilterRuleName, NotificationConfigurationFilter, QueueConfiguration, S3KeyFilter};
+
+    #[tokio::test]
+    async fn test_load_bucket_notification_validates_arn() {
+        assert!(
+            load_bucket_notification("bucket", &NotificationConfiguration::default())
+                .await
+                .is_ok()
+        );
+
+        let cfg = NotificationConfiguration {
+            queue_configurations: Some(vec![QueueConfiguration {
+                events: vec!["s3:ObjectCreated:*".to_string().into()],
+                filter: Some(NotificationConfigurationFilter {
+                    key: Some(S3KeyFilter {
+                        filter_rules: Some(vec![FilterRule {
+                            name: Some(FilterRuleName::from_static(FilterRuleName::PREFIX)),
+                            value: Some("images/".to_string()),
+                        }]),
+                    }),
+                }),
+                id: None,
+                queue_arn: "arn:rustfs:sqs::1:webhook".to_string(),
+            }]),
+            ..Default::default()
+        };
+        // No target is registered, so the ARN is unknown
+        let err = load_bucket_notification("bucket", &cfg).await.unwrap_err();
+        assert_eq!(*err.code(), s3s::S3ErrorCode::InvalidArgument);
+        assert!(err.to_string().contains("arn:rustfs:sqs::1:webhook"), "{err}");
+    }
+}
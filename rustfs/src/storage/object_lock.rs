@@ -0,0 +1,239 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::error::ApiError;
+use http::{HeaderMap, HeaderValue};
+use rustfs_ecstore::bucket::metadata_sys;
+use rustfs_ecstore::bucket::object_lock::ObjectLockApi;
+use rustfs_ecstore::bucket::object_lock::objectlock::{
+    self, ERR_INVALID_RETENTION_DATE, ERR_OBJECTLOCK_INVALID_HEADERS, ERR_PAST_OBJECTLOCK_RETAIN_DATE,
+    ERR_UNKNOWN_LEGAL_HOLD_STATUS, ERR_UNKNOWN_WORMMODE_DIRECTIVE, ObjectLockError, Retention,
+};
+use rustfs_ecstore::bucket::object_lock::objectlock_sys::check_retention_for_deletion;
+use rustfs_ecstore::cmd::bucket_replication::{
+    OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP, OBJECT_LOCK_RETENTION_TIMESTAMP, set_metadata_timestamp,
+};
+use rustfs_ecstore::error::{is_err_object_not_found, is_err_version_not_found};
+use rustfs_ecstore::new_object_layer_fn;
+use rustfs_ecstore::store_api::{ObjectOptions, StorageAPI};
+use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
+use s3s::dto::ObjectLockConfiguration;
+use s3s::header::{X_AMZ_OBJECT_LOCK_LEGAL_HOLD, X_AMZ_OBJECT_LOCK_MODE, X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE};
+use s3s::{S3Error, S3ErrorCode, S3Result};
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+/// S3 error of a refused object lock request
+pub fn object_lock_error(err: ObjectLockError) -> S3Error {
+    let code = match err {
+        ObjectLockError::Protected => S3ErrorCode::AccessDenied,
+        ObjectLockError::NotEnabled | ObjectLockError::InvalidRequest(_) => S3ErrorCode::InvalidRequest,
+        ObjectLockError::MalformedConfig(_) => S3ErrorCode::MalformedXML,
+    };
+    S3Error::with_message(code, err.to_string())
+}
+
+/// Object lock configuration of `bucket`, `None` unless object lock is enabled for it
+pub async fn bucket_object_lock(bucket: &str) -> Option<ObjectLockConfiguration> {
+    metadata_sys::get_object_lock_config(bucket)
+        .await
+        .ok()
+        .map(|(config, _)| config)
+        .filter(|config| config.enabled())
+}
+
+/// Remove the object lock of a source object from the metadata of its copy
+pub fn clear_object_lock_metadata(meta: &mut HashMap<String, String>) {
+    for key in [
+        X_AMZ_OBJECT_LOCK_MODE.as_str().to_string(),
+        X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE.as_str().to_string(),
+        X_AMZ_OBJECT_LOCK_LEGAL_HOLD.as_str().to_string(),
+        format!("{RESERVED_METADATA_PREFIX_LOWER}{OBJECT_LOCK_RETENTION_TIMESTAMP}"),
+        format!("{RESERVED_METADATA_PREFIX_LOWER}{OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP}"),
+    ] {
+        meta.remove(&key);
+    }
+}
+
+/// Resolve the object lock metadata of an upload to `bucket`
+///
+/// Uploads without lock headers get the bucket default retention. Lock headers are refused on
+/// buckets without object lock.
+pub async fn resolve_object_lock(bucket: &str, headers: &HeaderMap<HeaderValue>) -> S3Result<HashMap<String, String>> {
+    let config = bucket_object_lock(bucket).await;
+    resolve_lock(headers, config.as_ref(), OffsetDateTime::now_utc()).map_err(object_lock_error)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap<HeaderValue>, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn resolve_lock(
+    headers: &HeaderMap<HeaderValue>,
+    config: Option<&ObjectLockConfiguration>,
+    now: OffsetDateTime,
+) -> Result<HashMap<String, String>, ObjectLockError> {
+    let mode = header_str(headers, X_AMZ_OBJECT_LOCK_MODE.as_str());
+    let retain_until = header_str(headers, X_AMZ_OBJECT_LOCK_RETAIN_UNTIL_DATE.as_str());
+    let legal_hold = header_str(headers, X_AMZ_OBJECT_LOCK_LEGAL_HOLD.as_str());
+
+    let mut meta = HashMap::new();
+    let Some(config) = config else {
+        if mode.is_some() || retain_until.is_some() || legal_hold.is_some() {
+            return Err(ObjectLockError::NotEnabled);
+        }
+        return Ok(meta);
+    };
+
+    let retention = match (mode, retain_until) {
+        (Some(mode), Some(retain_until)) => {
+            let mode = objectlock::parse_ret_mode(mode).ok_or(ObjectLockError::InvalidRequest(ERR_UNKNOWN_WORMMODE_DIRECTIVE))?;
+            let retain_until = objectlock::parse_retain_until_date(retain_until)
+                .ok_or(ObjectLockError::InvalidRequest(ERR_INVALID_RETENTION_DATE))?;
+            if retain_until <= now {
+                return Err(ObjectLockError::InvalidRequest(ERR_PAST_OBJECTLOCK_RETAIN_DATE));
+            }
+            Some(Retention { mode, retain_until })
+        }
+        (None, None) => config
+            .rule
+            .as_ref()
+            .and_then(|rule| rule.default_retention.as_ref())
+            .and_then(|retention| Retention::from_default(retention, now)),
+        _ => return Err(ObjectLockError::InvalidRequest(ERR_OBJECTLOCK_INVALID_HEADERS)),
+    };
+    if let Some(retention) = retention {
+        retention.write_meta(&mut meta);
+        set_metadata_timestamp(&mut meta, OBJECT_LOCK_RETENTION_TIMESTAMP, now);
+    }
+
+    if let Some(legal_hold) = legal_hold {
+        let status = objectlock::parse_legalhold_status(legal_hold)
+            .ok_or(ObjectLockError::InvalidRequest(ERR_UNKNOWN_LEGAL_HOLD_STATUS))?;
+        meta.insert(X_AMZ_OBJECT_LOCK_LEGAL_HOLD.as_str().to_string(), status.as_str().to_string());
+        set_metadata_timestamp(&mut meta, OBJECT_LOCK_LEGAL_HOLD_TIMESTAMP, now);
+    }
+    Ok(meta)
+}
+
+/// Refuse to write over or delete the version of `key` that `opts` address if it is WORM protected
+///
+/// Writes and deletes that only add a version or a delete marker leave existing versions intact.
+pub async fn check_version_unprotected(bucket: &str, key: &str, opts: &ObjectOptions, bypass_governance: bool) -> S3Result<()> {
+    if opts.versioned && opts.version_id.is_none() {
+        return Ok(());
+    }
+    if bucket_object_lock(bucket).await.is_none() {
+        return Ok(());
+    }
+    let Some(store) = new_object_layer_fn() else {
+        return Err(S3Error::with_message(S3ErrorCode::InternalError, "Not init".to_string()));
+    };
+
+    let info_opts = ObjectOptions {
+        version_id: opts.version_id.clone(),
+        versioned: opts.versioned,
+        version_suspended: opts.version_suspended,
+        ..Default::default()
+    };
+    let info = match store.get_object_info(bucket, key, &info_opts).await {
+        Ok(info) => info,
+        Err(err) if is_err_object_not_found(&err) || is_err_version_not_found(&err) => return Ok(()),
+        Err(err) => return Err(ApiError::from(err).into()),
+    };
+    check_retention_for_deletion(&info, bypass_governance, OffsetDateTime::now_utc()).map_err(object_lock_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::dto::{DefaultRetention, ObjectLockEnabled, ObjectLockRetentionMode, ObjectLockRule};
+
+    fn locked_bucket(default_days: Option<i32>) -> ObjectLockConfiguration {
+        ObjectLockConfiguration {
+            object_lock_enabled: Some(ObjectLockEnabled::from_static(ObjectLockEnabled::ENABLED)),
+            rule: default_days.map(|days| ObjectLockRule {
+                default_retention: Some(DefaultRetention {
+                    mode: Some(ObjectLockRetentionMode::from_static(ObjectLockRetentionMode::GOVERNANCE)),
+                    days: Some(days),
+                    years: None,
+                }),
+            }),
+        }
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap<HeaderValue> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_resolve_lock_headers() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let config = locked_bucket(None);
+
+        let meta = resolve_lock(
+            &headers(&[
+                ("x-amz-object-lock-mode", "COMPLIANCE"),
+                ("x-amz-object-lock-retain-until-date", "2030-01-01T00:00:00Z"),
+                ("x-amz-object-lock-legal-hold", "ON"),
+            ]),
+            Some(&config),
+            now,
+        )
+        .unwrap();
+        let retention = Retention::from_meta(&meta).unwrap();
+        assert!(retention.is_compliance());
+        assert!(objectlock::legal_hold_on(&meta));
+
+        assert_eq!(
+            resolve_lock(&headers(&[("x-amz-object-lock-mode", "COMPLIANCE")]), Some(&config), now),
+            Err(ObjectLockError::InvalidRequest(ERR_OBJECTLOCK_INVALID_HEADERS))
+        );
+        assert_eq!(
+            resolve_lock(
+                &headers(&[
+                    ("x-amz-object-lock-mode", "GOVERNANCE"),
+                    ("x-amz-object-lock-retain-until-date", "2020-01-01T00:00:00Z"),
+                ]),
+                Some(&config),
+                now,
+            ),
+            Err(ObjectLockError::InvalidRequest(ERR_PAST_OBJECTLOCK_RETAIN_DATE))
+        );
+        assert_eq!(
+            resolve_lock(&headers(&[("x-amz-object-lock-legal-hold", "ON")]), None, now),
+            Err(ObjectLockError::NotEnabled)
+        );
+    }
+
+    #[test]
+    fn test_resolve_lock_bucket_default() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        assert!(resolve_lock(&HeaderMap::new(), None, now).unwrap().is_empty());
+        assert!(
+            resolve_lock(&HeaderMap::new(), Some(&locked_bucket(None)), now)
+                .unwrap()
+                .is_empty()
+        );
+
+        let meta = resolve_lock(&HeaderMap::new(), Some(&locked_bucket(Some(10))), now).unwrap();
+        let retention = Retention::from_meta(&meta).unwrap();
+        assert!(!retention.is_compliance());
+        assert_eq!(retention.retain_until, now + time::Duration::days(10));
+    }
+}
@@ -14,14 +14,71 @@
 
 use http::{HeaderMap, HeaderValue};
 use rustfs_ecstore::bucket::versioning_sys::BucketVersioningSys;
+use rustfs_ecstore::cmd::bucket_replication::{REPLICA_STATUS, REPLICA_TIMESTAMP, ReplicationStatusType};
 use rustfs_ecstore::error::Result;
 use rustfs_ecstore::error::StorageError;
 use rustfs_ecstore::store_api::ObjectOptions;
+use rustfs_filemeta::NULL_VERSION_ID;
+use rustfs_filemeta::headers::{
+    RESERVED_METADATA_PREFIX_LOWER, RUSTFS_SOURCE_DELETE_MARKER, RUSTFS_SOURCE_MTIME, RUSTFS_SOURCE_REPLICATION_REQUEST,
+};
 use rustfs_utils::path::is_dir_object;
 use std::collections::HashMap;
 use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use uuid::Uuid;
 
+/// Environment variable holding the seconds an S3 request may wait for its locks, unlimited when unset or 0
+pub const ENV_API_REQUEST_TIMEOUT: &str = "RUSTFS_API_REQUEST_TIMEOUT";
+
+static REQUEST_TIMEOUT: LazyLock<Option<Duration>> = LazyLock::new(|| {
+    std::env::var(ENV_API_REQUEST_TIMEOUT)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+});
+
+/// When a request starting now times out, handed down to the lockers so remote ones stop waiting too
+pub fn request_deadline() -> Option<Instant> {
+    REQUEST_TIMEOUT.map(|timeout| Instant::now() + timeout)
+}
+
+/// Parse the version id of a request, the null version being the nil version id
+pub fn parse_version_id(vid: &str) -> Option<Uuid> {
+    if vid == NULL_VERSION_ID {
+        Some(Uuid::nil())
+    } else {
+        Uuid::parse_str(vid).ok()
+    }
+}
+
+/// Version id as S3 shows it, `null` for the null version
+pub fn s3_version_id(vid: Option<String>) -> Option<String> {
+    vid.map(|v| match Uuid::parse_str(&v) {
+        Ok(id) if id.is_nil() => NULL_VERSION_ID.to_string(),
+        _ => v,
+    })
+}
+
+/// Version id a request addresses
+///
+/// Version ids other than `null` only exist in buckets that are or were versioned.
+fn request_version_id(bucket: &str, object: &str, vid: Option<String>, versioned: bool) -> Result<Option<String>> {
+    let Some(vid) = vid.map(|v| v.trim().to_owned()) else {
+        return Ok(None);
+    };
+    let Some(id) = parse_version_id(&vid) else {
+        return Err(StorageError::InvalidVersionID(bucket.to_owned(), object.to_owned(), vid));
+    };
+    if !id.is_nil() && !versioned {
+        return Err(StorageError::InvalidArgument(bucket.to_owned(), object.to_owned(), vid));
+    }
+    Ok(Some(id.to_string()))
+}
+
 /// Creates options for deleting an object in a bucket.
 pub async fn del_opts(
     bucket: &str,
@@ -35,17 +92,7 @@ pub async fn del_opts(
 
     // TODO: delete_prefix
 
-    let vid = vid.map(|v| v.as_str().trim().to_owned());
-
-    if let Some(ref id) = vid {
-        if let Err(_err) = Uuid::parse_str(id.as_str()) {
-            return Err(StorageError::InvalidVersionID(bucket.to_owned(), object.to_owned(), id.clone()));
-        }
-
-        if !versioned {
-            return Err(StorageError::InvalidArgument(bucket.to_owned(), object.to_owned(), id.clone()));
-        }
-    }
+    let vid = request_version_id(bucket, object, vid, versioned || version_suspended)?;
 
     let mut opts = put_opts_from_headers(headers, metadata.clone())
         .map_err(|err| StorageError::InvalidArgument(bucket.to_owned(), object.to_owned(), err.to_string()))?;
@@ -60,6 +107,11 @@ pub async fn del_opts(
     opts.version_suspended = version_suspended;
     opts.versioned = versioned;
 
+    if opts.replication_request && headers.contains_key(RUSTFS_SOURCE_DELETE_MARKER) {
+        opts.delete_marker = true;
+        opts.mod_time = source_mtime(headers);
+    }
+
     Ok(opts)
 }
 
@@ -74,17 +126,7 @@ pub async fn get_opts(
     let versioned = BucketVersioningSys::prefix_enabled(bucket, object).await;
     let version_suspended = BucketVersioningSys::prefix_suspended(bucket, object).await;
 
-    let vid = vid.map(|v| v.as_str().trim().to_owned());
-
-    if let Some(ref id) = vid {
-        if let Err(_err) = Uuid::parse_str(id.as_str()) {
-            return Err(StorageError::InvalidVersionID(bucket.to_owned(), object.to_owned(), id.clone()));
-        }
-
-        if !versioned {
-            return Err(StorageError::InvalidArgument(bucket.to_owned(), object.to_owned(), id.clone()));
-        }
-    }
+    let vid = request_version_id(bucket, object, vid, versioned || version_suspended)?;
 
     let mut opts = get_default_opts(headers, HashMap::new(), false)
         .map_err(|err| StorageError::InvalidArgument(bucket.to_owned(), object.to_owned(), err.to_string()))?;
@@ -116,21 +158,23 @@ pub async fn put_opts(
     let versioned = BucketVersioningSys::prefix_enabled(bucket, object).await;
     let version_suspended = BucketVersioningSys::prefix_suspended(bucket, object).await;
 
-    let vid = vid.map(|v| v.as_str().trim().to_owned());
-
-    if let Some(ref id) = vid {
-        if let Err(_err) = Uuid::parse_str(id.as_str()) {
-            return Err(StorageError::InvalidVersionID(bucket.to_owned(), object.to_owned(), id.clone()));
-        }
-
-        if !versioned {
-            return Err(StorageError::InvalidArgument(bucket.to_owned(), object.to_owned(), id.clone()));
-        }
-    }
+    let vid = request_version_id(bucket, object, vid, versioned || version_suspended)?;
 
     let mut opts = put_opts_from_headers(headers, metadata)
         .map_err(|err| StorageError::InvalidArgument(bucket.to_owned(), object.to_owned(), err.to_string()))?;
 
+    if opts.replication_request {
+        opts.user_defined.insert(
+            format!("{RESERVED_METADATA_PREFIX_LOWER}{REPLICA_STATUS}"),
+            ReplicationStatusType::Replica.as_str().to_owned(),
+        );
+        opts.user_defined.insert(
+            format!("{RESERVED_METADATA_PREFIX_LOWER}{REPLICA_TIMESTAMP}"),
+            OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+        );
+        opts.mod_time = source_mtime(headers);
+    }
+
     opts.version_id = {
         if is_dir_object(object) && vid.is_none() {
             Some(Uuid::max().to_string())
@@ -165,16 +209,34 @@ pub fn put_opts_from_headers(headers: &HeaderMap<HeaderValue>, metadata: HashMap
 
 /// Creates default options for getting an object from a bucket.
 pub fn get_default_opts(
-    _headers: &HeaderMap<HeaderValue>,
+    headers: &HeaderMap<HeaderValue>,
     metadata: HashMap<String, String>,
     _copy_source: bool,
 ) -> Result<ObjectOptions> {
     Ok(ObjectOptions {
         user_defined: metadata,
+        replication_request: is_replication_request(headers),
         ..Default::default()
     })
 }
 
+/// Whether a request comes from the replication of a source bucket, which is not replicated again
+pub fn is_replication_request(headers: &HeaderMap<HeaderValue>) -> bool {
+    headers.contains_key(RUSTFS_SOURCE_REPLICATION_REQUEST)
+}
+
+/// Modification time of the source version of a replication request, kept by the replica so both sites order
+/// their versions the same way
+pub fn source_mtime(headers: &HeaderMap<HeaderValue>) -> Option<OffsetDateTime> {
+    if !is_replication_request(headers) {
+        return None;
+    }
+    headers
+        .get(RUSTFS_SOURCE_MTIME)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| OffsetDateTime::parse(v, &Rfc3339).ok())
+}
+
 /// Extracts metadata from headers and returns it as a HashMap.
 pub fn extract_metadata(headers: &HeaderMap<HeaderValue>) -> HashMap<String, String> {
     let mut metadata = HashMap::new();
@@ -265,6 +327,25 @@ mod tests {
         assert_eq!(opts.version_id, None);
     }
 
+    #[tokio::test]
+    async fn test_del_opts_replicated_delete_marker() {
+        let mut headers = create_test_headers();
+        headers.insert(RUSTFS_SOURCE_DELETE_MARKER, HeaderValue::from_static("true"));
+        headers.insert(RUSTFS_SOURCE_MTIME, HeaderValue::from_static("2025-01-02T03:04:05Z"));
+        let opts = del_opts("test-bucket", "test-object", None, &headers, HashMap::new())
+            .await
+            .unwrap();
+        assert!(!opts.delete_marker);
+        assert!(opts.mod_time.is_none());
+
+        headers.insert(RUSTFS_SOURCE_REPLICATION_REQUEST, HeaderValue::from_static("true"));
+        let opts = del_opts("test-bucket", "test-object", None, &headers, HashMap::new())
+            .await
+            .unwrap();
+        assert!(opts.delete_marker);
+        assert_eq!(opts.mod_time.unwrap().unix_timestamp(), 1735787045);
+    }
+
     #[tokio::test]
     async fn test_del_opts_with_directory_object() {
         let headers = create_test_headers();
@@ -315,6 +396,22 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_null_version_id() {
+        let headers = create_test_headers();
+
+        // the null version is addressable whether or not the bucket is versioned
+        let opts = get_opts("test-bucket", "test-object", Some("null".to_string()), None, &headers)
+            .await
+            .unwrap();
+        assert_eq!(opts.version_id, Some(Uuid::nil().to_string()));
+
+        let opts = del_opts("test-bucket", "test-object", Some("null".to_string()), &headers, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(opts.version_id, Some(Uuid::nil().to_string()));
+    }
+
     #[tokio::test]
     async fn test_get_opts_basic() {
         let headers = create_test_headers();
@@ -477,6 +574,31 @@ mod tests {
         assert!(opts.user_defined.is_empty());
     }
 
+    #[test]
+    fn test_get_default_opts_replication_request() {
+        let mut headers = create_test_headers();
+        assert!(!get_default_opts(&headers, HashMap::new(), false).unwrap().replication_request);
+
+        headers.insert(RUSTFS_SOURCE_REPLICATION_REQUEST, HeaderValue::from_static("true"));
+        let opts = get_default_opts(&headers, HashMap::new(), false).unwrap();
+        assert!(opts.replication_request);
+    }
+
+    #[test]
+    fn test_source_mtime() {
+        let mut headers = create_test_headers();
+        headers.insert(RUSTFS_SOURCE_MTIME, HeaderValue::from_static("2025-01-02T03:04:05.123456789Z"));
+        assert!(source_mtime(&headers).is_none());
+
+        headers.insert(RUSTFS_SOURCE_REPLICATION_REQUEST, HeaderValue::from_static("true"));
+        let mtime = source_mtime(&headers).unwrap();
+        assert_eq!(mtime.unix_timestamp(), 1735787045);
+        assert_eq!(mtime.nanosecond(), 123456789);
+
+        headers.insert(RUSTFS_SOURCE_MTIME, HeaderValue::from_static("yesterday"));
+        assert!(source_mtime(&headers).is_none());
+    }
+
     #[test]
     fn test_extract_metadata_basic() {
         let headers = create_test_headers();
@@ -0,0 +1,328 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Policies of browser-based POST uploads
+//!
+//! s3s checks the signature of the policy document; the expiration and the conditions the
+//! document puts on the form fields and the uploaded file are checked here.
+
+use base64::Engine;
+use s3s::{S3Error, S3ErrorCode, S3Result, s3_error};
+use serde_json::Value;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// Form fields that need no condition in the policy
+const UNCONDITIONED_FIELDS: &[&str] = &["policy", "x-amz-signature", "signature", "awsaccesskeyid", "file"];
+
+/// Form of a POST upload, field names are lowercase
+#[derive(Debug, Default)]
+pub struct PostForm {
+    pub fields: Vec<(String, String)>,
+    pub file_size: usize,
+}
+
+impl PostForm {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+fn find(data: &[u8], pattern: &[u8], from: usize) -> Option<usize> {
+    data.get(from..)?
+        .windows(pattern.len())
+        .position(|w| w == pattern)
+        .map(|pos| pos + from)
+}
+
+fn disposition_param<'a>(headers: &'a str, param: &str) -> Option<&'a str> {
+    let disposition = headers
+        .lines()
+        .find(|line| line.to_ascii_lowercase().starts_with("content-disposition:"))?;
+    disposition.split(';').find_map(|part| {
+        let (name, value) = part.trim().split_once('=')?;
+        name.eq_ignore_ascii_case(param).then(|| value.trim_matches('"'))
+    })
+}
+
+/// Parse the multipart/form-data `body` of a POST upload
+pub fn parse_post_form(body: &[u8], boundary: &str) -> S3Result<PostForm> {
+    let malformed = || {
+        s3_error!(
+            MalformedPOSTRequest,
+            "The body of the POST request is not well-formed multipart/form-data"
+        )
+    };
+    let delimiter = format!("--{boundary}").into_bytes();
+    let next_part = format!("\r\n--{boundary}").into_bytes();
+
+    let mut form = PostForm::default();
+    let mut pos = find(body, &delimiter, 0).ok_or_else(malformed)? + delimiter.len();
+    loop {
+        if body[pos..].starts_with(b"--") {
+            return Ok(form);
+        }
+        let headers_start = pos + 2;
+        let headers_end = find(body, b"\r\n\r\n", headers_start).ok_or_else(malformed)?;
+        let headers = std::str::from_utf8(&body[headers_start..headers_end]).map_err(|_| malformed())?;
+        let content_start = headers_end + 4;
+        let content_end = find(body, &next_part, content_start).ok_or_else(malformed)?;
+
+        let name = disposition_param(headers, "name").ok_or_else(malformed)?.to_ascii_lowercase();
+        if name == "file" {
+            form.file_size = content_end - content_start;
+        } else {
+            let value = std::str::from_utf8(&body[content_start..content_end]).map_err(|_| malformed())?;
+            form.fields.push((name, value.to_string()));
+        }
+        pos = content_end + next_part.len();
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Condition {
+    Eq(String, String),
+    StartsWith(String, String),
+    ContentLengthRange(i64, i64),
+}
+
+/// Decoded POST policy document
+#[derive(Debug)]
+pub struct PostPolicy {
+    expiration: OffsetDateTime,
+    conditions: Vec<Condition>,
+}
+
+fn invalid_policy(msg: &str) -> S3Error {
+    S3Error::with_message(S3ErrorCode::InvalidPolicyDocument, format!("Invalid Policy: {msg}"))
+}
+
+fn policy_failed(msg: String) -> S3Error {
+    S3Error::with_message(S3ErrorCode::AccessDenied, format!("Invalid according to Policy: {msg}"))
+}
+
+fn condition_field(name: &Value) -> Result<String, S3Error> {
+    let name = name
+        .as_str()
+        .ok_or_else(|| invalid_policy("condition field must be a string"))?;
+    Ok(name.trim_start_matches('$').to_ascii_lowercase())
+}
+
+fn condition_value(value: &Value) -> Result<String, S3Error> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        _ => Err(invalid_policy("condition value must be a string")),
+    }
+}
+
+impl PostPolicy {
+    /// Decode the base64-encoded policy field of a POST upload
+    pub fn parse(policy: &str) -> S3Result<Self> {
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(policy.trim())
+            .map_err(|_| invalid_policy("policy is not valid base64"))?;
+        let doc: Value = serde_json::from_slice(&data).map_err(|_| invalid_policy("policy is not a JSON object"))?;
+
+        let expiration = doc
+            .get("expiration")
+            .and_then(Value::as_str)
+            .and_then(|s| OffsetDateTime::parse(s, &Rfc3339).ok())
+            .ok_or_else(|| invalid_policy("policy misses a valid expiration"))?;
+
+        let mut conditions = Vec::new();
+        let items = doc
+            .get("conditions")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid_policy("policy misses conditions"))?;
+        for item in items {
+            match item {
+                Value::Object(map) => {
+                    for (name, value) in map {
+                        conditions.push(Condition::Eq(name.to_ascii_lowercase(), condition_value(value)?));
+                    }
+                }
+                Value::Array(args) if args.len() == 3 => {
+                    let op = args[0].as_str().unwrap_or_default().to_ascii_lowercase();
+                    match op.as_str() {
+                        "eq" => conditions.push(Condition::Eq(condition_field(&args[1])?, condition_value(&args[2])?)),
+                        "starts-with" => {
+                            conditions.push(Condition::StartsWith(condition_field(&args[1])?, condition_value(&args[2])?))
+                        }
+                        "content-length-range" => {
+                            let bound = |v: &Value| {
+                                condition_value(v)?
+                                    .parse::<i64>()
+                                    .map_err(|_| invalid_policy("content-length-range bounds must be integers"))
+                            };
+                            conditions.push(Condition::ContentLengthRange(bound(&args[1])?, bound(&args[2])?));
+                        }
+                        _ => return Err(invalid_policy(&format!("unknown condition {op}"))),
+                    }
+                }
+                _ => return Err(invalid_policy("condition must be an object or an array of three")),
+            }
+        }
+
+        Ok(Self { expiration, conditions })
+    }
+
+    /// Check a POST upload to `bucket` against the policy at `now`
+    pub fn check(&self, bucket: &str, form: &PostForm, now: OffsetDateTime) -> S3Result<()> {
+        if now > self.expiration {
+            return Err(policy_failed("Policy expired.".to_string()));
+        }
+
+        let value = |name: &str| {
+            if name == "bucket" {
+                bucket
+            } else {
+                form.field(name).unwrap_or_default()
+            }
+        };
+        for condition in &self.conditions {
+            match condition {
+                Condition::Eq(name, expected) if value(name) != expected => {
+                    return Err(policy_failed(format!("Policy Condition failed: [\"eq\", \"${name}\", \"{expected}\"]")));
+                }
+                Condition::StartsWith(name, prefix) if !value(name).starts_with(prefix.as_str()) => {
+                    return Err(policy_failed(format!(
+                        "Policy Condition failed: [\"starts-with\", \"${name}\", \"{prefix}\"]"
+                    )));
+                }
+                Condition::ContentLengthRange(min, _) if (form.file_size as i64) < *min => {
+                    return Err(s3_error!(EntityTooSmall, "Your proposed upload is smaller than the minimum allowed size"));
+                }
+                Condition::ContentLengthRange(_, max) if (form.file_size as i64) > *max => {
+                    return Err(s3_error!(EntityTooLarge, "Your proposed upload exceeds the maximum allowed size"));
+                }
+                _ => {}
+            }
+        }
+
+        for (name, _) in &form.fields {
+            if UNCONDITIONED_FIELDS.contains(&name.as_str()) || name.starts_with("x-ignore-") {
+                continue;
+            }
+            let covered = self.conditions.iter().any(|c| match c {
+                Condition::Eq(field, _) | Condition::StartsWith(field, _) => field == name,
+                Condition::ContentLengthRange(..) => false,
+            });
+            if !covered {
+                return Err(policy_failed(format!("Extra input fields: {name}")));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form_body(fields: &[(&str, &str)], file: &str) -> Vec<u8> {
+        let mut body = String::new();
+        for (name, value) in fields {
+            body.push_str(&format!("--XyZ\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"));
+        }
+        body.push_str(&format!(
+            "--XyZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\n{file}\r\n--XyZ--\r\n"
+        ));
+        body.into_bytes()
+    }
+
+    fn policy(doc: &str) -> PostPolicy {
+        PostPolicy::parse(&base64::engine::general_purpose::STANDARD.encode(doc)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_post_form() {
+        let body = form_body(&[("Key", "uploads/a.txt"), ("x-amz-meta-tag", "v")], "hello world");
+        let form = parse_post_form(&body, "XyZ").unwrap();
+        assert_eq!(form.field("key"), Some("uploads/a.txt"));
+        assert_eq!(form.field("x-amz-meta-tag"), Some("v"));
+        assert_eq!(form.file_size, 11);
+
+        assert!(parse_post_form(b"not multipart", "XyZ").is_err());
+    }
+
+    #[test]
+    fn test_post_policy_conditions() {
+        let policy = policy(
+            r#"{"expiration":"2030-01-01T00:00:00.000Z","conditions":[
+                {"bucket":"photos"},
+                ["starts-with","$key","uploads/"],
+                ["eq","$x-amz-meta-tag","v"],
+                ["content-length-range",1,10]
+            ]}"#,
+        );
+        let now = OffsetDateTime::parse("2025-01-01T00:00:00Z", &Rfc3339).unwrap();
+        let form = |key: &str, file: &str| {
+            parse_post_form(&form_body(&[("key", key), ("x-amz-meta-tag", "v"), ("policy", "p")], file), "XyZ").unwrap()
+        };
+
+        assert!(policy.check("photos", &form("uploads/a.txt", "hello"), now).is_ok());
+        assert_eq!(
+            *policy
+                .check("other", &form("uploads/a.txt", "hello"), now)
+                .unwrap_err()
+                .code(),
+            S3ErrorCode::AccessDenied
+        );
+        assert_eq!(
+            *policy
+                .check("photos", &form("private/a.txt", "hello"), now)
+                .unwrap_err()
+                .code(),
+            S3ErrorCode::AccessDenied
+        );
+        assert_eq!(
+            *policy.check("photos", &form("uploads/a.txt", ""), now).unwrap_err().code(),
+            S3ErrorCode::EntityTooSmall
+        );
+        assert_eq!(
+            *policy
+                .check("photos", &form("uploads/a.txt", "hello world"), now)
+                .unwrap_err()
+                .code(),
+            S3ErrorCode::EntityTooLarge
+        );
+
+        // Every field needs a condition
+        let extra = parse_post_form(
+            &form_body(&[("key", "uploads/a"), ("x-amz-meta-tag", "v"), ("acl", "public-read")], "x"),
+            "XyZ",
+        )
+        .unwrap();
+        assert!(policy.check("photos", &extra, now).is_err());
+        let ignored = parse_post_form(
+            &form_body(&[("key", "uploads/a"), ("x-amz-meta-tag", "v"), ("x-ignore-me", "1")], "x"),
+            "XyZ",
+        )
+        .unwrap();
+        assert!(policy.check("photos", &ignored, now).is_ok());
+
+        let expired = OffsetDateTime::parse("2031-01-01T00:00:00Z", &Rfc3339).unwrap();
+        assert!(policy.check("photos", &form("uploads/a.txt", "hello"), expired).is_err());
+    }
+
+    #[test]
+    fn test_invalid_post_policy() {
+        let encode = |doc: &str| base64::engine::general_purpose::STANDARD.encode(doc);
+        assert!(PostPolicy::parse("%%%").is_err());
+        assert!(PostPolicy::parse(&encode(r#"{"conditions":[]}"#)).is_err());
+        assert!(PostPolicy::parse(&encode(r#"{"expiration":"2030-01-01T00:00:00Z","conditions":[["in","$key","a"]]}"#)).is_err());
+    }
+}
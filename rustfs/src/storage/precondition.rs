@@ -0,0 +1,218 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conditional reads: `If-Match`, `If-None-Match`, `If-Modified-Since` and `If-Unmodified-Since` on the
+//! object of a GET or HEAD, and their `x-amz-copy-source-if-*` forms on the source of a copy
+//!
+//! The conditions of writes are checked by the object layer under the object lock, see `WritePreconditions`.
+
+use rustfs_ecstore::store_api::{ObjectInfo, WritePreconditions, etag_matches};
+use s3s::dto::Timestamp;
+use s3s::{S3Result, s3_error};
+use time::OffsetDateTime;
+
+/// Conditions a request puts on the object it reads
+#[derive(Debug, Default, Clone)]
+pub struct Preconditions {
+    pub if_match: Option<String>,
+    pub if_none_match: Option<String>,
+    pub if_modified_since: Option<Timestamp>,
+    pub if_unmodified_since: Option<Timestamp>,
+}
+
+/// Which condition of a request does not hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    /// `If-Match` or `If-Unmodified-Since`, the object is not the one the client expects
+    Changed,
+    /// `If-None-Match` or `If-Modified-Since`, the object is the one the client already has
+    NotChanged,
+}
+
+impl Preconditions {
+    /// Checks the object of a GET or HEAD, which is not modified (304) when it is the one the client already has
+    pub fn check_read(&self, info: &ObjectInfo) -> S3Result<()> {
+        match self.evaluate(info) {
+            None => Ok(()),
+            Some(Failure::Changed) => Err(s3_error!(PreconditionFailed)),
+            Some(Failure::NotChanged) => Err(s3_error!(NotModified)),
+        }
+    }
+
+    /// Checks the source of a copy, which fails with 412 whichever condition does not hold
+    pub fn check_copy_source(&self, info: &ObjectInfo) -> S3Result<()> {
+        match self.evaluate(info) {
+            None => Ok(()),
+            Some(_) => Err(s3_error!(
+                PreconditionFailed,
+                "At least one of the copy source preconditions did not hold"
+            )),
+        }
+    }
+
+    /// Conditions evaluated in the order of RFC 7232: a matching `If-Match` overrides `If-Unmodified-Since`
+    /// and any `If-None-Match` overrides `If-Modified-Since`
+    fn evaluate(&self, info: &ObjectInfo) -> Option<Failure> {
+        let etag = info.etag.as_deref().unwrap_or_default();
+
+        if let Some(if_match) = &self.if_match {
+            if !etag_matches(if_match, etag) {
+                return Some(Failure::Changed);
+            }
+        } else if let Some(since) = &self.if_unmodified_since {
+            if modified_since(info, since) {
+                return Some(Failure::Changed);
+            }
+        }
+
+        if let Some(if_none_match) = &self.if_none_match {
+            if etag_matches(if_none_match, etag) {
+                return Some(Failure::NotChanged);
+            }
+        } else if let Some(since) = &self.if_modified_since {
+            if !modified_since(info, since) {
+                return Some(Failure::NotChanged);
+            }
+        }
+
+        None
+    }
+}
+
+/// `If-Match`/`If-None-Match` conditions of a PUT or CompleteMultipartUpload, none when it has neither
+pub fn write_preconditions(if_match: Option<String>, if_none_match: Option<String>) -> Option<WritePreconditions> {
+    (if_match.is_some() || if_none_match.is_some()).then_some(WritePreconditions {
+        if_match,
+        if_none_match,
+        mod_time: None,
+    })
+}
+
+/// Whether the object changed after `since`, compared to the second as HTTP dates are
+fn modified_since(info: &ObjectInfo, since: &Timestamp) -> bool {
+    let since = OffsetDateTime::from(since.clone());
+    info.mod_time
+        .is_some_and(|mod_time| mod_time.unix_timestamp() > since.unix_timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s3s::S3ErrorCode;
+    use time::Duration;
+
+    fn object(etag: &str, mod_time: OffsetDateTime) -> ObjectInfo {
+        ObjectInfo {
+            etag: Some(etag.to_owned()),
+            mod_time: Some(mod_time),
+            ..Default::default()
+        }
+    }
+
+    fn code(result: S3Result<()>) -> Option<S3ErrorCode> {
+        result.err().map(|e| e.code().clone())
+    }
+
+    #[test]
+    fn test_check_read() {
+        let now = OffsetDateTime::now_utc();
+        let info = object("abc", now);
+        let before = Some(Timestamp::from(now - Duration::hours(1)));
+        let after = Some(Timestamp::from(now + Duration::hours(1)));
+
+        let check = |p: Preconditions| code(p.check_read(&info));
+
+        assert_eq!(check(Preconditions::default()), None);
+        assert_eq!(
+            check(Preconditions {
+                if_match: Some("\"abc\"".to_owned()),
+                ..Default::default()
+            }),
+            None
+        );
+        assert_eq!(
+            check(Preconditions {
+                if_match: Some("\"def\", \"ghi\"".to_owned()),
+                ..Default::default()
+            }),
+            Some(S3ErrorCode::PreconditionFailed)
+        );
+        assert_eq!(
+            check(Preconditions {
+                if_none_match: Some("*".to_owned()),
+                ..Default::default()
+            }),
+            Some(S3ErrorCode::NotModified)
+        );
+        assert_eq!(
+            check(Preconditions {
+                if_modified_since: after.clone(),
+                ..Default::default()
+            }),
+            Some(S3ErrorCode::NotModified)
+        );
+        assert_eq!(
+            check(Preconditions {
+                if_unmodified_since: before.clone(),
+                ..Default::default()
+            }),
+            Some(S3ErrorCode::PreconditionFailed)
+        );
+
+        // A matching If-Match wins over a failing If-Unmodified-Since, and a failing If-None-Match over
+        // a passing If-Modified-Since
+        assert_eq!(
+            check(Preconditions {
+                if_match: Some("abc".to_owned()),
+                if_unmodified_since: before.clone(),
+                ..Default::default()
+            }),
+            None
+        );
+        assert_eq!(
+            check(Preconditions {
+                if_none_match: Some("abc".to_owned()),
+                if_modified_since: before,
+                ..Default::default()
+            }),
+            Some(S3ErrorCode::NotModified)
+        );
+
+        let copy = Preconditions {
+            if_modified_since: after,
+            ..Default::default()
+        };
+        assert_eq!(code(copy.check_copy_source(&info)), Some(S3ErrorCode::PreconditionFailed));
+    }
+
+    #[test]
+    fn test_write_preconditions() {
+        assert!(write_preconditions(None, None).is_none());
+
+        let info = object("abc", OffsetDateTime::now_utc());
+        let if_none_match = write_preconditions(None, Some("*".to_owned())).unwrap();
+        assert!(if_none_match.check("bucket", "key", None).is_ok());
+        assert!(if_none_match.check("bucket", "key", Some(&info)).is_err());
+
+        let if_match = write_preconditions(Some("\"abc\"".to_owned()), None).unwrap();
+        assert!(if_match.check("bucket", "key", Some(&info)).is_ok());
+        assert!(if_match.check("bucket", "key", None).is_err());
+        assert!(
+            write_preconditions(Some("def".to_owned()), None)
+                .unwrap()
+                .check("bucket", "key", Some(&info))
+                .is_err()
+        );
+    }
+}
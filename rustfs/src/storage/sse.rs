@@ -0,0 +1,594 @@
+// Copyright 2024 RustFS Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::error::ApiError;
+use crate::server::ConnectionInfo;
+use http::{HeaderMap, HeaderValue};
+use rustfs_ecstore::bucket::encryption::keys::{
+    CustomerKey, ERR_MASTER_KEY_NOT_CONFIGURED, ObjectKey, is_customer_encrypted, master_key, rewrap_kms_key,
+};
+use rustfs_ecstore::bucket::encryption::kms::{ERR_KMS_NOT_CONFIGURED, kms};
+use rustfs_ecstore::bucket::encryption::{
+    BucketEncryptionPolicy, ObjectEncryption, SSE_KMS_KEY_ID_META, SSE_TYPE_CUSTOMER, SSE_TYPE_META,
+};
+use rustfs_ecstore::bucket::metadata_sys;
+use rustfs_filemeta::headers::RESERVED_METADATA_PREFIX_LOWER;
+use rustfs_rio::{EncryptReader, HashReader, Reader};
+use rustfs_utils::crypto::base64_decode;
+use s3s::dto::{SSECustomerAlgorithm, SSECustomerKeyMD5, SSEKMSKeyId, ServerSideEncryption, ServerSideEncryptionConfiguration};
+use s3s::{S3Error, S3ErrorCode, S3Result, s3_error};
+use std::collections::{BTreeMap, HashMap};
+
+pub const AMZ_SERVER_SIDE_ENCRYPTION: &str = "x-amz-server-side-encryption";
+pub const AMZ_SERVER_SIDE_ENCRYPTION_KMS_ID: &str = "x-amz-server-side-encryption-aws-kms-key-id";
+pub const AMZ_SERVER_SIDE_ENCRYPTION_KMS_CONTEXT: &str = "x-amz-server-side-encryption-context";
+pub const AMZ_SERVER_SIDE_ENCRYPTION_BUCKET_KEY_ENABLED: &str = "x-amz-server-side-encryption-bucket-key-enabled";
+pub const AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM: &str = "x-amz-server-side-encryption-customer-algorithm";
+pub const AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY: &str = "x-amz-server-side-encryption-customer-key";
+pub const AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY_MD5: &str = "x-amz-server-side-encryption-customer-key-md5";
+pub const AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM: &str =
+    "x-amz-copy-source-server-side-encryption-customer-algorithm";
+pub const AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY: &str = "x-amz-copy-source-server-side-encryption-customer-key";
+pub const AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY_MD5: &str =
+    "x-amz-copy-source-server-side-encryption-customer-key-md5";
+
+/// Resolve the server-side encryption of an upload to `bucket`
+///
+/// Uploads without SSE headers get the bucket default encryption. `None` means the object is
+/// stored unencrypted, which is rejected when the bucket denies unencrypted uploads.
+pub async fn resolve_object_encryption(bucket: &str, headers: &HeaderMap<HeaderValue>) -> S3Result<Option<ObjectEncryption>> {
+    let sse_config = metadata_sys::get_sse_config(bucket).await.ok().map(|(cfg, _)| cfg);
+    let policy = metadata_sys::get_encryption_policy(bucket)
+        .await
+        .map(|(policy, _)| policy)
+        .unwrap_or_default();
+
+    resolve_encryption(headers, sse_config.as_ref(), &policy)
+}
+
+/// Generate the data key of an encrypted upload to `bucket/object` and seal it into `metadata`
+///
+/// Returns `None` when `encryption` is not SSE-S3, SSE-KMS or SSE-C.
+pub async fn new_object_key(
+    encryption: Option<&ObjectEncryption>,
+    bucket: &str,
+    object: &str,
+    metadata: &mut HashMap<String, String>,
+) -> S3Result<Option<ObjectKey>> {
+    let Some(encryption) = encryption else {
+        return Ok(None);
+    };
+
+    if let Some(customer_key) = &encryption.customer_key {
+        let key = ObjectKey::generate();
+        key.seal_customer(customer_key, bucket, object, metadata)
+            .map_err(ApiError::from)?;
+        return Ok(Some(key));
+    }
+
+    if encryption.is_kms() {
+        let Some(kms) = kms() else {
+            return Err(S3Error::with_message(S3ErrorCode::NotImplemented, ERR_KMS_NOT_CONFIGURED.to_string()));
+        };
+        let key_id = encryption.kms_key_id.as_deref().unwrap_or(kms.default_key_id());
+        let key = ObjectKey::generate_kms(kms.as_ref(), key_id, bucket, object, &encryption.kms_context, metadata)
+            .await
+            .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidArgument, e.to_string()))?;
+        return Ok(Some(key));
+    }
+
+    if encryption.sse_type != ServerSideEncryption::AES256 {
+        return Ok(None);
+    }
+    let Some(master) = master_key() else {
+        return Err(S3Error::with_message(
+            S3ErrorCode::NotImplemented,
+            ERR_MASTER_KEY_NOT_CONFIGURED.to_string(),
+        ));
+    };
+
+    let key = ObjectKey::generate();
+    key.seal(master, bucket, object, metadata).map_err(ApiError::from)?;
+    Ok(Some(key))
+}
+
+/// Wrap the data key of the SSE-KMS object `bucket/object` with the KMS key `encryption` asks for,
+/// which rotates the key encryption key of the object without rewriting its data
+pub async fn rewrap_object_key(
+    encryption: &ObjectEncryption,
+    bucket: &str,
+    object: &str,
+    metadata: &mut HashMap<String, String>,
+) -> S3Result<()> {
+    let Some(kms) = kms() else {
+        return Err(S3Error::with_message(S3ErrorCode::NotImplemented, ERR_KMS_NOT_CONFIGURED.to_string()));
+    };
+    let key_id = encryption.kms_key_id.as_deref().unwrap_or(kms.default_key_id());
+    rewrap_kms_key(bucket, object, metadata, key_id, &encryption.kms_context)
+        .await
+        .map_err(|e| S3Error::with_message(S3ErrorCode::InvalidArgument, e.to_string()))
+}
+
+/// Seal the data key of the SSE-C object `bucket/object` with `new_key` instead of `old_key`,
+/// which changes the customer key of the object without rewriting its data
+pub fn reseal_customer_key(
+    old_key: &CustomerKey,
+    new_key: &CustomerKey,
+    bucket: &str,
+    object: &str,
+    metadata: &mut HashMap<String, String>,
+) -> S3Result<()> {
+    let key = ObjectKey::unseal_customer(old_key, bucket, object, metadata).map_err(ApiError::from)?;
+    key.seal_customer(new_key, bucket, object, metadata).map_err(ApiError::from)?;
+    Ok(())
+}
+
+/// The SSE-C key of a request, `None` if it has no SSE-C headers
+pub fn customer_key(headers: &HeaderMap<HeaderValue>) -> S3Result<Option<CustomerKey>> {
+    parse_customer_key(
+        headers,
+        AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM,
+        AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY,
+        AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY_MD5,
+    )
+}
+
+/// The SSE-C key of the source of a copy, `None` if the request has no copy source SSE-C headers
+pub fn copy_source_customer_key(headers: &HeaderMap<HeaderValue>) -> S3Result<Option<CustomerKey>> {
+    parse_customer_key(
+        headers,
+        AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM,
+        AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY,
+        AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY_MD5,
+    )
+}
+
+/// Headers carrying a customer-provided key, of the object or of a copy source
+const CUSTOMER_KEY_HEADERS: &[&str] = &[
+    AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM,
+    AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY,
+    AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY_MD5,
+    AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM,
+    AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY,
+    AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY_MD5,
+];
+
+/// Refuse customer-provided keys sent over a connection without TLS, where anyone on the path reads them
+pub fn check_customer_key_transport(headers: &HeaderMap<HeaderValue>, conn: Option<&ConnectionInfo>) -> S3Result<()> {
+    if conn.is_some_and(|conn| conn.secure) || !CUSTOMER_KEY_HEADERS.iter().any(|name| headers.contains_key(*name)) {
+        return Ok(());
+    }
+    Err(s3_error!(
+        InvalidRequest,
+        "Requests specifying Server Side Encryption with Customer provided keys must be made over a secure connection."
+    ))
+}
+
+/// Check that `customer_key` opens the object `bucket/object`: SSE-C objects need the key they
+/// were written with and other objects take no key at all
+pub fn check_customer_key(
+    bucket: &str,
+    object: &str,
+    metadata: &HashMap<String, String>,
+    customer_key: Option<&CustomerKey>,
+) -> S3Result<()> {
+    match customer_key {
+        Some(customer_key) if is_customer_encrypted(metadata) => {
+            ObjectKey::unseal_customer(customer_key, bucket, object, metadata).map_err(ApiError::from)?;
+            Ok(())
+        }
+        None if is_customer_encrypted(metadata) => Err(s3_error!(
+            InvalidRequest,
+            "The object was stored using a customer-provided key, which must be provided to access it"
+        )),
+        Some(_) => Err(s3_error!(InvalidRequest, "The encryption parameters are not applicable to this object")),
+        None => Ok(()),
+    }
+}
+
+/// The SSE-C response headers echoing the key of the request
+pub fn customer_key_response(customer_key: Option<&CustomerKey>) -> (Option<SSECustomerAlgorithm>, Option<SSECustomerKeyMD5>) {
+    match customer_key {
+        Some(customer_key) => (Some(ServerSideEncryption::AES256.to_string()), Some(customer_key.key_md5())),
+        None => (None, None),
+    }
+}
+
+/// Encrypt the `actual_size` bytes of `reader` with `nonce`, the encrypted size is unknown up front
+/// so `size` becomes -1
+pub fn encrypt_reader(
+    reader: Box<dyn Reader>,
+    size: &mut i64,
+    actual_size: i64,
+    key: &ObjectKey,
+    nonce: [u8; 12],
+) -> S3Result<Box<dyn Reader>> {
+    let hrd = HashReader::new(reader, *size, actual_size, None, false).map_err(ApiError::from)?;
+    *size = -1;
+    Ok(Box::new(EncryptReader::new(hrd, key.key(), nonce)))
+}
+
+/// The `x-amz-server-side-encryption` and KMS key id response headers of an object
+pub fn encryption_response(metadata: &HashMap<String, String>) -> (Option<ServerSideEncryption>, Option<SSEKMSKeyId>) {
+    let get = |name: &str| metadata.get(&format!("{RESERVED_METADATA_PREFIX_LOWER}{name}"));
+    match get(SSE_TYPE_META) {
+        Some(sse_type) if sse_type != SSE_TYPE_CUSTOMER => {
+            (Some(ServerSideEncryption::from(sse_type.clone())), get(SSE_KMS_KEY_ID_META).cloned())
+        }
+        _ => (None, None),
+    }
+}
+
+fn resolve_encryption(
+    headers: &HeaderMap<HeaderValue>,
+    sse_config: Option<&ServerSideEncryptionConfiguration>,
+    policy: &BucketEncryptionPolicy,
+) -> S3Result<Option<ObjectEncryption>> {
+    let requested = encryption_from_headers(headers)?;
+    let mut encryption = match requested {
+        Some(encryption) => Some(encryption),
+        None => sse_config.and_then(ObjectEncryption::from_bucket_default),
+    };
+
+    match encryption.as_mut() {
+        Some(encryption) => encryption.apply_bucket_context(policy),
+        None if policy.deny_unencrypted_uploads => {
+            return Err(S3Error::with_message(
+                S3ErrorCode::AccessDenied,
+                "Server-side encryption is required for uploads to this bucket".to_string(),
+            ));
+        }
+        None => {}
+    }
+
+    Ok(encryption)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap<HeaderValue>, name: &str) -> S3Result<Option<&'a str>> {
+    match headers.get(name) {
+        Some(v) => v
+            .to_str()
+            .map(Some)
+            .map_err(|_| S3Error::with_message(S3ErrorCode::InvalidArgument, format!("invalid {name} header"))),
+        None => Ok(None),
+    }
+}
+
+fn encryption_from_headers(headers: &HeaderMap<HeaderValue>) -> S3Result<Option<ObjectEncryption>> {
+    let algorithm = header_str(headers, AMZ_SERVER_SIDE_ENCRYPTION)?;
+
+    if let Some(customer_key) = customer_key(headers)? {
+        if algorithm.is_some() {
+            return Err(s3_error!(InvalidArgument, "SSE-C cannot be combined with server-managed encryption"));
+        }
+        let mut encryption = ObjectEncryption::new(SSE_TYPE_CUSTOMER);
+        encryption.customer_key = Some(customer_key);
+        return Ok(Some(encryption));
+    }
+
+    let Some(algorithm) = algorithm else {
+        return Ok(None);
+    };
+
+    let mut encryption = ObjectEncryption::new(algorithm);
+    match algorithm {
+        ServerSideEncryption::AES256 => {
+            if headers.contains_key(AMZ_SERVER_SIDE_ENCRYPTION_KMS_ID)
+                || headers.contains_key(AMZ_SERVER_SIDE_ENCRYPTION_KMS_CONTEXT)
+            {
+                return Err(s3_error!(InvalidArgument, "KMS parameters require aws:kms encryption"));
+            }
+        }
+        ServerSideEncryption::AWS_KMS | ServerSideEncryption::AWS_KMS_DSSE => {
+            encryption.kms_key_id = header_str(headers, AMZ_SERVER_SIDE_ENCRYPTION_KMS_ID)?.map(str::to_string);
+            if let Some(context) = header_str(headers, AMZ_SERVER_SIDE_ENCRYPTION_KMS_CONTEXT)? {
+                encryption.kms_context = parse_kms_context(context)?;
+            }
+        }
+        _ => return Err(s3_error!(InvalidEncryptionAlgorithmError)),
+    }
+    encryption.bucket_key_enabled =
+        header_str(headers, AMZ_SERVER_SIDE_ENCRYPTION_BUCKET_KEY_ENABLED)?.is_some_and(|v| v.eq_ignore_ascii_case("true"));
+
+    Ok(Some(encryption))
+}
+
+fn parse_customer_key(
+    headers: &HeaderMap<HeaderValue>,
+    algorithm_header: &str,
+    key_header: &str,
+    md5_header: &str,
+) -> S3Result<Option<CustomerKey>> {
+    let algorithm = header_str(headers, algorithm_header)?;
+    let key = header_str(headers, key_header)?;
+    let key_md5 = header_str(headers, md5_header)?;
+    if algorithm.is_none() && key.is_none() && key_md5.is_none() {
+        return Ok(None);
+    }
+
+    match algorithm {
+        Some(algorithm) if algorithm == ServerSideEncryption::AES256 => {}
+        Some(_) => return Err(s3_error!(InvalidEncryptionAlgorithmError)),
+        None => {
+            return Err(s3_error!(
+                InvalidArgument,
+                "Requests specifying Server Side Encryption with Customer provided keys must provide a valid encryption algorithm"
+            ));
+        }
+    }
+
+    let customer_key = key
+        .and_then(CustomerKey::from_base64)
+        .ok_or_else(|| s3_error!(InvalidArgument, "The secret key was invalid for the specified algorithm"))?;
+
+    if key_md5 != Some(customer_key.key_md5().as_str()) {
+        return Err(s3_error!(
+            InvalidArgument,
+            "The calculated MD5 hash of the key did not match the hash that was provided"
+        ));
+    }
+
+    Ok(Some(customer_key))
+}
+
+/// Decode the base64-encoded JSON object of the encryption context header
+fn parse_kms_context(value: &str) -> S3Result<BTreeMap<String, String>> {
+    let data = base64_decode(value.as_bytes())
+        .map_err(|_| s3_error!(InvalidArgument, "encryption context must be base64-encoded JSON"))?;
+    serde_json::from_slice(&data).map_err(|_| s3_error!(InvalidArgument, "encryption context must be a JSON object of strings"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustfs_utils::crypto::base64_encode;
+    use s3s::dto::{ServerSideEncryptionByDefault, ServerSideEncryptionRule};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap<HeaderValue> {
+        let mut headers = HeaderMap::new();
+        for (k, v) in pairs {
+            headers.insert(*k, HeaderValue::from_str(v).unwrap());
+        }
+        headers
+    }
+
+    fn kms_default() -> ServerSideEncryptionConfiguration {
+        ServerSideEncryptionConfiguration {
+            rules: vec![ServerSideEncryptionRule {
+                apply_server_side_encryption_by_default: Some(ServerSideEncryptionByDefault {
+                    kms_master_key_id: Some("bucket-key".to_string()),
+                    sse_algorithm: ServerSideEncryption::from(ServerSideEncryption::AWS_KMS.to_string()),
+                }),
+                bucket_key_enabled: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_deny_unencrypted_uploads() {
+        let deny = BucketEncryptionPolicy {
+            deny_unencrypted_uploads: true,
+            ..Default::default()
+        };
+
+        let err = resolve_encryption(&HeaderMap::new(), None, &deny).unwrap_err();
+        assert_eq!(*err.code(), S3ErrorCode::AccessDenied);
+
+        // The bucket default encryption satisfies the requirement
+        let enc = resolve_encryption(&HeaderMap::new(), Some(&kms_default()), &deny)
+            .unwrap()
+            .unwrap();
+        assert_eq!(enc.sse_type, "aws:kms");
+        assert_eq!(enc.kms_key_id.as_deref(), Some("bucket-key"));
+
+        let enc = resolve_encryption(&headers(&[(AMZ_SERVER_SIDE_ENCRYPTION, "AES256")]), None, &deny)
+            .unwrap()
+            .unwrap();
+        assert_eq!(enc.sse_type, "AES256");
+
+        assert!(
+            resolve_encryption(&HeaderMap::new(), None, &BucketEncryptionPolicy::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_request_headers_override_bucket_default() {
+        let policy = BucketEncryptionPolicy {
+            deny_unencrypted_uploads: false,
+            kms_context: BTreeMap::from([("bucket".to_string(), "reports".to_string())]),
+        };
+        let context = base64_encode(br#"{"project":"x"}"#);
+        let h = headers(&[
+            (AMZ_SERVER_SIDE_ENCRYPTION, "aws:kms"),
+            (AMZ_SERVER_SIDE_ENCRYPTION_KMS_ID, "request-key"),
+            (AMZ_SERVER_SIDE_ENCRYPTION_KMS_CONTEXT, &context),
+        ]);
+
+        let enc = resolve_encryption(&h, Some(&kms_default()), &policy).unwrap().unwrap();
+        assert_eq!(enc.kms_key_id.as_deref(), Some("request-key"));
+        assert_eq!(enc.kms_context.get("project").map(String::as_str), Some("x"));
+        assert_eq!(enc.kms_context.get("bucket").map(String::as_str), Some("reports"));
+    }
+
+    #[test]
+    fn test_encryption_response() {
+        let mut metadata = HashMap::new();
+        assert_eq!(encryption_response(&metadata), (None, None));
+
+        ObjectEncryption::new(ServerSideEncryption::AES256)
+            .write_metadata(&mut metadata)
+            .unwrap();
+        let (sse, kms_key_id) = encryption_response(&metadata);
+        assert_eq!(sse.as_ref().map(|s| s.as_str()), Some(ServerSideEncryption::AES256));
+        assert!(kms_key_id.is_none());
+
+        // SSE-C is reported with the customer algorithm headers instead
+        let mut metadata = HashMap::new();
+        ObjectEncryption::new(SSE_TYPE_CUSTOMER)
+            .write_metadata(&mut metadata)
+            .unwrap();
+        assert_eq!(encryption_response(&metadata), (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_new_object_key_without_encryption() {
+        let mut metadata = HashMap::new();
+        assert!(
+            new_object_key(None, "bucket", "object", &mut metadata)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let customer = ObjectEncryption::new(SSE_TYPE_CUSTOMER);
+        assert!(
+            new_object_key(Some(&customer), "bucket", "object", &mut metadata)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(metadata.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_sse_headers() {
+        let policy = BucketEncryptionPolicy::default();
+        assert!(resolve_encryption(&headers(&[(AMZ_SERVER_SIDE_ENCRYPTION, "rot13")]), None, &policy).is_err());
+        assert!(
+            resolve_encryption(
+                &headers(&[
+                    (AMZ_SERVER_SIDE_ENCRYPTION, "AES256"),
+                    (AMZ_SERVER_SIDE_ENCRYPTION_KMS_ID, "k")
+                ]),
+                None,
+                &policy
+            )
+            .is_err()
+        );
+        assert!(
+            resolve_encryption(
+                &headers(&[
+                    (AMZ_SERVER_SIDE_ENCRYPTION, "aws:kms"),
+                    (AMZ_SERVER_SIDE_ENCRYPTION_KMS_CONTEXT, "not base64!")
+                ]),
+                None,
+                &policy
+            )
+            .is_err()
+        );
+
+        // SSE-C needs the key along with the algorithm
+        assert!(
+            resolve_encryption(&headers(&[(AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM, "AES256")]), None, &policy).is_err()
+        );
+        let enc = resolve_encryption(&headers(&customer_headers(KEY_A, KEY_A_MD5)), None, &policy)
+            .unwrap()
+            .unwrap();
+        assert_eq!(enc.sse_type, SSE_TYPE_CUSTOMER);
+        assert_eq!(enc.customer_key, CustomerKey::from_base64(KEY_A));
+    }
+
+    const KEY_A: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+    const KEY_A_MD5: &str = "hRasmdxgYDKV3nvbahU1MA==";
+    const KEY_B: &str = "ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=";
+
+    fn customer_headers(key: &'static str, key_md5: &'static str) -> Vec<(&'static str, &'static str)> {
+        vec![
+            (AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM, "AES256"),
+            (AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY, key),
+            (AMZ_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY_MD5, key_md5),
+        ]
+    }
+
+    #[test]
+    fn test_customer_key_headers() {
+        assert!(customer_key(&HeaderMap::new()).unwrap().is_none());
+
+        let key = customer_key(&headers(&customer_headers(KEY_A, KEY_A_MD5))).unwrap().unwrap();
+        assert_eq!(key.key_md5(), KEY_A_MD5);
+        assert_eq!(
+            customer_key_response(Some(&key)),
+            (Some("AES256".to_string()), Some(KEY_A_MD5.to_string()))
+        );
+
+        // The MD5 must match the key, which must be 32 bytes of AES256
+        let err = customer_key(&headers(&customer_headers(KEY_B, KEY_A_MD5))).unwrap_err();
+        assert_eq!(*err.code(), S3ErrorCode::InvalidArgument);
+        assert!(customer_key(&headers(&customer_headers("MDEyMw==", KEY_A_MD5))).is_err());
+        let mut wrong_algorithm = customer_headers(KEY_A, KEY_A_MD5);
+        wrong_algorithm[0].1 = "aws:kms";
+        let err = customer_key(&headers(&wrong_algorithm)).unwrap_err();
+        assert_eq!(*err.code(), S3ErrorCode::InvalidEncryptionAlgorithmError);
+
+        // The key of the copy source comes from its own headers
+        let copy = headers(&[
+            (AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_ALGORITHM, "AES256"),
+            (AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY, KEY_A),
+            (AMZ_COPY_SOURCE_SERVER_SIDE_ENCRYPTION_CUSTOMER_KEY_MD5, KEY_A_MD5),
+        ]);
+        assert!(customer_key(&copy).unwrap().is_none());
+        assert_eq!(copy_source_customer_key(&copy).unwrap(), Some(key));
+    }
+
+    #[test]
+    fn test_customer_key_transport() {
+        let secure = ConnectionInfo {
+            remote_addr: None,
+            secure: true,
+        };
+        let plain = ConnectionInfo { secure: false, ..secure };
+        let sse_c = headers(&customer_headers(KEY_A, KEY_A_MD5));
+
+        assert!(check_customer_key_transport(&sse_c, Some(&secure)).is_ok());
+        let err = check_customer_key_transport(&sse_c, Some(&plain)).unwrap_err();
+        assert_eq!(*err.code(), S3ErrorCode::InvalidRequest);
+        assert!(check_customer_key_transport(&sse_c, None).is_err());
+        // Requests without a customer key may come over any connection
+        assert!(check_customer_key_transport(&HeaderMap::new(), Some(&plain)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_customer_key() {
+        let key_a = CustomerKey::from_base64(KEY_A).unwrap();
+        let key_b = CustomerKey::from_base64(KEY_B).unwrap();
+        let mut encryption = ObjectEncryption::new(SSE_TYPE_CUSTOMER);
+        encryption.customer_key = Some(key_a);
+
+        let mut metadata = HashMap::new();
+        encryption.write_metadata(&mut metadata).unwrap();
+        assert!(
+            new_object_key(Some(&encryption), "bucket", "object", &mut metadata)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        assert!(check_customer_key("bucket", "object", &metadata, Some(&key_a)).is_ok());
+        let err = check_customer_key("bucket", "object", &metadata, Some(&key_b)).unwrap_err();
+        assert_eq!(*err.code(), S3ErrorCode::AccessDenied);
+        let err = check_customer_key("bucket", "object", &metadata, None).unwrap_err();
+        assert_eq!(*err.code(), S3ErrorCode::InvalidRequest);
+        // The sealed key is bound to the object
+        assert!(check_customer_key("bucket", "other", &metadata, Some(&key_a)).is_err());
+
+        // Objects without SSE-C take no key
+        assert!(check_customer_key("bucket", "object", &HashMap::new(), None).is_ok());
+        assert!(check_customer_key("bucket", "object", &HashMap::new(), Some(&key_a)).is_err());
+
+        reseal_customer_key(&key_a, &key_b, "bucket", "object", &mut metadata).unwrap();
+        assert!(check_customer_key("bucket", "object", &metadata, Some(&key_b)).is_ok());
+        assert!(check_customer_key("bucket", "object", &metadata, Some(&key_a)).is_err());
+    }
+}